@@ -8,6 +8,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         "proto/rag_manager_persona_layer.proto",
         "proto/memory_chain_engine.proto",
         "proto/router_core_model_registry.proto",
+        "proto/health.proto",
     ];
 
     let proto_dir = PathBuf::from("proto");
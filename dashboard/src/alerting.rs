@@ -0,0 +1,662 @@
+//! Alerting
+//!
+//! This module evaluates threshold rules on the metrics collected by the
+//! dashboard, the same ones charted on the history page, and notifies
+//! webhooks, Slack channels and email recipients when an alert fires or
+//! resolves. Rules are created and edited through the dashboard and kept,
+//! with the history of the alerts they raised, in the dashboard's SQLite
+//! database. Notifiers hold credentials, so they are only configured in
+//! `alerting.json` in the data directory.
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use intellirouter::modules::monitoring::AlertSeverity;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::history;
+
+/// How a rule compares a metric with its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    /// Fire when the metric is above the threshold
+    Above,
+    /// Fire when the metric is below the threshold
+    Below,
+}
+
+impl Comparison {
+    /// Whether a value breaches a threshold
+    pub fn breached(&self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::Below => value < threshold,
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::Above => write!(f, "above"),
+            Comparison::Below => write!(f, "below"),
+        }
+    }
+}
+
+/// Threshold rule, as created or edited through the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleSpec {
+    /// Rule name
+    pub name: String,
+    /// Metric watched, one of [`history::METRICS`]
+    pub metric: String,
+    /// Instance watched, every instance if unset
+    #[serde(default)]
+    pub instance: Option<String>,
+    /// Comparison with the threshold
+    pub comparison: Comparison,
+    /// Threshold, in the unit of the metric
+    pub threshold: f64,
+    /// Seconds the threshold must stay breached before the alert fires
+    #[serde(default)]
+    pub for_seconds: u64,
+    /// Severity of the alerts raised
+    pub severity: AlertSeverity,
+    /// Names of the notifiers told about the alerts
+    #[serde(default)]
+    pub notifiers: Vec<String>,
+    /// Whether the rule is evaluated
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Stored threshold rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Rule ID
+    pub id: String,
+    /// Rule definition
+    #[serde(flatten)]
+    pub spec: AlertRuleSpec,
+    /// Last update
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    /// Whether the rule watches a sample
+    fn watches(&self, metric: &str, instance: &str) -> bool {
+        self.spec.enabled
+            && self.spec.metric == metric
+            && self.spec.instance.as_deref().is_none_or(|i| i == instance)
+    }
+}
+
+/// Alert raised by a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// Alert ID
+    pub id: i64,
+    /// ID of the rule that raised the alert
+    pub rule_id: String,
+    /// Name of the rule, as of when the alert fired
+    pub rule_name: String,
+    /// Metric breaching the threshold
+    pub metric: String,
+    /// Instance breaching the threshold
+    pub instance: String,
+    /// Severity
+    pub severity: AlertSeverity,
+    /// Comparison with the threshold
+    pub comparison: Comparison,
+    /// Threshold
+    pub threshold: f64,
+    /// Value that fired the alert
+    pub value: f64,
+    /// When the alert fired
+    pub fired_at: DateTime<Utc>,
+    /// When the alert resolved, if it has
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Names of the notifiers told about the alert
+    pub notifiers: Vec<String>,
+}
+
+impl AlertEvent {
+    /// One-line summary of the alert
+    pub fn summary(&self) -> String {
+        format!(
+            "[{}] {}: {} of {} is {} {} ({:.2})",
+            if self.resolved_at.is_some() {
+                "RESOLVED"
+            } else {
+                "FIRING"
+            },
+            self.rule_name,
+            self.metric,
+            self.instance,
+            self.comparison,
+            self.threshold,
+            self.value
+        )
+    }
+}
+
+/// Destination of alert notifications
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum NotifierKind {
+    /// JSON POSTed to a URL
+    Webhook {
+        /// URL notifications are POSTed to
+        url: String,
+        /// Additional headers
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    /// Slack incoming webhook
+    Slack {
+        /// Incoming webhook URL
+        webhook_url: String,
+        /// Channel overriding the webhook's default
+        #[serde(default)]
+        channel: Option<String>,
+    },
+    /// Email sent over SMTP with STARTTLS
+    Email {
+        /// SMTP server
+        smtp_server: String,
+        /// SMTP port
+        #[serde(default = "default_smtp_port")]
+        smtp_port: u16,
+        /// SMTP username
+        #[serde(default)]
+        smtp_username: Option<String>,
+        /// SMTP password
+        #[serde(default)]
+        smtp_password: Option<String>,
+        /// From address
+        from_address: String,
+        /// To addresses
+        to_addresses: Vec<String>,
+    },
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+/// Named notifier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notifier {
+    /// Name rules refer to the notifier by
+    pub name: String,
+    /// Destination
+    #[serde(flatten)]
+    pub kind: NotifierKind,
+}
+
+/// Notifier as listed by the API, without its credentials
+#[derive(Debug, Clone, Serialize)]
+pub struct NotifierInfo {
+    /// Name
+    pub name: String,
+    /// Type (`webhook`, `slack` or `email`)
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+/// Alerting configuration, read from `alerting.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertingConfig {
+    /// Notifiers rules can send to
+    #[serde(default)]
+    pub notifiers: Vec<Notifier>,
+}
+
+/// Rule and instance of a breach
+type BreachKey = (String, String);
+
+/// Threshold being breached
+#[derive(Debug)]
+struct Breach {
+    /// First sample breaching the threshold
+    since: DateTime<Utc>,
+    /// Alert fired for the breach, once it lasted long enough
+    alert: Option<i64>,
+}
+
+/// Alert rules, their evaluation and the alert history
+pub struct Alerting {
+    conn: Mutex<Connection>,
+    notifiers: Vec<Notifier>,
+    breaches: Mutex<HashMap<BreachKey, Breach>>,
+    http: reqwest::Client,
+}
+
+impl Alerting {
+    /// Open the rules and alert history in a database, creating them if
+    /// needed
+    pub fn open(path: &Path, config: AlertingConfig) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open alerts: {}", path.display()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS alert_rules (
+                id TEXT PRIMARY KEY,
+                rule TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_id TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                metric TEXT NOT NULL,
+                instance TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                comparison TEXT NOT NULL,
+                threshold REAL NOT NULL,
+                value REAL NOT NULL,
+                fired_at INTEGER NOT NULL,
+                resolved_at INTEGER,
+                notifiers TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS alerts_fired_at ON alerts (fired_at);",
+        )
+        .context("Failed to create alerts schema")?;
+
+        // Pick up the alerts still firing when the dashboard stopped
+        let mut breaches = HashMap::new();
+        {
+            let mut select = conn.prepare(
+                "SELECT id, rule_id, instance, fired_at FROM alerts WHERE resolved_at IS NULL",
+            )?;
+            let rows = select.query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                ))
+            })?;
+            for row in rows {
+                let (id, rule_id, instance, fired_at) = row?;
+                breaches.insert(
+                    (rule_id, instance),
+                    Breach {
+                        since: timestamp(fired_at),
+                        alert: Some(id),
+                    },
+                );
+            }
+        }
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            notifiers: config.notifiers,
+            breaches: Mutex::new(breaches),
+            http: reqwest::Client::new(),
+        })
+    }
+
+    /// Configured notifiers
+    pub fn notifiers(&self) -> Vec<NotifierInfo> {
+        self.notifiers
+            .iter()
+            .map(|n| NotifierInfo {
+                name: n.name.clone(),
+                kind: match n.kind {
+                    NotifierKind::Webhook { .. } => "webhook",
+                    NotifierKind::Slack { .. } => "slack",
+                    NotifierKind::Email { .. } => "email",
+                },
+            })
+            .collect()
+    }
+
+    /// All rules, by name
+    pub fn rules(&self) -> Result<Vec<AlertRule>> {
+        let conn = self.conn.lock().unwrap();
+        let mut select = conn.prepare_cached("SELECT rule FROM alert_rules")?;
+        let mut rules = select
+            .query_map([], |row| row.get::<_, String>(0))?
+            .map(|rule| Ok(serde_json::from_str::<AlertRule>(&rule?)?))
+            .collect::<Result<Vec<_>>>()?;
+        rules.sort_by(|a, b| a.spec.name.cmp(&b.spec.name));
+        Ok(rules)
+    }
+
+    /// Create a rule
+    pub fn create_rule(&self, spec: AlertRuleSpec) -> Result<AlertRule> {
+        self.validate(&spec)?;
+        let rule = AlertRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            spec,
+            updated_at: Utc::now(),
+        };
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO alert_rules (id, rule) VALUES (?1, ?2)",
+            params![rule.id, serde_json::to_string(&rule)?],
+        )?;
+        Ok(rule)
+    }
+
+    /// Replace the definition of a rule, returning `None` if there is no
+    /// such rule
+    pub fn update_rule(&self, id: &str, spec: AlertRuleSpec) -> Result<Option<AlertRule>> {
+        self.validate(&spec)?;
+        let rule = AlertRule {
+            id: id.to_string(),
+            spec,
+            updated_at: Utc::now(),
+        };
+        let updated = self.conn.lock().unwrap().execute(
+            "UPDATE alert_rules SET rule = ?2 WHERE id = ?1",
+            params![id, serde_json::to_string(&rule)?],
+        )?;
+        Ok((updated > 0).then_some(rule))
+    }
+
+    /// Delete a rule, returning whether it existed. Its alerts resolve on
+    /// the next evaluation.
+    pub fn delete_rule(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM alert_rules WHERE id = ?1", params![id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Check a rule definition
+    pub fn validate(&self, spec: &AlertRuleSpec) -> Result<()> {
+        if spec.name.trim().is_empty() {
+            bail!("Rule name is empty");
+        }
+        if !history::METRICS.iter().any(|m| m.name == spec.metric) {
+            bail!("Unknown metric: {}", spec.metric);
+        }
+        if !spec.threshold.is_finite() {
+            bail!("Threshold must be a number");
+        }
+        if let Some(name) = spec
+            .notifiers
+            .iter()
+            .find(|name| !self.notifiers.iter().any(|n| &&n.name == name))
+        {
+            bail!("Unknown notifier: {}", name);
+        }
+        Ok(())
+    }
+
+    /// Latest alerts, firing or resolved
+    pub fn alerts(&self, limit: usize) -> Result<Vec<AlertEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let mut select = conn.prepare_cached(&format!(
+            "SELECT {} FROM alerts ORDER BY fired_at DESC, id DESC LIMIT ?1",
+            ALERT_COLUMNS
+        ))?;
+        let alerts = select
+            .query_map(params![limit as i64], alert_from_row)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(alerts)
+    }
+
+    /// Evaluate the rules on samples taken at the same time, as
+    /// `(metric, instance, value)`, and send the notifications of the alerts
+    /// that fired or resolved
+    pub async fn evaluate(&self, at: DateTime<Utc>, samples: &[(&str, &str, f64)]) {
+        let events = match self.transitions(at, samples) {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Failed to evaluate alert rules: {:#}", e);
+                return;
+            }
+        };
+
+        for event in events {
+            for notifier in self
+                .notifiers
+                .iter()
+                .filter(|n| event.notifiers.contains(&n.name))
+            {
+                if let Err(e) = self.notify(notifier, &event).await {
+                    eprintln!(
+                        "Failed to notify {} of alert {}: {:#}",
+                        notifier.name, event.id, e
+                    );
+                }
+            }
+        }
+    }
+
+    /// Fire and resolve alerts for samples, returning the alerts that did.
+    /// Alerts resolve to the notifiers told they fired, even if the rule
+    /// changed since.
+    fn transitions(
+        &self,
+        at: DateTime<Utc>,
+        samples: &[(&str, &str, f64)],
+    ) -> Result<Vec<AlertEvent>> {
+        let rules = self.rules()?;
+        let mut breaches = self.breaches.lock().unwrap();
+        let mut events = Vec::new();
+
+        for rule in &rules {
+            for &(metric, instance, value) in samples {
+                if !rule.watches(metric, instance) {
+                    continue;
+                }
+                let key = (rule.id.clone(), instance.to_string());
+                if rule.spec.comparison.breached(value, rule.spec.threshold) {
+                    let breach = breaches.entry(key).or_insert(Breach {
+                        since: at,
+                        alert: None,
+                    });
+                    let lasted = (at - breach.since).num_seconds();
+                    if breach.alert.is_none() && lasted >= rule.spec.for_seconds as i64 {
+                        let event = self.fire(rule, instance, value, at)?;
+                        breach.alert = Some(event.id);
+                        events.push(event);
+                    }
+                } else if let Some(Breach {
+                    alert: Some(id), ..
+                }) = breaches.remove(&key)
+                {
+                    events.push(self.resolve(id, at)?);
+                }
+            }
+        }
+
+        // Resolve the alerts of rules deleted or disabled since they fired
+        let orphaned: Vec<BreachKey> = breaches
+            .keys()
+            .filter(|(rule_id, _)| !rules.iter().any(|r| &r.id == rule_id && r.spec.enabled))
+            .cloned()
+            .collect();
+        for key in orphaned {
+            if let Some(Breach {
+                alert: Some(id), ..
+            }) = breaches.remove(&key)
+            {
+                events.push(self.resolve(id, at)?);
+            }
+        }
+        Ok(events)
+    }
+
+    fn fire(
+        &self,
+        rule: &AlertRule,
+        instance: &str,
+        value: f64,
+        at: DateTime<Utc>,
+    ) -> Result<AlertEvent> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO alerts (rule_id, rule_name, metric, instance, severity, comparison,
+                threshold, value, fired_at, notifiers)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                rule.id,
+                rule.spec.name,
+                rule.spec.metric,
+                instance,
+                serde_json::to_string(&rule.spec.severity)?,
+                serde_json::to_string(&rule.spec.comparison)?,
+                rule.spec.threshold,
+                value,
+                at.timestamp(),
+                serde_json::to_string(&rule.spec.notifiers)?
+            ],
+        )?;
+        Ok(AlertEvent {
+            id: conn.last_insert_rowid(),
+            rule_id: rule.id.clone(),
+            rule_name: rule.spec.name.clone(),
+            metric: rule.spec.metric.clone(),
+            instance: instance.to_string(),
+            severity: rule.spec.severity,
+            comparison: rule.spec.comparison,
+            threshold: rule.spec.threshold,
+            value,
+            fired_at: at,
+            resolved_at: None,
+            notifiers: rule.spec.notifiers.clone(),
+        })
+    }
+
+    fn resolve(&self, id: i64, at: DateTime<Utc>) -> Result<AlertEvent> {
+        self.conn.lock().unwrap().execute(
+            "UPDATE alerts SET resolved_at = ?2 WHERE id = ?1",
+            params![id, at.timestamp()],
+        )?;
+        self.alert(id)?
+            .ok_or_else(|| anyhow!("Alert {} disappeared", id))
+    }
+
+    fn alert(&self, id: i64) -> Result<Option<AlertEvent>> {
+        let conn = self.conn.lock().unwrap();
+        let alert = conn
+            .query_row(
+                &format!("SELECT {} FROM alerts WHERE id = ?1", ALERT_COLUMNS),
+                params![id],
+                alert_from_row,
+            )
+            .optional()?;
+        Ok(alert)
+    }
+
+    /// Send the notification of an alert
+    async fn notify(&self, notifier: &Notifier, event: &AlertEvent) -> Result<()> {
+        let status = if event.resolved_at.is_some() {
+            "resolved"
+        } else {
+            "firing"
+        };
+
+        match &notifier.kind {
+            NotifierKind::Webhook { url, headers } => {
+                let mut request = self.http.post(url).json(&serde_json::json!({
+                    "status": status,
+                    "alert": event,
+                }));
+                for (name, value) in headers {
+                    request = request.header(name, value);
+                }
+                request.send().await?.error_for_status()?;
+            }
+            NotifierKind::Slack {
+                webhook_url,
+                channel,
+            } => {
+                let mut message = serde_json::json!({ "text": event.summary() });
+                if let Some(channel) = channel {
+                    message["channel"] = serde_json::json!(channel);
+                }
+                self.http
+                    .post(webhook_url)
+                    .json(&message)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+            }
+            NotifierKind::Email {
+                smtp_server,
+                smtp_port,
+                smtp_username,
+                smtp_password,
+                from_address,
+                to_addresses,
+            } => {
+                let mut message = Message::builder()
+                    .from(from_address.parse::<Mailbox>()?)
+                    .subject(event.summary());
+                for to in to_addresses {
+                    message = message.to(to.parse::<Mailbox>()?);
+                }
+                let message = message.body(format!(
+                    "{}\n\nSeverity: {}\nFired at: {}\n{}",
+                    event.summary(),
+                    event.severity,
+                    event.fired_at.to_rfc3339(),
+                    event
+                        .resolved_at
+                        .map(|at| format!("Resolved at: {}\n", at.to_rfc3339()))
+                        .unwrap_or_default()
+                ))?;
+
+                let mut transport =
+                    AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_server)?
+                        .port(*smtp_port);
+                if let (Some(username), Some(password)) = (smtp_username, smtp_password) {
+                    transport =
+                        transport.credentials(Credentials::new(username.clone(), password.clone()));
+                }
+                transport.build().send(message).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Columns [`alert_from_row`] reads
+const ALERT_COLUMNS: &str = "id, rule_id, rule_name, metric, instance, severity, comparison, \
+    threshold, value, fired_at, resolved_at, notifiers";
+
+fn alert_from_row(row: &Row<'_>) -> rusqlite::Result<AlertEvent> {
+    Ok(AlertEvent {
+        id: row.get(0)?,
+        rule_id: row.get(1)?,
+        rule_name: row.get(2)?,
+        metric: row.get(3)?,
+        instance: row.get(4)?,
+        severity: json_column(row, 5)?,
+        comparison: json_column(row, 6)?,
+        threshold: row.get(7)?,
+        value: row.get(8)?,
+        fired_at: timestamp(row.get(9)?),
+        resolved_at: row.get::<_, Option<i64>>(10)?.map(timestamp),
+        notifiers: json_column(row, 11)?,
+    })
+}
+
+fn json_column<T: DeserializeOwned>(row: &Row<'_>, index: usize) -> rusqlite::Result<T> {
+    let text: String = row.get(index)?;
+    serde_json::from_str(&text)
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, Type::Text, Box::new(e)))
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+}
@@ -0,0 +1,568 @@
+//! Dashboard authentication
+//!
+//! Once `auth.json` is present in the data directory, every page and API of
+//! the dashboard requires a signed-in user. Users sign in with static
+//! credentials or through an OpenID Connect provider, and can hand out
+//! signed links that give read-only access to a single view until they
+//! expire. Sessions and share links are both HMAC-signed tokens kept in
+//! cookies, so the dashboard keeps no session state of its own.
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{hmac, pbkdf2};
+use rocket::http::{Cookie, CookieJar, SameSite, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::Redirect;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+
+/// Cookie holding the session of a signed-in user
+pub const SESSION_COOKIE: &str = "dashboard_session";
+/// Cookie holding the share link a visitor followed
+pub const SHARE_COOKIE: &str = "dashboard_share";
+/// Cookie holding the state of a pending OpenID Connect sign-in
+pub const OIDC_STATE_COOKIE: &str = "dashboard_oidc_state";
+
+/// PBKDF2 iterations of newly hashed passwords
+const PASSWORD_ITERATIONS: u32 = 100_000;
+
+/// User signing in with static credentials
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticUser {
+    /// Username
+    pub username: String,
+    /// Password hash, as printed by `intellirouter-dashboard hash-password`
+    pub password_hash: String,
+}
+
+/// OpenID Connect provider users sign in through
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Issuer URL, where `/.well-known/openid-configuration` is served
+    pub issuer: String,
+    /// Client ID of the dashboard
+    pub client_id: String,
+    /// Client secret of the dashboard
+    pub client_secret: String,
+    /// Public URL of the dashboard's `/login/oidc/callback`
+    pub redirect_url: String,
+    /// Scopes requested
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    /// Usernames or emails allowed to sign in, all of the provider's users
+    /// if empty
+    #[serde(default)]
+    pub allowed_users: Vec<String>,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "profile".to_string(),
+        "email".to_string(),
+    ]
+}
+
+/// Authentication configuration, read from `auth.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Users signing in with static credentials
+    #[serde(default)]
+    pub users: Vec<StaticUser>,
+    /// OpenID Connect provider
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+    /// Secret sessions and share links are signed with. A random one is
+    /// generated at startup if unset, which signs everyone out on restart.
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Hours a session lasts
+    #[serde(default = "default_session_hours")]
+    pub session_hours: i64,
+    /// Longest lifetime of a share link in hours
+    #[serde(default = "default_max_share_hours")]
+    pub max_share_hours: i64,
+    /// Whether cookies are only sent over HTTPS
+    #[serde(default)]
+    pub secure_cookies: bool,
+}
+
+fn default_session_hours() -> i64 {
+    12
+}
+
+fn default_max_share_hours() -> i64 {
+    7 * 24
+}
+
+/// View a share link gives access to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShareView {
+    /// Home page
+    Home,
+    /// Code quality page
+    CodeQuality,
+    /// Performance page
+    Performance,
+    /// Security page
+    Security,
+    /// Documentation page
+    Documentation,
+    /// Runtime page
+    Runtime,
+    /// History page
+    History,
+}
+
+impl ShareView {
+    /// Path of the view's page
+    pub fn path(&self) -> &'static str {
+        match self {
+            ShareView::Home => "/",
+            ShareView::CodeQuality => "/code-quality",
+            ShareView::Performance => "/performance",
+            ShareView::Security => "/security",
+            ShareView::Documentation => "/documentation",
+            ShareView::Runtime => "/runtime",
+            ShareView::History => "/history",
+        }
+    }
+
+    /// Whether a request path belongs to the view, i.e. is its page or an
+    /// API the page reads from
+    pub fn allows(&self, path: &str) -> bool {
+        match self {
+            ShareView::Runtime => path == "/runtime" || path == "/api/runtime",
+            ShareView::History => path == "/history" || path.starts_with("/api/history/"),
+            view => path == view.path(),
+        }
+    }
+}
+
+/// What a signed token grants
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Grant {
+    /// Session of a signed-in user
+    Session {
+        /// Username
+        user: String,
+    },
+    /// Read-only access to a single view
+    Share {
+        /// View shared
+        view: ShareView,
+        /// User who created the link
+        by: String,
+    },
+}
+
+/// Signed token contents
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    #[serde(flatten)]
+    grant: Grant,
+    /// Expiry as a Unix timestamp
+    exp: i64,
+}
+
+/// OpenID Connect provider metadata
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+/// Token endpoint response
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// Userinfo endpoint response
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    email: Option<String>,
+}
+
+/// Dashboard authentication
+pub struct Auth {
+    config: Option<AuthConfig>,
+    key: hmac::Key,
+    rng: SystemRandom,
+    http: reqwest::Client,
+    discovery: tokio::sync::OnceCell<Discovery>,
+}
+
+impl Auth {
+    /// Authentication that lets everyone in
+    pub fn disabled() -> Self {
+        let rng = SystemRandom::new();
+        let key = hmac::Key::generate(hmac::HMAC_SHA256, &rng).expect("Failed to generate key");
+        Self {
+            config: None,
+            key,
+            rng,
+            http: reqwest::Client::new(),
+            discovery: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    /// Authentication from its configuration
+    pub fn new(config: AuthConfig) -> Result<Self> {
+        if config.users.is_empty() && config.oidc.is_none() {
+            bail!("No users or OpenID Connect provider configured");
+        }
+        for user in &config.users {
+            parse_password_hash(&user.password_hash)
+                .with_context(|| format!("Invalid password hash of {}", user.username))?;
+        }
+
+        let rng = SystemRandom::new();
+        let key = match &config.secret {
+            Some(secret) if secret.len() < 32 => bail!("Secret must be at least 32 bytes"),
+            Some(secret) => hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            None => hmac::Key::generate(hmac::HMAC_SHA256, &rng)
+                .map_err(|_| anyhow!("Failed to generate key"))?,
+        };
+        Ok(Self {
+            config: Some(config),
+            key,
+            rng,
+            http: reqwest::Client::new(),
+            discovery: tokio::sync::OnceCell::new(),
+        })
+    }
+
+    /// Whether sign-in is required
+    pub fn enabled(&self) -> bool {
+        self.config.is_some()
+    }
+
+    /// Whether users can sign in with static credentials
+    pub fn static_login(&self) -> bool {
+        self.config.as_ref().is_some_and(|c| !c.users.is_empty())
+    }
+
+    /// Whether users can sign in through OpenID Connect
+    pub fn oidc_login(&self) -> bool {
+        self.oidc().is_some()
+    }
+
+    fn oidc(&self) -> Option<&OidcConfig> {
+        self.config.as_ref().and_then(|c| c.oidc.as_ref())
+    }
+
+    fn secure_cookies(&self) -> bool {
+        self.config.as_ref().is_some_and(|c| c.secure_cookies)
+    }
+
+    /// Check static credentials
+    pub fn verify_password(&self, username: &str, password: &str) -> bool {
+        let Some(user) = self
+            .config
+            .iter()
+            .flat_map(|c| &c.users)
+            .find(|u| u.username == username)
+        else {
+            return false;
+        };
+        let Ok((iterations, salt, hash)) = parse_password_hash(&user.password_hash) else {
+            return false;
+        };
+        pbkdf2::verify(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            password.as_bytes(),
+            &hash,
+        )
+        .is_ok()
+    }
+
+    /// Sign a grant valid until an expiry
+    pub fn sign(&self, grant: Grant, expires_at: DateTime<Utc>) -> String {
+        let claims = Claims {
+            grant,
+            exp: expires_at.timestamp(),
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let tag = hmac::sign(&self.key, payload.as_bytes());
+        format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(tag.as_ref()))
+    }
+
+    /// Grant of a token, if it is signed by this dashboard and unexpired
+    pub fn verify(&self, token: &str) -> Option<Grant> {
+        let (payload, tag) = token.split_once('.')?;
+        let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+        hmac::verify(&self.key, payload.as_bytes(), &tag).ok()?;
+
+        let claims: Claims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        (claims.exp > Utc::now().timestamp()).then_some(claims.grant)
+    }
+
+    /// Create a share link token for a view, returning it with its expiry.
+    /// The lifetime is capped at the configured maximum.
+    pub fn share(&self, view: ShareView, by: &str, hours: i64) -> (String, DateTime<Utc>) {
+        let max = self
+            .config
+            .as_ref()
+            .map_or_else(default_max_share_hours, |c| c.max_share_hours);
+        let expires_at = Utc::now() + Duration::hours(hours.clamp(1, max.max(1)));
+        let grant = Grant::Share {
+            view,
+            by: by.to_string(),
+        };
+        (self.sign(grant, expires_at), expires_at)
+    }
+
+    /// Sign a user in
+    pub fn start_session(&self, cookies: &CookieJar<'_>, user: &str) {
+        let hours = self
+            .config
+            .as_ref()
+            .map_or_else(default_session_hours, |c| c.session_hours);
+        let token = self.sign(
+            Grant::Session {
+                user: user.to_string(),
+            },
+            Utc::now() + Duration::hours(hours),
+        );
+        cookies.remove(SHARE_COOKIE);
+        cookies.add(self.cookie(SESSION_COOKIE, token, Duration::hours(hours)));
+    }
+
+    /// Remember the share link a visitor followed, returning the path of
+    /// its view
+    pub fn follow_share(&self, cookies: &CookieJar<'_>, token: &str) -> Option<&'static str> {
+        let Grant::Share { view, .. } = self.verify(token)? else {
+            return None;
+        };
+        cookies.add(self.cookie(
+            SHARE_COOKIE,
+            token.to_string(),
+            Duration::hours(default_max_share_hours()),
+        ));
+        Some(view.path())
+    }
+
+    /// Cookie set by the dashboard
+    fn cookie(&self, name: &'static str, value: String, max_age: Duration) -> Cookie<'static> {
+        Cookie::build((name, value))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(self.secure_cookies())
+            .max_age(rocket::time::Duration::seconds(max_age.num_seconds()))
+            .build()
+    }
+
+    async fn discovery(&self, oidc: &OidcConfig) -> Result<&Discovery> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    oidc.issuer.trim_end_matches('/')
+                );
+                let response = self.http.get(&url).send().await?.error_for_status()?;
+                response
+                    .json::<Discovery>()
+                    .await
+                    .with_context(|| format!("Invalid provider metadata at {}", url))
+            })
+            .await
+    }
+
+    /// Start an OpenID Connect sign-in, returning the provider URL to send
+    /// the user to
+    pub async fn start_oidc(&self, cookies: &CookieJar<'_>) -> Result<String> {
+        let oidc = self
+            .oidc()
+            .ok_or_else(|| anyhow!("OpenID Connect is not configured"))?;
+        let discovery = self.discovery(oidc).await?;
+
+        let mut state = [0u8; 16];
+        self.rng
+            .fill(&mut state)
+            .map_err(|_| anyhow!("Failed to generate state"))?;
+        let state = URL_SAFE_NO_PAD.encode(state);
+        cookies.add(self.cookie(OIDC_STATE_COOKIE, state.clone(), Duration::minutes(10)));
+
+        let url = reqwest::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", oidc.client_id.as_str()),
+                ("redirect_uri", oidc.redirect_url.as_str()),
+                ("scope", oidc.scopes.join(" ").as_str()),
+                ("state", state.as_str()),
+            ],
+        )?;
+        Ok(url.to_string())
+    }
+
+    /// Finish an OpenID Connect sign-in, returning the signed-in user
+    pub async fn finish_oidc(
+        &self,
+        cookies: &CookieJar<'_>,
+        code: &str,
+        state: &str,
+    ) -> Result<String> {
+        let oidc = self
+            .oidc()
+            .ok_or_else(|| anyhow!("OpenID Connect is not configured"))?;
+        let expected = cookies
+            .get(OIDC_STATE_COOKIE)
+            .map(|c| c.value().to_string())
+            .ok_or_else(|| anyhow!("No sign-in in progress"))?;
+        cookies.remove(OIDC_STATE_COOKIE);
+        if expected != state {
+            bail!("Sign-in state mismatch");
+        }
+        let discovery = self.discovery(oidc).await?;
+
+        let token: TokenResponse = self
+            .http
+            .post(&discovery.token_endpoint)
+            .basic_auth(&oidc.client_id, Some(&oidc.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", oidc.redirect_url.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .context("Code exchange failed")?
+            .json()
+            .await?;
+        let info: UserInfo = self
+            .http
+            .get(&discovery.userinfo_endpoint)
+            .bearer_auth(&token.access_token)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Userinfo request failed")?
+            .json()
+            .await?;
+
+        let allowed = oidc.allowed_users.is_empty()
+            || oidc.allowed_users.iter().any(|allowed| {
+                Some(allowed) == info.preferred_username.as_ref()
+                    || Some(allowed) == info.email.as_ref()
+            });
+        if !allowed {
+            bail!("{} is not allowed to sign in", info.sub);
+        }
+        Ok(info.preferred_username.or(info.email).unwrap_or(info.sub))
+    }
+}
+
+/// Hash a password for `auth.json`
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .expect("Failed to generate salt");
+    let mut hash = [0u8; ring::digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSWORD_ITERATIONS).unwrap(),
+        &salt,
+        password.as_bytes(),
+        &mut hash,
+    );
+    format!(
+        "pbkdf2-sha256${}${}${}",
+        PASSWORD_ITERATIONS,
+        STANDARD_NO_PAD.encode(salt),
+        STANDARD_NO_PAD.encode(hash)
+    )
+}
+
+/// Split a password hash into its iterations, salt and hash
+fn parse_password_hash(encoded: &str) -> Result<(NonZeroU32, Vec<u8>, Vec<u8>)> {
+    let mut parts = encoded.split('$');
+    if parts.next() != Some("pbkdf2-sha256") {
+        bail!("Unsupported password hash");
+    }
+    let (Some(iterations), Some(salt), Some(hash), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        bail!("Malformed password hash");
+    };
+    let iterations = iterations
+        .parse::<u32>()
+        .ok()
+        .and_then(NonZeroU32::new)
+        .ok_or_else(|| anyhow!("Invalid iteration count"))?;
+    Ok((
+        iterations,
+        STANDARD_NO_PAD.decode(salt)?,
+        STANDARD_NO_PAD.decode(hash)?,
+    ))
+}
+
+/// Who is viewing the dashboard. Routes taking a viewer only run for
+/// signed-in users, or for share links to the view they belong to.
+#[derive(Debug, Clone, Serialize)]
+pub struct Viewer {
+    /// Signed-in user, if any
+    pub user: Option<String>,
+    /// View of the share link followed, if not signed in
+    pub shared: Option<ShareView>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Viewer {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+        let Some(auth) = request.rocket().state::<Auth>() else {
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+        if !auth.enabled() {
+            return Outcome::Success(Viewer {
+                user: None,
+                shared: None,
+            });
+        }
+
+        let cookies = request.cookies();
+        let grant = |name: &str| cookies.get(name).and_then(|c| auth.verify(c.value()));
+        if let Some(Grant::Session { user }) = grant(SESSION_COOKIE) {
+            return Outcome::Success(Viewer {
+                user: Some(user),
+                shared: None,
+            });
+        }
+        if let Some(Grant::Share { view, .. }) = grant(SHARE_COOKIE) {
+            if view.allows(request.uri().path().as_str()) {
+                return Outcome::Success(Viewer {
+                    user: None,
+                    shared: Some(view),
+                });
+            }
+        }
+        Outcome::Error((Status::Unauthorized, ()))
+    }
+}
+
+/// Send visitors who aren't signed in to the login page, and tell API
+/// clients they aren't authorized
+#[rocket::catch(401)]
+pub fn unauthorized(request: &Request<'_>) -> Result<Redirect, (Status, &'static str)> {
+    if request.uri().path().as_str().starts_with("/api/") {
+        Err((Status::Unauthorized, "Sign in to the dashboard first"))
+    } else {
+        Ok(Redirect::to("/login"))
+    }
+}
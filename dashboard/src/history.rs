@@ -0,0 +1,258 @@
+//! Historical metrics storage
+//!
+//! This module keeps the metrics collected by the dashboard in an embedded
+//! SQLite database, so trends can be charted over days and weeks instead of
+//! only showing the latest snapshot. Samples are stored per metric and
+//! instance, and averaged into buckets sized to the requested range when
+//! queried.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::data::{MetricDataPoint, MetricSeries};
+use crate::metrics::ProjectHealthMetrics;
+use crate::runtime::RuntimeMetrics;
+
+/// Instance name of samples that aren't about a running instance
+pub const PROJECT: &str = "project";
+
+/// Metric charted by the history page
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct HistoryMetric {
+    /// Name as stored
+    pub name: &'static str,
+    /// Human-readable title
+    pub title: &'static str,
+    /// Unit of the values
+    pub unit: &'static str,
+}
+
+/// Metrics recorded into the history
+pub const METRICS: &[HistoryMetric] = &[
+    HistoryMetric {
+        name: "request_rate",
+        title: "Request rate",
+        unit: "req/s",
+    },
+    HistoryMetric {
+        name: "error_rate",
+        title: "Error rate",
+        unit: "%",
+    },
+    HistoryMetric {
+        name: "latency_avg_ms",
+        title: "Mean latency",
+        unit: "ms",
+    },
+    HistoryMetric {
+        name: "llm_cost_usd",
+        title: "LLM call cost",
+        unit: "USD",
+    },
+    HistoryMetric {
+        name: "queue_depth",
+        title: "Queue depth",
+        unit: "requests",
+    },
+    HistoryMetric {
+        name: "project_health",
+        title: "Project health",
+        unit: "%",
+    },
+];
+
+/// Time range of a history query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HistoryRange {
+    /// Last hour, in one-minute buckets
+    Hour,
+    /// Last day, in five-minute buckets
+    Day,
+    /// Last week, in one-hour buckets
+    Week,
+    /// Last 30 days, in six-hour buckets
+    Month,
+}
+
+impl HistoryRange {
+    /// Parse a range such as `24h` or `7d`
+    pub fn parse(range: &str) -> Option<Self> {
+        match range {
+            "1h" => Some(HistoryRange::Hour),
+            "24h" | "1d" => Some(HistoryRange::Day),
+            "7d" => Some(HistoryRange::Week),
+            "30d" => Some(HistoryRange::Month),
+            _ => None,
+        }
+    }
+
+    /// Length of the range
+    pub fn duration(&self) -> Duration {
+        match self {
+            HistoryRange::Hour => Duration::hours(1),
+            HistoryRange::Day => Duration::days(1),
+            HistoryRange::Week => Duration::days(7),
+            HistoryRange::Month => Duration::days(30),
+        }
+    }
+
+    /// Width of the buckets samples are averaged into, in seconds
+    pub fn bucket_seconds(&self) -> i64 {
+        match self {
+            HistoryRange::Hour => 60,
+            HistoryRange::Day => 5 * 60,
+            HistoryRange::Week => 60 * 60,
+            HistoryRange::Month => 6 * 60 * 60,
+        }
+    }
+}
+
+/// Samples of the metrics of a poll of the running instances, as
+/// `(metric, instance, value)`
+pub fn runtime_samples(runtime: &RuntimeMetrics) -> Vec<(&'static str, &str, f64)> {
+    let mut samples = Vec::new();
+    for instance in runtime.instances.iter().filter(|i| i.reachable) {
+        let values = [
+            ("request_rate", instance.request_rate),
+            ("error_rate", instance.error_rate.map(|rate| rate * 100.0)),
+            ("latency_avg_ms", instance.latency_avg_ms),
+            ("llm_cost_usd", instance.llm_cost_usd),
+            (
+                "queue_depth",
+                (!instance.queue_depths.is_empty()).then(|| instance.queue_depths.values().sum()),
+            ),
+        ];
+        samples.extend(
+            values
+                .into_iter()
+                .filter_map(|(metric, value)| Some((metric, instance.name.as_str(), value?))),
+        );
+    }
+    samples
+}
+
+/// Embedded time-series store of the collected metrics
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+}
+
+impl HistoryStore {
+    /// Open the store at a path, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open history: {}", path.display()))?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS samples (
+                metric TEXT NOT NULL,
+                instance TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                value REAL NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS samples_metric_timestamp
+                ON samples (metric, timestamp);
+            CREATE INDEX IF NOT EXISTS samples_timestamp ON samples (timestamp);",
+        )
+        .context("Failed to create history schema")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Record samples taken at the same time
+    pub fn record(&self, timestamp: DateTime<Utc>, samples: &[(&str, &str, f64)]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO samples (metric, instance, timestamp, value) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (metric, instance, value) in samples {
+                if value.is_finite() {
+                    insert.execute(params![metric, instance, timestamp.timestamp(), value])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Record a poll of the running instances
+    pub fn record_runtime(&self, runtime: &RuntimeMetrics) -> Result<()> {
+        self.record(runtime.last_updated, &runtime_samples(runtime))
+    }
+
+    /// Record the project health scores
+    pub fn record_project_health(&self, health: &ProjectHealthMetrics) -> Result<()> {
+        self.record(
+            health.last_updated,
+            &[("project_health", PROJECT, health.overall_health)],
+        )
+    }
+
+    /// Series of a metric over a range, one per instance
+    pub fn query(&self, metric: &str, range: HistoryRange) -> Result<Vec<MetricSeries>> {
+        let conn = self.conn.lock().unwrap();
+        let bucket = range.bucket_seconds();
+        let since = (Utc::now() - range.duration()).timestamp();
+
+        let mut select = conn.prepare_cached(
+            "SELECT instance, (timestamp / ?1) * ?1 AS bucket, AVG(value)
+             FROM samples
+             WHERE metric = ?2 AND timestamp >= ?3
+             GROUP BY instance, bucket
+             ORDER BY instance, bucket",
+        )?;
+        let rows = select.query_map(params![bucket, metric, since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, f64>(2)?,
+            ))
+        })?;
+
+        let spec = METRICS.iter().find(|m| m.name == metric);
+        let mut series: Vec<MetricSeries> = Vec::new();
+        for row in rows {
+            let (instance, bucket, value) = row?;
+            if series.last().map(|s| &s.name) != Some(&instance) {
+                series.push(MetricSeries {
+                    name: instance.clone(),
+                    description: spec.map(|m| m.title.to_string()),
+                    unit: spec.map(|m| m.unit.to_string()),
+                    data_points: Vec::new(),
+                    metadata: HashMap::from([("metric".to_string(), metric.to_string())]),
+                });
+            }
+            if let Some(timestamp) = Utc.timestamp_opt(bucket, 0).single() {
+                series
+                    .last_mut()
+                    .unwrap()
+                    .data_points
+                    .push(MetricDataPoint {
+                        timestamp,
+                        value,
+                        label: None,
+                    });
+            }
+        }
+        Ok(series)
+    }
+
+    /// Delete samples older than the retention, returning how many were
+    /// deleted
+    pub fn prune(&self, retention: Duration) -> Result<usize> {
+        let cutoff = (Utc::now() - retention).timestamp();
+        let conn = self.conn.lock().unwrap();
+        Ok(conn.execute("DELETE FROM samples WHERE timestamp < ?1", params![cutoff])?)
+    }
+}
@@ -3,27 +3,39 @@
 //! This is the main entry point for the IntelliRouter dashboard server.
 //! The dashboard provides a unified interface for monitoring the health and quality
 //! of the IntelliRouter project, including code quality, performance benchmarking,
-//! security audit, and documentation generation.
+//! security audit, and documentation generation, along with live runtime metrics
+//! of running IntelliRouter instances.
 
 use chrono::{DateTime, Utc};
+use rocket::form::Form;
 use rocket::fs::{relative, FileServer};
-use rocket::{get, routes};
+use rocket::http::CookieJar;
+use rocket::response::Redirect;
+use rocket::{catchers, delete, get, post, put, routes, FromForm};
 use rocket_dyn_templates::{context, Template};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
+mod alerting;
+mod auth;
 mod components;
 mod data;
+mod history;
 mod metrics;
+mod query;
+mod runtime;
+mod store;
 mod utils;
 
-use data::DashboardData;
-use metrics::{
-    CodeQualityMetrics, DocumentationMetrics, PerformanceMetrics, ProjectHealthMetrics,
-    SecurityMetrics,
-};
+use alerting::{AlertEvent, AlertRule, AlertRuleSpec, Alerting, AlertingConfig, NotifierInfo};
+use auth::{Auth, AuthConfig, ShareView, Viewer};
+use data::{DashboardData, MetricSeries};
+use history::{HistoryRange, HistoryStore};
+use query::{Category, MetricsPage, MetricsQuery};
+use runtime::{RuntimeCollector, RuntimeInstance, RuntimeMetrics};
+use store::DashboardStore;
 
 /// Dashboard configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,6 +54,14 @@ pub struct DashboardConfig {
     pub data_dir: PathBuf,
     /// Refresh interval in seconds
     pub refresh_interval: u64,
+    /// Running instances to poll for live metrics
+    pub instances: Vec<RuntimeInstance>,
+    /// Interval between polls of the running instances in seconds
+    pub runtime_refresh_interval: u64,
+    /// Path to the database of historical metrics
+    pub history_path: PathBuf,
+    /// Days historical metrics are kept for
+    pub history_retention_days: i64,
     /// Dashboard theme
     pub theme: String,
     /// Path to logo file
@@ -62,6 +82,10 @@ impl Default for DashboardConfig {
             static_dir: PathBuf::from("dashboard/static"),
             data_dir: PathBuf::from("dashboard/data"),
             refresh_interval: 60,
+            instances: Vec::new(),
+            runtime_refresh_interval: 5,
+            history_path: PathBuf::from("dashboard/data/history.sqlite"),
+            history_retention_days: 30,
             theme: "default".to_string(),
             logo: None,
             metadata: HashMap::new(),
@@ -72,16 +96,16 @@ impl Default for DashboardConfig {
 /// Dashboard state
 struct DashboardState {
     config: DashboardConfig,
-    data: Arc<Mutex<DashboardData>>,
-    last_updated: Arc<Mutex<DateTime<Utc>>>,
+    store: Arc<DashboardStore>,
+    history: Option<Arc<HistoryStore>>,
+    alerting: Option<Arc<Alerting>>,
 }
 
 /// Home page route
 #[get("/")]
-fn index(state: &rocket::State<DashboardState>) -> Template {
+fn index(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
     let config = &state.config;
-    let data = state.data.lock().unwrap();
-    let last_updated = *state.last_updated.lock().unwrap();
+    let data = state.store.load();
 
     Template::render(
         "index",
@@ -90,21 +114,22 @@ fn index(state: &rocket::State<DashboardState>) -> Template {
             description: &config.description,
             refresh_interval: config.refresh_interval,
             theme: &config.theme,
-            last_updated: last_updated.to_rfc3339(),
-            code_quality: &data.code_quality,
-            performance: &data.performance,
-            security: &data.security,
-            documentation: &data.documentation,
-            project_health: &data.project_health,
+            viewer: &viewer,
+            last_updated: data.last_updated.to_rfc3339(),
+            code_quality: &*data.code_quality,
+            performance: &*data.performance,
+            security: &*data.security,
+            documentation: &*data.documentation,
+            project_health: &*data.project_health,
         },
     )
 }
 
 /// Code quality page route
 #[get("/code-quality")]
-fn code_quality(state: &rocket::State<DashboardState>) -> Template {
+fn code_quality(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
     let config = &state.config;
-    let data = state.data.lock().unwrap();
+    let data = state.store.load();
 
     Template::render(
         "code_quality",
@@ -113,16 +138,17 @@ fn code_quality(state: &rocket::State<DashboardState>) -> Template {
             description: &config.description,
             refresh_interval: config.refresh_interval,
             theme: &config.theme,
-            code_quality: &data.code_quality,
+            viewer: &viewer,
+            code_quality: &*data.code_quality,
         },
     )
 }
 
 /// Performance page route
 #[get("/performance")]
-fn performance(state: &rocket::State<DashboardState>) -> Template {
+fn performance(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
     let config = &state.config;
-    let data = state.data.lock().unwrap();
+    let data = state.store.load();
 
     Template::render(
         "performance",
@@ -131,16 +157,17 @@ fn performance(state: &rocket::State<DashboardState>) -> Template {
             description: &config.description,
             refresh_interval: config.refresh_interval,
             theme: &config.theme,
-            performance: &data.performance,
+            viewer: &viewer,
+            performance: &*data.performance,
         },
     )
 }
 
 /// Security page route
 #[get("/security")]
-fn security(state: &rocket::State<DashboardState>) -> Template {
+fn security(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
     let config = &state.config;
-    let data = state.data.lock().unwrap();
+    let data = state.store.load();
 
     Template::render(
         "security",
@@ -149,16 +176,17 @@ fn security(state: &rocket::State<DashboardState>) -> Template {
             description: &config.description,
             refresh_interval: config.refresh_interval,
             theme: &config.theme,
-            security: &data.security,
+            viewer: &viewer,
+            security: &*data.security,
         },
     )
 }
 
 /// Documentation page route
 #[get("/documentation")]
-fn documentation(state: &rocket::State<DashboardState>) -> Template {
+fn documentation(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
     let config = &state.config;
-    let data = state.data.lock().unwrap();
+    let data = state.store.load();
 
     Template::render(
         "documentation",
@@ -167,22 +195,394 @@ fn documentation(state: &rocket::State<DashboardState>) -> Template {
             description: &config.description,
             refresh_interval: config.refresh_interval,
             theme: &config.theme,
-            documentation: &data.documentation,
+            viewer: &viewer,
+            documentation: &*data.documentation,
         },
     )
 }
 
+/// Runtime page route
+#[get("/runtime")]
+fn runtime_page(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
+    let config = &state.config;
+    let data = state.store.load();
+
+    Template::render(
+        "runtime",
+        context! {
+            title: &config.title,
+            description: &config.description,
+            refresh_interval: config.refresh_interval,
+            runtime_refresh_interval: config.runtime_refresh_interval,
+            theme: &config.theme,
+            viewer: &viewer,
+            runtime: &*data.runtime,
+        },
+    )
+}
+
+/// API route to get the live runtime metrics
+#[get("/api/runtime")]
+fn api_runtime(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+) -> rocket::serde::json::Json<RuntimeMetrics> {
+    rocket::serde::json::Json((*state.store.load().runtime).clone())
+}
+
+/// History page route
+#[get("/history")]
+fn history_page(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
+    let config = &state.config;
+
+    Template::render(
+        "history",
+        context! {
+            title: &config.title,
+            description: &config.description,
+            refresh_interval: config.refresh_interval,
+            theme: &config.theme,
+            viewer: &viewer,
+            history_enabled: state.history.is_some(),
+            metrics: history::METRICS,
+        },
+    )
+}
+
+/// API route to get the history of a metric
+#[get("/api/history/<metric>?<range>")]
+fn api_history(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    metric: &str,
+    range: Option<&str>,
+) -> Result<rocket::serde::json::Json<Vec<MetricSeries>>, rocket::http::Status> {
+    let history = state
+        .history
+        .as_ref()
+        .ok_or(rocket::http::Status::ServiceUnavailable)?;
+    if !history::METRICS.iter().any(|m| m.name == metric) {
+        return Err(rocket::http::Status::NotFound);
+    }
+    let range = match range {
+        Some(range) => HistoryRange::parse(range).ok_or(rocket::http::Status::BadRequest)?,
+        None => HistoryRange::Day,
+    };
+
+    history
+        .query(metric, range)
+        .map(rocket::serde::json::Json)
+        .map_err(|e| {
+            eprintln!("Failed to query history of {}: {:#}", metric, e);
+            rocket::http::Status::InternalServerError
+        })
+}
+
+/// Alerts page route. The page isn't refreshed, which would throw away
+/// rules being edited, and can't be shared.
+#[get("/alerts")]
+fn alerts_page(state: &rocket::State<DashboardState>, viewer: Viewer) -> Template {
+    let config = &state.config;
+
+    Template::render(
+        "alerts",
+        context! {
+            title: &config.title,
+            description: &config.description,
+            theme: &config.theme,
+            viewer: &viewer,
+            private_view: true,
+            alerting_enabled: state.alerting.is_some(),
+            metrics: history::METRICS,
+            instances: &config.instances,
+        },
+    )
+}
+
+/// Alerting of the dashboard, or 503 if it is disabled
+fn require_alerting(state: &DashboardState) -> Result<&Alerting, (rocket::http::Status, String)> {
+    state.alerting.as_deref().ok_or((
+        rocket::http::Status::ServiceUnavailable,
+        "Alerting is disabled".to_string(),
+    ))
+}
+
+/// Respond to a failed alerting call with a 500
+fn alerting_error(e: anyhow::Error) -> (rocket::http::Status, String) {
+    eprintln!("Alerting failed: {:#}", e);
+    (
+        rocket::http::Status::InternalServerError,
+        "Alerting failed".to_string(),
+    )
+}
+
+/// API route to get the alert rules
+#[get("/api/alerts/rules")]
+fn api_alert_rules(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+) -> Result<rocket::serde::json::Json<Vec<AlertRule>>, (rocket::http::Status, String)> {
+    require_alerting(state)?
+        .rules()
+        .map(rocket::serde::json::Json)
+        .map_err(alerting_error)
+}
+
+/// API route to create an alert rule
+#[post("/api/alerts/rules", data = "<spec>")]
+fn api_create_alert_rule(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    spec: rocket::serde::json::Json<AlertRuleSpec>,
+) -> Result<rocket::serde::json::Json<AlertRule>, (rocket::http::Status, String)> {
+    let alerting = require_alerting(state)?;
+    alerting
+        .validate(&spec)
+        .map_err(|e| (rocket::http::Status::BadRequest, e.to_string()))?;
+
+    alerting
+        .create_rule(spec.into_inner())
+        .map(rocket::serde::json::Json)
+        .map_err(alerting_error)
+}
+
+/// API route to edit an alert rule
+#[put("/api/alerts/rules/<id>", data = "<spec>")]
+fn api_update_alert_rule(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    id: &str,
+    spec: rocket::serde::json::Json<AlertRuleSpec>,
+) -> Result<rocket::serde::json::Json<AlertRule>, (rocket::http::Status, String)> {
+    let alerting = require_alerting(state)?;
+    alerting
+        .validate(&spec)
+        .map_err(|e| (rocket::http::Status::BadRequest, e.to_string()))?;
+
+    alerting
+        .update_rule(id, spec.into_inner())
+        .map_err(alerting_error)?
+        .map(rocket::serde::json::Json)
+        .ok_or((
+            rocket::http::Status::NotFound,
+            format!("No alert rule {}", id),
+        ))
+}
+
+/// API route to delete an alert rule
+#[delete("/api/alerts/rules/<id>")]
+fn api_delete_alert_rule(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    id: &str,
+) -> Result<rocket::http::Status, (rocket::http::Status, String)> {
+    if require_alerting(state)?
+        .delete_rule(id)
+        .map_err(alerting_error)?
+    {
+        Ok(rocket::http::Status::NoContent)
+    } else {
+        Err((
+            rocket::http::Status::NotFound,
+            format!("No alert rule {}", id),
+        ))
+    }
+}
+
+/// API route to get the notifiers alert rules can send to
+#[get("/api/alerts/notifiers")]
+fn api_alert_notifiers(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+) -> Result<rocket::serde::json::Json<Vec<NotifierInfo>>, (rocket::http::Status, String)> {
+    Ok(rocket::serde::json::Json(
+        require_alerting(state)?.notifiers(),
+    ))
+}
+
+/// API route to get the latest alerts, firing or resolved
+#[get("/api/alerts?<limit>")]
+fn api_alerts(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    limit: Option<usize>,
+) -> Result<rocket::serde::json::Json<Vec<AlertEvent>>, (rocket::http::Status, String)> {
+    require_alerting(state)?
+        .alerts(limit.unwrap_or(100).min(1000))
+        .map(rocket::serde::json::Json)
+        .map_err(alerting_error)
+}
+
 /// API route to get all metrics
 #[get("/api/metrics")]
-fn api_metrics(state: &rocket::State<DashboardState>) -> rocket::serde::json::Json<DashboardData> {
-    let data = state.data.lock().unwrap();
-    rocket::serde::json::Json(data.clone())
+fn api_metrics(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+) -> rocket::serde::json::Json<DashboardData> {
+    rocket::serde::json::Json(state.store.load().data())
+}
+
+/// API route to query the items of a metric category, such as the
+/// benchmark results of `/api/metrics/performance`
+#[get("/api/metrics/<category>?<params..>")]
+fn api_metrics_category(
+    state: &rocket::State<DashboardState>,
+    _viewer: Viewer,
+    category: &str,
+    params: MetricsQuery,
+) -> Result<rocket::serde::json::Json<MetricsPage>, (rocket::http::Status, String)> {
+    let category = Category::parse(category).ok_or((
+        rocket::http::Status::NotFound,
+        format!("Unknown metric category '{}'", category),
+    ))?;
+    query::run(&state.store.load(), category, &params, Utc::now())
+        .map(rocket::serde::json::Json)
+        .map_err(|message| (rocket::http::Status::BadRequest, message))
+}
+
+/// Login form
+#[derive(FromForm)]
+struct LoginForm {
+    username: String,
+    password: String,
+}
+
+/// Login page route
+#[get("/login?<error>")]
+fn login_page(
+    state: &rocket::State<DashboardState>,
+    auth: &rocket::State<Auth>,
+    error: Option<&str>,
+) -> Template {
+    let config = &state.config;
+
+    Template::render(
+        "login",
+        context! {
+            title: &config.title,
+            theme: &config.theme,
+            static_login: auth.static_login(),
+            oidc_login: auth.oidc_login(),
+            error: error.is_some(),
+        },
+    )
+}
+
+/// Route signing a user in with static credentials
+#[post("/login", data = "<form>")]
+fn login(auth: &rocket::State<Auth>, cookies: &CookieJar<'_>, form: Form<LoginForm>) -> Redirect {
+    if auth.verify_password(&form.username, &form.password) {
+        auth.start_session(cookies, &form.username);
+        Redirect::to("/")
+    } else {
+        eprintln!("Failed sign-in of {}", form.username);
+        Redirect::to("/login?error=credentials")
+    }
+}
+
+/// Route sending a user to the OpenID Connect provider
+#[get("/login/oidc")]
+async fn login_oidc(
+    auth: &rocket::State<Auth>,
+    cookies: &CookieJar<'_>,
+) -> Result<Redirect, rocket::http::Status> {
+    match auth.start_oidc(cookies).await {
+        Ok(url) => Ok(Redirect::to(url)),
+        Err(e) => {
+            eprintln!("Failed to start OpenID Connect sign-in: {:#}", e);
+            Err(rocket::http::Status::BadGateway)
+        }
+    }
+}
+
+/// Route the OpenID Connect provider sends users back to
+#[get("/login/oidc/callback?<code>&<state>")]
+async fn login_oidc_callback(
+    auth: &rocket::State<Auth>,
+    cookies: &CookieJar<'_>,
+    code: &str,
+    state: &str,
+) -> Redirect {
+    match auth.finish_oidc(cookies, code, state).await {
+        Ok(user) => {
+            auth.start_session(cookies, &user);
+            Redirect::to("/")
+        }
+        Err(e) => {
+            eprintln!("Failed OpenID Connect sign-in: {:#}", e);
+            Redirect::to("/login?error=oidc")
+        }
+    }
+}
+
+/// Route signing a user out
+#[post("/logout")]
+fn logout(cookies: &CookieJar<'_>) -> Redirect {
+    cookies.remove(auth::SESSION_COOKIE);
+    Redirect::to("/login")
+}
+
+/// Share link request
+#[derive(Debug, Deserialize)]
+struct ShareRequest {
+    /// View to share
+    view: ShareView,
+    /// Hours the link is valid for
+    #[serde(default = "default_share_hours")]
+    expires_in_hours: i64,
+}
+
+fn default_share_hours() -> i64 {
+    24
+}
+
+/// Share link
+#[derive(Debug, Serialize)]
+struct ShareLink {
+    /// Path of the link, relative to the dashboard
+    path: String,
+    /// Expiry of the link
+    expires_at: DateTime<Utc>,
+}
+
+/// API route to create a read-only share link for a view
+#[post("/api/share", data = "<request>")]
+fn api_share(
+    auth: &rocket::State<Auth>,
+    viewer: Viewer,
+    request: rocket::serde::json::Json<ShareRequest>,
+) -> Result<rocket::serde::json::Json<ShareLink>, rocket::http::Status> {
+    // Links can only be shared by signed-in users, not passed on by viewers
+    // of a shared link
+    let user = viewer.user.ok_or(rocket::http::Status::Forbidden)?;
+    let (token, expires_at) = auth.share(request.view, &user, request.expires_in_hours);
+
+    Ok(rocket::serde::json::Json(ShareLink {
+        path: format!("/share/{}", token),
+        expires_at,
+    }))
+}
+
+/// Route opening a share link
+#[get("/share/<token>")]
+fn share(
+    auth: &rocket::State<Auth>,
+    cookies: &CookieJar<'_>,
+    token: &str,
+) -> Result<Redirect, rocket::http::Status> {
+    auth.follow_share(cookies, token)
+        .map(Redirect::to)
+        .ok_or(rocket::http::Status::Forbidden)
 }
 
 /// Background task to update metrics
+///
+/// Each section is published as soon as it is collected, so pages show new
+/// code quality metrics while security and documentation are still running.
 async fn update_metrics(
-    data: Arc<Mutex<DashboardData>>,
-    last_updated: Arc<Mutex<DateTime<Utc>>>,
+    store: Arc<DashboardStore>,
+    history: Option<Arc<HistoryStore>>,
+    alerting: Option<Arc<Alerting>>,
     config: DashboardConfig,
 ) {
     let mut interval =
@@ -193,9 +593,13 @@ async fn update_metrics(
 
         // Update metrics
         let code_quality = metrics::collect_code_quality_metrics(&config.data_dir).await;
+        store.publish_code_quality(code_quality.clone());
         let performance = metrics::collect_performance_metrics(&config.data_dir).await;
+        store.publish_performance(performance.clone());
         let security = metrics::collect_security_metrics(&config.data_dir).await;
+        store.publish_security(security.clone());
         let documentation = metrics::collect_documentation_metrics(&config.data_dir).await;
+        store.publish_documentation(documentation.clone());
         let project_health = metrics::calculate_project_health(
             &code_quality,
             &performance,
@@ -203,19 +607,61 @@ async fn update_metrics(
             &documentation,
         );
 
-        // Update dashboard data
-        let mut data_lock = data.lock().unwrap();
-        data_lock.code_quality = code_quality;
-        data_lock.performance = performance;
-        data_lock.security = security;
-        data_lock.documentation = documentation;
-        data_lock.project_health = project_health;
+        // Record and prune history
+        if let Some(history) = &history {
+            if let Err(e) = history.record_project_health(&project_health) {
+                eprintln!("Failed to record project health: {:#}", e);
+            }
+            let retention = chrono::Duration::days(config.history_retention_days);
+            if let Err(e) = history.prune(retention) {
+                eprintln!("Failed to prune history: {:#}", e);
+            }
+        }
+
+        // Evaluate the alert rules on project health
+        if let Some(alerting) = &alerting {
+            let samples = [(
+                "project_health",
+                history::PROJECT,
+                project_health.overall_health,
+            )];
+            alerting
+                .evaluate(project_health.last_updated, &samples)
+                .await;
+        }
+
+        // Complete the collection, which also sets its last updated time
+        store.publish_project_health(project_health);
+
+        println!("Metrics updated at {}", store.load().last_updated);
+    }
+}
+
+/// Background task to poll the running instances
+async fn update_runtime_metrics(
+    store: Arc<DashboardStore>,
+    history: Option<Arc<HistoryStore>>,
+    alerting: Option<Arc<Alerting>>,
+    config: DashboardConfig,
+) {
+    let period = std::time::Duration::from_secs(config.runtime_refresh_interval.max(1));
+    let mut collector = RuntimeCollector::new(config.instances.clone(), period);
+    let mut interval = tokio::time::interval(period);
 
-        // Update last updated timestamp
-        let mut last_updated_lock = last_updated.lock().unwrap();
-        *last_updated_lock = Utc::now();
+    loop {
+        interval.tick().await;
 
-        println!("Metrics updated at {}", last_updated_lock);
+        let metrics = collector.collect().await;
+        if let Some(history) = &history {
+            if let Err(e) = history.record_runtime(&metrics) {
+                eprintln!("Failed to record runtime metrics: {:#}", e);
+            }
+        }
+        if let Some(alerting) = &alerting {
+            let samples = history::runtime_samples(&metrics);
+            alerting.evaluate(metrics.last_updated, &samples).await;
+        }
+        store.publish_runtime(metrics);
     }
 }
 
@@ -224,37 +670,109 @@ async fn main() -> Result<(), rocket::Error> {
     // Initialize logger
     env_logger::init();
 
+    // Hash a password read from stdin for auth.json
+    if std::env::args().nth(1).as_deref() == Some("hash-password") {
+        let mut password = String::new();
+        std::io::stdin()
+            .read_line(&mut password)
+            .expect("Failed to read password");
+        println!(
+            "{}",
+            auth::hash_password(password.trim_end_matches(['\r', '\n']))
+        );
+        return Ok(());
+    }
+
     // Load configuration
-    let config = DashboardConfig::default();
+    let mut config = DashboardConfig::default();
+
+    // Load the instances to poll, if any are configured
+    let instances_file = config.data_dir.join("instances.json");
+    if instances_file.exists() {
+        match utils::read_json_file(&instances_file) {
+            Ok(instances) => config.instances = instances,
+            Err(e) => eprintln!("Ignoring {}: {:#}", instances_file.display(), e),
+        }
+    }
+
+    // Require sign-in if authentication is configured. A broken
+    // configuration stops the dashboard rather than leaving it open.
+    let auth_file = config.data_dir.join("auth.json");
+    let auth = if auth_file.exists() {
+        match utils::read_json_file::<AuthConfig>(&auth_file).and_then(Auth::new) {
+            Ok(auth) => auth,
+            Err(e) => {
+                eprintln!("Invalid {}: {:#}", auth_file.display(), e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        println!(
+            "Authentication disabled, anyone who can reach the dashboard can view it. Add {} to require sign-in.",
+            auth_file.display()
+        );
+        Auth::disabled()
+    };
 
     // Create data directory if it doesn't exist
     std::fs::create_dir_all(&config.data_dir).expect("Failed to create data directory");
 
     // Initialize dashboard data
-    let data = Arc::new(Mutex::new(DashboardData {
-        code_quality: CodeQualityMetrics::default(),
-        performance: PerformanceMetrics::default(),
-        security: SecurityMetrics::default(),
-        documentation: DocumentationMetrics::default(),
-        project_health: ProjectHealthMetrics::default(),
-    }));
+    let store = Arc::new(DashboardStore::new());
+
+    // Open the history, running without it if it can't be opened
+    let history = match HistoryStore::open(&config.history_path) {
+        Ok(history) => Some(Arc::new(history)),
+        Err(e) => {
+            eprintln!("History disabled: {:#}", e);
+            None
+        }
+    };
 
-    // Initialize last updated timestamp
-    let last_updated = Arc::new(Mutex::new(Utc::now()));
+    // Open the alert rules next to the history, with the notifiers of
+    // alerting.json, running without alerting if either can't be read
+    let alerting_file = config.data_dir.join("alerting.json");
+    let alerting_config = if alerting_file.exists() {
+        utils::read_json_file(&alerting_file)
+    } else {
+        Ok(AlertingConfig::default())
+    };
+    let alerting = match alerting_config
+        .and_then(|alerting_config| Alerting::open(&config.history_path, alerting_config))
+    {
+        Ok(alerting) => Some(Arc::new(alerting)),
+        Err(e) => {
+            eprintln!("Alerting disabled: {:#}", e);
+            None
+        }
+    };
 
     // Start background task to update metrics
-    let data_clone = Arc::clone(&data);
-    let last_updated_clone = Arc::clone(&last_updated);
+    let store_clone = Arc::clone(&store);
+    let history_clone = history.clone();
+    let alerting_clone = alerting.clone();
     let config_clone = config.clone();
     tokio::spawn(async move {
-        update_metrics(data_clone, last_updated_clone, config_clone).await;
+        update_metrics(store_clone, history_clone, alerting_clone, config_clone).await;
     });
 
+    // Start background task to poll the running instances
+    if !config.instances.is_empty() {
+        let store_clone = Arc::clone(&store);
+        let history_clone = history.clone();
+        let alerting_clone = alerting.clone();
+        let config_clone = config.clone();
+        tokio::spawn(async move {
+            update_runtime_metrics(store_clone, history_clone, alerting_clone, config_clone).await;
+        });
+    }
+
     // Start Rocket server
     let dashboard_state = DashboardState {
         config: config.clone(),
-        data: Arc::clone(&data),
-        last_updated: Arc::clone(&last_updated),
+        store,
+        history,
+        alerting,
     };
 
     let _rocket = rocket::build()
@@ -266,11 +784,32 @@ async fn main() -> Result<(), rocket::Error> {
                 performance,
                 security,
                 documentation,
-                api_metrics
+                runtime_page,
+                history_page,
+                api_metrics,
+                api_metrics_category,
+                api_runtime,
+                api_history,
+                login_page,
+                login,
+                login_oidc,
+                login_oidc_callback,
+                logout,
+                api_share,
+                share,
+                alerts_page,
+                api_alert_rules,
+                api_create_alert_rule,
+                api_update_alert_rule,
+                api_delete_alert_rule,
+                api_alert_notifiers,
+                api_alerts
             ],
         )
+        .register("/", catchers![auth::unauthorized])
         .mount("/static", FileServer::from(relative!("static")))
         .manage(dashboard_state)
+        .manage(auth)
         .attach(Template::fairing())
         .launch()
         .await?;
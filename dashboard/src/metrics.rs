@@ -2,13 +2,14 @@
 //!
 //! This module defines the metrics structures and functions for collecting and processing metrics.
 
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use crate::data::{MetricDataPoint, MetricSeries, Recommendation, Status, StatusLevel};
+use crate::utils;
 
 /// Code quality metrics
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -352,8 +353,13 @@ pub async fn collect_code_quality_metrics(data_dir: &Path) -> CodeQualityMetrics
 
 /// Collect performance metrics
 pub async fn collect_performance_metrics(data_dir: &Path) -> PerformanceMetrics {
-    // In a real implementation, this would parse the metrics files
-    // For now, we'll return a placeholder with some sample data
+    match read_performance_metrics(&data_dir.join("performance")) {
+        Ok(Some(metrics)) => return metrics,
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to read benchmark results: {:#}", e),
+    }
+
+    // Without benchmark results, return a placeholder with some sample data
     let mut metrics = PerformanceMetrics::default();
 
     // Add sample benchmark results
@@ -437,10 +443,158 @@ pub async fn collect_performance_metrics(data_dir: &Path) -> PerformanceMetrics
     metrics
 }
 
+/// Mean time change, in percent, above which a benchmark has regressed
+const REGRESSION_THRESHOLD: f64 = 5.0;
+
+/// Line of a benchmark results file
+#[derive(Debug, Deserialize)]
+struct BenchmarkRecord {
+    component: String,
+    benchmark: String,
+    median_ms: f64,
+    mean_ms: f64,
+    std_dev_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    timestamp: String,
+}
+
+/// Read a benchmark results file
+///
+/// The file has no header, or a `Component,...` one, and one line per
+/// benchmark with its times in milliseconds.
+fn read_benchmark_results(path: &Path) -> Result<Vec<BenchmarkResult>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .from_path(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+    let mut results = Vec::new();
+    for record in reader.records() {
+        let record = record
+            .with_context(|| format!("Failed to read CSV record from file: {}", path.display()))?;
+        if record.get(0) == Some("Component") {
+            continue;
+        }
+        let record: BenchmarkRecord = record
+            .deserialize(None)
+            .with_context(|| format!("Failed to parse CSV record from file: {}", path.display()))?;
+        let timestamp = NaiveDateTime::parse_from_str(&record.timestamp, "%Y-%m-%d %H:%M:%S")
+            .with_context(|| format!("Invalid timestamp '{}'", record.timestamp))?
+            .and_utc();
+        results.push(BenchmarkResult {
+            name: record.benchmark,
+            component: record.component,
+            median_ms: record.median_ms,
+            mean_ms: record.mean_ms,
+            std_dev_ms: record.std_dev_ms,
+            min_ms: record.min_ms,
+            max_ms: record.max_ms,
+            timestamp,
+        });
+    }
+
+    Ok(results)
+}
+
+/// Read performance metrics from the benchmark results files
+///
+/// The latest file holds the current results; every file adds a point to
+/// the trends, and the file before the latest one is the baseline for
+/// regressions. Returns `None` without any file.
+fn read_performance_metrics(dir: &Path) -> Result<Option<PerformanceMetrics>> {
+    let files = utils::find_files(dir, "benchmark_results_", ".csv");
+    if files.is_empty() {
+        return Ok(None);
+    }
+    let runs = files
+        .iter()
+        .map(|file| read_benchmark_results(file))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut metrics = PerformanceMetrics::default();
+    for result in runs.iter().flatten() {
+        let key = format!("{}/{}", result.component, result.name);
+        metrics
+            .performance_trends
+            .entry(key.clone())
+            .or_insert_with(|| MetricSeries {
+                name: key.clone(),
+                description: Some(format!("Performance trend for {}", key)),
+                unit: Some("ms".to_string()),
+                data_points: Vec::new(),
+                metadata: HashMap::new(),
+            })
+            .data_points
+            .push(MetricDataPoint {
+                timestamp: result.timestamp,
+                value: result.mean_ms,
+                label: None,
+            });
+    }
+
+    let latest = &runs[runs.len() - 1];
+    let previous = runs.len().checked_sub(2).map(|i| &runs[i]);
+    for result in latest {
+        let previous = previous.and_then(|previous| {
+            previous
+                .iter()
+                .find(|p| p.component == result.component && p.name == result.name)
+        });
+        if let Some(previous) = previous.filter(|p| p.mean_ms > 0.0) {
+            let change_percentage = (result.mean_ms - previous.mean_ms) / previous.mean_ms * 100.0;
+            if change_percentage > REGRESSION_THRESHOLD {
+                metrics.regressions.push(PerformanceRegression {
+                    component: result.component.clone(),
+                    benchmark: result.name.clone(),
+                    previous_ms: previous.mean_ms,
+                    current_ms: result.mean_ms,
+                    change_percentage,
+                    status: Status {
+                        level: StatusLevel::Warning,
+                        message: "Performance regression detected".to_string(),
+                        details: None,
+                        timestamp: result.timestamp,
+                    },
+                });
+            }
+        }
+        metrics.benchmark_results.insert(
+            format!("{}/{}", result.component, result.name),
+            result.clone(),
+        );
+    }
+
+    metrics.status = Status {
+        level: if metrics.regressions.is_empty() {
+            StatusLevel::Info
+        } else {
+            StatusLevel::Warning
+        },
+        message: "Performance metrics collected".to_string(),
+        details: Some(format!(
+            "{} benchmarks, {} regressions",
+            metrics.benchmark_results.len(),
+            metrics.regressions.len()
+        )),
+        timestamp: Utc::now(),
+    };
+
+    metrics.last_updated = Utc::now();
+
+    Ok(Some(metrics))
+}
+
 /// Collect security metrics
 pub async fn collect_security_metrics(data_dir: &Path) -> SecurityMetrics {
-    // In a real implementation, this would parse the metrics files
-    // For now, we'll return a placeholder with some sample data
+    match read_security_metrics(&data_dir.join("security")) {
+        Ok(Some(metrics)) => return metrics,
+        Ok(None) => {}
+        Err(e) => eprintln!("Failed to read security metrics: {:#}", e),
+    }
+
+    // Without security metrics, return a placeholder with some sample data
     let mut metrics = SecurityMetrics::default();
 
     // Add sample data
@@ -493,6 +647,91 @@ pub async fn collect_security_metrics(data_dir: &Path) -> SecurityMetrics {
     metrics
 }
 
+/// Line of a security metrics file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SecurityRecord {
+    date: String,
+    total: u32,
+    critical: u32,
+    high: u32,
+    medium: u32,
+    low: u32,
+}
+
+/// Read security metrics from the security metrics files
+///
+/// The last line of the latest file holds the current issue counts, and the
+/// `vulnerabilities_<timestamp>.json` file of the same run, if any, the
+/// issues themselves. Every line of every file adds a point to the trend,
+/// the latest one of each day winning. Returns `None` without any file.
+fn read_security_metrics(dir: &Path) -> Result<Option<SecurityMetrics>> {
+    let files = utils::find_files(dir, "security_metrics_", ".csv");
+    let Some(latest) = files.last() else {
+        return Ok(None);
+    };
+
+    let mut trend = BTreeMap::new();
+    let mut current = None;
+    for file in &files {
+        let records: Vec<SecurityRecord> = utils::read_csv_file(file)?;
+        for record in records {
+            let date = NaiveDate::parse_from_str(&record.date, "%Y-%m-%d")
+                .with_context(|| format!("Invalid date '{}'", record.date))?;
+            trend.insert(date, record.total);
+            current = Some(record);
+        }
+    }
+    let Some(current) = current else {
+        return Ok(None);
+    };
+
+    let mut metrics = SecurityMetrics {
+        total_issues: current.total,
+        critical_issues: current.critical,
+        high_issues: current.high,
+        medium_issues: current.medium,
+        low_issues: current.low,
+        ..Default::default()
+    };
+    metrics.issue_trends.data_points = trend
+        .into_iter()
+        .map(|(date, total)| MetricDataPoint {
+            timestamp: date.and_time(NaiveTime::MIN).and_utc(),
+            value: total as f64,
+            label: None,
+        })
+        .collect();
+
+    let vulnerabilities_file = latest
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("security_metrics_"))
+        .and_then(|name| name.strip_suffix(".csv"))
+        .map(|timestamp| dir.join(format!("vulnerabilities_{}.json", timestamp)));
+    if let Some(file) = vulnerabilities_file.filter(|file| file.exists()) {
+        metrics.vulnerabilities = utils::read_json_file(&file)?;
+    }
+
+    metrics.status = Status {
+        level: if metrics.critical_issues > 0 || metrics.high_issues > 0 {
+            StatusLevel::Warning
+        } else {
+            StatusLevel::Info
+        },
+        message: "Security metrics collected".to_string(),
+        details: Some(format!(
+            "{} total issues, {} critical",
+            metrics.total_issues, metrics.critical_issues
+        )),
+        timestamp: Utc::now(),
+    };
+
+    metrics.last_updated = Utc::now();
+
+    Ok(Some(metrics))
+}
+
 /// Collect documentation metrics
 pub async fn collect_documentation_metrics(data_dir: &Path) -> DocumentationMetrics {
     // In a real implementation, this would parse the metrics files
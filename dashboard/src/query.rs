@@ -0,0 +1,331 @@
+//! Metric category queries
+//!
+//! This module backs the per-category JSON API. Every category of the
+//! current snapshot is split into a summary (its scores, status and
+//! recommendations) and a list of items: trend data points, benchmark
+//! results, vulnerabilities or polled instances. Items can be filtered by
+//! time range and component, sorted by any of their fields and paginated.
+
+use chrono::{DateTime, Duration, Utc};
+use rocket::FromForm;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+use crate::data::MetricSeries;
+use crate::store::Snapshot;
+
+/// Items returned when no limit is given
+pub const DEFAULT_LIMIT: usize = 100;
+/// Most items returned by one query
+pub const MAX_LIMIT: usize = 1000;
+
+/// Metric category served by the API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Category {
+    CodeQuality,
+    Performance,
+    Security,
+    Documentation,
+    ProjectHealth,
+    Runtime,
+}
+
+impl Category {
+    /// Categories, by their name in the API
+    pub const ALL: &'static [(&'static str, Category)] = &[
+        ("code-quality", Category::CodeQuality),
+        ("performance", Category::Performance),
+        ("security", Category::Security),
+        ("documentation", Category::Documentation),
+        ("project-health", Category::ProjectHealth),
+        ("runtime", Category::Runtime),
+    ];
+
+    /// Parse a category name such as `code-quality`
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, category)| *category)
+    }
+
+    /// Field of the items matched by the `component` filter
+    fn component_field(&self) -> Option<&'static str> {
+        match self {
+            Category::CodeQuality | Category::Documentation | Category::ProjectHealth => {
+                Some("series")
+            }
+            Category::Performance => Some("component"),
+            Category::Security => Some("location"),
+            Category::Runtime => Some("role"),
+        }
+    }
+
+    /// Field of the items matched by the `from` and `to` filters
+    fn timestamp_field(&self) -> Option<&'static str> {
+        match self {
+            Category::Security => None,
+            Category::Runtime => Some("last_polled"),
+            _ => Some("timestamp"),
+        }
+    }
+
+    /// Summary of the category, without its items
+    fn summary(&self, snapshot: &Snapshot) -> Value {
+        let (value, items) = match self {
+            Category::CodeQuality => (
+                to_value(&*snapshot.code_quality),
+                &[
+                    "warning_trends",
+                    "test_coverage_trends",
+                    "doc_coverage_trends",
+                ][..],
+            ),
+            Category::Performance => (
+                to_value(&*snapshot.performance),
+                &["benchmark_results", "performance_trends"][..],
+            ),
+            Category::Security => (to_value(&*snapshot.security), &["vulnerabilities"][..]),
+            Category::Documentation => {
+                (to_value(&*snapshot.documentation), &["coverage_trends"][..])
+            }
+            Category::ProjectHealth => {
+                (to_value(&*snapshot.project_health), &["health_trends"][..])
+            }
+            Category::Runtime => (to_value(&*snapshot.runtime), &["instances"][..]),
+        };
+        match value {
+            Value::Object(mut fields) => {
+                for field in items {
+                    fields.remove(*field);
+                }
+                Value::Object(fields)
+            }
+            value => value,
+        }
+    }
+
+    /// Items of the category
+    fn items(&self, snapshot: &Snapshot) -> Vec<Value> {
+        match self {
+            Category::CodeQuality => {
+                let metrics = &snapshot.code_quality;
+                [
+                    ("warnings", &metrics.warning_trends),
+                    ("test_coverage", &metrics.test_coverage_trends),
+                    ("doc_coverage", &metrics.doc_coverage_trends),
+                ]
+                .into_iter()
+                .flat_map(|(name, series)| data_points(name, series))
+                .collect()
+            }
+            Category::Performance => {
+                let mut results: Vec<_> = snapshot.performance.benchmark_results.iter().collect();
+                results.sort_by(|a, b| a.0.cmp(b.0));
+                results
+                    .into_iter()
+                    .map(|(_, result)| to_value(result))
+                    .collect()
+            }
+            Category::Security => snapshot
+                .security
+                .vulnerabilities
+                .iter()
+                .map(to_value)
+                .collect(),
+            Category::Documentation => {
+                let mut trends: Vec<_> = snapshot.documentation.coverage_trends.iter().collect();
+                trends.sort_by(|a, b| a.0.cmp(b.0));
+                trends
+                    .into_iter()
+                    .flat_map(|(name, series)| data_points(name, series))
+                    .collect()
+            }
+            Category::ProjectHealth => {
+                data_points("overall_health", &snapshot.project_health.health_trends)
+            }
+            Category::Runtime => snapshot.runtime.instances.iter().map(to_value).collect(),
+        }
+    }
+}
+
+/// Data points of a trend series as items
+fn data_points(name: &str, series: &MetricSeries) -> Vec<Value> {
+    series
+        .data_points
+        .iter()
+        .map(|point| {
+            let mut item = Map::new();
+            item.insert("series".to_string(), Value::from(name));
+            item.insert("timestamp".to_string(), to_value(point.timestamp));
+            item.insert("value".to_string(), Value::from(point.value));
+            if let Some(label) = &point.label {
+                item.insert("label".to_string(), Value::from(label.as_str()));
+            }
+            if let Some(unit) = &series.unit {
+                item.insert("unit".to_string(), Value::from(unit.as_str()));
+            }
+            Value::Object(item)
+        })
+        .collect()
+}
+
+fn to_value(value: impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// Filters, sorting and pagination of a category query
+#[derive(Debug, Clone, Default, FromForm)]
+pub struct MetricsQuery {
+    /// Earliest timestamp, RFC 3339 or relative to now such as `24h`
+    pub from: Option<String>,
+    /// Latest timestamp, RFC 3339 or relative to now such as `1h`
+    pub to: Option<String>,
+    /// Component, trend series, location or role the items must have
+    pub component: Option<String>,
+    /// Field to sort the items by
+    pub sort: Option<String>,
+    /// Sort order, `asc` (default) or `desc`
+    pub order: Option<String>,
+    /// Items to return
+    pub limit: Option<usize>,
+    /// Items to skip
+    pub offset: Option<usize>,
+}
+
+/// Result of a category query
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricsPage {
+    /// Category name
+    pub category: &'static str,
+    /// Summary of the category, without its items
+    pub summary: Value,
+    /// Items matching the filters
+    pub total: usize,
+    /// Items skipped
+    pub offset: usize,
+    /// Most items returned
+    pub limit: usize,
+    /// Returned items
+    pub items: Vec<Value>,
+}
+
+/// Run a category query on a snapshot
+///
+/// Items are sorted by timestamp unless another field is given, with items
+/// missing the field last. Fails with a message for the client on invalid
+/// parameters, or on filters the category has no field for.
+pub fn run(
+    snapshot: &Snapshot,
+    category: Category,
+    query: &MetricsQuery,
+    now: DateTime<Utc>,
+) -> Result<MetricsPage, String> {
+    let from = query
+        .from
+        .as_deref()
+        .map(|v| parse_time(v, now))
+        .transpose()?;
+    let to = query
+        .to
+        .as_deref()
+        .map(|v| parse_time(v, now))
+        .transpose()?;
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(order) => return Err(format!("Invalid order '{}', use asc or desc", order)),
+    };
+    let name = Category::ALL
+        .iter()
+        .find(|(_, c)| *c == category)
+        .map(|(name, _)| *name)
+        .unwrap_or_default();
+
+    let mut items = category.items(snapshot);
+
+    if from.is_some() || to.is_some() {
+        let field = category
+            .timestamp_field()
+            .ok_or_else(|| format!("Items of {} have no timestamp", name))?;
+        items.retain(|item| {
+            let Some(timestamp) = item[field]
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            else {
+                return false;
+            };
+            from.is_none_or(|from| timestamp >= from) && to.is_none_or(|to| timestamp <= to)
+        });
+    }
+    if let Some(component) = &query.component {
+        let field = category
+            .component_field()
+            .ok_or_else(|| format!("Items of {} have no component", name))?;
+        items.retain(|item| item[field].as_str() == Some(component.as_str()));
+    }
+
+    let sort = query.sort.as_deref().or(category.timestamp_field());
+    if let Some(field) = sort {
+        if !items.is_empty() && items.iter().all(|item| item.get(field).is_none()) {
+            return Err(format!("Items of {} have no field '{}'", name, field));
+        }
+        items.sort_by(|a, b| match (&a[field], &b[field]) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) => Ordering::Greater,
+            (_, Value::Null) => Ordering::Less,
+            (a, b) if descending => compare(b, a),
+            (a, b) => compare(a, b),
+        });
+    }
+
+    let total = items.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    Ok(MetricsPage {
+        category: name,
+        summary: category.summary(snapshot),
+        total,
+        offset,
+        limit,
+        items: items.into_iter().skip(offset).take(limit).collect(),
+    })
+}
+
+/// Parse a timestamp, RFC 3339 or a duration before `now` such as `30m`,
+/// `24h` or `7d`
+pub fn parse_time(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    let invalid = || {
+        format!(
+            "Invalid time '{}', use RFC 3339 or a duration such as 24h",
+            value
+        )
+    };
+    let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let duration = match unit {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => return Err(invalid()),
+    };
+    Ok(now - duration)
+}
+
+/// Order of two field values present on both items
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
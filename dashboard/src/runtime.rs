@@ -0,0 +1,475 @@
+//! Live runtime metrics
+//!
+//! This module polls running IntelliRouter role instances for the runtime
+//! view of the dashboard. Every instance is asked for its `/diagnostics`,
+//! which give its status, model health and circuit breaker states, and
+//! optionally scraped on its Prometheus endpoint for request rates and queue
+//! depths.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use intellirouter::modules::health::{DiagnosticsResponse, HealthStatus};
+use intellirouter::modules::telemetry::catalog;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use crate::data::{Status, StatusLevel};
+
+/// Running role instance polled by the dashboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeInstance {
+    /// Display name
+    pub name: String,
+    /// Role of the instance, e.g. `router`
+    pub role: String,
+    /// Base URL of the instance's health endpoints
+    pub url: String,
+    /// URL of the instance's Prometheus endpoint
+    #[serde(default)]
+    pub metrics_url: Option<String>,
+}
+
+/// Health of a model as reported by an instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelHealth {
+    /// Model ID
+    pub id: String,
+    /// Provider
+    pub provider: Option<String>,
+    /// Status reported by the instance
+    pub status: String,
+}
+
+/// State of a circuit breaker as reported by an instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerStatus {
+    /// Name of the protected dependency
+    pub name: String,
+    /// State (`closed`, `open` or `half_open`)
+    pub state: String,
+}
+
+/// Live metrics of one instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceMetrics {
+    /// Display name
+    pub name: String,
+    /// Role of the instance
+    pub role: String,
+    /// Base URL of the instance
+    pub url: String,
+    /// Whether the last poll reached the instance
+    pub reachable: bool,
+    /// Health status
+    pub health: Option<HealthStatus>,
+    /// Version
+    pub version: Option<String>,
+    /// Uptime in seconds
+    pub uptime_seconds: Option<u64>,
+    /// HTTP requests per second since the previous poll
+    pub request_rate: Option<f64>,
+    /// Share of the HTTP requests since the previous poll that failed with
+    /// a server error
+    pub error_rate: Option<f64>,
+    /// Mean HTTP latency since the previous poll in milliseconds
+    pub latency_avg_ms: Option<f64>,
+    /// Estimated cost of the latest call to each model in USD, summed
+    pub llm_cost_usd: Option<f64>,
+    /// Models the instance knows
+    pub models_total: Option<u64>,
+    /// Models currently available
+    pub models_available: Option<u64>,
+    /// Models and their health, listed by instances with verbose diagnostics
+    pub models: Vec<ModelHealth>,
+    /// Circuit breakers and their states
+    pub circuit_breakers: Vec<CircuitBreakerStatus>,
+    /// Depth of each queue, e.g. `provider:openai` for the requests in
+    /// flight to a provider
+    pub queue_depths: BTreeMap<String, f64>,
+    /// Error of the last poll
+    pub error: Option<String>,
+    /// Time of the last poll
+    pub last_polled: DateTime<Utc>,
+}
+
+/// Live metrics of all polled instances
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetrics {
+    /// Instances
+    pub instances: Vec<InstanceMetrics>,
+    /// Status
+    pub status: Status,
+    /// Last updated
+    pub last_updated: DateTime<Utc>,
+}
+
+impl Default for RuntimeMetrics {
+    fn default() -> Self {
+        Self {
+            instances: Vec::new(),
+            status: Status {
+                level: StatusLevel::Info,
+                message: "No instances configured".to_string(),
+                details: None,
+                timestamp: Utc::now(),
+            },
+            last_updated: Utc::now(),
+        }
+    }
+}
+
+/// Counters kept to turn totals into rates over the time between polls
+#[derive(Debug, Clone, Copy)]
+struct CounterSample {
+    at: DateTime<Utc>,
+    requests: f64,
+    server_errors: f64,
+    latency_sum: f64,
+    latency_count: f64,
+}
+
+/// Collector polling role instances
+pub struct RuntimeCollector {
+    client: reqwest::Client,
+    instances: Vec<RuntimeInstance>,
+    previous: HashMap<String, CounterSample>,
+}
+
+impl RuntimeCollector {
+    /// Create a collector for the given instances
+    pub fn new(instances: Vec<RuntimeInstance>, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+            instances,
+            previous: HashMap::new(),
+        }
+    }
+
+    /// Poll every instance
+    pub async fn collect(&mut self) -> RuntimeMetrics {
+        let mut instances = Vec::with_capacity(self.instances.len());
+        for instance in self.instances.clone() {
+            instances.push(self.poll(&instance).await);
+        }
+
+        let unreachable = instances.iter().filter(|i| !i.reachable).count();
+        let unhealthy = instances
+            .iter()
+            .filter(|i| i.health == Some(HealthStatus::Unhealthy))
+            .count();
+        let degraded = instances
+            .iter()
+            .filter(|i| i.health == Some(HealthStatus::Degraded))
+            .count();
+        let open_breakers = instances
+            .iter()
+            .flat_map(|i| &i.circuit_breakers)
+            .filter(|b| b.state != "closed")
+            .count();
+
+        let level = if instances.is_empty() {
+            StatusLevel::Info
+        } else if unreachable > 0 || unhealthy > 0 {
+            StatusLevel::Error
+        } else if degraded > 0 || open_breakers > 0 {
+            StatusLevel::Warning
+        } else {
+            StatusLevel::Success
+        };
+
+        RuntimeMetrics {
+            status: Status {
+                level,
+                message: "Runtime metrics collected".to_string(),
+                details: Some(format!(
+                    "{} instances, {} unreachable, {} unhealthy, {} circuit breakers not closed",
+                    instances.len(),
+                    unreachable,
+                    unhealthy,
+                    open_breakers
+                )),
+                timestamp: Utc::now(),
+            },
+            instances,
+            last_updated: Utc::now(),
+        }
+    }
+
+    /// Poll one instance
+    async fn poll(&mut self, instance: &RuntimeInstance) -> InstanceMetrics {
+        let mut metrics = InstanceMetrics {
+            name: instance.name.clone(),
+            role: instance.role.clone(),
+            url: instance.url.clone(),
+            reachable: false,
+            health: None,
+            version: None,
+            uptime_seconds: None,
+            request_rate: None,
+            error_rate: None,
+            latency_avg_ms: None,
+            llm_cost_usd: None,
+            models_total: None,
+            models_available: None,
+            models: Vec::new(),
+            circuit_breakers: Vec::new(),
+            queue_depths: BTreeMap::new(),
+            error: None,
+            last_polled: Utc::now(),
+        };
+
+        match self.diagnostics(instance).await {
+            Ok(diagnostics) => {
+                metrics.reachable = true;
+                metrics.health = Some(diagnostics.status);
+                metrics.version = Some(diagnostics.version.clone());
+                metrics.uptime_seconds = Some(diagnostics.uptime_seconds);
+                metrics.models_total = diagnostics
+                    .diagnostics
+                    .get("total_models")
+                    .and_then(|v| v.as_u64());
+                metrics.models_available = diagnostics
+                    .diagnostics
+                    .get("available_models")
+                    .and_then(|v| v.as_u64());
+                metrics.models = models(&diagnostics.diagnostics);
+                metrics.circuit_breakers = circuit_breakers(&diagnostics.diagnostics);
+            }
+            Err(e) => {
+                log::warn!("Failed to poll {}: {:#}", instance.name, e);
+                metrics.error = Some(format!("{:#}", e));
+                return metrics;
+            }
+        }
+
+        if let Some(metrics_url) = &instance.metrics_url {
+            match self.scrape(metrics_url).await {
+                Ok(text) => {
+                    let samples = parse_prometheus(&text);
+                    self.apply_counters(instance, &samples, &mut metrics);
+                    metrics.llm_cost_usd =
+                        sum(&samples, &catalog::prometheus_name(catalog::LLM_COST));
+                    metrics.queue_depths = queue_depths(&samples);
+                }
+                Err(e) => {
+                    log::warn!("Failed to scrape {}: {:#}", metrics_url, e);
+                    metrics.error = Some(format!("{:#}", e));
+                }
+            }
+        }
+
+        metrics
+    }
+
+    async fn diagnostics(&self, instance: &RuntimeInstance) -> Result<DiagnosticsResponse> {
+        let url = format!("{}/diagnostics", instance.url.trim_end_matches('/'));
+        self.client
+            .get(&url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("Failed to parse diagnostics from {}", url))
+    }
+
+    async fn scrape(&self, url: &str) -> Result<String> {
+        self.client
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?
+            .error_for_status()?
+            .text()
+            .await
+            .with_context(|| format!("Failed to read metrics from {}", url))
+    }
+
+    /// Compute request rate, error rate and latency since the previous
+    /// scrape of an instance
+    fn apply_counters(
+        &mut self,
+        instance: &RuntimeInstance,
+        samples: &[Sample],
+        metrics: &mut InstanceMetrics,
+    ) {
+        let requests = catalog::prometheus_name(catalog::HTTP_REQUESTS);
+        let latency = catalog::prometheus_name(catalog::HTTP_LATENCY);
+        let Some(total) = counter(samples, &requests) else {
+            return;
+        };
+        let current = CounterSample {
+            at: Utc::now(),
+            requests: total,
+            server_errors: samples
+                .iter()
+                .filter(|s| s.name == requests || s.name == format!("{}_total", requests))
+                .filter(|s| s.labels.get("status").is_some_and(|c| c.starts_with('5')))
+                .map(|s| s.value)
+                .sum(),
+            latency_sum: sum(samples, &format!("{}_sum", latency)).unwrap_or(0.0),
+            latency_count: sum(samples, &format!("{}_count", latency)).unwrap_or(0.0),
+        };
+        let Some(previous) = self.previous.insert(instance.name.clone(), current) else {
+            return;
+        };
+
+        let elapsed = (current.at - previous.at).num_milliseconds() as f64 / 1000.0;
+        // A counter going down means the instance restarted
+        if elapsed <= 0.0 || current.requests < previous.requests {
+            return;
+        }
+        let requests = current.requests - previous.requests;
+        metrics.request_rate = Some(requests / elapsed);
+        if requests > 0.0 {
+            metrics.error_rate = Some((current.server_errors - previous.server_errors) / requests);
+        }
+        let latency_count = current.latency_count - previous.latency_count;
+        if latency_count > 0.0 {
+            metrics.latency_avg_ms =
+                Some((current.latency_sum - previous.latency_sum) / latency_count);
+        }
+    }
+}
+
+/// Models listed in an instance's diagnostics
+fn models(diagnostics: &HashMap<String, serde_json::Value>) -> Vec<ModelHealth> {
+    diagnostics
+        .get("models")
+        .and_then(|models| models.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|model| {
+                    Some(ModelHealth {
+                        id: model.get("id")?.as_str()?.to_string(),
+                        provider: model
+                            .get("provider")
+                            .and_then(|p| p.as_str())
+                            .map(str::to_string),
+                        status: model
+                            .get("status")
+                            .and_then(|s| s.as_str())
+                            .unwrap_or("unknown")
+                            .to_lowercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Circuit breaker states in an instance's diagnostics
+///
+/// Roles report them as a `circuit_breakers` object mapping each protected
+/// dependency to its state.
+fn circuit_breakers(diagnostics: &HashMap<String, serde_json::Value>) -> Vec<CircuitBreakerStatus> {
+    let mut breakers: Vec<CircuitBreakerStatus> = diagnostics
+        .get("circuit_breakers")
+        .and_then(|breakers| breakers.as_object())
+        .map(|breakers| {
+            breakers
+                .iter()
+                .filter_map(|(name, state)| {
+                    Some(CircuitBreakerStatus {
+                        name: name.clone(),
+                        state: state.as_str()?.to_lowercase(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    breakers.sort_by(|a, b| a.name.cmp(&b.name));
+    breakers
+}
+
+/// Queue depths among the gauges of a scrape
+fn queue_depths(samples: &[Sample]) -> BTreeMap<String, f64> {
+    let mut depths = BTreeMap::new();
+
+    let in_flight = catalog::prometheus_name(catalog::PROVIDER_POOL_IN_FLIGHT);
+    for sample in samples.iter().filter(|s| s.name == in_flight) {
+        let provider = sample
+            .labels
+            .get("provider")
+            .map_or("unknown", String::as_str);
+        *depths
+            .entry(format!("provider:{}", provider))
+            .or_insert(0.0) += sample.value;
+    }
+    for (queue, metric) in [
+        ("coalesced", catalog::COALESCING_IN_FLIGHT),
+        ("stalled_streams", catalog::STREAMS_STALLED_ACTIVE),
+    ] {
+        if let Some(depth) = sum(samples, &catalog::prometheus_name(metric)) {
+            depths.insert(queue.to_string(), depth);
+        }
+    }
+
+    depths
+}
+
+/// Sample of a Prometheus text exposition
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    name: String,
+    labels: HashMap<String, String>,
+    value: f64,
+}
+
+/// Parse the samples of a Prometheus text exposition
+fn parse_prometheus(text: &str) -> Vec<Sample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (series, rest) = if line.contains('{') {
+                line.split_at(line.rfind('}')? + 1)
+            } else {
+                line.split_at(line.find(char::is_whitespace)?)
+            };
+            let value = rest.split_whitespace().next()?.parse().ok()?;
+
+            let (name, labels) = match series.split_once('{') {
+                Some((name, labels)) => (name, parse_labels(labels.trim_end_matches('}'))),
+                None => (series, HashMap::new()),
+            };
+            Some(Sample {
+                name: name.to_string(),
+                labels,
+                value,
+            })
+        })
+        .collect()
+}
+
+fn parse_labels(labels: &str) -> HashMap<String, String> {
+    labels
+        .split(',')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            Some((
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Total of a counter across labels, whether or not exported with the
+/// `_total` suffix
+fn counter(samples: &[Sample], name: &str) -> Option<f64> {
+    sum(samples, name).or_else(|| sum(samples, &format!("{}_total", name)))
+}
+
+/// Sum of the samples of a metric across labels
+fn sum(samples: &[Sample], name: &str) -> Option<f64> {
+    let mut matching = samples.iter().filter(|s| s.name == name).peekable();
+    matching.peek()?;
+    Some(matching.map(|s| s.value).sum())
+}
@@ -0,0 +1,134 @@
+//! Dashboard data store
+//!
+//! Collected metrics are published as immutable snapshots behind an
+//! `ArcSwap`. Pages and API routes load the current snapshot without taking
+//! a lock, so they never wait on a collection in progress. Collectors
+//! publish each section as soon as it is ready; publishing swaps in a new
+//! snapshot that shares every other section with the previous one.
+
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use crate::data::DashboardData;
+use crate::metrics::{
+    CodeQualityMetrics, DocumentationMetrics, PerformanceMetrics, ProjectHealthMetrics,
+    SecurityMetrics,
+};
+use crate::runtime::RuntimeMetrics;
+
+/// Metrics published at one point in time
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// Code quality metrics
+    pub code_quality: Arc<CodeQualityMetrics>,
+    /// Performance metrics
+    pub performance: Arc<PerformanceMetrics>,
+    /// Security metrics
+    pub security: Arc<SecurityMetrics>,
+    /// Documentation metrics
+    pub documentation: Arc<DocumentationMetrics>,
+    /// Project health metrics
+    pub project_health: Arc<ProjectHealthMetrics>,
+    /// Live metrics of the running instances
+    pub runtime: Arc<RuntimeMetrics>,
+    /// When the project metrics were last fully collected
+    pub last_updated: DateTime<Utc>,
+}
+
+impl Default for Snapshot {
+    fn default() -> Self {
+        Self {
+            code_quality: Arc::new(CodeQualityMetrics::default()),
+            performance: Arc::new(PerformanceMetrics::default()),
+            security: Arc::new(SecurityMetrics::default()),
+            documentation: Arc::new(DocumentationMetrics::default()),
+            project_health: Arc::new(ProjectHealthMetrics::default()),
+            runtime: Arc::new(RuntimeMetrics::default()),
+            last_updated: Utc::now(),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Project metrics of the snapshot
+    pub fn data(&self) -> DashboardData {
+        DashboardData {
+            code_quality: (*self.code_quality).clone(),
+            performance: (*self.performance).clone(),
+            security: (*self.security).clone(),
+            documentation: (*self.documentation).clone(),
+            project_health: (*self.project_health).clone(),
+        }
+    }
+}
+
+/// Store of the dashboard's current snapshot
+#[derive(Debug, Default)]
+pub struct DashboardStore {
+    current: ArcSwap<Snapshot>,
+}
+
+impl DashboardStore {
+    /// Create a store with empty metrics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current snapshot
+    pub fn load(&self) -> Arc<Snapshot> {
+        self.current.load_full()
+    }
+
+    /// Publish code quality metrics
+    pub fn publish_code_quality(&self, metrics: CodeQualityMetrics) {
+        let metrics = Arc::new(metrics);
+        self.update(|snapshot| snapshot.code_quality = Arc::clone(&metrics));
+    }
+
+    /// Publish performance metrics
+    pub fn publish_performance(&self, metrics: PerformanceMetrics) {
+        let metrics = Arc::new(metrics);
+        self.update(|snapshot| snapshot.performance = Arc::clone(&metrics));
+    }
+
+    /// Publish security metrics
+    pub fn publish_security(&self, metrics: SecurityMetrics) {
+        let metrics = Arc::new(metrics);
+        self.update(|snapshot| snapshot.security = Arc::clone(&metrics));
+    }
+
+    /// Publish documentation metrics
+    pub fn publish_documentation(&self, metrics: DocumentationMetrics) {
+        let metrics = Arc::new(metrics);
+        self.update(|snapshot| snapshot.documentation = Arc::clone(&metrics));
+    }
+
+    /// Publish project health metrics, which complete a collection
+    pub fn publish_project_health(&self, metrics: ProjectHealthMetrics) {
+        let metrics = Arc::new(metrics);
+        let now = Utc::now();
+        self.update(|snapshot| {
+            snapshot.project_health = Arc::clone(&metrics);
+            snapshot.last_updated = now;
+        });
+    }
+
+    /// Publish live metrics of the running instances
+    pub fn publish_runtime(&self, metrics: RuntimeMetrics) {
+        let metrics = Arc::new(metrics);
+        self.update(|snapshot| snapshot.runtime = Arc::clone(&metrics));
+    }
+
+    /// Swap in a copy of the current snapshot changed by `change`
+    ///
+    /// `change` runs again if another section was published meanwhile, so
+    /// concurrent publishers never drop each other's sections.
+    fn update(&self, change: impl Fn(&mut Snapshot)) {
+        self.current.rcu(|current| {
+            let mut next = Snapshot::clone(current);
+            change(&mut next);
+            next
+        });
+    }
+}
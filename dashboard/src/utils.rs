@@ -120,6 +120,28 @@ pub fn find_latest_file(dir: &Path, prefix: &str, extension: &str) -> Option<std
     latest_file
 }
 
+/// Find the files with a given prefix and extension, sorted by name
+///
+/// Data files carry their timestamp in their name, so this sorts them from
+/// oldest to latest.
+pub fn find_files(dir: &Path, prefix: &str, extension: &str) -> Vec<std::path::PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            file_name.starts_with(prefix) && file_name.ends_with(extension)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
 /// Format a timestamp as a human-readable string
 pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
     timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
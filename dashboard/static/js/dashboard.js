@@ -48,6 +48,18 @@ document.addEventListener('DOMContentLoaded', function () {
 
     // Setup API polling
     setupApiPolling();
+
+    // Setup runtime polling
+    setupRuntimePolling();
+
+    // Setup history charts
+    setupHistoryCharts();
+
+    // Setup share links
+    setupShareLinks();
+
+    // Setup alert rules and history
+    setupAlerts();
 });
 
 /**
@@ -320,4 +332,430 @@ function updateTable(element, data) {
             tbody.appendChild(row);
         });
     }
-}
\ No newline at end of file
+}
+
+/**
+ * Setup polling of the live runtime metrics
+ */
+function setupRuntimePolling() {
+    const container = document.querySelector('[data-runtime-poll]');
+    if (!container) {
+        return;
+    }
+
+    const endpoint = container.getAttribute('data-runtime-poll');
+    const interval = parseInt(container.getAttribute('data-runtime-poll-interval'), 10) || 5000;
+
+    const poll = () => {
+        fetch(endpoint)
+            .then(response => response.json())
+            .then(renderRuntime)
+            .catch(error => {
+                console.error('Error polling runtime metrics:', error);
+            });
+    };
+
+    poll();
+    setInterval(poll, interval);
+}
+
+/**
+ * Render the live runtime metrics
+ * @param {Object} data - The runtime metrics
+ */
+function renderRuntime(data) {
+    const status = document.getElementById('runtime-status');
+    if (status) {
+        status.textContent = data.status.message;
+    }
+    const details = document.getElementById('runtime-status-details');
+    if (details) {
+        details.textContent = data.status.details || '';
+    }
+    const lastUpdated = document.getElementById('runtime-last-updated');
+    if (lastUpdated) {
+        lastUpdated.textContent = formatDate(new Date(data.last_updated));
+    }
+
+    const tbody = document.querySelector('#runtime-instances tbody');
+    if (!tbody) {
+        return;
+    }
+    tbody.innerHTML = '';
+
+    data.instances.forEach(function (instance) {
+        const notClosed = instance.circuit_breakers.filter(b => b.state !== 'closed');
+        const cells = [
+            instance.name,
+            instance.role,
+            instance.reachable ? (instance.health || 'unknown') : 'unreachable',
+            instance.request_rate === null ? '-' : instance.request_rate.toFixed(2),
+            instance.models_total === null
+                ? '-'
+                : instance.models_available + '/' + instance.models_total + ' available',
+            instance.circuit_breakers.length
+                ? (notClosed.length
+                    ? notClosed.map(b => b.name + ': ' + b.state).join(', ')
+                    : 'all closed')
+                : '-',
+            Object.entries(instance.queue_depths).map(([queue, depth]) => queue + ': ' + depth).join(', ') || '-',
+            instance.uptime_seconds === null ? '-' : formatUptime(instance.uptime_seconds),
+        ];
+
+        const row = document.createElement('tr');
+        if (!instance.reachable || instance.health === 'unhealthy') {
+            row.classList.add('table-danger');
+        } else if (instance.health === 'degraded' || notClosed.length) {
+            row.classList.add('table-warning');
+        }
+        if (instance.error) {
+            row.title = instance.error;
+        }
+        cells.forEach(function (value) {
+            const cell = document.createElement('td');
+            cell.textContent = value;
+            row.appendChild(cell);
+        });
+        tbody.appendChild(row);
+    });
+}
+
+/**
+ * Format an uptime in seconds
+ * @param {number} seconds - The uptime
+ * @returns {string} The formatted uptime
+ */
+function formatUptime(seconds) {
+    const days = Math.floor(seconds / 86400);
+    const hours = Math.floor((seconds % 86400) / 3600);
+    const minutes = Math.floor((seconds % 3600) / 60);
+    if (days > 0) {
+        return days + 'd ' + hours + 'h';
+    } else if (hours > 0) {
+        return hours + 'h ' + minutes + 'm';
+    }
+    return minutes + 'm';
+}
+
+/**
+ * Setup the history charts and their range selector
+ */
+function setupHistoryCharts() {
+    const container = document.querySelector('[data-history-api]');
+    if (!container) {
+        return;
+    }
+
+    const endpoint = container.getAttribute('data-history-api');
+    const buttons = container.querySelectorAll('#history-range [data-range]');
+    const load = (range) => {
+        container.querySelectorAll('[data-history-metric]').forEach(function (canvas) {
+            const metric = canvas.getAttribute('data-history-metric');
+            fetch(endpoint + '/' + metric + '?range=' + range)
+                .then(response => response.json())
+                .then(series => renderHistoryChart(canvas, series, range))
+                .catch(error => {
+                    console.error('Error loading history of ' + metric + ':', error);
+                });
+        });
+    };
+
+    buttons.forEach(function (button) {
+        button.addEventListener('click', function () {
+            buttons.forEach(b => b.classList.remove('active'));
+            button.classList.add('active');
+            load(button.getAttribute('data-range'));
+        });
+    });
+
+    load('24h');
+}
+
+/**
+ * Render the history of a metric, one line per instance
+ * @param {HTMLCanvasElement} canvas - The chart canvas
+ * @param {Array} series - The series of the metric
+ * @param {string} range - The range shown
+ */
+function renderHistoryChart(canvas, series, range) {
+    const format = range === '1h' || range === '24h' ? 'HH:mm' : 'MMM D HH:mm';
+    const colors = ['#0d6efd', '#198754', '#dc3545', '#ffc107', '#0dcaf0', '#6c757d'];
+    const timestamps = [...new Set(series.flatMap(s => s.data_points.map(dp => dp.timestamp)))].sort();
+
+    const datasets = series.map(function (s, i) {
+        const values = new Map(s.data_points.map(dp => [dp.timestamp, dp.value]));
+        return {
+            label: s.name,
+            data: timestamps.map(ts => values.has(ts) ? values.get(ts) : null),
+            borderColor: colors[i % colors.length],
+            spanGaps: true,
+            tension: 0.3,
+            pointRadius: 0
+        };
+    });
+
+    const existing = Chart.getChart(canvas);
+    if (existing) {
+        existing.destroy();
+    }
+    new Chart(canvas.getContext('2d'), {
+        type: 'line',
+        data: {
+            labels: timestamps.map(ts => moment(ts).format(format)),
+            datasets: datasets
+        },
+        options: {
+            responsive: true,
+            maintainAspectRatio: false,
+            plugins: {
+                tooltip: {
+                    mode: 'index',
+                    intersect: false
+                }
+            },
+            scales: {
+                y: {
+                    beginAtZero: true,
+                    title: {
+                        display: true,
+                        text: canvas.getAttribute('data-history-unit')
+                    }
+                }
+            }
+        }
+    });
+}
+
+/**
+ * Setup the button creating read-only share links for the current view
+ */
+function setupShareLinks() {
+    const button = document.getElementById('share-view');
+    if (!button) {
+        return;
+    }
+
+    button.addEventListener('click', function () {
+        const hours = prompt('Share a read-only link to this view, valid for how many hours?', '24');
+        if (hours === null) {
+            return;
+        }
+
+        const path = window.location.pathname;
+        fetch('/api/share', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({
+                view: path === '/' ? 'home' : path.substring(1),
+                expires_in_hours: parseInt(hours, 10) || 24
+            })
+        })
+            .then(response => {
+                if (!response.ok) {
+                    throw new Error(response.statusText);
+                }
+                return response.json();
+            })
+            .then(link => {
+                const url = window.location.origin + link.path;
+                if (navigator.clipboard) {
+                    navigator.clipboard.writeText(url).catch(() => { });
+                }
+                prompt('Link valid until ' + moment(link.expires_at).format('YYYY-MM-DD HH:mm') + ':', url);
+            })
+            .catch(error => {
+                console.error('Error creating share link:', error);
+            });
+    });
+}
+
+/**
+ * Setup the alert rule editor and alert history
+ */
+function setupAlerts() {
+    const container = document.querySelector('[data-alerts-api]');
+    if (!container) {
+        return;
+    }
+
+    const endpoint = container.getAttribute('data-alerts-api');
+    const form = document.getElementById('alert-rule-form');
+    const error = document.getElementById('alert-rule-error');
+    let rules = [];
+
+    const request = (method, url, body) => fetch(url, {
+        method: method,
+        headers: { 'Content-Type': 'application/json' },
+        body: body === undefined ? undefined : JSON.stringify(body)
+    }).then(response => {
+        if (!response.ok) {
+            return response.text().then(text => {
+                throw new Error(text || response.statusText);
+            });
+        }
+        return response.status === 204 ? null : response.json();
+    });
+
+    const loadRules = () => request('GET', endpoint + '/rules')
+        .then(data => {
+            rules = data;
+            renderAlertRules(rules, editRule, deleteRule);
+        })
+        .catch(e => console.error('Error loading alert rules:', e));
+
+    const loadHistory = () => request('GET', endpoint + '?limit=100')
+        .then(renderAlertHistory)
+        .catch(e => console.error('Error loading alert history:', e));
+
+    const editRule = (id) => {
+        const rule = rules.find(r => r.id === id);
+        if (!rule) {
+            return;
+        }
+        form.elements.id.value = rule.id;
+        form.elements.name.value = rule.name;
+        form.elements.metric.value = rule.metric;
+        form.elements.instance.value = rule.instance || '';
+        form.elements.comparison.value = rule.comparison;
+        form.elements.threshold.value = rule.threshold;
+        form.elements.for_seconds.value = rule.for_seconds;
+        form.elements.severity.value = rule.severity;
+        Array.from(form.elements.notifiers.options).forEach(option => {
+            option.selected = rule.notifiers.includes(option.value);
+        });
+        form.elements.enabled.checked = rule.enabled;
+        document.getElementById('alert-rule-form-title').textContent = 'Edit Rule';
+        form.scrollIntoView();
+    };
+
+    const deleteRule = (id) => {
+        const rule = rules.find(r => r.id === id);
+        if (!rule || !confirm('Delete the rule ' + rule.name + '?')) {
+            return;
+        }
+        request('DELETE', endpoint + '/rules/' + id)
+            .then(loadRules)
+            .catch(e => console.error('Error deleting alert rule:', e));
+    };
+
+    form.addEventListener('reset', function () {
+        form.elements.id.value = '';
+        error.textContent = '';
+        document.getElementById('alert-rule-form-title').textContent = 'New Rule';
+    });
+
+    form.addEventListener('submit', function (event) {
+        event.preventDefault();
+        const id = form.elements.id.value;
+        const rule = {
+            name: form.elements.name.value,
+            metric: form.elements.metric.value,
+            instance: form.elements.instance.value || null,
+            comparison: form.elements.comparison.value,
+            threshold: parseFloat(form.elements.threshold.value),
+            for_seconds: parseInt(form.elements.for_seconds.value, 10) || 0,
+            severity: form.elements.severity.value,
+            notifiers: Array.from(form.elements.notifiers.selectedOptions).map(option => option.value),
+            enabled: form.elements.enabled.checked
+        };
+
+        const saved = id
+            ? request('PUT', endpoint + '/rules/' + id, rule)
+            : request('POST', endpoint + '/rules', rule);
+        saved
+            .then(() => {
+                form.reset();
+                loadRules();
+            })
+            .catch(e => {
+                error.textContent = e.message;
+            });
+    });
+
+    request('GET', endpoint + '/notifiers')
+        .then(notifiers => {
+            notifiers.forEach(notifier => {
+                const option = document.createElement('option');
+                option.value = notifier.name;
+                option.textContent = notifier.name + ' (' + notifier.type + ')';
+                form.elements.notifiers.appendChild(option);
+            });
+        })
+        .catch(e => console.error('Error loading notifiers:', e));
+
+    loadRules();
+    loadHistory();
+    setInterval(loadHistory, 30000);
+}
+
+/**
+ * Render the alert rules
+ * @param {Array} rules - The rules
+ * @param {Function} onEdit - Called with the ID of a rule to edit
+ * @param {Function} onDelete - Called with the ID of a rule to delete
+ */
+function renderAlertRules(rules, onEdit, onDelete) {
+    const tbody = document.querySelector('#alert-rules tbody');
+    tbody.innerHTML = '';
+
+    rules.forEach(function (rule) {
+        const row = document.createElement('tr');
+        const cells = [
+            rule.name,
+            rule.metric + ' ' + rule.comparison + ' ' + rule.threshold,
+            rule.instance || 'any',
+            rule.for_seconds + 's',
+            rule.severity,
+            rule.notifiers.join(', '),
+            rule.enabled ? 'yes' : 'no'
+        ];
+        cells.forEach(function (value) {
+            const cell = document.createElement('td');
+            cell.textContent = value;
+            row.appendChild(cell);
+        });
+
+        const actions = document.createElement('td');
+        [['Edit', onEdit], ['Delete', onDelete]].forEach(function ([label, action]) {
+            const button = document.createElement('button');
+            button.type = 'button';
+            button.className = 'btn btn-sm btn-outline-secondary me-1';
+            button.textContent = label;
+            button.addEventListener('click', () => action(rule.id));
+            actions.appendChild(button);
+        });
+        row.appendChild(actions);
+        tbody.appendChild(row);
+    });
+}
+
+/**
+ * Render the alert history
+ * @param {Array} alerts - The latest alerts
+ */
+function renderAlertHistory(alerts) {
+    const tbody = document.querySelector('#alert-history tbody');
+    tbody.innerHTML = '';
+
+    alerts.forEach(function (alert) {
+        const row = document.createElement('tr');
+        if (!alert.resolved_at) {
+            row.className = 'table-danger';
+        }
+        const cells = [
+            alert.resolved_at ? 'resolved' : 'firing',
+            alert.rule_name,
+            alert.instance,
+            alert.metric + ' = ' + Number(alert.value).toFixed(2) + ' (' + alert.comparison + ' ' + alert.threshold + ')',
+            alert.severity,
+            moment(alert.fired_at).format('YYYY-MM-DD HH:mm:ss'),
+            alert.resolved_at ? moment(alert.resolved_at).format('YYYY-MM-DD HH:mm:ss') : ''
+        ];
+        cells.forEach(function (value) {
+            const cell = document.createElement('td');
+            cell.textContent = value;
+            row.appendChild(cell);
+        });
+        tbody.appendChild(row);
+    });
+}
@@ -13,7 +13,9 @@ from .exceptions import (
     ServerError,
     ValidationError,
     ConfigurationError,
+    CancelledError,
 )
+from .options import CallOptions, CancellationToken
 from .types import (
     Role,
     JSONDict,
@@ -29,6 +31,13 @@ from .chat import (
     ChatCompletionChunk,
     ChatCompletionChunkChoice,
     ChatCompletionChunkDelta,
+    text_part,
+    image_part,
+    image_url_part,
+    audio_part,
+    file_part,
+    file_id_part,
+    user_message,
 )
 from .chains import (
     Chain,
@@ -38,6 +47,13 @@ from .chains import (
     ChainExecutionEvent,
     ChainExecutionStepResult,
 )
+from .agents import (
+    Agent,
+    AgentRun,
+    AgentStep,
+    AgentToolInvocation,
+    AgentUsage,
+)
 
 __version__ = "0.1.0"
 
@@ -50,6 +66,9 @@ __all__ = [
     "ServerError",
     "ValidationError",
     "ConfigurationError",
+    "CancelledError",
+    "CallOptions",
+    "CancellationToken",
     "Role",
     "JSONDict",
     "JSONList",
@@ -62,10 +81,22 @@ __all__ = [
     "ChatCompletionChunk",
     "ChatCompletionChunkChoice",
     "ChatCompletionChunkDelta",
+    "text_part",
+    "image_part",
+    "image_url_part",
+    "audio_part",
+    "file_part",
+    "file_id_part",
+    "user_message",
     "Chain",
     "ChainStep",
     "ChainDependency",
     "ChainExecution",
     "ChainExecutionEvent",
     "ChainExecutionStepResult",
+    "Agent",
+    "AgentRun",
+    "AgentStep",
+    "AgentToolInvocation",
+    "AgentUsage",
 ]
\ No newline at end of file
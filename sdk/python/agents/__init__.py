@@ -0,0 +1,17 @@
+from .api import AgentClient
+from .models import (
+    Agent,
+    AgentRun,
+    AgentStep,
+    AgentToolInvocation,
+    AgentUsage,
+)
+
+__all__ = [
+    "AgentClient",
+    "Agent",
+    "AgentRun",
+    "AgentStep",
+    "AgentToolInvocation",
+    "AgentUsage",
+]
@@ -0,0 +1,150 @@
+from typing import Dict, List, Any, Optional, Union
+from ..transport import Transport
+from ..exceptions import ValidationError
+from ..options import CallOptions
+from .models import Agent, AgentRun
+
+class AgentClient:
+    """
+    Client for the agent API.
+    
+    This client provides methods for running agents and inspecting their traces.
+    """
+    
+    def __init__(self, transport: Transport):
+        """
+        Initialize the agent client.
+        
+        Args:
+            transport: The transport layer to use for API requests.
+        """
+        self.transport = transport
+    
+    def _prepare_run(
+        self,
+        agent: Union[Agent, Dict[str, Any]],
+        input: str,
+        conversation_id: Optional[str],
+        options: Optional[CallOptions] = None,
+    ) -> Dict[str, Any]:
+        if isinstance(agent, dict):
+            try:
+                agent = Agent(**agent)
+            except Exception as e:
+                raise ValidationError(f"Invalid agent: {str(e)}")
+        elif not isinstance(agent, Agent):
+            raise ValidationError(f"Invalid agent type: {type(agent)}")
+        
+        if not input:
+            raise ValidationError("Agent input cannot be empty")
+        
+        if options is not None and options.model is not None:
+            agent = agent.copy(update={"model": options.model})
+        
+        data = {
+            "agent": agent.dict(exclude_none=True),
+            "input": input,
+        }
+        if conversation_id is not None:
+            data["conversation_id"] = conversation_id
+        return data
+    
+    def _parse_run(self, response: Dict[str, Any]) -> AgentRun:
+        try:
+            return AgentRun(**response)
+        except Exception as e:
+            raise ValidationError(f"Invalid agent run response: {str(e)}")
+    
+    def run(
+        self,
+        agent: Union[Agent, Dict[str, Any]],
+        input: str,
+        conversation_id: Optional[str] = None,
+        options: Optional[CallOptions] = None,
+    ) -> AgentRun:
+        """
+        Run an agent until it answers or a stop condition is hit.
+        
+        Args:
+            agent: The agent definition.
+            input: The task given to the agent.
+            conversation_id: A conversation to load history from and record the run in.
+            options: Options of the call, such as its timeout, model override or cancellation token.
+        
+        Returns:
+            The trace of the run.
+        
+        Raises:
+            ValidationError: If the request is invalid.
+            APIError: If the API returns an error.
+            AuthenticationError: If authentication fails.
+            RateLimitError: If the rate limit is exceeded.
+            ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
+        """
+        response = self.transport.request(
+            method="POST",
+            path="/v1/agents/run",
+            data=self._prepare_run(agent, input, conversation_id, options),
+            options=options,
+        )
+        return self._parse_run(response)
+    
+    async def arun(
+        self,
+        agent: Union[Agent, Dict[str, Any]],
+        input: str,
+        conversation_id: Optional[str] = None,
+        options: Optional[CallOptions] = None,
+    ) -> AgentRun:
+        """
+        Run an agent asynchronously until it answers or a stop condition is hit.
+        
+        Args:
+            agent: The agent definition.
+            input: The task given to the agent.
+            conversation_id: A conversation to load history from and record the run in.
+            options: Options of the call, such as its timeout, model override or cancellation token.
+        
+        Returns:
+            The trace of the run.
+        """
+        response = await self.transport.arequest(
+            method="POST",
+            path="/v1/agents/run",
+            data=self._prepare_run(agent, input, conversation_id, options),
+            options=options,
+        )
+        return self._parse_run(response)
+    
+    def get_run(self, run_id: str) -> AgentRun:
+        """
+        Get the trace of a recent agent run.
+        
+        Args:
+            run_id: The ID of the run.
+        
+        Returns:
+            The trace of the run.
+        """
+        response = self.transport.request(
+            method="GET",
+            path=f"/v1/agents/runs/{run_id}",
+        )
+        return self._parse_run(response)
+    
+    def list_runs(self) -> List[AgentRun]:
+        """
+        List recent agent runs, newest first.
+        
+        Returns:
+            A list of run traces.
+        """
+        response = self.transport.request(
+            method="GET",
+            path="/v1/agents/runs",
+        )
+        try:
+            return [AgentRun(**run) for run in response]
+        except Exception as e:
+            raise ValidationError(f"Invalid agent run list response: {str(e)}")
@@ -0,0 +1,109 @@
+from typing import Dict, List, Any, Optional, Literal
+from datetime import datetime
+from pydantic import BaseModel, Field
+
+class Agent(BaseModel):
+    """
+    Definition of a ReAct-style agent.
+    
+    Args:
+        id: The ID of the agent, used in traces.
+        model: The model the agent reasons with.
+        system_prompt: Instructions prepended to the conversation.
+        tools: The tools the agent may call, limited to those the server
+            configures for the agent. None if empty.
+        max_steps: The maximum number of model calls.
+        max_tool_calls: The maximum number of tool calls across the run.
+        stop_phrases: Phrases marking the final answer, e.g. "FINAL ANSWER:".
+        timeout_secs: The wall-clock budget for the run, in seconds.
+        temperature: The sampling temperature.
+        memory_window: The number of conversation messages loaded from memory.
+    """
+    id: str = "agent"
+    model: str
+    system_prompt: Optional[str] = None
+    tools: List[str] = Field(default_factory=list)
+    max_steps: int = 10
+    max_tool_calls: Optional[int] = None
+    stop_phrases: List[str] = Field(default_factory=list)
+    timeout_secs: Optional[int] = None
+    temperature: Optional[float] = None
+    memory_window: int = 20
+
+class AgentToolInvocation(BaseModel):
+    """
+    A tool call made by an agent.
+    
+    Args:
+        call_id: The ID of the tool call assigned by the model.
+        name: The name of the tool.
+        arguments: The arguments as sent by the model.
+        result: The tool result, if the call succeeded.
+        error: An error message if the call failed.
+        duration_ms: The call duration in milliseconds.
+    """
+    call_id: str
+    name: str
+    arguments: str
+    result: Optional[Any] = None
+    error: Optional[str] = None
+    duration_ms: int = 0
+
+class AgentStep(BaseModel):
+    """
+    One reasoning iteration of an agent run.
+    
+    Args:
+        index: The iteration number, starting at 1.
+        thought: The text produced by the model in this iteration.
+        actions: The tool calls made in this iteration.
+        total_tokens: The tokens used by the model call, if reported.
+        duration_ms: The iteration duration in milliseconds.
+    """
+    index: int
+    thought: str = ""
+    actions: List[AgentToolInvocation] = Field(default_factory=list)
+    total_tokens: Optional[int] = None
+    duration_ms: int = 0
+
+class AgentUsage(BaseModel):
+    """
+    The model usage of an agent run.
+    
+    Args:
+        prompt_tokens: The prompt tokens across all model calls.
+        completion_tokens: The completion tokens across all model calls.
+        cost: The cost of the model calls, in the server's pricing currency.
+    """
+    prompt_tokens: int = 0
+    completion_tokens: int = 0
+    cost: float = 0.0
+
+class AgentRun(BaseModel):
+    """
+    The trace of an agent run.
+    
+    Args:
+        run_id: The ID of the run.
+        agent_id: The ID of the agent.
+        input: The input the agent was given.
+        answer: The final answer, absent when a budget was exhausted first.
+        stop_reason: Why the run stopped.
+        steps: The reasoning iterations of the run.
+        total_tokens: The tokens used across all model calls.
+        usage: The usage of the model calls, priced by the server.
+        conversation_id: The conversation the run was recorded in.
+        started_at: When the run started.
+        finished_at: When the run finished.
+    """
+    run_id: str
+    agent_id: str
+    input: str
+    answer: Optional[str] = None
+    stop_reason: Literal["final_answer", "stop_phrase", "max_steps", "max_tool_calls", "timeout"]
+    steps: List[AgentStep] = Field(default_factory=list)
+    total_tokens: int = 0
+    usage: AgentUsage = Field(default_factory=AgentUsage)
+    conversation_id: Optional[str] = None
+    started_at: Optional[datetime] = None
+    finished_at: Optional[datetime] = None
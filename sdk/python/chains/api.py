@@ -3,6 +3,7 @@ import json
 import time
 from ..transport import Transport
 from ..exceptions import ValidationError
+from ..options import CallOptions
 from .models import (
     Chain,
     ChainStep,
@@ -297,6 +298,7 @@ class ChainClient:
         inputs: Dict[str, Any],
         config: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Union[ChainExecution, Iterator[ChainExecutionEvent]]:
         """
         Execute a chain.
@@ -306,6 +308,7 @@ class ChainClient:
             inputs: The inputs for the chain.
             config: Additional configuration for the execution.
             stream: Whether to stream the execution events.
+            options: Options of the call, such as its timeout or cancellation token.
         
         Returns:
             If stream is False, returns a ChainExecution object.
@@ -317,6 +320,7 @@ class ChainClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Prepare the request data
         data = {
@@ -328,13 +332,14 @@ class ChainClient:
             data["config"] = config
         
         if stream:
-            return self.stream(chain_id, inputs, config)
+            return self.stream(chain_id, inputs, config, options)
         
         # Make the request
         response = self.transport.request(
             method="POST",
             path=f"/v1/chains/{chain_id}/run",
             data=data,
+            options=options,
         )
         
         # Parse the response
@@ -348,6 +353,7 @@ class ChainClient:
         chain_id: str,
         inputs: Dict[str, Any],
         config: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> Iterator[ChainExecutionEvent]:
         """
         Execute a chain with streaming events.
@@ -356,6 +362,7 @@ class ChainClient:
             chain_id: The ID of the chain.
             inputs: The inputs for the chain.
             config: Additional configuration for the execution.
+            options: Options of the call, such as its timeout or cancellation token.
         
         Returns:
             An iterator of ChainExecutionEvent objects.
@@ -366,6 +373,7 @@ class ChainClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Prepare the request data
         data = {
@@ -381,6 +389,7 @@ class ChainClient:
             method="POST",
             path=f"/v1/chains/{chain_id}/run",
             data=data,
+            options=options,
         ):
             try:
                 yield ChainExecutionEvent(**event)
@@ -657,6 +666,7 @@ class ChainClient:
         inputs: Dict[str, Any],
         config: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Union[ChainExecution, AsyncIterator[ChainExecutionEvent]]:
         """
         Execute a chain asynchronously.
@@ -666,6 +676,7 @@ class ChainClient:
             inputs: The inputs for the chain.
             config: Additional configuration for the execution.
             stream: Whether to stream the execution events.
+            options: Options of the call, such as its timeout or cancellation token.
         
         Returns:
             If stream is False, returns a ChainExecution object.
@@ -677,6 +688,7 @@ class ChainClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Prepare the request data
         data = {
@@ -688,13 +700,14 @@ class ChainClient:
             data["config"] = config
         
         if stream:
-            return self.astream(chain_id, inputs, config)
+            return self.astream(chain_id, inputs, config, options)
         
         # Make the request
         response = await self.transport.arequest(
             method="POST",
             path=f"/v1/chains/{chain_id}/run",
             data=data,
+            options=options,
         )
         
         # Parse the response
@@ -708,6 +721,7 @@ class ChainClient:
         chain_id: str,
         inputs: Dict[str, Any],
         config: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> AsyncIterator[ChainExecutionEvent]:
         """
         Execute a chain with streaming events asynchronously.
@@ -716,6 +730,7 @@ class ChainClient:
             chain_id: The ID of the chain.
             inputs: The inputs for the chain.
             config: Additional configuration for the execution.
+            options: Options of the call, such as its timeout or cancellation token.
         
         Returns:
             An async iterator of ChainExecutionEvent objects.
@@ -726,6 +741,7 @@ class ChainClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Prepare the request data
         data = {
@@ -741,6 +757,7 @@ class ChainClient:
             method="POST",
             path=f"/v1/chains/{chain_id}/run",
             data=data,
+            options=options,
         ):
             try:
                 yield ChainExecutionEvent(**event)
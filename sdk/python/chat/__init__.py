@@ -8,6 +8,15 @@ from .models import (
     ChatCompletionChunkChoice,
     ChatCompletionChunkDelta,
 )
+from .multimodal import (
+    text_part,
+    image_part,
+    image_url_part,
+    audio_part,
+    file_part,
+    file_id_part,
+    user_message,
+)
 
 __all__ = [
     "ChatClient",
@@ -18,4 +27,11 @@ __all__ = [
     "ChatCompletionChunk",
     "ChatCompletionChunkChoice",
     "ChatCompletionChunkDelta",
+    "text_part",
+    "image_part",
+    "image_url_part",
+    "audio_part",
+    "file_part",
+    "file_id_part",
+    "user_message",
 ]
\ No newline at end of file
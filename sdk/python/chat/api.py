@@ -3,6 +3,7 @@ import json
 from ..transport import Transport
 from ..types import Role
 from ..exceptions import ValidationError
+from ..options import CallOptions
 from .models import (
     ChatMessage,
     ChatCompletionRequest,
@@ -40,6 +41,7 @@ class ChatClient:
         logit_bias: Optional[Dict[str, float]] = None,
         user: Optional[str] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Union[ChatCompletion, Iterator[ChatCompletionChunk]]:
         """
         Create a chat completion.
@@ -57,6 +59,7 @@ class ChatClient:
             logit_bias: Modify the likelihood of specified tokens appearing in the completion.
             user: A unique identifier representing your end-user.
             stream: Whether to stream the response.
+            options: Options of the call, such as its timeout, model override or cancellation token.
         
         Returns:
             If stream is False, returns a ChatCompletion object.
@@ -68,6 +71,7 @@ class ChatClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Convert messages to the expected format
         formatted_messages = self._format_messages(messages)
@@ -98,6 +102,8 @@ class ChatClient:
             data["logit_bias"] = logit_bias
         if user is not None:
             data["user"] = user
+        if options is not None and options.model is not None:
+            data["model"] = options.model
         
         # Validate the request
         try:
@@ -118,6 +124,7 @@ class ChatClient:
                 frequency_penalty=frequency_penalty,
                 logit_bias=logit_bias,
                 user=user,
+                options=options,
             )
         
         # Make the request
@@ -125,6 +132,7 @@ class ChatClient:
             method="POST",
             path="/v1/chat/completions",
             data=data,
+            options=options,
         )
         
         # Parse the response
@@ -146,6 +154,7 @@ class ChatClient:
         frequency_penalty: Optional[float] = None,
         logit_bias: Optional[Dict[str, float]] = None,
         user: Optional[str] = None,
+        options: Optional[CallOptions] = None,
     ) -> Iterator[ChatCompletionChunk]:
         """
         Create a streaming chat completion.
@@ -162,6 +171,7 @@ class ChatClient:
             frequency_penalty: Number between -2.0 and 2.0. Positive values penalize new tokens based on their frequency in the text so far.
             logit_bias: Modify the likelihood of specified tokens appearing in the completion.
             user: A unique identifier representing your end-user.
+            options: Options of the call, such as its timeout, model override or cancellation token.
         
         Returns:
             An iterator of ChatCompletionChunk objects.
@@ -172,6 +182,7 @@ class ChatClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Convert messages to the expected format
         formatted_messages = self._format_messages(messages)
@@ -202,6 +213,8 @@ class ChatClient:
             data["logit_bias"] = logit_bias
         if user is not None:
             data["user"] = user
+        if options is not None and options.model is not None:
+            data["model"] = options.model
         
         # Validate the request
         try:
@@ -214,6 +227,7 @@ class ChatClient:
             method="POST",
             path="/v1/chat/completions",
             data=data,
+            options=options,
         ):
             try:
                 yield ChatCompletionChunk(**chunk)
@@ -234,6 +248,7 @@ class ChatClient:
         logit_bias: Optional[Dict[str, float]] = None,
         user: Optional[str] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Union[ChatCompletion, AsyncIterator[ChatCompletionChunk]]:
         """
         Create a chat completion asynchronously.
@@ -251,6 +266,7 @@ class ChatClient:
             logit_bias: Modify the likelihood of specified tokens appearing in the completion.
             user: A unique identifier representing your end-user.
             stream: Whether to stream the response.
+            options: Options of the call, such as its timeout, model override or cancellation token.
         
         Returns:
             If stream is False, returns a ChatCompletion object.
@@ -262,6 +278,7 @@ class ChatClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Convert messages to the expected format
         formatted_messages = self._format_messages(messages)
@@ -292,6 +309,8 @@ class ChatClient:
             data["logit_bias"] = logit_bias
         if user is not None:
             data["user"] = user
+        if options is not None and options.model is not None:
+            data["model"] = options.model
         
         # Validate the request
         try:
@@ -312,6 +331,7 @@ class ChatClient:
                 frequency_penalty=frequency_penalty,
                 logit_bias=logit_bias,
                 user=user,
+                options=options,
             )
         
         # Make the request
@@ -319,6 +339,7 @@ class ChatClient:
             method="POST",
             path="/v1/chat/completions",
             data=data,
+            options=options,
         )
         
         # Parse the response
@@ -340,6 +361,7 @@ class ChatClient:
         frequency_penalty: Optional[float] = None,
         logit_bias: Optional[Dict[str, float]] = None,
         user: Optional[str] = None,
+        options: Optional[CallOptions] = None,
     ) -> AsyncIterator[ChatCompletionChunk]:
         """
         Create a streaming chat completion asynchronously.
@@ -356,6 +378,7 @@ class ChatClient:
             frequency_penalty: Number between -2.0 and 2.0. Positive values penalize new tokens based on their frequency in the text so far.
             logit_bias: Modify the likelihood of specified tokens appearing in the completion.
             user: A unique identifier representing your end-user.
+            options: Options of the call, such as its timeout, model override or cancellation token.
         
         Returns:
             An async iterator of ChatCompletionChunk objects.
@@ -366,6 +389,7 @@ class ChatClient:
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         # Convert messages to the expected format
         formatted_messages = self._format_messages(messages)
@@ -396,6 +420,8 @@ class ChatClient:
             data["logit_bias"] = logit_bias
         if user is not None:
             data["user"] = user
+        if options is not None and options.model is not None:
+            data["model"] = options.model
         
         # Validate the request
         try:
@@ -408,6 +434,7 @@ class ChatClient:
             method="POST",
             path="/v1/chat/completions",
             data=data,
+            options=options,
         ):
             try:
                 yield ChatCompletionChunk(**chunk)
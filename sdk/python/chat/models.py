@@ -7,13 +7,13 @@ class ChatMessage(BaseModel):
     
     Args:
         role: The role of the message sender. Can be "system", "user", "assistant", "function", or "tool".
-        content: The content of the message.
+        content: The content of the message: text, or a list of content parts for multimodal messages.
         name: Optional name of the sender. Required for function and tool roles.
         function_call: Optional function call information.
         tool_calls: Optional tool call information.
     """
     role: Literal["system", "user", "assistant", "function", "tool"]
-    content: Optional[str] = None
+    content: Optional[Union[str, List[Dict[str, Any]]]] = None
     name: Optional[str] = None
     function_call: Optional[Dict[str, Any]] = None
     tool_calls: Optional[List[Dict[str, Any]]] = None
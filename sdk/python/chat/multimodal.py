@@ -0,0 +1,302 @@
+"""
+Helpers for building multimodal chat messages.
+
+These helpers turn local files into content parts, handling base64 encoding,
+MIME type detection and size limits, so callers don't hand-build content
+arrays.
+"""
+
+import base64
+import io
+import mimetypes
+import os
+from typing import Any, Dict, List, Optional, Sequence, Tuple, Union
+
+from ..exceptions import ConfigurationError, ValidationError
+from .models import ChatMessage
+
+# Default maximum size of an attached image, in bytes
+DEFAULT_MAX_IMAGE_BYTES = 20 * 1024 * 1024
+
+# Default maximum size of an attached audio clip or file, in bytes
+DEFAULT_MAX_FILE_BYTES = 25 * 1024 * 1024
+
+# Image types accepted by vision models
+IMAGE_MIME_TYPES = ("image/png", "image/jpeg", "image/gif", "image/webp")
+
+# Audio formats accepted in input_audio parts
+AUDIO_FORMATS = ("wav", "mp3")
+
+# Image or file source: a path or raw bytes
+Source = Union[str, os.PathLike, bytes]
+
+ContentPart = Dict[str, Any]
+
+
+def text_part(text: str) -> ContentPart:
+    """
+    Create a text content part.
+
+    Args:
+        text: The text.
+
+    Returns:
+        The content part.
+    """
+    return {"type": "text", "text": text}
+
+
+def image_url_part(url: str, detail: str = "auto") -> ContentPart:
+    """
+    Create an image content part referencing an image by URL.
+
+    Args:
+        url: Web URL or data URL of the image.
+        detail: Detail level for image processing ("auto", "low" or "high").
+
+    Returns:
+        The content part.
+    """
+    return {"type": "image_url", "image_url": {"url": url, "detail": detail}}
+
+
+def image_part(
+    source: Source,
+    detail: str = "auto",
+    mime_type: Optional[str] = None,
+    max_bytes: int = DEFAULT_MAX_IMAGE_BYTES,
+    max_dimension: Optional[int] = None,
+) -> ContentPart:
+    """
+    Create an image content part from a local image, inlined as a data URL.
+
+    Args:
+        source: Path of the image, or its raw bytes.
+        detail: Detail level for image processing ("auto", "low" or "high").
+        mime_type: MIME type of the image. Detected from the content when omitted.
+        max_bytes: Maximum size of the encoded image, in bytes.
+        max_dimension: If set, images whose width or height exceeds it are
+            scaled down to fit, keeping their aspect ratio. Requires Pillow.
+
+    Returns:
+        The content part.
+
+    Raises:
+        ValidationError: If the image type is unsupported or the image is too large.
+        ConfigurationError: If resizing is requested but Pillow is not installed.
+    """
+    data, name = _read(source)
+    mime_type = mime_type or _sniff_image_type(data) or _guess_type(name)
+    if mime_type not in IMAGE_MIME_TYPES:
+        raise ValidationError(
+            f"Unsupported image type {mime_type or 'unknown'} for {name or 'image'}; "
+            f"expected one of {', '.join(IMAGE_MIME_TYPES)}"
+        )
+    if max_dimension is not None:
+        data = _resize(data, mime_type, max_dimension)
+    _check_size(data, max_bytes, name or "Image")
+
+    return image_url_part(_data_url(data, mime_type), detail)
+
+
+def audio_part(
+    source: Source,
+    audio_format: Optional[str] = None,
+    max_bytes: int = DEFAULT_MAX_FILE_BYTES,
+) -> ContentPart:
+    """
+    Create an input_audio content part from a local audio clip.
+
+    Args:
+        source: Path of the clip, or its raw bytes.
+        audio_format: Audio format ("wav" or "mp3"). Detected from the content or
+            file extension when omitted.
+        max_bytes: Maximum size of the clip, in bytes.
+
+    Returns:
+        The content part.
+
+    Raises:
+        ValidationError: If the audio format is unsupported or the clip is too large.
+    """
+    data, name = _read(source)
+    audio_format = audio_format or _sniff_audio_format(data) or _extension(name)
+    if audio_format not in AUDIO_FORMATS:
+        raise ValidationError(
+            f"Unsupported audio format {audio_format or 'unknown'} for {name or 'audio'}; "
+            f"expected one of {', '.join(AUDIO_FORMATS)}"
+        )
+    _check_size(data, max_bytes, name or "Audio")
+
+    return {
+        "type": "input_audio",
+        "input_audio": {"data": _b64(data), "format": audio_format},
+    }
+
+
+def file_part(
+    source: Source,
+    filename: Optional[str] = None,
+    max_bytes: int = DEFAULT_MAX_FILE_BYTES,
+) -> ContentPart:
+    """
+    Create a file content part from a local file, such as a PDF.
+
+    Args:
+        source: Path of the file, or its raw bytes.
+        filename: Name sent with the file. Defaults to the file's base name.
+        max_bytes: Maximum size of the file, in bytes.
+
+    Returns:
+        The content part.
+
+    Raises:
+        ValidationError: If the file is too large.
+    """
+    data, name = _read(source)
+    _check_size(data, max_bytes, name or "File")
+
+    file: Dict[str, Any] = {"file_data": _b64(data)}
+    filename = filename or (os.path.basename(name) if name else None)
+    if filename:
+        file["filename"] = filename
+    return {"type": "file", "file": file}
+
+
+def file_id_part(file_id: str) -> ContentPart:
+    """
+    Create a file content part referencing a previously uploaded file.
+
+    Args:
+        file_id: ID of the uploaded file.
+
+    Returns:
+        The content part.
+    """
+    return {"type": "file", "file": {"file_id": file_id}}
+
+
+def user_message(
+    text: Optional[str] = None,
+    images: Sequence[Union[Source, ContentPart]] = (),
+    audio: Sequence[Union[Source, ContentPart]] = (),
+    files: Sequence[Union[Source, ContentPart]] = (),
+    detail: str = "auto",
+    max_dimension: Optional[int] = None,
+) -> ChatMessage:
+    """
+    Create a user message with text and attachments.
+
+    Attachments can be paths, raw bytes or content parts built with the
+    helpers above. Image sources starting with http:// or https:// are
+    referenced by URL instead of being read.
+
+    Args:
+        text: Text of the message.
+        images: Images to attach.
+        audio: Audio clips to attach.
+        files: Files to attach.
+        detail: Detail level for attached images.
+        max_dimension: If set, attached images are scaled down to fit it.
+
+    Returns:
+        The message.
+
+    Raises:
+        ValidationError: If an attachment is invalid or the message is empty.
+    """
+    parts: List[ContentPart] = []
+    if text:
+        parts.append(text_part(text))
+    for image in images:
+        if isinstance(image, dict):
+            parts.append(image)
+        elif isinstance(image, str) and image.startswith(("http://", "https://")):
+            parts.append(image_url_part(image, detail))
+        else:
+            parts.append(image_part(image, detail=detail, max_dimension=max_dimension))
+    for clip in audio:
+        parts.append(clip if isinstance(clip, dict) else audio_part(clip))
+    for file in files:
+        parts.append(file if isinstance(file, dict) else file_part(file))
+
+    if not parts:
+        raise ValidationError("A message needs text or at least one attachment")
+    return ChatMessage(role="user", content=parts)
+
+
+def _read(source: Source) -> Tuple[bytes, Optional[str]]:
+    """Read a source, returning its bytes and path, if any."""
+    if isinstance(source, bytes):
+        return source, None
+    path = os.fspath(source)
+    try:
+        with open(path, "rb") as f:
+            return f.read(), path
+    except OSError as e:
+        raise ValidationError(f"Cannot read {path}: {e}")
+
+
+def _check_size(data: bytes, max_bytes: int, name: str) -> None:
+    if len(data) > max_bytes:
+        raise ValidationError(
+            f"{name} is {len(data)} bytes, more than the {max_bytes} byte limit"
+        )
+
+
+def _b64(data: bytes) -> str:
+    return base64.b64encode(data).decode("ascii")
+
+
+def _data_url(data: bytes, mime_type: str) -> str:
+    return f"data:{mime_type};base64,{_b64(data)}"
+
+
+def _guess_type(name: Optional[str]) -> Optional[str]:
+    return mimetypes.guess_type(name)[0] if name else None
+
+
+def _extension(name: Optional[str]) -> Optional[str]:
+    if not name:
+        return None
+    return os.path.splitext(name)[1].lstrip(".").lower() or None
+
+
+def _sniff_image_type(data: bytes) -> Optional[str]:
+    """Detect the MIME type of an image from its magic bytes."""
+    if data.startswith(b"\x89PNG\r\n\x1a\n"):
+        return "image/png"
+    if data.startswith(b"\xff\xd8\xff"):
+        return "image/jpeg"
+    if data.startswith((b"GIF87a", b"GIF89a")):
+        return "image/gif"
+    if data[:4] == b"RIFF" and data[8:12] == b"WEBP":
+        return "image/webp"
+    return None
+
+
+def _sniff_audio_format(data: bytes) -> Optional[str]:
+    """Detect the format of an audio clip from its magic bytes."""
+    if data[:4] == b"RIFF" and data[8:12] == b"WAVE":
+        return "wav"
+    if data.startswith(b"ID3") or (len(data) > 1 and data[0] == 0xFF and data[1] & 0xE0 == 0xE0):
+        return "mp3"
+    return None
+
+
+def _resize(data: bytes, mime_type: str, max_dimension: int) -> bytes:
+    """Scale an image down so neither side exceeds max_dimension."""
+    try:
+        from PIL import Image
+    except ImportError:
+        raise ConfigurationError(
+            "Resizing images requires Pillow; install it with `pip install pillow`"
+        )
+
+    with Image.open(io.BytesIO(data)) as image:
+        if max(image.size) <= max_dimension:
+            return data
+        image.thumbnail((max_dimension, max_dimension))
+        output = io.BytesIO()
+        image.save(output, format=mime_type.split("/")[1].upper())
+        return output.getvalue()
@@ -9,7 +9,7 @@ class IntelliRouter:
     Main client for interacting with the IntelliRouter API.
     
     This client provides access to all IntelliRouter functionality, including
-    chat completions, chain execution, and model management.
+    chat completions, chain execution, agents, and model management.
     
     Args:
         api_key: API key for authentication. If not provided, will be read from
@@ -41,6 +41,7 @@ class IntelliRouter:
         # Initialize sub-clients
         self._chat = None
         self._chains = None
+        self._agents = None
         self._models = None
     
     @property
@@ -69,6 +70,19 @@ class IntelliRouter:
             self._chains = ChainClient(self.transport)
         return self._chains
     
+    @property
+    def agents(self):
+        """
+        Access the agent API.
+        
+        Returns:
+            AgentClient: Client for running agents.
+        """
+        if self._agents is None:
+            from .agents import AgentClient
+            self._agents = AgentClient(self.transport)
+        return self._agents
+    
     @property
     def models(self):
         """
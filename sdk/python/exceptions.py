@@ -21,6 +21,10 @@ class ServerError(APIError):
     """Exception raised when the server returns an error."""
     pass
 
+class CancelledError(IntelliRouterError):
+    """Exception raised when a request is cancelled by its cancellation token."""
+    pass
+
 class ValidationError(IntelliRouterError):
     """Exception raised when validation fails."""
     pass
@@ -0,0 +1,104 @@
+from typing import Callable, Dict, List, Optional
+from dataclasses import dataclass, field
+import threading
+
+from .exceptions import CancelledError
+
+# Header carrying the priority of a request
+PRIORITY_HEADER = "X-IntelliRouter-Priority"
+
+
+class CancellationToken:
+    """
+    Token cancelling the requests it is passed to, from any thread.
+
+    Cancelling closes in-flight responses, so streams stop at once. A
+    request still waiting for its response headers is abandoned when they
+    arrive. Requests started with a cancelled token fail immediately.
+
+    Example:
+        token = CancellationToken()
+        threading.Timer(5.0, token.cancel).start()
+        for chunk in client.chat.stream(..., options=CallOptions(cancel=token)):
+            ...
+    """
+
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._cancelled = False
+        self._callbacks: List[Callable[[], None]] = []
+
+    @property
+    def cancelled(self) -> bool:
+        """Whether the token was cancelled."""
+        return self._cancelled
+
+    def cancel(self) -> None:
+        """Cancel the requests using this token."""
+        with self._lock:
+            if self._cancelled:
+                return
+            self._cancelled = True
+            callbacks, self._callbacks = self._callbacks, []
+        for callback in callbacks:
+            callback()
+
+    def raise_if_cancelled(self) -> None:
+        """
+        Raise CancelledError if the token was cancelled.
+
+        Raises:
+            CancelledError: If the token was cancelled.
+        """
+        if self._cancelled:
+            raise CancelledError("Request cancelled")
+
+    def on_cancel(self, callback: Callable[[], None]) -> Callable[[], None]:
+        """
+        Call a function when the token is cancelled, or now if it already is.
+
+        Args:
+            callback: Function to call.
+
+        Returns:
+            Function unregistering the callback.
+        """
+        with self._lock:
+            if not self._cancelled:
+                self._callbacks.append(callback)
+                return lambda: self._unregister(callback)
+        callback()
+        return lambda: None
+
+    def _unregister(self, callback: Callable[[], None]) -> None:
+        with self._lock:
+            if callback in self._callbacks:
+                self._callbacks.remove(callback)
+
+
+@dataclass
+class CallOptions:
+    """
+    Options of a single API call, overriding the client configuration.
+
+    Args:
+        timeout: Request timeout in seconds, instead of the client's.
+        headers: Headers added to the request.
+        model: Model used instead of the one in the request.
+        priority: Priority of the request (0-255), evaluated by routing
+            policies as ``request.priority``.
+        cancel: Token cancelling the request.
+    """
+
+    timeout: Optional[float] = None
+    headers: Dict[str, str] = field(default_factory=dict)
+    model: Optional[str] = None
+    priority: Optional[int] = None
+    cancel: Optional[CancellationToken] = None
+
+    def request_headers(self) -> Dict[str, str]:
+        """Headers to add to the request."""
+        headers = dict(self.headers)
+        if self.priority is not None:
+            headers[PRIORITY_HEADER] = str(self.priority)
+        return headers
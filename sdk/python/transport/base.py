@@ -1,5 +1,6 @@
 from typing import Dict, Any, Optional, Union, AsyncIterator, Iterator
 from abc import ABC, abstractmethod
+from ..options import CallOptions
 
 class Transport(ABC):
     """
@@ -16,6 +17,7 @@ class Transport(ABC):
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Dict[str, Any]:
         """
         Make a synchronous request to the IntelliRouter API.
@@ -26,6 +28,7 @@ class Transport(ABC):
             params: Query parameters.
             data: Request body.
             stream: Whether to stream the response.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Response data.
@@ -40,6 +43,7 @@ class Transport(ABC):
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Dict[str, Any]:
         """
         Make an asynchronous request to the IntelliRouter API.
@@ -50,6 +54,7 @@ class Transport(ABC):
             params: Query parameters.
             data: Request body.
             stream: Whether to stream the response.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Response data.
@@ -63,6 +68,7 @@ class Transport(ABC):
         path: str,
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> Iterator[Dict[str, Any]]:
         """
         Make a streaming synchronous request to the IntelliRouter API.
@@ -72,6 +78,7 @@ class Transport(ABC):
             path: API path.
             params: Query parameters.
             data: Request body.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Iterator of response chunks.
@@ -85,6 +92,7 @@ class Transport(ABC):
         path: str,
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> AsyncIterator[Dict[str, Any]]:
         """
         Make a streaming asynchronous request to the IntelliRouter API.
@@ -94,6 +102,7 @@ class Transport(ABC):
             path: API path.
             params: Query parameters.
             data: Request body.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Async iterator of response chunks.
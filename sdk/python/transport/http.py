@@ -1,11 +1,13 @@
-from typing import Dict, Any, Optional, Union, AsyncIterator, Iterator
+from typing import Callable, Dict, Any, Optional, Union, AsyncIterator, Iterator
 import requests
 import aiohttp
 import json
 import sseclient
 import asyncio
+import time
 from ..config import Configuration
-from ..exceptions import APIError, AuthenticationError, RateLimitError, ServerError
+from ..exceptions import APIError, AuthenticationError, CancelledError, RateLimitError, ServerError
+from ..options import CallOptions, CancellationToken
 from .base import Transport
 
 class HTTPTransport(Transport):
@@ -31,6 +33,7 @@ class HTTPTransport(Transport):
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Dict[str, Any]:
         """
         Make a synchronous request to the IntelliRouter API.
@@ -41,6 +44,7 @@ class HTTPTransport(Transport):
             params: Query parameters.
             data: Request body.
             stream: Whether to stream the response.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Response data.
@@ -50,8 +54,13 @@ class HTTPTransport(Transport):
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
         url = f"{self.config.base_url}{path}"
+        options = options or CallOptions()
+        cancel = options.cancel
+        if cancel is not None:
+            cancel.raise_if_cancelled()
         
         try:
             response = self.session.request(
@@ -59,18 +68,27 @@ class HTTPTransport(Transport):
                 url=url,
                 params=params,
                 json=data,
-                stream=stream,
-                timeout=self.config.timeout,
+                headers=options.request_headers(),
+                # Bodies of cancellable requests are read lazily, so that
+                # cancelling can close them
+                stream=stream or cancel is not None,
+                timeout=self._timeout(options),
             )
             
             if stream:
                 return response
             
-            if response.status_code >= 400:
-                self._handle_error_response(response)
-            
-            return response.json()
-        except requests.exceptions.RequestException as e:
+            unregister = self._close_on_cancel(cancel, response.close)
+            try:
+                if response.status_code >= 400:
+                    self._handle_error_response(response)
+                
+                return response.json()
+            finally:
+                unregister()
+        except (requests.exceptions.RequestException, ValueError) as e:
+            if cancel is not None and cancel.cancelled:
+                raise CancelledError("Request cancelled") from e
             raise APIError(f"Request failed: {str(e)}")
     
     async def arequest(
@@ -80,6 +98,7 @@ class HTTPTransport(Transport):
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
         stream: bool = False,
+        options: Optional[CallOptions] = None,
     ) -> Dict[str, Any]:
         """
         Make an asynchronous request to the IntelliRouter API.
@@ -90,6 +109,7 @@ class HTTPTransport(Transport):
             params: Query parameters.
             data: Request body.
             stream: Whether to stream the response.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Response data.
@@ -99,6 +119,40 @@ class HTTPTransport(Transport):
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
+        """
+        options = options or CallOptions()
+        cancel = options.cancel
+        if cancel is None:
+            return await self._arequest(method, path, params, data, stream, options)
+        
+        # Cancelling the token cancels the task making the request
+        cancel.raise_if_cancelled()
+        loop = asyncio.get_running_loop()
+        task = asyncio.ensure_future(
+            self._arequest(method, path, params, data, stream, options)
+        )
+        unregister = cancel.on_cancel(lambda: loop.call_soon_threadsafe(task.cancel))
+        try:
+            return await task
+        except asyncio.CancelledError:
+            if cancel.cancelled:
+                raise CancelledError("Request cancelled")
+            raise
+        finally:
+            unregister()
+    
+    async def _arequest(
+        self,
+        method: str,
+        path: str,
+        params: Optional[Dict[str, Any]],
+        data: Optional[Dict[str, Any]],
+        stream: bool,
+        options: CallOptions,
+    ) -> Dict[str, Any]:
+        """
+        Make an asynchronous request, without cancellation.
         """
         url = f"{self.config.base_url}{path}"
         
@@ -106,6 +160,7 @@ class HTTPTransport(Transport):
             headers = {
                 "Authorization": f"Bearer {self.config.api_key}",
                 "Content-Type": "application/json",
+                **options.request_headers(),
             }
             
             try:
@@ -115,7 +170,7 @@ class HTTPTransport(Transport):
                     params=params,
                     json=data,
                     headers=headers,
-                    timeout=self.config.timeout,
+                    timeout=self._timeout(options),
                 ) as response:
                     if stream:
                         return response
@@ -133,15 +188,22 @@ class HTTPTransport(Transport):
         path: str,
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> Iterator[Dict[str, Any]]:
         """
         Make a streaming synchronous request to the IntelliRouter API.
         
+        If the connection drops mid-stream, the request is repeated with the
+        ``Last-Event-ID`` of the last event received (up to ``max_retries``
+        times), so the server can resume the stream instead of restarting
+        the generation.
+        
         Args:
             method: HTTP method (GET, POST, etc.).
             path: API path.
             params: Query parameters.
             data: Request body.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Iterator of response chunks.
@@ -151,22 +213,91 @@ class HTTPTransport(Transport):
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
+        """
+        options = options or CallOptions()
+        cancel = options.cancel
+        last_event_id = None
+        retries = 0
+        
+        while True:
+            if cancel is not None:
+                cancel.raise_if_cancelled()
+            response = self._open_stream(method, path, params, data, last_event_id, options)
+            unregister = self._close_on_cancel(cancel, response.close)
+            
+            try:
+                for event in sseclient.SSEClient(response).events():
+                    if cancel is not None:
+                        cancel.raise_if_cancelled()
+                    if event.id:
+                        last_event_id = event.id
+                        retries = 0
+                    
+                    if event.data == "[DONE]":
+                        return
+                    
+                    try:
+                        yield json.loads(event.data)
+                    except json.JSONDecodeError:
+                        raise APIError(f"Invalid JSON in stream: {event.data}")
+                # A response closed by cancelling can also end quietly
+                if cancel is not None:
+                    cancel.raise_if_cancelled()
+                return
+            except CancelledError:
+                raise
+            except Exception as e:
+                # Reading a response closed by cancelling fails in many ways
+                if cancel is not None and cancel.cancelled:
+                    raise CancelledError("Request cancelled") from e
+                if not isinstance(e, requests.exceptions.RequestException):
+                    raise
+                
+                # Streams without event IDs cannot be resumed
+                if last_event_id is None or retries >= self.config.max_retries:
+                    raise APIError(f"Stream interrupted: {str(e)}")
+                retries += 1
+                
+                # Exponential backoff
+                time.sleep(2 ** (retries - 1))
+            finally:
+                unregister()
+    
+    def _open_stream(
+        self,
+        method: str,
+        path: str,
+        params: Optional[Dict[str, Any]],
+        data: Optional[Dict[str, Any]],
+        last_event_id: Optional[str] = None,
+        options: Optional[CallOptions] = None,
+    ) -> requests.Response:
         """
-        response = self.request(method, path, params, data, stream=True)
+        Open an event stream, resuming after last_event_id if given.
+        """
+        options = options or CallOptions()
+        headers = {**options.request_headers(), "Accept": "text/event-stream"}
+        if last_event_id is not None:
+            headers["Last-Event-ID"] = last_event_id
+        
+        try:
+            response = self.session.request(
+                method=method,
+                url=f"{self.config.base_url}{path}",
+                params=params,
+                json=data,
+                headers=headers,
+                stream=True,
+                timeout=self._timeout(options),
+            )
+        except requests.exceptions.RequestException as e:
+            raise APIError(f"Request failed: {str(e)}")
         
         if response.status_code >= 400:
             self._handle_error_response(response)
         
-        client = sseclient.SSEClient(response)
-        
-        for event in client.events():
-            if event.data == "[DONE]":
-                break
-            
-            try:
-                yield json.loads(event.data)
-            except json.JSONDecodeError:
-                raise APIError(f"Invalid JSON in stream: {event.data}")
+        return response
     
     async def astream(
         self,
@@ -174,15 +305,19 @@ class HTTPTransport(Transport):
         path: str,
         params: Optional[Dict[str, Any]] = None,
         data: Optional[Dict[str, Any]] = None,
+        options: Optional[CallOptions] = None,
     ) -> AsyncIterator[Dict[str, Any]]:
         """
         Make a streaming asynchronous request to the IntelliRouter API.
         
+        Dropped streams are resumed with ``Last-Event-ID`` like in ``stream``.
+        
         Args:
             method: HTTP method (GET, POST, etc.).
             path: API path.
             params: Query parameters.
             data: Request body.
+            options: Options of the call, overriding the configuration.
         
         Returns:
             Async iterator of response chunks.
@@ -192,28 +327,124 @@ class HTTPTransport(Transport):
             AuthenticationError: If authentication fails.
             RateLimitError: If the rate limit is exceeded.
             ServerError: If the server returns an error.
+            CancelledError: If the call is cancelled by its token.
         """
-        response = await self.arequest(method, path, params, data, stream=True)
-        
-        if response.status >= 400:
-            await self._ahandle_error_response(response)
+        url = f"{self.config.base_url}{path}"
+        options = options or CallOptions()
+        cancel = options.cancel
+        loop = asyncio.get_running_loop()
+        last_event_id = None
+        retries = 0
         
+        async with aiohttp.ClientSession() as session:
+            while True:
+                if cancel is not None:
+                    cancel.raise_if_cancelled()
+                headers = {
+                    "Authorization": f"Bearer {self.config.api_key}",
+                    "Content-Type": "application/json",
+                    **options.request_headers(),
+                    "Accept": "text/event-stream",
+                }
+                if last_event_id is not None:
+                    headers["Last-Event-ID"] = last_event_id
+                
+                try:
+                    async with session.request(
+                        method=method,
+                        url=url,
+                        params=params,
+                        json=data,
+                        headers=headers,
+                        timeout=self._timeout(options),
+                    ) as response:
+                        if response.status >= 400:
+                            await self._ahandle_error_response(response)
+                        
+                        unregister = self._close_on_cancel(
+                            cancel, lambda: loop.call_soon_threadsafe(response.close)
+                        )
+                        try:
+                            async for event in self._aevents(response, cancel):
+                                event_id, payload = event
+                                if event_id is not None:
+                                    last_event_id = event_id
+                                    retries = 0
+                                    continue
+                                
+                                if payload == "[DONE]":
+                                    return
+                                
+                                try:
+                                    yield json.loads(payload)
+                                except json.JSONDecodeError:
+                                    raise APIError(f"Invalid JSON in stream: {payload}")
+                        finally:
+                            unregister()
+                        return
+                except aiohttp.ClientError as e:
+                    if cancel is not None and cancel.cancelled:
+                        raise CancelledError("Request cancelled") from e
+                    
+                    # Streams without event IDs cannot be resumed
+                    if last_event_id is None or retries >= self.config.max_retries:
+                        raise APIError(f"Stream interrupted: {str(e)}")
+                    retries += 1
+                    
+                    # Exponential backoff
+                    await asyncio.sleep(2 ** (retries - 1))
+    
+    async def _aevents(
+        self,
+        response: aiohttp.ClientResponse,
+        cancel: Optional[CancellationToken],
+    ) -> AsyncIterator[tuple]:
+        """
+        Parse an event stream into ``(event_id, None)`` at the end of events
+        with an ID and ``(None, data)`` for data lines.
+        """
+        event_id = None
         async for line in response.content:
+            if cancel is not None:
+                cancel.raise_if_cancelled()
             line = line.decode("utf-8").strip()
             
             if not line:
+                # A blank line ends an event
+                if event_id is not None:
+                    yield event_id, None
+                    event_id = None
                 continue
             
-            if line.startswith("data:"):
-                data = line[5:].strip()
-                
-                if data == "[DONE]":
-                    break
-                
-                try:
-                    yield json.loads(data)
-                except json.JSONDecodeError:
-                    raise APIError(f"Invalid JSON in stream: {data}")
+            if line.startswith("id:"):
+                event_id = line[3:].strip()
+            elif line.startswith("data:"):
+                yield None, line[5:].strip()
+        
+        # A response closed by cancelling can also end quietly
+        if cancel is not None:
+            cancel.raise_if_cancelled()
+    
+    def _timeout(self, options: CallOptions) -> float:
+        """
+        Timeout of a call, from its options or the configuration.
+        """
+        return options.timeout if options.timeout is not None else self.config.timeout
+    
+    def _close_on_cancel(
+        self,
+        cancel: Optional[CancellationToken],
+        close: Callable[[], None],
+    ) -> Callable[[], None]:
+        """
+        Close a response when the call is cancelled.
+        
+        Returns:
+            Function unregistering the close.
+        """
+        if cancel is None:
+            return lambda: None
+        return cancel.on_cancel(close)
     
     def _handle_error_response(self, response: requests.Response) -> None:
         """
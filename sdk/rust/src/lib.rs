@@ -1,7 +1,7 @@
 //! # IntelliRouter
 //!
 //! The IntelliRouter Rust SDK provides a clean, idiomatic interface for interacting with IntelliRouter,
-//! including support for chat completions, streaming, and chain execution.
+//! including support for chat completions, streaming, chain execution, and agents.
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -11,8 +11,14 @@ use serde::{Deserialize, Serialize};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use thiserror::Error;
 
+pub use tokio_util::sync::CancellationToken;
+
+/// Header carrying the priority of a request
+pub const PRIORITY_HEADER: &str = "x-intellirouter-priority";
+
 /// Error types for the IntelliRouter SDK
 #[derive(Debug, Error)]
 pub enum Error {
@@ -36,6 +42,9 @@ pub enum Error {
     /// Request error
     #[error("Request error: {0}")]
     RequestError(#[from] reqwest::Error),
+    /// Request cancelled by its cancellation token
+    #[error("Request cancelled")]
+    Cancelled,
 }
 
 /// Result type for the IntelliRouter SDK
@@ -65,6 +74,90 @@ impl Default for ClientConfig {
     }
 }
 
+/// Options of a single API call, overriding the client configuration
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    /// Request timeout, instead of the client's
+    pub timeout: Option<Duration>,
+    /// Headers added to the request
+    pub headers: Vec<(String, String)>,
+    /// Model used instead of the one in the request
+    pub model: Option<String>,
+    /// Priority of the request, evaluated by routing policies as
+    /// `request.priority`
+    pub priority: Option<u8>,
+    /// Token cancelling the request; cancelled calls fail with
+    /// [`Error::Cancelled`]
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl CallOptions {
+    /// Create empty call options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Add a header to the request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Override the model of the request
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Set the priority of the request
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Cancel the request with a token
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Apply the timeout and headers to a request
+    fn apply(&self, mut request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(timeout) = self.timeout {
+            request = request.timeout(timeout);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(priority) = self.priority {
+            request = request.header(PRIORITY_HEADER, priority.to_string());
+        }
+        request
+    }
+
+    /// Send a request and parse its response, unless cancelled first
+    async fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<T> {
+        let call = async { parse_response(self.apply(request).send().await?).await };
+        match &self.cancellation {
+            Some(token) => tokio::select! {
+                // Dropping the call closes its connection
+                _ = token.cancelled() => Err(Error::Cancelled),
+                result = call => result,
+            },
+            None => call.await,
+        }
+    }
+}
+
 /// Main client for the IntelliRouter SDK
 pub struct IntelliRouter {
     client: Arc<Client>,
@@ -108,6 +201,14 @@ impl IntelliRouter {
             config: self.config.clone(),
         }
     }
+
+    /// Get the agents API
+    pub fn agents(&self) -> Agents {
+        Agents {
+            client: Arc::clone(&self.client),
+            config: self.config.clone(),
+        }
+    }
 }
 
 /// Chat completions API
@@ -122,5 +223,195 @@ pub struct Chains {
     config: ClientConfig,
 }
 
+/// Agents API
+pub struct Agents {
+    client: Arc<Client>,
+    config: ClientConfig,
+}
+
+/// Definition of a ReAct-style agent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    /// Agent ID, used in traces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Model the agent reasons with
+    pub model: String,
+    /// Instructions prepended to the conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Tools the agent may call, limited to those the server configures for
+    /// the agent (none if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+    /// Maximum number of model calls
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_steps: Option<u32>,
+    /// Maximum number of tool calls across the run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tool_calls: Option<u32>,
+    /// Phrases marking the final answer, e.g. `FINAL ANSWER:`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_phrases: Vec<String>,
+    /// Wall-clock budget for the run, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Sampling temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+}
+
+/// Tool call made by an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentToolInvocation {
+    /// Tool call ID assigned by the model
+    pub call_id: String,
+    /// Tool name
+    pub name: String,
+    /// Arguments as sent by the model
+    pub arguments: String,
+    /// Tool result, if the call succeeded
+    pub result: Option<serde_json::Value>,
+    /// Error message, if the call failed
+    pub error: Option<String>,
+    /// Call duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// One reasoning iteration of an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStep {
+    /// Iteration number, starting at 1
+    pub index: u32,
+    /// Text produced by the model in this iteration
+    pub thought: String,
+    /// Tool calls made in this iteration
+    pub actions: Vec<AgentToolInvocation>,
+    /// Tokens used by the model call, if reported
+    pub total_tokens: Option<u32>,
+    /// Iteration duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Model usage of an agent run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentUsage {
+    /// Prompt tokens across all model calls
+    pub prompt_tokens: u64,
+    /// Completion tokens across all model calls
+    pub completion_tokens: u64,
+    /// Cost of the model calls, in the server's pricing currency
+    pub cost: f64,
+}
+
+/// Trace of an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRun {
+    /// Run ID
+    pub run_id: String,
+    /// Agent ID
+    pub agent_id: String,
+    /// Input given to the agent
+    pub input: String,
+    /// Final answer, absent when a budget was exhausted first
+    pub answer: Option<String>,
+    /// Why the run stopped (`final_answer`, `stop_phrase`, `max_steps`,
+    /// `max_tool_calls` or `timeout`)
+    pub stop_reason: String,
+    /// Reasoning iterations
+    pub steps: Vec<AgentStep>,
+    /// Tokens used across all model calls
+    pub total_tokens: u32,
+    /// Usage of the model calls, priced by the server
+    #[serde(default)]
+    pub usage: AgentUsage,
+    /// Conversation the run was recorded in
+    pub conversation_id: Option<String>,
+    /// When the run started, in RFC 3339 format
+    pub started_at: String,
+    /// When the run finished, in RFC 3339 format
+    pub finished_at: String,
+}
+
+impl Agents {
+    /// Run an agent until it answers or a stop condition is hit
+    pub async fn run(
+        &self,
+        agent: &AgentDefinition,
+        input: &str,
+        conversation_id: Option<&str>,
+    ) -> Result<AgentRun> {
+        self.run_with_options(agent, input, conversation_id, &CallOptions::default())
+            .await
+    }
+
+    /// Run an agent with options overriding the client configuration
+    pub async fn run_with_options(
+        &self,
+        agent: &AgentDefinition,
+        input: &str,
+        conversation_id: Option<&str>,
+        options: &CallOptions,
+    ) -> Result<AgentRun> {
+        let mut body = serde_json::json!({
+            "agent": agent,
+            "input": input,
+            "conversation_id": conversation_id,
+        });
+        if let Some(model) = &options.model {
+            body["agent"]["model"] = serde_json::Value::String(model.clone());
+        }
+        let request = self
+            .client
+            .post(format!("{}/v1/agents/run", self.config.base_url))
+            .bearer_auth(&self.config.api_key)
+            .json(&body);
+        options.send(request).await
+    }
+
+    /// Get the trace of a recent agent run
+    pub async fn get_run(&self, run_id: &str) -> Result<AgentRun> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/v1/agents/runs/{}",
+                self.config.base_url, run_id
+            ))
+            .bearer_auth(&self.config.api_key)
+            .send()
+            .await?;
+        parse_response(response).await
+    }
+
+    /// List recent agent runs, newest first
+    pub async fn list_runs(&self) -> Result<Vec<AgentRun>> {
+        let response = self
+            .client
+            .get(format!("{}/v1/agents/runs", self.config.base_url))
+            .bearer_auth(&self.config.api_key)
+            .send()
+            .await?;
+        parse_response(response).await
+    }
+}
+
+/// Parse a JSON response, mapping API errors
+async fn parse_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+
+    let body: serde_json::Value = response.json().await.unwrap_or_default();
+    match body.get("error") {
+        Some(error) => Err(Error::ApiError {
+            code: error["code"].as_str().unwrap_or_default().to_string(),
+            message: error["message"].as_str().unwrap_or_default().to_string(),
+        }),
+        None => Err(Error::HttpError(status)),
+    }
+}
+
 // This is a basic skeleton - the actual implementation would include
 // methods for creating chat completions, streaming responses, etc.
@@ -0,0 +1,74 @@
+import { AgentClient } from '../client';
+import { ValidationError } from '../../errors';
+import { AgentRun, AgentRunRequest } from '../types';
+
+// Mock transport
+const mockTransport = {
+    post: jest.fn(),
+    get: jest.fn(),
+};
+
+describe('AgentClient', () => {
+    let client: AgentClient;
+
+    const run: AgentRun = {
+        run_id: 'run-1',
+        agent_id: 'agent',
+        input: 'What is 6 times 7?',
+        answer: '42',
+        stop_reason: 'final_answer',
+        steps: [],
+        total_tokens: 12,
+        started_at: '2024-01-01T00:00:00Z',
+        finished_at: '2024-01-01T00:00:01Z',
+    };
+
+    beforeEach(() => {
+        jest.clearAllMocks();
+        client = new AgentClient(mockTransport as any);
+    });
+
+    describe('run', () => {
+        it('should post the run request', async () => {
+            const request: AgentRunRequest = {
+                agent: { model: 'gpt-4', tools: ['calculator'], max_steps: 5 },
+                input: 'What is 6 times 7?',
+            };
+            mockTransport.post.mockResolvedValue(run);
+
+            const result = await client.run(request);
+            expect(result).toEqual(run);
+            expect(mockTransport.post).toHaveBeenCalledWith('/v1/agents/run', request);
+        });
+
+        it('should throw validation error without a model', async () => {
+            const request = {
+                agent: {},
+                input: 'Hello',
+            } as unknown as AgentRunRequest;
+
+            await expect(client.run(request)).rejects.toThrow(ValidationError);
+            expect(mockTransport.post).not.toHaveBeenCalled();
+        });
+
+        it('should throw validation error for a zero step budget', async () => {
+            const request: AgentRunRequest = {
+                agent: { model: 'gpt-4', max_steps: 0 },
+                input: 'Hello',
+            };
+
+            await expect(client.run(request)).rejects.toThrow(ValidationError);
+            expect(mockTransport.post).not.toHaveBeenCalled();
+        });
+    });
+
+    describe('getRun', () => {
+        it('should get the run trace by ID', async () => {
+            mockTransport.get.mockResolvedValue(run);
+
+            const result = await client.getRun('run-1');
+            expect(result).toEqual(run);
+            expect(mockTransport.get).toHaveBeenCalledWith('/v1/agents/runs/run-1');
+        });
+    });
+});
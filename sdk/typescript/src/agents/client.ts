@@ -0,0 +1,75 @@
+import { Transport } from '../transport';
+import { ValidationError } from '../errors';
+import { CallOptions } from '../types';
+import { AgentRun, AgentRunRequest } from './types';
+
+/**
+ * Client for running agents
+ */
+export class AgentClient {
+    private readonly transport: Transport;
+
+    /**
+     * Create a new agent client
+     * @param transport Transport layer
+     */
+    constructor(transport: Transport) {
+        this.transport = transport;
+    }
+
+    /**
+     * Validate agent run request
+     * @param request Agent run request
+     * @throws ValidationError if request is invalid
+     */
+    private validateRunRequest(request: AgentRunRequest): void {
+        if (!request.agent) {
+            throw new ValidationError('Agent definition is required');
+        }
+
+        if (!request.agent.model) {
+            throw new ValidationError('Agent model is required');
+        }
+
+        if (request.agent.max_steps !== undefined && request.agent.max_steps < 1) {
+            throw new ValidationError('Agent max_steps must be at least 1');
+        }
+
+        if (!request.input) {
+            throw new ValidationError('Agent input is required');
+        }
+    }
+
+    /**
+     * Run an agent until it answers or a stop condition is hit
+     * @param request Agent run request
+     * @param callOptions Options of the call, such as its timeout, model override or abort signal
+     * @returns Promise resolving to the trace of the run
+     */
+    public async run(request: AgentRunRequest, callOptions?: CallOptions): Promise<AgentRun> {
+        if (callOptions?.model) {
+            request = { ...request, agent: { ...request.agent, model: callOptions.model } };
+        }
+        this.validateRunRequest(request);
+        return callOptions
+            ? this.transport.post<AgentRun>('/v1/agents/run', request, undefined, callOptions)
+            : this.transport.post<AgentRun>('/v1/agents/run', request);
+    }
+
+    /**
+     * Get the trace of a recent agent run
+     * @param runId Run ID
+     * @returns Promise resolving to the trace of the run
+     */
+    public async getRun(runId: string): Promise<AgentRun> {
+        return this.transport.get<AgentRun>(`/v1/agents/runs/${runId}`);
+    }
+
+    /**
+     * List recent agent runs, newest first
+     * @returns Promise resolving to an array of run traces
+     */
+    public async listRuns(): Promise<AgentRun[]> {
+        return this.transport.get<AgentRun[]>('/v1/agents/runs');
+    }
+}
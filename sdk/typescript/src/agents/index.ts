@@ -0,0 +1,2 @@
+export * from './client';
+export * from './types';
\ No newline at end of file
@@ -0,0 +1,199 @@
+/**
+ * Definition of a ReAct-style agent
+ */
+export interface AgentDefinition {
+    /**
+     * Agent ID, used in traces
+     */
+    id?: string;
+
+    /**
+     * Model the agent reasons with
+     */
+    model: string;
+
+    /**
+     * Instructions prepended to the conversation
+     */
+    system_prompt?: string;
+
+    /**
+     * Tools the agent may call (empty allows every registered tool)
+     */
+    tools?: string[];
+
+    /**
+     * Maximum number of model calls
+     */
+    max_steps?: number;
+
+    /**
+     * Maximum number of tool calls across the run
+     */
+    max_tool_calls?: number;
+
+    /**
+     * Phrases marking the final answer, e.g. "FINAL ANSWER:"
+     */
+    stop_phrases?: string[];
+
+    /**
+     * Wall-clock budget for the run, in seconds
+     */
+    timeout_secs?: number;
+
+    /**
+     * Sampling temperature
+     */
+    temperature?: number;
+
+    /**
+     * Number of conversation messages loaded from memory
+     */
+    memory_window?: number;
+}
+
+/**
+ * Agent run request
+ */
+export interface AgentRunRequest {
+    /**
+     * Agent definition
+     */
+    agent: AgentDefinition;
+
+    /**
+     * Task given to the agent
+     */
+    input: string;
+
+    /**
+     * Conversation to load history from and record the run in
+     */
+    conversation_id?: string;
+}
+
+/**
+ * Why an agent run stopped
+ */
+export type AgentStopReason = 'final_answer' | 'stop_phrase' | 'max_steps' | 'max_tool_calls' | 'timeout';
+
+/**
+ * Tool call made by an agent
+ */
+export interface AgentToolInvocation {
+    /**
+     * Tool call ID assigned by the model
+     */
+    call_id: string;
+
+    /**
+     * Tool name
+     */
+    name: string;
+
+    /**
+     * Arguments as sent by the model
+     */
+    arguments: string;
+
+    /**
+     * Tool result, if the call succeeded
+     */
+    result?: unknown;
+
+    /**
+     * Error message, if the call failed
+     */
+    error?: string;
+
+    /**
+     * Call duration in milliseconds
+     */
+    duration_ms: number;
+}
+
+/**
+ * One reasoning iteration of an agent run
+ */
+export interface AgentStep {
+    /**
+     * Iteration number, starting at 1
+     */
+    index: number;
+
+    /**
+     * Text produced by the model in this iteration
+     */
+    thought: string;
+
+    /**
+     * Tool calls made in this iteration
+     */
+    actions: AgentToolInvocation[];
+
+    /**
+     * Tokens used by the model call, if reported
+     */
+    total_tokens?: number;
+
+    /**
+     * Iteration duration in milliseconds
+     */
+    duration_ms: number;
+}
+
+/**
+ * Trace of an agent run
+ */
+export interface AgentRun {
+    /**
+     * Run ID
+     */
+    run_id: string;
+
+    /**
+     * Agent ID
+     */
+    agent_id: string;
+
+    /**
+     * Input given to the agent
+     */
+    input: string;
+
+    /**
+     * Final answer, absent when a budget was exhausted first
+     */
+    answer?: string;
+
+    /**
+     * Why the run stopped
+     */
+    stop_reason: AgentStopReason;
+
+    /**
+     * Reasoning iterations
+     */
+    steps: AgentStep[];
+
+    /**
+     * Tokens used across all model calls
+     */
+    total_tokens: number;
+
+    /**
+     * Conversation the run was recorded in
+     */
+    conversation_id?: string;
+
+    /**
+     * Start time (ISO 8601)
+     */
+    started_at: string;
+
+    /**
+     * Finish time (ISO 8601)
+     */
+    finished_at: string;
+}
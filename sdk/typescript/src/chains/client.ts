@@ -1,5 +1,6 @@
 import { Transport } from '../transport';
 import { ValidationError } from '../errors';
+import { CallOptions } from '../types';
 import { ChainDefinition, ChainExecutionRequest, ChainExecutionResponse, ChainNode, ChainEdge } from './types';
 import { asyncIterableToStream } from '../transport/stream';
 
@@ -134,24 +135,31 @@ export class ChainClient {
     /**
      * Execute a chain
      * @param request Chain execution request
+     * @param callOptions Options of the call, such as its timeout or abort signal
      * @returns Promise resolving to the chain execution response
      */
-    public async executeChain(request: ChainExecutionRequest): Promise<ChainExecutionResponse> {
+    public async executeChain(
+        request: ChainExecutionRequest,
+        callOptions?: CallOptions
+    ): Promise<ChainExecutionResponse> {
         this.validateExecutionRequest(request);
 
         if (request.stream) {
             throw new Error('Use executeChainStream for streaming responses');
         }
 
-        return this.transport.post<ChainExecutionResponse>('/v1/chains/execute', request);
+        return callOptions
+            ? this.transport.post<ChainExecutionResponse>('/v1/chains/execute', request, undefined, callOptions)
+            : this.transport.post<ChainExecutionResponse>('/v1/chains/execute', request);
     }
 
     /**
      * Execute a chain with streaming response
      * @param request Chain execution request
+     * @param callOptions Options of the call, such as its timeout or abort signal
      * @returns Promise resolving to a stream of chain execution chunks
      */
-    public async executeChainStream(request: ChainExecutionRequest): Promise<ReadableStream<any>> {
+    public async executeChainStream(request: ChainExecutionRequest, callOptions?: CallOptions): Promise<ReadableStream<any>> {
         this.validateExecutionRequest(request);
 
         const streamRequest = {
@@ -159,7 +167,9 @@ export class ChainClient {
             stream: true,
         };
 
-        const stream = await this.transport.postStream('/v1/chains/execute', streamRequest);
+        const stream = callOptions
+            ? await this.transport.postStream('/v1/chains/execute', streamRequest, undefined, callOptions)
+            : await this.transport.postStream('/v1/chains/execute', streamRequest);
 
         return asyncIterableToStream(stream);
     }
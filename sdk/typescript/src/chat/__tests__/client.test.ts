@@ -137,6 +137,25 @@ describe('ChatClient', () => {
             await expect(client.createCompletion(options)).rejects.toThrow('Use createCompletionStream for streaming responses');
             expect(mockTransport.post).not.toHaveBeenCalled();
         });
+
+        it('should apply the model override and pass the options of the call', async () => {
+            const options: ChatCompletionOptions = {
+                model: 'gpt-3.5-turbo',
+                messages: [
+                    { role: 'user' as ChatRole, content: 'Hello' },
+                ],
+            };
+            const callOptions = { model: 'gpt-4o', timeout: 5000, priority: 3 };
+            mockTransport.post.mockResolvedValue({});
+
+            await client.createCompletion(options, callOptions);
+            expect(mockTransport.post).toHaveBeenCalledWith(
+                '/v1/chat/completions',
+                { ...options, model: 'gpt-4o' },
+                undefined,
+                callOptions
+            );
+        });
     });
 
     describe('createCompletionStream', () => {
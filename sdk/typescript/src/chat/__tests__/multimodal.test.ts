@@ -0,0 +1,84 @@
+import { promises as fs } from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import { ValidationError } from '../../errors';
+import { audioPart, filePart, imagePart, userMessage } from '../multimodal';
+
+const png = Buffer.from([0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x01]);
+const wav = Buffer.concat([Buffer.from('RIFF'), Buffer.alloc(4), Buffer.from('WAVE')]);
+
+describe('multimodal helpers', () => {
+    let dir: string;
+
+    beforeAll(async () => {
+        dir = await fs.mkdtemp(path.join(os.tmpdir(), 'intellirouter-'));
+    });
+
+    afterAll(async () => {
+        await fs.rm(dir, { recursive: true, force: true });
+    });
+
+    describe('imagePart', () => {
+        it('should inline images as data URLs with the detected type', async () => {
+            const part = await imagePart(png, { detail: 'low' });
+            expect(part.image_url.url).toBe(`data:image/png;base64,${png.toString('base64')}`);
+            expect(part.image_url.detail).toBe('low');
+        });
+
+        it('should reject unsupported images', async () => {
+            await expect(imagePart(Buffer.from('not an image'))).rejects.toThrow(ValidationError);
+        });
+
+        it('should reject images over the size limit', async () => {
+            await expect(imagePart(png, { maxBytes: 4 })).rejects.toThrow(ValidationError);
+        });
+
+        it('should resize images before the size check', async () => {
+            const resize = jest.fn().mockResolvedValue(png.subarray(0, 8));
+            const part = await imagePart(png, { maxBytes: 8, resize });
+            expect(resize).toHaveBeenCalledWith(png, 'image/png');
+            expect(part.image_url.url).toBe(`data:image/png;base64,${png.subarray(0, 8).toString('base64')}`);
+        });
+    });
+
+    describe('audioPart', () => {
+        it('should detect the audio format', async () => {
+            const part = await audioPart(wav);
+            expect(part.input_audio).toEqual({ data: wav.toString('base64'), format: 'wav' });
+        });
+    });
+
+    describe('filePart', () => {
+        it('should read local files with their name', async () => {
+            const file = path.join(dir, 'report.pdf');
+            await fs.writeFile(file, 'pdf');
+
+            const part = await filePart(file);
+            expect(part.file).toEqual({ filename: 'report.pdf', file_data: Buffer.from('pdf').toString('base64') });
+        });
+    });
+
+    describe('userMessage', () => {
+        it('should build content parts from text and attachments', async () => {
+            const image = path.join(dir, 'chart.png');
+            await fs.writeFile(image, png);
+
+            const message = await userMessage('Describe these', {
+                images: [image, 'https://example.com/photo.jpg'],
+                audio: [wav],
+            });
+
+            expect(message.role).toBe('user');
+            expect(message.content).toEqual([
+                { type: 'text', text: 'Describe these' },
+                { type: 'image_url', image_url: { url: `data:image/png;base64,${png.toString('base64')}`, detail: 'auto' } },
+                { type: 'image_url', image_url: { url: 'https://example.com/photo.jpg', detail: 'auto' } },
+                { type: 'input_audio', input_audio: { data: wav.toString('base64'), format: 'wav' } },
+            ]);
+        });
+
+        it('should reject empty messages', async () => {
+            await expect(userMessage(undefined)).rejects.toThrow(ValidationError);
+        });
+    });
+});
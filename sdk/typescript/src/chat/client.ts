@@ -1,5 +1,6 @@
 import { Transport } from '../transport';
 import { ValidationError } from '../errors';
+import { CallOptions } from '../types';
 import {
     ChatCompletionOptions,
     ChatCompletionResponse,
@@ -76,32 +77,56 @@ export class ChatClient {
         }
     }
 
+    /**
+     * Apply the model override of a call
+     * @param options Chat completion options
+     * @param callOptions Options of the call
+     * @returns Chat completion options using the overriding model
+     */
+    private withModelOverride(options: ChatCompletionOptions, callOptions?: CallOptions): ChatCompletionOptions {
+        return callOptions?.model ? { ...options, model: callOptions.model } : options;
+    }
+
     /**
      * Create a chat completion
      * @param options Chat completion options
+     * @param callOptions Options of the call, such as its timeout, model override or abort signal
      * @returns Promise resolving to a chat completion response
      */
-    public async createCompletion(options: ChatCompletionOptions): Promise<ChatCompletionResponse> {
+    public async createCompletion(
+        options: ChatCompletionOptions,
+        callOptions?: CallOptions
+    ): Promise<ChatCompletionResponse> {
+        options = this.withModelOverride(options, callOptions);
         this.validateOptions(options);
 
         if (options.stream) {
             throw new Error('Use createCompletionStream for streaming responses');
         }
 
-        return this.transport.post<ChatCompletionResponse>('/v1/chat/completions', options);
+        return callOptions
+            ? this.transport.post<ChatCompletionResponse>('/v1/chat/completions', options, undefined, callOptions)
+            : this.transport.post<ChatCompletionResponse>('/v1/chat/completions', options);
     }
 
     /**
      * Create a streaming chat completion
      * @param options Chat completion options
+     * @param callOptions Options of the call, such as its timeout, model override or abort signal
      * @returns Promise resolving to a stream of chat completion chunks
      */
-    public async createCompletionStream(options: ChatCompletionOptions): Promise<ReadableStream<ChatCompletionChunk>> {
+    public async createCompletionStream(
+        options: ChatCompletionOptions,
+        callOptions?: CallOptions
+    ): Promise<ReadableStream<ChatCompletionChunk>> {
+        options = this.withModelOverride(options, callOptions);
         this.validateOptions(options);
 
         const streamOptions = { ...options, stream: true };
 
-        const stream = await this.transport.postStream('/v1/chat/completions', streamOptions);
+        const stream = callOptions
+            ? await this.transport.postStream('/v1/chat/completions', streamOptions, undefined, callOptions)
+            : await this.transport.postStream('/v1/chat/completions', streamOptions);
 
         // Use type assertion to convert the unknown stream to ChatCompletionChunk stream
         return asyncIterableToStream<ChatCompletionChunk>(stream as AsyncIterable<ChatCompletionChunk>);
@@ -125,7 +150,8 @@ export class ChatClient {
             ...options,
         });
 
-        return response.choices[0]?.message?.content || '';
+        const content = response.choices[0]?.message?.content;
+        return typeof content === 'string' ? content : '';
     }
 
     /**
@@ -1,2 +1,3 @@
 export * from './client';
-export * from './types';
\ No newline at end of file
+export * from './types';
+export * from './multimodal';
\ No newline at end of file
@@ -0,0 +1,290 @@
+import { promises as fs } from 'fs';
+import * as path from 'path';
+import { ValidationError } from '../errors';
+import {
+    AudioContentPart,
+    ChatMessage,
+    ContentPart,
+    FileContentPart,
+    ImageContentPart,
+    TextContentPart,
+} from './types';
+
+/**
+ * Default maximum size of an attached image, in bytes
+ */
+export const DEFAULT_MAX_IMAGE_BYTES = 20 * 1024 * 1024;
+
+/**
+ * Default maximum size of an attached audio clip or file, in bytes
+ */
+export const DEFAULT_MAX_FILE_BYTES = 25 * 1024 * 1024;
+
+/**
+ * Image types accepted by vision models
+ */
+export const IMAGE_MIME_TYPES = ['image/png', 'image/jpeg', 'image/gif', 'image/webp'];
+
+/**
+ * Image, audio or file source: a path or raw bytes
+ */
+export type Source = string | Uint8Array;
+
+/**
+ * Scales an image down, returning the encoded result
+ *
+ * The SDK has no image dependency; pass a function backed by e.g. `sharp`.
+ */
+export type ImageResizer = (data: Buffer, mimeType: string) => Promise<Buffer>;
+
+/**
+ * Options for image parts
+ */
+export interface ImagePartOptions {
+    /**
+     * Detail level for image processing
+     * @default 'auto'
+     */
+    detail?: 'auto' | 'low' | 'high';
+
+    /**
+     * MIME type of the image, detected from the content when omitted
+     */
+    mimeType?: string;
+
+    /**
+     * Maximum size of the encoded image, in bytes
+     * @default DEFAULT_MAX_IMAGE_BYTES
+     */
+    maxBytes?: number;
+
+    /**
+     * Resizer applied before the size check
+     */
+    resize?: ImageResizer;
+}
+
+/**
+ * Attachments of a user message
+ */
+export interface UserMessageAttachments {
+    /**
+     * Images: paths, bytes, http(s) URLs or content parts
+     */
+    images?: Array<Source | ContentPart>;
+
+    /**
+     * Audio clips: paths, bytes or content parts
+     */
+    audio?: Array<Source | ContentPart>;
+
+    /**
+     * Files: paths, bytes or content parts
+     */
+    files?: Array<Source | ContentPart>;
+
+    /**
+     * Options applied to attached images
+     */
+    imageOptions?: ImagePartOptions;
+}
+
+/**
+ * Create a text content part
+ * @param text Text
+ */
+export function textPart(text: string): TextContentPart {
+    return { type: 'text', text };
+}
+
+/**
+ * Create an image content part referencing an image by URL
+ * @param url Web URL or data URL of the image
+ * @param detail Detail level for image processing
+ */
+export function imageUrlPart(url: string, detail: 'auto' | 'low' | 'high' = 'auto'): ImageContentPart {
+    return { type: 'image_url', image_url: { url, detail } };
+}
+
+/**
+ * Create an image content part from a local image, inlined as a data URL
+ * @param source Path of the image, or its bytes
+ * @param options Image options
+ * @throws ValidationError if the image type is unsupported or the image is too large
+ */
+export async function imagePart(source: Source, options: ImagePartOptions = {}): Promise<ImageContentPart> {
+    const { data, name } = await read(source);
+    const mimeType = options.mimeType || sniffImageType(data) || imageTypeFromExtension(name);
+    if (!mimeType || !IMAGE_MIME_TYPES.includes(mimeType)) {
+        throw new ValidationError(
+            `Unsupported image type ${mimeType || 'unknown'} for ${name || 'image'}; ` +
+            `expected one of ${IMAGE_MIME_TYPES.join(', ')}`
+        );
+    }
+    const image = options.resize ? await options.resize(data, mimeType) : data;
+    checkSize(image, options.maxBytes ?? DEFAULT_MAX_IMAGE_BYTES, name || 'Image');
+
+    return imageUrlPart(`data:${mimeType};base64,${image.toString('base64')}`, options.detail);
+}
+
+/**
+ * Create an input_audio content part from a local audio clip
+ * @param source Path of the clip, or its bytes
+ * @param format Audio format, detected from the content or extension when omitted
+ * @param maxBytes Maximum size of the clip, in bytes
+ * @throws ValidationError if the audio format is unsupported or the clip is too large
+ */
+export async function audioPart(
+    source: Source,
+    format?: 'wav' | 'mp3',
+    maxBytes: number = DEFAULT_MAX_FILE_BYTES
+): Promise<AudioContentPart> {
+    const { data, name } = await read(source);
+    const detected = format || sniffAudioFormat(data) || extension(name);
+    if (detected !== 'wav' && detected !== 'mp3') {
+        throw new ValidationError(
+            `Unsupported audio format ${detected || 'unknown'} for ${name || 'audio'}; expected wav or mp3`
+        );
+    }
+    checkSize(data, maxBytes, name || 'Audio');
+
+    return { type: 'input_audio', input_audio: { data: data.toString('base64'), format: detected } };
+}
+
+/**
+ * Create a file content part from a local file, such as a PDF
+ * @param source Path of the file, or its bytes
+ * @param filename Name sent with the file, defaulting to the file's base name
+ * @param maxBytes Maximum size of the file, in bytes
+ * @throws ValidationError if the file is too large
+ */
+export async function filePart(
+    source: Source,
+    filename?: string,
+    maxBytes: number = DEFAULT_MAX_FILE_BYTES
+): Promise<FileContentPart> {
+    const { data, name } = await read(source);
+    checkSize(data, maxBytes, name || 'File');
+
+    const file: FileContentPart['file'] = { file_data: data.toString('base64') };
+    const sentName = filename || (name ? path.basename(name) : undefined);
+    if (sentName) {
+        file.filename = sentName;
+    }
+    return { type: 'file', file };
+}
+
+/**
+ * Create a file content part referencing a previously uploaded file
+ * @param fileId ID of the uploaded file
+ */
+export function fileIdPart(fileId: string): FileContentPart {
+    return { type: 'file', file: { file_id: fileId } };
+}
+
+/**
+ * Create a user message with text and attachments
+ * @param text Text of the message
+ * @param attachments Images, audio clips and files to attach
+ * @throws ValidationError if an attachment is invalid or the message is empty
+ */
+export async function userMessage(
+    text: string | undefined,
+    attachments: UserMessageAttachments = {}
+): Promise<ChatMessage> {
+    const parts: ContentPart[] = [];
+    if (text) {
+        parts.push(textPart(text));
+    }
+    for (const image of attachments.images || []) {
+        if (isContentPart(image)) {
+            parts.push(image);
+        } else if (typeof image === 'string' && /^https?:\/\//.test(image)) {
+            parts.push(imageUrlPart(image, attachments.imageOptions?.detail));
+        } else {
+            parts.push(await imagePart(image, attachments.imageOptions));
+        }
+    }
+    for (const clip of attachments.audio || []) {
+        parts.push(isContentPart(clip) ? clip : await audioPart(clip));
+    }
+    for (const file of attachments.files || []) {
+        parts.push(isContentPart(file) ? file : await filePart(file));
+    }
+
+    if (parts.length === 0) {
+        throw new ValidationError('A message needs text or at least one attachment');
+    }
+    return { role: 'user', content: parts };
+}
+
+function isContentPart(value: Source | ContentPart): value is ContentPart {
+    return typeof value === 'object' && !(value instanceof Uint8Array);
+}
+
+async function read(source: Source): Promise<{ data: Buffer; name?: string }> {
+    if (typeof source !== 'string') {
+        return { data: Buffer.from(source) };
+    }
+    try {
+        return { data: await fs.readFile(source), name: source };
+    } catch (error) {
+        throw new ValidationError(`Cannot read ${source}: ${(error as Error).message}`);
+    }
+}
+
+function checkSize(data: Buffer, maxBytes: number, name: string): void {
+    if (data.length > maxBytes) {
+        throw new ValidationError(`${name} is ${data.length} bytes, more than the ${maxBytes} byte limit`);
+    }
+}
+
+function extension(name?: string): string | undefined {
+    return name ? path.extname(name).slice(1).toLowerCase() || undefined : undefined;
+}
+
+function imageTypeFromExtension(name?: string): string | undefined {
+    const types: Record<string, string> = {
+        png: 'image/png',
+        jpg: 'image/jpeg',
+        jpeg: 'image/jpeg',
+        gif: 'image/gif',
+        webp: 'image/webp',
+    };
+    const ext = extension(name);
+    return ext ? types[ext] : undefined;
+}
+
+function startsWith(data: Buffer, bytes: number[], offset = 0): boolean {
+    return bytes.every((byte, i) => data[offset + i] === byte);
+}
+
+function ascii(data: Buffer, start: number, end: number): string {
+    return data.subarray(start, end).toString('latin1');
+}
+
+function sniffImageType(data: Buffer): string | undefined {
+    if (startsWith(data, [0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a])) {
+        return 'image/png';
+    }
+    if (startsWith(data, [0xff, 0xd8, 0xff])) {
+        return 'image/jpeg';
+    }
+    if (ascii(data, 0, 6) === 'GIF87a' || ascii(data, 0, 6) === 'GIF89a') {
+        return 'image/gif';
+    }
+    if (ascii(data, 0, 4) === 'RIFF' && ascii(data, 8, 12) === 'WEBP') {
+        return 'image/webp';
+    }
+    return undefined;
+}
+
+function sniffAudioFormat(data: Buffer): 'wav' | 'mp3' | undefined {
+    if (ascii(data, 0, 4) === 'RIFF' && ascii(data, 8, 12) === 'WAVE') {
+        return 'wav';
+    }
+    if (ascii(data, 0, 3) === 'ID3' || (data[0] === 0xff && (data[1] & 0xe0) === 0xe0)) {
+        return 'mp3';
+    }
+    return undefined;
+}
@@ -3,6 +3,59 @@
  */
 export type ChatRole = 'system' | 'user' | 'assistant' | 'function' | 'tool';
 
+/**
+ * Text content part
+ */
+export interface TextContentPart {
+    type: 'text';
+    text: string;
+}
+
+/**
+ * Image content part, referencing a web URL or a base64 data URL
+ */
+export interface ImageContentPart {
+    type: 'image_url';
+    image_url: {
+        url: string;
+        detail?: 'auto' | 'low' | 'high';
+    };
+}
+
+/**
+ * Audio content part
+ */
+export interface AudioContentPart {
+    type: 'input_audio';
+    input_audio: {
+        /**
+         * Base64-encoded audio
+         */
+        data: string;
+        format: 'wav' | 'mp3';
+    };
+}
+
+/**
+ * File content part, inlined or referencing an uploaded file
+ */
+export interface FileContentPart {
+    type: 'file';
+    file: {
+        filename?: string;
+        /**
+         * Base64-encoded file content
+         */
+        file_data?: string;
+        file_id?: string;
+    };
+}
+
+/**
+ * Part of a multimodal message
+ */
+export type ContentPart = TextContentPart | ImageContentPart | AudioContentPart | FileContentPart;
+
 /**
  * A single message in a chat conversation
  */
@@ -13,9 +66,9 @@ export interface ChatMessage {
     role: ChatRole;
 
     /**
-     * Content of the message
+     * Content of the message: text, or content parts for multimodal messages
      */
-    content: string | null;
+    content: string | ContentPart[] | null;
 
     /**
      * Optional name of the sender
@@ -3,6 +3,7 @@ import { HttpTransport } from './transport';
 import { ConfigManager } from './config';
 import { ChatClient } from './chat';
 import { ChainClient } from './chains';
+import { AgentClient } from './agents';
 
 /**
  * Main client for interacting with IntelliRouter
@@ -12,6 +13,7 @@ export class IntelliRouter {
     private readonly _config: ConfigManager;
     private readonly _chat: ChatClient;
     private readonly _chains: ChainClient;
+    private readonly _agents: AgentClient;
 
     /**
      * Create a new IntelliRouter client
@@ -30,6 +32,7 @@ export class IntelliRouter {
         // Initialize clients
         this._chat = new ChatClient(this.transport);
         this._chains = new ChainClient(this.transport);
+        this._agents = new AgentClient(this.transport);
     }
 
     /**
@@ -46,6 +49,13 @@ export class IntelliRouter {
         return this._chains;
     }
 
+    /**
+     * Agent API
+     */
+    public get agents(): AgentClient {
+        return this._agents;
+    }
+
     /**
      * Configuration management
      */
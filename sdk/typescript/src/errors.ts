@@ -79,6 +79,16 @@ export class TimeoutError extends IntelliRouterError {
     }
 }
 
+/**
+ * Error thrown when a request is cancelled by its abort signal
+ */
+export class CancelledError extends IntelliRouterError {
+    constructor(message: string, options: { details?: unknown } = {}) {
+        super(message, { code: 'cancelled', details: options.details });
+        Object.setPrototypeOf(this, CancelledError.prototype);
+    }
+}
+
 /**
  * Error thrown when validation fails
  */
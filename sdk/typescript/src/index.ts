@@ -3,6 +3,7 @@ export * from './types';
 export * from './errors';
 export * from './chat';
 export * from './chains';
+export * from './agents';
 export * from './config';
 export * from './transport';
 
@@ -4,6 +4,7 @@ import {
     ApiError,
     AuthenticationError,
     AuthorizationError,
+    CancelledError,
     NotFoundError,
     RateLimitError,
     ServerError,
@@ -200,6 +201,36 @@ describe('HttpTransport', () => {
             ).rejects.toThrow(TimeoutError);
         });
 
+        it('should pass the timeout and signal of the call', async () => {
+            mockAxiosInstance.request.mockResolvedValue({ data: {} });
+            const controller = new AbortController();
+
+            await transport.request({
+                method: 'POST',
+                path: '/test',
+                timeout: 5000,
+                signal: controller.signal,
+            });
+
+            expect(mockAxiosInstance.request).toHaveBeenCalledWith(
+                expect.objectContaining({ timeout: 5000, signal: controller.signal })
+            );
+        });
+
+        it('should handle cancelled requests', async () => {
+            mockAxiosInstance.request.mockRejectedValue({
+                code: 'ERR_CANCELED',
+                message: 'canceled',
+            });
+
+            await expect(
+                transport.request({
+                    method: 'GET',
+                    path: '/test',
+                })
+            ).rejects.toThrow(CancelledError);
+        });
+
         it('should handle network error', async () => {
             mockAxiosInstance.request.mockRejectedValue({
                 message: 'Network error',
@@ -214,6 +245,95 @@ describe('HttpTransport', () => {
         });
     });
 
+    describe('requestStream', () => {
+        let transport: HttpTransport;
+        let mockAxiosInstance: any;
+
+        async function* body(chunks: string[], error?: Error) {
+            for (const chunk of chunks) {
+                yield Buffer.from(chunk);
+            }
+            if (error) {
+                throw error;
+            }
+        }
+
+        async function collect(stream: AsyncIterable<unknown>): Promise<unknown[]> {
+            const events = [];
+            for await (const event of stream) {
+                events.push(event);
+            }
+            return events;
+        }
+
+        beforeEach(() => {
+            mockAxiosInstance = {
+                request: jest.fn(),
+                interceptors: {
+                    response: {
+                        use: jest.fn(),
+                    },
+                },
+            };
+            mockedAxios.create.mockReturnValue(mockAxiosInstance);
+            transport = new HttpTransport({ maxRetries: 1 });
+        });
+
+        it('should parse events until [DONE]', async () => {
+            mockAxiosInstance.request.mockResolvedValue({
+                data: body(['data: {"n":1}\n\n', 'data: {"n":2}\n\ndata: [DONE]\n\n']),
+            });
+
+            const stream = await transport.requestStream({ method: 'POST', path: '/stream' });
+            expect(await collect(stream)).toEqual([{ n: 1 }, { n: 2 }]);
+        });
+
+        it('should resume dropped streams with the last event ID', async () => {
+            mockAxiosInstance.request
+                .mockResolvedValueOnce({
+                    data: body(['id: s1:0\ndata: {"n":1}\n\n'], new Error('socket hang up')),
+                })
+                .mockResolvedValueOnce({
+                    data: body(['id: s1:1\ndata: {"n":2}\n\n']),
+                });
+
+            const stream = await transport.requestStream({ method: 'POST', path: '/stream', body: { a: 1 } });
+            expect(await collect(stream)).toEqual([{ n: 1 }, { n: 2 }]);
+            expect(mockAxiosInstance.request).toHaveBeenCalledTimes(2);
+            expect(mockAxiosInstance.request.mock.calls[1][0].headers).toEqual({
+                'Accept': 'text/event-stream',
+                'Last-Event-ID': 's1:0',
+            });
+            expect(mockAxiosInstance.request.mock.calls[1][0].data).toEqual({ a: 1 });
+        });
+
+        it('should not resume streams without event IDs', async () => {
+            mockAxiosInstance.request.mockResolvedValue({
+                data: body(['data: {"n":1}\n\n'], new Error('socket hang up')),
+            });
+
+            const stream = await transport.requestStream({ method: 'POST', path: '/stream' });
+            await expect(collect(stream)).rejects.toThrow(ApiError);
+            expect(mockAxiosInstance.request).toHaveBeenCalledTimes(1);
+        });
+
+        it('should not resume cancelled streams', async () => {
+            const controller = new AbortController();
+            mockAxiosInstance.request.mockResolvedValue({
+                data: body(['id: s1:0\ndata: {"n":1}\n\n'], new Error('aborted')),
+            });
+
+            const stream = await transport.requestStream({
+                method: 'POST',
+                path: '/stream',
+                signal: controller.signal,
+            });
+            controller.abort();
+            await expect(collect(stream)).rejects.toThrow(CancelledError);
+            expect(mockAxiosInstance.request).toHaveBeenCalledTimes(1);
+        });
+    });
+
     describe('helper methods', () => {
         let transport: HttpTransport;
 
@@ -272,5 +392,23 @@ describe('HttpTransport', () => {
                 stream: true,
             });
         });
+
+        it('should apply the options of the call', async () => {
+            const controller = new AbortController();
+            await transport.post('/test', { foo: 'bar' }, { 'X-Test': 'test' }, {
+                timeout: 5000,
+                headers: { 'X-Extra': 'extra' },
+                priority: 7,
+                signal: controller.signal,
+            });
+            expect((transport as any).request).toHaveBeenCalledWith({
+                method: 'POST',
+                path: '/test',
+                body: { foo: 'bar' },
+                headers: { 'X-Test': 'test', 'X-Extra': 'extra', 'X-IntelliRouter-Priority': '7' },
+                timeout: 5000,
+                signal: controller.signal,
+            });
+        });
     });
 });
\ No newline at end of file
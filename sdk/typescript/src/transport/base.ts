@@ -1,4 +1,4 @@
-import { RequestOptions } from '../types';
+import { CallOptions, RequestOptions } from '../types';
 
 /**
  * Base interface for transport implementations
@@ -32,9 +32,10 @@ export interface Transport {
      * @param path URL path
      * @param body Request body
      * @param headers Request headers
+     * @param options Options of the call
      * @returns Promise resolving to the response data
      */
-    post<T>(path: string, body?: unknown, headers?: Record<string, string>): Promise<T>;
+    post<T>(path: string, body?: unknown, headers?: Record<string, string>, options?: CallOptions): Promise<T>;
 
     /**
      * Send a PUT request
@@ -58,7 +59,8 @@ export interface Transport {
      * @param path URL path
      * @param body Request body
      * @param headers Request headers
+     * @param options Options of the call
      * @returns Promise resolving to an async iterable of response chunks
      */
-    postStream(path: string, body?: unknown, headers?: Record<string, string>): Promise<AsyncIterable<unknown>>;
+    postStream(path: string, body?: unknown, headers?: Record<string, string>, options?: CallOptions): Promise<AsyncIterable<unknown>>;
 }
\ No newline at end of file
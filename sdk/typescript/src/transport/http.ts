@@ -1,11 +1,13 @@
 import axios, { AxiosInstance, AxiosRequestConfig, AxiosResponse } from 'axios';
-import { createParser } from 'eventsource-parser';
-import { IntelliRouterConfig, RequestOptions, HttpMethod } from '../types';
+import { createParser, ParsedEvent } from 'eventsource-parser';
+import { CallOptions, IntelliRouterConfig, RequestOptions, HttpMethod } from '../types';
 import { Transport } from './base';
 import {
     ApiError,
     AuthenticationError,
+    IntelliRouterError,
     AuthorizationError,
+    CancelledError,
     NotFoundError,
     RateLimitError,
     ServerError,
@@ -13,6 +15,11 @@ import {
     ValidationError,
 } from '../errors';
 
+/**
+ * Header carrying the priority of a request
+ */
+export const PRIORITY_HEADER = 'X-IntelliRouter-Priority';
+
 /**
  * HTTP transport implementation
  */
@@ -70,6 +77,11 @@ export class HttpTransport implements Transport {
      * @returns Whether the request should be retried
      */
     private shouldRetry(error: any): boolean {
+        // Don't retry cancelled requests
+        if (error.config?.signal?.aborted) {
+            return false;
+        }
+
         // Don't retry if we don't have a response
         if (!error.response) {
             return false;
@@ -110,6 +122,10 @@ export class HttpTransport implements Transport {
                     }
                     throw new ApiError(message, { code: 'api_error', status, details: data });
             }
+        } else if (error instanceof IntelliRouterError) {
+            throw error;
+        } else if (error.code === 'ERR_CANCELED' || axios.isCancel(error)) {
+            throw new CancelledError('Request cancelled');
         } else if (error.code === 'ECONNABORTED') {
             throw new TimeoutError('Request timed out');
         } else {
@@ -130,6 +146,8 @@ export class HttpTransport implements Transport {
                 params: options.params,
                 data: options.body,
                 headers: options.headers,
+                ...(options.timeout !== undefined ? { timeout: options.timeout } : {}),
+                ...(options.signal ? { signal: options.signal } : {}),
             };
 
             const response = await this.client.request<T>(config);
@@ -141,10 +159,26 @@ export class HttpTransport implements Transport {
 
     /**
      * Send a request with streaming response
+     *
+     * If the connection drops mid-stream, the request is repeated with the
+     * `Last-Event-ID` of the last event received (up to `maxRetries` times),
+     * so the server can resume the stream instead of restarting the
+     * generation.
      * @param options Request options
      * @returns Promise resolving to an async iterable of response chunks
      */
     public async requestStream(options: RequestOptions): Promise<AsyncIterable<unknown>> {
+        const response = await this.openStream(options);
+        return this.resumableStream(options, response);
+    }
+
+    /**
+     * Open an event stream
+     * @param options Request options
+     * @param lastEventId ID of the last event received, when resuming
+     * @returns Axios response with a streamed body
+     */
+    private async openStream(options: RequestOptions, lastEventId?: string): Promise<AxiosResponse> {
         try {
             const config: AxiosRequestConfig = {
                 method: options.method,
@@ -154,50 +188,89 @@ export class HttpTransport implements Transport {
                 headers: {
                     ...options.headers,
                     'Accept': 'text/event-stream',
+                    ...(lastEventId ? { 'Last-Event-ID': lastEventId } : {}),
                 },
                 responseType: 'stream',
+                ...(options.timeout !== undefined ? { timeout: options.timeout } : {}),
+                ...(options.signal ? { signal: options.signal } : {}),
             };
 
-            const response = await this.client.request(config);
-
-            return this.parseEventStream(response);
+            return await this.client.request(config);
         } catch (error) {
             this.handleError(error);
         }
     }
 
     /**
-     * Parse an event stream response
-     * @param response Axios response
+     * Yield the events of a stream, reconnecting after dropped connections
+     * @param options Request options
+     * @param response Response of the initial request
      * @returns Async iterable of parsed events
      */
-    private async *parseEventStream(response: AxiosResponse): AsyncIterable<unknown> {
-        const parser = createParser((event) => {
-            if (event.type === 'event' && event.data) {
-                if (event.data === '[DONE]') {
-                    return;
+    private async *resumableStream(options: RequestOptions, response: AxiosResponse): AsyncIterable<unknown> {
+        const maxRetries = this.config.maxRetries ?? 3;
+        let lastEventId: string | undefined;
+        let retries = 0;
+
+        for (;;) {
+            try {
+                for await (const event of this.parseEventStream(response)) {
+                    if (event.id) {
+                        lastEventId = event.id;
+                        retries = 0;
+                    }
+                    if (event.data === '[DONE]') {
+                        return;
+                    }
+                    try {
+                        yield JSON.parse(event.data);
+                    } catch (e) {
+                        yield event.data;
+                    }
+                }
+                // Aborting can also end a stream quietly
+                if (options.signal?.aborted) {
+                    throw new CancelledError('Request cancelled');
+                }
+                return;
+            } catch (error) {
+                if (options.signal?.aborted) {
+                    throw new CancelledError('Request cancelled');
                 }
 
-                try {
-                    const parsedData = JSON.parse(event.data);
-                    return parsedData;
-                } catch (e) {
-                    return event.data;
+                // Streams without event IDs cannot be resumed
+                if (!lastEventId || retries >= maxRetries) {
+                    throw error instanceof IntelliRouterError
+                        ? error
+                        : new ApiError((error as Error).message || 'Stream interrupted', { code: 'stream_interrupted', status: 0 });
                 }
-            }
-        });
+                retries += 1;
 
-        const stream = response.data;
+                // Exponential backoff
+                const delay = Math.pow(2, retries - 1) * 1000;
+                await new Promise((resolve) => setTimeout(resolve, delay));
 
-        for await (const chunk of stream) {
-            const str = chunk.toString();
-            const parsedEvents = parser.feed(str);
+                response = await this.openStream(options, lastEventId);
+            }
+        }
+    }
 
-            for (const event of parsedEvents) {
-                if (event) {
-                    yield event;
-                }
+    /**
+     * Parse an event stream response
+     * @param response Axios response
+     * @returns Async iterable of events with data
+     */
+    private async *parseEventStream(response: AxiosResponse): AsyncIterable<ParsedEvent> {
+        const events: ParsedEvent[] = [];
+        const parser = createParser((event) => {
+            if (event.type === 'event' && event.data) {
+                events.push(event);
             }
+        });
+
+        for await (const chunk of response.data) {
+            parser.feed(chunk.toString());
+            yield* events.splice(0);
         }
     }
 
@@ -222,14 +295,15 @@ export class HttpTransport implements Transport {
      * @param path URL path
      * @param body Request body
      * @param headers Request headers
+     * @param options Options of the call
      * @returns Promise resolving to the response data
      */
-    public async post<T>(path: string, body?: unknown, headers?: Record<string, string>): Promise<T> {
+    public async post<T>(path: string, body?: unknown, headers?: Record<string, string>, options?: CallOptions): Promise<T> {
         return this.request<T>({
             method: 'POST',
             path,
             body,
-            headers,
+            ...this.callOptions(headers, options),
         });
     }
 
@@ -268,15 +342,45 @@ export class HttpTransport implements Transport {
      * @param path URL path
      * @param body Request body
      * @param headers Request headers
+     * @param options Options of the call
      * @returns Promise resolving to an async iterable of response chunks
      */
-    public async postStream(path: string, body?: unknown, headers?: Record<string, string>): Promise<AsyncIterable<unknown>> {
+    public async postStream(
+        path: string,
+        body?: unknown,
+        headers?: Record<string, string>,
+        options?: CallOptions
+    ): Promise<AsyncIterable<unknown>> {
         return this.requestStream({
             method: 'POST',
             path,
             body,
-            headers,
+            ...this.callOptions(headers, options),
             stream: true,
         });
     }
+
+    /**
+     * Apply the options of a call to its request
+     * @param headers Request headers
+     * @param options Options of the call
+     * @returns Headers, timeout and signal of the request
+     */
+    private callOptions(
+        headers?: Record<string, string>,
+        options?: CallOptions
+    ): Pick<RequestOptions, 'headers' | 'timeout' | 'signal'> {
+        if (!options) {
+            return { headers };
+        }
+        return {
+            headers: {
+                ...headers,
+                ...options.headers,
+                ...(options.priority !== undefined ? { [PRIORITY_HEADER]: String(options.priority) } : {}),
+            },
+            timeout: options.timeout,
+            signal: options.signal,
+        };
+    }
 }
\ No newline at end of file
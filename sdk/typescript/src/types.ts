@@ -97,4 +97,46 @@ export interface RequestOptions {
      * Whether to stream the response
      */
     stream?: boolean;
+
+    /**
+     * Request timeout in milliseconds, instead of the configured one
+     */
+    timeout?: number;
+
+    /**
+     * Signal aborting the request
+     */
+    signal?: AbortSignal;
+}
+
+/**
+ * Options of a single API call, overriding the client configuration
+ */
+export interface CallOptions {
+    /**
+     * Request timeout in milliseconds
+     */
+    timeout?: number;
+
+    /**
+     * Headers added to the request
+     */
+    headers?: Record<string, string>;
+
+    /**
+     * Model used instead of the one in the request
+     */
+    model?: string;
+
+    /**
+     * Priority of the request (0-255), evaluated by routing policies as
+     * `request.priority`
+     */
+    priority?: number;
+
+    /**
+     * Signal cancelling the request, such as `AbortController.signal`.
+     * Cancelled calls reject with a `CancelledError`.
+     */
+    signal?: AbortSignal;
 }
\ No newline at end of file
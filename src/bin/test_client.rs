@@ -25,6 +25,12 @@ async fn main() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Use the legacy method for simplicity
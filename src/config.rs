@@ -18,6 +18,15 @@ use serde::{Deserialize, Serialize};
 use toml;
 use tracing::Level as LogLevel;
 
+use crate::modules::chain_engine::checkpoint::ExecutionStatus;
+use crate::modules::chain_engine::ChainBudget;
+use crate::modules::common::traffic::IpRange;
+use crate::modules::llm_proxy::admin::AdminRole;
+use crate::modules::llm_proxy::versioning::ApiVersion;
+use crate::modules::model_registry::connectors::tls;
+use crate::modules::router_core::language::LanguageConfig;
+use crate::modules::router_core::policy::RoutingPolicy;
+
 /// Environment type for configuration profiles
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum AppEnvironment {
@@ -60,6 +69,261 @@ pub struct ServerConfig {
     pub cors_enabled: bool,
     /// CORS allowed origins
     pub cors_allowed_origins: Vec<String>,
+    /// Sockets of the roles' servers, when not listening on TCP
+    #[serde(default)]
+    pub listeners: RoleListeners,
+    /// gRPC health checking protocol served next to the HTTP health endpoints
+    #[serde(default)]
+    pub grpc_health: GrpcHealthConfig,
+    /// Compression of the roles' HTTP traffic
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    /// Request body size limits and timeouts of the roles' servers
+    #[serde(default)]
+    pub limits: RequestLimitsConfig,
+    /// Recognition of health checks and monitoring scrapes
+    #[serde(default)]
+    pub synthetic_traffic: SyntheticTrafficConfig,
+}
+
+/// gRPC health checking protocol (`grpc.health.v1.Health`) of the roles
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct GrpcHealthConfig {
+    /// Whether each role serves the protocol
+    #[serde(default)]
+    pub enabled: bool,
+    /// Offset of the gRPC port from the role's HTTP port
+    #[serde(default = "default_grpc_health_port_offset")]
+    pub port_offset: u16,
+    /// Seconds between checks of the status streamed to watchers
+    #[serde(default = "default_grpc_health_watch_interval_secs")]
+    pub watch_interval_secs: u64,
+}
+
+fn default_grpc_health_port_offset() -> u16 {
+    1000
+}
+
+fn default_grpc_health_watch_interval_secs() -> u64 {
+    5
+}
+
+impl Default for GrpcHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port_offset: default_grpc_health_port_offset(),
+            watch_interval_secs: default_grpc_health_watch_interval_secs(),
+        }
+    }
+}
+
+/// Content coding of compressed HTTP bodies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionAlgorithm {
+    /// `gzip`
+    Gzip,
+    /// `zstd`
+    Zstd,
+}
+
+/// Compression of the roles' HTTP request and response bodies
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompressionConfig {
+    /// Whether the roles compress responses for clients accepting it and
+    /// decompress compressed requests
+    #[serde(default = "default_compression_enabled")]
+    pub enabled: bool,
+    /// Codings offered, in order of preference when a client accepts several
+    #[serde(default = "default_compression_algorithms")]
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Bodies smaller than this are sent uncompressed
+    #[serde(default = "default_compression_min_size_bytes")]
+    pub min_size_bytes: usize,
+    /// Largest size a compressed request may decompress to
+    #[serde(default = "default_compression_max_decompressed_bytes")]
+    pub max_decompressed_bytes: usize,
+    /// Coding of the request bodies roles send to other roles; uncompressed
+    /// when unset
+    #[serde(default)]
+    pub outbound: Option<CompressionAlgorithm>,
+}
+
+fn default_compression_enabled() -> bool {
+    true
+}
+
+fn default_compression_algorithms() -> Vec<CompressionAlgorithm> {
+    vec![CompressionAlgorithm::Zstd, CompressionAlgorithm::Gzip]
+}
+
+fn default_compression_min_size_bytes() -> usize {
+    1024
+}
+
+fn default_compression_max_decompressed_bytes() -> usize {
+    32 * 1024 * 1024
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_compression_enabled(),
+            algorithms: default_compression_algorithms(),
+            min_size_bytes: default_compression_min_size_bytes(),
+            max_decompressed_bytes: default_compression_max_decompressed_bytes(),
+            outbound: None,
+        }
+    }
+}
+
+/// Request body size limits and timeouts of the roles' servers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RequestLimitsConfig {
+    /// Largest request body accepted, as sent (before decompression)
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Time allowed to receive a request's body, in seconds
+    #[serde(default = "default_body_read_timeout_secs")]
+    pub body_read_timeout_secs: u64,
+    /// Time allowed to receive a request's headers, in seconds, which
+    /// closes connections of clients sending them slowly
+    #[serde(default = "default_header_read_timeout_secs")]
+    pub header_read_timeout_secs: u64,
+    /// Limits of the routes under given path prefixes, replacing the
+    /// defaults above; the longest matching prefix applies
+    #[serde(default)]
+    pub routes: Vec<RouteLimitsConfig>,
+}
+
+fn default_max_body_bytes() -> usize {
+    10 * 1024 * 1024
+}
+
+fn default_body_read_timeout_secs() -> u64 {
+    30
+}
+
+fn default_header_read_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for RequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: default_max_body_bytes(),
+            body_read_timeout_secs: default_body_read_timeout_secs(),
+            header_read_timeout_secs: default_header_read_timeout_secs(),
+            routes: Vec::new(),
+        }
+    }
+}
+
+/// Request limits of the routes under a path prefix
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouteLimitsConfig {
+    /// Path prefix, such as `/v1/audio`
+    pub path_prefix: String,
+    /// Largest request body accepted; the default when unset
+    #[serde(default)]
+    pub max_body_bytes: Option<usize>,
+    /// Time allowed to receive a request's body, in seconds; the default
+    /// when unset
+    #[serde(default)]
+    pub body_read_timeout_secs: Option<u64>,
+}
+
+/// Recognition of health checks and monitoring scrapes, which are counted
+/// apart from real traffic, exempt from token quotas and kept out of the
+/// request log and the audit log of denied admin requests
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SyntheticTrafficConfig {
+    /// Whether requests are classified; all traffic is real when disabled
+    pub enabled: bool,
+    /// Case-insensitive substrings of the user agents of health checkers
+    /// and scrapers
+    pub user_agents: Vec<String>,
+    /// IP addresses or CIDR ranges health checks and scrapes come from
+    pub ip_ranges: Vec<String>,
+    /// Path prefixes only health checks and scrapes request
+    pub paths: Vec<String>,
+}
+
+impl Default for SyntheticTrafficConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            user_agents: vec![
+                "kube-probe".to_string(),
+                "Prometheus".to_string(),
+                "ELB-HealthChecker".to_string(),
+                "GoogleHC".to_string(),
+                "Consul Health Check".to_string(),
+            ],
+            ip_ranges: Vec::new(),
+            paths: vec![
+                "/health".to_string(),
+                "/readiness".to_string(),
+                "/diagnostics".to_string(),
+                "/metrics".to_string(),
+            ],
+        }
+    }
+}
+
+/// Sockets each role's server listens on, in place of its TCP port
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RoleListeners {
+    /// Router role
+    #[serde(default)]
+    pub router: Option<ListenerConfig>,
+    /// Orchestrator (chain engine) role
+    #[serde(default)]
+    pub orchestrator: Option<ListenerConfig>,
+    /// RAG injector role
+    #[serde(default)]
+    pub rag_injector: Option<ListenerConfig>,
+    /// Summarizer (persona layer) role
+    #[serde(default)]
+    pub summarizer: Option<ListenerConfig>,
+    /// Audit controller role
+    #[serde(default)]
+    pub audit: Option<ListenerConfig>,
+}
+
+impl RoleListeners {
+    /// Listeners configured, by role name
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &ListenerConfig)> {
+        [
+            ("router", &self.router),
+            ("orchestrator", &self.orchestrator),
+            ("rag_injector", &self.rag_injector),
+            ("summarizer", &self.summarizer),
+            ("audit", &self.audit),
+        ]
+        .into_iter()
+        .filter_map(|(role, listener)| listener.as_ref().map(|listener| (role, listener)))
+    }
+}
+
+/// Socket a role's server listens on
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ListenerConfig {
+    /// Path of a Unix domain socket to bind, replacing a stale one
+    #[serde(default)]
+    pub unix_socket: Option<String>,
+    /// Permissions of the Unix domain socket, such as `0o660`
+    #[serde(default)]
+    pub socket_mode: Option<u32>,
+    /// Take the socket passed by systemd socket activation instead of binding
+    #[serde(default)]
+    pub socket_activation: bool,
+    /// `FileDescriptorName` of the activated socket, when systemd passes
+    /// several; the first one otherwise
+    #[serde(default)]
+    pub socket_name: Option<String>,
 }
 
 impl Default for ServerConfig {
@@ -71,6 +335,11 @@ impl Default for ServerConfig {
             request_timeout_secs: 30,
             cors_enabled: false,
             cors_allowed_origins: vec!["*".to_string()],
+            listeners: RoleListeners::default(),
+            grpc_health: GrpcHealthConfig::default(),
+            compression: CompressionConfig::default(),
+            limits: RequestLimitsConfig::default(),
+            synthetic_traffic: SyntheticTrafficConfig::default(),
         }
     }
 }
@@ -101,6 +370,79 @@ pub struct LlmProviderConfig {
     pub max_retries: u32,
     /// Additional provider-specific settings
     pub settings: HashMap<String, String>,
+    /// Credentials switched to, in order, when the primary API key fails
+    #[serde(default)]
+    pub standby_credentials: Vec<ProviderCredentialConfig>,
+    /// Further API keys traffic is spread across, with the primary one
+    #[serde(default)]
+    pub pooled_credentials: Vec<ProviderCredentialConfig>,
+}
+
+/// Standby or pooled credential of a provider
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ProviderCredentialConfig {
+    /// Name of the credential, used in metrics and alerts
+    pub name: String,
+    /// API key environment variable name
+    pub api_key_env: String,
+    /// Organization the key belongs to, if the provider has organizations
+    #[serde(default)]
+    pub org_id: Option<String>,
+}
+
+/// Failover of providers to standby credentials
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CredentialFailoverConfig {
+    /// Time a credential that failed is skipped before it is tried again,
+    /// in seconds
+    pub cooldown_secs: u64,
+    /// Texts, matched case-insensitively, of rate limit errors that fail
+    /// over; these name limits of the organization or account rather than
+    /// of a single request. Every rate limit fails over when empty.
+    pub rate_limit_markers: Vec<String>,
+    /// Endpoints notified when a provider switches credentials
+    pub webhooks: Vec<AlertWebhookConfig>,
+    /// Delivery attempts of an alert after the first
+    pub webhook_retries: u32,
+}
+
+impl Default for CredentialFailoverConfig {
+    fn default() -> Self {
+        Self {
+            cooldown_secs: 300,
+            rate_limit_markers: vec!["organization".to_string(), "quota".to_string()],
+            webhooks: Vec::new(),
+            webhook_retries: 3,
+        }
+    }
+}
+
+/// Scheduling of provider requests across pooled API keys
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyPoolConfig {
+    /// Fraction of a key's request or token limit left under which the key
+    /// cools down until the limit resets
+    pub cooldown_threshold: f64,
+    /// Cooldown of a key nearing exhaustion whose provider doesn't report
+    /// when its limit resets, in seconds
+    pub cooldown_secs: u64,
+    /// Longest cooldown of a key nearing exhaustion, in seconds
+    pub max_cooldown_secs: u64,
+    /// Cooldown of a key the provider rejected, in seconds
+    pub auth_cooldown_secs: u64,
+}
+
+impl Default for KeyPoolConfig {
+    fn default() -> Self {
+        Self {
+            cooldown_threshold: 0.05,
+            cooldown_secs: 60,
+            max_cooldown_secs: 300,
+            auth_cooldown_secs: 3600,
+        }
+    }
 }
 
 /// Model registry configuration
@@ -112,6 +454,100 @@ pub struct ModelRegistryConfig {
     pub providers: Vec<LlmProviderConfig>,
     /// Cache TTL in seconds
     pub cache_ttl_secs: u64,
+    /// Tokenizers counting the tokens of model families, besides the
+    /// built-in OpenAI encodings
+    #[serde(default)]
+    pub tokenizers: Vec<TokenizerConfig>,
+    /// Model names resolved to other model names, such as `gpt-4` to
+    /// `gpt-4o-2024-08-06`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// Routes sending models that match a pattern to a provider
+    #[serde(default)]
+    pub routes: Vec<ModelRouteConfig>,
+    /// Failover of providers to their standby credentials
+    #[serde(default)]
+    pub credential_failover: CredentialFailoverConfig,
+    /// Scheduling of requests across the pooled credentials of providers
+    #[serde(default)]
+    pub key_pool: KeyPoolConfig,
+    /// Discovery of the models providers list, proposed for registration
+    #[serde(default)]
+    pub discovery: ModelDiscoveryConfig,
+}
+
+/// Discovery of new models from the list-models APIs of providers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModelDiscoveryConfig {
+    /// Whether the models of providers are synced
+    pub enabled: bool,
+    /// Seconds between syncs
+    pub interval_secs: u64,
+    /// Providers synced; every OpenAI, Anthropic and Ollama provider when
+    /// empty
+    pub providers: Vec<String>,
+    /// Models never proposed: names, or prefixes ending with `*`
+    pub ignore: Vec<String>,
+    /// Timeout of the requests to providers in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for ModelDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 3600,
+            providers: Vec::new(),
+            ignore: Vec::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Route sending the models that match a pattern to a provider
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelRouteConfig {
+    /// Model name, or prefix ending with `*`; the prefix is stripped from the
+    /// model name when it ends with `/*`, as in `anthropic/*`
+    pub pattern: String,
+    /// Provider serving the matching models
+    pub provider: String,
+}
+
+/// Format of a tokenizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenizerKind {
+    /// tiktoken BPE ranks, from a `.tiktoken` file or a built-in encoding
+    Tiktoken,
+    /// HuggingFace `tokenizer.json`
+    HuggingFace,
+    /// SentencePiece `.model`
+    SentencePiece,
+}
+
+/// Tokenizer assigned to a family of models
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TokenizerConfig {
+    /// Tokenizer name
+    pub name: String,
+    /// Format of the tokenizer
+    pub kind: TokenizerKind,
+    /// Tokenizer file
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Built-in tiktoken encoding (`cl100k_base` or `o200k_base`), used
+    /// instead of a file
+    #[serde(default)]
+    pub encoding: Option<String>,
+    /// Pre-tokenization pattern of a `.tiktoken` file; defaults to the one of
+    /// `cl100k_base`
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Models counted with this tokenizer, as exact IDs or prefixes ending
+    /// with `*`
+    pub models: Vec<String>,
 }
 
 impl Default for ModelRegistryConfig {
@@ -132,6 +568,8 @@ impl Default for ModelRegistryConfig {
                     timeout_secs: 60,
                     max_retries: 3,
                     settings: HashMap::new(),
+                    standby_credentials: Vec::new(),
+                    pooled_credentials: Vec::new(),
                 },
                 LlmProviderConfig {
                     name: "anthropic".to_string(),
@@ -146,232 +584,2576 @@ impl Default for ModelRegistryConfig {
                     timeout_secs: 60,
                     max_retries: 3,
                     settings: HashMap::new(),
+                    standby_credentials: Vec::new(),
+                    pooled_credentials: Vec::new(),
                 },
             ],
             cache_ttl_secs: 3600,
+            tokenizers: Vec::new(),
+            aliases: HashMap::new(),
+            routes: Vec::new(),
+            credential_failover: CredentialFailoverConfig::default(),
+            key_pool: KeyPoolConfig::default(),
+            discovery: ModelDiscoveryConfig::default(),
+        }
+    }
+}
+
+/// Router configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RouterConfig {
+    /// Default routing strategy
+    pub default_strategy: String,
+    /// Available routing strategies
+    pub available_strategies: Vec<String>,
+    /// Routing rules
+    pub rules: HashMap<String, String>,
+    /// Declarative routing policy evaluated for every request
+    #[serde(default)]
+    pub policy: Option<RoutingPolicy>,
+    /// Plugins hooked into routing, run in the listed order
+    #[serde(default)]
+    pub plugins: Vec<PluginConfig>,
+    /// Language detection and steering toward models supporting the language
+    #[serde(default)]
+    pub language: LanguageConfig,
+}
+
+/// A router plugin enabled through configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginConfig {
+    /// Name the plugin is registered under
+    pub name: String,
+    /// Whether the plugin runs
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// What happens to a request when the plugin fails
+    #[serde(default)]
+    pub on_error: PluginFailurePolicy,
+    /// Plugin-specific settings
+    #[serde(default)]
+    pub settings: serde_json::Value,
+}
+
+/// How a request is handled when a router plugin fails
+///
+/// Deliberate rejections by a plugin always reject the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginFailurePolicy {
+    /// Log the failure and route the request as if the plugin hadn't run
+    #[default]
+    Continue,
+    /// Reject the request
+    Reject,
+}
+
+impl Default for RouterConfig {
+    fn default() -> Self {
+        Self {
+            default_strategy: "cost-optimized".to_string(),
+            available_strategies: vec![
+                "cost-optimized".to_string(),
+                "performance-optimized".to_string(),
+                "round-robin".to_string(),
+                "fallback".to_string(),
+            ],
+            rules: HashMap::new(),
+            policy: None,
+            plugins: Vec::new(),
+            language: LanguageConfig::default(),
+        }
+    }
+}
+
+/// Memory backend configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MemoryConfig {
+    /// Memory backend type
+    pub backend_type: String,
+    /// Redis connection string (if using Redis backend)
+    pub redis_url: Option<String>,
+    /// File storage path (if using file backend)
+    pub file_path: Option<String>,
+    /// Maximum conversation history length
+    pub max_history_length: usize,
+    /// TTL for conversation history in seconds
+    pub history_ttl_secs: u64,
+    /// Key prefix of conversations stored in Redis
+    #[serde(default = "default_memory_key_prefix")]
+    pub key_prefix: String,
+    /// Interval between sweeps deleting expired namespaced conversations, in seconds
+    #[serde(default = "default_retention_sweep_interval_secs")]
+    pub retention_sweep_interval_secs: u64,
+    /// How long deleted namespaced conversations can be restored before
+    /// they are purged, in seconds; 0 deletes them at once
+    #[serde(default)]
+    pub soft_delete_secs: u64,
+    /// Long-term semantic memory of facts extracted from conversations
+    #[serde(default)]
+    pub semantic: SemanticMemoryConfig,
+    /// Topology, TLS and AUTH of the Redis shared by the Redis-backed modules
+    #[serde(default)]
+    pub redis: RedisTopologyConfig,
+}
+
+fn default_memory_key_prefix() -> String {
+    "intellirouter:memory".to_string()
+}
+
+fn default_retention_sweep_interval_secs() -> u64 {
+    300
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            backend_type: "memory".to_string(),
+            redis_url: None,
+            file_path: None,
+            max_history_length: 100,
+            history_ttl_secs: 86400, // 24 hours
+            key_prefix: default_memory_key_prefix(),
+            retention_sweep_interval_secs: default_retention_sweep_interval_secs(),
+            soft_delete_secs: 0,
+            semantic: SemanticMemoryConfig::default(),
+            redis: RedisTopologyConfig::default(),
+        }
+    }
+}
+
+/// Where state is kept
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageProfile {
+    /// Each module's own backend, such as `memory.backend_type`
+    #[default]
+    Standard,
+    /// A single SQLite file, for single-node deployments without Redis
+    Embedded,
+}
+
+/// Storage profile of the deployment
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Storage profile
+    pub profile: StorageProfile,
+    /// SQLite file of the embedded profile, also used by the `sqlite`
+    /// memory backend
+    pub sqlite_path: String,
+    /// How long to wait for a lock held by another connection, in
+    /// milliseconds
+    pub busy_timeout_ms: u64,
+    /// Interval between saves of the model registry, in seconds
+    pub registry_save_interval_secs: u64,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            profile: StorageProfile::Standard,
+            sqlite_path: "data/intellirouter.db".to_string(),
+            busy_timeout_ms: 5000,
+            registry_save_interval_secs: 30,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Whether state is kept in the SQLite file
+    pub fn is_embedded(&self) -> bool {
+        self.profile == StorageProfile::Embedded
+    }
+}
+
+/// Verification of the dependencies of a role before it serves traffic
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StartupConfig {
+    /// Whether dependencies are checked at startup
+    pub check_dependencies: bool,
+    /// Whether startup waits for unavailable dependencies instead of
+    /// failing, as with `--wait-for-deps`
+    pub wait_for_dependencies: bool,
+    /// Timeout of each check in seconds
+    pub check_timeout_secs: u64,
+    /// How long to wait for dependencies in seconds (0 waits forever)
+    pub wait_timeout_secs: u64,
+    /// Delay before the first retry in milliseconds, doubled on each retry
+    pub initial_backoff_ms: u64,
+    /// Longest delay between retries in seconds
+    pub max_backoff_secs: u64,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            check_dependencies: true,
+            wait_for_dependencies: false,
+            check_timeout_secs: 5,
+            wait_timeout_secs: 300,
+            initial_backoff_ms: 500,
+            max_backoff_secs: 30,
+        }
+    }
+}
+
+/// Topology of a Redis deployment
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisTopology {
+    /// A single server at `memory.redis_url`
+    #[default]
+    Single,
+    /// A master monitored by Redis Sentinel
+    Sentinel,
+    /// A Redis Cluster
+    Cluster,
+}
+
+impl RedisTopology {
+    /// Name used in configuration and metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Single => "single",
+            Self::Sentinel => "sentinel",
+            Self::Cluster => "cluster",
+        }
+    }
+}
+
+/// Topology, TLS and AUTH settings of the deployment's Redis
+///
+/// A single server is described by `memory.redis_url` alone; the credentials
+/// and TLS settings here, when set, override those of the URL.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RedisTopologyConfig {
+    pub topology: RedisTopology,
+    /// Sentinels as `host:port`
+    pub sentinels: Vec<String>,
+    /// Name of the master monitored by the sentinels
+    pub master_name: String,
+    /// Seed nodes of the cluster as `host:port`
+    pub nodes: Vec<String>,
+    /// Username of the servers, if they use ACLs
+    pub username: Option<String>,
+    /// Password of the servers
+    pub password: Option<String>,
+    /// Password of the sentinels, if they require AUTH
+    pub sentinel_password: Option<String>,
+    /// Database of a sentinel master; cluster nodes only have database 0
+    pub database: i64,
+    /// Connect to the servers and sentinels over TLS
+    pub tls: bool,
+    /// Accept any certificate from the servers and sentinels
+    pub tls_insecure: bool,
+    /// Interval between refreshes of the cluster slot map, in seconds
+    pub refresh_interval_secs: u64,
+    /// Redirects and failover retries followed per command
+    pub max_redirects: u32,
+}
+
+impl Default for RedisTopologyConfig {
+    fn default() -> Self {
+        Self {
+            topology: RedisTopology::Single,
+            sentinels: Vec::new(),
+            master_name: "mymaster".to_string(),
+            nodes: Vec::new(),
+            username: None,
+            password: None,
+            sentinel_password: None,
+            database: 0,
+            tls: false,
+            tls_insecure: false,
+            refresh_interval_secs: 60,
+            max_redirects: 5,
+        }
+    }
+}
+
+/// Vector store holding semantic memories
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryVectorStoreType {
+    /// In-process store, lost on restart
+    #[default]
+    Memory,
+    /// Qdrant collection
+    Qdrant,
+}
+
+/// Long-term semantic memory configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SemanticMemoryConfig {
+    /// Whether semantic memory is enabled
+    pub enabled: bool,
+    /// OpenAI-compatible endpoint serving embeddings and fact extraction (defaults to the router)
+    pub endpoint: Option<String>,
+    /// API key for the endpoint
+    pub api_key: Option<String>,
+    /// Model embedding facts and queries
+    pub embedding_model: String,
+    /// Model extracting facts from conversations
+    pub extraction_model: String,
+    /// Maximum facts kept from one extraction
+    pub max_facts_per_extraction: usize,
+    /// Similarity above which a new fact is treated as a duplicate of a stored one
+    pub dedupe_threshold: f32,
+    /// Vector store holding the facts
+    pub vector_store: MemoryVectorStoreType,
+    /// Qdrant URL (defaults to `rag.vector_db_url`)
+    pub qdrant_url: Option<String>,
+    /// Qdrant API key
+    pub qdrant_api_key: Option<String>,
+    /// Qdrant collection
+    pub collection: String,
+}
+
+impl Default for SemanticMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            api_key: None,
+            embedding_model: "text-embedding-3-small".to_string(),
+            extraction_model: "gpt-4o-mini".to_string(),
+            max_facts_per_extraction: 10,
+            dedupe_threshold: 0.95,
+            vector_store: MemoryVectorStoreType::Memory,
+            qdrant_url: None,
+            qdrant_api_key: None,
+            collection: "intellirouter_memories".to_string(),
+        }
+    }
+}
+
+/// Telemetry configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TelemetryConfig {
+    /// Log level
+    pub log_level: String,
+    /// Enable metrics collection
+    pub metrics_enabled: bool,
+    /// Enable distributed tracing
+    pub tracing_enabled: bool,
+    /// Metrics endpoint
+    pub metrics_endpoint: Option<String>,
+    /// Tracing endpoint
+    pub tracing_endpoint: Option<String>,
+    /// Export of per-request records to analytics stores
+    #[serde(default)]
+    pub export: TelemetryExportConfig,
+    /// Log output format and per-module levels
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// Detection of unusual traffic and cost per tenant
+    #[serde(default)]
+    pub anomaly: AnomalyDetectionConfig,
+    /// Metering of tenant usage into billing events
+    #[serde(default)]
+    pub metering: MeteringConfig,
+    /// Canary prompts sent through the router to check its models
+    #[serde(default)]
+    pub canaries: CanaryMonitorConfig,
+    /// Prices of models and the currency of costs
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    /// Sampling of the process's CPU, memory and file descriptors, and its
+    /// soft limits
+    #[serde(default)]
+    pub resources: ResourceMonitorConfig,
+}
+
+/// Sampling of a role's process resources
+///
+/// Every `interval_secs`, each role samples the CPU, resident memory and
+/// open file descriptors of its process. While a sample is over one of the
+/// soft limits, the role sheds batch requests.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourceMonitorConfig {
+    /// Whether the process's resources are sampled
+    pub enabled: bool,
+    /// Seconds between samples
+    pub interval_secs: u64,
+    /// Soft limits above which batch requests are shed
+    pub limits: ResourceLimitsConfig,
+}
+
+impl Default for ResourceMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 15,
+            limits: ResourceLimitsConfig::default(),
+        }
+    }
+}
+
+/// Soft limits of a role's process, in percent; unset limits are not
+/// enforced
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResourceLimitsConfig {
+    /// Resident memory, of the container's memory limit or else of the
+    /// host's memory
+    pub memory_percent: Option<f64>,
+    /// CPU time, of the CPUs available to the process
+    pub cpu_percent: Option<f64>,
+    /// Open file descriptors, of the process's limit
+    pub fd_percent: Option<f64>,
+}
+
+/// Prices of models, on top of the built-in prices
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PricingConfig {
+    /// Currency prices and costs are kept in
+    pub currency: String,
+    /// Value of a unit of other currencies in `currency`, for prices
+    /// entered in them
+    pub exchange_rates: HashMap<String, f64>,
+    /// Most recent calls whose costs are recomputed when a past price is
+    /// corrected
+    pub ledger_capacity: usize,
+    /// Prices of models
+    pub prices: Vec<ModelPriceConfig>,
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            currency: "USD".to_string(),
+            exchange_rates: HashMap::new(),
+            ledger_capacity: 100_000,
+            prices: Vec::new(),
+        }
+    }
+}
+
+/// Price of a model, effective from a point in time
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ModelPriceConfig {
+    /// Model name
+    pub model: String,
+    /// Price per 1K prompt tokens
+    pub input_per_1k: f64,
+    /// Price per 1K completion tokens
+    pub output_per_1k: f64,
+    /// Currency of the price, when it is not the pricing currency
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Time from which the price applies; always when unset
+    #[serde(default)]
+    pub effective_from: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            log_level: "info".to_string(),
+            metrics_enabled: true,
+            tracing_enabled: true,
+            metrics_endpoint: None,
+            tracing_endpoint: None,
+            export: TelemetryExportConfig::default(),
+            logging: LoggingConfig::default(),
+            anomaly: AnomalyDetectionConfig::default(),
+            metering: MeteringConfig::default(),
+            canaries: CanaryMonitorConfig::default(),
+            pricing: PricingConfig::default(),
+            resources: ResourceMonitorConfig::default(),
+        }
+    }
+}
+
+impl TelemetryConfig {
+    /// Convert string log level to LogLevel enum
+    pub fn log_level(&self) -> Result<LogLevel, String> {
+        match self.log_level.to_lowercase().as_str() {
+            "debug" => Ok(LogLevel::DEBUG),
+            "info" => Ok(LogLevel::INFO),
+            "warning" | "warn" => Ok(LogLevel::WARN),
+            "error" => Ok(LogLevel::ERROR),
+            _ => Err(format!("Invalid log level: {}", self.log_level)),
+        }
+    }
+}
+
+/// Format of log output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Multi-line human-readable output
+    #[default]
+    Pretty,
+    /// One human-readable line per event
+    Compact,
+    /// One JSON object per event, for log aggregation
+    Json,
+}
+
+/// Log output configuration
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Output format
+    pub format: LogFormat,
+    /// Levels of individual modules overriding `log_level`, keyed by module
+    /// path (e.g. `intellirouter::modules::router_core`)
+    pub modules: HashMap<String, String>,
+}
+
+/// Detection of spikes in the error rate, latency, token consumption and
+/// spend of each tenant
+///
+/// Every window, each signal is compared with an exponentially weighted
+/// moving average of its past values; a value more than `z_threshold`
+/// standard deviations above the average is an anomaly.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AnomalyDetectionConfig {
+    /// Whether anomalies are detected
+    pub enabled: bool,
+    /// Length of the windows signals are measured over, in seconds
+    pub window_secs: u64,
+    /// Weight of the latest window in the moving averages, between 0 and 1
+    pub alpha: f64,
+    /// Standard deviations above the average that make a value anomalous
+    pub z_threshold: f64,
+    /// Windows observed before a tenant's signals are checked
+    pub warmup_windows: u32,
+    /// Requests a window needs before its error rate and latency are checked
+    pub min_requests: u64,
+    /// Anomalies kept for the admin API
+    pub history_capacity: usize,
+    /// Endpoints notified when an anomaly starts and ends
+    pub webhooks: Vec<AlertWebhookConfig>,
+    /// Delivery attempts of an alert after the first
+    pub webhook_retries: u32,
+    /// Whether the signals of each end user, identified by the `user` field
+    /// of requests, are checked besides those of their tenant
+    pub per_user: bool,
+}
+
+impl Default for AnomalyDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 60,
+            alpha: 0.1,
+            z_threshold: 4.0,
+            warmup_windows: 30,
+            min_requests: 20,
+            history_capacity: 200,
+            webhooks: Vec::new(),
+            webhook_retries: 3,
+            per_user: false,
+        }
+    }
+}
+
+/// Synthetic monitoring of models with canary prompts
+///
+/// Every `interval_secs`, each canary's prompt is sent through the router's
+/// own chat completions endpoint and its answer checked against what is
+/// expected. A canary failing `failure_threshold` times in a row is failing
+/// until it passes again.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CanaryMonitorConfig {
+    /// Whether canaries are sent
+    pub enabled: bool,
+    /// Seconds between rounds of canaries
+    pub interval_secs: u64,
+    /// Seconds a canary's answer is waited for
+    pub timeout_secs: u64,
+    /// Base URL of the router the canaries are sent to (defaults to the
+    /// router's own address)
+    pub base_url: Option<String>,
+    /// API key the canaries authenticate with, if tenants are configured
+    pub api_key: Option<String>,
+    /// Consecutive failures after which a canary is failing
+    pub failure_threshold: u32,
+    /// Results kept per canary for the admin API
+    pub history_capacity: usize,
+    /// Canary prompts, each sent to one model
+    pub canaries: Vec<CanaryConfig>,
+    /// Endpoints notified when a canary starts failing and recovers
+    pub webhooks: Vec<AlertWebhookConfig>,
+    /// Delivery attempts of an alert after the first
+    pub webhook_retries: u32,
+}
+
+impl Default for CanaryMonitorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 300,
+            timeout_secs: 30,
+            base_url: None,
+            api_key: None,
+            failure_threshold: 2,
+            history_capacity: 20,
+            canaries: Vec::new(),
+            webhooks: Vec::new(),
+            webhook_retries: 3,
+        }
+    }
+}
+
+/// Canary prompt with a known answer
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CanaryConfig {
+    /// Name of the canary, unique among canaries
+    pub name: String,
+    /// Model the prompt is sent to
+    pub model: String,
+    /// Prompt sent as a user message
+    pub prompt: String,
+    /// Texts the answer must contain, case-insensitively
+    #[serde(default)]
+    pub expect_contains: Vec<String>,
+    /// Regex the answer must match
+    #[serde(default)]
+    pub expect_pattern: Option<String>,
+    /// Latency above which the canary fails even with a correct answer
+    #[serde(default)]
+    pub max_latency_ms: Option<u64>,
+    /// Maximum tokens of the answer
+    #[serde(default = "default_canary_max_tokens")]
+    pub max_tokens: u32,
+}
+
+fn default_canary_max_tokens() -> u32 {
+    64
+}
+
+/// Metering of tenant usage into billing events
+///
+/// Usage is summed per tenant over periods of `period_secs`. A period is
+/// billed `grace_secs` after it ends; requests reported later, up to
+/// `correction_window_secs` after its end, are billed by correction events.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MeteringConfig {
+    /// Whether usage is metered
+    pub enabled: bool,
+    /// Length of the periods usage is summed over, in seconds
+    pub period_secs: u64,
+    /// Time after the end of a period before it is billed, in seconds
+    pub grace_secs: u64,
+    /// Time after the end of a period during which late requests still
+    /// correct it, in seconds
+    pub correction_window_secs: u64,
+    /// Interval between closing periods and pushing events, in seconds
+    pub flush_interval_secs: u64,
+    /// Billing events kept for export
+    pub history_capacity: usize,
+    /// Push of billing events to Stripe's meter events API
+    pub stripe: Option<StripeMeteringConfig>,
+}
+
+impl Default for MeteringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period_secs: 3600,
+            grace_secs: 300,
+            // Stripe accepts meter events up to 35 days old
+            correction_window_secs: 35 * 24 * 3600,
+            flush_interval_secs: 60,
+            history_capacity: 100_000,
+            stripe: None,
+        }
+    }
+}
+
+/// Stripe meter events destination of billing events
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StripeMeteringConfig {
+    /// Base URL of the Stripe API
+    #[serde(default = "default_stripe_endpoint")]
+    pub endpoint: String,
+    /// Secret API key, read from `STRIPE_API_KEY` when unset
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Stripe meter event names by meter (`tokens`, `requests` or
+    /// `cache_savings`); meters without one are not pushed
+    #[serde(default)]
+    pub event_names: HashMap<String, String>,
+    /// Stripe customer IDs by tenant ID; tenants without one are not pushed
+    #[serde(default)]
+    pub customers: HashMap<String, String>,
+    /// Retries of a failed push before it waits for the next flush
+    #[serde(default = "default_stripe_retries")]
+    pub max_retries: u32,
+}
+
+fn default_stripe_endpoint() -> String {
+    "https://api.stripe.com".to_string()
+}
+
+fn default_stripe_retries() -> u32 {
+    3
+}
+
+/// Endpoint notified of alerts
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AlertWebhookConfig {
+    /// URL alerts are POSTed to
+    pub url: String,
+    /// Secret used to sign deliveries, if any
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// Export of per-request telemetry records to analytics stores
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TelemetryExportConfig {
+    /// Whether records are exported
+    pub enabled: bool,
+    /// Number of records sent per batch
+    pub batch_size: usize,
+    /// Maximum time a record waits before its batch is flushed
+    pub flush_interval_secs: u64,
+    /// Records buffered before new ones are dropped
+    pub queue_capacity: usize,
+    /// Retries of a failed batch before it is dropped
+    pub max_retries: u32,
+    /// ClickHouse sink
+    pub clickhouse: Option<ClickHouseExportConfig>,
+    /// BigQuery sink
+    pub bigquery: Option<BigQueryExportConfig>,
+}
+
+impl Default for TelemetryExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            batch_size: 500,
+            flush_interval_secs: 10,
+            queue_capacity: 10_000,
+            max_retries: 3,
+            clickhouse: None,
+            bigquery: None,
+        }
+    }
+}
+
+/// ClickHouse telemetry sink, written through the HTTP interface
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClickHouseExportConfig {
+    /// HTTP interface URL (e.g. "http://localhost:8123")
+    pub url: String,
+    /// Database holding the table
+    #[serde(default = "default_clickhouse_database")]
+    pub database: String,
+    /// Table receiving the records
+    #[serde(default = "default_telemetry_table")]
+    pub table: String,
+    /// User name
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+fn default_clickhouse_database() -> String {
+    "default".to_string()
+}
+
+fn default_telemetry_table() -> String {
+    "intellirouter_requests".to_string()
+}
+
+/// BigQuery telemetry sink, written through the streaming insert API
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BigQueryExportConfig {
+    /// Google Cloud project
+    pub project_id: String,
+    /// Dataset holding the table
+    pub dataset: String,
+    /// Table receiving the records
+    #[serde(default = "default_telemetry_table")]
+    pub table: String,
+    /// OAuth access token; falls back to the BIGQUERY_ACCESS_TOKEN environment variable
+    #[serde(default)]
+    pub access_token: Option<String>,
+    /// API base URL
+    #[serde(default = "default_bigquery_endpoint")]
+    pub endpoint: String,
+}
+
+fn default_bigquery_endpoint() -> String {
+    "https://bigquery.googleapis.com".to_string()
+}
+
+/// Period covered by a compliance report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportPeriod {
+    #[default]
+    Daily,
+    /// Weeks starting on Monday
+    Weekly,
+    Monthly,
+}
+
+/// File format of a compliance report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFileFormat {
+    Csv,
+    Parquet,
+}
+
+impl ReportFileFormat {
+    /// File name extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ReportFileFormat::Csv => "csv",
+            ReportFileFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// Periodic compliance reports generated by the audit role from the
+/// exported telemetry records
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ComplianceConfig {
+    /// Whether a report is generated for every completed period
+    pub enabled: bool,
+    /// Period covered by each scheduled report
+    pub period: ReportPeriod,
+    /// Formats every report is written in
+    pub formats: Vec<ReportFileFormat>,
+    /// Directory holding the state of export jobs, so they can be resumed
+    pub state_dir: String,
+    /// Records read from the source per query
+    pub page_size: usize,
+    /// ClickHouse table the telemetry records are exported to
+    pub source: Option<ClickHouseExportConfig>,
+    /// S3-compatible bucket the reports are written to
+    pub sink: Option<S3SinkConfig>,
+}
+
+impl Default for ComplianceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period: ReportPeriod::Daily,
+            formats: vec![ReportFileFormat::Csv, ReportFileFormat::Parquet],
+            state_dir: "data/compliance".to_string(),
+            page_size: 5000,
+            source: None,
+            sink: None,
+        }
+    }
+}
+
+/// S3-compatible object storage bucket
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct S3SinkConfig {
+    /// Endpoint URL (e.g. "https://s3.us-east-1.amazonaws.com" or a MinIO URL)
+    pub endpoint: String,
+    /// Bucket receiving the objects
+    pub bucket: String,
+    /// Region the requests are signed for
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    /// Prefix of the object keys
+    #[serde(default = "default_compliance_prefix")]
+    pub prefix: String,
+    /// Access key ID; falls back to the AWS_ACCESS_KEY_ID environment variable
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// Secret access key; falls back to the AWS_SECRET_ACCESS_KEY environment variable
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_compliance_prefix() -> String {
+    "compliance".to_string()
+}
+
+/// Backend discovering the instances of other roles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryBackendKind {
+    /// Endpoints listed in the configuration
+    #[default]
+    Static,
+    /// DNS SRV records
+    Dns,
+    /// Passing instances of a Consul service
+    Consul,
+    /// Ready addresses of a Kubernetes service's endpoints
+    Kubernetes,
+}
+
+/// Discovery of the instances of other roles (orchestrator, RAG injector,
+/// summarizer) for inter-service clients
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DiscoveryConfig {
+    /// Backend discovering instances
+    #[serde(default)]
+    pub backend: DiscoveryBackendKind,
+    /// Services to discover, by role name
+    #[serde(default)]
+    pub services: HashMap<String, DiscoveredServiceConfig>,
+    /// Seconds between refreshes of the discovered instances
+    #[serde(default = "default_discovery_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+    /// Consecutive failed calls evicting an instance
+    #[serde(default = "default_discovery_eviction_threshold")]
+    pub eviction_threshold: u32,
+    /// Seconds an evicted instance is left out of the rotation
+    #[serde(default = "default_discovery_eviction_secs")]
+    pub eviction_secs: u64,
+    /// Probe each instance's `/health` endpoint on refresh, evicting those
+    /// failing it
+    #[serde(default)]
+    pub health_check: bool,
+    /// DNS SRV backend
+    #[serde(default)]
+    pub dns: DnsDiscoveryConfig,
+    /// Consul backend
+    #[serde(default)]
+    pub consul: ConsulDiscoveryConfig,
+    /// Kubernetes backend
+    #[serde(default)]
+    pub kubernetes: KubernetesDiscoveryConfig,
+}
+
+fn default_discovery_refresh_interval_secs() -> u64 {
+    30
+}
+
+fn default_discovery_eviction_threshold() -> u32 {
+    3
+}
+
+fn default_discovery_eviction_secs() -> u64 {
+    30
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            backend: DiscoveryBackendKind::default(),
+            services: HashMap::new(),
+            refresh_interval_secs: default_discovery_refresh_interval_secs(),
+            eviction_threshold: default_discovery_eviction_threshold(),
+            eviction_secs: default_discovery_eviction_secs(),
+            health_check: false,
+            dns: DnsDiscoveryConfig::default(),
+            consul: ConsulDiscoveryConfig::default(),
+            kubernetes: KubernetesDiscoveryConfig::default(),
+        }
+    }
+}
+
+/// A service discovered for a role
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DiscoveredServiceConfig {
+    /// Name looked up with the backend: an SRV record name for DNS, a
+    /// service name for Consul, or `service` or `service:port-name` for
+    /// Kubernetes; the role name when unset
+    #[serde(default)]
+    pub name: Option<String>,
+    /// `host:port` endpoints of the static backend
+    #[serde(default)]
+    pub endpoints: Vec<String>,
+}
+
+/// DNS SRV discovery
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct DnsDiscoveryConfig {
+    /// `ip:port` of the resolver; the first `nameserver` of
+    /// `/etc/resolv.conf` when unset
+    #[serde(default)]
+    pub resolver: Option<String>,
+}
+
+/// Consul discovery
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConsulDiscoveryConfig {
+    /// Base URL of the Consul agent
+    #[serde(default = "default_consul_url")]
+    pub url: String,
+    /// Environment variable holding the ACL token
+    #[serde(default)]
+    pub token_env: Option<String>,
+    /// Datacenter to query; the agent's own when unset
+    #[serde(default)]
+    pub datacenter: Option<String>,
+}
+
+fn default_consul_url() -> String {
+    "http://127.0.0.1:8500".to_string()
+}
+
+impl Default for ConsulDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            url: default_consul_url(),
+            token_env: None,
+            datacenter: None,
+        }
+    }
+}
+
+/// Kubernetes discovery, with the pod's service account
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KubernetesDiscoveryConfig {
+    /// Base URL of the API server
+    #[serde(default = "default_kubernetes_api_url")]
+    pub api_url: String,
+    /// Namespace of the services; the pod's own when unset
+    #[serde(default)]
+    pub namespace: Option<String>,
+}
+
+fn default_kubernetes_api_url() -> String {
+    "https://kubernetes.default.svc".to_string()
+}
+
+impl Default for KubernetesDiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            api_url: default_kubernetes_api_url(),
+            namespace: None,
+        }
+    }
+}
+
+/// Authentication and authorization configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AuthConfig {
+    /// Enable authentication
+    pub auth_enabled: bool,
+    /// Authentication method
+    pub auth_method: String,
+    /// JWT secret (if using JWT authentication)
+    pub jwt_secret: Option<String>,
+    /// JWT expiration time in seconds
+    pub jwt_expiration_secs: u64,
+    /// API key header name (if using API key authentication)
+    pub api_key_header: String,
+    /// Valid API keys
+    pub api_keys: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            auth_enabled: false,
+            auth_method: "api_key".to_string(),
+            jwt_secret: None,
+            jwt_expiration_secs: 3600, // 1 hour
+            api_key_header: "X-API-Key".to_string(),
+            api_keys: vec![],
+        }
+    }
+}
+
+/// Key provider wrapping data encryption keys
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProviderType {
+    /// Master keys listed in the configuration
+    #[default]
+    Local,
+    /// HashiCorp Vault transit secrets engine
+    Vault,
+}
+
+/// Encryption at rest configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EncryptionConfig {
+    /// Encrypt stored conversations and chain checkpoints
+    pub enabled: bool,
+    /// Key provider wrapping data keys
+    pub key_provider: KeyProviderType,
+    /// Local master key new data keys are wrapped with
+    pub active_key: String,
+    /// Local master keys by id, as base64-encoded 32-byte keys or `env:VAR` references.
+    /// Retired keys stay listed so existing data can still be decrypted.
+    pub keys: HashMap<String, String>,
+    /// Vault transit configuration
+    pub vault: VaultKeyConfig,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_provider: KeyProviderType::Local,
+            active_key: "default".to_string(),
+            keys: HashMap::new(),
+            vault: VaultKeyConfig::default(),
+        }
+    }
+}
+
+/// Vault transit key configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VaultKeyConfig {
+    /// Vault address
+    pub address: String,
+    /// Vault token (defaults to the VAULT_TOKEN environment variable)
+    pub token: Option<String>,
+    /// Mount path of the transit secrets engine
+    pub mount: String,
+    /// Name of the transit key
+    pub key_name: String,
+}
+
+impl Default for VaultKeyConfig {
+    fn default() -> Self {
+        Self {
+            address: "http://127.0.0.1:8200".to_string(),
+            token: None,
+            mount: "transit".to_string(),
+            key_name: "intellirouter".to_string(),
+        }
+    }
+}
+
+/// RAG configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RagConfig {
+    /// Enable RAG
+    pub enabled: bool,
+    /// Vector database connection string
+    pub vector_db_url: Option<String>,
+    /// Default embedding model
+    pub default_embedding_model: String,
+    /// Chunk size for text splitting
+    pub chunk_size: usize,
+    /// Chunk overlap
+    pub chunk_overlap: usize,
+    /// Guarding of prompts against instructions in retrieved content
+    #[serde(default)]
+    pub injection: RagInjectionConfig,
+    /// Caching of retrieval results for similar queries
+    #[serde(default)]
+    pub retrieval_cache: RagRetrievalCacheConfig,
+    /// Chunkers splitting the documents of each collection
+    #[serde(default)]
+    pub chunking: RagChunkingConfig,
+    /// Checking that answers are supported by the retrieved chunks
+    #[serde(default)]
+    pub grounding: RagGroundingConfig,
+}
+
+impl Default for RagConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vector_db_url: None,
+            default_embedding_model: "text-embedding-3-small".to_string(),
+            chunk_size: 1000,
+            chunk_overlap: 200,
+            injection: RagInjectionConfig::default(),
+            retrieval_cache: RagRetrievalCacheConfig::default(),
+            chunking: RagChunkingConfig::default(),
+            grounding: RagGroundingConfig::default(),
+        }
+    }
+}
+
+/// What is done with retrieved chunks that look like prompt injections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectionAction {
+    /// Keep the chunk and mark it as suspected in the prompt
+    #[default]
+    Flag,
+    /// Leave the chunk out of the prompt
+    Drop,
+}
+
+/// Guarding of prompts against instructions in retrieved content
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RagInjectionConfig {
+    /// Whether retrieved chunks are checked for instruction-like content
+    pub detect: bool,
+    /// What is done with chunks that look like prompt injections
+    pub action: InjectionAction,
+    /// Patterns (regexes, case-insensitive) that mark a chunk as suspected,
+    /// besides the built-in heuristics
+    pub extra_patterns: Vec<String>,
+    /// Whether each chunk is wrapped in `<untrusted_content>` delimiters
+    pub delimiters: bool,
+    /// Whether a system message warns the model that retrieved content is
+    /// data, not instructions
+    pub system_warning: bool,
+}
+
+impl Default for RagInjectionConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            action: InjectionAction::Flag,
+            extra_patterns: Vec::new(),
+            delimiters: true,
+            system_warning: true,
+        }
+    }
+}
+
+/// Chunkers splitting the documents of each collection
+///
+/// The built-in chunkers are `fixed`, `markdown`, `code` and `semantic`, and
+/// split documents into chunks of at most `rag.chunk_size` characters.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RagChunkingConfig {
+    /// Chunker of the collections without their own
+    pub default_chunker: String,
+    /// Chunker of each collection, by collection name
+    pub collections: HashMap<String, String>,
+    /// Minimum cosine similarity of adjacent sentences kept in the same chunk
+    /// by the `semantic` chunker
+    pub semantic_threshold: f32,
+    /// OpenAI-compatible endpoint embedding sentences for the `semantic`
+    /// chunker (defaults to the router), with `rag.default_embedding_model`
+    pub endpoint: Option<String>,
+    /// API key for the endpoint
+    pub api_key: Option<String>,
+}
+
+impl Default for RagChunkingConfig {
+    fn default() -> Self {
+        Self {
+            default_chunker: "fixed".to_string(),
+            collections: HashMap::new(),
+            semantic_threshold: 0.75,
+            endpoint: None,
+            api_key: None,
+        }
+    }
+}
+
+/// Judge of whether an answer is supported by the retrieved chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroundingJudgeKind {
+    /// A model asked through a chat completions endpoint
+    #[default]
+    Llm,
+    /// A natural language inference (NLI) classifier, served by a
+    /// text-embeddings-inference `/predict` endpoint
+    Nli,
+}
+
+/// What is done with answers not supported by the retrieved chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroundingAction {
+    /// Return the answer, flagged as low confidence in the response metadata
+    #[default]
+    Flag,
+    /// Regenerate the answer with stricter instructions, flagging it if it
+    /// is still not supported
+    Regenerate,
+}
+
+/// Checking that answers are supported by the retrieved chunks
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RagGroundingConfig {
+    /// Whether answers are checked
+    pub enabled: bool,
+    /// Judge of the answers
+    pub judge: GroundingJudgeKind,
+    /// Chat model judging the answers, for the `llm` judge; the `nli` judge
+    /// uses the model its endpoint serves
+    pub model: String,
+    /// Endpoint of the judge: OpenAI-compatible for the `llm` judge
+    /// (defaults to the router), text-embeddings-inference for the `nli`
+    /// judge (required)
+    pub endpoint: Option<String>,
+    /// API key for the endpoint
+    pub api_key: Option<String>,
+    /// Minimum share of the answer supported by the chunks, from 0 to 1
+    pub min_score: f32,
+    /// What is done with answers below `min_score`
+    pub action: GroundingAction,
+    /// Number of regenerations of an unsupported answer, with the
+    /// `regenerate` action
+    pub max_regenerations: u32,
+}
+
+impl Default for RagGroundingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            judge: GroundingJudgeKind::Llm,
+            model: "gpt-4o-mini".to_string(),
+            endpoint: None,
+            api_key: None,
+            min_score: 0.7,
+            action: GroundingAction::Flag,
+            max_regenerations: 1,
+        }
+    }
+}
+
+/// Caching of the chunks retrieved for a query, keyed by its embedding
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RagRetrievalCacheConfig {
+    /// Whether retrieval results are cached
+    pub enabled: bool,
+    /// How long a cached result is served, in seconds
+    pub ttl_secs: u64,
+    /// Maximum number of cached results, the least recently used being
+    /// evicted first
+    pub max_entries: usize,
+    /// Minimum cosine similarity of a query's embedding to a cached query's
+    /// for the cached result to be served
+    pub similarity_threshold: f32,
+    /// OpenAI-compatible endpoint embedding queries (defaults to the router)
+    pub endpoint: Option<String>,
+    /// API key for the endpoint
+    pub api_key: Option<String>,
+    /// Model embedding queries (defaults to `rag.default_embedding_model`)
+    pub embedding_model: Option<String>,
+}
+
+impl Default for RagRetrievalCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: 60,
+            max_entries: 1000,
+            similarity_threshold: 0.95,
+            endpoint: None,
+            api_key: None,
+            embedding_model: None,
+        }
+    }
+}
+
+/// Chain engine configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChainEngineConfig {
+    /// Maximum chain length
+    pub max_chain_length: usize,
+    /// Maximum execution time in seconds
+    pub max_execution_time_secs: u64,
+    /// Enable chain caching
+    pub enable_caching: bool,
+    /// Cache TTL in seconds
+    pub cache_ttl_secs: u64,
+    /// Execution checkpointing
+    #[serde(default)]
+    pub checkpoint: ChainCheckpointConfig,
+    /// Chains triggered on cron schedules
+    #[serde(default)]
+    pub schedules: Vec<ChainScheduleConfig>,
+    /// Number of runs kept in each schedule's history
+    #[serde(default = "default_schedule_history_limit")]
+    pub schedule_history_limit: usize,
+    /// Inbound webhook triggers and outbound lifecycle webhooks
+    #[serde(default)]
+    pub webhooks: ChainWebhookConfig,
+    /// Agent runtime
+    #[serde(default)]
+    pub agents: ChainAgentConfig,
+    /// Dead-letter queue of failed steps and webhook deliveries
+    #[serde(default)]
+    pub dead_letters: ChainDeadLetterConfig,
+    /// Default limits of executions, for chains leaving theirs unset
+    #[serde(default)]
+    pub budget: ChainBudget,
+}
+
+fn default_schedule_history_limit() -> usize {
+    50
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for ChainEngineConfig {
+    fn default() -> Self {
+        Self {
+            max_chain_length: 10,
+            max_execution_time_secs: 300, // 5 minutes
+            enable_caching: true,
+            cache_ttl_secs: 3600, // 1 hour
+            checkpoint: ChainCheckpointConfig::default(),
+            schedules: Vec::new(),
+            schedule_history_limit: default_schedule_history_limit(),
+            webhooks: ChainWebhookConfig::default(),
+            agents: ChainAgentConfig::default(),
+            dead_letters: ChainDeadLetterConfig::default(),
+            budget: ChainBudget::default(),
+        }
+    }
+}
+
+/// Agent runtime configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChainAgentConfig {
+    /// OpenAI-compatible endpoint agents call models through (defaults to the router)
+    pub model_endpoint: Option<String>,
+    /// API key sent to the model endpoint
+    pub api_key: Option<String>,
+    /// Number of agent runs kept for trace lookup
+    pub run_history: usize,
+    /// Tools agents run through the agent API may call, by agent ID
+    /// (conversation participants by name)
+    ///
+    /// The tools an API request asks for are limited to these; agents not
+    /// listed get no tools.
+    pub tools: HashMap<String, Vec<String>>,
+}
+
+impl Default for ChainAgentConfig {
+    fn default() -> Self {
+        Self {
+            model_endpoint: None,
+            api_key: None,
+            run_history: 100,
+            tools: HashMap::new(),
+        }
+    }
+}
+
+/// Chain webhook configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChainWebhookConfig {
+    /// Inbound webhooks that trigger chains
+    pub triggers: Vec<WebhookTriggerConfig>,
+    /// Endpoints notified of execution lifecycle events
+    pub outbound: Vec<OutboundWebhookConfig>,
+    /// Delivery attempts after the first before dead-lettering
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on each attempt, in milliseconds
+    pub retry_backoff_ms: u64,
+    /// Timeout of each delivery attempt, in seconds
+    pub timeout_secs: u64,
+    /// Number of failed deliveries kept for inspection and redelivery by a
+    /// dispatcher without a shared dead-letter queue
+    pub dead_letter_capacity: usize,
+}
+
+impl Default for ChainWebhookConfig {
+    fn default() -> Self {
+        Self {
+            triggers: Vec::new(),
+            outbound: Vec::new(),
+            max_retries: 5,
+            retry_backoff_ms: 1000,
+            timeout_secs: 10,
+            dead_letter_capacity: 100,
+        }
+    }
+}
+
+/// Inbound webhook triggering a chain
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookTriggerConfig {
+    /// Trigger identifier, used in the webhook URL
+    pub id: String,
+    /// Path to the JSON chain definition to execute
+    pub chain_file: String,
+    /// Shared secret for HMAC-SHA256 signature verification
+    pub secret: String,
+    /// Header carrying the `sha256=<hex>` signature
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+}
+
+fn default_signature_header() -> String {
+    "x-intellirouter-signature".to_string()
+}
+
+/// Endpoint notified of chain execution lifecycle events
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OutboundWebhookConfig {
+    /// URL events are POSTed to
+    pub url: String,
+    /// Secret used to sign deliveries, if any
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Statuses to notify about (empty for all)
+    #[serde(default)]
+    pub events: Vec<ExecutionStatus>,
+    /// Chains to notify about (empty for all)
+    #[serde(default)]
+    pub chain_ids: Vec<String>,
+}
+
+/// Recurring chain execution
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ChainScheduleConfig {
+    /// Schedule identifier
+    pub id: String,
+    /// Cron expression, with or without a leading seconds field
+    pub cron: String,
+    /// Path to the JSON chain definition to execute
+    pub chain_file: String,
+    /// Inputs passed to every execution
+    #[serde(default)]
+    pub inputs: HashMap<String, serde_json::Value>,
+    /// What to do when a run is due while the previous one is still running
+    #[serde(default)]
+    pub overlap: ScheduleOverlapPolicy,
+    /// Maximum runs waiting under the queue policy; further runs are skipped
+    #[serde(default = "default_schedule_max_queued")]
+    pub max_queued: usize,
+    /// Maximum random delay added to each run, in seconds
+    #[serde(default)]
+    pub jitter_secs: u64,
+    /// Whether the schedule is active
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_schedule_max_queued() -> usize {
+    10
+}
+
+/// How a schedule handles a run that is due while the previous one is running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleOverlapPolicy {
+    /// Skip the new run
+    #[default]
+    Skip,
+    /// Start the new run once the previous one finishes, skipping it when
+    /// `max_queued` runs are already waiting
+    Queue,
+    /// Cancel the previous run and start the new one
+    CancelPrevious,
+}
+
+/// Chain execution checkpoint configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChainCheckpointConfig {
+    /// Persist execution state at each step boundary
+    pub enabled: bool,
+    /// Checkpoint backend ("memory" or "redis")
+    pub backend: String,
+    /// Key prefix for persisted checkpoints
+    pub key_prefix: String,
+    /// Time checkpoints are kept after their last update, in seconds
+    pub ttl_secs: u64,
+    /// Resume interrupted executions when the orchestrator starts
+    pub resume_on_startup: bool,
+}
+
+impl Default for ChainCheckpointConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: "memory".to_string(),
+            key_prefix: "intellirouter:chain_execution".to_string(),
+            ttl_secs: 86400, // 24 hours
+            resume_on_startup: true,
+        }
+    }
+}
+
+/// Chain dead-letter queue configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChainDeadLetterConfig {
+    /// Dead-letter backend ("memory" or "redis")
+    pub backend: String,
+    /// Key prefix for persisted dead letters
+    pub key_prefix: String,
+    /// Number of dead letters kept; the oldest are dropped beyond it
+    pub capacity: usize,
+}
+
+impl Default for ChainDeadLetterConfig {
+    fn default() -> Self {
+        Self {
+            backend: "memory".to_string(),
+            key_prefix: "intellirouter:chain_dead_letter".to_string(),
+            capacity: 1000,
+        }
+    }
+}
+
+/// Persona layer configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PersonaLayerConfig {
+    /// Enable persona layer
+    pub enabled: bool,
+    /// Default persona
+    pub default_persona: String,
+    /// Persona definitions directory
+    pub personas_dir: String,
+}
+
+impl Default for PersonaLayerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            default_persona: "default".to_string(),
+            personas_dir: "config/personas".to_string(),
+        }
+    }
+}
+
+/// Plugin SDK configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PluginSdkConfig {
+    /// Enable plugins
+    pub enabled: bool,
+    /// Plugin directory
+    pub plugins_dir: String,
+    /// Allowed plugin hosts
+    pub allowed_hosts: Vec<String>,
+    /// Plugin timeout in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for PluginSdkConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            plugins_dir: "plugins".to_string(),
+            allowed_hosts: vec![],
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Per-model request/response transformation rule
+///
+/// Rules are matched against the requested model ID and applied transparently
+/// by the LLM proxy before a request is forwarded to a provider.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ModelTransformConfig {
+    /// Model ID the rule applies to (a trailing `*` matches any suffix)
+    pub model: String,
+    /// Text prepended to the system prompt (a system message is added if missing)
+    #[serde(default)]
+    pub system_prompt_prefix: Option<String>,
+    /// Stop sequences injected into every request
+    #[serde(default)]
+    pub stop_sequences: Vec<String>,
+    /// Lower bound for the sampling temperature
+    #[serde(default)]
+    pub min_temperature: Option<f32>,
+    /// Upper bound for the sampling temperature
+    #[serde(default)]
+    pub max_temperature: Option<f32>,
+    /// Parameter renames applied to the outgoing request (old name -> new name)
+    #[serde(default)]
+    pub rename_params: HashMap<String, String>,
+    /// Prefixes stripped from the response content
+    #[serde(default)]
+    pub strip_response_prefixes: Vec<String>,
+}
+
+/// Overrides of the message rules of a provider
+///
+/// Unset rules keep the provider's built-in behavior.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MessageQuirksConfig {
+    /// Merge all system messages into one leading system message
+    pub merge_system_messages: Option<bool>,
+    /// Move the system prompt into the first user message
+    pub system_to_user: Option<bool>,
+    /// Merge consecutive user or assistant messages so roles alternate
+    pub alternate_roles: Option<bool>,
+    /// Drop messages without content
+    pub strip_empty_messages: Option<bool>,
+}
+
+/// Tenant served by the LLM proxy
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct TenantConfig {
+    /// Tenant identifier
+    pub id: String,
+    /// API keys that authenticate as this tenant
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Models the tenant may use (a trailing `*` matches any suffix; empty allows all)
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// Token quota enforced over a sliding window
+    #[serde(default)]
+    pub token_quota: Option<TokenQuotaConfig>,
+    /// Token quota of each end user, identified by the `user` field of
+    /// requests, enforced under the tenant's quota
+    #[serde(default)]
+    pub user_token_quota: Option<TokenQuotaConfig>,
+    /// Regions the tenant's requests may be served from (a trailing `*` matches any suffix; empty allows all)
+    #[serde(default)]
+    pub allowed_regions: Vec<String>,
+    /// Retention of the tenant's conversation memories in seconds (defaults to `memory.history_ttl_secs`)
+    #[serde(default)]
+    pub memory_retention_secs: Option<u64>,
+}
+
+/// Token quota for a tenant over a sliding window
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TokenQuotaConfig {
+    /// Maximum prompt tokens per window
+    pub max_prompt_tokens: Option<u64>,
+    /// Maximum completion tokens per window
+    pub max_completion_tokens: Option<u64>,
+    /// Maximum prompt plus completion tokens per window
+    pub max_total_tokens: Option<u64>,
+    /// Window length in seconds
+    pub window_secs: u64,
+}
+
+impl Default for TokenQuotaConfig {
+    fn default() -> Self {
+        Self {
+            max_prompt_tokens: None,
+            max_completion_tokens: None,
+            max_total_tokens: None,
+            window_secs: 86400,
+        }
+    }
+}
+
+/// How the proxy treats request parameters a provider does not support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsupportedParamPolicy {
+    /// Drop the parameter and log a warning
+    #[default]
+    Drop,
+    /// Reject the request with a validation error
+    Reject,
+}
+
+/// LLM proxy configuration
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ProxyConfig {
+    /// Per-model transformation rules, applied in order
+    #[serde(default)]
+    pub transforms: Vec<ModelTransformConfig>,
+    /// Overrides of the message rules of providers, by provider name
+    #[serde(default)]
+    pub message_quirks: HashMap<String, MessageQuirksConfig>,
+    /// Tenants identified by API key
+    #[serde(default)]
+    pub tenants: Vec<TenantConfig>,
+    /// Handling of parameters the target provider does not support
+    #[serde(default)]
+    pub unsupported_params: UnsupportedParamPolicy,
+    /// API keys granted the admin role on the admin endpoints
+    ///
+    /// Admin endpoints are refused when neither these, `admin_keys` nor OIDC
+    /// roles are configured, unless `allow_anonymous_admin` is set.
+    #[serde(default)]
+    pub admin_api_keys: Vec<String>,
+    /// Whether callers get the admin role on the admin endpoints when no
+    /// admin credentials are configured, for local development only
+    #[serde(default)]
+    pub allow_anonymous_admin: bool,
+    /// Named admin API keys whose scopes map to admin roles
+    #[serde(default)]
+    pub admin_keys: Vec<AdminKeyConfig>,
+    /// Role-based access control of the admin endpoints
+    #[serde(default)]
+    pub admin_rbac: AdminRbacConfig,
+    /// Sampled log of routing decisions
+    #[serde(default)]
+    pub decision_log: DecisionLogConfig,
+    /// Buffering of streamed responses for resumption
+    #[serde(default)]
+    pub stream_resume: StreamResumeConfig,
+    /// Buffering and slow-client handling of streamed responses
+    #[serde(default)]
+    pub stream_backpressure: StreamBackpressureConfig,
+    /// Pacing of streamed responses
+    #[serde(default)]
+    pub stream_smoothing: StreamSmoothingConfig,
+    /// Coalescing of identical in-flight requests
+    #[serde(default)]
+    pub coalescing: CoalescingConfig,
+    /// Connection pooling of the HTTP clients calling providers
+    #[serde(default)]
+    pub outbound_http: OutboundHttpConfig,
+    /// Versioned system prompts selected per request
+    #[serde(default)]
+    pub prompts: PromptRegistryConfig,
+    /// Signing of exported deployment bundles
+    #[serde(default)]
+    pub bundle: BundleConfig,
+    /// Gradual rollout of changed model endpoints and versions
+    #[serde(default)]
+    pub model_rollout: ModelRolloutConfig,
+    /// Tracing of prompt assembly requested with the debug header
+    #[serde(default)]
+    pub prompt_trace: PromptTraceConfig,
+    /// Erasure of data subjects and signing of erasure reports
+    #[serde(default)]
+    pub erasure: ErasureConfig,
+    /// Usage analytics of end users
+    #[serde(default)]
+    pub user_usage: UserUsageConfig,
+    /// Validation and repair of output requested as JSON
+    #[serde(default)]
+    pub json_mode: JsonModeConfig,
+    /// Capture of request payloads for replay
+    #[serde(default)]
+    pub payloads: PayloadCaptureConfig,
+    /// Recording of the partial completions of interrupted streams
+    #[serde(default)]
+    pub partial_responses: PartialResponseConfig,
+    /// Routing provenance returned with responses
+    #[serde(default)]
+    pub response_metadata: ResponseMetadataConfig,
+    /// Versions of the public HTTP API and their deprecations
+    #[serde(default)]
+    pub api_versioning: ApiVersioningConfig,
+}
+
+/// Versions of the public HTTP API and their deprecations
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ApiVersioningConfig {
+    /// Versions served, each under its own path prefix such as `/v2`
+    pub versions: Vec<ApiVersion>,
+    /// Deprecated versions and routes; the first matching a request applies
+    pub deprecations: Vec<ApiDeprecationConfig>,
+}
+
+impl Default for ApiVersioningConfig {
+    fn default() -> Self {
+        Self {
+            versions: ApiVersion::ALL.to_vec(),
+            deprecations: Vec::new(),
+        }
+    }
+}
+
+/// Deprecation of an API version, or of a route of it
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiDeprecationConfig {
+    /// Deprecated version
+    pub version: ApiVersion,
+    /// Route within the version, such as `/chat/completions`, or prefix
+    /// ending with `*`; the whole version when unset
+    #[serde(default)]
+    pub path: Option<String>,
+    /// When the route was deprecated, sent in the `Deprecation` header
+    #[serde(default)]
+    pub deprecated_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// When the route stops being served, sent in the `Sunset` header
+    #[serde(default)]
+    pub sunset: Option<chrono::DateTime<chrono::Utc>>,
+    /// Documentation of the deprecation, sent in a `Link` header
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Whether requests after the sunset are refused with `410 Gone`
+    #[serde(default)]
+    pub enforce_sunset: bool,
+}
+
+/// Routing provenance returned with responses
+///
+/// Responses name the provider and model that served them, the cache
+/// status, the RAG collections and persona applied, and a latency breakdown,
+/// in `x-intellirouter-*` headers and a `metadata` field of the response body.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ResponseMetadataConfig {
+    /// Whether responses carry routing metadata
+    pub enabled: bool,
+    /// Whether the metadata is sent as response headers
+    pub headers: bool,
+    /// Whether the metadata is added to response bodies
+    pub body: bool,
+}
+
+impl Default for ResponseMetadataConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            headers: true,
+            body: true,
+        }
+    }
+}
+
+/// Recording of the partial completions of interrupted streams
+///
+/// Streams sent with the `x-intellirouter-conversation-id` header that end
+/// before their completion have what the client received appended to the
+/// conversation, as an assistant message marked partial, which
+/// `/v1/conversations/{id}/continue` can ask the model to continue.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PartialResponseConfig {
+    /// Whether partial completions are recorded
+    pub enabled: bool,
+    /// Shortest partial completion recorded, in characters
+    pub min_chars: usize,
+    /// User message asking the model to continue a partial completion
+    pub continue_instruction: String,
+}
+
+impl Default for PartialResponseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_chars: 1,
+            continue_instruction: "Continue your last response exactly where it stopped, \
+                                   without repeating any of it."
+                .to_string(),
+        }
+    }
+}
+
+/// Validation and repair of output requested with a JSON `response_format`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct JsonModeConfig {
+    /// Fail output that is not valid JSON instead of repairing it
+    pub strict: bool,
+    /// Times the model is asked again for output that can't be repaired
+    pub max_reasks: u32,
+}
+
+/// Usage analytics of the end users named by the `user` field of requests
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UserUsageConfig {
+    /// Whether usage is tracked per end user
+    pub enabled: bool,
+    /// End users tracked; the least recently seen is dropped beyond it
+    pub capacity: usize,
+}
+
+impl Default for UserUsageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            capacity: 10_000,
+        }
+    }
+}
+
+/// Tracing of how the prompt of a request is assembled
+///
+/// Requests with the `x-intellirouter-debug: prompt-trace` header record the
+/// messages after each assembly stage. Traces contain full prompts, so they
+/// are off by default and only returned to callers when allowed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PromptTraceConfig {
+    /// Whether requests may ask for a prompt trace
+    pub enabled: bool,
+    /// Whether traces are returned in the response body, besides being kept
+    /// for the admin API
+    pub include_in_response: bool,
+    /// Number of traces kept for the admin API
+    pub capacity: usize,
+}
+
+impl Default for PromptTraceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            include_in_response: false,
+            capacity: 100,
+        }
+    }
+}
+
+/// Capture of request payloads for replay
+///
+/// Each completed chat completion is written with the request as sent to
+/// the model, the prompt and routing policy versions that shaped it and the
+/// response, so `intellirouter replay` can re-execute it. Payloads contain
+/// full prompts and outputs, so capture is off by default.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PayloadCaptureConfig {
+    /// Whether payloads are captured
+    pub enabled: bool,
+    /// Directory the payloads are written to, one JSON file per request
+    pub dir: String,
+    /// Share of requests captured, from 0.0 to 1.0
+    pub sample_rate: f64,
+    /// Payloads kept; the oldest are deleted beyond it
+    pub max_records: usize,
+}
+
+impl Default for PayloadCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "data/payloads".to_string(),
+            sample_rate: 1.0,
+            max_records: 10_000,
+        }
+    }
+}
+
+/// Gradual rollout of a model whose endpoint or version changes
+///
+/// The new configuration serves a growing share of the model's traffic,
+/// one stage at a time. Each stage lasts until the new configuration served
+/// `min_requests` requests and `min_stage_secs` passed; its error rate and
+/// mean latency are compared with those of the previous configuration, and
+/// the rollout is rolled back as soon as either regresses past its limit.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ModelRolloutConfig {
+    /// Share of traffic served by the new configuration at each stage, in
+    /// percent; the last stage must be 100. Empty to apply changes at once.
+    pub stages: Vec<u8>,
+    /// Requests each configuration must serve before a stage is evaluated
+    pub min_requests: u64,
+    /// Minimum duration of a stage, in seconds
+    pub min_stage_secs: u64,
+    /// Largest tolerated increase of the error rate, as a fraction of
+    /// requests (0.05 allows 5 more failed requests in 100)
+    pub max_error_rate_increase: f64,
+    /// Largest tolerated ratio of the new to the old mean latency
+    pub max_latency_ratio: f64,
+    /// Endpoints notified when a rollout is rolled back
+    pub webhooks: Vec<AlertWebhookConfig>,
+    /// Rollouts kept for the admin API, in progress or finished
+    pub history_capacity: usize,
+}
+
+impl Default for ModelRolloutConfig {
+    fn default() -> Self {
+        Self {
+            stages: vec![5, 25, 100],
+            min_requests: 50,
+            min_stage_secs: 300,
+            max_error_rate_increase: 0.05,
+            max_latency_ratio: 1.5,
+            webhooks: Vec::new(),
+            history_capacity: 50,
+        }
+    }
+}
+
+/// Signing of deployment bundles exported and imported through the admin API
+///
+/// Deployments that exchange bundles must share the signing key.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct BundleConfig {
+    /// HMAC key bundles are signed with, or an `env:VAR` reference; bundle
+    /// export and import are disabled when unset
+    pub signing_key: Option<String>,
+}
+
+impl BundleConfig {
+    /// Resolve the signing key, reading `env:VAR` references
+    pub fn signing_key(&self) -> Option<String> {
+        resolve_key(self.signing_key.as_deref()?)
+    }
+}
+
+/// Resolve a key, reading `env:VAR` references
+fn resolve_key(key: &str) -> Option<String> {
+    match key.strip_prefix("env:") {
+        Some(var) => std::env::var(var).ok().filter(|key| !key.is_empty()),
+        None => Some(key.to_string()),
+    }
+}
+
+/// Erasure of data subjects through the admin API
+///
+/// Erasure reports are signed so they can be handed to the data subject or
+/// a supervisory authority as proof of erasure.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ErasureConfig {
+    /// HMAC key erasure reports are signed with, or an `env:VAR` reference;
+    /// erasure requests are refused when unset
+    pub signing_key: Option<String>,
+    /// Number of erasure reports kept for the admin API
+    pub report_capacity: usize,
+}
+
+impl Default for ErasureConfig {
+    fn default() -> Self {
+        Self {
+            signing_key: None,
+            report_capacity: 100,
         }
     }
 }
 
-/// Router configuration
+impl ErasureConfig {
+    /// Resolve the signing key, reading `env:VAR` references
+    pub fn signing_key(&self) -> Option<String> {
+        resolve_key(self.signing_key.as_deref()?)
+    }
+}
+
+/// Admin API key with scopes
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct RouterConfig {
-    /// Default routing strategy
-    pub default_strategy: String,
-    /// Available routing strategies
-    pub available_strategies: Vec<String>,
-    /// Routing rules
-    pub rules: HashMap<String, String>,
+pub struct AdminKeyConfig {
+    /// Name recorded in audit events
+    pub name: String,
+    /// API key
+    pub key: String,
+    /// Scopes, mapped to roles through `admin_rbac.scope_roles`
+    #[serde(default)]
+    pub scopes: Vec<String>,
 }
 
-impl Default for RouterConfig {
+/// Role-based access control of the admin endpoints
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AdminRbacConfig {
+    /// Role granted by each API key scope
+    pub scope_roles: HashMap<String, AdminRole>,
+    /// Roles granted by OIDC token claims
+    pub oidc: Option<OidcRoleConfig>,
+    /// Number of recent audit events kept in memory
+    pub audit_capacity: usize,
+    /// Seconds persisted audit events are kept before being pruned
+    pub audit_retention_secs: u64,
+}
+
+impl Default for AdminRbacConfig {
     fn default() -> Self {
         Self {
-            default_strategy: "cost-optimized".to_string(),
-            available_strategies: vec![
-                "cost-optimized".to_string(),
-                "performance-optimized".to_string(),
-                "round-robin".to_string(),
-                "fallback".to_string(),
-            ],
-            rules: HashMap::new(),
+            scope_roles: HashMap::from([
+                ("admin:read".to_string(), AdminRole::Viewer),
+                ("admin:operate".to_string(), AdminRole::Operator),
+                ("admin:*".to_string(), AdminRole::Admin),
+            ]),
+            oidc: None,
+            audit_capacity: 1000,
+            audit_retention_secs: 90 * 24 * 60 * 60,
         }
     }
 }
 
-/// Memory backend configuration
+/// Mapping of OIDC ID or access token claims to admin roles
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct MemoryConfig {
-    /// Memory backend type
-    pub backend_type: String,
-    /// Redis connection string (if using Redis backend)
-    pub redis_url: Option<String>,
-    /// File storage path (if using file backend)
-    pub file_path: Option<String>,
-    /// Maximum conversation history length
-    pub max_history_length: usize,
-    /// TTL for conversation history in seconds
-    pub history_ttl_secs: u64,
+#[serde(default)]
+pub struct OidcRoleConfig {
+    /// Expected token issuer
+    pub issuer: Option<String>,
+    /// Expected token audience
+    pub audience: Option<String>,
+    /// PEM-encoded RSA public key verifying RS256 tokens
+    pub public_key_pem: Option<String>,
+    /// Secret verifying HS256 tokens
+    pub hmac_secret: Option<String>,
+    /// Dotted path of the claim listing the caller's groups or roles
+    pub role_claim: String,
+    /// Role granted by each claim value
+    pub claim_roles: HashMap<String, AdminRole>,
 }
 
-impl Default for MemoryConfig {
+impl Default for OidcRoleConfig {
     fn default() -> Self {
         Self {
-            backend_type: "memory".to_string(),
-            redis_url: None,
-            file_path: None,
-            max_history_length: 100,
-            history_ttl_secs: 86400, // 24 hours
+            issuer: None,
+            audience: None,
+            public_key_pem: None,
+            hmac_secret: None,
+            role_claim: "roles".to_string(),
+            claim_roles: HashMap::new(),
         }
     }
 }
 
-/// Telemetry configuration
+/// Sampled feed of routing decisions exposed to admins
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct TelemetryConfig {
-    /// Log level
-    pub log_level: String,
-    /// Enable metrics collection
-    pub metrics_enabled: bool,
-    /// Enable distributed tracing
-    pub tracing_enabled: bool,
-    /// Metrics endpoint
-    pub metrics_endpoint: Option<String>,
-    /// Tracing endpoint
-    pub tracing_endpoint: Option<String>,
+#[serde(default)]
+pub struct DecisionLogConfig {
+    /// Whether routing decisions are recorded
+    pub enabled: bool,
+    /// Fraction of decisions recorded, between 0.0 and 1.0
+    pub sample_rate: f64,
+    /// Number of recent decisions kept in memory
+    pub capacity: usize,
+    /// Decision fields replaced with a redaction marker (e.g. "tenant", "user")
+    pub redact_fields: Vec<String>,
 }
 
-impl Default for TelemetryConfig {
+impl Default for DecisionLogConfig {
     fn default() -> Self {
         Self {
-            log_level: "info".to_string(),
-            metrics_enabled: true,
-            tracing_enabled: true,
-            metrics_endpoint: None,
-            tracing_endpoint: None,
+            enabled: true,
+            sample_rate: 1.0,
+            capacity: 100,
+            redact_fields: Vec::new(),
         }
     }
 }
 
-impl TelemetryConfig {
-    /// Convert string log level to LogLevel enum
-    pub fn log_level(&self) -> Result<LogLevel, String> {
-        match self.log_level.to_lowercase().as_str() {
-            "debug" => Ok(LogLevel::DEBUG),
-            "info" => Ok(LogLevel::INFO),
-            "warning" | "warn" => Ok(LogLevel::WARN),
-            "error" => Ok(LogLevel::ERROR),
-            _ => Err(format!("Invalid log level: {}", self.log_level)),
+/// Buffering of streamed responses so dropped clients can resume them
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StreamResumeConfig {
+    /// Whether streamed responses are buffered and can be resumed
+    pub enabled: bool,
+    /// Seconds a finished stream stays resumable
+    pub ttl_secs: u64,
+    /// Maximum number of buffered streams; the oldest are dropped first
+    pub max_streams: usize,
+}
+
+impl Default for StreamResumeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_secs: 60,
+            max_streams: 1000,
         }
     }
 }
 
-/// Authentication and authorization configuration
+/// Coalescing of identical deterministic requests into one upstream call
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct AuthConfig {
-    /// Enable authentication
-    pub auth_enabled: bool,
-    /// Authentication method
-    pub auth_method: String,
-    /// JWT secret (if using JWT authentication)
-    pub jwt_secret: Option<String>,
-    /// JWT expiration time in seconds
-    pub jwt_expiration_secs: u64,
-    /// API key header name (if using API key authentication)
-    pub api_key_header: String,
-    /// Valid API keys
-    pub api_keys: Vec<String>,
+#[serde(default)]
+pub struct CoalescingConfig {
+    /// Whether identical in-flight requests share one upstream call
+    pub enabled: bool,
 }
 
-impl Default for AuthConfig {
+impl Default for CoalescingConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Registry of versioned system prompts
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PromptRegistryConfig {
+    /// Number of records kept of the prompt version serving each request
+    pub audit_capacity: usize,
+}
+
+impl Default for PromptRegistryConfig {
     fn default() -> Self {
         Self {
-            auth_enabled: false,
-            auth_method: "api_key".to_string(),
-            jwt_secret: None,
-            jwt_expiration_secs: 3600, // 1 hour
-            api_key_header: "X-API-Key".to_string(),
-            api_keys: vec![],
+            audit_capacity: 10000,
         }
     }
 }
 
-/// RAG configuration
+/// What the proxy does when a streaming client stops reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlowClientPolicy {
+    /// Drop the connection once the client stalled for `stall_timeout_secs`
+    #[default]
+    Disconnect,
+    /// Stop reading from the provider until the client catches up, for up
+    /// to `max_pause_secs`; streams that can't be paused are disconnected
+    PauseUpstream,
+}
+
+/// Bounded buffering of streamed responses per client
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct RagConfig {
-    /// Enable RAG
+#[serde(default)]
+pub struct StreamBackpressureConfig {
+    /// Events buffered for a client before it counts as stalled
+    pub buffer_events: usize,
+    /// Handling of clients that stall
+    pub policy: SlowClientPolicy,
+    /// Seconds a stalled client is kept before its connection is dropped
+    pub stall_timeout_secs: u64,
+    /// Seconds the provider stream may be paused for a stalled client
+    pub max_pause_secs: u64,
+}
+
+impl Default for StreamBackpressureConfig {
+    fn default() -> Self {
+        Self {
+            buffer_events: 64,
+            policy: SlowClientPolicy::Disconnect,
+            stall_timeout_secs: 30,
+            max_pause_secs: 120,
+        }
+    }
+}
+
+/// Pacing of streamed responses to a ceiling of content tokens per second
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StreamSmoothingConfig {
+    /// Pace all streams; requests can still ask for pacing with a header
     pub enabled: bool,
-    /// Vector database connection string
-    pub vector_db_url: Option<String>,
-    /// Default embedding model
-    pub default_embedding_model: String,
-    /// Chunk size for text splitting
-    pub chunk_size: usize,
-    /// Chunk overlap
-    pub chunk_overlap: usize,
+    /// Content tokens delivered per second
+    pub tokens_per_sec: f64,
+    /// Tokens delivered without waiting at the start of a stream or after a pause
+    pub burst_tokens: usize,
 }
 
-impl Default for RagConfig {
+impl Default for StreamSmoothingConfig {
     fn default() -> Self {
         Self {
             enabled: false,
-            vector_db_url: None,
-            default_embedding_model: "text-embedding-3-small".to_string(),
-            chunk_size: 1000,
-            chunk_overlap: 200,
+            tokens_per_sec: 30.0,
+            burst_tokens: 10,
         }
     }
 }
 
-/// Chain engine configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct ChainEngineConfig {
-    /// Maximum chain length
-    pub max_chain_length: usize,
-    /// Maximum execution time in seconds
-    pub max_execution_time_secs: u64,
-    /// Enable chain caching
-    pub enable_caching: bool,
-    /// Cache TTL in seconds
-    pub cache_ttl_secs: u64,
+/// Connection pooling and transport tuning of outbound provider clients
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConnectionPoolConfig {
+    /// Idle connections kept open per host
+    pub max_idle_per_host: usize,
+    /// Seconds an idle connection is kept open
+    pub idle_timeout_secs: u64,
+    /// Seconds allowed for establishing a connection
+    pub connect_timeout_secs: u64,
+    /// Whether Nagle's algorithm is disabled on provider connections
+    pub tcp_nodelay: bool,
+    /// Seconds between TCP keepalive probes (0 disables them)
+    pub tcp_keepalive_secs: u64,
+    /// Whether to speak HTTP/2 without negotiating it first
+    pub http2_prior_knowledge: bool,
+    /// Seconds between HTTP/2 keepalive pings (0 disables them)
+    pub http2_keepalive_interval_secs: u64,
+    /// Seconds to wait for an HTTP/2 keepalive acknowledgement
+    pub http2_keepalive_timeout_secs: u64,
+    /// Whether HTTP/2 keepalive pings are sent on idle connections
+    pub http2_keepalive_while_idle: bool,
+    /// Whether HTTP/2 flow control windows adapt to the connection's throughput
+    pub http2_adaptive_window: bool,
+    /// Seconds resolved provider addresses are cached (0 disables caching)
+    pub dns_cache_ttl_secs: u64,
+    /// Proxy provider traffic is sent through
+    pub proxy: Option<OutboundProxyConfig>,
+    /// Whether the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment
+    /// variables apply when no proxy is configured
+    pub use_env_proxy: bool,
+    /// Verification of provider certificates, when it departs from the
+    /// system's trusted CAs
+    pub tls: Option<OutboundTlsConfig>,
 }
 
-impl Default for ChainEngineConfig {
+impl Default for ConnectionPoolConfig {
     fn default() -> Self {
         Self {
-            max_chain_length: 10,
-            max_execution_time_secs: 300, // 5 minutes
-            enable_caching: true,
-            cache_ttl_secs: 3600, // 1 hour
+            max_idle_per_host: 32,
+            idle_timeout_secs: 90,
+            connect_timeout_secs: 10,
+            tcp_nodelay: true,
+            tcp_keepalive_secs: 60,
+            http2_prior_knowledge: false,
+            http2_keepalive_interval_secs: 30,
+            http2_keepalive_timeout_secs: 10,
+            http2_keepalive_while_idle: false,
+            http2_adaptive_window: false,
+            dns_cache_ttl_secs: 60,
+            proxy: None,
+            use_env_proxy: true,
+            tls: None,
         }
     }
 }
 
-/// Persona layer configuration
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct PersonaLayerConfig {
-    /// Enable persona layer
-    pub enabled: bool,
-    /// Default persona
-    pub default_persona: String,
-    /// Persona definitions directory
-    pub personas_dir: String,
+/// Proxy outbound provider traffic is sent through
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutboundProxyConfig {
+    /// Proxy URL: `http://` or `https://` for HTTP CONNECT proxies, or
+    /// `socks5://` (`socks5h://` to resolve hosts at the proxy)
+    pub url: String,
+    /// User the proxy authenticates
+    pub username: Option<String>,
+    /// Password of the user, or an `env:VAR` reference
+    pub password: Option<String>,
+    /// Hosts reached directly rather than through the proxy, as domains,
+    /// IP addresses or CIDR ranges
+    pub no_proxy: Vec<String>,
 }
 
-impl Default for PersonaLayerConfig {
+impl OutboundProxyConfig {
+    /// Resolve the password, reading `env:VAR` references
+    pub fn password(&self) -> Option<String> {
+        resolve_key(self.password.as_deref()?)
+    }
+}
+
+/// Verification of provider certificates, for endpoints serving
+/// certificates of a private CA
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutboundTlsConfig {
+    /// PEM file of the CA certificates provider certificates may chain to
+    pub ca_bundle: Option<String>,
+    /// Whether the system's trusted CAs are trusted alongside the bundle
+    pub system_roots: bool,
+    /// Base64 SHA-256 hashes of pinned public keys (SPKI); when set, a
+    /// provider's certificate chain must contain one of the keys
+    pub spki_pins: Vec<String>,
+}
+
+impl Default for OutboundTlsConfig {
     fn default() -> Self {
         Self {
-            enabled: true,
-            default_persona: "default".to_string(),
-            personas_dir: "config/personas".to_string(),
+            ca_bundle: None,
+            system_roots: true,
+            spki_pins: Vec::new(),
         }
     }
 }
 
-/// Plugin SDK configuration
+/// Outbound provider client settings, with per-provider overrides
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct OutboundHttpConfig {
+    /// Settings of providers without an override
+    #[serde(flatten)]
+    pub defaults: ConnectionPoolConfig,
+    /// Settings replacing the defaults for a provider, keyed by provider name
+    pub providers: HashMap<String, ConnectionPoolConfig>,
+}
+
+impl OutboundHttpConfig {
+    /// Settings of a provider's client
+    pub fn for_provider(&self, provider: &str) -> &ConnectionPoolConfig {
+        self.providers.get(provider).unwrap_or(&self.defaults)
+    }
+}
+
+/// Tool execution configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct PluginSdkConfig {
-    /// Enable plugins
-    pub enabled: bool,
-    /// Plugin directory
-    pub plugins_dir: String,
-    /// Allowed plugin hosts
-    pub allowed_hosts: Vec<String>,
-    /// Plugin timeout in seconds
+#[serde(default)]
+pub struct ToolsConfig {
+    /// Maximum model/tool round trips before a tool loop is aborted
+    pub max_iterations: u32,
+    /// Timeout of a single tool call, in seconds
     pub timeout_secs: u64,
+    /// Whether the built-in calculator tool is available
+    pub calculator: bool,
+    /// Web search tool (disabled when unset)
+    pub search: Option<SearchToolConfig>,
+    /// HTTP endpoints exposed as tools
+    pub http: Vec<HttpToolConfig>,
+    /// External MCP tool servers
+    pub mcp_servers: Vec<McpServerConfig>,
 }
 
-impl Default for PluginSdkConfig {
+impl Default for ToolsConfig {
     fn default() -> Self {
         Self {
-            enabled: false,
-            plugins_dir: "plugins".to_string(),
-            allowed_hosts: vec![],
+            max_iterations: 5,
             timeout_secs: 30,
+            calculator: true,
+            search: None,
+            http: Vec::new(),
+            mcp_servers: Vec::new(),
         }
     }
 }
 
+/// Web search tool configuration
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SearchToolConfig {
+    /// SearxNG-compatible search endpoint returning JSON results
+    pub endpoint: String,
+    /// Bearer token sent with search requests
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Number of results returned to the model
+    #[serde(default = "default_search_max_results")]
+    pub max_results: usize,
+}
+
+fn default_search_max_results() -> usize {
+    5
+}
+
+/// HTTP endpoint exposed as a tool
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct HttpToolConfig {
+    /// Tool name advertised to models
+    pub name: String,
+    /// Tool description advertised to models
+    pub description: String,
+    /// HTTP method
+    #[serde(default = "default_http_tool_method")]
+    pub method: String,
+    /// URL, with optional `{{argument}}` placeholders
+    pub url: String,
+    /// Extra request headers
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// JSON Schema of the tool arguments
+    #[serde(default = "default_http_tool_parameters")]
+    pub parameters: serde_json::Value,
+}
+
+fn default_http_tool_method() -> String {
+    "GET".to_string()
+}
+
+fn default_http_tool_parameters() -> serde_json::Value {
+    serde_json::json!({ "type": "object", "properties": {} })
+}
+
+/// Transport used to reach an MCP server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum McpTransport {
+    /// Spawn the server as a child process and talk over stdin/stdout
+    #[default]
+    Stdio,
+    /// POST JSON-RPC messages to an HTTP endpoint
+    Http,
+}
+
+/// MCP server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct McpServerConfig {
+    /// Server name, used to prefix its tool names
+    pub name: String,
+    /// Transport used to reach the server
+    pub transport: McpTransport,
+    /// Command to spawn (stdio transport)
+    pub command: Option<String>,
+    /// Command arguments (stdio transport)
+    pub args: Vec<String>,
+    /// Environment variables for the spawned command (stdio transport)
+    pub env: HashMap<String, String>,
+    /// Endpoint URL (HTTP transport)
+    pub url: Option<String>,
+    /// Extra request headers (HTTP transport)
+    pub headers: HashMap<String, String>,
+}
+
 /// Main configuration structure for IntelliRouter
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
@@ -397,6 +3179,28 @@ pub struct Config {
     pub persona_layer: PersonaLayerConfig,
     /// Plugin SDK configuration
     pub plugin_sdk: PluginSdkConfig,
+    /// LLM proxy configuration
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    /// Tool execution configuration
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Encryption at rest configuration
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    /// Compliance report exports of the audit role
+    #[serde(default)]
+    pub compliance: ComplianceConfig,
+    /// Discovery of other roles' instances for inter-service clients
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    /// Where the state of memory, the model registry, the audit log and
+    /// token quotas is kept
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// Dependency checks run before serving
+    #[serde(default)]
+    pub startup: StartupConfig,
 }
 
 impl Default for Config {
@@ -413,6 +3217,13 @@ impl Default for Config {
             chain_engine: ChainEngineConfig::default(),
             persona_layer: PersonaLayerConfig::default(),
             plugin_sdk: PluginSdkConfig::default(),
+            proxy: ProxyConfig::default(),
+            tools: ToolsConfig::default(),
+            encryption: EncryptionConfig::default(),
+            compliance: ComplianceConfig::default(),
+            discovery: DiscoveryConfig::default(),
+            storage: StorageConfig::default(),
+            startup: StartupConfig::default(),
         }
     }
 }
@@ -598,10 +3409,27 @@ impl Config {
         if self.server.port == 0 {
             return Err("Server port cannot be 0".to_string());
         }
+        for (role, listener) in self.server.listeners.iter() {
+            if listener.socket_activation && listener.unix_socket.is_some() {
+                return Err(format!(
+                    "Listener of role '{}' cannot both bind a Unix socket and use socket activation",
+                    role
+                ));
+            }
+        }
+
+        // Validate synthetic traffic ranges
+        for range in &self.server.synthetic_traffic.ip_ranges {
+            range.parse::<IpRange>()?;
+        }
 
         // Validate telemetry config
         self.telemetry.log_level().map_err(|e| e)?;
 
+        // Validate prices
+        crate::modules::telemetry::cost::CostCalculator::from_config(&self.telemetry.pricing)
+            .map_err(|e| format!("Pricing is invalid: {}", e))?;
+
         // Validate model registry config
         if self.model_registry.providers.is_empty() {
             return Err("At least one LLM provider must be configured".to_string());
@@ -626,10 +3454,13 @@ impl Config {
                 // No additional validation needed for memory backend
             }
             "redis" => {
-                if self.memory.redis_url.is_none() {
+                if self.memory.redis_url.is_none()
+                    && self.memory.redis.topology == RedisTopology::Single
+                {
                     return Err("Redis URL must be provided for Redis memory backend".to_string());
                 }
             }
+            "sqlite" => {}
             "file" => {
                 if self.memory.file_path.is_none() {
                     return Err("File path must be provided for file memory backend".to_string());
@@ -643,6 +3474,126 @@ impl Config {
             }
         }
 
+        // Validate API versions
+        let api_versioning = &self.proxy.api_versioning;
+        if api_versioning.versions.is_empty() {
+            return Err("At least one API version must be served".to_string());
+        }
+        for deprecation in &api_versioning.deprecations {
+            if !api_versioning.versions.contains(&deprecation.version) {
+                return Err(format!(
+                    "API version {} is deprecated but not served",
+                    deprecation.version
+                ));
+            }
+            if deprecation.enforce_sunset && deprecation.sunset.is_none() {
+                return Err(format!(
+                    "Deprecation of API version {} enforces a sunset but has none",
+                    deprecation.version
+                ));
+            }
+        }
+
+        // Validate storage
+        let sqlite_used =
+            self.storage.is_embedded() || self.memory.backend_type.as_str() == "sqlite";
+        if sqlite_used && self.storage.sqlite_path.is_empty() {
+            return Err("SQLite storage needs a sqlite_path".to_string());
+        }
+
+        // Validate Redis topology
+        let redis = &self.memory.redis;
+        match redis.topology {
+            RedisTopology::Single => {}
+            RedisTopology::Sentinel => {
+                if redis.sentinels.is_empty() || redis.master_name.is_empty() {
+                    return Err(
+                        "Redis Sentinel topology needs sentinels and a master_name".to_string()
+                    );
+                }
+            }
+            RedisTopology::Cluster => {
+                if redis.nodes.is_empty() {
+                    return Err("Redis Cluster topology needs seed nodes".to_string());
+                }
+                if redis.database != 0 {
+                    return Err("Redis Cluster only has database 0".to_string());
+                }
+            }
+        }
+        crate::modules::common::RedisConnector::from_config(&self.memory)
+            .map_err(|e| format!("Invalid Redis configuration: {}", e))?;
+
+        // Validate model rollout stages
+        let stages = &self.proxy.model_rollout.stages;
+        if !stages.is_empty()
+            && (stages.last() != Some(&100)
+                || stages.windows(2).any(|pair| pair[0] >= pair[1])
+                || stages[0] == 0)
+        {
+            return Err("Model rollout stages must increase from above 0 up to 100".to_string());
+        }
+
+        // Validate stream smoothing
+        let smoothing = &self.proxy.stream_smoothing;
+        if smoothing.enabled
+            && !(smoothing.tokens_per_sec.is_finite() && smoothing.tokens_per_sec > 0.0)
+        {
+            return Err("Stream smoothing needs a positive tokens_per_sec".to_string());
+        }
+
+        // Validate outbound proxies
+        let outbound = &self.proxy.outbound_http;
+        let pools = std::iter::once(("default", &outbound.defaults)).chain(
+            outbound
+                .providers
+                .iter()
+                .map(|(provider, pool)| (provider.as_str(), pool)),
+        );
+        for (provider, pool) in pools {
+            if let Some(proxy) = &pool.proxy {
+                let scheme = reqwest::Url::parse(&proxy.url)
+                    .map(|url| url.scheme().to_string())
+                    .unwrap_or_default();
+                if !matches!(scheme.as_str(), "http" | "https" | "socks5" | "socks5h") {
+                    return Err(format!(
+                        "Outbound proxy of provider '{}' needs an http, https, socks5 or socks5h URL",
+                        provider
+                    ));
+                }
+            }
+            if let Some(settings) = &pool.tls {
+                tls::client_config(settings, false).map_err(|e| {
+                    format!("TLS settings of provider '{}' are invalid: {}", provider, e)
+                })?;
+            }
+        }
+
+        // Validate metering
+        let metering = &self.telemetry.metering;
+        if metering.enabled && metering.period_secs == 0 {
+            return Err("Metering needs a positive period_secs".to_string());
+        }
+
+        // Validate resource limits
+        let resources = &self.telemetry.resources;
+        if resources.enabled && resources.interval_secs == 0 {
+            return Err("Resource monitoring needs a positive interval_secs".to_string());
+        }
+        let limits = &resources.limits;
+        for (name, limit) in [
+            ("memory_percent", limits.memory_percent),
+            ("cpu_percent", limits.cpu_percent),
+            ("fd_percent", limits.fd_percent),
+        ] {
+            if limit.is_some_and(|limit| !(limit > 0.0 && limit <= 100.0)) {
+                return Err(format!(
+                    "Resource limit {} must be above 0 and at most 100",
+                    name
+                ));
+            }
+        }
+
         // Validate auth config
         if self.auth.auth_enabled {
             match self.auth.auth_method.as_str() {
@@ -676,6 +3627,30 @@ impl Config {
         if self.rag.enabled && self.rag.vector_db_url.is_none() {
             return Err("Vector database URL must be provided when RAG is enabled".to_string());
         }
+        let grounding = &self.rag.grounding;
+        if grounding.enabled {
+            if !(0.0..=1.0).contains(&grounding.min_score) {
+                return Err("RAG grounding minimum score must be between 0 and 1".to_string());
+            }
+            if grounding.judge == GroundingJudgeKind::Nli && grounding.endpoint.is_none() {
+                return Err("RAG grounding with the nli judge requires an endpoint".to_string());
+            }
+        }
+        let retrieval_cache = &self.rag.retrieval_cache;
+        if retrieval_cache.enabled {
+            if !(retrieval_cache.similarity_threshold > 0.0
+                && retrieval_cache.similarity_threshold <= 1.0)
+            {
+                return Err(
+                    "RAG retrieval cache similarity threshold must be in (0, 1]".to_string()
+                );
+            }
+            if retrieval_cache.ttl_secs == 0 || retrieval_cache.max_entries == 0 {
+                return Err(
+                    "RAG retrieval cache TTL and maximum entries must be positive".to_string(),
+                );
+            }
+        }
 
         Ok(())
     }
@@ -0,0 +1,206 @@
+//! Embedded Mode
+//!
+//! Runs IntelliRouter in-process, as a library, for Rust applications that
+//! want its routing, caching and fallback without deploying the HTTP server.
+//! An embedded [`Router`] is built from the same [`Config`] as the server and
+//! hands each call to the same handlers, without binding a socket. It can
+//! also be mounted into an application's own axum server with
+//! [`Router::into_axum`].
+
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use thiserror::Error;
+use tower::ServiceExt;
+
+use crate::config::Config;
+use crate::modules::llm_proxy::dto::{ChatCompletionRequest, ChatCompletionResponse, ModelList};
+use crate::modules::llm_proxy::server::{create_router, AppState};
+use crate::modules::model_registry::{ModelAliases, ModelRegistry};
+use crate::modules::router_core::PolicyEngine;
+
+/// Error of a call to an embedded router
+#[derive(Debug, Error)]
+pub enum EmbeddedError {
+    /// The configuration could not be loaded or is invalid
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    /// The router rejected the call, as the server would with this status
+    #[error("Router error ({status}): {message}")]
+    Api {
+        /// HTTP status the server would answer with
+        status: StatusCode,
+        /// Error type, such as `invalid_request_error`
+        r#type: Option<String>,
+        /// Error message
+        message: String,
+    },
+
+    /// The router's answer could not be read
+    #[error("Invalid response: {0}")]
+    Response(String),
+}
+
+/// IntelliRouter running in-process
+#[derive(Debug, Clone)]
+pub struct Router {
+    state: AppState,
+    app: axum::Router,
+}
+
+impl Router {
+    /// Create a router from configuration
+    pub fn new(config: Config) -> Result<Self, EmbeddedError> {
+        let registry = Arc::new(
+            ModelRegistry::new().with_aliases(ModelAliases::from_config(&config.model_registry)),
+        );
+        Self::with_registry(config, registry)
+    }
+
+    /// Create a router from a configuration file
+    pub fn from_file(path: &str) -> Result<Self, EmbeddedError> {
+        let config = Config::from_file(path).map_err(EmbeddedError::Config)?;
+        config.validate().map_err(EmbeddedError::Config)?;
+        Self::new(config)
+    }
+
+    /// Create a router over a model registry the application manages, with
+    /// its own models and connectors registered
+    pub fn with_registry(
+        config: Config,
+        registry: Arc<ModelRegistry>,
+    ) -> Result<Self, EmbeddedError> {
+        let policies = Arc::new(match config.router.policy.clone() {
+            Some(policy) => PolicyEngine::with_policy(policy)
+                .map_err(|e| EmbeddedError::Config(format!("Invalid routing policy: {}", e)))?,
+            None => PolicyEngine::new(),
+        });
+        let state = AppState::from_config(&config, registry, policies, None);
+        let app = create_router(state.clone());
+        Ok(Self { state, app })
+    }
+
+    /// State shared by the router's handlers, such as its registry and logs
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Routes of the router, to mount into an axum server
+    pub fn into_axum(self) -> axum::Router {
+        self.app
+    }
+
+    /// Create a chat completion
+    pub async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, EmbeddedError> {
+        self.chat_completion_with_headers(request, HeaderMap::new())
+            .await
+    }
+
+    /// Create a chat completion with the headers of an HTTP call, such as the
+    /// API key selecting a tenant
+    pub async fn chat_completion_with_headers(
+        &self,
+        request: ChatCompletionRequest,
+        headers: HeaderMap,
+    ) -> Result<ChatCompletionResponse, EmbeddedError> {
+        let body =
+            serde_json::to_vec(&request).map_err(|e| EmbeddedError::Response(e.to_string()))?;
+        self.call(Method::POST, "/v1/chat/completions", headers, body)
+            .await
+    }
+
+    /// List the models available
+    pub async fn list_models(&self) -> Result<ModelList, EmbeddedError> {
+        self.call(Method::GET, "/v1/models", HeaderMap::new(), Vec::new())
+            .await
+    }
+
+    /// Hand a call to the router's handlers and read their answer
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<T, EmbeddedError> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(path)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .map_err(|e| EmbeddedError::Response(e.to_string()))?;
+        request.headers_mut().extend(headers);
+
+        // Routers never fail as services; errors are answered as responses
+        let Ok(response) = self.app.clone().oneshot(request).await;
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .map_err(|e| EmbeddedError::Response(e.to_string()))?;
+
+        if !status.is_success() {
+            return Err(api_error(status, &body));
+        }
+        serde_json::from_slice(&body).map_err(|e| EmbeddedError::Response(e.to_string()))
+    }
+}
+
+/// Error of a call the router rejected, from the OpenAI error body it answered
+/// with
+fn api_error(status: StatusCode, body: &[u8]) -> EmbeddedError {
+    let error = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body.get("error").cloned());
+    let field = |name: &str| {
+        error
+            .as_ref()
+            .and_then(|error| error.get(name))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+    };
+    EmbeddedError::Api {
+        status,
+        r#type: field("type"),
+        message: field("message").unwrap_or_else(|| String::from_utf8_lossy(body).into_owned()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str) -> ChatCompletionRequest {
+        let body = serde_json::json!({
+            "model": model,
+            "messages": [{"role": "user", "content": "Hello"}],
+        });
+        serde_json::from_str(&body.to_string()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chat_completion_in_process() {
+        let router = Router::new(Config::default()).unwrap();
+        let response = router.chat_completion(request("gpt-4o")).await.unwrap();
+        assert_eq!(response.object, "chat.completion");
+        assert_eq!(response.choices.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_request() {
+        let router = Router::new(Config::default()).unwrap();
+        let mut request = request("gpt-4o");
+        request.messages.clear();
+        match router.chat_completion(request).await {
+            Err(EmbeddedError::Api { status, .. }) => assert_eq!(status, StatusCode::BAD_REQUEST),
+            other => panic!("expected an API error, got {:?}", other),
+        }
+    }
+}
@@ -6,6 +6,7 @@
 // Core modules
 pub mod cli;
 pub mod config;
+pub mod embedded;
 pub mod modules;
 
 // Make test_utils available when the test-utils feature is enabled
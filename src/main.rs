@@ -2,27 +2,81 @@ use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use intellirouter::config::Config;
 // Import public interfaces only
-use intellirouter::modules::chain_engine::ChainEngine;
+use intellirouter::modules::audit::compliance::{self, ComplianceExporter};
+use intellirouter::modules::bundle::{api as bundle_api, Deployment};
+use intellirouter::modules::chain_engine::{
+    api as chain_api, checkpoint, dead_letter, webhooks, AgentRuntime, ChainEngine, ChainScheduler,
+    DeadLetterQueue,
+};
+use intellirouter::modules::common::{
+    with_compression, with_request_limits, with_resource_limits, with_traffic_classification,
+    RedisConnector, RequestCompression, ResourceMonitor, RoleListener, ShutdownCoordinator,
+};
+use intellirouter::modules::encryption::{
+    encryptor_from_config, migrate_redis_keys, EnvelopeEncryptor, MigrationReport,
+};
+use intellirouter::modules::erasure::{api as erasure_api, ErasureService};
 use intellirouter::modules::health::{
-    create_chain_engine_health_manager, create_persona_layer_health_manager,
-    create_rag_manager_health_manager, create_router_health_manager,
+    config_fingerprint, create_chain_engine_health_manager, create_persona_layer_health_manager,
+    create_rag_manager_health_manager, create_router_health_manager, CanaryProbe,
+    GrpcHealthService, HealthCheckManager, StartupChecks, VectorSearchProbe,
+};
+use intellirouter::modules::ipc::ServiceDirectory;
+use intellirouter::modules::llm_proxy::admin::AdminAuditLog;
+use intellirouter::modules::llm_proxy::payloads;
+use intellirouter::modules::llm_proxy::server::AppState;
+use intellirouter::modules::memory::{
+    self as memory, api as memory_api, InMemoryBackend, MemoryManager, OpenAIEmbedder,
+    RetentionPolicy, SemanticMemory,
 };
-use intellirouter::modules::memory::{InMemoryBackend, MemoryManager};
 use intellirouter::modules::model_registry::api::ModelRegistryApi;
+use intellirouter::modules::model_registry::connectors::{ConnectorConfig, OpenAIConnector};
 use intellirouter::modules::model_registry::storage::ModelRegistry;
+use intellirouter::modules::model_registry::{
+    create_embedded_persistent_registry, install_tokenizers, ModelAliases, TokenizerRegistry,
+};
 use intellirouter::modules::persona_layer::manager::PersonaManager;
+use intellirouter::modules::persona_layer::{
+    api as persona_api, load_personas_dir, PersonaDirectory,
+};
+use intellirouter::modules::rag_manager::chunking::eval as chunking_eval;
+use intellirouter::modules::rag_manager::injection::InjectionGuard;
 use intellirouter::modules::rag_manager::manager::RagManager;
+use intellirouter::modules::rag_manager::{
+    ChunkerRegistry, GroundingChecker, RagError, RetrievalCache,
+};
+use intellirouter::modules::remote::client::path_segment;
+use intellirouter::modules::remote::eval::{self, EvalRunner, EvalVariant};
+use intellirouter::modules::remote::replay::{self, ReplayTarget};
+use intellirouter::modules::remote::support as support_bundle;
+use intellirouter::modules::remote::{Context, ContextStore, RemoteClient, RemoteError};
 use intellirouter::modules::router_core::router::RouterImpl;
+use intellirouter::modules::router_core::{
+    LanguageSteering, PersonaPreferences, PluginChain, PluginRegistry, PolicyEngine,
+    ResidencyEnforcer,
+};
+use intellirouter::modules::support::{api as support_api, SupportService};
+use intellirouter::modules::telemetry::dashboard::{generate_dashboard, DashboardOptions};
+use intellirouter::modules::telemetry::export::ClickHouseSink;
 use intellirouter::modules::telemetry::telemetry::TelemetryManager;
-use tracing::{error, info};
+use intellirouter::modules::telemetry::CostCalculator;
+use intellirouter::modules::tools::{routes as tool_routes, ToolRegistry};
+use reqwest::Method;
+use serde_json::{json, Value};
+use tracing::{error, info, warn};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
+    /// Remote context to manage, instead of the current one
+    #[arg(long, global = true)]
+    context: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -42,6 +96,11 @@ enum Commands {
         /// Environment (development, production)
         #[arg(short, long, default_value = "development")]
         env: String,
+
+        /// Wait for unavailable dependencies, retrying with backoff, instead
+        /// of failing at startup
+        #[arg(long)]
+        wait_for_deps: bool,
     },
     /// Generate a default configuration file
     GenerateConfig {
@@ -53,6 +112,366 @@ enum Commands {
         #[arg(short, long, default_value = "development")]
         env: String,
     },
+    /// Generate a Grafana dashboard for the metrics the router exposes
+    GenerateDashboard {
+        /// Output file path (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Dashboard title
+        #[arg(long, default_value = "IntelliRouter")]
+        title: String,
+
+        /// UID of the Prometheus data source (defaults to a dashboard variable)
+        #[arg(long)]
+        datasource: Option<String>,
+    },
+    /// Encrypt plaintext conversations and checkpoints stored in Redis and
+    /// captured payloads, and rewrap data keys after a master key rotation
+    MigrateEncryption {
+        /// Configuration file path
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+
+        /// Environment (development, production)
+        #[arg(short, long, default_value = "development")]
+        env: String,
+
+        /// Report what would change without writing
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Manage the remote contexts (deployment endpoints and credentials)
+    Ctx {
+        #[command(subcommand)]
+        command: CtxCommand,
+    },
+    /// Manage the models of a remote deployment
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommand,
+    },
+    /// Manage the admin and tenant keys of a remote deployment
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Inspect the personas of a remote deployment
+    Personas {
+        #[command(subcommand)]
+        command: PersonasCommand,
+    },
+    /// Manage the chain executions, schedules and dead letters of a remote
+    /// deployment
+    Chains {
+        #[command(subcommand)]
+        command: ChainsCommand,
+    },
+    /// Export the configuration of a remote deployment as a signed bundle
+    ExportBundle {
+        /// Output file path (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import a signed bundle into a remote deployment
+    ImportBundle {
+        /// Bundle file
+        file: PathBuf,
+
+        /// Only show the differences with the deployment
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Re-execute a request captured by a remote deployment and diff its
+    /// output against the original
+    Replay {
+        /// Request ID, the ID of the request's routing decision
+        request_id: String,
+
+        /// Read the payload record from this file instead of the deployment
+        #[arg(long)]
+        file: Option<PathBuf>,
+
+        /// Configuration whose encryption keys open payload records
+        /// encrypted at rest
+        #[arg(long)]
+        config: Option<PathBuf>,
+
+        /// Replay against this model through the deployment instead of the
+        /// built-in mock
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a corpus of saved requests against a baseline and a candidate
+    /// configuration through a remote deployment and report the differences
+    Eval(EvalArgs),
+    /// Compare the RAG chunkers on a corpus of documents and of questions
+    /// answered by passages of the documents
+    EvalChunking(EvalChunkingArgs),
+    /// Collect the redacted configuration, recent errors, diagnostics and
+    /// runtime stats of a remote deployment into a tarball for a bug report
+    SupportBundle {
+        /// Output file path (defaults to intellirouter-support-<time>.tar.gz)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// URL of the deployment's Prometheus metrics to include, e.g.
+        /// http://router:9091/metrics
+        #[arg(long)]
+        metrics_url: Option<String>,
+    },
+    /// Load test a remote deployment by replaying a window of captured
+    /// traffic at its recorded pace
+    #[cfg(feature = "test-harness")]
+    LoadReplay(LoadReplayArgs),
+}
+
+#[cfg(feature = "test-harness")]
+#[derive(Args)]
+struct LoadReplayArgs {
+    /// Payload capture directory holding the captured requests
+    dir: PathBuf,
+
+    /// Only requests that arrived at or after this RFC 3339 time
+    #[arg(long)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Only requests that arrived before this RFC 3339 time
+    #[arg(long)]
+    until: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Speed multiplier of the replay; 2 sends the window in half its time
+    #[arg(long, default_value_t = 1.0)]
+    speed: f64,
+
+    /// Send every request to this model instead of the one it asked for
+    #[arg(long)]
+    model: Option<String>,
+
+    /// Mask the letters and digits of message text before sending it
+    #[arg(long)]
+    mask_content: bool,
+
+    /// Configuration whose encryption keys open payload records
+    /// encrypted at rest
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Seconds after which a request counts as failed
+    #[arg(long, default_value_t = 60)]
+    timeout_secs: u64,
+
+    /// Print the summary as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct EvalArgs {
+    /// JSONL file of eval cases, JSON file with an array of cases, or
+    /// directory of captured payload records
+    corpus: PathBuf,
+
+    /// Configuration whose encryption keys open payload records
+    /// encrypted at rest
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Model of the baseline, instead of the model of each case
+    #[arg(long)]
+    baseline_model: Option<String>,
+
+    /// Prompt version of the baseline, as `<prompt>@<version>`
+    #[arg(long)]
+    baseline_prompt: Option<String>,
+
+    /// Model of the candidate, instead of the model of each case
+    #[arg(long)]
+    candidate_model: Option<String>,
+
+    /// Prompt version of the candidate, as `<prompt>@<version>`
+    #[arg(long)]
+    candidate_prompt: Option<String>,
+
+    /// Model asked which output is better when the outputs differ
+    #[arg(long)]
+    judge: Option<String>,
+
+    /// Similarity at or above which outputs count as unchanged
+    #[arg(long, default_value_t = 0.9)]
+    min_similarity: f64,
+
+    /// Number of cases run at the same time
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+
+    /// Also write the markdown report to this file
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct EvalChunkingArgs {
+    /// Document, or directory of documents, to chunk
+    corpus: PathBuf,
+
+    /// JSONL file of questions, or JSON file with an array of questions,
+    /// each with the passage of the corpus answering it
+    #[arg(long)]
+    questions: PathBuf,
+
+    /// Chunkers to compare (defaults to every chunker)
+    #[arg(long, value_delimiter = ',')]
+    chunkers: Vec<String>,
+
+    /// Number of chunks retrieved per question
+    #[arg(short, default_value_t = 5)]
+    k: usize,
+
+    /// Configuration file path, for the chunk size and embedding model
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Environment (development, production)
+    #[arg(short, long, default_value = "development")]
+    env: String,
+
+    /// Also write the markdown report to this file
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum CtxCommand {
+    /// List the contexts
+    List,
+    /// Show the current context
+    Current,
+    /// Make a context the current one
+    Use {
+        /// Context name
+        name: String,
+    },
+    /// Add or update a context
+    Set {
+        /// Context name
+        name: String,
+
+        /// Base URL of the deployment's HTTP API
+        #[arg(long)]
+        endpoint: String,
+
+        /// Admin API key, stored in the contexts file
+        #[arg(long, conflicts_with = "api_key_env")]
+        api_key: Option<String>,
+
+        /// Environment variable to read the admin API key from
+        #[arg(long)]
+        api_key_env: Option<String>,
+    },
+    /// Delete a context
+    Delete {
+        /// Context name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommand {
+    /// List the registered models
+    List,
+    /// Register a model from a JSON metadata file
+    Register {
+        /// Model metadata file
+        file: PathBuf,
+    },
+    /// Remove a model
+    Remove {
+        /// Model ID
+        id: String,
+    },
+    /// Set the status of a model
+    Status {
+        /// Model ID
+        id: String,
+
+        /// New status (Available, Unavailable, Limited, Maintenance, Deprecated)
+        status: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// List the admin and tenant keys, masked
+    List,
+}
+
+#[derive(Subcommand)]
+enum PersonasCommand {
+    /// List the personas
+    List,
+    /// Show a persona
+    Get {
+        /// Persona ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ChainsCommand {
+    /// Show the status of a chain execution
+    Execution {
+        /// Execution ID
+        id: String,
+    },
+    /// Resume an interrupted chain execution
+    Resume {
+        /// Execution ID
+        id: String,
+    },
+    /// Cancel a chain execution
+    Cancel {
+        /// Execution ID
+        id: String,
+    },
+    /// List the chain schedules
+    Schedules,
+    /// Show the run history of a schedule
+    History {
+        /// Schedule ID
+        id: String,
+    },
+    /// Run a schedule now
+    Trigger {
+        /// Schedule ID
+        id: String,
+    },
+    /// List the dead-lettered chain steps and deliveries
+    DeadLetters,
+    /// Redeliver a dead-lettered chain delivery
+    Redeliver {
+        /// Dead letter ID
+        id: String,
+    },
+    /// Retry a dead-lettered chain step or delivery
+    Retry {
+        /// Dead letter ID
+        id: String,
+    },
+    /// Remove all dead-lettered chain steps and deliveries
+    PurgeDeadLetters,
 }
 
 #[derive(Clone, Debug)]
@@ -65,6 +484,20 @@ enum Role {
     Audit,
 }
 
+impl Role {
+    /// Name of the role in metrics
+    fn name(&self) -> &'static str {
+        match self {
+            Role::Router => "router",
+            Role::Orchestrator => "orchestrator",
+            Role::RagInjector => "rag-injector",
+            Role::Summarizer => "summarizer",
+            Role::All => "all",
+            Role::Audit => "audit",
+        }
+    }
+}
+
 impl FromStr for Role {
     type Err = String;
 
@@ -83,10 +516,6 @@ impl FromStr for Role {
 
 #[tokio::main]
 async fn main() {
-    // Initialize telemetry
-    // Set up basic logging
-    TelemetryManager::setup_logging().expect("Failed to set up logging");
-
     // Create shutdown coordinator for graceful shutdown
     let shutdown_coordinator =
         Arc::new(intellirouter::modules::common::ShutdownCoordinator::new(1));
@@ -111,8 +540,18 @@ async fn main() {
 
     let cli = Cli::parse();
 
+    // The run command sets up logging once its configuration is loaded
+    if !matches!(cli.command, Commands::Run { .. }) {
+        TelemetryManager::setup_logging().expect("Failed to set up logging");
+    }
+
     match cli.command {
-        Commands::Run { role, config, env } => {
+        Commands::Run {
+            role,
+            config,
+            env,
+            wait_for_deps,
+        } => {
             // Load configuration
             let config_path = config.unwrap_or_else(|| {
                 let mut path = PathBuf::from("config");
@@ -123,6 +562,16 @@ async fn main() {
             println!("Loading configuration from {:?}", config_path);
             let config = Config::from_file(config_path.to_str().unwrap())
                 .expect("Failed to load configuration");
+            TelemetryManager::setup_logging_with_config(&config.telemetry)
+                .expect("Failed to set up logging");
+
+            // Count tokens with the tokenizers assigned to each model family
+            match TokenizerRegistry::from_config(&config.model_registry.tokenizers) {
+                Ok(tokenizers) => {
+                    install_tokenizers(tokenizers);
+                }
+                Err(e) => error!("Configured tokenizers disabled: {}", e),
+            }
 
             // Initialize telemetry with configuration
             let telemetry = Arc::new(TelemetryManager::new(
@@ -131,6 +580,29 @@ async fn main() {
                 env!("CARGO_PKG_VERSION").to_string(),
             ));
 
+            // Sample the process's resources, shedding batch requests while
+            // they are over their soft limits
+            let resources = Arc::new(ResourceMonitor::new(
+                role.name(),
+                &config.telemetry.resources,
+            ));
+            resources.spawn_sampling();
+            let fingerprint = config_fingerprint(&config);
+
+            // Verify the role's dependencies before serving, failing with what
+            // to fix or waiting for them
+            if config.startup.check_dependencies {
+                let checks = StartupChecks::from_config(&config, role.name());
+                let wait = wait_for_deps || config.startup.wait_for_dependencies;
+                if let Err(e) = checks.verify(wait).await {
+                    eprintln!("Error: {}", e);
+                    for failure in e.failures() {
+                        eprintln!("  {}", failure);
+                    }
+                    std::process::exit(1);
+                }
+            }
+
             // Run the appropriate role
             match role {
                 Role::Router => {
@@ -145,72 +617,227 @@ async fn main() {
                     let router_config =
                         intellirouter::modules::router_core::config::RouterConfig::default();
 
-                    // Create model registry
-                    let model_registry = Arc::new(ModelRegistry::new());
+                    // Create model registry, resolving the configured aliases and routes.
+                    // The embedded storage profile keeps its models in the SQLite file.
+                    let aliases = ModelAliases::from_config(&config.model_registry);
+                    let persistent_registry =
+                        create_embedded_persistent_registry(&config.storage, aliases.clone())
+                            .expect("Failed to load the persisted model registry");
+                    let model_registry = match &persistent_registry {
+                        Some(persistent_registry) => persistent_registry.registry(),
+                        None => Arc::new(ModelRegistry::new().with_aliases(aliases)),
+                    };
+
+                    // Create routing policy engine
+                    let policy_engine = Arc::new(match config.router.policy.clone() {
+                        Some(policy) => PolicyEngine::with_policy(policy)
+                            .expect("Failed to compile routing policy"),
+                        None => PolicyEngine::new(),
+                    });
+
+                    // Create data-residency enforcer from tenant region constraints
+                    let residency = Arc::new(config.proxy.tenants.iter().fold(
+                        ResidencyEnforcer::new(),
+                        |enforcer, tenant| {
+                            enforcer.with_tenant(tenant.id.clone(), tenant.allowed_regions.clone())
+                        },
+                    ));
+
+                    // Collect the model preferences of the configured personas
+                    let personas = if config.persona_layer.enabled {
+                        load_personas_dir(&config.persona_layer.personas_dir).unwrap_or_else(|e| {
+                            warn!(
+                                "Failed to load personas from {}: {}",
+                                config.persona_layer.personas_dir, e
+                            );
+                            Vec::new()
+                        })
+                    } else {
+                        Vec::new()
+                    };
+                    let persona_preferences =
+                        Arc::new(PersonaPreferences::from_personas(&personas));
+                    let persona_directory = Arc::new(PersonaDirectory::new(
+                        &config.persona_layer.personas_dir,
+                        personas,
+                    ));
+
+                    // Hook the configured plugins into routing
+                    let plugins = Arc::new(
+                        PluginChain::from_config(&PluginRegistry::new(), &config.router.plugins)
+                            .expect("Failed to load router plugins"),
+                    );
+                    if !plugins.is_empty() {
+                        info!("Router plugins: {:?}", plugins.names());
+                    }
 
                     // Create router
                     let _router = RouterImpl::new(router_config.clone(), model_registry.clone())
-                        .expect("Failed to create router");
-
-                    // Create memory backend
-                    let memory_backend = Arc::new(InMemoryBackend::new());
+                        .expect("Failed to create router")
+                        .with_policy_engine(policy_engine.clone())
+                        .with_residency(residency)
+                        .with_personas(persona_preferences)
+                        .with_language_steering(Arc::new(LanguageSteering::new(
+                            config.router.language.clone(),
+                        )))
+                        .with_plugins(plugins);
+
+                    // Conversations hold the partial completions of
+                    // interrupted streams, encrypted at rest if configured
+                    let encryptor = encryptor_from_config(&config.encryption)
+                        .expect("Failed to initialize encryption at rest");
+                    let memory_backend =
+                        memory::backend_from_config(&config.memory, &config.storage, encryptor)
+                            .expect("Failed to create memory backend");
 
                     // Create memory manager with default window size
-                    let _memory_manager = MemoryManager::new(memory_backend, 100);
+                    let memory_manager = Arc::new(MemoryManager::new(memory_backend, 100));
 
                     // Create chain engine
                     let _chain_engine = ChainEngine::new();
 
                     // Create app with telemetry and LLM proxy routes
-                    let app_state = intellirouter::modules::llm_proxy::server::AppState {
-                        provider: intellirouter::modules::llm_proxy::Provider::OpenAI,
-                        config:
-                            intellirouter::modules::llm_proxy::server::ServerConfig::from_config(
-                                &config,
-                            ),
-                        shared: Arc::new(tokio::sync::Mutex::new(
-                            intellirouter::modules::llm_proxy::server::SharedState::new(),
-                        )),
-                        telemetry: Some(telemetry.clone()),
-                        cost_calculator: Some(Arc::new(
-                            intellirouter::modules::telemetry::CostCalculator::new(),
-                        )),
-                    };
+                    let app_state = AppState::from_config(
+                        &config,
+                        model_registry.clone(),
+                        policy_engine.clone(),
+                        Some(telemetry.clone()),
+                    )
+                    .with_conversation_memory(memory_manager);
+                    let admin_audit = app_state.admin_audit.clone();
+
+                    // Erase data subjects from the logs this role keeps
+                    let mut erasure = ErasureService::new(&config.proxy.erasure)
+                        .with_target(app_state.decisions.clone())
+                        .with_target(app_state.prompts.clone())
+                        .with_target(app_state.prompt_traces.clone())
+                        .with_target(app_state.user_usage.clone())
+                        .with_target(app_state.payloads.clone())
+                        .with_target(admin_audit.clone());
+                    if let Some(cost_calculator) = &app_state.cost_calculator {
+                        erasure = erasure.with_target(cost_calculator.clone());
+                    }
+                    let export = &config.telemetry.export;
+                    if let Some(clickhouse) = export.clickhouse.as_ref().filter(|_| export.enabled)
+                    {
+                        erasure =
+                            erasure.with_target(Arc::new(ClickHouseSink::new(clickhouse.clone())));
+                    }
+                    let erasure = Arc::new(erasure);
+                    let deployment = Arc::new(Deployment::new(
+                        model_registry.clone(),
+                        policy_engine.clone(),
+                        persona_directory.clone(),
+                        &config,
+                    ));
 
                     // Create health check manager
-                    let redis_url = config.memory.redis_url.clone();
-                    let health_manager = create_router_health_manager(
+                    let redis = RedisConnector::from_config(&config.memory)
+                        .expect("Invalid Redis configuration");
+                    let mut health_manager = create_router_health_manager(
                         model_registry.clone(),
                         router_config.clone(),
-                        redis_url,
+                        redis,
+                    );
+                    // Failing canaries degrade the router's readiness
+                    if let Some(canaries) = &app_state.canaries {
+                        health_manager.add_dependency_checker(canaries.clone());
+                        health_manager.add_probe(Arc::new(CanaryProbe::new(canaries.clone())));
+                    }
+                    health_manager.set_resource_monitor(resources.clone());
+                    health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &health_manager,
+                        config.server.port,
+                        &shutdown_coordinator,
                     );
                     let health_router = health_manager.create_router();
 
+                    // Report the state of the coalescer, resumable streams,
+                    // canaries and models in support bundles
+                    let coalescer = app_state.coalescer.clone();
+                    let streams = app_state.streams.clone();
+                    let canaries = app_state.canaries.clone();
+                    let registry = model_registry.clone();
+                    let support = SupportService::new(&config)
+                        .with_stats(
+                            "coalescing",
+                            move || json!({ "in_flight": coalescer.in_flight() }),
+                        )
+                        .with_stats(
+                            "stream_buffer",
+                            move || json!({ "buffered_streams": streams.len() }),
+                        )
+                        .with_stats("canaries", move || {
+                            let statuses = canaries
+                                .as_ref()
+                                .map(|canaries| canaries.statuses())
+                                .unwrap_or_default();
+                            json!({
+                                "total": statuses.len(),
+                                "failing": statuses.iter().filter(|status| status.failing).count(),
+                            })
+                        })
+                        .with_stats("models", move || {
+                            let mut statuses = std::collections::BTreeMap::<String, usize>::new();
+                            for model in registry.list_models() {
+                                *statuses.entry(model.status.to_string()).or_default() += 1;
+                            }
+                            json!({ "by_status": statuses })
+                        });
+
                     // Create router with routes
                     let app = intellirouter::modules::llm_proxy::server::create_router(app_state)
-                        .merge(health_router);
+                        .merge(health_router)
+                        .merge(persona_api::create_persona_router(
+                            persona_directory,
+                            config.proxy.clone(),
+                        ))
+                        .merge(bundle_api::create_bundle_router(
+                            deployment,
+                            config.proxy.clone(),
+                            admin_audit.clone(),
+                        ))
+                        .merge(erasure_api::create_erasure_router(
+                            erasure,
+                            config.proxy.clone(),
+                            admin_audit,
+                        ))
+                        .merge(support_api::create_support_router(
+                            Arc::new(support),
+                            config.proxy.clone(),
+                        ));
 
                     // Start server
                     let addr = config.server.socket_addr();
-                    println!("Router listening on {}", addr);
-
-                    // Create TCP listener
-                    let listener = tokio::net::TcpListener::bind(&addr)
-                        .await
-                        .expect("Failed to bind to address");
+                    let listener =
+                        RoleListener::bind(config.server.listeners.router.as_ref(), addr)
+                            .await
+                            .expect("Failed to bind listener");
+                    println!("Router listening on {}", listener);
 
                     println!("Health check endpoints available at:");
                     println!("  - /health");
                     println!("  - /readiness");
                     println!("  - /diagnostics");
+                    println!("Chain execution endpoints available at:");
+                    println!("  - /v1/chains/executions/{{id}}");
+                    println!("  - /v1/chains/schedules");
+                    println!("  - /v1/chains/webhooks/{{id}}");
 
                     // Create graceful shutdown future
                     let mut shutdown_rx = shutdown_coordinator.subscribe();
                     let completion_tx = shutdown_coordinator.completion_sender();
 
                     // Start server with graceful shutdown
-                    let server = axum::serve(listener, app);
-                    let graceful = server.with_graceful_shutdown(async move {
+                    let app = with_compression(app, &config.server.compression);
+                    let app = with_request_limits(app, &config.server.limits);
+                    let app = with_resource_limits(app, resources.clone());
+                    let app = with_traffic_classification(app, &config.server.synthetic_traffic);
+                    let header_read_timeout =
+                        Duration::from_secs(config.server.limits.header_read_timeout_secs);
+                    let graceful = listener.serve(app, header_read_timeout, async move {
                         if let Ok(signal) = shutdown_rx.recv().await {
                             info!("Router received shutdown signal: {:?}", signal);
                         }
@@ -236,11 +863,38 @@ async fn main() {
                     // Create model registry API
                     let _model_registry_api = Arc::new(ModelRegistryApi::new());
 
+                    // Stored conversations and checkpoints are encrypted at rest if configured
+                    let encryptor = encryptor_from_config(&config.encryption)
+                        .expect("Failed to initialize encryption at rest");
+
                     // Create memory backend
-                    let memory_backend = Arc::new(InMemoryBackend::new());
+                    let memory_backend = memory::backend_from_config(
+                        &config.memory,
+                        &config.storage,
+                        encryptor.clone(),
+                    )
+                    .expect("Failed to create memory backend");
 
                     // Create memory manager with default window size
-                    let _memory_manager = MemoryManager::new(memory_backend, 100);
+                    let memory_manager =
+                        Arc::new(MemoryManager::new(memory_backend, 100).with_retention(
+                            RetentionPolicy::from_config(&config.memory, &config.proxy.tenants),
+                        ));
+
+                    // Periodically delete namespaced memories past their retention
+                    let sweep_interval = std::time::Duration::from_secs(
+                        config.memory.retention_sweep_interval_secs.max(1),
+                    );
+                    let sweep_manager = memory_manager.clone();
+                    tokio::spawn(async move {
+                        let mut interval = tokio::time::interval(sweep_interval);
+                        loop {
+                            interval.tick().await;
+                            if let Err(e) = sweep_manager.purge_expired().await {
+                                error!("Failed to purge expired memories: {}", e);
+                            }
+                        }
+                    });
 
                     // Create rag manager
                     let _rag_manager = RagManager::new();
@@ -248,35 +902,226 @@ async fn main() {
                     // Create persona layer manager
                     let _persona_manager = PersonaManager::new();
 
-                    // Create chain engine
-                    let chain_engine = Arc::new(ChainEngine::new());
+                    // Create chain engine, with durable executions if configured
+                    let mut chain_engine = ChainEngine::new();
+                    let checkpoint_config = &config.chain_engine.checkpoint;
+                    let redis = RedisConnector::from_config(&config.memory)
+                        .expect("Invalid Redis configuration");
+                    match checkpoint::store_from_config(
+                        checkpoint_config,
+                        redis.as_ref(),
+                        encryptor.clone(),
+                    ) {
+                        Ok(Some(store)) => {
+                            chain_engine = chain_engine.with_checkpoint_store(store);
+                        }
+                        Ok(None) => {}
+                        Err(e) => error!("Chain checkpointing disabled: {}", e),
+                    }
+
+                    // Keep failed steps and webhook deliveries for inspection and retry
+                    let dead_letters = Arc::new(
+                        dead_letter::queue_from_config(
+                            &config.chain_engine.dead_letters,
+                            redis.as_ref(),
+                            encryptor.clone(),
+                        )
+                        .unwrap_or_else(|e| {
+                            error!("Chain dead letters kept in memory: {}", e);
+                            DeadLetterQueue::in_memory(config.chain_engine.dead_letters.capacity)
+                        }),
+                    );
+                    // Model calls of steps are priced against chain budgets
+                    // with the router's prices
+                    let cost_calculator = Arc::new(
+                        CostCalculator::from_config(&config.telemetry.pricing)
+                            .expect("Invalid pricing configuration"),
+                    );
+                    chain_engine = chain_engine
+                        .with_dead_letters(dead_letters.clone())
+                        .with_default_budget(config.chain_engine.budget.clone())
+                        .with_cost_calculator(cost_calculator.clone());
+
+                    // Register built-in and MCP tools for tool use steps
+                    let tool_registry = Arc::new(ToolRegistry::from_config(&config.tools).await);
+                    info!("Registered tools: {:?}", tool_registry.names());
+
+                    // Agents call models through the router unless configured otherwise
+                    let agent_config = &config.chain_engine.agents;
+                    let model_endpoint = agent_config.model_endpoint.clone().unwrap_or_else(|| {
+                        format!("http://{}:{}", config.server.host, config.server.port)
+                    });
+                    let agent_connector = Arc::new(
+                        OpenAIConnector::with_pool(
+                            ConnectorConfig {
+                                base_url: model_endpoint,
+                                api_key: agent_config.api_key.clone(),
+                                ..ConnectorConfig::default()
+                            },
+                            config.proxy.outbound_http.for_provider("openai"),
+                        )
+                        .with_request_compression(
+                            RequestCompression::from_config(&config.server.compression),
+                        ),
+                    );
+                    let agent_runtime = Arc::new(
+                        AgentRuntime::new(agent_connector, tool_registry.clone())
+                            .with_memory(memory_manager.clone())
+                            .with_cost_calculator(cost_calculator)
+                            .with_run_history(agent_config.run_history),
+                    );
+
+                    // Long-term memory extracts and embeds facts through the router by default
+                    let semantic_memory = match SemanticMemory::from_config(
+                        &config.memory.semantic,
+                        &config.rag,
+                        &format!("http://{}:{}", config.server.host, config.server.port),
+                    ) {
+                        Ok(semantic_memory) => semantic_memory.map(Arc::new),
+                        Err(e) => {
+                            error!("Semantic memory disabled: {}", e);
+                            None
+                        }
+                    };
+                    let chain_engine = Arc::new(
+                        chain_engine
+                            .with_tool_registry(tool_registry.clone())
+                            .with_agent_runtime(agent_runtime.clone()),
+                    );
+
+                    // Resume executions interrupted by a previous shutdown or crash
+                    if checkpoint_config.enabled && checkpoint_config.resume_on_startup {
+                        let engine = chain_engine.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = engine.resume_interrupted().await {
+                                error!("Failed to resume interrupted chain executions: {}", e);
+                            }
+                        });
+                    }
 
                     // Create health check manager
-                    let redis_url = config.memory.redis_url.clone();
                     let router_endpoint = Some(format!(
                         "http://{}:{}",
                         config.server.host, config.server.port
                     ));
-                    let health_manager = create_chain_engine_health_manager(
+                    let mut health_manager = create_chain_engine_health_manager(
                         chain_engine.clone(),
-                        redis_url,
+                        redis,
                         router_endpoint,
                     );
+                    health_manager.set_resource_monitor(resources.clone());
+                    health_manager.set_config_fingerprint(fingerprint.clone());
+                    // Search the store of long-term memories when diagnosing
+                    if let Some(semantic_memory) = &semantic_memory {
+                        health_manager.add_probe(Arc::new(VectorSearchProbe::new(
+                            semantic_memory.store().clone(),
+                        )));
+                    }
+                    spawn_grpc_health(
+                        &config,
+                        &health_manager,
+                        config.server.port + 1,
+                        &shutdown_coordinator,
+                    );
                     let health_router = health_manager.create_router();
 
+                    // Erase data subjects from conversations and long-term memories
+                    let admin_audit = Arc::new(AdminAuditLog::from_config(&config));
+                    let mut erasure = ErasureService::new(&config.proxy.erasure)
+                        .with_target(memory_manager.clone());
+                    if let Some(semantic_memory) = &semantic_memory {
+                        erasure = erasure.with_target(semantic_memory.clone());
+                    }
+                    let erasure = erasure.with_target(admin_audit.clone());
+
                     // Create app with telemetry and health routes
                     let app = axum::Router::new()
                         .with_state(telemetry.clone())
-                        .merge(health_router);
+                        .merge(health_router)
+                        .merge(chain_api::create_router(
+                            chain_engine.clone(),
+                            config.proxy.clone(),
+                        ))
+                        .merge(tool_routes::create_router(
+                            tool_registry,
+                            config.proxy.clone(),
+                        ))
+                        .merge(chain_api::create_agent_router(
+                            agent_runtime,
+                            config.chain_engine.agents.tools.clone(),
+                            config.proxy.clone(),
+                        ))
+                        .merge(memory_api::create_memory_router(
+                            memory_manager,
+                            semantic_memory,
+                            config.proxy.clone(),
+                            admin_audit.clone(),
+                        ))
+                        .merge(erasure_api::create_erasure_router(
+                            Arc::new(erasure),
+                            config.proxy.clone(),
+                            admin_audit,
+                        ))
+                        .merge(support_api::create_support_router(
+                            Arc::new(SupportService::new(&config)),
+                            config.proxy.clone(),
+                        ));
+
+                    // Start scheduled chain executions
+                    let app = match ChainScheduler::from_config(
+                        chain_engine.clone(),
+                        &config.chain_engine,
+                    ) {
+                        Ok(scheduler) => {
+                            let scheduler = Arc::new(scheduler);
+                            scheduler.start();
+                            app.merge(chain_api::create_schedule_router(
+                                scheduler,
+                                config.proxy.clone(),
+                            ))
+                        }
+                        Err(e) => {
+                            error!("Chain schedules disabled: {}", e);
+                            app
+                        }
+                    };
+
+                    // Accept inbound webhooks and deliver lifecycle events
+                    let webhook_config = &config.chain_engine.webhooks;
+                    let app = match webhooks::WebhookTriggers::from_config(
+                        chain_engine.clone(),
+                        webhook_config,
+                    ) {
+                        Ok(triggers) => {
+                            app.merge(chain_api::create_webhook_router(Arc::new(triggers)))
+                        }
+                        Err(e) => {
+                            error!("Chain webhook triggers disabled: {}", e);
+                            app
+                        }
+                    };
+                    let dispatcher = Arc::new(
+                        webhooks::WebhookDispatcher::new(webhook_config.clone())
+                            .with_dead_letters(dead_letters.clone()),
+                    );
+                    if !webhook_config.outbound.is_empty() {
+                        dispatcher.start(&chain_engine);
+                    }
+                    dead_letters.record_depth().await;
+                    let app = app.merge(chain_api::create_dead_letter_router(
+                        dead_letters,
+                        chain_engine.clone(),
+                        dispatcher,
+                        config.proxy.clone(),
+                    ));
 
                     // Start server
                     let addr = SocketAddr::new(config.server.host, config.server.port + 1);
-                    println!("Chain Engine listening on {}", addr);
-
-                    // Create TCP listener
-                    let listener = tokio::net::TcpListener::bind(&addr)
-                        .await
-                        .expect("Failed to bind to address");
+                    let listener =
+                        RoleListener::bind(config.server.listeners.orchestrator.as_ref(), addr)
+                            .await
+                            .expect("Failed to bind listener");
+                    println!("Chain Engine listening on {}", listener);
 
                     println!("Health check endpoints available at:");
                     println!("  - /health");
@@ -288,8 +1133,13 @@ async fn main() {
                     let completion_tx = shutdown_coordinator.completion_sender();
 
                     // Start server with graceful shutdown
-                    let server = axum::serve(listener, app);
-                    let graceful = server.with_graceful_shutdown(async move {
+                    let app = with_compression(app, &config.server.compression);
+                    let app = with_request_limits(app, &config.server.limits);
+                    let app = with_resource_limits(app, resources.clone());
+                    let app = with_traffic_classification(app, &config.server.synthetic_traffic);
+                    let header_read_timeout =
+                        Duration::from_secs(config.server.limits.header_read_timeout_secs);
+                    let graceful = listener.serve(app, header_read_timeout, async move {
                         if let Ok(signal) = shutdown_rx.recv().await {
                             info!("Chain Engine received shutdown signal: {:?}", signal);
                         }
@@ -321,37 +1171,77 @@ async fn main() {
                     // Create memory manager with default window size
                     let _memory_manager = MemoryManager::new(memory_backend, 100);
 
-                    // Create RAG manager
-                    let rag_manager = Arc::new(RagManager::new());
+                    // Create RAG manager, embedding queries and sentences and
+                    // judging answers through the router by default
+                    let router_endpoint =
+                        format!("http://{}:{}", config.server.host, config.server.port);
+                    let mut rag_manager = RagManager::new()
+                        .with_injection_guard(
+                            InjectionGuard::new(config.rag.injection.clone())
+                                .expect("Invalid RAG injection patterns"),
+                        )
+                        .with_chunkers(
+                            ChunkerRegistry::from_config(&config.rag, &router_endpoint)
+                                .expect("Invalid RAG chunking configuration"),
+                        );
+                    if let Some(cache) = RetrievalCache::from_config(&config.rag, &router_endpoint)
+                    {
+                        rag_manager = rag_manager.with_retrieval_cache(cache);
+                    }
+                    if let Some(checker) =
+                        GroundingChecker::from_config(&config.rag, &router_endpoint)
+                    {
+                        rag_manager = rag_manager.with_grounding_checker(checker);
+                    }
+                    let rag_manager = Arc::new(rag_manager);
+
+                    // Erase data subjects from cached retrieval results
+                    let admin_audit = Arc::new(AdminAuditLog::from_config(&config));
+                    let erasure = ErasureService::new(&config.proxy.erasure)
+                        .with_target(rag_manager.clone())
+                        .with_target(admin_audit.clone());
 
                     // Create health check manager
-                    let redis_url = config.memory.redis_url.clone();
+                    let redis = RedisConnector::from_config(&config.memory)
+                        .expect("Invalid Redis configuration");
                     let router_endpoint = Some(format!(
                         "http://{}:{}",
                         config.server.host, config.server.port
                     ));
                     let vector_db_url = config.rag.vector_db_url.clone();
-                    let health_manager = create_rag_manager_health_manager(
+                    let mut health_manager = create_rag_manager_health_manager(
                         rag_manager.clone(),
-                        redis_url,
+                        redis,
                         router_endpoint,
                         vector_db_url,
                     );
+                    health_manager.set_resource_monitor(resources.clone());
+                    health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &health_manager,
+                        config.server.port + 2,
+                        &shutdown_coordinator,
+                    );
                     let health_router = health_manager.create_router();
 
                     // Create app with telemetry and health routes
                     let app = axum::Router::new()
                         .with_state(telemetry.clone())
-                        .merge(health_router);
+                        .merge(health_router)
+                        .merge(erasure_api::create_erasure_router(
+                            Arc::new(erasure),
+                            config.proxy.clone(),
+                            admin_audit,
+                        ));
 
                     // Start server
                     let addr = SocketAddr::new(config.server.host, config.server.port + 2);
-                    println!("RAG Manager listening on {}", addr);
-
-                    // Create TCP listener
-                    let listener = tokio::net::TcpListener::bind(&addr)
-                        .await
-                        .expect("Failed to bind to address");
+                    let listener =
+                        RoleListener::bind(config.server.listeners.rag_injector.as_ref(), addr)
+                            .await
+                            .expect("Failed to bind listener");
+                    println!("RAG Manager listening on {}", listener);
 
                     println!("Health check endpoints available at:");
                     println!("  - /health");
@@ -363,8 +1253,13 @@ async fn main() {
                     let completion_tx = shutdown_coordinator.completion_sender();
 
                     // Start server with graceful shutdown
-                    let server = axum::serve(listener, app);
-                    let graceful = server.with_graceful_shutdown(async move {
+                    let app = with_compression(app, &config.server.compression);
+                    let app = with_request_limits(app, &config.server.limits);
+                    let app = with_resource_limits(app, resources.clone());
+                    let app = with_traffic_classification(app, &config.server.synthetic_traffic);
+                    let header_read_timeout =
+                        Duration::from_secs(config.server.limits.header_read_timeout_secs);
+                    let graceful = listener.serve(app, header_read_timeout, async move {
                         if let Ok(signal) = shutdown_rx.recv().await {
                             info!("RAG Manager received shutdown signal: {:?}", signal);
                         }
@@ -400,16 +1295,25 @@ async fn main() {
                     let persona_manager = Arc::new(PersonaManager::new());
 
                     // Create health check manager
-                    let redis_url = config.memory.redis_url.clone();
+                    let redis = RedisConnector::from_config(&config.memory)
+                        .expect("Invalid Redis configuration");
                     let router_endpoint = Some(format!(
                         "http://{}:{}",
                         config.server.host, config.server.port
                     ));
-                    let health_manager = create_persona_layer_health_manager(
+                    let mut health_manager = create_persona_layer_health_manager(
                         persona_manager.clone(),
-                        redis_url,
+                        redis,
                         router_endpoint,
                     );
+                    health_manager.set_resource_monitor(resources.clone());
+                    health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &health_manager,
+                        config.server.port + 3,
+                        &shutdown_coordinator,
+                    );
                     let health_router = health_manager.create_router();
 
                     // Create app with telemetry and health routes
@@ -419,12 +1323,11 @@ async fn main() {
 
                     // Start server
                     let addr = SocketAddr::new(config.server.host, config.server.port + 3);
-                    println!("Persona Layer listening on {}", addr);
-
-                    // Create TCP listener
-                    let listener = tokio::net::TcpListener::bind(&addr)
-                        .await
-                        .expect("Failed to bind to address");
+                    let listener =
+                        RoleListener::bind(config.server.listeners.summarizer.as_ref(), addr)
+                            .await
+                            .expect("Failed to bind listener");
+                    println!("Persona Layer listening on {}", listener);
 
                     println!("Health check endpoints available at:");
                     println!("  - /health");
@@ -436,8 +1339,13 @@ async fn main() {
                     let completion_tx = shutdown_coordinator.completion_sender();
 
                     // Start server with graceful shutdown
-                    let server = axum::serve(listener, app);
-                    let graceful = server.with_graceful_shutdown(async move {
+                    let app = with_compression(app, &config.server.compression);
+                    let app = with_request_limits(app, &config.server.limits);
+                    let app = with_resource_limits(app, resources.clone());
+                    let app = with_traffic_classification(app, &config.server.synthetic_traffic);
+                    let header_read_timeout =
+                        Duration::from_secs(config.server.limits.header_read_timeout_secs);
+                    let graceful = listener.serve(app, header_read_timeout, async move {
                         if let Ok(signal) = shutdown_rx.recv().await {
                             info!("Persona Layer received shutdown signal: {:?}", signal);
                         }
@@ -460,16 +1368,25 @@ async fn main() {
                     println!("Starting in Audit Controller role");
 
                     // Create app with telemetry
-                    let app = axum::Router::new().with_state(telemetry.clone());
+                    let mut app = axum::Router::new().with_state(telemetry.clone());
+
+                    // Serve compliance report exports when a source and sink are configured
+                    match ComplianceExporter::from_config(&config.compliance) {
+                        Ok(Some(exporter)) => {
+                            exporter.spawn_schedule();
+                            app = app.merge(compliance::routes(exporter, config.proxy.clone()));
+                            println!("Compliance exports available at /v1/compliance/exports");
+                        }
+                        Ok(None) => {}
+                        Err(e) => error!("Failed to set up compliance exports: {}", e),
+                    }
 
                     // Start server
                     let addr = SocketAddr::new(config.server.host, config.server.port + 4);
-                    println!("Audit Controller listening on {}", addr);
-
-                    // Create TCP listener
-                    let listener = tokio::net::TcpListener::bind(&addr)
+                    let listener = RoleListener::bind(config.server.listeners.audit.as_ref(), addr)
                         .await
-                        .expect("Failed to bind to address");
+                        .expect("Failed to bind listener");
+                    println!("Audit Controller listening on {}", listener);
 
                     println!("Health check endpoints available at:");
                     println!("  - /health");
@@ -481,8 +1398,13 @@ async fn main() {
                     let completion_tx = shutdown_coordinator.completion_sender();
 
                     // Start server with graceful shutdown
-                    let server = axum::serve(listener, app);
-                    let graceful = server.with_graceful_shutdown(async move {
+                    let app = with_compression(app, &config.server.compression);
+                    let app = with_request_limits(app, &config.server.limits);
+                    let app = with_resource_limits(app, resources.clone());
+                    let app = with_traffic_classification(app, &config.server.synthetic_traffic);
+                    let header_read_timeout =
+                        Duration::from_secs(config.server.limits.header_read_timeout_secs);
+                    let graceful = listener.serve(app, header_read_timeout, async move {
                         if let Ok(signal) = shutdown_rx.recv().await {
                             info!("Audit Controller received shutdown signal: {:?}", signal);
                         }
@@ -531,17 +1453,48 @@ async fn main() {
                     // Create chain engine
                     let chain_engine = Arc::new(ChainEngine::new());
 
-                    // Create RAG manager
-                    let rag_manager = Arc::new(RagManager::new());
+                    // Create RAG manager, embedding queries and sentences and
+                    // judging answers through the router by default
+                    let router_endpoint =
+                        format!("http://{}:{}", config.server.host, config.server.port);
+                    let mut rag_manager = RagManager::new()
+                        .with_injection_guard(
+                            InjectionGuard::new(config.rag.injection.clone())
+                                .expect("Invalid RAG injection patterns"),
+                        )
+                        .with_chunkers(
+                            ChunkerRegistry::from_config(&config.rag, &router_endpoint)
+                                .expect("Invalid RAG chunking configuration"),
+                        );
+                    if let Some(cache) = RetrievalCache::from_config(&config.rag, &router_endpoint)
+                    {
+                        rag_manager = rag_manager.with_retrieval_cache(cache);
+                    }
+                    if let Some(checker) =
+                        GroundingChecker::from_config(&config.rag, &router_endpoint)
+                    {
+                        rag_manager = rag_manager.with_grounding_checker(checker);
+                    }
+                    let rag_manager = Arc::new(rag_manager);
 
                     // Create persona layer manager
                     let persona_manager = Arc::new(PersonaManager::new());
 
+                    // Discover the instances of the other roles, then keep them fresh
+                    let directory = Arc::new(
+                        ServiceDirectory::from_config(&config.discovery)
+                            .expect("Failed to configure service discovery"),
+                    );
+                    if !directory.is_empty() {
+                        directory.refresh().await;
+                        directory.spawn_refresh();
+                    }
+
                     // Create resilient clients for inter-service communication
                     // These will be used when services need to communicate with each other
                     let _resilient_clients =
                         match intellirouter::modules::ipc::utils::create_all_resilient_clients(
-                            &config,
+                            &config, &directory,
                         )
                         .await
                         {
@@ -585,14 +1538,23 @@ async fn main() {
                         .insert(intellirouter::modules::router_core::retry::ErrorCategory::Server);
 
                     // Create health check managers
-                    let redis_url = config.memory.redis_url.clone();
+                    let redis = RedisConnector::from_config(&config.memory)
+                        .expect("Invalid Redis configuration");
                     let vector_db_url = config.rag.vector_db_url.clone();
 
                     // Router health check
-                    let router_health_manager = create_router_health_manager(
+                    let mut router_health_manager = create_router_health_manager(
                         model_registry.clone(),
                         router_config.clone(),
-                        redis_url.clone(),
+                        redis.clone(),
+                    );
+                    router_health_manager.set_resource_monitor(resources.clone());
+                    router_health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &router_health_manager,
+                        config.server.port,
+                        &shutdown_coordinator,
                     );
                     let router_health_router = router_health_manager.create_router();
 
@@ -601,28 +1563,52 @@ async fn main() {
                         "http://{}:{}",
                         config.server.host, config.server.port
                     ));
-                    let chain_engine_health_manager = create_chain_engine_health_manager(
+                    let mut chain_engine_health_manager = create_chain_engine_health_manager(
                         chain_engine.clone(),
-                        redis_url.clone(),
+                        redis.clone(),
                         router_endpoint.clone(),
                     );
+                    chain_engine_health_manager.set_resource_monitor(resources.clone());
+                    chain_engine_health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &chain_engine_health_manager,
+                        config.server.port + 1,
+                        &shutdown_coordinator,
+                    );
                     let chain_engine_health_router = chain_engine_health_manager.create_router();
 
                     // RAG Manager health check
-                    let rag_manager_health_manager = create_rag_manager_health_manager(
+                    let mut rag_manager_health_manager = create_rag_manager_health_manager(
                         rag_manager.clone(),
-                        redis_url.clone(),
+                        redis.clone(),
                         router_endpoint.clone(),
                         vector_db_url,
                     );
+                    rag_manager_health_manager.set_resource_monitor(resources.clone());
+                    rag_manager_health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &rag_manager_health_manager,
+                        config.server.port + 2,
+                        &shutdown_coordinator,
+                    );
                     let rag_manager_health_router = rag_manager_health_manager.create_router();
 
                     // Persona Layer health check
-                    let persona_layer_health_manager = create_persona_layer_health_manager(
+                    let mut persona_layer_health_manager = create_persona_layer_health_manager(
                         persona_manager.clone(),
-                        redis_url.clone(),
+                        redis.clone(),
                         router_endpoint.clone(),
                     );
+                    persona_layer_health_manager.set_resource_monitor(resources.clone());
+                    persona_layer_health_manager.set_config_fingerprint(fingerprint.clone());
+                    spawn_grpc_health(
+                        &config,
+                        &persona_layer_health_manager,
+                        config.server.port + 3,
+                        &shutdown_coordinator,
+                    );
                     let persona_layer_health_router = persona_layer_health_manager.create_router();
 
                     // Create apps with telemetry and health routes
@@ -645,15 +1631,15 @@ async fn main() {
                     // Start servers
                     // Clone config and shutdown_coordinator for each async block to avoid move issues
                     let config1 = config.clone();
+                    let resources1 = resources.clone();
                     let shutdown_coordinator1 = shutdown_coordinator.clone();
                     tokio::spawn(async move {
                         let addr = config1.server.socket_addr();
-                        println!("Router listening on {}", addr);
-
-                        // Create TCP listener
-                        let listener = tokio::net::TcpListener::bind(&addr)
-                            .await
-                            .expect("Failed to bind to address");
+                        let listener =
+                            RoleListener::bind(config1.server.listeners.router.as_ref(), addr)
+                                .await
+                                .expect("Failed to bind listener");
+                        println!("Router listening on {}", listener);
 
                         println!("Health check endpoints available at:");
                         println!("  - /health");
@@ -665,13 +1651,22 @@ async fn main() {
                         let completion_tx = shutdown_coordinator1.completion_sender();
 
                         // Start server with graceful shutdown
-                        let server = axum::serve(listener, router_app);
-                        let graceful = server.with_graceful_shutdown(async move {
-                            if let Ok(signal) = shutdown_rx.recv().await {
-                                info!("Router received shutdown signal: {:?}", signal);
-                            }
-                            info!("Router shutting down gracefully...");
-                        });
+                        let router_app = with_compression(router_app, &config1.server.compression);
+                        let router_app = with_request_limits(router_app, &config1.server.limits);
+                        let router_app = with_resource_limits(router_app, resources1);
+                        let router_app = with_traffic_classification(
+                            router_app,
+                            &config1.server.synthetic_traffic,
+                        );
+                        let header_read_timeout =
+                            Duration::from_secs(config1.server.limits.header_read_timeout_secs);
+                        let graceful =
+                            listener.serve(router_app, header_read_timeout, async move {
+                                if let Ok(signal) = shutdown_rx.recv().await {
+                                    info!("Router received shutdown signal: {:?}", signal);
+                                }
+                                info!("Router shutting down gracefully...");
+                            });
 
                         // Run the server and handle errors
                         if let Err(e) = graceful.await {
@@ -687,15 +1682,17 @@ async fn main() {
                     });
 
                     let config2 = config.clone();
+                    let resources2 = resources.clone();
                     let shutdown_coordinator2 = shutdown_coordinator.clone();
                     tokio::spawn(async move {
                         let addr = SocketAddr::new(config2.server.host, config2.server.port + 1);
-                        println!("Chain Engine listening on {}", addr);
-
-                        // Create TCP listener
-                        let listener = tokio::net::TcpListener::bind(&addr)
-                            .await
-                            .expect("Failed to bind to address");
+                        let listener = RoleListener::bind(
+                            config2.server.listeners.orchestrator.as_ref(),
+                            addr,
+                        )
+                        .await
+                        .expect("Failed to bind listener");
+                        println!("Chain Engine listening on {}", listener);
 
                         println!("Health check endpoints available at:");
                         println!("  - /health");
@@ -707,13 +1704,24 @@ async fn main() {
                         let completion_tx = shutdown_coordinator2.completion_sender();
 
                         // Start server with graceful shutdown
-                        let server = axum::serve(listener, chain_engine_app);
-                        let graceful = server.with_graceful_shutdown(async move {
-                            if let Ok(signal) = shutdown_rx.recv().await {
-                                info!("Chain Engine received shutdown signal: {:?}", signal);
-                            }
-                            info!("Chain Engine shutting down gracefully...");
-                        });
+                        let chain_engine_app =
+                            with_compression(chain_engine_app, &config2.server.compression);
+                        let chain_engine_app =
+                            with_request_limits(chain_engine_app, &config2.server.limits);
+                        let chain_engine_app = with_resource_limits(chain_engine_app, resources2);
+                        let chain_engine_app = with_traffic_classification(
+                            chain_engine_app,
+                            &config2.server.synthetic_traffic,
+                        );
+                        let header_read_timeout =
+                            Duration::from_secs(config2.server.limits.header_read_timeout_secs);
+                        let graceful =
+                            listener.serve(chain_engine_app, header_read_timeout, async move {
+                                if let Ok(signal) = shutdown_rx.recv().await {
+                                    info!("Chain Engine received shutdown signal: {:?}", signal);
+                                }
+                                info!("Chain Engine shutting down gracefully...");
+                            });
 
                         // Run the server and handle errors
                         if let Err(e) = graceful.await {
@@ -729,15 +1737,17 @@ async fn main() {
                     });
 
                     let config3 = config.clone();
+                    let resources3 = resources.clone();
                     let shutdown_coordinator3 = shutdown_coordinator.clone();
                     tokio::spawn(async move {
                         let addr = SocketAddr::new(config3.server.host, config3.server.port + 2);
-                        println!("RAG Manager listening on {}", addr);
-
-                        // Create TCP listener
-                        let listener = tokio::net::TcpListener::bind(&addr)
-                            .await
-                            .expect("Failed to bind to address");
+                        let listener = RoleListener::bind(
+                            config3.server.listeners.rag_injector.as_ref(),
+                            addr,
+                        )
+                        .await
+                        .expect("Failed to bind listener");
+                        println!("RAG Manager listening on {}", listener);
 
                         println!("Health check endpoints available at:");
                         println!("  - /health");
@@ -749,13 +1759,24 @@ async fn main() {
                         let completion_tx = shutdown_coordinator3.completion_sender();
 
                         // Start server with graceful shutdown
-                        let server = axum::serve(listener, rag_manager_app);
-                        let graceful = server.with_graceful_shutdown(async move {
-                            if let Ok(signal) = shutdown_rx.recv().await {
-                                info!("RAG Manager received shutdown signal: {:?}", signal);
-                            }
-                            info!("RAG Manager shutting down gracefully...");
-                        });
+                        let rag_manager_app =
+                            with_compression(rag_manager_app, &config3.server.compression);
+                        let rag_manager_app =
+                            with_request_limits(rag_manager_app, &config3.server.limits);
+                        let rag_manager_app = with_resource_limits(rag_manager_app, resources3);
+                        let rag_manager_app = with_traffic_classification(
+                            rag_manager_app,
+                            &config3.server.synthetic_traffic,
+                        );
+                        let header_read_timeout =
+                            Duration::from_secs(config3.server.limits.header_read_timeout_secs);
+                        let graceful =
+                            listener.serve(rag_manager_app, header_read_timeout, async move {
+                                if let Ok(signal) = shutdown_rx.recv().await {
+                                    info!("RAG Manager received shutdown signal: {:?}", signal);
+                                }
+                                info!("RAG Manager shutting down gracefully...");
+                            });
 
                         // Run the server and handle errors
                         if let Err(e) = graceful.await {
@@ -771,15 +1792,15 @@ async fn main() {
                     });
 
                     let config4 = config.clone();
+                    let resources4 = resources.clone();
                     let shutdown_coordinator4 = shutdown_coordinator.clone();
                     tokio::spawn(async move {
                         let addr = SocketAddr::new(config4.server.host, config4.server.port + 3);
-                        println!("Persona Layer listening on {}", addr);
-
-                        // Create TCP listener
-                        let listener = tokio::net::TcpListener::bind(&addr)
-                            .await
-                            .expect("Failed to bind to address");
+                        let listener =
+                            RoleListener::bind(config4.server.listeners.summarizer.as_ref(), addr)
+                                .await
+                                .expect("Failed to bind listener");
+                        println!("Persona Layer listening on {}", listener);
 
                         println!("Health check endpoints available at:");
                         println!("  - /health");
@@ -791,13 +1812,24 @@ async fn main() {
                         let completion_tx = shutdown_coordinator4.completion_sender();
 
                         // Start server with graceful shutdown
-                        let server = axum::serve(listener, persona_layer_app);
-                        let graceful = server.with_graceful_shutdown(async move {
-                            if let Ok(signal) = shutdown_rx.recv().await {
-                                info!("Persona Layer received shutdown signal: {:?}", signal);
-                            }
-                            info!("Persona Layer shutting down gracefully...");
-                        });
+                        let persona_layer_app =
+                            with_compression(persona_layer_app, &config4.server.compression);
+                        let persona_layer_app =
+                            with_request_limits(persona_layer_app, &config4.server.limits);
+                        let persona_layer_app = with_resource_limits(persona_layer_app, resources4);
+                        let persona_layer_app = with_traffic_classification(
+                            persona_layer_app,
+                            &config4.server.synthetic_traffic,
+                        );
+                        let header_read_timeout =
+                            Duration::from_secs(config4.server.limits.header_read_timeout_secs);
+                        let graceful =
+                            listener.serve(persona_layer_app, header_read_timeout, async move {
+                                if let Ok(signal) = shutdown_rx.recv().await {
+                                    info!("Persona Layer received shutdown signal: {:?}", signal);
+                                }
+                                info!("Persona Layer shutting down gracefully...");
+                            });
 
                         // Run the server and handle errors
                         if let Err(e) = graceful.await {
@@ -856,5 +1888,638 @@ async fn main() {
                 .expect("Failed to write configuration file");
             println!("Configuration file generated at {:?}", output);
         }
+        Commands::GenerateDashboard {
+            output,
+            title,
+            datasource,
+        } => {
+            let mut options = DashboardOptions {
+                title,
+                ..DashboardOptions::default()
+            };
+            if let Some(datasource) = datasource {
+                options.datasource_uid = datasource;
+            }
+            let dashboard = serde_json::to_string_pretty(&generate_dashboard(&options))
+                .expect("Failed to serialize dashboard");
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, dashboard + "\n")
+                        .expect("Failed to write dashboard file");
+                    println!("Dashboard generated at {:?}", output);
+                }
+                None => println!("{}", dashboard),
+            }
+        }
+        Commands::MigrateEncryption {
+            config,
+            env,
+            dry_run,
+        } => {
+            let config_path = config.unwrap_or_else(|| {
+                let mut path = PathBuf::from("config");
+                path.push(format!("{}.toml", env));
+                path
+            });
+            let config = Config::from_file(config_path.to_str().unwrap())
+                .expect("Failed to load configuration");
+            let encryptor = encryptor_from_config(&config.encryption)
+                .expect("Failed to initialize encryption at rest")
+                .expect("Encryption at rest is not enabled in the configuration");
+            let print_report = |name: &str, report: MigrationReport| {
+                println!(
+                    "{}{}: {} scanned, {} encrypted, {} rewrapped, {} unchanged",
+                    if dry_run { "[dry run] " } else { "" },
+                    name,
+                    report.scanned,
+                    report.encrypted,
+                    report.rewrapped,
+                    report.unchanged
+                );
+            };
+
+            let mut prefixes = Vec::new();
+            if config.memory.backend_type == "redis" {
+                prefixes.push(("conversations", config.memory.key_prefix.clone()));
+            }
+            if config.chain_engine.checkpoint.backend == "redis" {
+                prefixes.push((
+                    "chain checkpoints",
+                    config.chain_engine.checkpoint.key_prefix.clone(),
+                ));
+            }
+            if config.chain_engine.dead_letters.backend == "redis" {
+                prefixes.push((
+                    "chain dead letters",
+                    config.chain_engine.dead_letters.key_prefix.clone(),
+                ));
+            }
+            if !prefixes.is_empty() {
+                let redis = RedisConnector::from_config(&config.memory)
+                    .expect("Invalid Redis configuration")
+                    .expect("memory.redis_url is not configured");
+                for (name, prefix) in prefixes {
+                    let report =
+                        migrate_redis_keys(&redis, &format!("{}:*", prefix), &encryptor, dry_run)
+                            .await
+                            .expect("Encryption migration failed");
+                    print_report(name, report);
+                }
+            }
+
+            // Payloads can remain from a time capture was enabled
+            let report = payloads::migrate_records(
+                std::path::Path::new(&config.proxy.payloads.dir),
+                &encryptor,
+                dry_run,
+            )
+            .await
+            .expect("Encryption migration failed");
+            print_report("captured payloads", report);
+        }
+        Commands::Ctx { command } => {
+            if let Err(e) = manage_contexts(command) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Replay {
+            request_id,
+            file,
+            config,
+            model,
+            json,
+        } => {
+            let target = model.map_or(ReplayTarget::Mock, ReplayTarget::Model);
+            if let Err(e) = replay_request(
+                cli.context.as_deref(),
+                &request_id,
+                file,
+                config,
+                target,
+                json,
+            )
+            .await
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Eval(args) => match run_eval(cli.context.as_deref(), args).await {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::EvalChunking(args) => {
+            if let Err(e) = run_chunking_eval(args).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::SupportBundle {
+            output,
+            metrics_url,
+        } => {
+            if let Err(e) =
+                write_support_bundle(cli.context.as_deref(), output, metrics_url.as_deref()).await
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        #[cfg(feature = "test-harness")]
+        Commands::LoadReplay(args) => {
+            if let Err(e) = replay_traffic(cli.context.as_deref(), args).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        command => {
+            if let Err(e) = manage_remote(cli.context.as_deref(), command).await {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Serve the gRPC health checking protocol of a role next to its HTTP health
+/// endpoints, when enabled
+fn spawn_grpc_health(
+    config: &Config,
+    health_manager: &HealthCheckManager,
+    http_port: u16,
+    shutdown_coordinator: &ShutdownCoordinator,
+) {
+    let grpc_health = &config.server.grpc_health;
+    if !grpc_health.enabled {
+        return;
+    }
+    let addr = SocketAddr::new(
+        config.server.host,
+        http_port.saturating_add(grpc_health.port_offset),
+    );
+    let service = GrpcHealthService::new(
+        Arc::new(health_manager.clone()),
+        Duration::from_secs(grpc_health.watch_interval_secs),
+    );
+    let mut shutdown_rx = shutdown_coordinator.subscribe();
+    println!("gRPC health checks available at {}", addr);
+    tokio::spawn(async move {
+        let shutdown = async move {
+            let _ = shutdown_rx.recv().await;
+        };
+        if let Err(e) = service.serve(addr, shutdown).await {
+            error!("gRPC health server error: {}", e);
+        }
+    });
+}
+
+/// Run a `ctx` subcommand against the contexts file
+fn manage_contexts(command: CtxCommand) -> Result<(), RemoteError> {
+    let mut store = ContextStore::load(ContextStore::default_path())?;
+    match command {
+        CtxCommand::List => {
+            for (name, context) in &store.contexts {
+                let marker = if store.current.as_deref() == Some(name) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{} {}\t{}", marker, name, context.endpoint);
+            }
+        }
+        CtxCommand::Current => {
+            let (name, context) = store.resolve(None)?;
+            println!("{}\t{}", name, context.endpoint);
+        }
+        CtxCommand::Use { name } => {
+            store.use_context(&name)?;
+            store.save()?;
+            println!("Switched to context {}", name);
+        }
+        CtxCommand::Set {
+            name,
+            endpoint,
+            api_key,
+            api_key_env,
+        } => {
+            store.set(
+                &name,
+                Context {
+                    endpoint,
+                    api_key,
+                    api_key_env,
+                },
+            )?;
+            store.save()?;
+            println!("Context {} saved", name);
+        }
+        CtxCommand::Delete { name } => {
+            store.remove(&name)?;
+            store.save()?;
+            println!("Context {} deleted", name);
+        }
     }
+    Ok(())
+}
+
+/// Replay a captured request and print how its output differs from the
+/// original
+///
+/// The remote context is only used to fetch the payload record, unless it
+/// is read from a file, and to run a model target. A record file encrypted
+/// at rest is opened with the keys of the `config` file.
+async fn replay_request(
+    context: Option<&str>,
+    request_id: &str,
+    file: Option<PathBuf>,
+    config: Option<PathBuf>,
+    target: ReplayTarget,
+    json: bool,
+) -> Result<(), RemoteError> {
+    let client = if file.is_none() || target != ReplayTarget::Mock {
+        let store = ContextStore::load(ContextStore::default_path())?;
+        let (_, context) = store.resolve(context)?;
+        Some(RemoteClient::new(context)?)
+    } else {
+        None
+    };
+    let record = match (&file, &client) {
+        (Some(file), _) => {
+            let encryptor = payload_encryptor(config.as_deref())?;
+            replay::load(file, encryptor.as_deref()).await?
+        }
+        (None, Some(client)) => replay::fetch(client, request_id).await?,
+        (None, None) => unreachable!("the client is created without a file"),
+    };
+    if record.request_id != request_id {
+        return Err(RemoteError::Request(format!(
+            "The payload record is of request {}, not {}",
+            record.request_id, request_id
+        )));
+    }
+
+    let report = replay::replay(&record, &target, client.as_ref()).await?;
+    if json {
+        let report = serde_json::to_string_pretty(&report)
+            .map_err(|e| RemoteError::Request(e.to_string()))?;
+        println!("{}", report);
+    } else {
+        print!("{}", report.render());
+    }
+    Ok(())
+}
+
+/// Encryptor of a configuration file, to open payload records encrypted at
+/// rest
+fn payload_encryptor(
+    config: Option<&std::path::Path>,
+) -> Result<Option<Arc<EnvelopeEncryptor>>, RemoteError> {
+    let Some(config) = config else {
+        return Ok(None);
+    };
+    let config = Config::from_file(&config.to_string_lossy())
+        .map_err(|e| RemoteError::Request(format!("Failed to load configuration: {}", e)))?;
+    encryptor_from_config(&config.encryption)
+        .map_err(|e| RemoteError::Request(format!("Failed to initialize encryption: {}", e)))
+}
+
+/// Replay a window of captured traffic against the selected remote context
+/// and print the load test summary
+#[cfg(feature = "test-harness")]
+async fn replay_traffic(context: Option<&str>, args: LoadReplayArgs) -> Result<(), RemoteError> {
+    use intellirouter::modules::remote::traffic::{self, Redaction, ReplaySummary, TrafficWindow};
+    use intellirouter::modules::test_harness::performance::LoadGenerator;
+    use intellirouter::modules::test_harness::types::TestHarnessError;
+
+    let window = TrafficWindow {
+        since: args.since,
+        until: args.until,
+    };
+    let encryptor = payload_encryptor(args.config.as_deref())?;
+    let records = traffic::load_window(&args.dir, &window, encryptor.as_deref()).await?;
+    if records.is_empty() {
+        return Err(RemoteError::Request(format!(
+            "No captured requests in {} for the window",
+            args.dir.display()
+        )));
+    }
+    let mut redaction = Redaction::new();
+    if args.mask_content {
+        redaction = redaction.with_masked_content();
+    }
+    if let Some(model) = args.model {
+        redaction = redaction.with_model(model);
+    }
+    let scheduled = traffic::schedule(&records, args.speed, &redaction)?;
+
+    let store = ContextStore::load(ContextStore::default_path())?;
+    let (_, context) = store.resolve(context)?;
+    let client = Arc::new(RemoteClient::new(context)?);
+    let requests = Arc::new(
+        scheduled
+            .iter()
+            .map(|scheduled| scheduled.request.clone())
+            .collect::<Vec<_>>(),
+    );
+    let offsets: Vec<Duration> = scheduled.iter().map(|scheduled| scheduled.offset).collect();
+
+    let started = std::time::Instant::now();
+    let metrics = LoadGenerator::new("load-replay")
+        .replay_load(
+            &offsets,
+            Duration::from_secs(args.timeout_secs),
+            move |index| {
+                let client = client.clone();
+                let requests = requests.clone();
+                async move {
+                    traffic::send(&client, &requests[index])
+                        .await
+                        .map_err(|e| TestHarnessError::ExecutionError(e.to_string()))
+                }
+            },
+        )
+        .await
+        .map_err(|e| RemoteError::Request(e.to_string()))?;
+
+    let latencies = metrics
+        .iter()
+        .filter(|metric| metric.name == "request_latency")
+        .map(|metric| metric.value)
+        .collect();
+    let errors = metrics
+        .iter()
+        .filter(|metric| metric.name == "request_error" || metric.name == "request_timeout")
+        .count();
+    let window = offsets
+        .last()
+        .copied()
+        .unwrap_or_default()
+        .mul_f64(args.speed);
+    let summary = ReplaySummary::new(latencies, errors, started.elapsed(), window, args.speed);
+    if args.json {
+        let json = serde_json::to_string_pretty(&summary)
+            .map_err(|e| RemoteError::Request(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        print!("{}", summary.render());
+    }
+    Ok(())
+}
+
+/// Collect a support bundle from the selected remote context and write it
+/// as a gzipped tarball
+async fn write_support_bundle(
+    context: Option<&str>,
+    output: Option<PathBuf>,
+    metrics_url: Option<&str>,
+) -> Result<(), RemoteError> {
+    let store = ContextStore::load(ContextStore::default_path())?;
+    let (_, context) = store.resolve(context)?;
+    let client = RemoteClient::new(context)?;
+    let bundle = support_bundle::collect(&client, metrics_url).await;
+
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{}.tar.gz", bundle.directory())));
+    bundle.write_tar_gz(std::fs::File::create(&output)?)?;
+    println!(
+        "Support bundle of {} files written to {:?}",
+        bundle.files.len(),
+        output
+    );
+    Ok(())
+}
+
+/// Run an eval corpus against a baseline and a candidate through the selected
+/// remote context, returning whether no case regressed
+async fn run_eval(context: Option<&str>, args: EvalArgs) -> Result<bool, RemoteError> {
+    let encryptor = payload_encryptor(args.config.as_deref())?;
+    let cases = eval::load_corpus(&args.corpus, encryptor.as_deref()).await?;
+    let store = ContextStore::load(ContextStore::default_path())?;
+    let (_, context) = store.resolve(context)?;
+    let client = RemoteClient::new(context)?;
+
+    let variant = |name: &str, model: Option<String>| {
+        let variant = EvalVariant::new(name);
+        match model {
+            Some(model) => variant.with_model(model),
+            None => variant,
+        }
+    };
+    let mut baseline = variant("baseline", args.baseline_model);
+    let mut candidate = variant("candidate", args.candidate_model);
+    for (variant, prompt) in [
+        (&mut baseline, args.baseline_prompt),
+        (&mut candidate, args.candidate_prompt),
+    ] {
+        let Some(prompt) = prompt else {
+            continue;
+        };
+        let (id, version) = prompt
+            .rsplit_once('@')
+            .and_then(|(id, version)| Some((id, version.parse().ok()?)))
+            .ok_or_else(|| {
+                RemoteError::Request(format!(
+                    "Invalid prompt version '{}', expected <prompt>@<version>",
+                    prompt
+                ))
+            })?;
+        variant.prompt = Some(eval::fetch_prompt(&client, id, version).await?);
+    }
+
+    let mut runner = EvalRunner::new(Arc::new(client))
+        .with_min_similarity(args.min_similarity)
+        .with_concurrency(args.concurrency);
+    if let Some(judge) = args.judge {
+        runner = runner.with_judge(judge);
+    }
+    let report = runner.run(&cases, &baseline, &candidate).await;
+
+    if let Some(output) = &args.output {
+        std::fs::write(output, report.to_markdown())?;
+    }
+    if args.json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| RemoteError::Request(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        print!("{}", report.to_markdown());
+    }
+    Ok(!report.has_regressions())
+}
+
+/// Compare the RAG chunkers on a corpus, embedding through the router unless
+/// `[rag.chunking]` configures an endpoint
+async fn run_chunking_eval(args: EvalChunkingArgs) -> Result<(), RagError> {
+    let config_path = args.config.unwrap_or_else(|| {
+        let mut path = PathBuf::from("config");
+        path.push(format!("{}.toml", args.env));
+        path
+    });
+    let config = Config::from_file(&config_path.to_string_lossy()).map_err(RagError::Other)?;
+    let endpoint = config
+        .rag
+        .chunking
+        .endpoint
+        .clone()
+        .unwrap_or_else(|| format!("http://{}:{}", config.server.host, config.server.port));
+    let registry = ChunkerRegistry::from_config(&config.rag, &endpoint)?;
+    let embedder = OpenAIEmbedder::new(
+        &endpoint,
+        config.rag.chunking.api_key.clone(),
+        &config.rag.default_embedding_model,
+    );
+
+    let chunkers = if args.chunkers.is_empty() {
+        registry.names()
+    } else {
+        args.chunkers
+    };
+    let documents = chunking_eval::load_documents(&args.corpus)?;
+    let questions = chunking_eval::load_questions(&args.questions)?;
+    let report = chunking_eval::evaluate_chunkers(
+        &registry, &chunkers, &documents, &questions, &embedder, args.k,
+    )
+    .await?;
+
+    if let Some(output) = &args.output {
+        std::fs::write(output, report.to_markdown())?;
+    }
+    if args.json {
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| RagError::SerializationError(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        print!("{}", report.to_markdown());
+    }
+    Ok(())
+}
+
+/// Run a management subcommand against the selected remote context and
+/// print the JSON response
+async fn manage_remote(context: Option<&str>, command: Commands) -> Result<(), RemoteError> {
+    let output = match &command {
+        Commands::ExportBundle { output } => output.clone(),
+        _ => None,
+    };
+    let (method, path, body) = remote_request(command)?;
+    let store = ContextStore::load(ContextStore::default_path())?;
+    let (_, context) = store.resolve(context)?;
+    let response = RemoteClient::new(context)?
+        .send(method, &path, body)
+        .await?;
+    if response.is_null() {
+        return Ok(());
+    }
+    let response = serde_json::to_string_pretty(&response).unwrap_or_else(|_| response.to_string());
+    match output {
+        Some(output) => {
+            std::fs::write(&output, response + "\n")?;
+            println!("Bundle exported to {:?}", output);
+        }
+        None => println!("{}", response),
+    }
+    Ok(())
+}
+
+/// Map a management subcommand to its admin API request
+fn remote_request(command: Commands) -> Result<(Method, String, Option<Value>), RemoteError> {
+    let request = match command {
+        Commands::Models { command } => match command {
+            ModelsCommand::List => (Method::GET, "/v1/admin/models".to_string(), None),
+            ModelsCommand::Register { file } => {
+                let content = std::fs::read_to_string(&file).map_err(|e| {
+                    RemoteError::Request(format!("Failed to read {}: {}", file.display(), e))
+                })?;
+                let model: Value = serde_json::from_str(&content).map_err(|e| {
+                    RemoteError::Request(format!("Invalid model file {}: {}", file.display(), e))
+                })?;
+                (Method::POST, "/v1/admin/models".to_string(), Some(model))
+            }
+            ModelsCommand::Remove { id } => (
+                Method::DELETE,
+                format!("/v1/admin/models/{}", path_segment(&id)),
+                None,
+            ),
+            ModelsCommand::Status { id, status } => (
+                Method::PUT,
+                format!("/v1/admin/models/{}/status", path_segment(&id)),
+                Some(json!({ "status": status })),
+            ),
+        },
+        Commands::Keys { command } => match command {
+            KeysCommand::List => (Method::GET, "/v1/admin/keys".to_string(), None),
+        },
+        Commands::Personas { command } => match command {
+            PersonasCommand::List => (Method::GET, "/v1/admin/personas".to_string(), None),
+            PersonasCommand::Get { id } => (
+                Method::GET,
+                format!("/v1/admin/personas/{}", path_segment(&id)),
+                None,
+            ),
+        },
+        Commands::Chains { command } => match command {
+            ChainsCommand::Execution { id } => (
+                Method::GET,
+                format!("/v1/chains/executions/{}", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::Resume { id } => (
+                Method::POST,
+                format!("/v1/chains/executions/{}/resume", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::Cancel { id } => (
+                Method::POST,
+                format!("/v1/chains/executions/{}/cancel", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::Schedules => (Method::GET, "/v1/chains/schedules".to_string(), None),
+            ChainsCommand::History { id } => (
+                Method::GET,
+                format!("/v1/chains/schedules/{}/history", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::Trigger { id } => (
+                Method::POST,
+                format!("/v1/chains/schedules/{}/trigger", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::DeadLetters => {
+                (Method::GET, "/v1/chains/dead-letters".to_string(), None)
+            }
+            ChainsCommand::Redeliver { id } => (
+                Method::POST,
+                format!("/v1/chains/dead-letters/{}/redeliver", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::Retry { id } => (
+                Method::POST,
+                format!("/v1/chains/dead-letters/{}/retry", path_segment(&id)),
+                None,
+            ),
+            ChainsCommand::PurgeDeadLetters => {
+                (Method::DELETE, "/v1/chains/dead-letters".to_string(), None)
+            }
+        },
+        Commands::ExportBundle { .. } => (Method::GET, "/v1/admin/bundle".to_string(), None),
+        Commands::ImportBundle { file, dry_run } => {
+            let content = std::fs::read_to_string(&file).map_err(|e| {
+                RemoteError::Request(format!("Failed to read {}: {}", file.display(), e))
+            })?;
+            let bundle: Value = serde_json::from_str(&content).map_err(|e| {
+                RemoteError::Request(format!("Invalid bundle file {}: {}", file.display(), e))
+            })?;
+            (
+                Method::POST,
+                format!("/v1/admin/bundle/import?dry_run={}", dry_run),
+                Some(bundle),
+            )
+        }
+        _ => unreachable!("not a remote management command"),
+    };
+    Ok(request)
 }
@@ -0,0 +1,205 @@
+//! Compliance Export API
+//!
+//! Endpoints of the audit role starting, listing and resuming compliance
+//! export jobs. They are authorized with the admin credentials of the proxy
+//! configuration: viewers can read jobs, operators can also start and
+//! resume them.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use utoipa::{OpenApi, ToSchema};
+
+use super::{ComplianceError, ComplianceExporter, ExportJob, JobStatus, ModelUsage};
+use crate::config::{ProxyConfig, ReportFileFormat};
+use crate::modules::llm_proxy::admin::{authorize, AdminRole};
+use crate::modules::llm_proxy::dto::{ApiError, ApiErrorDetail};
+
+/// State of the compliance export endpoints
+#[derive(Clone)]
+struct ComplianceState {
+    exporter: Arc<ComplianceExporter>,
+    proxy: Arc<ProxyConfig>,
+}
+
+/// Request to export the report of a time range
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ExportRequest {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Formats to write (`csv` or `parquet`); the configured formats when omitted
+    #[serde(default)]
+    #[schema(value_type = Option<Vec<String>>)]
+    pub formats: Option<Vec<ReportFileFormat>>,
+}
+
+/// OpenAPI description of the compliance export endpoints
+#[derive(OpenApi)]
+#[openapi(
+    paths(list_exports, get_export, start_export, resume_export),
+    components(schemas(ExportRequest, ExportJob, JobStatus, ModelUsage))
+)]
+pub struct ComplianceApiDoc;
+
+/// Routes of the compliance export endpoints
+pub fn routes(exporter: Arc<ComplianceExporter>, proxy: ProxyConfig) -> Router {
+    Router::new()
+        .route(
+            "/v1/compliance/exports",
+            get(list_exports).post(start_export),
+        )
+        .route("/v1/compliance/exports/{id}", get(get_export))
+        .route("/v1/compliance/exports/{id}/resume", post(resume_export))
+        .with_state(ComplianceState {
+            exporter,
+            proxy: Arc::new(proxy),
+        })
+}
+
+/// Route handler for GET /v1/compliance/exports
+#[utoipa::path(
+    get,
+    path = "/v1/compliance/exports",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Compliance export jobs, as `{\"jobs\": [...]}`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_exports(State(state): State<ComplianceState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(json!({ "jobs": state.exporter.jobs().list() })).into_response()
+}
+
+/// Route handler for GET /v1/compliance/exports/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/compliance/exports/{id}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Export job ID")),
+    responses(
+        (status = 200, description = "Compliance export job", body = ExportJob),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown export job", body = ApiError)
+    )
+)]
+async fn get_export(
+    State(state): State<ComplianceState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.exporter.jobs().get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => error_response(&ComplianceError::NotFound(id)),
+    }
+}
+
+/// Route handler for POST /v1/compliance/exports
+#[utoipa::path(
+    post,
+    path = "/v1/compliance/exports",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    request_body = ExportRequest,
+    responses(
+        (status = 202, description = "Export job started", body = ExportJob),
+        (status = 400, description = "Invalid time range or formats", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn start_export(
+    State(state): State<ComplianceState>,
+    headers: HeaderMap,
+    Json(request): Json<ExportRequest>,
+) -> Response {
+    let principal = match authorize(&state.proxy, &headers, AdminRole::Operator) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    match state
+        .exporter
+        .start_job(request.start, request.end, request.formats)
+    {
+        Ok(job) => {
+            info!(
+                "Compliance export job {} requested by {}",
+                job.id, principal.subject
+            );
+            (StatusCode::ACCEPTED, Json(job)).into_response()
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Route handler for POST /v1/compliance/exports/{id}/resume
+#[utoipa::path(
+    post,
+    path = "/v1/compliance/exports/{id}/resume",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Export job ID")),
+    responses(
+        (status = 202, description = "Export job resumed", body = ExportJob),
+        (status = 400, description = "Job already completed or running", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown export job", body = ApiError)
+    )
+)]
+async fn resume_export(
+    State(state): State<ComplianceState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let principal = match authorize(&state.proxy, &headers, AdminRole::Operator) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    match state.exporter.resume_job(&id) {
+        Ok(job) => {
+            info!(
+                "Compliance export job {} resumed by {}",
+                job.id, principal.subject
+            );
+            (StatusCode::ACCEPTED, Json(job)).into_response()
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+/// Map a compliance error to an error response
+fn error_response(e: &ComplianceError) -> Response {
+    let (status, code) = match e {
+        ComplianceError::NotFound(_) => (StatusCode::NOT_FOUND, "export_not_found"),
+        ComplianceError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, "invalid_export"),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, "export_error"),
+    };
+    (
+        status,
+        Json(ApiError {
+            error: ApiErrorDetail {
+                message: e.to_string(),
+                r#type: "invalid_request_error".to_string(),
+                param: None,
+                code: Some(code.to_string()),
+            },
+        }),
+    )
+        .into_response()
+}
@@ -0,0 +1,786 @@
+//! Compliance Reports
+//!
+//! The audit role generates compliance reports from the per-request
+//! telemetry records: every request of each tenant, the models used and the
+//! data categories flagged by guardrails. Each report covers a time range and
+//! is written as CSV and/or Parquet objects to an S3-compatible bucket:
+//!
+//! - `<job>/tenants/<tenant>/requests.<ext>`: the tenant's requests
+//! - `<job>/summary.<ext>`: requests, tokens, cost and flagged categories per
+//!   tenant and model
+//!
+//! Reports are generated by export jobs, started through the API or, when
+//! enabled, for every completed period. The state of a job is saved after
+//! each tenant, so a failed or interrupted job resumes with the tenants it
+//! hasn't exported yet.
+
+mod api;
+mod sink;
+mod source;
+mod table;
+
+pub use api::{routes, ComplianceApiDoc};
+pub use sink::{ObjectSink, S3Sink};
+pub use source::{ClickHouseSource, RecordSource};
+pub use table::{Cell, ColumnType, Table};
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{error, info, warn};
+use utoipa::ToSchema;
+
+use crate::config::{ComplianceConfig, ReportFileFormat, ReportPeriod};
+use crate::modules::telemetry::TelemetryRecord;
+
+/// Interval between checks for a completed report period
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Directory name of the records without a tenant
+const NO_TENANT: &str = "_none";
+
+/// Error generating compliance reports
+#[derive(Error, Debug)]
+pub enum ComplianceError {
+    #[error("Configuration error: {0}")]
+    Config(String),
+
+    #[error("Record source error: {0}")]
+    Source(String),
+
+    #[error("Report sink error: {0}")]
+    Sink(String),
+
+    #[error("Encoding error: {0}")]
+    Encoding(String),
+
+    #[error("Job state error: {0}")]
+    State(String),
+
+    #[error("Export job not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+}
+
+/// Status of an export job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    /// Stopped by an error; resuming continues with the remaining tenants
+    Failed,
+}
+
+/// Usage of a model by a tenant over a report's time range
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ModelUsage {
+    pub tenant: Option<String>,
+    pub model: String,
+    pub provider: String,
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub total_tokens: u64,
+    pub cost_usd: f64,
+    /// Requests per flagged data category
+    pub flagged_categories: BTreeMap<String, u64>,
+}
+
+/// Job exporting the compliance report of a time range
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExportJob {
+    /// Job ID, derived from the time range
+    pub id: String,
+    /// Start of the time range, inclusive
+    pub start: DateTime<Utc>,
+    /// End of the time range, exclusive
+    pub end: DateTime<Utc>,
+    #[schema(value_type = Vec<String>)]
+    pub formats: Vec<ReportFileFormat>,
+    pub status: JobStatus,
+    /// Tenants with records in the time range, once listed
+    pub tenants: Option<Vec<Option<String>>>,
+    /// Tenants whose requests were exported
+    pub completed_tenants: Vec<Option<String>>,
+    /// Model usage of the completed tenants
+    pub usage: Vec<ModelUsage>,
+    /// Keys of the objects written
+    pub objects: Vec<String>,
+    /// Records exported
+    pub records: u64,
+    /// Error that stopped the job
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ExportJob {
+    /// Create a pending job for a time range
+    pub fn new(start: DateTime<Utc>, end: DateTime<Utc>, formats: Vec<ReportFileFormat>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: job_id(start, end),
+            start,
+            end,
+            formats,
+            status: JobStatus::Pending,
+            tenants: None,
+            completed_tenants: Vec::new(),
+            usage: Vec::new(),
+            objects: Vec::new(),
+            records: 0,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// ID of the job of a time range, so a range is only exported once
+fn job_id(start: DateTime<Utc>, end: DateTime<Utc>) -> String {
+    format!(
+        "{}_{}",
+        start.format("%Y%m%dT%H%M%SZ"),
+        end.format("%Y%m%dT%H%M%SZ")
+    )
+}
+
+/// Export jobs, saved as one JSON file per job
+pub struct JobStore {
+    dir: PathBuf,
+    jobs: Mutex<BTreeMap<String, ExportJob>>,
+}
+
+impl JobStore {
+    /// Open the job store in a directory, loading the saved jobs
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, ComplianceError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| ComplianceError::State(e.to_string()))?;
+
+        let mut jobs = BTreeMap::new();
+        let entries = std::fs::read_dir(&dir).map_err(|e| ComplianceError::State(e.to_string()))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            match std::fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| {
+                    serde_json::from_slice::<ExportJob>(&json).map_err(|e| e.to_string())
+                }) {
+                Ok(job) => {
+                    jobs.insert(job.id.clone(), job);
+                }
+                Err(e) => warn!("Skipping unreadable export job {:?}: {}", path, e),
+            }
+        }
+
+        Ok(Self {
+            dir,
+            jobs: Mutex::new(jobs),
+        })
+    }
+
+    /// Save a job, replacing its previous state
+    pub fn save(&self, job: &ExportJob) -> Result<(), ComplianceError> {
+        let json =
+            serde_json::to_vec_pretty(job).map_err(|e| ComplianceError::State(e.to_string()))?;
+        // Write then rename, so a crash never leaves a truncated job
+        let path = self.dir.join(format!("{}.json", job.id));
+        let partial = self.dir.join(format!("{}.json.partial", job.id));
+        std::fs::write(&partial, json)
+            .and_then(|_| std::fs::rename(&partial, &path))
+            .map_err(|e| ComplianceError::State(e.to_string()))?;
+
+        self.jobs
+            .lock()
+            .unwrap()
+            .insert(job.id.clone(), job.clone());
+        Ok(())
+    }
+
+    /// Get a job
+    pub fn get(&self, id: &str) -> Option<ExportJob> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// All jobs, oldest time range first
+    pub fn list(&self) -> Vec<ExportJob> {
+        self.jobs.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// Generator of compliance reports
+pub struct ComplianceExporter {
+    config: ComplianceConfig,
+    source: Arc<dyn RecordSource>,
+    sink: Arc<dyn ObjectSink>,
+    jobs: JobStore,
+    /// Jobs being run, so a job never runs twice at once
+    running: Mutex<HashSet<String>>,
+}
+
+impl ComplianceExporter {
+    /// Create an exporter from the configuration
+    ///
+    /// Returns `None` when no source or sink is configured.
+    pub fn from_config(config: &ComplianceConfig) -> Result<Option<Arc<Self>>, ComplianceError> {
+        let (Some(source), Some(sink)) = (&config.source, &config.sink) else {
+            return Ok(None);
+        };
+        let source = Arc::new(ClickHouseSource::new(source.clone()));
+        let sink = Arc::new(S3Sink::new(sink.clone())?);
+        Ok(Some(Arc::new(Self::new(config.clone(), source, sink)?)))
+    }
+
+    /// Create an exporter
+    pub fn new(
+        config: ComplianceConfig,
+        source: Arc<dyn RecordSource>,
+        sink: Arc<dyn ObjectSink>,
+    ) -> Result<Self, ComplianceError> {
+        Ok(Self {
+            jobs: JobStore::open(&config.state_dir)?,
+            config,
+            source,
+            sink,
+            running: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Export jobs
+    pub fn jobs(&self) -> &JobStore {
+        &self.jobs
+    }
+
+    /// Create the job of a time range and start it in the background
+    ///
+    /// A time range is only exported once: the existing job is returned
+    /// when there is one.
+    pub fn start_job(
+        self: &Arc<Self>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        formats: Option<Vec<ReportFileFormat>>,
+    ) -> Result<ExportJob, ComplianceError> {
+        if start >= end {
+            return Err(ComplianceError::InvalidRequest(
+                "start must be before end".to_string(),
+            ));
+        }
+        let formats = formats.unwrap_or_else(|| self.config.formats.clone());
+        if formats.is_empty() {
+            return Err(ComplianceError::InvalidRequest(
+                "at least one format is required".to_string(),
+            ));
+        }
+        if let Some(job) = self.jobs.get(&job_id(start, end)) {
+            return Ok(job);
+        }
+
+        let job = ExportJob::new(start, end, formats);
+        self.jobs.save(&job)?;
+        self.spawn(job.id.clone());
+        Ok(job)
+    }
+
+    /// Resume a job that failed or was interrupted
+    pub fn resume_job(self: &Arc<Self>, id: &str) -> Result<ExportJob, ComplianceError> {
+        let job = self
+            .jobs
+            .get(id)
+            .ok_or_else(|| ComplianceError::NotFound(id.to_string()))?;
+        if job.status == JobStatus::Completed {
+            return Err(ComplianceError::InvalidRequest(format!(
+                "export job {} is already completed",
+                id
+            )));
+        }
+        self.spawn(job.id.clone());
+        Ok(job)
+    }
+
+    /// Run a job in the background
+    fn spawn(self: &Arc<Self>, id: String) {
+        let exporter = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = exporter.run(&id).await {
+                error!("Compliance export job {} failed: {}", id, e);
+            }
+        });
+    }
+
+    /// Run a job to completion, skipping the tenants it already exported
+    pub async fn run(&self, id: &str) -> Result<ExportJob, ComplianceError> {
+        if !self.running.lock().unwrap().insert(id.to_string()) {
+            return Err(ComplianceError::InvalidRequest(format!(
+                "export job {} is already running",
+                id
+            )));
+        }
+        let result = self.run_job(id).await;
+        self.running.lock().unwrap().remove(id);
+        result
+    }
+
+    async fn run_job(&self, id: &str) -> Result<ExportJob, ComplianceError> {
+        let mut job = self
+            .jobs
+            .get(id)
+            .ok_or_else(|| ComplianceError::NotFound(id.to_string()))?;
+        info!(
+            "Running compliance export job {} ({} to {})",
+            job.id, job.start, job.end
+        );
+        job.status = JobStatus::Running;
+        job.error = None;
+        self.update(&mut job)?;
+
+        let result = self.export(&mut job).await;
+        match &result {
+            Ok(()) => {
+                job.status = JobStatus::Completed;
+                info!(
+                    "Compliance export job {} completed: {} records in {} objects",
+                    job.id,
+                    job.records,
+                    job.objects.len()
+                );
+            }
+            Err(e) => {
+                job.status = JobStatus::Failed;
+                job.error = Some(e.to_string());
+            }
+        }
+        self.update(&mut job)?;
+        result.map(|_| job)
+    }
+
+    /// Export the remaining tenants of a job, then its summary
+    async fn export(&self, job: &mut ExportJob) -> Result<(), ComplianceError> {
+        let tenants = match &job.tenants {
+            Some(tenants) => tenants.clone(),
+            None => {
+                let tenants = self.source.tenants(job.start, job.end).await?;
+                job.tenants = Some(tenants.clone());
+                self.update(job)?;
+                tenants
+            }
+        };
+
+        for tenant in tenants {
+            if job.completed_tenants.contains(&tenant) {
+                continue;
+            }
+            let records = self.tenant_records(tenant.as_deref(), job).await?;
+            let table = requests_table(&records);
+            let dir = format!("{}/tenants/{}", job.id, tenant_dir(tenant.as_deref()));
+            for format in job.formats.clone() {
+                let key = self.put(&dir, "requests", &table, format).await?;
+                job.objects.push(key);
+            }
+
+            job.usage.extend(model_usage(tenant.as_deref(), &records));
+            job.records += records.len() as u64;
+            job.completed_tenants.push(tenant);
+            self.update(job)?;
+        }
+
+        let summary = usage_table(&job.usage);
+        for format in job.formats.clone() {
+            let key = self.put(&job.id, "summary", &summary, format).await?;
+            if !job.objects.contains(&key) {
+                job.objects.push(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// All records of a tenant in a job's time range
+    async fn tenant_records(
+        &self,
+        tenant: Option<&str>,
+        job: &ExportJob,
+    ) -> Result<Vec<TelemetryRecord>, ComplianceError> {
+        let page_size = self.config.page_size.max(1);
+        let mut records = Vec::new();
+        loop {
+            let page = self
+                .source
+                .records(tenant, job.start, job.end, records.len(), page_size)
+                .await?;
+            let last = page.len() < page_size;
+            records.extend(page);
+            if last {
+                return Ok(records);
+            }
+        }
+    }
+
+    /// Write a table to the sink, returning its key
+    async fn put(
+        &self,
+        dir: &str,
+        name: &str,
+        table: &Table,
+        format: ReportFileFormat,
+    ) -> Result<String, ComplianceError> {
+        let key = format!("{}/{}.{}", dir, name, format.extension());
+        let content_type = match format {
+            ReportFileFormat::Csv => "text/csv",
+            ReportFileFormat::Parquet => "application/vnd.apache.parquet",
+        };
+        self.sink
+            .put(&key, table.encode(format)?, content_type)
+            .await?;
+        Ok(key)
+    }
+
+    /// Save a job's progress
+    fn update(&self, job: &mut ExportJob) -> Result<(), ComplianceError> {
+        job.updated_at = Utc::now();
+        self.jobs.save(job)
+    }
+
+    /// Resume interrupted jobs, then export every completed period when
+    /// scheduled reports are enabled
+    pub fn spawn_schedule(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let exporter = self.clone();
+        tokio::spawn(async move {
+            for job in exporter.jobs.list() {
+                if matches!(job.status, JobStatus::Pending | JobStatus::Running) {
+                    info!("Resuming interrupted compliance export job {}", job.id);
+                    exporter.spawn(job.id);
+                }
+            }
+            if !exporter.config.enabled {
+                return;
+            }
+
+            let mut interval = tokio::time::interval(SCHEDULE_INTERVAL);
+            loop {
+                interval.tick().await;
+                let (start, end) = last_period(exporter.config.period, Utc::now());
+                if exporter.jobs.get(&job_id(start, end)).is_none() {
+                    if let Err(e) = exporter.start_job(start, end, None) {
+                        error!("Failed to start scheduled compliance export: {}", e);
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Time range of the last completed report period
+pub fn last_period(period: ReportPeriod, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let today = now.date_naive();
+    let end = match period {
+        ReportPeriod::Daily => today,
+        ReportPeriod::Weekly => {
+            today - chrono::Days::new(today.weekday().num_days_from_monday().into())
+        }
+        ReportPeriod::Monthly => today.with_day(1).unwrap_or(today),
+    };
+    let start = match period {
+        ReportPeriod::Daily => end - chrono::Days::new(1),
+        ReportPeriod::Weekly => end - chrono::Days::new(7),
+        ReportPeriod::Monthly => (end - chrono::Days::new(1)).with_day(1).unwrap_or(end),
+    };
+    (midnight(start), midnight(end))
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// Directory name of a tenant's reports
+fn tenant_dir(tenant: Option<&str>) -> String {
+    match tenant {
+        Some(tenant) if !tenant.is_empty() => tenant.replace('/', "_"),
+        _ => NO_TENANT.to_string(),
+    }
+}
+
+/// Table of requests, one row per record
+pub fn requests_table(records: &[TelemetryRecord]) -> Table {
+    let mut table = Table::new(vec![
+        ("request_id", ColumnType::Str),
+        ("timestamp", ColumnType::Time),
+        ("tenant", ColumnType::Str),
+        ("user", ColumnType::Str),
+        ("requested_model", ColumnType::Str),
+        ("model", ColumnType::Str),
+        ("provider", ColumnType::Str),
+        ("prompt_tokens", ColumnType::Int),
+        ("completion_tokens", ColumnType::Int),
+        ("total_tokens", ColumnType::Int),
+        ("cost_usd", ColumnType::Float),
+        ("latency_ms", ColumnType::Int),
+        ("cache_status", ColumnType::Str),
+        ("success", ColumnType::Bool),
+        ("error", ColumnType::Str),
+        ("flagged_categories", ColumnType::Str),
+    ]);
+    for record in records {
+        let cache_status = serde_json::to_value(record.cache_status)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string));
+        table.rows.push(vec![
+            Cell::Str(record.request_id.clone()),
+            Cell::Time(record.timestamp),
+            record.tenant.clone().into(),
+            record.user.clone().into(),
+            Cell::Str(record.requested_model.clone()),
+            Cell::Str(record.model.clone()),
+            Cell::Str(record.provider.clone()),
+            Cell::Int(record.prompt_tokens.into()),
+            Cell::Int(record.completion_tokens.into()),
+            Cell::Int(record.total_tokens.into()),
+            record.cost_usd.into(),
+            Cell::Int(record.latency_ms as i64),
+            cache_status.into(),
+            Cell::Bool(record.success),
+            record.error.clone().into(),
+            Cell::Str(record.flagged_categories.join(";")),
+        ]);
+    }
+    table
+}
+
+/// Usage of each model in a tenant's records
+pub fn model_usage(tenant: Option<&str>, records: &[TelemetryRecord]) -> Vec<ModelUsage> {
+    let mut usage: BTreeMap<(&str, &str), ModelUsage> = BTreeMap::new();
+    for record in records {
+        let entry = usage
+            .entry((record.model.as_str(), record.provider.as_str()))
+            .or_insert_with(|| ModelUsage {
+                tenant: tenant.map(str::to_string),
+                model: record.model.clone(),
+                provider: record.provider.clone(),
+                requests: 0,
+                failed_requests: 0,
+                total_tokens: 0,
+                cost_usd: 0.0,
+                flagged_categories: BTreeMap::new(),
+            });
+        entry.requests += 1;
+        entry.failed_requests += u64::from(!record.success);
+        entry.total_tokens += u64::from(record.total_tokens);
+        entry.cost_usd += record.cost_usd.unwrap_or_default();
+        for category in &record.flagged_categories {
+            *entry
+                .flagged_categories
+                .entry(category.clone())
+                .or_default() += 1;
+        }
+    }
+    usage.into_values().collect()
+}
+
+/// Summary table, one row per tenant and model
+pub fn usage_table(usage: &[ModelUsage]) -> Table {
+    let mut table = Table::new(vec![
+        ("tenant", ColumnType::Str),
+        ("model", ColumnType::Str),
+        ("provider", ColumnType::Str),
+        ("requests", ColumnType::Int),
+        ("failed_requests", ColumnType::Int),
+        ("total_tokens", ColumnType::Int),
+        ("cost_usd", ColumnType::Float),
+        ("flagged_categories", ColumnType::Str),
+    ]);
+    for entry in usage {
+        let flagged: Vec<String> = entry
+            .flagged_categories
+            .iter()
+            .map(|(category, count)| format!("{}:{}", category, count))
+            .collect();
+        table.rows.push(vec![
+            entry.tenant.clone().into(),
+            Cell::Str(entry.model.clone()),
+            Cell::Str(entry.provider.clone()),
+            Cell::Int(entry.requests as i64),
+            Cell::Int(entry.failed_requests as i64),
+            Cell::Int(entry.total_tokens as i64),
+            Cell::Float(entry.cost_usd),
+            Cell::Str(flagged.join(";")),
+        ]);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::telemetry::CacheStatus;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn record(id: &str, tenant: &str, model: &str, flagged: &[&str]) -> TelemetryRecord {
+        TelemetryRecord {
+            request_id: id.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap(),
+            tenant: Some(tenant.to_string()),
+            user: None,
+            requested_model: model.to_string(),
+            model: model.to_string(),
+            provider: "openai".to_string(),
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+            cost_usd: Some(0.01),
+            latency_ms: 100,
+            cache_status: CacheStatus::Bypass,
+            success: true,
+            error: None,
+            flagged_categories: flagged.iter().map(|c| c.to_string()).collect(),
+            language: None,
+        }
+    }
+
+    struct MemorySource(Vec<TelemetryRecord>);
+
+    #[async_trait]
+    impl RecordSource for MemorySource {
+        async fn tenants(
+            &self,
+            _start: DateTime<Utc>,
+            _end: DateTime<Utc>,
+        ) -> Result<Vec<Option<String>>, ComplianceError> {
+            let mut tenants: Vec<_> = self.0.iter().map(|r| r.tenant.clone()).collect();
+            tenants.dedup();
+            Ok(tenants)
+        }
+
+        async fn records(
+            &self,
+            tenant: Option<&str>,
+            _start: DateTime<Utc>,
+            _end: DateTime<Utc>,
+            offset: usize,
+            limit: usize,
+        ) -> Result<Vec<TelemetryRecord>, ComplianceError> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|r| r.tenant.as_deref() == tenant)
+                .skip(offset)
+                .take(limit)
+                .cloned()
+                .collect())
+        }
+    }
+
+    /// Sink failing its `fail_at`th write
+    #[derive(Default)]
+    struct MemorySink {
+        objects: Mutex<BTreeMap<String, Vec<u8>>>,
+        writes: AtomicUsize,
+        fail_at: usize,
+    }
+
+    #[async_trait]
+    impl ObjectSink for MemorySink {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn put(
+            &self,
+            key: &str,
+            body: Vec<u8>,
+            _content_type: &str,
+        ) -> Result<(), ComplianceError> {
+            if self.writes.fetch_add(1, Ordering::SeqCst) + 1 == self.fail_at {
+                return Err(ComplianceError::Sink("unavailable".to_string()));
+            }
+            self.objects.lock().unwrap().insert(key.to_string(), body);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_job_resumes_with_remaining_tenants() {
+        let state_dir = tempfile::tempdir().unwrap();
+        let config = ComplianceConfig {
+            formats: vec![ReportFileFormat::Csv],
+            state_dir: state_dir.path().to_string_lossy().into_owned(),
+            page_size: 1,
+            ..ComplianceConfig::default()
+        };
+        let source = Arc::new(MemorySource(vec![
+            record("1", "acme", "gpt-4o", &["pii"]),
+            record("2", "acme", "gpt-4o", &[]),
+            record("3", "globex", "claude-3-haiku", &["pii", "secrets"]),
+        ]));
+        let sink = Arc::new(MemorySink {
+            fail_at: 2,
+            ..MemorySink::default()
+        });
+        let exporter =
+            ComplianceExporter::new(config.clone(), source.clone(), sink.clone()).unwrap();
+
+        let (start, end) = last_period(
+            ReportPeriod::Daily,
+            Utc.with_ymd_and_hms(2026, 10, 17, 8, 0, 0).unwrap(),
+        );
+        let job = ExportJob::new(start, end, config.formats.clone());
+        exporter.jobs().save(&job).unwrap();
+
+        // The write of the second tenant's requests fails
+        assert!(exporter.run(&job.id).await.is_err());
+        let failed = exporter.jobs().get(&job.id).unwrap();
+        assert_eq!(failed.status, JobStatus::Failed);
+        assert_eq!(failed.completed_tenants, vec![Some("acme".to_string())]);
+
+        // A new exporter finds the saved job and only exports what is left
+        let exporter = ComplianceExporter::new(config, source, sink.clone()).unwrap();
+        let job = exporter.run(&job.id).await.unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.records, 3);
+        assert_eq!(sink.writes.load(Ordering::SeqCst), 4);
+
+        let objects = sink.objects.lock().unwrap();
+        let summary =
+            String::from_utf8(objects[&format!("{}/summary.csv", job.id)].clone()).unwrap();
+        assert_eq!(
+            summary.lines().collect::<Vec<_>>(),
+            vec![
+                "tenant,model,provider,requests,failed_requests,total_tokens,cost_usd,flagged_categories",
+                "acme,gpt-4o,openai,2,0,30,0.02,pii:1",
+                "globex,claude-3-haiku,openai,1,0,15,0.01,pii:1;secrets:1",
+            ]
+        );
+        assert!(objects.contains_key(&format!("{}/tenants/acme/requests.csv", job.id)));
+    }
+
+    #[test]
+    fn test_last_period() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 8, 30, 0).unwrap();
+        let day = |d: u32, m: u32| Utc.with_ymd_and_hms(2026, m, d, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            last_period(ReportPeriod::Daily, now),
+            (day(16, 10), day(17, 10))
+        );
+        // 2026-10-17 is a Saturday
+        assert_eq!(
+            last_period(ReportPeriod::Weekly, now),
+            (day(5, 10), day(12, 10))
+        );
+        assert_eq!(
+            last_period(ReportPeriod::Monthly, now),
+            (day(1, 9), day(1, 10))
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Report Sources
+//!
+//! Compliance reports are built from the per-request telemetry records the
+//! router exports. The ClickHouse source queries the table written by the
+//! telemetry exporter's ClickHouse sink.
+
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::Client;
+
+use super::ComplianceError;
+use crate::config::ClickHouseExportConfig;
+use crate::modules::telemetry::TelemetryRecord;
+
+/// Store of exported telemetry records
+#[async_trait]
+pub trait RecordSource: Send + Sync {
+    /// Tenants with records in `[start, end)`; `None` stands for records
+    /// without a tenant
+    async fn tenants(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Option<String>>, ComplianceError>;
+
+    /// A page of a tenant's records in `[start, end)`, ordered by time
+    async fn records(
+        &self,
+        tenant: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TelemetryRecord>, ComplianceError>;
+}
+
+/// Source querying a ClickHouse table through the HTTP interface
+pub struct ClickHouseSource {
+    client: Client,
+    config: ClickHouseExportConfig,
+}
+
+impl ClickHouseSource {
+    /// Create a ClickHouse source
+    pub fn new(config: ClickHouseExportConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+
+    /// Run a query returning `JSONEachRow` rows, binding its parameters
+    async fn query(
+        &self,
+        query: String,
+        params: &[(&str, String)],
+    ) -> Result<Vec<serde_json::Value>, ComplianceError> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .query(&[("date_time_output_format", "iso")])
+            .query(
+                &params
+                    .iter()
+                    .map(|(name, value)| (format!("param_{}", name), value))
+                    .collect::<Vec<_>>(),
+            )
+            .body(query);
+        if let Some(username) = &self.config.username {
+            request = request.header("X-ClickHouse-User", username);
+        }
+        if let Some(password) = &self.config.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ComplianceError::Source(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ComplianceError::Source(format!("{}: {}", status, body)));
+        }
+        let body = response
+            .text()
+            .await
+            .map_err(|e| ComplianceError::Source(e.to_string()))?;
+        body.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| ComplianceError::Source(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Table holding the records
+    fn table(&self) -> String {
+        format!("{}.{}", self.config.database, self.config.table)
+    }
+}
+
+/// Query parameters of a time range
+fn range_params(start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(&'static str, String)> {
+    vec![
+        ("start", start.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        ("end", end.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    ]
+}
+
+/// Condition selecting the records of a time range
+const RANGE_CONDITION: &str = "timestamp >= parseDateTime64BestEffort({start:String}, 3) \
+     AND timestamp < parseDateTime64BestEffort({end:String}, 3)";
+
+#[async_trait]
+impl RecordSource for ClickHouseSource {
+    async fn tenants(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Option<String>>, ComplianceError> {
+        let query = format!(
+            "SELECT DISTINCT tenant FROM {} WHERE {} ORDER BY tenant FORMAT JSONEachRow",
+            self.table(),
+            RANGE_CONDITION
+        );
+        let rows = self.query(query, &range_params(start, end)).await?;
+        Ok(rows
+            .iter()
+            .map(|row| row["tenant"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn records(
+        &self,
+        tenant: Option<&str>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TelemetryRecord>, ComplianceError> {
+        let mut params = range_params(start, end);
+        let tenant_condition = match tenant {
+            Some(tenant) => {
+                params.push(("tenant", tenant.to_string()));
+                "tenant = {tenant:String}"
+            }
+            None => "tenant IS NULL",
+        };
+        let query = format!(
+            "SELECT * FROM {} WHERE {} AND {} ORDER BY timestamp, request_id \
+             LIMIT {} OFFSET {} FORMAT JSONEachRow",
+            self.table(),
+            RANGE_CONDITION,
+            tenant_condition,
+            limit,
+            offset
+        );
+        self.query(query, &params)
+            .await?
+            .into_iter()
+            .map(|row| {
+                serde_json::from_value(row).map_err(|e| ComplianceError::Source(e.to_string()))
+            })
+            .collect()
+    }
+}
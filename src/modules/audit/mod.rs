@@ -11,10 +11,12 @@
 //! - Executing test flows to verify system integration
 //! - Collecting metrics on system performance
 //! - Providing comprehensive logging of the audit process
+//! - Generating compliance reports of the requests served
 
 mod boot_orchestrator;
 mod cli;
 mod communication_tests;
+pub mod compliance;
 mod metrics;
 mod report;
 mod reporting;
@@ -6,6 +6,7 @@ use std::sync::Arc;
 
 use super::auth::{AuthContext, AuthManager};
 use super::rbac::{check_permission, RbacManager};
+use crate::modules::llm_proxy::ApiVersion;
 
 pub async fn auth_middleware(
     State(auth_manager): State<Arc<AuthManager>>,
@@ -36,12 +37,13 @@ pub async fn auth_middleware(
     // Create auth context
     let auth_context = AuthContext { api_key: key };
 
-    // Extract required permission from request path
+    // Extract required permission from request path, whatever its API version
     let path = request.uri().path();
-    let permission = match path {
-        "/v1/chat/completions" => "execute:chat",
-        "/v1/models" => "read:models",
-        "/v1/chains" => "execute:chains",
+    let route = ApiVersion::split(path).map_or(path, |(_, route)| route);
+    let permission = match route {
+        "/chat/completions" => "execute:chat",
+        "/models" => "read:models",
+        "/chains" => "execute:chains",
         _ => "access:api",
     };
 
@@ -0,0 +1,164 @@
+//! Bundle API
+//!
+//! This module exports and imports deployment bundles over HTTP. Exporting
+//! needs the operator admin role and importing the admin role; imports,
+//! other than dry runs, are recorded as audit events.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::{IntoParams, OpenApi};
+
+use super::{BundleDiff, BundleError, Deployment, SignedBundle};
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{self, AdminAuditLog, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// State of the bundle API
+struct BundleApiState {
+    deployment: Arc<Deployment>,
+    /// Admin keys and the bundle signing key
+    proxy: ProxyConfig,
+    /// Audit events of imports
+    audit: Arc<AdminAuditLog>,
+}
+
+/// OpenAPI description of the bundle API
+#[derive(OpenApi)]
+#[openapi(
+    paths(export_bundle, import_bundle),
+    components(schemas(SignedBundle, BundleDiff, super::SectionDiff))
+)]
+pub struct BundleApiDoc;
+
+/// Create a router for the bundle API
+pub fn create_bundle_router(
+    deployment: Arc<Deployment>,
+    proxy: ProxyConfig,
+    audit: Arc<AdminAuditLog>,
+) -> Router {
+    let state = Arc::new(BundleApiState {
+        deployment,
+        proxy,
+        audit,
+    });
+    Router::new()
+        .route("/v1/admin/bundle", get(export_bundle))
+        .route("/v1/admin/bundle/import", post(import_bundle))
+        .with_state(state)
+}
+
+/// Options of a bundle import
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct ImportQuery {
+    /// Only report the differences
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Map a bundle error to an error response
+fn bundle_error(e: &BundleError) -> Response {
+    let (status, code) = match e {
+        BundleError::SigningKeyMissing => (StatusCode::SERVICE_UNAVAILABLE, "bundle_disabled"),
+        BundleError::InvalidSignature => (StatusCode::BAD_REQUEST, "invalid_signature"),
+        BundleError::UnsupportedVersion(_) | BundleError::Invalid(_) => {
+            (StatusCode::BAD_REQUEST, "invalid_bundle")
+        }
+        BundleError::Serialization(_) => (StatusCode::BAD_REQUEST, "invalid_bundle"),
+        BundleError::Io(_) | BundleError::Apply(_) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, "bundle_apply_failed")
+        }
+    };
+    let body = json!({
+        "error": {
+            "message": e.to_string(),
+            "type": "invalid_request_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Export the deployment's configuration as a signed bundle
+#[utoipa::path(
+    get,
+    path = "/v1/admin/bundle",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Signed bundle", body = SignedBundle),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "No bundle signing key is configured", body = ApiError)
+    )
+)]
+async fn export_bundle(State(state): State<Arc<BundleApiState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    let Some(key) = state.proxy.bundle.signing_key() else {
+        return bundle_error(&BundleError::SigningKeyMissing);
+    };
+    match SignedBundle::sign(&state.deployment.export(), &key) {
+        Ok(bundle) => Json(bundle).into_response(),
+        Err(e) => bundle_error(&e),
+    }
+}
+
+/// Import a signed bundle, or compare it with the deployment on a dry run
+#[utoipa::path(
+    post,
+    path = "/v1/admin/bundle/import",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(ImportQuery),
+    request_body = SignedBundle,
+    responses(
+        (status = 200, description = "Differences, applied unless `dry_run` is set", body = Object),
+        (status = 400, description = "Invalid bundle or signature", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "No bundle signing key is configured", body = ApiError)
+    )
+)]
+async fn import_bundle(
+    State(state): State<Arc<BundleApiState>>,
+    headers: HeaderMap,
+    Query(query): Query<ImportQuery>,
+    Json(bundle): Json<SignedBundle>,
+) -> Response {
+    let action = "bundle.import";
+    let principal = match admin::authorize(&state.proxy, &headers, AdminRole::Admin) {
+        Ok(principal) => principal,
+        Err(e) => {
+            state.audit.record(Err(&e), action, "bundle", Ok(()));
+            return e.into_response();
+        }
+    };
+    let Some(key) = state.proxy.bundle.signing_key() else {
+        return bundle_error(&BundleError::SigningKeyMissing);
+    };
+
+    let result = bundle
+        .verify(&key)
+        .and_then(|bundle| state.deployment.import(bundle, query.dry_run));
+    if !query.dry_run {
+        state.audit.record(
+            Ok(&principal),
+            action,
+            "bundle",
+            result.as_ref().map(|_| ()).map_err(|e| e.to_string()),
+        );
+    }
+    match result {
+        Ok(diff) => Json(json!({ "dry_run": query.dry_run, "diff": diff })).into_response(),
+        Err(e) => bundle_error(&e),
+    }
+}
@@ -0,0 +1,555 @@
+//! Deployment Bundle Module
+//!
+//! This module snapshots the configuration of a deployment into a single
+//! signed bundle, so it can be promoted between environments, e.g. from
+//! staging to production. A bundle holds the registered models, the
+//! personas with their guardrails, the chain definitions of the configured
+//! schedules and webhook triggers, and the active routing policy.
+//!
+//! Bundles are signed with an HMAC key shared by the deployments exchanging
+//! them. Importing a bundle verifies the signature, then adds or updates
+//! what differs; nothing missing from the bundle is removed. A dry run
+//! reports the differences without applying them.
+
+pub mod api;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::modules::chain_engine::Chain;
+use crate::modules::model_registry::storage::ModelRegistry;
+use crate::modules::model_registry::ModelMetadata;
+use crate::modules::persona_layer::{Persona, PersonaDirectory};
+use crate::modules::router_core::{PolicyEngine, RoutingPolicy};
+
+/// Version of the bundle format
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// Model fields that describe runtime state rather than configuration, and
+/// are ignored when comparing models
+const MODEL_RUNTIME_FIELDS: &[&str] = &["created_at", "updated_at", "last_checked"];
+
+/// Errors that can occur when exporting or importing a bundle
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Bundle signing key is not configured")]
+    SigningKeyMissing,
+
+    #[error("Bundle signature is invalid")]
+    InvalidSignature,
+
+    #[error("Unsupported bundle format version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid bundle: {0}")]
+    Invalid(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Failed to apply bundle: {0}")]
+    Apply(String),
+}
+
+/// Configuration of a deployment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentBundle {
+    /// Version of the bundle format
+    pub format_version: u32,
+    /// Time the bundle was exported
+    pub created_at: DateTime<Utc>,
+    /// Registered models, without their credentials
+    #[serde(default)]
+    pub models: Vec<ModelMetadata>,
+    /// Personas, including their guardrails
+    #[serde(default)]
+    pub personas: Vec<Persona>,
+    /// Chain definitions by chain file path
+    #[serde(default)]
+    pub chains: BTreeMap<String, Value>,
+    /// Active routing policy
+    #[serde(default)]
+    pub policy: Option<RoutingPolicy>,
+}
+
+/// A bundle and its signature, as exported
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SignedBundle {
+    /// The bundle
+    #[schema(value_type = Object)]
+    pub bundle: Value,
+    /// Hex-encoded HMAC-SHA256 of the bundle's canonical JSON
+    pub signature: String,
+}
+
+impl SignedBundle {
+    /// Sign a bundle
+    pub fn sign(bundle: &DeploymentBundle, key: &str) -> Result<Self, BundleError> {
+        let bundle = serde_json::to_value(bundle)?;
+        let signature = hex::encode(hmac::sign(&signing_key(key), &canonical(&bundle)?).as_ref());
+        Ok(Self { bundle, signature })
+    }
+
+    /// Verify the signature and decode the bundle
+    pub fn verify(&self, key: &str) -> Result<DeploymentBundle, BundleError> {
+        let signature = hex::decode(&self.signature).map_err(|_| BundleError::InvalidSignature)?;
+        hmac::verify(&signing_key(key), &canonical(&self.bundle)?, &signature)
+            .map_err(|_| BundleError::InvalidSignature)?;
+
+        let version = self
+            .bundle
+            .get("format_version")
+            .and_then(Value::as_u64)
+            .unwrap_or_default() as u32;
+        if version != BUNDLE_FORMAT_VERSION {
+            return Err(BundleError::UnsupportedVersion(version));
+        }
+        Ok(serde_json::from_value(self.bundle.clone())?)
+    }
+}
+
+/// Build the HMAC key bundles are signed with
+fn signing_key(key: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes())
+}
+
+/// Canonical JSON of a bundle: objects have sorted keys and no whitespace
+fn canonical(value: &Value) -> Result<Vec<u8>, BundleError> {
+    Ok(serde_json::to_vec(value)?)
+}
+
+/// Differences of one kind of configuration between a deployment and a
+/// bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SectionDiff {
+    /// In the bundle only; added on import
+    pub added: Vec<String>,
+    /// Different in the bundle; updated on import
+    pub changed: Vec<String>,
+    /// Identical in both
+    pub unchanged: Vec<String>,
+    /// In the deployment only; kept on import
+    pub only_in_deployment: Vec<String>,
+    /// In the bundle but not applicable to the deployment; ignored on import
+    pub skipped: Vec<String>,
+}
+
+impl SectionDiff {
+    /// Compare items by key, after normalizing them to JSON
+    fn compare(current: BTreeMap<String, Value>, incoming: BTreeMap<String, Value>) -> Self {
+        let mut diff = Self::default();
+        for (key, value) in &incoming {
+            match current.get(key) {
+                None => diff.added.push(key.clone()),
+                Some(existing) if existing != value => diff.changed.push(key.clone()),
+                Some(_) => diff.unchanged.push(key.clone()),
+            }
+        }
+        diff.only_in_deployment = current
+            .keys()
+            .filter(|key| !incoming.contains_key(*key))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Keys of the items an import writes
+    fn to_apply(&self) -> BTreeSet<&str> {
+        self.added
+            .iter()
+            .chain(&self.changed)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// Differences between a deployment and a bundle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BundleDiff {
+    pub models: SectionDiff,
+    pub personas: SectionDiff,
+    pub chains: SectionDiff,
+    /// Keyed by policy name
+    pub policy: SectionDiff,
+}
+
+impl BundleDiff {
+    /// Compare a deployment's configuration with a bundle
+    ///
+    /// Chains whose file is not configured in the deployment are skipped.
+    pub fn compare(
+        current: &DeploymentBundle,
+        incoming: &DeploymentBundle,
+        chain_files: &[String],
+    ) -> Result<Self, BundleError> {
+        let mut chains = SectionDiff::compare(
+            current.chains.clone(),
+            incoming
+                .chains
+                .iter()
+                .filter(|(path, _)| chain_files.contains(path))
+                .map(|(path, chain)| (path.clone(), chain.clone()))
+                .collect(),
+        );
+        chains.skipped = incoming
+            .chains
+            .keys()
+            .filter(|path| !chain_files.contains(path))
+            .cloned()
+            .collect();
+
+        Ok(Self {
+            models: SectionDiff::compare(
+                model_values(&current.models)?,
+                model_values(&incoming.models)?,
+            ),
+            personas: SectionDiff::compare(
+                keyed(&current.personas, |p| p.id.clone())?,
+                keyed(&incoming.personas, |p| p.id.clone())?,
+            ),
+            chains,
+            policy: SectionDiff::compare(
+                keyed(current.policy.as_slice(), |p| p.name.clone())?,
+                keyed(incoming.policy.as_slice(), |p| p.name.clone())?,
+            ),
+        })
+    }
+}
+
+/// Normalize items to JSON, keyed by ID
+fn keyed<T: Serialize>(
+    items: &[T],
+    key: impl Fn(&T) -> String,
+) -> Result<BTreeMap<String, Value>, BundleError> {
+    items
+        .iter()
+        .map(|item| Ok((key(item), serde_json::to_value(item)?)))
+        .collect()
+}
+
+/// Normalize models to JSON without their runtime state, keyed by ID
+fn model_values(models: &[ModelMetadata]) -> Result<BTreeMap<String, Value>, BundleError> {
+    let mut values = keyed(models, |m| m.id.clone())?;
+    for value in values.values_mut() {
+        if let Value::Object(fields) = value {
+            for field in MODEL_RUNTIME_FIELDS {
+                fields.remove(*field);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// The live configuration of a deployment that bundles are exported from
+/// and imported into
+pub struct Deployment {
+    registry: Arc<ModelRegistry>,
+    policies: Arc<PolicyEngine>,
+    personas: Arc<PersonaDirectory>,
+    /// Chain files of the configured schedules and webhook triggers
+    chain_files: Vec<String>,
+}
+
+impl Deployment {
+    /// Create a deployment from its components
+    pub fn new(
+        registry: Arc<ModelRegistry>,
+        policies: Arc<PolicyEngine>,
+        personas: Arc<PersonaDirectory>,
+        config: &Config,
+    ) -> Self {
+        let chain_engine = &config.chain_engine;
+        let chain_files = chain_engine
+            .schedules
+            .iter()
+            .map(|s| s.chain_file.clone())
+            .chain(
+                chain_engine
+                    .webhooks
+                    .triggers
+                    .iter()
+                    .map(|t| t.chain_file.clone()),
+            )
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        Self {
+            registry,
+            policies,
+            personas,
+            chain_files,
+        }
+    }
+
+    /// Snapshot the deployment's configuration
+    pub fn export(&self) -> DeploymentBundle {
+        let mut models = self.registry.list_models();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        let mut personas = self.personas.list();
+        personas.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut chains = BTreeMap::new();
+        for path in &self.chain_files {
+            let chain = std::fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()));
+            match chain {
+                Ok(chain) => {
+                    chains.insert(path.clone(), chain);
+                }
+                Err(e) => warn!("Chain file {} not exported: {}", path, e),
+            }
+        }
+
+        DeploymentBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            created_at: Utc::now(),
+            models,
+            personas,
+            chains,
+            policy: self.policies.active_policy(),
+        }
+    }
+
+    /// Compare a bundle with the deployment and, unless `dry_run` is set,
+    /// apply the differences
+    ///
+    /// The whole bundle is validated before anything is applied. Updated
+    /// models keep their credentials, creation time and health state.
+    /// Personas take effect for routing when the router restarts, and chain
+    /// definitions when the orchestrator restarts.
+    pub fn import(
+        &self,
+        bundle: DeploymentBundle,
+        dry_run: bool,
+    ) -> Result<BundleDiff, BundleError> {
+        let diff = BundleDiff::compare(&self.export(), &bundle, &self.chain_files)?;
+
+        let chains_to_apply = diff.chains.to_apply();
+        let mut chains = Vec::new();
+        for (path, chain) in &bundle.chains {
+            if chains_to_apply.contains(path.as_str()) {
+                serde_json::from_value::<Chain>(chain.clone())
+                    .map_err(|e| BundleError::Invalid(format!("chain {}: {}", path, e)))?;
+                chains.push((path, serde_json::to_string_pretty(chain)?));
+            }
+        }
+        let policy = bundle
+            .policy
+            .filter(|policy| diff.policy.to_apply().contains(policy.name.as_str()));
+        if let Some(policy) = &policy {
+            PolicyEngine::with_policy(policy.clone())
+                .map_err(|e| BundleError::Invalid(format!("policy {}: {}", policy.name, e)))?;
+        }
+        if dry_run {
+            return Ok(diff);
+        }
+
+        let models_to_apply = diff.models.to_apply();
+        for mut model in bundle.models {
+            if !models_to_apply.contains(model.id.as_str()) {
+                continue;
+            }
+            let result = match self.registry.get_model(&model.id) {
+                Ok(existing) => {
+                    model.auth_key = existing.auth_key;
+                    model.created_at = existing.created_at;
+                    model.last_checked = existing.last_checked;
+                    model.updated_at = Utc::now();
+                    self.registry.update_model(model)
+                }
+                Err(_) => self.registry.register_model(model),
+            };
+            result.map_err(|e| BundleError::Apply(e.to_string()))?;
+        }
+
+        let personas_to_apply = diff.personas.to_apply();
+        let personas: Vec<Persona> = bundle
+            .personas
+            .into_iter()
+            .filter(|p| personas_to_apply.contains(p.id.as_str()))
+            .collect();
+        if !personas.is_empty() {
+            self.personas
+                .upsert(personas)
+                .map_err(|e| BundleError::Apply(e.to_string()))?;
+        }
+
+        for (path, json) in chains {
+            std::fs::write(path, json)?;
+        }
+
+        if let Some(policy) = policy {
+            self.policies
+                .publish(policy)
+                .map_err(|e| BundleError::Apply(e.to_string()))?;
+        }
+
+        info!(
+            "Imported bundle: {} models, {} personas, {} chains and {} policies added or updated",
+            models_to_apply.len(),
+            personas_to_apply.len(),
+            chains_to_apply.len(),
+            diff.policy.to_apply().len()
+        );
+        Ok(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::persona_layer::create_persona;
+
+    fn model(id: &str, endpoint: &str) -> ModelMetadata {
+        ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "openai".to_string(),
+            "1".to_string(),
+            endpoint.to_string(),
+        )
+    }
+
+    fn bundle() -> DeploymentBundle {
+        DeploymentBundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            created_at: Utc::now(),
+            models: vec![model("gpt-4o", "https://api.openai.com/v1")],
+            personas: vec![create_persona("support", "Support agent", "Be helpful.")],
+            chains: BTreeMap::new(),
+            policy: None,
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signed = SignedBundle::sign(&bundle(), "secret").unwrap();
+        let verified = signed.verify("secret").unwrap();
+        assert_eq!(verified.models[0].id, "gpt-4o");
+        assert!(matches!(
+            signed.verify("other"),
+            Err(BundleError::InvalidSignature)
+        ));
+
+        let mut tampered = signed.clone();
+        tampered.bundle["models"][0]["endpoint"] = Value::from("https://evil.example.com");
+        assert!(matches!(
+            tampered.verify("secret"),
+            Err(BundleError::InvalidSignature)
+        ));
+
+        // The signature survives a round trip through the archive file
+        let archive = serde_json::to_string_pretty(&signed).unwrap();
+        let reloaded: SignedBundle = serde_json::from_str(&archive).unwrap();
+        assert!(reloaded.verify("secret").is_ok());
+    }
+
+    #[test]
+    fn test_diff_ignores_model_runtime_state() {
+        let current = bundle();
+        let mut incoming = bundle();
+        incoming.models[0].updated_at = Utc::now() + chrono::Duration::hours(1);
+        incoming
+            .models
+            .push(model("claude-3", "https://api.anthropic.com"));
+        incoming.personas[0].system_prompt_template = "Be concise.".to_string();
+        incoming
+            .chains
+            .insert("chains/report.json".to_string(), Value::from("{}"));
+
+        let diff = BundleDiff::compare(&current, &incoming, &[]).unwrap();
+        assert_eq!(diff.models.added, vec!["claude-3"]);
+        assert_eq!(diff.models.unchanged, vec!["gpt-4o"]);
+        assert_eq!(diff.personas.changed, vec!["support"]);
+        assert_eq!(diff.chains.skipped, vec!["chains/report.json"]);
+        assert!(diff.chains.added.is_empty());
+    }
+
+    #[test]
+    fn test_import_applies_differences() {
+        let dir = tempfile::tempdir().unwrap();
+        let chain_file = dir.path().join("chain.json").display().to_string();
+        let mut config = Config::default();
+        config
+            .chain_engine
+            .schedules
+            .push(crate::config::ChainScheduleConfig {
+                id: "nightly".to_string(),
+                cron: "0 0 * * *".to_string(),
+                chain_file: chain_file.clone(),
+                inputs: Default::default(),
+                overlap: Default::default(),
+                max_queued: 10,
+                jitter_secs: 0,
+                enabled: true,
+            });
+
+        let registry = Arc::new(ModelRegistry::new());
+        let mut existing = model("gpt-4o", "https://old.example.com");
+        existing.auth_key = Some("sk-target".to_string());
+        registry.register_model(existing).unwrap();
+        let deployment = Deployment::new(
+            registry.clone(),
+            Arc::new(PolicyEngine::new()),
+            Arc::new(PersonaDirectory::new(
+                dir.path().join("personas"),
+                Vec::new(),
+            )),
+            &config,
+        );
+
+        let mut incoming = bundle();
+        incoming.models[0].endpoint = "https://api.openai.com/v1".to_string();
+        incoming.chains.insert(
+            chain_file.clone(),
+            serde_json::json!({
+                "id": "report",
+                "name": "Report",
+                "description": "",
+                "version": "1",
+                "steps": {}
+            }),
+        );
+        incoming.policy = Some(RoutingPolicy {
+            name: "default".to_string(),
+            ..Default::default()
+        });
+
+        let diff = deployment.import(incoming.clone(), true).unwrap();
+        assert_eq!(diff.models.changed, vec!["gpt-4o"]);
+        assert_eq!(diff.chains.added, vec![chain_file.clone()]);
+        assert_eq!(diff.policy.added, vec!["default"]);
+        assert_eq!(
+            registry.get_model("gpt-4o").unwrap().endpoint,
+            "https://old.example.com"
+        );
+        assert!(!std::path::Path::new(&chain_file).exists());
+
+        deployment.import(incoming.clone(), false).unwrap();
+        let updated = registry.get_model("gpt-4o").unwrap();
+        assert_eq!(updated.endpoint, "https://api.openai.com/v1");
+        assert_eq!(updated.auth_key.as_deref(), Some("sk-target"));
+        assert!(std::path::Path::new(&chain_file).exists());
+        assert_eq!(deployment.personas.list().len(), 1);
+        assert_eq!(deployment.policies.active_policy().unwrap().name, "default");
+
+        let diff = deployment.import(incoming, true).unwrap();
+        assert_eq!(diff.models.unchanged, vec!["gpt-4o"]);
+        assert_eq!(diff.personas.unchanged, vec!["support"]);
+        assert_eq!(diff.chains.unchanged, vec![chain_file]);
+        assert_eq!(diff.policy.unchanged, vec!["default"]);
+    }
+}
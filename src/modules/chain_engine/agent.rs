@@ -0,0 +1,564 @@
+//! Agent Loop
+//!
+//! This module provides a ReAct-style agent: the model reasons about a task,
+//! requests tool calls, observes their results and repeats until it produces
+//! a final answer or a stop condition (stop phrase, step budget, tool call
+//! budget or timeout) is hit. Every iteration is captured in a trace.
+//!
+//! Agents run standalone through [`AgentRuntime::run`] or as `Agent` steps of
+//! a chain.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::modules::chain_engine::budget::StepUsage;
+use crate::modules::chain_engine::conversation::{
+    run_conversation, ConversationDefinition, ConversationTranscript,
+};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::memory::MemoryManager;
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatMessage, MessageRole, ModelConnector,
+};
+use crate::modules::telemetry::CostCalculator;
+use crate::modules::tools::{ToolInvocation, ToolRegistry};
+
+/// Default number of runs kept for trace lookup
+const DEFAULT_RUN_HISTORY: usize = 100;
+
+/// Definition of an agent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentDefinition {
+    /// Agent identifier, used in traces
+    #[serde(default = "default_agent_id")]
+    pub id: String,
+    /// Model the agent reasons with
+    pub model: String,
+    /// Instructions prepended to the conversation
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Tools the agent may call (none if empty)
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Maximum number of model calls
+    #[serde(default = "default_max_steps")]
+    pub max_steps: u32,
+    /// Maximum number of tool calls across the run
+    #[serde(default)]
+    pub max_tool_calls: Option<u32>,
+    /// Phrases marking the final answer, e.g. `FINAL ANSWER:`
+    ///
+    /// Text after the phrase becomes the answer, even if the model also
+    /// requested tools.
+    #[serde(default)]
+    pub stop_phrases: Vec<String>,
+    /// Wall-clock budget for the run, in seconds
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Sampling temperature
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// Number of conversation messages loaded from memory
+    #[serde(default = "default_memory_window")]
+    pub memory_window: usize,
+}
+
+fn default_agent_id() -> String {
+    "agent".to_string()
+}
+
+fn default_max_steps() -> u32 {
+    10
+}
+
+fn default_memory_window() -> usize {
+    20
+}
+
+/// Why an agent run stopped
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentStopReason {
+    /// The model answered without requesting tools
+    FinalAnswer,
+    /// The model emitted a stop phrase
+    StopPhrase,
+    /// The step budget was exhausted
+    MaxSteps,
+    /// The tool call budget was exhausted
+    MaxToolCalls,
+    /// The time budget was exhausted
+    Timeout,
+}
+
+/// One reasoning iteration of an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentStep {
+    /// Iteration number, starting at 1
+    pub index: u32,
+    /// Text produced by the model in this iteration
+    pub thought: String,
+    /// Tool calls made in this iteration
+    pub actions: Vec<ToolInvocation>,
+    /// Tokens used by the model call, if reported
+    pub total_tokens: Option<u32>,
+    /// Iteration duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Trace of an agent run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentRun {
+    pub run_id: String,
+    pub agent_id: String,
+    pub input: String,
+    /// Final answer, absent when a budget was exhausted first
+    pub answer: Option<String>,
+    pub stop_reason: AgentStopReason,
+    pub steps: Vec<AgentStep>,
+    /// Tokens used across all model calls
+    pub total_tokens: u32,
+    /// Usage of the model calls, priced by the runtime's cost calculator
+    #[serde(default)]
+    pub usage: StepUsage,
+    /// Conversation the run was recorded in
+    pub conversation_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// Runs agents against a model connector, tools and conversation memory
+pub struct AgentRuntime {
+    connector: Arc<dyn ModelConnector>,
+    tools: Arc<ToolRegistry>,
+    memory: Option<Arc<MemoryManager>>,
+    cost_calculator: Option<Arc<CostCalculator>>,
+    runs: RwLock<VecDeque<AgentRun>>,
+    transcripts: RwLock<VecDeque<ConversationTranscript>>,
+    run_history: usize,
+}
+
+impl std::fmt::Debug for AgentRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentRuntime")
+            .field("provider", &self.connector.provider_name())
+            .field("tools", &self.tools.names())
+            .field("memory", &self.memory.is_some())
+            .finish()
+    }
+}
+
+impl AgentRuntime {
+    /// Create an agent runtime
+    pub fn new(connector: Arc<dyn ModelConnector>, tools: Arc<ToolRegistry>) -> Self {
+        Self {
+            connector,
+            tools,
+            memory: None,
+            cost_calculator: None,
+            runs: RwLock::new(VecDeque::new()),
+            transcripts: RwLock::new(VecDeque::new()),
+            run_history: DEFAULT_RUN_HISTORY,
+        }
+    }
+
+    /// Load and record conversations in the given memory
+    pub fn with_memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Price the model calls of runs with the given calculator
+    pub fn with_cost_calculator(mut self, calculator: Arc<CostCalculator>) -> Self {
+        self.cost_calculator = Some(calculator);
+        self
+    }
+
+    /// Tools available to agents
+    pub fn tools(&self) -> &Arc<ToolRegistry> {
+        &self.tools
+    }
+
+    /// Set the number of runs and conversation transcripts kept for lookup
+    pub fn with_run_history(mut self, run_history: usize) -> Self {
+        self.run_history = run_history;
+        self
+    }
+
+    /// Get a recent run by ID
+    pub fn get_run(&self, run_id: &str) -> Option<AgentRun> {
+        self.runs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|run| run.run_id == run_id)
+            .cloned()
+    }
+
+    /// Recent runs, newest first
+    pub fn runs(&self) -> Vec<AgentRun> {
+        self.runs.read().unwrap().iter().rev().cloned().collect()
+    }
+
+    /// Get a recent conversation transcript by ID
+    pub fn get_transcript(&self, conversation_id: &str) -> Option<ConversationTranscript> {
+        self.transcripts
+            .read()
+            .unwrap()
+            .iter()
+            .find(|t| t.conversation_id == conversation_id)
+            .cloned()
+    }
+
+    /// Recent conversation transcripts, newest first
+    pub fn transcripts(&self) -> Vec<ConversationTranscript> {
+        self.transcripts
+            .read()
+            .unwrap()
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// Run a multi-agent conversation about a topic and keep its transcript
+    pub async fn converse(
+        &self,
+        definition: &ConversationDefinition,
+        topic: &str,
+    ) -> ChainResult<ConversationTranscript> {
+        let transcript = run_conversation(self, definition, topic).await?;
+
+        let mut transcripts = self.transcripts.write().unwrap();
+        transcripts.push_back(transcript.clone());
+        while transcripts.len() > self.run_history {
+            transcripts.pop_front();
+        }
+
+        Ok(transcript)
+    }
+
+    /// Run an agent on the given input
+    ///
+    /// With a conversation ID and configured memory, recent messages of the
+    /// conversation are given to the model and the input and answer are
+    /// appended to it.
+    pub async fn run(
+        &self,
+        agent: &AgentDefinition,
+        input: &str,
+        conversation_id: Option<&str>,
+    ) -> ChainResult<AgentRun> {
+        self.run_with_tools(agent, input, conversation_id, &self.tools)
+            .await
+    }
+
+    /// Run an agent with a specific tool registry instead of the runtime's
+    pub async fn run_with_tools(
+        &self,
+        agent: &AgentDefinition,
+        input: &str,
+        conversation_id: Option<&str>,
+        tools: &ToolRegistry,
+    ) -> ChainResult<AgentRun> {
+        if agent.max_steps == 0 {
+            return Err(ChainError::ValidationError(
+                "Agent max_steps must be greater than 0".to_string(),
+            ));
+        }
+
+        let started_at = Utc::now();
+        let start = Instant::now();
+        let deadline = agent.timeout_secs.map(Duration::from_secs);
+
+        let mut messages = Vec::new();
+        if let Some(system_prompt) = &agent.system_prompt {
+            messages.push(message(MessageRole::System, system_prompt));
+        }
+        messages.extend(self.history(agent, conversation_id).await);
+        messages.push(message(MessageRole::User, input));
+
+        let allowed: HashSet<&str> = agent.tools.iter().map(String::as_str).collect();
+        let definitions: Vec<_> = tools
+            .definitions()
+            .into_iter()
+            .filter(|tool| allowed.contains(tool.function.name.as_str()))
+            .collect();
+
+        let mut steps = Vec::new();
+        let mut total_tokens = 0;
+        let mut usage = StepUsage::default();
+        let mut tool_calls_made = 0;
+        let mut answer = None;
+        let mut stop_reason = AgentStopReason::MaxSteps;
+
+        for index in 1..=agent.max_steps {
+            let step_start = Instant::now();
+            let remaining = match deadline {
+                Some(limit) if start.elapsed() >= limit => {
+                    stop_reason = AgentStopReason::Timeout;
+                    break;
+                }
+                Some(limit) => Some(limit - start.elapsed()),
+                None => None,
+            };
+
+            let request = ChatCompletionRequest {
+                model: agent.model.clone(),
+                messages: messages.clone(),
+                temperature: agent.temperature,
+                top_p: None,
+                max_tokens: None,
+                stream: Some(false),
+                functions: None,
+                tools: (!definitions.is_empty()).then(|| definitions.clone()),
+                additional_params: None,
+            };
+            let generation = self.connector.generate(request);
+            let response = match remaining {
+                Some(remaining) => match tokio::time::timeout(remaining, generation).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        stop_reason = AgentStopReason::Timeout;
+                        break;
+                    }
+                },
+                None => generation.await,
+            }
+            .map_err(|e| {
+                ChainError::StepExecutionError(format!(
+                    "Agent {} model call failed: {}",
+                    agent.id, e
+                ))
+            })?;
+
+            let step_tokens = response.usage.as_ref().map(|u| u.total_tokens);
+            total_tokens += step_tokens.unwrap_or(0);
+            if let Some(reported) = &response.usage {
+                usage.add(&StepUsage::priced(
+                    &agent.model,
+                    reported.prompt_tokens as u64,
+                    reported.completion_tokens as u64,
+                    self.cost_calculator.as_deref(),
+                ));
+            }
+            let reply = response
+                .choices
+                .into_iter()
+                .next()
+                .map(|choice| choice.message)
+                .ok_or_else(|| {
+                    ChainError::StepExecutionError("Model returned no choices".to_string())
+                })?;
+
+            let mut step = AgentStep {
+                index,
+                thought: reply.content.clone(),
+                actions: Vec::new(),
+                total_tokens: step_tokens,
+                duration_ms: 0,
+            };
+
+            if let Some(final_answer) = find_stop_phrase(&reply.content, &agent.stop_phrases) {
+                step.duration_ms = step_start.elapsed().as_millis() as u64;
+                steps.push(step);
+                answer = Some(final_answer);
+                stop_reason = AgentStopReason::StopPhrase;
+                break;
+            }
+
+            let calls = reply.tool_calls.clone().unwrap_or_default();
+            if calls.is_empty() {
+                step.duration_ms = step_start.elapsed().as_millis() as u64;
+                steps.push(step);
+                answer = Some(reply.content);
+                stop_reason = AgentStopReason::FinalAnswer;
+                break;
+            }
+
+            if let Some(max_tool_calls) = agent.max_tool_calls {
+                if tool_calls_made + calls.len() as u32 > max_tool_calls {
+                    step.duration_ms = step_start.elapsed().as_millis() as u64;
+                    steps.push(step);
+                    stop_reason = AgentStopReason::MaxToolCalls;
+                    break;
+                }
+            }
+            tool_calls_made += calls.len() as u32;
+
+            messages.push(reply);
+            for call in &calls {
+                let call_start = Instant::now();
+                let (tool_message, result) = if allowed.contains(call.function.name.as_str()) {
+                    tools.execute(call).await
+                } else {
+                    let error =
+                        format!("Tool {} is not available to this agent", call.function.name);
+                    let mut tool_message = message(
+                        MessageRole::Tool,
+                        &serde_json::json!({ "error": error }).to_string(),
+                    );
+                    tool_message.name = Some(call.function.name.clone());
+                    tool_message.tool_call_id = Some(call.id.clone());
+                    (tool_message, Err(error))
+                };
+                let (result, error) = match result {
+                    Ok(value) => (Some(value), None),
+                    Err(error) => (None, Some(error)),
+                };
+                step.actions.push(ToolInvocation {
+                    call_id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    arguments: call.function.arguments.clone(),
+                    result,
+                    error,
+                    duration_ms: call_start.elapsed().as_millis() as u64,
+                });
+                messages.push(tool_message);
+            }
+
+            debug!(
+                "Agent {} step {}: {} tool call(s)",
+                agent.id,
+                index,
+                step.actions.len()
+            );
+            step.duration_ms = step_start.elapsed().as_millis() as u64;
+            steps.push(step);
+        }
+
+        if let (Some(memory), Some(conversation_id)) = (&self.memory, conversation_id) {
+            let recorded = async {
+                memory.add_message(conversation_id, "user", input).await?;
+                if let Some(answer) = &answer {
+                    memory
+                        .add_message(conversation_id, "assistant", answer)
+                        .await?;
+                }
+                Ok::<_, crate::modules::memory::MemoryError>(())
+            };
+            if let Err(e) = recorded.await {
+                warn!(
+                    "Failed to record agent run in conversation {}: {}",
+                    conversation_id, e
+                );
+            }
+        }
+
+        let run = AgentRun {
+            run_id: Uuid::new_v4().to_string(),
+            agent_id: agent.id.clone(),
+            input: input.to_string(),
+            answer,
+            stop_reason,
+            steps,
+            total_tokens,
+            usage,
+            conversation_id: conversation_id.map(str::to_string),
+            started_at,
+            finished_at: Utc::now(),
+        };
+
+        let mut runs = self.runs.write().unwrap();
+        runs.push_back(run.clone());
+        while runs.len() > self.run_history {
+            runs.pop_front();
+        }
+
+        Ok(run)
+    }
+
+    /// Recent messages of a conversation, as chat messages
+    async fn history(
+        &self,
+        agent: &AgentDefinition,
+        conversation_id: Option<&str>,
+    ) -> Vec<ChatMessage> {
+        let (Some(memory), Some(conversation_id)) = (&self.memory, conversation_id) else {
+            return Vec::new();
+        };
+
+        let messages = match memory
+            .get_last_messages(conversation_id, agent.memory_window)
+            .await
+        {
+            Ok(messages) => messages,
+            Err(crate::modules::memory::MemoryError::NotFound(_)) => {
+                return Vec::new();
+            }
+            Err(e) => {
+                warn!("Failed to load conversation {}: {}", conversation_id, e);
+                return Vec::new();
+            }
+        };
+
+        messages
+            .iter()
+            .filter_map(|m| {
+                let role = match m.role.as_str() {
+                    "system" => MessageRole::System,
+                    "user" => MessageRole::User,
+                    "assistant" => MessageRole::Assistant,
+                    _ => return None,
+                };
+                Some(message(role, &m.content))
+            })
+            .collect()
+    }
+}
+
+/// Build a plain chat message
+fn message(role: MessageRole, content: &str) -> ChatMessage {
+    ChatMessage {
+        role,
+        content: content.to_string(),
+        name: None,
+        function_call: None,
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+/// Text following the first stop phrase found in the content
+fn find_stop_phrase(content: &str, stop_phrases: &[String]) -> Option<String> {
+    stop_phrases
+        .iter()
+        .filter_map(|phrase| content.find(phrase.as_str()).map(|pos| (pos, phrase)))
+        .min_by_key(|(pos, _)| *pos)
+        .map(|(pos, phrase)| content[pos + phrase.len()..].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_stop_phrase() {
+        let phrases = vec!["FINAL ANSWER:".to_string(), "DONE".to_string()];
+        assert_eq!(
+            find_stop_phrase("Thinking... FINAL ANSWER: 42", &phrases),
+            Some("42".to_string())
+        );
+        assert_eq!(
+            find_stop_phrase("DONE early. FINAL ANSWER: 1", &phrases),
+            Some("early. FINAL ANSWER: 1".to_string())
+        );
+        assert_eq!(find_stop_phrase("still working", &phrases), None);
+    }
+
+    #[test]
+    fn test_definition_defaults() {
+        let agent: AgentDefinition =
+            serde_json::from_value(serde_json::json!({ "model": "gpt-4o" })).unwrap();
+        assert_eq!(agent.id, "agent");
+        assert_eq!(agent.max_steps, 10);
+        assert!(agent.tools.is_empty());
+        assert!(agent.max_tool_calls.is_none());
+    }
+}
@@ -0,0 +1,1054 @@
+//! Chain execution API
+//!
+//! This module exposes checkpointed chain executions over HTTP (status lookup,
+//! resume and cancel), chain schedules and their run history, chain
+//! webhooks, the JSON schemas of scheduled and webhook-triggered chains, the
+//! dead-letter queue, agent runs with their traces, and multi-agent
+//! conversation transcripts. Resuming and cancelling executions, triggering
+//! schedules, handling dead letters, running agents and conversations, and
+//! reading their traces and transcripts need the operator admin role.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{error, info};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::config::ProxyConfig;
+use crate::modules::chain_engine::agent::{AgentDefinition, AgentRuntime};
+use crate::modules::chain_engine::budget::ChainUsage;
+use crate::modules::chain_engine::checkpoint::{
+    ChainCheckpoint, CheckpointedStep, ExecutionStatus, StepCompensation, StepRetry,
+};
+use crate::modules::chain_engine::conversation::{ConversationDefinition, TurnPolicy};
+use crate::modules::chain_engine::dead_letter::{DeadLetterQueue, DeadLetterSource};
+use crate::modules::chain_engine::engine::ChainEngine;
+use crate::modules::chain_engine::error::ChainError;
+use crate::modules::chain_engine::scheduler::ChainScheduler;
+use crate::modules::chain_engine::schema::ChainSchemas;
+use crate::modules::chain_engine::webhooks::{
+    WebhookDispatcher, WebhookRejection, WebhookTriggers,
+};
+use crate::modules::llm_proxy::admin::{self, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// Status of a chain execution
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ExecutionStatusResponse {
+    pub execution_id: String,
+    pub chain_id: String,
+    #[schema(value_type = String)]
+    pub status: ExecutionStatus,
+    /// Whether the execution is currently running in this orchestrator
+    pub active: bool,
+    pub completed_steps: Vec<String>,
+    #[schema(value_type = HashMap<String, Object>)]
+    pub step_results: HashMap<String, CheckpointedStep>,
+    pub outputs: HashMap<String, serde_json::Value>,
+    pub error: Option<String>,
+    /// Retries of failed steps, in order
+    #[schema(value_type = Vec<Object>)]
+    pub retries: Vec<StepRetry>,
+    /// Compensations of completed steps after a failure, in order
+    #[schema(value_type = Vec<Object>)]
+    pub compensations: Vec<StepCompensation>,
+    /// Resources used, counted against the chain budget
+    #[schema(value_type = Object)]
+    pub usage: ChainUsage,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ExecutionStatusResponse {
+    fn new(checkpoint: ChainCheckpoint, active: bool) -> Self {
+        Self {
+            execution_id: checkpoint.execution_id,
+            chain_id: checkpoint.chain.id,
+            status: checkpoint.status,
+            active,
+            completed_steps: checkpoint.completed_steps,
+            step_results: checkpoint.step_results,
+            outputs: checkpoint.outputs,
+            error: checkpoint.error,
+            retries: checkpoint.retries,
+            compensations: checkpoint.compensations,
+            usage: checkpoint.usage,
+            created_at: checkpoint.created_at,
+            updated_at: checkpoint.updated_at,
+        }
+    }
+}
+
+/// OpenAPI description of the chain, schedule, webhook and agent APIs
+#[derive(OpenApi)]
+#[openapi(paths(
+    get_execution,
+    resume_execution,
+    cancel_execution,
+    list_schedules,
+    schedule_history,
+    trigger_schedule,
+    schedule_schemas,
+    receive_webhook,
+    webhook_schemas,
+    list_dead_letters,
+    purge_dead_letters,
+    get_dead_letter,
+    delete_dead_letter,
+    retry_dead_letter,
+    redeliver_dead_letter,
+    run_agent,
+    list_agent_runs,
+    get_agent_run,
+    start_conversation,
+    list_conversations,
+    get_conversation,
+))]
+pub struct ChainApiDoc;
+
+/// State of the chain execution API
+struct ExecutionApiState {
+    engine: Arc<ChainEngine>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// Create the router for the chain execution API
+pub fn create_router(engine: Arc<ChainEngine>, proxy: ProxyConfig) -> Router {
+    let state = Arc::new(ExecutionApiState { engine, proxy });
+    Router::new()
+        .route("/v1/chains/executions/{id}", get(get_execution))
+        .route("/v1/chains/executions/{id}/resume", post(resume_execution))
+        .route("/v1/chains/executions/{id}/cancel", post(cancel_execution))
+        .with_state(state)
+}
+
+/// State of the chain schedule API
+struct ScheduleApiState {
+    scheduler: Arc<ChainScheduler>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// Create the router for the chain schedule API
+pub fn create_schedule_router(scheduler: Arc<ChainScheduler>, proxy: ProxyConfig) -> Router {
+    let state = Arc::new(ScheduleApiState { scheduler, proxy });
+    Router::new()
+        .route("/v1/chains/schedules", get(list_schedules))
+        .route("/v1/chains/schedules/{id}/history", get(schedule_history))
+        .route("/v1/chains/schedules/{id}/trigger", post(trigger_schedule))
+        .route("/v1/chains/schedules/{id}/schemas", get(schedule_schemas))
+        .with_state(state)
+}
+
+/// Create the router for inbound chain webhooks
+pub fn create_webhook_router(triggers: Arc<WebhookTriggers>) -> Router {
+    Router::new()
+        .route("/v1/chains/webhooks/{id}", post(receive_webhook))
+        .route("/v1/chains/webhooks/{id}/schemas", get(webhook_schemas))
+        .with_state(triggers)
+}
+
+/// State of the dead-letter API
+struct DeadLetterApiState {
+    queue: Arc<DeadLetterQueue>,
+    /// Retries dead-lettered steps
+    engine: Arc<ChainEngine>,
+    /// Redelivers dead-lettered webhook deliveries
+    dispatcher: Arc<WebhookDispatcher>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// Create the router for the dead-letter queue of failed steps and webhook
+/// deliveries
+pub fn create_dead_letter_router(
+    queue: Arc<DeadLetterQueue>,
+    engine: Arc<ChainEngine>,
+    dispatcher: Arc<WebhookDispatcher>,
+    proxy: ProxyConfig,
+) -> Router {
+    let state = Arc::new(DeadLetterApiState {
+        queue,
+        engine,
+        dispatcher,
+        proxy,
+    });
+    Router::new()
+        .route(
+            "/v1/chains/dead-letters",
+            get(list_dead_letters).delete(purge_dead_letters),
+        )
+        .route(
+            "/v1/chains/dead-letters/{id}",
+            get(get_dead_letter).delete(delete_dead_letter),
+        )
+        .route(
+            "/v1/chains/dead-letters/{id}/retry",
+            post(retry_dead_letter),
+        )
+        .route(
+            "/v1/chains/dead-letters/{id}/redeliver",
+            post(redeliver_dead_letter),
+        )
+        .with_state(state)
+}
+
+/// State of the agent API
+struct AgentApiState {
+    runtime: Arc<AgentRuntime>,
+    /// Tools agents may call, by agent ID
+    tools: HashMap<String, Vec<String>>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// Create the router for the agent API
+///
+/// The tools an agent asks for are limited to those configured for its ID in
+/// `tools`.
+pub fn create_agent_router(
+    runtime: Arc<AgentRuntime>,
+    tools: HashMap<String, Vec<String>>,
+    proxy: ProxyConfig,
+) -> Router {
+    let state = Arc::new(AgentApiState {
+        runtime,
+        tools,
+        proxy,
+    });
+    Router::new()
+        .route("/v1/agents/run", post(run_agent))
+        .route("/v1/agents/runs", get(list_agent_runs))
+        .route("/v1/agents/runs/{id}", get(get_agent_run))
+        .route(
+            "/v1/agents/conversations",
+            get(list_conversations).post(start_conversation),
+        )
+        .route("/v1/agents/conversations/{id}", get(get_conversation))
+        .with_state(state)
+}
+
+/// Limit the tools of an agent to those configured for its ID
+fn limit_tools(agent: &mut AgentDefinition, configured: &HashMap<String, Vec<String>>) {
+    let allowed = configured
+        .get(&agent.id)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    agent.tools.retain(|tool| allowed.contains(tool));
+}
+
+/// Request to run an agent
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AgentRunRequest {
+    #[schema(value_type = Object)]
+    pub agent: AgentDefinition,
+    pub input: String,
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+}
+
+/// Request to run a multi-agent conversation
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ConversationRequest {
+    #[schema(value_type = Object)]
+    pub conversation: ConversationDefinition,
+    pub topic: String,
+}
+
+/// Build an error response
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "chain_execution_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Map a chain error to an error response
+fn chain_error_response(e: ChainError) -> Response {
+    match e {
+        ChainError::ValidationError(message) => {
+            error_response(StatusCode::CONFLICT, message, "invalid_execution_state")
+        }
+        e => error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            "internal_error",
+        ),
+    }
+}
+
+/// Load an execution, or build the error response
+async fn load_execution(engine: &ChainEngine, id: &str) -> Result<ChainCheckpoint, Response> {
+    match engine.get_execution(id).await {
+        Ok(Some(checkpoint)) => Ok(checkpoint),
+        Ok(None) => Err(error_response(
+            StatusCode::NOT_FOUND,
+            format!("Execution not found: {}", id),
+            "execution_not_found",
+        )),
+        Err(e) => Err(chain_error_response(e)),
+    }
+}
+
+/// Route handler for GET /v1/chains/executions/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/chains/executions/{id}",
+    tag = "chains",
+    params(("id" = String, Path, description = "Execution ID")),
+    responses(
+        (status = 200, description = "Execution status", body = ExecutionStatusResponse),
+        (status = 404, description = "Unknown execution", body = ApiError)
+    )
+)]
+async fn get_execution(
+    State(state): State<Arc<ExecutionApiState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match load_execution(&state.engine, &id).await {
+        Ok(checkpoint) => {
+            let active = state.engine.is_execution_active(&id);
+            Json(ExecutionStatusResponse::new(checkpoint, active)).into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+/// Route handler for POST /v1/chains/executions/{id}/resume
+///
+/// The execution resumes in the background; poll the status endpoint for
+/// progress.
+#[utoipa::path(
+    post,
+    path = "/v1/chains/executions/{id}/resume",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Execution ID")),
+    responses(
+        (status = 202, description = "Execution resuming in the background", body = ExecutionStatusResponse),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown execution", body = ApiError),
+        (status = 409, description = "Execution cannot be resumed", body = ApiError)
+    )
+)]
+async fn resume_execution(
+    State(state): State<Arc<ExecutionApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    let engine = &state.engine;
+    let checkpoint = match load_execution(engine, &id).await {
+        Ok(checkpoint) => checkpoint,
+        Err(response) => return response,
+    };
+    if !checkpoint.status.is_resumable() || engine.is_execution_active(&id) {
+        return error_response(
+            StatusCode::CONFLICT,
+            format!("Execution {} cannot be resumed", id),
+            "invalid_execution_state",
+        );
+    }
+
+    let background_engine = engine.clone();
+    let execution_id = id.clone();
+    tokio::spawn(async move {
+        match background_engine.resume_execution(&execution_id).await {
+            Ok(_) => info!("Resumed execution {} completed", execution_id),
+            Err(e) => error!("Resumed execution {} failed: {}", execution_id, e),
+        }
+    });
+
+    let mut response = ExecutionStatusResponse::new(checkpoint, true);
+    response.status = ExecutionStatus::Running;
+    response.error = None;
+    (StatusCode::ACCEPTED, Json(response)).into_response()
+}
+
+/// Route handler for POST /v1/chains/executions/{id}/cancel
+#[utoipa::path(
+    post,
+    path = "/v1/chains/executions/{id}/cancel",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Execution ID")),
+    responses(
+        (status = 200, description = "Cancelled execution", body = ExecutionStatusResponse),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown execution", body = ApiError),
+        (status = 409, description = "Execution cannot be cancelled", body = ApiError)
+    )
+)]
+async fn cancel_execution(
+    State(state): State<Arc<ExecutionApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    let engine = &state.engine;
+    if let Err(response) = load_execution(engine, &id).await {
+        return response;
+    }
+    match engine.cancel_execution(&id).await {
+        Ok(checkpoint) => {
+            let active = engine.is_execution_active(&id);
+            Json(ExecutionStatusResponse::new(checkpoint, active)).into_response()
+        }
+        Err(e) => chain_error_response(e),
+    }
+}
+
+/// Route handler for GET /v1/chains/schedules
+#[utoipa::path(
+    get,
+    path = "/v1/chains/schedules",
+    tag = "chains",
+    responses(
+        (status = 200, description = "Chain schedules", body = Vec<Object>)
+    )
+)]
+async fn list_schedules(State(state): State<Arc<ScheduleApiState>>) -> Response {
+    Json(state.scheduler.schedules()).into_response()
+}
+
+/// Route handler for GET /v1/chains/schedules/{id}/history
+#[utoipa::path(
+    get,
+    path = "/v1/chains/schedules/{id}/history",
+    tag = "chains",
+    params(("id" = String, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "Recent runs of the schedule", body = Vec<Object>),
+        (status = 404, description = "Unknown schedule", body = ApiError)
+    )
+)]
+async fn schedule_history(
+    State(state): State<Arc<ScheduleApiState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.scheduler.history(&id) {
+        Some(history) => Json(history).into_response(),
+        None => schedule_not_found(&id),
+    }
+}
+
+/// Route handler for POST /v1/chains/schedules/{id}/trigger
+///
+/// Runs the schedule now, subject to its overlap policy.
+#[utoipa::path(
+    post,
+    path = "/v1/chains/schedules/{id}/trigger",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Schedule ID")),
+    responses(
+        (status = 202, description = "Triggered run", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown schedule", body = ApiError)
+    )
+)]
+async fn trigger_schedule(
+    State(state): State<Arc<ScheduleApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    let scheduler = &state.scheduler;
+    if scheduler.history(&id).is_none() {
+        return schedule_not_found(&id);
+    }
+    match scheduler.trigger(&id).await {
+        Ok(run) => (StatusCode::ACCEPTED, Json(run)).into_response(),
+        Err(e) => chain_error_response(e),
+    }
+}
+
+/// Route handler for GET /v1/chains/schedules/{id}/schemas
+#[utoipa::path(
+    get,
+    path = "/v1/chains/schedules/{id}/schemas",
+    tag = "chains",
+    params(("id" = String, Path, description = "Schedule ID")),
+    responses(
+        (status = 200, description = "JSON schemas of the schedule's chain", body = ChainSchemas),
+        (status = 404, description = "Unknown schedule", body = ApiError)
+    )
+)]
+async fn schedule_schemas(
+    State(state): State<Arc<ScheduleApiState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.scheduler.schemas(&id) {
+        Some(schemas) => Json(schemas).into_response(),
+        None => schedule_not_found(&id),
+    }
+}
+
+/// Response for unknown schedules
+fn schedule_not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        format!("Schedule not found: {}", id),
+        "schedule_not_found",
+    )
+}
+
+/// Route handler for POST /v1/chains/webhooks/{id}
+///
+/// Starts the trigger's chain in the background and returns its execution ID.
+#[utoipa::path(
+    post,
+    path = "/v1/chains/webhooks/{id}",
+    tag = "chains",
+    params(("id" = String, Path, description = "Webhook trigger ID")),
+    request_body = Object,
+    responses(
+        (status = 202, description = "ID of the started execution under `execution_id`", body = Object),
+        (status = 401, description = "Missing or invalid signature", body = ApiError),
+        (status = 404, description = "Unknown trigger", body = ApiError),
+        (status = 422, description = "Body not matching the chain's input schema", body = ApiError)
+    )
+)]
+async fn receive_webhook(
+    State(triggers): State<Arc<WebhookTriggers>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let signature = triggers
+        .signature_header(&id)
+        .and_then(|header| headers.get(header))
+        .and_then(|value| value.to_str().ok());
+
+    match triggers.trigger(&id, signature, &body) {
+        Ok(execution_id) => (
+            StatusCode::ACCEPTED,
+            Json(json!({ "execution_id": execution_id })),
+        )
+            .into_response(),
+        Err(WebhookRejection::UnknownTrigger) => trigger_not_found(&id),
+        Err(WebhookRejection::InvalidSignature) => error_response(
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid webhook signature".to_string(),
+            "invalid_signature",
+        ),
+        Err(WebhookRejection::InvalidInputs(message)) => error_response(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            message,
+            "schema_violation",
+        ),
+    }
+}
+
+/// Route handler for GET /v1/chains/webhooks/{id}/schemas
+#[utoipa::path(
+    get,
+    path = "/v1/chains/webhooks/{id}/schemas",
+    tag = "chains",
+    params(("id" = String, Path, description = "Webhook trigger ID")),
+    responses(
+        (status = 200, description = "JSON schemas of the trigger's chain", body = ChainSchemas),
+        (status = 404, description = "Unknown trigger", body = ApiError)
+    )
+)]
+async fn webhook_schemas(
+    State(triggers): State<Arc<WebhookTriggers>>,
+    Path(id): Path<String>,
+) -> Response {
+    match triggers.schemas(&id) {
+        Some(schemas) => Json(schemas).into_response(),
+        None => trigger_not_found(&id),
+    }
+}
+
+/// Response for unknown webhook triggers
+fn trigger_not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        format!("Webhook trigger not found: {}", id),
+        "trigger_not_found",
+    )
+}
+
+/// Filter of dead letters
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct DeadLetterQuery {
+    /// Kind of dead letters, `step` or `webhook` (all kinds if omitted)
+    pub kind: Option<String>,
+}
+
+/// Build the response to a failed dead-letter queue operation
+fn dead_letter_error(e: ChainError) -> Response {
+    error!("Dead-letter queue operation failed: {}", e);
+    error_response(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        e.to_string(),
+        "dead_letter_queue_error",
+    )
+}
+
+fn dead_letter_not_found(id: &str) -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        format!("Dead letter not found: {}", id),
+        "dead_letter_not_found",
+    )
+}
+
+/// Route handler for GET /v1/chains/dead-letters
+#[utoipa::path(
+    get,
+    path = "/v1/chains/dead-letters",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(DeadLetterQuery),
+    responses(
+        (status = 200, description = "Dead-lettered steps and webhook deliveries, oldest first", body = Vec<Object>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_dead_letters(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Query(query): Query<DeadLetterQuery>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.queue.list(query.kind.as_deref()).await {
+        Ok(dead_letters) => Json(dead_letters).into_response(),
+        Err(e) => dead_letter_error(e),
+    }
+}
+
+/// Route handler for DELETE /v1/chains/dead-letters
+#[utoipa::path(
+    delete,
+    path = "/v1/chains/dead-letters",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(DeadLetterQuery),
+    responses(
+        (status = 200, description = "Number of dead letters purged", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn purge_dead_letters(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Query(query): Query<DeadLetterQuery>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.queue.purge(query.kind.as_deref()).await {
+        Ok(purged) => {
+            info!("Purged {} dead letters", purged);
+            Json(json!({ "purged": purged })).into_response()
+        }
+        Err(e) => dead_letter_error(e),
+    }
+}
+
+/// Route handler for GET /v1/chains/dead-letters/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/chains/dead-letters/{id}",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "Dead letter with the context of the failure", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown dead letter", body = ApiError)
+    )
+)]
+async fn get_dead_letter(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.queue.get(&id).await {
+        Ok(Some(dead_letter)) => Json(dead_letter).into_response(),
+        Ok(None) => dead_letter_not_found(&id),
+        Err(e) => dead_letter_error(e),
+    }
+}
+
+/// Route handler for DELETE /v1/chains/dead-letters/{id}
+#[utoipa::path(
+    delete,
+    path = "/v1/chains/dead-letters/{id}",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "Removed dead letter", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown dead letter", body = ApiError)
+    )
+)]
+async fn delete_dead_letter(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.queue.take(&id).await {
+        Ok(Some(dead_letter)) => Json(dead_letter).into_response(),
+        Ok(None) => dead_letter_not_found(&id),
+        Err(e) => dead_letter_error(e),
+    }
+}
+
+/// Route handler for POST /v1/chains/dead-letters/{id}/retry
+///
+/// Steps are retried by resuming their execution from the failed step, and
+/// webhook deliveries by delivering their event again. A failed retry is
+/// dead-lettered again.
+#[utoipa::path(
+    post,
+    path = "/v1/chains/dead-letters/{id}/retry",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "Retry succeeded", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown dead letter", body = ApiError),
+        (status = 409, description = "The execution cannot be resumed", body = ApiError),
+        (status = 502, description = "Retry failed", body = ApiError)
+    )
+)]
+async fn retry_dead_letter(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    let dead_letter = match state.queue.take(&id).await {
+        Ok(Some(dead_letter)) => dead_letter,
+        Ok(None) => return dead_letter_not_found(&id),
+        Err(e) => return dead_letter_error(e),
+    };
+
+    match &dead_letter.source {
+        DeadLetterSource::Step { execution, .. } => {
+            match state.engine.retry_step(execution).await {
+                Ok(outputs) => Json(json!({
+                    "retried": true,
+                    "execution_id": execution.execution_id,
+                    "outputs": outputs,
+                }))
+                .into_response(),
+                Err(ChainError::ValidationError(message)) => {
+                    // The execution did not run; keep the dead letter
+                    state.queue.push(dead_letter.clone()).await;
+                    error_response(StatusCode::CONFLICT, message, "invalid_execution_state")
+                }
+                Err(e) => error_response(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Retry failed; the step was dead-lettered again: {}", e),
+                    "retry_failed",
+                ),
+            }
+        }
+        DeadLetterSource::Webhook { .. } => {
+            match state.dispatcher.redeliver_dead_letter(&dead_letter).await {
+                Some(true) => Json(json!({ "retried": true })).into_response(),
+                _ => error_response(
+                    StatusCode::BAD_GATEWAY,
+                    "Redelivery failed; the delivery was dead-lettered again".to_string(),
+                    "delivery_failed",
+                ),
+            }
+        }
+    }
+}
+
+/// Route handler for POST /v1/chains/dead-letters/{id}/redeliver
+#[utoipa::path(
+    post,
+    path = "/v1/chains/dead-letters/{id}/redeliver",
+    tag = "chains",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Dead letter ID")),
+    responses(
+        (status = 200, description = "Delivery succeeded", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown dead-lettered delivery", body = ApiError),
+        (status = 502, description = "Redelivery failed", body = ApiError)
+    )
+)]
+async fn redeliver_dead_letter(
+    State(state): State<Arc<DeadLetterApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.dispatcher.redeliver(&id).await {
+        Ok(Some(true)) => Json(json!({ "delivered": true })).into_response(),
+        Ok(Some(false)) => error_response(
+            StatusCode::BAD_GATEWAY,
+            "Redelivery failed; the delivery was dead-lettered again".to_string(),
+            "delivery_failed",
+        ),
+        Ok(None) => dead_letter_not_found(&id),
+        Err(e) => dead_letter_error(e),
+    }
+}
+
+/// Route handler for POST /v1/agents/run
+#[utoipa::path(
+    post,
+    path = "/v1/agents/run",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    request_body = AgentRunRequest,
+    responses(
+        (status = 200, description = "Agent run with its trace", body = Object),
+        (status = 400, description = "Invalid agent", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 502, description = "Agent failed", body = ApiError)
+    )
+)]
+async fn run_agent(
+    State(state): State<Arc<AgentApiState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<AgentRunRequest>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    limit_tools(&mut request.agent, &state.tools);
+    match state
+        .runtime
+        .run(
+            &request.agent,
+            &request.input,
+            request.conversation_id.as_deref(),
+        )
+        .await
+    {
+        Ok(run) => Json(run).into_response(),
+        Err(ChainError::ValidationError(message)) => {
+            error_response(StatusCode::BAD_REQUEST, message, "invalid_agent")
+        }
+        Err(e) => {
+            error!("Agent {} failed: {}", request.agent.id, e);
+            error_response(StatusCode::BAD_GATEWAY, e.to_string(), "agent_failed")
+        }
+    }
+}
+
+/// Route handler for GET /v1/agents/runs
+#[utoipa::path(
+    get,
+    path = "/v1/agents/runs",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent agent runs", body = Vec<Object>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_agent_runs(State(state): State<Arc<AgentApiState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    Json(state.runtime.runs()).into_response()
+}
+
+/// Route handler for GET /v1/agents/runs/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/agents/runs/{id}",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Agent run ID")),
+    responses(
+        (status = 200, description = "Agent run with its trace", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown agent run", body = ApiError)
+    )
+)]
+async fn get_agent_run(
+    State(state): State<Arc<AgentApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.runtime.get_run(&id) {
+        Some(run) => Json(run).into_response(),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Agent run not found: {}", id),
+            "agent_run_not_found",
+        ),
+    }
+}
+
+/// Route handler for POST /v1/agents/conversations
+#[utoipa::path(
+    post,
+    path = "/v1/agents/conversations",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    request_body = ConversationRequest,
+    responses(
+        (status = 200, description = "Conversation transcript", body = Object),
+        (status = 400, description = "Invalid conversation", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 502, description = "Conversation failed", body = ApiError)
+    )
+)]
+async fn start_conversation(
+    State(state): State<Arc<AgentApiState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<ConversationRequest>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    // Participants run under their names
+    for participant in &mut request.conversation.participants {
+        participant.agent.id = participant.name.clone();
+        limit_tools(&mut participant.agent, &state.tools);
+    }
+    if let TurnPolicy::Moderator { moderator } = &mut request.conversation.turn_policy {
+        limit_tools(moderator, &state.tools);
+    }
+    match state
+        .runtime
+        .converse(&request.conversation, &request.topic)
+        .await
+    {
+        Ok(transcript) => Json(transcript).into_response(),
+        Err(ChainError::ValidationError(message)) => {
+            error_response(StatusCode::BAD_REQUEST, message, "invalid_conversation")
+        }
+        Err(e) => {
+            error!("Conversation failed: {}", e);
+            error_response(
+                StatusCode::BAD_GATEWAY,
+                e.to_string(),
+                "conversation_failed",
+            )
+        }
+    }
+}
+
+/// Route handler for GET /v1/agents/conversations
+#[utoipa::path(
+    get,
+    path = "/v1/agents/conversations",
+    operation_id = "list_agent_conversations",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent conversation transcripts", body = Vec<Object>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_conversations(
+    State(state): State<Arc<AgentApiState>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    Json(state.runtime.transcripts()).into_response()
+}
+
+/// Route handler for GET /v1/agents/conversations/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/agents/conversations/{id}",
+    operation_id = "get_agent_conversation",
+    tag = "agents",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, description = "Conversation transcript", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown conversation", body = ApiError)
+    )
+)]
+async fn get_conversation(
+    State(state): State<Arc<AgentApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.runtime.get_transcript(&id) {
+        Some(transcript) => Json(transcript).into_response(),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Conversation not found: {}", id),
+            "conversation_not_found",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_tools() {
+        let configured = HashMap::from([(
+            "researcher".to_string(),
+            vec!["web_search".to_string(), "calculator".to_string()],
+        )]);
+        let mut agent: AgentDefinition = serde_json::from_value(json!({
+            "id": "researcher",
+            "model": "gpt-4o",
+            "tools": ["calculator", "http"],
+        }))
+        .unwrap();
+        limit_tools(&mut agent, &configured);
+        assert_eq!(agent.tools, vec!["calculator".to_string()]);
+
+        // Agents without configured tools get none
+        agent.id = "writer".to_string();
+        agent.tools = vec!["calculator".to_string()];
+        limit_tools(&mut agent, &configured);
+        assert!(agent.tools.is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! Chain execution budgets
+//!
+//! Executions are limited in the tokens and cost their steps report, their
+//! wall time and the number of steps they run, as set by the chain's budget
+//! or the engine's default budget. Steps that call models report their
+//! [`StepUsage`] in their result, priced by the engine's cost calculator;
+//! step outputs are never counted, whatever their names.
+//!
+//! An execution over budget stops at the next step boundary, or during its
+//! step once out of wall time, with a `BudgetExceeded` error carrying the
+//! results of its completed steps. The interrupted step's partial work is
+//! dropped, and completed steps are not compensated.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+use crate::modules::chain_engine::definition::ChainBudget;
+use crate::modules::telemetry::CostCalculator;
+
+/// Model usage reported by a step
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StepUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Cost of the model calls, in the pricing currency
+    pub cost: f64,
+}
+
+impl StepUsage {
+    /// Usage of a model call, priced when a cost calculator is given
+    pub fn priced(
+        model: &str,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+        calculator: Option<&CostCalculator>,
+    ) -> Self {
+        let cost = calculator
+            .and_then(|calculator| {
+                calculator
+                    .calculate_cost(model, prompt_tokens as usize, completion_tokens as usize)
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            cost,
+        }
+    }
+
+    /// Tokens of the prompts and completions
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Add the usage of another model call
+    pub fn add(&mut self, other: &StepUsage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Resources used by a chain execution
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainUsage {
+    /// Tokens reported by the steps
+    pub total_tokens: u64,
+    /// Cost reported by the steps, in the pricing currency
+    pub cost: f64,
+    /// Steps run, excluding retries and compensations
+    pub steps: u32,
+    /// Wall time spent running, in milliseconds
+    pub wall_time_ms: u64,
+}
+
+impl ChainUsage {
+    /// Count a completed step and the usage it reported
+    pub fn add_step(&mut self, usage: Option<&StepUsage>) {
+        if let Some(usage) = usage {
+            self.total_tokens += usage.total_tokens();
+            self.cost += usage.cost;
+        }
+        self.steps += 1;
+    }
+
+    /// Limit of the budget this usage leaves no room under, if any
+    ///
+    /// Tokens and cost may reach their limits exactly; the execution then
+    /// stops before its next step.
+    pub fn exhausted(&self, budget: &ChainBudget) -> Option<BudgetLimit> {
+        if budget
+            .max_total_tokens
+            .is_some_and(|max| self.total_tokens >= max)
+        {
+            Some(BudgetLimit::TotalTokens)
+        } else if budget.max_cost.is_some_and(|max| self.cost >= max) {
+            Some(BudgetLimit::Cost)
+        } else if budget.max_steps.is_some_and(|max| self.steps >= max) {
+            Some(BudgetLimit::Steps)
+        } else if self.remaining_time(budget) == Some(Duration::ZERO) {
+            Some(BudgetLimit::WallTime)
+        } else {
+            None
+        }
+    }
+
+    /// Limit of the budget this usage went over, if any
+    pub fn exceeded(&self, budget: &ChainBudget) -> Option<BudgetLimit> {
+        if budget
+            .max_total_tokens
+            .is_some_and(|max| self.total_tokens > max)
+        {
+            Some(BudgetLimit::TotalTokens)
+        } else if budget.max_cost.is_some_and(|max| self.cost > max) {
+            Some(BudgetLimit::Cost)
+        } else {
+            None
+        }
+    }
+
+    /// Wall time left in the budget, if limited
+    pub fn remaining_time(&self, budget: &ChainBudget) -> Option<Duration> {
+        budget
+            .max_wall_time
+            .map(|max| max.saturating_sub(Duration::from_millis(self.wall_time_ms)))
+    }
+}
+
+/// Limit of a chain budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetLimit {
+    TotalTokens,
+    Cost,
+    WallTime,
+    Steps,
+}
+
+impl BudgetLimit {
+    /// Name of the limit in chain budgets
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TotalTokens => "max_total_tokens",
+            Self::Cost => "max_cost",
+            Self::WallTime => "max_wall_time",
+            Self::Steps => "max_steps",
+        }
+    }
+}
+
+/// Termination of an execution that exceeded its budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    pub execution_id: String,
+    /// Limit that stopped the execution
+    pub limit: BudgetLimit,
+    /// Budget the execution ran under
+    pub budget: ChainBudget,
+    /// Resources used when the execution stopped
+    pub usage: ChainUsage,
+    /// Completed steps, in completion order
+    pub completed_steps: Vec<String>,
+    /// Outputs of the completed steps
+    pub step_outputs: HashMap<String, HashMap<String, serde_json::Value>>,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "execution {} reached its {} after {} steps ({} tokens, ${:.4}, {} ms)",
+            self.execution_id,
+            self.limit.as_str(),
+            self.usage.steps,
+            self.usage.total_tokens,
+            self.usage.cost,
+            self.usage.wall_time_ms
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priced_usage() {
+        let calculator = CostCalculator::new();
+        // gpt-4 costs $0.03 per 1k prompt and $0.06 per 1k completion tokens
+        let usage = StepUsage::priced("gpt-4", 1000, 500, Some(&calculator));
+        assert_eq!(usage.total_tokens(), 1500);
+        assert!((usage.cost - 0.06).abs() < 1e-9);
+        assert_eq!(StepUsage::priced("gpt-4", 1000, 500, None).cost, 0.0);
+    }
+
+    #[test]
+    fn test_usage_against_budget() {
+        let budget = ChainBudget {
+            max_total_tokens: Some(300),
+            max_cost: Some(0.01),
+            max_wall_time: Some(Duration::from_secs(10)),
+            max_steps: Some(3),
+        };
+        let step = |prompt_tokens, completion_tokens, cost| StepUsage {
+            prompt_tokens,
+            completion_tokens,
+            cost,
+        };
+        let mut usage = ChainUsage::default();
+        usage.add_step(Some(&step(100, 20, 0.002)));
+        usage.add_step(Some(&step(50, 30, 0.004)));
+        assert_eq!((usage.total_tokens, usage.steps), (200, 2));
+        assert_eq!(usage.exhausted(&budget), None);
+
+        usage.add_step(Some(&step(90, 10, 0.001)));
+        assert_eq!(usage.exhausted(&budget), Some(BudgetLimit::TotalTokens));
+        assert_eq!(usage.exceeded(&budget), None);
+        usage.total_tokens += 1;
+        assert_eq!(usage.exceeded(&budget), Some(BudgetLimit::TotalTokens));
+
+        // Steps without model calls count only as steps
+        let mut usage = ChainUsage::default();
+        usage.add_step(None);
+        assert_eq!((usage.total_tokens, usage.steps), (0, 1));
+        usage.add_step(Some(&step(10, 10, 0.012)));
+        assert_eq!(usage.exceeded(&budget), Some(BudgetLimit::Cost));
+
+        let usage = ChainUsage {
+            wall_time_ms: 4_000,
+            ..ChainUsage::default()
+        };
+        assert_eq!(usage.remaining_time(&budget), Some(Duration::from_secs(6)));
+        let usage = ChainUsage {
+            wall_time_ms: 12_000,
+            ..ChainUsage::default()
+        };
+        assert_eq!(usage.exhausted(&budget), Some(BudgetLimit::WallTime));
+        assert_eq!(usage.exhausted(&ChainBudget::default()), None);
+    }
+}
@@ -0,0 +1,674 @@
+//! Chain execution checkpoints
+//!
+//! This module persists chain execution state (completed steps and their
+//! outputs) at each step boundary, so executions interrupted by a crashed
+//! orchestrator can be resumed instead of lost.
+//!
+//! A running execution holds a lease in the store, renewed while it runs, so
+//! orchestrators sharing the store don't resume an execution another one is
+//! still running. The lease of a crashed orchestrator expires after
+//! [`EXECUTION_LEASE_TTL`].
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::config::ChainCheckpointConfig;
+use crate::modules::chain_engine::budget::ChainUsage;
+use crate::modules::chain_engine::context::{ChainContext, StepResult};
+use crate::modules::chain_engine::definition::Chain;
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::common::{RedisConnection, RedisConnector};
+use crate::modules::encryption::EnvelopeEncryptor;
+
+/// Time an execution lease lasts without being renewed
+pub const EXECUTION_LEASE_TTL: Duration = Duration::from_secs(30);
+
+/// Status of a chain execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    /// The execution is in progress (or was interrupted)
+    Running,
+    /// The execution finished successfully
+    Completed,
+    /// The execution stopped with an error
+    Failed,
+    /// The execution was cancelled
+    Cancelled,
+    /// The execution stopped on reaching a limit of its budget
+    BudgetExceeded,
+}
+
+impl ExecutionStatus {
+    /// Whether the execution can be resumed
+    pub fn is_resumable(&self) -> bool {
+        matches!(self, Self::Running | Self::Failed)
+    }
+}
+
+/// Persisted result of a completed step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointedStep {
+    pub outputs: HashMap<String, serde_json::Value>,
+    pub error: Option<String>,
+    pub execution_time_ms: u64,
+}
+
+/// Retry of a failed step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRetry {
+    pub step_id: String,
+    /// Retry number, counting from 1
+    pub retry: u32,
+    /// Error of the attempt before the retry
+    pub error: String,
+    pub at: DateTime<Utc>,
+}
+
+/// Compensation of a completed step after a later step failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepCompensation {
+    /// Step undone
+    pub step_id: String,
+    /// Step that undid it
+    pub compensation_step: String,
+    /// Error of the compensation step, if it failed
+    pub error: Option<String>,
+    pub at: DateTime<Utc>,
+}
+
+/// Persisted state of a chain execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainCheckpoint {
+    pub execution_id: String,
+    pub chain: Chain,
+    pub status: ExecutionStatus,
+    pub inputs: HashMap<String, serde_json::Value>,
+    pub variables: HashMap<String, serde_json::Value>,
+    /// Completed steps, in completion order
+    pub completed_steps: Vec<String>,
+    pub step_results: HashMap<String, CheckpointedStep>,
+    pub outputs: HashMap<String, serde_json::Value>,
+    pub error: Option<String>,
+    /// Retries performed, in order
+    #[serde(default)]
+    pub retries: Vec<StepRetry>,
+    /// Compensations performed, in order
+    #[serde(default)]
+    pub compensations: Vec<StepCompensation>,
+    /// Resources used so far, counted against the chain budget on resume
+    #[serde(default)]
+    pub usage: ChainUsage,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ChainCheckpoint {
+    /// Create a checkpoint for a new execution
+    pub fn new(execution_id: impl Into<String>, chain: &Chain, context: &ChainContext) -> Self {
+        let now = Utc::now();
+        Self {
+            execution_id: execution_id.into(),
+            chain: chain.clone(),
+            status: ExecutionStatus::Running,
+            inputs: context.inputs.clone(),
+            variables: context.variables.clone(),
+            completed_steps: Vec::new(),
+            step_results: HashMap::new(),
+            outputs: HashMap::new(),
+            error: None,
+            retries: Vec::new(),
+            compensations: Vec::new(),
+            usage: ChainUsage::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Record the context state after a step completed
+    pub fn record_step(&mut self, step_id: &str, context: &ChainContext) {
+        if !self.completed_steps.iter().any(|s| s == step_id) {
+            self.completed_steps.push(step_id.to_string());
+        }
+        self.variables = context.variables.clone();
+        self.step_results = context
+            .step_results
+            .iter()
+            .map(|(id, result)| {
+                let step = CheckpointedStep {
+                    outputs: result.outputs.clone(),
+                    error: result.error.clone(),
+                    execution_time_ms: result.execution_time.as_millis() as u64,
+                };
+                (id.clone(), step)
+            })
+            .collect();
+        self.updated_at = Utc::now();
+    }
+
+    /// Mark the execution as finished
+    pub fn finish(&mut self, status: ExecutionStatus, error: Option<String>) {
+        self.status = status;
+        self.error = error;
+        self.updated_at = Utc::now();
+    }
+
+    /// Rebuild the execution context from the checkpoint
+    pub fn to_context(&self) -> ChainContext {
+        ChainContext {
+            chain_id: self.chain.id.clone(),
+            variables: self.variables.clone(),
+            step_results: self
+                .step_results
+                .iter()
+                .map(|(id, step)| {
+                    let result = StepResult {
+                        step_id: id.clone(),
+                        outputs: step.outputs.clone(),
+                        error: step.error.clone(),
+                        execution_time: Duration::from_millis(step.execution_time_ms),
+                        usage: None,
+                    };
+                    (id.clone(), result)
+                })
+                .collect(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+}
+
+/// Storage for chain execution checkpoints
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Save a checkpoint, replacing any previous one for the execution
+    async fn save(&self, checkpoint: &ChainCheckpoint) -> ChainResult<()>;
+
+    /// Load the checkpoint of an execution
+    async fn load(&self, execution_id: &str) -> ChainResult<Option<ChainCheckpoint>>;
+
+    /// Delete the checkpoint of an execution
+    async fn delete(&self, execution_id: &str) -> ChainResult<()>;
+
+    /// List all checkpoints
+    async fn list(&self) -> ChainResult<Vec<ChainCheckpoint>>;
+
+    /// Lease an execution to `holder` for `ttl`, unless another holder's
+    /// lease is live
+    ///
+    /// Returns whether `holder` holds the lease. Claiming a lease already
+    /// held renews it.
+    async fn claim(&self, execution_id: &str, holder: &str, ttl: Duration) -> ChainResult<bool>;
+
+    /// Release the lease of an execution, if `holder` holds it
+    async fn release(&self, execution_id: &str, holder: &str) -> ChainResult<()>;
+}
+
+/// In-memory checkpoint store, for single-process deployments and tests
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: RwLock<HashMap<String, ChainCheckpoint>>,
+    /// Holder and expiry of the lease of each execution
+    leases: RwLock<HashMap<String, (String, Instant)>>,
+}
+
+impl InMemoryCheckpointStore {
+    /// Create a new in-memory checkpoint store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn save(&self, checkpoint: &ChainCheckpoint) -> ChainResult<()> {
+        self.checkpoints
+            .write()
+            .unwrap()
+            .insert(checkpoint.execution_id.clone(), checkpoint.clone());
+        Ok(())
+    }
+
+    async fn load(&self, execution_id: &str) -> ChainResult<Option<ChainCheckpoint>> {
+        Ok(self.checkpoints.read().unwrap().get(execution_id).cloned())
+    }
+
+    async fn delete(&self, execution_id: &str) -> ChainResult<()> {
+        self.checkpoints.write().unwrap().remove(execution_id);
+        Ok(())
+    }
+
+    async fn list(&self) -> ChainResult<Vec<ChainCheckpoint>> {
+        Ok(self.checkpoints.read().unwrap().values().cloned().collect())
+    }
+
+    async fn claim(&self, execution_id: &str, holder: &str, ttl: Duration) -> ChainResult<bool> {
+        let mut leases = self.leases.write().unwrap();
+        let now = Instant::now();
+        match leases.get(execution_id) {
+            Some((owner, expiry)) if owner != holder && *expiry > now => Ok(false),
+            _ => {
+                leases.insert(execution_id.to_string(), (holder.to_string(), now + ttl));
+                Ok(true)
+            }
+        }
+    }
+
+    async fn release(&self, execution_id: &str, holder: &str) -> ChainResult<()> {
+        let mut leases = self.leases.write().unwrap();
+        if leases
+            .get(execution_id)
+            .is_some_and(|(owner, _)| owner == holder)
+        {
+            leases.remove(execution_id);
+        }
+        Ok(())
+    }
+}
+
+/// Lease an execution unless another holder's lease is live, renewing a
+/// lease already held
+const CLAIM_SCRIPT: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+    return 1
+end
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    redis.call('PEXPIRE', KEYS[1], ARGV[2])
+    return 1
+end
+return 0
+"#;
+
+/// Delete a lease if its holder releases it
+const RELEASE_SCRIPT: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Redis-backed checkpoint store
+///
+/// The IDs of stored checkpoints are kept in a set, so listing them doesn't
+/// scan the keyspace. IDs of expired checkpoints are dropped from the set
+/// when listed.
+pub struct RedisCheckpointStore {
+    redis: RedisConnector,
+    prefix: String,
+    ttl: Duration,
+    /// Encrypts checkpoints at rest, if enabled
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+}
+
+impl RedisCheckpointStore {
+    /// Create a new Redis checkpoint store
+    ///
+    /// Checkpoints expire `ttl` after their last update.
+    pub fn new(redis_url: &str, prefix: &str, ttl: Duration) -> ChainResult<Self> {
+        let redis = RedisConnector::from_url(redis_url)
+            .map_err(|e| ChainError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        Ok(Self::from_connector(redis, prefix, ttl))
+    }
+
+    /// Create a Redis checkpoint store connecting through a connector
+    pub fn from_connector(redis: RedisConnector, prefix: &str, ttl: Duration) -> Self {
+        Self {
+            redis,
+            prefix: prefix.to_string(),
+            ttl,
+            encryptor: None,
+        }
+    }
+
+    /// Encrypt checkpoints, which hold step inputs and outputs, at rest
+    pub fn with_encryption(mut self, encryptor: Arc<EnvelopeEncryptor>) -> Self {
+        self.encryptor = Some(encryptor);
+        self
+    }
+
+    /// Seal a serialized checkpoint if encryption is enabled
+    async fn seal(&self, json: String, key: &str) -> ChainResult<String> {
+        match &self.encryptor {
+            Some(encryptor) => encryptor
+                .seal_str(&json, key)
+                .await
+                .map_err(|e| ChainError::StorageError(e.to_string())),
+            None => Ok(json),
+        }
+    }
+
+    /// Open a stored checkpoint and deserialize it
+    async fn open(&self, data: String, key: &str) -> ChainResult<ChainCheckpoint> {
+        let json = match &self.encryptor {
+            Some(encryptor) => encryptor
+                .open_str(data, key)
+                .await
+                .map_err(|e| ChainError::StorageError(e.to_string()))?,
+            None => data,
+        };
+        serde_json::from_str(&json).map_err(|e| ChainError::DeserializationError(e.to_string()))
+    }
+
+    /// Generate a Redis key with the configured prefix
+    fn get_key(&self, execution_id: &str) -> String {
+        format!("{}:{}", self.prefix, execution_id)
+    }
+
+    /// Key of the set of stored execution IDs
+    ///
+    /// The index and leases are kept outside of the checkpoints' `prefix:*`
+    /// keys, which `migrate-encryption` seals.
+    fn index_key(&self) -> String {
+        format!("{}-index", self.prefix)
+    }
+
+    /// Key of the lease of an execution
+    fn lease_key(&self, execution_id: &str) -> String {
+        format!("{}-lease:{}", self.prefix, execution_id)
+    }
+
+    async fn connection(&self) -> ChainResult<RedisConnection> {
+        self.redis
+            .connection()
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis connection error: {}", e)))
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for RedisCheckpointStore {
+    async fn save(&self, checkpoint: &ChainCheckpoint) -> ChainResult<()> {
+        let mut conn = self.connection().await?;
+        let key = self.get_key(&checkpoint.execution_id);
+        let json = serde_json::to_string(checkpoint)
+            .map_err(|e| ChainError::SerializationError(e.to_string()))?;
+        let json = self.seal(json, &key).await?;
+        let ttl = self.ttl.as_secs().max(1) as usize;
+
+        // The checkpoint and the index hash to different cluster slots, so
+        // they are written separately
+        conn.set_ex(key, json, ttl)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        let index = self.index_key();
+        conn.sadd(&index, &checkpoint.execution_id)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        conn.expire(&index, ttl)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))
+    }
+
+    async fn load(&self, execution_id: &str) -> ChainResult<Option<ChainCheckpoint>> {
+        let mut conn = self.connection().await?;
+        let key = self.get_key(execution_id);
+        let json: Option<String> = conn
+            .get(&key)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+
+        match json {
+            Some(json) => self.open(json, &key).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn delete(&self, execution_id: &str) -> ChainResult<()> {
+        let mut conn = self.connection().await?;
+        conn.del(self.get_key(execution_id))
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        conn.srem(self.index_key(), execution_id)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))
+    }
+
+    async fn list(&self) -> ChainResult<Vec<ChainCheckpoint>> {
+        let mut conn = self.connection().await?;
+        let index = self.index_key();
+        let ids: Vec<String> = conn
+            .smembers(&index)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+
+        let mut checkpoints = Vec::with_capacity(ids.len());
+        for id in ids {
+            let key = self.get_key(&id);
+            let json: Option<String> = conn
+                .get(&key)
+                .await
+                .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+            match json {
+                Some(json) => checkpoints.push(self.open(json, &key).await?),
+                // The checkpoint expired
+                None => conn
+                    .srem(&index, &id)
+                    .await
+                    .map(|_: redis::Value| ())
+                    .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?,
+            }
+        }
+
+        Ok(checkpoints)
+    }
+
+    async fn claim(&self, execution_id: &str, holder: &str, ttl: Duration) -> ChainResult<bool> {
+        let mut conn = self.connection().await?;
+        let claimed: i64 = redis::cmd("EVAL")
+            .arg(CLAIM_SCRIPT)
+            .arg(1)
+            .arg(self.lease_key(execution_id))
+            .arg(holder)
+            .arg(ttl.as_millis().max(1) as u64)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        Ok(claimed == 1)
+    }
+
+    async fn release(&self, execution_id: &str, holder: &str) -> ChainResult<()> {
+        let mut conn = self.connection().await?;
+        redis::cmd("EVAL")
+            .arg(RELEASE_SCRIPT)
+            .arg(1)
+            .arg(self.lease_key(execution_id))
+            .arg(holder)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))
+    }
+}
+
+/// Create the checkpoint store described by the configuration
+///
+/// Returns `None` when checkpointing is disabled.
+///
+/// Redis checkpoints are encrypted at rest when an encryptor is given.
+pub fn store_from_config(
+    config: &ChainCheckpointConfig,
+    redis: Option<&RedisConnector>,
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+) -> ChainResult<Option<Arc<dyn CheckpointStore>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    match config.backend.as_str() {
+        "memory" => Ok(Some(Arc::new(InMemoryCheckpointStore::new()))),
+        "redis" => {
+            let redis = redis.ok_or_else(|| {
+                ChainError::ValidationError(
+                    "Redis checkpoint backend requires memory.redis_url".to_string(),
+                )
+            })?;
+            let mut store = RedisCheckpointStore::from_connector(
+                redis.clone(),
+                &config.key_prefix,
+                Duration::from_secs(config.ttl_secs),
+            );
+            if let Some(encryptor) = encryptor {
+                store = store.with_encryption(encryptor);
+            }
+            Ok(Some(Arc::new(store)))
+        }
+        other => Err(ChainError::ValidationError(format!(
+            "Unknown checkpoint backend: {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::chain_engine::engine::ChainEngine;
+    use serde_json::json;
+
+    /// Chain extracting a number with a regex, then reading it back via JSONPath
+    fn chain() -> Chain {
+        serde_json::from_value(json!({
+            "id": "extract",
+            "name": "Extract",
+            "description": "Two-step extraction",
+            "version": "1.0.0",
+            "steps": {
+                "find": {
+                    "id": "find",
+                    "name": "Find",
+                    "description": "Find the order number",
+                    "role": "system",
+                    "step_type": {
+                        "type": "RegexExtract",
+                        "config": {"input": "{{inputs.text}}", "pattern": "#(?P<order>\\d+)"}
+                    }
+                },
+                "read": {
+                    "id": "read",
+                    "name": "Read",
+                    "description": "Read the order number",
+                    "role": "system",
+                    "step_type": {
+                        "type": "JsonTransform",
+                        "config": {"input": "{{steps.find}}", "mappings": {"order": "$.order"}}
+                    }
+                }
+            },
+            "dependencies": [{
+                "dependent_step": "read",
+                "dependency_type": {"type": "Simple", "config": {"required_step": "find"}}
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_and_resume() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let engine = ChainEngine::new().with_checkpoint_store(store.clone());
+        let inputs = HashMap::from([("text".to_string(), json!("Order #42 shipped"))]);
+
+        engine
+            .execute_chain_with_id("exec-1", &chain(), inputs)
+            .await
+            .unwrap();
+        let done = engine.get_execution("exec-1").await.unwrap().unwrap();
+        assert_eq!(done.status, ExecutionStatus::Completed);
+        assert_eq!(done.completed_steps, vec!["find", "read"]);
+        assert_eq!(done.step_results["read"].outputs["order"], json!("42"));
+        assert!(engine.resume_execution("exec-1").await.is_err());
+
+        // Simulate a crash after the first step; resuming must not rerun it
+        let mut interrupted = done.clone();
+        interrupted.execution_id = "exec-2".to_string();
+        interrupted.status = ExecutionStatus::Running;
+        interrupted.completed_steps = vec!["find".to_string()];
+        interrupted.step_results.remove("read");
+        interrupted
+            .step_results
+            .get_mut("find")
+            .unwrap()
+            .outputs
+            .insert("order".to_string(), json!("7"));
+        store.save(&interrupted).await.unwrap();
+
+        assert_eq!(
+            engine.interrupted_executions().await.unwrap(),
+            vec!["exec-2"]
+        );
+        engine.resume_execution("exec-2").await.unwrap();
+        let resumed = store.load("exec-2").await.unwrap().unwrap();
+        assert_eq!(resumed.status, ExecutionStatus::Completed);
+        assert_eq!(resumed.step_results["read"].outputs["order"], json!("7"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_execution() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let engine = ChainEngine::new().with_checkpoint_store(store.clone());
+
+        let context = ChainContext {
+            chain_id: "extract".to_string(),
+            variables: HashMap::new(),
+            step_results: HashMap::new(),
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+        };
+        store
+            .save(&ChainCheckpoint::new("exec", &chain(), &context))
+            .await
+            .unwrap();
+
+        let cancelled = engine.cancel_execution("exec").await.unwrap();
+        assert_eq!(cancelled.status, ExecutionStatus::Cancelled);
+        assert!(engine.resume_execution("exec").await.is_err());
+        assert!(engine.cancel_execution("exec").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execution_lease() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let ttl = Duration::from_secs(60);
+        assert!(store.claim("exec", "a", ttl).await.unwrap());
+        assert!(store.claim("exec", "a", ttl).await.unwrap());
+        assert!(!store.claim("exec", "b", ttl).await.unwrap());
+        store.release("exec", "b").await.unwrap();
+        assert!(!store.claim("exec", "b", ttl).await.unwrap());
+        store.release("exec", "a").await.unwrap();
+        assert!(store.claim("exec", "b", ttl).await.unwrap());
+        assert!(store.claim("expired", "a", Duration::ZERO).await.unwrap());
+        assert!(store.claim("expired", "b", ttl).await.unwrap());
+
+        // An execution leased by another orchestrator isn't resumed
+        let engine = ChainEngine::new().with_checkpoint_store(store.clone());
+        let context = ChainContext {
+            chain_id: "extract".to_string(),
+            variables: HashMap::new(),
+            step_results: HashMap::new(),
+            inputs: HashMap::from([("text".to_string(), json!("Order #42 shipped"))]),
+            outputs: HashMap::new(),
+        };
+        store
+            .save(&ChainCheckpoint::new("exec", &chain(), &context))
+            .await
+            .unwrap();
+        assert!(matches!(
+            engine.resume_execution("exec").await,
+            Err(ChainError::ExecutionLeased(_))
+        ));
+        store.release("exec", "b").await.unwrap();
+        engine.resume_execution("exec").await.unwrap();
+        // The lease is released once the execution finished
+        assert!(store.claim("exec", "b", ttl).await.unwrap());
+    }
+}
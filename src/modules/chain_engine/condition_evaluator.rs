@@ -7,6 +7,8 @@ use regex::Regex;
 use crate::modules::chain_engine::context::ChainContext;
 use crate::modules::chain_engine::definition::{ComparisonOperator, Condition};
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::template::template_data;
+use crate::modules::chain_engine::expression::Reference;
 
 /// Condition evaluator for chain execution
 #[derive(Clone, Default)]
@@ -146,17 +148,15 @@ impl ConditionEvaluator {
     /// Evaluate an expression
     fn evaluate_expression(&self, expression: &str, context: &ChainContext) -> ChainResult<bool> {
         // Simple expression evaluation
-        // This is a simplified implementation that only handles variable references
-        // and basic boolean expressions
+        // This is a simplified implementation that only handles references, such
+        // as `{{steps.check.output.valid}}`, and basic boolean expressions
 
-        // Check if the expression is a variable reference
+        // Check if the expression is a reference
         if expression.starts_with("{{") && expression.ends_with("}}") {
-            let var_name = expression[2..expression.len() - 2].trim();
-            let var_value = context.variables.get(var_name).ok_or_else(|| {
-                ChainError::VariableNotFound(format!("Variable not found: {}", var_name))
-            })?;
+            let var_value =
+                self.resolve_reference(&expression[2..expression.len() - 2], context)?;
 
-            let value_str = match var_value {
+            let value_str = match &var_value {
                 serde_json::Value::String(s) => s.clone(),
                 _ => var_value.to_string(),
             };
@@ -251,10 +251,7 @@ impl ConditionEvaluator {
     fn resolve_value(&self, value: &str, context: &ChainContext) -> ChainResult<serde_json::Value> {
         // Check if the value is a variable reference
         if value.starts_with("{{") && value.ends_with("}}") {
-            let var_name = value[2..value.len() - 2].trim();
-            return context.variables.get(var_name).cloned().ok_or_else(|| {
-                ChainError::VariableNotFound(format!("Variable not found: {}", var_name))
-            });
+            return self.resolve_reference(&value[2..value.len() - 2], context);
         }
 
         // Try to parse as JSON
@@ -264,6 +261,18 @@ impl ConditionEvaluator {
         }
     }
 
+    /// Resolve a reference, e.g. `steps.retrieve.output.documents[0].text`
+    fn resolve_reference(
+        &self,
+        expression: &str,
+        context: &ChainContext,
+    ) -> ChainResult<serde_json::Value> {
+        Reference::parse(expression)?
+            .resolve(&template_data(context))
+            .cloned()
+            .ok_or_else(|| ChainError::VariableNotFound(expression.trim().to_string()))
+    }
+
     /// Convert a value to a number
     fn to_number(&self, value: &serde_json::Value) -> ChainResult<f64> {
         match value {
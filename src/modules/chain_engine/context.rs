@@ -5,6 +5,8 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use crate::modules::chain_engine::budget::StepUsage;
+
 /// Result of a step execution
 #[derive(Debug, Clone)]
 pub struct StepResult {
@@ -12,6 +14,8 @@ pub struct StepResult {
     pub outputs: HashMap<String, serde_json::Value>,
     pub error: Option<String>,
     pub execution_time: Duration,
+    /// Model usage of the step, counted against the execution's budget
+    pub usage: Option<StepUsage>,
 }
 
 /// Context for chain execution
@@ -0,0 +1,473 @@
+//! Multi-Agent Conversations
+//!
+//! This module lets several agents converse about a topic. Speakers take
+//! turns in round-robin order or are picked by a moderator agent; each turn
+//! is a full agent run, so participants can use tools, including a
+//! scratchpad shared by all participants. The full transcript, moderator
+//! decisions and scratchpad are captured for auditing.
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::modules::chain_engine::agent::{AgentDefinition, AgentRuntime, AgentStopReason};
+use crate::modules::chain_engine::budget::StepUsage;
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::model_registry::connectors::ToolDefinition;
+use crate::modules::tools::{function_definition, Tool, ToolError};
+
+/// Name of the tool reading the shared scratchpad
+pub const SCRATCHPAD_READ_TOOL: &str = "scratchpad_read";
+
+/// Name of the tool writing to the shared scratchpad
+pub const SCRATCHPAD_WRITE_TOOL: &str = "scratchpad_write";
+
+/// Moderator reply ending the conversation
+const MODERATOR_END: &str = "DONE";
+
+/// Definition of a multi-agent conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationDefinition {
+    /// Agents taking part, each with its own model and persona
+    pub participants: Vec<Participant>,
+    /// How the next speaker is chosen
+    #[serde(default)]
+    pub turn_policy: TurnPolicy,
+    /// Maximum number of turns
+    #[serde(default = "default_max_turns")]
+    pub max_turns: u32,
+    /// Phrase ending the conversation when a participant says it
+    #[serde(default)]
+    pub end_phrase: Option<String>,
+    /// Whether participants get the shared scratchpad tools
+    #[serde(default = "default_scratchpad")]
+    pub scratchpad: bool,
+}
+
+fn default_max_turns() -> u32 {
+    6
+}
+
+fn default_scratchpad() -> bool {
+    true
+}
+
+/// A participant of a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Participant {
+    /// Name other participants address this one by
+    pub name: String,
+    /// Agent speaking for the participant
+    pub agent: AgentDefinition,
+}
+
+/// How the next speaker of a conversation is chosen
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TurnPolicy {
+    /// Participants speak in order
+    #[default]
+    RoundRobin,
+    /// A moderator agent names the next speaker, or `DONE` to end
+    Moderator { moderator: AgentDefinition },
+}
+
+/// Why a conversation ended
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversationEndReason {
+    /// The turn budget was exhausted
+    MaxTurns,
+    /// A participant said the end phrase
+    EndPhrase,
+    /// The moderator ended the conversation
+    Moderator,
+}
+
+/// A turn of a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTurn {
+    /// Turn number, starting at 1
+    pub index: u32,
+    pub speaker: String,
+    pub model: String,
+    /// What the speaker said (empty if its run ended without an answer)
+    pub content: String,
+    /// Agent run behind the turn, for its full trace
+    pub run_id: String,
+    pub stop_reason: AgentStopReason,
+    pub tool_calls: usize,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A moderator's choice of the next speaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeratorDecision {
+    /// Turn the decision was made for
+    pub turn: u32,
+    /// Raw moderator reply
+    pub reply: String,
+    /// Chosen speaker, absent if the moderator ended the conversation
+    pub speaker: Option<String>,
+    /// Whether the reply named no participant and round-robin was used
+    pub fallback: bool,
+}
+
+/// An entry of the shared scratchpad
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScratchpadEntry {
+    pub author: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Transcript of a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationTranscript {
+    pub conversation_id: String,
+    pub topic: String,
+    pub participants: Vec<String>,
+    pub turns: Vec<ConversationTurn>,
+    pub moderator_decisions: Vec<ModeratorDecision>,
+    pub scratchpad: Vec<ScratchpadEntry>,
+    pub end_reason: ConversationEndReason,
+    /// Usage of the model calls of every turn and moderator decision
+    #[serde(default)]
+    pub usage: StepUsage,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+impl ConversationTranscript {
+    /// Content of the last turn
+    pub fn last_message(&self) -> Option<&str> {
+        self.turns.last().map(|turn| turn.content.as_str())
+    }
+}
+
+/// Scratchpad shared by the participants of a conversation
+type Scratchpad = Arc<RwLock<Vec<ScratchpadEntry>>>;
+
+/// Tool reading the shared scratchpad
+struct ScratchpadReadTool {
+    scratchpad: Scratchpad,
+}
+
+#[async_trait]
+impl Tool for ScratchpadReadTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            SCRATCHPAD_READ_TOOL,
+            "Read the notes all participants have written to the shared scratchpad",
+            json!({ "type": "object", "properties": {} }),
+        )
+    }
+
+    async fn call(&self, _arguments: Value) -> Result<Value, ToolError> {
+        let entries = self.scratchpad.read().unwrap().clone();
+        serde_json::to_value(entries).map_err(|e| ToolError::Execution(e.to_string()))
+    }
+}
+
+/// Tool writing to the shared scratchpad as a participant
+struct ScratchpadWriteTool {
+    scratchpad: Scratchpad,
+    author: String,
+}
+
+#[async_trait]
+impl Tool for ScratchpadWriteTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            SCRATCHPAD_WRITE_TOOL,
+            "Add a note to the scratchpad shared with the other participants",
+            json!({
+                "type": "object",
+                "properties": {
+                    "note": {"type": "string", "description": "Note to add"}
+                },
+                "required": ["note"]
+            }),
+        )
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, ToolError> {
+        let note = arguments
+            .get("note")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::InvalidArguments("note is required".to_string()))?;
+        let mut scratchpad = self.scratchpad.write().unwrap();
+        scratchpad.push(ScratchpadEntry {
+            author: self.author.clone(),
+            content: note.to_string(),
+            timestamp: Utc::now(),
+        });
+        Ok(json!({ "entries": scratchpad.len() }))
+    }
+}
+
+/// Validate a conversation definition
+pub fn validate_conversation(definition: &ConversationDefinition) -> ChainResult<()> {
+    if definition.participants.len() < 2 {
+        return Err(ChainError::ValidationError(
+            "A conversation needs at least two participants".to_string(),
+        ));
+    }
+    if definition.max_turns == 0 {
+        return Err(ChainError::ValidationError(
+            "Conversation max_turns must be greater than 0".to_string(),
+        ));
+    }
+
+    let mut names = std::collections::HashSet::new();
+    for participant in &definition.participants {
+        if participant.name.is_empty() || participant.agent.model.is_empty() {
+            return Err(ChainError::ValidationError(
+                "Participant name and model cannot be empty".to_string(),
+            ));
+        }
+        if !names.insert(participant.name.to_lowercase()) {
+            return Err(ChainError::ValidationError(format!(
+                "Duplicate participant: {}",
+                participant.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Run a conversation about a topic
+pub(crate) async fn run_conversation(
+    runtime: &AgentRuntime,
+    definition: &ConversationDefinition,
+    topic: &str,
+) -> ChainResult<ConversationTranscript> {
+    validate_conversation(definition)?;
+
+    let started_at = Utc::now();
+    let scratchpad: Scratchpad = Arc::new(RwLock::new(Vec::new()));
+    let names: Vec<String> = definition
+        .participants
+        .iter()
+        .map(|p| p.name.clone())
+        .collect();
+
+    // Each participant gets the runtime's tools plus the scratchpad, writing
+    // under its own name
+    let participants: Vec<(AgentDefinition, crate::modules::tools::ToolRegistry)> = definition
+        .participants
+        .iter()
+        .map(|participant| {
+            let tools = runtime.tools().fork();
+            let mut agent = participant.agent.clone();
+            if definition.scratchpad {
+                tools.register(Arc::new(ScratchpadReadTool {
+                    scratchpad: scratchpad.clone(),
+                }));
+                tools.register(Arc::new(ScratchpadWriteTool {
+                    scratchpad: scratchpad.clone(),
+                    author: participant.name.clone(),
+                }));
+                agent.tools.push(SCRATCHPAD_READ_TOOL.to_string());
+                agent.tools.push(SCRATCHPAD_WRITE_TOOL.to_string());
+            }
+            agent.id = participant.name.clone();
+            (agent, tools)
+        })
+        .collect();
+
+    let mut turns: Vec<ConversationTurn> = Vec::new();
+    let mut moderator_decisions = Vec::new();
+    let mut end_reason = ConversationEndReason::MaxTurns;
+    let mut usage = StepUsage::default();
+
+    for index in 1..=definition.max_turns {
+        let round_robin = (index as usize - 1) % participants.len();
+        let speaker = match &definition.turn_policy {
+            TurnPolicy::RoundRobin => round_robin,
+            TurnPolicy::Moderator { moderator } => {
+                let prompt = moderator_prompt(topic, &names, &turns);
+                let run = runtime
+                    .run_with_tools(
+                        moderator,
+                        &prompt,
+                        None,
+                        &crate::modules::tools::ToolRegistry::default(),
+                    )
+                    .await?;
+                usage.add(&run.usage);
+                let reply = run.answer.unwrap_or_default();
+                if reply.trim().eq_ignore_ascii_case(MODERATOR_END) {
+                    moderator_decisions.push(ModeratorDecision {
+                        turn: index,
+                        reply,
+                        speaker: None,
+                        fallback: false,
+                    });
+                    end_reason = ConversationEndReason::Moderator;
+                    break;
+                }
+
+                let chosen = pick_speaker(&reply, &names);
+                moderator_decisions.push(ModeratorDecision {
+                    turn: index,
+                    reply,
+                    speaker: Some(names[chosen.unwrap_or(round_robin)].clone()),
+                    fallback: chosen.is_none(),
+                });
+                chosen.unwrap_or(round_robin)
+            }
+        };
+
+        let (agent, tools) = &participants[speaker];
+        let input = participant_prompt(topic, &names[speaker], &names, &turns);
+        let run = runtime.run_with_tools(agent, &input, None, tools).await?;
+        usage.add(&run.usage);
+        let content = run.answer.clone().unwrap_or_default();
+        debug!("Conversation turn {}: {} spoke", index, names[speaker]);
+
+        let ended = definition
+            .end_phrase
+            .as_deref()
+            .is_some_and(|phrase| content.contains(phrase));
+        turns.push(ConversationTurn {
+            index,
+            speaker: names[speaker].clone(),
+            model: agent.model.clone(),
+            content,
+            run_id: run.run_id,
+            stop_reason: run.stop_reason,
+            tool_calls: run.steps.iter().map(|s| s.actions.len()).sum(),
+            timestamp: Utc::now(),
+        });
+        if ended {
+            end_reason = ConversationEndReason::EndPhrase;
+            break;
+        }
+    }
+
+    let scratchpad = scratchpad.read().unwrap().clone();
+    Ok(ConversationTranscript {
+        conversation_id: Uuid::new_v4().to_string(),
+        topic: topic.to_string(),
+        participants: names,
+        turns,
+        moderator_decisions,
+        scratchpad,
+        end_reason,
+        usage,
+        started_at,
+        finished_at: Utc::now(),
+    })
+}
+
+/// Render the transcript so far as `name: content` lines
+fn render_transcript(turns: &[ConversationTurn]) -> String {
+    if turns.is_empty() {
+        return "(nobody has spoken yet)".to_string();
+    }
+    turns
+        .iter()
+        .map(|turn| format!("{}: {}", turn.speaker, turn.content))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Input given to a participant for its turn
+fn participant_prompt(
+    topic: &str,
+    speaker: &str,
+    names: &[String],
+    turns: &[ConversationTurn],
+) -> String {
+    format!(
+        "You are {} in a conversation with {}.\nTopic: {}\n\nConversation so far:\n{}\n\nReply with your next message.",
+        speaker,
+        names
+            .iter()
+            .filter(|name| name.as_str() != speaker)
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(", "),
+        topic,
+        render_transcript(turns)
+    )
+}
+
+/// Input given to the moderator to pick the next speaker
+fn moderator_prompt(topic: &str, names: &[String], turns: &[ConversationTurn]) -> String {
+    format!(
+        "You moderate a conversation between {}.\nTopic: {}\n\nConversation so far:\n{}\n\nReply with only the name of the participant who should speak next, or {} if the conversation is complete.",
+        names.join(", "),
+        topic,
+        render_transcript(turns),
+        MODERATOR_END
+    )
+}
+
+/// Index of the participant named in a moderator reply
+///
+/// An exact match wins; otherwise the earliest mentioned name is used.
+fn pick_speaker(reply: &str, names: &[String]) -> Option<usize> {
+    let reply = reply.trim().trim_matches(|c: char| !c.is_alphanumeric());
+    if let Some(index) = names.iter().position(|n| n.eq_ignore_ascii_case(reply)) {
+        return Some(index);
+    }
+
+    let lower = reply.to_lowercase();
+    names
+        .iter()
+        .enumerate()
+        .filter_map(|(index, name)| lower.find(&name.to_lowercase()).map(|pos| (pos, index)))
+        .min()
+        .map(|(_, index)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        vec!["Critic".to_string(), "Writer".to_string()]
+    }
+
+    #[test]
+    fn test_pick_speaker() {
+        assert_eq!(pick_speaker("Writer", &names()), Some(1));
+        assert_eq!(pick_speaker(" critic. ", &names()), Some(0));
+        assert_eq!(
+            pick_speaker("I think the Writer should answer the Critic", &names()),
+            Some(1)
+        );
+        assert_eq!(pick_speaker("nobody", &names()), None);
+    }
+
+    #[test]
+    fn test_validate_conversation() {
+        let agent: AgentDefinition = serde_json::from_value(json!({ "model": "gpt-4o" })).unwrap();
+        let participant = |name: &str| Participant {
+            name: name.to_string(),
+            agent: agent.clone(),
+        };
+
+        let mut definition = ConversationDefinition {
+            participants: vec![participant("Critic"), participant("Writer")],
+            turn_policy: TurnPolicy::RoundRobin,
+            max_turns: 4,
+            end_phrase: None,
+            scratchpad: true,
+        };
+        assert!(validate_conversation(&definition).is_ok());
+
+        definition.participants.push(participant("critic"));
+        assert!(validate_conversation(&definition).is_err());
+
+        definition.participants.truncate(1);
+        assert!(validate_conversation(&definition).is_err());
+    }
+}
@@ -0,0 +1,518 @@
+//! Chain dead-letter queue
+//!
+//! This module keeps chain steps that failed for good and webhook deliveries
+//! that failed after all retries, with the context needed to retry them: the
+//! execution state at the failed step, or the undelivered event. Dead letters
+//! are kept in memory or persisted in Redis, and can be inspected, retried and
+//! purged through the chain API.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+
+use crate::config::ChainDeadLetterConfig;
+use crate::modules::chain_engine::checkpoint::ChainCheckpoint;
+use crate::modules::chain_engine::engine::ChainEvent;
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::common::{RedisConnection, RedisConnector};
+use crate::modules::encryption::EnvelopeEncryptor;
+use crate::modules::telemetry::catalog;
+
+/// What failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeadLetterSource {
+    /// A chain step
+    Step {
+        step_id: String,
+        /// State of the execution when the step failed
+        execution: Box<ChainCheckpoint>,
+    },
+    /// An outbound webhook delivery
+    Webhook { url: String, event: ChainEvent },
+}
+
+impl DeadLetterSource {
+    /// Kind of the dead letter, `step` or `webhook`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Step { .. } => "step",
+            Self::Webhook { .. } => "webhook",
+        }
+    }
+}
+
+/// A chain step or webhook delivery that failed for good
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: String,
+    #[serde(flatten)]
+    pub source: DeadLetterSource,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+impl DeadLetter {
+    /// Kind of the dead letter, `step` or `webhook`
+    pub fn kind(&self) -> &'static str {
+        self.source.kind()
+    }
+}
+
+/// Storage for dead letters
+#[async_trait]
+pub trait DeadLetterStore: Send + Sync {
+    /// Add a dead letter, dropping the oldest ones beyond the capacity
+    async fn push(&self, dead_letter: &DeadLetter) -> ChainResult<()>;
+
+    /// Get a dead letter
+    async fn get(&self, id: &str) -> ChainResult<Option<DeadLetter>>;
+
+    /// Remove a dead letter, returning it
+    async fn remove(&self, id: &str) -> ChainResult<Option<DeadLetter>>;
+
+    /// List all dead letters, oldest first
+    async fn list(&self) -> ChainResult<Vec<DeadLetter>>;
+
+    /// Remove all dead letters, returning how many there were
+    async fn purge(&self) -> ChainResult<usize>;
+}
+
+/// In-memory dead-letter store, for single-process deployments and tests
+#[derive(Debug)]
+pub struct InMemoryDeadLetterStore {
+    capacity: usize,
+    dead_letters: Mutex<VecDeque<DeadLetter>>,
+}
+
+impl InMemoryDeadLetterStore {
+    /// Create a store keeping up to `capacity` dead letters
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            dead_letters: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for InMemoryDeadLetterStore {
+    async fn push(&self, dead_letter: &DeadLetter) -> ChainResult<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        while dead_letters.len() >= self.capacity {
+            dead_letters.pop_front();
+        }
+        dead_letters.push_back(dead_letter.clone());
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        let dead_letters = self.dead_letters.lock().unwrap();
+        Ok(dead_letters.iter().find(|d| d.id == id).cloned())
+    }
+
+    async fn remove(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        Ok(dead_letters
+            .iter()
+            .position(|d| d.id == id)
+            .and_then(|index| dead_letters.remove(index)))
+    }
+
+    async fn list(&self) -> ChainResult<Vec<DeadLetter>> {
+        Ok(self.dead_letters.lock().unwrap().iter().cloned().collect())
+    }
+
+    async fn purge(&self) -> ChainResult<usize> {
+        let mut dead_letters = self.dead_letters.lock().unwrap();
+        let purged = dead_letters.len();
+        dead_letters.clear();
+        Ok(purged)
+    }
+}
+
+/// Redis-backed dead-letter store
+///
+/// Each dead letter is kept under `{prefix}:{id}`, and their IDs in a sorted
+/// set under `{prefix}` ordered by failure time. Dead letters do not expire.
+pub struct RedisDeadLetterStore {
+    redis: RedisConnector,
+    prefix: String,
+    capacity: usize,
+    /// Encrypts dead letters at rest, if enabled
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+}
+
+impl RedisDeadLetterStore {
+    /// Create a new Redis dead-letter store keeping up to `capacity` dead
+    /// letters
+    pub fn new(redis_url: &str, prefix: &str, capacity: usize) -> ChainResult<Self> {
+        let redis = RedisConnector::from_url(redis_url)
+            .map_err(|e| ChainError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        Ok(Self::from_connector(redis, prefix, capacity))
+    }
+
+    /// Create a Redis dead-letter store connecting through a connector
+    pub fn from_connector(redis: RedisConnector, prefix: &str, capacity: usize) -> Self {
+        Self {
+            redis,
+            prefix: prefix.to_string(),
+            capacity,
+            encryptor: None,
+        }
+    }
+
+    /// Encrypt dead letters, which hold step inputs and outputs, at rest
+    pub fn with_encryption(mut self, encryptor: Arc<EnvelopeEncryptor>) -> Self {
+        self.encryptor = Some(encryptor);
+        self
+    }
+
+    /// Seal a serialized dead letter if encryption is enabled
+    async fn seal(&self, json: String, key: &str) -> ChainResult<String> {
+        match &self.encryptor {
+            Some(encryptor) => encryptor
+                .seal_str(&json, key)
+                .await
+                .map_err(|e| ChainError::StorageError(e.to_string())),
+            None => Ok(json),
+        }
+    }
+
+    /// Open a stored dead letter and deserialize it
+    async fn open(&self, data: String, key: &str) -> ChainResult<DeadLetter> {
+        let json = match &self.encryptor {
+            Some(encryptor) => encryptor
+                .open_str(data, key)
+                .await
+                .map_err(|e| ChainError::StorageError(e.to_string()))?,
+            None => data,
+        };
+        serde_json::from_str(&json).map_err(|e| ChainError::DeserializationError(e.to_string()))
+    }
+
+    /// Generate a Redis key with the configured prefix
+    fn get_key(&self, id: &str) -> String {
+        format!("{}:{}", self.prefix, id)
+    }
+
+    async fn connection(&self) -> ChainResult<RedisConnection> {
+        self.redis
+            .connection()
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis connection error: {}", e)))
+    }
+
+    /// IDs of the stored dead letters in `start..=stop` of the index
+    async fn ids(
+        &self,
+        conn: &mut RedisConnection,
+        start: isize,
+        stop: isize,
+    ) -> ChainResult<Vec<String>> {
+        conn.zrange(&self.prefix, start, stop)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))
+    }
+
+    /// Delete dead letters and their index entries
+    async fn delete(&self, conn: &mut RedisConnection, ids: &[String]) -> ChainResult<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<String> = ids.iter().map(|id| self.get_key(id)).collect();
+        conn.del(keys)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        conn.zrem(&self.prefix, ids)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for RedisDeadLetterStore {
+    async fn push(&self, dead_letter: &DeadLetter) -> ChainResult<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let mut conn = self.connection().await?;
+        let key = self.get_key(&dead_letter.id);
+        let json = serde_json::to_string(dead_letter)
+            .map_err(|e| ChainError::SerializationError(e.to_string()))?;
+        let json = self.seal(json, &key).await?;
+
+        conn.set(&key, json)
+            .await
+            .map(|_: redis::Value| ())
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        conn.zadd(
+            &self.prefix,
+            &dead_letter.id,
+            dead_letter.failed_at.timestamp_millis(),
+        )
+        .await
+        .map(|_: redis::Value| ())
+        .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+
+        // Drop the oldest dead letters beyond the capacity
+        let len: usize = conn
+            .zcard(&self.prefix)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+        if len > self.capacity {
+            let oldest = self
+                .ids(&mut conn, 0, (len - self.capacity) as isize - 1)
+                .await?;
+            self.delete(&mut conn, &oldest).await?;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        let mut conn = self.connection().await?;
+        let key = self.get_key(id);
+        let json: Option<String> = conn
+            .get(&key)
+            .await
+            .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+
+        match json {
+            Some(json) => self.open(json, &key).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn remove(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        let dead_letter = self.get(id).await?;
+        if dead_letter.is_some() {
+            let mut conn = self.connection().await?;
+            self.delete(&mut conn, &[id.to_string()]).await?;
+        }
+        Ok(dead_letter)
+    }
+
+    async fn list(&self) -> ChainResult<Vec<DeadLetter>> {
+        let mut conn = self.connection().await?;
+        let ids = self.ids(&mut conn, 0, -1).await?;
+
+        let mut dead_letters = Vec::with_capacity(ids.len());
+        for id in ids {
+            let key = self.get_key(&id);
+            let json: Option<String> = conn
+                .get(&key)
+                .await
+                .map_err(|e| ChainError::StorageError(format!("Redis error: {}", e)))?;
+            // Another orchestrator can remove a dead letter between ZRANGE and GET
+            if let Some(json) = json {
+                dead_letters.push(self.open(json, &key).await?);
+            }
+        }
+
+        Ok(dead_letters)
+    }
+
+    async fn purge(&self) -> ChainResult<usize> {
+        let mut conn = self.connection().await?;
+        let ids = self.ids(&mut conn, 0, -1).await?;
+        self.delete(&mut conn, &ids).await?;
+        Ok(ids.len())
+    }
+}
+
+/// Queue of dead letters, recording its depth
+pub struct DeadLetterQueue {
+    store: Arc<dyn DeadLetterStore>,
+}
+
+impl DeadLetterQueue {
+    /// Create a queue over a store
+    pub fn new(store: Arc<dyn DeadLetterStore>) -> Self {
+        Self { store }
+    }
+
+    /// Create an in-memory queue keeping up to `capacity` dead letters
+    pub fn in_memory(capacity: usize) -> Self {
+        Self::new(Arc::new(InMemoryDeadLetterStore::new(capacity)))
+    }
+
+    /// Add a dead letter
+    ///
+    /// Storage errors are logged rather than returned, as the failure being
+    /// dead-lettered is already being reported.
+    pub async fn push(&self, dead_letter: DeadLetter) {
+        let kind = dead_letter.kind();
+        if let Err(e) = self.store.push(&dead_letter).await {
+            error!("Failed to dead-letter {} {}: {}", kind, dead_letter.id, e);
+            return;
+        }
+        counter!(catalog::CHAIN_DEAD_LETTERED, 1, "kind" => kind);
+        self.record_depth().await;
+    }
+
+    /// Dead letters of the given kind, or of all kinds, oldest first
+    pub async fn list(&self, kind: Option<&str>) -> ChainResult<Vec<DeadLetter>> {
+        let mut dead_letters = self.store.list().await?;
+        if let Some(kind) = kind {
+            dead_letters.retain(|d| d.kind() == kind);
+        }
+        Ok(dead_letters)
+    }
+
+    /// Get a dead letter
+    pub async fn get(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        self.store.get(id).await
+    }
+
+    /// Remove a dead letter, returning it
+    pub async fn take(&self, id: &str) -> ChainResult<Option<DeadLetter>> {
+        let dead_letter = self.store.remove(id).await?;
+        if dead_letter.is_some() {
+            self.record_depth().await;
+        }
+        Ok(dead_letter)
+    }
+
+    /// Remove the dead letters of the given kind, or all of them, returning
+    /// how many were removed
+    pub async fn purge(&self, kind: Option<&str>) -> ChainResult<usize> {
+        let purged = match kind {
+            None => self.store.purge().await?,
+            Some(_) => {
+                let mut purged = 0;
+                for dead_letter in self.list(kind).await? {
+                    if self.store.remove(&dead_letter.id).await?.is_some() {
+                        purged += 1;
+                    }
+                }
+                purged
+            }
+        };
+        self.record_depth().await;
+        Ok(purged)
+    }
+
+    /// Record the number of dead letters of each kind
+    pub async fn record_depth(&self) {
+        let dead_letters = match self.store.list().await {
+            Ok(dead_letters) => dead_letters,
+            Err(e) => {
+                warn!("Failed to measure the dead-letter queue: {}", e);
+                return;
+            }
+        };
+        let mut depth = HashMap::from([("step", 0), ("webhook", 0)]);
+        for dead_letter in &dead_letters {
+            *depth.entry(dead_letter.kind()).or_default() += 1;
+        }
+        for (kind, count) in depth {
+            gauge!(catalog::CHAIN_DEAD_LETTERS, count as f64, "kind" => kind);
+        }
+    }
+}
+
+/// Create the dead-letter queue described by the configuration
+///
+/// Redis dead letters are encrypted at rest when an encryptor is given.
+pub fn queue_from_config(
+    config: &ChainDeadLetterConfig,
+    redis: Option<&RedisConnector>,
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+) -> ChainResult<DeadLetterQueue> {
+    match config.backend.as_str() {
+        "memory" => Ok(DeadLetterQueue::in_memory(config.capacity)),
+        "redis" => {
+            let redis = redis.ok_or_else(|| {
+                ChainError::ValidationError(
+                    "Redis dead-letter backend requires memory.redis_url".to_string(),
+                )
+            })?;
+            let mut store = RedisDeadLetterStore::from_connector(
+                redis.clone(),
+                &config.key_prefix,
+                config.capacity,
+            );
+            if let Some(encryptor) = encryptor {
+                store = store.with_encryption(encryptor);
+            }
+            Ok(DeadLetterQueue::new(Arc::new(store)))
+        }
+        other => Err(ChainError::ValidationError(format!(
+            "Unknown dead-letter backend: {}",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::chain_engine::checkpoint::ExecutionStatus;
+
+    fn webhook_dead_letter(id: &str) -> DeadLetter {
+        DeadLetter {
+            id: id.to_string(),
+            source: DeadLetterSource::Webhook {
+                url: "http://127.0.0.1:9/hook".to_string(),
+                event: ChainEvent {
+                    execution_id: "exec".to_string(),
+                    chain_id: "chain".to_string(),
+                    status: ExecutionStatus::Failed,
+                    outputs: HashMap::new(),
+                    error: Some("boom".to_string()),
+                    timestamp: Utc::now(),
+                },
+            },
+            attempts: 3,
+            last_error: "endpoint returned 500".to_string(),
+            failed_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_capacity_and_purge() {
+        let queue = DeadLetterQueue::in_memory(2);
+        for id in ["a", "b", "c"] {
+            queue.push(webhook_dead_letter(id)).await;
+        }
+
+        let ids: Vec<_> = queue
+            .list(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|d| d.id)
+            .collect();
+        assert_eq!(ids, vec!["b", "c"]);
+        assert!(queue.list(Some("step")).await.unwrap().is_empty());
+
+        assert_eq!(queue.take("b").await.unwrap().unwrap().attempts, 3);
+        assert!(queue.take("b").await.unwrap().is_none());
+        assert_eq!(queue.purge(Some("step")).await.unwrap(), 0);
+        assert_eq!(queue.purge(Some("webhook")).await.unwrap(), 1);
+        assert!(queue.list(None).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dead_letter_serialization() {
+        let json = serde_json::to_value(webhook_dead_letter("a")).unwrap();
+        assert_eq!(json["kind"], "webhook");
+        assert_eq!(json["url"], "http://127.0.0.1:9/hook");
+
+        let dead_letter: DeadLetter = serde_json::from_value(json).unwrap();
+        assert_eq!(dead_letter.kind(), "webhook");
+    }
+}
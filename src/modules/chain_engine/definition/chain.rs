@@ -31,6 +31,14 @@ pub struct Chain {
     #[serde(default)]
     pub variables: HashMap<String, Variable>,
 
+    // Input/output contracts
+    /// JSON schema of the chain inputs, checked before execution
+    #[serde(default)]
+    pub input_schema: Option<serde_json::Value>,
+    /// JSON schema of the chain outputs, checked after execution
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
+
     // Execution settings
     #[serde(default)]
     pub error_handling: ErrorHandlingStrategy,
@@ -38,4 +46,41 @@ pub struct Chain {
     pub max_parallel_steps: Option<usize>,
     #[serde(with = "duration_serde", default)]
     pub timeout: Option<Duration>,
+    /// Limits on each execution, falling back to the engine's default budget
+    #[serde(default)]
+    pub budget: Option<ChainBudget>,
+}
+
+/// Limits on the resources a chain execution may use
+///
+/// Unset limits fall back to the engine's default budget, if any.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChainBudget {
+    /// Maximum tokens of the model calls of the steps
+    pub max_total_tokens: Option<u64>,
+    /// Maximum cost of the model calls of the steps, in the pricing currency
+    pub max_cost: Option<f64>,
+    /// Maximum wall time of the execution, in seconds
+    #[serde(with = "duration_serde")]
+    pub max_wall_time: Option<Duration>,
+    /// Maximum number of steps run, excluding retries and compensations
+    pub max_steps: Option<u32>,
+}
+
+impl ChainBudget {
+    /// This budget, with its unset limits taken from `defaults`
+    pub fn or(&self, defaults: &ChainBudget) -> ChainBudget {
+        ChainBudget {
+            max_total_tokens: self.max_total_tokens.or(defaults.max_total_tokens),
+            max_cost: self.max_cost.or(defaults.max_cost),
+            max_wall_time: self.max_wall_time.or(defaults.max_wall_time),
+            max_steps: self.max_steps.or(defaults.max_steps),
+        }
+    }
+
+    /// Whether no limit is set
+    pub fn is_unlimited(&self) -> bool {
+        *self == ChainBudget::default()
+    }
 }
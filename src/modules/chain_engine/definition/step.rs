@@ -26,6 +26,12 @@ pub struct ChainStep {
     pub inputs: Vec<InputMapping>,
     #[serde(default)]
     pub outputs: Vec<OutputMapping>,
+    /// JSON schema of the object of the step's input mappings
+    #[serde(default)]
+    pub input_schema: Option<serde_json::Value>,
+    /// JSON schema of the object of the step's outputs
+    #[serde(default)]
+    pub output_schema: Option<serde_json::Value>,
 
     // Execution settings
     #[serde(default)]
@@ -36,6 +42,9 @@ pub struct ChainStep {
     pub timeout: Option<Duration>,
     #[serde(default)]
     pub error_handler: Option<ErrorHandler>,
+    /// Step undoing this one when a later step fails; it only runs then
+    #[serde(default)]
+    pub compensation: Option<String>,
 }
 
 /// Types of steps that can be executed in a chain
@@ -89,6 +98,47 @@ pub enum StepType {
         break_condition: Option<Condition>,
     },
 
+    // Templated HTTP request
+    HttpRequest {
+        #[serde(default = "default_http_method")]
+        method: String,
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        body: Option<serde_json::Value>,
+        #[serde(default)]
+        allow_error_status: bool,
+    },
+
+    // JSONPath extraction from a JSON value
+    JsonTransform {
+        input: serde_json::Value,
+        mappings: HashMap<String, String>,
+    },
+
+    // Regex extraction from text
+    RegexExtract {
+        input: String,
+        pattern: String,
+        #[serde(default)]
+        all_matches: bool,
+    },
+
+    // ReAct-style agent loop
+    Agent {
+        agent: crate::modules::chain_engine::agent::AgentDefinition,
+        input: String,
+        #[serde(default)]
+        conversation_id: Option<String>,
+    },
+
+    // Conversation between several agents
+    MultiAgent {
+        conversation: crate::modules::chain_engine::conversation::ConversationDefinition,
+        topic: String,
+    },
+
     // Custom step type (extensibility)
     Custom {
         handler: String,
@@ -97,6 +147,10 @@ pub enum StepType {
     },
 }
 
+fn default_http_method() -> String {
+    "GET".to_string()
+}
+
 /// Roles that can be assigned to steps
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -159,9 +213,29 @@ pub struct ConditionalBranch {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RetryPolicy {
     pub max_retries: u32,
-    #[serde(with = "duration_serde")]
+    #[serde(with = "duration_serde", default)]
     pub retry_interval: Option<Duration>,
+    #[serde(default = "default_backoff_factor")]
     pub retry_backoff_factor: f32,
+    /// Error codes to retry, such as `timeout`; empty retries any error
     #[serde(default)]
     pub retry_on_error_codes: Vec<String>,
 }
+
+fn default_backoff_factor() -> f32 {
+    1.0
+}
+
+impl RetryPolicy {
+    /// Delay before a retry, counting from 1
+    pub fn delay(&self, retry: u32) -> Duration {
+        let interval = self.retry_interval.unwrap_or_default();
+        let factor = self.retry_backoff_factor.max(1.0);
+        interval.mul_f32(factor.powi(retry.saturating_sub(1) as i32))
+    }
+
+    /// Whether an error with the given code is retried
+    pub fn retries(&self, code: &str) -> bool {
+        self.retry_on_error_codes.is_empty() || self.retry_on_error_codes.iter().any(|c| c == code)
+    }
+}
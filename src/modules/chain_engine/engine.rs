@@ -2,22 +2,47 @@
 //!
 //! This module provides the core execution engine for chains.
 
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
-use tokio::sync::Mutex;
-
+use tokio::sync::{broadcast, Mutex};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::modules::chain_engine::agent::AgentRuntime;
+use crate::modules::chain_engine::budget::{BudgetExceeded, BudgetLimit};
+use crate::modules::chain_engine::checkpoint::{
+    ChainCheckpoint, CheckpointStore, ExecutionStatus, StepCompensation, StepRetry,
+    EXECUTION_LEASE_TTL,
+};
 use crate::modules::chain_engine::condition_evaluator::ConditionEvaluator;
 use crate::modules::chain_engine::context::ChainContext;
+use crate::modules::chain_engine::dead_letter::{DeadLetter, DeadLetterQueue, DeadLetterSource};
 use crate::modules::chain_engine::definition::{
-    Chain, ChainStep, Condition, DependencyType, StepType,
+    Chain, ChainBudget, ChainStep, Condition, DependencyType, StepType,
 };
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
 use crate::modules::chain_engine::executors::{
-    conditional::ConditionalExecutor, custom::CustomExecutor, function::FunctionCallExecutor,
-    llm::LLMInferenceExecutor, loop_executor::LoopExecutor, parallel::ParallelExecutor,
-    tool::ToolUseExecutor, StepExecutor,
+    agent::AgentExecutor,
+    conditional::ConditionalExecutor,
+    custom::CustomExecutor,
+    function::FunctionCallExecutor,
+    http::HttpRequestExecutor,
+    llm::LLMInferenceExecutor,
+    loop_executor::LoopExecutor,
+    parallel::ParallelExecutor,
+    tool::ToolUseExecutor,
+    transform::{JsonTransformExecutor, RegexExtractExecutor},
+    StepExecutor,
+};
+use crate::modules::chain_engine::schema::{
+    validate_chain_inputs, validate_chain_outputs, validate_step_inputs, validate_step_outputs,
 };
 use crate::modules::chain_engine::validation::validate_chain;
+use crate::modules::telemetry::{catalog, CostCalculator};
+use crate::modules::tools::ToolRegistry;
 
 /// Chain engine for executing chains
 
@@ -34,10 +59,45 @@ pub struct ExecutionStats {
     pub avg_execution_time_ms: f64,
 }
 
+/// Capacity of the lifecycle event channel
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Lifecycle event emitted when a chain execution finishes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainEvent {
+    pub execution_id: String,
+    pub chain_id: String,
+    pub status: ExecutionStatus,
+    pub outputs: HashMap<String, serde_json::Value>,
+    pub error: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ChainEvent {
+    /// Event name, e.g. `chain.completed`
+    pub fn name(&self) -> String {
+        let status = serde_json::to_value(self.status).unwrap_or_default();
+        format!("chain.{}", status.as_str().unwrap_or("unknown"))
+    }
+}
+
 // Remove the Debug derive and implement it manually
 pub struct ChainEngine {
     executors: Arc<RwLock<HashMap<String, Arc<dyn StepExecutor>>>>,
     stats: Arc<RwLock<ExecutionStats>>,
+    tool_registry: Option<Arc<ToolRegistry>>,
+    agent_runtime: Option<Arc<AgentRuntime>>,
+    /// Calculator pricing the usage of LLM inference steps
+    cost_calculator: Option<Arc<CostCalculator>>,
+    checkpoints: Option<Arc<dyn CheckpointStore>>,
+    dead_letters: Option<Arc<DeadLetterQueue>>,
+    /// Limits of executions whose chain leaves them unset
+    default_budget: ChainBudget,
+    /// Executions currently running in this engine
+    active_executions: Arc<RwLock<HashSet<String>>>,
+    /// Holder of the leases of this engine's executions
+    instance_id: String,
+    events: broadcast::Sender<ChainEvent>,
 }
 
 impl std::fmt::Debug for ChainEngine {
@@ -45,6 +105,12 @@ impl std::fmt::Debug for ChainEngine {
         f.debug_struct("ChainEngine")
             .field("executors_count", &self.executors.read().unwrap().len())
             .field("stats", &self.stats)
+            .field("tools", &self.tool_registry.as_ref().map(|r| r.names()))
+            .field("agents", &self.agent_runtime.is_some())
+            .field("pricing", &self.cost_calculator.is_some())
+            .field("checkpointing", &self.checkpoints.is_some())
+            .field("dead_letters", &self.dead_letters.is_some())
+            .field("default_budget", &self.default_budget)
             .finish()
     }
 }
@@ -55,9 +121,61 @@ impl ChainEngine {
         Self {
             executors: Arc::new(RwLock::new(HashMap::new())),
             stats: Arc::new(RwLock::new(ExecutionStats::default())),
+            tool_registry: None,
+            agent_runtime: None,
+            cost_calculator: None,
+            checkpoints: None,
+            dead_letters: None,
+            default_budget: ChainBudget::default(),
+            active_executions: Arc::new(RwLock::new(HashSet::new())),
+            instance_id: Uuid::new_v4().to_string(),
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Subscribe to execution lifecycle events
+    pub fn subscribe_events(&self) -> broadcast::Receiver<ChainEvent> {
+        self.events.subscribe()
+    }
+
+    /// Persist execution state to the given store at each step boundary
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// Dead-letter steps that fail to the given queue
+    pub fn with_dead_letters(mut self, queue: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = Some(queue);
+        self
+    }
+
+    /// Limit executions whose chain leaves a budget limit unset
+    pub fn with_default_budget(mut self, budget: ChainBudget) -> Self {
+        self.default_budget = budget;
+        self
+    }
+
+    /// Set the registry used by tool use steps
+    pub fn with_tool_registry(mut self, registry: Arc<ToolRegistry>) -> Self {
+        self.tool_registry = Some(registry);
+        self
+    }
+
+    /// Set the runtime used by agent steps
+    pub fn with_agent_runtime(mut self, runtime: Arc<AgentRuntime>) -> Self {
+        self.agent_runtime = Some(runtime);
+        self
+    }
+
+    /// Price the usage of LLM inference steps with the given calculator
+    ///
+    /// Agent steps are priced by their runtime's calculator.
+    pub fn with_cost_calculator(mut self, calculator: Arc<CostCalculator>) -> Self {
+        self.cost_calculator = Some(calculator);
+        self
+    }
+
     /// Get execution statistics
     pub fn get_execution_stats(&self) -> ExecutionStats {
         self.stats.read().unwrap().clone()
@@ -140,20 +258,27 @@ impl ChainEngine {
         chain: &Chain,
         inputs: HashMap<String, serde_json::Value>,
     ) -> ChainResult<HashMap<String, serde_json::Value>> {
-        // Update total executions
-        {
-            let mut stats = self.stats.write().unwrap();
-            stats.total_executions += 1;
-        }
-
-        // Record start time
-        let start_time = std::time::Instant::now();
+        let execution_id = Uuid::new_v4().to_string();
+        self.execute_chain_with_id(&execution_id, chain, inputs)
+            .await
+    }
 
-        // Validate the chain
+    /// Execute a chain under a caller-chosen execution ID
+    ///
+    /// With a checkpoint store configured, the execution can be inspected,
+    /// resumed and cancelled by this ID.
+    pub async fn execute_chain_with_id(
+        &self,
+        execution_id: &str,
+        chain: &Chain,
+        inputs: HashMap<String, serde_json::Value>,
+    ) -> ChainResult<HashMap<String, serde_json::Value>> {
+        // Validate the chain and its inputs
         validate_chain(chain)?;
+        validate_chain_inputs(chain, &inputs)?;
 
         // Create execution context
-        let context = Arc::new(Mutex::new(ChainContext {
+        let context = ChainContext {
             chain_id: chain.id.clone(),
             variables: chain
                 .variables
@@ -165,18 +290,307 @@ impl ChainEngine {
             step_results: HashMap::new(),
             inputs,
             outputs: HashMap::new(),
-        }));
+        };
+
+        let checkpoint = self
+            .checkpoints
+            .as_ref()
+            .map(|_| ChainCheckpoint::new(execution_id, chain, &context));
+        self.run_execution(execution_id, chain, context, checkpoint)
+            .await
+    }
+
+    /// Resume an interrupted or failed execution from its last checkpoint
+    ///
+    /// Completed steps are not executed again.
+    pub async fn resume_execution(
+        &self,
+        execution_id: &str,
+    ) -> ChainResult<HashMap<String, serde_json::Value>> {
+        let checkpoint = self
+            .get_execution(execution_id)
+            .await?
+            .ok_or_else(|| ChainError::Other(format!("Execution not found: {}", execution_id)))?;
+        self.resume_from(checkpoint).await
+    }
+
+    /// Retry the execution of a dead-lettered step from that step
+    ///
+    /// The execution resumes from its last checkpoint if it is still stored,
+    /// otherwise from the state recorded in the dead letter.
+    pub async fn retry_step(
+        &self,
+        execution: &ChainCheckpoint,
+    ) -> ChainResult<HashMap<String, serde_json::Value>> {
+        let checkpoint = match &self.checkpoints {
+            Some(store) => store.load(&execution.execution_id).await?,
+            None => None,
+        };
+        self.resume_from(checkpoint.unwrap_or_else(|| execution.clone()))
+            .await
+    }
+
+    /// Resume an execution from a checkpoint, if it is resumable
+    async fn resume_from(
+        &self,
+        mut checkpoint: ChainCheckpoint,
+    ) -> ChainResult<HashMap<String, serde_json::Value>> {
+        if !checkpoint.status.is_resumable() {
+            return Err(ChainError::ValidationError(format!(
+                "Execution {} is {:?} and cannot be resumed",
+                checkpoint.execution_id, checkpoint.status
+            )));
+        }
+
+        checkpoint.finish(ExecutionStatus::Running, None);
+        let execution_id = checkpoint.execution_id.clone();
+        let chain = checkpoint.chain.clone();
+        let context = checkpoint.to_context();
+        self.run_execution(&execution_id, &chain, context, Some(checkpoint))
+            .await
+    }
+
+    /// Cancel an execution
+    ///
+    /// A running execution stops at its next step boundary.
+    pub async fn cancel_execution(&self, execution_id: &str) -> ChainResult<ChainCheckpoint> {
+        let store = self.checkpoint_store()?;
+        let mut checkpoint = store
+            .load(execution_id)
+            .await?
+            .ok_or_else(|| ChainError::Other(format!("Execution not found: {}", execution_id)))?;
+
+        if !checkpoint.status.is_resumable() {
+            return Err(ChainError::ValidationError(format!(
+                "Execution {} is {:?} and cannot be cancelled",
+                execution_id, checkpoint.status
+            )));
+        }
+
+        checkpoint.finish(ExecutionStatus::Cancelled, None);
+        store.save(&checkpoint).await?;
+        Ok(checkpoint)
+    }
+
+    /// Get the checkpointed state of an execution
+    pub async fn get_execution(&self, execution_id: &str) -> ChainResult<Option<ChainCheckpoint>> {
+        self.checkpoint_store()?.load(execution_id).await
+    }
+
+    /// Whether an execution is currently running in this engine
+    pub fn is_execution_active(&self, execution_id: &str) -> bool {
+        self.active_executions
+            .read()
+            .unwrap()
+            .contains(execution_id)
+    }
+
+    /// IDs of executions left running by a previous orchestrator
+    pub async fn interrupted_executions(&self) -> ChainResult<Vec<String>> {
+        let checkpoints = self.checkpoint_store()?.list().await?;
+        Ok(checkpoints
+            .into_iter()
+            .filter(|c| c.status == ExecutionStatus::Running)
+            .map(|c| c.execution_id)
+            .filter(|id| !self.is_execution_active(id))
+            .collect())
+    }
 
-        // Build execution plan
-        let execution_plan = self.build_execution_plan(chain)?;
+    /// Resume the executions left running by a previous orchestrator
+    ///
+    /// Executions leased by another orchestrator are skipped, then tried
+    /// again once their lease would have expired, in case its holder
+    /// crashed. Returns the number of executions resumed.
+    pub async fn resume_interrupted(&self) -> ChainResult<usize> {
+        let mut resumed = 0;
+        let mut leased = Vec::new();
+        for execution_id in self.interrupted_executions().await? {
+            match self.resume_leased(&execution_id).await {
+                Some(true) => resumed += 1,
+                Some(false) => {}
+                None => leased.push(execution_id),
+            }
+        }
+        if leased.is_empty() {
+            return Ok(resumed);
+        }
 
-        // Execute the plan
-        self.execute_plan(chain, execution_plan, context.clone())
-            .await?;
+        tokio::time::sleep(EXECUTION_LEASE_TTL).await;
+        let interrupted = self.interrupted_executions().await?;
+        for execution_id in leased.iter().filter(|id| interrupted.contains(id)) {
+            match self.resume_leased(execution_id).await {
+                Some(true) => resumed += 1,
+                Some(false) => {}
+                None => info!(
+                    "Chain execution {} is running on another orchestrator",
+                    execution_id
+                ),
+            }
+        }
+        Ok(resumed)
+    }
 
-        // Return the outputs
-        let final_context = context.lock().await;
-        let result = final_context.outputs.clone();
+    /// Resume an execution, unless another orchestrator holds its lease
+    ///
+    /// Returns whether it resumed successfully, or `None` if it is leased.
+    async fn resume_leased(&self, execution_id: &str) -> Option<bool> {
+        info!("Resuming chain execution {}", execution_id);
+        match self.resume_execution(execution_id).await {
+            Ok(_) => Some(true),
+            Err(ChainError::ExecutionLeased(_)) => None,
+            Err(e) => {
+                warn!("Failed to resume chain execution {}: {}", execution_id, e);
+                Some(false)
+            }
+        }
+    }
+
+    /// Lease an execution to this engine while it runs
+    ///
+    /// The lease is renewed until the returned task is aborted.
+    async fn lease_execution(
+        &self,
+        execution_id: &str,
+    ) -> ChainResult<Option<tokio::task::JoinHandle<()>>> {
+        let Some(store) = self.checkpoints.clone() else {
+            return Ok(None);
+        };
+        if !store
+            .claim(execution_id, &self.instance_id, EXECUTION_LEASE_TTL)
+            .await?
+        {
+            return Err(ChainError::ExecutionLeased(execution_id.to_string()));
+        }
+
+        let execution_id = execution_id.to_string();
+        let holder = self.instance_id.clone();
+        Ok(Some(tokio::spawn(async move {
+            let mut renewals = tokio::time::interval(EXECUTION_LEASE_TTL / 3);
+            renewals.tick().await;
+            loop {
+                renewals.tick().await;
+                match store
+                    .claim(&execution_id, &holder, EXECUTION_LEASE_TTL)
+                    .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        warn!("Lost the lease of chain execution {}", execution_id);
+                        return;
+                    }
+                    Err(e) => warn!(
+                        "Failed to renew the lease of chain execution {}: {}",
+                        execution_id, e
+                    ),
+                }
+            }
+        })))
+    }
+
+    /// Get the configured checkpoint store
+    fn checkpoint_store(&self) -> ChainResult<&Arc<dyn CheckpointStore>> {
+        self.checkpoints.as_ref().ok_or_else(|| {
+            ChainError::ValidationError("Chain checkpointing is not enabled".to_string())
+        })
+    }
+
+    /// Run an execution to completion, checkpointing if enabled
+    async fn run_execution(
+        &self,
+        execution_id: &str,
+        chain: &Chain,
+        context: ChainContext,
+        mut checkpoint: Option<ChainCheckpoint>,
+    ) -> ChainResult<HashMap<String, serde_json::Value>> {
+        if !self
+            .active_executions
+            .write()
+            .unwrap()
+            .insert(execution_id.to_string())
+        {
+            return Err(ChainError::ValidationError(format!(
+                "Execution {} is already running",
+                execution_id
+            )));
+        }
+        let lease = match self.lease_execution(execution_id).await {
+            Ok(lease) => lease,
+            Err(e) => {
+                self.active_executions.write().unwrap().remove(execution_id);
+                return Err(e);
+            }
+        };
+
+        // Update total executions
+        {
+            let mut stats = self.stats.write().unwrap();
+            stats.total_executions += 1;
+        }
+
+        // Record start time
+        let start_time = std::time::Instant::now();
+
+        let context = Arc::new(Mutex::new(context));
+        let result = match self.build_execution_plan(chain) {
+            Ok(execution_plan) => {
+                self.execute_plan(
+                    execution_id,
+                    chain,
+                    execution_plan,
+                    context.clone(),
+                    &mut checkpoint,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        self.active_executions.write().unwrap().remove(execution_id);
+
+        // Record the final state
+        let outputs = context.lock().await.outputs.clone();
+        let (status, error) = match &result {
+            Ok(()) => (ExecutionStatus::Completed, None),
+            Err(ChainError::Cancelled(_)) => (ExecutionStatus::Cancelled, None),
+            Err(e @ ChainError::BudgetExceeded(_)) => {
+                (ExecutionStatus::BudgetExceeded, Some(e.to_string()))
+            }
+            Err(e) => (ExecutionStatus::Failed, Some(e.to_string())),
+        };
+        if let (Some(store), Some(mut checkpoint)) = (&self.checkpoints, checkpoint) {
+            checkpoint.outputs = outputs.clone();
+            checkpoint.finish(status, error.clone());
+            if let Err(e) = store.save(&checkpoint).await {
+                warn!(
+                    "Failed to save checkpoint for execution {}: {}",
+                    execution_id, e
+                );
+            }
+        }
+        if let (Some(store), Some(lease)) = (&self.checkpoints, lease) {
+            lease.abort();
+            if let Err(e) = store.release(execution_id, &self.instance_id).await {
+                warn!(
+                    "Failed to release the lease of execution {}: {}",
+                    execution_id, e
+                );
+            }
+        }
+
+        // Sending only fails when nobody is subscribed
+        let _ = self.events.send(ChainEvent {
+            execution_id: execution_id.to_string(),
+            chain_id: chain.id.clone(),
+            status,
+            outputs: outputs.clone(),
+            error,
+            timestamp: Utc::now(),
+        });
+
+        if let Err(e) = result {
+            self.stats.write().unwrap().failed_executions += 1;
+            return Err(e);
+        }
 
         // Update stats for successful execution
         {
@@ -193,7 +607,7 @@ impl ChainEngine {
                 ((current_avg * (total_executions - 1.0)) + execution_time) / total_executions;
         }
 
-        Ok(result)
+        Ok(outputs)
     }
 
     /// Build an execution plan for a chain
@@ -294,12 +708,52 @@ impl ChainEngine {
     /// Execute a plan
     async fn execute_plan(
         &self,
+        execution_id: &str,
         chain: &Chain,
         plan: Vec<String>,
         context: Arc<Mutex<ChainContext>>,
+        checkpoint: &mut Option<ChainCheckpoint>,
     ) -> ChainResult<()> {
-        // Track completed steps
-        let completed_steps = Arc::new(Mutex::new(HashSet::new()));
+        // Track completed steps, including those restored from a checkpoint
+        let completed_steps = Arc::new(Mutex::new(
+            checkpoint
+                .as_ref()
+                .map(|c| c.completed_steps.iter().cloned().collect::<HashSet<_>>())
+                .unwrap_or_default(),
+        ));
+
+        let mut completed_order = checkpoint
+            .as_ref()
+            .map(|c| c.completed_steps.clone())
+            .unwrap_or_default();
+        let mut retries = checkpoint
+            .as_ref()
+            .map(|c| c.retries.clone())
+            .unwrap_or_default();
+        let mut compensations = checkpoint
+            .as_ref()
+            .map(|c| c.compensations.clone())
+            .unwrap_or_default();
+
+        // Usage counts against the budget across resumes
+        let budget = match &chain.budget {
+            Some(budget) => budget.or(&self.default_budget),
+            None => self.default_budget.clone(),
+        };
+        let mut usage = checkpoint
+            .as_ref()
+            .map(|c| c.usage.clone())
+            .unwrap_or_default();
+        let started = std::time::Instant::now();
+        let previous_wall_time_ms = usage.wall_time_ms;
+        let mut stopped = None;
+
+        // Compensation steps only run when a later step fails
+        let compensation_steps: HashSet<&str> = chain
+            .steps
+            .values()
+            .filter_map(|step| step.compensation.as_deref())
+            .collect();
 
         // Execute steps in the plan
         for step_id in plan {
@@ -307,6 +761,16 @@ impl ChainEngine {
                 ChainError::StepNotFound(format!("Step not found in execution plan: {}", step_id))
             })?;
 
+            // Skip steps completed before a resume, and compensation steps
+            if completed_steps.lock().await.contains(&step_id)
+                || compensation_steps.contains(step_id.as_str())
+            {
+                continue;
+            }
+
+            // Stop at the step boundary if the execution was cancelled
+            self.check_cancelled(checkpoint.as_ref()).await?;
+
             // Check if the step should be executed
             if let Some(condition) = &step.condition {
                 let context_guard = context.lock().await;
@@ -365,66 +829,331 @@ impl ChainEngine {
                 continue;
             }
 
-            // Execute the step
-            match &step.step_type {
-                StepType::LLMInference { .. } => {
-                    self.execute_llm_inference_step(step, context.clone())
-                        .await?;
-                }
-                StepType::FunctionCall { .. } => {
-                    self.execute_function_call_step(step, context.clone())
-                        .await?;
-                }
-                StepType::ToolUse { .. } => {
-                    self.execute_tool_use_step(step, context.clone()).await?;
-                }
-                StepType::Conditional {
-                    branches,
-                    default_branch,
-                } => {
-                    self.execute_conditional_step(
-                        step,
-                        branches,
-                        default_branch.clone(),
+            // Stop at the step boundary once the budget leaves no room
+            usage.wall_time_ms = previous_wall_time_ms + started.elapsed().as_millis() as u64;
+            if let Some(limit) = usage.exhausted(&budget) {
+                stopped = Some(limit);
+                break;
+            }
+
+            // Execute the step with its retries, within the wall time left.
+            // If it still fails, undo the completed steps and dead-letter it
+            let step_run =
+                self.execute_step_with_retries(step, chain, context.clone(), &mut retries);
+            let result = match usage.remaining_time(&budget) {
+                Some(remaining) => tokio::time::timeout(remaining, step_run).await.ok(),
+                None => Some(step_run.await),
+            };
+            let Some(result) = result else {
+                stopped = Some(BudgetLimit::WallTime);
+                break;
+            };
+            if let Err(e) = result {
+                if !matches!(e, ChainError::Cancelled(_)) {
+                    self.compensate(
                         chain,
+                        &mut completed_order,
                         context.clone(),
+                        &mut retries,
+                        &mut compensations,
                     )
-                    .await?;
-                }
-                StepType::Parallel {
-                    steps,
-                    wait_for_all,
-                } => {
-                    self.execute_parallel_step(step, steps, *wait_for_all, chain, context.clone())
-                        .await?;
-                }
-                StepType::Loop {
-                    iteration_variable,
-                    max_iterations,
-                    steps,
-                    break_condition,
-                } => {
-                    self.execute_loop_step(
-                        step,
-                        iteration_variable,
-                        *max_iterations,
-                        steps,
-                        break_condition.as_ref(),
+                    .await;
+                    self.dead_letter_step(
+                        execution_id,
                         chain,
-                        context.clone(),
+                        step,
+                        &completed_order,
+                        &retries,
+                        &compensations,
+                        &context,
+                        &e,
                     )
-                    .await?;
+                    .await;
                 }
-                StepType::Custom { handler, config } => {
-                    self.execute_custom_step(step, handler, config, context.clone())
-                        .await?;
+                if let Some(checkpoint) = checkpoint.as_mut() {
+                    checkpoint.completed_steps = completed_order;
+                    checkpoint.retries = retries;
+                    checkpoint.compensations = compensations;
+                    checkpoint.usage = usage;
                 }
+                return Err(e);
+            }
+
+            // Count the usage the step reported
+            let step_usage = context
+                .lock()
+                .await
+                .step_results
+                .get(&step_id)
+                .and_then(|result| result.usage.clone());
+            usage.add_step(step_usage.as_ref());
+            usage.wall_time_ms = previous_wall_time_ms + started.elapsed().as_millis() as u64;
+
+            // Checkpoint the step boundary, unless cancelled meanwhile
+            self.check_cancelled(checkpoint.as_ref()).await?;
+            if let (Some(store), Some(checkpoint)) = (&self.checkpoints, checkpoint.as_mut()) {
+                checkpoint.record_step(&step_id, &*context.lock().await);
+                checkpoint.retries = retries.clone();
+                checkpoint.usage = usage.clone();
+                store.save(checkpoint).await?;
             }
 
             // Mark the step as completed
+            completed_order.push(step_id.clone());
             completed_steps.lock().await.insert(step_id);
+
+            if let Some(limit) = usage.exceeded(&budget) {
+                stopped = Some(limit);
+                break;
+            }
+        }
+
+        usage.wall_time_ms = previous_wall_time_ms + started.elapsed().as_millis() as u64;
+        if let Some(checkpoint) = checkpoint.as_mut() {
+            checkpoint.completed_steps = completed_order.clone();
+            checkpoint.retries = retries;
+            checkpoint.compensations = compensations;
+            checkpoint.usage = usage.clone();
+        }
+
+        // Over budget, the execution ends with the results of its completed
+        // steps, which are not compensated
+        if let Some(limit) = stopped {
+            warn!(
+                "Execution {} of chain {} reached its {}",
+                execution_id,
+                chain.id,
+                limit.as_str()
+            );
+            counter!(catalog::CHAIN_BUDGET_EXCEEDED, 1, "limit" => limit.as_str());
+            let context = context.lock().await;
+            let step_outputs = completed_order
+                .iter()
+                .filter_map(|id| {
+                    let result = context.step_results.get(id)?;
+                    Some((id.clone(), result.outputs.clone()))
+                })
+                .collect();
+            return Err(ChainError::BudgetExceeded(Box::new(BudgetExceeded {
+                execution_id: execution_id.to_string(),
+                limit,
+                budget,
+                usage,
+                completed_steps: completed_order,
+                step_outputs,
+            })));
+        }
+
+        validate_chain_outputs(chain, &*context.lock().await)
+    }
+
+    /// Execute a single step of the plan, checking its input and output
+    /// schemas
+    async fn execute_step(
+        &self,
+        step: &ChainStep,
+        chain: &Chain,
+        context: Arc<Mutex<ChainContext>>,
+    ) -> ChainResult<()> {
+        validate_step_inputs(step, &*context.lock().await)?;
+
+        match &step.step_type {
+            StepType::LLMInference { .. } => {
+                self.execute_llm_inference_step(step, context.clone())
+                    .await?;
+            }
+            StepType::FunctionCall { .. } => {
+                self.execute_function_call_step(step, context.clone())
+                    .await?;
+            }
+            StepType::ToolUse { .. } => {
+                self.execute_tool_use_step(step, context.clone()).await?;
+            }
+            StepType::Conditional {
+                branches,
+                default_branch,
+            } => {
+                self.execute_conditional_step(
+                    step,
+                    branches,
+                    default_branch.clone(),
+                    chain,
+                    context.clone(),
+                )
+                .await?;
+            }
+            StepType::Parallel {
+                steps,
+                wait_for_all,
+            } => {
+                self.execute_parallel_step(step, steps, *wait_for_all, chain, context.clone())
+                    .await?;
+            }
+            StepType::Loop {
+                iteration_variable,
+                max_iterations,
+                steps,
+                break_condition,
+            } => {
+                self.execute_loop_step(
+                    step,
+                    iteration_variable,
+                    *max_iterations,
+                    steps,
+                    break_condition.as_ref(),
+                    chain,
+                    context.clone(),
+                )
+                .await?;
+            }
+            StepType::HttpRequest { .. }
+            | StepType::JsonTransform { .. }
+            | StepType::RegexExtract { .. }
+            | StepType::Agent { .. }
+            | StepType::MultiAgent { .. } => {
+                self.execute_library_step(step, context.clone()).await?;
+            }
+            StepType::Custom { handler, config } => {
+                self.execute_custom_step(step, handler, config, context.clone())
+                    .await?;
+            }
+        }
+
+        validate_step_outputs(step, &*context.lock().await)
+    }
+
+    /// Execute a step, retrying it as its retry policy allows
+    ///
+    /// Each retry is appended to `retries`. Cancellations are never retried.
+    async fn execute_step_with_retries(
+        &self,
+        step: &ChainStep,
+        chain: &Chain,
+        context: Arc<Mutex<ChainContext>>,
+        retries: &mut Vec<StepRetry>,
+    ) -> ChainResult<()> {
+        let mut retry = 0;
+        loop {
+            let error = match self.execute_step(step, chain, context.clone()).await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            let Some(policy) = &step.retry_policy else {
+                return Err(error);
+            };
+            if matches!(error, ChainError::Cancelled(_))
+                || retry >= policy.max_retries
+                || !policy.retries(error.code())
+            {
+                return Err(error);
+            }
+
+            retry += 1;
+            warn!(
+                "Step {} failed, retry {} of {}: {}",
+                step.id, retry, policy.max_retries, error
+            );
+            retries.push(StepRetry {
+                step_id: step.id.clone(),
+                retry,
+                error: error.to_string(),
+                at: Utc::now(),
+            });
+            tokio::time::sleep(policy.delay(retry)).await;
+        }
+    }
+
+    /// Run the compensation steps of the completed steps, latest first
+    ///
+    /// Compensated steps are no longer completed, so resuming the execution
+    /// runs them again. A failed compensation is recorded, its step stays
+    /// completed and the other compensations still run.
+    async fn compensate(
+        &self,
+        chain: &Chain,
+        completed_steps: &mut Vec<String>,
+        context: Arc<Mutex<ChainContext>>,
+        retries: &mut Vec<StepRetry>,
+        compensations: &mut Vec<StepCompensation>,
+    ) {
+        for step_id in completed_steps.clone().iter().rev() {
+            let Some(compensation) = chain
+                .steps
+                .get(step_id)
+                .and_then(|step| step.compensation.as_ref())
+                .and_then(|id| chain.steps.get(id))
+            else {
+                continue;
+            };
+
+            let result = self
+                .execute_step_with_retries(compensation, chain, context.clone(), retries)
+                .await;
+            match &result {
+                Ok(()) => completed_steps.retain(|s| s != step_id),
+                Err(e) => warn!(
+                    "Compensation {} of step {} failed: {}",
+                    compensation.id, step_id, e
+                ),
+            }
+            compensations.push(StepCompensation {
+                step_id: step_id.clone(),
+                compensation_step: compensation.id.clone(),
+                error: result.err().map(|e| e.to_string()),
+                at: Utc::now(),
+            });
         }
+    }
 
+    /// Dead-letter a failed step with the state of its execution
+    #[allow(clippy::too_many_arguments)]
+    async fn dead_letter_step(
+        &self,
+        execution_id: &str,
+        chain: &Chain,
+        step: &ChainStep,
+        completed_steps: &[String],
+        retries: &[StepRetry],
+        compensations: &[StepCompensation],
+        context: &Mutex<ChainContext>,
+        error: &ChainError,
+    ) {
+        let Some(queue) = &self.dead_letters else {
+            return;
+        };
+        let context = context.lock().await;
+        let mut execution = ChainCheckpoint::new(execution_id, chain, &context);
+        for step_id in completed_steps {
+            execution.record_step(step_id, &context);
+        }
+        execution.outputs = context.outputs.clone();
+        execution.retries = retries.to_vec();
+        execution.compensations = compensations.to_vec();
+        execution.finish(ExecutionStatus::Failed, Some(error.to_string()));
+        let attempts = 1 + retries.iter().filter(|r| r.step_id == step.id).count() as u32;
+        queue
+            .push(DeadLetter {
+                id: Uuid::new_v4().to_string(),
+                source: DeadLetterSource::Step {
+                    step_id: step.id.clone(),
+                    execution: Box::new(execution),
+                },
+                attempts,
+                last_error: error.to_string(),
+                failed_at: Utc::now(),
+            })
+            .await;
+    }
+
+    /// Fail with `Cancelled` if the checkpointed execution was cancelled
+    async fn check_cancelled(&self, checkpoint: Option<&ChainCheckpoint>) -> ChainResult<()> {
+        if let (Some(store), Some(checkpoint)) = (&self.checkpoints, checkpoint) {
+            if let Some(stored) = store.load(&checkpoint.execution_id).await? {
+                if stored.status == ExecutionStatus::Cancelled {
+                    return Err(ChainError::Cancelled(checkpoint.execution_id.clone()));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -434,7 +1163,12 @@ impl ChainEngine {
         step: &ChainStep,
         context: Arc<Mutex<ChainContext>>,
     ) -> ChainResult<()> {
-        let executor = LLMInferenceExecutor::new();
+        let executor = match &self.cost_calculator {
+            Some(calculator) => {
+                LLMInferenceExecutor::new().with_cost_calculator(calculator.clone())
+            }
+            None => LLMInferenceExecutor::new(),
+        };
         let context_guard = context.lock().await;
         let result = executor.execute_step(step, &context_guard).await?;
 
@@ -470,7 +1204,10 @@ impl ChainEngine {
         step: &ChainStep,
         context: Arc<Mutex<ChainContext>>,
     ) -> ChainResult<()> {
-        let executor = ToolUseExecutor::new();
+        let executor = match &self.tool_registry {
+            Some(registry) => ToolUseExecutor::new().with_registry(Arc::clone(registry)),
+            None => ToolUseExecutor::new(),
+        };
         let context_guard = context.lock().await;
         let result = executor.execute_step(step, &context_guard).await?;
 
@@ -547,6 +1284,43 @@ impl ChainEngine {
         Ok(())
     }
 
+    /// Execute a step from the built-in step library
+    pub async fn execute_library_step(
+        &self,
+        step: &ChainStep,
+        context: Arc<Mutex<ChainContext>>,
+    ) -> ChainResult<()> {
+        let executor: Box<dyn StepExecutor> = match &step.step_type {
+            StepType::HttpRequest { .. } => Box::new(HttpRequestExecutor::new()),
+            StepType::JsonTransform { .. } => Box::new(JsonTransformExecutor::new()),
+            StepType::RegexExtract { .. } => Box::new(RegexExtractExecutor::new()),
+            StepType::Agent { .. } | StepType::MultiAgent { .. } => {
+                let executor = AgentExecutor::new();
+                Box::new(match &self.agent_runtime {
+                    Some(runtime) => executor.with_runtime(runtime.clone()),
+                    None => executor,
+                })
+            }
+            _ => {
+                return Err(ChainError::StepExecutionError(format!(
+                    "Step {} is not a library step",
+                    step.id
+                )))
+            }
+        };
+
+        // Render templates against a snapshot so the context is not locked
+        // during slow steps such as HTTP requests
+        let snapshot = context.lock().await.clone();
+        let result = executor.execute_step(step, &snapshot).await?;
+
+        // Update the context with the result
+        let mut context_guard = context.lock().await;
+        context_guard.step_results.insert(step.id.clone(), result);
+
+        Ok(())
+    }
+
     /// Execute a custom step
     pub async fn execute_custom_step(
         &self,
@@ -577,3 +1351,190 @@ impl ChainEngine {
         evaluator.evaluate_condition(condition, context)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::chain_engine::checkpoint::InMemoryCheckpointStore;
+    use serde_json::json;
+
+    /// Chain reserving an order, undone by `release`, then failing to ship it
+    fn chain(retry_on_error_codes: &[&str]) -> Chain {
+        serde_json::from_value(json!({
+            "id": "order",
+            "name": "Order",
+            "description": "Reserve and ship an order",
+            "version": "1.0.0",
+            "steps": {
+                "reserve": {
+                    "id": "reserve",
+                    "name": "Reserve",
+                    "description": "Reserve the order",
+                    "role": "system",
+                    "step_type": {
+                        "type": "RegexExtract",
+                        "config": {"input": "{{inputs.text}}", "pattern": "#(?P<order>\\d+)"}
+                    },
+                    "compensation": "release"
+                },
+                "release": {
+                    "id": "release",
+                    "name": "Release",
+                    "description": "Release the reservation",
+                    "role": "system",
+                    "step_type": {
+                        "type": "JsonTransform",
+                        "config": {"input": "{{steps.reserve}}", "mappings": {"released": "$.order"}}
+                    }
+                },
+                "ship": {
+                    "id": "ship",
+                    "name": "Ship",
+                    "description": "Ship the order",
+                    "role": "system",
+                    "step_type": {
+                        "type": "RegexExtract",
+                        "config": {"input": "{{ broken", "pattern": "."}
+                    },
+                    "retry_policy": {
+                        "max_retries": 2,
+                        "retry_on_error_codes": retry_on_error_codes
+                    }
+                }
+            },
+            "dependencies": [{
+                "dependent_step": "ship",
+                "dependency_type": {"type": "Simple", "config": {"required_step": "reserve"}}
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn inputs() -> HashMap<String, serde_json::Value> {
+        HashMap::from([("text".to_string(), json!("Order #42"))])
+    }
+
+    #[tokio::test]
+    async fn test_retries_and_compensation() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let dead_letters = Arc::new(DeadLetterQueue::in_memory(10));
+        let engine = ChainEngine::new()
+            .with_checkpoint_store(store.clone())
+            .with_dead_letters(dead_letters.clone());
+
+        assert!(engine
+            .execute_chain_with_id("exec", &chain(&[]), inputs())
+            .await
+            .is_err());
+
+        let execution = store.load("exec").await.unwrap().unwrap();
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        let retries: Vec<_> = execution
+            .retries
+            .iter()
+            .map(|r| (r.step_id.as_str(), r.retry))
+            .collect();
+        assert_eq!(retries, vec![("ship", 1), ("ship", 2)]);
+        assert_eq!(execution.compensations.len(), 1);
+        assert_eq!(execution.compensations[0].step_id, "reserve");
+        assert_eq!(execution.compensations[0].compensation_step, "release");
+        assert!(execution.compensations[0].error.is_none());
+        // The reservation was undone, so a resume reserves it again
+        assert!(execution.completed_steps.is_empty());
+
+        let dead_letter = dead_letters.list(Some("step")).await.unwrap().remove(0);
+        assert_eq!(dead_letter.attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_error_codes() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let engine = ChainEngine::new().with_checkpoint_store(store.clone());
+
+        assert!(engine
+            .execute_chain_with_id("exec", &chain(&["timeout"]), inputs())
+            .await
+            .is_err());
+        let execution = store.load("exec").await.unwrap().unwrap();
+        assert!(execution.retries.is_empty());
+        assert_eq!(execution.compensations.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schema_violations() {
+        let engine = ChainEngine::new();
+        let mut chain = chain(&[]);
+        chain.input_schema = Some(json!({
+            "type": "object",
+            "properties": {"text": {"type": "string"}},
+            "required": ["text"]
+        }));
+        let error = engine
+            .execute_chain(&chain, HashMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ChainError::SchemaViolation(_)));
+        assert!(error.to_string().contains("input of the chain at /:"));
+
+        chain.steps.get_mut("reserve").unwrap().output_schema = Some(json!({
+            "type": "object",
+            "properties": {"order": {"type": "integer"}}
+        }));
+        let error = engine.execute_chain(&chain, inputs()).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("output of step reserve at /order:"));
+    }
+
+    #[tokio::test]
+    async fn test_budget_exceeded() {
+        let store = Arc::new(InMemoryCheckpointStore::new());
+        let engine = ChainEngine::new()
+            .with_checkpoint_store(store.clone())
+            .with_default_budget(ChainBudget {
+                max_steps: Some(1),
+                ..ChainBudget::default()
+            });
+
+        let error = engine
+            .execute_chain_with_id("exec", &chain(&[]), inputs())
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), "budget_exceeded");
+        let ChainError::BudgetExceeded(exceeded) = error else {
+            unreachable!()
+        };
+        assert_eq!(exceeded.limit, BudgetLimit::Steps);
+        assert_eq!(exceeded.completed_steps, vec!["reserve"]);
+        assert_eq!(exceeded.step_outputs["reserve"]["order"], json!("42"));
+
+        // Completed steps keep their results and are not compensated
+        let execution = store.load("exec").await.unwrap().unwrap();
+        assert_eq!(execution.status, ExecutionStatus::BudgetExceeded);
+        assert_eq!(execution.usage.steps, 1);
+        assert!(execution.compensations.is_empty());
+        assert!(!execution.status.is_resumable());
+
+        // The chain's own limits take precedence over the default budget
+        let mut chain = chain(&[]);
+        chain.budget = Some(ChainBudget {
+            max_wall_time: Some(std::time::Duration::ZERO),
+            ..ChainBudget::default()
+        });
+        let error = engine.execute_chain(&chain, inputs()).await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("reached its max_wall_time after 0 steps"));
+    }
+
+    #[test]
+    fn test_compensation_validation() {
+        let mut chain = chain(&[]);
+        chain.steps.get_mut("release").unwrap().compensation = Some("reserve".to_string());
+        assert!(validate_chain(&chain).is_err());
+
+        chain.steps.get_mut("release").unwrap().compensation = None;
+        chain.steps.get_mut("reserve").unwrap().compensation = Some("missing".to_string());
+        assert!(validate_chain(&chain).is_err());
+    }
+}
@@ -4,6 +4,8 @@
 
 use thiserror::Error;
 
+use crate::modules::chain_engine::budget::BudgetExceeded;
+
 /// Errors that can occur during chain execution
 #[derive(Error, Debug)]
 pub enum ChainError {
@@ -37,9 +39,48 @@ pub enum ChainError {
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
+    #[error("Schema violation: {0}")]
+    SchemaViolation(String),
+
+    #[error("Execution cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("Budget exceeded: {0}")]
+    BudgetExceeded(Box<BudgetExceeded>),
+
+    #[error("Execution {0} is running on another orchestrator")]
+    ExecutionLeased(String),
+
     #[error("Error: {0}")]
     Other(String),
 }
 
+impl ChainError {
+    /// Code of the error, matched by step retry policies
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChainError::StepNotFound(_) => "step_not_found",
+            ChainError::CircularDependency(_) => "circular_dependency",
+            ChainError::VariableNotFound(_) => "variable_not_found",
+            ChainError::StepExecutionError(_) => "step_execution_error",
+            ChainError::Timeout(_) => "timeout",
+            ChainError::ValidationError(_) => "validation_error",
+            ChainError::SerializationError(_) => "serialization_error",
+            ChainError::DeserializationError(_) => "deserialization_error",
+            ChainError::IoError(_) => "io_error",
+            ChainError::JsonError(_) => "json_error",
+            ChainError::StorageError(_) => "storage_error",
+            ChainError::SchemaViolation(_) => "schema_violation",
+            ChainError::Cancelled(_) => "cancelled",
+            ChainError::BudgetExceeded(_) => "budget_exceeded",
+            ChainError::ExecutionLeased(_) => "execution_leased",
+            ChainError::Other(_) => "other",
+        }
+    }
+}
+
 /// Result type for Chain Engine operations
 pub type ChainResult<T> = Result<T, ChainError>;
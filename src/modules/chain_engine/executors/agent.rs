@@ -0,0 +1,135 @@
+//! Agent Executor
+//!
+//! This module provides an executor for agent steps, which run a ReAct-style
+//! agent loop through the engine's [`AgentRuntime`], and for multi-agent
+//! conversation steps.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::modules::chain_engine::agent::AgentRuntime;
+use crate::modules::chain_engine::context::{ChainContext, StepResult};
+use crate::modules::chain_engine::definition::{ChainStep, StepType};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::template;
+use crate::modules::chain_engine::executors::StepExecutor;
+
+/// Agent step executor
+#[derive(Default)]
+pub struct AgentExecutor {
+    runtime: Option<Arc<AgentRuntime>>,
+}
+
+impl AgentExecutor {
+    /// Create a new agent executor without a runtime
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the runtime used to run agents
+    pub fn with_runtime(mut self, runtime: Arc<AgentRuntime>) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+}
+
+#[async_trait]
+impl StepExecutor for AgentExecutor {
+    async fn execute_step(
+        &self,
+        step: &ChainStep,
+        context: &ChainContext,
+    ) -> ChainResult<StepResult> {
+        let start_time = Instant::now();
+
+        let runtime = self.runtime.as_ref().ok_or_else(|| {
+            ChainError::StepExecutionError(format!(
+                "No agent runtime is configured for step {}",
+                step.id
+            ))
+        })?;
+        let data = template::template_data(context);
+
+        let mut outputs = HashMap::new();
+        let usage = match &step.step_type {
+            StepType::Agent {
+                agent,
+                input,
+                conversation_id,
+            } => {
+                let input = template::render(input, &data)?;
+                let conversation_id = conversation_id
+                    .as_deref()
+                    .map(|id| template::render(id, &data))
+                    .transpose()?;
+
+                let run = runtime
+                    .run(agent, &input, conversation_id.as_deref())
+                    .await?;
+
+                outputs.insert(
+                    "answer".to_string(),
+                    run.answer
+                        .clone()
+                        .map(serde_json::Value::String)
+                        .unwrap_or(serde_json::Value::Null),
+                );
+                outputs.insert(
+                    "stop_reason".to_string(),
+                    serde_json::to_value(run.stop_reason)?,
+                );
+                outputs.insert(
+                    "run_id".to_string(),
+                    serde_json::Value::String(run.run_id.clone()),
+                );
+                outputs.insert("steps".to_string(), serde_json::to_value(&run.steps)?);
+                outputs.insert(
+                    "total_tokens".to_string(),
+                    serde_json::Value::from(run.total_tokens),
+                );
+                run.usage
+            }
+            StepType::MultiAgent {
+                conversation,
+                topic,
+            } => {
+                let topic = template::render(topic, &data)?;
+                let transcript = runtime.converse(conversation, &topic).await?;
+
+                outputs.insert(
+                    "last_message".to_string(),
+                    transcript
+                        .last_message()
+                        .map(|m| serde_json::Value::String(m.to_string()))
+                        .unwrap_or(serde_json::Value::Null),
+                );
+                outputs.insert(
+                    "conversation_id".to_string(),
+                    serde_json::Value::String(transcript.conversation_id.clone()),
+                );
+                outputs.insert(
+                    "scratchpad".to_string(),
+                    serde_json::to_value(&transcript.scratchpad)?,
+                );
+                outputs.insert("transcript".to_string(), serde_json::to_value(&transcript)?);
+                transcript.usage
+            }
+            _ => {
+                return Err(ChainError::StepExecutionError(format!(
+                    "Step type mismatch: expected Agent or MultiAgent, got {:?}",
+                    step.step_type
+                )));
+            }
+        };
+
+        Ok(StepResult {
+            step_id: step.id.clone(),
+            outputs,
+            error: None,
+            execution_time: start_time.elapsed(),
+            usage: Some(usage),
+        })
+    }
+}
@@ -76,6 +76,7 @@ impl StepExecutor for ConditionalExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
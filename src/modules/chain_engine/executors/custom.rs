@@ -60,6 +60,7 @@ impl StepExecutor for CustomExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
@@ -9,7 +9,8 @@ use std::time::Instant;
 use crate::modules::chain_engine::context::{ChainContext, StepResult};
 use crate::modules::chain_engine::definition::ChainStep;
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
-use crate::modules::chain_engine::executors::StepExecutor;
+use crate::modules::chain_engine::executors::{template, StepExecutor};
+use crate::modules::chain_engine::expression::Reference;
 
 /// Function call step executor
 pub struct FunctionCallExecutor {
@@ -32,15 +33,17 @@ impl FunctionCallExecutor {
         context: &ChainContext,
     ) -> ChainResult<HashMap<String, serde_json::Value>> {
         let mut resolved_args = HashMap::new();
+        let data = template::template_data(context);
 
         for (name, value) in arguments {
             let resolved_value = match value {
                 serde_json::Value::String(s) if s.starts_with("{{") && s.ends_with("}}") => {
-                    // Variable reference
-                    let var_name = s[2..s.len() - 2].trim();
-                    context.variables.get(var_name).cloned().ok_or_else(|| {
-                        ChainError::VariableNotFound(format!("Variable not found: {}", var_name))
-                    })?
+                    // Reference to a variable, input or step output
+                    let expression = s[2..s.len() - 2].trim();
+                    Reference::parse(expression)?
+                        .resolve(&data)
+                        .cloned()
+                        .ok_or_else(|| ChainError::VariableNotFound(expression.to_string()))?
                 }
                 _ => value.clone(),
             };
@@ -91,6 +94,7 @@ impl StepExecutor for FunctionCallExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
@@ -0,0 +1,132 @@
+//! HTTP Request Executor
+//!
+//! This module provides an executor for templated HTTP request steps. The URL,
+//! header values and body are rendered against the chain context before the
+//! request is sent.
+
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::modules::chain_engine::context::{ChainContext, StepResult};
+use crate::modules::chain_engine::definition::{ChainStep, StepType};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::template;
+use crate::modules::chain_engine::executors::StepExecutor;
+
+/// Timeout applied when the step does not set one
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// HTTP request step executor
+pub struct HttpRequestExecutor {
+    client: Client,
+}
+
+impl HttpRequestExecutor {
+    /// Create a new HTTP request executor
+    pub fn new() -> Self {
+        Self::with_client(Client::new())
+    }
+
+    /// Create an executor using the given HTTP client
+    pub fn with_client(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Default for HttpRequestExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl StepExecutor for HttpRequestExecutor {
+    async fn execute_step(
+        &self,
+        step: &ChainStep,
+        context: &ChainContext,
+    ) -> ChainResult<StepResult> {
+        let start_time = Instant::now();
+
+        let StepType::HttpRequest {
+            method,
+            url,
+            headers,
+            body,
+            allow_error_status,
+        } = &step.step_type
+        else {
+            return Err(ChainError::StepExecutionError(format!(
+                "Step type mismatch: expected HttpRequest, got {:?}",
+                step.step_type
+            )));
+        };
+
+        let data = template::template_data(context);
+        let method = Method::from_bytes(method.to_uppercase().as_bytes()).map_err(|_| {
+            ChainError::ValidationError(format!("Invalid HTTP method in step {}", step.id))
+        })?;
+        let url = template::render(url, &data)?;
+
+        let mut request = self
+            .client
+            .request(method, &url)
+            .timeout(step.timeout.unwrap_or(DEFAULT_TIMEOUT));
+        for (name, value) in headers {
+            request = request.header(name, template::render(value, &data)?);
+        }
+        if let Some(body) = body {
+            request = match template::render_value(body, &data)? {
+                Value::String(text) => request.body(text),
+                json => request.json(&json),
+            };
+        }
+
+        let response = request.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ChainError::Timeout(format!("HTTP request to {} timed out", url))
+            } else {
+                ChainError::StepExecutionError(format!("HTTP request to {} failed: {}", url, e))
+            }
+        })?;
+
+        let status = response.status();
+        let response_headers: Map<String, Value> = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.to_string(), Value::String(value.to_string())))
+            })
+            .collect();
+        let text = response.text().await.map_err(|e| {
+            ChainError::StepExecutionError(format!("Failed to read response from {}: {}", url, e))
+        })?;
+
+        if !status.is_success() && !allow_error_status {
+            return Err(ChainError::StepExecutionError(format!(
+                "HTTP request to {} returned {}",
+                url, status
+            )));
+        }
+
+        // Expose JSON bodies as structured values
+        let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+
+        let mut outputs = HashMap::new();
+        outputs.insert("status".to_string(), Value::from(status.as_u16()));
+        outputs.insert("headers".to_string(), Value::Object(response_headers));
+        outputs.insert("body".to_string(), body);
+
+        Ok(StepResult {
+            step_id: step.id.clone(),
+            outputs,
+            error: None,
+            execution_time: start_time.elapsed(),
+            usage: None,
+        })
+    }
+}
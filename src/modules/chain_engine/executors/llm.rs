@@ -4,17 +4,22 @@
 
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
+use crate::modules::chain_engine::budget::StepUsage;
 use crate::modules::chain_engine::context::{ChainContext, StepResult};
 use crate::modules::chain_engine::definition::ChainStep;
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
-use crate::modules::chain_engine::executors::StepExecutor;
+use crate::modules::chain_engine::executors::{template, StepExecutor};
+use crate::modules::model_registry::global_tokenizers;
+use crate::modules::telemetry::CostCalculator;
 
 /// LLM inference step executor
 pub struct LLMInferenceExecutor {
     // This would typically contain a client for the model registry
     // model_registry_client: Arc<dyn ModelRegistryClient>,
+    cost_calculator: Option<Arc<CostCalculator>>,
 }
 
 impl LLMInferenceExecutor {
@@ -22,9 +27,16 @@ impl LLMInferenceExecutor {
     pub fn new(/* model_registry_client: Arc<dyn ModelRegistryClient> */) -> Self {
         Self {
             // model_registry_client,
+            cost_calculator: None,
         }
     }
 
+    /// Price the usage of steps with the given calculator
+    pub fn with_cost_calculator(mut self, calculator: Arc<CostCalculator>) -> Self {
+        self.cost_calculator = Some(calculator);
+        self
+    }
+
     /// Resolve input mappings for a step
     fn resolve_input_mappings(
         &self,
@@ -79,8 +91,8 @@ impl LLMInferenceExecutor {
                     value.clone()
                 }
                 crate::modules::chain_engine::definition::DataSource::Template { template } => {
-                    // Simple template substitution
-                    let mut result = template.clone();
+                    // Render `{{...}}` references, then substitute `{name}` variables
+                    let mut result = template::render(template, &template::template_data(context))?;
 
                     // Replace variables in the template
                     for (name, value) in &context.variables {
@@ -127,8 +139,8 @@ impl StepExecutor for LLMInferenceExecutor {
         // Extract step configuration
         let config = match &step.step_type {
             crate::modules::chain_engine::definition::StepType::LLMInference {
-                model: _,
-                system_prompt: _,
+                model,
+                system_prompt,
                 temperature: _,
                 max_tokens: _,
                 top_p: _,
@@ -156,17 +168,35 @@ impl StepExecutor for LLMInferenceExecutor {
 
                 // Simulate a response for now
                 let output = format!("LLM response for input: {}", input);
-                let _tokens = 100; // Simulated token count
+
+                // Count the prompt and completion with the model's tokenizer
+                let tokenizers = global_tokenizers();
+                let prompt_tokens = system_prompt
+                    .as_deref()
+                    .map_or(0, |prompt| tokenizers.count_tokens(model, prompt))
+                    + tokenizers.count_tokens(model, &input);
+                let completion_tokens = tokenizers.count_tokens(model, &output);
+                let usage = StepUsage::priced(
+                    model,
+                    prompt_tokens as u64,
+                    completion_tokens as u64,
+                    self.cost_calculator.as_deref(),
+                );
 
                 // Create the result
                 let mut outputs = HashMap::new();
                 outputs.insert("output".to_string(), serde_json::Value::String(output));
+                outputs.insert(
+                    "total_tokens".to_string(),
+                    serde_json::Value::from(usage.total_tokens()),
+                );
 
                 StepResult {
                     step_id: step.id.clone(),
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: Some(usage),
                 }
             }
             _ => {
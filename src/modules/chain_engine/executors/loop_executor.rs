@@ -106,6 +106,7 @@ impl StepExecutor for LoopExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
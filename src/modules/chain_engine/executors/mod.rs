@@ -19,11 +19,14 @@ pub trait StepExecutor: Send + Sync {
 }
 
 // Re-export specific executors
+pub mod agent;
 pub mod conditional;
 pub mod custom;
 pub mod function;
+pub mod http;
 pub mod llm;
 pub mod loop_executor;
 pub mod parallel;
+pub mod template;
 pub mod tool;
-
+pub mod transform;
@@ -107,6 +107,7 @@ impl StepExecutor for ParallelExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
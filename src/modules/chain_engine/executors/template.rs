@@ -0,0 +1,151 @@
+//! Step Templates
+//!
+//! This module renders the `{{...}}` templates used by the built-in step
+//! library. Templates see the chain inputs under `inputs`, step outputs under
+//! `steps.<step_id>.<output>` (or `steps.<step_id>.output.<output>`), and chain
+//! variables both under `variables` and at the top level. References may index
+//! arrays, e.g. `{{steps.retrieve.output.documents[0].text}}`.
+
+use handlebars::{no_escape, Handlebars};
+use serde_json::{Map, Value};
+
+use crate::modules::chain_engine::context::ChainContext;
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::expression::{to_handlebars, Reference};
+
+/// Build the data templates are rendered against
+pub fn template_data(context: &ChainContext) -> Value {
+    let mut data: Map<String, Value> = context
+        .variables
+        .iter()
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    let steps = context
+        .step_results
+        .iter()
+        .map(|(id, result)| {
+            let mut outputs: Map<String, Value> = result
+                .outputs
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            if !outputs.contains_key("output") {
+                outputs.insert("output".to_string(), Value::Object(outputs.clone()));
+            }
+            (id.clone(), Value::Object(outputs))
+        })
+        .collect();
+
+    data.insert(
+        "variables".to_string(),
+        serde_json::to_value(&context.variables).unwrap_or_default(),
+    );
+    data.insert(
+        "inputs".to_string(),
+        serde_json::to_value(&context.inputs).unwrap_or_default(),
+    );
+    data.insert("steps".to_string(), Value::Object(steps));
+    Value::Object(data)
+}
+
+/// Render a string template
+pub fn render(template: &str, data: &Value) -> ChainResult<String> {
+    if !template.contains("{{") {
+        return Ok(template.to_string());
+    }
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(no_escape);
+    handlebars
+        .render_template(&to_handlebars(template), data)
+        .map_err(|e| ChainError::StepExecutionError(format!("Template error: {}", e)))
+}
+
+/// Render every string in a JSON value
+///
+/// A string consisting of a single `{{path}}` reference is replaced with the
+/// referenced value itself, so objects and numbers keep their type.
+pub fn render_value(value: &Value, data: &Value) -> ChainResult<Value> {
+    match value {
+        Value::String(s) => match single_reference(s) {
+            Some(reference) => Ok(reference.resolve(data).cloned().unwrap_or(Value::Null)),
+            None => render(s, data).map(Value::String),
+        },
+        Value::Array(items) => items
+            .iter()
+            .map(|item| render_value(item, data))
+            .collect::<ChainResult<Vec<_>>>()
+            .map(Value::Array),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(k, v)| Ok((k.clone(), render_value(v, data)?)))
+            .collect::<ChainResult<Map<_, _>>>()
+            .map(Value::Object),
+        _ => Ok(value.clone()),
+    }
+}
+
+/// Reference of a template consisting of a single reference, e.g.
+/// `{{steps.a.body}}`
+fn single_reference(template: &str) -> Option<Reference> {
+    let path = template.trim().strip_prefix("{{")?.strip_suffix("}}")?;
+    Reference::parse(path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::chain_engine::context::StepResult;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn context() -> ChainContext {
+        let mut step_results = HashMap::new();
+        step_results.insert(
+            "fetch".to_string(),
+            StepResult {
+                step_id: "fetch".to_string(),
+                outputs: HashMap::from([("body".to_string(), json!({"id": 7, "tags": ["a"]}))]),
+                error: None,
+                execution_time: Duration::ZERO,
+                usage: None,
+            },
+        );
+
+        ChainContext {
+            chain_id: "chain".to_string(),
+            variables: HashMap::from([("name".to_string(), json!("<Ada>"))]),
+            step_results,
+            inputs: HashMap::from([("query".to_string(), json!("rust"))]),
+            outputs: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_templates() {
+        let data = template_data(&context());
+
+        assert_eq!(
+            render("Hello {{name}}, searching {{inputs.query}}", &data).unwrap(),
+            "Hello <Ada>, searching rust"
+        );
+        assert_eq!(
+            render_value(&json!({"item": "{{steps.fetch.body}}", "n": 1}), &data).unwrap(),
+            json!({"item": {"id": 7, "tags": ["a"]}, "n": 1})
+        );
+        assert_eq!(
+            render_value(&json!("{{steps.fetch.body.tags.0}}"), &data).unwrap(),
+            json!("a")
+        );
+        assert_eq!(
+            render_value(&json!("{{steps.fetch.output.body.tags[0]}}"), &data).unwrap(),
+            json!("a")
+        );
+        assert_eq!(
+            render("Tag: {{steps.fetch.output.body.tags[0]}}", &data).unwrap(),
+            "Tag: a"
+        );
+    }
+}
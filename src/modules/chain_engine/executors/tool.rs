@@ -4,27 +4,37 @@
 
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use crate::modules::chain_engine::context::{ChainContext, StepResult};
 use crate::modules::chain_engine::definition::ChainStep;
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
-use crate::modules::chain_engine::executors::StepExecutor;
+use crate::modules::chain_engine::executors::{template, StepExecutor};
+use crate::modules::chain_engine::expression::Reference;
+use crate::modules::tools::ToolRegistry;
 
 /// Tool use step executor
+///
+/// Without a tool registry the step output is simulated.
 pub struct ToolUseExecutor {
-    // This would typically contain a registry of available tools
-    // tool_registry: Arc<dyn ToolRegistry>,
+    tool_registry: Option<Arc<ToolRegistry>>,
 }
 
 impl ToolUseExecutor {
     /// Create a new tool use executor
-    pub fn new(/* tool_registry: Arc<dyn ToolRegistry> */) -> Self {
+    pub fn new() -> Self {
         Self {
-            // tool_registry,
+            tool_registry: None,
         }
     }
 
+    /// Execute tools through the given registry
+    pub fn with_registry(mut self, tool_registry: Arc<ToolRegistry>) -> Self {
+        self.tool_registry = Some(tool_registry);
+        self
+    }
+
     /// Resolve arguments for a tool
     fn resolve_arguments(
         &self,
@@ -32,15 +42,17 @@ impl ToolUseExecutor {
         context: &ChainContext,
     ) -> ChainResult<HashMap<String, serde_json::Value>> {
         let mut resolved_args = HashMap::new();
+        let data = template::template_data(context);
 
         for (name, value) in arguments {
             let resolved_value = match value {
                 serde_json::Value::String(s) if s.starts_with("{{") && s.ends_with("}}") => {
-                    // Variable reference
-                    let var_name = s[2..s.len() - 2].trim();
-                    context.variables.get(var_name).cloned().ok_or_else(|| {
-                        ChainError::VariableNotFound(format!("Variable not found: {}", var_name))
-                    })?
+                    // Reference to a variable, input or step output
+                    let expression = s[2..s.len() - 2].trim();
+                    Reference::parse(expression)?
+                        .resolve(&data)
+                        .cloned()
+                        .ok_or_else(|| ChainError::VariableNotFound(expression.to_string()))?
                 }
                 _ => value.clone(),
             };
@@ -70,27 +82,33 @@ impl StepExecutor for ToolUseExecutor {
                 // Prepare the arguments
                 let resolved_args = self.resolve_arguments(arguments, context)?;
 
-                // In a real implementation, we would call the tool registry
-                // let result = self.tool_registry.use_tool(
-                //     tool_name,
-                //     resolved_args,
-                // ).await?;
-
-                // Simulate a response for now
-                let output = format!(
-                    "Tool {} used with arguments: {:?}",
-                    tool_name, resolved_args
-                );
+                let output = match &self.tool_registry {
+                    Some(registry) => {
+                        let arguments =
+                            serde_json::Value::Object(resolved_args.into_iter().collect());
+                        registry.invoke(tool_name, arguments).await.map_err(|e| {
+                            ChainError::StepExecutionError(format!(
+                                "Tool {} failed: {}",
+                                tool_name, e
+                            ))
+                        })?
+                    }
+                    None => serde_json::Value::String(format!(
+                        "Tool {} used with arguments: {:?}",
+                        tool_name, resolved_args
+                    )),
+                };
 
                 // Create the result
                 let mut outputs = HashMap::new();
-                outputs.insert("output".to_string(), serde_json::Value::String(output));
+                outputs.insert("output".to_string(), output);
 
                 StepResult {
                     step_id: step.id.clone(),
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 }
             }
             _ => {
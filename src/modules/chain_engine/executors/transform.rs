@@ -0,0 +1,300 @@
+//! Transform Executors
+//!
+//! This module provides executors for data transformation steps: JSONPath
+//! extraction from JSON values and regex extraction from text.
+
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::modules::chain_engine::context::{ChainContext, StepResult};
+use crate::modules::chain_engine::definition::{ChainStep, StepType};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::template;
+use crate::modules::chain_engine::executors::StepExecutor;
+
+/// Segment of a JSONPath expression
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    /// Object member
+    Key(String),
+    /// Array element (negative indices count from the end)
+    Index(i64),
+    /// Every member or element
+    Wildcard,
+}
+
+/// Parsed JSONPath expression
+///
+/// Supports the common subset: `$`, `.key`, `['key']`, `[0]`, `[-1]`, `.*`
+/// and `[*]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    segments: Vec<PathSegment>,
+}
+
+impl JsonPath {
+    /// Parse a JSONPath expression
+    pub fn parse(path: &str) -> ChainResult<Self> {
+        let invalid = |reason: &str| {
+            ChainError::ValidationError(format!("Invalid JSONPath '{}': {}", path, reason))
+        };
+
+        let trimmed = path.trim();
+        let trimmed = trimmed.strip_prefix('$').unwrap_or(trimmed);
+        // Allow a bare leading member name, e.g. `items[0]`
+        let normalized = if trimmed.is_empty() || trimmed.starts_with(['.', '[']) {
+            trimmed.to_string()
+        } else {
+            format!(".{}", trimmed)
+        };
+        let mut rest = normalized.as_str();
+        let mut segments = Vec::new();
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(['.', '[']).unwrap_or(after.len());
+                let key = &after[..end];
+                if key.is_empty() {
+                    return Err(invalid("empty member name"));
+                }
+                segments.push(if key == "*" {
+                    PathSegment::Wildcard
+                } else {
+                    PathSegment::Key(key.to_string())
+                });
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| invalid("unclosed bracket"))?;
+                let inner = after[..end].trim();
+                segments.push(if inner == "*" {
+                    PathSegment::Wildcard
+                } else if let Some(key) = inner
+                    .strip_prefix('\'')
+                    .and_then(|k| k.strip_suffix('\''))
+                    .or_else(|| inner.strip_prefix('"').and_then(|k| k.strip_suffix('"')))
+                {
+                    PathSegment::Key(key.to_string())
+                } else {
+                    PathSegment::Index(inner.parse().map_err(|_| invalid("invalid index"))?)
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid("expected '.' or '['"));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Whether the expression can select more than one value
+    pub fn is_multi(&self) -> bool {
+        self.segments.contains(&PathSegment::Wildcard)
+    }
+
+    /// Select the values matching the expression
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&'a Value> {
+                    match (segment, value) {
+                        (PathSegment::Key(key), Value::Object(fields)) => {
+                            fields.get(key).into_iter().collect()
+                        }
+                        (PathSegment::Index(index), Value::Array(items)) => {
+                            let index = if *index < 0 {
+                                items.len() as i64 + index
+                            } else {
+                                *index
+                            };
+                            usize::try_from(index)
+                                .ok()
+                                .and_then(|i| items.get(i))
+                                .into_iter()
+                                .collect()
+                        }
+                        (PathSegment::Wildcard, Value::Object(fields)) => fields.values().collect(),
+                        (PathSegment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+
+    /// Evaluate the expression to a single value
+    ///
+    /// Expressions with wildcards yield an array of matches; others yield the
+    /// matching value or null.
+    pub fn evaluate(&self, value: &Value) -> Value {
+        let matches = self.select(value);
+        if self.is_multi() {
+            Value::Array(matches.into_iter().cloned().collect())
+        } else {
+            matches.first().map(|v| (*v).clone()).unwrap_or(Value::Null)
+        }
+    }
+}
+
+/// JSON transform step executor
+#[derive(Default)]
+pub struct JsonTransformExecutor {}
+
+impl JsonTransformExecutor {
+    /// Create a new JSON transform executor
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[async_trait]
+impl StepExecutor for JsonTransformExecutor {
+    async fn execute_step(
+        &self,
+        step: &ChainStep,
+        context: &ChainContext,
+    ) -> ChainResult<StepResult> {
+        let start_time = Instant::now();
+
+        let StepType::JsonTransform { input, mappings } = &step.step_type else {
+            return Err(ChainError::StepExecutionError(format!(
+                "Step type mismatch: expected JsonTransform, got {:?}",
+                step.step_type
+            )));
+        };
+
+        let mut input = template::render_value(input, &template::template_data(context))?;
+        // Accept JSON documents passed as strings, e.g. raw HTTP bodies
+        if let Value::String(s) = &input {
+            if let Ok(parsed) = serde_json::from_str(s) {
+                input = parsed;
+            }
+        }
+
+        let mut outputs = HashMap::new();
+        for (name, path) in mappings {
+            let path = JsonPath::parse(path)?;
+            outputs.insert(name.clone(), path.evaluate(&input));
+        }
+
+        Ok(StepResult {
+            step_id: step.id.clone(),
+            outputs,
+            error: None,
+            execution_time: start_time.elapsed(),
+            usage: None,
+        })
+    }
+}
+
+/// Regex extraction step executor
+#[derive(Default)]
+pub struct RegexExtractExecutor {}
+
+impl RegexExtractExecutor {
+    /// Create a new regex extraction executor
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Collect the groups of a match as a JSON object
+    fn captures_to_value(regex: &Regex, captures: &regex::Captures) -> Value {
+        let mut groups = Map::new();
+        groups.insert("match".to_string(), Value::String(captures[0].to_string()));
+        for (i, name) in regex.capture_names().enumerate().skip(1) {
+            let key = name
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("group_{}", i));
+            let value = captures
+                .get(i)
+                .map(|m| Value::String(m.as_str().to_string()))
+                .unwrap_or(Value::Null);
+            groups.insert(key, value);
+        }
+        Value::Object(groups)
+    }
+}
+
+#[async_trait]
+impl StepExecutor for RegexExtractExecutor {
+    async fn execute_step(
+        &self,
+        step: &ChainStep,
+        context: &ChainContext,
+    ) -> ChainResult<StepResult> {
+        let start_time = Instant::now();
+
+        let StepType::RegexExtract {
+            input,
+            pattern,
+            all_matches,
+        } = &step.step_type
+        else {
+            return Err(ChainError::StepExecutionError(format!(
+                "Step type mismatch: expected RegexExtract, got {:?}",
+                step.step_type
+            )));
+        };
+
+        let regex = Regex::new(pattern).map_err(|e| {
+            ChainError::ValidationError(format!("Invalid regex in step {}: {}", step.id, e))
+        })?;
+        let text = template::render(input, &template::template_data(context))?;
+
+        let mut outputs = HashMap::new();
+        if *all_matches {
+            let matches: Vec<Value> = regex
+                .captures_iter(&text)
+                .map(|captures| Self::captures_to_value(&regex, &captures))
+                .collect();
+            outputs.insert("matched".to_string(), Value::Bool(!matches.is_empty()));
+            outputs.insert("matches".to_string(), Value::Array(matches));
+        } else {
+            let groups = regex
+                .captures(&text)
+                .map(|captures| Self::captures_to_value(&regex, &captures));
+            outputs.insert("matched".to_string(), Value::Bool(groups.is_some()));
+            match groups {
+                Some(Value::Object(groups)) => outputs.extend(groups),
+                _ => {
+                    outputs.insert("match".to_string(), Value::Null);
+                }
+            }
+        }
+
+        Ok(StepResult {
+            step_id: step.id.clone(),
+            outputs,
+            error: None,
+            execution_time: start_time.elapsed(),
+            usage: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_path() {
+        let doc = json!({
+            "data": {"items": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}]},
+            "odd key": true
+        });
+
+        let eval = |path: &str| JsonPath::parse(path).unwrap().evaluate(&doc);
+        assert_eq!(eval("$.data.items[0].name"), json!("a"));
+        assert_eq!(eval("$.data.items[-1].id"), json!(2));
+        assert_eq!(eval("$.data.items[*].id"), json!([1, 2]));
+        assert_eq!(eval("$['odd key']"), json!(true));
+        assert_eq!(eval("data.missing"), Value::Null);
+        assert!(JsonPath::parse("$.data[").is_err());
+    }
+}
@@ -0,0 +1,485 @@
+//! Step Expressions
+//!
+//! This module parses the references used in step templates and conditions,
+//! e.g. `{{steps.retrieve.output.documents[0].text}}`, resolves them against
+//! execution data, and checks them against the chain definition before the
+//! chain runs.
+//!
+//! A reference starts with `steps.<step_id>`, `inputs` or `variables`, or
+//! with the name of a variable, and continues with `.field` and `[index]`
+//! segments. `steps.<step_id>.output` is the object of the step's outputs,
+//! unless the step has an output named `output`.
+
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::modules::chain_engine::definition::{Chain, ChainStep, DataSource};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+
+/// Segment of a reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Object field, or array index when numeric
+    Field(String),
+    /// Array index
+    Index(usize),
+}
+
+/// Reference to a value, e.g. `steps.retrieve.output.documents[0].text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub segments: Vec<Segment>,
+}
+
+impl Reference {
+    /// Parse a reference, without its `{{ }}` delimiters
+    pub fn parse(expression: &str) -> ChainResult<Self> {
+        let invalid = |reason: &str| {
+            ChainError::ValidationError(format!(
+                "Invalid reference `{}`: {}",
+                expression.trim(),
+                reason
+            ))
+        };
+
+        let mut segments = Vec::new();
+        for part in expression.trim().split('.') {
+            let (name, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+            if name.is_empty() {
+                return Err(invalid("empty field name"));
+            }
+            if !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+            {
+                return Err(invalid(&format!("invalid field name `{}`", name)));
+            }
+            segments.push(Segment::Field(name.to_string()));
+
+            while !rest.is_empty() {
+                let (index, tail) = rest
+                    .strip_prefix('[')
+                    .and_then(|r| r.split_once(']'))
+                    .ok_or_else(|| invalid("unclosed `[`"))?;
+                let index = index
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid(&format!("`{}` is not an array index", index)))?;
+                segments.push(Segment::Index(index));
+                rest = tail;
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    /// Resolve the reference in a JSON value
+    pub fn resolve<'a>(&self, data: &'a Value) -> Option<&'a Value> {
+        self.segments
+            .iter()
+            .try_fold(data, |value, segment| match (segment, value) {
+                (Segment::Field(name), Value::Object(fields)) => fields.get(name),
+                (Segment::Field(name), Value::Array(items)) => {
+                    items.get(name.parse::<usize>().ok()?)
+                }
+                (Segment::Index(index), Value::Array(items)) => items.get(*index),
+                _ => None,
+            })
+    }
+
+    /// The reference in Handlebars syntax, which writes indexes as `.[0]`
+    fn to_handlebars(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.segments {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            match segment {
+                Segment::Field(name) => path.push_str(name),
+                Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        path
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Field(name) if i == 0 => write!(f, "{}", name)?,
+                Segment::Field(name) => write!(f, ".{}", name)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// References in a template, as written between `{{ }}`
+///
+/// Handlebars helpers, blocks and comments are not references and are
+/// skipped.
+pub fn template_references(template: &str) -> Vec<&str> {
+    let mut references = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let expression = after[..end].trim_matches(|c: char| c == '{' || c.is_whitespace());
+        let starts_like_reference = expression
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_');
+        if starts_like_reference
+            && !expression.contains(char::is_whitespace)
+            && !matches!(expression, "else" | "this")
+        {
+            references.push(expression);
+        }
+        rest = &after[end + 2..];
+    }
+    references
+}
+
+/// Rewrite the indexes of a template's references for Handlebars
+pub fn to_handlebars(template: &str) -> Cow<'_, str> {
+    if !template.contains('[') {
+        return Cow::Borrowed(template);
+    }
+    let mut rewritten = template.to_string();
+    for expression in template_references(template) {
+        if let Ok(reference) = Reference::parse(expression) {
+            if expression.contains('[') {
+                rewritten = rewritten.replace(expression, &reference.to_handlebars());
+            }
+        }
+    }
+    Cow::Owned(rewritten)
+}
+
+/// Check the references of every step against the chain definition
+///
+/// References to unknown steps, to outputs a step does not declare, to
+/// unknown chain inputs and to unknown variables are rejected, naming the
+/// step and suggesting the closest known name.
+pub fn check_references(chain: &Chain) -> ChainResult<()> {
+    let mut step_ids: Vec<&String> = chain.steps.keys().collect();
+    step_ids.sort();
+    for step_id in step_ids {
+        for template in step_templates(chain, &chain.steps[step_id]) {
+            for expression in template_references(&template) {
+                let reference = match Reference::parse(expression) {
+                    Ok(reference) => reference,
+                    Err(ChainError::ValidationError(message)) => {
+                        return Err(ChainError::ValidationError(format!(
+                            "{} in step {}",
+                            message, step_id
+                        )))
+                    }
+                    Err(e) => return Err(e),
+                };
+                if let Err(reason) = check_reference(chain, &reference) {
+                    return Err(ChainError::ValidationError(format!(
+                        "Unknown reference {{{{{}}}}} in step {}: {}",
+                        expression, step_id, reason
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Strings of a step that may hold references: its configuration, input
+/// templates and conditions
+fn step_templates(chain: &Chain, step: &ChainStep) -> Vec<String> {
+    let mut values = vec![serde_json::to_value(&step.step_type).unwrap_or_default()];
+    if let Some(condition) = &step.condition {
+        values.push(serde_json::to_value(condition).unwrap_or_default());
+    }
+    values.extend(
+        chain
+            .dependencies
+            .iter()
+            .filter(|d| d.dependent_step == step.id)
+            .map(|d| serde_json::to_value(&d.dependency_type).unwrap_or_default()),
+    );
+
+    let mut templates: Vec<String> = step
+        .inputs
+        .iter()
+        .filter_map(|input| match &input.source {
+            DataSource::Template { template } => Some(template.clone()),
+            _ => None,
+        })
+        .collect();
+    for value in &values {
+        collect_strings(value, &mut templates);
+    }
+    templates.retain(|t| t.contains("{{"));
+    templates
+}
+
+fn collect_strings(value: &Value, strings: &mut Vec<String>) {
+    match value {
+        Value::String(s) => strings.push(s.clone()),
+        Value::Array(items) => items.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(fields) => fields.values().for_each(|v| collect_strings(v, strings)),
+        _ => {}
+    }
+}
+
+/// Check a reference, returning why it is unknown
+fn check_reference(chain: &Chain, reference: &Reference) -> Result<(), String> {
+    let field = |i: usize| match reference.segments.get(i) {
+        Some(Segment::Field(name)) => Some(name.as_str()),
+        _ => None,
+    };
+
+    match field(0) {
+        Some("steps") => {
+            let Some(step_id) = field(1) else {
+                return Ok(());
+            };
+            let Some(step) = chain.steps.get(step_id) else {
+                return Err(unknown(
+                    format!("step `{}`", step_id),
+                    step_id,
+                    chain.steps.keys(),
+                ));
+            };
+
+            let declared = declared_outputs(step);
+            let mut rest = &reference.segments[2..];
+            if field(2) == Some("output") && !declared.contains("output") {
+                rest = &rest[1..];
+            }
+            let Some(Segment::Field(output)) = rest.first() else {
+                return Ok(());
+            };
+            if declared.is_empty() {
+                return Ok(());
+            }
+            if !declared.contains(output.as_str()) {
+                return Err(unknown(
+                    format!("output `{}` of step {}", output, step_id),
+                    output,
+                    declared.iter(),
+                ));
+            }
+            match step
+                .output_schema
+                .as_ref()
+                .and_then(|s| s.get("properties"))
+                .and_then(|p| p.get(output))
+            {
+                Some(schema) => check_schema_path(
+                    schema,
+                    &rest[1..],
+                    &format!("output `{}` of step {}", output, step_id),
+                ),
+                None => Ok(()),
+            }
+        }
+        Some("inputs") => match &chain.input_schema {
+            Some(schema) => check_schema_path(schema, &reference.segments[1..], "the chain inputs"),
+            None => Ok(()),
+        },
+        Some("variables") => match field(1) {
+            Some(name) if !chain.variables.contains_key(name) => Err(unknown(
+                format!("variable `{}`", name),
+                name,
+                chain.variables.keys(),
+            )),
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Names of the outputs a step declares, by output mapping or output schema
+fn declared_outputs(step: &ChainStep) -> BTreeSet<&str> {
+    let mut declared: BTreeSet<&str> = step.outputs.iter().map(|o| o.name.as_str()).collect();
+    if let Some(Value::Object(properties)) = step
+        .output_schema
+        .as_ref()
+        .and_then(|s| s.get("properties"))
+    {
+        declared.extend(properties.keys().map(String::as_str));
+    }
+    declared
+}
+
+/// Check that a path exists in the JSON schema of `owner`, as far as the
+/// schema declares `properties` and `items`
+fn check_schema_path(mut schema: &Value, segments: &[Segment], owner: &str) -> Result<(), String> {
+    for segment in segments {
+        schema = match segment {
+            Segment::Field(name) => match schema.get("properties") {
+                Some(Value::Object(properties)) => match properties.get(name) {
+                    Some(property) => property,
+                    None => {
+                        return Err(unknown(
+                            format!("field `{}` in {}", name, owner),
+                            name,
+                            properties.keys(),
+                        ))
+                    }
+                },
+                _ => match schema.get("items") {
+                    Some(items) if name.parse::<usize>().is_ok() => items,
+                    _ => return Ok(()),
+                },
+            },
+            Segment::Index(_) => match schema.get("items") {
+                Some(items) => items,
+                None => return Ok(()),
+            },
+        };
+    }
+    Ok(())
+}
+
+/// Describe an unknown name, suggesting the closest known one
+fn unknown<S: AsRef<str>>(what: String, name: &str, known: impl Iterator<Item = S>) -> String {
+    let mut known: Vec<String> = known.map(|s| s.as_ref().to_string()).collect();
+    known.sort();
+    let closest = known
+        .iter()
+        .map(|k| (edit_distance(name, k), k))
+        .filter(|(distance, k)| *distance <= (k.chars().count() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance);
+
+    match closest {
+        Some((_, suggestion)) => format!("unknown {}, did you mean `{}`?", what, suggestion),
+        None if known.is_empty() => format!("unknown {}", what),
+        None => format!("unknown {}, expected one of: {}", what, known.join(", ")),
+    }
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != *cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_and_resolve() {
+        let reference = Reference::parse("steps.retrieve.output.documents[0].text").unwrap();
+        assert_eq!(
+            reference.to_string(),
+            "steps.retrieve.output.documents[0].text"
+        );
+        assert_eq!(
+            reference.to_handlebars(),
+            "steps.retrieve.output.documents.[0].text"
+        );
+
+        let data = json!({"steps": {"retrieve": {"output": {"documents": [{"text": "a"}]}}}});
+        assert_eq!(reference.resolve(&data), Some(&json!("a")));
+        assert!(Reference::parse("steps.a[x]").is_err());
+        assert!(Reference::parse("steps..a").is_err());
+    }
+
+    #[test]
+    fn test_template_references() {
+        assert_eq!(
+            template_references("{{#each items}}{{ steps.a.output[1] }}{{/each}} {{{name}}}"),
+            vec!["steps.a.output[1]", "name"]
+        );
+        assert_eq!(
+            to_handlebars("Top: {{steps.a.output.docs[0].text}}"),
+            "Top: {{steps.a.output.docs.[0].text}}"
+        );
+    }
+
+    #[test]
+    fn test_check_references() {
+        let chain = |template: &str| -> Chain {
+            serde_json::from_value(json!({
+                "id": "rag",
+                "name": "RAG",
+                "description": "Retrieve and answer",
+                "version": "1.0.0",
+                "input_schema": {"type": "object", "properties": {"question": {"type": "string"}}},
+                "steps": {
+                    "retrieve": {
+                        "id": "retrieve",
+                        "name": "Retrieve",
+                        "description": "Retrieve documents",
+                        "role": "system",
+                        "step_type": {"type": "Custom", "config": {"handler": "search"}},
+                        "output_schema": {
+                            "type": "object",
+                            "properties": {
+                                "documents": {
+                                    "type": "array",
+                                    "items": {"type": "object", "properties": {"text": {"type": "string"}}}
+                                }
+                            }
+                        }
+                    },
+                    "answer": {
+                        "id": "answer",
+                        "name": "Answer",
+                        "description": "Answer the question",
+                        "role": "assistant",
+                        "step_type": {"type": "RegexExtract", "config": {"input": template, "pattern": "."}}
+                    }
+                }
+            }))
+            .unwrap()
+        };
+
+        assert!(check_references(&chain(
+            "{{inputs.question}}: {{steps.retrieve.output.documents[0].text}}"
+        ))
+        .is_ok());
+
+        let error = check_references(&chain("{{steps.retrive.output}}"))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("in step answer"), "{}", error);
+        assert!(error.contains("did you mean `retrieve`?"), "{}", error);
+
+        let error = check_references(&chain("{{steps.retrieve.output.document[0].text}}"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("unknown output `document` of step retrieve, did you mean `documents`?"),
+            "{}",
+            error
+        );
+
+        let error = check_references(&chain("{{steps.retrieve.documents[0].txt}}"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("unknown field `txt` in output `documents` of step retrieve"),
+            "{}",
+            error
+        );
+
+        assert!(check_references(&chain("{{inputs.questions}}")).is_err());
+    }
+}
@@ -4,22 +4,42 @@
 //! It allows for creating complex workflows with multiple steps, conditional branching,
 //! parallel execution, and data transformation between steps.
 
+pub mod agent;
+pub mod api;
+pub mod budget;
+pub mod checkpoint;
 mod condition_evaluator;
 mod context;
+pub mod conversation;
+pub mod dead_letter;
 mod definition;
 mod engine;
 mod error;
 mod executors;
+mod expression;
+pub mod scheduler;
+mod schema;
 mod validation;
+pub mod webhooks;
 
 // Tests moved to tests/unit/modules/chain_engine/
 
+pub use agent::{AgentDefinition, AgentRun, AgentRuntime, AgentStopReason};
+pub use budget::{BudgetExceeded, BudgetLimit, ChainUsage, StepUsage};
+pub use checkpoint::{
+    ChainCheckpoint, CheckpointStore, ExecutionStatus, StepCompensation, StepRetry,
+};
 pub use condition_evaluator::*;
 pub use context::*;
+pub use conversation::{ConversationDefinition, ConversationTranscript, TurnPolicy};
+pub use dead_letter::{DeadLetter, DeadLetterQueue, DeadLetterSource, DeadLetterStore};
 pub use definition::*;
 pub use engine::*;
 pub use error::*;
+pub use executors::transform::JsonPath;
 pub use executors::StepExecutor;
+pub use scheduler::ChainScheduler;
+pub use schema::{ChainSchemas, StepSchemas};
 pub use validation::*;
 
 // Note: The following files are now redundant and should be removed in a future cleanup:
@@ -0,0 +1,506 @@
+//! Chain scheduler
+//!
+//! This module triggers chains on cron schedules, so periodic chains (e.g.
+//! summaries or reports) don't need an external scheduler. Each schedule has
+//! an overlap policy for runs that come due while the previous run is still
+//! going, optional jitter, and a bounded run history.
+
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use rand::Rng;
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::{ChainEngineConfig, ChainScheduleConfig, ScheduleOverlapPolicy};
+use crate::modules::chain_engine::definition::Chain;
+use crate::modules::chain_engine::engine::ChainEngine;
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::schema::{validate_chain_inputs, ChainSchemas};
+
+/// Outcome of a scheduled run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledRunStatus {
+    /// Waiting for the previous run to finish
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    /// Not started because the previous run was still running, or too many
+    /// runs were queued
+    Skipped,
+    /// Cancelled by a newer run
+    Cancelled,
+}
+
+/// A single run of a schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledRun {
+    pub schedule_id: String,
+    /// Execution ID, usable with the chain execution API
+    pub execution_id: String,
+    pub status: ScheduledRunStatus,
+    /// Time the run was due, before jitter
+    pub scheduled_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+/// Summary of a schedule
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleSummary {
+    pub id: String,
+    pub cron: String,
+    pub chain_id: String,
+    pub overlap: ScheduleOverlapPolicy,
+    pub max_queued: usize,
+    pub jitter_secs: u64,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<ScheduledRun>,
+}
+
+/// Run in progress for a schedule
+struct ActiveRun {
+    execution_id: String,
+    handle: JoinHandle<()>,
+}
+
+/// Mutable state of a schedule
+#[derive(Default)]
+struct ScheduleState {
+    active: Option<ActiveRun>,
+    /// Runs waiting for the active run to finish, oldest first
+    queued: VecDeque<ScheduledRun>,
+}
+
+/// A parsed schedule
+struct ChainSchedule {
+    config: ChainScheduleConfig,
+    schedule: Schedule,
+    chain: Chain,
+    state: Mutex<ScheduleState>,
+}
+
+/// Scheduler triggering chains on cron expressions
+pub struct ChainScheduler {
+    engine: Arc<ChainEngine>,
+    schedules: Vec<ChainSchedule>,
+    history: StdMutex<HashMap<String, VecDeque<ScheduledRun>>>,
+    history_limit: usize,
+}
+
+impl std::fmt::Debug for ChainScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainScheduler")
+            .field(
+                "schedules",
+                &self
+                    .schedules
+                    .iter()
+                    .map(|s| &s.config.id)
+                    .collect::<Vec<_>>(),
+            )
+            .field("history_limit", &self.history_limit)
+            .finish()
+    }
+}
+
+/// Parse a cron expression
+///
+/// Standard five-field expressions are accepted as well as the six- and
+/// seven-field forms with seconds (and years).
+pub fn parse_cron(expression: &str) -> ChainResult<Schedule> {
+    let expression = expression.trim();
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    Schedule::from_str(&normalized).map_err(|e| {
+        ChainError::ValidationError(format!("Invalid cron expression '{}': {}", expression, e))
+    })
+}
+
+impl ChainScheduler {
+    /// Create a scheduler without schedules
+    pub fn new(engine: Arc<ChainEngine>, history_limit: usize) -> Self {
+        Self {
+            engine,
+            schedules: Vec::new(),
+            history: StdMutex::new(HashMap::new()),
+            history_limit,
+        }
+    }
+
+    /// Create a scheduler from the chain engine configuration
+    ///
+    /// Chain definitions are loaded from each schedule's `chain_file`.
+    /// Disabled schedules are ignored.
+    pub fn from_config(engine: Arc<ChainEngine>, config: &ChainEngineConfig) -> ChainResult<Self> {
+        let mut scheduler = Self::new(engine, config.schedule_history_limit);
+        for schedule in config.schedules.iter().filter(|s| s.enabled) {
+            let json = std::fs::read_to_string(&schedule.chain_file)?;
+            let chain: Chain = serde_json::from_str(&json)?;
+            scheduler = scheduler.with_schedule(schedule.clone(), chain)?;
+        }
+        Ok(scheduler)
+    }
+
+    /// Add a schedule for a chain
+    pub fn with_schedule(mut self, config: ChainScheduleConfig, chain: Chain) -> ChainResult<Self> {
+        if self.schedules.iter().any(|s| s.config.id == config.id) {
+            return Err(ChainError::ValidationError(format!(
+                "Duplicate schedule id: {}",
+                config.id
+            )));
+        }
+
+        let schedule = parse_cron(&config.cron)?;
+        validate_chain_inputs(&chain, &config.inputs)?;
+        self.schedules.push(ChainSchedule {
+            config,
+            schedule,
+            chain,
+            state: Mutex::new(ScheduleState::default()),
+        });
+        Ok(self)
+    }
+
+    /// Start a background task per schedule
+    pub fn start(self: &Arc<Self>) -> Vec<JoinHandle<()>> {
+        (0..self.schedules.len())
+            .map(|index| {
+                let scheduler = self.clone();
+                tokio::spawn(async move { scheduler.run_schedule(index).await })
+            })
+            .collect()
+    }
+
+    /// Summaries of all schedules
+    pub fn schedules(&self) -> Vec<ScheduleSummary> {
+        let history = self.history.lock().unwrap();
+        self.schedules
+            .iter()
+            .map(|s| ScheduleSummary {
+                id: s.config.id.clone(),
+                cron: s.config.cron.clone(),
+                chain_id: s.chain.id.clone(),
+                overlap: s.config.overlap,
+                max_queued: s.config.max_queued,
+                jitter_secs: s.config.jitter_secs,
+                next_run: s.schedule.upcoming(Utc).next(),
+                last_run: history.get(&s.config.id).and_then(|h| h.back().cloned()),
+            })
+            .collect()
+    }
+
+    /// JSON schemas of a schedule's chain
+    ///
+    /// Returns `None` for unknown schedules.
+    pub fn schemas(&self, schedule_id: &str) -> Option<ChainSchemas> {
+        self.schedules
+            .iter()
+            .find(|s| s.config.id == schedule_id)
+            .map(|s| ChainSchemas::new(&s.chain))
+    }
+
+    /// Run history of a schedule, oldest first
+    ///
+    /// Returns `None` for unknown schedules.
+    pub fn history(&self, schedule_id: &str) -> Option<Vec<ScheduledRun>> {
+        self.schedules
+            .iter()
+            .any(|s| s.config.id == schedule_id)
+            .then(|| {
+                self.history
+                    .lock()
+                    .unwrap()
+                    .get(schedule_id)
+                    .map(|h| h.iter().cloned().collect())
+                    .unwrap_or_default()
+            })
+    }
+
+    /// Trigger a schedule now, applying its overlap policy
+    pub async fn trigger(self: &Arc<Self>, schedule_id: &str) -> ChainResult<ScheduledRun> {
+        let index = self
+            .schedules
+            .iter()
+            .position(|s| s.config.id == schedule_id)
+            .ok_or_else(|| ChainError::Other(format!("Schedule not found: {}", schedule_id)))?;
+        Ok(self.fire(index, Utc::now()).await)
+    }
+
+    /// Wait for each due time of a schedule and fire it
+    async fn run_schedule(self: Arc<Self>, index: usize) {
+        let schedule = &self.schedules[index];
+        info!(
+            "Chain schedule '{}' started ({})",
+            schedule.config.id, schedule.config.cron
+        );
+
+        while let Some(due) = schedule.schedule.upcoming(Utc).next() {
+            let jitter = match schedule.config.jitter_secs {
+                0 => 0,
+                max => rand::thread_rng().gen_range(0..=max),
+            };
+            let delay =
+                (due - Utc::now()).to_std().unwrap_or_default() + Duration::from_secs(jitter);
+            tokio::time::sleep(delay).await;
+
+            self.fire(index, due).await;
+        }
+
+        info!("Chain schedule '{}' has no more runs", schedule.config.id);
+    }
+
+    /// Fire a schedule, applying its overlap policy
+    async fn fire(self: &Arc<Self>, index: usize, scheduled_at: DateTime<Utc>) -> ScheduledRun {
+        let schedule = &self.schedules[index];
+        let mut run = ScheduledRun {
+            schedule_id: schedule.config.id.clone(),
+            execution_id: Uuid::new_v4().to_string(),
+            status: ScheduledRunStatus::Running,
+            scheduled_at,
+            started_at: None,
+            finished_at: None,
+            error: None,
+        };
+
+        let mut state = schedule.state.lock().await;
+        let overlapping = state
+            .active
+            .as_ref()
+            .filter(|active| !active.handle.is_finished())
+            .map(|active| active.execution_id.clone());
+
+        if let Some(previous) = overlapping {
+            match schedule.config.overlap {
+                ScheduleOverlapPolicy::Skip => {
+                    debug!(
+                        "Skipping run of schedule '{}': {} is still running",
+                        schedule.config.id, previous
+                    );
+                    run.status = ScheduledRunStatus::Skipped;
+                    run.finished_at = Some(Utc::now());
+                    self.record(&run);
+                    return run;
+                }
+                ScheduleOverlapPolicy::Queue
+                    if state.queued.len() >= schedule.config.max_queued =>
+                {
+                    debug!(
+                        "Skipping run of schedule '{}': {} runs are already queued",
+                        schedule.config.id,
+                        state.queued.len()
+                    );
+                    run.status = ScheduledRunStatus::Skipped;
+                    run.finished_at = Some(Utc::now());
+                    self.record(&run);
+                    return run;
+                }
+                ScheduleOverlapPolicy::Queue => {
+                    run.status = ScheduledRunStatus::Queued;
+                    state.queued.push_back(run.clone());
+                    self.record(&run);
+                    return run;
+                }
+                ScheduleOverlapPolicy::CancelPrevious => {
+                    // Stop checkpointed executions cleanly, then abort the task
+                    if let Err(e) = self.engine.cancel_execution(&previous).await {
+                        debug!("Could not cancel execution {}: {}", previous, e);
+                    }
+                    if let Some(active) = state.active.take() {
+                        active.handle.abort();
+                    }
+                    self.update(&schedule.config.id, &previous, |r| {
+                        r.status = ScheduledRunStatus::Cancelled;
+                        r.finished_at = Some(Utc::now());
+                    });
+                }
+            }
+        }
+
+        run.started_at = Some(Utc::now());
+        self.record(&run);
+        state.active = Some(self.launch(index, run.execution_id.clone()));
+        run
+    }
+
+    /// Spawn the execution of a run
+    fn launch(self: &Arc<Self>, index: usize, execution_id: String) -> ActiveRun {
+        let scheduler = self.clone();
+        let id = execution_id.clone();
+        let handle = tokio::spawn(async move {
+            let schedule = &scheduler.schedules[index];
+            let result = scheduler
+                .engine
+                .execute_chain_with_id(&id, &schedule.chain, schedule.config.inputs.clone())
+                .await;
+
+            let (status, error) = match result {
+                Ok(_) => (ScheduledRunStatus::Completed, None),
+                Err(ChainError::Cancelled(_)) => (ScheduledRunStatus::Cancelled, None),
+                Err(e) => {
+                    warn!(
+                        "Scheduled run {} of '{}' failed: {}",
+                        id, schedule.config.id, e
+                    );
+                    (ScheduledRunStatus::Failed, Some(e.to_string()))
+                }
+            };
+            scheduler.update(&schedule.config.id, &id, |r| {
+                r.status = status;
+                r.error = error;
+                r.finished_at = Some(Utc::now());
+            });
+
+            // Start the next queued run, if any
+            let mut state = schedule.state.lock().await;
+            if state
+                .active
+                .as_ref()
+                .is_some_and(|active| active.execution_id == id)
+            {
+                state.active = None;
+                if let Some(next) = state.queued.pop_front() {
+                    scheduler.update(&schedule.config.id, &next.execution_id, |r| {
+                        r.status = ScheduledRunStatus::Running;
+                        r.started_at = Some(Utc::now());
+                    });
+                    state.active = Some(scheduler.launch(index, next.execution_id));
+                }
+            }
+        });
+
+        ActiveRun {
+            execution_id,
+            handle,
+        }
+    }
+
+    /// Append a run to its schedule's history
+    fn record(&self, run: &ScheduledRun) {
+        let mut history = self.history.lock().unwrap();
+        let runs = history.entry(run.schedule_id.clone()).or_default();
+        runs.push_back(run.clone());
+        while runs.len() > self.history_limit {
+            runs.pop_front();
+        }
+    }
+
+    /// Update a run in its schedule's history
+    fn update(&self, schedule_id: &str, execution_id: &str, f: impl FnOnce(&mut ScheduledRun)) {
+        let mut history = self.history.lock().unwrap();
+        if let Some(run) = history
+            .get_mut(schedule_id)
+            .and_then(|runs| runs.iter_mut().find(|r| r.execution_id == execution_id))
+        {
+            f(run);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schedule(overlap: ScheduleOverlapPolicy) -> ChainScheduleConfig {
+        ChainScheduleConfig {
+            id: "nightly".to_string(),
+            cron: "0 2 * * *".to_string(),
+            chain_file: String::new(),
+            inputs: HashMap::new(),
+            overlap,
+            max_queued: 1,
+            jitter_secs: 0,
+            enabled: true,
+        }
+    }
+
+    /// Chain whose only step waits on an unroutable HTTP endpoint
+    fn slow_chain() -> Chain {
+        serde_json::from_value(json!({
+            "id": "slow",
+            "name": "Slow",
+            "description": "Slow chain",
+            "version": "1.0.0",
+            "steps": {
+                "wait": {
+                    "id": "wait",
+                    "name": "Wait",
+                    "description": "Wait",
+                    "role": "system",
+                    "timeout": 2,
+                    "step_type": {
+                        "type": "HttpRequest",
+                        "config": {"url": "http://10.255.255.1/"}
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_cron() {
+        let schedule = parse_cron("*/15 * * * *").unwrap();
+        let next: Vec<_> = schedule.upcoming(Utc).take(2).collect();
+        assert_eq!((next[1] - next[0]).num_minutes(), 15);
+        assert!(parse_cron("0 0 * * * *").is_ok());
+        assert!(parse_cron("not a cron").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_overlap_policies() {
+        let engine = Arc::new(ChainEngine::new());
+
+        let skipping = Arc::new(
+            ChainScheduler::new(engine.clone(), 10)
+                .with_schedule(schedule(ScheduleOverlapPolicy::Skip), slow_chain())
+                .unwrap(),
+        );
+        let first = skipping.trigger("nightly").await.unwrap();
+        let second = skipping.trigger("nightly").await.unwrap();
+        assert_eq!(first.status, ScheduledRunStatus::Running);
+        assert_eq!(second.status, ScheduledRunStatus::Skipped);
+
+        let queueing = Arc::new(
+            ChainScheduler::new(engine.clone(), 10)
+                .with_schedule(schedule(ScheduleOverlapPolicy::Queue), slow_chain())
+                .unwrap(),
+        );
+        queueing.trigger("nightly").await.unwrap();
+        let queued = queueing.trigger("nightly").await.unwrap();
+        assert_eq!(queued.status, ScheduledRunStatus::Queued);
+        let overflow = queueing.trigger("nightly").await.unwrap();
+        assert_eq!(overflow.status, ScheduledRunStatus::Skipped);
+
+        let cancelling = Arc::new(
+            ChainScheduler::new(engine, 10)
+                .with_schedule(
+                    schedule(ScheduleOverlapPolicy::CancelPrevious),
+                    slow_chain(),
+                )
+                .unwrap(),
+        );
+        cancelling.trigger("nightly").await.unwrap();
+        cancelling.trigger("nightly").await.unwrap();
+        let history = cancelling.history("nightly").unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].status, ScheduledRunStatus::Cancelled);
+        assert_eq!(history[1].status, ScheduledRunStatus::Running);
+        assert!(cancelling.history("unknown").is_none());
+    }
+}
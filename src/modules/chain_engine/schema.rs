@@ -0,0 +1,216 @@
+//! Chain Schemas
+//!
+//! This module validates chain inputs and outputs, and step inputs and
+//! outputs, against the JSON schemas declared in chain definitions. A step's
+//! input is the object of its input mappings; its output is the object of
+//! the outputs it produced.
+
+use std::collections::{BTreeMap, HashMap};
+
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+use crate::modules::chain_engine::context::ChainContext;
+use crate::modules::chain_engine::definition::{Chain, ChainStep, DataSource};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::template;
+
+/// JSON schemas declared by a chain, for client code generation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChainSchemas {
+    pub chain_id: String,
+    /// Schema of the chain inputs
+    #[schema(value_type = Option<Object>)]
+    pub input: Option<Value>,
+    /// Schema of the chain outputs
+    #[schema(value_type = Option<Object>)]
+    pub output: Option<Value>,
+    /// Schemas of the steps declaring any
+    pub steps: BTreeMap<String, StepSchemas>,
+}
+
+/// JSON schemas declared by a step
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepSchemas {
+    #[schema(value_type = Option<Object>)]
+    pub input: Option<Value>,
+    #[schema(value_type = Option<Object>)]
+    pub output: Option<Value>,
+}
+
+impl ChainSchemas {
+    /// Collect the schemas of a chain
+    pub fn new(chain: &Chain) -> Self {
+        Self {
+            chain_id: chain.id.clone(),
+            input: chain.input_schema.clone(),
+            output: chain.output_schema.clone(),
+            steps: chain
+                .steps
+                .iter()
+                .filter(|(_, step)| step.input_schema.is_some() || step.output_schema.is_some())
+                .map(|(id, step)| {
+                    let schemas = StepSchemas {
+                        input: step.input_schema.clone(),
+                        output: step.output_schema.clone(),
+                    };
+                    (id.clone(), schemas)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Check that a schema compiles
+pub fn check_schema(schema: &Value) -> Result<(), String> {
+    JSONSchema::compile(schema)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Validate a value against a schema
+///
+/// `subject` names what is validated, e.g. `output of step summarize`. The
+/// error lists each violation with the path of the offending value.
+pub fn validate(schema: &Value, value: &Value, subject: &str) -> ChainResult<()> {
+    let compiled = JSONSchema::compile(schema).map_err(|e| {
+        ChainError::ValidationError(format!("Invalid schema for {}: {}", subject, e))
+    })?;
+    if let Err(errors) = compiled.validate(value) {
+        let violations: Vec<String> = errors
+            .map(|e| {
+                let path = e.instance_path.to_string();
+                let path = if path.is_empty() {
+                    "/".to_string()
+                } else {
+                    path
+                };
+                format!("at {}: {}", path, e)
+            })
+            .collect();
+        return Err(ChainError::SchemaViolation(format!(
+            "{} {}",
+            subject,
+            violations.join("; ")
+        )));
+    }
+    Ok(())
+}
+
+/// Validate the chain inputs against the chain's input schema
+pub fn validate_chain_inputs(chain: &Chain, inputs: &HashMap<String, Value>) -> ChainResult<()> {
+    match &chain.input_schema {
+        Some(schema) => validate(schema, &object(inputs), "input of the chain"),
+        None => Ok(()),
+    }
+}
+
+/// Validate the chain outputs against the chain's output schema
+pub fn validate_chain_outputs(chain: &Chain, context: &ChainContext) -> ChainResult<()> {
+    match &chain.output_schema {
+        Some(schema) => validate(schema, &object(&context.outputs), "output of the chain"),
+        None => Ok(()),
+    }
+}
+
+/// Validate the inputs of a step against its input schema
+pub fn validate_step_inputs(step: &ChainStep, context: &ChainContext) -> ChainResult<()> {
+    match &step.input_schema {
+        Some(schema) => validate(
+            schema,
+            &step_inputs(step, context)?,
+            &format!("input of step {}", step.id),
+        ),
+        None => Ok(()),
+    }
+}
+
+/// Validate the outputs of a step against its output schema
+pub fn validate_step_outputs(step: &ChainStep, context: &ChainContext) -> ChainResult<()> {
+    let Some(schema) = &step.output_schema else {
+        return Ok(());
+    };
+    let outputs = context
+        .step_results
+        .get(&step.id)
+        .map(|result| object(&result.outputs))
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    validate(schema, &outputs, &format!("output of step {}", step.id))
+}
+
+/// Resolve the input mappings of a step into an object
+///
+/// Mappings whose source is missing take their default value, if any, and
+/// are left out otherwise, for the schema to decide whether they are required.
+fn step_inputs(step: &ChainStep, context: &ChainContext) -> ChainResult<Value> {
+    let data = template::template_data(context);
+    let mut inputs = Map::new();
+    for input in &step.inputs {
+        let value = match &input.source {
+            DataSource::ChainInput { input_name } => context.inputs.get(input_name).cloned(),
+            DataSource::Variable { variable_name } => context.variables.get(variable_name).cloned(),
+            DataSource::StepOutput {
+                step_id,
+                output_name,
+            } => context
+                .step_results
+                .get(step_id)
+                .and_then(|result| result.outputs.get(output_name))
+                .cloned(),
+            DataSource::Literal { value } => Some(value.clone()),
+            DataSource::Template { template } => {
+                Some(Value::String(template::render(template, &data)?))
+            }
+        };
+        if let Some(value) = value.or_else(|| input.default_value.clone()) {
+            inputs.insert(input.name.clone(), value);
+        }
+    }
+    Ok(Value::Object(inputs))
+}
+
+fn object(values: &HashMap<String, Value>) -> Value {
+    Value::Object(values.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_names_path() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"order": {"type": "object", "properties": {"total": {"type": "number"}}}},
+            "required": ["order"]
+        });
+        assert!(validate(&schema, &json!({"order": {"total": 3}}), "output of step s").is_ok());
+
+        let error = validate(
+            &schema,
+            &json!({"order": {"total": "3"}}),
+            "output of step s",
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(
+            error.contains("output of step s at /order/total"),
+            "{}",
+            error
+        );
+
+        let error = validate(&schema, &json!({}), "input of the chain")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("input of the chain at /:"), "{}", error);
+    }
+
+    #[test]
+    fn test_check_schema() {
+        assert!(check_schema(&json!({"type": "object"})).is_ok());
+        assert!(check_schema(&json!({"type": "nothing"})).is_err());
+    }
+}
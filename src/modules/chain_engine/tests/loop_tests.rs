@@ -38,6 +38,7 @@ impl StepExecutor for MockStepExecutor {
             outputs: self.result.clone(),
             error: None,
             execution_time: self.delay,
+            usage: None,
         })
     }
 }
@@ -54,10 +55,13 @@ fn create_test_chain() -> Chain {
         steps: HashMap::new(),
         dependencies: Vec::new(),
         variables: HashMap::new(),
+        input_schema: None,
+        output_schema: None,
         error_handling:
             crate::modules::chain_engine::definition::ErrorHandlingStrategy::StopOnError,
         max_parallel_steps: None,
         timeout: None,
+        budget: None,
     }
 }
 
@@ -71,10 +75,13 @@ fn create_test_step(id: &str, step_type: StepType) -> ChainStep {
         role: Role::System,
         inputs: Vec::new(),
         outputs: Vec::new(),
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     }
 }
 
@@ -4,8 +4,12 @@
 
 use std::collections::{HashMap, HashSet};
 
+use crate::modules::chain_engine::conversation::validate_conversation;
 use crate::modules::chain_engine::definition::{Chain, Condition, DependencyType, StepType};
 use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::executors::transform::JsonPath;
+use crate::modules::chain_engine::expression::check_references;
+use crate::modules::chain_engine::schema::check_schema;
 
 /// Validates a chain definition
 pub fn validate_chain(chain: &Chain) -> ChainResult<()> {
@@ -81,6 +85,71 @@ pub fn validate_chain(chain: &Chain) -> ChainResult<()> {
                     }
                 }
             }
+            StepType::HttpRequest { method, url, .. } => {
+                // Validate HTTP request step
+                if url.is_empty() {
+                    return Err(ChainError::ValidationError(format!(
+                        "URL cannot be empty in step: {}",
+                        step_id
+                    )));
+                }
+                if reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).is_err() {
+                    return Err(ChainError::ValidationError(format!(
+                        "Invalid HTTP method '{}' in step: {}",
+                        method, step_id
+                    )));
+                }
+            }
+            StepType::JsonTransform { mappings, .. } => {
+                // Validate JSON transform step
+                for path in mappings.values() {
+                    JsonPath::parse(path)?;
+                }
+            }
+            StepType::RegexExtract { pattern, .. } => {
+                // Validate regex extraction step
+                if let Err(e) = regex::Regex::new(pattern) {
+                    return Err(ChainError::ValidationError(format!(
+                        "Invalid regex in step {}: {}",
+                        step_id, e
+                    )));
+                }
+            }
+            StepType::Agent { agent, input, .. } => {
+                // Validate agent step
+                if agent.model.is_empty() || input.is_empty() {
+                    return Err(ChainError::ValidationError(format!(
+                        "Agent model and input cannot be empty in step: {}",
+                        step_id
+                    )));
+                }
+                if agent.max_steps == 0 {
+                    return Err(ChainError::ValidationError(format!(
+                        "Agent max_steps must be greater than 0 in step: {}",
+                        step_id
+                    )));
+                }
+            }
+            StepType::MultiAgent {
+                conversation,
+                topic,
+            } => {
+                // Validate multi-agent conversation step
+                if topic.is_empty() {
+                    return Err(ChainError::ValidationError(format!(
+                        "Conversation topic cannot be empty in step: {}",
+                        step_id
+                    )));
+                }
+                if let Err(ChainError::ValidationError(message)) =
+                    validate_conversation(conversation)
+                {
+                    return Err(ChainError::ValidationError(format!(
+                        "{} in step: {}",
+                        message, step_id
+                    )));
+                }
+            }
             StepType::Custom { handler, .. } => {
                 // Validate custom step
                 if handler.is_empty() {
@@ -93,6 +162,59 @@ pub fn validate_chain(chain: &Chain) -> ChainResult<()> {
         }
     }
 
+    // Validate compensation steps
+    for (step_id, step) in &chain.steps {
+        let Some(compensation) = &step.compensation else {
+            continue;
+        };
+        if !chain.steps.contains_key(compensation) {
+            return Err(ChainError::ValidationError(format!(
+                "Compensation step not found: {}",
+                compensation
+            )));
+        }
+        if compensation == step_id {
+            return Err(ChainError::ValidationError(format!(
+                "Step {} cannot compensate itself",
+                step_id
+            )));
+        }
+        if chain.steps[compensation].compensation.is_some() {
+            return Err(ChainError::ValidationError(format!(
+                "Compensation step {} cannot have a compensation",
+                compensation
+            )));
+        }
+    }
+
+    // Validate input/output schemas
+    let mut schemas = vec![
+        ("input schema of the chain".to_string(), &chain.input_schema),
+        (
+            "output schema of the chain".to_string(),
+            &chain.output_schema,
+        ),
+    ];
+    for (step_id, step) in &chain.steps {
+        schemas.push((
+            format!("input schema of step {}", step_id),
+            &step.input_schema,
+        ));
+        schemas.push((
+            format!("output schema of step {}", step_id),
+            &step.output_schema,
+        ));
+    }
+    for (subject, schema) in schemas {
+        if let Some(schema) = schema {
+            check_schema(schema)
+                .map_err(|e| ChainError::ValidationError(format!("Invalid {}: {}", subject, e)))?;
+        }
+    }
+
+    // Validate references to step outputs, inputs and variables
+    check_references(chain)?;
+
     // Validate dependencies
     for dependency in &chain.dependencies {
         if !chain.steps.contains_key(&dependency.dependent_step) {
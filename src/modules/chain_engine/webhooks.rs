@@ -0,0 +1,399 @@
+//! Chain webhooks
+//!
+//! This module lets inbound webhooks trigger chains, after verifying their
+//! HMAC-SHA256 signature, and delivers execution lifecycle events to outbound
+//! webhooks with retries. Deliveries that still fail are added to the
+//! dead-letter queue for inspection and redelivery.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::Client;
+use ring::hmac;
+use tokio::sync::broadcast;
+use tracing::{debug, error, warn};
+use uuid::Uuid;
+
+use crate::config::{ChainWebhookConfig, OutboundWebhookConfig, WebhookTriggerConfig};
+use crate::modules::chain_engine::dead_letter::{DeadLetter, DeadLetterQueue, DeadLetterSource};
+use crate::modules::chain_engine::definition::Chain;
+use crate::modules::chain_engine::engine::{ChainEngine, ChainEvent};
+use crate::modules::chain_engine::error::{ChainError, ChainResult};
+use crate::modules::chain_engine::schema::{validate_chain_inputs, ChainSchemas};
+
+/// Header carrying the signature of outbound deliveries
+pub const SIGNATURE_HEADER: &str = "x-intellirouter-signature";
+
+/// Header carrying the event name of outbound deliveries
+pub const EVENT_HEADER: &str = "x-intellirouter-event";
+
+/// Header carrying the delivery ID of outbound deliveries
+pub const DELIVERY_HEADER: &str = "x-intellirouter-delivery";
+
+/// Sign a payload, producing a `sha256=<hex>` signature
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    format!("sha256={}", hex::encode(hmac::sign(&key, payload).as_ref()))
+}
+
+/// Verify a `sha256=<hex>` signature in constant time
+pub fn verify_signature(secret: &str, payload: &[u8], signature: &str) -> bool {
+    let Some(signature) = signature
+        .trim()
+        .strip_prefix("sha256=")
+        .and_then(|hex_digest| hex::decode(hex_digest).ok())
+    else {
+        return false;
+    };
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    hmac::verify(&key, payload, &signature).is_ok()
+}
+
+/// Why an inbound webhook was rejected
+#[derive(Debug, PartialEq, Eq)]
+pub enum WebhookRejection {
+    /// No trigger with the given ID
+    UnknownTrigger,
+    /// Missing or invalid signature
+    InvalidSignature,
+    /// Inputs not matching the chain's input schema
+    InvalidInputs(String),
+}
+
+/// Inbound webhooks triggering chains
+pub struct WebhookTriggers {
+    engine: Arc<ChainEngine>,
+    triggers: HashMap<String, (WebhookTriggerConfig, Chain)>,
+}
+
+impl WebhookTriggers {
+    /// Create an empty set of triggers
+    pub fn new(engine: Arc<ChainEngine>) -> Self {
+        Self {
+            engine,
+            triggers: HashMap::new(),
+        }
+    }
+
+    /// Create triggers from the configuration, loading each chain definition
+    pub fn from_config(engine: Arc<ChainEngine>, config: &ChainWebhookConfig) -> ChainResult<Self> {
+        config
+            .triggers
+            .iter()
+            .try_fold(Self::new(engine), |triggers, trigger| {
+                let json = std::fs::read_to_string(&trigger.chain_file)?;
+                triggers.with_trigger(trigger.clone(), serde_json::from_str(&json)?)
+            })
+    }
+
+    /// Add a trigger for a chain
+    pub fn with_trigger(mut self, config: WebhookTriggerConfig, chain: Chain) -> ChainResult<Self> {
+        if config.secret.is_empty() {
+            return Err(ChainError::ValidationError(format!(
+                "Webhook trigger {} has no secret",
+                config.id
+            )));
+        }
+        self.triggers.insert(config.id.clone(), (config, chain));
+        Ok(self)
+    }
+
+    /// Header carrying the signature for a trigger
+    pub fn signature_header(&self, trigger_id: &str) -> Option<&str> {
+        self.triggers
+            .get(trigger_id)
+            .map(|(config, _)| config.signature_header.as_str())
+    }
+
+    /// JSON schemas of a trigger's chain
+    pub fn schemas(&self, trigger_id: &str) -> Option<ChainSchemas> {
+        self.triggers
+            .get(trigger_id)
+            .map(|(_, chain)| ChainSchemas::new(chain))
+    }
+
+    /// Verify an inbound webhook and start its chain in the background
+    ///
+    /// A JSON object body becomes the chain inputs; any other body is passed
+    /// as the `payload` input. The inputs are checked against the chain's input
+    /// schema before the chain starts. Returns the execution ID.
+    pub fn trigger(
+        &self,
+        trigger_id: &str,
+        signature: Option<&str>,
+        body: &[u8],
+    ) -> Result<String, WebhookRejection> {
+        let (config, chain) = self
+            .triggers
+            .get(trigger_id)
+            .ok_or(WebhookRejection::UnknownTrigger)?;
+        if !signature.is_some_and(|s| verify_signature(&config.secret, body, s)) {
+            return Err(WebhookRejection::InvalidSignature);
+        }
+
+        let inputs = match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(serde_json::Value::Object(fields)) => fields.into_iter().collect(),
+            Ok(payload) => HashMap::from([("payload".to_string(), payload)]),
+            Err(_) => HashMap::from([(
+                "payload".to_string(),
+                serde_json::Value::String(String::from_utf8_lossy(body).into_owned()),
+            )]),
+        };
+        validate_chain_inputs(chain, &inputs)
+            .map_err(|e| WebhookRejection::InvalidInputs(e.to_string()))?;
+
+        let execution_id = Uuid::new_v4().to_string();
+        let engine = self.engine.clone();
+        let chain = chain.clone();
+        let id = execution_id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = engine.execute_chain_with_id(&id, &chain, inputs).await {
+                warn!("Webhook-triggered execution {} failed: {}", id, e);
+            }
+        });
+
+        Ok(execution_id)
+    }
+}
+
+/// Delivers execution lifecycle events to outbound webhooks
+pub struct WebhookDispatcher {
+    client: Client,
+    config: ChainWebhookConfig,
+    dead_letters: Arc<DeadLetterQueue>,
+}
+
+impl WebhookDispatcher {
+    /// Create a dispatcher, keeping failed deliveries in memory
+    pub fn new(config: ChainWebhookConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            dead_letters: Arc::new(DeadLetterQueue::in_memory(config.dead_letter_capacity)),
+            config,
+        }
+    }
+
+    /// Add failed deliveries to a shared dead-letter queue
+    pub fn with_dead_letters(mut self, queue: Arc<DeadLetterQueue>) -> Self {
+        self.dead_letters = queue;
+        self
+    }
+
+    /// Deliver the engine's lifecycle events until the engine is dropped
+    pub fn start(self: &Arc<Self>, engine: &ChainEngine) -> tokio::task::JoinHandle<()> {
+        let dispatcher = self.clone();
+        let mut events = engine.subscribe_events();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        for endpoint in dispatcher.endpoints_for(&event) {
+                            let dispatcher = dispatcher.clone();
+                            let event = event.clone();
+                            tokio::spawn(async move {
+                                dispatcher.deliver(&endpoint, &event).await;
+                            });
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        error!("Webhook dispatcher lagged, dropped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Endpoints subscribed to an event
+    fn endpoints_for(&self, event: &ChainEvent) -> Vec<OutboundWebhookConfig> {
+        self.config
+            .outbound
+            .iter()
+            .filter(|e| e.events.is_empty() || e.events.contains(&event.status))
+            .filter(|e| e.chain_ids.is_empty() || e.chain_ids.contains(&event.chain_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Deliver an event, retrying with exponential backoff
+    ///
+    /// Returns whether the delivery succeeded; failed deliveries are
+    /// dead-lettered.
+    pub async fn deliver(&self, endpoint: &OutboundWebhookConfig, event: &ChainEvent) -> bool {
+        let delivery_id = Uuid::new_v4().to_string();
+        let payload = match serde_json::to_vec(event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize chain event: {}", e);
+                return false;
+            }
+        };
+
+        let mut backoff = Duration::from_millis(self.config.retry_backoff_ms);
+        let mut last_error = String::new();
+        let attempts = self.config.max_retries + 1;
+        for attempt in 1..=attempts {
+            match self.send(endpoint, event, &delivery_id, &payload).await {
+                Ok(()) => return true,
+                Err(e) => {
+                    debug!(
+                        "Webhook delivery {} to {} failed (attempt {}/{}): {}",
+                        delivery_id, endpoint.url, attempt, attempts, e
+                    );
+                    last_error = e;
+                }
+            }
+            if attempt < attempts {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+
+        warn!(
+            "Webhook delivery {} to {} dead-lettered: {}",
+            delivery_id, endpoint.url, last_error
+        );
+        self.dead_letters
+            .push(DeadLetter {
+                id: delivery_id,
+                source: DeadLetterSource::Webhook {
+                    url: endpoint.url.clone(),
+                    event: event.clone(),
+                },
+                attempts,
+                last_error,
+                failed_at: Utc::now(),
+            })
+            .await;
+        false
+    }
+
+    /// Send a single delivery attempt
+    async fn send(
+        &self,
+        endpoint: &OutboundWebhookConfig,
+        event: &ChainEvent,
+        delivery_id: &str,
+        payload: &[u8],
+    ) -> Result<(), String> {
+        let mut request = self
+            .client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.name())
+            .header(DELIVERY_HEADER, delivery_id)
+            .body(payload.to_vec());
+        if let Some(secret) = &endpoint.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, payload));
+        }
+
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("endpoint returned {}", response.status()))
+        }
+    }
+
+    /// Failed deliveries, oldest first
+    pub async fn dead_letters(&self) -> ChainResult<Vec<DeadLetter>> {
+        self.dead_letters.list(Some("webhook")).await
+    }
+
+    /// Retry a dead-lettered delivery
+    ///
+    /// Returns `None` if there is no such delivery, otherwise whether the
+    /// redelivery succeeded. A failed redelivery is dead-lettered again.
+    pub async fn redeliver(&self, id: &str) -> ChainResult<Option<bool>> {
+        match self.dead_letters.get(id).await? {
+            Some(dead_letter) if dead_letter.kind() == "webhook" => {}
+            _ => return Ok(None),
+        }
+        let Some(dead_letter) = self.dead_letters.take(id).await? else {
+            return Ok(None);
+        };
+        Ok(self.redeliver_dead_letter(&dead_letter).await)
+    }
+
+    /// Deliver the event of a dead letter taken off the queue
+    ///
+    /// Returns `None` if it is not a webhook delivery.
+    pub async fn redeliver_dead_letter(&self, dead_letter: &DeadLetter) -> Option<bool> {
+        let DeadLetterSource::Webhook { url, event } = &dead_letter.source else {
+            return None;
+        };
+        let endpoint = self
+            .config
+            .outbound
+            .iter()
+            .find(|e| &e.url == url)
+            .cloned()
+            .unwrap_or(OutboundWebhookConfig {
+                url: url.clone(),
+                secret: None,
+                events: Vec::new(),
+                chain_ids: Vec::new(),
+            });
+        Some(self.deliver(&endpoint, event).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::chain_engine::checkpoint::ExecutionStatus;
+
+    #[test]
+    fn test_signature_verification() {
+        let signature = sign("secret", b"{\"a\":1}");
+        assert!(signature.starts_with("sha256="));
+        assert!(verify_signature("secret", b"{\"a\":1}", &signature));
+        assert!(!verify_signature("other", b"{\"a\":1}", &signature));
+        assert!(!verify_signature("secret", b"{\"a\":2}", &signature));
+        assert!(!verify_signature("secret", b"{\"a\":1}", "sha256=zz"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_dead_lettered() {
+        let endpoint = OutboundWebhookConfig {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            secret: Some("secret".to_string()),
+            events: vec![ExecutionStatus::Failed],
+            chain_ids: Vec::new(),
+        };
+        let dispatcher = WebhookDispatcher::new(ChainWebhookConfig {
+            outbound: vec![endpoint.clone()],
+            max_retries: 1,
+            retry_backoff_ms: 1,
+            ..Default::default()
+        });
+        let event = ChainEvent {
+            execution_id: "exec".to_string(),
+            chain_id: "chain".to_string(),
+            status: ExecutionStatus::Failed,
+            outputs: HashMap::new(),
+            error: Some("boom".to_string()),
+            timestamp: Utc::now(),
+        };
+
+        assert_eq!(event.name(), "chain.failed");
+        assert_eq!(dispatcher.endpoints_for(&event).len(), 1);
+        assert!(!dispatcher.deliver(&endpoint, &event).await);
+
+        let dead_letters = dispatcher.dead_letters().await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].attempts, 2);
+        assert_eq!(
+            dispatcher.redeliver(&dead_letters[0].id).await.unwrap(),
+            Some(false)
+        );
+        assert_eq!(dispatcher.dead_letters().await.unwrap().len(), 1);
+        assert_eq!(dispatcher.redeliver("missing").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,446 @@
+//! HTTP Compression
+//!
+//! The roles' servers decompress request bodies sent with a `gzip` or `zstd`
+//! `Content-Encoding`, and compress responses for clients listing one of
+//! these codings in `Accept-Encoding`, as configured under
+//! `[server.compression]`. Only responses of a known size above the
+//! configured threshold are compressed: streamed responses, such as
+//! server-sent events, are passed through so that chunks are not held back.
+//!
+//! Roles calling other roles compress their request bodies the same way with
+//! [`RequestCompression`], as prompts augmented by RAG can get large.
+
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use axum::body::{self, Body, HttpBody as _};
+use axum::extract::{Request, State};
+use axum::http::header::{
+    HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, VARY,
+};
+use axum::http::StatusCode;
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use metrics::counter;
+use serde::Serialize;
+use thiserror::Error;
+use tracing::warn;
+
+use crate::config::{CompressionAlgorithm, CompressionConfig};
+use crate::modules::telemetry::catalog;
+
+/// Content types never compressed: streamed, or compressed already
+const UNCOMPRESSED_CONTENT_TYPES: &[&str] = &[
+    "text/event-stream",
+    "application/grpc",
+    "image/",
+    "audio/",
+    "video/",
+];
+
+impl CompressionAlgorithm {
+    /// Name of the coding in `Content-Encoding` and `Accept-Encoding`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// Coding of a `Content-Encoding` value, if supported
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Self::Gzip),
+            "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Error decompressing a body
+#[derive(Debug, Error)]
+pub enum CompressionError {
+    /// The body decompresses to more than the allowed size
+    #[error("Decompressed body exceeds {0} bytes")]
+    TooLarge(usize),
+
+    /// The body is not validly compressed
+    #[error("Invalid compressed body: {0}")]
+    Invalid(#[from] io::Error),
+}
+
+/// Compress data with a coding, at its default level
+pub fn compress(algorithm: CompressionAlgorithm, data: &[u8]) -> io::Result<Vec<u8>> {
+    match algorithm {
+        CompressionAlgorithm::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        CompressionAlgorithm::Zstd => zstd::encode_all(data, 0),
+    }
+}
+
+/// Decompress data, failing if it decompresses to more than `limit` bytes
+pub fn decompress(
+    algorithm: CompressionAlgorithm,
+    data: &[u8],
+    limit: usize,
+) -> Result<Vec<u8>, CompressionError> {
+    let decoder: Box<dyn Read + '_> = match algorithm {
+        CompressionAlgorithm::Gzip => Box::new(GzDecoder::new(data)),
+        CompressionAlgorithm::Zstd => Box::new(zstd::Decoder::new(data)?),
+    };
+    let mut decompressed = Vec::new();
+    decoder
+        .take((limit as u64).saturating_add(1))
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() > limit {
+        return Err(CompressionError::TooLarge(limit));
+    }
+    Ok(decompressed)
+}
+
+/// Coding to respond with, among those offered in order of preference, for
+/// an `Accept-Encoding` header: the one with the highest quality value
+pub fn negotiate(
+    accept_encoding: &str,
+    offered: &[CompressionAlgorithm],
+) -> Option<CompressionAlgorithm> {
+    let accepted: Vec<(&str, f32)> = accept_encoding
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let coding = parts.next()?.trim();
+            if coding.is_empty() {
+                return None;
+            }
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse().ok())
+                .unwrap_or(1.0);
+            Some((coding, quality))
+        })
+        .collect();
+    let quality = |algorithm: &CompressionAlgorithm| {
+        let find = |name: &str| {
+            accepted
+                .iter()
+                .find(|(coding, _)| coding.eq_ignore_ascii_case(name))
+                .map(|(_, quality)| *quality)
+        };
+        find(algorithm.name()).or_else(|| find("*")).unwrap_or(0.0)
+    };
+
+    let mut best: Option<(CompressionAlgorithm, f32)> = None;
+    for algorithm in offered {
+        let q = quality(algorithm);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((*algorithm, q));
+        }
+    }
+    best.map(|(algorithm, _)| algorithm)
+}
+
+/// Count the bytes a body had before compression and those compression saved
+fn record(direction: &'static str, algorithm: CompressionAlgorithm, raw: usize, compressed: usize) {
+    counter!(
+        catalog::COMPRESSION_RAW_BYTES,
+        raw as u64,
+        "direction" => direction,
+        "algorithm" => algorithm.name()
+    );
+    counter!(
+        catalog::COMPRESSION_SAVED_BYTES,
+        raw.saturating_sub(compressed) as u64,
+        "direction" => direction,
+        "algorithm" => algorithm.name()
+    );
+}
+
+/// Add compression of request and response bodies to a role's app, if enabled
+pub fn with_compression(app: Router, config: &CompressionConfig) -> Router {
+    if !config.enabled {
+        return app;
+    }
+    app.layer(from_fn_with_state(
+        Arc::new(config.clone()),
+        compression_middleware,
+    ))
+}
+
+/// Middleware decompressing requests and compressing responses
+pub async fn compression_middleware(
+    State(config): State<Arc<CompressionConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let algorithm = request
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|accept| negotiate(accept, &config.algorithms));
+
+    let request = match decompress_request(&config, request).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let response = next.run(request).await;
+    match algorithm {
+        Some(algorithm) => compress_response(&config, algorithm, response).await,
+        None => response,
+    }
+}
+
+/// Replace a compressed request body with its decompressed content
+async fn decompress_request(
+    config: &CompressionConfig,
+    request: Request,
+) -> Result<Request, Response> {
+    let Some(encoding) = request.headers().get(CONTENT_ENCODING) else {
+        return Ok(request);
+    };
+    let encoding = encoding.to_str().unwrap_or_default().trim();
+    if encoding.is_empty() || encoding.eq_ignore_ascii_case("identity") {
+        return Ok(request);
+    }
+    let Some(algorithm) = CompressionAlgorithm::from_name(encoding)
+        .filter(|algorithm| config.algorithms.contains(algorithm))
+    else {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported Content-Encoding '{}'", encoding),
+        )
+            .into_response());
+    };
+
+    let (mut parts, body) = request.into_parts();
+    let too_large = || {
+        (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!(
+                "Request body exceeds {} bytes",
+                config.max_decompressed_bytes
+            ),
+        )
+            .into_response()
+    };
+    let compressed = body::to_bytes(body, config.max_decompressed_bytes)
+        .await
+        .map_err(|_| too_large())?;
+    let decompressed = match decompress(algorithm, &compressed, config.max_decompressed_bytes) {
+        Ok(decompressed) => decompressed,
+        Err(CompressionError::TooLarge(_)) => return Err(too_large()),
+        Err(e) => return Err((StatusCode::BAD_REQUEST, e.to_string()).into_response()),
+    };
+    record("request", algorithm, decompressed.len(), compressed.len());
+
+    parts.headers.remove(CONTENT_ENCODING);
+    parts.headers.remove(CONTENT_LENGTH);
+    Ok(Request::from_parts(parts, Body::from(decompressed)))
+}
+
+/// Compress a response of a known size above the threshold
+async fn compress_response(
+    config: &CompressionConfig,
+    algorithm: CompressionAlgorithm,
+    response: Response,
+) -> Response {
+    if response.headers().contains_key(CONTENT_ENCODING) {
+        return response;
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if UNCOMPRESSED_CONTENT_TYPES
+        .iter()
+        .any(|prefix| content_type.starts_with(prefix))
+    {
+        return response;
+    }
+    // Streamed bodies have no exact size
+    match response.body().size_hint().exact() {
+        Some(size) if size >= config.min_size_bytes as u64 => {}
+        _ => return response,
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let raw = match body::to_bytes(body, usize::MAX).await {
+        Ok(raw) => raw,
+        Err(e) => {
+            warn!("Failed to read response body to compress: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to read response body",
+            )
+                .into_response();
+        }
+    };
+    let compressed = match compress(algorithm, &raw) {
+        Ok(compressed) if compressed.len() < raw.len() => compressed,
+        Ok(_) => return Response::from_parts(parts, Body::from(raw)),
+        Err(e) => {
+            warn!(
+                "Failed to compress response with {}: {}",
+                algorithm.name(),
+                e
+            );
+            return Response::from_parts(parts, Body::from(raw));
+        }
+    };
+    record("response", algorithm, raw.len(), compressed.len());
+
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_ENCODING, HeaderValue::from_static(algorithm.name()));
+    parts
+        .headers
+        .append(VARY, HeaderValue::from_static("accept-encoding"));
+    Response::from_parts(parts, Body::from(compressed))
+}
+
+/// Compression of the JSON bodies a role sends to other roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestCompression {
+    /// Coding of the bodies
+    pub algorithm: CompressionAlgorithm,
+    /// Bodies smaller than this are sent uncompressed
+    pub min_size_bytes: usize,
+}
+
+impl RequestCompression {
+    /// Outbound compression of the configuration, if enabled
+    pub fn from_config(config: &CompressionConfig) -> Option<Self> {
+        config.outbound.map(|algorithm| Self {
+            algorithm,
+            min_size_bytes: config.min_size_bytes,
+        })
+    }
+
+    /// Set a request's body to JSON, compressed if large enough
+    pub fn json<T: Serialize + ?Sized>(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: &T,
+    ) -> reqwest::RequestBuilder {
+        let Ok(raw) = serde_json::to_vec(body) else {
+            // Let reqwest report the serialization error when sending
+            return request.json(body);
+        };
+        let request = request.header(reqwest::header::CONTENT_TYPE, "application/json");
+        if raw.len() < self.min_size_bytes {
+            return request.body(raw);
+        }
+        match compress(self.algorithm, &raw) {
+            Ok(compressed) if compressed.len() < raw.len() => {
+                record("outbound", self.algorithm, raw.len(), compressed.len());
+                request
+                    .header(reqwest::header::CONTENT_ENCODING, self.algorithm.name())
+                    .body(compressed)
+            }
+            _ => request.body(raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_negotiate() {
+        let offered = [CompressionAlgorithm::Zstd, CompressionAlgorithm::Gzip];
+        assert_eq!(
+            negotiate("gzip, deflate, br", &offered),
+            Some(CompressionAlgorithm::Gzip)
+        );
+        assert_eq!(
+            negotiate("gzip, zstd", &offered),
+            Some(CompressionAlgorithm::Zstd)
+        );
+        assert_eq!(
+            negotiate("zstd;q=0.5, gzip;q=0.8", &offered),
+            Some(CompressionAlgorithm::Gzip)
+        );
+        assert_eq!(
+            negotiate("*;q=0.1, zstd;q=0", &offered),
+            Some(CompressionAlgorithm::Gzip)
+        );
+        assert_eq!(negotiate("identity", &offered), None);
+        assert_eq!(negotiate("gzip", &[CompressionAlgorithm::Zstd]), None);
+    }
+
+    #[test]
+    fn test_decompress_limit() {
+        let data = vec![b'a'; 4096];
+        for algorithm in [CompressionAlgorithm::Gzip, CompressionAlgorithm::Zstd] {
+            let compressed = compress(algorithm, &data).unwrap();
+            assert!(compressed.len() < data.len());
+            assert_eq!(decompress(algorithm, &compressed, 4096).unwrap(), data);
+            assert!(matches!(
+                decompress(algorithm, &compressed, 4095),
+                Err(CompressionError::TooLarge(4095))
+            ));
+        }
+        assert!(matches!(
+            decompress(CompressionAlgorithm::Gzip, b"not gzip", 4096),
+            Err(CompressionError::Invalid(_))
+        ));
+    }
+
+    fn echo_app(config: &CompressionConfig) -> Router {
+        with_compression(
+            Router::new().route("/echo", post(|body: String| async move { body })),
+            config,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_middleware_round_trip() {
+        let config = CompressionConfig::default();
+        let prompt = "Retrieved context. ".repeat(200);
+        let request = Request::post("/echo")
+            .header(CONTENT_ENCODING, "gzip")
+            .header(ACCEPT_ENCODING, "gzip, zstd")
+            .body(Body::from(
+                compress(CompressionAlgorithm::Gzip, prompt.as_bytes()).unwrap(),
+            ))
+            .unwrap();
+
+        let response = echo_app(&config).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_ENCODING], "zstd");
+        assert_eq!(response.headers()[VARY], "accept-encoding");
+        let body = body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = decompress(CompressionAlgorithm::Zstd, &body, usize::MAX).unwrap();
+        assert_eq!(body, prompt.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_skips_small_and_unsupported() {
+        let config = CompressionConfig::default();
+        let request = Request::post("/echo")
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(Body::from("short"))
+            .unwrap();
+        let response = echo_app(&config).oneshot(request).await.unwrap();
+        assert!(!response.headers().contains_key(CONTENT_ENCODING));
+
+        let request = Request::post("/echo")
+            .header(CONTENT_ENCODING, "br")
+            .body(Body::from("short"))
+            .unwrap();
+        let response = echo_app(&config).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+}
@@ -0,0 +1,317 @@
+//! Request Limits
+//!
+//! The roles' servers reject request bodies larger than the configured size
+//! with 413 and bodies not received within the configured time with 408, as
+//! configured under `[server.limits]`, so that a single huge or trickled
+//! payload cannot exhaust memory or hold a connection. Routes under given
+//! path prefixes can have limits of their own, such as a larger size for
+//! audio uploads.
+//!
+//! Headers sent too slowly are cut off by the role's listener, with the
+//! header read timeout set by [`RoleListener::serve`](super::RoleListener::serve).
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::body::{Body, Bytes, HttpBody as _};
+use axum::extract::{DefaultBodyLimit, Request, State};
+use axum::http::header::{CONNECTION, CONTENT_LENGTH};
+use axum::http::{HeaderValue, StatusCode};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use futures::StreamExt;
+use metrics::counter;
+use serde_json::json;
+use tracing::debug;
+
+use crate::config::RequestLimitsConfig;
+use crate::modules::telemetry::catalog;
+
+/// Limits applying to a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteLimits {
+    /// Path prefix of the route's limits, `None` for the defaults
+    pub path_prefix: Option<String>,
+    pub max_body_bytes: usize,
+    pub body_read_timeout: Duration,
+}
+
+impl RouteLimits {
+    /// Label of the limits in metrics
+    fn label(&self) -> String {
+        self.path_prefix
+            .clone()
+            .unwrap_or_else(|| "default".to_string())
+    }
+}
+
+/// Limits of a role's requests, by path
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    default: RouteLimits,
+    /// Route limits, longest prefix first
+    routes: Vec<RouteLimits>,
+}
+
+impl RequestLimits {
+    /// Limits of the configuration
+    pub fn from_config(config: &RequestLimitsConfig) -> Self {
+        let default = RouteLimits {
+            path_prefix: None,
+            max_body_bytes: config.max_body_bytes,
+            body_read_timeout: Duration::from_secs(config.body_read_timeout_secs),
+        };
+        let mut routes: Vec<RouteLimits> = config
+            .routes
+            .iter()
+            .map(|route| RouteLimits {
+                path_prefix: Some(route.path_prefix.clone()),
+                max_body_bytes: route.max_body_bytes.unwrap_or(default.max_body_bytes),
+                body_read_timeout: route
+                    .body_read_timeout_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(default.body_read_timeout),
+            })
+            .collect();
+        routes.sort_by_key(|route| {
+            std::cmp::Reverse(route.path_prefix.as_ref().map_or(0, String::len))
+        });
+        Self { default, routes }
+    }
+
+    /// Limits applying to a path: those of the longest matching prefix, or
+    /// the defaults
+    pub fn for_path(&self, path: &str) -> &RouteLimits {
+        self.routes
+            .iter()
+            .find(|route| {
+                route
+                    .path_prefix
+                    .as_deref()
+                    .is_some_and(|prefix| path.starts_with(prefix))
+            })
+            .unwrap_or(&self.default)
+    }
+}
+
+/// Add request body limits to a role's app
+///
+/// The limits replace axum's default body limit, so that extractors accept
+/// bodies up to the configured size.
+pub fn with_request_limits(app: Router, config: &RequestLimitsConfig) -> Router {
+    app.layer(from_fn_with_state(
+        Arc::new(RequestLimits::from_config(config)),
+        limits_middleware,
+    ))
+    .layer(DefaultBodyLimit::disable())
+}
+
+/// Middleware reading request bodies within their route's limits
+pub async fn limits_middleware(
+    State(limits): State<Arc<RequestLimits>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = limits.for_path(request.uri().path());
+
+    // Reject declared oversized bodies before reading them
+    let declared = request
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|len| len > route.max_body_bytes as u64) {
+        return too_large(route);
+    }
+    if request.body().size_hint().exact() == Some(0) {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match tokio::time::timeout(
+        route.body_read_timeout,
+        read_body(body, route.max_body_bytes),
+    )
+    .await
+    {
+        Ok(Ok(body)) => body,
+        Ok(Err(BodyError::TooLarge)) => return too_large(route),
+        Ok(Err(BodyError::Read(e))) => {
+            debug!("Failed to read request body: {}", e);
+            return error_response(
+                StatusCode::BAD_REQUEST,
+                "Failed to read the request body".to_string(),
+                "invalid_request_body",
+            );
+        }
+        Err(_) => {
+            record_rejection(route, "body_timeout");
+            let mut response = error_response(
+                StatusCode::REQUEST_TIMEOUT,
+                format!(
+                    "Request body not received within {}s",
+                    route.body_read_timeout.as_secs()
+                ),
+                "request_timeout",
+            );
+            close_connection(&mut response);
+            return response;
+        }
+    };
+    next.run(Request::from_parts(parts, Body::from(body))).await
+}
+
+/// Error reading a request body
+enum BodyError {
+    TooLarge,
+    Read(axum::Error),
+}
+
+/// Read a body, failing as soon as it exceeds `limit` bytes
+async fn read_body(body: Body, limit: usize) -> Result<Bytes, BodyError> {
+    let mut stream = body.into_data_stream();
+    let mut buffer = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(BodyError::Read)?;
+        if buffer.len() + chunk.len() > limit {
+            return Err(BodyError::TooLarge);
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(buffer))
+}
+
+fn too_large(route: &RouteLimits) -> Response {
+    record_rejection(route, "body_too_large");
+    let mut response = error_response(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        format!("Request body exceeds {} bytes", route.max_body_bytes),
+        "request_too_large",
+    );
+    close_connection(&mut response);
+    response
+}
+
+/// Close the connection after a response sent before the body was read, so
+/// that the rest of the body is not taken for the next request
+fn close_connection(response: &mut Response) {
+    response
+        .headers_mut()
+        .insert(CONNECTION, HeaderValue::from_static("close"));
+}
+
+fn record_rejection(route: &RouteLimits, reason: &'static str) {
+    counter!(
+        catalog::REQUEST_LIMIT_REJECTIONS,
+        1,
+        "route" => route.label(),
+        "reason" => reason
+    );
+}
+
+/// Build an error response
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "invalid_request_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RouteLimitsConfig;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    fn config() -> RequestLimitsConfig {
+        RequestLimitsConfig {
+            max_body_bytes: 16,
+            body_read_timeout_secs: 1,
+            routes: vec![
+                RouteLimitsConfig {
+                    path_prefix: "/v1/audio".to_string(),
+                    max_body_bytes: Some(64),
+                    body_read_timeout_secs: None,
+                },
+                RouteLimitsConfig {
+                    path_prefix: "/v1/audio/speech".to_string(),
+                    max_body_bytes: Some(8),
+                    body_read_timeout_secs: Some(5),
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_route_limits() {
+        let limits = RequestLimits::from_config(&config());
+        assert_eq!(limits.for_path("/v1/chat/completions").max_body_bytes, 16);
+        assert_eq!(
+            limits.for_path("/v1/audio/transcriptions").max_body_bytes,
+            64
+        );
+        assert_eq!(
+            limits
+                .for_path("/v1/audio/transcriptions")
+                .body_read_timeout,
+            Duration::from_secs(1)
+        );
+        let speech = limits.for_path("/v1/audio/speech");
+        assert_eq!(speech.max_body_bytes, 8);
+        assert_eq!(speech.body_read_timeout, Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_middleware_limits() {
+        let app = with_request_limits(
+            Router::new()
+                .route("/echo", post(|body: String| async move { body }))
+                .route("/v1/audio/upload", post(|body: Bytes| async move { body })),
+            &config(),
+        );
+        let send = |path: &str, body: Vec<u8>| {
+            app.clone()
+                .oneshot(Request::post(path).body(Body::from(body)).unwrap())
+        };
+
+        let response = send("/echo", b"small".to_vec()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = send("/echo", vec![b'a'; 17]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.headers()[CONNECTION], "close");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "request_too_large");
+
+        let response = send("/v1/audio/upload", vec![b'a'; 64]).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_slow_body_times_out() {
+        let app = with_request_limits(
+            Router::new().route("/echo", post(|body: String| async move { body })),
+            &config(),
+        );
+        // A body whose second chunk never arrives
+        let chunks = futures::stream::iter([Ok::<_, std::io::Error>(Bytes::from("a"))])
+            .chain(futures::stream::pending());
+        let request = Request::post("/echo")
+            .body(Body::from_stream(chunks))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+}
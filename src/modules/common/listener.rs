@@ -0,0 +1,401 @@
+//! Role Listeners
+//!
+//! Each role's server listens on a TCP port by default. For sidecar
+//! deployments, where managing ports is painful, a role can listen on a Unix
+//! domain socket instead, or take the socket systemd passes it through socket
+//! activation (`LISTEN_FDS`), as configured under `[server.listeners]`.
+
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, IntoRawFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::Path;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::pin;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request};
+use axum::serve::Listener;
+use axum::Router;
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tower::ServiceExt;
+use tracing::debug;
+#[cfg(unix)]
+use tracing::warn;
+
+use crate::config::ListenerConfig;
+
+/// First file descriptor systemd passes to activated services
+#[cfg(unix)]
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Bind a Unix domain socket with `mode` in a directory only the process can
+/// enter, then move it to `path`
+#[cfg(unix)]
+fn bind_unix_private(path: &Path, mode: u32) -> io::Result<UnixListener> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is not a socket path", path.display()),
+        )
+    })?;
+    let mut private = name.to_os_string();
+    private.push(format!(".{}.tmp", std::process::id()));
+    let dir = path.with_file_name(private);
+    std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+
+    let staged = dir.join(name);
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(mode))?;
+        std::fs::rename(&staged, path)?;
+        Ok(listener)
+    });
+    if bound.is_err() {
+        let _ = std::fs::remove_file(&staged);
+    }
+    let _ = std::fs::remove_dir(&dir);
+    bound
+}
+
+/// Bound socket of a role's server
+#[derive(Debug)]
+pub enum RoleListener {
+    /// TCP socket
+    Tcp(TcpListener),
+    /// Unix domain socket, with the path the role bound and removes on
+    /// shutdown; `None` for activated sockets, which systemd owns
+    #[cfg(unix)]
+    Unix {
+        listener: UnixListener,
+        path: Option<PathBuf>,
+    },
+}
+
+impl RoleListener {
+    /// Bind the socket configured for a role, or its TCP address when none is
+    pub async fn bind(config: Option<&ListenerConfig>, addr: SocketAddr) -> io::Result<Self> {
+        let Some(config) = config else {
+            return Ok(Self::Tcp(TcpListener::bind(addr).await?));
+        };
+        if config.socket_activation {
+            return Self::activated(config.socket_name.as_deref());
+        }
+        match &config.unix_socket {
+            Some(path) => Self::bind_unix(Path::new(path), config.socket_mode),
+            None => Ok(Self::Tcp(TcpListener::bind(addr).await?)),
+        }
+    }
+
+    /// Bind a Unix domain socket, replacing a stale socket file left behind
+    ///
+    /// Any other file at the path is left alone and fails the bind. With a
+    /// mode, the socket is bound in a private directory next to the path and
+    /// moved into place once its permissions are set, so it is never
+    /// reachable with the permissions of the umask.
+    #[cfg(unix)]
+    fn bind_unix(path: &Path, mode: Option<u32>) -> io::Result<Self> {
+        match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        let listener = match mode {
+            Some(mode) => bind_unix_private(path, mode)?,
+            None => UnixListener::bind(path)?,
+        };
+        Ok(Self::Unix {
+            listener,
+            path: Some(path.to_path_buf()),
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn bind_unix(_path: &Path, _mode: Option<u32>) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix domain sockets are not supported on this platform",
+        ))
+    }
+
+    /// Take the socket systemd passed to the process
+    #[cfg(unix)]
+    fn activated(name: Option<&str>) -> io::Result<Self> {
+        let fd = activated_fd(
+            std::env::var("LISTEN_PID").ok().as_deref(),
+            std::env::var("LISTEN_FDS").ok().as_deref(),
+            std::env::var("LISTEN_FDNAMES").ok().as_deref(),
+            std::process::id(),
+            name,
+        )
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                match name {
+                    Some(name) => format!("No socket named '{}' passed by systemd", name),
+                    None => "No socket passed by systemd".to_string(),
+                },
+            )
+        })?;
+
+        // SAFETY: systemd hands the process ownership of the descriptors it
+        // passes, and each one is taken once, by the role it is named for
+        let unix = unsafe { std::os::unix::net::UnixListener::from_raw_fd(fd) };
+        if unix.local_addr().is_ok() {
+            unix.set_nonblocking(true)?;
+            return Ok(Self::Unix {
+                listener: UnixListener::from_std(unix)?,
+                path: None,
+            });
+        }
+        // Not a Unix socket: hand the descriptor over without closing it
+        let fd = unix.into_raw_fd();
+        let tcp = unsafe { std::net::TcpListener::from_raw_fd(fd) };
+        tcp.set_nonblocking(true)?;
+        Ok(Self::Tcp(TcpListener::from_std(tcp)?))
+    }
+
+    #[cfg(not(unix))]
+    fn activated(_name: Option<&str>) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Socket activation is not supported on this platform",
+        ))
+    }
+
+    /// Serve an app until the shutdown future completes, removing the Unix
+    /// socket the role bound afterwards
+    ///
+    /// HTTP/1 connections whose client does not send a request's headers
+    /// within `header_read_timeout` are closed, so that slow clients cannot
+    /// hold connections open.
+    pub async fn serve<F>(
+        self,
+        app: Router,
+        header_read_timeout: Duration,
+        shutdown: F,
+    ) -> io::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        match self {
+            Self::Tcp(listener) => {
+                serve_connections(listener, app, header_read_timeout, shutdown).await;
+                Ok(())
+            }
+            #[cfg(unix)]
+            Self::Unix { listener, path } => {
+                serve_connections(listener, app, header_read_timeout, shutdown).await;
+                if let Some(path) = path {
+                    if let Err(e) = std::fs::remove_file(&path) {
+                        warn!("Failed to remove socket {}: {}", path.display(), e);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Accept connections until the shutdown future completes, then wait for
+/// those open to finish their requests
+///
+/// This is `axum::serve` with a header read timeout, which it does not
+/// configure. Requests carry the peer address of their connection as
+/// [`ConnectInfo`].
+async fn serve_connections<L, F>(
+    mut listener: L,
+    app: Router,
+    header_read_timeout: Duration,
+    shutdown: F,
+) where
+    L: Listener,
+    L::Addr: Clone + Send + Sync + 'static,
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut builder = Builder::new(TokioExecutor::new());
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .header_read_timeout(header_read_timeout);
+    // CONNECT protocol needed for HTTP/2 websockets
+    builder.http2().enable_connect_protocol();
+
+    let graceful = GracefulShutdown::new();
+    let mut shutdown = pin!(shutdown);
+    loop {
+        let (io, peer) = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = &mut shutdown => break,
+        };
+        let service =
+            TowerToHyperService::new(app.clone().map_request(move |request: Request<Incoming>| {
+                let mut request = request.map(Body::new);
+                request.extensions_mut().insert(ConnectInfo(peer.clone()));
+                request
+            }));
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(io), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                debug!("Failed to serve connection: {}", e);
+            }
+        });
+    }
+    graceful.shutdown().await;
+}
+
+impl fmt::Display for RoleListener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "{}", addr),
+                Err(_) => write!(f, "TCP socket"),
+            },
+            #[cfg(unix)]
+            Self::Unix {
+                path: Some(path), ..
+            } => write!(f, "unix:{}", path.display()),
+            #[cfg(unix)]
+            Self::Unix { listener, .. } => match listener
+                .local_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(Path::to_path_buf))
+            {
+                Some(path) => write!(f, "unix:{} (activated)", path.display()),
+                None => write!(f, "activated Unix socket"),
+            },
+        }
+    }
+}
+
+/// Descriptor of the socket systemd passed to a process, following the
+/// `sd_listen_fds` protocol: the variables must be meant for this process,
+/// and names, when asked for, pick among the passed descriptors
+#[cfg(unix)]
+fn activated_fd(
+    listen_pid: Option<&str>,
+    listen_fds: Option<&str>,
+    fd_names: Option<&str>,
+    pid: u32,
+    name: Option<&str>,
+) -> Option<RawFd> {
+    if listen_pid?.parse::<u32>().ok()? != pid {
+        return None;
+    }
+    let count: RawFd = listen_fds?.parse().ok().filter(|count| *count > 0)?;
+    let index = match name {
+        Some(name) => fd_names?.split(':').position(|fd_name| fd_name == name)? as RawFd,
+        None => 0,
+    };
+    (index < count).then_some(LISTEN_FDS_START + index)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activated_fd() {
+        assert_eq!(activated_fd(Some("42"), Some("1"), None, 42, None), Some(3));
+        // Variables meant for another process
+        assert_eq!(activated_fd(Some("7"), Some("1"), None, 42, None), None);
+        assert_eq!(activated_fd(None, None, None, 42, None), None);
+
+        let names = Some("metrics:router");
+        assert_eq!(
+            activated_fd(Some("42"), Some("2"), names, 42, Some("router")),
+            Some(4)
+        );
+        assert_eq!(
+            activated_fd(Some("42"), Some("2"), names, 42, Some("audit")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_replaces_stale_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("router.sock");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let config = ListenerConfig {
+            unix_socket: Some(path.to_string_lossy().into_owned()),
+            socket_mode: Some(0o660),
+            ..ListenerConfig::default()
+        };
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let listener = RoleListener::bind(Some(&config), addr).await.unwrap();
+        assert_eq!(listener.to_string(), format!("unix:{}", path.display()));
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+        // The private directory the socket was bound in is gone
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        std::os::unix::net::UnixStream::connect(&path).unwrap();
+
+        let app = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let server = tokio::spawn(listener.serve(app, Duration::from_secs(10), async {}));
+        server.await.unwrap().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_keeps_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("router.sock");
+        std::fs::write(&path, b"not a socket").unwrap();
+
+        let config = ListenerConfig {
+            unix_socket: Some(path.to_string_lossy().into_owned()),
+            ..ListenerConfig::default()
+        };
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let err = RoleListener::bind(Some(&config), addr).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read(&path).unwrap(), b"not a socket");
+    }
+
+    #[tokio::test]
+    async fn test_slow_headers_close_connection() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let addr = SocketAddr::from(([127, 0, 0, 1], 0));
+        let listener = RoleListener::bind(None, addr).await.unwrap();
+        let RoleListener::Tcp(tcp) = &listener else {
+            unreachable!("no socket configured");
+        };
+        let addr = tcp.local_addr().unwrap();
+        let app = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        tokio::spawn(listener.serve(app, Duration::from_millis(200), std::future::pending()));
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        stream.write_all(b"GET /health HTTP/1.1\r\n").await.unwrap();
+        let mut response = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), stream.read_to_end(&mut response))
+            .await
+            .expect("connection left open")
+            .unwrap();
+        assert!(!String::from_utf8_lossy(&response).contains("200 OK"));
+    }
+}
@@ -1,8 +1,22 @@
 //! Common utilities and functionality shared across modules
 
+pub mod compression;
 pub mod error_handling;
+pub mod limits;
+pub mod listener;
+pub mod redis;
+pub mod resources;
+pub mod sqlite;
+pub mod traffic;
 
+pub use compression::{with_compression, RequestCompression};
 pub use error_handling::{
     create_default_error_handler, default_retryable_errors, ErrorHandler, ShutdownCoordinator,
     ShutdownSignal, TimeoutConfig,
 };
+pub use limits::with_request_limits;
+pub use listener::RoleListener;
+pub use redis::{RedisConnection, RedisConnector};
+pub use resources::{with_resource_limits, ResourceMonitor};
+pub use sqlite::{SqlValue, SqliteDatabase, SqliteError};
+pub use traffic::{with_traffic_classification, TrafficClass};
@@ -0,0 +1,1009 @@
+//! Redis Topologies
+//!
+//! Every Redis-backed module (conversation memory, token quotas, chain
+//! checkpoints and dead letters, the event bus) connects through a
+//! [`RedisConnector`], which hides the topology of the deployment's Redis:
+//!
+//! - `single`: the server at `memory.redis_url`
+//! - `sentinel`: the master named `memory.redis.master_name`, resolved
+//!   through the sentinels and resolved again when it fails over
+//! - `cluster`: the masters of a Redis Cluster, each command being sent to
+//!   the master owning the slot of its keys, following MOVED and ASK
+//!   redirects
+//!
+//! Connections implement [`ConnectionLike`], so modules keep issuing
+//! commands and pipelines as usual. In a cluster, the keys of a command or
+//! pipeline must hash to a single slot, except for KEYS, which is sent to
+//! every master, and DEL, UNLINK, EXISTS and TOUCH, which are split by slot.
+//!
+//! A command whose connection dropped may have run, so it is not retried;
+//! the master or slot map is resolved again for the next one. Commands
+//! refused by a demoted master (READONLY, MASTERDOWN) or by an unreachable
+//! node are retried against the new topology.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use metrics::{counter, gauge};
+use redis::aio::{self, ConnectionLike};
+use redis::{
+    Arg, Cmd, ConnectionAddr, ConnectionInfo, ErrorKind, IntoConnectionInfo, Pipeline,
+    RedisConnectionInfo, RedisError, RedisFuture, RedisResult, Value,
+};
+use tracing::{info, warn};
+
+use crate::config::{MemoryConfig, RedisTopology, RedisTopologyConfig};
+use crate::modules::telemetry::catalog;
+
+/// Number of hash slots of a Redis Cluster
+pub const CLUSTER_SLOTS: u16 = 16384;
+
+/// Pause before retrying a command refused during a cluster failover
+const CLUSTER_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+/// Commands without keys, sent to any node of a cluster
+const KEYLESS_COMMANDS: &[&str] = &[
+    "PING",
+    "ECHO",
+    "INFO",
+    "TIME",
+    "DBSIZE",
+    "PUBLISH",
+    "SCRIPT",
+    "CLUSTER",
+    "CONFIG",
+    "CLIENT",
+    "COMMAND",
+    "FLUSHDB",
+    "FLUSHALL",
+    "RANDOMKEY",
+    "SCAN",
+    "WAIT",
+];
+
+/// Commands taking any number of keys, split by slot in a cluster and
+/// answering with the sum of the replies
+const SPLIT_COMMANDS: &[&str] = &["DEL", "UNLINK", "EXISTS", "TOUCH"];
+
+/// Address of a server as host and port
+type Node = (String, u16);
+
+/// Hash slot of a key, hashing only its hash tag if it has one
+pub fn key_slot(key: &[u8]) -> u16 {
+    let key = match key.iter().position(|&b| b == b'{') {
+        Some(open) => match key[open + 1..].iter().position(|&b| b == b'}') {
+            Some(len) if len > 0 => &key[open + 1..open + 1 + len],
+            _ => key,
+        },
+        None => key,
+    };
+    crc16(key) % CLUSTER_SLOTS
+}
+
+/// CRC-16/XMODEM, the checksum of cluster key slots
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |crc, &byte| {
+        (0..8).fold(crc ^ (u16::from(byte) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+/// Master owning a range of cluster slots
+#[derive(Debug, Clone, PartialEq)]
+struct SlotRange {
+    start: u16,
+    end: u16,
+    node: Node,
+}
+
+/// Topology last seen
+#[derive(Debug, Default)]
+struct TopologyState {
+    /// Address of the sentinel master
+    master: Option<Node>,
+    /// Slot ranges of the cluster, sorted by first slot
+    slots: Vec<SlotRange>,
+    refreshed_at: Option<Instant>,
+}
+
+impl TopologyState {
+    /// Master owning a cluster slot
+    fn slot_node(&self, slot: u16) -> Option<Node> {
+        let index = self.slots.partition_point(|range| range.end < slot);
+        self.slots
+            .get(index)
+            .filter(|range| range.start <= slot)
+            .map(|range| range.node.clone())
+    }
+
+    /// Distinct masters of the cluster
+    fn masters(&self) -> Vec<Node> {
+        let mut masters: Vec<Node> = self.slots.iter().map(|range| range.node.clone()).collect();
+        masters.sort();
+        masters.dedup();
+        masters
+    }
+}
+
+struct ConnectorInner {
+    config: RedisTopologyConfig,
+    /// Server of the single topology
+    single: Option<ConnectionInfo>,
+    /// Seed nodes of the cluster or sentinels
+    seeds: Vec<Node>,
+    state: Mutex<TopologyState>,
+}
+
+/// Connects to the deployment's Redis, whatever its topology
+#[derive(Clone)]
+pub struct RedisConnector {
+    inner: Arc<ConnectorInner>,
+}
+
+impl RedisConnector {
+    /// Create the connector described by the memory configuration
+    ///
+    /// Returns `None` when no Redis is configured. Nothing is connected
+    /// until a connection is requested.
+    pub fn from_config(config: &MemoryConfig) -> RedisResult<Option<Self>> {
+        let topology = &config.redis;
+        let (single, seeds) = match topology.topology {
+            RedisTopology::Single => {
+                let Some(url) = &config.redis_url else {
+                    return Ok(None);
+                };
+                let mut info = url.as_str().into_connection_info()?;
+                if topology.username.is_some() {
+                    info.redis.username = topology.username.clone();
+                }
+                if topology.password.is_some() {
+                    info.redis.password = topology.password.clone();
+                }
+                if let ConnectionAddr::Tcp(host, port) = &info.addr {
+                    if topology.tls {
+                        info.addr = ConnectionAddr::TcpTls {
+                            host: host.clone(),
+                            port: *port,
+                            insecure: topology.tls_insecure,
+                        };
+                    }
+                }
+                (Some(info), Vec::new())
+            }
+            RedisTopology::Sentinel => (None, parse_nodes(&topology.sentinels, 26379)?),
+            RedisTopology::Cluster => (None, parse_nodes(&topology.nodes, 6379)?),
+        };
+        Ok(Some(Self::new(topology.clone(), single, seeds)))
+    }
+
+    /// Create a connector to a single server
+    pub fn from_url(url: &str) -> RedisResult<Self> {
+        let info = url.into_connection_info()?;
+        Ok(Self::new(
+            RedisTopologyConfig::default(),
+            Some(info),
+            Vec::new(),
+        ))
+    }
+
+    fn new(config: RedisTopologyConfig, single: Option<ConnectionInfo>, seeds: Vec<Node>) -> Self {
+        Self {
+            inner: Arc::new(ConnectorInner {
+                config,
+                single,
+                seeds,
+                state: Mutex::new(TopologyState::default()),
+            }),
+        }
+    }
+
+    /// Topology connected to
+    pub fn topology(&self) -> RedisTopology {
+        self.inner.config.topology
+    }
+
+    /// Open a connection
+    pub async fn connection(&self) -> RedisResult<RedisConnection> {
+        let target = match self.topology() {
+            RedisTopology::Single => {
+                let info = self.single_info();
+                Target::Single(redis::Client::open(info)?.get_async_connection().await?)
+            }
+            RedisTopology::Sentinel => {
+                let (node, conn) = self.connect_master().await?;
+                Target::Sentinel(Some((node, conn)))
+            }
+            RedisTopology::Cluster => {
+                if self.slots_stale() {
+                    self.refresh_slots("interval").await?;
+                }
+                Target::Cluster(HashMap::new())
+            }
+        };
+        Ok(RedisConnection {
+            connector: self.clone(),
+            target,
+        })
+    }
+
+    /// Open a multiplexed connection to the server of the single topology,
+    /// which reconnects by itself after an error
+    ///
+    /// Returns `None` for the sentinel and cluster topologies, whose
+    /// connections follow failovers and redirects instead.
+    pub async fn multiplexed(&self) -> RedisResult<Option<aio::ConnectionManager>> {
+        if self.topology() != RedisTopology::Single {
+            return Ok(None);
+        }
+        let manager = redis::Client::open(self.single_info())?
+            .get_tokio_connection_manager()
+            .await?;
+        Ok(Some(manager))
+    }
+
+    /// Open a connection for Pub/Sub
+    ///
+    /// Sentinel subscribers listen on the master. Cluster messages are
+    /// broadcast to every node, so subscribers listen on any master.
+    pub async fn pubsub(&self) -> RedisResult<aio::PubSub> {
+        let conn = match self.topology() {
+            RedisTopology::Single => {
+                redis::Client::open(self.single_info())?
+                    .get_async_connection()
+                    .await?
+            }
+            RedisTopology::Sentinel => self.connect_master().await?.1,
+            RedisTopology::Cluster => {
+                if self.slots_stale() {
+                    self.refresh_slots("interval").await?;
+                }
+                let node = self
+                    .state()
+                    .masters()
+                    .into_iter()
+                    .next()
+                    .ok_or_else(no_masters)?;
+                self.connect_node(&node).await?
+            }
+        };
+        Ok(conn.into_pubsub())
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, TopologyState> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn single_info(&self) -> ConnectionInfo {
+        self.inner
+            .single
+            .clone()
+            .expect("single topology has a server")
+    }
+
+    /// Connection settings of a master, cluster node or sentinel
+    fn node_info(&self, node: &Node, sentinel: bool) -> ConnectionInfo {
+        let config = &self.inner.config;
+        let addr = if config.tls {
+            ConnectionAddr::TcpTls {
+                host: node.0.clone(),
+                port: node.1,
+                insecure: config.tls_insecure,
+            }
+        } else {
+            ConnectionAddr::Tcp(node.0.clone(), node.1)
+        };
+        let redis = if sentinel {
+            RedisConnectionInfo {
+                db: 0,
+                username: None,
+                password: config.sentinel_password.clone(),
+            }
+        } else {
+            RedisConnectionInfo {
+                db: match config.topology {
+                    RedisTopology::Cluster => 0,
+                    _ => config.database,
+                },
+                username: config.username.clone(),
+                password: config.password.clone(),
+            }
+        };
+        ConnectionInfo { addr, redis }
+    }
+
+    async fn connect_node(&self, node: &Node) -> RedisResult<aio::Connection> {
+        redis::Client::open(self.node_info(node, false))?
+            .get_async_connection()
+            .await
+    }
+
+    /// Connect to the sentinel master, resolving it again if it is
+    /// unreachable
+    async fn connect_master(&self) -> RedisResult<(Node, aio::Connection)> {
+        let known = self.state().master.clone();
+        let node = match known {
+            Some(node) => match self.connect_node(&node).await {
+                Ok(conn) => return Ok((node, conn)),
+                Err(e) => {
+                    warn!("Redis master {}:{} is unreachable: {}", node.0, node.1, e);
+                    self.resolve_master("error").await?
+                }
+            },
+            None => self.resolve_master("startup").await?,
+        };
+        let conn = self.connect_node(&node).await?;
+        Ok((node, conn))
+    }
+
+    /// Ask the sentinels for the address of the master
+    async fn resolve_master(&self, reason: &'static str) -> RedisResult<Node> {
+        let mut last_error = None;
+        for sentinel in &self.inner.seeds {
+            match self.ask_sentinel(sentinel).await {
+                Ok(Some(node)) => {
+                    let previous = self.state().master.replace(node.clone());
+                    record_refresh(RedisTopology::Sentinel, reason, "success");
+                    gauge!(catalog::REDIS_NODES, 1.0, "topology" => "sentinel");
+                    if let Some(previous) = previous.filter(|previous| *previous != node) {
+                        info!(
+                            "Redis master {} failed over from {}:{} to {}:{}",
+                            self.inner.config.master_name, previous.0, previous.1, node.0, node.1
+                        );
+                        counter!(catalog::REDIS_FAILOVERS, 1, "topology" => "sentinel");
+                    }
+                    return Ok(node);
+                }
+                Ok(None) => {
+                    last_error = Some(RedisError::from((
+                        ErrorKind::ResponseError,
+                        "Sentinel does not monitor the master",
+                        self.inner.config.master_name.clone(),
+                    )))
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        record_refresh(RedisTopology::Sentinel, reason, "error");
+        Err(last_error.unwrap_or_else(|| {
+            RedisError::from((ErrorKind::InvalidClientConfig, "No sentinels configured"))
+        }))
+    }
+
+    async fn ask_sentinel(&self, sentinel: &Node) -> RedisResult<Option<Node>> {
+        let mut conn = redis::Client::open(self.node_info(sentinel, true))?
+            .get_async_connection()
+            .await?;
+        redis::cmd("SENTINEL")
+            .arg("get-master-addr-by-name")
+            .arg(&self.inner.config.master_name)
+            .query_async(&mut conn)
+            .await
+    }
+
+    /// Whether the cluster slot map is missing or due for a refresh
+    fn slots_stale(&self) -> bool {
+        let interval = Duration::from_secs(self.inner.config.refresh_interval_secs);
+        self.state()
+            .refreshed_at
+            .is_none_or(|refreshed_at| refreshed_at.elapsed() >= interval)
+    }
+
+    /// Read the slot map from the known masters or, failing that, the seeds
+    async fn refresh_slots(&self, reason: &'static str) -> RedisResult<()> {
+        let mut candidates = self.state().masters();
+        candidates.extend(self.inner.seeds.iter().cloned());
+        candidates.dedup();
+
+        let mut last_error = None;
+        for node in candidates {
+            let slots = match self.read_slots(&node).await {
+                Ok(slots) if !slots.is_empty() => slots,
+                Ok(_) => {
+                    last_error = Some(RedisError::from((
+                        ErrorKind::ClusterDown,
+                        "Cluster has no assigned slots",
+                    )));
+                    continue;
+                }
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let mut state = self.state();
+            let previous = state.masters();
+            state.slots = slots;
+            state.refreshed_at = Some(Instant::now());
+            let masters = state.masters();
+            drop(state);
+
+            record_refresh(RedisTopology::Cluster, reason, "success");
+            gauge!(catalog::REDIS_NODES, masters.len() as f64, "topology" => "cluster");
+            let lost = previous
+                .iter()
+                .filter(|node| !masters.contains(node))
+                .count();
+            if lost > 0 {
+                info!("{} Redis Cluster masters were replaced", lost);
+                counter!(catalog::REDIS_FAILOVERS, lost as u64, "topology" => "cluster");
+            }
+            return Ok(());
+        }
+
+        record_refresh(RedisTopology::Cluster, reason, "error");
+        Err(last_error.unwrap_or_else(no_masters))
+    }
+
+    async fn read_slots(&self, node: &Node) -> RedisResult<Vec<SlotRange>> {
+        let mut conn = self.connect_node(node).await?;
+        let value: Value = redis::cmd("CLUSTER")
+            .arg("SLOTS")
+            .query_async(&mut conn)
+            .await?;
+        parse_slots(&value, &node.0)
+    }
+
+    /// Master owning a slot
+    async fn slot_node(&self, slot: u16) -> RedisResult<Node> {
+        if let Some(node) = self.state().slot_node(slot) {
+            return Ok(node);
+        }
+        self.refresh_slots("error").await?;
+        self.state().slot_node(slot).ok_or_else(|| {
+            RedisError::from((
+                ErrorKind::ClusterDown,
+                "Slot is not served",
+                slot.to_string(),
+            ))
+        })
+    }
+}
+
+impl std::fmt::Debug for RedisConnector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisConnector")
+            .field("topology", &self.topology())
+            .field("seeds", &self.inner.seeds)
+            .finish()
+    }
+}
+
+/// A command or pipeline sent to a server
+#[derive(Clone, Copy)]
+enum Request<'a> {
+    Command(&'a Cmd),
+    Pipeline(&'a Pipeline, usize, usize),
+}
+
+impl Request<'_> {
+    async fn send(self, conn: &mut aio::Connection) -> RedisResult<Vec<Value>> {
+        match self {
+            Request::Command(cmd) => Ok(vec![conn.req_packed_command(cmd).await?]),
+            Request::Pipeline(pipeline, offset, count) => {
+                conn.req_packed_commands(pipeline, offset, count).await
+            }
+        }
+    }
+}
+
+/// Where the commands of a cluster request go
+#[derive(Debug, PartialEq)]
+enum Route {
+    /// Any master
+    Any,
+    /// The master owning a slot
+    Slot(u16),
+    /// Every master, concatenating the replies
+    AllMasters,
+    /// The masters owning the slots of the keys, summing the replies
+    Split,
+}
+
+/// Arguments of a command
+fn command_args(cmd: &Cmd) -> Vec<&[u8]> {
+    cmd.args_iter()
+        .filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg),
+            Arg::Cursor => None,
+        })
+        .collect()
+}
+
+/// Keys of a command that its cluster route depends on
+fn command_keys<'a>(args: &[&'a [u8]]) -> Vec<&'a [u8]> {
+    let Some(name) = args.first() else {
+        return Vec::new();
+    };
+    let name = String::from_utf8_lossy(name).to_ascii_uppercase();
+    if SPLIT_COMMANDS.contains(&name.as_str()) {
+        return args[1..].to_vec();
+    }
+    if KEYLESS_COMMANDS.contains(&name.as_str()) || name == "KEYS" {
+        return Vec::new();
+    }
+    if name == "EVAL" || name == "EVALSHA" {
+        let numkeys = args
+            .get(2)
+            .and_then(|n| std::str::from_utf8(n).ok()?.parse::<usize>().ok())
+            .unwrap_or(0);
+        return args.iter().skip(3).take(numkeys).copied().collect();
+    }
+    args.get(1).copied().into_iter().collect()
+}
+
+/// Cluster route of a command
+fn command_route(args: &[&[u8]]) -> Route {
+    let name = args
+        .first()
+        .map(|name| String::from_utf8_lossy(name).to_ascii_uppercase())
+        .unwrap_or_default();
+    if name == "KEYS" {
+        return Route::AllMasters;
+    }
+    let keys = command_keys(args);
+    let mut slots = keys.iter().map(|key| key_slot(key));
+    match slots.next() {
+        None => Route::Any,
+        Some(first) if slots.all(|slot| slot == first) => Route::Slot(first),
+        Some(_) => Route::Split,
+    }
+}
+
+/// Cluster route of a pipeline, whose keys must share a slot
+fn pipeline_route(pipeline: &Pipeline) -> RedisResult<Route> {
+    let mut route = Route::Any;
+    for cmd in pipeline.cmd_iter() {
+        let args = command_args(cmd);
+        for key in command_keys(&args) {
+            let slot = key_slot(key);
+            match route {
+                Route::Slot(first) if first != slot => {
+                    return Err(RedisError::from((
+                        ErrorKind::CrossSlot,
+                        "Keys of a pipeline must hash to the same slot in a Redis Cluster",
+                    )))
+                }
+                _ => route = Route::Slot(slot),
+            }
+        }
+    }
+    Ok(route)
+}
+
+/// Server side of a connection
+enum Target {
+    Single(aio::Connection),
+    /// Connection to the master, reopened after a failover
+    Sentinel(Option<(Node, aio::Connection)>),
+    /// Connections to the masters, opened when first needed
+    Cluster(HashMap<Node, aio::Connection>),
+}
+
+/// Connection to the deployment's Redis, whatever its topology
+pub struct RedisConnection {
+    connector: RedisConnector,
+    target: Target,
+}
+
+impl RedisConnection {
+    async fn request(&mut self, request: Request<'_>) -> RedisResult<Vec<Value>> {
+        match &mut self.target {
+            Target::Single(conn) => request.send(conn).await,
+            Target::Sentinel(_) => self.sentinel_request(request).await,
+            Target::Cluster(_) => self.cluster_request(request).await,
+        }
+    }
+
+    async fn sentinel_request(&mut self, request: Request<'_>) -> RedisResult<Vec<Value>> {
+        let connector = self.connector.clone();
+        let Target::Sentinel(master) = &mut self.target else {
+            unreachable!()
+        };
+        let mut attempts = 0;
+        loop {
+            let (_, conn) = match master {
+                Some(master) => master,
+                None => master.insert(connector.connect_master().await?),
+            };
+            let error = match request.send(conn).await {
+                Ok(values) => return Ok(values),
+                Err(e) => e,
+            };
+            if is_dropped(&error) {
+                *master = None;
+                connector.resolve_master("error").await.ok();
+                return Err(error);
+            }
+            if !is_refused(&error) || attempts >= connector.inner.config.max_redirects {
+                return Err(error);
+            }
+            attempts += 1;
+            *master = None;
+            connector.resolve_master("failover").await?;
+        }
+    }
+
+    async fn cluster_request(&mut self, request: Request<'_>) -> RedisResult<Vec<Value>> {
+        let route = match request {
+            Request::Command(cmd) => command_route(&command_args(cmd)),
+            Request::Pipeline(pipeline, _, _) => pipeline_route(pipeline)?,
+        };
+        match (route, request) {
+            (Route::Slot(slot), _) => self.send_to_slot(request, Some(slot)).await,
+            (Route::Any, _) => self.send_to_slot(request, None).await,
+            (Route::AllMasters, Request::Command(cmd)) => self.send_to_masters(cmd).await,
+            (Route::Split, Request::Command(cmd)) => self.send_split(cmd).await,
+            _ => unreachable!("pipelines route to a slot or any master"),
+        }
+    }
+
+    async fn node_connection(&mut self, node: &Node) -> RedisResult<&mut aio::Connection> {
+        let Target::Cluster(conns) = &mut self.target else {
+            unreachable!()
+        };
+        if !conns.contains_key(node) {
+            let conn = self.connector.connect_node(node).await?;
+            conns.insert(node.clone(), conn);
+        }
+        Ok(conns.get_mut(node).expect("connection was just opened"))
+    }
+
+    fn drop_connection(&mut self, node: &Node) {
+        if let Target::Cluster(conns) = &mut self.target {
+            conns.remove(node);
+        }
+    }
+
+    /// Send a request to the master owning a slot, or to any master,
+    /// following redirects
+    async fn send_to_slot(
+        &mut self,
+        request: Request<'_>,
+        slot: Option<u16>,
+    ) -> RedisResult<Vec<Value>> {
+        let connector = self.connector.clone();
+        let max_redirects = connector.inner.config.max_redirects;
+        let mut redirects = 0;
+        let mut asking = None;
+        loop {
+            if connector.slots_stale() {
+                connector.refresh_slots("interval").await?;
+            }
+            let node = match (asking.clone(), slot) {
+                (Some(node), _) => node,
+                (None, Some(slot)) => connector.slot_node(slot).await?,
+                (None, None) => connector
+                    .state()
+                    .masters()
+                    .into_iter()
+                    .next()
+                    .ok_or_else(no_masters)?,
+            };
+
+            let result = match self.node_connection(&node).await {
+                Ok(conn) => {
+                    if asking.is_some() {
+                        redis::cmd("ASKING").query_async::<_, ()>(conn).await?;
+                    }
+                    request.send(conn).await
+                }
+                Err(e) => Err(e),
+            };
+            let error = match result {
+                Ok(values) => return Ok(values),
+                Err(e) => e,
+            };
+            if is_dropped(&error) {
+                self.drop_connection(&node);
+                connector.refresh_slots("error").await.ok();
+                return Err(error);
+            }
+            if redirects >= max_redirects {
+                return Err(error);
+            }
+            redirects += 1;
+            asking = None;
+            match error.kind() {
+                ErrorKind::Moved => {
+                    counter!(catalog::REDIS_REDIRECTS, 1, "kind" => "moved");
+                    connector.refresh_slots("moved").await?;
+                }
+                ErrorKind::Ask => {
+                    counter!(catalog::REDIS_REDIRECTS, 1, "kind" => "ask");
+                    let redirect = error
+                        .redirect_node()
+                        .map(|(host, port)| (host.to_string(), port));
+                    match redirect {
+                        Some(node) => asking = Some(node),
+                        None => return Err(error),
+                    }
+                }
+                ErrorKind::TryAgain | ErrorKind::ClusterDown | ErrorKind::MasterDown => {
+                    tokio::time::sleep(CLUSTER_RETRY_DELAY).await;
+                    connector.refresh_slots("failover").await.ok();
+                }
+                ErrorKind::ReadOnly => {
+                    self.drop_connection(&node);
+                    connector.refresh_slots("failover").await?;
+                }
+                _ if error.is_connection_refusal() => {
+                    self.drop_connection(&node);
+                    tokio::time::sleep(CLUSTER_RETRY_DELAY).await;
+                    connector.refresh_slots("error").await.ok();
+                }
+                _ => return Err(error),
+            }
+        }
+    }
+
+    /// Send a command to every master, concatenating the replies
+    async fn send_to_masters(&mut self, cmd: &Cmd) -> RedisResult<Vec<Value>> {
+        if self.connector.slots_stale() {
+            self.connector.refresh_slots("interval").await?;
+        }
+        let masters = self.connector.state().masters();
+        let mut values = Vec::new();
+        for node in masters {
+            let conn = self.node_connection(&node).await?;
+            match conn.req_packed_command(cmd).await? {
+                Value::Bulk(items) => values.extend(items),
+                other => values.push(other),
+            }
+        }
+        Ok(vec![Value::Bulk(values)])
+    }
+
+    /// Send a multi-key command once per slot, summing the replies
+    async fn send_split(&mut self, cmd: &Cmd) -> RedisResult<Vec<Value>> {
+        let args = command_args(cmd);
+        let mut by_slot: BTreeMap<u16, Vec<&[u8]>> = BTreeMap::new();
+        for key in &args[1..] {
+            by_slot.entry(key_slot(key)).or_default().push(key);
+        }
+        let mut total = 0;
+        for (slot, keys) in by_slot {
+            let mut part = redis::cmd(&String::from_utf8_lossy(args[0]));
+            part.arg(keys);
+            let values = self
+                .send_to_slot(Request::Command(&part), Some(slot))
+                .await?;
+            total += values
+                .first()
+                .map(redis::from_redis_value::<i64>)
+                .transpose()?
+                .unwrap_or(0);
+        }
+        Ok(vec![Value::Int(total)])
+    }
+}
+
+impl ConnectionLike for RedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            let mut values = self.request(Request::Command(cmd)).await?;
+            Ok(values.pop().unwrap_or(Value::Nil))
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move { self.request(Request::Pipeline(cmd, offset, count)).await })
+    }
+
+    fn get_db(&self) -> i64 {
+        match &self.target {
+            Target::Single(conn) => conn.get_db(),
+            Target::Sentinel(_) => self.connector.inner.config.database,
+            Target::Cluster(_) => 0,
+        }
+    }
+}
+
+/// Whether the connection dropped, leaving the request's outcome unknown
+fn is_dropped(error: &RedisError) -> bool {
+    error.is_connection_dropped() || (error.is_io_error() && !error.is_connection_refusal())
+}
+
+/// Whether a former master refused the request, which can be retried on
+/// the new one
+fn is_refused(error: &RedisError) -> bool {
+    matches!(error.kind(), ErrorKind::ReadOnly | ErrorKind::MasterDown)
+        || error.is_connection_refusal()
+}
+
+fn no_masters() -> RedisError {
+    RedisError::from((ErrorKind::ClusterDown, "No Redis Cluster masters are known"))
+}
+
+fn record_refresh(topology: RedisTopology, reason: &'static str, outcome: &'static str) {
+    counter!(
+        catalog::REDIS_TOPOLOGY_REFRESHES,
+        1,
+        "topology" => topology.as_str(),
+        "reason" => reason,
+        "outcome" => outcome
+    );
+}
+
+/// Parse addresses as `host:port`, with a default port
+fn parse_nodes(addresses: &[String], default_port: u16) -> RedisResult<Vec<Node>> {
+    addresses
+        .iter()
+        .map(|address| match address.rsplit_once(':') {
+            Some((host, port)) => port
+                .parse()
+                .map(|port| (host.to_string(), port))
+                .map_err(|_| {
+                    RedisError::from((
+                        ErrorKind::InvalidClientConfig,
+                        "Invalid Redis node address",
+                        address.clone(),
+                    ))
+                }),
+            None => Ok((address.clone(), default_port)),
+        })
+        .collect()
+}
+
+/// Parse the reply to `CLUSTER SLOTS`; nodes without a host are the node
+/// that replied
+fn parse_slots(value: &Value, host: &str) -> RedisResult<Vec<SlotRange>> {
+    let invalid = || RedisError::from((ErrorKind::TypeError, "Invalid CLUSTER SLOTS reply"));
+    let Value::Bulk(entries) = value else {
+        return Err(invalid());
+    };
+    let mut slots = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Value::Bulk(fields) = entry else {
+            return Err(invalid());
+        };
+        let (Some(start), Some(end), Some(Value::Bulk(master))) =
+            (fields.first(), fields.get(1), fields.get(2))
+        else {
+            return Err(invalid());
+        };
+        let (Some(master_host), Some(master_port)) = (master.first(), master.get(1)) else {
+            return Err(invalid());
+        };
+        let master_host: String = redis::from_redis_value(master_host)?;
+        let master_host = match master_host.as_str() {
+            "" | "?" => host.to_string(),
+            _ => master_host,
+        };
+        slots.push(SlotRange {
+            start: redis::from_redis_value(start)?,
+            end: redis::from_redis_value(end)?,
+            node: (master_host, redis::from_redis_value(master_port)?),
+        });
+    }
+    slots.sort_by_key(|range| range.start);
+    Ok(slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_slot() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert_eq!(key_slot(b"foo"), 12182);
+        assert_eq!(key_slot(b"{user1000}.following"), key_slot(b"user1000"));
+        // Empty hash tags hash the whole key
+        assert_eq!(
+            key_slot(b"foo{}{bar}"),
+            crc16(b"foo{}{bar}") % CLUSTER_SLOTS
+        );
+    }
+
+    #[test]
+    fn test_command_route() {
+        let route = |cmd: Cmd| command_route(&command_args(&cmd));
+        assert_eq!(route(redis::cmd("PING")), Route::Any);
+        assert_eq!(
+            route(redis::cmd("GET").arg("foo").clone()),
+            Route::Slot(12182)
+        );
+        assert_eq!(
+            route(redis::cmd("KEYS").arg("*").clone()),
+            Route::AllMasters
+        );
+        assert_eq!(
+            route(redis::cmd("DEL").arg("{a}1").arg("{a}2").clone()),
+            Route::Slot(key_slot(b"a"))
+        );
+        assert_eq!(
+            route(redis::cmd("DEL").arg("a").arg("b").clone()),
+            Route::Split
+        );
+
+        let mut pipeline = redis::pipe();
+        pipeline
+            .atomic()
+            .cmd("ZADD")
+            .arg("q")
+            .arg(1)
+            .arg("m")
+            .cmd("EXPIRE")
+            .arg("q")
+            .arg(60);
+        assert_eq!(
+            pipeline_route(&pipeline).unwrap(),
+            Route::Slot(key_slot(b"q"))
+        );
+        pipeline.cmd("GET").arg("other");
+        assert_eq!(
+            pipeline_route(&pipeline).unwrap_err().kind(),
+            ErrorKind::CrossSlot
+        );
+    }
+
+    #[test]
+    fn test_parse_slots() {
+        let node = |host: &str, port: i64| {
+            Value::Bulk(vec![
+                Value::Data(host.as_bytes().to_vec()),
+                Value::Int(port),
+            ])
+        };
+        let reply = Value::Bulk(vec![
+            Value::Bulk(vec![Value::Int(8192), Value::Int(16383), node("", 7001)]),
+            Value::Bulk(vec![
+                Value::Int(0),
+                Value::Int(8191),
+                node("10.0.0.1", 7000),
+                node("10.0.0.3", 7002),
+            ]),
+        ]);
+        let state = TopologyState {
+            slots: parse_slots(&reply, "seed").unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(state.slot_node(0), Some(("10.0.0.1".to_string(), 7000)));
+        assert_eq!(state.slot_node(12182), Some(("seed".to_string(), 7001)));
+        assert_eq!(state.masters().len(), 2);
+    }
+
+    #[test]
+    fn test_from_config() {
+        let mut config = MemoryConfig {
+            redis_url: Some("redis://:old@localhost:6379/2".to_string()),
+            ..Default::default()
+        };
+        config.redis.password = Some("new".to_string());
+        config.redis.tls = true;
+        let connector = RedisConnector::from_config(&config).unwrap().unwrap();
+        let info = connector.single_info();
+        assert_eq!(info.redis.password.as_deref(), Some("new"));
+        assert_eq!(info.redis.db, 2);
+        assert!(matches!(info.addr, ConnectionAddr::TcpTls { .. }));
+
+        config.redis.topology = RedisTopology::Cluster;
+        config.redis.nodes = vec!["10.0.0.1:7000".to_string(), "10.0.0.2".to_string()];
+        let connector = RedisConnector::from_config(&config).unwrap().unwrap();
+        assert_eq!(connector.inner.seeds[1], ("10.0.0.2".to_string(), 6379));
+
+        config.redis.nodes.push("10.0.0.3:port".to_string());
+        assert!(RedisConnector::from_config(&config).is_err());
+        config.redis.nodes.pop();
+
+        config.redis_url = None;
+        config.redis.topology = RedisTopology::Single;
+        assert!(RedisConnector::from_config(&config).unwrap().is_none());
+    }
+}
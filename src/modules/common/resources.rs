@@ -0,0 +1,478 @@
+//! Process Resources
+//!
+//! Each role samples the CPU, resident memory and open file descriptors of
+//! its process every `interval_secs`, as configured under
+//! `[telemetry.resources]`, publishes them as gauges and reports them in its
+//! `/diagnostics`. While the latest sample is over one of the soft limits,
+//! the role sheds batch requests, those sent with
+//! `x-intellirouter-workload: batch`, with 503, leaving the headroom to
+//! interactive traffic.
+//!
+//! Resources are read from `/proc` and the cgroup filesystem, so they are
+//! only sampled on Linux. Elsewhere samples are empty and nothing is shed.
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::header::RETRY_AFTER;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use serde::Serialize;
+use serde_json::json;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::config::{ResourceLimitsConfig, ResourceMonitorConfig};
+use crate::modules::telemetry::catalog;
+
+/// Request header naming the workload a request belongs to; requests of the
+/// `batch` workload are shed first
+pub const WORKLOAD_HEADER: &str = "x-intellirouter-workload";
+
+/// Clock ticks per second of the CPU times in `/proc`, fixed by the kernel's
+/// user-space interface
+const USER_HZ: f64 = 100.0;
+
+/// Resource with a soft limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Memory,
+    Cpu,
+    Fds,
+}
+
+impl Resource {
+    /// Label of the resource in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Resource::Memory => "memory",
+            Resource::Cpu => "cpu",
+            Resource::Fds => "fds",
+        }
+    }
+}
+
+/// Resources of the process at a point in time; unknown values are `None`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ResourceSample {
+    /// CPU time since the previous sample, in percent of the CPUs available
+    pub cpu_percent: Option<f64>,
+    /// Resident memory
+    pub memory_bytes: Option<u64>,
+    /// Memory limit of the container, or else memory of the host
+    pub memory_limit_bytes: Option<u64>,
+    /// Resident memory in percent of the limit
+    pub memory_percent: Option<f64>,
+    /// Open file descriptors
+    pub open_fds: Option<u64>,
+    /// Soft limit of open file descriptors
+    pub fd_limit: Option<u64>,
+    /// Open file descriptors in percent of the limit
+    pub fd_percent: Option<f64>,
+}
+
+impl ResourceSample {
+    /// Sample with the percentages computed from the values and their limits
+    pub fn new(
+        cpu_percent: Option<f64>,
+        memory: (Option<u64>, Option<u64>),
+        fds: (Option<u64>, Option<u64>),
+    ) -> Self {
+        Self {
+            cpu_percent,
+            memory_bytes: memory.0,
+            memory_limit_bytes: memory.1,
+            memory_percent: percent(memory.0, memory.1),
+            open_fds: fds.0,
+            fd_limit: fds.1,
+            fd_percent: percent(fds.0, fds.1),
+        }
+    }
+
+    /// Resources of the sample over their soft limit
+    pub fn over_limits(&self, limits: &ResourceLimitsConfig) -> Vec<Resource> {
+        [
+            (Resource::Memory, self.memory_percent, limits.memory_percent),
+            (Resource::Cpu, self.cpu_percent, limits.cpu_percent),
+            (Resource::Fds, self.fd_percent, limits.fd_percent),
+        ]
+        .into_iter()
+        .filter_map(|(resource, value, limit)| (value? > limit?).then_some(resource))
+        .collect()
+    }
+}
+
+fn percent(value: Option<u64>, limit: Option<u64>) -> Option<f64> {
+    match (value, limit) {
+        (Some(value), Some(limit)) if limit > 0 => Some(value as f64 / limit as f64 * 100.0),
+        _ => None,
+    }
+}
+
+/// State of a role's resources, as reported in its diagnostics
+#[derive(Debug, Clone, Serialize)]
+pub struct ResourceStatus {
+    /// Role of the process
+    pub role: String,
+    /// When the latest sample was taken
+    pub sampled_at: Option<DateTime<Utc>>,
+    /// Latest sample
+    pub sample: ResourceSample,
+    /// Soft limits
+    pub limits: ResourceLimitsConfig,
+    /// Resources of the latest sample over their soft limit
+    pub over_limit: Vec<Resource>,
+    /// Whether batch requests are being shed
+    pub shedding: bool,
+    /// Batch requests shed since the process started
+    pub shed_requests: u64,
+}
+
+/// Latest sample and the CPU time it was taken at
+#[derive(Debug, Default)]
+struct Latest {
+    sampled_at: Option<DateTime<Utc>>,
+    sample: ResourceSample,
+    over_limit: Vec<Resource>,
+    /// CPU seconds of the process and when they were read
+    cpu: Option<(f64, Instant)>,
+}
+
+/// Sampler of a role's process resources, enforcing their soft limits
+#[derive(Debug)]
+pub struct ResourceMonitor {
+    role: String,
+    config: ResourceMonitorConfig,
+    /// CPUs available to the process
+    cpus: f64,
+    latest: Mutex<Latest>,
+    shedding: AtomicBool,
+    shed_requests: AtomicU64,
+}
+
+impl ResourceMonitor {
+    /// Monitor of the process of a role
+    pub fn new(role: impl Into<String>, config: &ResourceMonitorConfig) -> Self {
+        Self {
+            role: role.into(),
+            config: config.clone(),
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get()) as f64,
+            latest: Mutex::new(Latest::default()),
+            shedding: AtomicBool::new(false),
+            shed_requests: AtomicU64::new(0),
+        }
+    }
+
+    /// Sample the process's resources and record the sample
+    ///
+    /// CPU usage is measured since the previous sample, so the first sample
+    /// has none.
+    pub fn sample(&self) -> ResourceSample {
+        let now = Instant::now();
+        let cpu_secs = read_cpu_secs();
+        let cpu_percent = {
+            let mut latest = self.latest.lock().unwrap();
+            let previous = latest.cpu.take();
+            latest.cpu = cpu_secs.map(|secs| (secs, now));
+            match (previous, cpu_secs) {
+                (Some((previous_secs, at)), Some(secs)) => {
+                    let elapsed = now.duration_since(at).as_secs_f64();
+                    (elapsed > 0.0)
+                        .then(|| ((secs - previous_secs) / elapsed / self.cpus * 100.0).max(0.0))
+                }
+                _ => None,
+            }
+        };
+        let sample = ResourceSample::new(
+            cpu_percent,
+            (read_rss_bytes(), read_memory_limit()),
+            (read_open_fds(), read_fd_limit()),
+        );
+        self.record(sample.clone());
+        sample
+    }
+
+    /// Record a sample: publish it and start or stop shedding
+    pub fn record(&self, sample: ResourceSample) {
+        let over_limit = sample.over_limits(&self.config.limits);
+        self.publish(&sample, &over_limit);
+
+        let shedding = !over_limit.is_empty();
+        if self.shedding.swap(shedding, Ordering::Relaxed) != shedding {
+            if shedding {
+                warn!(
+                    "{} is over its soft limits of {:?}; shedding batch requests",
+                    self.role, over_limit
+                );
+            } else {
+                info!(
+                    "{} is back under its soft limits; no longer shedding",
+                    self.role
+                );
+            }
+        }
+
+        let mut latest = self.latest.lock().unwrap();
+        latest.sampled_at = Some(Utc::now());
+        latest.sample = sample;
+        latest.over_limit = over_limit;
+    }
+
+    fn publish(&self, sample: &ResourceSample, over_limit: &[Resource]) {
+        let values = [
+            (catalog::PROCESS_CPU_PERCENT, sample.cpu_percent),
+            (
+                catalog::PROCESS_MEMORY_BYTES,
+                sample.memory_bytes.map(|bytes| bytes as f64),
+            ),
+            (catalog::PROCESS_MEMORY_PERCENT, sample.memory_percent),
+            (
+                catalog::PROCESS_OPEN_FDS,
+                sample.open_fds.map(|fds| fds as f64),
+            ),
+            (catalog::PROCESS_FD_PERCENT, sample.fd_percent),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                gauge!(name, value, "role" => self.role.clone());
+            }
+        }
+        for resource in [Resource::Memory, Resource::Cpu, Resource::Fds] {
+            let over = if over_limit.contains(&resource) {
+                1.0
+            } else {
+                0.0
+            };
+            gauge!(
+                catalog::PROCESS_OVER_LIMIT,
+                over,
+                "role" => self.role.clone(),
+                "resource" => resource.as_str()
+            );
+        }
+    }
+
+    /// Whether batch requests are being shed
+    pub fn is_shedding(&self) -> bool {
+        self.shedding.load(Ordering::Relaxed)
+    }
+
+    /// State of the resources, for diagnostics
+    pub fn status(&self) -> ResourceStatus {
+        let latest = self.latest.lock().unwrap();
+        ResourceStatus {
+            role: self.role.clone(),
+            sampled_at: latest.sampled_at,
+            sample: latest.sample.clone(),
+            limits: self.config.limits.clone(),
+            over_limit: latest.over_limit.clone(),
+            shedding: self.is_shedding(),
+            shed_requests: self.shed_requests.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sample the resources now, then periodically in the background, if
+    /// monitoring is enabled
+    pub fn spawn_sampling(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        if !self.config.enabled {
+            return None;
+        }
+        let monitor = self.clone();
+        Some(tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(monitor.config.interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                monitor.sample();
+            }
+        }))
+    }
+}
+
+/// Whether a request belongs to the batch workload
+pub fn is_batch(headers: &HeaderMap) -> bool {
+    headers
+        .get(WORKLOAD_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.trim().eq_ignore_ascii_case("batch"))
+}
+
+/// Shed a role's batch requests while its process is over a soft limit
+pub fn with_resource_limits(app: Router, monitor: Arc<ResourceMonitor>) -> Router {
+    app.layer(from_fn_with_state(monitor, shedding_middleware))
+}
+
+/// Middleware rejecting batch requests while the process is over a soft limit
+pub async fn shedding_middleware(
+    State(monitor): State<Arc<ResourceMonitor>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !(monitor.is_shedding() && is_batch(request.headers())) {
+        return next.run(request).await;
+    }
+
+    monitor.shed_requests.fetch_add(1, Ordering::Relaxed);
+    counter!(catalog::REQUESTS_SHED, 1, "role" => monitor.role.clone());
+    let body = json!({
+        "error": {
+            "message": "The server is over its resource limits; retry batch requests later",
+            "type": "server_error",
+            "param": null,
+            "code": "overloaded",
+        }
+    });
+    let mut response = (StatusCode::SERVICE_UNAVAILABLE, Json(body)).into_response();
+    response.headers_mut().insert(
+        RETRY_AFTER,
+        HeaderValue::from(monitor.config.interval_secs.max(1)),
+    );
+    response
+}
+
+/// CPU seconds used by the process, user and system
+fn read_cpu_secs() -> Option<f64> {
+    parse_cpu_ticks(&fs::read_to_string("/proc/self/stat").ok()?)
+        .map(|ticks| ticks as f64 / USER_HZ)
+}
+
+/// User and system CPU ticks of a `/proc/<pid>/stat` line
+fn parse_cpu_ticks(stat: &str) -> Option<u64> {
+    // The command name may hold spaces and parentheses; fields follow the
+    // last parenthesis, starting with the third
+    let fields: Vec<&str> = stat
+        .get(stat.rfind(')')? + 1..)?
+        .split_whitespace()
+        .collect();
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// Resident memory of the process
+fn read_rss_bytes() -> Option<u64> {
+    parse_rss_bytes(&fs::read_to_string("/proc/self/status").ok()?)
+}
+
+/// Resident memory of a `/proc/<pid>/status` file
+fn parse_rss_bytes(status: &str) -> Option<u64> {
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Memory limit of the container, or else memory of the host
+fn read_memory_limit() -> Option<u64> {
+    let host = sys_info::mem_info().ok().map(|info| info.total * 1024);
+    let cgroup = [
+        "/sys/fs/cgroup/memory.max",
+        "/sys/fs/cgroup/memory/memory.limit_in_bytes",
+    ]
+    .iter()
+    .find_map(|path| fs::read_to_string(path).ok())
+    .and_then(|limit| limit.trim().parse::<u64>().ok());
+    // Unlimited cgroups report "max" or a limit above the host's memory
+    match (cgroup, host) {
+        (Some(cgroup), Some(host)) => Some(cgroup.min(host)),
+        (cgroup, host) => cgroup.or(host),
+    }
+}
+
+/// Open file descriptors of the process
+fn read_open_fds() -> Option<u64> {
+    Some(fs::read_dir("/proc/self/fd").ok()?.count() as u64)
+}
+
+/// Soft limit of open file descriptors of the process
+fn read_fd_limit() -> Option<u64> {
+    parse_fd_limit(&fs::read_to_string("/proc/self/limits").ok()?)
+}
+
+/// Soft limit of open files of a `/proc/<pid>/limits` file
+fn parse_fd_limit(limits: &str) -> Option<u64> {
+    let line = limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?;
+    line.split_whitespace().nth(3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_parse_proc() {
+        let stat = "4242 (intelli (router)) S 1 4242 4242 0 -1 4194560 \
+                    3190 0 0 0 250 70 0 0 20 0 12 0 1234 0";
+        assert_eq!(parse_cpu_ticks(stat), Some(320));
+        assert_eq!(
+            parse_rss_bytes("Name:\tintellirouter\nVmRSS:\t  204800 kB\nThreads:\t12\n"),
+            Some(200 * 1024 * 1024)
+        );
+        let limits = "Limit                     Soft Limit           Hard Limit           Units\n\
+                      Max open files            1024                 524288               files\n";
+        assert_eq!(parse_fd_limit(limits), Some(1024));
+    }
+
+    #[tokio::test]
+    async fn test_shed_batch_requests() {
+        let monitor = Arc::new(ResourceMonitor::new(
+            "router",
+            &ResourceMonitorConfig {
+                limits: ResourceLimitsConfig {
+                    memory_percent: Some(85.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ));
+        let app = with_resource_limits(
+            Router::new().route("/v1/chat/completions", post(|| async { "ok" })),
+            monitor.clone(),
+        );
+        let send = |workload: &'static str| {
+            let app = app.clone();
+            async move {
+                app.oneshot(
+                    Request::post("/v1/chat/completions")
+                        .header(WORKLOAD_HEADER, workload)
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+            }
+        };
+
+        monitor.record(ResourceSample::new(
+            None,
+            (Some(80), Some(100)),
+            (None, None),
+        ));
+        assert_eq!(send("batch").await, StatusCode::OK);
+
+        monitor.record(ResourceSample::new(
+            None,
+            (Some(90), Some(100)),
+            (None, None),
+        ));
+        assert_eq!(send("batch").await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(send("interactive").await, StatusCode::OK);
+
+        let status = monitor.status();
+        assert_eq!(status.over_limit, vec![Resource::Memory]);
+        assert_eq!(status.shed_requests, 1);
+    }
+}
@@ -0,0 +1,318 @@
+//! Embedded SQLite Storage
+//!
+//! The embedded storage profile keeps conversation memory, the model
+//! registry, the admin audit log and token quota usage in a single SQLite
+//! file, so a small deployment runs without Redis. SQLite is bundled with
+//! the build through `rusqlite`, so the profile needs no system library.
+//!
+//! Databases are opened in WAL mode, so readers don't block the writer, and
+//! wait `busy_timeout_ms` for a lock held by another connection. Each store
+//! opens its own connection to the file and creates its tables if needed.
+//! Statements run on a blocking thread when called from async code.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rusqlite::types::{ToSqlOutput, ValueRef};
+use rusqlite::{params_from_iter, Connection, ToSql};
+use thiserror::Error;
+
+use crate::config::StorageConfig;
+
+/// Errors raised by SQLite
+#[derive(Debug, Error)]
+pub enum SqliteError {
+    /// A statement failed
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    /// The database could not be opened
+    #[error("Failed to open SQLite database {path}: {message}")]
+    Open { path: String, message: String },
+    /// A blocking statement task failed
+    #[error("SQLite task failed: {0}")]
+    Task(String),
+}
+
+/// Value of a statement parameter or result column
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl SqlValue {
+    /// Integer value, if the value is one
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Text value, if the value is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Take the text value, if the value is one
+    pub fn into_text(self) -> Option<String> {
+        match self {
+            Self::Text(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(value: &str) -> Self {
+        Self::Text(value.to_string())
+    }
+}
+
+impl From<String> for SqlValue {
+    fn from(value: String) -> Self {
+        Self::Text(value)
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl<T: Into<SqlValue>> From<Option<T>> for SqlValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl From<ValueRef<'_>> for SqlValue {
+    fn from(value: ValueRef<'_>) -> Self {
+        match value {
+            ValueRef::Null => Self::Null,
+            ValueRef::Integer(value) => Self::Integer(value),
+            ValueRef::Real(value) => Self::Real(value),
+            ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                Self::Text(String::from_utf8_lossy(bytes).into_owned())
+            }
+        }
+    }
+}
+
+impl ToSql for SqlValue {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::Borrowed(match self {
+            Self::Null => ValueRef::Null,
+            Self::Integer(value) => ValueRef::Integer(*value),
+            Self::Real(value) => ValueRef::Real(*value),
+            Self::Text(value) => ValueRef::Text(value.as_bytes()),
+        }))
+    }
+}
+
+/// Row of a query result
+pub type SqlRow = Vec<SqlValue>;
+
+/// Connection to a SQLite database
+pub struct SqliteConnection {
+    connection: Connection,
+}
+
+impl SqliteConnection {
+    fn open(path: &Path, busy_timeout: Duration) -> Result<Self, SqliteError> {
+        let connection = Connection::open(path).map_err(|e| SqliteError::Open {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        connection.busy_timeout(busy_timeout)?;
+        let connection = Self { connection };
+        connection.execute_batch(
+            "PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL; PRAGMA foreign_keys = ON;",
+        )?;
+        Ok(connection)
+    }
+
+    /// Run statements separated by semicolons, without parameters
+    pub fn execute_batch(&self, sql: &str) -> Result<(), SqliteError> {
+        Ok(self.connection.execute_batch(sql)?)
+    }
+
+    /// Run a statement, returning the number of rows it changed
+    pub fn execute(&self, sql: &str, params: &[SqlValue]) -> Result<usize, SqliteError> {
+        let mut statement = self.connection.prepare(sql)?;
+        // Statements returning rows, like `RETURNING` clauses, run to
+        // completion
+        let mut rows = statement.query(params_from_iter(params))?;
+        while rows.next()?.is_some() {}
+        Ok(self.connection.changes() as usize)
+    }
+
+    /// Run a query, returning its rows
+    pub fn query(&self, sql: &str, params: &[SqlValue]) -> Result<Vec<SqlRow>, SqliteError> {
+        let mut statement = self.connection.prepare(sql)?;
+        let columns = statement.column_count();
+        let mut rows = statement.query(params_from_iter(params))?;
+        let mut result = Vec::new();
+        while let Some(row) = rows.next()? {
+            result.push(
+                (0..columns)
+                    .map(|column| row.get_ref(column).map(SqlValue::from))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Shared connection to the SQLite file of the embedded storage profile
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    path: PathBuf,
+    connection: Arc<Mutex<SqliteConnection>>,
+}
+
+impl SqliteDatabase {
+    /// Open the database of the storage configuration
+    pub fn from_config(config: &StorageConfig) -> Result<Self, SqliteError> {
+        Self::open(
+            &config.sqlite_path,
+            Duration::from_millis(config.busy_timeout_ms),
+        )
+    }
+
+    /// Open a database file, creating it and its directory if needed
+    pub fn open(path: impl AsRef<Path>, busy_timeout: Duration) -> Result<Self, SqliteError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent).map_err(|e| SqliteError::Open {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })?;
+        }
+        let connection = SqliteConnection::open(&path, busy_timeout)?;
+        Ok(Self {
+            path,
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// Path of the database file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Use the connection on the current thread
+    pub fn with<T>(
+        &self,
+        f: impl FnOnce(&SqliteConnection) -> Result<T, SqliteError>,
+    ) -> Result<T, SqliteError> {
+        let connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&connection)
+    }
+
+    /// Use the connection within a transaction, committed if `f` succeeds
+    pub fn transaction<T>(
+        &self,
+        f: impl FnOnce(&SqliteConnection) -> Result<T, SqliteError>,
+    ) -> Result<T, SqliteError> {
+        self.with(|connection| {
+            connection.execute_batch("BEGIN IMMEDIATE")?;
+            match f(connection) {
+                Ok(value) => {
+                    connection.execute_batch("COMMIT")?;
+                    Ok(value)
+                }
+                Err(e) => {
+                    connection.execute_batch("ROLLBACK").ok();
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    /// Use the connection within a transaction on a blocking thread
+    pub async fn call<T, F>(&self, f: F) -> Result<T, SqliteError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&SqliteConnection) -> Result<T, SqliteError> + Send + 'static,
+    {
+        let database = self.clone();
+        tokio::task::spawn_blocking(move || database.transaction(f))
+            .await
+            .map_err(|e| SqliteError::Task(e.to_string()))?
+    }
+}
+
+impl std::fmt::Debug for SqliteDatabase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteDatabase")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_and_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SqliteDatabase::open(dir.path().join("test.db"), Duration::from_secs(1)).unwrap();
+        db.with(|conn| {
+            conn.execute_batch("CREATE TABLE items (name TEXT PRIMARY KEY, count INTEGER)")?;
+            conn.execute(
+                "INSERT INTO items VALUES (?1, ?2), (?3, NULL)",
+                &["a".into(), 1.into(), "b'; --".into()],
+            )
+        })
+        .unwrap();
+
+        let rows = db
+            .with(|conn| conn.query("SELECT name, count FROM items ORDER BY name", &[]))
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![SqlValue::from("a"), SqlValue::Integer(1)],
+                vec![SqlValue::from("b'; --"), SqlValue::Null],
+            ]
+        );
+
+        let journal = db
+            .with(|conn| conn.query("PRAGMA journal_mode", &[]))
+            .unwrap();
+        assert_eq!(journal[0][0].as_str(), Some("wal"));
+    }
+
+    #[test]
+    fn test_transaction_rollback() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = SqliteDatabase::open(dir.path().join("test.db"), Duration::from_secs(1)).unwrap();
+        db.with(|conn| conn.execute_batch("CREATE TABLE items (name TEXT)"))
+            .unwrap();
+        let result: Result<(), _> = db.transaction(|conn| {
+            conn.execute("INSERT INTO items VALUES ('a')", &[])?;
+            conn.execute("INSERT INTO missing VALUES ('b')", &[])?;
+            Ok(())
+        });
+        assert!(result.is_err());
+        let rows = db
+            .with(|conn| conn.query("SELECT name FROM items", &[]))
+            .unwrap();
+        assert!(rows.is_empty());
+    }
+}
@@ -0,0 +1,340 @@
+//! Synthetic Traffic
+//!
+//! Health checks and monitoring scrapes hit the roles' servers around the
+//! clock. Requests are classified as synthetic when they come from one of
+//! the configured IP ranges, request one of the configured paths, or carry
+//! the user agent of a known health checker or scraper, as configured under
+//! `[server.synthetic_traffic]`. Synthetic requests are counted apart from
+//! real ones and logged at debug level.
+//!
+//! User agents are chosen by clients, so requests recognized by theirs alone
+//! are only kept quiet. Requests recognized by their address or path are
+//! also exempt from token quotas, and their denied admin requests are not
+//! audited.
+
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::header::USER_AGENT;
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::Response;
+use axum::Router;
+use metrics::counter;
+
+use crate::config::SyntheticTrafficConfig;
+use crate::modules::telemetry::catalog;
+
+tokio::task_local! {
+    /// Class of the request being handled
+    static CURRENT: TrafficClass;
+}
+
+/// What recognized a request as synthetic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntheticMatch {
+    /// Peer address in a configured IP range
+    IpRange,
+    /// Path under a configured prefix
+    Path,
+    /// User agent of a known health checker or scraper
+    UserAgent,
+}
+
+/// Class of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrafficClass {
+    /// Traffic of API clients
+    #[default]
+    Real,
+    /// Health checks and monitoring scrapes
+    Synthetic(SyntheticMatch),
+}
+
+impl TrafficClass {
+    /// Class of a request, once classified
+    pub fn of<B>(request: &axum::http::Request<B>) -> Self {
+        request
+            .extensions()
+            .get::<TrafficClass>()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Class of the request being handled by the current task
+    pub fn current() -> Self {
+        CURRENT.try_with(|class| *class).unwrap_or_default()
+    }
+
+    /// Whether the request is a health check or monitoring scrape
+    pub fn is_synthetic(&self) -> bool {
+        matches!(self, Self::Synthetic(_))
+    }
+
+    /// Whether the request is exempt from token quotas and denial audits,
+    /// having been recognized by something other than its user agent
+    pub fn is_exempt(&self) -> bool {
+        matches!(
+            self,
+            Self::Synthetic(SyntheticMatch::IpRange | SyntheticMatch::Path)
+        )
+    }
+
+    /// Label of the class in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Real => "real",
+            Self::Synthetic(_) => "synthetic",
+        }
+    }
+
+    /// Label of what recognized the request in metrics
+    fn matched(&self) -> &'static str {
+        match self {
+            Self::Real => "none",
+            Self::Synthetic(SyntheticMatch::IpRange) => "ip_range",
+            Self::Synthetic(SyntheticMatch::Path) => "path",
+            Self::Synthetic(SyntheticMatch::UserAgent) => "user_agent",
+        }
+    }
+}
+
+/// IP address or CIDR range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Whether an address is in the range
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        // IPv4 peers of dual-stack sockets appear as mapped IPv6 addresses
+        let addr = match addr {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+            IpAddr::V4(_) => addr,
+        };
+        match (self.network, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - self.prefix_len as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = match s.split_once('/') {
+            Some((addr, prefix_len)) => (addr, Some(prefix_len)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("Invalid IP range: {}", s))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(len) => len
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| format!("Invalid IP range: {}", s))?,
+            None => max_len,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+/// Classifier of the requests of a role
+#[derive(Debug, Clone)]
+pub struct TrafficClassifier {
+    enabled: bool,
+    /// Lowercased user agent substrings
+    user_agents: Vec<String>,
+    ip_ranges: Vec<IpRange>,
+    paths: Vec<String>,
+}
+
+impl TrafficClassifier {
+    /// Classifier of the configuration; invalid IP ranges are skipped, as
+    /// configuration validation reports them
+    pub fn from_config(config: &SyntheticTrafficConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            user_agents: config
+                .user_agents
+                .iter()
+                .map(|agent| agent.to_lowercase())
+                .collect(),
+            ip_ranges: config
+                .ip_ranges
+                .iter()
+                .filter_map(|range| range.parse().ok())
+                .collect(),
+            paths: config.paths.clone(),
+        }
+    }
+
+    /// Classify a request by its peer address, path and user agent
+    pub fn classify(
+        &self,
+        peer: Option<IpAddr>,
+        path: &str,
+        user_agent: Option<&str>,
+    ) -> TrafficClass {
+        if !self.enabled {
+            return TrafficClass::Real;
+        }
+        if peer.is_some_and(|peer| self.ip_ranges.iter().any(|range| range.contains(peer))) {
+            return TrafficClass::Synthetic(SyntheticMatch::IpRange);
+        }
+        if self.paths.iter().any(|prefix| path.starts_with(prefix)) {
+            return TrafficClass::Synthetic(SyntheticMatch::Path);
+        }
+        let user_agent = user_agent.unwrap_or_default().to_lowercase();
+        if !user_agent.is_empty()
+            && self
+                .user_agents
+                .iter()
+                .any(|agent| user_agent.contains(agent))
+        {
+            return TrafficClass::Synthetic(SyntheticMatch::UserAgent);
+        }
+        TrafficClass::Real
+    }
+}
+
+/// Classify a role's requests as real or synthetic
+pub fn with_traffic_classification(app: Router, config: &SyntheticTrafficConfig) -> Router {
+    app.layer(from_fn_with_state(
+        Arc::new(TrafficClassifier::from_config(config)),
+        classification_middleware,
+    ))
+}
+
+/// Middleware classifying requests, counting them by class and keeping
+/// their class for the layers and handlers below
+pub async fn classification_middleware(
+    State(classifier): State<Arc<TrafficClassifier>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let user_agent = request
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok());
+    let class = classifier.classify(peer, request.uri().path(), user_agent);
+    counter!(
+        catalog::HTTP_TRAFFIC, 1,
+        "class" => class.as_str(),
+        "matched" => class.matched()
+    );
+
+    request.extensions_mut().insert(class);
+    CURRENT.scope(class, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn classifier() -> TrafficClassifier {
+        TrafficClassifier::from_config(&SyntheticTrafficConfig {
+            ip_ranges: vec!["10.1.0.0/16".to_string(), "fd00::1".to_string()],
+            ..SyntheticTrafficConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_ip_ranges() {
+        let range: IpRange = "10.1.0.0/16".parse().unwrap();
+        assert!(range.contains("10.1.200.3".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.0.9".parse().unwrap()));
+        assert!(!range.contains("10.2.0.1".parse().unwrap()));
+        assert!("0.0.0.0/0"
+            .parse::<IpRange>()
+            .unwrap()
+            .contains("192.0.2.1".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("example.com".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn test_classify() {
+        let classifier = classifier();
+        let chat = "/v1/chat/completions";
+
+        assert_eq!(
+            classifier.classify(Some("10.1.4.4".parse().unwrap()), chat, None),
+            TrafficClass::Synthetic(SyntheticMatch::IpRange)
+        );
+        assert_eq!(
+            classifier.classify(None, "/readiness", Some("curl/8.0")),
+            TrafficClass::Synthetic(SyntheticMatch::Path)
+        );
+        let probe = classifier.classify(None, chat, Some("kube-probe/1.29"));
+        assert_eq!(probe, TrafficClass::Synthetic(SyntheticMatch::UserAgent));
+        assert!(probe.is_synthetic() && !probe.is_exempt());
+        assert_eq!(
+            classifier.classify(
+                Some("192.0.2.1".parse().unwrap()),
+                chat,
+                Some("openai-python")
+            ),
+            TrafficClass::Real
+        );
+
+        let disabled = TrafficClassifier::from_config(&SyntheticTrafficConfig {
+            enabled: false,
+            ..SyntheticTrafficConfig::default()
+        });
+        assert_eq!(disabled.classify(None, "/health", None), TrafficClass::Real);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_keeps_class() {
+        let app = with_traffic_classification(
+            Router::new().route(
+                "/health",
+                get(|request: Request| async move {
+                    assert_eq!(TrafficClass::of(&request), TrafficClass::current());
+                    TrafficClass::current().as_str()
+                }),
+            ),
+            &SyntheticTrafficConfig::default(),
+        );
+        let response = app
+            .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"synthetic");
+        assert_eq!(TrafficClass::current(), TrafficClass::Real);
+    }
+}
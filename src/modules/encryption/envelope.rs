@@ -0,0 +1,241 @@
+//! Envelope Encryption
+//!
+//! This module seals records with AES-256-GCM under a fresh data key and
+//! stores the data key, wrapped by the key provider, alongside the
+//! ciphertext. Sealed records are strings starting with [`ENVELOPE_PREFIX`],
+//! so stores can tell them apart from plaintext written before encryption
+//! was enabled.
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use super::keys::{KeyProvider, WrappedKey, KEY_LEN};
+use super::EncryptionError;
+
+/// Prefix of sealed records
+pub const ENVELOPE_PREFIX: &str = "enc:v1:";
+
+/// A sealed record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    /// Data key wrapped by the key provider
+    pub key: WrappedKey,
+    /// Base64-encoded nonce
+    pub nonce: String,
+    /// Base64-encoded ciphertext and authentication tag
+    pub ciphertext: String,
+}
+
+impl Envelope {
+    /// Parse a sealed record
+    pub fn parse(sealed: &str) -> Result<Self, EncryptionError> {
+        let json = sealed.strip_prefix(ENVELOPE_PREFIX).ok_or_else(|| {
+            EncryptionError::InvalidEnvelope("Missing envelope prefix".to_string())
+        })?;
+        serde_json::from_str(json).map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))
+    }
+
+    fn encode(&self) -> Result<String, EncryptionError> {
+        let json =
+            serde_json::to_string(self).map_err(|e| EncryptionError::Encrypt(e.to_string()))?;
+        Ok(format!("{}{}", ENVELOPE_PREFIX, json))
+    }
+}
+
+/// Seals and opens records with envelope encryption
+pub struct EnvelopeEncryptor {
+    provider: Arc<dyn KeyProvider>,
+}
+
+impl EnvelopeEncryptor {
+    /// Create an encryptor wrapping data keys with a key provider
+    pub fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// Key provider wrapping data keys
+    pub fn provider(&self) -> &Arc<dyn KeyProvider> {
+        &self.provider
+    }
+
+    /// Whether a stored record is sealed
+    pub fn is_sealed(data: &str) -> bool {
+        data.starts_with(ENVELOPE_PREFIX)
+    }
+
+    /// Seal a record
+    ///
+    /// `aad` binds the ciphertext to its context, typically the storage key,
+    /// so a sealed record cannot be moved to another key.
+    pub async fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<String, EncryptionError> {
+        let data_key = random_bytes(KEY_LEN)?;
+        let (nonce, ciphertext) = seal(&data_key, aad, plaintext)?;
+        Envelope {
+            key: self.provider.wrap_key(&data_key).await?,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        }
+        .encode()
+    }
+
+    /// Open a sealed record
+    pub async fn open(&self, sealed: &str, aad: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let envelope = Envelope::parse(sealed)?;
+        let data_key = self.provider.unwrap_key(&envelope.key).await?;
+        let nonce = STANDARD
+            .decode(&envelope.nonce)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        let ciphertext = STANDARD
+            .decode(&envelope.ciphertext)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        open(&data_key, &nonce, aad, &ciphertext)
+    }
+
+    /// Seal a string record
+    pub async fn seal_str(&self, plaintext: &str, aad: &str) -> Result<String, EncryptionError> {
+        self.seal(plaintext.as_bytes(), aad.as_bytes()).await
+    }
+
+    /// Open a string record, passing plaintext records through unchanged
+    ///
+    /// Plaintext is accepted so data written before encryption was enabled
+    /// stays readable until it is migrated.
+    pub async fn open_str(&self, data: String, aad: &str) -> Result<String, EncryptionError> {
+        if !Self::is_sealed(&data) {
+            return Ok(data);
+        }
+        let plaintext = self.open(&data, aad.as_bytes()).await?;
+        String::from_utf8(plaintext).map_err(|e| EncryptionError::Decrypt(e.to_string()))
+    }
+
+    /// Rewrap the data key of a sealed record with the active master key
+    ///
+    /// Returns `None` if the record already uses the active key. The
+    /// ciphertext itself is left untouched.
+    pub async fn rotate(&self, sealed: &str) -> Result<Option<String>, EncryptionError> {
+        let mut envelope = Envelope::parse(sealed)?;
+        if envelope.key.key_id == self.provider.active_key_id().await? {
+            return Ok(None);
+        }
+        envelope.key = self.provider.rewrap_key(&envelope.key).await?;
+        envelope.encode().map(Some)
+    }
+}
+
+/// Generate cryptographically secure random bytes
+fn random_bytes(len: usize) -> Result<Vec<u8>, EncryptionError> {
+    let mut bytes = vec![0u8; len];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| EncryptionError::Encrypt("Random number generation failed".to_string()))?;
+    Ok(bytes)
+}
+
+fn aead_key(key: &[u8]) -> Result<LessSafeKey, EncryptionError> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| EncryptionError::Key(format!("Keys must be {} bytes long", KEY_LEN)))
+}
+
+/// Encrypt with AES-256-GCM under a random nonce, returning the nonce and ciphertext
+pub(crate) fn seal(
+    key: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), EncryptionError> {
+    let key = aead_key(key)?;
+    let nonce = random_bytes(NONCE_LEN)?;
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::try_assume_unique_for_key(&nonce)
+            .map_err(|_| EncryptionError::Encrypt("Invalid nonce".to_string()))?,
+        Aad::from(aad),
+        &mut in_out,
+    )
+    .map_err(|_| EncryptionError::Encrypt("AES-GCM encryption failed".to_string()))?;
+    Ok((nonce, in_out))
+}
+
+/// Decrypt AES-256-GCM ciphertext
+pub(crate) fn open(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    let key = aead_key(key)?;
+    let nonce = Nonce::try_assume_unique_for_key(nonce)
+        .map_err(|_| EncryptionError::InvalidEnvelope("Invalid nonce".to_string()))?;
+    let mut in_out = ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(nonce, Aad::from(aad), &mut in_out)
+        .map_err(|_| EncryptionError::Decrypt("Authentication failed or wrong key".to_string()))?;
+    Ok(plaintext.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::encryption::LocalKeyProvider;
+    use std::collections::HashMap;
+
+    fn encryptor(active: &str, ids: &[&str]) -> EnvelopeEncryptor {
+        let keys = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.to_string(), vec![i as u8 + 1; KEY_LEN]))
+            .collect::<HashMap<_, _>>();
+        EnvelopeEncryptor::new(Arc::new(LocalKeyProvider::new(active, keys).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_seal_and_open() {
+        let encryptor = encryptor("k1", &["k1"]);
+        let sealed = encryptor.seal_str("secret", "conv:1").await.unwrap();
+
+        assert!(EnvelopeEncryptor::is_sealed(&sealed));
+        assert!(!sealed.contains("secret"));
+        assert_eq!(
+            encryptor.open_str(sealed.clone(), "conv:1").await.unwrap(),
+            "secret"
+        );
+        // Sealed records are bound to their key
+        assert!(encryptor.open_str(sealed, "conv:2").await.is_err());
+        // Plaintext passes through
+        assert_eq!(
+            encryptor
+                .open_str("plain".to_string(), "conv:1")
+                .await
+                .unwrap(),
+            "plain"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rotation() {
+        let old = encryptor("k1", &["k1"]);
+        let sealed = old.seal_str("secret", "a").await.unwrap();
+
+        let rotated = encryptor("k2", &["k1", "k2"]);
+        let rewrapped = rotated.rotate(&sealed).await.unwrap().unwrap();
+        assert_eq!(Envelope::parse(&rewrapped).unwrap().key.key_id, "k2");
+        assert_eq!(rotated.rotate(&rewrapped).await.unwrap(), None);
+        assert_eq!(rotated.open_str(rewrapped, "a").await.unwrap(), "secret");
+
+        // Retiring a key makes records it still wraps unreadable
+        let retired = encryptor("k2", &["k2"]);
+        assert!(retired.open_str(sealed, "a").await.is_err());
+    }
+
+    #[test]
+    fn test_local_key_validation() {
+        let keys = HashMap::from([("k1".to_string(), vec![0u8; 16])]);
+        assert!(LocalKeyProvider::new("k1", keys).is_err());
+        let keys = HashMap::from([("k1".to_string(), vec![0u8; KEY_LEN])]);
+        assert!(LocalKeyProvider::new("k2", keys).is_err());
+    }
+}
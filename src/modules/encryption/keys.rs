@@ -0,0 +1,286 @@
+//! Key Providers
+//!
+//! This module defines the master keys wrapping data keys. Local keys are
+//! read from the configuration; Vault keys never leave Vault and data keys
+//! are wrapped through its transit secrets engine.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::envelope::{open, seal};
+use super::EncryptionError;
+use crate::config::VaultKeyConfig;
+
+/// Length in bytes of AES-256 keys
+pub(crate) const KEY_LEN: usize = 32;
+
+/// Data key wrapped by a master key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedKey {
+    /// Identifier of the master key version that wrapped the data key
+    pub key_id: String,
+    /// Wrapped data key, in the provider's encoding
+    pub ciphertext: String,
+}
+
+/// Holder of the master keys wrapping data keys
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// Provider name
+    fn name(&self) -> &'static str;
+
+    /// Identifier of the master key version new data keys are wrapped with
+    async fn active_key_id(&self) -> Result<String, EncryptionError>;
+
+    /// Wrap a data key with the active master key
+    async fn wrap_key(&self, data_key: &[u8]) -> Result<WrappedKey, EncryptionError>;
+
+    /// Unwrap a data key
+    async fn unwrap_key(&self, wrapped: &WrappedKey) -> Result<Vec<u8>, EncryptionError>;
+
+    /// Rewrap a data key with the active master key
+    async fn rewrap_key(&self, wrapped: &WrappedKey) -> Result<WrappedKey, EncryptionError> {
+        let data_key = self.unwrap_key(wrapped).await?;
+        self.wrap_key(&data_key).await
+    }
+}
+
+/// Master keys listed in the configuration
+///
+/// Rotation adds a key and makes it active; retired keys stay listed so
+/// data keys they wrapped can still be unwrapped.
+pub struct LocalKeyProvider {
+    active: String,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl LocalKeyProvider {
+    /// Create a provider from raw keys
+    pub fn new(
+        active: impl Into<String>,
+        keys: HashMap<String, Vec<u8>>,
+    ) -> Result<Self, EncryptionError> {
+        let active = active.into();
+        if let Some((id, _)) = keys.iter().find(|(_, key)| key.len() != KEY_LEN) {
+            return Err(EncryptionError::Key(format!(
+                "Key '{}' must be {} bytes long",
+                id, KEY_LEN
+            )));
+        }
+        if !keys.contains_key(&active) {
+            return Err(EncryptionError::Key(format!(
+                "Active key '{}' is not configured",
+                active
+            )));
+        }
+        Ok(Self { active, keys })
+    }
+
+    /// Create a provider from base64-encoded keys or `env:VAR` references
+    pub fn from_config(
+        active: &str,
+        keys: &HashMap<String, String>,
+    ) -> Result<Self, EncryptionError> {
+        let keys = keys
+            .iter()
+            .map(|(id, value)| {
+                let encoded = match value.strip_prefix("env:") {
+                    Some(var) => std::env::var(var).map_err(|_| {
+                        EncryptionError::Key(format!(
+                            "Environment variable {} of key '{}' is not set",
+                            var, id
+                        ))
+                    })?,
+                    None => value.clone(),
+                };
+                let key = STANDARD.decode(encoded.trim()).map_err(|e| {
+                    EncryptionError::Key(format!("Key '{}' is not valid base64: {}", id, e))
+                })?;
+                Ok((id.clone(), key))
+            })
+            .collect::<Result<_, EncryptionError>>()?;
+        Self::new(active, keys)
+    }
+
+    fn key(&self, id: &str) -> Result<&[u8], EncryptionError> {
+        self.keys
+            .get(id)
+            .map(Vec::as_slice)
+            .ok_or_else(|| EncryptionError::Key(format!("Unknown key '{}'", id)))
+    }
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn active_key_id(&self) -> Result<String, EncryptionError> {
+        Ok(self.active.clone())
+    }
+
+    async fn wrap_key(&self, data_key: &[u8]) -> Result<WrappedKey, EncryptionError> {
+        let (nonce, ciphertext) = seal(self.key(&self.active)?, self.active.as_bytes(), data_key)?;
+        Ok(WrappedKey {
+            key_id: self.active.clone(),
+            ciphertext: STANDARD.encode([nonce, ciphertext].concat()),
+        })
+    }
+
+    async fn unwrap_key(&self, wrapped: &WrappedKey) -> Result<Vec<u8>, EncryptionError> {
+        let bytes = STANDARD
+            .decode(&wrapped.ciphertext)
+            .map_err(|e| EncryptionError::InvalidEnvelope(e.to_string()))?;
+        if bytes.len() < ring::aead::NONCE_LEN {
+            return Err(EncryptionError::InvalidEnvelope(
+                "Wrapped key is truncated".to_string(),
+            ));
+        }
+        let (nonce, ciphertext) = bytes.split_at(ring::aead::NONCE_LEN);
+        open(
+            self.key(&wrapped.key_id)?,
+            nonce,
+            wrapped.key_id.as_bytes(),
+            ciphertext,
+        )
+    }
+}
+
+/// Master key held by a Vault transit secrets engine
+///
+/// Key versions come from Vault's ciphertext prefix (`vault:v<N>:`), so
+/// rotating the key in Vault and running the migration tool rewraps data
+/// keys without Vault ever releasing the master key.
+pub struct VaultKeyProvider {
+    client: Client,
+    config: VaultKeyConfig,
+    token: String,
+}
+
+impl VaultKeyProvider {
+    /// Create a provider from configuration
+    pub fn from_config(config: &VaultKeyConfig) -> Result<Self, EncryptionError> {
+        let token = config
+            .token
+            .clone()
+            .or_else(|| std::env::var("VAULT_TOKEN").ok())
+            .ok_or_else(|| EncryptionError::Key("No Vault token is configured".to_string()))?;
+        Ok(Self {
+            client: Client::new(),
+            config: config.clone(),
+            token,
+        })
+    }
+
+    fn url(&self, operation: &str) -> String {
+        format!(
+            "{}/v1/{}/{}/{}",
+            self.config.address.trim_end_matches('/'),
+            self.config.mount,
+            operation,
+            self.config.key_name
+        )
+    }
+
+    /// Key identifier of a Vault ciphertext
+    fn key_id(&self, ciphertext: &str) -> Result<String, EncryptionError> {
+        let version = ciphertext
+            .strip_prefix("vault:")
+            .and_then(|rest| rest.split(':').next())
+            .ok_or_else(|| {
+                EncryptionError::InvalidEnvelope("Not a Vault ciphertext".to_string())
+            })?;
+        Ok(format!("vault:{}:{}", self.config.key_name, version))
+    }
+
+    async fn call(&self, operation: &str, body: Option<Value>) -> Result<Value, EncryptionError> {
+        let request = match body {
+            Some(body) => self.client.post(self.url(operation)).json(&body),
+            None => self.client.get(self.url(operation)),
+        };
+        let response = request
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .map_err(|e| EncryptionError::Provider(e.to_string()))?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| EncryptionError::Provider(e.to_string()))?;
+        if !status.is_success() {
+            return Err(EncryptionError::Provider(format!(
+                "Vault returned {}: {}",
+                status, body
+            )));
+        }
+        Ok(body)
+    }
+
+    fn data_field<'a>(body: &'a Value, field: &str) -> Result<&'a Value, EncryptionError> {
+        body.get("data")
+            .and_then(|data| data.get(field))
+            .ok_or_else(|| EncryptionError::Provider(format!("Vault response lacks {}", field)))
+    }
+
+    fn wrapped(&self, body: &Value) -> Result<WrappedKey, EncryptionError> {
+        let ciphertext = Self::data_field(body, "ciphertext")?
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        Ok(WrappedKey {
+            key_id: self.key_id(&ciphertext)?,
+            ciphertext,
+        })
+    }
+}
+
+#[async_trait]
+impl KeyProvider for VaultKeyProvider {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn active_key_id(&self) -> Result<String, EncryptionError> {
+        let body = self.call("keys", None).await?;
+        let version = Self::data_field(&body, "latest_version")?
+            .as_u64()
+            .unwrap_or(1);
+        Ok(format!("vault:{}:v{}", self.config.key_name, version))
+    }
+
+    async fn wrap_key(&self, data_key: &[u8]) -> Result<WrappedKey, EncryptionError> {
+        let body = self
+            .call(
+                "encrypt",
+                Some(json!({ "plaintext": STANDARD.encode(data_key) })),
+            )
+            .await?;
+        self.wrapped(&body)
+    }
+
+    async fn unwrap_key(&self, wrapped: &WrappedKey) -> Result<Vec<u8>, EncryptionError> {
+        let body = self
+            .call("decrypt", Some(json!({ "ciphertext": wrapped.ciphertext })))
+            .await?;
+        let plaintext = Self::data_field(&body, "plaintext")?
+            .as_str()
+            .unwrap_or_default();
+        STANDARD
+            .decode(plaintext)
+            .map_err(|e| EncryptionError::Provider(format!("Invalid data key: {}", e)))
+    }
+
+    async fn rewrap_key(&self, wrapped: &WrappedKey) -> Result<WrappedKey, EncryptionError> {
+        let body = self
+            .call("rewrap", Some(json!({ "ciphertext": wrapped.ciphertext })))
+            .await?;
+        self.wrapped(&body)
+    }
+}
@@ -0,0 +1,193 @@
+//! Erasure API
+//!
+//! This module serves data subject erasure requests over HTTP. Erasing and
+//! reading erasure reports need the admin role. Erasures are recorded as
+//! audit events naming the report rather than the subject, so the audit log
+//! keeps no trace of who was erased.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use utoipa::OpenApi;
+
+use super::{
+    ErasureError, ErasureReport, ErasureService, ErasureSubject, SignedErasureReport, TargetOutcome,
+};
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{self, AdminAuditLog, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// State of the erasure API
+struct ErasureApiState {
+    service: Arc<ErasureService>,
+    /// Admin keys
+    proxy: ProxyConfig,
+    /// Audit events of erasures
+    audit: Arc<AdminAuditLog>,
+}
+
+/// OpenAPI description of the erasure API
+#[derive(OpenApi)]
+#[openapi(
+    paths(erase_subject, list_reports, get_report),
+    components(schemas(ErasureSubject, SignedErasureReport, ErasureReport, TargetOutcome))
+)]
+pub struct ErasureApiDoc;
+
+/// Create a router for the erasure API
+pub fn create_erasure_router(
+    service: Arc<ErasureService>,
+    proxy: ProxyConfig,
+    audit: Arc<AdminAuditLog>,
+) -> Router {
+    let state = Arc::new(ErasureApiState {
+        service,
+        proxy,
+        audit,
+    });
+    Router::new()
+        .route("/v1/admin/erasure", post(erase_subject))
+        .route("/v1/admin/erasure/reports", get(list_reports))
+        .route("/v1/admin/erasure/reports/{id}", get(get_report))
+        .with_state(state)
+}
+
+/// Build an error response
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "invalid_request_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Map an erasure error to an error response
+fn erasure_error(e: &ErasureError) -> Response {
+    let (status, code) = match e {
+        ErasureError::SigningKeyMissing => (StatusCode::SERVICE_UNAVAILABLE, "erasure_disabled"),
+        ErasureError::InvalidSubject(_) | ErasureError::Memory(_) => {
+            (StatusCode::BAD_REQUEST, "invalid_subject")
+        }
+        ErasureError::InvalidSignature
+        | ErasureError::Serialization(_)
+        | ErasureError::Payloads(_)
+        | ErasureError::Export(_) => (StatusCode::INTERNAL_SERVER_ERROR, "erasure_failed"),
+    };
+    error_response(status, e.to_string(), code)
+}
+
+/// Erase a tenant, or one of its users, and return the signed report
+///
+/// Targets that fail are listed in the report, which is then not complete.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/erasure",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    request_body = ErasureSubject,
+    responses(
+        (status = 200, description = "Signed erasure report", body = SignedErasureReport),
+        (status = 400, description = "Invalid subject", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "No erasure report signing key is configured", body = ApiError)
+    )
+)]
+async fn erase_subject(
+    State(state): State<Arc<ErasureApiState>>,
+    headers: HeaderMap,
+    Json(subject): Json<ErasureSubject>,
+) -> Response {
+    let action = "erasure.erase";
+    let principal = match admin::authorize(&state.proxy, &headers, AdminRole::Admin) {
+        Ok(principal) => principal,
+        Err(e) => {
+            state.audit.record(Err(&e), action, "erasure", Ok(()));
+            return e.into_response();
+        }
+    };
+
+    match state
+        .service
+        .erase(subject, Some(principal.subject.clone()))
+        .await
+    {
+        Ok(signed) => {
+            let outcome = if signed.report.complete {
+                Ok(())
+            } else {
+                Err("incomplete erasure".to_string())
+            };
+            let resource = format!("erasure/{}", signed.report.id);
+            state
+                .audit
+                .record(Ok(&principal), action, &resource, outcome);
+            Json(signed).into_response()
+        }
+        Err(e) => {
+            state
+                .audit
+                .record(Ok(&principal), action, "erasure", Err(e.to_string()));
+            erasure_error(&e)
+        }
+    }
+}
+
+/// Recent signed erasure reports, oldest first
+#[utoipa::path(
+    get,
+    path = "/v1/admin/erasure/reports",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent signed erasure reports", body = Vec<SignedErasureReport>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_reports(State(state): State<Arc<ErasureApiState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Admin) {
+        return e.into_response();
+    }
+    Json(state.service.reports()).into_response()
+}
+
+/// A signed erasure report
+#[utoipa::path(
+    get,
+    path = "/v1/admin/erasure/reports/{id}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Report ID")),
+    responses(
+        (status = 200, description = "Signed erasure report", body = SignedErasureReport),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown or evicted report", body = ApiError)
+    )
+)]
+async fn get_report(
+    State(state): State<Arc<ErasureApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Admin) {
+        return e.into_response();
+    }
+    match state.service.report(&id) {
+        Some(signed) => Json(signed).into_response(),
+        None => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Erasure report not found: {}", id),
+            "erasure_report_not_found",
+        ),
+    }
+}
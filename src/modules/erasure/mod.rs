@@ -0,0 +1,663 @@
+//! Data Subject Erasure
+//!
+//! This module serves erasure requests, e.g. under GDPR, for a tenant or for
+//! one user of a tenant. Every subsystem holding their data is an
+//! [`ErasureTarget`]: conversations, semantic memories, prompt traces,
+//! captured payloads and the telemetry records exported to ClickHouse are
+//! purged, while the logs kept as operational records (routing decisions,
+//! served prompts and admin audit events) are kept with the subject replaced
+//! by a pseudonym unique to the erasure. Cached RAG retrieval results are not
+//! attributed to tenants, so the whole cache is dropped.
+//!
+//! Each erasure produces a report of what every target purged or
+//! anonymized, signed with an HMAC key so it can be handed out as proof of
+//! erasure. A failing target does not stop the others; it is reported and
+//! the report is marked incomplete.
+//!
+//! Each role erases the subsystems it runs, so requests must reach every
+//! role holding the subject's data. Some data is out of reach and must be
+//! erased where it lives:
+//!
+//! - documents indexed by the RAG sources, which are not attributed to
+//!   tenants or users
+//! - telemetry exported to BigQuery, whose streaming API cannot delete rows
+//! - compliance reports already written to their bucket
+//! - log pipelines and billing events
+
+pub mod api;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config::ErasureConfig;
+use crate::modules::llm_proxy::admin::AdminAuditLog;
+use crate::modules::llm_proxy::decision_log::DecisionLog;
+use crate::modules::llm_proxy::payloads::{PayloadError, PayloadStore};
+use crate::modules::llm_proxy::prompt_trace::PromptTraceLog;
+use crate::modules::llm_proxy::prompts::PromptRegistry;
+use crate::modules::llm_proxy::user_usage::UserUsageLog;
+use crate::modules::memory::{MemoryError, MemoryManager, MemoryNamespace, SemanticMemory};
+use crate::modules::rag_manager::RagManager;
+use crate::modules::telemetry::export::{ClickHouseSink, ExportError};
+use crate::modules::telemetry::CostCalculator;
+
+/// Errors that can occur when erasing a data subject
+#[derive(Error, Debug)]
+pub enum ErasureError {
+    #[error("Erasure report signing key is not configured")]
+    SigningKeyMissing,
+
+    #[error("Invalid erasure subject: {0}")]
+    InvalidSubject(String),
+
+    #[error("Erasure report signature is invalid")]
+    InvalidSignature,
+
+    #[error("Memory error: {0}")]
+    Memory(#[from] MemoryError),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Payload store error: {0}")]
+    Payloads(#[from] PayloadError),
+
+    #[error("Telemetry export error: {0}")]
+    Export(#[from] ExportError),
+}
+
+/// Tenant, or user of a tenant, whose data is erased
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ErasureSubject {
+    /// Tenant ID
+    pub tenant: String,
+    /// End user ID; the whole tenant is erased when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+}
+
+impl ErasureSubject {
+    /// Create a subject, rejecting empty identifiers
+    pub fn new(tenant: impl Into<String>, user: Option<String>) -> Result<Self, ErasureError> {
+        let subject = Self {
+            tenant: tenant.into(),
+            user,
+        };
+        subject.validate()?;
+        Ok(subject)
+    }
+
+    /// Reject empty identifiers
+    pub fn validate(&self) -> Result<(), ErasureError> {
+        if self.tenant.trim().is_empty() {
+            return Err(ErasureError::InvalidSubject("tenant is empty".to_string()));
+        }
+        if self
+            .user
+            .as_deref()
+            .is_some_and(|user| user.trim().is_empty())
+        {
+            return Err(ErasureError::InvalidSubject("user is empty".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether data of a tenant and user belongs to the subject
+    pub fn matches(&self, tenant: Option<&str>, user: Option<&str>) -> bool {
+        tenant == Some(self.tenant.as_str())
+            && (self.user.is_none() || user == self.user.as_deref())
+    }
+}
+
+/// What a target erased
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Erased {
+    /// Records deleted
+    pub purged: usize,
+    /// Records kept with the subject replaced by the pseudonym
+    pub anonymized: usize,
+}
+
+/// Subsystem holding data of tenants and their users
+#[async_trait]
+pub trait ErasureTarget: Send + Sync {
+    /// Name of the target in erasure reports
+    fn name(&self) -> &'static str;
+
+    /// Purge or anonymize the subject's data, replacing the subject by
+    /// `pseudonym` where records are kept
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        pseudonym: &str,
+    ) -> Result<Erased, ErasureError>;
+}
+
+#[async_trait]
+impl ErasureTarget for MemoryManager {
+    fn name(&self) -> &'static str {
+        "memory.conversations"
+    }
+
+    async fn erase(&self, subject: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        let purged = match &subject.user {
+            Some(user) => {
+                self.delete_namespace(&MemoryNamespace::new(&subject.tenant, user)?)
+                    .await?
+            }
+            None => self.delete_tenant(&subject.tenant).await?,
+        };
+        Ok(Erased {
+            purged,
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for SemanticMemory {
+    fn name(&self) -> &'static str {
+        "memory.semantic"
+    }
+
+    async fn erase(&self, subject: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        let purged = match &subject.user {
+            Some(user) => {
+                self.store()
+                    .delete_namespace(&MemoryNamespace::new(&subject.tenant, user)?)
+                    .await?
+            }
+            None => self.store().delete_tenant(&subject.tenant).await?,
+        };
+        Ok(Erased {
+            purged,
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for DecisionLog {
+    fn name(&self) -> &'static str {
+        "routing.decisions"
+    }
+
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        pseudonym: &str,
+    ) -> Result<Erased, ErasureError> {
+        let (purged, anonymized) = DecisionLog::erase(self, subject, pseudonym);
+        Ok(Erased { purged, anonymized })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for UserUsageLog {
+    fn name(&self) -> &'static str {
+        "usage.users"
+    }
+
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        _pseudonym: &str,
+    ) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: UserUsageLog::erase(self, subject),
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for CostCalculator {
+    fn name(&self) -> &'static str {
+        "pricing.ledger"
+    }
+
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        _pseudonym: &str,
+    ) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: CostCalculator::erase(self, subject),
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for PromptRegistry {
+    fn name(&self) -> &'static str {
+        "prompts.served"
+    }
+
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        pseudonym: &str,
+    ) -> Result<Erased, ErasureError> {
+        let (purged, anonymized) = self.erase_served(subject, pseudonym);
+        Ok(Erased { purged, anonymized })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for PromptTraceLog {
+    fn name(&self) -> &'static str {
+        "prompt_traces"
+    }
+
+    async fn erase(&self, subject: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: PromptTraceLog::erase(self, subject),
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for PayloadStore {
+    fn name(&self) -> &'static str {
+        "payloads"
+    }
+
+    async fn erase(&self, subject: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: PayloadStore::erase(self, subject).await?,
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for AdminAuditLog {
+    fn name(&self) -> &'static str {
+        "admin.audit"
+    }
+
+    async fn erase(
+        &self,
+        subject: &ErasureSubject,
+        pseudonym: &str,
+    ) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: 0,
+            anonymized: self.anonymize(subject, pseudonym).await,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for ClickHouseSink {
+    fn name(&self) -> &'static str {
+        "telemetry.clickhouse"
+    }
+
+    async fn erase(&self, subject: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        Ok(Erased {
+            purged: self
+                .delete_subject(&subject.tenant, subject.user.as_deref())
+                .await?,
+            anonymized: 0,
+        })
+    }
+}
+
+#[async_trait]
+impl ErasureTarget for RagManager {
+    fn name(&self) -> &'static str {
+        "rag.retrieval_cache"
+    }
+
+    async fn erase(&self, _: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+        // Cached queries are not attributed to tenants, so any may be the
+        // subject's
+        Ok(Erased {
+            purged: self.retrieval_cache().map_or(0, |cache| cache.clear()),
+            anonymized: 0,
+        })
+    }
+}
+
+/// Outcome of an erasure at one target
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TargetOutcome {
+    /// Target name
+    pub target: String,
+    /// Records deleted
+    pub purged: usize,
+    /// Records kept with the subject replaced by the pseudonym
+    pub anonymized: usize,
+    /// Why the target failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Record of an erasure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ErasureReport {
+    /// Report identifier
+    pub id: String,
+    /// Erased subject
+    pub subject: ErasureSubject,
+    /// Pseudonym replacing the subject in the records that were kept
+    pub pseudonym: String,
+    /// Admin principal that requested the erasure
+    pub requested_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    /// Outcome at each target, in the order they were erased
+    pub targets: Vec<TargetOutcome>,
+    /// Whether every target succeeded
+    pub complete: bool,
+}
+
+/// An erasure report and its signature
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SignedErasureReport {
+    pub report: ErasureReport,
+    /// Hex-encoded HMAC-SHA256 of the report's canonical JSON
+    pub signature: String,
+}
+
+impl SignedErasureReport {
+    /// Sign a report
+    pub fn sign(report: ErasureReport, key: &str) -> Result<Self, ErasureError> {
+        let signature = hex::encode(hmac::sign(&signing_key(key), &canonical(&report)?).as_ref());
+        Ok(Self { report, signature })
+    }
+
+    /// Verify the signature of the report
+    pub fn verify(&self, key: &str) -> Result<(), ErasureError> {
+        let signature = hex::decode(&self.signature).map_err(|_| ErasureError::InvalidSignature)?;
+        hmac::verify(&signing_key(key), &canonical(&self.report)?, &signature)
+            .map_err(|_| ErasureError::InvalidSignature)
+    }
+}
+
+/// Build the HMAC key reports are signed with
+fn signing_key(key: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, key.as_bytes())
+}
+
+/// Canonical JSON of a report: objects have sorted keys and no whitespace
+fn canonical(report: &ErasureReport) -> Result<Vec<u8>, ErasureError> {
+    Ok(serde_json::to_vec(&serde_json::to_value(report)?)?)
+}
+
+/// Erases data subjects from the registered targets and keeps the reports
+pub struct ErasureService {
+    targets: Vec<Arc<dyn ErasureTarget>>,
+    signing_key: Option<String>,
+    capacity: usize,
+    /// Most recent reports, oldest first
+    reports: Mutex<VecDeque<SignedErasureReport>>,
+}
+
+impl ErasureService {
+    /// Create a service without targets
+    pub fn new(config: &ErasureConfig) -> Self {
+        Self {
+            targets: Vec::new(),
+            signing_key: config.signing_key(),
+            capacity: config.report_capacity,
+            reports: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Register a target
+    pub fn with_target(mut self, target: Arc<dyn ErasureTarget>) -> Self {
+        self.targets.push(target);
+        self
+    }
+
+    /// Names of the registered targets
+    pub fn target_names(&self) -> Vec<&'static str> {
+        self.targets.iter().map(|target| target.name()).collect()
+    }
+
+    /// Erase a subject from every target and sign the report
+    ///
+    /// Nothing is erased without a signing key, as the erasure could not be
+    /// proven.
+    pub async fn erase(
+        &self,
+        subject: ErasureSubject,
+        requested_by: Option<String>,
+    ) -> Result<SignedErasureReport, ErasureError> {
+        subject.validate()?;
+        let Some(key) = &self.signing_key else {
+            return Err(ErasureError::SigningKeyMissing);
+        };
+
+        let started_at = Utc::now();
+        let pseudonym = format!("erased-{}", Uuid::new_v4().simple());
+        let mut targets = Vec::with_capacity(self.targets.len());
+        for target in &self.targets {
+            let outcome = match target.erase(&subject, &pseudonym).await {
+                Ok(erased) => TargetOutcome {
+                    target: target.name().to_string(),
+                    purged: erased.purged,
+                    anonymized: erased.anonymized,
+                    error: None,
+                },
+                Err(e) => {
+                    warn!("Erasure failed at {}: {}", target.name(), e);
+                    TargetOutcome {
+                        target: target.name().to_string(),
+                        purged: 0,
+                        anonymized: 0,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+            targets.push(outcome);
+        }
+
+        let report = ErasureReport {
+            id: Uuid::new_v4().to_string(),
+            subject,
+            pseudonym,
+            requested_by,
+            started_at,
+            completed_at: Utc::now(),
+            complete: targets.iter().all(|outcome| outcome.error.is_none()),
+            targets,
+        };
+        info!(
+            "Erasure {} completed over {} target(s), complete: {}",
+            report.id,
+            report.targets.len(),
+            report.complete
+        );
+
+        let signed = SignedErasureReport::sign(report, key)?;
+        if self.capacity > 0 {
+            let mut reports = self.reports.lock().unwrap();
+            if reports.len() >= self.capacity {
+                reports.pop_front();
+            }
+            reports.push_back(signed.clone());
+        }
+        Ok(signed)
+    }
+
+    /// Recent reports, oldest first
+    pub fn reports(&self) -> Vec<SignedErasureReport> {
+        self.reports.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Get a report by ID
+    pub fn report(&self, id: &str) -> Option<SignedErasureReport> {
+        self.reports
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|signed| signed.report.id == id)
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DecisionLogConfig;
+    use crate::modules::llm_proxy::admin::{AuditEvent, AuditOutcome};
+    use crate::modules::llm_proxy::decision_log::RoutingDecision;
+    use crate::modules::memory::InMemoryBackend;
+
+    /// Target that always fails
+    struct Failing;
+
+    #[async_trait]
+    impl ErasureTarget for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        async fn erase(&self, _: &ErasureSubject, _: &str) -> Result<Erased, ErasureError> {
+            Err(ErasureError::InvalidSubject("unreachable".to_string()))
+        }
+    }
+
+    fn config() -> ErasureConfig {
+        ErasureConfig {
+            signing_key: Some("secret".to_string()),
+            report_capacity: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_erase_user() {
+        let memory = Arc::new(MemoryManager::new(Arc::new(InMemoryBackend::new()), 10));
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let bob = MemoryNamespace::new("acme", "bob").unwrap();
+        memory.create_conversation_in(&alice).await.unwrap();
+        memory.create_conversation_in(&bob).await.unwrap();
+
+        let decisions = Arc::new(DecisionLog::new(DecisionLogConfig::default()));
+        for user in ["alice", "bob"] {
+            decisions.record(RoutingDecision {
+                tenant: Some("acme".to_string()),
+                user: Some(user.to_string()),
+                ..RoutingDecision::new("gpt-4o", "gpt-4o")
+            });
+        }
+
+        let audit = Arc::new(AdminAuditLog::new(10));
+        for resource in ["acme/alice/conversations/c1", "acme/alice2", "acme"] {
+            audit.push(AuditEvent::new(
+                Some("root".to_string()),
+                None,
+                "memory.delete_conversation",
+                resource,
+                AuditOutcome::Success,
+                None,
+            ));
+        }
+
+        let service = ErasureService::new(&config())
+            .with_target(memory.clone())
+            .with_target(decisions.clone())
+            .with_target(audit.clone());
+        let subject = ErasureSubject::new("acme", Some("alice".to_string())).unwrap();
+        let signed = service
+            .erase(subject, Some("root".to_string()))
+            .await
+            .unwrap();
+        let report = &signed.report;
+
+        assert!(report.complete);
+        assert_eq!(
+            report
+                .targets
+                .iter()
+                .map(|outcome| (outcome.target.as_str(), outcome.purged, outcome.anonymized))
+                .collect::<Vec<_>>(),
+            vec![
+                ("memory.conversations", 1, 0),
+                ("routing.decisions", 0, 1),
+                ("admin.audit", 0, 1),
+            ]
+        );
+        assert!(memory
+            .list_conversations_in(&alice)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(memory.list_conversations_in(&bob).await.unwrap().len(), 1);
+
+        let users: Vec<_> = decisions
+            .recent()
+            .iter()
+            .map(|entry| entry["user"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(users, vec![report.pseudonym.clone(), "bob".to_string()]);
+        let resources: Vec<_> = audit.recent().into_iter().map(|e| e.resource).collect();
+        assert_eq!(
+            resources[0],
+            format!("acme/{}/conversations/c1", report.pseudonym)
+        );
+        assert_eq!(&resources[1..], ["acme/alice2", "acme"]);
+
+        // The report is signed and kept
+        signed.verify("secret").unwrap();
+        assert!(matches!(
+            signed.verify("other"),
+            Err(ErasureError::InvalidSignature)
+        ));
+        let mut tampered = signed.clone();
+        tampered.report.targets[0].purged = 0;
+        assert!(tampered.verify("secret").is_err());
+        assert_eq!(
+            service.report(&report.id).unwrap().signature,
+            signed.signature
+        );
+    }
+
+    #[tokio::test]
+    async fn test_erase_tenant() {
+        let decisions = Arc::new(DecisionLog::new(DecisionLogConfig::default()));
+        for tenant in ["acme", "globex"] {
+            decisions.record(RoutingDecision {
+                tenant: Some(tenant.to_string()),
+                ..RoutingDecision::new("gpt-4o", "gpt-4o")
+            });
+        }
+        let service = ErasureService::new(&config())
+            .with_target(decisions.clone())
+            .with_target(Arc::new(Failing));
+
+        let report = service
+            .erase(ErasureSubject::new("acme", None).unwrap(), None)
+            .await
+            .unwrap()
+            .report;
+        assert_eq!(report.targets[0].purged, 1);
+        assert_eq!(decisions.recent().len(), 1);
+        assert!(!report.complete);
+        assert!(report.targets[1].error.is_some());
+
+        // Without a signing key nothing is erased
+        let unsigned = ErasureService::new(&ErasureConfig::default()).with_target(decisions);
+        assert!(matches!(
+            unsigned
+                .erase(ErasureSubject::new("globex", None).unwrap(), None)
+                .await,
+            Err(ErasureError::SigningKeyMissing)
+        ));
+        assert!(ErasureSubject::new("", None).is_err());
+    }
+}
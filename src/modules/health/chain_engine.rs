@@ -8,6 +8,7 @@ use std::sync::Arc;
 use serde_json::json;
 
 use crate::modules::chain_engine::ChainEngine;
+use crate::modules::common::RedisConnector;
 use crate::modules::health::{DiagnosticsProvider, HealthCheckManager, HttpDependencyChecker};
 
 /// Chain Engine diagnostics provider
@@ -92,16 +93,14 @@ impl DiagnosticsProvider for ChainEngineDiagnosticsProvider {
 /// Create a health check manager for the Chain Engine service
 pub fn create_chain_engine_health_manager(
     chain_engine: Arc<ChainEngine>,
-    redis_url: Option<String>,
+    redis: Option<RedisConnector>,
     router_endpoint: Option<String>,
 ) -> HealthCheckManager {
     let mut manager = HealthCheckManager::new("ChainEngine", env!("CARGO_PKG_VERSION"), None);
 
-    // Add Redis dependency checker if Redis URL is provided
-    if let Some(redis_url) = redis_url {
-        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(
-            redis_url,
-        ));
+    // Add Redis dependency checker if Redis is configured
+    if let Some(redis) = redis {
+        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(redis));
         manager.add_dependency_checker(redis_checker);
     }
 
@@ -0,0 +1,181 @@
+//! gRPC Health Checking
+//!
+//! Serves the standard gRPC health checking protocol (`grpc.health.v1.Health`)
+//! next to a role's HTTP health endpoints, so Kubernetes gRPC probes and Envoy
+//! can health-check roles natively. A role reports its readiness: `SERVING`
+//! while healthy or degraded, `NOT_SERVING` when unhealthy. Besides the empty
+//! name, standing for the whole server, the only service a role knows is its
+//! own, under its health check name (such as `Router`).
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::stream::{self, Stream};
+use tonic::{Request, Response, Status};
+
+use super::{HealthCheckManager, HealthStatus};
+use proto::health_check_response::ServingStatus;
+use proto::health_server::{Health, HealthServer};
+use proto::{HealthCheckRequest, HealthCheckResponse};
+
+/// Code generated from `proto/health.proto`
+#[allow(clippy::all, missing_docs)]
+pub mod proto {
+    include!("../../generated/grpc.health.v1.rs");
+}
+
+/// `grpc.health.v1.Health` service of a role
+#[derive(Debug, Clone)]
+pub struct GrpcHealthService {
+    manager: Arc<HealthCheckManager>,
+    /// Time between checks of the status streamed to watchers
+    watch_interval: Duration,
+}
+
+impl GrpcHealthService {
+    /// Create a health service reporting the readiness of a role
+    pub fn new(manager: Arc<HealthCheckManager>, watch_interval: Duration) -> Self {
+        Self {
+            manager,
+            watch_interval,
+        }
+    }
+
+    /// Serving status of a service, or `None` for services the role doesn't
+    /// know
+    pub async fn status(&self, service: &str) -> Option<ServingStatus> {
+        if !service.is_empty() && service != self.manager.service_name() {
+            return None;
+        }
+        Some(match self.manager.readiness_check().await.status {
+            HealthStatus::Healthy | HealthStatus::Degraded => ServingStatus::Serving,
+            HealthStatus::Unhealthy => ServingStatus::NotServing,
+        })
+    }
+
+    /// Serve the protocol over HTTP/2 until the shutdown future completes
+    pub async fn serve<F>(
+        self,
+        addr: SocketAddr,
+        shutdown: F,
+    ) -> Result<(), tonic::transport::Error>
+    where
+        F: Future<Output = ()> + Send,
+    {
+        tonic::transport::Server::builder()
+            .add_service(HealthServer::new(self))
+            .serve_with_shutdown(addr, shutdown)
+            .await
+    }
+}
+
+fn response(status: ServingStatus) -> HealthCheckResponse {
+    HealthCheckResponse {
+        status: status.into(),
+    }
+}
+
+#[tonic::async_trait]
+impl Health for GrpcHealthService {
+    async fn check(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<HealthCheckResponse>, Status> {
+        let service = request.into_inner().service;
+        match self.status(&service).await {
+            Some(status) => Ok(Response::new(response(status))),
+            None => Err(Status::not_found(format!("Unknown service: {}", service))),
+        }
+    }
+
+    type WatchStream = Pin<Box<dyn Stream<Item = Result<HealthCheckResponse, Status>> + Send>>;
+
+    /// Stream the status of a service, then each change of it; unknown
+    /// services are reported as `SERVICE_UNKNOWN`, as they may appear later
+    async fn watch(
+        &self,
+        request: Request<HealthCheckRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let service = request.into_inner().service;
+        let updates = stream::unfold(
+            (self.clone(), service, None),
+            |(health, service, last)| async move {
+                let mut checked = last.is_some();
+                loop {
+                    if checked {
+                        tokio::time::sleep(health.watch_interval).await;
+                    }
+                    checked = true;
+                    let status = health
+                        .status(&service)
+                        .await
+                        .unwrap_or(ServingStatus::ServiceUnknown);
+                    if last != Some(status) {
+                        return Some((Ok(response(status)), (health, service, Some(status))));
+                    }
+                }
+            },
+        );
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::health::{ConnectionStatus, DependencyChecker};
+    use futures::StreamExt;
+
+    #[derive(Debug)]
+    struct Down;
+
+    #[async_trait::async_trait]
+    impl DependencyChecker for Down {
+        fn name(&self) -> &str {
+            "redis"
+        }
+
+        async fn check(
+            &self,
+        ) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+            Err("connection refused".into())
+        }
+    }
+
+    fn service() -> GrpcHealthService {
+        let mut manager = HealthCheckManager::new("Router", "1.0.0", None);
+        manager.add_dependency_checker(Arc::new(Down));
+        GrpcHealthService::new(Arc::new(manager), Duration::from_millis(10))
+    }
+
+    #[tokio::test]
+    async fn test_check() {
+        let health = service();
+        for name in ["", "Router"] {
+            let request = Request::new(HealthCheckRequest {
+                service: name.to_string(),
+            });
+            let response = health.check(request).await.unwrap().into_inner();
+            assert_eq!(response.status(), ServingStatus::NotServing);
+        }
+
+        let request = Request::new(HealthCheckRequest {
+            service: "ChainEngine".to_string(),
+        });
+        let error = health.check(request).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_watch_unknown_service() {
+        let request = Request::new(HealthCheckRequest {
+            service: "ChainEngine".to_string(),
+        });
+        let mut updates = service().watch(request).await.unwrap().into_inner();
+        let first = updates.next().await.unwrap().unwrap();
+        assert_eq!(first.status(), ServingStatus::ServiceUnknown);
+    }
+}
@@ -3,6 +3,10 @@
 //! This module provides standardized health check endpoints for all services
 //! in the IntelliRouter system. It includes functionality for basic health checks,
 //! readiness checks, and detailed diagnostics.
+//!
+//! Diagnostics add to the readiness report the probes of the service, its
+//! build, a fingerprint of its configuration and samples of its latest
+//! warnings and errors, as structured JSON meant for support bundles.
 
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -13,17 +17,27 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tracing::error;
 
+use crate::config::Config;
+use crate::modules::common::{RedisConnector, ResourceMonitor};
+use crate::modules::telemetry::logging::{self, LogSample};
+
 // Service-specific health check implementations
 pub mod chain_engine;
+pub mod grpc;
 pub mod persona_layer;
+pub mod probes;
 pub mod rag_manager;
 pub mod router;
+pub mod startup;
 
 // Re-export service-specific health check functions
 pub use chain_engine::create_chain_engine_health_manager;
+pub use grpc::GrpcHealthService;
 pub use persona_layer::create_persona_layer_health_manager;
+pub use probes::{CanaryProbe, VectorSearchProbe};
 pub use rag_manager::create_rag_manager_health_manager;
 pub use router::create_router_health_manager;
+pub use startup::{StartupChecks, StartupError, StartupFailure};
 
 /// Health status enum
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -115,6 +129,50 @@ pub struct ReadinessCheckResponse {
     pub resources: ResourceUtilization,
 }
 
+/// Build of the running binary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// Commit the binary was built from, when the build set
+    /// `INTELLIROUTER_BUILD_COMMIT`
+    pub commit: Option<String>,
+    /// `debug` or `release`
+    pub profile: String,
+    /// Operating system and architecture, e.g. `linux-x86_64`
+    pub target: String,
+}
+
+impl BuildInfo {
+    /// Build of this binary
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: option_env!("INTELLIROUTER_BUILD_COMMIT").map(str::to_string),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+            target: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        }
+    }
+}
+
+/// SHA-256 of the effective configuration, in hex
+///
+/// Instances running the same configuration have the same fingerprint,
+/// which tells them apart without revealing the secrets it holds.
+pub fn config_fingerprint(config: &Config) -> String {
+    // Through a JSON value, whose maps are sorted, so that the fingerprint
+    // doesn't depend on the iteration order of the configuration's maps
+    let json = serde_json::to_value(config)
+        .and_then(|value| serde_json::to_vec(&value))
+        .unwrap_or_default();
+    hex::encode(ring::digest::digest(&ring::digest::SHA256, &json))
+}
+
 /// Diagnostics response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiagnosticsResponse {
@@ -132,12 +190,23 @@ pub struct DiagnosticsResponse {
     pub connections: Vec<ConnectionStatus>,
     /// Resource utilization
     pub resources: ResourceUtilization,
+    /// Probes of the dependencies, run for diagnostics only
+    #[serde(default)]
+    pub probes: Vec<ConnectionStatus>,
     /// Service-specific diagnostics
     pub diagnostics: HashMap<String, serde_json::Value>,
     /// Configuration information
     pub config: HashMap<String, serde_json::Value>,
+    /// Fingerprint of the effective configuration
+    #[serde(default)]
+    pub config_fingerprint: Option<String>,
+    /// Build of the running binary
+    pub build: BuildInfo,
     /// Recent errors or warnings
     pub recent_issues: Vec<String>,
+    /// Latest warnings and errors logged, oldest first
+    #[serde(default)]
+    pub error_samples: Vec<LogSample>,
 }
 
 /// Health check configuration
@@ -153,6 +222,8 @@ pub struct HealthCheckConfig {
     pub cpu_critical_threshold: f32,
     /// Timeout for dependency checks in milliseconds
     pub dependency_check_timeout_ms: u64,
+    /// Timeout for diagnostics probes in milliseconds
+    pub probe_timeout_ms: u64,
     /// Verbosity level for diagnostics (0-3)
     pub diagnostics_verbosity: u8,
 }
@@ -165,6 +236,7 @@ impl Default for HealthCheckConfig {
             cpu_warning_threshold: 70.0,
             cpu_critical_threshold: 90.0,
             dependency_check_timeout_ms: 1000,
+            probe_timeout_ms: 10_000,
             diagnostics_verbosity: 1,
         }
     }
@@ -183,8 +255,14 @@ pub struct HealthCheckManager {
     config: HealthCheckConfig,
     /// Dependency checkers
     dependency_checkers: Vec<Arc<dyn DependencyChecker>>,
+    /// Probes run for diagnostics only
+    probes: Vec<Arc<dyn DependencyChecker>>,
+    /// Fingerprint of the effective configuration
+    config_fingerprint: Option<String>,
     /// Service-specific diagnostics provider
     diagnostics_provider: Option<Arc<dyn DiagnosticsProvider>>,
+    /// Monitor of the process's resources and their soft limits
+    resource_monitor: Option<Arc<ResourceMonitor>>,
     /// Recent issues (errors or warnings)
     recent_issues: Arc<RwLock<Vec<String>>>,
     /// Maximum number of recent issues to keep
@@ -204,7 +282,10 @@ impl HealthCheckManager {
             start_time: Instant::now(),
             config: config.unwrap_or_default(),
             dependency_checkers: Vec::new(),
+            probes: Vec::new(),
+            config_fingerprint: None,
             diagnostics_provider: None,
+            resource_monitor: None,
             recent_issues: Arc::new(RwLock::new(Vec::new())),
             max_recent_issues: 100,
         }
@@ -215,11 +296,27 @@ impl HealthCheckManager {
         self.dependency_checkers.push(checker);
     }
 
+    /// Add a probe, run for diagnostics only
+    pub fn add_probe(&mut self, probe: Arc<dyn DependencyChecker>) {
+        self.probes.push(probe);
+    }
+
+    /// Set the fingerprint of the effective configuration, reported in
+    /// diagnostics
+    pub fn set_config_fingerprint(&mut self, fingerprint: impl Into<String>) {
+        self.config_fingerprint = Some(fingerprint.into());
+    }
+
     /// Set the diagnostics provider
     pub fn set_diagnostics_provider(&mut self, provider: Arc<dyn DiagnosticsProvider>) {
         self.diagnostics_provider = Some(provider);
     }
 
+    /// Set the monitor of the process's resources, reported in diagnostics
+    pub fn set_resource_monitor(&mut self, monitor: Arc<ResourceMonitor>) {
+        self.resource_monitor = Some(monitor);
+    }
+
     /// Log an issue
     pub async fn log_issue(&self, issue: impl Into<String>) {
         let issue = issue.into();
@@ -230,6 +327,11 @@ impl HealthCheckManager {
         issues.push(issue);
     }
 
+    /// Name of the service checked
+    pub fn service_name(&self) -> &str {
+        &self.service_name
+    }
+
     /// Get uptime in seconds
     pub fn uptime_seconds(&self) -> u64 {
         self.start_time.elapsed().as_secs()
@@ -282,50 +384,25 @@ impl HealthCheckManager {
 
     /// Check dependencies
     pub async fn check_dependencies(&self) -> Vec<ConnectionStatus> {
+        let timeout = Duration::from_millis(self.config.dependency_check_timeout_ms);
         let mut results = Vec::new();
-
         for checker in &self.dependency_checkers {
-            let timeout = Duration::from_millis(self.config.dependency_check_timeout_ms);
-
-            let result = match tokio::time::timeout(timeout, checker.check()).await {
-                Ok(Ok(status)) => status,
-                Ok(Err(e)) => {
-                    let error_msg = format!("Dependency check failed: {}", e);
-                    error!("{}", error_msg);
-
-                    ConnectionStatus {
-                        name: checker.name().to_string(),
-                        status: HealthStatus::Unhealthy,
-                        last_success: None,
-                        error: Some(error_msg),
-                        response_time_ms: None,
-                        details: None,
-                    }
-                }
-                Err(_) => {
-                    let error_msg = format!(
-                        "Dependency check timed out after {} ms",
-                        self.config.dependency_check_timeout_ms
-                    );
-                    error!("{}", error_msg);
-
-                    ConnectionStatus {
-                        name: checker.name().to_string(),
-                        status: HealthStatus::Unhealthy,
-                        last_success: None,
-                        error: Some(error_msg),
-                        response_time_ms: None,
-                        details: None,
-                    }
-                }
-            };
-
-            results.push(result);
+            results.push(check_within(checker.as_ref(), timeout).await);
         }
-
         results
     }
 
+    /// Run the probes, concurrently
+    pub async fn run_probes(&self) -> Vec<ConnectionStatus> {
+        let timeout = Duration::from_millis(self.config.probe_timeout_ms);
+        futures::future::join_all(
+            self.probes
+                .iter()
+                .map(|probe| check_within(probe.as_ref(), timeout)),
+        )
+        .await
+    }
+
     /// Get service-specific diagnostics
     pub async fn get_diagnostics(&self) -> HashMap<String, serde_json::Value> {
         if let Some(provider) = &self.diagnostics_provider {
@@ -427,7 +504,14 @@ impl HealthCheckManager {
         let connections = self.check_dependencies().await;
         let resources = self.get_resource_utilization().await;
         let status = self.get_overall_status(&connections, &resources).await;
-        let diagnostics = self.get_diagnostics().await;
+        let probes = self.run_probes().await;
+        let mut diagnostics = self.get_diagnostics().await;
+        if let Some(monitor) = &self.resource_monitor {
+            diagnostics.insert(
+                "process_resources".to_string(),
+                serde_json::to_value(monitor.status()).unwrap_or_default(),
+            );
+        }
         let recent_issues = self.get_recent_issues().await;
 
         // Get configuration information
@@ -462,6 +546,10 @@ impl HealthCheckManager {
                 self.config.dependency_check_timeout_ms,
             )),
         );
+        config.insert(
+            "probe_timeout_ms".to_string(),
+            serde_json::Value::Number(serde_json::Number::from(self.config.probe_timeout_ms)),
+        );
         config.insert(
             "diagnostics_verbosity".to_string(),
             serde_json::Value::Number(serde_json::Number::from(self.config.diagnostics_verbosity)),
@@ -475,9 +563,13 @@ impl HealthCheckManager {
             uptime_seconds: self.uptime_seconds(),
             connections,
             resources,
+            probes,
             diagnostics,
             config,
+            config_fingerprint: self.config_fingerprint.clone(),
+            build: BuildInfo::current(),
             recent_issues,
+            error_samples: logging::recent_errors(),
         }
     }
 
@@ -493,6 +585,29 @@ impl HealthCheckManager {
     }
 }
 
+/// Check a dependency, reporting it unhealthy when its check fails or
+/// outlasts `timeout`
+async fn check_within(checker: &dyn DependencyChecker, timeout: Duration) -> ConnectionStatus {
+    let error_msg = match tokio::time::timeout(timeout, checker.check()).await {
+        Ok(Ok(status)) => return status,
+        Ok(Err(e)) => format!("Dependency check failed: {}", e),
+        Err(_) => format!(
+            "Dependency check timed out after {} ms",
+            timeout.as_millis()
+        ),
+    };
+    error!("{}", error_msg);
+
+    ConnectionStatus {
+        name: checker.name().to_string(),
+        status: HealthStatus::Unhealthy,
+        last_success: None,
+        error: Some(error_msg),
+        response_time_ms: None,
+        details: None,
+    }
+}
+
 /// Dependency checker trait
 #[async_trait::async_trait]
 pub trait DependencyChecker: Send + Sync + std::fmt::Debug {
@@ -548,17 +663,16 @@ async fn diagnostics_handler(State(state): State<Arc<HealthCheckManager>>) -> im
 /// Redis dependency checker
 #[derive(Debug)]
 pub struct RedisDependencyChecker {
-    /// Redis URL
-    redis_url: String,
+    redis: RedisConnector,
     /// Last successful connection time
     last_success: std::sync::Mutex<Option<chrono::DateTime<chrono::Utc>>>,
 }
 
 impl RedisDependencyChecker {
     /// Create a new Redis dependency checker
-    pub fn new(redis_url: impl Into<String>) -> Self {
+    pub fn new(redis: RedisConnector) -> Self {
         Self {
-            redis_url: redis_url.into(),
+            redis,
             last_success: std::sync::Mutex::new(None),
         }
     }
@@ -574,8 +688,7 @@ impl DependencyChecker for RedisDependencyChecker {
         let start = Instant::now();
 
         // Try to connect to Redis
-        let client = redis::Client::open(self.redis_url.clone())?;
-        let mut conn = client.get_async_connection().await?;
+        let mut conn = self.redis.connection().await?;
 
         // Try a simple PING command
         let pong: String = redis::cmd("PING").query_async(&mut conn).await?;
@@ -596,7 +709,10 @@ impl DependencyChecker for RedisDependencyChecker {
             last_success: Some(now),
             error: None,
             response_time_ms: Some(elapsed.as_millis() as u64),
-            details: None,
+            details: Some(HashMap::from([(
+                "topology".to_string(),
+                self.redis.topology().as_str().to_string(),
+            )])),
         })
     }
 }
@@ -698,4 +814,38 @@ mod tests {
         assert!(manager.dependency_checkers.is_empty());
         assert!(manager.diagnostics_provider.is_none());
     }
+
+    #[derive(Debug)]
+    struct UnreachableProbe;
+
+    #[async_trait::async_trait]
+    impl DependencyChecker for UnreachableProbe {
+        fn name(&self) -> &str {
+            "vector_db_search"
+        }
+
+        async fn check(
+            &self,
+        ) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+            Err("connection refused".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_probes() {
+        let mut manager = HealthCheckManager::new("test-service", "1.0.0", None);
+        manager.add_probe(Arc::new(UnreachableProbe));
+        manager.set_config_fingerprint(config_fingerprint(&Config::default()));
+
+        // Probes are reported by diagnostics but don't fail readiness
+        assert!(manager.readiness_check().await.connections.is_empty());
+        let diagnostics = manager.diagnostics().await;
+        assert_eq!(diagnostics.probes[0].name, "vector_db_search");
+        assert_eq!(diagnostics.probes[0].status, HealthStatus::Unhealthy);
+        assert_eq!(diagnostics.build.version, env!("CARGO_PKG_VERSION"));
+
+        let fingerprint = diagnostics.config_fingerprint.unwrap();
+        assert_eq!(fingerprint.len(), 64);
+        assert_eq!(fingerprint, config_fingerprint(&Config::default()));
+    }
 }
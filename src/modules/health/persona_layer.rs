@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use serde_json::json;
 
+use crate::modules::common::RedisConnector;
 use crate::modules::health::{DiagnosticsProvider, HealthCheckManager, HttpDependencyChecker};
 use crate::modules::persona_layer::manager::PersonaManager;
 use crate::modules::persona_layer::Guardrail;
@@ -136,16 +137,14 @@ impl DiagnosticsProvider for PersonaLayerDiagnosticsProvider {
 /// Create a health check manager for the Persona Layer service
 pub fn create_persona_layer_health_manager(
     persona_manager: Arc<PersonaManager>,
-    redis_url: Option<String>,
+    redis: Option<RedisConnector>,
     router_endpoint: Option<String>,
 ) -> HealthCheckManager {
     let mut manager = HealthCheckManager::new("PersonaLayer", env!("CARGO_PKG_VERSION"), None);
 
-    // Add Redis dependency checker if Redis URL is provided
-    if let Some(redis_url) = redis_url {
-        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(
-            redis_url,
-        ));
+    // Add Redis dependency checker if Redis is configured
+    if let Some(redis) = redis {
+        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(redis));
         manager.add_dependency_checker(redis_checker);
     }
 
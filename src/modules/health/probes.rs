@@ -0,0 +1,110 @@
+//! Diagnostics Probes
+//!
+//! Probes exercise a dependency the way requests use it, rather than only
+//! checking that it is reachable: a vector store is searched and a provider
+//! answers a canary prompt. They cost more than a dependency check, a
+//! provider probe spending tokens, so they run for `/diagnostics` only and
+//! never fail readiness.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::modules::health::{ConnectionStatus, DependencyChecker, HealthStatus};
+use crate::modules::memory::VectorStore;
+use crate::modules::telemetry::canary::{CanaryMonitor, CanaryOutcome};
+
+/// Probe searching a vector store
+pub struct VectorSearchProbe {
+    store: Arc<dyn VectorStore>,
+}
+
+impl VectorSearchProbe {
+    /// Create a probe of a vector store
+    pub fn new(store: Arc<dyn VectorStore>) -> Self {
+        Self { store }
+    }
+}
+
+impl std::fmt::Debug for VectorSearchProbe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VectorSearchProbe").finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl DependencyChecker for VectorSearchProbe {
+    fn name(&self) -> &str {
+        "vector_db_search"
+    }
+
+    async fn check(&self) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let start = Instant::now();
+        self.store.probe().await?;
+
+        Ok(ConnectionStatus {
+            name: self.name().to_string(),
+            status: HealthStatus::Healthy,
+            last_success: Some(chrono::Utc::now()),
+            error: None,
+            response_time_ms: Some(start.elapsed().as_millis() as u64),
+            details: None,
+        })
+    }
+}
+
+/// Probe sending the first canary prompt through the router to its provider
+#[derive(Debug)]
+pub struct CanaryProbe {
+    monitor: Arc<CanaryMonitor>,
+}
+
+impl CanaryProbe {
+    /// Create a probe sending the first canary of a monitor
+    pub fn new(monitor: Arc<CanaryMonitor>) -> Self {
+        Self { monitor }
+    }
+}
+
+#[async_trait::async_trait]
+impl DependencyChecker for CanaryProbe {
+    fn name(&self) -> &str {
+        "provider_canary"
+    }
+
+    /// Healthy on a correct answer, degraded on a wrong or slow one
+    async fn check(&self) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let (canary, result) = self
+            .monitor
+            .probe()
+            .await
+            .ok_or("No canary is configured")?;
+        let status = match result.outcome {
+            CanaryOutcome::Pass => HealthStatus::Healthy,
+            CanaryOutcome::WrongAnswer | CanaryOutcome::Slow => HealthStatus::Degraded,
+            CanaryOutcome::Error => {
+                return Err(result
+                    .error
+                    .unwrap_or_else(|| "Canary request failed".to_string())
+                    .into())
+            }
+        };
+
+        Ok(ConnectionStatus {
+            name: self.name().to_string(),
+            status,
+            last_success: (status == HealthStatus::Healthy).then_some(result.checked_at),
+            error: (status != HealthStatus::Healthy)
+                .then(|| format!("Canary answered: {}", result.outcome.as_str())),
+            response_time_ms: result.latency_ms,
+            details: Some(
+                [
+                    ("canary".to_string(), canary.name.clone()),
+                    ("model".to_string(), canary.model.clone()),
+                    ("outcome".to_string(), result.outcome.as_str().to_string()),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        })
+    }
+}
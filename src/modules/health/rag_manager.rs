@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use serde_json::json;
 
+use crate::modules::common::RedisConnector;
 use crate::modules::health::{DiagnosticsProvider, HealthCheckManager, HttpDependencyChecker};
 use crate::modules::rag_manager::manager::RagManager;
 
@@ -114,17 +115,15 @@ impl DiagnosticsProvider for RagManagerDiagnosticsProvider {
 /// Create a health check manager for the RAG Manager service
 pub fn create_rag_manager_health_manager(
     rag_manager: Arc<RagManager>,
-    redis_url: Option<String>,
+    redis: Option<RedisConnector>,
     router_endpoint: Option<String>,
     vector_db_url: Option<String>,
 ) -> HealthCheckManager {
     let mut manager = HealthCheckManager::new("RagManager", env!("CARGO_PKG_VERSION"), None);
 
-    // Add Redis dependency checker if Redis URL is provided
-    if let Some(redis_url) = redis_url {
-        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(
-            redis_url,
-        ));
+    // Add Redis dependency checker if Redis is configured
+    if let Some(redis) = redis {
+        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(redis));
         manager.add_dependency_checker(redis_checker);
     }
 
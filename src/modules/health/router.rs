@@ -7,6 +7,7 @@ use std::sync::Arc;
 
 use serde_json::json;
 
+use crate::modules::common::RedisConnector;
 use crate::modules::health::{DiagnosticsProvider, HealthCheckManager};
 use crate::modules::model_registry::storage::ModelRegistry;
 use crate::modules::router_core::{RetryPolicy, RouterConfig};
@@ -129,15 +130,13 @@ impl DiagnosticsProvider for RouterDiagnosticsProvider {
 pub fn create_router_health_manager(
     model_registry: Arc<ModelRegistry>,
     router_config: RouterConfig,
-    redis_url: Option<String>,
+    redis: Option<RedisConnector>,
 ) -> HealthCheckManager {
     let mut manager = HealthCheckManager::new("Router", env!("CARGO_PKG_VERSION"), None);
 
-    // Add Redis dependency checker if Redis URL is provided
-    if let Some(redis_url) = redis_url {
-        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(
-            redis_url,
-        ));
+    // Add Redis dependency checker if Redis is configured
+    if let Some(redis) = redis {
+        let redis_checker = Arc::new(crate::modules::health::RedisDependencyChecker::new(redis));
         manager.add_dependency_checker(redis_checker);
     }
 
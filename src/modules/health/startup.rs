@@ -0,0 +1,511 @@
+//! Startup Dependency Checks
+//!
+//! Before a role serves traffic, the dependencies it needs are verified: the
+//! Redis its stores use, the vector databases of RAG and semantic memory, and
+//! the models and providers the configuration refers to. A failed check
+//! stops startup with a message saying what to fix, or, when waiting for
+//! dependencies, is retried with exponential backoff until it passes.
+//! Configuration errors never fix themselves, so they are never retried.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::config::{
+    Config, MemoryVectorStoreType, ModelRegistryConfig, RedisTopology, StartupConfig,
+};
+use crate::modules::common::RedisConnector;
+use crate::modules::health::{DependencyChecker, HttpDependencyChecker, RedisDependencyChecker};
+use crate::modules::model_registry::aliases::MAX_ALIAS_DEPTH;
+use crate::modules::model_registry::ModelAliases;
+
+/// A check that failed, with what to do about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartupFailure {
+    /// Dependency or configuration section checked
+    pub name: String,
+    /// What went wrong
+    pub error: String,
+    /// How to fix it
+    pub remedy: String,
+}
+
+impl fmt::Display for StartupFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}\n    fix: {}", self.name, self.error, self.remedy)
+    }
+}
+
+/// Why a role could not start
+#[derive(Debug, Error)]
+pub enum StartupError {
+    /// The configuration is invalid, such as an alias of a model no
+    /// provider serves
+    #[error("the configuration is invalid")]
+    Configuration(Vec<StartupFailure>),
+    /// Dependencies could not be reached
+    #[error("required dependencies are unavailable")]
+    Unavailable(Vec<StartupFailure>),
+    /// Dependencies were still unavailable when the wait timed out
+    #[error("required dependencies are still unavailable after {0} seconds")]
+    Timeout(u64, Vec<StartupFailure>),
+}
+
+impl StartupError {
+    /// The failed checks
+    pub fn failures(&self) -> &[StartupFailure] {
+        match self {
+            Self::Configuration(failures)
+            | Self::Unavailable(failures)
+            | Self::Timeout(_, failures) => failures,
+        }
+    }
+}
+
+/// A dependency check, with the remedy reported when it fails
+struct StartupCheck {
+    checker: Arc<dyn DependencyChecker>,
+    remedy: String,
+}
+
+/// Dependency checks run before a role serves traffic
+pub struct StartupChecks {
+    config: StartupConfig,
+    checks: Vec<StartupCheck>,
+    configuration: Vec<StartupFailure>,
+}
+
+impl StartupChecks {
+    /// Create an empty set of checks
+    pub fn new(config: StartupConfig) -> Self {
+        Self {
+            config,
+            checks: Vec::new(),
+            configuration: Vec::new(),
+        }
+    }
+
+    /// Create the checks of a role's dependencies
+    ///
+    /// Every role but `audit` needs the configured Redis; `rag-injector` and
+    /// `all` need the RAG vector database, and `orchestrator` the Qdrant of
+    /// semantic memory.
+    pub fn from_config(config: &Config, role: &str) -> Self {
+        let mut checks = Self::new(config.startup.clone());
+        checks.configuration = check_model_references(&config.model_registry);
+
+        if role != "audit" {
+            match RedisConnector::from_config(&config.memory) {
+                Ok(Some(redis)) => {
+                    let remedy = match redis.topology() {
+                        RedisTopology::Single => {
+                            "check that Redis is running and reachable at memory.redis_url, \
+                             or remove memory.redis_url to keep state in memory"
+                        }
+                        RedisTopology::Sentinel => {
+                            "check that the sentinels in memory.redis.sentinels are reachable \
+                             and monitor the master named memory.redis.master_name"
+                        }
+                        RedisTopology::Cluster => {
+                            "check that the nodes in memory.redis.nodes are reachable and \
+                             the cluster is up"
+                        }
+                    };
+                    checks =
+                        checks.with_check(Arc::new(RedisDependencyChecker::new(redis)), remedy);
+                }
+                Ok(None) => {}
+                Err(e) => checks.configuration.push(StartupFailure {
+                    name: "redis".to_string(),
+                    error: e.to_string(),
+                    remedy: "fix memory.redis_url or the [memory.redis] section".to_string(),
+                }),
+            }
+        }
+
+        if matches!(role, "rag-injector" | "all") {
+            if let Some(url) = &config.rag.vector_db_url {
+                checks = checks.with_check(
+                    Arc::new(HttpDependencyChecker::new(
+                        "vector_db",
+                        format!("{}/api/v1/heartbeat", url.trim_end_matches('/')),
+                        200,
+                    )),
+                    format!(
+                        "check that the vector database is running at {} (rag.vector_db_url), \
+                         or unset rag.vector_db_url",
+                        url
+                    ),
+                );
+            }
+        }
+
+        let semantic = &config.memory.semantic;
+        if role == "orchestrator"
+            && semantic.enabled
+            && semantic.vector_store == MemoryVectorStoreType::Qdrant
+        {
+            if let Some(url) = semantic
+                .qdrant_url
+                .as_ref()
+                .or(config.rag.vector_db_url.as_ref())
+            {
+                checks = checks.with_check(
+                    Arc::new(HttpDependencyChecker::new(
+                        "semantic_memory_qdrant",
+                        format!("{}/healthz", url.trim_end_matches('/')),
+                        200,
+                    )),
+                    format!(
+                        "check that Qdrant is running at {} (memory.semantic.qdrant_url), or \
+                         set memory.semantic.vector_store = \"memory\"",
+                        url
+                    ),
+                );
+            }
+        }
+
+        checks
+    }
+
+    /// Add a dependency check, with how to fix the dependency if it fails
+    pub fn with_check(
+        mut self,
+        checker: Arc<dyn DependencyChecker>,
+        remedy: impl Into<String>,
+    ) -> Self {
+        self.checks.push(StartupCheck {
+            checker,
+            remedy: remedy.into(),
+        });
+        self
+    }
+
+    /// Names of the checked dependencies
+    pub fn names(&self) -> Vec<&str> {
+        self.checks
+            .iter()
+            .map(|check| check.checker.name())
+            .collect()
+    }
+
+    /// Verify the dependencies, waiting for them if `wait` is set
+    pub async fn verify(&self, wait: bool) -> Result<(), StartupError> {
+        if !self.configuration.is_empty() {
+            return Err(StartupError::Configuration(self.configuration.clone()));
+        }
+        if wait {
+            return self.wait().await;
+        }
+
+        let failures = self.run(&self.checks.iter().collect::<Vec<_>>()).await;
+        if !failures.is_empty() {
+            return Err(StartupError::Unavailable(failures));
+        }
+        info!("Startup dependency checks passed: {:?}", self.names());
+        Ok(())
+    }
+
+    /// Retry the failing checks with exponential backoff until they pass
+    async fn wait(&self) -> Result<(), StartupError> {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.config.wait_timeout_secs);
+        let max_backoff = Duration::from_secs(self.config.max_backoff_secs);
+        let mut backoff = Duration::from_millis(self.config.initial_backoff_ms).min(max_backoff);
+        let mut pending: Vec<&StartupCheck> = self.checks.iter().collect();
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+            let failures = self.run(&pending).await;
+            if failures.is_empty() {
+                info!(
+                    "Startup dependencies ready after {} attempt(s): {:?}",
+                    attempt,
+                    self.names()
+                );
+                return Ok(());
+            }
+
+            let failed: HashSet<&str> = failures.iter().map(|f| f.name.as_str()).collect();
+            pending.retain(|check| failed.contains(check.checker.name()));
+            if !timeout.is_zero() && started.elapsed() + backoff > timeout {
+                return Err(StartupError::Timeout(
+                    self.config.wait_timeout_secs,
+                    failures,
+                ));
+            }
+            for failure in &failures {
+                warn!(
+                    "Waiting for {} (attempt {}, retrying in {:?}): {}",
+                    failure.name, attempt, backoff, failure.error
+                );
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(max_backoff);
+        }
+    }
+
+    /// Run checks, returning the failed ones
+    async fn run(&self, checks: &[&StartupCheck]) -> Vec<StartupFailure> {
+        let timeout = Duration::from_secs(self.config.check_timeout_secs.max(1));
+        let results = futures::future::join_all(
+            checks
+                .iter()
+                .map(|check| tokio::time::timeout(timeout, check.checker.check())),
+        )
+        .await;
+
+        checks
+            .iter()
+            .zip(results)
+            .filter_map(|(check, result)| {
+                let error = match result {
+                    Ok(Ok(_)) => return None,
+                    Ok(Err(e)) => e.to_string(),
+                    Err(_) => format!("no answer within {:?}", timeout),
+                };
+                Some(StartupFailure {
+                    name: check.checker.name().to_string(),
+                    error,
+                    remedy: check.remedy.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Check that the models and providers the model registry configuration
+/// refers to exist
+///
+/// The default provider and the providers of routes must be configured, the
+/// default model of a provider must be one of its available models, and
+/// every alias must lead to an available model, a `provider/model` name of a
+/// configured provider or a route.
+pub fn check_model_references(config: &ModelRegistryConfig) -> Vec<StartupFailure> {
+    let providers: Vec<&str> = config.providers.iter().map(|p| p.name.as_str()).collect();
+    let mut failures = Vec::new();
+    let mut fail = |error: String, remedy: String| {
+        failures.push(StartupFailure {
+            name: "model_registry".to_string(),
+            error,
+            remedy,
+        })
+    };
+
+    if !providers.is_empty() && !providers.contains(&config.default_provider.as_str()) {
+        fail(
+            format!(
+                "default_provider '{}' is not a configured provider",
+                config.default_provider
+            ),
+            format!(
+                "set model_registry.default_provider to one of {:?}, or add a provider \
+                 named '{}'",
+                providers, config.default_provider
+            ),
+        );
+    }
+
+    for provider in &config.providers {
+        if !provider.available_models.is_empty()
+            && !provider.available_models.contains(&provider.default_model)
+        {
+            fail(
+                format!(
+                    "default_model '{}' of provider '{}' is not one of its available_models",
+                    provider.default_model, provider.name
+                ),
+                format!(
+                    "add '{}' to the available_models of provider '{}', or change its \
+                     default_model",
+                    provider.default_model, provider.name
+                ),
+            );
+        }
+    }
+
+    for route in &config.routes {
+        if !providers.contains(&route.provider.as_str()) {
+            fail(
+                format!(
+                    "route '{}' sends models to provider '{}', which is not configured",
+                    route.pattern, route.provider
+                ),
+                format!(
+                    "add a provider named '{}' to model_registry.providers, or change the \
+                     route's provider to one of {:?}",
+                    route.provider, providers
+                ),
+            );
+        }
+    }
+
+    let aliases = ModelAliases::from_config(config);
+    let mut names: Vec<&String> = config.aliases.keys().collect();
+    names.sort();
+    for alias in names {
+        if let Err(error) = resolve_alias(config, &aliases, alias) {
+            fail(
+                format!("alias '{}' {}", alias, error),
+                format!(
+                    "point model_registry.aliases.\"{}\" at an available model of a \
+                     provider, or add a route matching its target",
+                    alias
+                ),
+            );
+        }
+    }
+
+    failures
+}
+
+/// Follow an alias to a model that can be served
+fn resolve_alias(
+    config: &ModelRegistryConfig,
+    aliases: &ModelAliases,
+    alias: &str,
+) -> Result<(), String> {
+    let mut model = alias;
+    for _ in 0..MAX_ALIAS_DEPTH {
+        let Some(target) = aliases.target(model) else {
+            break;
+        };
+        model = target;
+    }
+    if aliases.target(model).is_some() {
+        return Err(format!(
+            "is circular or nests more than {} aliases",
+            MAX_ALIAS_DEPTH
+        ));
+    }
+
+    let available = |provider: &crate::config::LlmProviderConfig, model: &str| {
+        provider.available_models.is_empty() || provider.available_models.iter().any(|m| m == model)
+    };
+    let served = config.providers.iter().any(|p| {
+        p.available_models.iter().any(|m| m == model)
+            || model
+                .strip_prefix(p.name.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+                .is_some_and(|rest| available(p, rest))
+    });
+    if served || aliases.route(model).is_some() {
+        Ok(())
+    } else {
+        Err(format!(
+            "resolves to '{}', which no provider lists and no route matches",
+            model
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelRouteConfig;
+    use crate::modules::health::{ConnectionStatus, HealthStatus};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_model_references() {
+        let mut config = ModelRegistryConfig::default();
+        assert!(check_model_references(&config).is_empty());
+
+        config.default_provider = "mistral".to_string();
+        config.providers[0].default_model = "gpt-5".to_string();
+        config.routes.push(ModelRouteConfig {
+            pattern: "mistral-*".to_string(),
+            provider: "mistral".to_string(),
+        });
+        config
+            .aliases
+            .insert("fast".to_string(), "gpt-3.5-turbo".to_string());
+        config.aliases.insert(
+            "best".to_string(),
+            "anthropic/claude-3-opus-20240229".to_string(),
+        );
+        config
+            .aliases
+            .insert("typo".to_string(), "gtp-4o".to_string());
+        config.aliases.insert("a".to_string(), "b".to_string());
+        config.aliases.insert("b".to_string(), "a".to_string());
+
+        let errors: Vec<String> = check_model_references(&config)
+            .into_iter()
+            .map(|f| f.error)
+            .collect();
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors[0].contains("default_provider 'mistral'"));
+        assert!(errors[1].contains("default_model 'gpt-5'"));
+        assert!(errors[2].contains("route 'mistral-*'"));
+        assert!(errors[3].starts_with("alias 'a' is circular"));
+        assert!(errors[4].starts_with("alias 'b' is circular"));
+        assert!(errors[5].contains("'gtp-4o'"));
+    }
+
+    /// Checker failing a number of times before passing
+    #[derive(Debug)]
+    struct FlakyChecker {
+        failures_left: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl DependencyChecker for FlakyChecker {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn check(
+            &self,
+        ) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+            if self
+                .failures_left
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err("connection refused".into());
+            }
+            Ok(ConnectionStatus {
+                name: "flaky".to_string(),
+                status: HealthStatus::Healthy,
+                last_success: None,
+                error: None,
+                response_time_ms: None,
+                details: None,
+            })
+        }
+    }
+
+    fn flaky_checks(failures: usize, wait_timeout_secs: u64) -> StartupChecks {
+        let config = StartupConfig {
+            wait_timeout_secs,
+            initial_backoff_ms: 10,
+            max_backoff_secs: 1,
+            ..StartupConfig::default()
+        };
+        StartupChecks::new(config).with_check(
+            Arc::new(FlakyChecker {
+                failures_left: AtomicUsize::new(failures),
+            }),
+            "start it",
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_and_wait() {
+        let err = flaky_checks(1, 0).verify(false).await.unwrap_err();
+        assert!(matches!(err, StartupError::Unavailable(_)));
+        assert_eq!(err.failures()[0].error, "connection refused");
+        assert_eq!(err.failures()[0].remedy, "start it");
+
+        flaky_checks(3, 0).verify(true).await.unwrap();
+
+        let err = flaky_checks(usize::MAX, 1).verify(true).await.unwrap_err();
+        assert!(matches!(err, StartupError::Timeout(1, _)));
+    }
+}
@@ -0,0 +1,156 @@
+//! Consul Discovery
+//!
+//! Lists the instances of a service passing their Consul health checks, from
+//! the `/v1/health/service/{name}` endpoint of a Consul agent.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DiscoveryBackend, DiscoveryError, Endpoint};
+use crate::config::ConsulDiscoveryConfig;
+
+/// Discovery with the Consul catalog
+#[derive(Debug, Clone)]
+pub struct ConsulDiscovery {
+    url: String,
+    token: Option<String>,
+    datacenter: Option<String>,
+    http: reqwest::Client,
+}
+
+impl ConsulDiscovery {
+    /// Create a backend querying a Consul agent
+    pub fn new(url: impl Into<String>, token: Option<String>, datacenter: Option<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            token,
+            datacenter,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Create a backend from its configuration, reading the ACL token from
+    /// its environment variable
+    pub fn from_config(config: &ConsulDiscoveryConfig) -> Result<Self, DiscoveryError> {
+        let token = match &config.token_env {
+            Some(var) => Some(std::env::var(var).map_err(|_| {
+                DiscoveryError::Config(format!("Consul token variable {} is not set", var))
+            })?),
+            None => None,
+        };
+        Ok(Self::new(
+            config.url.clone(),
+            token,
+            config.datacenter.clone(),
+        ))
+    }
+}
+
+/// Entry of `/v1/health/service/{name}`
+#[derive(Debug, Deserialize)]
+struct ServiceEntry {
+    #[serde(rename = "Node")]
+    node: Node,
+    #[serde(rename = "Service")]
+    service: Service,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    #[serde(rename = "Address")]
+    address: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Service {
+    #[serde(rename = "Address", default)]
+    address: String,
+    #[serde(rename = "Port")]
+    port: u16,
+}
+
+impl ServiceEntry {
+    /// Address of the instance, the node's when the service has none
+    fn endpoint(self) -> Endpoint {
+        let host = if self.service.address.is_empty() {
+            self.node.address
+        } else {
+            self.service.address
+        };
+        Endpoint::new(host, self.service.port)
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for ConsulDiscovery {
+    fn name(&self) -> &'static str {
+        "consul"
+    }
+
+    async fn discover(&self, service: &str) -> Result<Vec<Endpoint>, DiscoveryError> {
+        let url = format!("{}/v1/health/service/{}", self.url, service);
+        let mut request = self.http.get(&url).query(&[("passing", "true")]);
+        if let Some(datacenter) = &self.datacenter {
+            request = request.query(&[("dc", datacenter)]);
+        }
+        if let Some(token) = &self.token {
+            request = request.header("X-Consul-Token", token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| DiscoveryError::lookup(service, e))?;
+        if !response.status().is_success() {
+            return Err(DiscoveryError::lookup(
+                service,
+                format!("Consul returned {}", response.status()),
+            ));
+        }
+        let entries: Vec<ServiceEntry> = response
+            .json()
+            .await
+            .map_err(|e| DiscoveryError::lookup(service, e))?;
+        Ok(entries.into_iter().map(ServiceEntry::endpoint).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_discover_passing_instances() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("GET", "/v1/health/service/rag-injector")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("passing".into(), "true".into()),
+                mockito::Matcher::UrlEncoded("dc".into(), "eu-west".into()),
+            ]))
+            .match_header("X-Consul-Token", "secret")
+            .with_body(
+                r#"[
+                    {"Node": {"Address": "10.0.1.4"}, "Service": {"Address": "", "Port": 8082}},
+                    {"Node": {"Address": "10.0.1.5"}, "Service": {"Address": "172.17.0.3", "Port": 8082}}
+                ]"#,
+            )
+            .create_async()
+            .await;
+
+        let discovery = ConsulDiscovery::new(
+            server.url(),
+            Some("secret".to_string()),
+            Some("eu-west".to_string()),
+        );
+        let endpoints = discovery.discover("rag-injector").await.unwrap();
+        assert_eq!(
+            endpoints,
+            vec![
+                Endpoint::new("10.0.1.4", 8082),
+                Endpoint::new("172.17.0.3", 8082),
+            ]
+        );
+        mock.assert_async().await;
+    }
+}
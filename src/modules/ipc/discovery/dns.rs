@@ -0,0 +1,349 @@
+//! DNS SRV Discovery
+//!
+//! Looks up the SRV records of a service, such as
+//! `_grpc._tcp.orchestrator.intellirouter.svc.cluster.local`, with a minimal
+//! DNS client: a query over UDP, retried over TCP when the answer is
+//! truncated. Records are ordered by priority, then by descending weight.
+
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+use super::{DiscoveryBackend, DiscoveryError, Endpoint};
+use crate::config::DnsDiscoveryConfig;
+
+/// Resolver configuration read when no resolver is configured
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Time allowed for the resolver to answer
+const QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Record type of SRV records
+const TYPE_SRV: u16 = 33;
+
+/// Record class of Internet records
+const CLASS_IN: u16 = 1;
+
+/// Discovery with DNS SRV records
+#[derive(Debug, Clone)]
+pub struct DnsSrvDiscovery {
+    resolver: SocketAddr,
+}
+
+impl DnsSrvDiscovery {
+    /// Create a backend querying a resolver
+    pub fn new(resolver: SocketAddr) -> Self {
+        Self { resolver }
+    }
+
+    /// Create a backend querying the configured resolver, or the system's
+    pub fn from_config(config: &DnsDiscoveryConfig) -> Result<Self, DiscoveryError> {
+        let resolver = match &config.resolver {
+            Some(resolver) => resolver.parse().map_err(|_| {
+                DiscoveryError::Config(format!("Invalid DNS resolver '{}'", resolver))
+            })?,
+            None => {
+                let resolv_conf = std::fs::read_to_string(RESOLV_CONF).map_err(|e| {
+                    DiscoveryError::Config(format!("Failed to read {}: {}", RESOLV_CONF, e))
+                })?;
+                system_resolver(&resolv_conf).ok_or_else(|| {
+                    DiscoveryError::Config(format!("No nameserver in {}", RESOLV_CONF))
+                })?
+            }
+        };
+        Ok(Self::new(resolver))
+    }
+
+    async fn query_udp(&self, query: &[u8]) -> std::io::Result<Vec<u8>> {
+        let bind: SocketAddr = if self.resolver.is_ipv6() {
+            "[::]:0".parse().unwrap()
+        } else {
+            "0.0.0.0:0".parse().unwrap()
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(self.resolver).await?;
+        socket.send(query).await?;
+        let mut buffer = vec![0; 65535];
+        let len = socket.recv(&mut buffer).await?;
+        buffer.truncate(len);
+        Ok(buffer)
+    }
+
+    async fn query_tcp(&self, query: &[u8]) -> std::io::Result<Vec<u8>> {
+        let mut stream = TcpStream::connect(self.resolver).await?;
+        stream
+            .write_all(&(query.len() as u16).to_be_bytes())
+            .await?;
+        stream.write_all(query).await?;
+        let len = stream.read_u16().await?;
+        let mut buffer = vec![0; len as usize];
+        stream.read_exact(&mut buffer).await?;
+        Ok(buffer)
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for DnsSrvDiscovery {
+    fn name(&self) -> &'static str {
+        "dns"
+    }
+
+    async fn discover(&self, service: &str) -> Result<Vec<Endpoint>, DiscoveryError> {
+        let id: u16 = rand::random();
+        let query = encode_query(id, service).map_err(|e| DiscoveryError::lookup(service, e))?;
+
+        let exchange = async {
+            let response = self.query_udp(&query).await?;
+            if is_truncated(&response) {
+                self.query_tcp(&query).await
+            } else {
+                Ok(response)
+            }
+        };
+        let response = tokio::time::timeout(QUERY_TIMEOUT, exchange)
+            .await
+            .map_err(|_| DiscoveryError::lookup(service, "DNS query timed out"))?
+            .map_err(|e| DiscoveryError::lookup(service, e))?;
+
+        let mut records =
+            parse_srv_response(id, &response).map_err(|e| DiscoveryError::lookup(service, e))?;
+        records.sort_by(|a, b| a.priority.cmp(&b.priority).then(b.weight.cmp(&a.weight)));
+        Ok(records
+            .into_iter()
+            .map(|record| Endpoint::new(record.target, record.port))
+            .collect())
+    }
+}
+
+/// First `nameserver` of a `resolv.conf`
+fn system_resolver(resolv_conf: &str) -> Option<SocketAddr> {
+    resolv_conf.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some("nameserver") {
+            return None;
+        }
+        // Drop the zone of link-local IPv6 addresses
+        let address = fields.next()?.split('%').next()?;
+        let ip: IpAddr = address.parse().ok()?;
+        Some(SocketAddr::new(ip, 53))
+    })
+}
+
+/// An SRV record
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SrvRecord {
+    priority: u16,
+    weight: u16,
+    port: u16,
+    target: String,
+}
+
+/// Query for the SRV records of a name, with recursion desired
+fn encode_query(id: u16, name: &str) -> Result<Vec<u8>, String> {
+    let mut query = Vec::with_capacity(18 + name.len());
+    query.extend_from_slice(&id.to_be_bytes());
+    // Flags: recursion desired
+    query.extend_from_slice(&0x0100u16.to_be_bytes());
+    // One question, no answer, authority or additional record
+    query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!("Invalid DNS name '{}'", name));
+        }
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&TYPE_SRV.to_be_bytes());
+    query.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(query)
+}
+
+fn is_truncated(response: &[u8]) -> bool {
+    response.len() > 2 && response[2] & 0x02 != 0
+}
+
+/// Reader of a DNS message
+struct Message<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Message<'a> {
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.position + len;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| "Truncated DNS response".to_string())?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Read a possibly compressed name
+    fn name(&mut self) -> Result<String, String> {
+        let mut labels = Vec::new();
+        let mut position = self.position;
+        let mut jumped = false;
+        // Bound the pointers followed, so that a loop cannot hang the reader
+        for _ in 0..128 {
+            let len = *self
+                .bytes
+                .get(position)
+                .ok_or_else(|| "Truncated DNS name".to_string())?;
+            match len {
+                0 => {
+                    if !jumped {
+                        self.position = position + 1;
+                    }
+                    return Ok(labels.join("."));
+                }
+                len if len & 0xC0 == 0xC0 => {
+                    let low = *self
+                        .bytes
+                        .get(position + 1)
+                        .ok_or_else(|| "Truncated DNS name".to_string())?;
+                    if !jumped {
+                        self.position = position + 2;
+                        jumped = true;
+                    }
+                    position = (((len & 0x3F) as usize) << 8) | low as usize;
+                }
+                len => {
+                    let start = position + 1;
+                    let label = self
+                        .bytes
+                        .get(start..start + len as usize)
+                        .ok_or_else(|| "Truncated DNS name".to_string())?;
+                    labels.push(String::from_utf8_lossy(label).into_owned());
+                    position = start + len as usize;
+                }
+            }
+        }
+        Err("Too many compression pointers in DNS name".to_string())
+    }
+}
+
+/// SRV records answering the query `id`
+fn parse_srv_response(id: u16, response: &[u8]) -> Result<Vec<SrvRecord>, String> {
+    let mut message = Message {
+        bytes: response,
+        position: 0,
+    };
+    if message.u16()? != id {
+        return Err("DNS response to another query".to_string());
+    }
+    let flags = message.u16()?;
+    match flags & 0x000F {
+        0 => {}
+        3 => return Err("No such DNS name".to_string()),
+        rcode => return Err(format!("DNS error code {}", rcode)),
+    }
+    let questions = message.u16()?;
+    let answers = message.u16()?;
+    message.take(4)?;
+
+    for _ in 0..questions {
+        message.name()?;
+        message.take(4)?;
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..answers {
+        message.name()?;
+        let record_type = message.u16()?;
+        message.take(6)?;
+        let len = message.u16()? as usize;
+        let end = message.position + len;
+        if record_type == TYPE_SRV {
+            let priority = message.u16()?;
+            let weight = message.u16()?;
+            let port = message.u16()?;
+            let target = message.name()?;
+            records.push(SrvRecord {
+                priority,
+                weight,
+                port,
+                target,
+            });
+        }
+        // Skip the rest of the record, such as the CNAME records of an alias
+        message.position = end;
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answer to `encode_query(7, "_grpc._tcp.orchestrator")` with two
+    /// records, the second one's target compressed
+    fn response() -> Vec<u8> {
+        let query = encode_query(7, "_grpc._tcp.orchestrator").unwrap();
+        let mut response = query.clone();
+        // Flags: response, recursion available; two answers
+        response[2] = 0x81;
+        response[3] = 0x80;
+        response[7] = 2;
+
+        let answer = |target: &[u8], priority: u8, weight: u8| {
+            let mut record = vec![0xC0, 12];
+            record.extend_from_slice(&[0, 33, 0, 1, 0, 0, 0, 60]);
+            let mut rdata = vec![0, priority, 0, weight, 0x1F, 0x91];
+            rdata.extend_from_slice(target);
+            record.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            record.extend_from_slice(&rdata);
+            record
+        };
+        let first_target = response.len() + 18;
+        response.extend(answer(b"\x02o1\x0corchestrator\x00", 10, 5));
+        response.extend(answer(&[2, b'o', b'2', 0xC0, first_target as u8 + 3], 0, 5));
+        response
+    }
+
+    #[test]
+    fn test_parse_srv_response() {
+        let records = parse_srv_response(7, &response()).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                SrvRecord {
+                    priority: 10,
+                    weight: 5,
+                    port: 8081,
+                    target: "o1.orchestrator".to_string(),
+                },
+                SrvRecord {
+                    priority: 0,
+                    weight: 5,
+                    port: 8081,
+                    target: "o2.orchestrator".to_string(),
+                },
+            ]
+        );
+
+        assert!(parse_srv_response(8, &response()).is_err());
+        let mut nxdomain = response();
+        nxdomain[3] = 0x83;
+        assert!(parse_srv_response(7, &nxdomain).is_err());
+    }
+
+    #[test]
+    fn test_system_resolver() {
+        let resolv_conf = "# generated\nsearch svc.cluster.local\nnameserver fe80::1%eth0\n";
+        assert_eq!(
+            system_resolver(resolv_conf),
+            Some("[fe80::1]:53".parse().unwrap())
+        );
+        assert_eq!(system_resolver("search local\n"), None);
+    }
+}
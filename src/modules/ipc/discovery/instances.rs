@@ -0,0 +1,252 @@
+//! Discovered Instances
+//!
+//! The instances of a service with their health: calls are balanced
+//! round-robin across the instances, and an instance failing too many calls in
+//! a row is evicted from the rotation for a while.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use super::Endpoint;
+
+/// An instance of a service and its health
+#[derive(Debug, Clone)]
+pub struct Instance {
+    /// Address of the instance
+    pub endpoint: Endpoint,
+    consecutive_failures: u32,
+    evicted_until: Option<Instant>,
+}
+
+impl Instance {
+    /// Create a healthy instance
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            consecutive_failures: 0,
+            evicted_until: None,
+        }
+    }
+
+    /// Whether the instance is in the rotation
+    pub fn is_available(&self, now: Instant) -> bool {
+        self.evicted_until.is_none_or(|until| now >= until)
+    }
+
+    /// Consecutive failed calls to the instance
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.evicted_until = None;
+    }
+
+    /// Count a failed call, evicting the instance for `eviction` at
+    /// `threshold` consecutive failures; returns whether it was evicted
+    fn record_failure(&mut self, threshold: u32, eviction: Duration, now: Instant) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.consecutive_failures >= threshold.max(1) && self.is_available(now) {
+            self.evict(eviction, now);
+            return true;
+        }
+        false
+    }
+
+    fn evict(&mut self, eviction: Duration, now: Instant) {
+        self.evicted_until = Some(now + eviction);
+    }
+}
+
+/// State of an instance, for status reports
+#[derive(Debug, Clone, Serialize)]
+pub struct InstanceStatus {
+    /// Address of the instance
+    pub endpoint: Endpoint,
+    /// Whether the instance is in the rotation
+    pub available: bool,
+    /// Consecutive failed calls to the instance
+    pub consecutive_failures: u32,
+}
+
+/// The instances of a service, balanced round-robin
+#[derive(Debug, Default)]
+pub struct Instances {
+    instances: Vec<Instance>,
+    next: usize,
+}
+
+impl Instances {
+    /// Create an empty set of instances
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the instances with those just discovered, keeping the health
+    /// of instances still present
+    pub fn update(&mut self, endpoints: Vec<Endpoint>) {
+        let mut previous = std::mem::take(&mut self.instances);
+        for endpoint in endpoints {
+            if self.instances.iter().any(|i| i.endpoint == endpoint) {
+                continue;
+            }
+            let instance = match previous.iter().position(|i| i.endpoint == endpoint) {
+                Some(index) => previous.swap_remove(index),
+                None => Instance::new(endpoint),
+            };
+            self.instances.push(instance);
+        }
+        if self.next >= self.instances.len() {
+            self.next = 0;
+        }
+    }
+
+    /// Next instance in the rotation; when every instance is evicted, the
+    /// next one regardless, as a failing instance beats none
+    pub fn select(&mut self, now: Instant) -> Option<Endpoint> {
+        let len = self.instances.len();
+        if len == 0 {
+            return None;
+        }
+        let start = self.next;
+        let index = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|&index| self.instances[index].is_available(now))
+            .unwrap_or(start % len);
+        self.next = (index + 1) % len;
+        Some(self.instances[index].endpoint.clone())
+    }
+
+    /// Count a successful call to an instance, returning it to the rotation
+    pub fn record_success(&mut self, endpoint: &Endpoint) {
+        if let Some(instance) = self.get_mut(endpoint) {
+            instance.record_success();
+        }
+    }
+
+    /// Count a failed call to an instance; returns whether it was evicted
+    pub fn record_failure(
+        &mut self,
+        endpoint: &Endpoint,
+        threshold: u32,
+        eviction: Duration,
+        now: Instant,
+    ) -> bool {
+        self.get_mut(endpoint)
+            .is_some_and(|instance| instance.record_failure(threshold, eviction, now))
+    }
+
+    /// Evict an instance failing its health check
+    pub fn evict(&mut self, endpoint: &Endpoint, eviction: Duration, now: Instant) {
+        if let Some(instance) = self.get_mut(endpoint) {
+            instance.evict(eviction, now);
+        }
+    }
+
+    /// Addresses of every instance, evicted or not
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.instances.iter().map(|i| i.endpoint.clone()).collect()
+    }
+
+    /// Number of instances in the rotation
+    pub fn available(&self, now: Instant) -> usize {
+        self.instances
+            .iter()
+            .filter(|i| i.is_available(now))
+            .count()
+    }
+
+    /// State of every instance
+    pub fn status(&self, now: Instant) -> Vec<InstanceStatus> {
+        self.instances
+            .iter()
+            .map(|i| InstanceStatus {
+                endpoint: i.endpoint.clone(),
+                available: i.is_available(now),
+                consecutive_failures: i.consecutive_failures,
+            })
+            .collect()
+    }
+
+    /// Number of instances
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    /// Whether there is no instance
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    fn get_mut(&mut self, endpoint: &Endpoint) -> Option<&mut Instance> {
+        self.instances.iter_mut().find(|i| &i.endpoint == endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoints(ports: &[u16]) -> Vec<Endpoint> {
+        ports
+            .iter()
+            .map(|&port| Endpoint::new("10.0.0.1", port))
+            .collect()
+    }
+
+    #[test]
+    fn test_round_robin_skips_evicted() {
+        let now = Instant::now();
+        let mut instances = Instances::new();
+        instances.update(endpoints(&[1, 2, 3]));
+
+        let picked: Vec<u16> = (0..3)
+            .map(|_| instances.select(now).unwrap().port)
+            .collect();
+        assert_eq!(picked, vec![1, 2, 3]);
+
+        let second = Endpoint::new("10.0.0.1", 2);
+        let eviction = Duration::from_secs(30);
+        assert!(!instances.record_failure(&second, 2, eviction, now));
+        assert!(instances.record_failure(&second, 2, eviction, now));
+        assert_eq!(instances.available(now), 2);
+
+        let picked: Vec<u16> = (0..4)
+            .map(|_| instances.select(now).unwrap().port)
+            .collect();
+        assert_eq!(picked, vec![1, 3, 1, 3]);
+
+        // Back in the rotation once the eviction is over
+        let later = now + eviction;
+        assert_eq!(instances.available(later), 3);
+    }
+
+    #[test]
+    fn test_update_keeps_health() {
+        let now = Instant::now();
+        let mut instances = Instances::new();
+        instances.update(endpoints(&[1, 2]));
+        let first = Endpoint::new("10.0.0.1", 1);
+        instances.evict(&first, Duration::from_secs(30), now);
+
+        instances.update(endpoints(&[1, 3, 3]));
+        assert_eq!(instances.len(), 2);
+        assert_eq!(instances.available(now), 1);
+
+        instances.record_success(&first);
+        assert_eq!(instances.available(now), 2);
+    }
+
+    #[test]
+    fn test_all_evicted_still_selects() {
+        let now = Instant::now();
+        let mut instances = Instances::new();
+        assert!(instances.select(now).is_none());
+
+        instances.update(endpoints(&[1]));
+        instances.evict(&Endpoint::new("10.0.0.1", 1), Duration::from_secs(30), now);
+        assert_eq!(instances.select(now).unwrap().port, 1);
+    }
+}
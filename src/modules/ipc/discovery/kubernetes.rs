@@ -0,0 +1,212 @@
+//! Kubernetes Discovery
+//!
+//! Lists the ready addresses of a service's `Endpoints` through the
+//! Kubernetes API, authenticated with the pod's service account. The service
+//! account token is read again on every lookup, as the kubelet rotates it.
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{DiscoveryBackend, DiscoveryError, Endpoint};
+use crate::config::KubernetesDiscoveryConfig;
+
+/// Directory where the service account is mounted in pods
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// Discovery with the endpoints of Kubernetes services
+#[derive(Debug, Clone)]
+pub struct KubernetesDiscovery {
+    api_url: String,
+    namespace: String,
+    token_path: Option<PathBuf>,
+    http: reqwest::Client,
+}
+
+impl KubernetesDiscovery {
+    /// Create a backend querying an API server, authenticated with the token
+    /// at `token_path` if any
+    pub fn new(
+        api_url: impl Into<String>,
+        namespace: impl Into<String>,
+        token_path: Option<PathBuf>,
+        http: reqwest::Client,
+    ) -> Self {
+        Self {
+            api_url: api_url.into().trim_end_matches('/').to_string(),
+            namespace: namespace.into(),
+            token_path,
+            http,
+        }
+    }
+
+    /// Create a backend with the pod's service account, trusting its CA
+    pub fn from_config(config: &KubernetesDiscoveryConfig) -> Result<Self, DiscoveryError> {
+        let dir = PathBuf::from(SERVICE_ACCOUNT_DIR);
+        let namespace = match &config.namespace {
+            Some(namespace) => namespace.clone(),
+            None => std::fs::read_to_string(dir.join("namespace"))
+                .map(|namespace| namespace.trim().to_string())
+                .map_err(|e| {
+                    DiscoveryError::Config(format!("Failed to read the pod's namespace: {}", e))
+                })?,
+        };
+
+        let mut builder = reqwest::Client::builder();
+        if let Ok(ca) = std::fs::read(dir.join("ca.crt")) {
+            let certificate = reqwest::Certificate::from_pem(&ca).map_err(|e| {
+                DiscoveryError::Config(format!("Invalid service account CA: {}", e))
+            })?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        let http = builder
+            .build()
+            .map_err(|e| DiscoveryError::Config(e.to_string()))?;
+
+        Ok(Self::new(
+            config.api_url.clone(),
+            namespace,
+            Some(dir.join("token")),
+            http,
+        ))
+    }
+}
+
+/// `Endpoints` object of a service
+#[derive(Debug, Deserialize)]
+struct Endpoints {
+    #[serde(default)]
+    subsets: Vec<EndpointSubset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EndpointSubset {
+    /// Ready addresses; those not ready are in `notReadyAddresses`
+    #[serde(default)]
+    addresses: Vec<EndpointAddress>,
+    #[serde(default)]
+    ports: Vec<EndpointPort>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EndpointAddress {
+    ip: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EndpointPort {
+    #[serde(default)]
+    name: Option<String>,
+    port: u16,
+}
+
+impl Endpoints {
+    /// Ready addresses with the named port, or each subset's first port
+    fn endpoints(self, port_name: Option<&str>) -> Vec<Endpoint> {
+        let mut endpoints = Vec::new();
+        for subset in self.subsets {
+            let port = match port_name {
+                Some(name) => subset
+                    .ports
+                    .iter()
+                    .find(|port| port.name.as_deref() == Some(name)),
+                None => subset.ports.first(),
+            };
+            let Some(port) = port else {
+                continue;
+            };
+            for address in &subset.addresses {
+                endpoints.push(Endpoint::new(address.ip.clone(), port.port));
+            }
+        }
+        endpoints
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for KubernetesDiscovery {
+    fn name(&self) -> &'static str {
+        "kubernetes"
+    }
+
+    /// Look up `service`, or `service:port-name` to pick one of its ports
+    async fn discover(&self, service: &str) -> Result<Vec<Endpoint>, DiscoveryError> {
+        let (name, port_name) = match service.split_once(':') {
+            Some((name, port_name)) => (name, Some(port_name)),
+            None => (service, None),
+        };
+        let url = format!(
+            "{}/api/v1/namespaces/{}/endpoints/{}",
+            self.api_url, self.namespace, name
+        );
+        let mut request = self.http.get(&url);
+        if let Some(token_path) = &self.token_path {
+            let token = tokio::fs::read_to_string(token_path)
+                .await
+                .map_err(|e| DiscoveryError::lookup(service, e))?;
+            request = request.bearer_auth(token.trim());
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| DiscoveryError::lookup(service, e))?;
+        if !response.status().is_success() {
+            return Err(DiscoveryError::lookup(
+                service,
+                format!("Kubernetes API returned {}", response.status()),
+            ));
+        }
+        let endpoints: Endpoints = response
+            .json()
+            .await
+            .map_err(|e| DiscoveryError::lookup(service, e))?;
+        Ok(endpoints.endpoints(port_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENDPOINTS: &str = r#"{
+        "kind": "Endpoints",
+        "subsets": [
+            {
+                "addresses": [{"ip": "10.1.0.7"}, {"ip": "10.1.0.8"}],
+                "notReadyAddresses": [{"ip": "10.1.0.9"}],
+                "ports": [{"name": "http", "port": 8083}, {"name": "grpc", "port": 9083}]
+            }
+        ]
+    }"#;
+
+    #[tokio::test]
+    async fn test_discover_ready_addresses() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock(
+                "GET",
+                "/api/v1/namespaces/intellirouter/endpoints/summarizer",
+            )
+            .with_body(ENDPOINTS)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let discovery =
+            KubernetesDiscovery::new(server.url(), "intellirouter", None, reqwest::Client::new());
+        assert_eq!(
+            discovery.discover("summarizer").await.unwrap(),
+            vec![
+                Endpoint::new("10.1.0.7", 8083),
+                Endpoint::new("10.1.0.8", 8083),
+            ]
+        );
+        assert_eq!(
+            discovery.discover("summarizer:grpc").await.unwrap()[0],
+            Endpoint::new("10.1.0.7", 9083)
+        );
+        mock.assert_async().await;
+    }
+}
@@ -0,0 +1,266 @@
+//! Service Discovery
+//!
+//! Inter-service clients find the instances of other roles (orchestrator, RAG
+//! injector, summarizer) through a discovery backend instead of hard-coded
+//! endpoints: endpoints listed in the configuration, DNS SRV records, a
+//! Consul catalog or a Kubernetes service's endpoints. Each service's
+//! instances are kept in a [`ServicePool`], refreshed periodically, which
+//! balances calls across them and evicts instances that keep failing.
+
+pub mod consul;
+pub mod dns;
+pub mod instances;
+pub mod kubernetes;
+pub mod pool;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::config::{DiscoveryBackendKind, DiscoveryConfig};
+
+pub use consul::ConsulDiscovery;
+pub use dns::DnsSrvDiscovery;
+pub use instances::{Instance, Instances};
+pub use kubernetes::KubernetesDiscovery;
+pub use pool::{PoolSettings, ServicePool};
+
+/// Error discovering the instances of a service
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    /// The discovery configuration is invalid
+    #[error("Invalid discovery configuration: {0}")]
+    Config(String),
+
+    /// The backend could not be queried
+    #[error("Lookup of {service} failed: {message}")]
+    Lookup {
+        /// Name looked up
+        service: String,
+        /// What went wrong
+        message: String,
+    },
+}
+
+impl DiscoveryError {
+    fn lookup(service: &str, message: impl fmt::Display) -> Self {
+        Self::Lookup {
+            service: service.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Address of an instance of a service
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct Endpoint {
+    /// Host name or IP address
+    pub host: String,
+    /// Port
+    pub port: u16,
+}
+
+impl Endpoint {
+    /// Create an endpoint
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// URL of the endpoint with a scheme, such as `http://10.0.0.7:8081`
+    pub fn url(&self, scheme: &str) -> String {
+        if self.host.contains(':') {
+            format!("{}://[{}]:{}", scheme, self.host, self.port)
+        } else {
+            format!("{}://{}:{}", scheme, self.host, self.port)
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = DiscoveryError;
+
+    /// Parse a `host:port` endpoint, with IPv6 hosts in brackets
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || DiscoveryError::Config(format!("Invalid endpoint '{}'", s));
+        let (host, port) = s.rsplit_once(':').ok_or_else(invalid)?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let port = port.parse().map_err(|_| invalid())?;
+        Ok(Self::new(host, port))
+    }
+}
+
+/// Backend finding the instances of services
+#[async_trait]
+pub trait DiscoveryBackend: Send + Sync + fmt::Debug {
+    /// Name of the backend, for logs
+    fn name(&self) -> &'static str;
+
+    /// Current instances of a service, by the name looked up with the backend
+    async fn discover(&self, service: &str) -> Result<Vec<Endpoint>, DiscoveryError>;
+}
+
+/// Endpoints listed in the configuration
+#[derive(Debug, Clone)]
+pub struct StaticDiscovery {
+    endpoints: Vec<Endpoint>,
+}
+
+impl StaticDiscovery {
+    /// Create a backend always returning the same endpoints
+    pub fn new(endpoints: Vec<Endpoint>) -> Self {
+        Self { endpoints }
+    }
+}
+
+#[async_trait]
+impl DiscoveryBackend for StaticDiscovery {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    async fn discover(&self, _service: &str) -> Result<Vec<Endpoint>, DiscoveryError> {
+        Ok(self.endpoints.clone())
+    }
+}
+
+/// Pools of the services discovered for the roles, by role name
+#[derive(Debug, Default)]
+pub struct ServiceDirectory {
+    pools: HashMap<String, Arc<ServicePool>>,
+    refresh_interval: Duration,
+}
+
+impl ServiceDirectory {
+    /// Create the pools of the services configured for discovery
+    pub fn from_config(config: &DiscoveryConfig) -> Result<Self, DiscoveryError> {
+        let shared: Option<Arc<dyn DiscoveryBackend>> = match config.backend {
+            DiscoveryBackendKind::Static => None,
+            DiscoveryBackendKind::Dns => Some(Arc::new(DnsSrvDiscovery::from_config(&config.dns)?)),
+            DiscoveryBackendKind::Consul => {
+                Some(Arc::new(ConsulDiscovery::from_config(&config.consul)?))
+            }
+            DiscoveryBackendKind::Kubernetes => Some(Arc::new(KubernetesDiscovery::from_config(
+                &config.kubernetes,
+            )?)),
+        };
+        let settings = PoolSettings::from_config(config);
+
+        let mut pools = HashMap::new();
+        for (role, service) in &config.services {
+            let backend = match &shared {
+                Some(backend) => backend.clone(),
+                None => {
+                    let endpoints = service
+                        .endpoints
+                        .iter()
+                        .map(|endpoint| endpoint.parse())
+                        .collect::<Result<Vec<Endpoint>, _>>()?;
+                    Arc::new(StaticDiscovery::new(endpoints)) as Arc<dyn DiscoveryBackend>
+                }
+            };
+            let name = service.name.clone().unwrap_or_else(|| role.clone());
+            pools.insert(
+                role.clone(),
+                Arc::new(ServicePool::new(role, name, backend, settings.clone())),
+            );
+        }
+
+        Ok(Self {
+            pools,
+            refresh_interval: Duration::from_secs(config.refresh_interval_secs.max(1)),
+        })
+    }
+
+    /// Pool of a role's instances, if the role is discovered
+    pub fn pool(&self, role: &str) -> Option<Arc<ServicePool>> {
+        self.pools.get(role).cloned()
+    }
+
+    /// Whether no service is discovered
+    pub fn is_empty(&self) -> bool {
+        self.pools.is_empty()
+    }
+
+    /// Refresh every pool, logging failed lookups
+    pub async fn refresh(&self) {
+        for pool in self.pools.values() {
+            if let Err(e) = pool.refresh().await {
+                warn!("Failed to refresh instances of {}: {}", pool.role(), e);
+            }
+        }
+    }
+
+    /// Refresh every pool now, then periodically in the background
+    pub fn spawn_refresh(self: &Arc<Self>) -> JoinHandle<()> {
+        let directory = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(directory.refresh_interval);
+            loop {
+                interval.tick().await;
+                directory.refresh().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DiscoveredServiceConfig;
+
+    #[test]
+    fn test_parse_endpoint() {
+        let endpoint: Endpoint = "orchestrator.internal:8081".parse().unwrap();
+        assert_eq!(endpoint, Endpoint::new("orchestrator.internal", 8081));
+        assert_eq!(endpoint.url("http"), "http://orchestrator.internal:8081");
+
+        let endpoint: Endpoint = "[fd00::7]:8082".parse().unwrap();
+        assert_eq!(endpoint.host, "fd00::7");
+        assert_eq!(endpoint.to_string(), "[fd00::7]:8082");
+
+        assert!("orchestrator".parse::<Endpoint>().is_err());
+        assert!(":8081".parse::<Endpoint>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_static_directory() {
+        let mut config = DiscoveryConfig::default();
+        config.services.insert(
+            "orchestrator".to_string(),
+            DiscoveredServiceConfig {
+                name: None,
+                endpoints: vec!["10.0.0.1:8081".to_string(), "10.0.0.2:8081".to_string()],
+            },
+        );
+        let directory = ServiceDirectory::from_config(&config).unwrap();
+        assert!(directory.pool("rag_injector").is_none());
+
+        directory.refresh().await;
+        let pool = directory.pool("orchestrator").unwrap();
+        assert_eq!(pool.endpoints().len(), 2);
+    }
+}
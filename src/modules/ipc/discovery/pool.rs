@@ -0,0 +1,272 @@
+//! Service Pool
+//!
+//! The instances of one role's service, refreshed from a discovery backend.
+//! Clients pick an instance with [`ServicePool::select`] and report how the
+//! call went, so instances failing repeatedly leave the rotation.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::join_all;
+use metrics::{counter, gauge};
+use tracing::{debug, warn};
+
+use super::instances::InstanceStatus;
+use super::{DiscoveryBackend, DiscoveryError, Endpoint, Instances};
+use crate::config::DiscoveryConfig;
+use crate::modules::telemetry::catalog;
+
+/// Time allowed for an instance to answer its health check
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How a pool evicts instances
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    /// Consecutive failed calls evicting an instance
+    pub eviction_threshold: u32,
+    /// Time an evicted instance is left out of the rotation
+    pub eviction: Duration,
+    /// Probe each instance's `/health` endpoint on refresh
+    pub health_check: bool,
+}
+
+impl PoolSettings {
+    /// Settings of the discovery configuration
+    pub fn from_config(config: &DiscoveryConfig) -> Self {
+        Self {
+            eviction_threshold: config.eviction_threshold,
+            eviction: Duration::from_secs(config.eviction_secs),
+            health_check: config.health_check,
+        }
+    }
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self::from_config(&DiscoveryConfig::default())
+    }
+}
+
+/// The instances of a role's service
+#[derive(Debug)]
+pub struct ServicePool {
+    role: String,
+    name: String,
+    backend: Arc<dyn DiscoveryBackend>,
+    settings: PoolSettings,
+    instances: Mutex<Instances>,
+    http: reqwest::Client,
+}
+
+impl ServicePool {
+    /// Create an empty pool of the instances of `name`, filled by
+    /// [`refresh`](Self::refresh)
+    pub fn new(
+        role: impl Into<String>,
+        name: impl Into<String>,
+        backend: Arc<dyn DiscoveryBackend>,
+        settings: PoolSettings,
+    ) -> Self {
+        Self {
+            role: role.into(),
+            name: name.into(),
+            backend,
+            settings,
+            instances: Mutex::new(Instances::new()),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Role whose instances are pooled
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    /// Name looked up with the backend
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Look the instances up again, keeping the previous ones if the lookup
+    /// fails and evicting those failing their health check
+    pub async fn refresh(&self) -> Result<(), DiscoveryError> {
+        let endpoints = self.backend.discover(&self.name).await?;
+        debug!(
+            "Discovered {} instances of {} with {}",
+            endpoints.len(),
+            self.role,
+            self.backend.name()
+        );
+
+        let unhealthy = if self.settings.health_check {
+            let checks = endpoints.iter().map(|endpoint| self.probe(endpoint));
+            join_all(checks)
+                .await
+                .into_iter()
+                .zip(&endpoints)
+                .filter(|(healthy, _)| !healthy)
+                .map(|(_, endpoint)| endpoint.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let mut instances = self.instances.lock().unwrap();
+        instances.update(endpoints);
+        let now = Instant::now();
+        for endpoint in &unhealthy {
+            warn!(
+                "Instance {} of {} failed its health check",
+                endpoint, self.role
+            );
+            instances.evict(endpoint, self.settings.eviction, now);
+            counter!(catalog::DISCOVERY_EVICTIONS, 1, "role" => self.role.clone(), "reason" => "health_check");
+        }
+        self.record_available(&instances, now);
+        Ok(())
+    }
+
+    /// Next instance to call, if any was discovered
+    pub fn select(&self) -> Option<Endpoint> {
+        self.instances.lock().unwrap().select(Instant::now())
+    }
+
+    /// Report a successful call to an instance
+    pub fn record_success(&self, endpoint: &Endpoint) {
+        let mut instances = self.instances.lock().unwrap();
+        instances.record_success(endpoint);
+        self.record_available(&instances, Instant::now());
+    }
+
+    /// Report a failed call to an instance, evicting it after too many
+    pub fn record_failure(&self, endpoint: &Endpoint) {
+        let mut instances = self.instances.lock().unwrap();
+        let now = Instant::now();
+        let evicted = instances.record_failure(
+            endpoint,
+            self.settings.eviction_threshold,
+            self.settings.eviction,
+            now,
+        );
+        if evicted {
+            warn!(
+                "Evicted instance {} of {} for {}s after {} failed calls",
+                endpoint,
+                self.role,
+                self.settings.eviction.as_secs(),
+                self.settings.eviction_threshold
+            );
+            counter!(catalog::DISCOVERY_EVICTIONS, 1, "role" => self.role.clone(), "reason" => "failures");
+            self.record_available(&instances, now);
+        }
+    }
+
+    /// Addresses of every instance, evicted or not
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.instances.lock().unwrap().endpoints()
+    }
+
+    /// State of every instance
+    pub fn status(&self) -> Vec<InstanceStatus> {
+        self.instances.lock().unwrap().status(Instant::now())
+    }
+
+    async fn probe(&self, endpoint: &Endpoint) -> bool {
+        let url = format!("{}/health", endpoint.url("http"));
+        match self
+            .http
+            .get(&url)
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                debug!("Health check of {} failed: {}", url, e);
+                false
+            }
+        }
+    }
+
+    fn record_available(&self, instances: &Instances, now: Instant) {
+        gauge!(
+            catalog::DISCOVERY_INSTANCES,
+            instances.available(now) as f64,
+            "role" => self.role.clone()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Backend failing on demand after a first lookup
+    #[derive(Debug, Default)]
+    struct FlakyDiscovery {
+        failing: AtomicBool,
+    }
+
+    #[async_trait]
+    impl DiscoveryBackend for FlakyDiscovery {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn discover(&self, service: &str) -> Result<Vec<Endpoint>, DiscoveryError> {
+            if self.failing.load(Ordering::SeqCst) {
+                return Err(DiscoveryError::lookup(service, "unreachable"));
+            }
+            Ok(vec![
+                Endpoint::new("10.0.0.1", 8081),
+                Endpoint::new("10.0.0.2", 8081),
+            ])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_refresh_keeps_instances() {
+        let backend = Arc::new(FlakyDiscovery::default());
+        let pool = ServicePool::new(
+            "orchestrator",
+            "orchestrator",
+            backend.clone(),
+            PoolSettings::default(),
+        );
+        assert!(pool.select().is_none());
+
+        pool.refresh().await.unwrap();
+        backend.failing.store(true, Ordering::SeqCst);
+        assert!(pool.refresh().await.is_err());
+        assert_eq!(pool.endpoints().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failing_instance_evicted() {
+        let settings = PoolSettings {
+            eviction_threshold: 2,
+            eviction: Duration::from_secs(60),
+            health_check: false,
+        };
+        let pool = ServicePool::new(
+            "orchestrator",
+            "orchestrator",
+            Arc::new(FlakyDiscovery::default()),
+            settings,
+        );
+        pool.refresh().await.unwrap();
+
+        let failing = Endpoint::new("10.0.0.1", 8081);
+        pool.record_failure(&failing);
+        pool.record_failure(&failing);
+        for _ in 0..3 {
+            assert_eq!(pool.select().unwrap(), Endpoint::new("10.0.0.2", 8081));
+        }
+
+        pool.record_success(&failing);
+        let status = pool.status();
+        assert!(status.iter().all(|instance| instance.available));
+    }
+}
@@ -3,6 +3,7 @@
 //! This module provides inter-process communication functionality for the IntelliRouter system.
 
 pub mod chain_engine;
+pub mod discovery;
 pub mod memory;
 pub mod model_registry;
 pub mod persona_layer;
@@ -27,6 +28,9 @@ pub use persona_layer::PersonaLayerClient;
 pub use rag_manager::RAGManagerClient;
 pub use redis_pubsub::{ChannelName, EventPayload, Message, RedisClient, Subscription};
 
+// Re-export service discovery
+pub use discovery::{DiscoveryBackend, DiscoveryError, Endpoint, ServiceDirectory, ServicePool};
+
 // Re-export security
 pub use security::{JwtAuthenticator, JwtConfig, TlsConfig};
 
@@ -5,15 +5,18 @@
 
 use async_trait::async_trait;
 use futures::{Stream, StreamExt};
-use redis::aio::ConnectionManager;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio_stream::wrappers::ReceiverStream;
 
+use crate::modules::common::{RedisConnection, RedisConnector};
 use crate::modules::ipc::{IpcError, IpcResult};
 
+/// Idle connections kept for publishing to a sentinel master or cluster
+const IDLE_PUBLISH_CONNECTIONS: usize = 8;
+
 /// Channel naming convention for Redis pub/sub channels
 ///
 /// Format: `intellirouter:{source_module}:{destination_module}:{event_type}`
@@ -228,50 +231,93 @@ pub trait RedisClient: Send + Sync {
     async fn psubscribe(&self, pattern: &str) -> IpcResult<Subscription>;
 }
 
+/// Connections messages are published on
+enum Publisher {
+    /// Multiplexed connection to a single server, shared by every publish
+    Multiplexed(redis::aio::ConnectionManager),
+    /// Pool of connections following a sentinel master or cluster, each
+    /// publish taking an idle one or opening a new one
+    Pool(std::sync::Mutex<Vec<RedisConnection>>),
+}
+
 /// Redis client implementation
+///
+/// Messages are published concurrently: on a multiplexed connection to a
+/// single server, or on a pool of connections to a sentinel master or
+/// cluster, from which connections are dropped after an error. Each
+/// subscription listens on a connection of its own.
 pub struct RedisClientImpl {
-    client: redis::Client,
-    connection_manager: ConnectionManager,
+    redis: RedisConnector,
+    publisher: Publisher,
 }
 
 impl RedisClientImpl {
     /// Create a new Redis client
     pub async fn new(redis_url: &str) -> IpcResult<Self> {
-        let client = redis::Client::open(redis_url)
+        let redis = RedisConnector::from_url(redis_url)
             .map_err(|e| IpcError::Connection(format!("Failed to connect to Redis: {}", e)))?;
+        Self::from_connector(redis).await
+    }
 
-        let connection_manager = client.get_tokio_connection_manager().await.map_err(|e| {
-            IpcError::Connection(format!("Failed to get connection manager: {}", e))
-        })?;
+    /// Create a Redis client connecting through a connector
+    pub async fn from_connector(redis: RedisConnector) -> IpcResult<Self> {
+        let connect_error = |e| IpcError::Connection(format!("Failed to connect to Redis: {}", e));
+        let publisher = match redis.multiplexed().await.map_err(connect_error)? {
+            Some(manager) => Publisher::Multiplexed(manager),
+            None => {
+                let connection = redis.connection().await.map_err(connect_error)?;
+                Publisher::Pool(std::sync::Mutex::new(vec![connection]))
+            }
+        };
 
-        Ok(Self {
-            client,
-            connection_manager,
-        })
+        Ok(Self { redis, publisher })
+    }
+
+    /// Open a connection for a subscription
+    async fn pubsub(&self) -> IpcResult<redis::aio::PubSub> {
+        self.redis
+            .pubsub()
+            .await
+            .map_err(|e| IpcError::Connection(format!("Failed to get connection: {}", e)))
     }
 }
 
 #[async_trait]
 impl RedisClient for RedisClientImpl {
     async fn publish(&self, channel: &str, message: &[u8]) -> IpcResult<()> {
-        let mut conn = self.connection_manager.clone();
-        redis::cmd("PUBLISH")
-            .arg(channel)
-            .arg(message)
-            .query_async(&mut conn)
+        let mut cmd = redis::cmd("PUBLISH");
+        cmd.arg(channel).arg(message);
+        let redis_error = |e| IpcError::Connection(format!("Redis error: {}", e));
+
+        let pool = match &self.publisher {
+            Publisher::Multiplexed(manager) => {
+                return cmd
+                    .query_async(&mut manager.clone())
+                    .await
+                    .map(|_: redis::Value| ()) // Explicitly map Ok(value) to Ok(())
+                    .map_err(redis_error);
+            }
+            Publisher::Pool(pool) => pool,
+        };
+
+        let idle = pool.lock().unwrap().pop();
+        let mut conn = match idle {
+            Some(conn) => conn,
+            None => self.redis.connection().await.map_err(redis_error)?,
+        };
+        cmd.query_async(&mut conn)
             .await
-            .map(|_: redis::Value| ()) // Explicitly map Ok(value) to Ok(())
-            .map_err(|e| IpcError::Connection(format!("Redis error: {}", e)))?;
+            .map(|_: redis::Value| ())
+            .map_err(redis_error)?;
+        let mut idle = pool.lock().unwrap();
+        if idle.len() < IDLE_PUBLISH_CONNECTIONS {
+            idle.push(conn);
+        }
         Ok(())
     }
 
     async fn subscribe(&self, channel: &str) -> IpcResult<Subscription> {
-        let mut pubsub = self
-            .client
-            .get_async_connection()
-            .await
-            .map_err(|e| IpcError::Connection(format!("Failed to get connection: {}", e)))?
-            .into_pubsub();
+        let mut pubsub = self.pubsub().await?;
 
         pubsub
             .subscribe(channel)
@@ -282,12 +328,7 @@ impl RedisClient for RedisClientImpl {
     }
 
     async fn psubscribe(&self, pattern: &str) -> IpcResult<Subscription> {
-        let mut pubsub = self
-            .client
-            .get_async_connection()
-            .await
-            .map_err(|e| IpcError::Connection(format!("Failed to get connection: {}", e)))?
-            .into_pubsub();
+        let mut pubsub = self.pubsub().await?;
 
         pubsub
             .psubscribe(pattern)
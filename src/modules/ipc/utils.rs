@@ -4,8 +4,8 @@
 
 use crate::config::Config;
 use crate::modules::ipc::{
-    IpcResult, ResilientChainEngineClient, ResilientMemoryClient,
-    ResilientModelRegistryClient, ResilientPersonaLayerClient, ResilientRAGManagerClient,
+    IpcResult, ResilientChainEngineClient, ResilientMemoryClient, ResilientModelRegistryClient,
+    ResilientPersonaLayerClient, ResilientRAGManagerClient, ServiceDirectory,
 };
 
 /// A collection of resilient clients for all services
@@ -51,35 +51,66 @@ impl ResilientClients {
     }
 }
 
-/// Create all resilient clients based on configuration
-pub async fn create_all_resilient_clients(config: &Config) -> IpcResult<ResilientClients> {
-    let host = config.server.host.to_string();
-    let base_port = config.server.port;
+/// Address of a role's service: the next instance discovered for the role,
+/// or the role's port on the configured host
+fn role_address(config: &Config, directory: &ServiceDirectory, role: &str, offset: u16) -> String {
+    directory
+        .pool(role)
+        .and_then(|pool| pool.select())
+        .map(|endpoint| endpoint.url("http"))
+        .unwrap_or_else(|| {
+            format!(
+                "http://{}:{}",
+                config.server.host,
+                config.server.port + offset
+            )
+        })
+}
 
+/// Create all resilient clients based on configuration, addressing the roles
+/// discovered by `directory`
+pub async fn create_all_resilient_clients(
+    config: &Config,
+    directory: &ServiceDirectory,
+) -> IpcResult<ResilientClients> {
     // Create clients with appropriate ports
-    let chain_engine =
-        super::create_resilient_chain_engine_client(&format!("http://{}:{}", host, base_port + 1))
-            .await
-            .ok();
+    let chain_engine = super::create_resilient_chain_engine_client(&role_address(
+        config,
+        directory,
+        "orchestrator",
+        1,
+    ))
+    .await
+    .ok();
 
-    let memory = super::create_resilient_memory_client(&format!("http://{}:{}", host, base_port))
-        .await
-        .ok();
-
-    let model_registry =
-        super::create_resilient_model_registry_client(&format!("http://{}:{}", host, base_port))
+    let memory =
+        super::create_resilient_memory_client(&role_address(config, directory, "router", 0))
             .await
             .ok();
 
-    let persona_layer =
-        super::create_resilient_persona_layer_client(&format!("http://{}:{}", host, base_port + 3))
-            .await
-            .ok();
+    let model_registry = super::create_resilient_model_registry_client(&role_address(
+        config, directory, "router", 0,
+    ))
+    .await
+    .ok();
 
-    let rag_manager =
-        super::create_resilient_rag_manager_client(&format!("http://{}:{}", host, base_port + 2))
-            .await
-            .ok();
+    let persona_layer = super::create_resilient_persona_layer_client(&role_address(
+        config,
+        directory,
+        "summarizer",
+        3,
+    ))
+    .await
+    .ok();
+
+    let rag_manager = super::create_resilient_rag_manager_client(&role_address(
+        config,
+        directory,
+        "rag_injector",
+        2,
+    ))
+    .await
+    .ok();
 
     Ok(ResilientClients {
         chain_engine,
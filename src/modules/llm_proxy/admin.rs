@@ -0,0 +1,2587 @@
+//! Admin Access Control
+//!
+//! This module enforces roles on the admin endpoints and serves the admin
+//! APIs for the model registry, tenant budgets, credentials and log levels.
+//! Callers get a role from their admin API key scopes or from the claims of
+//! an OIDC token: viewers can read, operators can also change operational
+//! state (model status, budget resets, log levels) and admins can change
+//! configuration. Every privileged mutation, allowed or denied, is recorded
+//! as an audit event.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::dto::{ApiError, ApiErrorDetail};
+use super::model_rollout::{ModelRollout, RolloutError};
+use super::payloads::{PayloadError, PayloadRecord};
+use super::prompt_trace::PromptTrace;
+use super::prompts::{PromptError, PromptInfo, PromptServeRecord};
+use super::server::AppState;
+use super::tenant::extract_api_key;
+use super::user_usage::UserUsage;
+use crate::config::{Config, OidcRoleConfig, ProxyConfig};
+use crate::modules::common::{SqliteDatabase, SqliteError, TrafficClass};
+use crate::modules::encryption::{encryptor_from_config, EnvelopeEncryptor};
+use crate::modules::erasure::ErasureSubject;
+use crate::modules::model_registry::connectors::{
+    ModelConnector, OllamaConnector, OpenAIConnector,
+};
+use crate::modules::model_registry::{
+    DiscoveryError, FineTuneStatus, ModelMetadata, ModelStatus, RegistryError,
+};
+use crate::modules::router_core::policy::{PolicyError, RoutingPolicy};
+use crate::modules::telemetry::logging::{self, LogLevels, LoggingError};
+use crate::modules::telemetry::metering;
+use crate::modules::telemetry::{CostBackfill, ModelPrice};
+
+/// Role granted on the admin endpoints, ordered by privilege
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    /// Read admin state
+    Viewer,
+    /// Also change operational state
+    Operator,
+    /// Also change configuration
+    Admin,
+}
+
+impl fmt::Display for AdminRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminRole::Viewer => write!(f, "viewer"),
+            AdminRole::Operator => write!(f, "operator"),
+            AdminRole::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// Caller authenticated on the admin endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AdminPrincipal {
+    /// Key name or token subject
+    pub subject: String,
+    /// Role granted to the caller
+    pub role: AdminRole,
+}
+
+/// Reasons a caller may not use an admin endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdminAuthError {
+    /// No admin credentials were presented
+    Unauthenticated,
+    /// The caller's role is below the one required
+    Forbidden {
+        subject: String,
+        role: AdminRole,
+        required: AdminRole,
+    },
+}
+
+impl IntoResponse for AdminAuthError {
+    fn into_response(self) -> Response {
+        let (message, code) = match self {
+            AdminAuthError::Unauthenticated => (
+                "Admin credentials are required".to_string(),
+                "admin_required",
+            ),
+            AdminAuthError::Forbidden { role, required, .. } => (
+                format!("The {} role is required, caller has {}", required, role),
+                "insufficient_role",
+            ),
+        };
+        (
+            StatusCode::FORBIDDEN,
+            Json(ApiError {
+                error: ApiErrorDetail {
+                    message,
+                    r#type: "permission_error".to_string(),
+                    param: None,
+                    code: Some(code.to_string()),
+                },
+            }),
+        )
+            .into_response()
+    }
+}
+
+/// Whether any admin credentials are configured
+///
+/// Admin endpoints are refused to everyone until some are, unless anonymous
+/// admins are explicitly allowed.
+pub fn admin_configured(config: &ProxyConfig) -> bool {
+    !config.admin_api_keys.is_empty()
+        || !config.admin_keys.is_empty()
+        || config.admin_rbac.oidc.is_some()
+}
+
+/// Resolve the admin principal of the request credentials, if any
+pub fn resolve_principal(config: &ProxyConfig, headers: &HeaderMap) -> Option<AdminPrincipal> {
+    if !admin_configured(config) {
+        return config.allow_anonymous_admin.then(|| AdminPrincipal {
+            subject: "anonymous".to_string(),
+            role: AdminRole::Admin,
+        });
+    }
+    let credential = extract_api_key(headers)?;
+
+    if config.admin_api_keys.iter().any(|k| k == credential) {
+        return Some(AdminPrincipal {
+            subject: masked_key(credential),
+            role: AdminRole::Admin,
+        });
+    }
+    if let Some(key) = config.admin_keys.iter().find(|k| k.key == credential) {
+        let role = key
+            .scopes
+            .iter()
+            .filter_map(|scope| config.admin_rbac.scope_roles.get(scope))
+            .max()?;
+        return Some(AdminPrincipal {
+            subject: key.name.clone(),
+            role: *role,
+        });
+    }
+
+    let oidc = config.admin_rbac.oidc.as_ref()?;
+    match oidc_principal(oidc, credential) {
+        Ok(principal) => principal,
+        Err(e) => {
+            // Tenant API keys end up here too, so failures are not warnings
+            debug!("Bearer credential is not a valid OIDC token: {}", e);
+            None
+        }
+    }
+}
+
+/// Check that the request credentials grant at least a role
+pub fn authorize(
+    config: &ProxyConfig,
+    headers: &HeaderMap,
+    required: AdminRole,
+) -> Result<AdminPrincipal, AdminAuthError> {
+    let principal = resolve_principal(config, headers).ok_or(AdminAuthError::Unauthenticated)?;
+    if principal.role < required {
+        return Err(AdminAuthError::Forbidden {
+            subject: principal.subject,
+            role: principal.role,
+            required,
+        });
+    }
+    Ok(principal)
+}
+
+/// Verify an OIDC token and map its claims to the highest granted role
+fn oidc_principal(
+    config: &OidcRoleConfig,
+    token: &str,
+) -> Result<Option<AdminPrincipal>, jsonwebtoken::errors::Error> {
+    let (key, algorithm) = match (&config.public_key_pem, &config.hmac_secret) {
+        (Some(pem), _) => (DecodingKey::from_rsa_pem(pem.as_bytes())?, Algorithm::RS256),
+        (None, Some(secret)) => (
+            DecodingKey::from_secret(secret.as_bytes()),
+            Algorithm::HS256,
+        ),
+        (None, None) => {
+            warn!("OIDC admin roles are configured without a verification key");
+            return Ok(None);
+        }
+    };
+    let mut validation = Validation::new(algorithm);
+    match &config.audience {
+        Some(audience) => validation.set_audience(&[audience]),
+        None => validation.validate_aud = false,
+    }
+    if let Some(issuer) = &config.issuer {
+        validation.set_issuer(&[issuer]);
+    }
+    let claims = decode::<Value>(token, &key, &validation)?.claims;
+
+    let role = claim_values(&claims, &config.role_claim)
+        .iter()
+        .filter_map(|value| config.claim_roles.get(value))
+        .max()
+        .copied();
+    Ok(role.map(|role| AdminPrincipal {
+        subject: claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string(),
+        role,
+    }))
+}
+
+/// Values of a claim addressed by a dotted path, as strings
+fn claim_values(claims: &Value, path: &str) -> Vec<String> {
+    let claim = path
+        .split('.')
+        .try_fold(claims, |value, segment| value.get(segment));
+    match claim {
+        Some(Value::String(value)) => value.split_whitespace().map(str::to_string).collect(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Identify an API key in audit events without revealing it
+fn masked_key(key: &str) -> String {
+    let suffix: String = key
+        .chars()
+        .rev()
+        .take(4)
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("key:...{}", suffix)
+}
+
+/// Outcome of an audited action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The action was performed
+    Success,
+    /// The caller lacked the required role
+    Denied,
+    /// The action failed
+    Failure,
+}
+
+/// Record of a privileged mutation
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEvent {
+    /// Event identifier
+    pub id: String,
+    /// Time of the action
+    pub timestamp: DateTime<Utc>,
+    /// Key name or token subject of the caller
+    pub subject: Option<String>,
+    /// Role of the caller
+    pub role: Option<AdminRole>,
+    /// Action attempted, e.g. `model.register`
+    pub action: String,
+    /// Resource acted upon
+    pub resource: String,
+    /// Outcome of the action
+    pub outcome: AuditOutcome,
+    /// Error or denial detail
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl AuditEvent {
+    /// Create an event timestamped now
+    pub fn new(
+        subject: Option<String>,
+        role: Option<AdminRole>,
+        action: &str,
+        resource: &str,
+        outcome: AuditOutcome,
+        detail: Option<String>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            subject,
+            role,
+            action: action.to_string(),
+            resource: resource.to_string(),
+            outcome,
+            detail,
+        }
+    }
+}
+
+/// SQLite table of persisted audit events
+const AUDIT_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS audit_events (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    id TEXT NOT NULL UNIQUE,
+    recorded_at INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS audit_events_recorded_at ON audit_events (recorded_at)";
+
+/// Bounded log of audit events
+///
+/// Events are also emitted on the `audit` tracing target so they reach the
+/// configured log pipeline. With the embedded storage profile every event is
+/// also kept in the SQLite file, and the most recent ones survive restarts.
+#[derive(Debug)]
+pub struct AdminAuditLog {
+    capacity: usize,
+    events: Arc<Mutex<VecDeque<AuditEvent>>>,
+    store: Option<AuditStore>,
+}
+
+/// Persisted audit events, written by a background task off the request
+/// path
+struct AuditStore {
+    db: SqliteDatabase,
+    /// Encrypts events at rest, if enabled
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+    writer: mpsc::UnboundedSender<AuditWrite>,
+}
+
+impl fmt::Debug for AuditStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditStore")
+            .field("db", &self.db)
+            .field("encrypted", &self.encryptor.is_some())
+            .finish()
+    }
+}
+
+/// Request to the writer task of an audit store
+enum AuditWrite {
+    Event(AuditEvent),
+    /// Acknowledged once the writes queued before it are done
+    Flush(oneshot::Sender<()>),
+}
+
+impl AuditStore {
+    /// Wait until the events queued so far are persisted
+    async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        if self.writer.send(AuditWrite::Flush(done)).is_ok() {
+            flushed.await.ok();
+        }
+    }
+
+    /// Rewrite the persisted events changed by `anonymize_event`
+    async fn anonymize(
+        &self,
+        anonymize_event: impl Fn(&mut AuditEvent) -> bool,
+    ) -> Result<usize, String> {
+        let rows = self
+            .db
+            .call(|conn| conn.query("SELECT seq, id, data FROM audit_events", &[]))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut updates = Vec::new();
+        for row in rows {
+            let (Some(seq), Some(id), Some(data)) =
+                (row[0].as_i64(), row[1].as_str(), row[2].as_str())
+            else {
+                continue;
+            };
+            let Ok(mut event) = open_event(self.encryptor.as_deref(), id, data.to_string()).await
+            else {
+                continue;
+            };
+            if !anonymize_event(&mut event) {
+                continue;
+            }
+            updates.push((seq, seal_event(self.encryptor.as_deref(), &event).await?));
+        }
+
+        let anonymized = updates.len();
+        self.db
+            .call(move |conn| {
+                for (seq, data) in updates {
+                    conn.execute(
+                        "UPDATE audit_events SET data = ?1 WHERE seq = ?2",
+                        &[data.into(), seq.into()],
+                    )?;
+                }
+                Ok(())
+            })
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(anonymized)
+    }
+}
+
+/// Associated data sealing an audit event
+fn sealing_key(id: &str) -> String {
+    format!("audit_events:{}", id)
+}
+
+/// Serialize an audit event, sealed if encryption is enabled
+async fn seal_event(
+    encryptor: Option<&EnvelopeEncryptor>,
+    event: &AuditEvent,
+) -> Result<String, String> {
+    let json = serde_json::to_string(event).map_err(|e| e.to_string())?;
+    match encryptor {
+        Some(encryptor) => encryptor
+            .seal_str(&json, &sealing_key(&event.id))
+            .await
+            .map_err(|e| e.to_string()),
+        None => Ok(json),
+    }
+}
+
+/// Read a persisted audit event, opening it if it is sealed
+async fn open_event(
+    encryptor: Option<&EnvelopeEncryptor>,
+    id: &str,
+    data: String,
+) -> Result<AuditEvent, String> {
+    let json = match encryptor {
+        Some(encryptor) => encryptor
+            .open_str(data, &sealing_key(id))
+            .await
+            .map_err(|e| e.to_string())?,
+        None => data,
+    };
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+/// Load the most recent persisted events, then persist the recorded ones
+/// in order, pruning the events older than `retention`
+async fn write_audit_events(
+    db: SqliteDatabase,
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+    retention: Duration,
+    capacity: usize,
+    events: Arc<Mutex<VecDeque<AuditEvent>>>,
+    mut writes: mpsc::UnboundedReceiver<AuditWrite>,
+) {
+    let loaded = db
+        .call(move |conn| {
+            conn.query(
+                "SELECT id, data FROM audit_events ORDER BY seq DESC LIMIT ?1",
+                &[(capacity as i64).into()],
+            )
+        })
+        .await;
+    match loaded {
+        Ok(rows) => {
+            let mut loaded = Vec::new();
+            for row in rows {
+                let (Some(id), Some(data)) = (row[0].as_str(), row[1].as_str()) else {
+                    continue;
+                };
+                match open_event(encryptor.as_deref(), id, data.to_string()).await {
+                    Ok(event) => loaded.push(event),
+                    Err(e) => warn!("Skipping unreadable audit event {}: {}", id, e),
+                }
+            }
+            // Events recorded while loading are newer than the loaded ones
+            let mut events = events.lock().unwrap();
+            for event in loaded {
+                events.push_front(event);
+            }
+            while events.len() > capacity {
+                events.pop_front();
+            }
+        }
+        Err(e) => warn!("Failed to load persisted audit events: {}", e),
+    }
+
+    while let Some(write) = writes.recv().await {
+        let mut batch = Vec::new();
+        let mut flushed = Vec::new();
+        let mut next = Some(write);
+        while let Some(write) = next {
+            match write {
+                AuditWrite::Event(event) => batch.push(event),
+                AuditWrite::Flush(done) => flushed.push(done),
+            }
+            next = writes.try_recv().ok();
+        }
+
+        let mut rows = Vec::with_capacity(batch.len());
+        for event in batch {
+            match seal_event(encryptor.as_deref(), &event).await {
+                Ok(data) => rows.push((event.id, event.timestamp.timestamp_millis(), data)),
+                Err(e) => warn!("Failed to persist audit event {}: {}", event.id, e),
+            }
+        }
+        let retention_ms = i64::try_from(retention.as_millis()).unwrap_or(i64::MAX);
+        let cutoff = Utc::now().timestamp_millis().saturating_sub(retention_ms);
+        let stored = db
+            .call(move |conn| {
+                for (id, recorded_at, data) in rows {
+                    conn.execute(
+                        "INSERT INTO audit_events (id, recorded_at, data) VALUES (?1, ?2, ?3)",
+                        &[id.into(), recorded_at.into(), data.into()],
+                    )?;
+                }
+                conn.execute(
+                    "DELETE FROM audit_events WHERE recorded_at < ?1",
+                    &[cutoff.into()],
+                )
+            })
+            .await;
+        if let Err(e) = stored {
+            warn!("Failed to persist audit events: {}", e);
+        }
+        for done in flushed {
+            done.send(()).ok();
+        }
+    }
+}
+
+impl AdminAuditLog {
+    /// Create an audit log keeping the most recent `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            events: Arc::new(Mutex::new(VecDeque::new())),
+            store: None,
+        }
+    }
+
+    /// Create the audit log of the deployment's storage
+    ///
+    /// The embedded storage profile persists events to its SQLite file,
+    /// sealed when encryption at rest is enabled, falling back to memory
+    /// only if the file cannot be opened.
+    pub fn from_config(config: &Config) -> Self {
+        let rbac = &config.proxy.admin_rbac;
+        if config.storage.is_embedded() {
+            let encryptor = encryptor_from_config(&config.encryption)
+                .expect("Failed to initialize encryption at rest");
+            let log = SqliteDatabase::from_config(&config.storage).and_then(|db| {
+                Self::with_store(
+                    rbac.audit_capacity,
+                    db,
+                    encryptor,
+                    Duration::from_secs(rbac.audit_retention_secs),
+                )
+            });
+            match log {
+                Ok(log) => return log,
+                Err(e) => warn!("Falling back to an in-memory audit log: {}", e),
+            }
+        }
+        Self::new(rbac.audit_capacity)
+    }
+
+    /// Create an audit log persisting its events to a SQLite database
+    ///
+    /// Events are written by a background task, which first loads the most
+    /// recent `capacity` stored events, and pruned once older than
+    /// `retention`. Events are sealed with `encryptor` when one is given.
+    pub fn with_store(
+        capacity: usize,
+        db: SqliteDatabase,
+        encryptor: Option<Arc<EnvelopeEncryptor>>,
+        retention: Duration,
+    ) -> Result<Self, SqliteError> {
+        db.with(|conn| conn.execute_batch(AUDIT_SCHEMA))?;
+
+        let mut log = Self::new(capacity);
+        let (writer, writes) = mpsc::unbounded_channel();
+        tokio::spawn(write_audit_events(
+            db.clone(),
+            encryptor.clone(),
+            retention,
+            log.capacity,
+            log.events.clone(),
+            writes,
+        ));
+        log.store = Some(AuditStore {
+            db,
+            encryptor,
+            writer,
+        });
+        Ok(log)
+    }
+
+    /// Wait until the events recorded so far are persisted
+    pub async fn flush(&self) {
+        if let Some(store) = &self.store {
+            store.flush().await;
+        }
+    }
+
+    /// Record the outcome of a privileged action
+    pub fn record(
+        &self,
+        principal: Result<&AdminPrincipal, &AdminAuthError>,
+        action: &str,
+        resource: &str,
+        outcome: Result<(), String>,
+    ) {
+        // Health checks and monitoring scrapes probing admin endpoints
+        // would drown the denials worth reviewing
+        if principal.is_err() && TrafficClass::current().is_exempt() {
+            debug!(target: "audit", action, resource, "Synthetic admin request denied");
+            return;
+        }
+        let (subject, role, outcome, detail) = match (principal, outcome) {
+            (Ok(p), Ok(())) => (
+                Some(p.subject.clone()),
+                Some(p.role),
+                AuditOutcome::Success,
+                None,
+            ),
+            (Ok(p), Err(e)) => (
+                Some(p.subject.clone()),
+                Some(p.role),
+                AuditOutcome::Failure,
+                Some(e),
+            ),
+            (Err(AdminAuthError::Unauthenticated), _) => (
+                None,
+                None,
+                AuditOutcome::Denied,
+                Some("unauthenticated".to_string()),
+            ),
+            (
+                Err(AdminAuthError::Forbidden {
+                    subject,
+                    role,
+                    required,
+                }),
+                _,
+            ) => (
+                Some(subject.clone()),
+                Some(*role),
+                AuditOutcome::Denied,
+                Some(format!("requires {}", required)),
+            ),
+        };
+        self.push(AuditEvent::new(
+            subject, role, action, resource, outcome, detail,
+        ));
+    }
+
+    /// Record an audit event
+    pub fn push(&self, event: AuditEvent) {
+        info!(
+            target: "audit",
+            action = %event.action,
+            resource = %event.resource,
+            subject = event.subject.as_deref().unwrap_or("-"),
+            outcome = ?event.outcome,
+            "Admin action"
+        );
+
+        if let Some(store) = &self.store {
+            if store.writer.send(AuditWrite::Event(event.clone())).is_err() {
+                warn!("Failed to persist audit event {}: writer stopped", event.id);
+            }
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Recent events, oldest first
+    pub fn recent(&self) -> Vec<AuditEvent> {
+        self.events.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Replace a data subject by `pseudonym` in the recent events
+    ///
+    /// Events are kept as the record of who did what; only the tenant or
+    /// user segment of their resources, and tenant subjects, are replaced.
+    /// Persisted events are anonymized too. Returns how many events were
+    /// anonymized.
+    pub async fn anonymize(&self, subject: &ErasureSubject, pseudonym: &str) -> usize {
+        let (prefix, replacement) = match &subject.user {
+            Some(user) => (
+                format!("{}/{}", subject.tenant, user),
+                format!("{}/{}", subject.tenant, pseudonym),
+            ),
+            None => (subject.tenant.clone(), pseudonym.to_string()),
+        };
+        let tenant_subject = format!("tenant:{}", subject.tenant);
+        let anonymize_event = |event: &mut AuditEvent| {
+            let mut changed = false;
+            if let Some(rest) = event.resource.strip_prefix(&prefix) {
+                if rest.is_empty() || rest.starts_with('/') {
+                    event.resource = format!("{}{}", replacement, rest);
+                    changed = true;
+                }
+            }
+            if subject.user.is_none() && event.subject.as_deref() == Some(&tenant_subject) {
+                event.subject = Some(format!("tenant:{}", pseudonym));
+                changed = true;
+            }
+            changed
+        };
+
+        let mut anonymized = 0;
+        for event in self.events.lock().unwrap().iter_mut() {
+            anonymized += usize::from(anonymize_event(event));
+        }
+
+        if let Some(store) = &self.store {
+            store.flush().await;
+            match store.anonymize(anonymize_event).await {
+                Ok(stored) => anonymized = anonymized.max(stored),
+                Err(e) => warn!("Failed to anonymize persisted audit events: {}", e),
+            }
+        }
+        anonymized
+    }
+}
+
+impl Default for AdminAuditLog {
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+/// Authorize a privileged mutation, auditing denials
+fn authorize_mutation(
+    state: &AppState,
+    headers: &HeaderMap,
+    required: AdminRole,
+    action: &str,
+    resource: &str,
+) -> Result<AdminPrincipal, AdminAuthError> {
+    authorize(&state.config.proxy, headers, required)
+        .inspect_err(|e| state.admin_audit.record(Err(e), action, resource, Ok(())))
+}
+
+/// Build an admin API error response
+fn admin_error(status: StatusCode, message: String, code: &str) -> Response {
+    (
+        status,
+        Json(ApiError {
+            error: ApiErrorDetail {
+                message,
+                r#type: "invalid_request_error".to_string(),
+                param: None,
+                code: Some(code.to_string()),
+            },
+        }),
+    )
+        .into_response()
+}
+
+/// Map a registry error to an error response
+fn registry_error(e: &RegistryError) -> Response {
+    match e {
+        RegistryError::NotFound(_) => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "model_not_found")
+        }
+        RegistryError::AlreadyExists(_) => {
+            admin_error(StatusCode::CONFLICT, e.to_string(), "model_exists")
+        }
+        RegistryError::InvalidMetadata(_) => {
+            admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_model")
+        }
+        _ => admin_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            "registry_error",
+        ),
+    }
+}
+
+/// Request to change a model's status
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ModelStatusRequest {
+    #[schema(value_type = String, example = "Available")]
+    pub status: ModelStatus,
+}
+
+/// Route handler for GET /v1/admin/models
+#[utoipa::path(
+    get,
+    path = "/v1/admin/models",
+    operation_id = "admin_list_models",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Registered models", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_models(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(json!({ "models": state.registry.list_models() })).into_response()
+}
+
+/// Route handler for POST /v1/admin/models
+#[utoipa::path(
+    post,
+    path = "/v1/admin/models",
+    tag = "admin",
+    request_body = Object,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Registered model", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn register_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(model): Json<ModelMetadata>,
+) -> Response {
+    let action = "model.register";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Admin, action, &model.id)
+    {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let id = model.id.clone();
+    let result = state.registry.register_model(model.clone());
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(()) => (StatusCode::CREATED, Json(model)).into_response(),
+        Err(e) => registry_error(&e),
+    }
+}
+
+/// Map a model rollout error to an error response
+fn rollout_error(e: &RolloutError) -> Response {
+    match e {
+        RolloutError::UnknownModel(_) => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "model_not_found")
+        }
+        RolloutError::InProgress(_) => {
+            admin_error(StatusCode::CONFLICT, e.to_string(), "rollout_in_progress")
+        }
+        RolloutError::NotInProgress(_) => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "rollout_not_found")
+        }
+        RolloutError::Registry(_) => admin_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            "registry_error",
+        ),
+    }
+}
+
+/// Connector calling a model's updated endpoint, based on the connector of
+/// its current endpoint
+fn updated_connector(state: &AppState, model: &ModelMetadata) -> Option<Arc<dyn ModelConnector>> {
+    let current = state.registry.get_connector(&model.id)?;
+    let mut config = current.get_config().clone();
+    config.base_url = model.endpoint.clone();
+    if let Some(key) = &model.auth_key {
+        config.api_key = Some(key.clone());
+    }
+    let pool = state
+        .config
+        .proxy
+        .outbound_http
+        .for_provider(&model.provider);
+    Some(match model.provider.as_str() {
+        "ollama" => Arc::new(OllamaConnector::with_pool(config, pool)),
+        _ => Arc::new(OpenAIConnector::with_pool(config, pool)),
+    })
+}
+
+/// Route handler for PUT /v1/admin/models/{id}
+///
+/// A new endpoint or version is rolled out gradually, and rolled back if
+/// its error rate or latency regresses; other changes apply at once.
+#[utoipa::path(
+    put,
+    path = "/v1/admin/models/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    request_body = Object,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Updated model and the rollout it started, if any", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown model", body = ApiError),
+        (status = 409, description = "A rollout of the model is in progress", body = ApiError)
+    )
+)]
+pub async fn update_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(mut model): Json<ModelMetadata>,
+) -> Response {
+    let action = "model.update";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Admin, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    model.id = id.clone();
+    let connector = updated_connector(&state, &model);
+    let result = state.rollouts.update_model(model, connector);
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(rollout) => {
+            if let Some(rollout) = &rollout {
+                info!(
+                    "Rollout of model {} to {} started by {}",
+                    id, rollout.candidate.endpoint, principal.subject
+                );
+            }
+            let model = match &rollout {
+                Some(rollout) => Ok(rollout.candidate.clone()),
+                None => state.registry.get_model(&id),
+            };
+            match model {
+                Ok(model) => Json(json!({ "model": model, "rollout": rollout })).into_response(),
+                Err(e) => registry_error(&e),
+            }
+        }
+        Err(e) => rollout_error(&e),
+    }
+}
+
+/// Route handler for GET /v1/admin/rollouts
+#[utoipa::path(
+    get,
+    path = "/v1/admin/rollouts",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Model rollouts in progress and recently finished", body = Vec<ModelRollout>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_rollouts(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(state.rollouts.list()).into_response()
+}
+
+/// Route handler for GET /v1/admin/rollouts/{model}
+#[utoipa::path(
+    get,
+    path = "/v1/admin/rollouts/{model}",
+    tag = "admin",
+    params(("model" = String, Path, description = "Model ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Latest rollout of the model", body = ModelRollout),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "The model has no rollout", body = ApiError)
+    )
+)]
+pub async fn get_rollout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(model): Path<String>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.rollouts.get(&model) {
+        Some(rollout) => Json(rollout).into_response(),
+        None => rollout_error(&RolloutError::NotInProgress(model)),
+    }
+}
+
+/// Route handler for POST /v1/admin/rollouts/{model}/rollback
+#[utoipa::path(
+    post,
+    path = "/v1/admin/rollouts/{model}/rollback",
+    tag = "admin",
+    params(("model" = String, Path, description = "Model ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Rolled back rollout", body = ModelRollout),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "The model has no rollout in progress", body = ApiError)
+    )
+)]
+pub async fn rollback_rollout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(model): Path<String>,
+) -> Response {
+    let action = "model.rollback";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &model)
+    {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let reason = format!("rolled back by {}", principal.subject);
+    let result = state.rollouts.rollback(&model, &reason);
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &model,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(rollout) => Json(rollout).into_response(),
+        Err(e) => rollout_error(&e),
+    }
+}
+
+/// Route handler for PUT /v1/admin/models/{id}/status
+#[utoipa::path(
+    put,
+    path = "/v1/admin/models/{id}/status",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    request_body = ModelStatusRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Updated status", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown model", body = ApiError)
+    )
+)]
+pub async fn update_model_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<ModelStatusRequest>,
+) -> Response {
+    let action = "model.update_status";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state
+        .registry
+        .update_model_status(&id, request.status.clone());
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(()) => Json(json!({ "id": id, "status": request.status })).into_response(),
+        Err(e) => registry_error(&e),
+    }
+}
+
+/// Request to move a fine-tuned model to another lifecycle stage
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct FineTuneStatusRequest {
+    #[schema(value_type = String, example = "deprecated")]
+    pub status: FineTuneStatus,
+    /// What callers of the deprecated model should do instead, returned in
+    /// the deprecation warning
+    pub deprecation_notice: Option<String>,
+}
+
+/// Route handler for PUT /v1/admin/models/{id}/fine-tune
+///
+/// A model in training is not routed to; a deprecated model still serves
+/// requests, with a warning in their response metadata.
+#[utoipa::path(
+    put,
+    path = "/v1/admin/models/{id}/fine-tune",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    request_body = FineTuneStatusRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Updated model", body = Object),
+        (status = 400, description = "The model is not fine-tuned", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown model", body = ApiError)
+    )
+)]
+pub async fn update_fine_tune_status(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<FineTuneStatusRequest>,
+) -> Response {
+    let action = "model.fine_tune_status";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result =
+        state
+            .registry
+            .update_fine_tune_status(&id, request.status, request.deprecation_notice);
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(model) => Json(model).into_response(),
+        Err(e) => registry_error(&e),
+    }
+}
+
+/// Filter of the fine-tuned model listing
+#[derive(Debug, Clone, Deserialize)]
+pub struct FineTunesQuery {
+    /// Only fine-tunes of this base model
+    pub base_model: Option<String>,
+}
+
+/// Route handler for GET /v1/admin/fine-tunes
+#[utoipa::path(
+    get,
+    path = "/v1/admin/fine-tunes",
+    tag = "admin",
+    params(("base_model" = Option<String>, Query, description = "Only fine-tunes of this base model")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Fine-tuned models with their lineage, under `models`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_fine_tunes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FineTunesQuery>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let mut models = state.registry.find_fine_tunes(query.base_model.as_deref());
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Json(json!({ "models": models })).into_response()
+}
+
+/// Route handler for DELETE /v1/admin/models/{id}
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/models/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Model removed"),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown model", body = ApiError)
+    )
+)]
+pub async fn remove_model(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let action = "model.remove";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Admin, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state.registry.remove_model(&id);
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => registry_error(&e),
+    }
+}
+
+/// Error response of a disabled model discovery
+fn discovery_disabled() -> Response {
+    admin_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Model discovery is disabled".to_string(),
+        "model_discovery_disabled",
+    )
+}
+
+/// Map a model discovery error to an error response
+fn discovery_error(e: &DiscoveryError) -> Response {
+    match e {
+        DiscoveryError::NotFound(_) => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "proposal_not_found")
+        }
+        DiscoveryError::Decided(..) => {
+            admin_error(StatusCode::CONFLICT, e.to_string(), "proposal_decided")
+        }
+        DiscoveryError::Registry(e) => registry_error(e),
+    }
+}
+
+/// Route handler for GET /v1/admin/model-proposals
+#[utoipa::path(
+    get,
+    path = "/v1/admin/model-proposals",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Proposals of discovered models under `proposals`, and the last sync of each provider under `syncs`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Model discovery is disabled", body = ApiError)
+    )
+)]
+pub async fn model_proposals(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match &state.discovery {
+        Some(discovery) => Json(json!({
+            "proposals": discovery.proposals(),
+            "syncs": discovery.last_syncs(),
+        }))
+        .into_response(),
+        None => discovery_disabled(),
+    }
+}
+
+/// Route handler for POST /v1/admin/model-proposals/sync
+///
+/// Syncs the models of every provider without waiting for the next sync.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/model-proposals/sync",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Outcome of the sync of each provider under `syncs`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Model discovery is disabled", body = ApiError)
+    )
+)]
+pub async fn sync_model_proposals(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let action = "model.discover";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, "*") {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let Some(discovery) = &state.discovery else {
+        return discovery_disabled();
+    };
+    let syncs = discovery.sync().await;
+    state
+        .admin_audit
+        .record(Ok(&principal), action, "*", Ok(()));
+    Json(json!({ "syncs": syncs })).into_response()
+}
+
+/// Route handler for POST /v1/admin/model-proposals/{id}/approve
+///
+/// Registers the proposed model as available.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/model-proposals/{id}/approve",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Approved proposal", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "No proposal for the model", body = ApiError),
+        (status = 409, description = "The proposal was already decided, or the model is registered", body = ApiError),
+        (status = 503, description = "Model discovery is disabled", body = ApiError)
+    )
+)]
+pub async fn approve_model_proposal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    decide_model_proposal(state, headers, id, true)
+}
+
+/// Route handler for POST /v1/admin/model-proposals/{id}/reject
+///
+/// Rejected models are not proposed again.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/model-proposals/{id}/reject",
+    tag = "admin",
+    params(("id" = String, Path, description = "Model ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Rejected proposal", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "No proposal for the model", body = ApiError),
+        (status = 409, description = "The proposal was already decided", body = ApiError),
+        (status = 503, description = "Model discovery is disabled", body = ApiError)
+    )
+)]
+pub async fn reject_model_proposal(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    decide_model_proposal(state, headers, id, false)
+}
+
+/// Approve or reject the proposal of a discovered model
+fn decide_model_proposal(
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+    approve: bool,
+) -> Response {
+    let action = if approve {
+        "model.approve"
+    } else {
+        "model.reject"
+    };
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Admin, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let Some(discovery) = &state.discovery else {
+        return discovery_disabled();
+    };
+    let result = if approve {
+        discovery.approve(&id, &principal.subject)
+    } else {
+        discovery.reject(&id, &principal.subject)
+    };
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(proposal) => Json(proposal).into_response(),
+        Err(e) => discovery_error(&e),
+    }
+}
+
+/// Price of a model entered through the admin API
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PriceRequest {
+    /// Price per 1K prompt tokens
+    pub input_per_1k: f64,
+    /// Price per 1K completion tokens
+    pub output_per_1k: f64,
+    /// Currency of the price; the pricing currency when unset
+    #[serde(default)]
+    pub currency: Option<String>,
+    /// Time from which the price applies; now when unset
+    #[serde(default)]
+    pub effective_from: Option<DateTime<Utc>>,
+    /// Why the price was entered, e.g. a price change or a correction
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Error response of a missing cost calculator
+fn pricing_disabled() -> Response {
+    admin_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Cost calculation is disabled".to_string(),
+        "pricing_disabled",
+    )
+}
+
+/// Route handler for GET /v1/admin/pricing
+#[utoipa::path(
+    get,
+    path = "/v1/admin/pricing",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Currency of costs under `currency`, and the price history of each model under `prices`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Cost calculation is disabled", body = ApiError)
+    )
+)]
+pub async fn pricing(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match &state.cost_calculator {
+        Some(calculator) => Json(json!({
+            "currency": calculator.currency(),
+            "prices": calculator.prices(),
+        }))
+        .into_response(),
+        None => pricing_disabled(),
+    }
+}
+
+/// Route handler for POST /v1/admin/pricing/{model}
+///
+/// Enters a price of a model. A price effective in the past corrects the
+/// costs of the calls since, and the usage of their end users.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/pricing/{model}",
+    tag = "admin",
+    params(("model" = String, Path, description = "Model ID")),
+    request_body = PriceRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Entered price and the recomputed costs", body = CostBackfill),
+        (status = 400, description = "Invalid price, or a currency without an exchange rate", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Cost calculation is disabled", body = ApiError)
+    )
+)]
+pub async fn set_price(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(model): Path<String>,
+    Json(request): Json<PriceRequest>,
+) -> Response {
+    let action = "pricing.set";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Admin, action, &model) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let Some(calculator) = &state.cost_calculator else {
+        return pricing_disabled();
+    };
+    let price = ModelPrice {
+        entered_currency: request.currency,
+        note: request.note,
+        ..ModelPrice::new(
+            request.input_per_1k,
+            request.output_per_1k,
+            request.effective_from.unwrap_or_else(Utc::now),
+        )
+    };
+    let result = calculator.set_price(&model, price);
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &model,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(backfill) => {
+            let users = state
+                .user_usage
+                .apply_cost_corrections(&backfill.corrections);
+            let decisions = state
+                .decisions
+                .apply_cost_corrections(&backfill.corrections);
+            if backfill.recomputed > 0 {
+                info!(
+                    "Recomputed the costs of {} calls to {}: {:.4} {} to {:.4} {}",
+                    backfill.recomputed,
+                    model,
+                    backfill.previous_cost,
+                    backfill.currency,
+                    backfill.cost,
+                    backfill.currency
+                );
+            }
+            let mut body = json!(backfill);
+            body["adjusted_users"] = json!(users);
+            body["adjusted_decisions"] = json!(decisions);
+            Json(body).into_response()
+        }
+        Err(e) => admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_price"),
+    }
+}
+
+/// Route handler for GET /v1/admin/budgets
+///
+/// Lists the remaining token budget of every tenant with a quota.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/budgets",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Tenant token budgets", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_budgets(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let mut budgets = Vec::new();
+    for tenant in &state.config.proxy.tenants {
+        let Some(quota) = &tenant.token_quota else {
+            continue;
+        };
+        let status = state.quotas.status(tenant).await;
+        budgets.push(json!({
+            "tenant": tenant.id,
+            "quota": quota,
+            "limit": status.as_ref().map(|s| s.limit),
+            "remaining": status.as_ref().map(|s| s.remaining),
+            "reset_secs": status.as_ref().map(|s| s.reset.as_secs()),
+        }));
+    }
+    Json(json!({ "budgets": budgets })).into_response()
+}
+
+/// Route handler for DELETE /v1/admin/budgets/{tenant}
+///
+/// Clears a tenant's recorded token usage, restoring its full budget.
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/budgets/{tenant}",
+    tag = "admin",
+    params(("tenant" = String, Path, description = "Tenant ID")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Budget reset"),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown tenant", body = ApiError)
+    )
+)]
+pub async fn reset_budget(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+) -> Response {
+    let action = "budget.reset";
+    let principal =
+        match authorize_mutation(&state, &headers, AdminRole::Operator, action, &tenant_id) {
+            Ok(principal) => principal,
+            Err(e) => return e.into_response(),
+        };
+    if !state
+        .config
+        .proxy
+        .tenants
+        .iter()
+        .any(|tenant| tenant.id == tenant_id)
+    {
+        let message = format!("Tenant not found: {}", tenant_id);
+        state
+            .admin_audit
+            .record(Ok(&principal), action, &tenant_id, Err(message.clone()));
+        return admin_error(StatusCode::NOT_FOUND, message, "tenant_not_found");
+    }
+    let result = state.quotas.reset(&tenant_id).await;
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &tenant_id,
+        result.as_ref().map_err(ToString::to_string).copied(),
+    );
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => admin_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            "quota_error",
+        ),
+    }
+}
+
+/// Route handler for GET /v1/admin/keys
+///
+/// Lists admin and tenant credentials with the keys themselves masked.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/keys",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Masked admin and tenant keys", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_keys(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Admin) {
+        return e.into_response();
+    }
+    let proxy = &state.config.proxy;
+    let admin_keys: Vec<Value> = proxy
+        .admin_api_keys
+        .iter()
+        .map(|key| json!({ "key": masked_key(key), "role": AdminRole::Admin }))
+        .chain(proxy.admin_keys.iter().map(|key| {
+            json!({
+                "name": key.name,
+                "key": masked_key(&key.key),
+                "scopes": key.scopes,
+                "role": key
+                    .scopes
+                    .iter()
+                    .filter_map(|scope| proxy.admin_rbac.scope_roles.get(scope))
+                    .max(),
+            })
+        }))
+        .collect();
+    let tenant_keys: Vec<Value> = proxy
+        .tenants
+        .iter()
+        .map(|tenant| {
+            json!({
+                "tenant": tenant.id,
+                "keys": tenant.api_keys.iter().map(|k| masked_key(k)).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    Json(json!({ "admin_keys": admin_keys, "tenant_keys": tenant_keys })).into_response()
+}
+
+/// Route handler for GET /v1/admin/audit
+#[utoipa::path(
+    get,
+    path = "/v1/admin/audit",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent `AuditEvent`s under `events`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn audit_events(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Admin) {
+        return e.into_response();
+    }
+    Json(json!({ "events": state.admin_audit.recent() })).into_response()
+}
+
+/// Route handler for GET /v1/admin/logging
+#[utoipa::path(
+    get,
+    path = "/v1/admin/logging",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Log levels in effect", body = LogLevels),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Logging isn't configurable in this process", body = ApiError)
+    )
+)]
+pub async fn log_levels(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match logging::handle() {
+        Some(handle) => Json(handle.levels()).into_response(),
+        None => logging_error(&LoggingError::NotInitialized),
+    }
+}
+
+/// Route handler for PUT /v1/admin/logging
+#[utoipa::path(
+    put,
+    path = "/v1/admin/logging",
+    tag = "admin",
+    request_body = LogLevels,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Log levels now in effect", body = LogLevels),
+        (status = 400, description = "Invalid level or module", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Logging isn't configurable in this process", body = ApiError)
+    )
+)]
+pub async fn set_log_levels(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(levels): Json<LogLevels>,
+) -> Response {
+    let action = "logging.set_levels";
+    let resource = levels.directives();
+    let principal =
+        match authorize_mutation(&state, &headers, AdminRole::Operator, action, &resource) {
+            Ok(principal) => principal,
+            Err(e) => return e.into_response(),
+        };
+    let result = logging::handle()
+        .ok_or(LoggingError::NotInitialized)
+        .and_then(|handle| handle.set(levels.clone()));
+    state.admin_audit.record(
+        Ok(&principal),
+        action,
+        &resource,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(()) => {
+            info!(
+                "Log levels changed by {} to {}",
+                principal.subject, resource
+            );
+            Json(levels).into_response()
+        }
+        Err(e) => logging_error(&e),
+    }
+}
+
+/// Map a logging error to an error response
+fn logging_error(e: &LoggingError) -> Response {
+    match e {
+        LoggingError::InvalidLevel { .. } | LoggingError::InvalidDirective(_) => {
+            admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_log_level")
+        }
+        LoggingError::NotInitialized | LoggingError::Init(_) => admin_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            e.to_string(),
+            "logging_unavailable",
+        ),
+    }
+}
+
+/// Route handler for GET /v1/admin/anomalies
+#[utoipa::path(
+    get,
+    path = "/v1/admin/anomalies",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Ongoing and recently resolved anomalies under `anomalies`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Anomaly detection is disabled", body = ApiError)
+    )
+)]
+pub async fn anomalies(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match &state.anomalies {
+        Some(detector) => Json(json!({ "anomalies": detector.recent() })).into_response(),
+        None => admin_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Anomaly detection is disabled".to_string(),
+            "anomaly_detection_disabled",
+        ),
+    }
+}
+
+/// Route handler for GET /v1/admin/canaries
+#[utoipa::path(
+    get,
+    path = "/v1/admin/canaries",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "State and latest results of each canary under `canaries`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Canaries are disabled", body = ApiError)
+    )
+)]
+pub async fn canaries(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match &state.canaries {
+        Some(monitor) => Json(json!({ "canaries": monitor.statuses() })).into_response(),
+        None => admin_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Canaries are disabled".to_string(),
+            "canaries_disabled",
+        ),
+    }
+}
+
+/// Filter and format of the billing event export
+#[derive(Debug, Clone, Deserialize)]
+pub struct BillingEventsQuery {
+    /// Only events of this tenant
+    pub tenant: Option<String>,
+    /// Only events of periods starting at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// `json` (the default) or `csv`
+    pub format: Option<String>,
+}
+
+/// Route handler for GET /v1/admin/billing/events
+///
+/// Exports the billing events emitted by metering, oldest first.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/billing/events",
+    tag = "admin",
+    params(
+        ("tenant" = Option<String>, Query, description = "Only events of this tenant"),
+        ("since" = Option<String>, Query, description = "Only events of periods starting at or after this RFC 3339 time"),
+        ("format" = Option<String>, Query, description = "`json` (the default) or `csv`")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Billing events under `events`, or as CSV", body = Object),
+        (status = 400, description = "Unknown format", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 503, description = "Metering is disabled", body = ApiError)
+    )
+)]
+pub async fn billing_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<BillingEventsQuery>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let Some(metering) = &state.metering else {
+        return admin_error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Metering is disabled".to_string(),
+            "metering_disabled",
+        );
+    };
+    let events = metering.events(query.tenant.as_deref(), query.since);
+    match query.format.as_deref() {
+        None | Some("json") => Json(json!({ "events": events })).into_response(),
+        Some("csv") => (
+            [(axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            metering::to_csv(&events),
+        )
+            .into_response(),
+        Some(format) => admin_error(
+            StatusCode::BAD_REQUEST,
+            format!("Unknown format: {}", format),
+            "invalid_format",
+        ),
+    }
+}
+
+/// Request to publish a prompt version
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PublishPromptRequest {
+    /// System prompt text
+    pub template: String,
+    /// What changed in this version
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Request to roll a prompt version out to part of the traffic
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct PromptRolloutRequest {
+    /// Version to roll out
+    pub version: u32,
+    /// Share of callers served the version; 100 promotes it to stable
+    pub percentage: u8,
+}
+
+/// Request to roll a prompt back
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct PromptRollbackRequest {
+    /// Version to make stable; without one the rollout in progress stops,
+    /// or the last promotion is undone
+    #[serde(default)]
+    pub version: Option<u32>,
+}
+
+/// Filter of the prompt serving audit trail
+#[derive(Debug, Clone, Deserialize)]
+pub struct PromptServedQuery {
+    /// Only records of this prompt
+    pub prompt: Option<String>,
+}
+
+/// Filter of the end user usage
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserUsageQuery {
+    /// Only users of this tenant
+    pub tenant: Option<String>,
+    /// Maximum number of users, by most tokens used
+    pub limit: Option<usize>,
+}
+
+/// Map a prompt registry error to an error response
+fn prompt_error(e: &PromptError) -> Response {
+    match e {
+        PromptError::UnknownPrompt(_) | PromptError::UnknownVersion { .. } => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "prompt_not_found")
+        }
+        PromptError::NothingToRollBack(_) => admin_error(
+            StatusCode::CONFLICT,
+            e.to_string(),
+            "prompt_rollback_conflict",
+        ),
+        PromptError::InvalidPercentage(_) | PromptError::EmptyTemplate => {
+            admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_prompt")
+        }
+    }
+}
+
+/// Record a prompt mutation and respond with the prompt's state
+fn prompt_mutation_response<T>(
+    state: &AppState,
+    principal: &AdminPrincipal,
+    action: &str,
+    id: &str,
+    result: Result<T, PromptError>,
+) -> Response {
+    state.admin_audit.record(
+        Ok(principal),
+        action,
+        id,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result.and_then(|_| state.prompts.get(id)) {
+        Ok(info) => Json(info).into_response(),
+        Err(e) => prompt_error(&e),
+    }
+}
+
+/// Route handler for GET /v1/admin/prompts
+#[utoipa::path(
+    get,
+    path = "/v1/admin/prompts",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Prompts with their versions and rollouts", body = Vec<PromptInfo>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn list_prompts(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(state.prompts.list()).into_response()
+}
+
+/// Route handler for POST /v1/admin/prompts/{id}/versions
+///
+/// The first version of a prompt is served right away; later versions are
+/// served once rolled out.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/prompts/{id}/versions",
+    tag = "admin",
+    params(("id" = String, Path, description = "Prompt name")),
+    request_body = PublishPromptRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Prompt with the published version", body = PromptInfo),
+        (status = 400, description = "Empty template", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn publish_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<PublishPromptRequest>,
+) -> Response {
+    let action = "prompt.publish";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state.prompts.publish(
+        &id,
+        request.template,
+        request.description,
+        Some(principal.subject.clone()),
+    );
+    if let Ok(version) = result {
+        info!(
+            "Prompt {} version {} published by {}",
+            id, version, principal.subject
+        );
+    }
+    let created = result.is_ok();
+    let response = prompt_mutation_response(&state, &principal, action, &id, result);
+    if created {
+        (StatusCode::CREATED, response).into_response()
+    } else {
+        response
+    }
+}
+
+/// Route handler for PUT /v1/admin/prompts/{id}/rollout
+#[utoipa::path(
+    put,
+    path = "/v1/admin/prompts/{id}/rollout",
+    tag = "admin",
+    params(("id" = String, Path, description = "Prompt name")),
+    request_body = PromptRolloutRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Prompt with the updated rollout", body = PromptInfo),
+        (status = 400, description = "Invalid percentage", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown prompt or version", body = ApiError)
+    )
+)]
+pub async fn rollout_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<PromptRolloutRequest>,
+) -> Response {
+    let action = "prompt.rollout";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state
+        .prompts
+        .rollout(&id, request.version, request.percentage);
+    if result.is_ok() {
+        info!(
+            "Prompt {} version {} rolled out to {}% by {}",
+            id, request.version, request.percentage, principal.subject
+        );
+    }
+    prompt_mutation_response(&state, &principal, action, &id, result)
+}
+
+/// Route handler for POST /v1/admin/prompts/{id}/rollback
+#[utoipa::path(
+    post,
+    path = "/v1/admin/prompts/{id}/rollback",
+    tag = "admin",
+    params(("id" = String, Path, description = "Prompt name")),
+    request_body = PromptRollbackRequest,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Prompt after the rollback", body = PromptInfo),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown prompt or version", body = ApiError),
+        (status = 409, description = "No earlier version to roll back to", body = ApiError)
+    )
+)]
+pub async fn rollback_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<PromptRollbackRequest>,
+) -> Response {
+    let action = "prompt.rollback";
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &id) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state.prompts.rollback(&id, request.version);
+    if let Ok(stable) = result {
+        warn!(
+            "Prompt {} rolled back to version {} by {}",
+            id, stable, principal.subject
+        );
+    }
+    prompt_mutation_response(&state, &principal, action, &id, result)
+}
+
+/// Map a policy engine error to an error response
+fn policy_error(e: &PolicyError) -> Response {
+    match e {
+        PolicyError::UnknownVersion(_) => {
+            admin_error(StatusCode::NOT_FOUND, e.to_string(), "policy_not_found")
+        }
+        _ => admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_policy"),
+    }
+}
+
+/// Record a policy mutation and respond with the version it made active
+fn policy_mutation_response(
+    state: &AppState,
+    principal: &AdminPrincipal,
+    action: &str,
+    resource: &str,
+    result: Result<u32, PolicyError>,
+) -> Response {
+    state.admin_audit.record(
+        Ok(principal),
+        action,
+        resource,
+        result.as_ref().map(|_| ()).map_err(ToString::to_string),
+    );
+    match result {
+        Ok(version) => match state
+            .policies
+            .versions()
+            .into_iter()
+            .find(|info| info.version == version)
+        {
+            Some(info) => Json(info).into_response(),
+            None => policy_error(&PolicyError::UnknownVersion(version)),
+        },
+        Err(e) => policy_error(&e),
+    }
+}
+
+/// Route handler for POST /v1/admin/policies
+///
+/// The published version becomes the active one.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/policies",
+    tag = "admin",
+    request_body(content = Object, description = "Routing policy to publish"),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "Published policy version", body = Object),
+        (status = 400, description = "Invalid policy", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn publish_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(policy): Json<RoutingPolicy>,
+) -> Response {
+    let action = "policy.publish";
+    let name = policy.name.clone();
+    let principal = match authorize_mutation(&state, &headers, AdminRole::Operator, action, &name) {
+        Ok(principal) => principal,
+        Err(e) => return e.into_response(),
+    };
+    let result = state.policies.publish(policy);
+    if let Ok(version) = result {
+        info!(
+            "Routing policy {} version {} published by {}",
+            name, version, principal.subject
+        );
+    }
+    let created = result.is_ok();
+    let response = policy_mutation_response(&state, &principal, action, &name, result);
+    if created {
+        (StatusCode::CREATED, response).into_response()
+    } else {
+        response
+    }
+}
+
+/// Route handler for POST /v1/admin/policies/{version}/activate
+///
+/// Activating an earlier version rolls the routing policy back.
+#[utoipa::path(
+    post,
+    path = "/v1/admin/policies/{version}/activate",
+    tag = "admin",
+    params(("version" = u32, Path, description = "Published policy version")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Activated policy version", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown policy version", body = ApiError)
+    )
+)]
+pub async fn activate_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(version): Path<u32>,
+) -> Response {
+    let action = "policy.activate";
+    let resource = version.to_string();
+    let principal =
+        match authorize_mutation(&state, &headers, AdminRole::Operator, action, &resource) {
+            Ok(principal) => principal,
+            Err(e) => return e.into_response(),
+        };
+    let result = state.policies.activate(version).map(|_| version);
+    if result.is_ok() {
+        warn!(
+            "Routing policy version {} activated by {}",
+            version, principal.subject
+        );
+    }
+    policy_mutation_response(&state, &principal, action, &resource, result)
+}
+
+/// Route handler for GET /v1/admin/prompts/served
+#[utoipa::path(
+    get,
+    path = "/v1/admin/prompts/served",
+    tag = "admin",
+    params(("prompt" = Option<String>, Query, description = "Only records of this prompt")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Prompt versions that served recent requests, oldest first", body = Vec<PromptServeRecord>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn served_prompts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<PromptServedQuery>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(state.prompts.served(query.prompt.as_deref())).into_response()
+}
+
+/// Route handler for GET /v1/admin/prompt-traces
+#[utoipa::path(
+    get,
+    path = "/v1/admin/prompt-traces",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent prompt assembly traces, oldest first", body = Vec<PromptTrace>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn prompt_traces(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(state.prompt_traces.recent()).into_response()
+}
+
+/// Route handler for GET /v1/admin/prompt-traces/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/admin/prompt-traces/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Trace ID from the `x-intellirouter-prompt-trace` response header")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Prompt assembly trace", body = PromptTrace),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown or expired trace", body = ApiError)
+    )
+)]
+pub async fn prompt_trace(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.prompt_traces.get(&id) {
+        Some(trace) => Json(trace).into_response(),
+        None => admin_error(
+            StatusCode::NOT_FOUND,
+            format!("Unknown prompt trace '{}'", id),
+            "prompt_trace_not_found",
+        ),
+    }
+}
+
+/// Route handler for GET /v1/admin/payloads/{id}
+///
+/// Payloads hold full prompts and outputs, so reading them takes the
+/// operator role.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/payloads/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Request ID, the ID of the request's routing decision")),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Captured payload of the request", body = PayloadRecord),
+        (status = 400, description = "Invalid request ID", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "No payload captured for the request", body = ApiError)
+    )
+)]
+pub async fn payload(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.payloads.get(&id).await {
+        Ok(Some(record)) => Json(record).into_response(),
+        Ok(None) => admin_error(
+            StatusCode::NOT_FOUND,
+            format!("No payload captured for request '{}'", id),
+            "payload_not_found",
+        ),
+        Err(e @ PayloadError::InvalidId(_)) => {
+            admin_error(StatusCode::BAD_REQUEST, e.to_string(), "invalid_request_id")
+        }
+        Err(e) => {
+            warn!("Failed to read payload {}: {}", id, e);
+            admin_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+                "payload_error",
+            )
+        }
+    }
+}
+
+/// Route handler for GET /v1/admin/users/usage
+#[utoipa::path(
+    get,
+    path = "/v1/admin/users/usage",
+    tag = "admin",
+    params(
+        ("tenant" = Option<String>, Query, description = "Only users of this tenant"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of users, by most tokens used")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Usage of the end users, by most tokens used", body = Vec<UserUsage>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn user_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UserUsageQuery>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let mut users = state.user_usage.users(query.tenant.as_deref());
+    if let Some(limit) = query.limit {
+        users.truncate(limit);
+    }
+    Json(users).into_response()
+}
+
+/// Route handler for GET /v1/admin/users/{user}/usage
+#[utoipa::path(
+    get,
+    path = "/v1/admin/users/{user}/usage",
+    tag = "admin",
+    params(
+        ("user" = String, Path, description = "End user from the `user` field of requests"),
+        ("tenant" = Option<String>, Query, description = "Tenant of the user, omitted for unauthenticated requests")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Usage of the end user", body = UserUsage),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown or evicted user", body = ApiError)
+    )
+)]
+pub async fn user_usage_detail(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(user): Path<String>,
+    Query(query): Query<UserUsageQuery>,
+) -> Response {
+    if let Err(e) = authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.user_usage.user(query.tenant.as_deref(), &user) {
+        Some(usage) => Json(usage).into_response(),
+        None => admin_error(
+            StatusCode::NOT_FOUND,
+            format!("No usage of user '{}'", user),
+            "user_not_found",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AdminKeyConfig;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use std::collections::HashMap;
+
+    fn headers(key: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", key).parse().unwrap());
+        headers
+    }
+
+    fn config() -> ProxyConfig {
+        let mut config = ProxyConfig {
+            admin_api_keys: vec!["sk-root".to_string()],
+            admin_keys: vec![
+                AdminKeyConfig {
+                    name: "dashboards".to_string(),
+                    key: "sk-view".to_string(),
+                    scopes: vec!["admin:read".to_string()],
+                },
+                AdminKeyConfig {
+                    name: "oncall".to_string(),
+                    key: "sk-ops".to_string(),
+                    scopes: vec!["admin:read".to_string(), "admin:operate".to_string()],
+                },
+            ],
+            ..Default::default()
+        };
+        config.admin_rbac.oidc = Some(OidcRoleConfig {
+            issuer: Some("https://idp.example.com".to_string()),
+            hmac_secret: Some("secret".to_string()),
+            role_claim: "realm_access.roles".to_string(),
+            claim_roles: HashMap::from([
+                ("router-viewers".to_string(), AdminRole::Viewer),
+                ("router-admins".to_string(), AdminRole::Admin),
+            ]),
+            ..Default::default()
+        });
+        config
+    }
+
+    fn token(issuer: &str, roles: &[&str]) -> String {
+        encode(
+            &Header::default(),
+            &json!({
+                "sub": "alice",
+                "iss": issuer,
+                "exp": Utc::now().timestamp() + 600,
+                "realm_access": { "roles": roles },
+            }),
+            &EncodingKey::from_secret(b"secret"),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_roles_from_api_key_scopes() {
+        let config = config();
+
+        let root = authorize(&config, &headers("sk-root"), AdminRole::Admin).unwrap();
+        assert_eq!(root.role, AdminRole::Admin);
+        assert_eq!(root.subject, "key:...root");
+
+        let ops = authorize(&config, &headers("sk-ops"), AdminRole::Operator).unwrap();
+        assert_eq!(ops.subject, "oncall");
+        assert!(matches!(
+            authorize(&config, &headers("sk-ops"), AdminRole::Admin),
+            Err(AdminAuthError::Forbidden {
+                role: AdminRole::Operator,
+                ..
+            })
+        ));
+
+        assert!(authorize(&config, &headers("sk-view"), AdminRole::Viewer).is_ok());
+        assert!(authorize(&config, &headers("sk-view"), AdminRole::Operator).is_err());
+        assert_eq!(
+            authorize(&config, &headers("sk-unknown"), AdminRole::Viewer),
+            Err(AdminAuthError::Unauthenticated)
+        );
+    }
+
+    #[test]
+    fn test_roles_from_oidc_claims() {
+        let config = config();
+
+        let admin = token(
+            "https://idp.example.com",
+            &["router-viewers", "router-admins"],
+        );
+        let principal = authorize(&config, &headers(&admin), AdminRole::Admin).unwrap();
+        assert_eq!(principal.subject, "alice");
+
+        let viewer = token("https://idp.example.com", &["router-viewers"]);
+        assert!(authorize(&config, &headers(&viewer), AdminRole::Viewer).is_ok());
+        assert!(authorize(&config, &headers(&viewer), AdminRole::Operator).is_err());
+
+        let foreign = token("https://evil.example.com", &["router-admins"]);
+        assert!(authorize(&config, &headers(&foreign), AdminRole::Viewer).is_err());
+    }
+
+    #[test]
+    fn test_closed_without_admin_credentials() {
+        let mut config = ProxyConfig::default();
+        assert_eq!(
+            authorize(&config, &HeaderMap::new(), AdminRole::Viewer),
+            Err(AdminAuthError::Unauthenticated)
+        );
+        assert_eq!(
+            authorize(&config, &headers("sk-anything"), AdminRole::Viewer),
+            Err(AdminAuthError::Unauthenticated)
+        );
+
+        // Anonymous admins are an explicit opt-in
+        config.allow_anonymous_admin = true;
+        let principal = authorize(&config, &HeaderMap::new(), AdminRole::Admin).unwrap();
+        assert_eq!(principal.role, AdminRole::Admin);
+
+        // and do not survive configuring admin credentials
+        config.admin_api_keys = vec!["sk-root".to_string()];
+        assert!(authorize(&config, &HeaderMap::new(), AdminRole::Viewer).is_err());
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let log = AdminAuditLog::new(2);
+        let principal = AdminPrincipal {
+            subject: "oncall".to_string(),
+            role: AdminRole::Operator,
+        };
+        log.record(Ok(&principal), "budget.reset", "acme", Ok(()));
+        log.record(
+            Err(&AdminAuthError::Forbidden {
+                subject: "oncall".to_string(),
+                role: AdminRole::Operator,
+                required: AdminRole::Admin,
+            }),
+            "model.remove",
+            "gpt-4o",
+            Ok(()),
+        );
+        log.record(
+            Ok(&principal),
+            "model.update_status",
+            "gpt-4o",
+            Err("boom".into()),
+        );
+
+        let events = log.recent();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].outcome, AuditOutcome::Denied);
+        assert_eq!(events[0].detail.as_deref(), Some("requires admin"));
+        assert_eq!(events[1].outcome, AuditOutcome::Failure);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = crate::modules::common::SqliteDatabase::open(
+            dir.path().join("audit.db"),
+            std::time::Duration::from_secs(1),
+        )
+        .unwrap();
+        let principal = AdminPrincipal {
+            subject: "tenant:acme".to_string(),
+            role: AdminRole::Operator,
+        };
+
+        let keys = HashMap::from([("k1".to_string(), vec![7; 32])]);
+        let encryptor = Arc::new(EnvelopeEncryptor::new(Arc::new(
+            crate::modules::encryption::LocalKeyProvider::new("k1", keys).unwrap(),
+        )));
+        let retention = std::time::Duration::from_secs(3600);
+
+        let log =
+            AdminAuditLog::with_store(2, db.clone(), Some(encryptor.clone()), retention).unwrap();
+        log.flush().await;
+        db.with(|conn| {
+            conn.execute(
+                "INSERT INTO audit_events (id, recorded_at, data) VALUES ('expired', 0, '{}')",
+                &[],
+            )
+        })
+        .unwrap();
+        log.record(Ok(&principal), "budget.reset", "acme", Ok(()));
+        log.record(Ok(&principal), "user.block", "acme/alice", Ok(()));
+        log.record(Ok(&principal), "budget.reset", "globex", Ok(()));
+        log.flush().await;
+
+        // Events are sealed at rest, and expired ones pruned
+        let rows = db
+            .with(|conn| conn.query("SELECT id, data FROM audit_events", &[]))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        for row in &rows {
+            let data = row[1].as_str().unwrap();
+            assert!(EnvelopeEncryptor::is_sealed(data));
+            assert!(!data.contains("acme"));
+        }
+
+        // Events that left the recent window are anonymized in the store
+        let subject = ErasureSubject::new("acme", None).unwrap();
+        assert_eq!(log.anonymize(&subject, "anon-1").await, 3);
+
+        // A restarted log reloads the most recent events
+        let restarted = AdminAuditLog::with_store(2, db, Some(encryptor), retention).unwrap();
+        restarted.flush().await;
+        let events = restarted.recent();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].resource, "anon-1/alice");
+        assert_eq!(events[0].subject.as_deref(), Some("tenant:anon-1"));
+        assert_eq!(events[1].resource, "globex");
+    }
+}
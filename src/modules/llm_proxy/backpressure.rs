@@ -0,0 +1,194 @@
+//! Slow Streaming Clients
+//!
+//! Streamed responses are read from their source by a task that hands the
+//! events to the client through a bounded buffer. A client that leaves the
+//! buffer full is stalled: the source isn't read while it catches up, so a
+//! slow client holds at most `buffer_events` events instead of the whole
+//! response. The slow-client policy decides how long a stall is tolerated
+//! before the connection is dropped.
+
+use std::time::{Duration, Instant};
+
+use futures::stream::{Stream, StreamExt};
+use metrics::{counter, decrement_gauge, histogram, increment_gauge};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::warn;
+
+use crate::config::{SlowClientPolicy, StreamBackpressureConfig};
+use crate::modules::telemetry::catalog;
+
+/// Where the events of a streamed response come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamSource {
+    /// Provider body forwarded as is; pausing it stops reading from the provider
+    Passthrough,
+    /// Chunks generated for the request
+    Generated,
+    /// Events replayed from the resumable stream buffer, which keeps
+    /// receiving them while the client stalls
+    Buffered,
+}
+
+impl StreamSource {
+    fn label(self) -> &'static str {
+        match self {
+            StreamSource::Passthrough => "passthrough",
+            StreamSource::Generated => "generated",
+            StreamSource::Buffered => "buffered",
+        }
+    }
+
+    /// Whether not reading the source pauses the upstream generation
+    fn pausable(self) -> bool {
+        !matches!(self, StreamSource::Buffered)
+    }
+}
+
+/// Forward a stream to a client through a bounded buffer
+///
+/// The returned stream ends early when the client is dropped for stalling;
+/// when the client goes away, the source is dropped with it.
+pub fn bounded<S>(
+    events: S,
+    source: StreamSource,
+    config: &StreamBackpressureConfig,
+) -> impl Stream<Item = S::Item> + Send + 'static
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(config.buffer_events.max(1));
+    let (policy, wait) = match config.policy {
+        SlowClientPolicy::PauseUpstream if source.pausable() => {
+            ("pause_upstream", Duration::from_secs(config.max_pause_secs))
+        }
+        _ => ("disconnect", Duration::from_secs(config.stall_timeout_secs)),
+    };
+    tokio::spawn(pump(events, sender, source.label(), policy, wait));
+    ReceiverStream::new(receiver)
+}
+
+/// Move events from the source to the client's buffer until either ends
+async fn pump<S>(
+    events: S,
+    sender: mpsc::Sender<S::Item>,
+    source: &'static str,
+    policy: &'static str,
+    wait: Duration,
+) where
+    S: Stream,
+{
+    let mut events = Box::pin(events);
+    loop {
+        let event = tokio::select! {
+            event = events.next() => event,
+            _ = sender.closed() => return,
+        };
+        let Some(event) = event else {
+            return;
+        };
+
+        let event = match sender.try_send(event) {
+            Ok(()) => continue,
+            Err(TrySendError::Closed(_)) => return,
+            Err(TrySendError::Full(event)) => event,
+        };
+
+        // The client stopped reading; hold the source until it catches up
+        counter!(catalog::STREAMS_STALLED, 1, "source" => source, "policy" => policy);
+        increment_gauge!(catalog::STREAMS_STALLED_ACTIVE, 1.0, "source" => source);
+        let stalled_at = Instant::now();
+        let sent = tokio::time::timeout(wait, sender.send(event)).await;
+        decrement_gauge!(catalog::STREAMS_STALLED_ACTIVE, 1.0, "source" => source);
+        histogram!(
+            catalog::STREAMS_STALL_DURATION,
+            stalled_at.elapsed().as_secs_f64() * 1000.0,
+            "source" => source
+        );
+
+        match sent {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => return,
+            Err(_) => {
+                counter!(
+                    catalog::STREAMS_SLOW_CLIENT_DISCONNECTS, 1,
+                    "source" => source, "policy" => policy
+                );
+                warn!(
+                    "Dropping {} stream of a client stalled for {:?}",
+                    source, wait
+                );
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn config(policy: SlowClientPolicy, stall_timeout_secs: u64) -> StreamBackpressureConfig {
+        StreamBackpressureConfig {
+            buffer_events: 1,
+            policy,
+            stall_timeout_secs,
+            max_pause_secs: 60,
+        }
+    }
+
+    /// Numbers 0..10, counting how many were read
+    fn counted_source(read: Arc<AtomicUsize>) -> impl Stream<Item = usize> + Send {
+        stream::iter(0..10).inspect(move |_| {
+            read.fetch_add(1, Ordering::SeqCst);
+        })
+    }
+
+    #[tokio::test]
+    async fn test_stalled_client_is_disconnected() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let events = bounded(
+            counted_source(read.clone()),
+            StreamSource::Generated,
+            &config(SlowClientPolicy::Disconnect, 0),
+        );
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let received: Vec<_> = events.collect().await;
+        assert_eq!(received, vec![0]);
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_paused_upstream_resumes() {
+        let read = Arc::new(AtomicUsize::new(0));
+        let events = bounded(
+            counted_source(read.clone()),
+            StreamSource::Passthrough,
+            &config(SlowClientPolicy::PauseUpstream, 0),
+        );
+
+        // The source isn't read past the buffer while the client stalls
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(read.load(Ordering::SeqCst), 2);
+
+        let received: Vec<_> = events.collect().await;
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_buffered_streams_are_not_paused() {
+        let events = bounded(
+            stream::iter(0..10),
+            StreamSource::Buffered,
+            &config(SlowClientPolicy::PauseUpstream, 0),
+        );
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(events.collect::<Vec<_>>().await, vec![0]);
+    }
+}
@@ -0,0 +1,235 @@
+//! Capability Discovery
+//!
+//! This module serves `/v1/capabilities`, describing which optional
+//! subsystems this deployment has enabled so SDKs and UIs can feature-detect
+//! instead of failing at call time. Configured subsystems (RAG, guardrails,
+//! caching, experiments) are read from the configuration at startup; model
+//! features (audio, vision) are enabled when a model available to the caller
+//! accepts them.
+
+use std::collections::BTreeMap;
+
+use axum::{extract::State, http::HeaderMap, Json};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::server::AppState;
+use super::tenant;
+use crate::config::Config;
+use crate::modules::model_registry::types::formats::InputFormat;
+use crate::modules::model_registry::ModelMetadata;
+
+/// Version of the RAG endpoints
+const RAG_VERSION: &str = "1.0";
+/// Version of the persona guardrails
+const GUARDRAILS_VERSION: &str = "1.0";
+/// Version of the chain result cache
+const CACHING_VERSION: &str = "1.0";
+/// Version of the audio content parts of chat completions
+const AUDIO_VERSION: &str = "1.0";
+/// Version of the image content parts of chat completions
+const VISION_VERSION: &str = "1.0";
+
+/// Optional subsystems enabled in the configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnabledSubsystems {
+    pub rag: bool,
+    pub guardrails: bool,
+    pub caching: bool,
+}
+
+impl EnabledSubsystems {
+    /// Read the enabled subsystems from the global configuration
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            rag: config.rag.enabled,
+            guardrails: config.persona_layer.enabled,
+            caching: config.chain_engine.enable_caching,
+        }
+    }
+}
+
+/// Availability of an optional subsystem
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Capability {
+    /// Whether the subsystem can be used in this deployment
+    pub enabled: bool,
+    /// Version of the subsystem's interface; absent when this build doesn't
+    /// include the subsystem
+    pub version: Option<String>,
+    /// Models available to the caller that support the feature, for model
+    /// features
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<Vec<String>>,
+}
+
+impl Capability {
+    fn subsystem(enabled: bool, version: &str) -> Self {
+        Self {
+            enabled,
+            version: Some(version.to_string()),
+            models: None,
+        }
+    }
+
+    fn model_feature(models: Vec<String>, version: &str) -> Self {
+        Self {
+            enabled: !models.is_empty(),
+            version: Some(version.to_string()),
+            models: Some(models),
+        }
+    }
+
+    fn unavailable() -> Self {
+        Self {
+            enabled: false,
+            version: None,
+            models: None,
+        }
+    }
+}
+
+/// Capabilities of the deployment
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CapabilityList {
+    /// Object type (always "capabilities")
+    pub object: String,
+    /// Version of the router
+    pub version: String,
+    /// Optional subsystems by name: `rag`, `guardrails`, `caching`, `audio`,
+    /// `vision` and `experiments`
+    pub capabilities: BTreeMap<String, Capability>,
+}
+
+impl CapabilityList {
+    /// Describe the capabilities of the enabled subsystems and models
+    pub fn new(subsystems: &EnabledSubsystems, models: &[ModelMetadata]) -> Self {
+        let supporting = |supports: fn(&ModelMetadata) -> bool| {
+            let mut ids: Vec<String> = models
+                .iter()
+                .filter(|model| supports(model))
+                .map(|model| model.id.clone())
+                .collect();
+            ids.sort();
+            ids
+        };
+        let audio = supporting(|model| {
+            model.capabilities.supports_feature("audio")
+                || model
+                    .capabilities
+                    .supports_input_format(&InputFormat::Audio)
+        });
+        let vision = supporting(|model| {
+            model.capabilities.supports_feature("vision")
+                || model
+                    .capabilities
+                    .supports_input_format(&InputFormat::Image)
+        });
+
+        let capabilities = BTreeMap::from([
+            (
+                "rag".to_string(),
+                Capability::subsystem(subsystems.rag, RAG_VERSION),
+            ),
+            (
+                "guardrails".to_string(),
+                Capability::subsystem(subsystems.guardrails, GUARDRAILS_VERSION),
+            ),
+            (
+                "caching".to_string(),
+                Capability::subsystem(subsystems.caching, CACHING_VERSION),
+            ),
+            (
+                "audio".to_string(),
+                Capability::model_feature(audio, AUDIO_VERSION),
+            ),
+            (
+                "vision".to_string(),
+                Capability::model_feature(vision, VISION_VERSION),
+            ),
+            // Experiments aren't part of this build
+            ("experiments".to_string(), Capability::unavailable()),
+        ]);
+
+        Self {
+            object: "capabilities".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            capabilities,
+        }
+    }
+}
+
+/// Route handler for GET /v1/capabilities
+#[utoipa::path(
+    get,
+    path = "/v1/capabilities",
+    tag = "models",
+    responses((status = 200, description = "Optional subsystems enabled in this deployment", body = CapabilityList))
+)]
+pub async fn capabilities(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Json<CapabilityList> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, &headers);
+    let models: Vec<ModelMetadata> = state
+        .registry
+        .list_models()
+        .into_iter()
+        .filter(|model| tenant::is_model_allowed(tenant, &model.id))
+        .collect();
+
+    Json(CapabilityList::new(&state.config.subsystems, &models))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(id: &str) -> ModelMetadata {
+        ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "openai".to_string(),
+            "1".to_string(),
+            "https://api.openai.com/v1".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_capabilities_reflect_config_and_models() {
+        let mut vision = model("gpt-4o");
+        vision.capabilities.supports_vision = true;
+        vision.capabilities.add_input_format(InputFormat::Audio);
+        let mut image_only = model("llava");
+        image_only.capabilities.add_input_format(InputFormat::Image);
+        let models = vec![model("gpt-3.5-turbo"), vision, image_only];
+        let subsystems = EnabledSubsystems {
+            rag: true,
+            ..Default::default()
+        };
+
+        let list = CapabilityList::new(&subsystems, &models);
+        let capabilities = &list.capabilities;
+        assert!(capabilities["rag"].enabled);
+        assert!(!capabilities["guardrails"].enabled);
+        assert_eq!(capabilities["rag"].version.as_deref(), Some(RAG_VERSION));
+        assert_eq!(
+            capabilities["vision"].models.as_deref(),
+            Some(&["gpt-4o".to_string(), "llava".to_string()][..])
+        );
+        assert_eq!(
+            capabilities["audio"].models.as_deref(),
+            Some(&["gpt-4o".to_string()][..])
+        );
+        assert!(!capabilities["experiments"].enabled);
+        assert!(capabilities["experiments"].version.is_none());
+    }
+
+    #[test]
+    fn test_model_features_disabled_without_models() {
+        let list = CapabilityList::new(&EnabledSubsystems::default(), &[]);
+        assert!(!list.capabilities["audio"].enabled);
+        assert!(!list.capabilities["vision"].enabled);
+        assert_eq!(list.capabilities.len(), 6);
+    }
+}
@@ -0,0 +1,195 @@
+//! Request Coalescing
+//!
+//! Identical deterministic requests that arrive while one of them is in
+//! flight are attached to the same upstream call, and its response is fanned
+//! out to all of them. Only requests whose output doesn't vary between calls
+//! (temperature 0 or a fixed seed) are coalesced, and clients can opt out
+//! per request with the `x-intellirouter-no-coalesce` header.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use axum::http::HeaderMap;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use metrics::{counter, gauge};
+
+use super::dto::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::config::CoalescingConfig;
+use crate::modules::router_core::RouterError;
+use crate::modules::telemetry::catalog;
+
+/// Header opting a request out of coalescing
+pub const NO_COALESCE_HEADER: &str = "x-intellirouter-no-coalesce";
+
+/// Coalescer of chat completion requests
+pub type RequestCoalescer = Coalescer<Result<ChatCompletionResponse, RouterError>>;
+
+type SharedCall<T> = Shared<BoxFuture<'static, T>>;
+
+/// Single-flight execution of calls sharing a key
+///
+/// A call removes itself when it completes, whichever of its waiters drives
+/// it, so a later request with the same key starts a fresh call.
+pub struct Coalescer<T> {
+    in_flight: Arc<Mutex<HashMap<String, SharedCall<T>>>>,
+}
+
+impl<T> Coalescer<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    /// Create a coalescer
+    pub fn new() -> Self {
+        Self {
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Run `call`, or wait for the call already in flight for `key`
+    pub async fn run<F>(&self, key: String, model: &str, call: F) -> T
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        self.join(key, model, call).await.0
+    }
+
+    /// Run `call`, or wait for the call already in flight for `key`,
+    /// returning whether the output came from a call already in flight
+    pub async fn join<F>(&self, key: String, model: &str, call: F) -> (T, bool)
+    where
+        F: Future<Output = T> + Send + 'static,
+    {
+        let (shared, joined) = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(shared) => {
+                    counter!(catalog::COALESCING_COALESCED, 1, "model" => model.to_string());
+                    (shared.clone(), true)
+                }
+                None => {
+                    let calls = self.in_flight.clone();
+                    let call_key = key.clone();
+                    let shared = async move {
+                        let output = call.await;
+                        let mut in_flight = calls.lock().unwrap();
+                        in_flight.remove(&call_key);
+                        gauge!(catalog::COALESCING_IN_FLIGHT, in_flight.len() as f64);
+                        output
+                    }
+                    .boxed()
+                    .shared();
+                    in_flight.insert(key, shared.clone());
+                    gauge!(catalog::COALESCING_IN_FLIGHT, in_flight.len() as f64);
+                    (shared, false)
+                }
+            }
+        };
+        (shared.await, joined)
+    }
+
+    /// Number of distinct calls in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+}
+
+impl<T> std::fmt::Debug for Coalescer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Coalescer")
+            .field("in_flight", &self.in_flight.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl<T> Default for Coalescer<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Coalescing key of a request, if it may share an upstream call
+///
+/// Requests of different tenants are never coalesced.
+pub fn key(
+    config: &CoalescingConfig,
+    headers: &HeaderMap,
+    tenant: Option<&str>,
+    request: &ChatCompletionRequest,
+) -> Option<String> {
+    if !config.enabled || headers.contains_key(NO_COALESCE_HEADER) || !is_deterministic(request) {
+        return None;
+    }
+    let body = serde_json::to_string(request).ok()?;
+    Some(format!("{}\n{}", tenant.unwrap_or_default(), body))
+}
+
+/// Whether identical requests produce the same response
+fn is_deterministic(request: &ChatCompletionRequest) -> bool {
+    request.temperature == Some(0.0) || request.seed.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::llm_proxy::domain::message::Message;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn request(temperature: Option<f32>) -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [Message::new_user("What is 2 + 2?".to_string())],
+            "temperature": temperature,
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_identical_calls_share_one_upstream_call() {
+        let coalescer = Arc::new(Coalescer::<usize>::new());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let run = |coalescer: Arc<Coalescer<usize>>, calls: Arc<AtomicUsize>| async move {
+            coalescer
+                .run("key".to_string(), "gpt-4o", async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    calls.fetch_add(1, Ordering::SeqCst) + 1
+                })
+                .await
+        };
+        let (first, second) = tokio::join!(
+            run(coalescer.clone(), calls.clone()),
+            run(coalescer.clone(), calls.clone())
+        );
+        assert_eq!((first, second), (1, 1));
+        assert_eq!(coalescer.in_flight(), 0);
+
+        // Once the call completed, the next request starts a new one
+        assert_eq!(run(coalescer.clone(), calls.clone()).await, 2);
+    }
+
+    #[test]
+    fn test_only_deterministic_requests_are_coalesced() {
+        let config = CoalescingConfig::default();
+        let headers = HeaderMap::new();
+
+        let deterministic = request(Some(0.0));
+        assert!(key(&config, &headers, None, &deterministic).is_some());
+        assert_ne!(
+            key(&config, &headers, Some("a"), &deterministic),
+            key(&config, &headers, Some("b"), &deterministic)
+        );
+        assert!(key(&config, &headers, None, &request(Some(0.7))).is_none());
+
+        let mut opted_out = HeaderMap::new();
+        opted_out.insert(NO_COALESCE_HEADER, "1".parse().unwrap());
+        assert!(key(&config, &opted_out, None, &deterministic).is_none());
+
+        let disabled = CoalescingConfig { enabled: false };
+        assert!(key(&disabled, &headers, None, &deterministic).is_none());
+    }
+}
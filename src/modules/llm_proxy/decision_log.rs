@@ -0,0 +1,375 @@
+//! Routing Decision Log
+//!
+//! This module keeps a sampled feed of recent routing decisions (model chosen,
+//! latency, cost) for real-time observability. Decisions are sampled at the
+//! configured rate, configured fields are redacted, and the result is kept in
+//! a bounded buffer and broadcast to live subscribers.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::admin::{self, AdminRole};
+use super::dto::ApiError;
+use super::model_rollout::RolloutVariant;
+use super::server::AppState;
+use crate::config::DecisionLogConfig;
+use crate::modules::erasure::ErasureSubject;
+use crate::modules::model_registry::ResolutionKind;
+use crate::modules::telemetry::CostCorrection;
+
+/// Marker replacing redacted field values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Capacity of the broadcast channel feeding live subscribers
+const BROADCAST_CAPACITY: usize = 256;
+
+/// A single routing decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingDecision {
+    /// Decision identifier
+    pub id: String,
+    /// Time the request completed
+    pub timestamp: DateTime<Utc>,
+    /// Tenant that sent the request
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    pub user: Option<String>,
+    /// Model requested by the client
+    pub requested_model: String,
+    /// Model that served the request
+    pub model: String,
+    /// Provider of the serving model
+    pub provider: String,
+    /// End-to-end latency in milliseconds
+    pub latency_ms: u64,
+    /// Prompt tokens used
+    pub prompt_tokens: u32,
+    /// Completion tokens used
+    pub completion_tokens: u32,
+    /// Estimated cost in USD, if pricing is known
+    pub cost_usd: Option<f64>,
+    /// Error message when the request failed
+    pub error: Option<String>,
+    /// Data categories flagged by guardrails (e.g. `pii`)
+    pub flagged_categories: Vec<String>,
+    /// Registry prompt prepended to the request
+    pub prompt: Option<String>,
+    /// Version of the registry prompt served
+    pub prompt_version: Option<u32>,
+    /// Configuration of the model that served the request, during a rollout
+    pub rollout: Option<RolloutVariant>,
+    /// Whether the response was shared by an identical request in flight
+    #[serde(default)]
+    pub coalesced: bool,
+    /// How the requested model was resolved, when it was resolved to
+    /// another model
+    #[serde(default)]
+    pub model_resolution: Option<ResolutionKind>,
+    /// ISO 639-1 code of the language detected in the request
+    #[serde(default)]
+    pub language: Option<String>,
+}
+
+impl RoutingDecision {
+    /// Create a decision for a request to a model
+    pub fn new(requested_model: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            tenant: None,
+            user: None,
+            requested_model: requested_model.into(),
+            model: model.into(),
+            provider: String::new(),
+            latency_ms: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost_usd: None,
+            error: None,
+            flagged_categories: Vec::new(),
+            prompt: None,
+            prompt_version: None,
+            rollout: None,
+            coalesced: false,
+            model_resolution: None,
+            language: None,
+        }
+    }
+}
+
+/// Sampled, redacted log of recent routing decisions
+#[derive(Debug)]
+pub struct DecisionLog {
+    /// Sampling and redaction settings
+    config: DecisionLogConfig,
+    /// Most recent sampled decisions, oldest first
+    recent: Mutex<VecDeque<Value>>,
+    /// Feed of sampled decisions for live subscribers
+    sender: broadcast::Sender<Value>,
+}
+
+impl DecisionLog {
+    /// Create a decision log
+    pub fn new(config: DecisionLogConfig) -> Self {
+        let (sender, _) = broadcast::channel(BROADCAST_CAPACITY);
+        Self {
+            recent: Mutex::new(VecDeque::with_capacity(config.capacity)),
+            config,
+            sender,
+        }
+    }
+
+    /// Record a decision, subject to sampling
+    ///
+    /// Returns whether the decision was sampled.
+    pub fn record(&self, decision: RoutingDecision) -> bool {
+        if !self.config.enabled || rand::random::<f64>() >= self.config.sample_rate {
+            return false;
+        }
+
+        let entry = match serde_json::to_value(&decision) {
+            Ok(entry) => self.redact(entry),
+            Err(e) => {
+                warn!("Failed to serialize routing decision: {}", e);
+                return false;
+            }
+        };
+
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() >= self.config.capacity {
+                recent.pop_front();
+            }
+            if self.config.capacity > 0 {
+                recent.push_back(entry.clone());
+            }
+        }
+
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(entry);
+        true
+    }
+
+    /// Recent sampled decisions, oldest first
+    pub fn recent(&self) -> Vec<Value> {
+        self.recent.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Replace the costs of recent decisions by recomputed costs
+    ///
+    /// Redacted costs stay redacted. Returns how many decisions were
+    /// updated.
+    pub fn apply_cost_corrections(&self, corrections: &[CostCorrection]) -> usize {
+        let costs: HashMap<&str, f64> = corrections
+            .iter()
+            .map(|correction| (correction.request_id.as_str(), correction.cost))
+            .collect();
+        let mut updated = 0;
+        for entry in self.recent.lock().unwrap().iter_mut() {
+            let Some(cost) = entry
+                .get("id")
+                .and_then(Value::as_str)
+                .and_then(|id| costs.get(id))
+                .copied()
+            else {
+                continue;
+            };
+            if let Value::Object(fields) = entry {
+                if fields.get("cost_usd").and_then(Value::as_str) != Some(REDACTED) {
+                    fields.insert("cost_usd".to_string(), json!(cost));
+                    updated += 1;
+                }
+            }
+        }
+        updated
+    }
+
+    /// Erase a data subject from the recent decisions
+    ///
+    /// A tenant's decisions are dropped; a user's are kept with the user
+    /// replaced by `pseudonym`. Returns how many decisions were dropped and
+    /// how many were anonymized.
+    pub fn erase(&self, subject: &ErasureSubject, pseudonym: &str) -> (usize, usize) {
+        let field =
+            |entry: &Value, name: &str| entry.get(name).and_then(Value::as_str).map(str::to_string);
+        let mut recent = self.recent.lock().unwrap();
+        let before = recent.len();
+        if subject.user.is_none() {
+            recent.retain(|entry| !subject.matches(field(entry, "tenant").as_deref(), None));
+            return (before - recent.len(), 0);
+        }
+
+        let mut anonymized = 0;
+        for entry in recent.iter_mut() {
+            let matches = subject.matches(
+                field(entry, "tenant").as_deref(),
+                field(entry, "user").as_deref(),
+            );
+            if let (true, Value::Object(fields)) = (matches, entry) {
+                fields.insert("user".to_string(), Value::String(pseudonym.to_string()));
+                anonymized += 1;
+            }
+        }
+        (0, anonymized)
+    }
+
+    /// Subscribe to sampled decisions as they are recorded
+    pub fn subscribe(&self) -> broadcast::Receiver<Value> {
+        self.sender.subscribe()
+    }
+
+    /// Replace the configured fields with the redaction marker
+    fn redact(&self, mut entry: Value) -> Value {
+        if let Value::Object(fields) = &mut entry {
+            for field in &self.config.redact_fields {
+                if let Some(value) = fields.get_mut(field) {
+                    if !value.is_null() {
+                        *value = Value::String(REDACTED.to_string());
+                    }
+                }
+            }
+        }
+        entry
+    }
+}
+
+impl Default for DecisionLog {
+    fn default() -> Self {
+        Self::new(DecisionLogConfig::default())
+    }
+}
+
+/// Route handler for /v1/admin/routing/decisions
+#[utoipa::path(
+    get,
+    path = "/v1/admin/routing/decisions",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Recent sampled routing decisions", body = Vec<Object>),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn recent_decisions(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(e) = admin::authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    Json(state.decisions.recent()).into_response()
+}
+
+/// Route handler for /v1/admin/routing/decisions/stream
+///
+/// Streams sampled decisions over a WebSocket as JSON text messages.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/routing/decisions/stream",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 101, description = "WebSocket of sampled routing decisions"),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+pub async fn stream_decisions(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.config.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let receiver = state.decisions.subscribe();
+    ws.on_upgrade(|socket| forward_decisions(socket, receiver))
+}
+
+/// Forward sampled decisions to a WebSocket until either side closes
+async fn forward_decisions(mut socket: WebSocket, mut receiver: broadcast::Receiver<Value>) {
+    loop {
+        tokio::select! {
+            decision = receiver.recv() => match decision {
+                Ok(decision) => {
+                    if socket
+                        .send(Message::Text(decision.to_string().into()))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    debug!("Decision stream subscriber lagged, skipped {}", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision() -> RoutingDecision {
+        let mut decision = RoutingDecision::new("gpt-4o", "gpt-4o");
+        decision.tenant = Some("acme".to_string());
+        decision.user = Some("user-1".to_string());
+        decision
+    }
+
+    #[test]
+    fn test_redacts_configured_fields() {
+        let log = DecisionLog::new(DecisionLogConfig {
+            redact_fields: vec!["user".to_string(), "error".to_string()],
+            ..Default::default()
+        });
+        let mut receiver = log.subscribe();
+
+        assert!(log.record(decision()));
+
+        let recent = log.recent();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0]["user"], REDACTED);
+        assert_eq!(recent[0]["tenant"], "acme");
+        assert!(recent[0]["error"].is_null());
+        assert_eq!(receiver.try_recv().unwrap(), recent[0]);
+    }
+
+    #[test]
+    fn test_sampling_and_capacity() {
+        let disabled = DecisionLog::new(DecisionLogConfig {
+            sample_rate: 0.0,
+            ..Default::default()
+        });
+        assert!(!disabled.record(decision()));
+        assert!(disabled.recent().is_empty());
+
+        let log = DecisionLog::new(DecisionLogConfig {
+            capacity: 2,
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            log.record(decision());
+        }
+        assert_eq!(log.recent().len(), 2);
+    }
+}
@@ -4,9 +4,10 @@
 //! supporting both simple text messages and multimodal content.
 
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// Represents the content of a message, which can be either a string or an array of content parts
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(untagged)]
 pub enum MessageContent {
     /// Simple text content as a string
@@ -16,7 +17,7 @@ pub enum MessageContent {
 }
 
 /// Represents a part of a message's content with a specific type
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum ContentPart {
     /// Text content part
@@ -37,7 +38,7 @@ pub enum ContentPart {
 }
 
 /// Represents an image URL in a content part
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct ImageUrl {
     /// URL of the image (can be a web URL or base64 data URL)
     pub url: String,
@@ -47,7 +48,7 @@ pub struct ImageUrl {
 }
 
 /// Represents audio data in a content part
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct AudioData {
     /// Base64 encoded audio data
     pub data: String,
@@ -56,7 +57,7 @@ pub struct AudioData {
 }
 
 /// Represents a file in a content part
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct FileData {
     /// Optional filename
     pub filename: Option<String>,
@@ -67,7 +68,7 @@ pub struct FileData {
 }
 
 /// Represents the format of audio data
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum AudioFormat {
     /// WAV audio format
@@ -6,6 +6,9 @@
 use super::content::MessageContent;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use utoipa::openapi::schema::{ObjectBuilder, Schema, Type};
+use utoipa::openapi::{Ref, RefOr};
+use utoipa::{PartialSchema, ToSchema};
 
 /// Represents a message in a chat conversation
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,7 +17,6 @@ pub struct Message {
     pub role: MessageRole,
 
     /// The content of the message (can be text or multimodal)
-    #[serde(flatten)]
     pub content: MessageContent,
 
     /// Optional name of the author for role disambiguation
@@ -23,7 +25,7 @@ pub struct Message {
 }
 
 /// Represents the role of a message author
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum MessageRole {
     /// System message (instructions to the model)
@@ -44,6 +46,34 @@ pub enum MessageRole {
     Unknown,
 }
 
+// `content` is flattened for serde but is a plain field on the wire, so the
+// schema is written by hand rather than derived
+impl PartialSchema for Message {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .description(Some("Represents a message in a chat conversation"))
+            .property("role", Ref::from_schema_name(MessageRole::name()))
+            .required("role")
+            .property("content", Ref::from_schema_name(MessageContent::name()))
+            .required("content")
+            .property(
+                "name",
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .description(Some("Optional name of the author for role disambiguation")),
+            )
+            .into()
+    }
+}
+
+impl ToSchema for Message {
+    fn schemas(schemas: &mut Vec<(String, RefOr<Schema>)>) {
+        schemas.push((MessageRole::name().into(), MessageRole::schema()));
+        schemas.push((MessageContent::name().into(), MessageContent::schema()));
+        MessageContent::schemas(schemas);
+    }
+}
+
 impl fmt::Display for MessageRole {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
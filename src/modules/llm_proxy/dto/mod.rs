@@ -5,14 +5,20 @@
 //! layer from the domain layer.
 
 use crate::modules::llm_proxy::domain::message::Message;
+use crate::modules::model_registry::connectors::ContentFilterDetail;
+use crate::modules::model_registry::ModelMetadata;
+use crate::modules::router_core::policy::RoutingPolicy;
+use crate::modules::router_core::strategy::RoutingStrategy;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// OpenAI API chat completion request
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ChatCompletionRequest {
     /// The model to use for completion
     pub model: String,
@@ -42,10 +48,90 @@ pub struct ChatCompletionRequest {
     /// User identifier for tracking
     #[serde(default)]
     pub user: Option<String>,
+    /// Sequences where the model will stop generating further tokens
+    #[serde(default)]
+    pub stop: Option<StopSequences>,
+    /// Token ID to bias (-100 to 100) mapping
+    #[serde(default)]
+    pub logit_bias: Option<HashMap<String, f32>>,
+    /// Seed for deterministic sampling
+    #[serde(default)]
+    pub seed: Option<i64>,
+    /// Top-k sampling parameter
+    #[serde(default)]
+    pub top_k: Option<u32>,
+    /// Options of streamed responses
+    #[serde(default)]
+    pub stream_options: Option<StreamOptions>,
+    /// Format the model must produce
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+}
+
+impl ChatCompletionRequest {
+    /// Whether a streamed response should end with a usage chunk
+    pub fn include_usage(&self) -> bool {
+        self.stream_options
+            .as_ref()
+            .is_some_and(|options| options.include_usage)
+    }
+
+    /// Whether the client asked for JSON output
+    pub fn wants_json(&self) -> bool {
+        self.response_format
+            .as_ref()
+            .is_some_and(ResponseFormat::is_json)
+    }
+}
+
+/// Format of a chat completion's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ResponseFormat {
+    /// `text`, `json_object` or `json_schema`
+    #[serde(rename = "type")]
+    pub format_type: String,
+    /// Schema of the output, for `json_schema`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<serde_json::Value>,
+}
+
+impl ResponseFormat {
+    /// Whether the format is JSON output
+    pub fn is_json(&self) -> bool {
+        matches!(self.format_type.as_str(), "json_object" | "json_schema")
+    }
+}
+
+/// Options of a streamed chat completion
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StreamOptions {
+    /// End the stream with a chunk carrying the token usage of the request
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// Stop sequences, given either as a single string or an array of strings
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum StopSequences {
+    /// A single stop sequence
+    Single(String),
+    /// Multiple stop sequences
+    Multiple(Vec<String>),
+}
+
+impl StopSequences {
+    /// Get the stop sequences as a list
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            StopSequences::Single(s) => vec![s.clone()],
+            StopSequences::Multiple(v) => v.clone(),
+        }
+    }
 }
 
 /// OpenAI API chat completion response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionResponse {
     /// Unique identifier for the completion
     pub id: String,
@@ -62,7 +148,7 @@ pub struct ChatCompletionResponse {
 }
 
 /// A single completion choice in a response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionChoice {
     /// Index of the choice
     pub index: u32,
@@ -70,10 +156,16 @@ pub struct ChatCompletionChoice {
     pub message: Message,
     /// Reason why generation finished
     pub finish_reason: String,
+    /// Whether the choice came from a separate call emulating `n > 1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emulated: Option<bool>,
+    /// What the provider reported, when `finish_reason` is `content_filter`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_filter: Option<ContentFilterDetail>,
 }
 
 /// OpenAI API chat completion chunk for streaming responses
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionChunk {
     /// Unique identifier for the completion
     pub id: String,
@@ -85,10 +177,14 @@ pub struct ChatCompletionChunk {
     pub model: String,
     /// Generated completion chunks
     pub choices: Vec<ChatCompletionChunkChoice>,
+    /// Token usage of the whole request, only in the final chunk of streams
+    /// requesting it, which has no choices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
 }
 
 /// A single completion chunk choice in a streaming response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChatCompletionChunkChoice {
     /// Index of the choice
     pub index: u32,
@@ -100,7 +196,7 @@ pub struct ChatCompletionChunkChoice {
 }
 
 /// Delta content for a streaming response chunk
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ChatMessageDelta {
     /// Role of the message author (only in first chunk)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -111,7 +207,7 @@ pub struct ChatMessageDelta {
 }
 
 /// Token usage statistics
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct TokenUsage {
     /// Number of tokens in the prompt
     pub prompt_tokens: u32,
@@ -122,7 +218,7 @@ pub struct TokenUsage {
 }
 
 /// API error response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiError {
     /// Error details
     pub error: ApiErrorDetail,
@@ -141,7 +237,7 @@ impl IntoResponse for ApiError {
 }
 
 /// API error detail
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ApiErrorDetail {
     /// Error message
     pub message: String,
@@ -155,6 +251,118 @@ pub struct ApiErrorDetail {
     pub code: Option<String>,
 }
 
+/// OpenAI API model object
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ModelObject {
+    /// Model identifier
+    pub id: String,
+    /// Object type (always "model")
+    pub object: String,
+    /// Creation timestamp
+    pub created: u64,
+    /// Organization or provider that owns the model
+    pub owned_by: String,
+    /// IntelliRouter extension describing model capabilities
+    pub capabilities: ModelCapabilitiesSummary,
+}
+
+/// Summary of model capabilities exposed on the models endpoints
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ModelCapabilitiesSummary {
+    /// Maximum context window size in tokens
+    pub max_context_length: usize,
+    /// Maximum number of tokens the model can generate
+    pub max_tokens_to_generate: usize,
+    /// Whether the model supports function calling
+    pub function_calling: bool,
+    /// Whether the model supports vision/image inputs
+    pub vision: bool,
+    /// Whether the model supports streaming responses
+    pub streaming: bool,
+    /// Whether the model supports embeddings generation
+    pub embeddings: bool,
+}
+
+/// OpenAI API model list response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ModelList {
+    /// Object type (always "list")
+    pub object: String,
+    /// Models available to the caller
+    pub data: Vec<ModelObject>,
+}
+
+impl From<&ModelMetadata> for ModelObject {
+    fn from(metadata: &ModelMetadata) -> Self {
+        let capabilities = &metadata.capabilities;
+        Self {
+            id: metadata.id.clone(),
+            object: "model".to_string(),
+            created: metadata.created_at.timestamp().max(0) as u64,
+            owned_by: metadata.provider.clone(),
+            capabilities: ModelCapabilitiesSummary {
+                max_context_length: capabilities.max_context_length,
+                max_tokens_to_generate: capabilities.max_tokens_to_generate,
+                function_calling: capabilities.supports_function_calling,
+                vision: capabilities.supports_vision,
+                streaming: capabilities.supports_streaming,
+                embeddings: capabilities.supports_embeddings,
+            },
+        }
+    }
+}
+
+impl ModelList {
+    /// Create a new model list
+    pub fn new(data: Vec<ModelObject>) -> Self {
+        Self {
+            object: "list".to_string(),
+            data,
+        }
+    }
+}
+
+/// Request body for evaluating a routing policy without routing
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PolicyDryRunRequest {
+    /// Policy to evaluate (defaults to the active policy)
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub policy: Option<RoutingPolicy>,
+    /// Request to evaluate the policy for
+    pub request: ChatCompletionRequest,
+}
+
+/// Request body for explaining how a request would be routed
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RouteExplainRequest {
+    /// Strategy to weigh candidates with (defaults to the router's strategy)
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub strategy: Option<RoutingStrategy>,
+    /// Request to explain
+    pub request: ChatCompletionRequest,
+}
+
+/// Request body for continuing a partial completion of a conversation
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ContinueRequest {
+    /// ID of the partial completion to continue
+    pub response_id: String,
+    /// Model to continue with (defaults to the partial completion's model)
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Maximum number of tokens of the continuation
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature (0.0 to 2.0)
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    /// User identifier for tracking
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
 impl ChatCompletionResponse {
     /// Create a new chat completion response
     pub fn new(model: String, message: Message) -> Self {
@@ -171,6 +379,8 @@ impl ChatCompletionResponse {
                 index: 0,
                 message,
                 finish_reason: "stop".to_string(),
+                emulated: None,
+                content_filter: None,
             }],
             usage: TokenUsage {
                 prompt_tokens: 10,                     // Mock values
@@ -197,6 +407,20 @@ impl ChatCompletionChunk {
                 delta,
                 finish_reason,
             }],
+            usage: None,
+        }
+    }
+
+    /// Create the final chunk of a stream, carrying the token usage of the
+    /// request
+    pub fn with_usage(id: String, created: u64, model: String, usage: TokenUsage) -> Self {
+        Self {
+            id,
+            object: "chat.completion.chunk".to_string(),
+            created,
+            model,
+            choices: Vec::new(),
+            usage: Some(usage),
         }
     }
 
@@ -29,6 +29,8 @@ pub fn format_completion_response(
             index: 0,
             message: Message::new_assistant(content.to_string()),
             finish_reason: finish_reason.to_string(),
+            emulated: None,
+            content_filter: None,
         }],
         usage: calculate_token_usage(messages, content),
     }
@@ -52,6 +54,7 @@ pub fn format_completion_chunk(
             delta: ChatMessageDelta { role, content },
             finish_reason,
         }],
+        usage: None,
     }
 }
 
@@ -42,11 +42,15 @@ mod tests {
         Router::new()
             .route(
                 "/v1/chat/completions",
-                post(|state, json| async move { chat_completions(state, json).await }),
+                post(|state, headers, json| async move {
+                    chat_completions(state, headers, json).await
+                }),
             )
             .route(
                 "/v1/chat/completions/stream",
-                post(|state, json| async move { chat_completions_stream(state, json).await }),
+                post(|state, headers, json| async move {
+                    chat_completions_stream(state, headers, json).await
+                }),
             )
             .with_state(app_state)
     }
@@ -114,6 +118,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Create service
@@ -154,6 +164,12 @@ mod tests {
                 presence_penalty: None,
                 frequency_penalty: None,
                 user: None,
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
             };
 
             // Create service
@@ -0,0 +1,236 @@
+//! JSON Mode Output Repair
+//!
+//! Providers asked for JSON output with a `response_format` of `json_object`
+//! or `json_schema` sometimes wrap it in a code fence, follow it with
+//! commentary or stop in the middle of it. Before such output fails the
+//! request, it is repaired in stages:
+//!
+//! 1. `trim`: the first JSON value is cut out of the surrounding text and
+//!    trailing commas are dropped.
+//! 2. `balance`: output cut off before its end has its open string, arrays
+//!    and objects closed.
+//! 3. `reask`: the model is asked again, up to `max_reasks` times, with its
+//!    invalid output and an instruction to reply with JSON only.
+//!
+//! Repairs are counted per model and stage. With `strict` set under
+//! `[proxy.json_mode]`, output that is not valid JSON fails without repair.
+
+/// Stage that repaired an output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairMethod {
+    /// Text around the JSON value was removed
+    Trim,
+    /// Unclosed strings, arrays and objects were closed
+    Balance,
+    /// The model was asked again
+    Reask,
+}
+
+impl RepairMethod {
+    /// Name of the stage in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RepairMethod::Trim => "trim",
+            RepairMethod::Balance => "balance",
+            RepairMethod::Reask => "reask",
+        }
+    }
+}
+
+/// Instruction sent with invalid output when the model is asked again
+pub const REASK_PROMPT: &str = "Your previous reply was not valid JSON. Reply again with only the \
+JSON value, without code fences or any other text.";
+
+/// Whether a text is a valid JSON value
+pub fn is_valid(content: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(content).is_ok()
+}
+
+/// Repair an output that is not valid JSON without asking the model again
+///
+/// Returns the repaired output and the stage that repaired it, or `None` when
+/// no JSON value could be recovered.
+pub fn repair(content: &str) -> Option<(String, RepairMethod)> {
+    let content = strip_code_fence(content.trim());
+    let start = content.find(['{', '['])?;
+    let value = &content[start..];
+
+    let scan = scan(value);
+    if let Some(end) = scan.end {
+        let trimmed = strip_trailing_commas(&value[..end]);
+        if is_valid(&trimmed) {
+            return Some((trimmed, RepairMethod::Trim));
+        }
+        return None;
+    }
+
+    let balanced = strip_trailing_commas(&balance(value.trim_end(), &scan));
+    is_valid(&balanced).then_some((balanced, RepairMethod::Balance))
+}
+
+/// Remove a Markdown code fence around a text, keeping what is inside it
+fn strip_code_fence(content: &str) -> &str {
+    let Some(start) = content.find("```") else {
+        return content;
+    };
+    // Skip the fence's info string, such as `json`
+    let inner = &content[start + 3..];
+    let inner = inner.find('\n').map_or(inner, |line| &inner[line + 1..]);
+    match inner.find("```") {
+        Some(end) => &inner[..end],
+        None => inner,
+    }
+}
+
+/// State of a text scanned from the start of a JSON value
+struct Scan {
+    /// Byte offset just past the end of the value, if it is closed
+    end: Option<usize>,
+    /// Closing brackets of the arrays and objects left open
+    open: Vec<char>,
+    /// Whether the text ends inside a string
+    in_string: bool,
+}
+
+/// Scan a text starting with `{` or `[` for the end of its JSON value
+fn scan(value: &str) -> Scan {
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in value.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => open.push('}'),
+            '[' => open.push(']'),
+            '}' | ']' => {
+                open.pop();
+                if open.is_empty() {
+                    return Scan {
+                        end: Some(index + 1),
+                        open,
+                        in_string,
+                    };
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Scan {
+        end: None,
+        open,
+        in_string,
+    }
+}
+
+/// Close the open string, arrays and objects of a value cut off before its end
+fn balance(value: &str, scan: &Scan) -> String {
+    let mut balanced = value.to_string();
+    if scan.in_string {
+        if balanced.ends_with('\\') {
+            balanced.pop();
+        }
+        balanced.push('"');
+    }
+
+    // A key without its value gets a null one
+    let trimmed = balanced.trim_end();
+    if trimmed.ends_with(':') {
+        balanced = format!("{} null", trimmed);
+    }
+
+    for close in scan.open.iter().rev() {
+        balanced.push(*close);
+    }
+    balanced
+}
+
+/// Remove commas directly before a closing bracket, outside of strings
+fn strip_trailing_commas(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut stripped = String::with_capacity(value.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (index, c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if *c == '"' {
+            in_string = true;
+        } else if *c == ',' {
+            let next = chars[index + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']') | None) {
+                continue;
+            }
+        }
+        stripped.push(*c);
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_repairs() {
+        let cases = [
+            (
+                "Here is the result:\n```json\n{\"a\": 1}\n```\nLet me know!",
+                r#"{"a": 1}"#,
+            ),
+            (
+                r#"{"a": [1, 2,], "b": "x}"} trailing note"#,
+                r#"{"a": [1, 2], "b": "x}"}"#,
+            ),
+            ("Sure! [1, 2, 3] is the list.", "[1, 2, 3]"),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(
+                repair(content),
+                Some((expected.to_string(), RepairMethod::Trim)),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_balance_repairs() {
+        let cases = [
+            (r#"{"a": {"b": [1, 2"#, r#"{"a": {"b": [1, 2]}}"#),
+            (r#"{"name": "Ada Love"#, r#"{"name": "Ada Love"}"#),
+            (r#"{"a": 1, "b":"#, r#"{"a": 1, "b": null}"#),
+            (r#"{"a": [1,"#, r#"{"a": [1]}"#),
+        ];
+        for (content, expected) in cases {
+            assert_eq!(
+                repair(content),
+                Some((expected.to_string(), RepairMethod::Balance)),
+                "{}",
+                content
+            );
+        }
+    }
+
+    #[test]
+    fn test_unrepairable_output() {
+        assert_eq!(repair("I can't produce JSON for that."), None);
+        assert_eq!(repair(r#"{"a": tru}"#), None);
+    }
+}
@@ -101,8 +101,10 @@ impl MockModelBackend {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 },
             ],
             usage: Some(crate::modules::model_registry::connectors::TokenUsage {
@@ -302,6 +304,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -341,6 +344,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -3,19 +3,43 @@
 //! This module provides an OpenAI-compatible API interface for various LLM providers.
 //! It handles request formatting, response parsing, and API compatibility layers.
 
+pub mod admin;
+pub mod backpressure;
+pub mod capabilities;
+pub mod coalesce;
 pub mod conformance_tests;
+pub mod decision_log;
 pub mod domain;
 pub mod dto;
 pub mod formatting;
 pub mod formatting_tests;
 pub mod integration_tests;
+pub mod json_mode;
 pub mod mock_backend;
+pub mod model_rollout;
+pub mod openapi;
+pub mod params;
+pub mod partials;
+pub mod passthrough;
+pub mod payloads;
+pub mod prompt_trace;
+pub mod prompts;
+pub mod provenance;
+pub mod quirks;
+pub mod quota;
 pub mod router_integration;
 pub mod routes;
 pub mod server;
 pub mod service;
+pub mod smoothing;
+pub mod stream_buffer;
+pub mod stream_usage;
 pub mod telemetry_integration;
+pub mod tenant;
+pub mod transform;
+pub mod user_usage;
 pub mod validation;
+pub mod versioning;
 pub mod websocket;
 pub mod websocket_tests;
 
@@ -65,8 +89,17 @@ pub use domain::message::{Message, MessageRole};
 // Re-export key types from the dto module
 pub use dto::{
     ApiError, ApiErrorDetail, ChatCompletionChoice, ChatCompletionChunk, ChatCompletionChunkChoice,
-    ChatCompletionRequest, ChatCompletionResponse, ChatMessageDelta, TokenUsage,
+    ChatCompletionRequest, ChatCompletionResponse, ChatMessageDelta, ModelList, ModelObject,
+    StopSequences, TokenUsage,
 };
 
+// Re-export the per-model transformer
+pub use decision_log::DecisionLog;
+pub use quirks::MessageNormalizer;
+pub use quota::TokenQuotaManager;
+pub use transform::ModelTransformer;
+pub use user_usage::UserUsageLog;
+pub use versioning::{ApiVersion, ApiVersioning};
+
 // Re-export key functions from the validation module
 pub use validation::create_validation_error;
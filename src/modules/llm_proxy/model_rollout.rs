@@ -0,0 +1,706 @@
+//! Model Rollouts
+//!
+//! This module rolls out a changed model endpoint or version gradually,
+//! blue/green style. While a rollout is in progress the registry keeps the
+//! previous configuration (the baseline) and the new one (the candidate)
+//! serves a growing share of the model's callers, stage by stage; like
+//! prompt rollouts, callers are bucketed by user or tenant so they stick to
+//! one configuration.
+//!
+//! The error rate and mean latency of the candidate's requests in the
+//! current stage are compared with those of the baseline's requests. When
+//! either regresses past its limit the rollout is rolled back and the
+//! configured alert webhooks are notified; when the last stage passes the
+//! candidate replaces the baseline in the registry.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::config::ModelRolloutConfig;
+use crate::modules::chain_engine::webhooks::{
+    sign, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+use crate::modules::model_registry::connectors::ModelConnector;
+use crate::modules::model_registry::storage::ModelRegistry;
+use crate::modules::model_registry::ModelMetadata;
+
+/// Errors of model rollouts
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RolloutError {
+    #[error("Unknown model '{0}'")]
+    UnknownModel(String),
+
+    #[error("Model '{0}' already has a rollout in progress")]
+    InProgress(String),
+
+    #[error("Model '{0}' has no rollout in progress")]
+    NotInProgress(String),
+
+    #[error("Registry error: {0}")]
+    Registry(String),
+}
+
+/// Configuration of a model serving a request during a rollout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutVariant {
+    /// Configuration before the change
+    Baseline,
+    /// Configuration being rolled out
+    Candidate,
+}
+
+/// State of a rollout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RolloutStatus {
+    /// The candidate serves part of the traffic
+    InProgress,
+    /// The candidate replaced the baseline
+    Completed,
+    /// The baseline serves all traffic again
+    RolledBack,
+}
+
+/// Outcomes of the requests served by one configuration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct VariantStats {
+    /// Requests served
+    pub requests: u64,
+    /// Requests that failed
+    pub errors: u64,
+    /// Sum of the request latencies, in milliseconds
+    pub total_latency_ms: u64,
+}
+
+impl VariantStats {
+    /// Fraction of failed requests
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+
+    /// Mean request latency in milliseconds
+    pub fn mean_latency_ms(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.total_latency_ms as f64 / self.requests as f64
+        }
+    }
+
+    fn record(&mut self, success: bool, latency_ms: u64) {
+        self.requests += 1;
+        if !success {
+            self.errors += 1;
+        }
+        self.total_latency_ms += latency_ms;
+    }
+}
+
+/// A model rollout
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ModelRollout {
+    /// Rollout identifier
+    pub id: String,
+    /// Model ID
+    pub model: String,
+    /// Configuration before the change
+    #[schema(value_type = Object)]
+    pub baseline: ModelMetadata,
+    /// Configuration being rolled out
+    #[schema(value_type = Object)]
+    pub candidate: ModelMetadata,
+    /// State of the rollout
+    pub status: RolloutStatus,
+    /// Index of the current stage
+    pub stage: usize,
+    /// Share of callers served by the candidate, in percent
+    pub percentage: u8,
+    /// Requests served by the baseline since the rollout started
+    pub baseline_stats: VariantStats,
+    /// Requests served by the candidate since the stage started
+    pub candidate_stats: VariantStats,
+    /// Why the rollout was rolled back
+    pub reason: Option<String>,
+    /// When the rollout started
+    pub started_at: DateTime<Utc>,
+    /// When the current stage started
+    pub stage_started_at: DateTime<Utc>,
+    /// When the rollout completed or was rolled back
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ModelRollout {
+    /// Regression of the candidate past the configured limits, if any
+    fn regression(&self, config: &ModelRolloutConfig) -> Option<String> {
+        let (baseline, candidate) = (&self.baseline_stats, &self.candidate_stats);
+        if baseline.requests < config.min_requests || candidate.requests < config.min_requests {
+            return None;
+        }
+        if candidate.error_rate() - baseline.error_rate() > config.max_error_rate_increase {
+            return Some(format!(
+                "error rate {:.1}% against {:.1}% for the baseline",
+                candidate.error_rate() * 100.0,
+                baseline.error_rate() * 100.0
+            ));
+        }
+        if baseline.mean_latency_ms() > 0.0
+            && candidate.mean_latency_ms() > baseline.mean_latency_ms() * config.max_latency_ratio
+        {
+            return Some(format!(
+                "mean latency {:.0}ms against {:.0}ms for the baseline",
+                candidate.mean_latency_ms(),
+                baseline.mean_latency_ms()
+            ));
+        }
+        None
+    }
+}
+
+/// Change of a rollout's stage or state
+#[derive(Debug, Clone, PartialEq)]
+pub enum RolloutEvent {
+    /// The candidate now serves `percentage` percent of callers
+    Advanced { model: String, percentage: u8 },
+    /// The candidate replaced the baseline
+    Completed { model: String },
+    /// The rollout was rolled back
+    RolledBack { model: String, reason: String },
+}
+
+/// A rollout and the connector calling the candidate
+struct RolloutEntry {
+    rollout: ModelRollout,
+    connector: Option<Arc<dyn ModelConnector>>,
+}
+
+impl std::fmt::Debug for RolloutEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RolloutEntry")
+            .field("rollout", &self.rollout)
+            .field("connector", &self.connector.is_some())
+            .finish()
+    }
+}
+
+/// Controller of the model rollouts
+#[derive(Debug)]
+pub struct ModelRolloutController {
+    config: ModelRolloutConfig,
+    registry: Arc<ModelRegistry>,
+    /// Rollouts by model, in progress or finished
+    rollouts: RwLock<BTreeMap<String, RolloutEntry>>,
+    /// Models of finished rollouts, oldest first, to bound the history
+    finished: RwLock<VecDeque<String>>,
+    client: reqwest::Client,
+}
+
+impl ModelRolloutController {
+    /// Create a controller for the models of a registry
+    pub fn new(config: ModelRolloutConfig, registry: Arc<ModelRegistry>) -> Self {
+        Self {
+            config,
+            registry,
+            rollouts: RwLock::new(BTreeMap::new()),
+            finished: RwLock::new(VecDeque::new()),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Update a registered model
+    ///
+    /// A change of endpoint or version starts a rollout of the new
+    /// configuration, which is returned; other changes are applied at once.
+    /// The new configuration keeps the current credentials unless it has
+    /// its own. `connector` calls the new configuration during the rollout
+    /// and replaces the model's connector when the rollout completes.
+    pub fn update_model(
+        &self,
+        mut candidate: ModelMetadata,
+        connector: Option<Arc<dyn ModelConnector>>,
+    ) -> Result<Option<ModelRollout>, RolloutError> {
+        let id = candidate.id.clone();
+        let baseline = self
+            .registry
+            .get_model(&id)
+            .map_err(|_| RolloutError::UnknownModel(id.clone()))?;
+        if candidate.auth_key.is_none() {
+            candidate.auth_key = baseline.auth_key.clone();
+        }
+        candidate.created_at = baseline.created_at;
+        candidate.updated_at = Utc::now();
+
+        let mut rollouts = self.rollouts.write().unwrap();
+        if rollouts
+            .get(&id)
+            .is_some_and(|entry| entry.rollout.status == RolloutStatus::InProgress)
+        {
+            return Err(RolloutError::InProgress(id));
+        }
+
+        let changed =
+            candidate.endpoint != baseline.endpoint || candidate.version != baseline.version;
+        if !changed || self.config.stages.is_empty() {
+            self.registry
+                .update_model(candidate)
+                .map_err(|e| RolloutError::Registry(e.to_string()))?;
+            if let Some(connector) = connector.filter(|_| changed) {
+                self.registry.register_connector(&id, connector);
+            }
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let rollout = ModelRollout {
+            id: Uuid::new_v4().to_string(),
+            model: id.clone(),
+            baseline,
+            candidate,
+            status: RolloutStatus::InProgress,
+            stage: 0,
+            percentage: self.config.stages[0],
+            baseline_stats: VariantStats::default(),
+            candidate_stats: VariantStats::default(),
+            reason: None,
+            started_at: now,
+            stage_started_at: now,
+            finished_at: None,
+        };
+        info!(
+            "Started rollout of model '{}' at {}%",
+            id, rollout.percentage
+        );
+        self.finished.write().unwrap().retain(|model| model != &id);
+        rollouts.insert(
+            id,
+            RolloutEntry {
+                rollout: rollout.clone(),
+                connector,
+            },
+        );
+        Ok(Some(rollout))
+    }
+
+    /// Select the configuration of a model serving the caller identified by
+    /// `key`, when the model has a rollout in progress
+    pub fn select(&self, model: &str, key: &str) -> Option<RolloutVariant> {
+        let rollouts = self.rollouts.read().unwrap();
+        let rollout = &rollouts.get(model)?.rollout;
+        if rollout.status != RolloutStatus::InProgress {
+            return None;
+        }
+        Some(if bucket(&rollout.id, key) < rollout.percentage {
+            RolloutVariant::Candidate
+        } else {
+            RolloutVariant::Baseline
+        })
+    }
+
+    /// Connector calling the candidate of a model's rollout in progress
+    pub fn candidate_connector(&self, model: &str) -> Option<Arc<dyn ModelConnector>> {
+        let rollouts = self.rollouts.read().unwrap();
+        let entry = rollouts.get(model)?;
+        if entry.rollout.status != RolloutStatus::InProgress {
+            return None;
+        }
+        entry.connector.clone()
+    }
+
+    /// Record the outcome of a request served during a rollout, advancing,
+    /// completing or rolling back the rollout as needed
+    pub fn record(
+        &self,
+        model: &str,
+        variant: RolloutVariant,
+        success: bool,
+        latency_ms: u64,
+    ) -> Option<RolloutEvent> {
+        let event = self.record_at(model, variant, success, latency_ms, Utc::now());
+        if let Some(RolloutEvent::RolledBack { .. }) = &event {
+            self.alert(model);
+        }
+        event
+    }
+
+    fn record_at(
+        &self,
+        model: &str,
+        variant: RolloutVariant,
+        success: bool,
+        latency_ms: u64,
+        now: DateTime<Utc>,
+    ) -> Option<RolloutEvent> {
+        let mut rollouts = self.rollouts.write().unwrap();
+        let entry = rollouts.get_mut(model)?;
+        let rollout = &mut entry.rollout;
+        if rollout.status != RolloutStatus::InProgress {
+            return None;
+        }
+        match variant {
+            RolloutVariant::Baseline => rollout.baseline_stats.record(success, latency_ms),
+            RolloutVariant::Candidate => rollout.candidate_stats.record(success, latency_ms),
+        }
+
+        if let Some(reason) = rollout.regression(&self.config) {
+            drop(rollouts);
+            return self.roll_back(model, reason, now).ok();
+        }
+
+        let stage_done = rollout.candidate_stats.requests >= self.config.min_requests
+            && rollout.baseline_stats.requests >= self.config.min_requests
+            && (now - rollout.stage_started_at).num_seconds() >= self.config.min_stage_secs as i64;
+        if !stage_done {
+            return None;
+        }
+
+        if rollout.stage + 1 < self.config.stages.len() {
+            rollout.stage += 1;
+            rollout.percentage = self.config.stages[rollout.stage];
+            rollout.stage_started_at = now;
+            rollout.candidate_stats = VariantStats::default();
+            info!(
+                "Rollout of model '{}' advanced to {}%",
+                model, rollout.percentage
+            );
+            return Some(RolloutEvent::Advanced {
+                model: model.to_string(),
+                percentage: rollout.percentage,
+            });
+        }
+
+        if let Err(e) = self.registry.update_model(rollout.candidate.clone()) {
+            warn!("Failed to complete rollout of model '{}': {}", model, e);
+            return None;
+        }
+        if let Some(connector) = entry.connector.clone() {
+            self.registry.register_connector(model, connector);
+        }
+        let rollout = &mut entry.rollout;
+        rollout.status = RolloutStatus::Completed;
+        rollout.finished_at = Some(now);
+        info!("Rollout of model '{}' completed", model);
+        drop(rollouts);
+        self.finish(model);
+        Some(RolloutEvent::Completed {
+            model: model.to_string(),
+        })
+    }
+
+    /// Roll back a model's rollout in progress
+    pub fn rollback(&self, model: &str, reason: &str) -> Result<ModelRollout, RolloutError> {
+        self.roll_back(model, reason.to_string(), Utc::now())?;
+        self.alert(model);
+        self.get(model)
+            .ok_or_else(|| RolloutError::NotInProgress(model.to_string()))
+    }
+
+    fn roll_back(
+        &self,
+        model: &str,
+        reason: String,
+        now: DateTime<Utc>,
+    ) -> Result<RolloutEvent, RolloutError> {
+        let mut rollouts = self.rollouts.write().unwrap();
+        let rollout = match rollouts.get_mut(model) {
+            Some(entry) if entry.rollout.status == RolloutStatus::InProgress => &mut entry.rollout,
+            _ => return Err(RolloutError::NotInProgress(model.to_string())),
+        };
+        rollout.status = RolloutStatus::RolledBack;
+        rollout.reason = Some(reason.clone());
+        rollout.finished_at = Some(now);
+        warn!("Rolled back rollout of model '{}': {}", model, reason);
+        drop(rollouts);
+        self.finish(model);
+        Ok(RolloutEvent::RolledBack {
+            model: model.to_string(),
+            reason,
+        })
+    }
+
+    /// Keep at most `history_capacity` finished rollouts
+    fn finish(&self, model: &str) {
+        let mut finished = self.finished.write().unwrap();
+        finished.retain(|m| m != model);
+        finished.push_back(model.to_string());
+        while finished.len() > self.config.history_capacity {
+            if let Some(oldest) = finished.pop_front() {
+                self.rollouts.write().unwrap().remove(&oldest);
+            }
+        }
+    }
+
+    /// The rollout of a model, in progress or finished
+    pub fn get(&self, model: &str) -> Option<ModelRollout> {
+        self.rollouts
+            .read()
+            .unwrap()
+            .get(model)
+            .map(|entry| entry.rollout.clone())
+    }
+
+    /// All rollouts, by model
+    pub fn list(&self) -> Vec<ModelRollout> {
+        self.rollouts
+            .read()
+            .unwrap()
+            .values()
+            .map(|entry| entry.rollout.clone())
+            .collect()
+    }
+
+    /// Notify the alert webhooks that a model's rollout was rolled back
+    fn alert(&self, model: &str) {
+        if self.config.webhooks.is_empty() {
+            return;
+        }
+        let Some(rollout) = self.get(model) else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let payload =
+            json!({ "event": "model_rollout.rolled_back", "rollout": rollout }).to_string();
+        for webhook in self.config.webhooks.clone() {
+            let client = self.client.clone();
+            let payload = payload.clone();
+            runtime.spawn(async move {
+                let mut request = client
+                    .post(&webhook.url)
+                    .header(reqwest::header::CONTENT_TYPE, "application/json")
+                    .header(EVENT_HEADER, "model_rollout.rolled_back")
+                    .header(DELIVERY_HEADER, Uuid::new_v4().to_string())
+                    .body(payload.clone());
+                if let Some(secret) = &webhook.secret {
+                    request = request.header(SIGNATURE_HEADER, sign(secret, payload.as_bytes()));
+                }
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => {}
+                    Ok(response) => warn!(
+                        "Rollback alert to {} failed: {}",
+                        webhook.url,
+                        response.status()
+                    ),
+                    Err(e) => warn!("Rollback alert to {} failed: {}", webhook.url, e),
+                }
+            });
+        }
+    }
+}
+
+impl Default for ModelRolloutController {
+    fn default() -> Self {
+        Self::new(
+            ModelRolloutConfig::default(),
+            Arc::new(ModelRegistry::new()),
+        )
+    }
+}
+
+/// Rollout bucket of a caller, between 0 and 99
+fn bucket(rollout: &str, key: &str) -> u8 {
+    let hash = digest::digest(&digest::SHA256, format!("{}:{}", rollout, key).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_ref()[..8]);
+    (u64::from_be_bytes(bytes) % 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn config() -> ModelRolloutConfig {
+        ModelRolloutConfig {
+            stages: vec![5, 25, 100],
+            min_requests: 10,
+            min_stage_secs: 60,
+            ..Default::default()
+        }
+    }
+
+    fn controller() -> ModelRolloutController {
+        let registry = Arc::new(ModelRegistry::new());
+        let mut model = ModelMetadata::new(
+            "gpt-4o".to_string(),
+            "GPT-4o".to_string(),
+            "openai".to_string(),
+            "2024-05-13".to_string(),
+            "https://blue.example.com/v1".to_string(),
+        );
+        model.auth_key = Some("sk-blue".to_string());
+        registry.register_model(model).unwrap();
+        ModelRolloutController::new(config(), registry)
+    }
+
+    fn candidate(controller: &ModelRolloutController) -> ModelMetadata {
+        let mut model = controller.registry.get_model("gpt-4o").unwrap();
+        model.endpoint = "https://green.example.com/v1".to_string();
+        model.auth_key = None;
+        model
+    }
+
+    /// Record `count` requests of each variant with the given outcomes
+    fn serve(
+        controller: &ModelRolloutController,
+        count: usize,
+        candidate: (bool, u64),
+        at: DateTime<Utc>,
+    ) -> Vec<RolloutEvent> {
+        let mut events = Vec::new();
+        for _ in 0..count {
+            events.extend(controller.record_at("gpt-4o", RolloutVariant::Baseline, true, 100, at));
+            events.extend(controller.record_at(
+                "gpt-4o",
+                RolloutVariant::Candidate,
+                candidate.0,
+                candidate.1,
+                at,
+            ));
+        }
+        events
+    }
+
+    #[test]
+    fn test_other_changes_apply_at_once() {
+        let controller = controller();
+        let mut model = controller.registry.get_model("gpt-4o").unwrap();
+        model.description = Some("Flagship".to_string());
+        assert!(controller.update_model(model, None).unwrap().is_none());
+        assert_eq!(
+            controller.registry.get_model("gpt-4o").unwrap().description,
+            Some("Flagship".to_string())
+        );
+        assert_eq!(controller.select("gpt-4o", "user-1"), None);
+    }
+
+    #[test]
+    fn test_rollout_advances_and_completes() {
+        let controller = controller();
+        let rollout = controller
+            .update_model(candidate(&controller), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rollout.percentage, 5);
+        assert_eq!(rollout.candidate.auth_key.as_deref(), Some("sk-blue"));
+        assert_eq!(
+            controller
+                .update_model(candidate(&controller), None)
+                .unwrap_err(),
+            RolloutError::InProgress("gpt-4o".to_string())
+        );
+
+        let share = (0..1000)
+            .filter(|i| {
+                controller.select("gpt-4o", &format!("user-{}", i))
+                    == Some(RolloutVariant::Candidate)
+            })
+            .count();
+        assert!((20..90).contains(&share), "candidate share {}", share);
+
+        // The stage needs both enough requests and enough time
+        let start = rollout.started_at;
+        assert!(serve(&controller, 20, (true, 110), start).is_empty());
+        let events = serve(&controller, 1, (true, 110), start + Duration::seconds(61));
+        assert_eq!(
+            events,
+            vec![RolloutEvent::Advanced {
+                model: "gpt-4o".to_string(),
+                percentage: 25
+            }]
+        );
+        assert_eq!(
+            controller.registry.get_model("gpt-4o").unwrap().endpoint,
+            "https://blue.example.com/v1"
+        );
+
+        let later = start + Duration::seconds(200);
+        serve(&controller, 10, (true, 110), later);
+        let events = serve(&controller, 10, (true, 110), later + Duration::seconds(61));
+        assert_eq!(
+            events,
+            vec![RolloutEvent::Completed {
+                model: "gpt-4o".to_string()
+            }]
+        );
+        let model = controller.registry.get_model("gpt-4o").unwrap();
+        assert_eq!(model.endpoint, "https://green.example.com/v1");
+        assert_eq!(model.auth_key.as_deref(), Some("sk-blue"));
+        assert_eq!(controller.select("gpt-4o", "user-1"), None);
+        assert_eq!(
+            controller.get("gpt-4o").unwrap().status,
+            RolloutStatus::Completed
+        );
+    }
+
+    #[test]
+    fn test_error_rate_regression_rolls_back() {
+        let controller = controller();
+        let rollout = controller
+            .update_model(candidate(&controller), None)
+            .unwrap()
+            .unwrap();
+        let events = serve(&controller, 10, (false, 100), rollout.started_at);
+        assert!(matches!(
+            events.as_slice(),
+            [RolloutEvent::RolledBack { .. }]
+        ));
+
+        let rollout = controller.get("gpt-4o").unwrap();
+        assert_eq!(rollout.status, RolloutStatus::RolledBack);
+        assert!(rollout.reason.unwrap().starts_with("error rate 100.0%"));
+        assert_eq!(
+            controller.registry.get_model("gpt-4o").unwrap().endpoint,
+            "https://blue.example.com/v1"
+        );
+        assert_eq!(controller.select("gpt-4o", "user-1"), None);
+
+        // A new rollout can start after a rollback
+        assert!(controller
+            .update_model(candidate(&controller), None)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_latency_regression_rolls_back() {
+        let controller = controller();
+        let rollout = controller
+            .update_model(candidate(&controller), None)
+            .unwrap()
+            .unwrap();
+        let events = serve(&controller, 10, (true, 400), rollout.started_at);
+        assert!(matches!(
+            events.as_slice(),
+            [RolloutEvent::RolledBack { reason, .. }] if reason.starts_with("mean latency 400ms")
+        ));
+    }
+
+    #[test]
+    fn test_manual_rollback() {
+        let controller = controller();
+        assert_eq!(
+            controller.rollback("gpt-4o", "manual").unwrap_err(),
+            RolloutError::NotInProgress("gpt-4o".to_string())
+        );
+        controller
+            .update_model(candidate(&controller), None)
+            .unwrap();
+        let rollout = controller.rollback("gpt-4o", "manual").unwrap();
+        assert_eq!(rollout.status, RolloutStatus::RolledBack);
+        assert_eq!(rollout.reason.as_deref(), Some("manual"));
+    }
+}
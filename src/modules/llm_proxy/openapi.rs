@@ -0,0 +1,190 @@
+//! OpenAPI Documentation
+//!
+//! This module assembles the OpenAPI description of the HTTP API from the
+//! `utoipa` annotations on the route handlers of the proxy, admin, persona,
+//! bundle, erasure, support, compliance, memory, chain, agent and tool
+//! endpoints. The spec is served at `/openapi.json` and browsable through a
+//! Swagger UI page at `/docs`, so client generators and docs stay in sync
+//! with the routes.
+
+use axum::{response::Html, Json};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use super::{admin, capabilities, decision_log, partials, routes, server};
+use crate::modules::audit::compliance::ComplianceApiDoc;
+use crate::modules::bundle::api::BundleApiDoc;
+use crate::modules::chain_engine::api::ChainApiDoc;
+use crate::modules::erasure::api::ErasureApiDoc;
+use crate::modules::memory::api::MemoryApiDoc;
+use crate::modules::persona_layer::api::PersonaApiDoc;
+use crate::modules::support::api::SupportApiDoc;
+use crate::modules::tools::routes::ToolApiDoc;
+
+/// OpenAPI description of the proxy and admin endpoints
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "IntelliRouter API",
+        description = "OpenAI-compatible LLM routing, with admin, memory, chain, agent and tool endpoints"
+    ),
+    paths(
+        server::health_check,
+        routes::chat_completions,
+        routes::chat_completions_stream,
+        routes::resume_chat_completions_stream,
+        routes::last_partial,
+        routes::continue_completion,
+        routes::list_models,
+        routes::retrieve_model,
+        capabilities::capabilities,
+        routes::list_policies,
+        routes::policy_dry_run,
+        routes::explain_route,
+        decision_log::recent_decisions,
+        decision_log::stream_decisions,
+        admin::list_models,
+        admin::register_model,
+        admin::update_model,
+        admin::update_model_status,
+        admin::update_fine_tune_status,
+        admin::list_fine_tunes,
+        admin::remove_model,
+        admin::model_proposals,
+        admin::sync_model_proposals,
+        admin::approve_model_proposal,
+        admin::reject_model_proposal,
+        admin::list_rollouts,
+        admin::get_rollout,
+        admin::rollback_rollout,
+        admin::pricing,
+        admin::set_price,
+        admin::list_budgets,
+        admin::reset_budget,
+        admin::list_keys,
+        admin::audit_events,
+        admin::log_levels,
+        admin::set_log_levels,
+        admin::anomalies,
+        admin::canaries,
+        admin::billing_events,
+        admin::list_prompts,
+        admin::publish_prompt,
+        admin::rollout_prompt,
+        admin::rollback_prompt,
+        admin::publish_policy,
+        admin::activate_policy,
+        admin::served_prompts,
+        admin::prompt_traces,
+        admin::prompt_trace,
+        admin::payload,
+        admin::user_usage,
+        admin::user_usage_detail,
+    ),
+    components(schemas(admin::AuditEvent, partials::Continuation)),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "chat", description = "Chat completions"),
+        (name = "models", description = "Models and capabilities available to the caller"),
+        (name = "routing", description = "Routing policies and explanations"),
+        (name = "admin", description = "Registry, rollout, budget, key, prompt, audit and logging administration"),
+        (name = "memory", description = "Conversation and long-term memory"),
+        (name = "chains", description = "Chain executions, schedules and webhooks"),
+        (name = "agents", description = "Agent runs and multi-agent conversations"),
+        (name = "tools", description = "Registered tools"),
+        (name = "health", description = "Service health"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the bearer API key scheme used by the admin and memory endpoints
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+/// Build the OpenAPI description of the whole HTTP API
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    openapi.merge(MemoryApiDoc::openapi());
+    openapi.merge(ChainApiDoc::openapi());
+    openapi.merge(ToolApiDoc::openapi());
+    openapi.merge(PersonaApiDoc::openapi());
+    openapi.merge(BundleApiDoc::openapi());
+    openapi.merge(ErasureApiDoc::openapi());
+    openapi.merge(SupportApiDoc::openapi());
+    openapi.merge(ComplianceApiDoc::openapi());
+    openapi
+}
+
+/// Route handler for /openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+/// Route handler for /docs
+///
+/// Serves a Swagger UI page for `/openapi.json`; the UI assets are loaded
+/// from a CDN so they aren't bundled into the binary.
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8" />
+  <title>IntelliRouter API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.onload = () => {
+      window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+    };
+  </script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_all_apis() {
+        let openapi = openapi();
+        for path in [
+            "/v1/chat/completions",
+            "/v1/chat/completions/stream/{id}",
+            "/v1/conversations/{id}/last-partial",
+            "/v1/conversations/{id}/continue",
+            "/v1/admin/models/{id}/status",
+            "/v1/admin/model-proposals/{id}/approve",
+            "/v1/admin/pricing/{model}",
+            "/v1/admin/policies/{version}/activate",
+            "/v1/compliance/exports",
+            "/v1/compliance/exports/{id}/resume",
+            "/v1/memory/tenants/{tenant}/users/{user}/memories/search",
+            "/v1/chains/executions/{id}/resume",
+            "/v1/agents/run",
+            "/v1/tools/{name}/invoke",
+        ] {
+            assert!(openapi.paths.paths.contains_key(path), "missing {}", path);
+        }
+
+        let components = openapi.components.unwrap();
+        assert!(components.schemas.contains_key("ChatCompletionRequest"));
+        assert!(components.schemas.contains_key("ApiError"));
+        assert!(components.security_schemes.contains_key("bearer_auth"));
+    }
+}
@@ -0,0 +1,415 @@
+//! Provider Parameter Capability Mapping
+//!
+//! This module maps optional sampling parameters (`stop`, `logit_bias`, `seed`,
+//! `top_k`, `frequency_penalty`, `presence_penalty`) onto what each provider
+//! supports. Unsupported parameters are either dropped with a warning or
+//! rejected, depending on the configured [`UnsupportedParamPolicy`]. It also
+//! records which providers can generate `n` choices natively, how each
+//! provider accepts the end user of a request, and which providers report
+//! token usage at the end of streams.
+
+use std::collections::HashMap;
+
+use tracing::{debug, warn};
+
+use super::dto::{ApiError, ChatCompletionRequest};
+use super::validation::create_validation_error;
+use crate::config::UnsupportedParamPolicy;
+use crate::modules::model_registry::{connectors, ModelRegistry};
+
+/// How a provider accepts the end user of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserParam {
+    /// As the OpenAI `user` field
+    User,
+    /// As `metadata.user_id`
+    MetadataUserId,
+    /// Not at all; the end user is only used by the router
+    Unsupported,
+}
+
+/// Optional parameters supported by a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderParamSupport {
+    /// Supports `stop`
+    pub stop: bool,
+    /// Supports `logit_bias`
+    pub logit_bias: bool,
+    /// Supports `seed`
+    pub seed: bool,
+    /// Supports `top_k`
+    pub top_k: bool,
+    /// Supports `frequency_penalty`
+    pub frequency_penalty: bool,
+    /// Supports `presence_penalty`
+    pub presence_penalty: bool,
+    /// Supports generating `n` choices in a single call
+    pub n: bool,
+    /// How the end user is passed on
+    pub user: UserParam,
+    /// Supports `stream_options.include_usage`
+    pub stream_usage: bool,
+}
+
+impl ProviderParamSupport {
+    /// Support matrix for a provider name
+    ///
+    /// Unknown providers are assumed to be OpenAI-compatible.
+    pub fn for_provider(provider: &str) -> Self {
+        match provider.to_lowercase().as_str() {
+            "anthropic" => Self {
+                stop: true,
+                logit_bias: false,
+                seed: false,
+                top_k: true,
+                frequency_penalty: false,
+                presence_penalty: false,
+                n: false,
+                user: UserParam::MetadataUserId,
+                stream_usage: false,
+            },
+            "mistral" => Self {
+                stop: true,
+                logit_bias: false,
+                seed: true,
+                top_k: false,
+                frequency_penalty: true,
+                presence_penalty: true,
+                n: false,
+                user: UserParam::Unsupported,
+                stream_usage: false,
+            },
+            "google" | "gemini" => Self {
+                stop: true,
+                logit_bias: false,
+                seed: true,
+                top_k: true,
+                frequency_penalty: true,
+                presence_penalty: true,
+                n: true,
+                user: UserParam::Unsupported,
+                stream_usage: false,
+            },
+            "ollama" => Self {
+                stop: true,
+                logit_bias: false,
+                seed: true,
+                top_k: true,
+                frequency_penalty: true,
+                presence_penalty: true,
+                n: false,
+                user: UserParam::Unsupported,
+                stream_usage: false,
+            },
+            _ => Self {
+                stop: true,
+                logit_bias: true,
+                seed: true,
+                top_k: false,
+                frequency_penalty: true,
+                presence_penalty: true,
+                n: true,
+                user: UserParam::User,
+                stream_usage: true,
+            },
+        }
+    }
+}
+
+/// Infer the provider of a model from its name
+pub fn infer_provider(model: &str) -> &'static str {
+    if model.starts_with("claude-") {
+        "anthropic"
+    } else if model.starts_with("mistral-") {
+        "mistral"
+    } else if model.starts_with("gemini-") {
+        "google"
+    } else if model.starts_with("llama-") {
+        "ollama"
+    } else {
+        "openai"
+    }
+}
+
+/// Resolve the provider serving a model
+///
+/// Falls back to [`infer_provider`] for models that are not registered.
+pub fn resolve_provider(registry: &ModelRegistry, model: &str) -> String {
+    registry
+        .get_model(model)
+        .map(|model| model.provider)
+        .unwrap_or_else(|_| infer_provider(model).to_string())
+}
+
+/// Apply the unsupported-parameter policy for a provider to a request
+///
+/// With [`UnsupportedParamPolicy::Drop`], unsupported parameters are cleared and a
+/// warning is logged. With [`UnsupportedParamPolicy::Reject`], the first
+/// unsupported parameter produces a validation error.
+pub fn apply_param_policy(
+    request: &mut ChatCompletionRequest,
+    provider: &str,
+    policy: UnsupportedParamPolicy,
+) -> Result<(), ApiError> {
+    let support = ProviderParamSupport::for_provider(provider);
+
+    let unsupported: Vec<&'static str> = [
+        ("stop", request.stop.is_some() && !support.stop),
+        (
+            "logit_bias",
+            request.logit_bias.is_some() && !support.logit_bias,
+        ),
+        ("seed", request.seed.is_some() && !support.seed),
+        ("top_k", request.top_k.is_some() && !support.top_k),
+        (
+            "frequency_penalty",
+            request.frequency_penalty.is_some() && !support.frequency_penalty,
+        ),
+        (
+            "presence_penalty",
+            request.presence_penalty.is_some() && !support.presence_penalty,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, unsupported)| *unsupported)
+    .map(|(name, _)| name)
+    .collect();
+
+    for param in unsupported {
+        match policy {
+            UnsupportedParamPolicy::Reject => {
+                return Err(create_validation_error(
+                    &format!(
+                        "{} is not supported by provider '{}' (model '{}')",
+                        param, provider, request.model
+                    ),
+                    Some(param),
+                ));
+            }
+            UnsupportedParamPolicy::Drop => {
+                warn!(
+                    "Dropping unsupported parameter '{}' for provider '{}' (model '{}')",
+                    param, provider, request.model
+                );
+                match param {
+                    "stop" => request.stop = None,
+                    "logit_bias" => request.logit_bias = None,
+                    "seed" => request.seed = None,
+                    "top_k" => request.top_k = None,
+                    "frequency_penalty" => request.frequency_penalty = None,
+                    "presence_penalty" => request.presence_penalty = None,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the optional parameters to pass through to the provider connector
+pub fn passthrough_params(
+    request: &ChatCompletionRequest,
+) -> Option<HashMap<String, serde_json::Value>> {
+    let mut params = HashMap::new();
+
+    if let Some(stop) = &request.stop {
+        params.insert("stop".to_string(), serde_json::json!(stop.to_vec()));
+    }
+    if let Some(logit_bias) = &request.logit_bias {
+        params.insert("logit_bias".to_string(), serde_json::json!(logit_bias));
+    }
+    if let Some(seed) = request.seed {
+        params.insert("seed".to_string(), serde_json::json!(seed));
+    }
+    if let Some(top_k) = request.top_k {
+        params.insert("top_k".to_string(), serde_json::json!(top_k));
+    }
+    if let Some(format) = &request.response_format {
+        params.insert("response_format".to_string(), serde_json::json!(format));
+    }
+    if let Some(penalty) = request.frequency_penalty {
+        params.insert("frequency_penalty".to_string(), serde_json::json!(penalty));
+    }
+    if let Some(penalty) = request.presence_penalty {
+        params.insert("presence_penalty".to_string(), serde_json::json!(penalty));
+    }
+
+    if let Some(n) = request.n.filter(|n| *n > 1) {
+        params.insert("n".to_string(), serde_json::json!(n));
+    }
+    if let Some(user) = &request.user {
+        params.insert("user".to_string(), serde_json::json!(user));
+    }
+
+    if params.is_empty() {
+        None
+    } else {
+        Some(params)
+    }
+}
+
+/// Ask the provider of a streamed connector request for a final usage
+/// chunk, if the client asked for one and the provider supports it
+///
+/// Streams of other providers get their usage chunk from the proxy.
+pub fn apply_stream_options(
+    request: &mut connectors::ChatCompletionRequest,
+    provider: &str,
+    include_usage: bool,
+) {
+    if !include_usage || !ProviderParamSupport::for_provider(provider).stream_usage {
+        return;
+    }
+    request
+        .additional_params
+        .get_or_insert_with(HashMap::new)
+        .insert(
+            "stream_options".to_string(),
+            serde_json::json!({ "include_usage": true }),
+        );
+}
+
+/// Pass the end user of a connector request on the way its provider
+/// accepts it
+///
+/// The end user is collected as the `user` passthrough parameter; it is moved
+/// to `metadata.user_id` for providers expecting it there, and removed for
+/// providers without an equivalent.
+pub fn apply_user_param(request: &mut connectors::ChatCompletionRequest, provider: &str) {
+    let Some(params) = request.additional_params.as_mut() else {
+        return;
+    };
+    match ProviderParamSupport::for_provider(provider).user {
+        UserParam::User => {}
+        UserParam::MetadataUserId => {
+            if let Some(user) = params.remove("user") {
+                params.insert(
+                    "metadata".to_string(),
+                    serde_json::json!({ "user_id": user }),
+                );
+            }
+        }
+        UserParam::Unsupported => {
+            if params.remove("user").is_some() {
+                debug!("Provider '{}' does not accept the end user", provider);
+            }
+        }
+    }
+    if params.is_empty() {
+        request.additional_params = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::llm_proxy::domain::message::Message;
+    use crate::modules::llm_proxy::dto::{StopSequences, StreamOptions};
+    use crate::modules::llm_proxy::service::convert_to_connector_request as convert;
+
+    fn request(model: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![Message::new_user("Hello".to_string())],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: false,
+            max_tokens: None,
+            presence_penalty: Some(0.5),
+            frequency_penalty: None,
+            user: None,
+            stop: Some(StopSequences::Single("END".to_string())),
+            logit_bias: Some(HashMap::from([("50256".to_string(), -100.0)])),
+            seed: Some(42),
+            top_k: None,
+            stream_options: None,
+            response_format: None,
+        }
+    }
+
+    #[test]
+    fn test_drop_unsupported_params() {
+        let mut req = request("claude-3-haiku");
+        apply_param_policy(&mut req, "anthropic", UnsupportedParamPolicy::Drop).unwrap();
+
+        assert!(req.logit_bias.is_none());
+        assert!(req.seed.is_none());
+        assert!(req.presence_penalty.is_none());
+        assert_eq!(req.stop, Some(StopSequences::Single("END".to_string())));
+    }
+
+    #[test]
+    fn test_reject_unsupported_params() {
+        let mut req = request("claude-3-haiku");
+        let err =
+            apply_param_policy(&mut req, "anthropic", UnsupportedParamPolicy::Reject).unwrap_err();
+        assert_eq!(err.error.param.as_deref(), Some("logit_bias"));
+    }
+
+    #[test]
+    fn test_passthrough_params() {
+        let req = request("gpt-4o");
+        let params = passthrough_params(&req).unwrap();
+
+        assert_eq!(params["stop"], serde_json::json!(["END"]));
+        assert_eq!(params["seed"], serde_json::json!(42));
+        assert!(!params.contains_key("top_k"));
+        assert!(!params.contains_key("n"));
+    }
+
+    #[test]
+    fn test_user_param() {
+        let mut req = request("gpt-4o");
+        req.user = Some("alice".to_string());
+
+        let mut openai = convert(&req);
+        apply_user_param(&mut openai, "openai");
+        assert_eq!(openai.additional_params.unwrap()["user"], "alice");
+
+        let mut anthropic = convert(&req);
+        apply_user_param(&mut anthropic, "anthropic");
+        let params = anthropic.additional_params.unwrap();
+        assert!(!params.contains_key("user"));
+        assert_eq!(params["metadata"]["user_id"], "alice");
+
+        let mut google = convert(&req);
+        apply_user_param(&mut google, "google");
+        assert!(!google.additional_params.unwrap().contains_key("user"));
+    }
+
+    #[test]
+    fn test_stream_options() {
+        let mut req = request("gpt-4o");
+        req.stream_options = Some(StreamOptions {
+            include_usage: true,
+        });
+        // Only streams ask providers for usage
+        assert!(!convert(&req)
+            .additional_params
+            .unwrap()
+            .contains_key("stream_options"));
+
+        let mut openai = convert(&req);
+        apply_stream_options(&mut openai, "openai", req.include_usage());
+        assert_eq!(
+            openai.additional_params.unwrap()["stream_options"]["include_usage"],
+            true
+        );
+
+        let mut ollama = convert(&req);
+        apply_stream_options(&mut ollama, "ollama", req.include_usage());
+        assert!(!ollama
+            .additional_params
+            .unwrap()
+            .contains_key("stream_options"));
+    }
+
+    #[test]
+    fn test_native_n_support() {
+        assert!(ProviderParamSupport::for_provider("openai").n);
+        assert!(!ProviderParamSupport::for_provider("anthropic").n);
+        assert!(!ProviderParamSupport::for_provider("ollama").n);
+    }
+}
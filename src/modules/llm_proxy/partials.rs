@@ -0,0 +1,644 @@
+//! Partial Responses
+//!
+//! This module records what a client received of a streamed completion that
+//! was interrupted, so a UI can offer to continue generating. Streams sent
+//! with the `x-intellirouter-conversation-id` header have the content of
+//! their chunks observed as they are delivered. When the client disconnects
+//! before the stream's completion, or the stream ends without a finish
+//! reason because the provider failed, the partial completion is appended to
+//! the conversation in memory as an assistant message marked `partial`,
+//! creating the conversation if needed. `GET
+//! /v1/conversations/{id}/last-partial` returns the latest one.
+//!
+//! Only the first choice of a stream is recorded. Resumed streams are not
+//! recorded again, so a client that resumes and completes an interrupted
+//! stream leaves its partial in the conversation.
+//!
+//! The messages of the request are kept with the partial completion, so
+//! `POST /v1/conversations/{id}/continue` can rebuild its context: the
+//! prompt, the partial completion as the assistant's turn and an instruction
+//! to continue it. Partials recorded without a prompt are continued after the
+//! conversation's earlier messages instead. The continuation is appended to
+//! the conversation as an assistant message naming the completion it
+//! `continues`, after which that partial is no longer the last one.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+use super::domain::message::{Message as ChatMessage, MessageRole};
+use super::dto::ChatCompletionRequest;
+use super::passthrough;
+use crate::config::PartialResponseConfig;
+use crate::modules::memory::{MemoryError, MemoryManager, Message};
+use crate::modules::model_registry::global_tokenizers;
+use crate::modules::telemetry::catalog;
+
+/// Header naming the conversation a streamed completion belongs to
+pub const CONVERSATION_HEADER: &str = "x-intellirouter-conversation-id";
+
+/// Metadata keys of partial messages
+const PARTIAL_KEY: &str = "partial";
+const RESPONSE_ID_KEY: &str = "response_id";
+const MODEL_KEY: &str = "model";
+const INTERRUPTION_KEY: &str = "interruption";
+const COMPLETION_TOKENS_KEY: &str = "completion_tokens";
+const PROMPT_KEY: &str = "prompt";
+const CONTINUES_KEY: &str = "continues";
+
+/// Why a stream ended before its completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Interruption {
+    /// The client stopped reading
+    ClientDisconnect,
+    /// The stream ended without a finish reason, as when the provider failed
+    StreamIncomplete,
+}
+
+impl Interruption {
+    /// Name of the interruption in metadata and metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ClientDisconnect => "client_disconnect",
+            Self::StreamIncomplete => "stream_incomplete",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "client_disconnect" => Some(Self::ClientDisconnect),
+            "stream_incomplete" => Some(Self::StreamIncomplete),
+            _ => None,
+        }
+    }
+}
+
+/// Partial completion of an interrupted stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PartialResponse {
+    /// Conversation the completion belongs to
+    pub conversation_id: String,
+    /// ID of the completion, from its chunks
+    pub response_id: Option<String>,
+    /// Model that generated the completion
+    pub model: String,
+    /// Content the client received
+    pub content: String,
+    /// Why the stream ended early
+    pub interruption: Interruption,
+    /// Tokens of the content, counted with the model's tokenizer
+    pub completion_tokens: u32,
+    /// When the stream was interrupted
+    pub created_at: DateTime<Utc>,
+}
+
+impl PartialResponse {
+    /// Conversation message recording the partial completion
+    fn to_message(&self) -> Message {
+        let mut message = Message::new("assistant", &self.content)
+            .with_metadata(PARTIAL_KEY, "true")
+            .with_metadata(MODEL_KEY, &self.model)
+            .with_metadata(INTERRUPTION_KEY, self.interruption.as_str())
+            .with_metadata(COMPLETION_TOKENS_KEY, &self.completion_tokens.to_string());
+        if let Some(response_id) = &self.response_id {
+            message = message.with_metadata(RESPONSE_ID_KEY, response_id);
+        }
+        message.timestamp = self.created_at;
+        message
+    }
+
+    /// Partial completion recorded by a conversation message, if it is one
+    fn from_message(conversation_id: &str, message: &Message) -> Option<Self> {
+        if !is_partial(message) {
+            return None;
+        }
+        let metadata = |key: &str| message.metadata.get(key).cloned();
+        Some(Self {
+            conversation_id: conversation_id.to_string(),
+            response_id: metadata(RESPONSE_ID_KEY),
+            model: metadata(MODEL_KEY).unwrap_or_default(),
+            content: message.content.clone(),
+            interruption: metadata(INTERRUPTION_KEY)
+                .and_then(|value| Interruption::parse(&value))
+                .unwrap_or(Interruption::StreamIncomplete),
+            completion_tokens: metadata(COMPLETION_TOKENS_KEY)
+                .and_then(|value| value.parse().ok())
+                .unwrap_or_default(),
+            created_at: message.timestamp,
+        })
+    }
+}
+
+/// Whether a conversation message records a partial completion
+fn is_partial(message: &Message) -> bool {
+    message.metadata.get(PARTIAL_KEY).map(String::as_str) == Some("true")
+}
+
+/// Chat message of a conversation message, unless its role is unknown
+fn to_chat_message(message: &Message) -> Option<ChatMessage> {
+    let role = serde_json::from_value::<MessageRole>(Value::String(message.role.clone())).ok()?;
+    (role != MessageRole::Unknown).then(|| ChatMessage::new(role, message.content.clone(), None))
+}
+
+/// Context rebuilt to continue a partial completion
+#[derive(Debug, Clone)]
+pub struct ContinuationContext {
+    /// Partial completion to continue
+    pub partial: PartialResponse,
+    /// Messages asking the model to continue it
+    pub messages: Vec<ChatMessage>,
+}
+
+/// How a continuation extends the partial completion it continues
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Continuation {
+    /// ID of the continued completion
+    pub continues: String,
+    /// Partial completion followed by its continuation
+    pub content: String,
+    /// Tokens of the partial completion
+    pub previous_completion_tokens: u32,
+    /// Tokens of the partial completion and its continuation
+    pub total_completion_tokens: u32,
+}
+
+/// Partial completions of interrupted streams, kept in conversation memory
+pub struct PartialResponses {
+    config: PartialResponseConfig,
+    memory: Option<Arc<MemoryManager>>,
+}
+
+impl fmt::Debug for PartialResponses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartialResponses")
+            .field("config", &self.config)
+            .field("memory", &self.memory.is_some())
+            .finish()
+    }
+}
+
+impl PartialResponses {
+    /// Create partial responses without memory, which record nothing
+    pub fn new(config: PartialResponseConfig) -> Self {
+        Self {
+            config,
+            memory: None,
+        }
+    }
+
+    /// Record partial responses in the conversations of a memory
+    pub fn with_memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Whether partial responses are recorded
+    pub fn enabled(&self) -> bool {
+        self.config.enabled && self.memory.is_some()
+    }
+
+    /// Recorder of a stream sent with the conversation header
+    pub fn recorder(
+        &self,
+        headers: &HeaderMap,
+        request: &ChatCompletionRequest,
+    ) -> Option<PartialRecorder> {
+        if !self.config.enabled {
+            return None;
+        }
+        let memory = self.memory.clone()?;
+        let conversation_id = headers
+            .get(CONVERSATION_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|id| !id.is_empty())?;
+        Some(PartialRecorder {
+            memory,
+            min_chars: self.config.min_chars,
+            conversation_id: conversation_id.to_string(),
+            model: request.model.clone(),
+            prompt: request.messages.clone(),
+            response_id: None,
+            content: String::new(),
+            finished: false,
+            ended: false,
+        })
+    }
+
+    /// Latest partial completion of a conversation
+    ///
+    /// Fails with `MemoryError::NotFound` for unknown conversations.
+    pub async fn last(
+        &self,
+        conversation_id: &str,
+    ) -> Result<Option<PartialResponse>, MemoryError> {
+        let Some(memory) = &self.memory else {
+            return Err(MemoryError::NotFound(conversation_id.to_string()));
+        };
+        let messages = memory.get_messages(conversation_id).await?;
+        let mut continued = HashSet::new();
+        for message in messages.iter().rev() {
+            if let Some(continues) = message.metadata.get(CONTINUES_KEY) {
+                continued.insert(continues.as_str());
+            }
+            let Some(partial) = PartialResponse::from_message(conversation_id, message) else {
+                continue;
+            };
+            if !partial
+                .response_id
+                .as_deref()
+                .is_some_and(|id| continued.contains(id))
+            {
+                return Ok(Some(partial));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Context asking the model to continue the partial completion
+    /// `response_id` of a conversation
+    ///
+    /// Fails with `MemoryError::NotFound` for unknown conversations.
+    pub async fn continuation(
+        &self,
+        conversation_id: &str,
+        response_id: &str,
+    ) -> Result<Option<ContinuationContext>, MemoryError> {
+        let Some(memory) = &self.memory else {
+            return Err(MemoryError::NotFound(conversation_id.to_string()));
+        };
+        let messages = memory.get_messages(conversation_id).await?;
+        let Some(position) = messages.iter().rposition(|message| {
+            is_partial(message)
+                && message.metadata.get(RESPONSE_ID_KEY).map(String::as_str) == Some(response_id)
+        }) else {
+            return Ok(None);
+        };
+        let message = &messages[position];
+        let Some(partial) = PartialResponse::from_message(conversation_id, message) else {
+            return Ok(None);
+        };
+
+        let prompt = message
+            .metadata
+            .get(PROMPT_KEY)
+            .and_then(|prompt| serde_json::from_str::<Vec<ChatMessage>>(prompt).ok());
+        let mut context = match prompt {
+            Some(prompt) => prompt,
+            None => messages[..position]
+                .iter()
+                .filter(|message| !is_partial(message))
+                .filter_map(to_chat_message)
+                .collect(),
+        };
+        context.push(ChatMessage::new_assistant(partial.content.clone()));
+        context.push(ChatMessage::new_user(
+            self.config.continue_instruction.clone(),
+        ));
+        Ok(Some(ContinuationContext {
+            partial,
+            messages: context,
+        }))
+    }
+
+    /// Append the continuation `content` of a partial completion to its
+    /// conversation
+    pub async fn record_continuation(
+        &self,
+        partial: &PartialResponse,
+        response_id: &str,
+        content: &str,
+        completion_tokens: u32,
+    ) -> Result<(), MemoryError> {
+        let Some(memory) = &self.memory else {
+            return Err(MemoryError::NotFound(partial.conversation_id.clone()));
+        };
+        let message = Message::new("assistant", content)
+            .with_metadata(RESPONSE_ID_KEY, response_id)
+            .with_metadata(MODEL_KEY, &partial.model)
+            .with_metadata(COMPLETION_TOKENS_KEY, &completion_tokens.to_string())
+            .with_metadata(
+                CONTINUES_KEY,
+                partial.response_id.as_deref().unwrap_or_default(),
+            );
+        memory
+            .add_message_with_metadata(
+                &partial.conversation_id,
+                &message.role,
+                &message.content,
+                message.metadata,
+            )
+            .await
+    }
+}
+
+impl Default for PartialResponses {
+    fn default() -> Self {
+        Self::new(PartialResponseConfig::default())
+    }
+}
+
+/// Records the content delivered by a stream, saving it if the stream is
+/// dropped before its completion
+pub struct PartialRecorder {
+    memory: Arc<MemoryManager>,
+    min_chars: usize,
+    conversation_id: String,
+    model: String,
+    /// Messages of the request
+    prompt: Vec<ChatMessage>,
+    response_id: Option<String>,
+    /// Content of the first choice delivered so far
+    content: String,
+    /// Whether a finish reason or `[DONE]` was delivered
+    finished: bool,
+    /// Whether the stream ended, as opposed to being dropped
+    ended: bool,
+}
+
+impl PartialRecorder {
+    /// Observe the event data of a delivered chunk
+    pub fn observe(&mut self, data: &str) {
+        if data == passthrough::DONE {
+            self.finished = true;
+            return;
+        }
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        if self.response_id.is_none() {
+            self.response_id = chunk.get("id").and_then(Value::as_str).map(str::to_string);
+        }
+        let choice = chunk
+            .get("choices")
+            .and_then(Value::as_array)
+            .and_then(|choices| {
+                choices
+                    .iter()
+                    .find(|choice| choice.get("index").and_then(Value::as_u64).unwrap_or(0) == 0)
+            });
+        if let Some(choice) = choice {
+            if let Some(content) = choice.pointer("/delta/content").and_then(Value::as_str) {
+                self.content.push_str(content);
+            }
+            if choice
+                .get("finish_reason")
+                .is_some_and(|reason| !reason.is_null())
+            {
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Partial completion to record, if the stream was interrupted
+    fn partial(&self) -> Option<PartialResponse> {
+        if self.finished || self.content.chars().count() < self.min_chars.max(1) {
+            return None;
+        }
+        let completion_tokens = global_tokenizers().count_tokens(&self.model, &self.content);
+        Some(PartialResponse {
+            conversation_id: self.conversation_id.clone(),
+            response_id: self.response_id.clone(),
+            model: self.model.clone(),
+            content: self.content.clone(),
+            interruption: if self.ended {
+                Interruption::StreamIncomplete
+            } else {
+                Interruption::ClientDisconnect
+            },
+            completion_tokens: u32::try_from(completion_tokens).unwrap_or(u32::MAX),
+            created_at: Utc::now(),
+        })
+    }
+}
+
+impl Drop for PartialRecorder {
+    fn drop(&mut self) {
+        let Some(partial) = self.partial() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let memory = self.memory.clone();
+        let prompt = serde_json::to_string(&self.prompt).ok();
+        runtime.spawn(async move {
+            let saved = async {
+                memory
+                    .get_or_create_conversation(&partial.conversation_id)
+                    .await?;
+                let mut message = partial.to_message();
+                if let Some(prompt) = prompt {
+                    message = message.with_metadata(PROMPT_KEY, &prompt);
+                }
+                memory
+                    .add_message_with_metadata(
+                        &partial.conversation_id,
+                        &message.role,
+                        &message.content,
+                        message.metadata,
+                    )
+                    .await
+            };
+            match saved.await {
+                Ok(()) => {
+                    counter!(
+                        catalog::STREAMS_PARTIALS_RECORDED,
+                        1,
+                        "interruption" => partial.interruption.as_str()
+                    );
+                    debug!(
+                        "Recorded partial completion of {} characters in conversation {}",
+                        partial.content.len(),
+                        partial.conversation_id
+                    );
+                }
+                Err(e) => warn!(
+                    "Failed to record partial completion in conversation {}: {}",
+                    partial.conversation_id, e
+                ),
+            }
+        });
+    }
+}
+
+/// Record the events delivered by a stream
+///
+/// `data` gives the event data of an item, if it has any. The recorder is
+/// dropped with the stream, saving the partial completion if the stream is
+/// dropped or ends before its completion.
+pub fn record<S, F>(
+    events: S,
+    recorder: Option<PartialRecorder>,
+    data: F,
+) -> impl Stream<Item = S::Item> + Send + 'static
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+    F: Fn(&S::Item) -> Option<String> + Send + 'static,
+{
+    let mut recorder = recorder;
+    events
+        .map(Some)
+        .chain(stream::iter([None]))
+        .filter_map(move |event| {
+            match (&event, recorder.as_mut()) {
+                (Some(event), Some(recorder)) => {
+                    if let Some(data) = data(event) {
+                        recorder.observe(&data);
+                    }
+                }
+                (None, Some(recorder)) => recorder.ended = true,
+                _ => {}
+            }
+            future::ready(event)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::InMemoryBackend;
+    use std::time::Duration;
+
+    fn chunk(content: &str, finish_reason: Option<&str>) -> String {
+        serde_json::json!({
+            "id": "chatcmpl-1",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish_reason}],
+        })
+        .to_string()
+    }
+
+    fn partials() -> PartialResponses {
+        let memory = MemoryManager::new(Arc::new(InMemoryBackend::new()), 100);
+        PartialResponses::new(PartialResponseConfig::default()).with_memory(Arc::new(memory))
+    }
+
+    fn request() -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Tell me a story"}],
+        }))
+        .unwrap()
+    }
+
+    fn headers(conversation_id: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONVERSATION_HEADER, conversation_id.parse().unwrap());
+        headers
+    }
+
+    async fn last(partials: &PartialResponses, id: &str) -> Option<PartialResponse> {
+        for _ in 0..50 {
+            if let Ok(Some(partial)) = partials.last(id).await {
+                return Some(partial);
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn test_disconnected_stream_is_recorded() {
+        let partials = partials();
+        let events = stream::iter([chunk("Once upon", None), chunk(" a time", None)]);
+        let mut recorded = Box::pin(record(
+            events,
+            partials.recorder(&headers("c1"), &request()),
+            |data: &String| Some(data.clone()),
+        ));
+        recorded.next().await;
+        recorded.next().await;
+        drop(recorded);
+
+        let partial = last(&partials, "c1").await.unwrap();
+        assert_eq!(partial.content, "Once upon a time");
+        assert_eq!(partial.response_id.as_deref(), Some("chatcmpl-1"));
+        assert_eq!(partial.interruption, Interruption::ClientDisconnect);
+        assert!(partial.completion_tokens > 0);
+    }
+
+    #[tokio::test]
+    async fn test_completed_stream_is_not_recorded() {
+        let partials = partials();
+        let events = stream::iter([chunk("Done", None), chunk("", Some("stop"))]);
+        let recorded = record(
+            events,
+            partials.recorder(&headers("c2"), &request()),
+            |data: &String| Some(data.clone()),
+        );
+        assert_eq!(recorded.collect::<Vec<_>>().await.len(), 2);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(partials.last("c2").await.is_err());
+
+        // Streams ending without a finish reason were cut short upstream
+        let events = stream::iter([chunk("Cut", None)]);
+        let recorded = record(
+            events,
+            partials.recorder(&headers("c3"), &request()),
+            |data: &String| Some(data.clone()),
+        );
+        recorded.collect::<Vec<_>>().await;
+        let partial = last(&partials, "c3").await.unwrap();
+        assert_eq!(partial.interruption, Interruption::StreamIncomplete);
+    }
+
+    #[tokio::test]
+    async fn test_continuation() {
+        let partials = partials();
+        let events = stream::iter([chunk("Once upon", None)]);
+        let mut recorded = Box::pin(record(
+            events,
+            partials.recorder(&headers("c4"), &request()),
+            |data: &String| Some(data.clone()),
+        ));
+        recorded.next().await;
+        drop(recorded);
+        let partial = last(&partials, "c4").await.unwrap();
+
+        assert!(partials
+            .continuation("c4", "unknown")
+            .await
+            .unwrap()
+            .is_none());
+        let context = partials
+            .continuation("c4", "chatcmpl-1")
+            .await
+            .unwrap()
+            .unwrap();
+        let messages: Vec<_> = context
+            .messages
+            .iter()
+            .map(|message| (message.role.clone(), message.extract_text_content()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (MessageRole::User, "Tell me a story".to_string()),
+                (MessageRole::Assistant, "Once upon".to_string()),
+                (
+                    MessageRole::User,
+                    PartialResponseConfig::default().continue_instruction
+                ),
+            ]
+        );
+
+        // A continued partial is no longer the last one
+        partials
+            .record_continuation(&partial, "chatcmpl-2", " a time", 2)
+            .await
+            .unwrap();
+        assert_eq!(partials.last("c4").await.unwrap(), None);
+    }
+}
@@ -0,0 +1,234 @@
+//! Streaming Passthrough
+//!
+//! When a provider streams in the OpenAI SSE format clients already expect,
+//! the proxy forwards the provider's bytes instead of parsing every chunk and
+//! serializing it again. The body is only split into whole events, so clients
+//! never receive half an event and the end of the stream is detected. Events
+//! are sliced out of the received buffers without copying; only an event
+//! split across network reads is copied to join its parts.
+
+use std::collections::VecDeque;
+
+use axum::{
+    body::Body,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use bytes::{Bytes, BytesMut};
+use futures::stream::{self, Stream, StreamExt};
+
+/// Data of the event ending an OpenAI stream
+pub const DONE: &str = "[DONE]";
+
+/// Splits an SSE body into whole events
+#[derive(Debug, Default)]
+pub struct FrameSplitter {
+    /// Start of an event whose end hasn't been received yet
+    pending: BytesMut,
+}
+
+impl FrameSplitter {
+    /// Create a frame splitter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split a received chunk into the events it completes
+    ///
+    /// Returned events include their blank-line terminator; an incomplete
+    /// trailing event is kept until the chunk completing it is pushed.
+    pub fn push(&mut self, mut chunk: Bytes) -> Vec<Bytes> {
+        let mut frames = Vec::new();
+
+        if !self.pending.is_empty() {
+            match continued_frame_end(&self.pending, &chunk) {
+                Some(end) => {
+                    self.pending.extend_from_slice(&chunk[..end]);
+                    frames.push(self.pending.split().freeze());
+                    chunk = chunk.slice(end..);
+                }
+                None => {
+                    self.pending.extend_from_slice(&chunk);
+                    return frames;
+                }
+            }
+        }
+
+        while let Some(end) = frame_end(&chunk) {
+            frames.push(chunk.split_to(end));
+        }
+        if !chunk.is_empty() {
+            self.pending.extend_from_slice(&chunk);
+        }
+        frames
+    }
+
+    /// Take the incomplete event left when the body ends
+    pub fn finish(&mut self) -> Option<Bytes> {
+        (!self.pending.is_empty()).then(|| self.pending.split().freeze())
+    }
+}
+
+/// Offset just past the first event terminator in `buf`
+fn frame_end(buf: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(i) = buf[offset..].iter().position(|&b| b == b'\n') {
+        let newline = offset + i;
+        match &buf[newline + 1..] {
+            [b'\n', ..] => return Some(newline + 2),
+            [b'\r', b'\n', ..] => return Some(newline + 3),
+            _ => offset = newline + 1,
+        }
+    }
+    None
+}
+
+/// Offset in `chunk` just past the terminator of the event started by `pending`
+fn continued_frame_end(pending: &[u8], chunk: &[u8]) -> Option<usize> {
+    // The terminator may straddle the two buffers
+    if pending.ends_with(b"\n\r") || pending.ends_with(b"\n") {
+        if chunk.starts_with(b"\n") {
+            return Some(1);
+        }
+        if pending.ends_with(b"\n") && chunk.starts_with(b"\r\n") {
+            return Some(2);
+        }
+    }
+    frame_end(chunk)
+}
+
+/// Data of an event, joining its `data:` lines
+///
+/// Returns `None` for events without data, such as keep-alive comments.
+pub fn event_data(frame: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(frame).ok()?;
+    let lines: Vec<&str> = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| data.strip_prefix(' ').unwrap_or(data))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Whether an event is the `[DONE]` event ending the stream
+///
+/// Checked on every forwarded event, so it compares bytes rather than
+/// extracting the event's data.
+pub fn is_done(frame: &[u8]) -> bool {
+    let frame = frame.trim_ascii_end();
+    frame == b"data: [DONE]" || frame == b"data:[DONE]"
+}
+
+/// Split a provider's SSE body into whole events
+///
+/// The stream ends after the `[DONE]` event, even if the provider keeps the
+/// connection open.
+pub fn frames<S, E>(body: S) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + Unpin,
+    E: Send,
+{
+    stream::unfold(
+        (body, FrameSplitter::new(), VecDeque::<Bytes>::new(), false),
+        |(mut body, mut splitter, mut ready, mut done)| async move {
+            loop {
+                if let Some(frame) = ready.pop_front() {
+                    done = done || is_done(&frame);
+                    return Some((Ok(frame), (body, splitter, ready, done)));
+                }
+                if done {
+                    return None;
+                }
+                match body.next().await {
+                    Some(Ok(chunk)) => ready.extend(splitter.push(chunk)),
+                    Some(Err(e)) => {
+                        // Nothing can follow a failed read
+                        return Some((Err(e), (body, splitter, ready, true)));
+                    }
+                    None => {
+                        ready.extend(splitter.finish());
+                        done = true;
+                    }
+                }
+            }
+        },
+    )
+}
+
+/// SSE response forwarding the events split from a provider's OpenAI-format stream as is
+pub fn sse_response<S, E>(frames: S) -> Response
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + 'static,
+    E: Into<Box<dyn std::error::Error + Send + Sync>> + Send + 'static,
+{
+    (
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(frames),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn split(chunks: &[&'static str]) -> Vec<String> {
+        let mut splitter = FrameSplitter::new();
+        let mut frames: Vec<Bytes> = chunks
+            .iter()
+            .flat_map(|chunk| splitter.push(Bytes::from_static(chunk.as_bytes())))
+            .collect();
+        frames.extend(splitter.finish());
+        frames
+            .iter()
+            .map(|frame| String::from_utf8(frame.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_splits_events_across_chunks() {
+        assert_eq!(
+            split(&["data: a\n\ndata: b\n", "\ndata: c", "\n\n"]),
+            vec!["data: a\n\n", "data: b\n\n", "data: c\n\n"]
+        );
+        assert_eq!(
+            split(&["data: a\r\n\r", "\ndata: b\r\n", "\r\n"]),
+            vec!["data: a\r\n\r\n", "data: b\r\n\r\n"]
+        );
+        assert_eq!(split(&["data: a\n\ndata: trailing"]).len(), 2);
+    }
+
+    #[test]
+    fn test_whole_events_are_not_copied() {
+        let chunk = Bytes::from_static(b"data: a\n\ndata: b\n\n");
+        let frames = FrameSplitter::new().push(chunk.clone());
+        assert_eq!(frames[0].as_ptr(), chunk.as_ptr());
+        assert_eq!(frames[1].as_ptr(), chunk[9..].as_ptr());
+    }
+
+    #[test]
+    fn test_event_data() {
+        assert_eq!(
+            event_data(b"data: {\"a\":1}\n\n"),
+            Some("{\"a\":1}".to_string())
+        );
+        assert_eq!(event_data(b"data:x\ndata: y\n\n"), Some("x\ny".to_string()));
+        assert_eq!(event_data(b": keep-alive\n\n"), None);
+        assert!(is_done(b"data: [DONE]\r\n\r\n"));
+        assert!(!is_done(b"data: {}\n\n"));
+    }
+
+    #[tokio::test]
+    async fn test_frames_end_after_done() {
+        let body = stream::iter(
+            ["data: a\n\nda", "ta: [DONE]\n\n", "data: ignored\n\n"]
+                .map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))),
+        );
+        let frames: Vec<_> = frames(body).map(Result::unwrap).collect().await;
+        assert_eq!(frames, vec!["data: a\n\n", "data: [DONE]\n\n"]);
+    }
+}
@@ -0,0 +1,569 @@
+//! Request Payload Capture
+//!
+//! This module keeps the payloads of completed chat completions, so a
+//! production request can be replayed for debugging with
+//! `intellirouter replay <request-id>`. A record holds the request as sent to
+//! the model, after model resolution and the registry prompt and with any
+//! system prompts and retrieved context added before routing, along with
+//! the prompt and routing policy versions that shaped it and the response
+//! the client received. Records are keyed by the ID of the request's routing
+//! decision, which is also the `request_id` of its telemetry record.
+//!
+//! Records are queued without blocking the request path and written by a
+//! background task as `<request_id>.json` under the configured directory,
+//! which keeps the most recent `max_records`. Streamed completions are not
+//! captured. With encryption at rest enabled, records are sealed with their
+//! request ID as associated data; records captured before it was enabled
+//! stay readable until `intellirouter migrate-encryption` seals them.
+
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::warn;
+use utoipa::ToSchema;
+
+use super::dto::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::config::PayloadCaptureConfig;
+use crate::modules::encryption::{EncryptionError, EnvelopeEncryptor, MigrationReport};
+use crate::modules::erasure::ErasureSubject;
+use crate::modules::telemetry::catalog;
+
+/// Capacity of the queue of records waiting to be written
+const QUEUE_CAPACITY: usize = 256;
+
+/// Errors that can occur when reading captured payloads
+#[derive(Error, Debug)]
+pub enum PayloadError {
+    #[error("Invalid request ID: {0}")]
+    InvalidId(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Encryption error: {0}")]
+    Encryption(#[from] EncryptionError),
+
+    #[error("Payload {0} is encrypted at rest and no encryption key is configured")]
+    Sealed(String),
+}
+
+/// Registry prompt prepended to a captured request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PromptSnapshot {
+    /// Prompt ID
+    pub id: String,
+    /// Version served
+    pub version: u32,
+    /// Text of the version served
+    pub template: String,
+}
+
+/// Captured payload of a request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PayloadRecord {
+    /// ID of the request's routing decision
+    pub request_id: String,
+    /// Time the request completed
+    pub timestamp: DateTime<Utc>,
+    /// Tenant that sent the request
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Model requested by the client
+    pub requested_model: String,
+    /// Model that served the request
+    pub model: String,
+    /// Provider of the serving model
+    pub provider: String,
+    /// Version of the routing policy active when the request was routed
+    pub policy_version: Option<u32>,
+    /// Registry prompt prepended to the request
+    pub prompt: Option<PromptSnapshot>,
+    /// Request as sent to the model
+    pub request: ChatCompletionRequest,
+    /// Response returned to the client
+    pub response: Option<ChatCompletionResponse>,
+    /// Error message when the request failed
+    pub error: Option<String>,
+    /// End-to-end latency in milliseconds
+    pub latency_ms: u64,
+}
+
+impl PayloadRecord {
+    /// Text of the first choice of the response
+    pub fn output(&self) -> Option<String> {
+        self.response
+            .as_ref()
+            .and_then(|response| response.choices.first())
+            .map(|choice| choice.message.extract_text_content())
+    }
+}
+
+/// Store of captured request payloads
+pub struct PayloadStore {
+    config: PayloadCaptureConfig,
+    /// Queue of the writer task, when capture is enabled
+    sender: Option<mpsc::Sender<PayloadRecord>>,
+    /// Encryptor sealing records at rest
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+}
+
+impl PayloadStore {
+    /// Create a store, starting its writer task when capture is enabled
+    ///
+    /// Records are sealed with `encryptor` when one is given.
+    pub fn new(config: PayloadCaptureConfig, encryptor: Option<Arc<EnvelopeEncryptor>>) -> Self {
+        let sender = config.enabled.then(|| {
+            let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+            tokio::spawn(write_records(
+                receiver,
+                PathBuf::from(&config.dir),
+                config.max_records,
+                encryptor.clone(),
+            ));
+            sender
+        });
+        Self {
+            config,
+            sender,
+            encryptor,
+        }
+    }
+
+    /// Whether payloads are captured
+    pub fn enabled(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    /// Queue a record to be written, subject to sampling
+    ///
+    /// Records are dropped and counted when the queue is full.
+    pub fn record(&self, record: PayloadRecord) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if rand::random::<f64>() >= self.config.sample_rate {
+            return;
+        }
+        if sender.try_send(record).is_err() {
+            counter!(catalog::PAYLOADS_CAPTURED, 1, "outcome" => "dropped");
+        }
+    }
+
+    /// Read the captured record of a request
+    pub async fn get(&self, request_id: &str) -> Result<Option<PayloadRecord>, PayloadError> {
+        if !is_valid_id(request_id) {
+            return Err(PayloadError::InvalidId(request_id.to_string()));
+        }
+        match tokio::fs::read_to_string(record_path(Path::new(&self.config.dir), request_id)).await
+        {
+            Ok(content) => Ok(Some(
+                open_record(content, request_id, self.encryptor.as_deref()).await?,
+            )),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete the records of a data subject, returning how many were deleted
+    pub async fn erase(&self, subject: &ErasureSubject) -> Result<usize, PayloadError> {
+        let mut erased = 0;
+        for path in record_files(Path::new(&self.config.dir)).await? {
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                // Deleted by the writer task since the listing
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let record = open_record(content, &file_id(&path), self.encryptor.as_deref()).await;
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("Skipping unreadable payload {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if subject.matches(record.tenant.as_deref(), record.user.as_deref()) {
+                remove(&path).await?;
+                erased += 1;
+            }
+        }
+        Ok(erased)
+    }
+}
+
+impl std::fmt::Debug for PayloadStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PayloadStore")
+            .field("config", &self.config)
+            .field("enabled", &self.enabled())
+            .field("encrypted", &self.encryptor.is_some())
+            .finish()
+    }
+}
+
+impl Default for PayloadStore {
+    fn default() -> Self {
+        Self::new(PayloadCaptureConfig::default(), None)
+    }
+}
+
+/// Parse the content of a record file, opening it when it is sealed
+///
+/// Sealed records are bound to their request ID, the stem of their file name.
+pub async fn open_record(
+    content: String,
+    request_id: &str,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<PayloadRecord, PayloadError> {
+    let content = match encryptor {
+        Some(encryptor) => encryptor.open_str(content, request_id).await?,
+        None if EnvelopeEncryptor::is_sealed(&content) => {
+            return Err(PayloadError::Sealed(request_id.to_string()))
+        }
+        None => content,
+    };
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Read a record file, such as one copied out of the capture directory
+pub async fn read_record(
+    path: &Path,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<PayloadRecord, PayloadError> {
+    let content = tokio::fs::read_to_string(path).await?;
+    open_record(content, &file_id(path), encryptor).await
+}
+
+/// Seal or rewrap every record in a payload capture directory
+///
+/// Records are sealed with their request ID as associated data, matching the
+/// store, and keep their modification time so the oldest are still deleted
+/// first. With `dry_run` the report is computed without writing anything.
+pub async fn migrate_records(
+    dir: &Path,
+    encryptor: &EnvelopeEncryptor,
+    dry_run: bool,
+) -> Result<MigrationReport, PayloadError> {
+    let mut report = MigrationReport::default();
+    for path in record_files(dir).await? {
+        let content = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => content,
+            // Deleted by a running writer task since the listing
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        report.scanned += 1;
+
+        let migrated = if EnvelopeEncryptor::is_sealed(&content) {
+            match encryptor.rotate(&content).await? {
+                Some(rewrapped) => {
+                    report.rewrapped += 1;
+                    rewrapped
+                }
+                None => {
+                    report.unchanged += 1;
+                    continue;
+                }
+            }
+        } else {
+            report.encrypted += 1;
+            encryptor.seal_str(&content, &file_id(&path)).await?
+        };
+
+        if !dry_run {
+            let modified = tokio::fs::metadata(&path).await?.modified()?;
+            tokio::fs::write(&path, migrated).await?;
+            let file = tokio::fs::File::options().write(true).open(&path).await?;
+            file.into_std().await.set_modified(modified)?;
+        }
+    }
+    Ok(report)
+}
+
+/// Whether a request ID is safe to use as a file name
+fn is_valid_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Path of the record of a request
+fn record_path(dir: &Path, request_id: &str) -> PathBuf {
+    dir.join(format!("{}.json", request_id))
+}
+
+/// Request ID of a record file
+fn file_id(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Record files in a directory, oldest first
+async fn record_files(dir: &Path) -> Result<Vec<PathBuf>, PayloadError> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut files = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let modified = entry.metadata().await?.modified()?;
+            files.push((modified, path));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Delete a file, ignoring one that is already gone
+async fn remove(path: &Path) -> Result<(), std::io::Error> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Write queued records, deleting the oldest beyond `max_records`
+async fn write_records(
+    mut receiver: mpsc::Receiver<PayloadRecord>,
+    dir: PathBuf,
+    max_records: usize,
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+) {
+    if let Err(e) = tokio::fs::create_dir_all(&dir).await {
+        warn!(
+            "Failed to create payload directory {}: {}",
+            dir.display(),
+            e
+        );
+    }
+    let mut written: VecDeque<PathBuf> = match record_files(&dir).await {
+        Ok(files) => files.into(),
+        Err(e) => {
+            warn!("Failed to list payloads in {}: {}", dir.display(), e);
+            VecDeque::new()
+        }
+    };
+
+    while let Some(record) = receiver.recv().await {
+        let path = record_path(&dir, &record.request_id);
+        let result = match seal_record(&record, encryptor.as_deref()).await {
+            Ok(content) => tokio::fs::write(&path, content)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        let outcome = match result {
+            Ok(()) => {
+                written.push_back(path);
+                "written"
+            }
+            Err(e) => {
+                warn!("Failed to write payload {}: {}", record.request_id, e);
+                "failed"
+            }
+        };
+        counter!(catalog::PAYLOADS_CAPTURED, 1, "outcome" => outcome);
+
+        while written.len() > max_records {
+            if let Some(oldest) = written.pop_front() {
+                if let Err(e) = remove(&oldest).await {
+                    warn!("Failed to delete payload {}: {}", oldest.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Serialize a record, sealing it when encryption at rest is enabled
+async fn seal_record(
+    record: &PayloadRecord,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<String, PayloadError> {
+    let content = serde_json::to_string_pretty(record)?;
+    Ok(match encryptor {
+        Some(encryptor) => encryptor.seal_str(&content, &record.request_id).await?,
+        None => content,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::encryption::LocalKeyProvider;
+    use crate::modules::llm_proxy::domain::message::Message;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn record(request_id: &str, tenant: &str) -> PayloadRecord {
+        let request = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message::new_user("Where is my order?".to_string())],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: false,
+            max_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
+        };
+        PayloadRecord {
+            request_id: request_id.to_string(),
+            timestamp: Utc::now(),
+            tenant: Some(tenant.to_string()),
+            user: None,
+            requested_model: "gpt-4o".to_string(),
+            model: "gpt-4o".to_string(),
+            provider: "openai".to_string(),
+            policy_version: Some(3),
+            prompt: None,
+            request,
+            response: Some(ChatCompletionResponse::new(
+                "gpt-4o".to_string(),
+                Message::new_assistant("It ships tomorrow.".to_string()),
+            )),
+            error: None,
+            latency_ms: 120,
+        }
+    }
+
+    async fn wait_for(store: &PayloadStore, request_id: &str) -> Option<PayloadRecord> {
+        for _ in 0..50 {
+            if let Some(record) = store.get(request_id).await.unwrap() {
+                return Some(record);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        None
+    }
+
+    #[tokio::test]
+    async fn test_capture_and_read() {
+        let dir = std::env::temp_dir().join(format!("payloads-{}", uuid::Uuid::new_v4()));
+        let store = PayloadStore::new(
+            PayloadCaptureConfig {
+                enabled: true,
+                dir: dir.to_string_lossy().to_string(),
+                max_records: 2,
+                ..Default::default()
+            },
+            None,
+        );
+
+        for id in ["req-1", "req-2", "req-3"] {
+            store.record(record(id, "acme"));
+            wait_for(&store, id).await.expect("payload written");
+        }
+        let record = store.get("req-3").await.unwrap().unwrap();
+        assert_eq!(record.output().as_deref(), Some("It ships tomorrow."));
+        assert_eq!(record.policy_version, Some(3));
+        // The oldest record is deleted to stay within `max_records`
+        let mut pruned = false;
+        for _ in 0..50 {
+            pruned = store.get("req-1").await.unwrap().is_none();
+            if pruned {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(pruned);
+        assert!(matches!(
+            store.get("../config").await,
+            Err(PayloadError::InvalidId(_))
+        ));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_erase_subject() {
+        let dir = std::env::temp_dir().join(format!("payloads-{}", uuid::Uuid::new_v4()));
+        let store = PayloadStore::new(
+            PayloadCaptureConfig {
+                enabled: true,
+                dir: dir.to_string_lossy().to_string(),
+                ..Default::default()
+            },
+            None,
+        );
+        store.record(record("req-a", "acme"));
+        store.record(record("req-b", "globex"));
+        wait_for(&store, "req-b").await.expect("payload written");
+
+        let subject = ErasureSubject::new("acme", None).unwrap();
+        assert_eq!(store.erase(&subject).await.unwrap(), 1);
+        assert!(store.get("req-a").await.unwrap().is_none());
+        assert!(store.get("req-b").await.unwrap().is_some());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_sealed_records() {
+        let dir = std::env::temp_dir().join(format!("payloads-{}", uuid::Uuid::new_v4()));
+        let config = PayloadCaptureConfig {
+            enabled: true,
+            dir: dir.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let keys = HashMap::from([("k1".to_string(), vec![7; 32])]);
+        let encryptor = Arc::new(EnvelopeEncryptor::new(Arc::new(
+            LocalKeyProvider::new("k1", keys).unwrap(),
+        )));
+
+        // Captured before encryption was enabled
+        let plain = PayloadStore::new(config.clone(), None);
+        plain.record(record("req-old", "acme"));
+        wait_for(&plain, "req-old").await.expect("payload written");
+
+        let store = PayloadStore::new(config, Some(encryptor.clone()));
+        store.record(record("req-new", "acme"));
+        wait_for(&store, "req-new").await.expect("payload written");
+        let content = std::fs::read_to_string(dir.join("req-new.json")).unwrap();
+        assert!(EnvelopeEncryptor::is_sealed(&content));
+        assert!(!content.contains("Where is my order?"));
+        assert!(matches!(
+            plain.get("req-new").await,
+            Err(PayloadError::Sealed(_))
+        ));
+        assert!(store.get("req-old").await.unwrap().is_some());
+
+        let report = migrate_records(&dir, &encryptor, false).await.unwrap();
+        assert_eq!(
+            (report.scanned, report.encrypted, report.unchanged),
+            (2, 1, 1)
+        );
+        let content = std::fs::read_to_string(dir.join("req-old.json")).unwrap();
+        assert!(EnvelopeEncryptor::is_sealed(&content));
+        let record = store.get("req-old").await.unwrap().unwrap();
+        assert_eq!(record.output().as_deref(), Some("It ships tomorrow."));
+
+        let subject = ErasureSubject::new("acme", None).unwrap();
+        assert_eq!(store.erase(&subject).await.unwrap(), 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
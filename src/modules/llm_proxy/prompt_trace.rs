@@ -0,0 +1,340 @@
+//! Prompt Assembly Tracing
+//!
+//! This module records how the prompt of a request is assembled. Requests
+//! sent with the `x-intellirouter-debug: prompt-trace` header get a trace of
+//! the messages after each stage (the client's request, the registry prompt,
+//! the conversion to the provider's format and the per-model transforms),
+//! with each message of the final prompt labeled with the stage that
+//! contributed it. Traces are kept in a bounded log for the admin API and,
+//! when allowed, returned to the caller.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::dto::ChatCompletionRequest;
+use crate::config::PromptTraceConfig;
+use crate::modules::erasure::ErasureSubject;
+use crate::modules::model_registry::connectors;
+
+/// Header enabling debug modes of a request
+pub const DEBUG_HEADER: &str = "x-intellirouter-debug";
+
+/// Debug mode tracing the prompt assembly
+pub const PROMPT_TRACE_MODE: &str = "prompt-trace";
+
+/// Response header carrying the ID of a request's prompt trace
+pub const TRACE_ID_HEADER: &str = "x-intellirouter-prompt-trace";
+
+/// Stage of the client's own messages
+pub const CLIENT_STAGE: &str = "client";
+
+/// Whether a request asks for a prompt trace
+///
+/// The debug header holds a comma-separated list of modes.
+pub fn requested(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(DEBUG_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|mode| mode.trim().eq_ignore_ascii_case(PROMPT_TRACE_MODE))
+}
+
+/// A message of the assembled prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TracedMessage {
+    /// Role of the message
+    pub role: String,
+    /// Text of the message
+    pub content: String,
+    /// Stage that contributed the message in this form
+    pub stage: String,
+}
+
+/// Contribution of one assembly stage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TraceStage {
+    /// Stage name
+    pub stage: String,
+    /// What the stage applied, e.g. the prompt version
+    pub detail: Option<String>,
+    /// Messages the stage added or rewrote
+    pub added: Vec<TracedMessage>,
+    /// Messages the stage dropped or rewrote, as they were before
+    pub removed: Vec<TracedMessage>,
+}
+
+/// Trace of the prompt assembly of a request
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PromptTrace {
+    /// Trace identifier, returned in the `x-intellirouter-prompt-trace` header
+    pub id: String,
+    /// When the request was received
+    pub timestamp: DateTime<Utc>,
+    /// Tenant that sent the request
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    /// Model requested by the client
+    pub model: String,
+    /// Stages in the order they were applied
+    pub stages: Vec<TraceStage>,
+    /// Prompt sent to the model
+    pub messages: Vec<TracedMessage>,
+}
+
+impl PromptTrace {
+    /// Start a trace from the messages of the client's request
+    pub fn new(request: &ChatCompletionRequest, tenant: Option<String>) -> Self {
+        let messages = request_messages(request);
+        let mut trace = Self {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            tenant,
+            user: request.user.clone(),
+            model: request.model.clone(),
+            stages: Vec::new(),
+            messages: Vec::new(),
+        };
+        trace.stage(CLIENT_STAGE, None, messages);
+        trace
+    }
+
+    /// Record the messages after a stage
+    ///
+    /// Messages left as they were keep the label of the stage that
+    /// contributed them; new or rewritten ones are labeled with this stage.
+    pub fn stage(&mut self, stage: &str, detail: Option<String>, messages: Vec<(String, String)>) {
+        let mut previous: Vec<Option<TracedMessage>> = self.messages.drain(..).map(Some).collect();
+        let mut added = Vec::new();
+        for (role, content) in messages {
+            let kept = previous.iter_mut().find(|message| {
+                message
+                    .as_ref()
+                    .is_some_and(|m| m.role == role && m.content == content)
+            });
+            let message = match kept.and_then(Option::take) {
+                Some(message) => message,
+                None => {
+                    let message = TracedMessage {
+                        role,
+                        content,
+                        stage: stage.to_string(),
+                    };
+                    added.push(message.clone());
+                    message
+                }
+            };
+            self.messages.push(message);
+        }
+        self.stages.push(TraceStage {
+            stage: stage.to_string(),
+            detail,
+            added,
+            removed: previous.into_iter().flatten().collect(),
+        });
+    }
+}
+
+/// Role and text of the messages of a client request
+pub fn request_messages(request: &ChatCompletionRequest) -> Vec<(String, String)> {
+    request
+        .messages
+        .iter()
+        .map(|message| {
+            (
+                format!("{:?}", message.role).to_lowercase(),
+                message.extract_text_content(),
+            )
+        })
+        .collect()
+}
+
+/// Role and text of the messages of a provider request
+pub fn connector_messages(request: &connectors::ChatCompletionRequest) -> Vec<(String, String)> {
+    request
+        .messages
+        .iter()
+        .map(|message| {
+            (
+                format!("{:?}", message.role).to_lowercase(),
+                message.content.clone(),
+            )
+        })
+        .collect()
+}
+
+/// Bounded log of recent prompt traces
+#[derive(Debug)]
+pub struct PromptTraceLog {
+    config: PromptTraceConfig,
+    traces: Mutex<VecDeque<PromptTrace>>,
+}
+
+impl PromptTraceLog {
+    /// Create a trace log
+    pub fn new(config: PromptTraceConfig) -> Self {
+        Self {
+            config,
+            traces: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Whether requests may ask for prompt traces
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Whether traces are returned in the response body
+    pub fn include_in_response(&self) -> bool {
+        self.config.include_in_response
+    }
+
+    /// Keep a trace, dropping the oldest once the log is full
+    pub fn record(&self, trace: PromptTrace) {
+        let mut traces = self.traces.lock().unwrap();
+        traces.push_back(trace);
+        while traces.len() > self.config.capacity {
+            traces.pop_front();
+        }
+    }
+
+    /// Get a trace by ID
+    pub fn get(&self, id: &str) -> Option<PromptTrace> {
+        self.traces
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|trace| trace.id == id)
+            .cloned()
+    }
+
+    /// Recent traces, oldest first
+    pub fn recent(&self) -> Vec<PromptTrace> {
+        self.traces.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Drop the traces of a data subject, returning how many were dropped
+    pub fn erase(&self, subject: &ErasureSubject) -> usize {
+        let mut traces = self.traces.lock().unwrap();
+        let before = traces.len();
+        traces.retain(|trace| !subject.matches(trace.tenant.as_deref(), trace.user.as_deref()));
+        before - traces.len()
+    }
+}
+
+impl Default for PromptTraceLog {
+    fn default() -> Self {
+        Self::new(PromptTraceConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::llm_proxy::domain::message::Message;
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![
+                Message::new_system("Answer in French.".to_string()),
+                Message::new_user("Where is my order?".to_string()),
+            ],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: false,
+            max_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
+        }
+    }
+
+    fn message(role: &str, content: &str) -> (String, String) {
+        (role.to_string(), content.to_string())
+    }
+
+    #[test]
+    fn test_requested() {
+        let mut headers = HeaderMap::new();
+        assert!(!requested(&headers));
+        headers.insert(DEBUG_HEADER, "timing, Prompt-Trace".parse().unwrap());
+        assert!(requested(&headers));
+        headers.insert(DEBUG_HEADER, "timing".parse().unwrap());
+        assert!(!requested(&headers));
+    }
+
+    #[test]
+    fn test_stages_label_their_contributions() {
+        let mut request = request();
+        let mut trace = PromptTrace::new(&request, None);
+        request.messages.insert(
+            0,
+            Message::new_system("You are a support agent.".to_string()),
+        );
+        trace.stage(
+            "prompt_registry",
+            Some("support v2".to_string()),
+            request_messages(&request),
+        );
+        trace.stage(
+            "transform",
+            None,
+            vec![
+                message("system", "You are a support agent."),
+                message("system", "Answer in French. Be brief."),
+                message("user", "Where is my order?"),
+            ],
+        );
+
+        let labels: Vec<&str> = trace.messages.iter().map(|m| m.stage.as_str()).collect();
+        assert_eq!(labels, vec!["prompt_registry", "transform", "client"]);
+
+        let stages: Vec<(&str, usize, usize)> = trace
+            .stages
+            .iter()
+            .map(|s| (s.stage.as_str(), s.added.len(), s.removed.len()))
+            .collect();
+        assert_eq!(
+            stages,
+            vec![
+                ("client", 2, 0),
+                ("prompt_registry", 1, 0),
+                ("transform", 1, 1)
+            ]
+        );
+        assert_eq!(trace.stages[2].removed[0].content, "Answer in French.");
+        assert_eq!(trace.stages[2].removed[0].stage, "client");
+    }
+
+    #[test]
+    fn test_log_is_bounded() {
+        let log = PromptTraceLog::new(PromptTraceConfig {
+            enabled: true,
+            capacity: 2,
+            ..Default::default()
+        });
+        let traces: Vec<PromptTrace> = (0..3).map(|_| PromptTrace::new(&request(), None)).collect();
+        for trace in &traces {
+            log.record(trace.clone());
+        }
+        assert_eq!(log.recent().len(), 2);
+        assert!(log.get(&traces[0].id).is_none());
+        assert_eq!(log.get(&traces[2].id).unwrap().messages.len(), 2);
+    }
+}
@@ -0,0 +1,465 @@
+//! Prompt Registry
+//!
+//! This module keeps versioned system prompts that requests select by name
+//! with the `x-intellirouter-prompt` header. Each prompt has a stable version
+//! and optionally a canary version served to a percentage of callers; the
+//! bucket of a caller is derived from its user (or tenant) so it sees the
+//! same version across requests. Rollouts can be promoted or rolled back at
+//! any time, and the version served to each request is kept in a bounded
+//! audit trail.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::config::PromptRegistryConfig;
+use crate::modules::erasure::ErasureSubject;
+
+/// Header selecting the prompt of a request
+pub const PROMPT_HEADER: &str = "x-intellirouter-prompt";
+
+/// Errors of the prompt registry
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PromptError {
+    #[error("Unknown prompt '{0}'")]
+    UnknownPrompt(String),
+
+    #[error("Prompt '{prompt}' has no version {version}")]
+    UnknownVersion { prompt: String, version: u32 },
+
+    #[error("Rollout percentage must be between 0 and 100, got {0}")]
+    InvalidPercentage(u8),
+
+    #[error("Prompt template must not be empty")]
+    EmptyTemplate,
+
+    #[error("Prompt '{0}' has no earlier version to roll back to")]
+    NothingToRollBack(String),
+}
+
+/// A published version of a prompt
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PromptVersion {
+    /// Version number, starting at 1
+    pub version: u32,
+    /// System prompt text
+    pub template: String,
+    /// What changed in this version
+    pub description: Option<String>,
+    /// Who published the version
+    pub author: Option<String>,
+    /// When the version was published
+    pub created_at: DateTime<Utc>,
+}
+
+/// Version being rolled out to part of the traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PromptRollout {
+    /// Version served to the rollout's share of callers
+    pub version: u32,
+    /// Share of callers served the version, between 0 and 100
+    pub percentage: u8,
+}
+
+/// Summary of a prompt's versions and rollout
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PromptInfo {
+    /// Prompt name
+    pub id: String,
+    /// Version served outside the rollout
+    pub stable: u32,
+    /// Version being rolled out, if any
+    pub rollout: Option<PromptRollout>,
+    /// Published versions, oldest first
+    pub versions: Vec<PromptVersion>,
+}
+
+/// Prompt version selected for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPrompt {
+    /// Prompt name
+    pub id: String,
+    /// Version served
+    pub version: u32,
+    /// System prompt text of the version
+    pub template: String,
+    /// Whether the version was selected by a rollout
+    pub rollout: bool,
+}
+
+/// Record of the prompt version that served a request
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PromptServeRecord {
+    /// Request identifier, the routing decision ID for completions
+    pub request_id: String,
+    /// Time the prompt was served
+    pub timestamp: DateTime<Utc>,
+    /// Prompt name
+    pub prompt: String,
+    /// Version served
+    pub version: u32,
+    /// Whether the version was selected by a rollout
+    pub rollout: bool,
+    /// Tenant that sent the request
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    pub user: Option<String>,
+}
+
+/// Versions and rollout state of a prompt
+#[derive(Debug)]
+struct PromptEntry {
+    versions: Vec<PromptVersion>,
+    stable: u32,
+    /// Stable versions replaced by promotions, most recent last
+    previous: Vec<u32>,
+    rollout: Option<PromptRollout>,
+}
+
+impl PromptEntry {
+    fn version(&self, id: &str, version: u32) -> Result<&PromptVersion, PromptError> {
+        self.versions
+            .iter()
+            .find(|v| v.version == version)
+            .ok_or_else(|| PromptError::UnknownVersion {
+                prompt: id.to_string(),
+                version,
+            })
+    }
+
+    fn promote(&mut self, version: u32) {
+        if version != self.stable {
+            self.previous.push(self.stable);
+            self.stable = version;
+        }
+        self.rollout = None;
+    }
+}
+
+/// Registry of versioned system prompts
+#[derive(Debug)]
+pub struct PromptRegistry {
+    prompts: RwLock<BTreeMap<String, PromptEntry>>,
+    /// Number of served records kept
+    audit_capacity: usize,
+    /// Most recent served records, oldest first
+    served: Mutex<VecDeque<PromptServeRecord>>,
+}
+
+impl PromptRegistry {
+    /// Create a registry from its configuration
+    pub fn new(config: &PromptRegistryConfig) -> Self {
+        Self {
+            prompts: RwLock::new(BTreeMap::new()),
+            audit_capacity: config.audit_capacity,
+            served: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Publish a new version of a prompt, creating the prompt if needed
+    ///
+    /// The first version of a prompt becomes its stable version; later
+    /// versions are only served once rolled out. Returns the version number.
+    pub fn publish(
+        &self,
+        id: &str,
+        template: String,
+        description: Option<String>,
+        author: Option<String>,
+    ) -> Result<u32, PromptError> {
+        if template.trim().is_empty() {
+            return Err(PromptError::EmptyTemplate);
+        }
+
+        let mut prompts = self.prompts.write().unwrap();
+        let version = prompts
+            .get(id)
+            .map_or(1, |entry| entry.versions.len() as u32 + 1);
+        let published = PromptVersion {
+            version,
+            template,
+            description,
+            author,
+            created_at: Utc::now(),
+        };
+        prompts
+            .entry(id.to_string())
+            .or_insert_with(|| PromptEntry {
+                versions: Vec::new(),
+                stable: version,
+                previous: Vec::new(),
+                rollout: None,
+            })
+            .versions
+            .push(published);
+        Ok(version)
+    }
+
+    /// Serve `version` to `percentage` percent of callers
+    ///
+    /// A rollout to 100 percent promotes the version to stable, and a rollout
+    /// to 0 percent stops the rollout in progress.
+    pub fn rollout(&self, id: &str, version: u32, percentage: u8) -> Result<(), PromptError> {
+        if percentage > 100 {
+            return Err(PromptError::InvalidPercentage(percentage));
+        }
+
+        let mut prompts = self.prompts.write().unwrap();
+        let entry = prompts
+            .get_mut(id)
+            .ok_or_else(|| PromptError::UnknownPrompt(id.to_string()))?;
+        entry.version(id, version)?;
+
+        match percentage {
+            100 => entry.promote(version),
+            0 => entry.rollout = None,
+            _ if version == entry.stable => entry.rollout = None,
+            _ => {
+                entry.rollout = Some(PromptRollout {
+                    version,
+                    percentage,
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Roll a prompt back
+    ///
+    /// With a version, that version becomes stable and any rollout stops.
+    /// Without one, the rollout in progress stops, or when there is none the
+    /// stable version before the last promotion is restored. Returns the
+    /// stable version afterwards.
+    pub fn rollback(&self, id: &str, version: Option<u32>) -> Result<u32, PromptError> {
+        let mut prompts = self.prompts.write().unwrap();
+        let entry = prompts
+            .get_mut(id)
+            .ok_or_else(|| PromptError::UnknownPrompt(id.to_string()))?;
+
+        match version {
+            Some(version) => {
+                entry.version(id, version)?;
+                entry.promote(version);
+            }
+            None if entry.rollout.is_some() => entry.rollout = None,
+            None => {
+                entry.stable = entry
+                    .previous
+                    .pop()
+                    .ok_or_else(|| PromptError::NothingToRollBack(id.to_string()))?;
+            }
+        }
+        Ok(entry.stable)
+    }
+
+    /// Select the version of a prompt served to the caller identified by `key`
+    pub fn resolve(&self, id: &str, key: &str) -> Result<ResolvedPrompt, PromptError> {
+        let prompts = self.prompts.read().unwrap();
+        let entry = prompts
+            .get(id)
+            .ok_or_else(|| PromptError::UnknownPrompt(id.to_string()))?;
+
+        let (version, rollout) = match entry.rollout {
+            Some(rollout) if bucket(id, key) < rollout.percentage => (rollout.version, true),
+            _ => (entry.stable, false),
+        };
+        Ok(ResolvedPrompt {
+            id: id.to_string(),
+            version,
+            template: entry.version(id, version)?.template.clone(),
+            rollout,
+        })
+    }
+
+    /// Summaries of all prompts, by name
+    pub fn list(&self) -> Vec<PromptInfo> {
+        self.prompts
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(id, entry)| PromptInfo {
+                id: id.clone(),
+                stable: entry.stable,
+                rollout: entry.rollout,
+                versions: entry.versions.clone(),
+            })
+            .collect()
+    }
+
+    /// Summary of a prompt
+    pub fn get(&self, id: &str) -> Result<PromptInfo, PromptError> {
+        self.list()
+            .into_iter()
+            .find(|info| info.id == id)
+            .ok_or_else(|| PromptError::UnknownPrompt(id.to_string()))
+    }
+
+    /// Record the prompt version that served a request
+    pub fn record_served(&self, record: PromptServeRecord) {
+        if self.audit_capacity == 0 {
+            return;
+        }
+        let mut served = self.served.lock().unwrap();
+        if served.len() >= self.audit_capacity {
+            served.pop_front();
+        }
+        served.push_back(record);
+    }
+
+    /// Recent served records, oldest first, optionally of one prompt
+    pub fn served(&self, prompt: Option<&str>) -> Vec<PromptServeRecord> {
+        self.served
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|record| prompt.is_none_or(|prompt| record.prompt == prompt))
+            .cloned()
+            .collect()
+    }
+
+    /// Erase a data subject from the served records
+    ///
+    /// A tenant's records are dropped; a user's are kept with the user
+    /// replaced by `pseudonym`. Returns how many records were dropped and
+    /// how many were anonymized.
+    pub fn erase_served(&self, subject: &ErasureSubject, pseudonym: &str) -> (usize, usize) {
+        let mut served = self.served.lock().unwrap();
+        let before = served.len();
+        if subject.user.is_none() {
+            served.retain(|record| !subject.matches(record.tenant.as_deref(), None));
+            return (before - served.len(), 0);
+        }
+
+        let mut anonymized = 0;
+        for record in served.iter_mut() {
+            if subject.matches(record.tenant.as_deref(), record.user.as_deref()) {
+                record.user = Some(pseudonym.to_string());
+                anonymized += 1;
+            }
+        }
+        (0, anonymized)
+    }
+}
+
+impl Default for PromptRegistry {
+    fn default() -> Self {
+        Self::new(&PromptRegistryConfig::default())
+    }
+}
+
+/// Rollout bucket of a caller for a prompt, between 0 and 99
+fn bucket(id: &str, key: &str) -> u8 {
+    let hash = digest::digest(&digest::SHA256, format!("{}:{}", id, key).as_bytes());
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash.as_ref()[..8]);
+    (u64::from_be_bytes(bytes) % 100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> PromptRegistry {
+        let registry = PromptRegistry::default();
+        registry
+            .publish("support", "You are helpful.".to_string(), None, None)
+            .unwrap();
+        registry
+            .publish(
+                "support",
+                "You are concise.".to_string(),
+                Some("Shorter answers".to_string()),
+                Some("alice".to_string()),
+            )
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_rollout_splits_callers_stickily() {
+        let registry = registry();
+        assert_eq!(registry.resolve("support", "user-1").unwrap().version, 1);
+
+        registry.rollout("support", 2, 30).unwrap();
+        let canary = (0..1000)
+            .filter(|i| {
+                registry
+                    .resolve("support", &format!("user-{}", i))
+                    .unwrap()
+                    .rollout
+            })
+            .count();
+        assert!((200..400).contains(&canary), "canary share {}", canary);
+
+        let first = registry.resolve("support", "user-7").unwrap();
+        for _ in 0..10 {
+            assert_eq!(registry.resolve("support", "user-7").unwrap(), first);
+        }
+
+        assert_eq!(
+            registry.rollout("support", 3, 10),
+            Err(PromptError::UnknownVersion {
+                prompt: "support".to_string(),
+                version: 3
+            })
+        );
+        assert_eq!(
+            registry.rollout("support", 2, 101),
+            Err(PromptError::InvalidPercentage(101))
+        );
+    }
+
+    #[test]
+    fn test_promote_and_roll_back() {
+        let registry = registry();
+        registry.rollout("support", 2, 50).unwrap();
+        // Rolling back a rollout keeps the stable version
+        assert_eq!(registry.rollback("support", None), Ok(1));
+        assert!(registry.get("support").unwrap().rollout.is_none());
+
+        registry.rollout("support", 2, 100).unwrap();
+        let resolved = registry.resolve("support", "anyone").unwrap();
+        assert_eq!((resolved.version, resolved.rollout), (2, false));
+        assert_eq!(resolved.template, "You are concise.");
+
+        // Without a rollout, the promotion is undone
+        assert_eq!(registry.rollback("support", None), Ok(1));
+        assert_eq!(
+            registry.rollback("support", None),
+            Err(PromptError::NothingToRollBack("support".to_string()))
+        );
+        assert_eq!(registry.rollback("support", Some(2)), Ok(2));
+        assert_eq!(
+            registry.resolve("missing", "anyone"),
+            Err(PromptError::UnknownPrompt("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_served_records_are_bounded() {
+        let registry = PromptRegistry::new(&PromptRegistryConfig { audit_capacity: 2 });
+        for (i, prompt) in ["a", "b", "a"].iter().enumerate() {
+            registry.record_served(PromptServeRecord {
+                request_id: i.to_string(),
+                timestamp: Utc::now(),
+                prompt: prompt.to_string(),
+                version: 1,
+                rollout: false,
+                tenant: None,
+                user: None,
+            });
+        }
+        let ids: Vec<String> = registry
+            .served(None)
+            .into_iter()
+            .map(|r| r.request_id)
+            .collect();
+        assert_eq!(ids, vec!["1", "2"]);
+        assert_eq!(registry.served(Some("a")).len(), 1);
+    }
+}
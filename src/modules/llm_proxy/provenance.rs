@@ -0,0 +1,260 @@
+//! Response Metadata
+//!
+//! This module describes how a request was routed, so client teams can debug
+//! routing without access to the server. When enabled, responses carry the
+//! provider and model that served them, whether the response came from a
+//! cache, the RAG collections and persona applied, and where the time went,
+//! as `x-intellirouter-*` headers and as a `metadata` field of the response
+//! body. Warnings, such as the deprecation of the
+//! serving model, come with them, and so does the grounding check of answers
+//! generated from RAG context.
+//!
+//! Streamed responses only get the headers known before their first event:
+//! their body is a stream of chunks and they have no latency to report yet.
+
+use axum::http::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+use super::decision_log::RoutingDecision;
+use crate::config::ResponseMetadataConfig;
+use crate::modules::model_registry::ResolutionKind;
+use crate::modules::rag_manager::GroundingResult;
+use crate::modules::telemetry::CacheStatus;
+
+/// Response header naming the provider that served the request
+pub const PROVIDER_HEADER: &str = "x-intellirouter-provider";
+
+/// Response header naming the model that served the request
+pub const MODEL_HEADER: &str = "x-intellirouter-model";
+
+/// Response header telling whether the response came from a cache
+pub const CACHE_HEADER: &str = "x-intellirouter-cache";
+
+/// Response header listing the RAG collections the request retrieved from
+pub const RAG_COLLECTIONS_HEADER: &str = "x-intellirouter-rag-collections";
+
+/// Response header naming the persona applied to the request
+pub const PERSONA_HEADER: &str = "x-intellirouter-persona";
+
+/// Response header telling whether the answer is supported by the retrieved
+/// RAG context, e.g. `low-confidence; score=0.31`
+pub const GROUNDING_HEADER: &str = "x-intellirouter-grounding";
+
+/// Response header carrying the latency breakdown of the request
+pub const LATENCY_HEADER: &str = "x-intellirouter-latency";
+
+/// Response header carrying warnings about the request, e.g. that its model
+/// is deprecated
+pub const WARNING_HEADER: &str = "x-intellirouter-warning";
+
+/// Where the time of a request went, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBreakdown {
+    /// Time from receiving the request to sending the response
+    pub total_ms: u64,
+    /// Time waiting for the provider, or for the identical request in flight
+    /// whose response was shared
+    pub upstream_ms: u64,
+    /// Time spent in the router itself
+    pub overhead_ms: u64,
+}
+
+impl LatencyBreakdown {
+    /// Split the total latency of a request around its upstream time
+    pub fn new(total_ms: u64, upstream_ms: u64) -> Self {
+        let upstream_ms = upstream_ms.min(total_ms);
+        Self {
+            total_ms,
+            upstream_ms,
+            overhead_ms: total_ms - upstream_ms,
+        }
+    }
+
+    /// Value of the latency header, e.g. `total=120, upstream=100, overhead=20`
+    pub fn header_value(&self) -> String {
+        format!(
+            "total={}, upstream={}, overhead={}",
+            self.total_ms, self.upstream_ms, self.overhead_ms
+        )
+    }
+}
+
+/// How a request was routed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResponseMetadata {
+    /// Provider that served the request
+    pub provider: String,
+    /// Model that served the request
+    pub model: String,
+    /// Model requested by the client
+    pub requested_model: String,
+    /// How the requested model was resolved, when it was resolved to
+    /// another model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_resolution: Option<ResolutionKind>,
+    /// Whether the response came from a cache
+    pub cache_status: CacheStatus,
+    /// RAG collections the request retrieved context from
+    pub rag_collections: Vec<String>,
+    /// Persona applied to the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub persona: Option<String>,
+    /// Grounding check of an answer generated from RAG context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grounding: Option<GroundingResult>,
+    /// Where the time went, once the response is complete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency: Option<LatencyBreakdown>,
+    /// Warnings about the request, e.g. that its model is deprecated
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl ResponseMetadata {
+    /// Metadata of the request a routing decision was made for
+    ///
+    /// `upstream_ms` is the time the request waited for its response, if
+    /// known.
+    pub fn from_decision(decision: &RoutingDecision, upstream_ms: Option<u64>) -> Self {
+        Self {
+            provider: decision.provider.clone(),
+            model: decision.model.clone(),
+            requested_model: decision.requested_model.clone(),
+            model_resolution: decision.model_resolution,
+            // A coalesced request is served from the response of another
+            cache_status: if decision.coalesced {
+                CacheStatus::Hit
+            } else {
+                CacheStatus::Bypass
+            },
+            rag_collections: Vec::new(),
+            persona: None,
+            grounding: None,
+            latency: upstream_ms
+                .map(|upstream_ms| LatencyBreakdown::new(decision.latency_ms, upstream_ms)),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Set the RAG collections the request retrieved context from
+    pub fn with_rag_collections(mut self, collections: Vec<String>) -> Self {
+        self.rag_collections = collections;
+        self
+    }
+
+    /// Set the persona applied to the request
+    pub fn with_persona(mut self, persona: impl Into<String>) -> Self {
+        self.persona = Some(persona.into());
+        self
+    }
+
+    /// Set the grounding check of the answer
+    pub fn with_grounding(mut self, grounding: GroundingResult) -> Self {
+        self.grounding = Some(grounding);
+        self
+    }
+
+    /// Add a warning about the request
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.warnings.push(warning.into());
+        self
+    }
+
+    /// Add the metadata headers to a response's headers
+    ///
+    /// Empty lists and unknown values are left out. Values that are not
+    /// valid header values are skipped.
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        let cache = match self.cache_status {
+            CacheStatus::Hit => "hit",
+            CacheStatus::Miss => "miss",
+            CacheStatus::Bypass => "bypass",
+        };
+        let values = [
+            (PROVIDER_HEADER, Some(self.provider.clone())),
+            (MODEL_HEADER, Some(self.model.clone())),
+            (CACHE_HEADER, Some(cache.to_string())),
+            (RAG_COLLECTIONS_HEADER, join(&self.rag_collections)),
+            (PERSONA_HEADER, self.persona.clone()),
+            (
+                GROUNDING_HEADER,
+                self.grounding.as_ref().map(GroundingResult::header_value),
+            ),
+            (
+                LATENCY_HEADER,
+                self.latency.map(|latency| latency.header_value()),
+            ),
+        ];
+        for (name, value) in values {
+            let Some(value) = value.and_then(|value| HeaderValue::from_str(&value).ok()) else {
+                continue;
+            };
+            headers.insert(HeaderName::from_static(name), value);
+        }
+        for warning in &self.warnings {
+            if let Ok(value) = HeaderValue::from_str(warning) {
+                headers.append(WARNING_HEADER, value);
+            }
+        }
+    }
+}
+
+/// Comma-separated list of names, unless empty
+fn join(names: &[String]) -> Option<String> {
+    (!names.is_empty()).then(|| names.join(", "))
+}
+
+/// Whether responses carry metadata headers
+pub fn headers_enabled(config: &ResponseMetadataConfig) -> bool {
+    config.enabled && config.headers
+}
+
+/// Whether response bodies carry a `metadata` field
+pub fn body_enabled(config: &ResponseMetadataConfig) -> bool {
+    config.enabled && config.body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_breakdown() {
+        let latency = LatencyBreakdown::new(120, 100);
+        assert_eq!(latency.overhead_ms, 20);
+        assert_eq!(
+            latency.header_value(),
+            "total=120, upstream=100, overhead=20"
+        );
+
+        // Upstream time measured past the total is capped to it
+        assert_eq!(LatencyBreakdown::new(10, 15).overhead_ms, 0);
+    }
+
+    #[test]
+    fn test_metadata_headers() {
+        let mut decision = RoutingDecision::new("fast", "gpt-4o-mini");
+        decision.provider = "openai".to_string();
+        decision.coalesced = true;
+        decision.latency_ms = 250;
+        let metadata = ResponseMetadata::from_decision(&decision, Some(200)).with_persona("tutor");
+
+        let mut headers = HeaderMap::new();
+        metadata.apply_headers(&mut headers);
+        assert_eq!(headers[PROVIDER_HEADER], "openai");
+        assert_eq!(headers[MODEL_HEADER], "gpt-4o-mini");
+        assert_eq!(headers[CACHE_HEADER], "hit");
+        assert_eq!(headers[PERSONA_HEADER], "tutor");
+        assert_eq!(
+            headers[LATENCY_HEADER],
+            "total=250, upstream=200, overhead=50"
+        );
+        assert!(!headers.contains_key(RAG_COLLECTIONS_HEADER));
+        assert!(!headers.contains_key(GROUNDING_HEADER));
+
+        let body = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(body["requested_model"], "fast");
+        assert_eq!(body["cache_status"], "hit");
+        assert_eq!(body["latency"]["upstream_ms"], 200);
+    }
+}
@@ -0,0 +1,306 @@
+//! Provider Message Quirks
+//!
+//! This module normalizes the messages of a provider request to what the
+//! provider accepts. Some providers reject more than one system message,
+//! system messages after the conversation has started, consecutive messages
+//! of the same role or messages without content, all of which are valid for
+//! OpenAI. Each provider has built-in rules, which can be overridden per
+//! provider in the `[proxy.message_quirks]` section.
+
+use std::collections::HashMap;
+
+use tracing::trace;
+
+use crate::config::MessageQuirksConfig;
+use crate::modules::model_registry::connectors::{ChatCompletionRequest, ChatMessage, MessageRole};
+
+/// Message rules of a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MessageQuirks {
+    /// Merge all system messages into one leading system message
+    pub merge_system_messages: bool,
+    /// Move the system prompt into the first user message
+    pub system_to_user: bool,
+    /// Merge consecutive user or assistant messages so roles alternate
+    pub alternate_roles: bool,
+    /// Drop messages without content
+    pub strip_empty_messages: bool,
+}
+
+impl MessageQuirks {
+    /// Built-in rules for a provider name
+    ///
+    /// Unknown providers are assumed to be OpenAI-compatible.
+    pub fn for_provider(provider: &str) -> Self {
+        match provider.to_lowercase().as_str() {
+            "anthropic" => Self {
+                merge_system_messages: true,
+                system_to_user: false,
+                alternate_roles: true,
+                strip_empty_messages: true,
+            },
+            "mistral" => Self {
+                merge_system_messages: true,
+                system_to_user: false,
+                alternate_roles: false,
+                strip_empty_messages: true,
+            },
+            "google" | "gemini" => Self {
+                merge_system_messages: true,
+                system_to_user: false,
+                alternate_roles: true,
+                strip_empty_messages: true,
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// Whether no rule is enabled
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Names of the enabled rules, as configured
+    pub fn enabled(&self) -> Vec<&'static str> {
+        [
+            ("merge_system_messages", self.merge_system_messages),
+            ("system_to_user", self.system_to_user),
+            ("alternate_roles", self.alternate_roles),
+            ("strip_empty_messages", self.strip_empty_messages),
+        ]
+        .into_iter()
+        .filter_map(|(name, enabled)| enabled.then_some(name))
+        .collect()
+    }
+
+    /// Apply configured overrides to these rules
+    fn with_overrides(self, config: &MessageQuirksConfig) -> Self {
+        Self {
+            merge_system_messages: config
+                .merge_system_messages
+                .unwrap_or(self.merge_system_messages),
+            system_to_user: config.system_to_user.unwrap_or(self.system_to_user),
+            alternate_roles: config.alternate_roles.unwrap_or(self.alternate_roles),
+            strip_empty_messages: config
+                .strip_empty_messages
+                .unwrap_or(self.strip_empty_messages),
+        }
+    }
+}
+
+/// Normalizes provider requests to the message rules of their provider
+#[derive(Debug, Clone, Default)]
+pub struct MessageNormalizer {
+    /// Configured overrides by provider name
+    overrides: HashMap<String, MessageQuirksConfig>,
+}
+
+impl MessageNormalizer {
+    /// Create a normalizer with per-provider overrides
+    pub fn new(overrides: HashMap<String, MessageQuirksConfig>) -> Self {
+        Self { overrides }
+    }
+
+    /// Rules of a provider, with its configured overrides
+    pub fn quirks_for(&self, provider: &str) -> MessageQuirks {
+        let quirks = MessageQuirks::for_provider(provider);
+        self.overrides
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(provider))
+            .map(|(_, config)| quirks.with_overrides(config))
+            .unwrap_or(quirks)
+    }
+
+    /// Normalize the messages of a request for a provider
+    ///
+    /// Returns the rules that were applied.
+    pub fn apply(&self, provider: &str, request: &mut ChatCompletionRequest) -> MessageQuirks {
+        let quirks = self.quirks_for(provider);
+        normalize_messages(&mut request.messages, quirks);
+        trace!(
+            provider = provider,
+            model = %request.model,
+            "quirks: normalized messages with {:?}",
+            quirks
+        );
+        quirks
+    }
+}
+
+/// Normalize messages to a set of rules
+pub fn normalize_messages(messages: &mut Vec<ChatMessage>, quirks: MessageQuirks) {
+    if quirks.strip_empty_messages {
+        messages.retain(|m| !is_empty(m));
+    }
+
+    if quirks.merge_system_messages || quirks.system_to_user {
+        let (system, mut rest): (Vec<ChatMessage>, Vec<ChatMessage>) = messages
+            .drain(..)
+            .partition(|m| m.role == MessageRole::System);
+        let prompt = join(system.iter().map(|m| m.content.as_str()));
+
+        match prompt {
+            Some(prompt) if quirks.system_to_user => {
+                match rest.iter_mut().find(|m| m.role == MessageRole::User) {
+                    Some(user) => user.content = format!("{}\n\n{}", prompt, user.content),
+                    None => rest.insert(0, message(MessageRole::User, prompt)),
+                }
+            }
+            Some(prompt) => rest.insert(0, message(MessageRole::System, prompt)),
+            None => {}
+        }
+        *messages = rest;
+    }
+
+    if quirks.alternate_roles {
+        let mut merged: Vec<ChatMessage> = Vec::with_capacity(messages.len());
+        for m in messages.drain(..) {
+            match merged.last_mut() {
+                Some(last) if mergeable(last, &m) => {
+                    last.content = format!("{}\n\n{}", last.content, m.content);
+                }
+                _ => merged.push(m),
+            }
+        }
+        *messages = merged;
+    }
+}
+
+/// Whether a message has no content
+///
+/// Assistant messages carrying tool or function calls are never empty.
+fn is_empty(message: &ChatMessage) -> bool {
+    message.content.trim().is_empty()
+        && message.function_call.is_none()
+        && message.tool_calls.as_ref().is_none_or(Vec::is_empty)
+}
+
+/// Whether a message can be merged into the one before it
+///
+/// Only plain user or assistant text is merged; tool traffic keeps its shape.
+fn mergeable(previous: &ChatMessage, next: &ChatMessage) -> bool {
+    let plain = |m: &ChatMessage| {
+        matches!(m.role, MessageRole::User | MessageRole::Assistant)
+            && m.function_call.is_none()
+            && m.tool_calls.is_none()
+            && m.tool_call_id.is_none()
+    };
+    previous.role == next.role && plain(previous) && plain(next)
+}
+
+/// Join the non-empty texts of messages
+fn join<'a>(contents: impl Iterator<Item = &'a str>) -> Option<String> {
+    let parts: Vec<&str> = contents.filter(|c| !c.trim().is_empty()).collect();
+    (!parts.is_empty()).then(|| parts.join("\n\n"))
+}
+
+fn message(role: MessageRole, content: String) -> ChatMessage {
+    ChatMessage {
+        role,
+        content,
+        name: None,
+        function_call: None,
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat(roles: &[(MessageRole, &str)]) -> Vec<ChatMessage> {
+        roles
+            .iter()
+            .map(|(role, content)| message(role.clone(), content.to_string()))
+            .collect()
+    }
+
+    fn shape(messages: &[ChatMessage]) -> Vec<(MessageRole, &str)> {
+        messages
+            .iter()
+            .map(|m| (m.role.clone(), m.content.as_str()))
+            .collect()
+    }
+
+    fn conversation() -> Vec<ChatMessage> {
+        chat(&[
+            (MessageRole::System, "Be brief."),
+            (MessageRole::User, "Hi"),
+            (MessageRole::User, ""),
+            (MessageRole::System, "Answer in French."),
+            (MessageRole::User, "Where is my order?"),
+            (MessageRole::Assistant, "Il est en route."),
+        ])
+    }
+
+    #[test]
+    fn test_anthropic_rules() {
+        let mut messages = conversation();
+        normalize_messages(&mut messages, MessageQuirks::for_provider("anthropic"));
+        assert_eq!(
+            shape(&messages),
+            vec![
+                (MessageRole::System, "Be brief.\n\nAnswer in French."),
+                (MessageRole::User, "Hi\n\nWhere is my order?"),
+                (MessageRole::Assistant, "Il est en route."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_openai_untouched() {
+        let mut messages = conversation();
+        normalize_messages(&mut messages, MessageQuirks::for_provider("openai"));
+        assert_eq!(messages.len(), 6);
+        assert!(MessageQuirks::for_provider("openai").is_empty());
+    }
+
+    #[test]
+    fn test_system_to_user_override() {
+        let normalizer = MessageNormalizer::new(HashMap::from([(
+            "Mistral".to_string(),
+            MessageQuirksConfig {
+                system_to_user: Some(true),
+                ..Default::default()
+            },
+        )]));
+        let quirks = normalizer.quirks_for("mistral");
+        assert_eq!(
+            quirks.enabled(),
+            vec![
+                "merge_system_messages",
+                "system_to_user",
+                "strip_empty_messages"
+            ]
+        );
+
+        let mut messages = conversation();
+        normalize_messages(&mut messages, quirks);
+        assert_eq!(
+            shape(&messages),
+            vec![
+                (MessageRole::User, "Be brief.\n\nAnswer in French.\n\nHi"),
+                (MessageRole::User, "Where is my order?"),
+                (MessageRole::Assistant, "Il est en route."),
+            ]
+        );
+
+        let mut system_only = chat(&[(MessageRole::System, "Be brief.")]);
+        normalize_messages(&mut system_only, quirks);
+        assert_eq!(shape(&system_only), vec![(MessageRole::User, "Be brief.")]);
+    }
+
+    #[test]
+    fn test_tool_messages_not_merged() {
+        let mut messages = chat(&[
+            (MessageRole::User, "Weather?"),
+            (MessageRole::Tool, "sunny"),
+            (MessageRole::Tool, "warm"),
+        ]);
+        messages[1].tool_call_id = Some("call_1".to_string());
+        messages[2].tool_call_id = Some("call_2".to_string());
+        normalize_messages(&mut messages, MessageQuirks::for_provider("anthropic"));
+        assert_eq!(messages.len(), 3);
+    }
+}
@@ -0,0 +1,737 @@
+//! Per-tenant Token Quotas
+//!
+//! This module enforces prompt and completion token quotas per tenant over a
+//! sliding window. Usage is kept in a [`QuotaStore`], either in memory, in
+//! Redis or in the SQLite file of the embedded storage profile, and the
+//! remaining budget is reported on every response through the
+//! `x-ratelimit-*-tokens` headers. Tenants can also limit each of their end
+//! users, identified by the `user` field of requests, under the tenant's own
+//! quota.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use super::dto::{ApiError, ApiErrorDetail};
+use super::server::AppState;
+use super::tenant;
+use crate::config::{Config, MemoryConfig, TenantConfig, TokenQuotaConfig};
+use crate::modules::common::{RedisConnector, TrafficClass};
+
+/// Header carrying the token limit of the most constrained quota
+pub const LIMIT_TOKENS_HEADER: &str = "x-ratelimit-limit-tokens";
+/// Header carrying the tokens remaining in the current window
+pub const REMAINING_TOKENS_HEADER: &str = "x-ratelimit-remaining-tokens";
+/// Header carrying the time until the window frees up tokens
+pub const RESET_TOKENS_HEADER: &str = "x-ratelimit-reset-tokens";
+
+/// Errors raised by quota stores
+#[derive(Debug, Error)]
+pub enum QuotaError {
+    /// The backing store failed
+    #[error("Quota storage error: {0}")]
+    StorageError(String),
+}
+
+/// Token usage within a window
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowUsage {
+    /// Tokens used within the window
+    pub used: u64,
+    /// Timestamp (ms since epoch) of the oldest entry within the window
+    pub oldest_ms: Option<u64>,
+}
+
+/// Storage for sliding-window token usage
+#[async_trait]
+pub trait QuotaStore: Send + Sync {
+    /// Get the usage recorded under a key within the window
+    async fn usage(&self, key: &str, window: Duration) -> Result<WindowUsage, QuotaError>;
+
+    /// Record token usage under a key
+    async fn record(&self, key: &str, tokens: u64, window: Duration) -> Result<(), QuotaError>;
+
+    /// Clear the usage recorded under a key
+    async fn reset(&self, key: &str) -> Result<(), QuotaError>;
+}
+
+/// In-memory quota store, local to a single process
+#[derive(Debug, Default)]
+pub struct InMemoryQuotaStore {
+    entries: Mutex<HashMap<String, VecDeque<(u64, u64)>>>,
+}
+
+impl InMemoryQuotaStore {
+    /// Create a new in-memory quota store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl QuotaStore for InMemoryQuotaStore {
+    async fn usage(&self, key: &str, window: Duration) -> Result<WindowUsage, QuotaError> {
+        let cutoff = now_ms().saturating_sub(window.as_millis() as u64);
+        let mut entries = self.entries.lock().await;
+
+        let Some(log) = entries.get_mut(key) else {
+            return Ok(WindowUsage::default());
+        };
+        while log.front().is_some_and(|(ts, _)| *ts <= cutoff) {
+            log.pop_front();
+        }
+
+        Ok(WindowUsage {
+            used: log.iter().map(|(_, tokens)| tokens).sum(),
+            oldest_ms: log.front().map(|(ts, _)| *ts),
+        })
+    }
+
+    async fn record(&self, key: &str, tokens: u64, _window: Duration) -> Result<(), QuotaError> {
+        self.entries
+            .lock()
+            .await
+            .entry(key.to_string())
+            .or_default()
+            .push_back((now_ms(), tokens));
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> Result<(), QuotaError> {
+        self.entries.lock().await.remove(key);
+        Ok(())
+    }
+}
+
+/// Redis quota store, shared between router instances
+///
+/// Each key is a sorted set of `<id>:<tokens>` members scored by timestamp.
+pub struct RedisQuotaStore {
+    redis: RedisConnector,
+    prefix: String,
+}
+
+impl RedisQuotaStore {
+    /// Create a new Redis quota store
+    pub fn new(redis_url: &str, prefix: &str) -> Result<Self, QuotaError> {
+        let redis = RedisConnector::from_url(redis_url)
+            .map_err(|e| QuotaError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        Ok(Self::from_connector(redis, prefix))
+    }
+
+    /// Create a Redis quota store connecting through a connector
+    pub fn from_connector(redis: RedisConnector, prefix: &str) -> Self {
+        Self {
+            redis,
+            prefix: prefix.to_string(),
+        }
+    }
+
+    /// Generate a Redis key with the configured prefix
+    fn get_key(&self, key: &str) -> String {
+        format!("{}:{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl QuotaStore for RedisQuotaStore {
+    async fn usage(&self, key: &str, window: Duration) -> Result<WindowUsage, QuotaError> {
+        let mut conn = self
+            .redis
+            .connection()
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        let key = self.get_key(key);
+        let cutoff = now_ms().saturating_sub(window.as_millis() as u64);
+        let (members,): (Vec<(String, u64)>,) = redis::pipe()
+            .atomic()
+            .cmd("ZREMRANGEBYSCORE")
+            .arg(&key)
+            .arg("-inf")
+            .arg(cutoff)
+            .ignore()
+            .cmd("ZRANGEBYSCORE")
+            .arg(&key)
+            .arg(format!("({}", cutoff))
+            .arg("+inf")
+            .arg("WITHSCORES")
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis error: {}", e)))?;
+
+        Ok(WindowUsage {
+            used: members
+                .iter()
+                .filter_map(|(member, _)| member.rsplit(':').next()?.parse::<u64>().ok())
+                .sum(),
+            oldest_ms: members.first().map(|(_, ts)| *ts),
+        })
+    }
+
+    async fn record(&self, key: &str, tokens: u64, window: Duration) -> Result<(), QuotaError> {
+        let mut conn = self
+            .redis
+            .connection()
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        let key = self.get_key(key);
+        redis::pipe()
+            .atomic()
+            .cmd("ZADD")
+            .arg(&key)
+            .arg(now_ms())
+            .arg(format!("{}:{}", Uuid::new_v4(), tokens))
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&key)
+            .arg(window.as_secs().max(1))
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis error: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> Result<(), QuotaError> {
+        let mut conn = self
+            .redis
+            .connection()
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis connection error: {}", e)))?;
+
+        redis::cmd("DEL")
+            .arg(self.get_key(key))
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| QuotaError::StorageError(format!("Redis error: {}", e)))
+    }
+}
+
+/// SQLite quota store of the embedded storage profile
+#[derive(Clone)]
+pub struct SqliteQuotaStore {
+    db: crate::modules::common::SqliteDatabase,
+}
+
+impl SqliteQuotaStore {
+    /// Create a SQLite quota store, creating its table if needed
+    pub fn new(db: crate::modules::common::SqliteDatabase) -> Result<Self, QuotaError> {
+        db.with(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS quota_usage (
+                    key TEXT NOT NULL,
+                    ts_ms INTEGER NOT NULL,
+                    tokens INTEGER NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS quota_usage_key_ts ON quota_usage (key, ts_ms);",
+            )
+        })
+        .map_err(sqlite_error)?;
+        Ok(Self { db })
+    }
+}
+
+fn sqlite_error(e: crate::modules::common::SqliteError) -> QuotaError {
+    QuotaError::StorageError(format!("SQLite error: {}", e))
+}
+
+#[async_trait]
+impl QuotaStore for SqliteQuotaStore {
+    async fn usage(&self, key: &str, window: Duration) -> Result<WindowUsage, QuotaError> {
+        let key = key.to_string();
+        let cutoff = now_ms().saturating_sub(window.as_millis() as u64) as i64;
+        let rows = self
+            .db
+            .call(move |conn| {
+                conn.execute(
+                    "DELETE FROM quota_usage WHERE key = ?1 AND ts_ms <= ?2",
+                    &[key.as_str().into(), cutoff.into()],
+                )?;
+                conn.query(
+                    "SELECT COALESCE(SUM(tokens), 0), MIN(ts_ms) FROM quota_usage WHERE key = ?1",
+                    &[key.into()],
+                )
+            })
+            .await
+            .map_err(sqlite_error)?;
+
+        let row = rows.into_iter().next().unwrap_or_default();
+        Ok(WindowUsage {
+            used: row.first().and_then(|v| v.as_i64()).unwrap_or(0) as u64,
+            oldest_ms: row.get(1).and_then(|v| v.as_i64()).map(|ts| ts as u64),
+        })
+    }
+
+    async fn record(&self, key: &str, tokens: u64, _window: Duration) -> Result<(), QuotaError> {
+        let key = key.to_string();
+        let ts = now_ms() as i64;
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO quota_usage (key, ts_ms, tokens) VALUES (?1, ?2, ?3)",
+                    &[key.into(), ts.into(), (tokens as i64).into()],
+                )
+            })
+            .await
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> Result<(), QuotaError> {
+        let key = key.to_string();
+        self.db
+            .call(move |conn| conn.execute("DELETE FROM quota_usage WHERE key = ?1", &[key.into()]))
+            .await
+            .map_err(sqlite_error)?;
+        Ok(())
+    }
+}
+
+/// Remaining budget of a tenant's most constrained quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaStatus {
+    /// Token limit of the quota
+    pub limit: u64,
+    /// Tokens remaining in the current window
+    pub remaining: u64,
+    /// Time until the oldest usage leaves the window
+    pub reset: Duration,
+}
+
+impl QuotaStatus {
+    /// Check whether the quota is used up
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Add the quota headers to a response
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(LIMIT_TOKENS_HEADER, HeaderValue::from(self.limit));
+        headers.insert(REMAINING_TOKENS_HEADER, HeaderValue::from(self.remaining));
+        if let Ok(value) = HeaderValue::from_str(&format!("{}s", self.reset.as_secs())) {
+            headers.insert(RESET_TOKENS_HEADER, value);
+        }
+    }
+
+    /// Build the `429` response rejecting a request over this quota
+    pub fn exceeded_response(&self, message: String) -> Response {
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ApiError {
+                error: ApiErrorDetail {
+                    message,
+                    r#type: "rate_limit_error".to_string(),
+                    param: None,
+                    code: Some("rate_limit_exceeded".to_string()),
+                },
+            }),
+        )
+            .into_response();
+        self.apply_headers(response.headers_mut());
+        response
+    }
+}
+
+/// Tracks and enforces per-tenant token quotas
+pub struct TokenQuotaManager {
+    store: Arc<dyn QuotaStore>,
+}
+
+impl std::fmt::Debug for TokenQuotaManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenQuotaManager").finish_non_exhaustive()
+    }
+}
+
+impl Default for TokenQuotaManager {
+    fn default() -> Self {
+        Self::new(Arc::new(InMemoryQuotaStore::new()))
+    }
+}
+
+impl TokenQuotaManager {
+    /// Create a new quota manager backed by a store
+    pub fn new(store: Arc<dyn QuotaStore>) -> Self {
+        Self { store }
+    }
+
+    /// Create a quota manager backed by Redis if a URL is given, otherwise in memory
+    pub fn from_redis_url(redis_url: Option<&str>) -> Self {
+        match redis_url.map(|url| RedisQuotaStore::new(url, "intellirouter:quota")) {
+            Some(Ok(store)) => Self::new(Arc::new(store)),
+            Some(Err(e)) => {
+                warn!("Falling back to in-memory token quotas: {}", e);
+                Self::default()
+            }
+            None => Self::default(),
+        }
+    }
+
+    /// Create the quota manager of the deployment's storage
+    ///
+    /// The embedded storage profile keeps usage in its SQLite file, other
+    /// deployments fall back to [`TokenQuotaManager::from_memory_config`].
+    pub fn from_config(config: &Config) -> Self {
+        if config.storage.is_embedded() {
+            let store = crate::modules::common::SqliteDatabase::from_config(&config.storage)
+                .map_err(sqlite_error)
+                .and_then(SqliteQuotaStore::new);
+            match store {
+                Ok(store) => return Self::new(Arc::new(store)),
+                Err(e) => warn!("Falling back to in-memory token quotas: {}", e),
+            }
+        }
+        Self::from_memory_config(&config.memory)
+    }
+
+    /// Create a quota manager backed by the deployment's Redis if one is
+    /// configured, otherwise in memory
+    ///
+    /// An invalid Redis configuration panics rather than falling back to
+    /// quotas kept by each instance.
+    pub fn from_memory_config(config: &MemoryConfig) -> Self {
+        match RedisConnector::from_config(config).expect("Invalid Redis configuration") {
+            Some(redis) => Self::new(Arc::new(RedisQuotaStore::from_connector(
+                redis,
+                "intellirouter:quota",
+            ))),
+            None => Self::default(),
+        }
+    }
+
+    /// Get the status of a tenant's most constrained quota
+    ///
+    /// Returns `None` when the tenant has no quota, or when the store fails
+    /// (quotas fail open).
+    pub async fn status(&self, tenant: &TenantConfig) -> Option<QuotaStatus> {
+        let quota = tenant.token_quota.as_ref()?;
+        match self.compute_status(&tenant.id, quota).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!(
+                    "Failed to read token quota for tenant '{}': {}",
+                    tenant.id, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Get the status of the most constrained quota of a tenant's end user
+    ///
+    /// Returns `None` when the tenant has no per-user quota, or when the
+    /// store fails (quotas fail open).
+    pub async fn user_status(&self, tenant: &TenantConfig, user: &str) -> Option<QuotaStatus> {
+        let quota = tenant.user_token_quota.as_ref()?;
+        match self
+            .compute_status(&user_key(&tenant.id, user), quota)
+            .await
+        {
+            Ok(status) => status,
+            Err(e) => {
+                warn!(
+                    "Failed to read token quota for user '{}' of tenant '{}': {}",
+                    user, tenant.id, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Record the tokens used by a tenant's request, and by its end user if
+    /// the request names one
+    pub async fn record(
+        &self,
+        tenant: &TenantConfig,
+        user: Option<&str>,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+    ) {
+        if let Some(quota) = &tenant.token_quota {
+            self.record_usage(&tenant.id, quota, prompt_tokens, completion_tokens)
+                .await;
+        }
+        if let (Some(quota), Some(user)) = (&tenant.user_token_quota, user) {
+            self.record_usage(
+                &user_key(&tenant.id, user),
+                quota,
+                prompt_tokens,
+                completion_tokens,
+            )
+            .await;
+        }
+        debug!(
+            "Recorded {} prompt and {} completion tokens for tenant '{}'",
+            prompt_tokens, completion_tokens, tenant.id
+        );
+    }
+
+    /// Record token usage under a key
+    async fn record_usage(
+        &self,
+        key: &str,
+        quota: &TokenQuotaConfig,
+        prompt_tokens: u64,
+        completion_tokens: u64,
+    ) {
+        let window = Duration::from_secs(quota.window_secs);
+        for (kind, tokens) in [("prompt", prompt_tokens), ("completion", completion_tokens)] {
+            if tokens == 0 {
+                continue;
+            }
+            if let Err(e) = self
+                .store
+                .record(&format!("{}:{}", key, kind), tokens, window)
+                .await
+            {
+                warn!("Failed to record token usage for '{}': {}", key, e);
+            }
+        }
+    }
+
+    /// Clear a tenant's recorded usage, restoring its full budget
+    pub async fn reset(&self, tenant_id: &str) -> Result<(), QuotaError> {
+        for kind in ["prompt", "completion"] {
+            self.store.reset(&format!("{}:{}", tenant_id, kind)).await?;
+        }
+        Ok(())
+    }
+
+    async fn compute_status(
+        &self,
+        key: &str,
+        quota: &TokenQuotaConfig,
+    ) -> Result<Option<QuotaStatus>, QuotaError> {
+        let window = Duration::from_secs(quota.window_secs);
+        let prompt = self.store.usage(&format!("{}:prompt", key), window).await?;
+        let completion = self
+            .store
+            .usage(&format!("{}:completion", key), window)
+            .await?;
+        let total = WindowUsage {
+            used: prompt.used + completion.used,
+            oldest_ms: match (prompt.oldest_ms, completion.oldest_ms) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        };
+
+        let now = now_ms();
+        let window_ms = window.as_millis() as u64;
+
+        Ok([
+            (quota.max_prompt_tokens, prompt),
+            (quota.max_completion_tokens, completion),
+            (quota.max_total_tokens, total),
+        ]
+        .into_iter()
+        .filter_map(|(limit, usage)| {
+            let limit = limit?;
+            let reset_ms = usage
+                .oldest_ms
+                .map(|ts| (ts + window_ms).saturating_sub(now))
+                .unwrap_or(0);
+            Some(QuotaStatus {
+                limit,
+                remaining: limit.saturating_sub(usage.used),
+                reset: Duration::from_millis(reset_ms),
+            })
+        })
+        .min_by_key(|status| status.remaining))
+    }
+}
+
+/// Middleware enforcing tenant token quotas and reporting them in headers
+///
+/// Requests from tenants with an exhausted quota are rejected with `429`.
+/// Usage itself is recorded by the handlers, which know the token counts.
+pub async fn token_quota_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Health checks and monitoring scrapes are not held to quotas
+    if TrafficClass::of(&request).is_exempt() {
+        return next.run(request).await;
+    }
+    let Some(tenant) = tenant::resolve_tenant(&state.config.proxy, request.headers()).cloned()
+    else {
+        return next.run(request).await;
+    };
+
+    if let Some(status) = state.quotas.status(&tenant).await {
+        if status.is_exhausted() {
+            warn!("Token quota exhausted for tenant '{}'", tenant.id);
+            return status.exceeded_response(format!(
+                "Token quota exceeded for tenant '{}', retry in {}s",
+                tenant.id,
+                status.reset.as_secs()
+            ));
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if let Some(status) = state.quotas.status(&tenant).await {
+        status.apply_headers(response.headers_mut());
+    }
+    response
+}
+
+/// Reject a request whose end user used up their quota
+///
+/// The request's `user` field is only known once its body is parsed, so the
+/// handlers check per-user quotas themselves.
+pub async fn check_user_quota(
+    state: &AppState,
+    headers: &HeaderMap,
+    user: Option<&str>,
+) -> Result<(), Response> {
+    let (Some(tenant), Some(user)) = (tenant::resolve_tenant(&state.config.proxy, headers), user)
+    else {
+        return Ok(());
+    };
+    match state.quotas.user_status(tenant, user).await {
+        Some(status) if status.is_exhausted() => {
+            warn!(
+                "Token quota exhausted for user '{}' of tenant '{}'",
+                user, tenant.id
+            );
+            Err(status.exceeded_response(format!(
+                "Token quota exceeded for user '{}', retry in {}s",
+                user,
+                status.reset.as_secs()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Key of the usage of a tenant's end user
+fn user_key(tenant_id: &str, user: &str) -> String {
+    format!("{}:user:{}", tenant_id, user)
+}
+
+/// Current time in milliseconds since the Unix epoch
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(quota: TokenQuotaConfig) -> TenantConfig {
+        TenantConfig {
+            id: "acme".to_string(),
+            api_keys: vec!["sk-acme".to_string()],
+            allowed_models: Vec::new(),
+            token_quota: Some(quota),
+            user_token_quota: None,
+            allowed_regions: Vec::new(),
+            memory_retention_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_quota_tracks_remaining_tokens() {
+        let manager = TokenQuotaManager::default();
+        let tenant = tenant(TokenQuotaConfig {
+            max_total_tokens: Some(1000),
+            max_completion_tokens: Some(300),
+            ..Default::default()
+        });
+
+        manager.record(&tenant, None, 400, 200).await;
+        let status = manager.status(&tenant).await.unwrap();
+
+        // Completion quota is the most constrained: 300 - 200
+        assert_eq!(status.limit, 300);
+        assert_eq!(status.remaining, 100);
+        assert!(!status.is_exhausted());
+
+        manager.record(&tenant, None, 0, 150).await;
+        assert!(manager.status(&tenant).await.unwrap().is_exhausted());
+    }
+
+    #[tokio::test]
+    async fn test_user_quota_under_tenant_quota() {
+        let manager = TokenQuotaManager::default();
+        let tenant = TenantConfig {
+            user_token_quota: Some(TokenQuotaConfig {
+                max_total_tokens: Some(100),
+                ..Default::default()
+            }),
+            ..tenant(TokenQuotaConfig {
+                max_total_tokens: Some(1000),
+                ..Default::default()
+            })
+        };
+
+        manager.record(&tenant, Some("alice"), 60, 40).await;
+        manager.record(&tenant, Some("bob"), 30, 0).await;
+        manager.record(&tenant, None, 200, 0).await;
+
+        // Each user has their own budget; all usage counts for the tenant
+        assert!(manager
+            .user_status(&tenant, "alice")
+            .await
+            .unwrap()
+            .is_exhausted());
+        assert_eq!(
+            manager.user_status(&tenant, "bob").await.unwrap().remaining,
+            70
+        );
+        assert_eq!(manager.status(&tenant).await.unwrap().remaining, 670);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_window_expires() {
+        let store = InMemoryQuotaStore::new();
+        store
+            .record("acme:prompt", 50, Duration::ZERO)
+            .await
+            .unwrap();
+
+        let usage = store.usage("acme:prompt", Duration::ZERO).await.unwrap();
+        assert_eq!(usage.used, 0);
+
+        let usage = store
+            .usage("acme:prompt", Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(usage, WindowUsage::default());
+    }
+
+    #[tokio::test]
+    async fn test_tenant_without_quota() {
+        let manager = TokenQuotaManager::default();
+        let tenant = TenantConfig {
+            id: "free".to_string(),
+            ..Default::default()
+        };
+
+        manager.record(&tenant, Some("alice"), 100, 100).await;
+        assert!(manager.status(&tenant).await.is_none());
+        assert!(manager.user_status(&tenant, "alice").await.is_none());
+    }
+}
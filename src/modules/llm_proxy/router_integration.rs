@@ -293,6 +293,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
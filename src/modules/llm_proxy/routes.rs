@@ -4,7 +4,8 @@
 //! providing OpenAI-compatible API endpoints.
 
 use axum::{
-    extract::State,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::{
         sse::{Event, Sse},
         IntoResponse, Response,
@@ -12,14 +13,68 @@ use axum::{
     Json,
 };
 use futures::stream;
+use metrics::counter;
 use std::convert::Infallible;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::info;
+use uuid::Uuid;
 
-use super::dto::{ApiError, ChatCompletionRequest, ChatCompletionResponse};
+use super::backpressure::{self, StreamSource};
+use super::coalesce;
+use super::decision_log::RoutingDecision;
+use super::domain::message::{Message, MessageRole};
+use super::dto::{
+    ApiError, ApiErrorDetail, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse,
+    ContinueRequest, ModelList, ModelObject, PolicyDryRunRequest, RouteExplainRequest,
+};
+use super::model_rollout::RolloutVariant;
+use super::params;
+use super::partials::{self, Continuation, PartialRecorder, PartialResponse};
+use super::passthrough;
+use super::payloads::{PayloadRecord, PromptSnapshot};
+use super::prompt_trace::{self, PromptTrace};
+use super::prompts::{self, PromptError, PromptServeRecord, ResolvedPrompt};
+use super::provenance::{self, ResponseMetadata};
+use super::quirks::MessageNormalizer;
+use super::quota;
 use super::server::AppState;
-use super::service::ChatCompletionService;
+use super::service::{
+    convert_from_connector_response, convert_to_connector_request, ChatCompletionService,
+};
+use super::smoothing;
+use super::stream_buffer;
+use super::stream_usage::{self, StreamUsage};
+use super::tenant;
+use super::transform::{model_matches, ModelTransformer};
 use super::validation;
-use crate::modules::router_core::RouterError;
+use crate::config::TenantConfig;
+use crate::modules::memory::MemoryError;
+use crate::modules::model_registry::connectors::{ModelConnector, RawStreamingResponse};
+use crate::modules::model_registry::ModelResolution;
+use crate::modules::router_core::explain::{self, RouteConstraints, RouteExplanation};
+use crate::modules::router_core::policy::{PolicyAttributes, PolicyEvaluation, PolicyVersionInfo};
+use crate::modules::router_core::residency::ResidencyEnforcer;
+use crate::modules::router_core::{
+    DetectedLanguage, LanguageSteering, RouterConfig, RouterError, RoutingRequest,
+};
+use crate::modules::telemetry::{catalog, CacheStatus, TelemetryRecord, UsageRecord};
+
+/// Response header naming how the requested model was resolved, when it was
+/// resolved to another model
+pub const MODEL_RESOLUTION_HEADER: &str = "x-intellirouter-model-resolution";
+
+/// Response header naming the model requested by the client, when it was
+/// resolved to another model
+pub const REQUESTED_MODEL_HEADER: &str = "x-intellirouter-requested-model";
+
+/// Response header carrying the ISO 639-1 code of the language detected in
+/// the request, when language detection is enabled
+pub const LANGUAGE_HEADER: &str = "x-intellirouter-language";
+
+/// Request header carrying the client's priority for the request (0-255),
+/// evaluated by routing policies as `request.priority`
+pub const PRIORITY_HEADER: &str = "x-intellirouter-priority";
 
 /// Validate service health before handling requests
 async fn validate_service_health(state: &AppState) -> Result<(), ApiError> {
@@ -55,13 +110,26 @@ async fn validate_service_health(state: &AppState) -> Result<(), ApiError> {
 }
 
 /// Route handler for /v1/chat/completions
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions",
+    tag = "chat",
+    request_body = ChatCompletionRequest,
+    responses(
+        (status = 200, description = "Completion", body = ChatCompletionResponse),
+        (status = 400, description = "Invalid request", body = ApiError)
+    )
+)]
 #[axum::debug_handler]
 pub async fn chat_completions(
     State(state): State<AppState>,
-    Json(request): Json<ChatCompletionRequest>,
-) -> Result<Json<ChatCompletionResponse>, ApiError> {
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
+) -> Result<Response, ApiError> {
     // Removed debug log
 
+    let started = Instant::now();
+
     // Validate service health before processing the request
     validate_service_health(&state).await?;
 
@@ -76,44 +144,656 @@ pub async fn chat_completions(
     // Validate the request
     validation::validate_chat_completion_request(&request)?;
 
-    // Create service with appropriate router
-    #[cfg(feature = "test-utils")]
-    let service = ChatCompletionService::new_with_mock_router();
-
-    #[cfg(not(feature = "test-utils"))]
+    // Reject end users over their own quota, under the tenant's
+    if let Err(response) = quota::check_user_quota(&state, &headers, request.user.as_deref()).await
     {
-        // In a real implementation, we would create a router service here
-        // For now, use the legacy method
-        return Ok(Json(
-            ChatCompletionService::legacy_process_completion_request(&request),
-        ));
+        return Ok(response);
     }
 
-    // Process the request using the service (only reached when test-utils is enabled)
-    #[cfg(feature = "test-utils")]
-    match service.process_completion_request(&request).await {
-        Ok(response) => Ok(Json(response)),
+    // Resolve aliases, provider prefixes and wildcard routes of the model
+    let resolution = resolve_model(&state, &mut request);
+
+    // Map optional parameters onto what the target provider supports
+    apply_provider_param_policy(&state, &mut request, &resolution)?;
+
+    // Rollout candidates are called directly, so apply the tenant's
+    // allow-list and regions and the routing policy before choosing one
+    check_model_access(&state, &headers, &request, false)?;
+
+    // Detect the language of the user's messages
+    let language = detect_language(&state, &request);
+
+    // Prepend the system prompt selected from the prompt registry
+    let trace = start_prompt_trace(&state, &headers, &request);
+    let prompt = apply_prompt(&state, &headers, &mut request)?;
+    let trace = trace.map(|mut trace| {
+        trace_prompt_stages(&state, &mut trace, &request, prompt.as_ref(), true);
+        trace
+    });
+
+    // Bucket the caller into the model's rollout, if one is in progress
+    let rollout = state
+        .rollouts
+        .select(&request.model, &caller_key(&state, &headers, &request));
+    let (candidate, rollout) = rollout_connector(&state, &request.model, rollout);
+
+    // Identical deterministic requests in flight share one upstream call;
+    // candidate callers don't share the baseline's
+    let tenant_id = tenant::resolve_tenant(&state.config.proxy, &headers).map(|t| t.id.as_str());
+    let coalesce_key = coalesce::key(
+        &state.config.proxy.coalescing,
+        &headers,
+        tenant_id,
+        &request,
+    )
+    .filter(|_| candidate.is_none());
+
+    let generate = {
+        let request = request.clone();
+        #[cfg(feature = "test-utils")]
+        let state = state.clone();
+        async move {
+            if let Some(connector) = candidate {
+                return connector
+                    .generate(convert_to_connector_request(&request))
+                    .await
+                    .map(convert_from_connector_response)
+                    .map_err(|e| RouterError::ConnectorError(e.to_string()));
+            }
+
+            // Create service with appropriate router
+            #[cfg(feature = "test-utils")]
+            let service = ChatCompletionService::new_with_mock_router()
+                .with_transformer(ModelTransformer::new(state.config.proxy.transforms.clone()))
+                .with_normalizer(MessageNormalizer::new(
+                    state.config.proxy.message_quirks.clone(),
+                ))
+                .with_registry(state.registry.clone())
+                .with_json_mode(state.config.proxy.json_mode.clone());
+
+            // Process the request using the service (only when test-utils is enabled)
+            #[cfg(feature = "test-utils")]
+            let response = service.process_completion_request(&request).await;
+
+            // In a real implementation, we would create a router service here
+            // For now, use the legacy method
+            #[cfg(not(feature = "test-utils"))]
+            let response = Ok(ChatCompletionService::legacy_process_completion_request(
+                &request,
+            ));
+
+            response
+        }
+    };
+
+    let upstream_started = Instant::now();
+    let (outcome, coalesced) = match coalesce_key {
+        Some(key) => state.coalescer.join(key, &request.model, generate).await,
+        None => (generate.await, false),
+    };
+    let upstream_ms = upstream_started.elapsed().as_millis() as u64;
+    let response = match outcome {
+        Ok(response) => response,
         Err(err) => {
-            error!("Error processing completion request: {}", err);
-            Err(_convert_router_error_to_api_error(err))
+            tracing::error!("Error processing completion request: {}", err);
+            record_decision(
+                &state,
+                &headers,
+                &request,
+                &resolution,
+                language.as_ref(),
+                prompt.as_ref(),
+                rollout,
+                started,
+                Some(upstream_ms),
+                coalesced,
+                Err(err.to_string()),
+            );
+            return Err(_convert_router_error_to_api_error(err));
         }
+    };
+
+    let metadata = record_decision(
+        &state,
+        &headers,
+        &request,
+        &resolution,
+        language.as_ref(),
+        prompt.as_ref(),
+        rollout,
+        started,
+        Some(upstream_ms),
+        coalesced,
+        Ok(&response),
+    );
+
+    // Count the tokens against the quotas of the tenant and its end user
+    if let Some(tenant) = tenant::resolve_tenant(&state.config.proxy, &headers) {
+        state
+            .quotas
+            .record(
+                tenant,
+                request.user.as_deref(),
+                response.usage.prompt_tokens as u64,
+                response.usage.completion_tokens as u64,
+            )
+            .await;
+    }
+
+    let metadata_config = &state.config.proxy.response_metadata;
+    let returned_trace = trace
+        .as_ref()
+        .filter(|_| state.prompt_traces.include_in_response());
+    let body_metadata = provenance::body_enabled(metadata_config).then_some(&metadata);
+    let response = if returned_trace.is_none() && body_metadata.is_none() {
+        Json(response).into_response()
+    } else {
+        let mut body = serde_json::to_value(&response).unwrap_or_default();
+        if let Some(trace) = returned_trace {
+            body["prompt_trace"] = serde_json::to_value(trace).unwrap_or_default();
+        }
+        if let Some(metadata) = body_metadata {
+            body["metadata"] = serde_json::to_value(metadata).unwrap_or_default();
+        }
+        Json(body).into_response()
+    };
+    let response = with_metadata_headers(
+        response,
+        provenance::headers_enabled(metadata_config).then_some(&metadata),
+    );
+    Ok(with_language_header(
+        with_resolution_headers(
+            with_trace_header(response, trace.as_ref().map(|trace| trace.id.as_str())),
+            &resolution,
+        ),
+        language.as_ref(),
+    ))
+}
+
+/// Record the routing decision for a completed request in the decision log
+///
+/// Returns the response metadata describing the decision. `upstream_ms` is
+/// the time the request waited for its response, if known.
+#[allow(clippy::too_many_arguments)]
+fn record_decision(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+    resolution: &ModelResolution,
+    language: Option<&DetectedLanguage>,
+    prompt: Option<&ResolvedPrompt>,
+    rollout: Option<RolloutVariant>,
+    started: Instant,
+    upstream_ms: Option<u64>,
+    coalesced: bool,
+    outcome: Result<&ChatCompletionResponse, String>,
+) -> ResponseMetadata {
+    let model = match &outcome {
+        Ok(response) => response.model.clone(),
+        Err(_) => request.model.clone(),
+    };
+
+    let mut decision = RoutingDecision::new(resolution.requested.clone(), model);
+    decision.tenant = tenant::resolve_tenant(&state.config.proxy, headers).map(|t| t.id.clone());
+    decision.user = request.user.clone();
+    decision.provider = match &resolution.provider {
+        Some(provider) if decision.model == resolution.model => provider.clone(),
+        _ => params::resolve_provider(&state.registry, &decision.model),
+    };
+    decision.model_resolution = resolution.is_rewritten().then_some(resolution.kind);
+    decision.language = language.map(|language| language.code.clone());
+    decision.latency_ms = started.elapsed().as_millis() as u64;
+    decision.rollout = rollout;
+    decision.coalesced = coalesced;
+    if let Some(variant) = rollout {
+        state.rollouts.record(
+            &request.model,
+            variant,
+            outcome.is_ok(),
+            decision.latency_ms,
+        );
+    }
+    if let Some(prompt) = prompt {
+        decision.prompt = Some(prompt.id.clone());
+        decision.prompt_version = Some(prompt.version);
+        record_prompt_served(
+            state,
+            &decision.id,
+            decision.tenant.clone(),
+            request,
+            prompt,
+        );
+    }
+
+    let response = outcome.as_ref().ok().copied();
+    match outcome {
+        Ok(response) => {
+            decision.prompt_tokens = response.usage.prompt_tokens;
+            decision.completion_tokens = response.usage.completion_tokens;
+            decision.cost_usd = state.cost_calculator.as_ref().and_then(|calculator| {
+                calculator
+                    .record_usage(UsageRecord {
+                        request_id: decision.id.clone(),
+                        timestamp: decision.timestamp,
+                        tenant: decision.tenant.clone(),
+                        user: decision.user.clone(),
+                        model: decision.model.clone(),
+                        prompt_tokens: response.usage.prompt_tokens,
+                        completion_tokens: response.usage.completion_tokens,
+                        cost: 0.0,
+                    })
+                    .ok()
+            });
+            let filtered = response
+                .choices
+                .iter()
+                .filter_map(|choice| choice.content_filter.as_ref());
+            for detail in filtered {
+                counter!(
+                    catalog::LLM_CONTENT_FILTERED,
+                    1,
+                    "model" => decision.model.clone(),
+                    "provider" => decision.provider.clone(),
+                    "stage" => detail.stage.as_str()
+                );
+            }
+        }
+        Err(error) => decision.error = Some(error),
+    }
+
+    observe_decision(state, &decision);
+    if state.payloads.enabled() {
+        state
+            .payloads
+            .record(payload_record(state, &decision, request, prompt, response));
+    }
+    let metadata = with_deprecation_warning(
+        state,
+        ResponseMetadata::from_decision(&decision, upstream_ms),
+    );
+    state.decisions.record(decision);
+    metadata
+}
+
+/// Hand a decision to telemetry export, anomaly detection, metering and the
+/// per-user usage
+fn observe_decision(state: &AppState, decision: &RoutingDecision) {
+    if state.telemetry_export.is_some() || state.anomalies.is_some() || state.metering.is_some() {
+        let record = telemetry_record(decision);
+        if let Some(detector) = &state.anomalies {
+            detector.observe(&record);
+        }
+        if let Some(metering) = &state.metering {
+            metering.observe(&record, chrono::Utc::now());
+        }
+        if let Some(exporter) = &state.telemetry_export {
+            exporter.record(record);
+        }
+    }
+    state.user_usage.observe(decision);
+}
+
+/// Warn in the metadata of a request served by a deprecated model, and count
+/// the request
+fn with_deprecation_warning(state: &AppState, metadata: ResponseMetadata) -> ResponseMetadata {
+    let Ok(model) = state.registry.get_model(&metadata.model) else {
+        return metadata;
+    };
+    let Some(warning) = model.deprecation_warning() else {
+        return metadata;
+    };
+    counter!(
+        catalog::MODEL_DEPRECATED_REQUESTS,
+        1,
+        "model" => model.id.clone(),
+        "owner" => model
+            .fine_tune
+            .as_ref()
+            .map(|lineage| lineage.owner.clone())
+            .unwrap_or_default()
+    );
+    metadata.with_warning(warning)
+}
+
+/// Build the captured payload of a request from its routing decision
+fn payload_record(
+    state: &AppState,
+    decision: &RoutingDecision,
+    request: &ChatCompletionRequest,
+    prompt: Option<&ResolvedPrompt>,
+    response: Option<&ChatCompletionResponse>,
+) -> PayloadRecord {
+    PayloadRecord {
+        request_id: decision.id.clone(),
+        timestamp: decision.timestamp,
+        tenant: decision.tenant.clone(),
+        user: decision.user.clone(),
+        requested_model: decision.requested_model.clone(),
+        model: decision.model.clone(),
+        provider: decision.provider.clone(),
+        policy_version: state.policies.active_version(),
+        prompt: prompt.map(|prompt| PromptSnapshot {
+            id: prompt.id.clone(),
+            version: prompt.version,
+            template: prompt.template.clone(),
+        }),
+        request: request.clone(),
+        response: response.cloned(),
+        error: decision.error.clone(),
+        latency_ms: decision.latency_ms,
+    }
+}
+
+/// Start tracing the prompt assembly of a request, when the request asks
+/// for it and tracing is enabled
+fn start_prompt_trace(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+) -> Option<PromptTrace> {
+    if !state.prompt_traces.enabled() || !prompt_trace::requested(headers) {
+        return None;
+    }
+    let tenant = tenant::resolve_tenant(&state.config.proxy, headers).map(|t| t.id.clone());
+    Some(PromptTrace::new(request, tenant))
+}
+
+/// Record the assembly stages following the client's request and keep the
+/// trace
+///
+/// `transform` tells whether the per-model transforms apply to the provider
+/// request, which they don't for streams.
+fn trace_prompt_stages(
+    state: &AppState,
+    trace: &mut PromptTrace,
+    request: &ChatCompletionRequest,
+    prompt: Option<&ResolvedPrompt>,
+    transform: bool,
+) {
+    if let Some(prompt) = prompt {
+        trace.stage(
+            "prompt_registry",
+            Some(format!("{} v{}", prompt.id, prompt.version)),
+            prompt_trace::request_messages(request),
+        );
+    }
+    let mut provider_request = convert_to_connector_request(request);
+    trace.stage(
+        "provider_format",
+        None,
+        prompt_trace::connector_messages(&provider_request),
+    );
+    if transform {
+        let transformer = ModelTransformer::new(state.config.proxy.transforms.clone());
+        let rules: Vec<&str> = transformer
+            .rules_for(&request.model)
+            .map(|rule| rule.model.as_str())
+            .collect();
+        if !rules.is_empty() {
+            transformer.apply_request(&mut provider_request);
+            trace.stage(
+                "transform",
+                Some(rules.join(", ")),
+                prompt_trace::connector_messages(&provider_request),
+            );
+        }
+        let provider = params::resolve_provider(&state.registry, &request.model);
+        let normalizer = MessageNormalizer::new(state.config.proxy.message_quirks.clone());
+        if !normalizer.quirks_for(&provider).is_empty() {
+            let quirks = normalizer.apply(&provider, &mut provider_request);
+            trace.stage(
+                "provider_quirks",
+                Some(format!("{}: {}", provider, quirks.enabled().join(", "))),
+                prompt_trace::connector_messages(&provider_request),
+            );
+        }
+    }
+    tracing::debug!(trace = %trace.id, model = %trace.model, "prompt trace recorded");
+    state.prompt_traces.record(trace.clone());
+}
+
+/// Name the requested model and how it was resolved in a response, when it
+/// was resolved to another model
+fn with_resolution_headers(mut response: Response, resolution: &ModelResolution) -> Response {
+    if !resolution.is_rewritten() {
+        return response;
+    }
+    let headers = response.headers_mut();
+    headers.insert(
+        MODEL_RESOLUTION_HEADER,
+        HeaderValue::from_static(resolution.kind.as_str()),
+    );
+    if let Ok(value) = HeaderValue::from_str(&resolution.requested) {
+        headers.insert(REQUESTED_MODEL_HEADER, value);
+    }
+    response
+}
+
+/// Name the language detected in a request in its response
+fn with_language_header(mut response: Response, language: Option<&DetectedLanguage>) -> Response {
+    if let Some(value) = language.and_then(|language| HeaderValue::from_str(&language.code).ok()) {
+        response.headers_mut().insert(LANGUAGE_HEADER, value);
+    }
+    response
+}
+
+/// Add the routing metadata headers of a request to its response, when
+/// enabled
+fn with_metadata_headers(mut response: Response, metadata: Option<&ResponseMetadata>) -> Response {
+    if let Some(metadata) = metadata {
+        metadata.apply_headers(response.headers_mut());
+    }
+    response
+}
+
+/// Routing metadata of a stream, known before its first event, when
+/// metadata headers are enabled
+fn stream_metadata(state: &AppState, resolution: &ModelResolution) -> Option<ResponseMetadata> {
+    if !provenance::headers_enabled(&state.config.proxy.response_metadata) {
+        return None;
+    }
+    let mut decision = RoutingDecision::new(resolution.requested.clone(), resolution.model.clone());
+    decision.provider = provider_of(state, resolution);
+    decision.model_resolution = resolution.is_rewritten().then_some(resolution.kind);
+    Some(with_deprecation_warning(
+        state,
+        ResponseMetadata::from_decision(&decision, None),
+    ))
+}
+
+/// Add the prompt trace ID of a request to its response
+fn with_trace_header(mut response: Response, trace_id: Option<&str>) -> Response {
+    if let Some(value) = trace_id.and_then(|id| HeaderValue::from_str(id).ok()) {
+        response
+            .headers_mut()
+            .insert(prompt_trace::TRACE_ID_HEADER, value);
+    }
+    response
+}
+
+/// Key bucketing a caller into prompt and model rollouts
+///
+/// Callers are identified by user, else by tenant; anonymous requests are
+/// bucketed at random.
+fn caller_key(state: &AppState, headers: &HeaderMap, request: &ChatCompletionRequest) -> String {
+    match (
+        &request.user,
+        tenant::resolve_tenant(&state.config.proxy, headers),
+    ) {
+        (Some(user), _) => user.clone(),
+        (None, Some(tenant)) => tenant.id.clone(),
+        (None, None) => Uuid::new_v4().to_string(),
+    }
+}
+
+/// Prepend the system prompt selected with the prompt header, if any
+fn apply_prompt(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &mut ChatCompletionRequest,
+) -> Result<Option<ResolvedPrompt>, ApiError> {
+    let Some(id) = headers
+        .get(prompts::PROMPT_HEADER)
+        .and_then(|value| value.to_str().ok())
+    else {
+        return Ok(None);
+    };
+
+    let key = caller_key(state, headers, request);
+    let prompt = state.prompts.resolve(id, &key).map_err(|e| ApiError {
+        error: ApiErrorDetail {
+            message: e.to_string(),
+            r#type: "invalid_request_error".to_string(),
+            param: Some(prompts::PROMPT_HEADER.to_string()),
+            code: Some(
+                match e {
+                    PromptError::UnknownPrompt(_) => "prompt_not_found",
+                    _ => "prompt_error",
+                }
+                .to_string(),
+            ),
+        },
+    })?;
+
+    request
+        .messages
+        .insert(0, Message::new_system(prompt.template.clone()));
+    Ok(Some(prompt))
+}
+
+/// Record the prompt version that served a request in the prompt audit trail
+fn record_prompt_served(
+    state: &AppState,
+    request_id: &str,
+    tenant: Option<String>,
+    request: &ChatCompletionRequest,
+    prompt: &ResolvedPrompt,
+) {
+    state.prompts.record_served(PromptServeRecord {
+        request_id: request_id.to_string(),
+        timestamp: chrono::Utc::now(),
+        prompt: prompt.id.clone(),
+        version: prompt.version,
+        rollout: prompt.rollout,
+        tenant,
+        user: request.user.clone(),
+    });
+}
+
+/// Detect the language of a request's user messages, when enabled
+fn detect_language(state: &AppState, request: &ChatCompletionRequest) -> Option<DetectedLanguage> {
+    if !state.config.language.enabled {
+        return None;
+    }
+    let texts: Vec<String> = request
+        .messages
+        .iter()
+        .filter(|message| message.role == MessageRole::User)
+        .map(Message::extract_text_content)
+        .collect();
+    LanguageSteering::new(state.config.language.clone()).detect(texts.iter().map(String::as_str))
+}
+
+/// Build the exported telemetry record of a routing decision
+fn telemetry_record(decision: &RoutingDecision) -> TelemetryRecord {
+    TelemetryRecord {
+        request_id: decision.id.clone(),
+        timestamp: decision.timestamp,
+        tenant: decision.tenant.clone(),
+        user: decision.user.clone(),
+        requested_model: decision.requested_model.clone(),
+        model: decision.model.clone(),
+        provider: decision.provider.clone(),
+        prompt_tokens: decision.prompt_tokens,
+        completion_tokens: decision.completion_tokens,
+        total_tokens: decision.prompt_tokens + decision.completion_tokens,
+        cost_usd: decision.cost_usd,
+        latency_ms: decision.latency_ms,
+        // A coalesced request is served from the response of another
+        cache_status: if decision.coalesced {
+            CacheStatus::Hit
+        } else {
+            CacheStatus::Bypass
+        },
+        success: decision.error.is_none(),
+        error: decision.error.clone(),
+        flagged_categories: decision.flagged_categories.clone(),
+        language: decision.language.clone(),
     }
 }
 
 /// Route handler for /v1/chat/completions/stream
+///
+/// A request carrying a `Last-Event-ID` of a buffered stream resumes that
+/// stream after the given event instead of starting a new generation.
+#[utoipa::path(
+    post,
+    path = "/v1/chat/completions/stream",
+    tag = "chat",
+    request_body = ChatCompletionRequest,
+    params(("Last-Event-ID" = Option<String>, Header, description = "ID of the last event received, to resume a stream")),
+    responses(
+        (status = 200, description = "Server-sent `ChatCompletionChunk` events", content_type = "text/event-stream", body = ChatCompletionChunk),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Unknown or expired stream", body = ApiError)
+    )
+)]
 #[axum::debug_handler]
 pub async fn chat_completions_stream(
     State(state): State<AppState>,
-    Json(request): Json<ChatCompletionRequest>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
 ) -> Result<Response, ApiError> {
     // Removed debug log
 
     // Validate service health before processing the request
     validate_service_health(&state).await?;
 
+    if let Some(response) = resume_stream(&state, &headers, None) {
+        return Ok(response);
+    }
+
     // Validate the request
     validation::validate_chat_completion_request(&request)?;
 
+    // Reject end users over their own quota, under the tenant's
+    if let Err(response) = quota::check_user_quota(&state, &headers, request.user.as_deref()).await
+    {
+        return Ok(response);
+    }
+
+    // Resolve aliases, provider prefixes and wildcard routes of the model
+    let resolution = resolve_model(&state, &mut request);
+
+    // Map optional parameters onto what the target provider supports
+    apply_provider_param_policy(&state, &mut request, &resolution)?;
+
+    // Streams go straight to a connector, so apply the tenant's allow-list
+    // and regions and the routing policy the router would have applied
+    check_model_access(&state, &headers, &request, true)?;
+
+    // Record what the client received if the stream is interrupted, with
+    // the messages to continue it from
+    let recorder = state.partials.recorder(&headers, &request);
+
+    // Prepend the system prompt selected from the prompt registry
+    let trace = start_prompt_trace(&state, &headers, &request);
+    let prompt = apply_prompt(&state, &headers, &mut request)?;
+    let trace_id = trace.map(|mut trace| {
+        trace_prompt_stages(&state, &mut trace, &request, prompt.as_ref(), false);
+        trace.id
+    });
+    if let Some(prompt) = prompt {
+        let tenant = tenant::resolve_tenant(&state.config.proxy, &headers).map(|t| t.id.clone());
+        record_prompt_served(
+            &state,
+            &Uuid::new_v4().to_string(),
+            tenant,
+            &request,
+            &prompt,
+        );
+    }
+
     // Create service with appropriate router (not used directly in this implementation)
     #[cfg(feature = "test-utils")]
     let _service = ChatCompletionService::new_with_mock_router();
@@ -124,22 +804,837 @@ pub async fn chat_completions_stream(
         // But for streaming, we're using the legacy method anyway
     };
 
+    // Name the provider and model in the headers, before the first event
+    let metadata = stream_metadata(&state, &resolution);
+
+    // Pace the chunks delivered to the client, if smoothing applies
+    let pacing = smoothing::pacing(
+        &state.config.proxy.stream_smoothing,
+        &headers,
+        &request.model,
+    );
+
+    // Bucket the caller into the model's rollout, if one is in progress; the
+    // candidate is judged on whether and how fast its stream starts
+    let rollout = state
+        .rollouts
+        .select(&request.model, &caller_key(&state, &headers, &request));
+    let (candidate, rollout) = rollout_connector(&state, &request.model, rollout);
+    let connector = candidate.or_else(|| state.registry.get_connector(&request.model));
+
+    // Count the usage of the stream, for the client if it asks for it and
+    // for quotas, metering and the decision log once the stream ends
+    let usage = || stream_usage(&state, &headers, &request, &resolution, rollout);
+
+    // Forward OpenAI-format provider streams without re-serializing them
+    if let Some(connector) = connector {
+        let started = Instant::now();
+        let mut provider_request = convert_to_connector_request(&request);
+        let provider = provider_of(&state, &resolution);
+        params::apply_user_param(&mut provider_request, &provider);
+        params::apply_stream_options(&mut provider_request, &provider, request.include_usage());
+        let body = connector.generate_streaming_raw(provider_request).await;
+        if let Some(variant) = rollout {
+            state.rollouts.record(
+                &request.model,
+                variant,
+                body.is_ok(),
+                started.elapsed().as_millis() as u64,
+            );
+        }
+        let body = body.map_err(|e| {
+            _convert_router_error_to_api_error(RouterError::ConnectorError(e.to_string()))
+        })?;
+        if let Some(body) = body {
+            return Ok(with_metadata_headers(
+                with_trace_header(
+                    passthrough_response(&state, body, pacing, usage(), recorder),
+                    trace_id.as_deref(),
+                ),
+                metadata.as_ref(),
+            ));
+        }
+    }
+
     // For now, use the legacy method for streaming
     // In a real implementation, we would use the router service
     let chunks = ChatCompletionService::legacy_generate_streaming_chunks(&request, 5);
+    let mut events = chunks
+        .iter()
+        .map(|chunk| serde_json::to_string(chunk).unwrap_or_default())
+        .collect::<Vec<_>>();
+    let mut usage = usage();
+    events.iter().for_each(|data| usage.observe(data));
+    events.extend(usage.final_chunk());
+    // The generated events are all known, so the stream is accounted now
+    drop(usage);
+
+    if !state.streams.enabled() {
+        // Create a stream from the chunks
+        let stream =
+            tokio_stream::StreamExt::throttle(stream::iter(events), Duration::from_millis(300));
+        let stream = smoothing::pace(stream, pacing, |data: &String| Some(data.clone()));
+        let stream = partials::record(stream, recorder, |data: &String| Some(data.clone()));
+        let stream = futures::StreamExt::map(stream, move |data| {
+            Ok::<_, Infallible>(Event::default().data(data))
+        });
+
+        // Apply backpressure and boxing
+        let stream = backpressure::bounded(
+            stream,
+            StreamSource::Generated,
+            &state.config.proxy.stream_backpressure,
+        );
+        let stream = futures::StreamExt::boxed(stream);
+
+        // Return the SSE stream wrapped in a Response
+        return Ok(with_metadata_headers(
+            with_trace_header(Sse::new(stream).into_response(), trace_id.as_deref()),
+            metadata.as_ref(),
+        ));
+    }
+
+    // Generate into the stream buffer, so the generation outlives the
+    // connection and a dropped client can resume it
+    let (stream_id, writer) = state.streams.create();
+    tokio::spawn(async move {
+        for (i, data) in events.into_iter().enumerate() {
+            if i > 0 {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+            writer.push(data);
+        }
+    });
+
+    let response = buffered_stream_response(&state, &stream_id, 0, pacing, recorder)
+        .unwrap_or_else(|| stream_not_found(&stream_id));
+    Ok(with_metadata_headers(
+        with_trace_header(response, trace_id.as_deref()),
+        metadata.as_ref(),
+    ))
+}
+
+/// Connector of the rollout candidate a caller is bucketed into, and the
+/// variant actually serving the caller
+///
+/// Callers bucketed into a candidate without a connector are served by, and
+/// credited to, the baseline.
+fn rollout_connector(
+    state: &AppState,
+    model: &str,
+    rollout: Option<RolloutVariant>,
+) -> (Option<Arc<dyn ModelConnector>>, Option<RolloutVariant>) {
+    let candidate = match rollout {
+        Some(RolloutVariant::Candidate) => state.rollouts.candidate_connector(model),
+        _ => None,
+    };
+    let served = rollout.map(|variant| match candidate {
+        Some(_) => variant,
+        None => RolloutVariant::Baseline,
+    });
+    (candidate, served)
+}
+
+/// Usage counter of a stream, recording its routing decision once the
+/// stream ends
+///
+/// The counted tokens go to the quotas of the stream's tenant and end user,
+/// and the decision to metering, telemetry and the decision log, as for
+/// non-streaming requests.
+fn stream_usage(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+    resolution: &ModelResolution,
+    rollout: Option<RolloutVariant>,
+) -> StreamUsage {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, headers).cloned();
+    let mut decision = RoutingDecision::new(resolution.requested.clone(), request.model.clone());
+    decision.tenant = tenant.as_ref().map(|tenant| tenant.id.clone());
+    decision.user = request.user.clone();
+    decision.provider = provider_of(state, resolution);
+    decision.model_resolution = resolution.is_rewritten().then_some(resolution.kind);
+    decision.rollout = rollout;
+
+    let state = state.clone();
+    let started = Instant::now();
+    StreamUsage::new(&request.model, &request.messages)
+        .in_stream(request.include_usage())
+        .on_finish(move |usage| {
+            decision.timestamp = chrono::Utc::now();
+            decision.latency_ms = started.elapsed().as_millis() as u64;
+            decision.prompt_tokens = usage.prompt_tokens;
+            decision.completion_tokens = usage.completion_tokens;
+            decision.cost_usd = state.cost_calculator.as_ref().and_then(|calculator| {
+                calculator
+                    .record_usage(UsageRecord {
+                        request_id: decision.id.clone(),
+                        timestamp: decision.timestamp,
+                        tenant: decision.tenant.clone(),
+                        user: decision.user.clone(),
+                        model: decision.model.clone(),
+                        prompt_tokens: usage.prompt_tokens,
+                        completion_tokens: usage.completion_tokens,
+                        cost: 0.0,
+                    })
+                    .ok()
+            });
+            observe_decision(&state, &decision);
+            let user = decision.user.clone();
+            state.decisions.record(decision);
+
+            // Streams end on the runtime, outside of any handler
+            let Some(tenant) = tenant else {
+                return;
+            };
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn(async move {
+                    state
+                        .quotas
+                        .record(
+                            &tenant,
+                            user.as_deref(),
+                            usage.prompt_tokens as u64,
+                            usage.completion_tokens as u64,
+                        )
+                        .await;
+                });
+            }
+        })
+}
+
+/// Response forwarding a provider's raw SSE stream
+///
+/// With resumable streams enabled the events go through the stream buffer,
+/// which needs their data, so only the event framing is skipped there.
+/// Streams whose usage is counted end with a usage chunk unless the provider
+/// sent one.
+fn passthrough_response(
+    state: &AppState,
+    body: RawStreamingResponse,
+    pacing: Option<smoothing::Pacing>,
+    usage: StreamUsage,
+    recorder: Option<PartialRecorder>,
+) -> Response {
+    if !state.streams.enabled() {
+        let frames = stream_usage::append_usage(passthrough::frames(body), usage);
+        let frames = smoothing::pace(frames, pacing, |frame| {
+            frame
+                .as_ref()
+                .ok()
+                .and_then(|frame| passthrough::event_data(frame))
+        });
+        let frames = partials::record(frames, recorder, |frame| {
+            frame
+                .as_ref()
+                .ok()
+                .and_then(|frame| passthrough::event_data(frame))
+        });
+        return passthrough::sse_response(backpressure::bounded(
+            frames,
+            StreamSource::Passthrough,
+            &state.config.proxy.stream_backpressure,
+        ));
+    }
+
+    let (stream_id, writer) = state.streams.create();
+    tokio::spawn(async move {
+        let mut usage = usage;
+        let mut frames = Box::pin(passthrough::frames(body));
+        while let Some(Ok(frame)) = futures::StreamExt::next(&mut frames).await {
+            match passthrough::event_data(&frame) {
+                Some(data) if data == passthrough::DONE => break,
+                Some(data) => {
+                    usage.observe(&data);
+                    writer.push(data);
+                }
+                None => {}
+            }
+        }
+        if let Some(data) = usage.final_chunk() {
+            writer.push(data);
+        }
+    });
+
+    buffered_stream_response(state, &stream_id, 0, pacing, recorder)
+        .unwrap_or_else(|| stream_not_found(&stream_id))
+}
+
+/// Route handler for /v1/chat/completions/stream/{id}
+///
+/// Resumes a buffered stream after the event given by `Last-Event-ID`, or
+/// replays it from the start without one.
+#[utoipa::path(
+    get,
+    path = "/v1/chat/completions/stream/{id}",
+    tag = "chat",
+    params(
+        ("id" = String, Path, description = "Stream ID"),
+        ("Last-Event-ID" = Option<String>, Header, description = "ID of the last event received")
+    ),
+    responses(
+        (status = 200, description = "Server-sent `ChatCompletionChunk` events", content_type = "text/event-stream", body = ChatCompletionChunk),
+        (status = 404, description = "Unknown or expired stream", body = ApiError)
+    )
+)]
+pub async fn resume_chat_completions_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(stream_id): Path<String>,
+) -> Response {
+    if let Err(e) = validate_service_health(&state).await {
+        return e.into_response();
+    }
 
-    // Create a stream from the chunks
-    let stream = futures::StreamExt::map(stream::iter(chunks.into_iter()), move |chunk| {
-        let json = serde_json::to_string(&chunk).unwrap_or_default();
-        Ok::<_, Infallible>(Event::default().data(json))
+    let pacing = || smoothing::pacing(&state.config.proxy.stream_smoothing, &headers, "");
+    resume_stream(&state, &headers, Some(&stream_id))
+        .or_else(|| buffered_stream_response(&state, &stream_id, 0, pacing(), None))
+        .unwrap_or_else(|| stream_not_found(&stream_id))
+}
+
+/// Resume the stream named by the `Last-Event-ID` header, if it is buffered
+///
+/// With `stream_id`, the header must refer to that stream.
+fn resume_stream(
+    state: &AppState,
+    headers: &HeaderMap,
+    stream_id: Option<&str>,
+) -> Option<Response> {
+    let last_event_id = headers
+        .get(stream_buffer::LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())?;
+    let (id, index) = stream_buffer::parse_event_id(last_event_id)?;
+    if stream_id.is_some_and(|stream_id| stream_id != id) {
+        return None;
+    }
+    // The model of a resumed stream isn't known; its tokens are estimated
+    let pacing = smoothing::pacing(&state.config.proxy.stream_smoothing, headers, "");
+    let response = buffered_stream_response(state, id, index + 1, pacing, None)?;
+    info!("Resuming stream {} after event {}", id, index);
+    Some(response)
+}
+
+/// SSE response replaying a buffered stream from event `from`
+fn buffered_stream_response(
+    state: &AppState,
+    stream_id: &str,
+    from: usize,
+    pacing: Option<smoothing::Pacing>,
+    recorder: Option<PartialRecorder>,
+) -> Option<Response> {
+    let events = state.streams.subscribe(stream_id, from)?;
+    let events = smoothing::pace(events, pacing, |(_, data): &(usize, String)| {
+        Some(data.clone())
+    });
+    let events = partials::record(events, recorder, |(_, data): &(usize, String)| {
+        Some(data.clone())
+    });
+    let stream_id = stream_id.to_string();
+    let stream = futures::StreamExt::map(events, move |(index, data)| {
+        Ok::<_, Infallible>(
+            Event::default()
+                .id(stream_buffer::event_id(&stream_id, index))
+                .data(data),
+        )
     });
+    let stream = backpressure::bounded(
+        stream,
+        StreamSource::Buffered,
+        &state.config.proxy.stream_backpressure,
+    );
+    Some(Sse::new(futures::StreamExt::boxed(stream)).into_response())
+}
+
+/// Response to requests for unknown or expired streams
+fn stream_not_found(stream_id: &str) -> Response {
+    let error = ApiError {
+        error: ApiErrorDetail {
+            message: format!("Stream {} is unknown or has expired", stream_id),
+            r#type: "invalid_request_error".to_string(),
+            param: None,
+            code: Some("stream_not_found".to_string()),
+        },
+    };
+    (StatusCode::NOT_FOUND, Json(error)).into_response()
+}
+
+/// Route handler for /v1/conversations/{id}/last-partial
+///
+/// Returns the latest partial completion recorded for the conversation when
+/// a stream sent with the conversation header was interrupted.
+#[utoipa::path(
+    get,
+    path = "/v1/conversations/{id}/last-partial",
+    tag = "chat",
+    params(("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, description = "Latest partial completion", body = PartialResponse),
+        (status = 404, description = "Unknown conversation or no partial completion", body = ApiError)
+    )
+)]
+pub async fn last_partial(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<PartialResponse>, (StatusCode, Json<ApiError>)> {
+    match state.partials.last(&id).await {
+        Ok(Some(partial)) => Ok(Json(partial)),
+        Ok(None) => Err(conversation_error(
+            StatusCode::NOT_FOUND,
+            format!("Conversation {} has no partial completion", id),
+            "partial_not_found",
+        )),
+        Err(e) => Err(memory_error(&id, e)),
+    }
+}
+
+/// Route handler for /v1/conversations/{id}/continue
+///
+/// Asks the model to continue a partial completion of the conversation from
+/// its prompt and the partial completion. The continuation is counted like
+/// any completion: its prompt tokens include the partial completion and its
+/// completion tokens only what it adds. The `continuation` field of the
+/// response joins the two.
+#[utoipa::path(
+    post,
+    path = "/v1/conversations/{id}/continue",
+    tag = "chat",
+    params(("id" = String, Path, description = "Conversation ID")),
+    request_body = ContinueRequest,
+    responses(
+        (status = 200, description = "Continuation, with a `continuation` field of type `Continuation`", body = ChatCompletionResponse),
+        (status = 400, description = "Invalid request", body = ApiError),
+        (status = 404, description = "Unknown conversation or partial completion", body = ApiError)
+    )
+)]
+pub async fn continue_completion(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(body): Json<ContinueRequest>,
+) -> Response {
+    let context = match state.partials.continuation(&id, &body.response_id).await {
+        Ok(Some(context)) => context,
+        Ok(None) => {
+            return conversation_error(
+                StatusCode::NOT_FOUND,
+                format!(
+                    "Conversation {} has no partial completion {}",
+                    id, body.response_id
+                ),
+                "partial_not_found",
+            )
+            .into_response()
+        }
+        Err(e) => return memory_error(&id, e).into_response(),
+    };
+    let partial = context.partial;
+
+    let request = ChatCompletionRequest {
+        model: body.model.unwrap_or_else(|| partial.model.clone()),
+        messages: context.messages,
+        temperature: body.temperature,
+        top_p: None,
+        n: None,
+        stream: false,
+        max_tokens: body.max_tokens,
+        presence_penalty: None,
+        frequency_penalty: None,
+        user: body.user,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
+    };
+    let response = match chat_completions(State(state.clone()), headers, Json(request)).await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => return response,
+        Err(e) => return e.into_response(),
+    };
+
+    // Join the continuation to the partial completion it continues
+    let (mut parts, completion) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(completion, usize::MAX).await else {
+        return conversation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to read the continuation".to_string(),
+            "continuation_failed",
+        )
+        .into_response();
+    };
+    let Ok(mut body_json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, axum::body::Body::from(bytes));
+    };
+    let Ok(completion) = serde_json::from_value::<ChatCompletionResponse>(body_json.clone()) else {
+        return Response::from_parts(parts, axum::body::Body::from(bytes));
+    };
+    let content = completion
+        .choices
+        .first()
+        .map(|choice| choice.message.extract_text_content())
+        .unwrap_or_default();
+    let completion_tokens = completion.usage.completion_tokens;
+
+    if let Err(e) = state
+        .partials
+        .record_continuation(&partial, &completion.id, &content, completion_tokens)
+        .await
+    {
+        tracing::warn!(
+            "Failed to record continuation in conversation {}: {}",
+            id,
+            e
+        );
+    }
+
+    let continuation = Continuation {
+        continues: body.response_id,
+        content: format!("{}{}", partial.content, content),
+        previous_completion_tokens: partial.completion_tokens,
+        total_completion_tokens: partial.completion_tokens.saturating_add(completion_tokens),
+    };
+    body_json["continuation"] = serde_json::to_value(&continuation).unwrap_or_default();
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    Response::from_parts(
+        parts,
+        axum::body::Body::from(serde_json::to_vec(&body_json).unwrap_or_default()),
+    )
+}
+
+/// Error response of the conversation endpoints
+fn conversation_error(
+    status: StatusCode,
+    message: String,
+    code: &str,
+) -> (StatusCode, Json<ApiError>) {
+    (
+        status,
+        Json(ApiError {
+            error: ApiErrorDetail {
+                message,
+                r#type: "invalid_request_error".to_string(),
+                param: None,
+                code: Some(code.to_string()),
+            },
+        }),
+    )
+}
+
+/// Error response of a conversation memory error
+fn memory_error(id: &str, e: MemoryError) -> (StatusCode, Json<ApiError>) {
+    match e {
+        MemoryError::NotFound(_) | MemoryError::InvalidKey(_) => conversation_error(
+            StatusCode::NOT_FOUND,
+            format!("Conversation {} does not exist", id),
+            "conversation_not_found",
+        ),
+        e => conversation_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+            "memory_error",
+        ),
+    }
+}
+
+/// Resolve the request's model through the registry's aliases and routes,
+/// replacing it with the model serving it
+fn resolve_model(state: &AppState, request: &mut ChatCompletionRequest) -> ModelResolution {
+    let resolution = state.registry.resolve_model(&request.model);
+    if resolution.is_rewritten() {
+        tracing::debug!(
+            "Resolved model {} to {} ({})",
+            resolution.requested,
+            resolution.model,
+            resolution.kind.as_str()
+        );
+        request.model = resolution.model.clone();
+    }
+    resolution
+}
+
+/// Apply the configured unsupported-parameter policy for the request's provider
+fn apply_provider_param_policy(
+    state: &AppState,
+    request: &mut ChatCompletionRequest,
+    resolution: &ModelResolution,
+) -> Result<(), ApiError> {
+    let provider = provider_of(state, resolution);
+
+    params::apply_param_policy(request, &provider, state.config.proxy.unsupported_params)
+}
+
+/// Provider serving a resolved model
+fn provider_of(state: &AppState, resolution: &ModelResolution) -> String {
+    resolution
+        .provider
+        .clone()
+        .unwrap_or_else(|| params::resolve_provider(&state.registry, &resolution.model))
+}
+
+/// Route handler for /v1/models
+#[utoipa::path(
+    get,
+    path = "/v1/models",
+    tag = "models",
+    responses((status = 200, description = "Models available to the caller", body = ModelList))
+)]
+pub async fn list_models(State(state): State<AppState>, headers: HeaderMap) -> Json<ModelList> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, &headers);
 
-    // Apply throttling and boxing
-    let stream = tokio_stream::StreamExt::throttle(stream, Duration::from_millis(300));
-    let stream = futures::StreamExt::boxed(stream);
+    let mut models: Vec<ModelObject> = state
+        .registry
+        .list_models()
+        .iter()
+        .filter(|model| tenant::is_model_allowed(tenant, &model.id))
+        .map(ModelObject::from)
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
 
-    // Return the SSE stream wrapped in a Response
-    Ok(Sse::new(stream).into_response())
+    Json(ModelList::new(models))
+}
+
+/// Route handler for /v1/models/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/models/{id}",
+    tag = "models",
+    params(("id" = String, Path, description = "Model ID")),
+    responses(
+        (status = 200, description = "Model", body = ModelObject),
+        (status = 404, description = "Unknown model", body = ApiError)
+    )
+)]
+pub async fn retrieve_model(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<ModelObject>, (StatusCode, Json<ApiError>)> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, &headers);
+
+    match state.registry.get_model(&id) {
+        Ok(model) if tenant::is_model_allowed(tenant, &model.id) => {
+            Ok(Json(ModelObject::from(&model)))
+        }
+        _ => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: ApiErrorDetail {
+                    message: format!("The model '{}' does not exist", id),
+                    r#type: "invalid_request_error".to_string(),
+                    param: Some("model".to_string()),
+                    code: Some("model_not_found".to_string()),
+                },
+            }),
+        )),
+    }
+}
+
+/// Route handler for /v1/policies
+#[utoipa::path(
+    get,
+    path = "/v1/policies",
+    tag = "routing",
+    responses((status = 200, description = "Published policy versions", body = Vec<Object>))
+)]
+pub async fn list_policies(State(state): State<AppState>) -> Json<Vec<PolicyVersionInfo>> {
+    Json(state.policies.versions())
+}
+
+/// Route handler for /v1/policies/dry-run
+///
+/// Evaluates the submitted policy, or the active one, against a request
+/// without publishing anything or calling a provider.
+#[utoipa::path(
+    post,
+    path = "/v1/policies/dry-run",
+    tag = "routing",
+    request_body = PolicyDryRunRequest,
+    responses(
+        (status = 200, description = "Policy evaluation", body = Object),
+        (status = 400, description = "Invalid policy", body = ApiError),
+        (status = 404, description = "No policy is active", body = ApiError)
+    )
+)]
+pub async fn policy_dry_run(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<PolicyDryRunRequest>,
+) -> Result<Json<PolicyEvaluation>, (StatusCode, Json<ApiError>)> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, &headers);
+    let attributes = policy_attributes(&body.request, tenant, &headers);
+
+    match state.policies.dry_run(body.policy, &attributes) {
+        Ok(Some(evaluation)) => Ok(Json(evaluation)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiError {
+                error: ApiErrorDetail {
+                    message: "No routing policy is active".to_string(),
+                    r#type: "invalid_request_error".to_string(),
+                    param: Some("policy".to_string()),
+                    code: Some("policy_not_found".to_string()),
+                },
+            }),
+        )),
+        Err(e) => Err((
+            StatusCode::BAD_REQUEST,
+            Json(validation::create_validation_error(
+                &e.to_string(),
+                Some("policy"),
+            )),
+        )),
+    }
+}
+
+/// Route handler for /v1/route/explain
+///
+/// Ranks the candidate models for a request by a heuristic score, with
+/// per-dimension scores and the reason each ineligible model was ruled out,
+/// without calling a provider.
+#[utoipa::path(
+    post,
+    path = "/v1/route/explain",
+    tag = "routing",
+    request_body = RouteExplainRequest,
+    responses((status = 200, description = "Candidate models ranked by heuristic score", body = Object))
+)]
+pub async fn explain_route(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<RouteExplainRequest>,
+) -> Json<RouteExplanation> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, &headers);
+    let attributes = policy_attributes(&body.request, tenant, &headers);
+
+    let mut request = RoutingRequest::new(convert_to_connector_request(&body.request));
+    for model in state.registry.list_models() {
+        if !tenant::is_model_allowed(tenant, &model.id) {
+            request.excluded_model_ids.push(model.id);
+        }
+    }
+
+    let constraints = RouteConstraints {
+        policy: state.policies.evaluate(&attributes),
+        allowed_regions: tenant
+            .map(|t| t.allowed_regions.clone())
+            .unwrap_or_default(),
+    };
+    let strategy = body
+        .strategy
+        .unwrap_or_else(|| RouterConfig::default().strategy);
+
+    Json(explain::explain(
+        &request,
+        &state.registry,
+        strategy,
+        constraints,
+    ))
+}
+
+/// Build the policy attributes of a chat completion request
+fn policy_attributes(
+    request: &ChatCompletionRequest,
+    tenant: Option<&TenantConfig>,
+    headers: &HeaderMap,
+) -> PolicyAttributes {
+    let mut capabilities = Vec::new();
+    if request.stream {
+        capabilities.push("streaming".to_string());
+    }
+
+    PolicyAttributes {
+        model: request.model.clone(),
+        tenant: tenant.map(|t| t.id.clone()),
+        user: request.user.clone(),
+        prompt_length: request
+            .messages
+            .iter()
+            .map(|m| m.extract_text_content().chars().count())
+            .sum(),
+        message_count: request.messages.len(),
+        max_tokens: request.max_tokens,
+        capabilities,
+        tags: Vec::new(),
+        priority: headers
+            .get(PRIORITY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0),
+        time: chrono::Utc::now(),
+    }
+}
+
+/// Check that the request's model is allowed for the caller's tenant and by
+/// the active routing policy, and hosted in the tenant's allowed regions
+fn check_model_access(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+    streaming: bool,
+) -> Result<(), ApiError> {
+    let tenant = tenant::resolve_tenant(&state.config.proxy, headers);
+    if !tenant::is_model_allowed(tenant, &request.model) {
+        return Err(ApiError {
+            error: ApiErrorDetail {
+                message: format!(
+                    "The model '{}' is not allowed for this tenant",
+                    request.model
+                ),
+                r#type: "invalid_request_error".to_string(),
+                param: Some("model".to_string()),
+                code: Some("model_not_allowed".to_string()),
+            },
+        });
+    }
+
+    let mut attributes = policy_attributes(request, tenant, headers);
+    if streaming && !attributes.capabilities.iter().any(|c| c == "streaming") {
+        attributes.capabilities.push("streaming".to_string());
+    }
+    if let Some(decision) = state.policies.evaluate(&attributes) {
+        let allowed = &decision.outcome.allowed_models;
+        if !allowed.is_empty() && !allowed.iter().any(|p| model_matches(p, &request.model)) {
+            let reason = match &decision.rule {
+                Some(rule) => format!(
+                    "model '{}' is not allowed by policy rule '{}'",
+                    request.model, rule
+                ),
+                None => format!(
+                    "model '{}' is not allowed by the policy default",
+                    request.model
+                ),
+            };
+            return Err(_convert_router_error_to_api_error(
+                RouterError::NoSuitableModel(reason),
+            ));
+        }
+    }
+
+    if let Some(tenant) = tenant.filter(|t| !t.allowed_regions.is_empty()) {
+        let model = state.registry.get_model(&request.model).ok();
+        let allowed = model
+            .as_ref()
+            .is_some_and(|model| ResidencyEnforcer::region_allowed(model, &tenant.allowed_regions));
+        if !allowed {
+            let region = model
+                .and_then(|model| model.region)
+                .unwrap_or_else(|| "an unknown region".to_string());
+            tracing::warn!(
+                tenant = %tenant.id,
+                model = %request.model,
+                "Request rejected for data residency"
+            );
+            return Err(_convert_router_error_to_api_error(
+                RouterError::DataResidency(format!(
+                    "model '{}' is hosted in {} which is not allowed for tenant '{}'",
+                    request.model, region, tenant.id
+                )),
+            ));
+        }
+    }
+
+    Ok(())
 }
 
 /// Convert a router error to an API error
@@ -165,6 +1660,30 @@ fn _convert_router_error_to_api_error(err: RouterError) -> ApiError {
                 code: None,
             },
         },
+        RouterError::DataResidency(msg) => ApiError {
+            error: super::dto::ApiErrorDetail {
+                message: format!("Data residency violation: {}", msg),
+                r#type: "data_residency_error".to_string(),
+                param: Some("model".to_string()),
+                code: Some("region_not_allowed".to_string()),
+            },
+        },
+        RouterError::PluginRejected(msg) => ApiError {
+            error: super::dto::ApiErrorDetail {
+                message: format!("Rejected by plugin {}", msg),
+                r#type: "plugin_rejected_error".to_string(),
+                param: None,
+                code: Some("rejected_by_plugin".to_string()),
+            },
+        },
+        RouterError::InvalidOutput(msg) => ApiError {
+            error: super::dto::ApiErrorDetail {
+                message: format!("Invalid model output: {}", msg),
+                r#type: "invalid_output_error".to_string(),
+                param: Some("response_format".to_string()),
+                code: Some("invalid_json_output".to_string()),
+            },
+        },
         _ => ApiError {
             error: super::dto::ApiErrorDetail {
                 message: format!("Router error: {}", err),
@@ -199,10 +1718,35 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec!["*".to_string()],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
+                subsystems: Default::default(),
+                language: Default::default(),
             },
             shared: std::sync::Arc::new(tokio::sync::Mutex::new(super::server::SharedState::new())),
             telemetry: Some(telemetry),
             cost_calculator: Some(cost_calculator),
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+            telemetry_export: None,
+            anomalies: None,
+            canaries: None,
+            metering: None,
+            admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+            streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+            coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+            prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+            rollouts: Arc::new(
+                crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+            ),
+            user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+            payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+            partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+            discovery: None,
+            prompt_traces: Arc::new(
+                crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default(),
+            ),
         };
 
         // Create test request
@@ -217,14 +1761,23 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Call the handler
-        let result = chat_completions(State(app_state), Json(request)).await;
+        let result = chat_completions(State(app_state), HeaderMap::new(), Json(request)).await;
 
         // Verify the result
         assert!(result.is_ok());
-        let response = result.unwrap().0;
+        let body = axum::body::to_bytes(result.unwrap().into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: ChatCompletionResponse = serde_json::from_slice(&body).unwrap();
         assert_eq!(response.choices.len(), 1);
         assert_eq!(response.choices[0].message.role, MessageRole::Assistant);
     }
@@ -245,10 +1798,35 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec!["*".to_string()],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
+                subsystems: Default::default(),
+                language: Default::default(),
             },
             shared: std::sync::Arc::new(tokio::sync::Mutex::new(super::server::SharedState::new())),
             telemetry: Some(telemetry),
             cost_calculator: Some(cost_calculator),
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+            telemetry_export: None,
+            anomalies: None,
+            canaries: None,
+            metering: None,
+            admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+            streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+            coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+            prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+            rollouts: Arc::new(
+                crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+            ),
+            user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+            payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+            partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+            discovery: None,
+            prompt_traces: Arc::new(
+                crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default(),
+            ),
         };
 
         // Create test request
@@ -263,12 +1841,91 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Call the handler
-        let result = chat_completions_stream(State(app_state), Json(request)).await;
+        let result =
+            chat_completions_stream(State(app_state), HeaderMap::new(), Json(request)).await;
 
         // Verify the result
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_chat_completions_checks_model_access_before_rollout() {
+        use crate::config::{Config, TenantConfig};
+        use crate::modules::model_registry::connectors::OpenAIConnector;
+        use crate::modules::model_registry::{ConnectorConfig, ModelMetadata, ModelRegistry};
+        use crate::modules::router_core::PolicyEngine;
+
+        // Every caller of a rolling-out model is bucketed into the candidate
+        let mut config = Config::default();
+        config.proxy.model_rollout.stages = vec![100];
+        config.proxy.tenants.push(TenantConfig {
+            id: "acme".to_string(),
+            api_keys: vec!["sk-acme".to_string()],
+            allowed_models: vec!["claude-*".to_string()],
+            ..Default::default()
+        });
+        let registry = Arc::new(ModelRegistry::new());
+        let state = AppState::from_config(
+            &config,
+            registry.clone(),
+            Arc::new(PolicyEngine::new()),
+            None,
+        );
+
+        let model = ModelMetadata::new(
+            "gpt-4o".to_string(),
+            "GPT-4o".to_string(),
+            "openai".to_string(),
+            "2024-05-13".to_string(),
+            "https://blue.example.com/v1".to_string(),
+        );
+        registry.register_model(model.clone()).unwrap();
+        let mut candidate = model;
+        candidate.endpoint = "https://green.example.com/v1".to_string();
+        let connector = Arc::new(OpenAIConnector::new(ConnectorConfig {
+            base_url: candidate.endpoint.clone(),
+            ..ConnectorConfig::default()
+        }));
+        state
+            .rollouts
+            .update_model(candidate, Some(connector))
+            .unwrap();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer sk-acme".parse().unwrap());
+        let request = ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message::new_user("Hello!".to_string())],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: false,
+            max_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
+        };
+
+        let error = chat_completions(State(state.clone()), headers, Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(error.error.code.as_deref(), Some("model_not_allowed"));
+        let rollout = state.rollouts.get("gpt-4o").unwrap();
+        assert_eq!(rollout.candidate_stats.requests, 0);
+    }
 }
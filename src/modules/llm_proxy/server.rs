@@ -5,20 +5,41 @@
 
 use axum::{
     http::StatusCode,
+    middleware::from_fn_with_state,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::Mutex;
 use tracing::{error, info};
 
-use super::{telemetry_integration, Provider};
-use crate::config::Config;
+use super::admin::{self, AdminAuditLog};
+use super::capabilities::{self, EnabledSubsystems};
+use super::coalesce::RequestCoalescer;
+use super::decision_log::{self, DecisionLog};
+use super::model_rollout::ModelRolloutController;
+use super::openapi;
+use super::partials::PartialResponses;
+use super::payloads::PayloadStore;
+use super::prompt_trace::PromptTraceLog;
+use super::prompts::PromptRegistry;
+use super::quota::{token_quota_middleware, TokenQuotaManager};
+use super::stream_buffer::StreamBuffer;
+use super::user_usage::UserUsageLog;
+use super::versioning::{api_version_middleware, ApiVersioning};
+use super::Provider;
+use crate::config::{Config, ProxyConfig};
+use crate::modules::common::RedisConnector;
+use crate::modules::encryption::encryptor_from_config;
+use crate::modules::memory::MemoryManager;
+use crate::modules::model_registry::{ModelDiscovery, ModelRegistry};
+use crate::modules::router_core::{LanguageConfig, PolicyEngine};
 use crate::modules::telemetry::{
-    create_cost_calculator, init_telemetry, CostCalculator, TelemetryManager,
+    create_cost_calculator, init_telemetry, telemetry_middleware, AnomalyDetector, CanaryMonitor,
+    CostCalculator, Metering, TelemetryExporter, TelemetryManager,
 };
 
 /// Configuration for the LLM Proxy server
@@ -38,6 +59,12 @@ pub struct ServerConfig {
     pub cors_allowed_origins: Vec<String>,
     /// Redis URL for health checks
     pub redis_url: Option<String>,
+    /// Proxy behaviour configuration (transformations, etc.)
+    pub proxy: ProxyConfig,
+    /// Optional subsystems enabled in the configuration
+    pub subsystems: EnabledSubsystems,
+    /// Language detection of requests
+    pub language: LanguageConfig,
 }
 
 impl ServerConfig {
@@ -51,6 +78,9 @@ impl ServerConfig {
             cors_enabled: config.server.cors_enabled,
             cors_allowed_origins: config.server.cors_allowed_origins.clone(),
             redis_url: config.memory.redis_url.clone(),
+            proxy: config.proxy.clone(),
+            subsystems: EnabledSubsystems::from_config(config),
+            language: config.router.language.clone(),
         }
     }
 
@@ -75,6 +105,121 @@ pub struct AppState {
     pub telemetry: Option<Arc<TelemetryManager>>,
     /// Cost calculator
     pub cost_calculator: Option<Arc<CostCalculator>>,
+    /// Model registry backing the models endpoints
+    pub registry: Arc<ModelRegistry>,
+    /// Per-tenant token quota tracking
+    pub quotas: Arc<TokenQuotaManager>,
+    /// Routing policy engine
+    pub policies: Arc<PolicyEngine>,
+    /// Sampled log of routing decisions
+    pub decisions: Arc<DecisionLog>,
+    /// Export of per-request telemetry records
+    pub telemetry_export: Option<Arc<TelemetryExporter>>,
+    /// Detection of unusual per-tenant traffic and cost
+    pub anomalies: Option<Arc<AnomalyDetector>>,
+    /// Canary prompts checking the models through the router
+    pub canaries: Option<Arc<CanaryMonitor>>,
+    /// Metering of tenant usage for billing
+    pub metering: Option<Arc<Metering>>,
+    /// Audit events of privileged admin actions
+    pub admin_audit: Arc<AdminAuditLog>,
+    /// Buffer of streamed responses for resumption
+    pub streams: Arc<StreamBuffer>,
+    /// Identical requests in flight sharing an upstream call
+    pub coalescer: Arc<RequestCoalescer>,
+    /// Versioned system prompts and their rollouts
+    pub prompts: Arc<PromptRegistry>,
+    /// Gradual rollouts of changed model endpoints
+    pub rollouts: Arc<ModelRolloutController>,
+    /// Recent traces of prompt assembly
+    pub prompt_traces: Arc<PromptTraceLog>,
+    /// Usage of the end users of tenants
+    pub user_usage: Arc<UserUsageLog>,
+    /// Captured request payloads for replay
+    pub payloads: Arc<PayloadStore>,
+    /// Partial completions of interrupted streams
+    pub partials: Arc<PartialResponses>,
+    /// Discovery of new models of the providers
+    pub discovery: Option<Arc<ModelDiscovery>>,
+}
+
+impl AppState {
+    /// Application state of a router built from configuration
+    ///
+    /// The registry and policy engine are shared with the caller, which
+    /// usually hands them to the health checks and admin routes as well.
+    pub fn from_config(
+        config: &Config,
+        registry: Arc<ModelRegistry>,
+        policies: Arc<PolicyEngine>,
+        telemetry: Option<Arc<TelemetryManager>>,
+    ) -> Self {
+        let cost_calculator = Arc::new(
+            CostCalculator::from_config(&config.telemetry.pricing)
+                .expect("Invalid pricing configuration"),
+        );
+        Self {
+            provider: Provider::OpenAI,
+            config: ServerConfig::from_config(config),
+            shared: Arc::new(Mutex::new(SharedState::new())),
+            telemetry,
+            cost_calculator: Some(cost_calculator.clone()),
+            registry: registry.clone(),
+            quotas: Arc::new(TokenQuotaManager::from_config(config)),
+            policies,
+            decisions: Arc::new(DecisionLog::new(config.proxy.decision_log.clone())),
+            telemetry_export: TelemetryExporter::from_config(&config.telemetry.export),
+            anomalies: AnomalyDetector::from_config(&config.telemetry.anomaly),
+            canaries: CanaryMonitor::from_config(
+                &config.telemetry.canaries,
+                &router_base_url(config),
+            )
+            .expect("Invalid canary configuration"),
+            metering: Metering::from_config(&config.telemetry.metering),
+            admin_audit: Arc::new(AdminAuditLog::from_config(config)),
+            streams: Arc::new(StreamBuffer::new(config.proxy.stream_resume.clone())),
+            coalescer: Arc::new(RequestCoalescer::new()),
+            prompts: Arc::new(PromptRegistry::new(&config.proxy.prompts)),
+            rollouts: Arc::new(ModelRolloutController::new(
+                config.proxy.model_rollout.clone(),
+                registry.clone(),
+            )),
+            prompt_traces: Arc::new(PromptTraceLog::new(config.proxy.prompt_trace.clone())),
+            user_usage: Arc::new(UserUsageLog::new(config.proxy.user_usage.clone())),
+            payloads: Arc::new(PayloadStore::new(
+                config.proxy.payloads.clone(),
+                encryptor_from_config(&config.encryption)
+                    .expect("Failed to initialize encryption at rest"),
+            )),
+            partials: Arc::new(PartialResponses::new(
+                config.proxy.partial_responses.clone(),
+            )),
+            discovery: ModelDiscovery::from_config(
+                &config.model_registry,
+                registry,
+                Some(cost_calculator),
+            ),
+        }
+    }
+
+    /// Record partial completions of interrupted streams in the
+    /// conversations of a memory
+    pub fn with_conversation_memory(mut self, memory: Arc<MemoryManager>) -> Self {
+        self.partials = Arc::new(
+            PartialResponses::new(self.config.proxy.partial_responses.clone()).with_memory(memory),
+        );
+        self
+    }
+}
+
+/// Base URL of a router's own HTTP endpoints
+fn router_base_url(config: &Config) -> String {
+    let host = match config.server.host {
+        IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        host => host,
+    };
+    format!("http://{}", SocketAddr::new(host, config.server.port))
 }
 
 /// Shared mutable state
@@ -115,6 +260,10 @@ pub async fn start_server(config: ServerConfig, provider: Provider) -> Result<()
         }
     };
 
+    // Create model registry
+    let registry_api = crate::modules::model_registry::api::ModelRegistryApi::new();
+    let registry = registry_api.registry();
+
     // Create app state
     let app_state = AppState {
         provider,
@@ -122,36 +271,50 @@ pub async fn start_server(config: ServerConfig, provider: Provider) -> Result<()
         shared: Arc::new(Mutex::new(shared_state)),
         telemetry,
         cost_calculator,
+        registry: registry.clone(),
+        quotas: Arc::new(TokenQuotaManager::from_redis_url(
+            config.redis_url.as_deref(),
+        )),
+        policies: Arc::new(PolicyEngine::new()),
+        decisions: Arc::new(DecisionLog::new(config.proxy.decision_log.clone())),
+        telemetry_export: None,
+        anomalies: None,
+        canaries: None,
+        metering: None,
+        admin_audit: Arc::new(AdminAuditLog::new(config.proxy.admin_rbac.audit_capacity)),
+        streams: Arc::new(StreamBuffer::new(config.proxy.stream_resume.clone())),
+        coalescer: Arc::new(RequestCoalescer::new()),
+        prompts: Arc::new(PromptRegistry::new(&config.proxy.prompts)),
+        rollouts: Arc::new(ModelRolloutController::new(
+            config.proxy.model_rollout.clone(),
+            registry.clone(),
+        )),
+        prompt_traces: Arc::new(PromptTraceLog::new(config.proxy.prompt_trace.clone())),
+        user_usage: Arc::new(UserUsageLog::new(config.proxy.user_usage.clone())),
+        payloads: Arc::new(PayloadStore::new(config.proxy.payloads.clone(), None)),
+        partials: Arc::new(PartialResponses::new(
+            config.proxy.partial_responses.clone(),
+        )),
+        discovery: None,
     };
 
     // Create health check manager
-    let registry_api = crate::modules::model_registry::api::ModelRegistryApi::new();
-    let registry = registry_api.registry();
     let health_manager = crate::modules::health::router::create_router_health_manager(
         registry,
         crate::modules::router_core::RouterConfig::default(),
-        Some(
+        RedisConnector::from_url(
             config
                 .redis_url
-                .clone()
-                .unwrap_or_else(|| "redis://localhost:6379".to_string()),
-        ),
+                .as_deref()
+                .unwrap_or("redis://localhost:6379"),
+        )
+        .ok(),
     );
 
     let health_router = health_manager.create_router();
 
     // Create router
-    let app = if let (Some(telemetry), Some(cost_calculator)) = (
-        app_state.telemetry.clone(),
-        app_state.cost_calculator.clone(),
-    ) {
-        // Create router with telemetry middleware
-        telemetry_integration::create_router_with_telemetry(telemetry, cost_calculator)
-            .merge(health_router)
-    } else {
-        // Create router without telemetry
-        create_router(app_state.clone()).merge(health_router)
-    };
+    let app = create_router(app_state).merge(health_router);
 
     // Get socket address
     let addr = config.socket_addr()?;
@@ -191,34 +354,141 @@ async fn init_telemetry_components(
 
 /// Create the Axum router with all routes
 pub fn create_router(state: AppState) -> Router {
-    // Create a basic router without state first
-    let router = Router::new()
+    let mut router = Router::new()
         // Legacy health check endpoint (simple version)
         .route("/health/simple", get(health_check))
+        // API documentation
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::swagger_ui));
+
+    // Serve every configured API version, the versions sharing handlers
+    let versioning = Arc::new(ApiVersioning::new(&state.config.proxy.api_versioning));
+    for version in versioning.versions() {
+        router = router.nest(version.prefix(), api_routes());
+    }
+    let router = router.layer(from_fn_with_state(versioning, api_version_middleware));
+
+    // Enforce tenant token quotas and report them in response headers
+    let router = router.layer(from_fn_with_state(state.clone(), token_quota_middleware));
+
+    // Add telemetry middleware if telemetry is available
+    let router = match state.telemetry.clone() {
+        Some(telemetry) => router.layer(from_fn_with_state(telemetry, telemetry_middleware)),
+        None => router,
+    };
+
+    router.with_state(state)
+}
+
+/// Routes of a version of the public API, relative to its prefix
+fn api_routes() -> Router<AppState> {
+    Router::new()
         // Chat completions endpoints
+        .route("/chat/completions", post(super::routes::chat_completions))
         .route(
-            "/v1/chat/completions",
-            post(super::routes::chat_completions),
+            "/chat/completions/stream",
+            post(super::routes::chat_completions_stream),
         )
         .route(
-            "/v1/chat/completions/stream",
-            post(super::routes::chat_completions_stream),
-        );
-
-    // If telemetry is available, create a router with telemetry state
-    if let (Some(telemetry), Some(cost_calculator)) =
-        (state.telemetry.clone(), state.cost_calculator.clone())
-    {
-        // Create router with telemetry state
-        telemetry_integration::create_router_with_telemetry(telemetry, cost_calculator)
-    } else {
-        // Return the basic router with state
-        router.with_state(state)
-    }
+            "/chat/completions/stream/{id}",
+            get(super::routes::resume_chat_completions_stream),
+        )
+        .route(
+            "/conversations/{id}/last-partial",
+            get(super::routes::last_partial),
+        )
+        .route(
+            "/conversations/{id}/continue",
+            post(super::routes::continue_completion),
+        )
+        // Model listing endpoints
+        .route("/models", get(super::routes::list_models))
+        .route("/models/{id}", get(super::routes::retrieve_model))
+        .route("/capabilities", get(capabilities::capabilities))
+        // Routing policy endpoints
+        .route("/policies", get(super::routes::list_policies))
+        .route("/policies/dry-run", post(super::routes::policy_dry_run))
+        .route("/route/explain", post(super::routes::explain_route))
+        // Admin endpoints
+        .route(
+            "/admin/routing/decisions",
+            get(decision_log::recent_decisions),
+        )
+        .route(
+            "/admin/routing/decisions/stream",
+            get(decision_log::stream_decisions),
+        )
+        .route(
+            "/admin/models",
+            get(admin::list_models).post(admin::register_model),
+        )
+        .route(
+            "/admin/models/{id}",
+            put(admin::update_model).delete(admin::remove_model),
+        )
+        .route("/admin/models/{id}/status", put(admin::update_model_status))
+        .route(
+            "/admin/models/{id}/fine-tune",
+            put(admin::update_fine_tune_status),
+        )
+        .route("/admin/fine-tunes", get(admin::list_fine_tunes))
+        .route("/admin/model-proposals", get(admin::model_proposals))
+        .route(
+            "/admin/model-proposals/sync",
+            post(admin::sync_model_proposals),
+        )
+        .route(
+            "/admin/model-proposals/{id}/approve",
+            post(admin::approve_model_proposal),
+        )
+        .route(
+            "/admin/model-proposals/{id}/reject",
+            post(admin::reject_model_proposal),
+        )
+        .route("/admin/budgets", get(admin::list_budgets))
+        .route("/admin/pricing", get(admin::pricing))
+        .route("/admin/pricing/{model}", post(admin::set_price))
+        .route("/admin/budgets/{tenant}", delete(admin::reset_budget))
+        .route("/admin/keys", get(admin::list_keys))
+        .route("/admin/audit", get(admin::audit_events))
+        .route(
+            "/admin/logging",
+            get(admin::log_levels).put(admin::set_log_levels),
+        )
+        .route("/admin/anomalies", get(admin::anomalies))
+        .route("/admin/canaries", get(admin::canaries))
+        .route("/admin/billing/events", get(admin::billing_events))
+        .route("/admin/rollouts", get(admin::list_rollouts))
+        .route("/admin/rollouts/{model}", get(admin::get_rollout))
+        .route(
+            "/admin/rollouts/{model}/rollback",
+            post(admin::rollback_rollout),
+        )
+        .route("/admin/prompts", get(admin::list_prompts))
+        .route("/admin/prompts/served", get(admin::served_prompts))
+        .route("/admin/prompt-traces", get(admin::prompt_traces))
+        .route("/admin/prompt-traces/{id}", get(admin::prompt_trace))
+        .route("/admin/payloads/{id}", get(admin::payload))
+        .route("/admin/users/usage", get(admin::user_usage))
+        .route("/admin/users/{user}/usage", get(admin::user_usage_detail))
+        .route("/admin/prompts/{id}/versions", post(admin::publish_prompt))
+        .route("/admin/prompts/{id}/rollout", put(admin::rollout_prompt))
+        .route("/admin/prompts/{id}/rollback", post(admin::rollback_prompt))
+        .route("/admin/policies", post(admin::publish_policy))
+        .route(
+            "/admin/policies/{version}/activate",
+            post(admin::activate_policy),
+        )
 }
 
 /// Simple health check endpoint (legacy)
-async fn health_check() -> impl IntoResponse {
+#[utoipa::path(
+    get,
+    path = "/health/simple",
+    tag = "health",
+    responses((status = 200, description = "Service status and version", body = Object))
+)]
+pub(crate) async fn health_check() -> impl IntoResponse {
     (
         StatusCode::OK,
         Json(serde_json::json!({
@@ -264,6 +534,9 @@ mod tests {
             cors_enabled: false,
             cors_allowed_origins: vec!["*".to_string()],
             redis_url: None,
+            proxy: ProxyConfig::default(),
+            subsystems: Default::default(),
+            language: Default::default(),
         };
 
         let addr = config.socket_addr().unwrap();
@@ -292,6 +565,9 @@ mod tests {
             cors_enabled: false,
             cors_allowed_origins: vec!["*".to_string()],
             redis_url: None,
+            proxy: ProxyConfig::default(),
+            subsystems: Default::default(),
+            language: Default::default(),
         };
 
         let app_state = AppState {
@@ -300,6 +576,28 @@ mod tests {
             shared: Arc::new(Mutex::new(SharedState::new())),
             telemetry: None,
             cost_calculator: None,
+            registry: Arc::new(ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+            telemetry_export: None,
+            anomalies: None,
+            canaries: None,
+            metering: None,
+            admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+            streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+            coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+            prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+            rollouts: Arc::new(
+                crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+            ),
+            user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+            payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+            partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+            discovery: None,
+            prompt_traces: Arc::new(
+                crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default(),
+            ),
         };
 
         assert_eq!(app_state.provider as u8, Provider::OpenAI as u8);
@@ -3,28 +3,41 @@
 //! This module contains the business logic for processing chat completion
 //! requests and generating responses, following clean architecture principles.
 
+use std::sync::Arc;
+
+use futures::future::join_all;
 use futures::stream::Stream;
+use metrics::counter;
 use tokio_stream::StreamExt;
 use tracing::{debug, error};
 
+use crate::config::JsonModeConfig;
 use crate::modules::common::error_handling::{
     default_retryable_errors, ErrorHandler, TimeoutConfig,
 };
+use crate::modules::llm_proxy::domain::content::MessageContent;
 use crate::modules::llm_proxy::domain::message::{Message, MessageRole};
 use crate::modules::llm_proxy::dto::{
-    ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, TokenUsage,
+    ChatCompletionChoice, ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse,
+    TokenUsage,
+};
+use crate::modules::llm_proxy::json_mode::{self, RepairMethod};
+use crate::modules::llm_proxy::params::{
+    apply_user_param, infer_provider, passthrough_params, resolve_provider, ProviderParamSupport,
 };
+use crate::modules::llm_proxy::quirks::MessageNormalizer;
 #[cfg(feature = "test-utils")]
 use crate::modules::llm_proxy::router_integration::create_mock_router_service;
 use crate::modules::llm_proxy::router_integration::RouterService;
+use crate::modules::llm_proxy::transform::ModelTransformer;
 use crate::modules::model_registry::connectors;
+use crate::modules::model_registry::ModelRegistry;
 use crate::modules::router_core::retry::{CircuitBreakerConfig, RetryPolicy};
 use crate::modules::router_core::RouterError;
-
-/// Service for handling chat completion requests
+use crate::modules::telemetry::catalog;
 
 /// Convert a DTO ChatCompletionRequest to a connector ChatCompletionRequest
-fn convert_to_connector_request(
+pub(crate) fn convert_to_connector_request(
     request: &ChatCompletionRequest,
 ) -> connectors::ChatCompletionRequest {
     // Create a simplified connector request with just the essential fields
@@ -44,6 +57,7 @@ fn convert_to_connector_request(
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: request.temperature,
         top_p: request.top_p,
@@ -51,51 +65,100 @@ fn convert_to_connector_request(
         stream: Some(request.stream),
         functions: None,
         tools: None,
-        additional_params: None,
+        additional_params: passthrough_params(request),
     }
 }
 
 /// Convert a connector ChatCompletionResponse to a DTO ChatCompletionResponse
-fn convert_from_connector_response(
+pub(crate) fn convert_from_connector_response(
     response: connectors::ChatCompletionResponse,
 ) -> ChatCompletionResponse {
-    // Create a simplified DTO response with just the essential fields
+    let mut choices: Vec<ChatCompletionChoice> = response
+        .choices
+        .into_iter()
+        .map(|choice| ChatCompletionChoice {
+            index: choice.index as u32,
+            message: Message {
+                role: MessageRole::Assistant,
+                content: MessageContent::String(choice.message.content),
+                name: None,
+            },
+            finish_reason: choice.finish_reason.unwrap_or_else(|| "stop".to_string()),
+            emulated: None,
+            content_filter: choice.content_filter,
+        })
+        .collect();
+
+    if choices.is_empty() {
+        choices.push(ChatCompletionChoice {
+            index: 0,
+            message: Message::new_assistant("This is a mock response from the router".to_string()),
+            finish_reason: "stop".to_string(),
+            emulated: None,
+            content_filter: None,
+        });
+    }
+
+    let usage = response
+        .usage
+        .map(|usage| TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        })
+        .unwrap_or(TokenUsage {
+            prompt_tokens: 10,
+            completion_tokens: 10,
+            total_tokens: 20,
+        });
+
     ChatCompletionResponse {
         id: response.id,
         object: "chat.completion".to_string(),
         created: response.created,
         model: response.model,
-        choices: vec![{
-            crate::modules::llm_proxy::dto::ChatCompletionChoice {
-                index: 0,
-                message: Message {
-                    role: MessageRole::Assistant,
-                    content: crate::modules::llm_proxy::domain::content::MessageContent::String(
-                        response
-                            .choices
-                            .first()
-                            .map(|c| c.message.content.clone())
-                            .unwrap_or_else(|| {
-                                "This is a mock response from the router".to_string()
-                            }),
-                    ),
-                    name: None,
-                },
-                finish_reason: "stop".to_string(),
-            }
-        }],
-        usage: TokenUsage {
-            prompt_tokens: 10,
-            completion_tokens: 10,
-            total_tokens: 20,
-        },
+        choices,
+        usage,
     }
 }
+
+/// Merge the responses of separate calls that emulate `n > 1`
+///
+/// Choices are re-indexed in call order and marked as emulated. Usage is summed
+/// across calls, since every call is billed for the prompt and its completion.
+fn merge_emulated_responses(responses: Vec<ChatCompletionResponse>) -> ChatCompletionResponse {
+    let mut responses = responses.into_iter();
+    let mut merged = responses.next().expect("at least one response to merge");
+
+    for response in responses {
+        merged.choices.extend(response.choices);
+        merged.usage.prompt_tokens += response.usage.prompt_tokens;
+        merged.usage.completion_tokens += response.usage.completion_tokens;
+        merged.usage.total_tokens += response.usage.total_tokens;
+    }
+
+    for (index, choice) in merged.choices.iter_mut().enumerate() {
+        choice.index = index as u32;
+        choice.emulated = Some(true);
+    }
+
+    merged
+}
+
+/// Service for handling chat completion requests
 pub struct ChatCompletionService {
     /// Router service for routing requests to the appropriate model
     router_service: RouterService,
     /// Error handler for retries, timeouts, and circuit breaking
     error_handler: ErrorHandler,
+    /// Per-model request/response transformations
+    transformer: ModelTransformer,
+    /// Per-provider message normalization
+    normalizer: MessageNormalizer,
+    /// Model registry used to resolve providers
+    registry: Option<Arc<ModelRegistry>>,
+    /// Repair of output requested as JSON
+    json_mode: JsonModeConfig,
 }
 
 impl ChatCompletionService {
@@ -132,6 +195,42 @@ impl ChatCompletionService {
         Self {
             router_service,
             error_handler,
+            transformer: ModelTransformer::default(),
+            normalizer: MessageNormalizer::default(),
+            registry: None,
+            json_mode: JsonModeConfig::default(),
+        }
+    }
+
+    /// Set the per-model transformer applied to provider requests and responses
+    pub fn with_transformer(mut self, transformer: ModelTransformer) -> Self {
+        self.transformer = transformer;
+        self
+    }
+
+    /// Set the normalizer applying provider message rules to provider requests
+    pub fn with_normalizer(mut self, normalizer: MessageNormalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Set the model registry used to resolve the provider of a model
+    pub fn with_registry(mut self, registry: Arc<ModelRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Set how output requested as JSON is validated and repaired
+    pub fn with_json_mode(mut self, json_mode: JsonModeConfig) -> Self {
+        self.json_mode = json_mode;
+        self
+    }
+
+    /// Resolve the provider serving a model
+    fn provider_for(&self, model: &str) -> String {
+        match &self.registry {
+            Some(registry) => resolve_provider(registry, model),
+            None => infer_provider(model).to_string(),
         }
     }
 
@@ -145,6 +244,10 @@ impl ChatCompletionService {
     }
 
     /// Process a chat completion request and generate a response
+    ///
+    /// Requests with `n > 1` are forwarded as-is to providers that generate
+    /// multiple choices natively. For other providers, `n` single-choice calls
+    /// are made in parallel and merged into one response.
     pub async fn process_completion_request(
         &self,
         request: &ChatCompletionRequest,
@@ -154,8 +257,49 @@ impl ChatCompletionService {
             request.model
         );
 
+        let n = request.n.unwrap_or(1);
+        if n > 1 {
+            let provider = self.provider_for(&request.model);
+            if !ProviderParamSupport::for_provider(&provider).n {
+                debug!(
+                    "Emulating n={} for provider '{}' (model '{}')",
+                    n, provider, request.model
+                );
+                return self.process_emulated_request(request, n).await;
+            }
+        }
+
+        self.process_single_request(request).await
+    }
+
+    /// Emulate `n > 1` with parallel single-choice calls
+    async fn process_emulated_request(
+        &self,
+        request: &ChatCompletionRequest,
+        n: u32,
+    ) -> Result<ChatCompletionResponse, RouterError> {
+        let mut single = request.clone();
+        single.n = None;
+
+        let responses = join_all((0..n).map(|_| self.process_single_request(&single)))
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(merge_emulated_responses(responses))
+    }
+
+    /// Send a single call to the routed provider
+    async fn process_single_request(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, RouterError> {
         // Convert the DTO request to a connector request
-        let connector_request = convert_to_connector_request(request);
+        let mut connector_request = convert_to_connector_request(request);
+        self.transformer.apply_request(&mut connector_request);
+        let provider = self.provider_for(&request.model);
+        self.normalizer.apply(&provider, &mut connector_request);
+        apply_user_param(&mut connector_request, &provider);
 
         // Use error handler to execute with retry, timeout, and circuit breaking
         let context = format!("chat_completion_request:{}", request.model);
@@ -174,7 +318,119 @@ impl ChatCompletionService {
             .await?;
 
         // Convert the connector response to a DTO response
-        Ok(convert_from_connector_response(connector_response))
+        let mut connector_response = connector_response;
+        self.transformer
+            .apply_response(&request.model, &mut connector_response);
+        let response = convert_from_connector_response(connector_response);
+
+        if request.wants_json() {
+            return self
+                .ensure_json(request, &connector_request, response)
+                .await;
+        }
+        Ok(response)
+    }
+
+    /// Make sure the choices of a response requested as JSON are valid JSON
+    ///
+    /// Invalid output is repaired, or fails the request when it can't be
+    /// repaired or repairs are disabled by strict mode.
+    async fn ensure_json(
+        &self,
+        request: &ChatCompletionRequest,
+        connector_request: &connectors::ChatCompletionRequest,
+        mut response: ChatCompletionResponse,
+    ) -> Result<ChatCompletionResponse, RouterError> {
+        for choice in response.choices.iter_mut() {
+            let MessageContent::String(content) = &choice.message.content else {
+                continue;
+            };
+            if json_mode::is_valid(content) {
+                continue;
+            }
+
+            let repaired = if self.json_mode.strict {
+                None
+            } else {
+                match json_mode::repair(content) {
+                    Some(repaired) => Some(repaired),
+                    None => self.reask_json(connector_request, content).await,
+                }
+            };
+            let Some((content, method)) = repaired else {
+                counter!(
+                    catalog::JSON_MODE_FAILURES,
+                    1,
+                    "model" => request.model.clone()
+                );
+                return Err(RouterError::InvalidOutput(format!(
+                    "model '{}' did not return valid JSON",
+                    request.model
+                )));
+            };
+            debug!(
+                "Repaired JSON output of model '{}' by {}",
+                request.model,
+                method.as_str()
+            );
+            counter!(
+                catalog::JSON_MODE_REPAIRS,
+                1,
+                "model" => request.model.clone(),
+                "method" => method.as_str()
+            );
+            choice.message.content = MessageContent::String(content);
+        }
+        Ok(response)
+    }
+
+    /// Ask the model again for JSON, up to `max_reasks` times
+    async fn reask_json(
+        &self,
+        connector_request: &connectors::ChatCompletionRequest,
+        content: &str,
+    ) -> Option<(String, RepairMethod)> {
+        let mut previous = content.to_string();
+        for _ in 0..self.json_mode.max_reasks {
+            let mut reask = connector_request.clone();
+            reask.messages.push(connectors::ChatMessage {
+                role: connectors::MessageRole::Assistant,
+                content: previous,
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            });
+            reask.messages.push(connectors::ChatMessage {
+                role: connectors::MessageRole::User,
+                content: json_mode::REASK_PROMPT.to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            });
+
+            let mut response = match self.router_service.route_request(&reask).await {
+                Ok(response) => response,
+                Err(e) => {
+                    error!(
+                        "Failed to ask model '{}' again for JSON: {}",
+                        reask.model, e
+                    );
+                    return None;
+                }
+            };
+            self.transformer.apply_response(&reask.model, &mut response);
+            let content = response.choices.into_iter().next()?.message.content;
+            if json_mode::is_valid(&content) {
+                return Some((content, RepairMethod::Reask));
+            }
+            if let Some((repaired, _)) = json_mode::repair(&content) {
+                return Some((repaired, RepairMethod::Reask));
+            }
+            previous = content;
+        }
+        None
     }
 
     /// Generate streaming chunks for a chat completion request
@@ -186,7 +442,7 @@ impl ChatCompletionService {
         debug!("Generating streaming chunks for model: {}", request.model);
 
         // Convert DTO request to connector request
-        let connector_request =
+        let mut connector_request =
             crate::modules::model_registry::connectors::ChatCompletionRequest {
                 model: request.model.clone(),
                 messages: request
@@ -227,6 +483,7 @@ impl ChatCompletionService {
                         name: m.name.clone(),
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     }
                     })
                     .collect(),
@@ -236,8 +493,12 @@ impl ChatCompletionService {
                 stream: Some(request.stream),
                 functions: None,
                 tools: None,
-                additional_params: None,
+                additional_params: passthrough_params(request),
             };
+        self.transformer.apply_request(&mut connector_request);
+        let provider = self.provider_for(&request.model);
+        self.normalizer.apply(&provider, &mut connector_request);
+        apply_user_param(&mut connector_request, &provider);
 
         // Use error handler to execute with timeout
         let context = format!("streaming_request:{}", request.model);
@@ -306,8 +567,19 @@ impl ChatCompletionService {
         // Create the assistant message
         let assistant_message = Message::new_assistant(response_content);
 
-        // Create the response
-        ChatCompletionResponse::new(request.model.clone(), assistant_message)
+        // Create the response, with one choice per requested completion
+        let mut response = ChatCompletionResponse::new(request.model.clone(), assistant_message);
+        for index in 1..request.n.unwrap_or(1) {
+            let message = response.choices[0].message.clone();
+            response.choices.push(ChatCompletionChoice {
+                index,
+                message,
+                finish_reason: "stop".to_string(),
+                emulated: None,
+                content_filter: None,
+            });
+        }
+        response
     }
 
     /// Legacy method for backward compatibility
@@ -452,6 +724,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         let response = service.process_completion_request(&request).await.unwrap();
@@ -474,6 +752,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         let response = ChatCompletionService::legacy_process_completion_request(&request);
@@ -487,6 +771,26 @@ mod tests {
             .contains("Hello"));
     }
 
+    #[test]
+    fn test_merge_emulated_responses() {
+        let responses = (0..3)
+            .map(|_| {
+                ChatCompletionResponse::new(
+                    "claude-3-sonnet".to_string(),
+                    Message::new_assistant("Hi".to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        let single_usage = responses[0].usage.total_tokens;
+
+        let merged = merge_emulated_responses(responses);
+
+        assert_eq!(merged.choices.len(), 3);
+        assert_eq!(merged.choices[2].index, 2);
+        assert!(merged.choices.iter().all(|c| c.emulated == Some(true)));
+        assert_eq!(merged.usage.total_tokens, single_usage * 3);
+    }
+
     #[test]
     fn test_legacy_generate_streaming_chunks() {
         let request = ChatCompletionRequest {
@@ -500,6 +804,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         let chunks = ChatCompletionService::legacy_generate_streaming_chunks(&request, 2);
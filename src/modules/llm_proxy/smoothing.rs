@@ -0,0 +1,232 @@
+//! Stream Smoothing
+//!
+//! Providers often deliver streamed tokens in bursts, which some consumers
+//! (text-to-speech pipelines, for instance) handle poorly. Smoothing paces
+//! the chunks of a streamed response so no more than a configured number of
+//! content tokens per second reach the client. Pacing uses a token bucket:
+//! up to `burst_tokens` are delivered at once, after which each chunk waits
+//! until the rate allows its tokens. Chunks without content, such as the
+//! role announcement and the end of the stream, are never delayed.
+
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use futures::stream::{Stream, StreamExt};
+use serde_json::Value;
+
+use crate::config::StreamSmoothingConfig;
+use crate::modules::model_registry::global_tokenizers;
+
+/// Header setting the smoothing rate of a request in tokens per second
+pub const RATE_HEADER: &str = "x-intellirouter-stream-rate";
+
+/// Pacing of a streamed response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pacing {
+    /// Content tokens delivered per second
+    pub tokens_per_sec: f64,
+    /// Tokens delivered without waiting
+    pub burst_tokens: f64,
+    /// Model whose tokenizer counts the content tokens
+    pub model: String,
+}
+
+/// Pacing of a request's stream
+///
+/// A valid rate header smooths the stream at that rate, even when smoothing
+/// isn't enabled for all streams; otherwise the configured rate applies when
+/// enabled.
+pub fn pacing(config: &StreamSmoothingConfig, headers: &HeaderMap, model: &str) -> Option<Pacing> {
+    let requested = headers
+        .get(RATE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|rate| rate.is_finite() && *rate > 0.0);
+    let tokens_per_sec = match requested {
+        Some(rate) => rate,
+        None if config.enabled => config.tokens_per_sec,
+        None => return None,
+    };
+    Some(Pacing {
+        tokens_per_sec,
+        burst_tokens: config.burst_tokens as f64,
+        model: model.to_string(),
+    })
+}
+
+/// Content tokens of a chunk's event data
+///
+/// Counts the text of every choice's delta; data that isn't a chunk counts
+/// as no tokens.
+pub fn chunk_tokens(model: &str, data: &str) -> usize {
+    let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+        return 0;
+    };
+    let tokenizers = global_tokenizers();
+    chunk
+        .get("choices")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|choice| choice.pointer("/delta/content").and_then(Value::as_str))
+        .map(|text| tokenizers.count_tokens(model, text))
+        .sum()
+}
+
+/// Token bucket deciding how long each chunk waits
+#[derive(Debug)]
+struct Bucket {
+    tokens_per_sec: f64,
+    burst_tokens: f64,
+    /// Tokens that may be delivered without waiting
+    available: f64,
+    /// When `available` was last brought up to date
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(pacing: &Pacing, now: Instant) -> Self {
+        Self {
+            tokens_per_sec: pacing.tokens_per_sec,
+            burst_tokens: pacing.burst_tokens,
+            available: pacing.burst_tokens,
+            updated: now,
+        }
+    }
+
+    /// Take the tokens of a chunk, returning how long it must wait
+    fn take(&mut self, tokens: usize, now: Instant) -> Duration {
+        let refill =
+            now.saturating_duration_since(self.updated).as_secs_f64() * self.tokens_per_sec;
+        self.available = (self.available + refill).min(self.burst_tokens);
+        self.updated = self.updated.max(now);
+
+        let tokens = tokens as f64;
+        if tokens <= self.available {
+            self.available -= tokens;
+            return Duration::ZERO;
+        }
+        // The chunk is sent once the missing tokens have accrued
+        let wait = Duration::from_secs_f64((tokens - self.available) / self.tokens_per_sec);
+        self.available = 0.0;
+        self.updated += wait;
+        self.updated.saturating_duration_since(now)
+    }
+}
+
+/// Pace the events of a stream
+///
+/// `data` gives the event data of an item, if it has any. Without pacing
+/// the events are passed through as they arrive.
+pub fn pace<S, F>(
+    events: S,
+    pacing: Option<Pacing>,
+    data: F,
+) -> impl Stream<Item = S::Item> + Send + 'static
+where
+    S: Stream + Send + 'static,
+    S::Item: Send + 'static,
+    F: Fn(&S::Item) -> Option<String> + Send + 'static,
+{
+    let mut bucket = pacing.map(|pacing| (Bucket::new(&pacing, Instant::now()), pacing.model));
+    events.then(move |event| {
+        let wait = match &mut bucket {
+            Some((bucket, model)) => {
+                let tokens = data(&event).map_or(0, |data| chunk_tokens(model, &data));
+                bucket.take(tokens, Instant::now())
+            }
+            None => Duration::ZERO,
+        };
+        async move {
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+            event
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn rate(tokens_per_sec: f64, burst_tokens: f64) -> Pacing {
+        Pacing {
+            tokens_per_sec,
+            burst_tokens,
+            model: "gpt-4o".to_string(),
+        }
+    }
+
+    fn chunk(content: &str) -> String {
+        serde_json::json!({"choices": [{"index": 0, "delta": {"content": content}}]}).to_string()
+    }
+
+    #[test]
+    fn test_bucket() {
+        let start = Instant::now();
+        let mut bucket = Bucket::new(&rate(10.0, 5.0), start);
+
+        assert_eq!(bucket.take(5, start), Duration::ZERO);
+        assert_eq!(bucket.take(2, start), Duration::from_millis(200));
+        // The next chunk queues behind the delayed one
+        assert_eq!(bucket.take(1, start), Duration::from_millis(300));
+        // Tokens accrue again once the client has caught up, up to the burst
+        let later = start + Duration::from_secs(10);
+        assert_eq!(bucket.take(5, later), Duration::ZERO);
+        assert_eq!(bucket.take(0, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_pacing_from_config_and_header() {
+        let mut config = StreamSmoothingConfig::default();
+        let mut headers = HeaderMap::new();
+        assert_eq!(pacing(&config, &headers, "gpt-4o"), None);
+
+        headers.insert(RATE_HEADER, "25".parse().unwrap());
+        assert_eq!(
+            pacing(&config, &headers, "gpt-4o").unwrap().tokens_per_sec,
+            25.0
+        );
+
+        headers.insert(RATE_HEADER, "fast".parse().unwrap());
+        assert_eq!(pacing(&config, &headers, "gpt-4o"), None);
+        config.enabled = true;
+        assert_eq!(
+            pacing(&config, &headers, "gpt-4o").unwrap().tokens_per_sec,
+            config.tokens_per_sec
+        );
+    }
+
+    #[test]
+    fn test_chunk_tokens() {
+        assert_eq!(chunk_tokens("gpt-4o", &chunk("hello world")), 2);
+        assert_eq!(chunk_tokens("gpt-4o", "[DONE]"), 0);
+        assert_eq!(
+            chunk_tokens("gpt-4o", r#"{"choices":[{"delta":{"role":"assistant"}}]}"#),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pace() {
+        // Four chunks of two tokens at 40 tokens per second, after a burst of 2
+        let events: Vec<String> = (0..4).map(|_| chunk("hello world")).collect();
+        let started = Instant::now();
+        let paced: Vec<String> = pace(stream::iter(events.clone()), Some(rate(40.0, 2.0)), |e| {
+            Some(e.clone())
+        })
+        .collect()
+        .await;
+        assert_eq!(paced, events);
+        assert!(started.elapsed() >= Duration::from_millis(150));
+
+        let started = Instant::now();
+        let unpaced: Vec<String> = pace(stream::iter(events.clone()), None, |e| Some(e.clone()))
+            .collect()
+            .await;
+        assert_eq!(unpaced, events);
+        assert!(started.elapsed() < Duration::from_millis(50));
+    }
+}
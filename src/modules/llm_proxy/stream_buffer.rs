@@ -0,0 +1,270 @@
+//! Resumable Streams
+//!
+//! This module keeps the events of recent streamed responses in a
+//! short-lived buffer. Generation writes to the buffer independently of the
+//! client connection, and every event carries a `<stream id>:<index>` ID, so
+//! a client whose connection drops can reconnect with `Last-Event-ID` and
+//! receive the events it missed instead of restarting the generation.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, Stream};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::config::StreamResumeConfig;
+
+/// Header carrying the ID of the last event a client received
+pub const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+/// Events of one streamed response
+#[derive(Debug, Default)]
+struct BufferedStream {
+    /// Events written so far and whether generation finished
+    state: Mutex<BufferState>,
+    /// Wakes readers waiting for new events
+    notify: Notify,
+}
+
+#[derive(Debug, Default)]
+struct BufferState {
+    events: Vec<String>,
+    finished_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    stream: Arc<BufferedStream>,
+    created_at: Instant,
+}
+
+/// Short-lived buffer of streamed responses
+#[derive(Debug)]
+pub struct StreamBuffer {
+    config: StreamResumeConfig,
+    streams: Mutex<HashMap<String, Entry>>,
+}
+
+/// Writes the events of a buffered stream
+///
+/// Dropping the writer finishes the stream, so readers never wait on a
+/// generation that was abandoned.
+#[derive(Debug)]
+pub struct StreamWriter {
+    stream: Arc<BufferedStream>,
+}
+
+impl StreamWriter {
+    /// Append an event
+    pub fn push(&self, data: String) {
+        self.stream.state.lock().unwrap().events.push(data);
+        self.stream.notify.notify_waiters();
+    }
+
+    /// Mark the stream as finished
+    pub fn finish(&self) {
+        {
+            let mut state = self.stream.state.lock().unwrap();
+            if state.finished_at.is_none() {
+                state.finished_at = Some(Instant::now());
+            }
+        }
+        self.stream.notify.notify_waiters();
+    }
+}
+
+impl Drop for StreamWriter {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl StreamBuffer {
+    /// Create a stream buffer
+    pub fn new(config: StreamResumeConfig) -> Self {
+        Self {
+            config,
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether streamed responses are buffered
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Start buffering a new stream, returning its ID and writer
+    pub fn create(&self) -> (String, StreamWriter) {
+        let id = Uuid::new_v4().simple().to_string();
+        let stream = Arc::new(BufferedStream::default());
+
+        let mut streams = self.streams.lock().unwrap();
+        self.expire(&mut streams);
+        while streams.len() >= self.config.max_streams.max(1) {
+            let oldest = streams
+                .iter()
+                .min_by_key(|(_, entry)| entry.created_at)
+                .map(|(id, _)| id.clone());
+            match oldest {
+                Some(id) => streams.remove(&id),
+                None => break,
+            };
+        }
+        streams.insert(
+            id.clone(),
+            Entry {
+                stream: stream.clone(),
+                created_at: Instant::now(),
+            },
+        );
+        (id, StreamWriter { stream })
+    }
+
+    /// Read a buffered stream starting at event `from`
+    ///
+    /// Buffered events are replayed immediately; later events are yielded as
+    /// they are written. Returns `None` if the stream is unknown or expired.
+    pub fn subscribe(
+        &self,
+        stream_id: &str,
+        from: usize,
+    ) -> Option<impl Stream<Item = (usize, String)> + Send + 'static> {
+        let stream = {
+            let mut streams = self.streams.lock().unwrap();
+            self.expire(&mut streams);
+            streams.get(stream_id)?.stream.clone()
+        };
+
+        Some(stream::unfold(
+            (stream, from),
+            |(stream, index)| async move {
+                loop {
+                    let notified = stream.notify.notified();
+                    let next = {
+                        let state = stream.state.lock().unwrap();
+                        match state.events.get(index) {
+                            Some(data) => Some(Some(data.clone())),
+                            None if state.finished_at.is_some() => Some(None),
+                            None => None,
+                        }
+                    };
+                    match next {
+                        Some(Some(data)) => {
+                            drop(notified);
+                            return Some(((index, data), (stream, index + 1)));
+                        }
+                        Some(None) => return None,
+                        None => notified.await,
+                    }
+                }
+            },
+        ))
+    }
+
+    /// Number of buffered streams
+    pub fn len(&self) -> usize {
+        self.streams.lock().unwrap().len()
+    }
+
+    /// Whether no stream is buffered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop finished streams whose TTL elapsed
+    fn expire(&self, streams: &mut HashMap<String, Entry>) {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        streams.retain(|_, entry| {
+            entry
+                .stream
+                .state
+                .lock()
+                .unwrap()
+                .finished_at
+                .is_none_or(|finished_at| finished_at.elapsed() < ttl)
+        });
+    }
+}
+
+impl Default for StreamBuffer {
+    fn default() -> Self {
+        Self::new(StreamResumeConfig::default())
+    }
+}
+
+/// ID of the event at `index` in a stream
+pub fn event_id(stream_id: &str, index: usize) -> String {
+    format!("{}:{}", stream_id, index)
+}
+
+/// Parse an event ID into its stream ID and index
+pub fn parse_event_id(event_id: &str) -> Option<(&str, usize)> {
+    let (stream_id, index) = event_id.trim().rsplit_once(':')?;
+    Some((stream_id, index.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_resume_from_offset() {
+        let buffer = StreamBuffer::default();
+        let (id, writer) = buffer.create();
+        writer.push("a".to_string());
+        writer.push("b".to_string());
+
+        let reader = buffer.subscribe(&id, 1).unwrap();
+        writer.push("c".to_string());
+        drop(writer);
+
+        let events: Vec<_> = reader.collect().await;
+        assert_eq!(events, vec![(1, "b".to_string()), (2, "c".to_string())]);
+        assert!(buffer.subscribe("unknown", 0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_new_events() {
+        let buffer = StreamBuffer::default();
+        let (id, writer) = buffer.create();
+        let mut reader = Box::pin(buffer.subscribe(&id, 0).unwrap());
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            writer.push("late".to_string());
+        });
+
+        assert_eq!(reader.next().await, Some((0, "late".to_string())));
+        assert_eq!(reader.next().await, None);
+    }
+
+    #[test]
+    fn test_eviction() {
+        let buffer = StreamBuffer::new(StreamResumeConfig {
+            enabled: true,
+            ttl_secs: 0,
+            max_streams: 2,
+        });
+        let (first, writer) = buffer.create();
+        drop(writer);
+        // Finished streams expire after the TTL
+        let (second, _w2) = buffer.create();
+        assert!(buffer.subscribe(&first, 0).is_none());
+
+        // Running streams are dropped oldest first when the buffer is full
+        let (_third, _w3) = buffer.create();
+        let (_fourth, _w4) = buffer.create();
+        assert!(buffer.subscribe(&second, 0).is_none());
+        assert_eq!(buffer.len(), 2);
+    }
+
+    #[test]
+    fn test_event_ids() {
+        assert_eq!(parse_event_id(&event_id("abc", 7)), Some(("abc", 7)));
+        assert_eq!(parse_event_id("abc"), None);
+        assert_eq!(parse_event_id("abc:x"), None);
+    }
+}
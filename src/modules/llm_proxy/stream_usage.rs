@@ -0,0 +1,322 @@
+//! Streaming Usage
+//!
+//! Streams requested with `stream_options: {"include_usage": true}` end, as
+//! with OpenAI, with a chunk carrying the token usage of the whole request
+//! and no choices. Providers that report usage in their streams are asked for
+//! it and their chunk is forwarded as is. For the others, the proxy counts the
+//! prompt and the content of the chunks passing through with the model's
+//! tokenizer, and adds the usage chunk before `[DONE]`.
+//!
+//! Every stream is counted, whether it asks for its usage or not, and the
+//! usage is handed to a hook once the stream ends, whether it completed or
+//! the client went away.
+
+use bytes::Bytes;
+use chrono::Utc;
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::Value;
+
+use super::domain::message::Message;
+use super::dto::{ChatCompletionChunk, TokenUsage};
+use super::formatting::generate_response_id;
+use super::passthrough;
+use crate::modules::model_registry::global_tokenizers;
+
+/// Tokens framing each message of a chat prompt
+const TOKENS_PER_MESSAGE: usize = 3;
+
+/// Tokens priming the assistant's reply after the prompt
+const REPLY_PRIMING_TOKENS: usize = 3;
+
+/// Prompt tokens of the messages of a request, counted as OpenAI bills them
+pub fn prompt_tokens(model: &str, messages: &[Message]) -> u32 {
+    let tokenizer = global_tokenizers().for_model(model);
+    let tokens: usize = messages
+        .iter()
+        .map(|message| {
+            TOKENS_PER_MESSAGE
+                + tokenizer.count(&message.role.to_string())
+                + tokenizer.count(&message.extract_text_content())
+                + message
+                    .name
+                    .as_deref()
+                    .map_or(0, |name| tokenizer.count(name) + 1)
+        })
+        .sum();
+    u32::try_from(tokens + REPLY_PRIMING_TOKENS).unwrap_or(u32::MAX)
+}
+
+/// Hook receiving the usage of a stream once it ends
+pub type UsageHook = Box<dyn FnOnce(TokenUsage) + Send>;
+
+/// Usage of a stream, counted from the chunks passing through it
+pub struct StreamUsage {
+    /// Model whose tokenizer counts the completion
+    model: String,
+    prompt_tokens: u32,
+    /// Content of every choice so far
+    completion: String,
+    /// ID, creation time and model of the stream's first chunk
+    first_chunk: Option<(String, u64, String)>,
+    /// Usage carried by a chunk of the stream, if the provider sent one
+    reported: Option<TokenUsage>,
+    /// Whether the client asked for the usage chunk
+    in_stream: bool,
+    on_finish: Option<UsageHook>,
+}
+
+impl StreamUsage {
+    /// Start counting the usage of a stream, counting its prompt
+    pub fn new(model: &str, messages: &[Message]) -> Self {
+        Self {
+            model: model.to_string(),
+            prompt_tokens: prompt_tokens(model, messages),
+            completion: String::new(),
+            first_chunk: None,
+            reported: None,
+            in_stream: true,
+            on_finish: None,
+        }
+    }
+
+    /// Set whether the usage chunk ends the stream; streams counted only
+    /// for accounting don't get one
+    pub fn in_stream(mut self, in_stream: bool) -> Self {
+        self.in_stream = in_stream;
+        self
+    }
+
+    /// Hand the usage to `hook` once the stream ends or is dropped
+    pub fn on_finish(mut self, hook: impl FnOnce(TokenUsage) + Send + 'static) -> Self {
+        self.on_finish = Some(Box::new(hook));
+        self
+    }
+
+    /// Count the event data of a chunk
+    ///
+    /// Data that isn't a chunk, such as `[DONE]`, counts as nothing.
+    pub fn observe(&mut self, data: &str) {
+        let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+            return;
+        };
+        if self.first_chunk.is_none() {
+            let field = |name: &str| chunk.get(name).and_then(Value::as_str).map(str::to_string);
+            self.first_chunk = Some((
+                field("id").unwrap_or_else(generate_response_id),
+                chunk
+                    .get("created")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_else(|| Utc::now().timestamp() as u64),
+                field("model").unwrap_or_else(|| self.model.clone()),
+            ));
+        }
+        if let Some(usage) = chunk.get("usage").filter(|usage| usage.is_object()) {
+            // A malformed usage is still the provider's; it isn't repeated
+            self.reported = Some(
+                serde_json::from_value(usage.clone()).unwrap_or_else(|_| self.counted_usage()),
+            );
+        }
+        let contents = chunk
+            .get("choices")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|choice| choice.pointer("/delta/content").and_then(Value::as_str));
+        for content in contents {
+            self.completion.push_str(content);
+        }
+    }
+
+    /// Usage of the stream so far, as the provider reported it if it did
+    pub fn usage(&self) -> TokenUsage {
+        self.reported
+            .clone()
+            .unwrap_or_else(|| self.counted_usage())
+    }
+
+    /// Usage counted with the model's tokenizer
+    fn counted_usage(&self) -> TokenUsage {
+        let completion_tokens = global_tokenizers().count_tokens(&self.model, &self.completion);
+        let completion_tokens = u32::try_from(completion_tokens).unwrap_or(u32::MAX);
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens,
+            total_tokens: self.prompt_tokens.saturating_add(completion_tokens),
+        }
+    }
+
+    /// Event data of the chunk ending the stream with its usage, unless the
+    /// provider sent one
+    pub fn final_chunk(&self) -> Option<String> {
+        if self.reported.is_some() || !self.in_stream {
+            return None;
+        }
+        let (id, created, model) = self.first_chunk.clone().unwrap_or_else(|| {
+            (
+                generate_response_id(),
+                Utc::now().timestamp() as u64,
+                self.model.clone(),
+            )
+        });
+        let chunk = ChatCompletionChunk::with_usage(id, created, model, self.usage());
+        serde_json::to_string(&chunk).ok()
+    }
+}
+
+impl Drop for StreamUsage {
+    fn drop(&mut self) {
+        if let Some(hook) = self.on_finish.take() {
+            hook(self.usage());
+        }
+    }
+}
+
+impl std::fmt::Debug for StreamUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamUsage")
+            .field("model", &self.model)
+            .field("prompt_tokens", &self.prompt_tokens)
+            .field("reported", &self.reported)
+            .field("in_stream", &self.in_stream)
+            .finish()
+    }
+}
+
+/// Add the usage chunk to the events of a provider's stream, before its
+/// `[DONE]` event or at its end
+///
+/// Nothing is added after a failed read, as the stream is cut short.
+pub fn append_usage<S, E>(
+    frames: S,
+    usage: StreamUsage,
+) -> impl Stream<Item = Result<Bytes, E>> + Send
+where
+    S: Stream<Item = Result<Bytes, E>> + Send,
+    E: Send,
+{
+    let usage_frame = |usage: &StreamUsage| {
+        usage
+            .final_chunk()
+            .map(|data| Ok(Bytes::from(format!("data: {}\n\n", data))))
+    };
+    frames
+        .map(Some)
+        .chain(stream::iter([None]))
+        .scan((usage, false), move |(usage, ended), frame| {
+            let frames: Vec<Result<Bytes, E>> = match frame {
+                _ if *ended => Vec::new(),
+                Some(Ok(frame)) if passthrough::is_done(&frame) => {
+                    *ended = true;
+                    usage_frame(usage).into_iter().chain([Ok(frame)]).collect()
+                }
+                Some(Ok(frame)) => {
+                    if let Some(data) = passthrough::event_data(&frame) {
+                        usage.observe(&data);
+                    }
+                    vec![Ok(frame)]
+                }
+                Some(Err(e)) => {
+                    *ended = true;
+                    vec![Err(e)]
+                }
+                None => usage_frame(usage).into_iter().collect(),
+            };
+            future::ready(Some(stream::iter(frames)))
+        })
+        .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    fn chunk(content: &str) -> String {
+        format!(
+            "data: {{\"id\":\"chatcmpl-1\",\"created\":7,\"model\":\"gpt-4o-2024-08-06\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"{}\"}}}}]}}\n\n",
+            content
+        )
+    }
+
+    async fn events(frames: Vec<String>, usage: StreamUsage) -> Vec<String> {
+        let body = stream::iter(
+            frames
+                .into_iter()
+                .map(|frame| Ok::<_, Infallible>(Bytes::from(frame))),
+        );
+        append_usage(body, usage)
+            .map(|frame| passthrough::event_data(&frame.unwrap()).unwrap())
+            .collect()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_usage_chunk_added_before_done() {
+        let messages = vec![Message::new_user("Hello there".to_string())];
+        let usage = StreamUsage::new("gpt-4o", &messages);
+        let prompt_tokens = usage.usage().prompt_tokens;
+        assert!(prompt_tokens > REPLY_PRIMING_TOKENS as u32 + TOKENS_PER_MESSAGE as u32);
+
+        let frames = vec![
+            chunk("The quick brown"),
+            chunk(" fox jumps"),
+            "data: [DONE]\n\n".to_string(),
+        ];
+        let events = events(frames, usage).await;
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[3], "[DONE]");
+
+        let last: ChatCompletionChunk = serde_json::from_str(&events[2]).unwrap();
+        assert!(last.choices.is_empty());
+        assert_eq!(last.id, "chatcmpl-1");
+        assert_eq!(last.model, "gpt-4o-2024-08-06");
+        let usage = last.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, prompt_tokens);
+        assert_eq!(
+            usage.completion_tokens as usize,
+            global_tokenizers().count_tokens("gpt-4o", "The quick brown fox jumps")
+        );
+        assert_eq!(usage.total_tokens, prompt_tokens + usage.completion_tokens);
+    }
+
+    #[tokio::test]
+    async fn test_provider_usage_is_kept() {
+        let usage = StreamUsage::new("gpt-4o", &[]);
+        let reported = "data: {\"id\":\"chatcmpl-1\",\"choices\":[],\"usage\":{\"prompt_tokens\":1,\"completion_tokens\":2,\"total_tokens\":3}}\n\n";
+        let events = events(vec![chunk("Hi"), reported.to_string()], usage).await;
+        assert_eq!(events.len(), 2);
+        assert!(events[1].contains("\"total_tokens\":3"));
+    }
+
+    #[tokio::test]
+    async fn test_usage_handed_to_hook() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let usage = StreamUsage::new("gpt-4o", &[])
+            .in_stream(false)
+            .on_finish(move |usage| sender.send(usage).unwrap());
+        let frames = vec![chunk("Hello"), "data: [DONE]\n\n".to_string()];
+        let events = events(frames, usage).await;
+        assert_eq!(events.len(), 2);
+        let usage = receiver.try_recv().unwrap();
+        assert_eq!(
+            usage.completion_tokens as usize,
+            global_tokenizers().count_tokens("gpt-4o", "Hello")
+        );
+
+        // A stream dropped by its client still hands over its usage
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let usage = StreamUsage::new("gpt-4o", &[]).on_finish(move |usage| {
+            sender.send(usage).unwrap();
+        });
+        let reported = "data: {\"id\":\"chatcmpl-1\",\"choices\":[],\"usage\":{\"prompt_tokens\":1,\"completion_tokens\":2,\"total_tokens\":3}}\n\n";
+        let body = stream::iter([
+            Ok::<_, Infallible>(Bytes::from(reported)),
+            Ok(Bytes::from(chunk("Hi"))),
+        ]);
+        let mut frames = Box::pin(append_usage(body, usage));
+        frames.next().await;
+        drop(frames);
+        assert_eq!(receiver.try_recv().unwrap().total_tokens, 3);
+    }
+}
@@ -1,37 +1,20 @@
-use axum::{
-    middleware::from_fn_with_state,
-    routing::{post, Router},
-};
+use axum::Router;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use crate::modules::model_registry::ModelRegistry;
 use crate::modules::telemetry::{
-    create_cost_calculator, init_telemetry, telemetry_middleware, LlmCallMetrics, RoutingMetrics,
-    TelemetryManager,
+    create_cost_calculator, init_telemetry, LlmCallMetrics, RoutingMetrics, TelemetryManager,
 };
 
 // Use the AppState from server.rs
 pub use super::server::AppState;
 
-
 /// Create a router with telemetry middleware
 pub fn create_router_with_telemetry(
     telemetry: Arc<TelemetryManager>,
     cost_calculator: Arc<crate::modules::telemetry::CostCalculator>,
 ) -> Router {
-    // Create a router with the actual handler functions from routes.rs
-    let router = Router::new()
-        .route(
-            "/v1/chat/completions",
-            post(super::routes::chat_completions),
-        )
-        .route(
-            "/v1/chat/completions/stream",
-            post(super::routes::chat_completions_stream),
-        )
-        // Add telemetry middleware
-        .layer(from_fn_with_state(telemetry.clone(), telemetry_middleware));
-
     // Create a minimal app state with just the telemetry components
     let app_state = AppState {
         provider: super::Provider::OpenAI, // Default provider
@@ -43,13 +26,37 @@ pub fn create_router_with_telemetry(
             cors_enabled: false,
             cors_allowed_origins: vec!["*".to_string()],
             redis_url: None,
+            proxy: crate::config::ProxyConfig::default(),
+            subsystems: Default::default(),
+            language: Default::default(),
         },
         shared: std::sync::Arc::new(tokio::sync::Mutex::new(super::server::SharedState::new())),
         telemetry: Some(telemetry),
         cost_calculator: Some(cost_calculator),
+        registry: Arc::new(ModelRegistry::new()),
+        quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+        policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+        decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+        telemetry_export: None,
+        anomalies: None,
+        canaries: None,
+        metering: None,
+        admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+        streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+        coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+        prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+        rollouts: Arc::new(
+            crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+        ),
+        user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+        payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+        partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+        discovery: None,
+        prompt_traces: Arc::new(crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default()),
     };
 
-    router.with_state(app_state)
+    // The server router adds the telemetry middleware when telemetry is present
+    super::server::create_router(app_state)
 }
 
 /// Initialize telemetry and create a router
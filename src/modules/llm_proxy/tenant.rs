@@ -0,0 +1,115 @@
+//! Tenant Resolution
+//!
+//! This module resolves the calling tenant from request credentials,
+//! applies the tenant's model allow-list and checks admin credentials.
+
+use axum::http::HeaderMap;
+
+use super::admin::{self, AdminRole};
+use super::transform::model_matches;
+use crate::config::{ProxyConfig, TenantConfig};
+
+/// Extract the caller's API key from the `Authorization` or `X-API-Key` header
+pub fn extract_api_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get("authorization").and_then(|v| v.to_str().ok()) {
+        if let Some(key) = value.strip_prefix("Bearer ") {
+            return Some(key.trim());
+        }
+    }
+
+    headers
+        .get("x-api-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+}
+
+/// Resolve the tenant associated with the request credentials, if any
+pub fn resolve_tenant<'a>(
+    config: &'a ProxyConfig,
+    headers: &HeaderMap,
+) -> Option<&'a TenantConfig> {
+    let api_key = extract_api_key(headers)?;
+    config
+        .tenants
+        .iter()
+        .find(|tenant| tenant.api_keys.iter().any(|k| k == api_key))
+}
+
+/// Check whether the request credentials grant the admin role
+///
+/// Without admin credentials configured, only anonymous admins explicitly
+/// allowed by `allow_anonymous_admin` are admins.
+pub fn is_admin(config: &ProxyConfig, headers: &HeaderMap) -> bool {
+    admin::authorize(config, headers, AdminRole::Admin).is_ok()
+}
+
+/// Check whether a tenant may use a model
+///
+/// Requests without a resolved tenant, and tenants without an allow-list,
+/// may use every model.
+pub fn is_model_allowed(tenant: Option<&TenantConfig>, model: &str) -> bool {
+    match tenant {
+        Some(tenant) if !tenant.allowed_models.is_empty() => tenant
+            .allowed_models
+            .iter()
+            .any(|pattern| model_matches(pattern, model)),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProxyConfig {
+        ProxyConfig {
+            tenants: vec![TenantConfig {
+                id: "acme".to_string(),
+                api_keys: vec!["sk-acme".to_string()],
+                allowed_models: vec!["gpt-4o".to_string(), "claude-*".to_string()],
+                token_quota: None,
+                user_token_quota: None,
+                allowed_regions: Vec::new(),
+                memory_retention_secs: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_tenant_from_bearer_token() {
+        let config = config();
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer sk-acme".parse().unwrap());
+
+        let tenant = resolve_tenant(&config, &headers).unwrap();
+        assert_eq!(tenant.id, "acme");
+        assert!(is_model_allowed(Some(tenant), "claude-3-haiku"));
+        assert!(!is_model_allowed(Some(tenant), "gpt-3.5-turbo"));
+    }
+
+    #[test]
+    fn test_unknown_key_has_no_tenant() {
+        let config = config();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "sk-other".parse().unwrap());
+
+        assert!(resolve_tenant(&config, &headers).is_none());
+        assert!(is_model_allowed(None, "gpt-3.5-turbo"));
+    }
+
+    #[test]
+    fn test_admin_keys() {
+        let mut config = config();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", "sk-admin".parse().unwrap());
+        assert!(!is_admin(&config, &headers));
+        config.allow_anonymous_admin = true;
+        assert!(is_admin(&config, &headers));
+
+        config.admin_api_keys = vec!["sk-root".to_string()];
+        assert!(!is_admin(&config, &headers));
+        headers.insert("x-api-key", "sk-root".parse().unwrap());
+        assert!(is_admin(&config, &headers));
+    }
+}
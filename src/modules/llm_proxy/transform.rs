@@ -0,0 +1,252 @@
+//! Per-model Request/Response Transformation
+//!
+//! This module applies operator-defined transformation rules to requests before
+//! they are forwarded to a provider, and to responses before they are returned
+//! to the client. Rules are configured per model in the `[proxy]` section.
+
+use std::collections::HashMap;
+
+use tracing::trace;
+
+use crate::config::ModelTransformConfig;
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatMessage, MessageRole,
+};
+
+/// Applies per-model transformation rules to connector requests and responses
+#[derive(Debug, Clone, Default)]
+pub struct ModelTransformer {
+    /// Configured rules, applied in order
+    rules: Vec<ModelTransformConfig>,
+}
+
+impl ModelTransformer {
+    /// Create a new transformer from a list of rules
+    pub fn new(rules: Vec<ModelTransformConfig>) -> Self {
+        Self { rules }
+    }
+
+    /// Check whether any rules are configured
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Get the rules that apply to a model
+    pub fn rules_for<'a>(
+        &'a self,
+        model: &'a str,
+    ) -> impl Iterator<Item = &'a ModelTransformConfig> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| model_matches(&rule.model, model))
+    }
+
+    /// Apply all matching rules to an outgoing request
+    pub fn apply_request(&self, request: &mut ChatCompletionRequest) {
+        let model = request.model.clone();
+        for rule in self.rules_for(&model) {
+            apply_request_rule(rule, request);
+        }
+    }
+
+    /// Apply all matching rules to a provider response
+    pub fn apply_response(&self, model: &str, response: &mut ChatCompletionResponse) {
+        for rule in self.rules_for(model) {
+            for choice in &mut response.choices {
+                for prefix in &rule.strip_response_prefixes {
+                    if let Some(stripped) = choice.message.content.strip_prefix(prefix.as_str()) {
+                        trace!(
+                            model = model,
+                            rule = %rule.model,
+                            "transform: stripped response prefix {:?}",
+                            prefix
+                        );
+                        choice.message.content = stripped.trim_start().to_string();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Check whether a model pattern matches a model ID
+///
+/// Patterns are either exact model IDs or prefixes terminated with `*`.
+/// Transform rules, routing policies, tenant allow-lists, residency regions
+/// and tokenizer mappings all match models this way.
+pub fn model_matches(pattern: &str, model: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => model.starts_with(prefix),
+        None => pattern == model,
+    }
+}
+
+/// Apply a single rule to a request
+fn apply_request_rule(rule: &ModelTransformConfig, request: &mut ChatCompletionRequest) {
+    if let Some(prefix) = &rule.system_prompt_prefix {
+        match request
+            .messages
+            .iter_mut()
+            .find(|m| m.role == MessageRole::System)
+        {
+            Some(system) => {
+                system.content = format!("{}\n\n{}", prefix, system.content);
+            }
+            None => {
+                request.messages.insert(
+                    0,
+                    ChatMessage {
+                        role: MessageRole::System,
+                        content: prefix.clone(),
+                        name: None,
+                        function_call: None,
+                        tool_calls: None,
+                        tool_call_id: None,
+                    },
+                );
+            }
+        }
+        trace!(rule = %rule.model, "transform: applied system prompt prefix");
+    }
+
+    if !rule.stop_sequences.is_empty() {
+        let params = request.additional_params.get_or_insert_with(HashMap::new);
+        let mut stop: Vec<String> = params
+            .get("stop")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        for sequence in &rule.stop_sequences {
+            if !stop.contains(sequence) {
+                stop.push(sequence.clone());
+            }
+        }
+        params.insert("stop".to_string(), serde_json::json!(stop));
+        trace!(rule = %rule.model, "transform: injected stop sequences {:?}", rule.stop_sequences);
+    }
+
+    if let Some(temperature) = request.temperature {
+        let mut clamped = temperature;
+        if let Some(min) = rule.min_temperature {
+            clamped = clamped.max(min);
+        }
+        if let Some(max) = rule.max_temperature {
+            clamped = clamped.min(max);
+        }
+        if clamped != temperature {
+            trace!(
+                rule = %rule.model,
+                "transform: clamped temperature {} -> {}",
+                temperature,
+                clamped
+            );
+            request.temperature = Some(clamped);
+        }
+    }
+
+    for (from, to) in &rule.rename_params {
+        if rename_param(request, from, to) {
+            trace!(rule = %rule.model, "transform: renamed parameter {} -> {}", from, to);
+        }
+    }
+}
+
+/// Rename a request parameter, moving typed fields into `additional_params`
+///
+/// Returns `true` if a parameter was renamed.
+fn rename_param(request: &mut ChatCompletionRequest, from: &str, to: &str) -> bool {
+    let value = match from {
+        "temperature" => request.temperature.take().map(|v| serde_json::json!(v)),
+        "top_p" => request.top_p.take().map(|v| serde_json::json!(v)),
+        "max_tokens" => request.max_tokens.take().map(|v| serde_json::json!(v)),
+        _ => request
+            .additional_params
+            .as_mut()
+            .and_then(|params| params.remove(from)),
+    };
+
+    match value {
+        Some(value) => {
+            request
+                .additional_params
+                .get_or_insert_with(HashMap::new)
+                .insert(to.to_string(), value);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(model: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: Some(1.8),
+            top_p: None,
+            max_tokens: Some(100),
+            stream: Some(false),
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    #[test]
+    fn test_model_matches() {
+        assert!(model_matches("gpt-4o", "gpt-4o"));
+        assert!(!model_matches("gpt-4o", "gpt-4o-mini"));
+        assert!(model_matches("claude-*", "claude-3-opus"));
+        assert!(model_matches("*", "anything"));
+    }
+
+    #[test]
+    fn test_apply_request_rules() {
+        let transformer = ModelTransformer::new(vec![ModelTransformConfig {
+            model: "o1-*".to_string(),
+            system_prompt_prefix: Some("Be concise.".to_string()),
+            stop_sequences: vec!["###".to_string()],
+            max_temperature: Some(1.0),
+            rename_params: HashMap::from([(
+                "max_tokens".to_string(),
+                "max_completion_tokens".to_string(),
+            )]),
+            ..Default::default()
+        }]);
+
+        let mut req = request("o1-preview");
+        transformer.apply_request(&mut req);
+
+        assert_eq!(req.messages[0].role, MessageRole::System);
+        assert_eq!(req.messages[0].content, "Be concise.");
+        assert_eq!(req.temperature, Some(1.0));
+        assert_eq!(req.max_tokens, None);
+        let params = req.additional_params.unwrap();
+        assert_eq!(params["max_completion_tokens"], serde_json::json!(100));
+        assert_eq!(params["stop"], serde_json::json!(["###"]));
+    }
+
+    #[test]
+    fn test_non_matching_model_untouched() {
+        let transformer = ModelTransformer::new(vec![ModelTransformConfig {
+            model: "o1-*".to_string(),
+            max_temperature: Some(1.0),
+            ..Default::default()
+        }]);
+
+        let mut req = request("gpt-4o");
+        transformer.apply_request(&mut req);
+
+        assert_eq!(req.temperature, Some(1.8));
+        assert_eq!(req.messages.len(), 1);
+    }
+}
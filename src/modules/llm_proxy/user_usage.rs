@@ -0,0 +1,222 @@
+//! End-User Usage Analytics
+//!
+//! This module sums the usage of each end user, as named by the OpenAI `user`
+//! field of requests, per tenant. Unlike the sampled decision log, every
+//! completed request is counted. The least recently seen users are dropped
+//! once the configured number of users is tracked.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::decision_log::RoutingDecision;
+use crate::config::UserUsageConfig;
+use crate::modules::erasure::ErasureSubject;
+use crate::modules::telemetry::CostCorrection;
+
+/// Usage of an end user of a tenant
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UserUsage {
+    /// Tenant of the user, if the requests were authenticated as one
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    pub user: String,
+    /// Requests completed
+    pub requests: u64,
+    /// Requests that failed
+    pub errors: u64,
+    /// Prompt tokens used
+    pub prompt_tokens: u64,
+    /// Completion tokens used
+    pub completion_tokens: u64,
+    /// Estimated cost in USD of the requests with known pricing
+    pub cost_usd: f64,
+    /// Requests by serving model
+    pub models: BTreeMap<String, u64>,
+    /// Time of the first request
+    pub first_seen: DateTime<Utc>,
+    /// Time of the latest request
+    pub last_seen: DateTime<Utc>,
+}
+
+impl UserUsage {
+    /// Usage of a user before any request
+    fn new(tenant: Option<String>, user: String, now: DateTime<Utc>) -> Self {
+        Self {
+            tenant,
+            user,
+            requests: 0,
+            errors: 0,
+            prompt_tokens: 0,
+            completion_tokens: 0,
+            cost_usd: 0.0,
+            models: BTreeMap::new(),
+            first_seen: now,
+            last_seen: now,
+        }
+    }
+
+    /// Prompt plus completion tokens used
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+}
+
+/// Usage of the end users of every tenant
+#[derive(Debug)]
+pub struct UserUsageLog {
+    config: UserUsageConfig,
+    /// Usage by tenant and user
+    users: Mutex<HashMap<(Option<String>, String), UserUsage>>,
+}
+
+impl Default for UserUsageLog {
+    fn default() -> Self {
+        Self::new(UserUsageConfig::default())
+    }
+}
+
+impl UserUsageLog {
+    /// Create an empty usage log
+    pub fn new(config: UserUsageConfig) -> Self {
+        Self {
+            config,
+            users: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a completed request against its end user, if it names one
+    pub fn observe(&self, decision: &RoutingDecision) {
+        if !self.config.enabled || self.config.capacity == 0 {
+            return;
+        }
+        let Some(user) = &decision.user else {
+            return;
+        };
+        let key = (decision.tenant.clone(), user.clone());
+        let mut users = self.users.lock().unwrap();
+        if !users.contains_key(&key) && users.len() >= self.config.capacity {
+            let oldest = users
+                .iter()
+                .min_by_key(|(_, usage)| usage.last_seen)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                users.remove(&oldest);
+            }
+        }
+
+        let usage = users.entry(key).or_insert_with(|| {
+            UserUsage::new(decision.tenant.clone(), user.clone(), decision.timestamp)
+        });
+        usage.requests += 1;
+        if decision.error.is_some() {
+            usage.errors += 1;
+        }
+        usage.prompt_tokens += u64::from(decision.prompt_tokens);
+        usage.completion_tokens += u64::from(decision.completion_tokens);
+        usage.cost_usd += decision.cost_usd.unwrap_or(0.0);
+        *usage.models.entry(decision.model.clone()).or_default() += 1;
+        usage.last_seen = usage.last_seen.max(decision.timestamp);
+    }
+
+    /// Adjust the costs of end users to recomputed costs of their requests
+    ///
+    /// Returns how many users were adjusted.
+    pub fn apply_cost_corrections(&self, corrections: &[CostCorrection]) -> usize {
+        let mut users = self.users.lock().unwrap();
+        let mut adjusted = HashSet::new();
+        for correction in corrections {
+            let Some(user) = &correction.user else {
+                continue;
+            };
+            let key = (correction.tenant.clone(), user.clone());
+            if let Some(usage) = users.get_mut(&key) {
+                usage.cost_usd = (usage.cost_usd + correction.delta()).max(0.0);
+                adjusted.insert(key);
+            }
+        }
+        adjusted.len()
+    }
+
+    /// Usage of the end users of a tenant, or of every tenant, by most
+    /// tokens used
+    pub fn users(&self, tenant: Option<&str>) -> Vec<UserUsage> {
+        let mut users: Vec<UserUsage> = self
+            .users
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|usage| tenant.is_none() || usage.tenant.as_deref() == tenant)
+            .cloned()
+            .collect();
+        users.sort_by(|a, b| {
+            b.total_tokens()
+                .cmp(&a.total_tokens())
+                .then_with(|| a.user.cmp(&b.user))
+        });
+        users
+    }
+
+    /// Usage of an end user of a tenant
+    pub fn user(&self, tenant: Option<&str>, user: &str) -> Option<UserUsage> {
+        self.users
+            .lock()
+            .unwrap()
+            .get(&(tenant.map(str::to_string), user.to_string()))
+            .cloned()
+    }
+
+    /// Delete the usage of a data subject, returning the users deleted
+    pub fn erase(&self, subject: &ErasureSubject) -> usize {
+        let mut users = self.users.lock().unwrap();
+        let before = users.len();
+        users.retain(|(tenant, user), _| !subject.matches(tenant.as_deref(), Some(user)));
+        before - users.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decision(tenant: &str, user: &str, tokens: u32) -> RoutingDecision {
+        RoutingDecision {
+            tenant: Some(tenant.to_string()),
+            user: Some(user.to_string()),
+            prompt_tokens: tokens,
+            completion_tokens: tokens,
+            ..RoutingDecision::new("gpt-4o", "gpt-4o")
+        }
+    }
+
+    #[test]
+    fn test_user_usage() {
+        let log = UserUsageLog::new(UserUsageConfig {
+            enabled: true,
+            capacity: 2,
+        });
+        log.observe(&decision("acme", "alice", 10));
+        log.observe(&decision("acme", "alice", 5));
+        log.observe(&decision("globex", "alice", 100));
+        log.observe(&RoutingDecision::new("gpt-4o", "gpt-4o"));
+
+        let acme = log.users(Some("acme"));
+        assert_eq!(acme.len(), 1);
+        assert_eq!(acme[0].requests, 2);
+        assert_eq!(acme[0].total_tokens(), 30);
+        assert_eq!(acme[0].models["gpt-4o"], 2);
+        assert_eq!(log.users(None)[0].tenant.as_deref(), Some("globex"));
+
+        // The least recently seen user is dropped at capacity
+        log.observe(&decision("acme", "bob", 1));
+        assert!(log.user(Some("acme"), "alice").is_none());
+        assert!(log.user(Some("acme"), "bob").is_some());
+
+        let subject = ErasureSubject::new("acme", Some("bob".to_string())).unwrap();
+        assert_eq!(log.erase(&subject), 1);
+        assert_eq!(log.users(None).len(), 1);
+    }
+}
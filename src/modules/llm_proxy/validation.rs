@@ -83,6 +83,49 @@ pub fn validate_chat_completion_request(request: &ChatCompletionRequest) -> Resu
         }
     }
 
+    // Validate stop sequences
+    if let Some(stop) = &request.stop {
+        let sequences = stop.to_vec();
+        if sequences.len() > 4 {
+            return Err(create_validation_error(
+                "stop must contain at most 4 sequences",
+                Some("stop"),
+            ));
+        }
+        if sequences.iter().any(|s| s.is_empty()) {
+            return Err(create_validation_error(
+                "stop sequences cannot be empty",
+                Some("stop"),
+            ));
+        }
+    }
+
+    // Validate logit_bias
+    if let Some(logit_bias) = &request.logit_bias {
+        for (token, bias) in logit_bias {
+            if token.parse::<u64>().is_err() {
+                return Err(create_validation_error(
+                    &format!("logit_bias key '{}' must be a token ID", token),
+                    Some("logit_bias"),
+                ));
+            }
+            if *bias < -100.0 || *bias > 100.0 {
+                return Err(create_validation_error(
+                    "logit_bias values must be between -100 and 100",
+                    Some("logit_bias"),
+                ));
+            }
+        }
+    }
+
+    // Validate top_k
+    if request.top_k == Some(0) {
+        return Err(create_validation_error(
+            "top_k must be greater than 0",
+            Some("top_k"),
+        ));
+    }
+
     Ok(())
 }
 
@@ -369,6 +412,12 @@ mod tests {
             presence_penalty: Some(0.0),
             frequency_penalty: Some(0.0),
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
         assert!(validate_chat_completion_request(&valid_request).is_ok());
 
@@ -390,6 +439,12 @@ mod tests {
             presence_penalty: Some(0.0),
             frequency_penalty: Some(0.0),
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
         assert!(validate_chat_completion_request(&valid_array_request).is_ok());
 
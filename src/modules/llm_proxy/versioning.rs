@@ -0,0 +1,306 @@
+//! API Versioning
+//!
+//! The public HTTP API is served under a path prefix per version, such as
+//! `/v1` and `/v2`, whose route trees share their handlers. Handlers that
+//! need to tell versions apart, when a request schema evolves, extract the
+//! [`ApiVersion`] of the request.
+//!
+//! Deprecations are configured per version or per route. Responses of
+//! deprecated routes carry the `Deprecation` (RFC 9745), `Sunset` (RFC 8594)
+//! and `Link` headers, and routes past their sunset can be refused with
+//! `410 Gone`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::dto::{ApiError, ApiErrorDetail};
+use crate::config::{ApiDeprecationConfig, ApiVersioningConfig};
+use crate::modules::telemetry::catalog::API_DEPRECATED_REQUESTS;
+
+/// Version of the public HTTP API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    /// Routes under `/v1`
+    V1,
+    /// Routes under `/v2`
+    V2,
+}
+
+impl ApiVersion {
+    /// Every version, oldest first
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    /// Name of the version, e.g. `v1`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+
+    /// Path prefix of the version's routes, e.g. `/v1`
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Self::V1 => "/v1",
+            Self::V2 => "/v2",
+        }
+    }
+
+    /// Split a request path into its version and the route within it
+    pub fn split(path: &str) -> Option<(Self, &str)> {
+        Self::ALL.into_iter().find_map(|version| {
+            let rest = path.strip_prefix(version.prefix())?;
+            (rest.is_empty() || rest.starts_with('/')).then_some((version, rest))
+        })
+    }
+}
+
+impl std::fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The version of a request, `v1` outside the versioned routes
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<ApiVersion>()
+            .copied()
+            .unwrap_or(ApiVersion::V1))
+    }
+}
+
+/// Served API versions and their deprecations
+#[derive(Debug, Clone)]
+pub struct ApiVersioning {
+    versions: Vec<ApiVersion>,
+    deprecations: Vec<ApiDeprecationConfig>,
+}
+
+impl ApiVersioning {
+    /// Create the versioning of the configuration
+    pub fn new(config: &ApiVersioningConfig) -> Self {
+        let mut versions = config.versions.clone();
+        versions.sort();
+        versions.dedup();
+        Self {
+            versions,
+            deprecations: config.deprecations.clone(),
+        }
+    }
+
+    /// Served versions, oldest first
+    pub fn versions(&self) -> &[ApiVersion] {
+        &self.versions
+    }
+
+    /// First configured deprecation of a route of a version
+    pub fn deprecation(&self, version: ApiVersion, route: &str) -> Option<&ApiDeprecationConfig> {
+        self.deprecations.iter().find(|deprecation| {
+            deprecation.version == version
+                && deprecation
+                    .path
+                    .as_deref()
+                    .is_none_or(|path| match path.strip_suffix('*') {
+                        Some(prefix) => route.starts_with(prefix),
+                        None => route == path,
+                    })
+        })
+    }
+
+    /// Newest served version after `version`, to which clients should move
+    pub fn successor(&self, version: ApiVersion) -> Option<ApiVersion> {
+        self.versions.iter().copied().filter(|v| *v > version).max()
+    }
+}
+
+/// Middleware tagging requests with their API version and reporting the
+/// deprecation of their route
+pub async fn api_version_middleware(
+    State(versioning): State<Arc<ApiVersioning>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some((version, route)) = ApiVersion::split(request.uri().path())
+        .map(|(version, route)| (version, route.to_string()))
+    else {
+        return next.run(request).await;
+    };
+    request.extensions_mut().insert(version);
+    let Some(deprecation) = versioning.deprecation(version, &route).cloned() else {
+        return next.run(request).await;
+    };
+
+    let path = deprecation.path.clone().unwrap_or_else(|| "*".to_string());
+    let sunset_passed = deprecation
+        .sunset
+        .is_some_and(|sunset| sunset <= Utc::now());
+    if deprecation.enforce_sunset && sunset_passed {
+        counter!(API_DEPRECATED_REQUESTS, 1, "version" => version.as_str(), "path" => path, "outcome" => "gone");
+        debug!("Refused {}{} past its sunset", version.prefix(), route);
+        let mut response = (
+            StatusCode::GONE,
+            Json(ApiError {
+                error: ApiErrorDetail {
+                    message: format!(
+                        "{}{} is no longer served{}",
+                        version.prefix(),
+                        route,
+                        match versioning.successor(version) {
+                            Some(successor) => format!("; use {}{}", successor.prefix(), route),
+                            None => String::new(),
+                        }
+                    ),
+                    r#type: "invalid_request_error".to_string(),
+                    param: None,
+                    code: Some("api_version_sunset".to_string()),
+                },
+            }),
+        )
+            .into_response();
+        apply_deprecation_headers(&versioning, version, &route, &deprecation, &mut response);
+        return response;
+    }
+
+    counter!(API_DEPRECATED_REQUESTS, 1, "version" => version.as_str(), "path" => path, "outcome" => "served");
+    let mut response = next.run(request).await;
+    apply_deprecation_headers(&versioning, version, &route, &deprecation, &mut response);
+    response
+}
+
+/// Add the `Deprecation`, `Sunset` and `Link` headers of a deprecated route
+fn apply_deprecation_headers(
+    versioning: &ApiVersioning,
+    version: ApiVersion,
+    route: &str,
+    deprecation: &ApiDeprecationConfig,
+    response: &mut Response,
+) {
+    let headers = response.headers_mut();
+    let deprecated = match deprecation.deprecated_at {
+        Some(at) => format!("@{}", at.timestamp()),
+        None => "true".to_string(),
+    };
+    if let Ok(value) = HeaderValue::from_str(&deprecated) {
+        headers.insert("deprecation", value);
+    }
+    if let Some(sunset) = deprecation.sunset {
+        let http_date = sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        if let Ok(value) = HeaderValue::from_str(&http_date) {
+            headers.insert("sunset", value);
+        }
+    }
+
+    let mut links = Vec::new();
+    if let Some(link) = &deprecation.link {
+        links.push(format!(
+            "<{}>; rel=\"deprecation\"; type=\"text/html\"",
+            link
+        ));
+    }
+    if let Some(successor) = versioning.successor(version) {
+        links.push(format!(
+            "<{}{}>; rel=\"successor-version\"",
+            successor.prefix(),
+            route
+        ));
+    }
+    if links.is_empty() {
+        return;
+    }
+    if let Ok(value) = HeaderValue::from_str(&links.join(", ")) {
+        headers.append("link", value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware::from_fn_with_state, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            ApiVersion::split("/v1/chat/completions"),
+            Some((ApiVersion::V1, "/chat/completions"))
+        );
+        assert_eq!(ApiVersion::split("/v2"), Some((ApiVersion::V2, "")));
+        assert_eq!(ApiVersion::split("/v10/models"), None);
+        assert_eq!(ApiVersion::split("/health"), None);
+    }
+
+    #[tokio::test]
+    async fn test_deprecation_headers() {
+        let config: ApiVersioningConfig = toml::from_str(
+            r#"
+            versions = ["v1", "v2"]
+
+            [[deprecations]]
+            version = "v1"
+            path = "/legacy*"
+            sunset = "2020-01-01T00:00:00Z"
+            enforce_sunset = true
+
+            [[deprecations]]
+            version = "v1"
+            deprecated_at = "2026-01-01T00:00:00Z"
+            sunset = "2099-06-30T00:00:00Z"
+            link = "https://example.com/migrate"
+            "#,
+        )
+        .unwrap();
+        let versioning = Arc::new(ApiVersioning::new(&config));
+
+        let version_of = |version: ApiVersion| async move { version.as_str() };
+        let routes = Router::new()
+            .route("/models", get(version_of))
+            .route("/legacy/models", get(version_of));
+        let app = Router::new()
+            .nest("/v1", routes.clone())
+            .nest("/v2", routes)
+            .layer(from_fn_with_state(versioning, api_version_middleware));
+        let send = |path: &'static str| {
+            app.clone()
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+        };
+
+        let response = send("/v1/models").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(headers["deprecation"], "@1767225600");
+        assert_eq!(headers["sunset"], "Tue, 30 Jun 2099 00:00:00 GMT");
+        assert_eq!(
+            headers["link"],
+            "<https://example.com/migrate>; rel=\"deprecation\"; type=\"text/html\", \
+             </v2/models>; rel=\"successor-version\""
+        );
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"v1");
+
+        let response = send("/v2/models").await.unwrap();
+        assert!(response.headers().get("deprecation").is_none());
+
+        let response = send("/v1/legacy/models").await.unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        assert_eq!(response.headers()["deprecation"], "true");
+    }
+}
@@ -266,10 +266,35 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec!["*".to_string()],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
+                subsystems: Default::default(),
+                language: Default::default(),
             },
             shared: std::sync::Arc::new(tokio::sync::Mutex::new(super::server::SharedState::new())),
             telemetry: Some(telemetry),
             cost_calculator: Some(cost_calculator),
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+            telemetry_export: None,
+            anomalies: None,
+            canaries: None,
+            metering: None,
+            admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+            streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+            coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+            prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+            rollouts: Arc::new(
+                crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+            ),
+            user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+            payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+            partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+            discovery: None,
+            prompt_traces: Arc::new(
+                crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default(),
+            ),
         };
 
         // Create a channel for testing
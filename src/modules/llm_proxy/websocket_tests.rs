@@ -33,6 +33,9 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec![],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
+                subsystems: Default::default(),
+                language: Default::default(),
             },
             shared: Arc::new(Mutex::new(SharedState {
                 active_connections: 0,
@@ -40,6 +43,28 @@ mod tests {
             })),
             telemetry: None,
             cost_calculator: None,
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
+            telemetry_export: None,
+            anomalies: None,
+            canaries: None,
+            metering: None,
+            admin_audit: Arc::new(crate::modules::llm_proxy::admin::AdminAuditLog::default()),
+            streams: Arc::new(crate::modules::llm_proxy::stream_buffer::StreamBuffer::default()),
+            coalescer: Arc::new(crate::modules::llm_proxy::coalesce::RequestCoalescer::default()),
+            prompts: Arc::new(crate::modules::llm_proxy::prompts::PromptRegistry::default()),
+            rollouts: Arc::new(
+                crate::modules::llm_proxy::model_rollout::ModelRolloutController::default(),
+            ),
+            user_usage: Arc::new(crate::modules::llm_proxy::user_usage::UserUsageLog::default()),
+            payloads: Arc::new(crate::modules::llm_proxy::payloads::PayloadStore::default()),
+            partials: Arc::new(crate::modules::llm_proxy::partials::PartialResponses::default()),
+            discovery: None,
+            prompt_traces: Arc::new(
+                crate::modules::llm_proxy::prompt_trace::PromptTraceLog::default(),
+            ),
             shared: Arc::new(Mutex::new(SharedState::new())),
         }
     }
@@ -90,6 +115,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: Some(0.7),
             top_p: None,
@@ -99,6 +125,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Serialize the request to JSON
@@ -164,6 +196,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: Some(0.7),
             top_p: None,
@@ -173,6 +206,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Serialize the request to JSON
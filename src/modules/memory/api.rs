@@ -0,0 +1,736 @@
+//! Memory API
+//!
+//! This module exposes namespaced conversation memories over HTTP, including
+//! the listing and deletion endpoints used to serve data deletion requests.
+//! When semantic memory is enabled, a user's long-term facts can also be
+//! extracted, searched, listed and deleted. Callers authenticate as the
+//! tenant owning the namespace, or with an admin role: viewers can read,
+//! operators can also write and admins can also delete and restore.
+//! Deletions and restores are recorded as audit events.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{
+    self, AdminAuditLog, AdminPrincipal, AdminRole, AuditEvent, AuditOutcome,
+};
+use crate::modules::llm_proxy::dto::ApiError;
+use crate::modules::llm_proxy::tenant;
+use crate::modules::memory::{
+    MemoryError, MemoryManager, MemoryNamespace, MemorySettings, SemanticMemory,
+};
+
+/// State of the memory API
+struct MemoryApiState {
+    manager: Arc<MemoryManager>,
+    /// Long-term semantic memory, if enabled
+    semantic: Option<Arc<SemanticMemory>>,
+    /// Tenant credentials and admin keys
+    proxy: ProxyConfig,
+    /// Audit events of deletions
+    audit: Arc<AdminAuditLog>,
+}
+
+/// OpenAPI description of the memory API
+#[derive(OpenApi)]
+#[openapi(paths(
+    list_conversations,
+    create_conversation,
+    get_conversation,
+    add_message,
+    delete_conversation,
+    restore_conversation,
+    fork_conversation,
+    delete_user,
+    delete_tenant,
+    remember_conversation,
+    list_memories,
+    search_memories,
+    delete_memory,
+))]
+pub struct MemoryApiDoc;
+
+/// Create a router for the memory API
+pub fn create_memory_router(
+    manager: Arc<MemoryManager>,
+    semantic: Option<Arc<SemanticMemory>>,
+    proxy: ProxyConfig,
+    audit: Arc<AdminAuditLog>,
+) -> Router {
+    let state = Arc::new(MemoryApiState {
+        manager,
+        semantic,
+        proxy,
+        audit,
+    });
+    Router::new()
+        .route("/v1/memory/tenants/{tenant}", delete(delete_tenant))
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}",
+            delete(delete_user),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations",
+            get(list_conversations).post(create_conversation),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}",
+            get(get_conversation).delete(delete_conversation),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/messages",
+            post(add_message),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/fork",
+            post(fork_conversation),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/restore",
+            post(restore_conversation),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/remember",
+            post(remember_conversation),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/memories",
+            get(list_memories),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/memories/search",
+            post(search_memories),
+        )
+        .route(
+            "/v1/memory/tenants/{tenant}/users/{user}/memories/{id}",
+            delete(delete_memory),
+        )
+        .with_state(state)
+}
+
+/// Request to add a message to a conversation
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddMessageRequest {
+    pub role: String,
+    pub content: String,
+}
+
+/// Request to fork a conversation
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct ForkConversationRequest {
+    /// Index of the first message left out of the fork
+    pub index: usize,
+}
+
+/// Request to search a user's long-term memories
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct SearchMemoriesRequest {
+    pub query: String,
+    pub top_k: Option<usize>,
+    pub min_score: Option<f32>,
+}
+
+/// Build an error response
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "memory_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+/// Map a memory error to an error response
+fn memory_error_response(e: MemoryError) -> Response {
+    match e {
+        MemoryError::NotFound(id) => error_response(
+            StatusCode::NOT_FOUND,
+            format!("Conversation not found: {}", id),
+            "conversation_not_found",
+        ),
+        MemoryError::InvalidKey(message) => {
+            error_response(StatusCode::BAD_REQUEST, message, "invalid_namespace")
+        }
+        MemoryError::AccessDenied(message) => {
+            error_response(StatusCode::FORBIDDEN, message, "access_denied")
+        }
+        e @ MemoryError::InvalidIndex { .. } => error_response(
+            StatusCode::BAD_REQUEST,
+            e.to_string(),
+            "invalid_message_index",
+        ),
+        e => {
+            error!("Memory operation failed: {}", e);
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+                "memory_error",
+            )
+        }
+    }
+}
+
+/// Caller of the memory API
+enum Caller {
+    /// Tenant owning the addressed memories
+    Tenant(String),
+    /// Admin principal
+    Admin(AdminPrincipal),
+}
+
+/// Check that the caller may access a tenant's memories
+///
+/// Tenants may access their own memories; admin principals need `required`.
+fn authorize(
+    state: &MemoryApiState,
+    headers: &HeaderMap,
+    tenant_id: &str,
+    required: AdminRole,
+) -> Result<Caller, MemoryError> {
+    if let Some(tenant) = tenant::resolve_tenant(&state.proxy, headers) {
+        if tenant.id == tenant_id {
+            return Ok(Caller::Tenant(tenant.id.clone()));
+        }
+    }
+    admin::authorize(&state.proxy, headers, required)
+        .map(Caller::Admin)
+        .map_err(|_| MemoryError::AccessDenied(format!("Not authorized for tenant {}", tenant_id)))
+}
+
+/// Response to semantic memory requests when it is disabled
+fn semantic_disabled() -> Response {
+    error_response(
+        StatusCode::NOT_FOUND,
+        "Semantic memory is not enabled".to_string(),
+        "semantic_memory_disabled",
+    )
+}
+
+/// Authorize the caller and build the namespace addressed by a request
+fn namespace(
+    state: &MemoryApiState,
+    headers: &HeaderMap,
+    tenant_id: String,
+    user: String,
+    required: AdminRole,
+) -> Result<(Caller, MemoryNamespace), MemoryError> {
+    let caller = authorize(state, headers, &tenant_id, required)?;
+    Ok((caller, MemoryNamespace::new(tenant_id, user)?))
+}
+
+/// Record the outcome of a deletion or restore
+fn audit<T>(
+    state: &MemoryApiState,
+    caller: Result<&Caller, &MemoryError>,
+    action: &str,
+    resource: &str,
+    result: Result<T, &MemoryError>,
+) {
+    let (subject, role) = match caller {
+        Ok(Caller::Tenant(id)) => (Some(format!("tenant:{}", id)), None),
+        Ok(Caller::Admin(principal)) => (Some(principal.subject.clone()), Some(principal.role)),
+        Err(_) => (None, None),
+    };
+    let (outcome, detail) = match (caller, result) {
+        (Err(e), _) => (AuditOutcome::Denied, Some(e.to_string())),
+        (Ok(_), Ok(_)) => (AuditOutcome::Success, None),
+        (Ok(_), Err(e)) => (AuditOutcome::Failure, Some(e.to_string())),
+    };
+    state.audit.push(AuditEvent::new(
+        subject, role, action, resource, outcome, detail,
+    ));
+}
+
+/// Route handler for GET /v1/memory/tenants/{tenant}/users/{user}/conversations
+#[utoipa::path(
+    get,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Conversation IDs under `conversations`", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError)
+    )
+)]
+async fn list_conversations(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user)): Path<(String, String)>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Viewer) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    match state.manager.list_conversations_in(&namespace).await {
+        Ok(ids) => Json(json!({ "conversations": ids })).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/conversations
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID")),
+    responses(
+        (status = 201, description = "Created conversation", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError)
+    )
+)]
+async fn create_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user)): Path<(String, String)>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Operator) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    match state.manager.create_conversation_in(&namespace).await {
+        Ok(conversation) => (StatusCode::CREATED, Json(conversation)).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for GET /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}
+#[utoipa::path(
+    get,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, description = "Conversation", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Unknown conversation", body = ApiError)
+    )
+)]
+async fn get_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Viewer) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    match state.manager.get_conversation_in(&namespace, &id).await {
+        Ok(Some(conversation)) => Json(conversation).into_response(),
+        Ok(None) => memory_error_response(MemoryError::NotFound(id)),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/messages
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/messages",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    request_body = AddMessageRequest,
+    responses(
+        (status = 204, description = "Message added"),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Unknown conversation", body = ApiError)
+    )
+)]
+async fn add_message(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+    Json(request): Json<AddMessageRequest>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Operator) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    match state
+        .manager
+        .add_message_in(&namespace, &id, &request.role, &request.content)
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/fork
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/fork",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    request_body = ForkConversationRequest,
+    responses(
+        (status = 201, description = "Fork holding the messages before the index", body = Object),
+        (status = 400, description = "Index past the end of the conversation", body = ApiError),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Unknown conversation", body = ApiError)
+    )
+)]
+async fn fork_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+    Json(request): Json<ForkConversationRequest>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Operator) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    match state
+        .manager
+        .fork_conversation_in(&namespace, &id, request.index)
+        .await
+    {
+        Ok(fork) => (StatusCode::CREATED, Json(fork)).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for DELETE /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}
+#[utoipa::path(
+    delete,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 204, description = "Conversation deleted"),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Unknown conversation", body = ApiError)
+    )
+)]
+async fn delete_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+) -> Response {
+    let action = "memory.delete_conversation";
+    let resource = format!("{}/{}/conversations/{}", tenant_id, user, id);
+    let (caller, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Admin) {
+        Ok(addressed) => addressed,
+        Err(e) => {
+            audit(&state, Err(&e), action, &resource, Ok(()));
+            return memory_error_response(e);
+        }
+    };
+    let result = state.manager.delete_conversation_in(&namespace, &id).await;
+    audit(&state, Ok(&caller), action, &resource, result.as_ref());
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/restore
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/restore",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, description = "Restored conversation", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Unknown conversation, or deleted past the soft-delete window", body = ApiError)
+    )
+)]
+async fn restore_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+) -> Response {
+    let action = "memory.restore_conversation";
+    let resource = format!("{}/{}/conversations/{}", tenant_id, user, id);
+    let (caller, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Admin) {
+        Ok(addressed) => addressed,
+        Err(e) => {
+            audit(&state, Err(&e), action, &resource, Ok(()));
+            return memory_error_response(e);
+        }
+    };
+    let result = state.manager.restore_conversation_in(&namespace, &id).await;
+    audit(&state, Ok(&caller), action, &resource, result.as_ref());
+    match result {
+        Ok(conversation) => Json(conversation).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for DELETE /v1/memory/tenants/{tenant}/users/{user}
+#[utoipa::path(
+    delete,
+    path = "/v1/memory/tenants/{tenant}/users/{user}",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Numbers of deleted conversations and memories", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError)
+    )
+)]
+async fn delete_user(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user)): Path<(String, String)>,
+) -> Response {
+    let action = "memory.delete_user";
+    let resource = format!("{}/{}", tenant_id, user);
+    let (caller, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Admin) {
+        Ok(addressed) => addressed,
+        Err(e) => {
+            audit(&state, Err(&e), action, &resource, Ok(()));
+            return memory_error_response(e);
+        }
+    };
+    let result = delete_namespace(&state, &namespace).await;
+    audit(&state, Ok(&caller), action, &resource, result.as_ref());
+    match result {
+        Ok((deleted, deleted_memories)) => {
+            info!(
+                "Deleted {} conversation(s) and {} memories of user {} in tenant {}",
+                deleted, deleted_memories, namespace.user, namespace.tenant
+            );
+            Json(json!({ "deleted": deleted, "deleted_memories": deleted_memories }))
+                .into_response()
+        }
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Delete a namespace's conversations and semantic memories, returning how many of each
+async fn delete_namespace(
+    state: &MemoryApiState,
+    namespace: &MemoryNamespace,
+) -> Result<(usize, usize), MemoryError> {
+    let deleted = state.manager.delete_namespace(namespace).await?;
+    let deleted_memories = match &state.semantic {
+        Some(semantic) => semantic.store().delete_namespace(namespace).await?,
+        None => 0,
+    };
+    Ok((deleted, deleted_memories))
+}
+
+/// Route handler for DELETE /v1/memory/tenants/{tenant}
+#[utoipa::path(
+    delete,
+    path = "/v1/memory/tenants/{tenant}",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID")),
+    responses(
+        (status = 200, description = "Numbers of deleted conversations and memories", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError)
+    )
+)]
+async fn delete_tenant(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path(tenant_id): Path<String>,
+) -> Response {
+    let action = "memory.delete_tenant";
+    let caller = match authorize(&state, &headers, &tenant_id, AdminRole::Admin) {
+        Ok(caller) => caller,
+        Err(e) => {
+            audit(&state, Err(&e), action, &tenant_id, Ok(()));
+            return memory_error_response(e);
+        }
+    };
+    let result = delete_tenant_data(&state, &tenant_id).await;
+    audit(&state, Ok(&caller), action, &tenant_id, result.as_ref());
+    match result {
+        Ok((deleted, deleted_memories)) => {
+            info!(
+                "Deleted {} conversation(s) and {} memories of tenant {}",
+                deleted, deleted_memories, tenant_id
+            );
+            Json(json!({ "deleted": deleted, "deleted_memories": deleted_memories }))
+                .into_response()
+        }
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Delete a tenant's conversations and semantic memories, returning how many of each
+async fn delete_tenant_data(
+    state: &MemoryApiState,
+    tenant_id: &str,
+) -> Result<(usize, usize), MemoryError> {
+    let deleted = state.manager.delete_tenant(tenant_id).await?;
+    let deleted_memories = match &state.semantic {
+        Some(semantic) => semantic.store().delete_tenant(tenant_id).await?,
+        None => 0,
+    };
+    Ok((deleted, deleted_memories))
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/remember
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/conversations/{id}/remember",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Conversation ID")),
+    responses(
+        (status = 200, description = "Extracted facts under `memories`", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Semantic memory is not enabled", body = ApiError)
+    )
+)]
+async fn remember_conversation(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Operator) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    let Some(semantic) = &state.semantic else {
+        return semantic_disabled();
+    };
+    let conversation = match state.manager.get_conversation_in(&namespace, &id).await {
+        Ok(Some(conversation)) => conversation,
+        Ok(None) => return memory_error_response(MemoryError::NotFound(id)),
+        Err(e) => return memory_error_response(e),
+    };
+    match semantic
+        .remember(&namespace, Some(&id), &conversation.messages)
+        .await
+    {
+        Ok(facts) => Json(json!({ "memories": facts })).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for GET /v1/memory/tenants/{tenant}/users/{user}/memories
+#[utoipa::path(
+    get,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/memories",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID")),
+    responses(
+        (status = 200, description = "Facts under `memories`", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Semantic memory is not enabled", body = ApiError)
+    )
+)]
+async fn list_memories(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user)): Path<(String, String)>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Viewer) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    let Some(semantic) = &state.semantic else {
+        return semantic_disabled();
+    };
+    match semantic.store().list(&namespace).await {
+        Ok(facts) => Json(json!({ "memories": facts })).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for POST /v1/memory/tenants/{tenant}/users/{user}/memories/search
+#[utoipa::path(
+    post,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/memories/search",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID")),
+    request_body = SearchMemoriesRequest,
+    responses(
+        (status = 200, description = "Matching facts under `memories`", body = Object),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Semantic memory is not enabled", body = ApiError)
+    )
+)]
+async fn search_memories(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user)): Path<(String, String)>,
+    Json(request): Json<SearchMemoriesRequest>,
+) -> Response {
+    let (_, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Viewer) {
+        Ok(addressed) => addressed,
+        Err(e) => return memory_error_response(e),
+    };
+    let Some(semantic) = &state.semantic else {
+        return semantic_disabled();
+    };
+    let defaults = MemorySettings::default();
+    match semantic
+        .recall(
+            &namespace,
+            &request.query,
+            request.top_k.unwrap_or(defaults.top_k),
+            request.min_score.unwrap_or(0.0),
+        )
+        .await
+    {
+        Ok(facts) => Json(json!({ "memories": facts })).into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
+
+/// Route handler for DELETE /v1/memory/tenants/{tenant}/users/{user}/memories/{id}
+#[utoipa::path(
+    delete,
+    path = "/v1/memory/tenants/{tenant}/users/{user}/memories/{id}",
+    tag = "memory",
+    security(("bearer_auth" = [])),
+    params(("tenant" = String, Path, description = "Tenant ID"), ("user" = String, Path, description = "User ID"), ("id" = String, Path, description = "Memory ID")),
+    responses(
+        (status = 204, description = "Memory deleted"),
+        (status = 403, description = "Caller may not access the tenant", body = ApiError),
+        (status = 404, description = "Semantic memory is not enabled", body = ApiError)
+    )
+)]
+async fn delete_memory(
+    State(state): State<Arc<MemoryApiState>>,
+    headers: HeaderMap,
+    Path((tenant_id, user, id)): Path<(String, String, String)>,
+) -> Response {
+    let action = "memory.delete_memory";
+    let resource = format!("{}/{}/memories/{}", tenant_id, user, id);
+    let (caller, namespace) = match namespace(&state, &headers, tenant_id, user, AdminRole::Admin) {
+        Ok(addressed) => addressed,
+        Err(e) => {
+            audit(&state, Err(&e), action, &resource, Ok(()));
+            return memory_error_response(e);
+        }
+    };
+    let Some(semantic) = &state.semantic else {
+        return semantic_disabled();
+    };
+    let result = semantic.store().delete(&namespace, &id).await;
+    audit(&state, Ok(&caller), action, &resource, result.as_ref());
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => memory_error_response(e),
+    }
+}
@@ -2,17 +2,20 @@ use crate::modules::memory::types::{Conversation, MemoryError};
 use async_trait::async_trait;
 
 /// Memory backend trait for different storage implementations
+///
+/// Conversations are stored under [`Conversation::storage_key`], which is the
+/// conversation ID for unscoped conversations and a namespaced key otherwise.
 #[async_trait]
 pub trait MemoryBackend: Send + Sync {
-    /// Get a conversation by ID
+    /// Get a conversation by storage key
     async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>, MemoryError>;
 
     /// Save a conversation
     async fn save_conversation(&self, conversation: Conversation) -> Result<(), MemoryError>;
 
-    /// Delete a conversation
+    /// Delete a conversation by storage key
     async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError>;
 
-    /// List all conversation IDs
+    /// List the storage keys of all conversations
     async fn list_conversations(&self) -> Result<Vec<String>, MemoryError>;
 }
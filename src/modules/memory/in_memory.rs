@@ -36,7 +36,7 @@ impl MemoryBackend for InMemoryBackend {
             .lock()
             .map_err(|_| MemoryError::LockError)?;
 
-        conversations.insert(conversation.id.clone(), conversation);
+        conversations.insert(conversation.storage_key(), conversation);
         Ok(())
     }
 
@@ -1,14 +1,30 @@
+use chrono::Utc;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tracing::debug;
 use uuid::Uuid;
 
 use crate::modules::memory::backend::MemoryBackend;
-use crate::modules::memory::types::{Conversation, MemoryError, Message};
+use crate::modules::memory::namespace::{self, MemoryNamespace, RetentionPolicy};
+use crate::modules::memory::types::{Conversation, Lineage, MemoryError, Message};
 
 /// Memory manager for handling conversation history with windowing support
+///
+/// Conversations are either unscoped, addressed by ID alone, or namespaced
+/// to a tenant and user through the `*_in` methods. Unscoped methods cannot
+/// reach namespaced conversations.
+///
+/// Conversations can be forked at a message into a new conversation in the
+/// same namespace. Forks share the parent's earlier messages instead of
+/// copying them; conversations are returned with their whole history.
+///
+/// When the retention policy has a soft-delete window, deleted namespaced
+/// conversations are hidden but kept until the window passes, and can be
+/// restored until then. Deleting a whole namespace or tenant always purges.
 pub struct MemoryManager {
     backend: Arc<dyn MemoryBackend>,
     window_size: usize,
+    retention: RetentionPolicy,
 }
 
 impl MemoryManager {
@@ -17,9 +33,16 @@ impl MemoryManager {
         Self {
             backend,
             window_size,
+            retention: RetentionPolicy::default(),
         }
     }
 
+    /// Set the retention policy of namespaced conversations
+    pub fn with_retention(mut self, retention: RetentionPolicy) -> Self {
+        self.retention = retention;
+        self
+    }
+
     /// Create a new conversation
     pub async fn create_conversation(&self) -> Result<Conversation, MemoryError> {
         let id = Uuid::new_v4().to_string();
@@ -32,7 +55,20 @@ impl MemoryManager {
 
     /// Get a conversation by ID
     pub async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
-        self.backend.get_conversation(id).await
+        match self.backend.get_conversation(unscoped(id)?).await? {
+            Some(conversation) => Ok(Some(self.with_history(conversation).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get a conversation by ID, creating it empty if it doesn't exist
+    pub async fn get_or_create_conversation(&self, id: &str) -> Result<Conversation, MemoryError> {
+        if let Some(conversation) = self.get_conversation(id).await? {
+            return Ok(conversation);
+        }
+        let conversation = Conversation::new(unscoped(id)?.to_string());
+        self.backend.save_conversation(conversation.clone()).await?;
+        Ok(conversation)
     }
 
     /// Add a message to a conversation
@@ -42,22 +78,8 @@ impl MemoryManager {
         role: &str,
         content: &str,
     ) -> Result<(), MemoryError> {
-        let mut conversation = match self.backend.get_conversation(conversation_id).await? {
-            Some(conv) => conv,
-            None => return Err(MemoryError::NotFound(conversation_id.to_string())),
-        };
-
-        let message = Message::new(role, content);
-        conversation.add_message(message);
-
-        // Apply windowing if needed
-        if self.window_size > 0 && conversation.messages.len() > self.window_size {
-            conversation.messages = conversation
-                .messages
-                .split_off(conversation.messages.len() - self.window_size);
-        }
-
-        self.backend.save_conversation(conversation).await
+        self.append(unscoped(conversation_id)?, Message::new(role, content))
+            .await
     }
 
     /// Add a message with metadata to a conversation
@@ -68,18 +90,33 @@ impl MemoryManager {
         content: &str,
         metadata: HashMap<String, String>,
     ) -> Result<(), MemoryError> {
-        let mut conversation = match self.backend.get_conversation(conversation_id).await? {
+        let mut message = Message::new(role, content);
+        message.metadata = metadata;
+
+        self.append(unscoped(conversation_id)?, message).await
+    }
+
+    /// Append a message to the conversation stored under a key, applying windowing
+    async fn append(&self, key: &str, message: Message) -> Result<(), MemoryError> {
+        let mut conversation = match self.backend.get_conversation(key).await? {
             Some(conv) => conv,
-            None => return Err(MemoryError::NotFound(conversation_id.to_string())),
+            None => return Err(MemoryError::NotFound(key.to_string())),
         };
 
-        let mut message = Message::new(role, content);
-        message.metadata = metadata;
+        // Windowing applies to a fork's whole history, so a fork about to
+        // outgrow the window takes its own copy of the inherited messages
+        if self.window_size > 0 && conversation.shares_history() {
+            let full = self.with_history(conversation.clone()).await?;
+            if full.messages.len() >= self.window_size {
+                conversation = unshared(full);
+            }
+        }
 
         conversation.add_message(message);
 
         // Apply windowing if needed
         if self.window_size > 0 && conversation.messages.len() > self.window_size {
+            self.detach_forks(&conversation).await?;
             conversation.messages = conversation
                 .messages
                 .split_off(conversation.messages.len() - self.window_size);
@@ -90,7 +127,7 @@ impl MemoryManager {
 
     /// Get all messages from a conversation
     pub async fn get_messages(&self, conversation_id: &str) -> Result<Vec<Message>, MemoryError> {
-        let conversation = match self.backend.get_conversation(conversation_id).await? {
+        let conversation = match self.get_conversation(conversation_id).await? {
             Some(conv) => conv,
             None => return Err(MemoryError::NotFound(conversation_id.to_string())),
         };
@@ -104,7 +141,7 @@ impl MemoryManager {
         conversation_id: &str,
         count: usize,
     ) -> Result<Vec<Message>, MemoryError> {
-        let conversation = match self.backend.get_conversation(conversation_id).await? {
+        let conversation = match self.get_conversation(conversation_id).await? {
             Some(conv) => conv,
             None => return Err(MemoryError::NotFound(conversation_id.to_string())),
         };
@@ -114,12 +151,16 @@ impl MemoryManager {
 
     /// Delete a conversation
     pub async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
-        self.backend.delete_conversation(id).await
+        self.delete(unscoped(id)?).await
     }
 
-    /// List all conversation IDs
+    /// List all unscoped conversation IDs
     pub async fn list_conversations(&self) -> Result<Vec<String>, MemoryError> {
-        self.backend.list_conversations().await
+        let keys = self.backend.list_conversations().await?;
+        Ok(keys
+            .into_iter()
+            .filter(|key| !namespace::is_namespaced(key))
+            .collect())
     }
 
     /// Add metadata to a conversation
@@ -129,7 +170,8 @@ impl MemoryManager {
         key: &str,
         value: &str,
     ) -> Result<(), MemoryError> {
-        let mut conversation = match self.backend.get_conversation(conversation_id).await? {
+        let id = unscoped(conversation_id)?;
+        let mut conversation = match self.backend.get_conversation(id).await? {
             Some(conv) => conv,
             None => return Err(MemoryError::NotFound(conversation_id.to_string())),
         };
@@ -139,6 +181,350 @@ impl MemoryManager {
         self.backend.save_conversation(conversation).await
     }
 
+    /// Create a new conversation in a namespace
+    pub async fn create_conversation_in(
+        &self,
+        namespace: &MemoryNamespace,
+    ) -> Result<Conversation, MemoryError> {
+        let id = Uuid::new_v4().to_string();
+        let conversation = Conversation::in_namespace(id, namespace.clone());
+
+        self.backend.save_conversation(conversation.clone()).await?;
+
+        Ok(conversation)
+    }
+
+    /// Get a conversation of a namespace
+    ///
+    /// Conversations past their tenant's retention TTL are deleted and
+    /// reported as missing, as are soft-deleted conversations.
+    pub async fn get_conversation_in(
+        &self,
+        namespace: &MemoryNamespace,
+        id: &str,
+    ) -> Result<Option<Conversation>, MemoryError> {
+        let key = namespace.key(id)?;
+        let conversation = match self.backend.get_conversation(&key).await? {
+            Some(conversation) => conversation,
+            None => return Ok(None),
+        };
+
+        if conversation.namespace.as_ref() != Some(namespace) {
+            return Err(MemoryError::AccessDenied(format!(
+                "Conversation {} belongs to another namespace",
+                id
+            )));
+        }
+        if let Some(deleted_at) = conversation.deleted_at {
+            if self.retention.is_purgeable(deleted_at) {
+                self.delete(&key).await?;
+            }
+            return Ok(None);
+        }
+        if self
+            .retention
+            .is_expired(&namespace.tenant, conversation.updated_at)
+        {
+            self.delete(&key).await?;
+            return Ok(None);
+        }
+
+        Ok(Some(self.with_history(conversation).await?))
+    }
+
+    /// Add a message to a conversation of a namespace
+    pub async fn add_message_in(
+        &self,
+        namespace: &MemoryNamespace,
+        conversation_id: &str,
+        role: &str,
+        content: &str,
+    ) -> Result<(), MemoryError> {
+        if self
+            .get_conversation_in(namespace, conversation_id)
+            .await?
+            .is_none()
+        {
+            return Err(MemoryError::NotFound(conversation_id.to_string()));
+        }
+
+        self.append(
+            &namespace.key(conversation_id)?,
+            Message::new(role, content),
+        )
+        .await
+    }
+
+    /// List the conversation IDs of a namespace, leaving out soft-deleted ones
+    pub async fn list_conversations_in(
+        &self,
+        namespace: &MemoryNamespace,
+    ) -> Result<Vec<String>, MemoryError> {
+        let prefix = namespace.prefix();
+        let keys = self.backend.list_conversations().await?;
+        let mut ids = Vec::new();
+        for key in &keys {
+            let Some(id) = key.strip_prefix(&prefix) else {
+                continue;
+            };
+            if self.retention.soft_delete.is_some() {
+                match self.backend.get_conversation(key).await? {
+                    Some(conversation) if conversation.deleted_at.is_none() => {}
+                    _ => continue,
+                }
+            }
+            ids.push(id.to_string());
+        }
+        Ok(ids)
+    }
+
+    /// Delete a conversation of a namespace
+    ///
+    /// With a soft-delete window, the conversation is only marked deleted
+    /// and can be restored until the window passes.
+    pub async fn delete_conversation_in(
+        &self,
+        namespace: &MemoryNamespace,
+        id: &str,
+    ) -> Result<(), MemoryError> {
+        let key = namespace.key(id)?;
+        if self.retention.soft_delete.is_none() {
+            return self.delete(&key).await;
+        }
+
+        let Some(mut conversation) = self.backend.get_conversation(&key).await? else {
+            return Ok(());
+        };
+        if conversation.deleted_at.is_none() {
+            conversation.deleted_at = Some(Utc::now());
+            self.backend.save_conversation(conversation).await?;
+            debug!("Soft-deleted conversation {}", id);
+        }
+        Ok(())
+    }
+
+    /// Restore a soft-deleted conversation of a namespace
+    ///
+    /// Conversations that were not deleted are returned as they are.
+    pub async fn restore_conversation_in(
+        &self,
+        namespace: &MemoryNamespace,
+        id: &str,
+    ) -> Result<Conversation, MemoryError> {
+        let key = namespace.key(id)?;
+        let Some(mut conversation) = self.backend.get_conversation(&key).await? else {
+            return Err(MemoryError::NotFound(id.to_string()));
+        };
+        if let Some(deleted_at) = conversation.deleted_at.take() {
+            if self.retention.is_purgeable(deleted_at) {
+                self.delete(&key).await?;
+                return Err(MemoryError::NotFound(id.to_string()));
+            }
+            self.backend.save_conversation(conversation.clone()).await?;
+            debug!("Restored conversation {}", id);
+        }
+        self.with_history(conversation).await
+    }
+
+    /// Fork a conversation before its message at `index`
+    ///
+    /// The fork starts with the first `index` messages, so forking at an
+    /// assistant message regenerates it from there.
+    pub async fn fork_conversation(
+        &self,
+        id: &str,
+        index: usize,
+    ) -> Result<Conversation, MemoryError> {
+        match self.get_conversation(id).await? {
+            Some(parent) => self.fork(parent, index).await,
+            None => Err(MemoryError::NotFound(id.to_string())),
+        }
+    }
+
+    /// Fork a conversation of a namespace before its message at `index`
+    pub async fn fork_conversation_in(
+        &self,
+        namespace: &MemoryNamespace,
+        id: &str,
+        index: usize,
+    ) -> Result<Conversation, MemoryError> {
+        match self.get_conversation_in(namespace, id).await? {
+            Some(parent) => self.fork(parent, index).await,
+            None => Err(MemoryError::NotFound(id.to_string())),
+        }
+    }
+
+    /// Create a fork of a conversation with its whole history
+    async fn fork(&self, parent: Conversation, index: usize) -> Result<Conversation, MemoryError> {
+        let len = parent.messages.len();
+        if index > len {
+            return Err(MemoryError::InvalidIndex { index, len });
+        }
+
+        let mut fork = Conversation {
+            namespace: parent.namespace.clone(),
+            lineage: Some(Lineage {
+                parent_id: parent.id.clone(),
+                fork_index: index,
+                shared: true,
+                forked_at: Utc::now(),
+            }),
+            ..Conversation::new(Uuid::new_v4().to_string())
+        };
+        self.backend.save_conversation(fork.clone()).await?;
+
+        // Link the fork from the stored parent, which may itself be a fork
+        let key = parent.storage_key();
+        let mut stored = match self.backend.get_conversation(&key).await? {
+            Some(stored) => stored,
+            None => return Err(MemoryError::NotFound(parent.id)),
+        };
+        stored.forks.push(fork.id.clone());
+        self.backend.save_conversation(stored).await?;
+
+        debug!("Forked conversation {} at message {}", parent.id, index);
+        fork.messages = parent.messages[..index].to_vec();
+        Ok(fork)
+    }
+
+    /// Resolve the messages a conversation shares with its ancestors
+    ///
+    /// Forks are stored with their own messages only; the returned
+    /// conversation holds the whole history and is not meant to be saved.
+    async fn with_history(&self, conversation: Conversation) -> Result<Conversation, MemoryError> {
+        let mut chain = vec![conversation];
+        while let Some(key) = chain
+            .last()
+            .filter(|conversation| conversation.shares_history())
+            .and_then(|conversation| {
+                let lineage = conversation.lineage.as_ref()?;
+                Some(conversation.related_key(&lineage.parent_id))
+            })
+        {
+            match self.backend.get_conversation(&key).await? {
+                Some(parent) => chain.push(parent),
+                None => break,
+            }
+        }
+
+        let mut conversation = chain.pop().expect("chain starts with the conversation");
+        while let Some(mut child) = chain.pop() {
+            let mut history = std::mem::take(&mut conversation.messages);
+            let index = child
+                .lineage
+                .as_ref()
+                .map_or(0, |lineage| lineage.fork_index);
+            history.truncate(index);
+            history.append(&mut child.messages);
+            child.messages = history;
+            conversation = child;
+        }
+        Ok(conversation)
+    }
+
+    /// Copy the inherited messages into the forks still sharing them with a
+    /// conversation, before it drops or trims them
+    ///
+    /// `parent` must hold its whole history.
+    async fn detach_forks(&self, parent: &Conversation) -> Result<(), MemoryError> {
+        for id in &parent.forks {
+            let key = parent.related_key(id);
+            let Some(mut fork) = self.backend.get_conversation(&key).await? else {
+                continue;
+            };
+            let Some(lineage) = fork
+                .lineage
+                .as_mut()
+                .filter(|lineage| lineage.shared && lineage.parent_id == parent.id)
+            else {
+                continue;
+            };
+            lineage.shared = false;
+            let index = lineage.fork_index.min(parent.messages.len());
+            let mut history = parent.messages[..index].to_vec();
+            history.append(&mut fork.messages);
+            fork.messages = history;
+            self.backend.save_conversation(fork).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete the conversation stored under a key, detaching its forks and
+    /// unlinking it from its parent
+    async fn delete(&self, key: &str) -> Result<(), MemoryError> {
+        if let Some(conversation) = self.backend.get_conversation(key).await? {
+            if !conversation.forks.is_empty() {
+                self.detach_forks(&self.with_history(conversation.clone()).await?)
+                    .await?;
+            }
+            if let Some(lineage) = &conversation.lineage {
+                let parent_key = conversation.related_key(&lineage.parent_id);
+                if let Some(mut parent) = self.backend.get_conversation(&parent_key).await? {
+                    parent.forks.retain(|id| *id != conversation.id);
+                    self.backend.save_conversation(parent).await?;
+                }
+            }
+        }
+        self.backend.delete_conversation(key).await
+    }
+
+    /// Delete every conversation of a namespace, returning how many were deleted
+    pub async fn delete_namespace(
+        &self,
+        namespace: &MemoryNamespace,
+    ) -> Result<usize, MemoryError> {
+        self.delete_prefixed(&namespace.prefix()).await
+    }
+
+    /// Delete every conversation of a tenant, returning how many were deleted
+    pub async fn delete_tenant(&self, tenant: &str) -> Result<usize, MemoryError> {
+        self.delete_prefixed(&namespace::tenant_prefix(tenant)?)
+            .await
+    }
+
+    async fn delete_prefixed(&self, prefix: &str) -> Result<usize, MemoryError> {
+        let keys = self.backend.list_conversations().await?;
+        let mut deleted = 0;
+        for key in keys.iter().filter(|key| key.starts_with(prefix)) {
+            self.backend.delete_conversation(key).await?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+
+    /// Delete namespaced conversations past their tenant's retention TTL,
+    /// and soft-deleted ones past the soft-delete window
+    ///
+    /// Returns how many conversations were deleted.
+    pub async fn purge_expired(&self) -> Result<usize, MemoryError> {
+        let keys = self.backend.list_conversations().await?;
+        let mut deleted = 0;
+        for key in keys.iter().filter(|key| namespace::is_namespaced(key)) {
+            let Some(conversation) = self.backend.get_conversation(key).await? else {
+                continue;
+            };
+            let Some(namespace) = &conversation.namespace else {
+                continue;
+            };
+            let purgeable = conversation
+                .deleted_at
+                .is_some_and(|deleted_at| self.retention.is_purgeable(deleted_at));
+            if purgeable
+                || self
+                    .retention
+                    .is_expired(&namespace.tenant, conversation.updated_at)
+            {
+                self.delete(key).await?;
+                deleted += 1;
+            }
+        }
+
+        if deleted > 0 {
+            debug!("Purged {} expired conversation(s)", deleted);
+        }
+        Ok(deleted)
+    }
+
     /// Get the window size
     pub fn get_window_size(&self) -> usize {
         self.window_size
@@ -150,6 +536,25 @@ impl MemoryManager {
     }
 }
 
+/// A conversation holding its whole history, stored without sharing it
+fn unshared(mut conversation: Conversation) -> Conversation {
+    if let Some(lineage) = &mut conversation.lineage {
+        lineage.shared = false;
+    }
+    conversation
+}
+
+/// Reject unscoped access to namespaced conversations
+fn unscoped(id: &str) -> Result<&str, MemoryError> {
+    if namespace::is_namespaced(id) {
+        return Err(MemoryError::AccessDenied(format!(
+            "Conversation {} is namespaced",
+            id
+        )));
+    }
+    Ok(id)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +614,250 @@ mod tests {
         let result = manager.get_conversation(&id).await.unwrap();
         assert!(result.is_none());
     }
+
+    #[tokio::test]
+    async fn test_namespace_isolation() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let manager = MemoryManager::new(backend, 5);
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let bob = MemoryNamespace::new("acme", "bob").unwrap();
+        let other = MemoryNamespace::new("globex", "alice").unwrap();
+
+        let conversation = manager.create_conversation_in(&alice).await.unwrap();
+        let id = conversation.id.clone();
+        manager
+            .add_message_in(&alice, &id, "user", "Hello")
+            .await
+            .unwrap();
+        manager.create_conversation_in(&bob).await.unwrap();
+        manager.create_conversation_in(&other).await.unwrap();
+        let unscoped = manager.create_conversation().await.unwrap();
+
+        // Other namespaces and unscoped access cannot reach the conversation
+        assert!(manager
+            .get_conversation_in(&bob, &id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(manager
+            .add_message_in(&bob, &id, "user", "Hi")
+            .await
+            .is_err());
+        assert!(matches!(
+            manager.get_conversation(&conversation.storage_key()).await,
+            Err(MemoryError::AccessDenied(_))
+        ));
+        assert_eq!(
+            manager.list_conversations().await.unwrap(),
+            vec![unscoped.id]
+        );
+        assert_eq!(
+            manager.list_conversations_in(&alice).await.unwrap(),
+            vec![id.clone()]
+        );
+
+        let stored = manager
+            .get_conversation_in(&alice, &id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.messages.len(), 1);
+
+        // Erasing a user or tenant leaves the others untouched
+        assert_eq!(manager.delete_namespace(&alice).await.unwrap(), 1);
+        assert!(manager
+            .list_conversations_in(&alice)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(manager.delete_tenant("acme").await.unwrap(), 1);
+        assert_eq!(
+            manager.list_conversations_in(&other).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retention() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let retention = RetentionPolicy {
+            default_ttl: None,
+            tenant_ttls: HashMap::from([("acme".to_string(), std::time::Duration::ZERO)]),
+            soft_delete: None,
+        };
+        let manager = MemoryManager::new(backend, 5).with_retention(retention);
+        let acme = MemoryNamespace::new("acme", "alice").unwrap();
+        let globex = MemoryNamespace::new("globex", "alice").unwrap();
+
+        let expired = manager.create_conversation_in(&acme).await.unwrap();
+        manager.create_conversation_in(&acme).await.unwrap();
+        manager.create_conversation_in(&globex).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+
+        assert!(manager
+            .get_conversation_in(&acme, &expired.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(manager.purge_expired().await.unwrap(), 1);
+        assert_eq!(
+            manager.list_conversations_in(&globex).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fork_conversation() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let manager = MemoryManager::new(backend.clone(), 4);
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let parent = manager.create_conversation_in(&alice).await.unwrap();
+        for (role, content) in [("user", "Hi"), ("assistant", "Hello"), ("user", "Joke?")] {
+            manager
+                .add_message_in(&alice, &parent.id, role, content)
+                .await
+                .unwrap();
+        }
+
+        // Regenerate the answer to the first message
+        let fork = manager
+            .fork_conversation_in(&alice, &parent.id, 1)
+            .await
+            .unwrap();
+        let lineage = fork.lineage.clone().unwrap();
+        assert_eq!(
+            (lineage.parent_id.as_str(), lineage.fork_index),
+            (parent.id.as_str(), 1)
+        );
+        assert_eq!(fork.messages.len(), 1);
+        assert!(matches!(
+            manager.fork_conversation_in(&alice, &parent.id, 4).await,
+            Err(MemoryError::InvalidIndex { index: 4, len: 3 })
+        ));
+
+        // The fork stores only its own messages and diverges from the parent
+        manager
+            .add_message_in(&alice, &fork.id, "assistant", "Hey")
+            .await
+            .unwrap();
+        let stored = backend
+            .get_conversation(&fork.storage_key())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.messages.len(), 1);
+        let contents = |conversation: Conversation| {
+            conversation
+                .messages
+                .into_iter()
+                .map(|m| m.content)
+                .collect::<Vec<_>>()
+        };
+        let get = |id: String| {
+            let manager = &manager;
+            let alice = &alice;
+            async move {
+                manager
+                    .get_conversation_in(alice, &id)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        assert_eq!(contents(get(fork.id.clone()).await), vec!["Hi", "Hey"]);
+        assert_eq!(get(parent.id.clone()).await.forks, vec![fork.id.clone()]);
+
+        // Trimming the parent's window hands the fork its own history
+        for content in ["Ha", "More?"] {
+            manager
+                .add_message_in(&alice, &parent.id, "user", content)
+                .await
+                .unwrap();
+        }
+        assert_eq!(contents(get(parent.id.clone()).await)[0], "Hello");
+        assert_eq!(contents(get(fork.id.clone()).await), vec!["Hi", "Hey"]);
+
+        // Deleting a fork unlinks it from its parent
+        let second = manager
+            .fork_conversation_in(&alice, &parent.id, 2)
+            .await
+            .unwrap();
+        manager
+            .delete_conversation_in(&alice, &second.id)
+            .await
+            .unwrap();
+        assert_eq!(get(parent.id.clone()).await.forks, vec![fork.id.clone()]);
+
+        // Deleting the parent keeps the fork's history
+        manager
+            .delete_conversation_in(&alice, &parent.id)
+            .await
+            .unwrap();
+        assert_eq!(contents(get(fork.id.clone()).await), vec!["Hi", "Hey"]);
+    }
+
+    #[tokio::test]
+    async fn test_soft_delete() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let retention = RetentionPolicy {
+            soft_delete: Some(std::time::Duration::from_secs(3600)),
+            ..RetentionPolicy::default()
+        };
+        let manager = MemoryManager::new(backend.clone(), 5).with_retention(retention);
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let conversation = manager.create_conversation_in(&alice).await.unwrap();
+        manager
+            .add_message_in(&alice, &conversation.id, "user", "Hello")
+            .await
+            .unwrap();
+
+        // A deleted conversation is hidden but kept
+        manager
+            .delete_conversation_in(&alice, &conversation.id)
+            .await
+            .unwrap();
+        assert!(manager
+            .get_conversation_in(&alice, &conversation.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(manager
+            .list_conversations_in(&alice)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(manager.purge_expired().await.unwrap(), 0);
+
+        // Until the window passes, it can be restored
+        let restored = manager
+            .restore_conversation_in(&alice, &conversation.id)
+            .await
+            .unwrap();
+        assert!(restored.deleted_at.is_none());
+        assert_eq!(restored.messages.len(), 1);
+        assert_eq!(
+            manager.list_conversations_in(&alice).await.unwrap(),
+            vec![conversation.id.clone()]
+        );
+
+        // Past the window, it is purged
+        manager
+            .delete_conversation_in(&alice, &conversation.id)
+            .await
+            .unwrap();
+        let mut stored = backend
+            .get_conversation(&conversation.storage_key())
+            .await
+            .unwrap()
+            .unwrap();
+        stored.deleted_at = Some(Utc::now() - chrono::Duration::hours(2));
+        backend.save_conversation(stored).await.unwrap();
+        assert_eq!(manager.purge_expired().await.unwrap(), 1);
+        assert!(matches!(
+            manager
+                .restore_conversation_in(&alice, &conversation.id)
+                .await,
+            Err(MemoryError::NotFound(_))
+        ));
+    }
 }
@@ -4,21 +4,84 @@
 //! It provides functionality for storing, retrieving, and managing
 //! conversation context across multiple interactions.
 
+pub mod api;
 mod backend;
 mod in_memory;
 mod manager;
+mod namespace;
 mod redis;
+mod semantic;
+mod sqlite;
+#[cfg(test)]
+pub(crate) mod testing;
 mod types;
+mod vector;
 
 // Re-export the new types and implementations
 pub use backend::MemoryBackend;
 pub use in_memory::InMemoryBackend;
 pub use manager::MemoryManager;
+pub use namespace::{MemoryNamespace, RetentionPolicy};
 pub use redis::RedisBackend;
-pub use types::{Conversation, MemoryError, Message};
+pub use semantic::{Embedder, MemorySettings, OpenAIEmbedder, SemanticMemory};
+pub use sqlite::SqliteBackend;
+pub use types::{Conversation, Lineage, MemoryError, Message};
+pub use vector::{
+    cosine_similarity, InMemoryVectorStore, MemoryFact, QdrantVectorStore, ScoredFact, VectorStore,
+};
+
+use std::sync::Arc;
 
 use uuid::Uuid;
 
+use crate::config::{MemoryConfig, StorageConfig};
+use crate::modules::common::RedisConnector;
+use crate::modules::encryption::EnvelopeEncryptor;
+
+/// Create the memory backend described by the configuration
+///
+/// The embedded storage profile keeps conversations in its SQLite file,
+/// whatever the backend type. Redis and SQLite conversations are encrypted
+/// at rest when an encryptor is given.
+pub fn backend_from_config(
+    config: &MemoryConfig,
+    storage: &StorageConfig,
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+) -> Result<Arc<dyn MemoryBackend>, MemoryError> {
+    let backend_type = match storage.is_embedded() {
+        true => "sqlite",
+        false => config.backend_type.as_str(),
+    };
+    match backend_type {
+        "memory" => Ok(Arc::new(InMemoryBackend::new())),
+        "sqlite" => {
+            let db = crate::modules::common::SqliteDatabase::from_config(storage)
+                .map_err(|e| MemoryError::StorageError(e.to_string()))?;
+            let mut backend = SqliteBackend::new(db)?;
+            if let Some(encryptor) = encryptor {
+                backend = backend.with_encryption(encryptor);
+            }
+            Ok(Arc::new(backend))
+        }
+        "redis" => {
+            let redis = RedisConnector::from_config(config)
+                .map_err(|e| MemoryError::Other(format!("Invalid Redis configuration: {}", e)))?
+                .ok_or_else(|| {
+                    MemoryError::Other("Redis memory backend requires memory.redis_url".to_string())
+                })?;
+            let mut backend = RedisBackend::from_connector(redis, &config.key_prefix);
+            if let Some(encryptor) = encryptor {
+                backend = backend.with_encryption(encryptor);
+            }
+            Ok(Arc::new(backend))
+        }
+        other => Err(MemoryError::Other(format!(
+            "Unknown memory backend: {}",
+            other
+        ))),
+    }
+}
+
 // Provide backward-compatible functions
 
 /// Create a new conversation
@@ -67,7 +130,6 @@ pub fn load_conversation(_id: &str) -> Result<types::Conversation, String> {
 #[cfg(all(test, not(feature = "production")))]
 mod tests {
     use super::*;
-    use std::sync::Arc;
     use tokio::runtime::Runtime;
 
     #[test]
@@ -0,0 +1,164 @@
+//! Memory Namespaces
+//!
+//! This module scopes conversations to a tenant and user. Namespaced
+//! conversations are stored under keys of the form
+//! `ns:<tenant>:<user>:<conversation>`, so a namespace can only address its
+//! own conversations and a user's or tenant's memories can be listed and
+//! erased by key prefix. Retention TTLs can be set per tenant.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{MemoryConfig, TenantConfig};
+use crate::modules::memory::types::MemoryError;
+
+/// Prefix of the storage keys of namespaced conversations
+pub(crate) const NAMESPACE_PREFIX: &str = "ns:";
+
+/// Tenant and user owning a set of conversations
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MemoryNamespace {
+    pub tenant: String,
+    pub user: String,
+}
+
+impl MemoryNamespace {
+    /// Create a namespace, validating its segments
+    pub fn new(tenant: impl Into<String>, user: impl Into<String>) -> Result<Self, MemoryError> {
+        let namespace = Self {
+            tenant: tenant.into(),
+            user: user.into(),
+        };
+        validate_segment("tenant", &namespace.tenant)?;
+        validate_segment("user", &namespace.user)?;
+        Ok(namespace)
+    }
+
+    /// Storage key of a conversation in this namespace
+    pub fn key(&self, conversation_id: &str) -> Result<String, MemoryError> {
+        validate_segment("conversation", conversation_id)?;
+        Ok(format!("{}{}", self.prefix(), conversation_id))
+    }
+
+    /// Key prefix shared by the namespace's conversations
+    pub(crate) fn prefix(&self) -> String {
+        format!("{}{}:{}:", NAMESPACE_PREFIX, self.tenant, self.user)
+    }
+}
+
+/// Key prefix shared by all conversations of a tenant
+pub(crate) fn tenant_prefix(tenant: &str) -> Result<String, MemoryError> {
+    validate_segment("tenant", tenant)?;
+    Ok(format!("{}{}:", NAMESPACE_PREFIX, tenant))
+}
+
+/// Whether a storage key belongs to a namespaced conversation
+pub(crate) fn is_namespaced(key: &str) -> bool {
+    key.starts_with(NAMESPACE_PREFIX)
+}
+
+/// Reject empty segments and segments that could escape their namespace
+fn validate_segment(kind: &str, value: &str) -> Result<(), MemoryError> {
+    if value.is_empty() || value.contains(':') || value.contains('*') {
+        return Err(MemoryError::InvalidKey(format!(
+            "Invalid {} identifier: '{}'",
+            kind, value
+        )));
+    }
+    Ok(())
+}
+
+/// How long namespaced conversations are kept after their last update
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    /// TTL of tenants without their own, `None` keeping conversations forever
+    pub default_ttl: Option<Duration>,
+    /// Per-tenant TTLs
+    pub tenant_ttls: HashMap<String, Duration>,
+    /// How long deleted conversations can be restored, `None` deleting
+    /// them at once
+    pub soft_delete: Option<Duration>,
+}
+
+impl RetentionPolicy {
+    /// Build the policy from the memory TTL and per-tenant overrides
+    pub fn from_config(memory: &MemoryConfig, tenants: &[TenantConfig]) -> Self {
+        Self {
+            default_ttl: (memory.history_ttl_secs > 0)
+                .then(|| Duration::from_secs(memory.history_ttl_secs)),
+            tenant_ttls: tenants
+                .iter()
+                .filter_map(|tenant| {
+                    tenant
+                        .memory_retention_secs
+                        .map(|secs| (tenant.id.clone(), Duration::from_secs(secs)))
+                })
+                .collect(),
+            soft_delete: (memory.soft_delete_secs > 0)
+                .then(|| Duration::from_secs(memory.soft_delete_secs)),
+        }
+    }
+
+    /// TTL applying to a tenant's conversations
+    pub fn ttl_for(&self, tenant: &str) -> Option<Duration> {
+        self.tenant_ttls.get(tenant).copied().or(self.default_ttl)
+    }
+
+    /// Whether a tenant's conversation last updated at `updated_at` has expired
+    pub fn is_expired(&self, tenant: &str, updated_at: DateTime<Utc>) -> bool {
+        match self.ttl_for(tenant) {
+            Some(ttl) => (Utc::now() - updated_at)
+                .to_std()
+                .is_ok_and(|age| age > ttl),
+            None => false,
+        }
+    }
+
+    /// Whether a conversation deleted at `deleted_at` can no longer be restored
+    pub fn is_purgeable(&self, deleted_at: DateTime<Utc>) -> bool {
+        match self.soft_delete {
+            Some(window) => (Utc::now() - deleted_at)
+                .to_std()
+                .is_ok_and(|age| age > window),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_keys() {
+        let namespace = MemoryNamespace::new("acme", "alice").unwrap();
+        assert_eq!(namespace.key("c1").unwrap(), "ns:acme:alice:c1");
+        assert!(namespace
+            .key("c1")
+            .unwrap()
+            .starts_with(&tenant_prefix("acme").unwrap()));
+
+        assert!(MemoryNamespace::new("acme", "").is_err());
+        assert!(MemoryNamespace::new("acme:evil", "alice").is_err());
+        assert!(namespace.key("other:c1").is_err());
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let policy = RetentionPolicy {
+            default_ttl: Some(Duration::from_secs(3600)),
+            tenant_ttls: HashMap::from([("acme".to_string(), Duration::from_secs(60))]),
+            soft_delete: Some(Duration::from_secs(3600)),
+        };
+        let two_minutes_ago = Utc::now() - chrono::Duration::seconds(120);
+
+        assert!(policy.is_expired("acme", two_minutes_ago));
+        assert!(!policy.is_expired("globex", two_minutes_ago));
+        assert!(!RetentionPolicy::default().is_expired("acme", two_minutes_ago));
+        assert!(!policy.is_purgeable(two_minutes_ago));
+        assert!(RetentionPolicy::default().is_purgeable(Utc::now()));
+    }
+}
@@ -1,26 +1,46 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use redis::AsyncCommands;
 use serde_json;
 
+use crate::modules::common::RedisConnector;
+use crate::modules::encryption::EnvelopeEncryptor;
 use crate::modules::memory::backend::MemoryBackend;
 use crate::modules::memory::types::{Conversation, MemoryError};
 
 /// Redis backend implementation for persistent storage
 pub struct RedisBackend {
-    client: redis::Client,
+    redis: RedisConnector,
     prefix: String,
+    /// Encrypts conversations at rest, if enabled
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
 }
 
 impl RedisBackend {
     /// Create a new Redis backend
     pub fn new(redis_url: &str, prefix: &str) -> Result<Self, MemoryError> {
-        let client = redis::Client::open(redis_url)
+        let redis = RedisConnector::from_url(redis_url)
             .map_err(|e| MemoryError::StorageError(format!("Redis connection error: {}", e)))?;
 
-        Ok(Self {
-            client,
+        Ok(Self::from_connector(redis, prefix))
+    }
+
+    /// Create a Redis backend connecting through a connector
+    pub fn from_connector(redis: RedisConnector, prefix: &str) -> Self {
+        Self {
+            redis,
             prefix: prefix.to_string(),
-        })
+            encryptor: None,
+        }
+    }
+
+    /// Encrypt conversations at rest
+    ///
+    /// Conversations stored before encryption was enabled stay readable.
+    pub fn with_encryption(mut self, encryptor: Arc<EnvelopeEncryptor>) -> Self {
+        self.encryptor = Some(encryptor);
+        self
     }
 
     /// Generate a Redis key with the configured prefix
@@ -33,8 +53,8 @@ impl RedisBackend {
 impl MemoryBackend for RedisBackend {
     async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
         let mut conn = self
-            .client
-            .get_async_connection()
+            .redis
+            .connection()
             .await
             .map_err(|e| MemoryError::StorageError(format!("Redis connection error: {}", e)))?;
 
@@ -48,10 +68,16 @@ impl MemoryBackend for RedisBackend {
             return Ok(None);
         }
 
-        let json: String = conn
+        let mut json: String = conn
             .get(&key)
             .await
             .map_err(|e| MemoryError::StorageError(format!("Redis error: {}", e)))?;
+        if let Some(encryptor) = &self.encryptor {
+            json = encryptor
+                .open_str(json, &key)
+                .await
+                .map_err(|e| MemoryError::StorageError(e.to_string()))?;
+        }
 
         let conversation: Conversation = serde_json::from_str(&json).map_err(|e| {
             MemoryError::SerializationError(format!("Deserialization error: {}", e))
@@ -62,14 +88,20 @@ impl MemoryBackend for RedisBackend {
 
     async fn save_conversation(&self, conversation: Conversation) -> Result<(), MemoryError> {
         let mut conn = self
-            .client
-            .get_async_connection()
+            .redis
+            .connection()
             .await
             .map_err(|e| MemoryError::StorageError(format!("Redis connection error: {}", e)))?;
 
-        let key = self.get_key(&conversation.id);
-        let json = serde_json::to_string(&conversation)
+        let key = self.get_key(&conversation.storage_key());
+        let mut json = serde_json::to_string(&conversation)
             .map_err(|e| MemoryError::SerializationError(format!("Serialization error: {}", e)))?;
+        if let Some(encryptor) = &self.encryptor {
+            json = encryptor
+                .seal_str(&json, &key)
+                .await
+                .map_err(|e| MemoryError::StorageError(e.to_string()))?;
+        }
 
         conn.set(&key, json)
             .await
@@ -81,8 +113,8 @@ impl MemoryBackend for RedisBackend {
 
     async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
         let mut conn = self
-            .client
-            .get_async_connection()
+            .redis
+            .connection()
             .await
             .map_err(|e| MemoryError::StorageError(format!("Redis connection error: {}", e)))?;
 
@@ -97,8 +129,8 @@ impl MemoryBackend for RedisBackend {
 
     async fn list_conversations(&self) -> Result<Vec<String>, MemoryError> {
         let mut conn = self
-            .client
-            .get_async_connection()
+            .redis
+            .connection()
             .await
             .map_err(|e| MemoryError::StorageError(format!("Redis connection error: {}", e)))?;
 
@@ -0,0 +1,588 @@
+//! Semantic Memory
+//!
+//! This module adds long-term memory alongside the conversation window.
+//! Salient facts are extracted from conversations by a summarizer model,
+//! embedded and stored in a vector store under the user's memory namespace.
+//! On new requests the facts most relevant to the latest user message are
+//! recalled and injected as a system message. Recall and extraction are
+//! configured per persona through [`MemorySettings`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::config::{MemoryVectorStoreType, RagConfig, SemanticMemoryConfig};
+use crate::modules::memory::namespace::MemoryNamespace;
+use crate::modules::memory::types::{MemoryError, Message};
+use crate::modules::memory::vector::{
+    InMemoryVectorStore, MemoryFact, QdrantVectorStore, ScoredFact, VectorStore,
+};
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatMessage, ConnectorConfig, MessageRole, ModelConnector,
+    OpenAIConnector,
+};
+use crate::modules::persona_layer::persona::Persona;
+
+/// Heading of the injected system message listing recalled facts
+const MEMORY_HEADING: &str = "Relevant facts remembered from earlier conversations:";
+
+/// Instructions given to the model extracting facts
+const EXTRACTION_PROMPT: &str = "Extract the salient long-term facts about the user from the \
+conversation below: preferences, personal details, goals and decisions worth remembering in \
+future conversations. Ignore small talk and anything only relevant to this conversation. \
+Reply with a JSON array of short, self-contained statements, or [] if there is nothing worth \
+remembering.";
+
+/// Per-persona semantic memory settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MemorySettings {
+    /// Whether relevant facts are injected into requests
+    pub recall: bool,
+    /// Whether facts are extracted from conversations
+    pub extract: bool,
+    /// Maximum facts injected
+    pub top_k: usize,
+    /// Minimum similarity of an injected fact
+    pub min_score: f32,
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        Self {
+            recall: true,
+            extract: true,
+            top_k: 5,
+            min_score: 0.75,
+        }
+    }
+}
+
+/// Produces embeddings of texts
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// Embed texts, returning one vector per text
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, MemoryError>;
+}
+
+/// Embedder calling an OpenAI-compatible `/v1/embeddings` endpoint
+pub struct OpenAIEmbedder {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAIEmbedder {
+    /// Create an embedder for an endpoint and model
+    pub fn new(endpoint: &str, api_key: Option<String>, model: &str) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+            model: model.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Embedder for OpenAIEmbedder {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, MemoryError> {
+        let mut request = self
+            .client
+            .post(format!("{}/v1/embeddings", self.endpoint))
+            .json(&json!({ "model": self.model, "input": texts }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| MemoryError::Other(format!("Embedding request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|e| {
+            MemoryError::SerializationError(format!("Invalid embedding response: {}", e))
+        })?;
+        if !status.is_success() {
+            return Err(MemoryError::Other(format!(
+                "Embedding endpoint returned {}: {}",
+                status, body
+            )));
+        }
+
+        let mut data: Vec<(u64, Vec<f32>)> = body
+            .get("data")
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let index = item.get("index").and_then(Value::as_u64).unwrap_or(0);
+                        let vector = item
+                            .get("embedding")?
+                            .as_array()?
+                            .iter()
+                            .filter_map(|v| v.as_f64().map(|v| v as f32))
+                            .collect();
+                        Some((index, vector))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if data.len() != texts.len() {
+            return Err(MemoryError::Other(format!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                data.len()
+            )));
+        }
+        data.sort_by_key(|(index, _)| *index);
+        Ok(data.into_iter().map(|(_, vector)| vector).collect())
+    }
+}
+
+/// Long-term memory of facts extracted from conversations
+pub struct SemanticMemory {
+    embedder: Arc<dyn Embedder>,
+    store: Arc<dyn VectorStore>,
+    extractor: Arc<dyn ModelConnector>,
+    config: SemanticMemoryConfig,
+}
+
+impl SemanticMemory {
+    /// Create a semantic memory
+    pub fn new(
+        embedder: Arc<dyn Embedder>,
+        store: Arc<dyn VectorStore>,
+        extractor: Arc<dyn ModelConnector>,
+        config: SemanticMemoryConfig,
+    ) -> Self {
+        Self {
+            embedder,
+            store,
+            extractor,
+            config,
+        }
+    }
+
+    /// Create a semantic memory from configuration
+    ///
+    /// Returns `None` when semantic memory is disabled. `default_endpoint`
+    /// is used for embeddings and extraction unless an endpoint is configured.
+    pub fn from_config(
+        config: &SemanticMemoryConfig,
+        rag: &RagConfig,
+        default_endpoint: &str,
+    ) -> Result<Option<Self>, MemoryError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let endpoint = config.endpoint.as_deref().unwrap_or(default_endpoint);
+        let store: Arc<dyn VectorStore> = match config.vector_store {
+            MemoryVectorStoreType::Memory => Arc::new(InMemoryVectorStore::new()),
+            MemoryVectorStoreType::Qdrant => {
+                let url = config
+                    .qdrant_url
+                    .as_deref()
+                    .or(rag.vector_db_url.as_deref())
+                    .ok_or_else(|| MemoryError::Other("No Qdrant URL is configured".to_string()))?;
+                Arc::new(QdrantVectorStore::new(
+                    url,
+                    &config.collection,
+                    config.qdrant_api_key.clone(),
+                ))
+            }
+        };
+        let embedder = Arc::new(OpenAIEmbedder::new(
+            endpoint,
+            config.api_key.clone(),
+            &config.embedding_model,
+        ));
+        let extractor = Arc::new(OpenAIConnector::new(ConnectorConfig {
+            base_url: endpoint.to_string(),
+            api_key: config.api_key.clone(),
+            ..ConnectorConfig::default()
+        }));
+
+        Ok(Some(Self::new(embedder, store, extractor, config.clone())))
+    }
+
+    /// Vector store holding the facts
+    pub fn store(&self) -> &Arc<dyn VectorStore> {
+        &self.store
+    }
+
+    /// Extract salient facts from conversation messages
+    pub async fn extract_facts(&self, messages: &[Message]) -> Result<Vec<String>, MemoryError> {
+        let transcript: String = messages
+            .iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .map(|m| format!("{}: {}\n", m.role, m.content))
+            .collect();
+        if transcript.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let request = ChatCompletionRequest {
+            model: self.config.extraction_model.clone(),
+            messages: vec![
+                message(MessageRole::System, EXTRACTION_PROMPT),
+                message(MessageRole::User, &transcript),
+            ],
+            temperature: Some(0.0),
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        };
+        let response = self
+            .extractor
+            .generate(request)
+            .await
+            .map_err(|e| MemoryError::Other(format!("Fact extraction failed: {}", e)))?;
+        let content = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.as_str())
+            .unwrap_or_default();
+
+        let mut facts = parse_facts(content);
+        facts.truncate(self.config.max_facts_per_extraction);
+        Ok(facts)
+    }
+
+    /// Extract facts from conversation messages and store the new ones
+    ///
+    /// Facts nearly identical to one already stored for the namespace are
+    /// skipped. Returns the facts stored.
+    pub async fn remember(
+        &self,
+        namespace: &MemoryNamespace,
+        conversation_id: Option<&str>,
+        messages: &[Message],
+    ) -> Result<Vec<MemoryFact>, MemoryError> {
+        let facts = self.extract_facts(messages).await?;
+        if facts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let embeddings = self.embedder.embed(&facts).await?;
+
+        let mut stored = Vec::new();
+        for (fact, embedding) in facts.into_iter().zip(embeddings) {
+            let duplicate = self
+                .store
+                .search(namespace, &embedding, 1)
+                .await?
+                .first()
+                .is_some_and(|nearest| nearest.score >= self.config.dedupe_threshold);
+            if duplicate {
+                debug!("Skipping already remembered fact: {}", fact);
+                continue;
+            }
+
+            let fact = MemoryFact {
+                id: Uuid::new_v4().to_string(),
+                namespace: namespace.clone(),
+                fact,
+                conversation_id: conversation_id.map(str::to_string),
+                created_at: Utc::now(),
+            };
+            self.store.upsert(fact.clone(), embedding).await?;
+            stored.push(fact);
+        }
+        Ok(stored)
+    }
+
+    /// Recall the facts most relevant to a query
+    pub async fn recall(
+        &self,
+        namespace: &MemoryNamespace,
+        query: &str,
+        top_k: usize,
+        min_score: f32,
+    ) -> Result<Vec<ScoredFact>, MemoryError> {
+        let embedding = self
+            .embedder
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .unwrap_or_default();
+        let mut facts = self.store.search(namespace, &embedding, top_k).await?;
+        facts.retain(|fact| fact.score >= min_score);
+        Ok(facts)
+    }
+
+    /// Inject facts into a request according to a persona's memory settings
+    ///
+    /// Nothing is injected for personas without memory settings.
+    pub async fn inject_for_persona(
+        &self,
+        namespace: &MemoryNamespace,
+        persona: &Persona,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<Vec<ScoredFact>, MemoryError> {
+        match &persona.memory {
+            Some(settings) => self.inject(namespace, settings, request).await,
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Inject facts relevant to the latest user message into a request
+    ///
+    /// The facts are added as a system message after the request's leading
+    /// system messages. Returns the injected facts.
+    pub async fn inject(
+        &self,
+        namespace: &MemoryNamespace,
+        settings: &MemorySettings,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<Vec<ScoredFact>, MemoryError> {
+        if !settings.recall {
+            return Ok(Vec::new());
+        }
+        let Some(query) = request
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == MessageRole::User)
+            .map(|m| m.content.clone())
+        else {
+            return Ok(Vec::new());
+        };
+
+        let facts = self
+            .recall(namespace, &query, settings.top_k, settings.min_score)
+            .await?;
+        if facts.is_empty() {
+            return Ok(facts);
+        }
+
+        let content = std::iter::once(MEMORY_HEADING.to_string())
+            .chain(facts.iter().map(|f| format!("- {}", f.fact.fact)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let position = request
+            .messages
+            .iter()
+            .take_while(|m| m.role == MessageRole::System)
+            .count();
+        request
+            .messages
+            .insert(position, message(MessageRole::System, &content));
+        Ok(facts)
+    }
+}
+
+fn message(role: MessageRole, content: &str) -> ChatMessage {
+    ChatMessage {
+        role,
+        content: content.to_string(),
+        name: None,
+        function_call: None,
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+/// Parse the facts listed by the extraction model
+///
+/// Accepts a JSON array of strings, optionally inside a code fence, and
+/// falls back to one fact per bullet line.
+fn parse_facts(content: &str) -> Vec<String> {
+    let trimmed = content.trim();
+    let json = match (trimmed.find('['), trimmed.rfind(']')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    };
+    if let Ok(facts) = serde_json::from_str::<Vec<String>>(json) {
+        return facts
+            .into_iter()
+            .map(|f| f.trim().to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+    }
+
+    trimmed
+        .lines()
+        .filter_map(|line| {
+            line.trim()
+                .strip_prefix("- ")
+                .or_else(|| line.trim().strip_prefix("* "))
+        })
+        .map(|fact| fact.trim().to_string())
+        .filter(|fact| !fact.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::testing::KeywordEmbedder;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionChoice, ChatCompletionResponse, ConnectorError, StreamingResponse,
+    };
+    use crate::modules::persona_layer::persona::Persona;
+
+    /// Connector answering every extraction with the same facts
+    struct FixedExtractor {
+        config: ConnectorConfig,
+    }
+
+    #[async_trait]
+    impl ModelConnector for FixedExtractor {
+        async fn generate(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, ConnectorError> {
+            Ok(ChatCompletionResponse {
+                id: "resp".to_string(),
+                model: request.model,
+                created: 0,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message: message(
+                        MessageRole::Assistant,
+                        r#"```json
+["Lives in Berlin", "Drinks coffee black"]
+```"#,
+                    ),
+                    finish_reason: Some("stop".to_string()),
+                    content_filter: None,
+                }],
+                usage: None,
+            })
+        }
+
+        async fn generate_streaming(
+            &self,
+            _request: ChatCompletionRequest,
+        ) -> Result<StreamingResponse, ConnectorError> {
+            Err(ConnectorError::Other("not supported".to_string()))
+        }
+
+        fn get_config(&self) -> &ConnectorConfig {
+            &self.config
+        }
+
+        fn update_config(&mut self, config: ConnectorConfig) {
+            self.config = config;
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+            Ok(vec!["mock".to_string()])
+        }
+    }
+
+    fn memory() -> SemanticMemory {
+        SemanticMemory::new(
+            Arc::new(KeywordEmbedder(&["coffee", "berlin", "rust"])),
+            Arc::new(InMemoryVectorStore::new()),
+            Arc::new(FixedExtractor {
+                config: ConnectorConfig::default(),
+            }),
+            SemanticMemoryConfig::default(),
+        )
+    }
+
+    fn request(content: &str) -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![
+                message(MessageRole::System, "You are helpful."),
+                message(MessageRole::User, content),
+            ],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_facts() {
+        assert_eq!(parse_facts(r#"["a", " b ", ""]"#), vec!["a", "b"]);
+        assert_eq!(parse_facts("Facts:\n- a\n* b\nnone"), vec!["a", "b"]);
+        assert!(parse_facts("[]").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remember_and_inject() {
+        let memory = memory();
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let bob = MemoryNamespace::new("acme", "bob").unwrap();
+        let messages = vec![
+            Message::new("user", "I live in Berlin and drink my coffee black"),
+            Message::new("assistant", "Noted!"),
+        ];
+
+        let stored = memory
+            .remember(&alice, Some("c1"), &messages)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        // The same facts are not stored twice
+        assert!(memory
+            .remember(&alice, Some("c2"), &messages)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut request = request("Where can I get good coffee?");
+        let injected = memory
+            .inject(&alice, &MemorySettings::default(), &mut request)
+            .await
+            .unwrap();
+        assert_eq!(injected.len(), 1);
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(request.messages[1].role, MessageRole::System);
+        assert!(request.messages[1].content.contains("Drinks coffee black"));
+
+        // Other users' facts are never recalled
+        let mut request = self::request("Where can I get good coffee?");
+        assert!(memory
+            .inject(&bob, &MemorySettings::default(), &mut request)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(request.messages.len(), 2);
+
+        // Personas without memory settings get no facts
+        let mut persona = Persona::new("p", "P", "", "You are helpful.");
+        let mut request = self::request("Any coffee tips?");
+        assert!(memory
+            .inject_for_persona(&alice, &persona, &mut request)
+            .await
+            .unwrap()
+            .is_empty());
+        persona.memory = Some(MemorySettings::default());
+        assert_eq!(
+            memory
+                .inject_for_persona(&alice, &persona, &mut request)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
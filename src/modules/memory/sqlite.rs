@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::modules::common::{SqlValue, SqliteDatabase};
+use crate::modules::encryption::EnvelopeEncryptor;
+use crate::modules::memory::backend::MemoryBackend;
+use crate::modules::memory::types::{Conversation, MemoryError};
+
+/// Table of conversations, by storage key
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS memory_conversations (
+    key TEXT PRIMARY KEY,
+    data TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+)";
+
+/// SQLite backend of the embedded storage profile
+pub struct SqliteBackend {
+    db: SqliteDatabase,
+    /// Encrypts conversations at rest, if enabled
+    encryptor: Option<Arc<EnvelopeEncryptor>>,
+}
+
+impl SqliteBackend {
+    /// Create a SQLite backend, creating its table if needed
+    pub fn new(db: SqliteDatabase) -> Result<Self, MemoryError> {
+        db.with(|conn| conn.execute_batch(SCHEMA))
+            .map_err(storage_error)?;
+        Ok(Self {
+            db,
+            encryptor: None,
+        })
+    }
+
+    /// Encrypt conversations at rest
+    ///
+    /// Conversations stored before encryption was enabled stay readable.
+    pub fn with_encryption(mut self, encryptor: Arc<EnvelopeEncryptor>) -> Self {
+        self.encryptor = Some(encryptor);
+        self
+    }
+
+    /// Associated data sealing a conversation, like the Redis key would
+    fn sealing_key(id: &str) -> String {
+        format!("memory_conversations:{}", id)
+    }
+}
+
+fn storage_error(e: impl std::fmt::Display) -> MemoryError {
+    MemoryError::StorageError(format!("SQLite error: {}", e))
+}
+
+#[async_trait]
+impl MemoryBackend for SqliteBackend {
+    async fn get_conversation(&self, id: &str) -> Result<Option<Conversation>, MemoryError> {
+        let key = id.to_string();
+        let rows = self
+            .db
+            .call(move |conn| {
+                conn.query(
+                    "SELECT data FROM memory_conversations WHERE key = ?1",
+                    &[key.into()],
+                )
+            })
+            .await
+            .map_err(storage_error)?;
+        let Some(mut json) = rows
+            .into_iter()
+            .next()
+            .and_then(|row| row[0].clone().into_text())
+        else {
+            return Ok(None);
+        };
+
+        if let Some(encryptor) = &self.encryptor {
+            json = encryptor
+                .open_str(json, &Self::sealing_key(id))
+                .await
+                .map_err(|e| MemoryError::StorageError(e.to_string()))?;
+        }
+        let conversation: Conversation = serde_json::from_str(&json).map_err(|e| {
+            MemoryError::SerializationError(format!("Deserialization error: {}", e))
+        })?;
+        Ok(Some(conversation))
+    }
+
+    async fn save_conversation(&self, conversation: Conversation) -> Result<(), MemoryError> {
+        let key = conversation.storage_key();
+        let mut json = serde_json::to_string(&conversation)
+            .map_err(|e| MemoryError::SerializationError(format!("Serialization error: {}", e)))?;
+        if let Some(encryptor) = &self.encryptor {
+            json = encryptor
+                .seal_str(&json, &Self::sealing_key(&key))
+                .await
+                .map_err(|e| MemoryError::StorageError(e.to_string()))?;
+        }
+
+        let updated_at = conversation.updated_at.timestamp_millis();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "INSERT INTO memory_conversations (key, data, updated_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT (key) DO UPDATE SET data = excluded.data,
+                     updated_at = excluded.updated_at",
+                    &[key.into(), json.into(), SqlValue::Integer(updated_at)],
+                )
+            })
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn delete_conversation(&self, id: &str) -> Result<(), MemoryError> {
+        let key = id.to_string();
+        self.db
+            .call(move |conn| {
+                conn.execute(
+                    "DELETE FROM memory_conversations WHERE key = ?1",
+                    &[key.into()],
+                )
+            })
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn list_conversations(&self) -> Result<Vec<String>, MemoryError> {
+        let rows = self
+            .db
+            .call(|conn| conn.query("SELECT key FROM memory_conversations", &[]))
+            .await
+            .map_err(storage_error)?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| row.into_iter().next()?.into_text())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::types::Message;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_sqlite_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let db =
+            SqliteDatabase::open(dir.path().join("memory.db"), Duration::from_secs(1)).unwrap();
+        let backend = SqliteBackend::new(db.clone()).unwrap();
+
+        let mut conversation = Conversation::new("conv-1".to_string());
+        conversation.add_message(Message::new("user", "Hello"));
+        backend
+            .save_conversation(conversation.clone())
+            .await
+            .unwrap();
+        conversation.add_message(Message::new("assistant", "Hi"));
+        backend.save_conversation(conversation).await.unwrap();
+
+        // A second backend on the same file sees the conversation
+        let other = SqliteBackend::new(db).unwrap();
+        let loaded = other.get_conversation("conv-1").await.unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(other.list_conversations().await.unwrap(), vec!["conv-1"]);
+
+        backend.delete_conversation("conv-1").await.unwrap();
+        assert!(other.get_conversation("conv-1").await.unwrap().is_none());
+    }
+}
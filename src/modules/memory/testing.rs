@@ -0,0 +1,26 @@
+//! Test Helpers
+//!
+//! Embedders shared by the tests of the memory and RAG modules.
+
+use async_trait::async_trait;
+
+use super::{Embedder, MemoryError};
+
+/// Embedder mapping texts onto counts of the given keywords
+pub(crate) struct KeywordEmbedder(pub &'static [&'static str]);
+
+#[async_trait]
+impl Embedder for KeywordEmbedder {
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, MemoryError> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let text = text.to_lowercase();
+                self.0
+                    .iter()
+                    .map(|keyword| text.matches(keyword).count() as f32)
+                    .collect()
+            })
+            .collect())
+    }
+}
@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use thiserror::Error;
 
+use crate::modules::memory::namespace::MemoryNamespace;
+
 /// Error types for memory operations
 #[derive(Error, Debug)]
 pub enum MemoryError {
@@ -18,6 +20,15 @@ pub enum MemoryError {
     #[error("Lock error")]
     LockError,
 
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+
+    #[error("Invalid message index {index}: the conversation has {len} messages")]
+    InvalidIndex { index: usize, len: usize },
+
     #[error("Error: {0}")]
     Other(String),
 }
@@ -39,6 +50,36 @@ pub struct Conversation {
     pub metadata: HashMap<String, String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Tenant and user owning the conversation, if namespaced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<MemoryNamespace>,
+    /// Conversation this one was forked from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<Lineage>,
+    /// IDs of the conversations forked from this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forks: Vec<String>,
+    /// When the conversation was deleted, while it can still be restored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Where a forked conversation branched off its parent
+///
+/// A fork starts with the first `fork_index` messages of its parent. While
+/// `shared`, those messages are read from the parent and only the fork's own
+/// messages are stored with it; they are copied into the fork before the
+/// parent drops or trims them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lineage {
+    /// ID of the parent conversation
+    pub parent_id: String,
+    /// Number of the parent's messages the fork starts with
+    pub fork_index: usize,
+    /// Whether the inherited messages are still read from the parent
+    pub shared: bool,
+    /// When the fork was created
+    pub forked_at: DateTime<Utc>,
 }
 
 impl Message {
@@ -69,9 +110,39 @@ impl Conversation {
             metadata: HashMap::new(),
             created_at: now,
             updated_at: now,
+            namespace: None,
+            lineage: None,
+            forks: Vec::new(),
+            deleted_at: None,
         }
     }
 
+    /// Create an empty conversation in a namespace
+    pub fn in_namespace(id: String, namespace: MemoryNamespace) -> Self {
+        Self {
+            namespace: Some(namespace),
+            ..Self::new(id)
+        }
+    }
+
+    /// Key the conversation is stored under
+    pub fn storage_key(&self) -> String {
+        self.related_key(&self.id)
+    }
+
+    /// Key of a conversation in the same namespace as this one
+    pub fn related_key(&self, id: &str) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{}{}", namespace.prefix(), id),
+            None => id.to_string(),
+        }
+    }
+
+    /// Whether the conversation reads inherited messages from its parent
+    pub fn shares_history(&self) -> bool {
+        self.lineage.as_ref().is_some_and(|lineage| lineage.shared)
+    }
+
     /// Add a message to the conversation
     pub fn add_message(&mut self, message: Message) {
         self.messages.push(message);
@@ -0,0 +1,484 @@
+//! Memory Vector Stores
+//!
+//! This module stores embedded long-term memory facts, scoped to a memory
+//! namespace, and searches them by cosine similarity. An in-memory store and
+//! a Qdrant-backed store are provided.
+
+use std::sync::RwLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+
+use crate::modules::memory::namespace::MemoryNamespace;
+use crate::modules::memory::types::MemoryError;
+
+/// A long-term fact remembered about a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryFact {
+    pub id: String,
+    pub namespace: MemoryNamespace,
+    pub fact: String,
+    /// Conversation the fact was extracted from
+    pub conversation_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A fact matching a search, with its similarity score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredFact {
+    #[serde(flatten)]
+    pub fact: MemoryFact,
+    /// Cosine similarity to the query, between -1.0 and 1.0
+    pub score: f32,
+}
+
+/// Storage of embedded memory facts
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    /// Store a fact with its embedding
+    async fn upsert(&self, fact: MemoryFact, embedding: Vec<f32>) -> Result<(), MemoryError>;
+
+    /// Find the facts of a namespace most similar to an embedding
+    async fn search(
+        &self,
+        namespace: &MemoryNamespace,
+        embedding: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<ScoredFact>, MemoryError>;
+
+    /// List the facts of a namespace
+    async fn list(&self, namespace: &MemoryNamespace) -> Result<Vec<MemoryFact>, MemoryError>;
+
+    /// Delete a fact of a namespace
+    async fn delete(&self, namespace: &MemoryNamespace, id: &str) -> Result<(), MemoryError>;
+
+    /// Delete every fact of a namespace, returning how many were deleted
+    async fn delete_namespace(&self, namespace: &MemoryNamespace) -> Result<usize, MemoryError>;
+
+    /// Delete every fact of a tenant, returning how many were deleted
+    async fn delete_tenant(&self, tenant: &str) -> Result<usize, MemoryError>;
+
+    /// Run a search of one result, to check that the store answers queries
+    ///
+    /// The default does nothing, for stores held in memory.
+    async fn probe(&self) -> Result<(), MemoryError> {
+        Ok(())
+    }
+}
+
+/// Cosine similarity of two vectors, 0.0 if either is empty or zero
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Vector store keeping facts in memory
+#[derive(Default)]
+pub struct InMemoryVectorStore {
+    facts: RwLock<Vec<(MemoryFact, Vec<f32>)>>,
+}
+
+impl InMemoryVectorStore {
+    /// Create an empty in-memory vector store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn delete_where(&self, matches: impl Fn(&MemoryFact) -> bool) -> usize {
+        let mut facts = self.facts.write().unwrap();
+        let before = facts.len();
+        facts.retain(|(fact, _)| !matches(fact));
+        before - facts.len()
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn upsert(&self, fact: MemoryFact, embedding: Vec<f32>) -> Result<(), MemoryError> {
+        let mut facts = self.facts.write().unwrap();
+        facts.retain(|(existing, _)| existing.id != fact.id);
+        facts.push((fact, embedding));
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        namespace: &MemoryNamespace,
+        embedding: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<ScoredFact>, MemoryError> {
+        let facts = self.facts.read().unwrap();
+        let mut scored: Vec<ScoredFact> = facts
+            .iter()
+            .filter(|(fact, _)| &fact.namespace == namespace)
+            .map(|(fact, vector)| ScoredFact {
+                fact: fact.clone(),
+                score: cosine_similarity(embedding, vector),
+            })
+            .collect();
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score));
+        scored.truncate(top_k);
+        Ok(scored)
+    }
+
+    async fn list(&self, namespace: &MemoryNamespace) -> Result<Vec<MemoryFact>, MemoryError> {
+        let facts = self.facts.read().unwrap();
+        Ok(facts
+            .iter()
+            .filter(|(fact, _)| &fact.namespace == namespace)
+            .map(|(fact, _)| fact.clone())
+            .collect())
+    }
+
+    async fn delete(&self, namespace: &MemoryNamespace, id: &str) -> Result<(), MemoryError> {
+        self.delete_where(|fact| &fact.namespace == namespace && fact.id == id);
+        Ok(())
+    }
+
+    async fn delete_namespace(&self, namespace: &MemoryNamespace) -> Result<usize, MemoryError> {
+        Ok(self.delete_where(|fact| &fact.namespace == namespace))
+    }
+
+    async fn delete_tenant(&self, tenant: &str) -> Result<usize, MemoryError> {
+        Ok(self.delete_where(|fact| fact.namespace.tenant == tenant))
+    }
+}
+
+/// Vector store backed by a Qdrant collection, accessed over its REST API
+///
+/// The collection is created with cosine distance on first write, sized to
+/// the first embedding stored.
+pub struct QdrantVectorStore {
+    client: Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+    created: OnceCell<()>,
+}
+
+impl QdrantVectorStore {
+    /// Create a store for a collection of a Qdrant server
+    pub fn new(url: &str, collection: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key,
+            created: OnceCell::new(),
+        }
+    }
+
+    /// Send a request to the collection, returning the `result` field
+    async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, MemoryError> {
+        let url = format!("{}/collections/{}{}", self.url, self.collection, path);
+        let mut request = self.client.request(method, &url);
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| MemoryError::StorageError(format!("Qdrant request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|e| {
+            MemoryError::SerializationError(format!("Invalid Qdrant response: {}", e))
+        })?;
+        if !status.is_success() {
+            return Err(MemoryError::StorageError(format!(
+                "Qdrant returned {}: {}",
+                status, body
+            )));
+        }
+        Ok(body.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Create the collection unless it exists
+    async fn ensure_collection(&self, dimension: usize) -> Result<(), MemoryError> {
+        self.created
+            .get_or_try_init(|| async {
+                if self.request(reqwest::Method::GET, "", None).await.is_ok() {
+                    return Ok(());
+                }
+                self.request(
+                    reqwest::Method::PUT,
+                    "",
+                    Some(json!({ "vectors": { "size": dimension, "distance": "Cosine" } })),
+                )
+                .await
+                .map(|_| ())
+            })
+            .await
+            .map(|_| ())
+    }
+
+    /// Count the points matching a filter
+    async fn count(&self, filter: &Value) -> Result<usize, MemoryError> {
+        let result = self
+            .request(
+                reqwest::Method::POST,
+                "/points/count",
+                Some(json!({ "filter": filter, "exact": true })),
+            )
+            .await?;
+        Ok(result.get("count").and_then(Value::as_u64).unwrap_or(0) as usize)
+    }
+
+    /// Delete the points matching a filter, returning how many there were
+    async fn delete_filtered(&self, filter: Value) -> Result<usize, MemoryError> {
+        let count = match self.count(&filter).await {
+            Ok(count) => count,
+            // The collection does not exist yet, so there is nothing to delete
+            Err(_) => return Ok(0),
+        };
+        if count > 0 {
+            self.request(
+                reqwest::Method::POST,
+                "/points/delete?wait=true",
+                Some(json!({ "filter": filter })),
+            )
+            .await?;
+        }
+        Ok(count)
+    }
+}
+
+/// Qdrant filter matching payload fields exactly
+fn match_filter(fields: &[(&str, &str)]) -> Value {
+    let must: Vec<Value> = fields
+        .iter()
+        .map(|(key, value)| json!({ "key": key, "match": { "value": value } }))
+        .collect();
+    json!({ "must": must })
+}
+
+fn namespace_filter(namespace: &MemoryNamespace) -> Value {
+    match_filter(&[("tenant", &namespace.tenant), ("user", &namespace.user)])
+}
+
+/// Rebuild a fact from a Qdrant point
+fn fact_from_point(point: &Value) -> Option<MemoryFact> {
+    let payload = point.get("payload")?;
+    let text = |key: &str| payload.get(key).and_then(Value::as_str).map(str::to_string);
+    Some(MemoryFact {
+        id: match point.get("id")? {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        },
+        namespace: MemoryNamespace {
+            tenant: text("tenant")?,
+            user: text("user")?,
+        },
+        fact: text("fact")?,
+        conversation_id: text("conversation_id"),
+        created_at: text("created_at")
+            .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now),
+    })
+}
+
+#[async_trait]
+impl VectorStore for QdrantVectorStore {
+    async fn upsert(&self, fact: MemoryFact, embedding: Vec<f32>) -> Result<(), MemoryError> {
+        self.ensure_collection(embedding.len()).await?;
+        let point = json!({
+            "id": fact.id,
+            "vector": embedding,
+            "payload": {
+                "tenant": fact.namespace.tenant,
+                "user": fact.namespace.user,
+                "fact": fact.fact,
+                "conversation_id": fact.conversation_id,
+                "created_at": fact.created_at.to_rfc3339(),
+            }
+        });
+        self.request(
+            reqwest::Method::PUT,
+            "/points?wait=true",
+            Some(json!({ "points": [point] })),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn search(
+        &self,
+        namespace: &MemoryNamespace,
+        embedding: &[f32],
+        top_k: usize,
+    ) -> Result<Vec<ScoredFact>, MemoryError> {
+        let result = self
+            .request(
+                reqwest::Method::POST,
+                "/points/search",
+                Some(json!({
+                    "vector": embedding,
+                    "limit": top_k,
+                    "with_payload": true,
+                    "filter": namespace_filter(namespace),
+                })),
+            )
+            .await?;
+        Ok(result
+            .as_array()
+            .map(|points| {
+                points
+                    .iter()
+                    .filter_map(|point| {
+                        Some(ScoredFact {
+                            fact: fact_from_point(point)?,
+                            score: point.get("score")?.as_f64()? as f32,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn list(&self, namespace: &MemoryNamespace) -> Result<Vec<MemoryFact>, MemoryError> {
+        let mut facts = Vec::new();
+        let mut offset = Value::Null;
+        loop {
+            let result = self
+                .request(
+                    reqwest::Method::POST,
+                    "/points/scroll",
+                    Some(json!({
+                        "filter": namespace_filter(namespace),
+                        "limit": 256,
+                        "offset": offset,
+                        "with_payload": true,
+                    })),
+                )
+                .await?;
+            if let Some(points) = result.get("points").and_then(Value::as_array) {
+                facts.extend(points.iter().filter_map(fact_from_point));
+            }
+            match result.get("next_page_offset") {
+                Some(next) if !next.is_null() => offset = next.clone(),
+                _ => return Ok(facts),
+            }
+        }
+    }
+
+    async fn delete(&self, namespace: &MemoryNamespace, id: &str) -> Result<(), MemoryError> {
+        let mut filter = namespace_filter(namespace);
+        filter["must"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "has_id": [id] }));
+        self.delete_filtered(filter).await.map(|_| ())
+    }
+
+    async fn delete_namespace(&self, namespace: &MemoryNamespace) -> Result<usize, MemoryError> {
+        self.delete_filtered(namespace_filter(namespace)).await
+    }
+
+    async fn delete_tenant(&self, tenant: &str) -> Result<usize, MemoryError> {
+        self.delete_filtered(match_filter(&[("tenant", tenant)]))
+            .await
+    }
+
+    /// Search the collection with a unit vector of its size
+    async fn probe(&self) -> Result<(), MemoryError> {
+        let collection = self.request(reqwest::Method::GET, "", None).await?;
+        let dimension = collection
+            .pointer("/config/params/vectors/size")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| {
+                MemoryError::SerializationError("Qdrant collection without a vector size".into())
+            })?;
+        let mut vector = vec![0.0f32; dimension as usize];
+        if let Some(first) = vector.first_mut() {
+            *first = 1.0;
+        }
+        self.request(
+            reqwest::Method::POST,
+            "/points/search",
+            Some(json!({ "vector": vector, "limit": 1 })),
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fact(id: &str, namespace: &MemoryNamespace) -> MemoryFact {
+        MemoryFact {
+            id: id.to_string(),
+            namespace: namespace.clone(),
+            fact: format!("fact {}", id),
+            conversation_id: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        let store = InMemoryVectorStore::new();
+        let alice = MemoryNamespace::new("acme", "alice").unwrap();
+        let bob = MemoryNamespace::new("acme", "bob").unwrap();
+
+        store
+            .upsert(fact("a", &alice), vec![1.0, 0.0])
+            .await
+            .unwrap();
+        store
+            .upsert(fact("b", &alice), vec![0.0, 1.0])
+            .await
+            .unwrap();
+        store.upsert(fact("c", &bob), vec![1.0, 0.0]).await.unwrap();
+
+        let results = store.search(&alice, &[0.9, 0.1], 5).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].fact.id, "a");
+        assert!(results[0].score > results[1].score);
+
+        assert_eq!(store.delete_namespace(&alice).await.unwrap(), 2);
+        assert!(store.list(&alice).await.unwrap().is_empty());
+        assert_eq!(store.delete_tenant("acme").await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_fact_from_point() {
+        let point = json!({
+            "id": "f1",
+            "score": 0.9,
+            "payload": {
+                "tenant": "acme",
+                "user": "alice",
+                "fact": "Prefers metric units",
+                "conversation_id": null,
+                "created_at": "2024-01-01T00:00:00Z"
+            }
+        });
+        let fact = fact_from_point(&point).unwrap();
+        assert_eq!(fact.namespace.user, "alice");
+        assert_eq!(fact.fact, "Prefers metric units");
+        assert!(fact.conversation_id.is_none());
+    }
+}
@@ -4,8 +4,11 @@
 
 pub mod audit;
 pub mod authz;
+pub mod bundle;
 pub mod chain_engine;
 pub mod common;
+pub mod encryption;
+pub mod erasure;
 pub mod health;
 pub mod ipc;
 pub mod llm_proxy;
@@ -15,8 +18,11 @@ pub mod monitoring;
 pub mod orchestrator;
 pub mod persona_layer;
 pub mod rag_manager;
+pub mod remote;
 pub mod router_core;
+pub mod support;
 pub mod telemetry;
+pub mod tools;
 
 // Re-enable the test harness module
 #[cfg(feature = "test-harness")]
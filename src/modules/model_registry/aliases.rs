@@ -0,0 +1,210 @@
+//! Model Aliases
+//!
+//! This module resolves the model names of requests to the models serving
+//! them. Besides registered model IDs, requests can name:
+//!
+//! - an alias, such as `gpt-4` for `gpt-4o-2024-08-06`
+//! - a provider-prefixed model, such as `openai/gpt-4o`
+//! - a model matching a wildcard route, such as `anthropic/*`, which is sent
+//!   to the route's provider
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ModelRegistryConfig;
+
+/// Maximum number of aliases followed to resolve a model name
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+/// How a requested model name was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolutionKind {
+    /// The name is a registered model ID
+    Exact,
+    /// The name is an alias of a registered model ID
+    Alias,
+    /// The name is a registered model ID prefixed with its provider
+    ProviderPrefix,
+    /// The name matches a wildcard route
+    Route,
+    /// The name matches nothing and is used as is
+    Unresolved,
+}
+
+impl ResolutionKind {
+    /// Name of the resolution in headers and logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResolutionKind::Exact => "exact",
+            ResolutionKind::Alias => "alias",
+            ResolutionKind::ProviderPrefix => "provider_prefix",
+            ResolutionKind::Route => "route",
+            ResolutionKind::Unresolved => "unresolved",
+        }
+    }
+}
+
+/// Model resolved for a requested model name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelResolution {
+    /// Model name in the request
+    pub requested: String,
+    /// Model to call
+    pub model: String,
+    /// Provider serving the model, if known
+    pub provider: Option<String>,
+    /// How the model was resolved
+    pub kind: ResolutionKind,
+    /// Whether an alias was followed, before a provider prefix or route
+    /// matched
+    pub aliased: bool,
+}
+
+impl ModelResolution {
+    /// Resolution of a name matching nothing
+    pub fn unresolved(requested: impl Into<String>) -> Self {
+        let requested = requested.into();
+        Self {
+            model: requested.clone(),
+            requested,
+            provider: None,
+            kind: ResolutionKind::Unresolved,
+            aliased: false,
+        }
+    }
+
+    /// Whether the model called differs from the one requested
+    pub fn is_rewritten(&self) -> bool {
+        self.model != self.requested
+    }
+}
+
+/// Route sending the models that match a pattern to a provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelRoute {
+    /// Model name, or prefix ending with `*`
+    pub pattern: String,
+    /// Provider serving the matching models
+    pub provider: String,
+}
+
+impl ModelRoute {
+    /// Create a route
+    pub fn new(pattern: impl Into<String>, provider: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            provider: provider.into(),
+        }
+    }
+
+    /// Model to call for a model name matching the route
+    ///
+    /// Prefixes ending with `/` are stripped, so `anthropic/claude-3-haiku`
+    /// matching `anthropic/*` calls `claude-3-haiku`.
+    pub fn apply(&self, model: &str) -> Option<String> {
+        let Some(prefix) = self.pattern.strip_suffix('*') else {
+            return (self.pattern == model).then(|| model.to_string());
+        };
+        let rest = model.strip_prefix(prefix).filter(|rest| !rest.is_empty())?;
+        Some(if prefix.ends_with('/') {
+            rest.to_string()
+        } else {
+            model.to_string()
+        })
+    }
+}
+
+/// Aliases and wildcard routes of model names
+#[derive(Debug, Clone, Default)]
+pub struct ModelAliases {
+    /// Alias to target model name
+    aliases: HashMap<String, String>,
+    /// Routes, in the order they are matched
+    routes: Vec<ModelRoute>,
+}
+
+impl ModelAliases {
+    /// Create an empty set of aliases
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create the aliases and routes configured for the model registry
+    pub fn from_config(config: &ModelRegistryConfig) -> Self {
+        let aliases = config
+            .aliases
+            .iter()
+            .fold(Self::new(), |aliases, (alias, target)| {
+                aliases.with_alias(alias.clone(), target.clone())
+            });
+        config.routes.iter().fold(aliases, |aliases, route| {
+            aliases.with_route(route.pattern.clone(), route.provider.clone())
+        })
+    }
+
+    /// Add an alias of a model name
+    pub fn with_alias(mut self, alias: impl Into<String>, target: impl Into<String>) -> Self {
+        self.aliases.insert(alias.into(), target.into());
+        self
+    }
+
+    /// Add a route, matched after the routes already added
+    pub fn with_route(mut self, pattern: impl Into<String>, provider: impl Into<String>) -> Self {
+        self.routes.push(ModelRoute::new(pattern, provider));
+        self
+    }
+
+    /// Whether there are neither aliases nor routes
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.routes.is_empty()
+    }
+
+    /// Target of an alias
+    pub fn target(&self, alias: &str) -> Option<&str> {
+        self.aliases.get(alias).map(String::as_str)
+    }
+
+    /// First route matching a model name, with the model to call
+    pub fn route(&self, model: &str) -> Option<(&ModelRoute, String)> {
+        self.routes
+            .iter()
+            .find_map(|route| route.apply(model).map(|model| (route, model)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_routes() {
+        let prefixed = ModelRoute::new("anthropic/*", "anthropic");
+        assert_eq!(
+            prefixed.apply("anthropic/claude-3-haiku").as_deref(),
+            Some("claude-3-haiku")
+        );
+        assert_eq!(prefixed.apply("anthropic/"), None);
+        assert_eq!(prefixed.apply("openai/gpt-4o"), None);
+
+        let family = ModelRoute::new("mistral-*", "mistral");
+        assert_eq!(
+            family.apply("mistral-large").as_deref(),
+            Some("mistral-large")
+        );
+
+        let exact = ModelRoute::new("local", "ollama");
+        assert_eq!(exact.apply("local").as_deref(), Some("local"));
+        assert_eq!(exact.apply("local-2"), None);
+
+        let aliases = ModelAliases::new()
+            .with_route("mistral-*", "mistral")
+            .with_route("*", "openrouter");
+        assert_eq!(
+            aliases.route("mistral-small").unwrap().0.provider,
+            "mistral"
+        );
+        assert_eq!(aliases.route("llama-3").unwrap().0.provider, "openrouter");
+    }
+}
@@ -0,0 +1,220 @@
+//! Content Filter Finishes
+//!
+//! Providers end filtered generations in their own ways: Azure OpenAI with a
+//! `content_filter` finish reason, or a `400` error when the prompt itself is
+//! filtered; Anthropic with a `refusal` stop reason; Gemini with `SAFETY`,
+//! `RECITATION` and similar finish reasons, or a blocked prompt. This module
+//! maps them all onto the OpenAI `content_filter` finish reason, with a
+//! [`ContentFilterDetail`] describing what the provider reported.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Finish reason of filtered generations
+pub const CONTENT_FILTER: &str = "content_filter";
+
+/// Provider finish reasons ending filtered generations, lowercased
+const FILTER_REASONS: &[&str] = &[
+    "content_filter",
+    "refusal",
+    "safety",
+    "recitation",
+    "blocklist",
+    "prohibited_content",
+    "spii",
+    "image_safety",
+];
+
+/// Part of a request a provider filtered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterStage {
+    /// The prompt was rejected before generating
+    Prompt,
+    /// The generation was stopped
+    Completion,
+}
+
+impl FilterStage {
+    /// Name of the stage, as used in metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilterStage::Prompt => "prompt",
+            FilterStage::Completion => "completion",
+        }
+    }
+}
+
+/// What a provider reported about a filtered request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContentFilterDetail {
+    /// Part of the request filtered
+    pub stage: FilterStage,
+    /// Reason given by the provider, such as `content_filter`, `refusal` or
+    /// `SAFETY`
+    pub reason: String,
+    /// Categories the provider flagged, such as `hate` or
+    /// `HARM_CATEGORY_HARASSMENT`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Message of the provider, for filtered prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Whether a provider finish reason ends a filtered generation
+pub fn is_filter_reason(reason: &str) -> bool {
+    FILTER_REASONS.contains(&reason.to_lowercase().as_str())
+}
+
+/// Map a provider finish reason onto the OpenAI ones
+pub fn normalize_finish_reason(reason: String) -> String {
+    if is_filter_reason(&reason) {
+        CONTENT_FILTER.to_string()
+    } else {
+        reason
+    }
+}
+
+impl ContentFilterDetail {
+    /// Detail of a generation a provider ended with a filter finish reason
+    ///
+    /// `results` are the Azure `content_filter_results` of the choice, if any.
+    pub fn from_finish_reason(reason: &str, results: Option<&Value>) -> Option<Self> {
+        is_filter_reason(reason).then(|| Self {
+            stage: FilterStage::Completion,
+            reason: reason.to_string(),
+            categories: results.map(filtered_categories).unwrap_or_default(),
+            message: None,
+        })
+    }
+
+    /// Detail of a provider error rejecting a filtered prompt
+    ///
+    /// Returns `None` for other errors.
+    pub fn from_error_body(body: &str) -> Option<Self> {
+        let body: Value = serde_json::from_str(body).ok()?;
+
+        // Azure OpenAI
+        if let Some(error) = body.get("error") {
+            let code = |value: &Value| {
+                value
+                    .get("code")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            let inner = error.get("innererror");
+            let filtered = code(error).as_deref() == Some(CONTENT_FILTER)
+                || inner.and_then(code).as_deref() == Some("ResponsibleAIPolicyViolation");
+            if !filtered {
+                return None;
+            }
+            return Some(Self {
+                stage: FilterStage::Prompt,
+                reason: CONTENT_FILTER.to_string(),
+                categories: inner
+                    .and_then(|inner| inner.get("content_filter_result"))
+                    .map(filtered_categories)
+                    .unwrap_or_default(),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            });
+        }
+
+        // Gemini
+        let feedback = body.get("promptFeedback")?;
+        let reason = feedback.get("blockReason").and_then(Value::as_str)?;
+        let categories = feedback
+            .get("safetyRatings")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|rating| rating.get("blocked").and_then(Value::as_bool) == Some(true))
+            .filter_map(|rating| rating.get("category").and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+        Some(Self {
+            stage: FilterStage::Prompt,
+            reason: reason.to_string(),
+            categories,
+            message: None,
+        })
+    }
+}
+
+/// Categories marked as filtered in Azure content filter results, which map
+/// each category to `{"filtered": bool, "severity": ...}`
+fn filtered_categories(results: &Value) -> Vec<String> {
+    let mut categories: Vec<String> = results
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(_, result)| result.get("filtered").and_then(Value::as_bool) == Some(true))
+        .map(|(category, _)| category.clone())
+        .collect();
+    categories.sort();
+    categories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_finish_reasons() {
+        assert_eq!(
+            normalize_finish_reason("SAFETY".to_string()),
+            "content_filter"
+        );
+        assert_eq!(
+            normalize_finish_reason("refusal".to_string()),
+            "content_filter"
+        );
+        assert_eq!(normalize_finish_reason("length".to_string()), "length");
+
+        let results = json!({
+            "hate": {"filtered": true, "severity": "high"},
+            "violence": {"filtered": false, "severity": "safe"}
+        });
+        let detail =
+            ContentFilterDetail::from_finish_reason("content_filter", Some(&results)).unwrap();
+        assert_eq!(detail.stage, FilterStage::Completion);
+        assert_eq!(detail.categories, vec!["hate"]);
+        assert!(ContentFilterDetail::from_finish_reason("stop", None).is_none());
+    }
+
+    #[test]
+    fn test_filtered_prompt_errors() {
+        let azure = json!({"error": {
+            "message": "The response was filtered",
+            "code": "content_filter",
+            "innererror": {
+                "code": "ResponsibleAIPolicyViolation",
+                "content_filter_result": {"self_harm": {"filtered": true, "severity": "medium"}}
+            }
+        }});
+        let detail = ContentFilterDetail::from_error_body(&azure.to_string()).unwrap();
+        assert_eq!(detail.stage, FilterStage::Prompt);
+        assert_eq!(detail.categories, vec!["self_harm"]);
+        assert_eq!(detail.message.as_deref(), Some("The response was filtered"));
+
+        let gemini = json!({"promptFeedback": {
+            "blockReason": "SAFETY",
+            "safetyRatings": [
+                {"category": "HARM_CATEGORY_HARASSMENT", "probability": "HIGH", "blocked": true},
+                {"category": "HARM_CATEGORY_HATE_SPEECH", "probability": "LOW"}
+            ]
+        }});
+        let detail = ContentFilterDetail::from_error_body(&gemini.to_string()).unwrap();
+        assert_eq!(detail.reason, "SAFETY");
+        assert_eq!(detail.categories, vec!["HARM_CATEGORY_HARASSMENT"]);
+
+        let other = json!({"error": {"message": "Invalid model", "code": "model_not_found"}});
+        assert!(ContentFilterDetail::from_error_body(&other.to_string()).is_none());
+        assert!(ContentFilterDetail::from_error_body("Bad request").is_none());
+    }
+}
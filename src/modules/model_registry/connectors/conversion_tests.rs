@@ -29,6 +29,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::User,
@@ -36,6 +37,7 @@ mod tests {
                     name: Some("user1".to_string()),
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Assistant,
@@ -43,6 +45,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Function,
@@ -50,6 +53,7 @@ mod tests {
                     name: Some("get_time".to_string()),
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Tool,
@@ -57,6 +61,7 @@ mod tests {
                     name: Some("calculator".to_string()),
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
             ],
             temperature: Some(0.7),
@@ -242,6 +247,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::User,
@@ -249,6 +255,7 @@ mod tests {
                     name: Some("user1".to_string()),
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Assistant,
@@ -259,6 +266,7 @@ mod tests {
                         arguments: "{\"timezone\": \"UTC\"}".to_string(),
                     }),
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Function,
@@ -266,6 +274,7 @@ mod tests {
                     name: Some("get_time".to_string()),
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::Tool,
@@ -280,6 +289,7 @@ mod tests {
                             arguments: "{\"expression\": \"6 * 7\"}".to_string(),
                         },
                     }]),
+                    tool_call_id: None,
                 },
             ],
             temperature: Some(0.7),
@@ -477,6 +487,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
             }],
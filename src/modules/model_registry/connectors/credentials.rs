@@ -0,0 +1,661 @@
+//! Standby Provider Credentials
+//!
+//! This module fails a provider over from its primary credentials to cold
+//! standby ones: other API keys, usually of another organization, that are
+//! only used while the primary can't be. A credential fails when the provider
+//! rejects it (an authentication error) or when it hits a rate limit of its
+//! whole organization, recognized by the `rate_limit_markers` in the error;
+//! the request is then retried with the next credential, in configuration
+//! order. Other errors, including rate limits of a single request, are
+//! returned as is.
+//!
+//! A credential that failed is skipped for `cooldown_secs`, after which the
+//! first credential in order is used again, so traffic returns to the
+//! primary once it recovers. Every switch is logged and POSTed to the
+//! configured alert webhooks, and the requests, failures and tokens of each
+//! credential are counted.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use metrics::counter;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::{
+    ChatCompletionRequest, ChatCompletionResponse, ConnectorConfig, ConnectorError, ModelConnector,
+    ModelConnectorFactory, RawStreamingResponse, StreamingResponse,
+};
+use crate::config::{CredentialFailoverConfig, LlmProviderConfig, ProviderCredentialConfig};
+use crate::modules::telemetry::anomaly::deliver_alert;
+use crate::modules::telemetry::catalog;
+
+/// Name of the credentials a connector is configured with
+pub const PRIMARY_CREDENTIAL: &str = "primary";
+
+/// Credentials a provider is called with
+#[derive(Clone)]
+pub struct Credential {
+    pub name: String,
+    pub api_key: Option<String>,
+    pub org_id: Option<String>,
+}
+
+impl Credential {
+    /// Create a credential
+    pub fn new(name: impl Into<String>, api_key: Option<String>, org_id: Option<String>) -> Self {
+        Self {
+            name: name.into(),
+            api_key,
+            org_id,
+        }
+    }
+
+    /// Create a credential from its configuration, reading the API key from
+    /// the environment
+    pub fn from_config(config: &ProviderCredentialConfig) -> Self {
+        Self::new(
+            config.name.clone(),
+            std::env::var(&config.api_key_env).ok(),
+            config.org_id.clone(),
+        )
+    }
+
+    /// Connector configuration using this credential
+    fn apply(&self, config: &ConnectorConfig) -> ConnectorConfig {
+        ConnectorConfig {
+            api_key: self.api_key.clone(),
+            org_id: self.org_id.clone(),
+            ..config.clone()
+        }
+    }
+}
+
+/// Usage of a credential, as counted since startup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CredentialUsage {
+    pub name: String,
+    /// Whether requests currently use the credential
+    pub active: bool,
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Times the credential failed over to another
+    pub failovers: u64,
+    /// Seconds left before a failed credential is tried again
+    pub cooldown_remaining_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// Change of the credential a provider is called with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CredentialEvent {
+    /// A credential failed and the next one is used
+    FailedOver {
+        from: String,
+        to: String,
+        reason: String,
+    },
+    /// Every credential failed
+    Exhausted { reason: String },
+    /// A credential is used again after its cooldown
+    Restored { from: String, to: String },
+}
+
+impl CredentialEvent {
+    /// Name of the event, as sent to alert webhooks
+    pub fn name(&self) -> &'static str {
+        match self {
+            CredentialEvent::FailedOver { .. } => "credentials.failed_over",
+            CredentialEvent::Exhausted { .. } => "credentials.exhausted",
+            CredentialEvent::Restored { .. } => "credentials.restored",
+        }
+    }
+}
+
+/// Credential with the connector calling the provider with it
+struct Slot {
+    credential: Credential,
+    connector: Arc<dyn ModelConnector>,
+}
+
+/// Which credential is used, and the usage of each
+struct State {
+    active: usize,
+    cooldown_until: Vec<Option<Instant>>,
+    usage: Vec<CredentialUsage>,
+}
+
+/// Connector failing over to standby credentials
+///
+/// Wraps one connector per credential, all created by the provider's
+/// connector factory from the same configuration.
+pub struct FailoverConnector {
+    factory: Arc<dyn ModelConnectorFactory>,
+    config: ConnectorConfig,
+    slots: Vec<Slot>,
+    failover: CredentialFailoverConfig,
+    state: Mutex<State>,
+    client: Client,
+}
+
+impl FailoverConnector {
+    /// Create a connector using the credentials of `config` first, then the
+    /// standby credentials in order
+    pub fn new(
+        factory: Arc<dyn ModelConnectorFactory>,
+        config: ConnectorConfig,
+        standby: Vec<Credential>,
+        failover: CredentialFailoverConfig,
+    ) -> Self {
+        let primary = Credential::new(
+            PRIMARY_CREDENTIAL,
+            config.api_key.clone(),
+            config.org_id.clone(),
+        );
+        let credentials: Vec<Credential> = std::iter::once(primary).chain(standby).collect();
+        let state = State {
+            active: 0,
+            cooldown_until: vec![None; credentials.len()],
+            usage: credentials
+                .iter()
+                .map(|credential| CredentialUsage {
+                    name: credential.name.clone(),
+                    ..CredentialUsage::default()
+                })
+                .collect(),
+        };
+        let slots = credentials
+            .into_iter()
+            .map(|credential| Slot {
+                connector: factory.create_connector(credential.apply(&config)),
+                credential,
+            })
+            .collect();
+        Self {
+            factory,
+            config,
+            slots,
+            failover,
+            state: Mutex::new(state),
+            client: Client::new(),
+        }
+    }
+
+    /// Create a connector for a configured provider
+    ///
+    /// The primary API key is read from `api_key_env` and its organization
+    /// from the `org_id` setting.
+    pub fn from_provider_config(
+        factory: Arc<dyn ModelConnectorFactory>,
+        provider: &LlmProviderConfig,
+        failover: CredentialFailoverConfig,
+    ) -> Self {
+        let config = ConnectorConfig {
+            base_url: provider.endpoint.clone(),
+            api_key: std::env::var(&provider.api_key_env).ok(),
+            org_id: provider.settings.get("org_id").cloned(),
+            timeout_secs: provider.timeout_secs,
+            max_retries: provider.max_retries,
+            additional_config: provider.settings.clone(),
+        };
+        let standby = provider
+            .standby_credentials
+            .iter()
+            .map(Credential::from_config)
+            .collect();
+        Self::new(factory, config, standby, failover)
+    }
+
+    /// Name of the credential requests currently use
+    pub fn active_credential(&self) -> String {
+        let state = self.state.lock().unwrap();
+        self.slots[state.active].credential.name.clone()
+    }
+
+    /// Usage of each credential, in failover order
+    pub fn usage(&self) -> Vec<CredentialUsage> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .usage
+            .iter()
+            .enumerate()
+            .map(|(index, usage)| CredentialUsage {
+                active: index == state.active,
+                cooldown_remaining_secs: state.cooldown_until[index]
+                    .filter(|until| *until > now)
+                    .map(|until| (until - now).as_secs()),
+                ..usage.clone()
+            })
+            .collect()
+    }
+
+    /// Whether an error means the credential, not the request, failed
+    fn fails_over(&self, error: &ConnectorError) -> bool {
+        match error {
+            ConnectorError::Authentication(_) => true,
+            ConnectorError::RateLimit(message) => {
+                let message = message.to_lowercase();
+                self.failover.rate_limit_markers.is_empty()
+                    || self
+                        .failover
+                        .rate_limit_markers
+                        .iter()
+                        .any(|marker| message.contains(&marker.to_lowercase()))
+            }
+            _ => false,
+        }
+    }
+
+    /// Pick the credential of the next attempt: the first one out of its
+    /// cooldown, or the one whose cooldown ends first
+    fn select(&self) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let available = state
+            .cooldown_until
+            .iter()
+            .position(|until| until.is_none_or(|until| until <= now));
+        let Some(index) = available else {
+            return (0..self.slots.len())
+                .min_by_key(|index| state.cooldown_until[*index])
+                .unwrap_or(0);
+        };
+        if index < state.active {
+            let event = CredentialEvent::Restored {
+                from: self.slots[state.active].credential.name.clone(),
+                to: self.slots[index].credential.name.clone(),
+            };
+            state.active = index;
+            drop(state);
+            self.alert(event);
+        }
+        index
+    }
+
+    /// Count a request made with a credential
+    fn record(&self, index: usize, result: Result<Option<&ChatCompletionResponse>, &str>) {
+        let name = self.slots[index].credential.name.clone();
+        let mut state = self.state.lock().unwrap();
+        let usage = &mut state.usage[index];
+        usage.requests += 1;
+        counter!(
+            catalog::PROVIDER_CREDENTIAL_REQUESTS,
+            1,
+            "provider" => self.provider_name(),
+            "credential" => name.clone(),
+            "success" => result.is_ok().to_string()
+        );
+        match result {
+            Ok(Some(response)) => {
+                let Some(tokens) = &response.usage else {
+                    return;
+                };
+                usage.prompt_tokens += tokens.prompt_tokens as u64;
+                usage.completion_tokens += tokens.completion_tokens as u64;
+                counter!(
+                    catalog::PROVIDER_CREDENTIAL_TOKENS,
+                    tokens.total_tokens as u64,
+                    "provider" => self.provider_name(),
+                    "credential" => name
+                );
+            }
+            Ok(None) => {}
+            Err(error) => {
+                usage.failures += 1;
+                usage.last_error = Some(error.to_string());
+            }
+        }
+    }
+
+    /// Put a failed credential in cooldown and switch to the next one
+    fn fail_over(&self, index: usize, error: &ConnectorError) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.cooldown_until[index] = Some(now + Duration::from_secs(self.failover.cooldown_secs));
+        state.usage[index].failovers += 1;
+        if index != state.active {
+            return;
+        }
+
+        let from = self.slots[index].credential.name.clone();
+        let next = state
+            .cooldown_until
+            .iter()
+            .position(|until| until.is_none_or(|until| until <= now));
+        let event = match next {
+            Some(next) => {
+                state.active = next;
+                let to = self.slots[next].credential.name.clone();
+                counter!(
+                    catalog::PROVIDER_CREDENTIAL_FAILOVERS,
+                    1,
+                    "provider" => self.provider_name(),
+                    "from" => from.clone(),
+                    "to" => to.clone()
+                );
+                CredentialEvent::FailedOver {
+                    from,
+                    to,
+                    reason: error.to_string(),
+                }
+            }
+            None => CredentialEvent::Exhausted {
+                reason: error.to_string(),
+            },
+        };
+        drop(state);
+        self.alert(event);
+    }
+
+    /// Log a credential change and POST it to the alert webhooks
+    fn alert(&self, event: CredentialEvent) {
+        let provider = self.provider_name();
+        let payload = match &event {
+            CredentialEvent::FailedOver { from, to, reason } => {
+                warn!(
+                    "Provider {} failed over from credential {} to {}: {}",
+                    provider, from, to, reason
+                );
+                json!({ "provider": provider, "from": from, "to": to, "reason": reason })
+            }
+            CredentialEvent::Exhausted { reason } => {
+                warn!(
+                    "Every credential of provider {} failed: {}",
+                    provider, reason
+                );
+                json!({ "provider": provider, "reason": reason })
+            }
+            CredentialEvent::Restored { from, to } => {
+                info!(
+                    "Provider {} switched back from credential {} to {}",
+                    provider, from, to
+                );
+                json!({ "provider": provider, "from": from, "to": to })
+            }
+        };
+        if self.failover.webhooks.is_empty() || tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+
+        let payload = json!({ "event": event.name(), "credentials": payload }).to_string();
+        let client = self.client.clone();
+        let webhooks = self.failover.webhooks.clone();
+        let retries = self.failover.webhook_retries;
+        tokio::spawn(async move {
+            for webhook in &webhooks {
+                deliver_alert(&client, webhook, event.name(), payload.clone(), retries).await;
+            }
+        });
+    }
+
+    /// Call the provider, failing over to the next credential while the
+    /// credentials fail
+    ///
+    /// Returns the index of the credential that answered with the result.
+    async fn call<T, F, Fut>(&self, call: F) -> Result<(usize, T), ConnectorError>
+    where
+        F: Fn(Arc<dyn ModelConnector>) -> Fut,
+        Fut: Future<Output = Result<T, ConnectorError>>,
+    {
+        let mut tried = vec![false; self.slots.len()];
+        loop {
+            let index = self.select();
+            tried[index] = true;
+            match call(self.slots[index].connector.clone()).await {
+                Ok(value) => return Ok((index, value)),
+                Err(error) => {
+                    self.record(index, Err(&error.to_string()));
+                    if !self.fails_over(&error) {
+                        return Err(error);
+                    }
+                    self.fail_over(index, &error);
+                    if tried.iter().all(|tried| *tried) {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ModelConnector for FailoverConnector {
+    async fn generate(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ConnectorError> {
+        let (index, response) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate(request).await }
+            })
+            .await?;
+        self.record(index, Ok(Some(&response)));
+        Ok(response)
+    }
+
+    async fn generate_streaming(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<StreamingResponse, ConnectorError> {
+        let (index, stream) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate_streaming(request).await }
+            })
+            .await?;
+        self.record(index, Ok(None));
+        Ok(stream)
+    }
+
+    async fn generate_streaming_raw(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<Option<RawStreamingResponse>, ConnectorError> {
+        let (index, body) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate_streaming_raw(request).await }
+            })
+            .await?;
+        if body.is_some() {
+            self.record(index, Ok(None));
+        }
+        Ok(body)
+    }
+
+    fn get_config(&self) -> &ConnectorConfig {
+        &self.config
+    }
+
+    fn update_config(&mut self, config: ConnectorConfig) {
+        let primary = &mut self.slots[0].credential;
+        primary.api_key = config.api_key.clone();
+        primary.org_id = config.org_id.clone();
+        for slot in &mut self.slots {
+            slot.connector = self
+                .factory
+                .create_connector(slot.credential.apply(&config));
+        }
+        self.config = config;
+    }
+
+    fn provider_name(&self) -> &'static str {
+        self.factory.provider_name()
+    }
+
+    fn supports_model(&self, model_id: &str) -> bool {
+        self.slots[0].connector.supports_model(model_id)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+        self.call(|connector| async move { connector.list_models().await })
+            .await
+            .map(|(_, models)| models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionChoice, ChatMessage, MessageRole, TokenUsage,
+    };
+
+    /// Connector answering with the error configured for its API key
+    struct KeyConnector {
+        config: ConnectorConfig,
+    }
+
+    #[async_trait]
+    impl ModelConnector for KeyConnector {
+        async fn generate(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, ConnectorError> {
+            match self.config.api_key.as_deref() {
+                Some("revoked") => Err(ConnectorError::Authentication("invalid key".into())),
+                Some("org-limited") => Err(ConnectorError::RateLimit(
+                    "Rate limit reached in organization org-1 on tokens per min".into(),
+                )),
+                Some("busy") => Err(ConnectorError::RateLimit("slow down".into())),
+                _ => Ok(ChatCompletionResponse {
+                    id: "1".to_string(),
+                    model: request.model,
+                    created: 0,
+                    choices: vec![ChatCompletionChoice {
+                        index: 0,
+                        message: ChatMessage {
+                            role: MessageRole::Assistant,
+                            content: "ok".to_string(),
+                            name: None,
+                            function_call: None,
+                            tool_calls: None,
+                            tool_call_id: None,
+                        },
+                        finish_reason: None,
+                        content_filter: None,
+                    }],
+                    usage: Some(TokenUsage {
+                        prompt_tokens: 3,
+                        completion_tokens: 2,
+                        total_tokens: 5,
+                    }),
+                }),
+            }
+        }
+
+        async fn generate_streaming(
+            &self,
+            _request: ChatCompletionRequest,
+        ) -> Result<StreamingResponse, ConnectorError> {
+            Err(ConnectorError::UnsupportedOperation("streaming".into()))
+        }
+
+        fn get_config(&self) -> &ConnectorConfig {
+            &self.config
+        }
+
+        fn update_config(&mut self, config: ConnectorConfig) {
+            self.config = config;
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "test"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+            Ok(Vec::new())
+        }
+    }
+
+    struct KeyConnectorFactory;
+
+    impl ModelConnectorFactory for KeyConnectorFactory {
+        fn create_connector(&self, config: ConnectorConfig) -> Arc<dyn ModelConnector> {
+            Arc::new(KeyConnector { config })
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "test"
+        }
+    }
+
+    fn failover_connector(primary: &str, standby: &[&str]) -> FailoverConnector {
+        FailoverConnector::new(
+            Arc::new(KeyConnectorFactory),
+            ConnectorConfig {
+                api_key: Some(primary.to_string()),
+                ..ConnectorConfig::default()
+            },
+            standby
+                .iter()
+                .map(|key| Credential::new(*key, Some(key.to_string()), None))
+                .collect(),
+            CredentialFailoverConfig::default(),
+        )
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: Vec::new(),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_on_credential_errors() {
+        let connector = failover_connector("revoked", &["org-limited", "valid"]);
+        assert!(connector.generate(request()).await.is_ok());
+        assert_eq!(connector.active_credential(), "valid");
+
+        let usage = connector.usage();
+        assert_eq!(usage[0].name, PRIMARY_CREDENTIAL);
+        assert_eq!(
+            (usage[0].requests, usage[0].failures, usage[0].failovers),
+            (1, 1, 1)
+        );
+        assert!(usage[0].cooldown_remaining_secs.is_some());
+        assert_eq!(usage[1].failovers, 1);
+        assert!(usage[2].active);
+        assert_eq!((usage[2].requests, usage[2].prompt_tokens), (1, 3));
+
+        // Credentials in cooldown are skipped
+        assert!(connector.generate(request()).await.is_ok());
+        assert_eq!(connector.usage()[0].requests, 1);
+        assert_eq!(connector.usage()[2].requests, 2);
+    }
+
+    #[tokio::test]
+    async fn test_request_rate_limits_do_not_fail_over() {
+        let connector = failover_connector("busy", &["valid"]);
+        let result = connector.generate(request()).await;
+        assert!(matches!(result, Err(ConnectorError::RateLimit(_))));
+        assert_eq!(connector.active_credential(), PRIMARY_CREDENTIAL);
+        assert_eq!(connector.usage()[1].requests, 0);
+
+        // Every credential failing returns the last error
+        let connector = failover_connector("revoked", &["revoked"]);
+        let result = connector.generate(request()).await;
+        assert!(matches!(result, Err(ConnectorError::Authentication(_))));
+        assert!(connector.usage().iter().all(|usage| usage.failovers == 1));
+    }
+}
@@ -0,0 +1,320 @@
+//! Pooled HTTP clients for provider connectors
+//!
+//! This module builds the `reqwest` clients connectors use to call
+//! providers, applying the configured connection pool, TCP and HTTP/2
+//! keepalive settings, sending traffic through the configured HTTP or SOCKS5
+//! proxy, verifying certificates against the configured CAs and pins, and
+//! resolving provider hosts through a caching DNS resolver. It also tracks in-flight requests per provider so pool
+//! utilization can be charted alongside the new connections being opened.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hyper_014::client::connect::dns::Name;
+use metrics::{counter, gauge};
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{Client, NoProxy, Proxy};
+use thiserror::Error;
+
+use super::tls::{self, TlsError};
+use crate::config::{ConnectionPoolConfig, OutboundProxyConfig};
+use crate::modules::telemetry::catalog;
+
+/// Errors building a provider client
+#[derive(Error, Debug)]
+pub enum ClientError {
+    /// Invalid client settings
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+
+    /// Invalid TLS settings
+    #[error("invalid TLS settings: {0}")]
+    Tls(#[from] TlsError),
+}
+
+/// Build a provider client with the given request timeout and pool settings
+pub fn build_client(timeout: Duration, pool: &ConnectionPoolConfig) -> Result<Client, ClientError> {
+    let mut builder = Client::builder()
+        .timeout(timeout)
+        .connect_timeout(Duration::from_secs(pool.connect_timeout_secs))
+        .pool_max_idle_per_host(pool.max_idle_per_host)
+        .pool_idle_timeout(Duration::from_secs(pool.idle_timeout_secs))
+        .tcp_nodelay(pool.tcp_nodelay)
+        .tcp_keepalive(secs(pool.tcp_keepalive_secs))
+        .http2_keep_alive_timeout(Duration::from_secs(pool.http2_keepalive_timeout_secs))
+        .http2_keep_alive_while_idle(pool.http2_keepalive_while_idle)
+        .http2_adaptive_window(pool.http2_adaptive_window)
+        .dns_resolver(Arc::new(CachingResolver::new(Duration::from_secs(
+            pool.dns_cache_ttl_secs,
+        ))));
+    if let Some(interval) = secs(pool.http2_keepalive_interval_secs) {
+        builder = builder.http2_keep_alive_interval(interval);
+    }
+    if pool.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(proxy) = &pool.proxy {
+        let proxy =
+            build_proxy(proxy).inspect_err(|e| tracing::error!("Invalid outbound proxy: {}", e))?;
+        builder = builder.proxy(proxy);
+    } else if !pool.use_env_proxy {
+        builder = builder.no_proxy();
+    }
+    if let Some(settings) = &pool.tls {
+        let config = tls::client_config(settings, pool.http2_prior_knowledge)
+            .inspect_err(|e| tracing::error!("Invalid provider TLS settings: {}", e))?;
+        builder = builder.use_preconfigured_tls(config);
+    }
+    Ok(builder.build()?)
+}
+
+/// Describe a request error with its causes, such as the reason a
+/// provider's certificate was rejected
+pub fn describe_error(error: &reqwest::Error) -> String {
+    let mut description = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        // Some errors already include their cause in their message
+        let cause_description = cause.to_string();
+        if !description.contains(&cause_description) {
+            description.push_str(": ");
+            description.push_str(&cause_description);
+        }
+        source = cause.source();
+    }
+    description
+}
+
+/// Build the proxy all of a client's traffic is sent through
+///
+/// A configured proxy replaces the proxies of the environment variables.
+pub fn build_proxy(config: &OutboundProxyConfig) -> reqwest::Result<Proxy> {
+    let mut proxy = Proxy::all(config.url.as_str())?;
+    if let Some(username) = &config.username {
+        proxy = proxy.basic_auth(username, &config.password().unwrap_or_default());
+    }
+    Ok(proxy.no_proxy(NoProxy::from_string(&config.no_proxy.join(","))))
+}
+
+/// Duration of a number of seconds, with 0 meaning disabled
+fn secs(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Resolved addresses of each host, with the time they were resolved
+type AddressCache = HashMap<String, (Instant, Vec<SocketAddr>)>;
+
+/// DNS resolver caching the addresses of provider hosts
+///
+/// Every new connection resolves its host, so caching avoids a lookup on
+/// each connection opened under load. Lookups are counted as connection
+/// attempts, labelled by whether the cache answered them.
+#[derive(Debug)]
+pub struct CachingResolver {
+    ttl: Duration,
+    cache: Arc<Mutex<AddressCache>>,
+}
+
+impl CachingResolver {
+    /// Create a resolver caching addresses for `ttl` (zero disables caching)
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Cached addresses of a host, if still fresh
+    fn cached(&self, host: &str) -> Option<Vec<SocketAddr>> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(host)
+            .filter(|(resolved_at, _)| resolved_at.elapsed() < self.ttl)
+            .map(|(_, addrs)| addrs.clone())
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        if let Some(addrs) = self.cached(&host) {
+            counter!(catalog::PROVIDER_CONNECTIONS, 1, "host" => host, "dns_cached" => "true");
+            return Box::pin(async move { Ok(Box::new(addrs.into_iter()) as Addrs) });
+        }
+
+        let ttl = self.ttl;
+        let cache = self.cache.clone();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if !ttl.is_zero() {
+                cache
+                    .lock()
+                    .unwrap()
+                    .insert(host.clone(), (Instant::now(), addrs.clone()));
+            }
+            counter!(catalog::PROVIDER_CONNECTIONS, 1, "host" => host, "dns_cached" => "false");
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// In-flight request tracking of a provider's connection pool
+#[derive(Debug)]
+pub struct PoolMetrics {
+    provider: String,
+    /// Idle connections the pool keeps per host
+    capacity: usize,
+    in_flight: AtomicUsize,
+}
+
+impl PoolMetrics {
+    /// Create pool metrics for a provider
+    pub fn new(provider: impl Into<String>, pool: &ConnectionPoolConfig) -> Self {
+        Self {
+            provider: provider.into(),
+            capacity: pool.max_idle_per_host,
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Count a request as in flight until the returned guard is dropped
+    pub fn track(self: &Arc<Self>) -> InFlightRequest {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.report(in_flight);
+        InFlightRequest {
+            metrics: self.clone(),
+        }
+    }
+
+    /// Number of requests in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// In-flight requests as a fraction of the pooled connections
+    pub fn utilization(&self) -> f64 {
+        self.in_flight() as f64 / self.capacity.max(1) as f64
+    }
+
+    fn report(&self, in_flight: usize) {
+        gauge!(
+            catalog::PROVIDER_POOL_IN_FLIGHT, in_flight as f64,
+            "provider" => self.provider.clone()
+        );
+        gauge!(
+            catalog::PROVIDER_POOL_UTILIZATION, in_flight as f64 / self.capacity.max(1) as f64,
+            "provider" => self.provider.clone()
+        );
+    }
+}
+
+/// Guard counting a request as in flight
+///
+/// Streaming connectors keep the guard alive with the response stream, as
+/// the connection stays busy until the stream ends.
+#[derive(Debug)]
+pub struct InFlightRequest {
+    metrics: Arc<PoolMetrics>,
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        let in_flight = self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed) - 1;
+        self.metrics.report(in_flight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::OutboundTlsConfig;
+
+    #[test]
+    fn test_in_flight_tracking() {
+        let pool = ConnectionPoolConfig {
+            max_idle_per_host: 4,
+            ..ConnectionPoolConfig::default()
+        };
+        let metrics = Arc::new(PoolMetrics::new("openai", &pool));
+
+        let first = metrics.track();
+        let _second = metrics.track();
+        assert_eq!(metrics.in_flight(), 2);
+        assert_eq!(metrics.utilization(), 0.5);
+
+        drop(first);
+        assert_eq!(metrics.in_flight(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolver_caches_addresses() {
+        let resolver = CachingResolver::new(Duration::from_secs(60));
+        let addrs: Vec<_> = resolver
+            .resolve("localhost".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert!(!addrs.is_empty());
+        assert_eq!(resolver.cached("localhost"), Some(addrs));
+
+        let uncached = CachingResolver::new(Duration::ZERO);
+        let resolved: Vec<_> = uncached
+            .resolve("localhost".parse().unwrap())
+            .await
+            .unwrap()
+            .collect();
+        assert!(!resolved.is_empty());
+        assert!(resolved.iter().all(|addr| addr.ip().is_loopback()));
+        assert_eq!(uncached.cached("localhost"), None);
+    }
+
+    #[test]
+    fn test_build_client() {
+        let pool = ConnectionPoolConfig {
+            tcp_keepalive_secs: 0,
+            http2_keepalive_interval_secs: 0,
+            http2_prior_knowledge: true,
+            ..ConnectionPoolConfig::default()
+        };
+        assert!(build_client(Duration::from_secs(30), &pool).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        for url in ["http://proxy.internal:3128", "socks5h://127.0.0.1:1080"] {
+            let pool = ConnectionPoolConfig {
+                proxy: Some(OutboundProxyConfig {
+                    url: url.to_string(),
+                    username: Some("router".to_string()),
+                    password: Some("secret".to_string()),
+                    no_proxy: vec!["localhost".to_string(), "10.0.0.0/8".to_string()],
+                }),
+                ..ConnectionPoolConfig::default()
+            };
+            assert!(build_client(Duration::from_secs(30), &pool).is_ok());
+        }
+
+        let invalid = OutboundProxyConfig {
+            url: "not a url".to_string(),
+            ..OutboundProxyConfig::default()
+        };
+        assert!(build_proxy(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_build_client_with_tls() {
+        let pool = ConnectionPoolConfig {
+            tls: Some(OutboundTlsConfig {
+                ca_bundle: Some("/nonexistent/ca.pem".to_string()),
+                ..OutboundTlsConfig::default()
+            }),
+            ..ConnectionPoolConfig::default()
+        };
+        let error = build_client(Duration::from_secs(30), &pool).unwrap_err();
+        assert!(matches!(error, ClientError::Tls(TlsError::Bundle { .. })));
+    }
+}
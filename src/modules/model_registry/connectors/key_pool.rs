@@ -0,0 +1,696 @@
+//! Provider Key Pools
+//!
+//! This module spreads the traffic of a provider across many API keys, so
+//! their rate limits add up. Every key has its own connector, created by the
+//! provider's connector factory, which reports the rate limits the provider
+//! returned with its last response (the `x-ratelimit-*` headers). Each
+//! request goes to the key with the most headroom, the smaller fraction of
+//! its request and token limits left, and keys with the same headroom take
+//! turns, so keys whose provider reports no limits are rotated.
+//!
+//! A key whose headroom falls under `cooldown_threshold`, or that is rate
+//! limited, cools down until its limit resets, as reported by the provider,
+//! and for at most `max_cooldown_secs`. A key the provider rejects cools down
+//! for `auth_cooldown_secs`. Requests failing with either error are retried
+//! with another key; other errors are returned as is. When every key is
+//! cooling down, the one whose cooldown ends first is used.
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use metrics::{counter, gauge};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use super::{
+    ChatCompletionRequest, ChatCompletionResponse, ConnectorConfig, ConnectorError, Credential,
+    ModelConnector, ModelConnectorFactory, RawStreamingResponse, StreamingResponse,
+};
+use crate::config::{KeyPoolConfig, LlmProviderConfig};
+use crate::modules::telemetry::catalog;
+
+/// Rate limits of a provider key, as reported with a response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitStatus {
+    pub limit_requests: Option<u64>,
+    pub remaining_requests: Option<u64>,
+    pub limit_tokens: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    /// Time until the request limit is replenished
+    pub reset_requests: Option<Duration>,
+    /// Time until the token limit is replenished
+    pub reset_tokens: Option<Duration>,
+}
+
+impl RateLimitStatus {
+    /// Rate limits reported by the `x-ratelimit-*` headers of a response,
+    /// if it has any
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::trim)
+        };
+        let count = |name: &str| header(name).and_then(|value| value.parse().ok());
+        let reset = |name: &str| header(name).and_then(parse_reset);
+
+        let status = Self {
+            limit_requests: count("x-ratelimit-limit-requests"),
+            remaining_requests: count("x-ratelimit-remaining-requests"),
+            limit_tokens: count("x-ratelimit-limit-tokens"),
+            remaining_tokens: count("x-ratelimit-remaining-tokens"),
+            reset_requests: reset("x-ratelimit-reset-requests"),
+            reset_tokens: reset("x-ratelimit-reset-tokens"),
+        };
+        (status != Self::default()).then_some(status)
+    }
+
+    /// Fraction of the scarcer of the request and token limits left, or 1
+    /// when neither is reported
+    pub fn headroom(&self) -> f64 {
+        let fraction = |remaining: Option<u64>, limit: Option<u64>| match (remaining, limit) {
+            (Some(remaining), Some(limit)) if limit > 0 => {
+                (remaining as f64 / limit as f64).min(1.0)
+            }
+            (Some(0), _) => 0.0,
+            _ => 1.0,
+        };
+        fraction(self.remaining_requests, self.limit_requests)
+            .min(fraction(self.remaining_tokens, self.limit_tokens))
+    }
+
+    /// Time until both limits are replenished, if reported
+    pub fn reset_after(&self) -> Option<Duration> {
+        match (self.reset_requests, self.reset_tokens) {
+            (Some(requests), Some(tokens)) => Some(requests.max(tokens)),
+            (requests, tokens) => requests.or(tokens),
+        }
+    }
+}
+
+/// Parse a reset time such as `1s`, `6m0s`, `20ms` or `1h2m3.5s`; plain
+/// numbers are seconds
+fn parse_reset(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let mut total = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        total += number
+            * match &rest[..unit] {
+                "h" => 3600.0,
+                "m" => 60.0,
+                "s" => 1.0,
+                "ms" => 0.001,
+                _ => return None,
+            };
+        rest = &rest[unit..];
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// Usage of a pooled key, as counted since startup
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct KeyUsage {
+    pub name: String,
+    pub requests: u64,
+    pub failures: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Times the key cooled down
+    pub cooldowns: u64,
+    /// Fraction of its rate limit last reported left
+    pub headroom: Option<f64>,
+    /// Seconds left before the key is used again
+    pub cooldown_remaining_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+/// Key with the connector calling the provider with it
+struct Slot {
+    credential: Credential,
+    connector: Arc<dyn ModelConnector>,
+}
+
+/// Cooldowns, rate limits and usage of the keys
+struct State {
+    /// Key the next selection starts from, so tied keys take turns
+    next: usize,
+    cooldown_until: Vec<Option<Instant>>,
+    rate_limits: Vec<Option<RateLimitStatus>>,
+    usage: Vec<KeyUsage>,
+}
+
+/// Connector spreading requests across a pool of API keys
+pub struct KeyPoolConnector {
+    factory: Arc<dyn ModelConnectorFactory>,
+    config: ConnectorConfig,
+    slots: Vec<Slot>,
+    pool: KeyPoolConfig,
+    state: Mutex<State>,
+}
+
+impl KeyPoolConnector {
+    /// Create a connector using the credentials of `config` and the pooled
+    /// credentials
+    pub fn new(
+        factory: Arc<dyn ModelConnectorFactory>,
+        config: ConnectorConfig,
+        pooled: Vec<Credential>,
+        pool: KeyPoolConfig,
+    ) -> Self {
+        let primary = Credential::new(
+            super::credentials::PRIMARY_CREDENTIAL,
+            config.api_key.clone(),
+            config.org_id.clone(),
+        );
+        let credentials: Vec<Credential> = std::iter::once(primary).chain(pooled).collect();
+        let state = State {
+            next: 0,
+            cooldown_until: vec![None; credentials.len()],
+            rate_limits: vec![None; credentials.len()],
+            usage: credentials
+                .iter()
+                .map(|credential| KeyUsage {
+                    name: credential.name.clone(),
+                    ..KeyUsage::default()
+                })
+                .collect(),
+        };
+        let slots = credentials
+            .into_iter()
+            .map(|credential| Slot {
+                connector: factory.create_connector(ConnectorConfig {
+                    api_key: credential.api_key.clone(),
+                    org_id: credential.org_id.clone(),
+                    ..config.clone()
+                }),
+                credential,
+            })
+            .collect();
+        Self {
+            factory,
+            config,
+            slots,
+            pool,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Create a connector for a configured provider
+    ///
+    /// The primary API key is read from `api_key_env` and the pooled ones
+    /// from the `api_key_env` of each pooled credential.
+    pub fn from_provider_config(
+        factory: Arc<dyn ModelConnectorFactory>,
+        provider: &LlmProviderConfig,
+        pool: KeyPoolConfig,
+    ) -> Self {
+        let config = ConnectorConfig {
+            base_url: provider.endpoint.clone(),
+            api_key: std::env::var(&provider.api_key_env).ok(),
+            org_id: provider.settings.get("org_id").cloned(),
+            timeout_secs: provider.timeout_secs,
+            max_retries: provider.max_retries,
+            additional_config: provider.settings.clone(),
+        };
+        let pooled = provider
+            .pooled_credentials
+            .iter()
+            .map(Credential::from_config)
+            .collect();
+        Self::new(factory, config, pooled, pool)
+    }
+
+    /// Usage of each key, in configuration order
+    pub fn usage(&self) -> Vec<KeyUsage> {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .usage
+            .iter()
+            .enumerate()
+            .map(|(index, usage)| KeyUsage {
+                cooldown_remaining_secs: state.cooldown_until[index]
+                    .filter(|until| *until > now)
+                    .map(|until| (until - now).as_secs()),
+                ..usage.clone()
+            })
+            .collect()
+    }
+
+    /// Pick the key of the next attempt: the one with the most headroom out
+    /// of its cooldown and not yet tried, or the one whose cooldown ends
+    /// first
+    fn select(&self, tried: &[bool]) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let count = self.slots.len();
+        let mut best: Option<(usize, f64)> = None;
+        for index in (0..count).map(|offset| (state.next + offset) % count) {
+            let cooling = state.cooldown_until[index].is_some_and(|until| until > now);
+            if tried[index] || cooling {
+                continue;
+            }
+            let headroom = state.rate_limits[index]
+                .as_ref()
+                .map_or(1.0, RateLimitStatus::headroom);
+            if best.is_none_or(|(_, most)| headroom > most) {
+                best = Some((index, headroom));
+            }
+        }
+        let index = match best {
+            Some((index, _)) => index,
+            None => (0..count)
+                .filter(|index| !tried[*index])
+                .min_by_key(|index| state.cooldown_until[*index])
+                .unwrap_or(0),
+        };
+        state.next = (index + 1) % count;
+        index
+    }
+
+    /// Count a request made with a key and take in the rate limits its
+    /// provider reported, cooling the key down when they near exhaustion
+    fn record(&self, index: usize, result: Result<Option<&ChatCompletionResponse>, &str>) {
+        let name = self.slots[index].credential.name.clone();
+        let rate_limit = self.slots[index].connector.rate_limit();
+        let mut state = self.state.lock().unwrap();
+        let usage = &mut state.usage[index];
+        usage.requests += 1;
+        counter!(
+            catalog::PROVIDER_CREDENTIAL_REQUESTS,
+            1,
+            "provider" => self.provider_name(),
+            "credential" => name.clone(),
+            "success" => result.is_ok().to_string()
+        );
+        match result {
+            Ok(Some(response)) => {
+                if let Some(tokens) = &response.usage {
+                    usage.prompt_tokens += tokens.prompt_tokens as u64;
+                    usage.completion_tokens += tokens.completion_tokens as u64;
+                    counter!(
+                        catalog::PROVIDER_CREDENTIAL_TOKENS,
+                        tokens.total_tokens as u64,
+                        "provider" => self.provider_name(),
+                        "credential" => name.clone()
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(error) => {
+                usage.failures += 1;
+                usage.last_error = Some(error.to_string());
+            }
+        }
+
+        let Some(rate_limit) = rate_limit else {
+            return;
+        };
+        let headroom = rate_limit.headroom();
+        state.usage[index].headroom = Some(headroom);
+        gauge!(
+            catalog::PROVIDER_CREDENTIAL_HEADROOM,
+            headroom,
+            "provider" => self.provider_name(),
+            "credential" => name
+        );
+        let exhausted = headroom < self.pool.cooldown_threshold;
+        let reset_after = rate_limit.reset_after();
+        state.rate_limits[index] = Some(rate_limit);
+        drop(state);
+        if exhausted {
+            self.cool_down(index, self.exhaustion_cooldown(reset_after), "exhausted");
+        }
+    }
+
+    /// Cooldown of a key until its limit resets
+    fn exhaustion_cooldown(&self, reset_after: Option<Duration>) -> Duration {
+        reset_after
+            .unwrap_or(Duration::from_secs(self.pool.cooldown_secs))
+            .min(Duration::from_secs(self.pool.max_cooldown_secs))
+    }
+
+    /// Skip a key for `duration`, extending a shorter cooldown
+    fn cool_down(&self, index: usize, duration: Duration, reason: &'static str) {
+        let name = &self.slots[index].credential.name;
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let until = now + duration;
+        let current = state.cooldown_until[index];
+        if current.is_some_and(|current| current >= until) {
+            return;
+        }
+        state.cooldown_until[index] = Some(until);
+        if current.is_some_and(|current| current > now) {
+            return;
+        }
+        state.usage[index].cooldowns += 1;
+        drop(state);
+        counter!(
+            catalog::PROVIDER_CREDENTIAL_COOLDOWNS,
+            1,
+            "provider" => self.provider_name(),
+            "credential" => name.clone(),
+            "reason" => reason
+        );
+        debug!(
+            "Key {} of provider {} cools down for {:?} ({})",
+            name,
+            self.provider_name(),
+            duration,
+            reason
+        );
+    }
+
+    /// Call the provider with the key of most headroom, retrying with
+    /// another key while keys are rate limited or rejected
+    ///
+    /// Returns the index of the key that answered with the result.
+    async fn call<T, F, Fut>(&self, call: F) -> Result<(usize, T), ConnectorError>
+    where
+        F: Fn(Arc<dyn ModelConnector>) -> Fut,
+        Fut: Future<Output = Result<T, ConnectorError>>,
+    {
+        let mut tried = vec![false; self.slots.len()];
+        loop {
+            let index = self.select(&tried);
+            tried[index] = true;
+            let error = match call(self.slots[index].connector.clone()).await {
+                Ok(value) => return Ok((index, value)),
+                Err(error) => error,
+            };
+            self.record(index, Err(&error.to_string()));
+            match &error {
+                ConnectorError::RateLimit(_) => {
+                    let reset_after = self.slots[index]
+                        .connector
+                        .rate_limit()
+                        .and_then(|rate_limit| rate_limit.reset_after());
+                    self.cool_down(index, self.exhaustion_cooldown(reset_after), "rate_limited");
+                }
+                ConnectorError::Authentication(_) => {
+                    warn!(
+                        "Provider {} rejected key {}: {}",
+                        self.provider_name(),
+                        self.slots[index].credential.name,
+                        error
+                    );
+                    self.cool_down(
+                        index,
+                        Duration::from_secs(self.pool.auth_cooldown_secs),
+                        "rejected",
+                    );
+                }
+                _ => return Err(error),
+            }
+            if tried.iter().all(|tried| *tried) {
+                return Err(error);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl ModelConnector for KeyPoolConnector {
+    async fn generate(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ConnectorError> {
+        let (index, response) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate(request).await }
+            })
+            .await?;
+        self.record(index, Ok(Some(&response)));
+        Ok(response)
+    }
+
+    async fn generate_streaming(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<StreamingResponse, ConnectorError> {
+        let (index, stream) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate_streaming(request).await }
+            })
+            .await?;
+        self.record(index, Ok(None));
+        Ok(stream)
+    }
+
+    async fn generate_streaming_raw(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<Option<RawStreamingResponse>, ConnectorError> {
+        let (index, body) = self
+            .call(|connector| {
+                let request = request.clone();
+                async move { connector.generate_streaming_raw(request).await }
+            })
+            .await?;
+        if body.is_some() {
+            self.record(index, Ok(None));
+        }
+        Ok(body)
+    }
+
+    fn get_config(&self) -> &ConnectorConfig {
+        &self.config
+    }
+
+    fn update_config(&mut self, config: ConnectorConfig) {
+        let primary = &mut self.slots[0].credential;
+        primary.api_key = config.api_key.clone();
+        primary.org_id = config.org_id.clone();
+        for slot in &mut self.slots {
+            slot.connector = self.factory.create_connector(ConnectorConfig {
+                api_key: slot.credential.api_key.clone(),
+                org_id: slot.credential.org_id.clone(),
+                ..config.clone()
+            });
+        }
+        self.config = config;
+    }
+
+    fn provider_name(&self) -> &'static str {
+        self.factory.provider_name()
+    }
+
+    fn supports_model(&self, model_id: &str) -> bool {
+        self.slots[0].connector.supports_model(model_id)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+        self.call(|connector| async move { connector.list_models().await })
+            .await
+            .map(|(_, models)| models)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionChoice, ChatMessage, MessageRole,
+    };
+
+    /// Connector reporting the rate limits configured for its API key
+    struct LimitedConnector {
+        config: ConnectorConfig,
+    }
+
+    impl LimitedConnector {
+        fn remaining(&self) -> u64 {
+            self.config
+                .api_key
+                .as_deref()
+                .and_then(|key| key.strip_prefix("remaining-"))
+                .and_then(|remaining| remaining.parse().ok())
+                .unwrap_or(100)
+        }
+    }
+
+    #[async_trait]
+    impl ModelConnector for LimitedConnector {
+        async fn generate(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, ConnectorError> {
+            match self.config.api_key.as_deref() {
+                Some("revoked") => Err(ConnectorError::Authentication("invalid key".into())),
+                Some("remaining-0") => Err(ConnectorError::RateLimit("slow down".into())),
+                _ => Ok(ChatCompletionResponse {
+                    id: "1".to_string(),
+                    model: request.model,
+                    created: 0,
+                    choices: vec![ChatCompletionChoice {
+                        index: 0,
+                        message: ChatMessage {
+                            role: MessageRole::Assistant,
+                            content: self.config.api_key.clone().unwrap_or_default(),
+                            name: None,
+                            function_call: None,
+                            tool_calls: None,
+                            tool_call_id: None,
+                        },
+                        finish_reason: None,
+                        content_filter: None,
+                    }],
+                    usage: None,
+                }),
+            }
+        }
+
+        async fn generate_streaming(
+            &self,
+            _request: ChatCompletionRequest,
+        ) -> Result<StreamingResponse, ConnectorError> {
+            Err(ConnectorError::UnsupportedOperation("streaming".into()))
+        }
+
+        fn get_config(&self) -> &ConnectorConfig {
+            &self.config
+        }
+
+        fn update_config(&mut self, config: ConnectorConfig) {
+            self.config = config;
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "test"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+            Ok(Vec::new())
+        }
+
+        fn rate_limit(&self) -> Option<RateLimitStatus> {
+            Some(RateLimitStatus {
+                limit_requests: Some(100),
+                remaining_requests: Some(self.remaining()),
+                reset_requests: Some(Duration::from_secs(30)),
+                ..RateLimitStatus::default()
+            })
+        }
+    }
+
+    struct LimitedConnectorFactory;
+
+    impl ModelConnectorFactory for LimitedConnectorFactory {
+        fn create_connector(&self, config: ConnectorConfig) -> Arc<dyn ModelConnector> {
+            Arc::new(LimitedConnector { config })
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "test"
+        }
+    }
+
+    fn pool_connector(primary: &str, pooled: &[&str]) -> KeyPoolConnector {
+        KeyPoolConnector::new(
+            Arc::new(LimitedConnectorFactory),
+            ConnectorConfig {
+                api_key: Some(primary.to_string()),
+                ..ConnectorConfig::default()
+            },
+            pooled
+                .iter()
+                .map(|key| Credential::new(*key, Some(key.to_string()), None))
+                .collect(),
+            KeyPoolConfig::default(),
+        )
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: Vec::new(),
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    async fn key_used(connector: &KeyPoolConnector) -> String {
+        let response = connector.generate(request()).await.unwrap();
+        response.choices[0].message.content.clone()
+    }
+
+    #[test]
+    fn test_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit-requests", "100".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-requests", "25".parse().unwrap());
+        headers.insert("x-ratelimit-limit-tokens", "1000".parse().unwrap());
+        headers.insert("x-ratelimit-remaining-tokens", "500".parse().unwrap());
+        headers.insert("x-ratelimit-reset-requests", "1m30s".parse().unwrap());
+        headers.insert("x-ratelimit-reset-tokens", "250ms".parse().unwrap());
+
+        let status = RateLimitStatus::from_headers(&headers).unwrap();
+        assert_eq!(status.headroom(), 0.25);
+        assert_eq!(status.reset_after(), Some(Duration::from_secs(90)));
+        assert_eq!(RateLimitStatus::from_headers(&HeaderMap::new()), None);
+
+        assert_eq!(parse_reset("6m0s"), Some(Duration::from_secs(360)));
+        assert_eq!(parse_reset("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_reset("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_reset("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn test_prefers_keys_with_headroom() {
+        let connector = pool_connector("remaining-10", &["remaining-80", "remaining-50"]);
+        // Keys are tried in turn until their limits are known
+        for _ in 0..3 {
+            key_used(&connector).await;
+        }
+        assert_eq!(key_used(&connector).await, "remaining-80");
+        assert_eq!(key_used(&connector).await, "remaining-80");
+
+        let usage = connector.usage();
+        assert_eq!(usage[1].requests, 3);
+        assert_eq!(usage[0].headroom, Some(0.1));
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_keys_cool_down() {
+        let connector = pool_connector("remaining-2", &["remaining-0", "revoked", "remaining-60"]);
+        assert_eq!(key_used(&connector).await, "remaining-2");
+        for _ in 0..3 {
+            assert_eq!(key_used(&connector).await, "remaining-60");
+        }
+
+        let usage = connector.usage();
+        // Under the threshold after its first request, rate limited and rejected
+        assert_eq!((usage[0].requests, usage[0].cooldowns), (1, 1));
+        assert_eq!((usage[1].failures, usage[1].cooldowns), (1, 1));
+        assert_eq!((usage[2].failures, usage[2].cooldowns), (1, 1));
+        assert!(usage[2].cooldown_remaining_secs.unwrap() > 30);
+        assert_eq!(usage[3].requests, 3);
+    }
+}
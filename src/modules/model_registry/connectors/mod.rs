@@ -5,6 +5,7 @@
 //! such as OpenAI, Ollama, and others.
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::Stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -97,6 +98,9 @@ pub struct ChatMessage {
     pub function_call: Option<FunctionCall>,
     /// Tool calls information (optional)
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// ID of the tool call a tool message responds to (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
 }
 
 /// Function call information
@@ -186,6 +190,9 @@ pub struct ChatCompletionChoice {
     pub message: ChatMessage,
     /// Reason for finishing
     pub finish_reason: Option<String>,
+    /// What the provider reported, when it filtered the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_filter: Option<ContentFilterDetail>,
 }
 
 /// Token usage statistics
@@ -292,6 +299,9 @@ impl Default for ConnectorConfig {
 pub type StreamingResponse =
     Pin<Box<dyn Stream<Item = Result<ChatCompletionChunk, ConnectorError>> + Send>>;
 
+/// Type alias for an unparsed streaming response in the OpenAI SSE format
+pub type RawStreamingResponse = Pin<Box<dyn Stream<Item = Result<Bytes, ConnectorError>> + Send>>;
+
 /// Interface for model connectors
 #[async_trait]
 pub trait ModelConnector: Send + Sync {
@@ -307,6 +317,19 @@ pub trait ModelConnector: Send + Sync {
         request: ChatCompletionRequest,
     ) -> Result<StreamingResponse, ConnectorError>;
 
+    /// Generate a streaming completion as the provider's raw SSE body
+    ///
+    /// Connectors whose provider streams in the OpenAI format return the
+    /// response bytes unparsed, so the proxy can forward them without
+    /// re-serializing every chunk. Returns `None` when the provider's format
+    /// differs, in which case callers fall back to `generate_streaming`.
+    async fn generate_streaming_raw(
+        &self,
+        _request: ChatCompletionRequest,
+    ) -> Result<Option<RawStreamingResponse>, ConnectorError> {
+        Ok(None)
+    }
+
     /// Get the configuration for this connector
     fn get_config(&self) -> &ConnectorConfig;
 
@@ -321,6 +344,12 @@ pub trait ModelConnector: Send + Sync {
 
     /// List available models for this connector
     async fn list_models(&self) -> Result<Vec<String>, ConnectorError>;
+
+    /// Rate limits the provider reported with its last response, for
+    /// connectors that track them
+    fn rate_limit(&self) -> Option<RateLimitStatus> {
+        None
+    }
 }
 
 /// Factory for creating model connectors
@@ -366,6 +395,21 @@ pub fn connector_error_to_registry_error(
     }
 }
 
+// Provider content filter finishes
+pub mod content_filter;
+pub use content_filter::{ContentFilterDetail, FilterStage};
+
+// Standby provider credentials
+pub mod credentials;
+pub use credentials::{Credential, CredentialUsage, FailoverConnector};
+
+// Pooled provider HTTP clients
+pub mod http_client;
+
+// Provider key pools
+pub mod key_pool;
+pub use key_pool::{KeyPoolConnector, KeyUsage, RateLimitStatus};
+
 // Ollama connector
 pub mod ollama;
 pub use ollama::{OllamaConnector, OllamaConnectorFactory};
@@ -374,6 +418,9 @@ pub use ollama::{OllamaConnector, OllamaConnectorFactory};
 pub mod openai;
 pub use openai::{OpenAIConnector, OpenAIConnectorFactory};
 
+// TLS verification of provider endpoints
+pub mod tls;
+
 #[cfg(all(test, not(feature = "production")))]
 mod tests;
 
@@ -3,13 +3,15 @@
 //! This module provides a connector for the Ollama API, which allows
 //! interaction with locally hosted LLM models through the Ollama server.
 
+use super::http_client::{self, PoolMetrics};
 use super::{
     ChatCompletionChoice, ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionDelta,
     ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ConnectorConfig, ConnectorError,
     MessageRole, ModelConnector, ModelConnectorFactory, StreamingResponse, TokenUsage,
 };
+use crate::config::ConnectionPoolConfig;
 use async_trait::async_trait;
-use futures::stream;
+use futures::{stream, StreamExt};
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -20,6 +22,8 @@ use uuid::Uuid;
 pub struct OllamaConnector {
     /// HTTP client
     client: Client,
+    /// In-flight requests of the client's connection pool
+    pool: Arc<PoolMetrics>,
     /// Configuration
     config: ConnectorConfig,
 }
@@ -59,6 +63,21 @@ struct OllamaOptions {
     /// Maximum number of tokens to generate
     #[serde(skip_serializing_if = "Option::is_none")]
     num_predict: Option<u32>,
+    /// Top-k sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_k: Option<u32>,
+    /// Seed for deterministic sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<i64>,
+    /// Stop sequences
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<Vec<String>>,
+    /// Frequency penalty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    /// Presence penalty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
 }
 
 /// Ollama chat response format
@@ -98,12 +117,20 @@ struct OllamaModel {
 impl OllamaConnector {
     /// Create a new Ollama connector
     pub fn new(config: ConnectorConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
+        Self::with_pool(config, &ConnectionPoolConfig::default())
+    }
+
+    /// Create a new Ollama connector with the given connection pool settings
+    pub fn with_pool(config: ConnectorConfig, pool: &ConnectionPoolConfig) -> Self {
+        let client = http_client::build_client(Duration::from_secs(config.timeout_secs), pool)
             .unwrap_or_default();
+        let pool = Arc::new(PoolMetrics::new("ollama", pool));
 
-        Self { client, config }
+        Self {
+            client,
+            pool,
+            config,
+        }
     }
 
     /// Convert our chat completion request to Ollama format
@@ -126,11 +153,25 @@ impl OllamaConnector {
             })
             .collect();
 
-        // Create options
+        // Create options, picking supported parameters out of the passthrough map
+        let param = |name: &str| {
+            request
+                .additional_params
+                .as_ref()
+                .and_then(|params| params.get(name))
+                .cloned()
+        };
         let options = Some(OllamaOptions {
             temperature: request.temperature,
             top_p: request.top_p,
             num_predict: request.max_tokens,
+            top_k: param("top_k").and_then(|v| serde_json::from_value(v).ok()),
+            seed: param("seed").and_then(|v| serde_json::from_value(v).ok()),
+            stop: param("stop").and_then(|v| serde_json::from_value(v).ok()),
+            frequency_penalty: param("frequency_penalty")
+                .and_then(|v| serde_json::from_value(v).ok()),
+            presence_penalty: param("presence_penalty")
+                .and_then(|v| serde_json::from_value(v).ok()),
         });
 
         OllamaChatRequest {
@@ -154,6 +195,7 @@ impl OllamaConnector {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         };
 
         // Create a choice
@@ -161,6 +203,7 @@ impl OllamaConnector {
             index: 0,
             message,
             finish_reason: Some("stop".to_string()),
+            content_filter: None,
         };
 
         // Create the response
@@ -285,6 +328,8 @@ impl ModelConnector for OllamaConnector {
         let mut ollama_request = self.convert_request(&request);
         ollama_request.stream = false;
 
+        let _in_flight = self.pool.track();
+
         // Send the request to Ollama with retry logic for transient errors
         let mut attempts = 0;
         let max_attempts = self.config.max_retries as usize + 1; // +1 for the initial attempt
@@ -310,7 +355,7 @@ impl ModelConnector for OllamaConnector {
                         // If _last_error is Some, convert it to ConnectorError::Network, else generic
                         let err_msg = _last_error.map_or_else(
                             || "Unknown error after all attempts".to_string(),
-                            |err| err.to_string()
+                            |err| http_client::describe_error(&err),
                         );
                         return Err(ConnectorError::Network(format!(
                             "Failed to send request after {} attempts: {}",
@@ -352,6 +397,8 @@ impl ModelConnector for OllamaConnector {
         let mut ollama_request = self.convert_request(&request);
         ollama_request.stream = true;
 
+        let in_flight = self.pool.track();
+
         // Send the request to Ollama with retry logic for transient errors
         let mut attempts = 0;
         let max_attempts = self.config.max_retries as usize + 1; // +1 for the initial attempt
@@ -377,7 +424,7 @@ impl ModelConnector for OllamaConnector {
                         // If _last_error is Some, convert it to ConnectorError::Network, else generic
                         let err_msg = _last_error.map_or_else(
                             || "Unknown error after all attempts".to_string(),
-                            |err| err.to_string()
+                            |err| http_client::describe_error(&err),
                         );
                         return Err(ConnectorError::Network(format!(
                             "Failed to send streaming request after {} attempts: {}",
@@ -460,8 +507,14 @@ impl ModelConnector for OllamaConnector {
             },
         ));
 
+        // Keep the request counted as in flight until the stream is dropped
+        let stream = stream.map(move |chunk| {
+            let _in_flight = &in_flight;
+            chunk
+        });
+
         // Return the stream
-        Ok(stream as StreamingResponse)
+        Ok(Box::pin(stream) as StreamingResponse)
     }
 
     fn get_config(&self) -> &ConnectorConfig {
@@ -489,7 +542,12 @@ impl ModelConnector for OllamaConnector {
             .get(self.build_url("tags"))
             .send()
             .await
-            .map_err(|e| ConnectorError::Network(format!("Failed to list models: {}", e)))?;
+            .map_err(|e| {
+                ConnectorError::Network(format!(
+                    "Failed to list models: {}",
+                    http_client::describe_error(&e)
+                ))
+            })?;
 
         // Check the response status
         let status = response.status();
@@ -519,6 +577,7 @@ impl Clone for OllamaConnector {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            pool: self.pool.clone(),
             config: self.config.clone(),
         }
     }
@@ -565,6 +624,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::User,
@@ -572,6 +632,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
             ],
             temperature: Some(0.7),
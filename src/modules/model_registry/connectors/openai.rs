@@ -3,25 +3,36 @@
 //! This module provides a connector for the OpenAI API, which allows
 //! interaction with OpenAI's hosted LLM models.
 
+use super::content_filter::{normalize_finish_reason, ContentFilterDetail, CONTENT_FILTER};
+use super::http_client::{self, PoolMetrics};
 use super::{
     ChatCompletionChoice, ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionDelta,
     ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ConnectorConfig, ConnectorError,
     FunctionCall, FunctionCallDelta, MessageRole, ModelConnector, ModelConnectorFactory,
-    StreamingResponse, TokenUsage, ToolCall, ToolCallDelta,
+    RateLimitStatus, RawStreamingResponse, StreamingResponse, TokenUsage, ToolCall, ToolCallDelta,
 };
+use crate::config::ConnectionPoolConfig;
+use crate::modules::common::RequestCompression;
 use async_trait::async_trait;
-use futures::stream;
+use futures::{stream, StreamExt};
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 /// OpenAI connector for interacting with OpenAI API
 pub struct OpenAIConnector {
     /// HTTP client
     client: Client,
+    /// In-flight requests of the client's connection pool
+    pool: Arc<PoolMetrics>,
     /// Configuration
     config: ConnectorConfig,
+    /// Compression of request bodies, for connectors calling other roles
+    request_compression: Option<RequestCompression>,
+    /// Rate limits reported with the last response
+    rate_limit: Arc<Mutex<Option<RateLimitStatus>>>,
 }
 
 /// OpenAI chat request format
@@ -49,6 +60,9 @@ struct OpenAIChatRequest {
     /// Tools that can be used by the model
     #[serde(skip_serializing_if = "Option::is_none")]
     tools: Option<Vec<OpenAIToolDefinition>>,
+    /// Additional parameters passed through verbatim (stop, seed, logit_bias, ...)
+    #[serde(flatten)]
+    extra_params: HashMap<String, serde_json::Value>,
 }
 
 /// OpenAI message format
@@ -68,6 +82,9 @@ struct OpenAIMessage {
     /// Tool calls information (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_calls: Option<Vec<OpenAIToolCall>>,
+    /// ID of the tool call a tool message responds to (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
 }
 
 /// OpenAI function call information
@@ -137,6 +154,9 @@ struct OpenAIChoice {
     message: OpenAIMessage,
     /// Reason for finishing
     finish_reason: Option<String>,
+    /// Results of the Azure OpenAI content filters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_filter_results: Option<serde_json::Value>,
 }
 
 /// OpenAI token usage statistics
@@ -266,12 +286,41 @@ struct OpenAIModel {
 impl OpenAIConnector {
     /// Create a new OpenAI connector
     pub fn new(config: ConnectorConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
+        Self::with_pool(config, &ConnectionPoolConfig::default())
+    }
+
+    /// Create a new OpenAI connector with the given connection pool settings
+    pub fn with_pool(config: ConnectorConfig, pool: &ConnectionPoolConfig) -> Self {
+        let client = http_client::build_client(Duration::from_secs(config.timeout_secs), pool)
             .unwrap_or_default();
+        let pool = Arc::new(PoolMetrics::new("openai", pool));
 
-        Self { client, config }
+        Self {
+            client,
+            pool,
+            config,
+            request_compression: None,
+            rate_limit: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Compress request bodies, for a connector calling the router of the
+    /// same deployment
+    pub fn with_request_compression(mut self, compression: Option<RequestCompression>) -> Self {
+        self.request_compression = compression;
+        self
+    }
+
+    /// Set a request's JSON body, compressed if configured
+    fn json_body<T: Serialize>(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: &T,
+    ) -> reqwest::RequestBuilder {
+        match &self.request_compression {
+            Some(compression) => compression.json(request, body),
+            None => request.json(body),
+        }
     }
 
     /// Convert our chat completion request to OpenAI format
@@ -305,6 +354,7 @@ impl OpenAIConnector {
                     name: msg.name.clone(),
                     function_call,
                     tool_calls,
+                    tool_call_id: msg.tool_call_id.clone(),
                 }
             })
             .collect();
@@ -343,6 +393,7 @@ impl OpenAIConnector {
             max_tokens: request.max_tokens,
             functions,
             tools,
+            extra_params: request.additional_params.clone().unwrap_or_default(),
         }
     }
 
@@ -380,6 +431,13 @@ impl OpenAIConnector {
                     _ => MessageRole::User, // Default to user for unknown roles
                 };
 
+                let content_filter = choice.finish_reason.as_deref().and_then(|reason| {
+                    ContentFilterDetail::from_finish_reason(
+                        reason,
+                        choice.content_filter_results.as_ref(),
+                    )
+                });
+
                 ChatCompletionChoice {
                     index: choice.index,
                     message: ChatMessage {
@@ -388,8 +446,10 @@ impl OpenAIConnector {
                         name: choice.message.name,
                         function_call,
                         tool_calls,
+                        tool_call_id: choice.message.tool_call_id,
                     },
-                    finish_reason: choice.finish_reason,
+                    finish_reason: choice.finish_reason.map(normalize_finish_reason),
+                    content_filter,
                 }
             })
             .collect();
@@ -453,7 +513,7 @@ impl OpenAIConnector {
                         function_call,
                         tool_calls,
                     },
-                    finish_reason: choice.finish_reason,
+                    finish_reason: choice.finish_reason.map(normalize_finish_reason),
                 }
             })
             .collect();
@@ -481,49 +541,33 @@ impl OpenAIConnector {
         status: StatusCode,
         response: reqwest::Response,
     ) -> ConnectorError {
-        let error_text = match response.text().await {
-            Ok(text) => {
-                // Try to parse as OpenAI error format
-                match serde_json::from_str::<OpenAIErrorResponse>(&text) {
-                    Ok(error_response) => error_response.error.message,
-                    Err(_) => text,
-                }
-            }
-            Err(_) => "Unknown error".to_string(),
-        };
+        match response.text().await {
+            Ok(text) => error_from_text(status, text),
+            Err(_) => error_from_text(status, "Unknown error".to_string()),
+        }
+    }
 
-        match status {
-            StatusCode::UNAUTHORIZED => {
-                ConnectorError::Authentication(format!("Unauthorized: {}", error_text))
-            }
-            StatusCode::TOO_MANY_REQUESTS => {
-                ConnectorError::RateLimit(format!("Rate limited: {}", error_text))
-            }
-            StatusCode::NOT_FOUND => {
-                ConnectorError::ModelNotFound(format!("Model not found: {}", error_text))
-            }
-            StatusCode::BAD_REQUEST => {
-                ConnectorError::InvalidRequest(format!("Bad request: {}", error_text))
-            }
-            _ => ConnectorError::Server(format!("Server error ({}): {}", status, error_text)),
+    /// Keep the rate limits reported with a response
+    fn observe_rate_limit(&self, response: &reqwest::Response) {
+        if let Some(status) = RateLimitStatus::from_headers(response.headers()) {
+            *self.rate_limit.lock().unwrap() = Some(status);
         }
     }
-}
 
-#[async_trait]
-impl ModelConnector for OpenAIConnector {
-    async fn generate(
+    /// Send a streaming chat request, returning the response once its status is checked
+    async fn send_streaming_request(
         &self,
-        request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse, ConnectorError> {
+        request: &ChatCompletionRequest,
+    ) -> Result<reqwest::Response, ConnectorError> {
         // Convert the request to OpenAI format
-        let openai_request = self.convert_request(&request);
+        let mut openai_request = self.convert_request(request);
+        openai_request.stream = Some(true);
 
         // Build the request
-        let mut req_builder = self
-            .client
-            .post(self.build_url("v1/chat/completions"))
-            .json(&openai_request);
+        let mut req_builder = self.json_body(
+            self.client.post(self.build_url("v1/chat/completions")),
+            &openai_request,
+        );
 
         // Add API key if available
         if let Some(api_key) = &self.config.api_key {
@@ -536,40 +580,86 @@ impl ModelConnector for OpenAIConnector {
         }
 
         // Send the request to OpenAI
-        let response = req_builder
-            .send()
-            .await
-            .map_err(|e| ConnectorError::Network(format!("Failed to send request: {}", e)))?;
+        let response = req_builder.send().await.map_err(|e| {
+            ConnectorError::Network(format!(
+                "Failed to send request: {}",
+                http_client::describe_error(&e)
+            ))
+        })?;
 
         // Check the response status
+        self.observe_rate_limit(&response);
         let status = response.status();
         if !status.is_success() {
             return Err(self.parse_error_response(status, response).await);
         }
 
-        // Parse the response
-        let openai_response = response
-            .json::<OpenAIChatResponse>()
-            .await
-            .map_err(|e| ConnectorError::Parsing(format!("Failed to parse response: {}", e)))?;
+        Ok(response)
+    }
+}
 
-        // Convert the response to our format
-        Ok(self.convert_response(openai_response))
+/// Map an error response of the OpenAI API to a connector error
+fn error_from_text(status: StatusCode, text: String) -> ConnectorError {
+    // Try to parse as OpenAI error format
+    let error_text = match serde_json::from_str::<OpenAIErrorResponse>(&text) {
+        Ok(error_response) => error_response.error.message,
+        Err(_) => text,
+    };
+
+    match status {
+        StatusCode::UNAUTHORIZED => {
+            ConnectorError::Authentication(format!("Unauthorized: {}", error_text))
+        }
+        StatusCode::TOO_MANY_REQUESTS => {
+            ConnectorError::RateLimit(format!("Rate limited: {}", error_text))
+        }
+        StatusCode::NOT_FOUND => {
+            ConnectorError::ModelNotFound(format!("Model not found: {}", error_text))
+        }
+        StatusCode::BAD_REQUEST => {
+            ConnectorError::InvalidRequest(format!("Bad request: {}", error_text))
+        }
+        _ => ConnectorError::Server(format!("Server error ({}): {}", status, error_text)),
     }
+}
 
-    async fn generate_streaming(
+/// Response to a request whose prompt the provider's content filter rejected
+fn filtered_response(model: &str, detail: ContentFilterDetail) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+        model: model.to_string(),
+        created: chrono::Utc::now().timestamp() as u64,
+        choices: vec![ChatCompletionChoice {
+            index: 0,
+            message: ChatMessage {
+                role: MessageRole::Assistant,
+                content: String::new(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            },
+            finish_reason: Some(CONTENT_FILTER.to_string()),
+            content_filter: Some(detail),
+        }],
+        usage: None,
+    }
+}
+
+#[async_trait]
+impl ModelConnector for OpenAIConnector {
+    async fn generate(
         &self,
         request: ChatCompletionRequest,
-    ) -> Result<StreamingResponse, ConnectorError> {
+    ) -> Result<ChatCompletionResponse, ConnectorError> {
         // Convert the request to OpenAI format
-        let mut openai_request = self.convert_request(&request);
-        openai_request.stream = Some(true);
+        let openai_request = self.convert_request(&request);
 
         // Build the request
-        let mut req_builder = self
-            .client
-            .post(self.build_url("v1/chat/completions"))
-            .json(&openai_request);
+        let mut req_builder = self.json_body(
+            self.client.post(self.build_url("v1/chat/completions")),
+            &openai_request,
+        );
 
         // Add API key if available
         if let Some(api_key) = &self.config.api_key {
@@ -581,18 +671,48 @@ impl ModelConnector for OpenAIConnector {
             req_builder = req_builder.header("OpenAI-Organization", org_id);
         }
 
-        // Send the request to OpenAI
-        let response = req_builder
-            .send()
-            .await
-            .map_err(|e| ConnectorError::Network(format!("Failed to send request: {}", e)))?;
+        let _in_flight = self.pool.track();
 
-        // Check the response status
+        // Send the request to OpenAI
+        let response = req_builder.send().await.map_err(|e| {
+            ConnectorError::Network(format!(
+                "Failed to send request: {}",
+                http_client::describe_error(&e)
+            ))
+        })?;
+
+        // Check the response status; a filtered prompt is a filtered response
+        // rather than an error
+        self.observe_rate_limit(&response);
         let status = response.status();
+        if status == StatusCode::BAD_REQUEST {
+            let text = response.text().await.unwrap_or_default();
+            if let Some(detail) = ContentFilterDetail::from_error_body(&text) {
+                return Ok(filtered_response(&request.model, detail));
+            }
+            return Err(error_from_text(status, text));
+        }
         if !status.is_success() {
             return Err(self.parse_error_response(status, response).await);
         }
 
+        // Parse the response
+        let openai_response = response
+            .json::<OpenAIChatResponse>()
+            .await
+            .map_err(|e| ConnectorError::Parsing(format!("Failed to parse response: {}", e)))?;
+
+        // Convert the response to our format
+        Ok(self.convert_response(openai_response))
+    }
+
+    async fn generate_streaming(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<StreamingResponse, ConnectorError> {
+        let in_flight = self.pool.track();
+        let response = self.send_streaming_request(&request).await?;
+
         // Create a stream that processes each line from the response
         let self_clone = self.clone();
 
@@ -657,8 +777,31 @@ impl ModelConnector for OpenAIConnector {
             },
         ));
 
+        // Keep the request counted as in flight until the stream is dropped
+        let stream = stream.map(move |chunk| {
+            let _in_flight = &in_flight;
+            chunk
+        });
+
         // Return the stream
-        Ok(stream as StreamingResponse)
+        Ok(Box::pin(stream) as StreamingResponse)
+    }
+
+    async fn generate_streaming_raw(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<Option<RawStreamingResponse>, ConnectorError> {
+        let in_flight = self.pool.track();
+        let response = self.send_streaming_request(&request).await?;
+
+        // The body is already in the format clients expect, so hand the
+        // bytes over as they arrive
+        let stream = response.bytes_stream().map(move |chunk| {
+            let _in_flight = &in_flight;
+            chunk.map_err(|e| ConnectorError::Network(format!("Error reading from stream: {}", e)))
+        });
+
+        Ok(Some(Box::pin(stream) as RawStreamingResponse))
     }
 
     fn get_config(&self) -> &ConnectorConfig {
@@ -704,10 +847,12 @@ impl ModelConnector for OpenAIConnector {
         }
 
         // Send the request to OpenAI
-        let response = req_builder
-            .send()
-            .await
-            .map_err(|e| ConnectorError::Network(format!("Failed to list models: {}", e)))?;
+        let response = req_builder.send().await.map_err(|e| {
+            ConnectorError::Network(format!(
+                "Failed to list models: {}",
+                http_client::describe_error(&e)
+            ))
+        })?;
 
         // Check the response status
         let status = response.status();
@@ -730,6 +875,10 @@ impl ModelConnector for OpenAIConnector {
 
         Ok(model_ids)
     }
+
+    fn rate_limit(&self) -> Option<RateLimitStatus> {
+        self.rate_limit.lock().unwrap().clone()
+    }
 }
 
 // Implement Clone for OpenAIConnector
@@ -737,7 +886,10 @@ impl Clone for OpenAIConnector {
     fn clone(&self) -> Self {
         Self {
             client: self.client.clone(),
+            pool: self.pool.clone(),
             config: self.config.clone(),
+            request_compression: self.request_compression,
+            rate_limit: self.rate_limit.clone(),
         }
     }
 }
@@ -783,6 +935,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 ChatMessage {
                     role: MessageRole::User,
@@ -790,6 +943,7 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
             ],
             temperature: Some(0.7),
@@ -847,8 +1001,10 @@ mod tests {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                content_filter_results: None,
             }],
             usage: Some(OpenAIUsage {
                 prompt_tokens: 9,
@@ -875,4 +1031,42 @@ mod tests {
         assert_eq!(response.usage.unwrap().completion_tokens, 12);
         assert_eq!(response.usage.unwrap().total_tokens, 21);
     }
+
+    #[test]
+    fn test_convert_filtered_response() {
+        let connector = OpenAIConnector::new(ConnectorConfig::default());
+        let azure_response: OpenAIChatResponse = serde_json::from_value(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "gpt-4o",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "I can"},
+                "finish_reason": "content_filter",
+                "content_filter_results": {
+                    "hate": {"filtered": false, "severity": "safe"},
+                    "violence": {"filtered": true, "severity": "high"}
+                }
+            }]
+        }))
+        .unwrap();
+
+        let response = connector.convert_response(azure_response);
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some(CONTENT_FILTER));
+        let detail = choice.content_filter.as_ref().unwrap();
+        assert_eq!(detail.categories, vec!["violence"]);
+
+        let error = ContentFilterDetail::from_error_body(
+            r#"{"error": {"message": "Filtered", "code": "content_filter"}}"#,
+        )
+        .unwrap();
+        let response = filtered_response("gpt-4o", error);
+        assert_eq!(response.choices[0].message.content, "");
+        assert_eq!(
+            response.choices[0].content_filter.as_ref().unwrap().stage,
+            crate::modules::model_registry::connectors::FilterStage::Prompt
+        );
+    }
 }
@@ -57,8 +57,10 @@ impl ModelConnector for MockConnector {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                content_filter: None,
             }],
             usage: Some(TokenUsage {
                 prompt_tokens: 10,
@@ -178,6 +180,7 @@ async fn test_mock_connector() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: Some(0.7),
         top_p: Some(0.9),
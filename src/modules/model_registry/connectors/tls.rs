@@ -0,0 +1,410 @@
+//! TLS verification of provider endpoints
+//!
+//! Self-hosted model servers often terminate TLS with certificates of a
+//! private CA. This module builds the `rustls` configuration of their
+//! clients, trusting the configured CA bundle and, when public keys are
+//! pinned, requiring the verified certificate chain, up to its trust anchor,
+//! to contain one of them.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::digest::{digest, SHA256};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use thiserror::Error;
+
+use crate::config::OutboundTlsConfig;
+
+/// Errors of TLS settings
+#[derive(Error, Debug)]
+pub enum TlsError {
+    /// The CA bundle could not be read
+    #[error("cannot read CA bundle {path}: {reason}")]
+    Bundle { path: String, reason: String },
+
+    /// The CA bundle holds no usable certificate
+    #[error("CA bundle {0} contains no valid certificate")]
+    EmptyBundle(String),
+
+    /// No CA is trusted
+    #[error("no trusted CA: set a CA bundle or enable system roots")]
+    NoRoots,
+
+    /// A pin is not a base64 SHA-256 hash
+    #[error("SPKI pin '{0}' is not a base64 SHA-256 hash")]
+    InvalidPin(String),
+}
+
+/// Build the TLS configuration of a provider client
+///
+/// `http2_only` restricts ALPN to HTTP/2, for clients speaking HTTP/2 with
+/// prior knowledge.
+pub fn client_config(
+    config: &OutboundTlsConfig,
+    http2_only: bool,
+) -> Result<ClientConfig, TlsError> {
+    let (roots, anchors) = root_store(config)?;
+    let roots = Arc::new(roots);
+    let pins = config
+        .spki_pins
+        .iter()
+        .map(|pin| parse_pin(pin))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let builder = ClientConfig::builder().with_safe_defaults();
+    let mut tls = if pins.is_empty() {
+        builder.with_root_certificates(roots).with_no_client_auth()
+    } else {
+        builder
+            .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                inner: WebPkiVerifier::new(roots, None),
+                anchors,
+                pins,
+            }))
+            .with_no_client_auth()
+    };
+    tls.alpn_protocols = if http2_only {
+        vec![b"h2".to_vec()]
+    } else {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    };
+    Ok(tls)
+}
+
+/// CAs trusted for provider certificates, with the certificates of the
+/// trust anchors
+fn root_store(config: &OutboundTlsConfig) -> Result<(RootCertStore, Vec<Certificate>), TlsError> {
+    let mut roots = RootCertStore::empty();
+    let mut anchors = Vec::new();
+    if config.system_roots {
+        // Native stores may hold certificates rustls cannot parse, which
+        // are skipped as other clients do
+        for cert in rustls_native_certs::load_native_certs().unwrap_or_default() {
+            let cert = Certificate(cert.0);
+            if roots.add(&cert).is_ok() {
+                anchors.push(cert);
+            }
+        }
+    }
+    if let Some(path) = &config.ca_bundle {
+        let bundle_error = |reason: String| TlsError::Bundle {
+            path: path.clone(),
+            reason,
+        };
+        let file = File::open(path).map_err(|e| bundle_error(e.to_string()))?;
+        let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+            .map_err(|e| bundle_error(e.to_string()))?;
+        let mut added = 0;
+        for cert in certs.into_iter().map(Certificate) {
+            if roots.add(&cert).is_ok() {
+                anchors.push(cert);
+                added += 1;
+            }
+        }
+        if added == 0 {
+            return Err(TlsError::EmptyBundle(path.clone()));
+        }
+    }
+    if roots.is_empty() {
+        return Err(TlsError::NoRoots);
+    }
+    Ok((roots, anchors))
+}
+
+/// Decode a pin, with an optional `sha256/` prefix
+fn parse_pin(pin: &str) -> Result<Vec<u8>, TlsError> {
+    STANDARD
+        .decode(pin.trim().trim_start_matches("sha256/"))
+        .ok()
+        .filter(|hash| hash.len() == 32)
+        .ok_or_else(|| TlsError::InvalidPin(pin.to_string()))
+}
+
+/// SHA-256 hash of a DER certificate's public key (SPKI), the value pins
+/// are compared with
+pub fn spki_sha256(cert: &[u8]) -> Option<Vec<u8>> {
+    let (certificate, _) = der_element(cert, 0x30)?;
+    let (mut tbs, _) = der_element(certificate, 0x30)?;
+    // Skip the explicit version, when present
+    if tbs.first() == Some(&0xa0) {
+        tbs = skip_element(tbs)?;
+    }
+    // Skip the serial number, signature algorithm, issuer, validity and
+    // subject
+    for _ in 0..5 {
+        tbs = skip_element(tbs)?;
+    }
+    let (_, rest) = der_element(tbs, 0x30)?;
+    let spki = &tbs[..tbs.len() - rest.len()];
+    Some(digest(&SHA256, spki).as_ref().to_vec())
+}
+
+/// Content of the DER element starting `input`, if of the expected tag,
+/// and the input following it
+fn der_element(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if *input.first()? != tag {
+        return None;
+    }
+    let (len, header) = match *input.get(1)? {
+        len if len < 0x80 => (len as usize, 2),
+        0x81..=0x84 => {
+            let octets = (input[1] & 0x7f) as usize;
+            let len = input
+                .get(2..2 + octets)?
+                .iter()
+                .fold(0usize, |len, &octet| (len << 8) | octet as usize);
+            (len, 2 + octets)
+        }
+        _ => return None,
+    };
+    let end = header.checked_add(len)?;
+    Some((input.get(header..end)?, &input[end..]))
+}
+
+/// Input following the DER element starting `input`
+fn skip_element(input: &[u8]) -> Option<&[u8]> {
+    der_element(input, *input.first()?).map(|(_, rest)| rest)
+}
+
+/// Certificate verifier requiring a pinned public key in verified chains
+struct PinnedVerifier {
+    inner: WebPkiVerifier,
+    /// Certificates of the trusted CAs, which pins may name
+    anchors: Vec<Certificate>,
+    pins: Vec<Vec<u8>>,
+}
+
+impl PinnedVerifier {
+    /// Whether the end entity chains up to a pinned key
+    ///
+    /// The presented intermediates are not trusted to be part of the chain:
+    /// a server may append any certificate. A pinned CA counts only when the
+    /// end entity verifies with it as the sole trust anchor, so the pinned
+    /// key signed the path.
+    fn chains_to_pin(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        now: SystemTime,
+    ) -> bool {
+        let pinned = |cert: &&Certificate| {
+            spki_sha256(&cert.0).is_some_and(|hash| self.pins.contains(&hash))
+        };
+        if pinned(&end_entity) {
+            return true;
+        }
+        intermediates
+            .iter()
+            .chain(&self.anchors)
+            .filter(pinned)
+            .any(|ca| {
+                let mut roots = RootCertStore::empty();
+                roots.add(ca).is_ok()
+                    && WebPkiVerifier::new(roots, None)
+                        .verify_server_cert(
+                            end_entity,
+                            intermediates,
+                            server_name,
+                            &mut std::iter::empty(),
+                            &[],
+                            now,
+                        )
+                        .is_ok()
+            })
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )?;
+        if !self.chains_to_pin(end_entity, intermediates, server_name, now) {
+            let host = match server_name {
+                ServerName::DnsName(name) => name.as_ref().to_string(),
+                ServerName::IpAddress(ip) => ip.to_string(),
+                _ => format!("{:?}", server_name),
+            };
+            return Err(rustls::Error::General(format!(
+                "certificate chain of {} contains no pinned public key",
+                host
+            )));
+        }
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test CA, with an Ed25519 key
+    const CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBZzCCARmgAwIBAgIUcpfavsgKuSYhElx6K2KzmpPvo+YwBQYDK2VwMCAxHjAc
+BgNVBAMMFUludGVsbGlSb3V0ZXIgVGVzdCBDQTAgFw0yNjEwMTcyMTI2MjZaGA8y
+MTI2MDkyMzIxMjYyNlowIDEeMBwGA1UEAwwVSW50ZWxsaVJvdXRlciBUZXN0IENB
+MCowBQYDK2VwAyEABkq5MNhxg9liZkmNO9QLszXSb6ZvC4x/vQnyHw8XU1WjYzBh
+MB0GA1UdDgQWBBS7gNyA7QMzsvoSdPQO5AY/UUhQvDAfBgNVHSMEGDAWgBS7gNyA
+7QMzsvoSdPQO5AY/UUhQvDAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIC
+BDAFBgMrZXADQQBNEX8aYvlAePe3fgop5zcO45bJPgJD0Wa+ct7v3jMHgePxS0MJ
+hxa6jBWoDOXHVoORAINec1t23CLl93MECOML
+-----END CERTIFICATE-----
+";
+
+    /// Certificate of `localhost` issued by the test CA
+    const LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBcDCCASKgAwIBAgIUf0EPRYR5w+U+FGNfnTraTuG/VckwBQYDK2VwMCAxHjAc
+BgNVBAMMFUludGVsbGlSb3V0ZXIgVGVzdCBDQTAgFw0yNjEwMTcyMTI2MjZaGA8y
+MTI2MDkyMzIxMjYyNlowFDESMBAGA1UEAwwJbG9jYWxob3N0MCowBQYDK2VwAyEA
+bxuppE9ymrr0sUL+N7DYcQP9A820dDZe8IXgwb0vsPmjeDB2MBQGA1UdEQQNMAuC
+CWxvY2FsaG9zdDAJBgNVHRMEAjAAMBMGA1UdJQQMMAoGCCsGAQUFBwMBMB0GA1Ud
+DgQWBBSUkVEokcfbMxGEJvw2nFCH6fDSlDAfBgNVHSMEGDAWgBS7gNyA7QMzsvoS
+dPQO5AY/UUhQvDAFBgMrZXADQQCqdjJGyshs2IBoAXn0viJCwTkSnlV6e8etfmur
+Mupkk2FAQkdajLf8OAA9ztj4/MhTYy/vXCXZKjhArc1D9t4E
+-----END CERTIFICATE-----
+";
+
+    /// CA unrelated to the test CA
+    const OTHER_CA: &str = "-----BEGIN CERTIFICATE-----
+MIIBXzCCARGgAwIBAgIUIoPT5KjiiEOPOy5G+2kDriCOlg8wBQYDK2VwMBwxGjAY
+BgNVBAMMEVVucmVsYXRlZCBUZXN0IENBMCAXDTI2MTAxODAzNTM1MVoYDzIxMjYw
+OTI0MDM1MzUxWjAcMRowGAYDVQQDDBFVbnJlbGF0ZWQgVGVzdCBDQTAqMAUGAytl
+cAMhAO4VbWIZGdhPu8uGZbPqOFMJu2AGagYjGD+qMyJYMNmso2MwYTAdBgNVHQ4E
+FgQUeI/SdvePp4qt3Dkd+k1a44eHOmUwHwYDVR0jBBgwFoAUeI/SdvePp4qt3Dkd
++k1a44eHOmUwDwYDVR0TAQH/BAUwAwEB/zAOBgNVHQ8BAf8EBAMCAgQwBQYDK2Vw
+A0EAKr5Fr41/4tZbf4kKFSBlGKmNqq6SzccigdY6N9ChTl7bcANc2jslUHvTX6Xw
+Lj4ItZ3HStUQms1qvB4ICobtBg==
+-----END CERTIFICATE-----
+";
+
+    /// Certificate of `localhost` issued by the unrelated CA
+    const OTHER_LEAF: &str = "-----BEGIN CERTIFICATE-----
+MIIBbDCCAR6gAwIBAgIUN2K1eLvqbIquC9kXcmvG1dVv7+8wBQYDK2VwMBwxGjAY
+BgNVBAMMEVVucmVsYXRlZCBUZXN0IENBMCAXDTI2MTAxODAzNTM1MVoYDzIxMjYw
+OTI0MDM1MzUxWjAUMRIwEAYDVQQDDAlsb2NhbGhvc3QwKjAFBgMrZXADIQBrZw0F
+cEghHtqvu2UM7d5TAcYMMKgbE5bSkLHpsqntGqN4MHYwFAYDVR0RBA0wC4IJbG9j
+YWxob3N0MAkGA1UdEwQCMAAwEwYDVR0lBAwwCgYIKwYBBQUHAwEwHQYDVR0OBBYE
+FB+QF1uQ+x2OzwjVS0r/jUUtDjQoMB8GA1UdIwQYMBaAFHiP0nb3j6eKrdw5HfpN
+WuOHhzplMAUGAytlcANBAFxBKiqm2ksCdiBcmXCsLgUevUAdcqMYjeuLrzOoc3YY
+EXoLqW8hVnrgIEcmuRWBpMWwLOKnMIGA+tt1ihB9/gw=
+-----END CERTIFICATE-----
+";
+
+    /// Pins of the CA and `localhost` keys
+    const CA_PIN: &str = "WvQz2nSNjWipcZPFvTBzeAJsmMaWPvv2wxCoOe5//wo=";
+    const LEAF_PIN: &str = "Q3WckGpRT/c8HSSHZQJbGyGxnvHFBX7qSaJy2DnK+Bg=";
+
+    fn der(pem: &str) -> Certificate {
+        Certificate(
+            rustls_pemfile::certs(&mut pem.as_bytes())
+                .unwrap()
+                .remove(0),
+        )
+    }
+
+    /// Verify `leaf` presented with `intermediates`, trusting both CAs
+    fn verify_chain(
+        leaf: &str,
+        intermediates: &[&str],
+        pins: &[&str],
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let anchors = vec![der(CA), der(OTHER_CA)];
+        let mut roots = RootCertStore::empty();
+        for anchor in &anchors {
+            roots.add(anchor).unwrap();
+        }
+        let verifier = PinnedVerifier {
+            inner: WebPkiVerifier::new(roots, None),
+            anchors,
+            pins: pins.iter().map(|pin| parse_pin(pin).unwrap()).collect(),
+        };
+        let intermediates: Vec<_> = intermediates.iter().map(|pem| der(pem)).collect();
+        verifier.verify_server_cert(
+            &der(leaf),
+            &intermediates,
+            &ServerName::try_from("localhost").unwrap(),
+            &mut std::iter::empty(),
+            &[],
+            SystemTime::now(),
+        )
+    }
+
+    fn verify(pins: &[&str]) -> Result<ServerCertVerified, rustls::Error> {
+        verify_chain(LEAF, &[], pins)
+    }
+
+    #[test]
+    fn test_client_config() {
+        let bundle = std::env::temp_dir().join(format!("ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&bundle, CA).unwrap();
+        let config = OutboundTlsConfig {
+            ca_bundle: Some(bundle.to_string_lossy().into_owned()),
+            system_roots: false,
+            spki_pins: vec![format!("sha256/{}", CA_PIN)],
+        };
+        let tls = client_config(&config, true).unwrap();
+        assert_eq!(tls.alpn_protocols, vec![b"h2".to_vec()]);
+
+        let missing = OutboundTlsConfig {
+            ca_bundle: Some("/nonexistent/ca.pem".to_string()),
+            ..config.clone()
+        };
+        assert!(matches!(
+            client_config(&missing, false),
+            Err(TlsError::Bundle { .. })
+        ));
+
+        let invalid_pin = OutboundTlsConfig {
+            spki_pins: vec!["not-a-hash".to_string()],
+            ..config
+        };
+        assert!(matches!(
+            client_config(&invalid_pin, false),
+            Err(TlsError::InvalidPin(_))
+        ));
+        std::fs::remove_file(bundle).unwrap();
+    }
+
+    #[test]
+    fn test_spki_sha256() {
+        assert_eq!(spki_sha256(&der(LEAF).0), parse_pin(LEAF_PIN).ok());
+        assert_eq!(spki_sha256(&der(CA).0), parse_pin(CA_PIN).ok());
+        assert_eq!(spki_sha256(b"not a certificate"), None);
+    }
+
+    #[test]
+    fn test_pinned_verification() {
+        assert!(verify(&[LEAF_PIN]).is_ok());
+        // The trust anchor is part of the verified chain, though not sent
+        assert!(verify(&[CA_PIN]).is_ok());
+        let error = verify(&[&STANDARD.encode([7u8; 32])]).unwrap_err();
+        assert!(error.to_string().contains("no pinned public key"));
+    }
+
+    #[test]
+    fn test_appended_pin_outside_chain() {
+        // An unrelated chain does not pass by presenting the pinned CA
+        assert!(verify_chain(OTHER_LEAF, &[], &[CA_PIN]).is_err());
+        let error = verify_chain(OTHER_LEAF, &[CA], &[CA_PIN]).unwrap_err();
+        assert!(error.to_string().contains("no pinned public key"));
+        assert!(verify_chain(LEAF, &[OTHER_CA], &[CA_PIN]).is_ok());
+    }
+}
@@ -0,0 +1,863 @@
+//! Model Discovery
+//!
+//! This module keeps the registry from going stale as providers release new
+//! models. Every `interval_secs`, the list-models APIs of the OpenAI,
+//! Anthropic and Ollama providers are queried, and each listed model that is
+//! neither registered, ignored nor already proposed becomes a proposal: a
+//! registry entry waiting for an admin to approve or reject it. Proposals
+//! carry the capabilities the provider reports (Ollama reports context
+//! lengths and capabilities such as vision and tools) or that are inferred
+//! from the model's name, and the model's prices when the cost calculator
+//! knows them.
+//!
+//! An approved proposal is registered as available. A rejected one is kept,
+//! so that the model is not proposed again. Registered models of a provider
+//! that it no longer lists are reported by each sync, but left registered.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::storage::ModelRegistry;
+use super::types::errors::RegistryError;
+use super::types::model::{ModelMetadata, ModelType};
+use super::types::status::ModelStatus;
+use crate::config::{LlmProviderConfig, ModelDiscoveryConfig, ModelRegistryConfig};
+use crate::modules::telemetry::catalog;
+use crate::modules::telemetry::cost::CostCalculator;
+
+/// Delay before the first sync, leaving the router time to start
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+
+/// Version of the Anthropic API the models are listed with
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Name fragments of models accepting images
+const VISION_HINTS: &[&str] = &[
+    "gpt-4o",
+    "gpt-4.1",
+    "gpt-4-turbo",
+    "gpt-5",
+    "claude-3",
+    "claude-sonnet-4",
+    "claude-opus-4",
+    "claude-haiku-4",
+    "llava",
+    "vision",
+];
+
+/// Context lengths of model families, by name prefix; the first match wins
+const CONTEXT_HINTS: &[(&str, usize)] = &[
+    ("gpt-4.1", 1_047_576),
+    ("gpt-4o", 128_000),
+    ("gpt-4-turbo", 128_000),
+    ("gpt-4-32k", 32_768),
+    ("gpt-4", 8_192),
+    ("gpt-3.5-turbo", 16_385),
+    ("claude-", 200_000),
+];
+
+/// List-models API of a provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProviderApi {
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl ProviderApi {
+    /// API of a provider, by its name
+    fn for_provider(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "openai" => Some(Self::OpenAi),
+            "anthropic" => Some(Self::Anthropic),
+            "ollama" => Some(Self::Ollama),
+            _ => None,
+        }
+    }
+}
+
+/// Provider whose models are synced
+#[derive(Debug, Clone)]
+struct Source {
+    provider: String,
+    api: ProviderApi,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl Source {
+    /// URL of an API path, whether or not the endpoint ends with `/v1`
+    fn url(&self, path: &str) -> String {
+        let base = self.endpoint.trim_end_matches('/');
+        format!("{}{}", base.strip_suffix("/v1").unwrap_or(base), path)
+    }
+}
+
+/// Model listed by a provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiscoveredModel {
+    pub id: String,
+    pub display_name: Option<String>,
+    /// Context window reported by the provider, in tokens
+    pub context_length: Option<usize>,
+    /// Capabilities reported by the provider, such as `vision` or `tools`
+    pub capabilities: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// Admin decision on a proposal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ProposalStatus {
+    /// Name of the status, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Approved => "approved",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// Registry entry proposed for a discovered model
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelProposal {
+    /// Entry registered on approval
+    pub model: ModelMetadata,
+    /// Whether the entry has the model's prices
+    pub priced: bool,
+    pub status: ProposalStatus,
+    pub discovered_at: DateTime<Utc>,
+    /// Last sync the provider listed the model in
+    pub last_seen: DateTime<Utc>,
+    /// Admin who approved or rejected the proposal
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of syncing the models of a provider
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProviderSync {
+    pub provider: String,
+    /// Models the provider listed
+    pub listed: usize,
+    /// Models proposed by this sync
+    pub proposed: Vec<String>,
+    /// Registered models of the provider that it no longer lists
+    pub missing: Vec<String>,
+    /// Why the models could not be listed
+    pub error: Option<String>,
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Error deciding on a proposal
+#[derive(Debug, Error)]
+pub enum DiscoveryError {
+    #[error("No proposal for model {0}")]
+    NotFound(String),
+    #[error("The proposal for model {0} is already {1}")]
+    Decided(String, &'static str),
+    #[error(transparent)]
+    Registry(#[from] RegistryError),
+}
+
+/// Sync of the models listed by providers, proposed for registration
+#[derive(Debug)]
+pub struct ModelDiscovery {
+    config: ModelDiscoveryConfig,
+    sources: Vec<Source>,
+    registry: Arc<ModelRegistry>,
+    prices: Option<Arc<CostCalculator>>,
+    client: Client,
+    proposals: Mutex<HashMap<String, ModelProposal>>,
+    last_syncs: Mutex<Vec<ProviderSync>>,
+}
+
+impl ModelDiscovery {
+    /// Create a discovery and start syncing in the background
+    ///
+    /// Returns `None` when discovery is disabled.
+    pub fn from_config(
+        config: &ModelRegistryConfig,
+        registry: Arc<ModelRegistry>,
+        prices: Option<Arc<CostCalculator>>,
+    ) -> Option<Arc<Self>> {
+        if !config.discovery.enabled {
+            return None;
+        }
+        let discovery = Arc::new(Self::new(config, registry, prices));
+        discovery.clone().spawn();
+        Some(discovery)
+    }
+
+    /// Create a discovery of the models of the configured providers
+    ///
+    /// Providers without a list-models API this module knows are skipped.
+    pub fn new(
+        config: &ModelRegistryConfig,
+        registry: Arc<ModelRegistry>,
+        prices: Option<Arc<CostCalculator>>,
+    ) -> Self {
+        let discovery = &config.discovery;
+        let sources = config
+            .providers
+            .iter()
+            .filter(|provider| {
+                discovery.providers.is_empty() || discovery.providers.contains(&provider.name)
+            })
+            .filter_map(Self::source)
+            .collect();
+        let client = Client::builder()
+            .timeout(Duration::from_secs(discovery.timeout_secs))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config: discovery.clone(),
+            sources,
+            registry,
+            prices,
+            client,
+            proposals: Mutex::new(HashMap::new()),
+            last_syncs: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Source of the models of a provider, if its API is known
+    fn source(provider: &LlmProviderConfig) -> Option<Source> {
+        let api = ProviderApi::for_provider(&provider.name)?;
+        Some(Source {
+            provider: provider.name.clone(),
+            api,
+            endpoint: provider.endpoint.clone(),
+            api_key: std::env::var(&provider.api_key_env).ok(),
+        })
+    }
+
+    /// Proposals, pending ones first
+    pub fn proposals(&self) -> Vec<ModelProposal> {
+        let mut proposals: Vec<ModelProposal> =
+            self.proposals.lock().unwrap().values().cloned().collect();
+        proposals.sort_by(|a, b| {
+            (a.status != ProposalStatus::Pending)
+                .cmp(&(b.status != ProposalStatus::Pending))
+                .then_with(|| a.model.provider.cmp(&b.model.provider))
+                .then_with(|| a.model.id.cmp(&b.model.id))
+        });
+        proposals
+    }
+
+    /// Outcomes of the last sync of each provider
+    pub fn last_syncs(&self) -> Vec<ProviderSync> {
+        self.last_syncs.lock().unwrap().clone()
+    }
+
+    /// Sync the models of every provider once
+    pub async fn sync(&self) -> Vec<ProviderSync> {
+        let listings =
+            futures::future::join_all(self.sources.iter().map(|source| self.list(source))).await;
+        let syncs: Vec<ProviderSync> = self
+            .sources
+            .iter()
+            .zip(listings)
+            .map(|(source, listing)| {
+                let sync = match listing {
+                    Ok(models) => self.record(source, models),
+                    Err(e) => {
+                        warn!("Failed to list the models of {}: {}", source.provider, e);
+                        ProviderSync {
+                            provider: source.provider.clone(),
+                            error: Some(e),
+                            synced_at: Utc::now(),
+                            ..Default::default()
+                        }
+                    }
+                };
+                let outcome = if sync.error.is_some() { "error" } else { "ok" };
+                counter!(catalog::MODEL_DISCOVERY_SYNCS, 1, "provider" => sync.provider.clone(), "outcome" => outcome);
+                sync
+            })
+            .collect();
+        *self.last_syncs.lock().unwrap() = syncs.clone();
+        self.update_pending();
+        syncs
+    }
+
+    /// Propose the new models a provider listed
+    fn record(&self, source: &Source, models: Vec<DiscoveredModel>) -> ProviderSync {
+        let now = Utc::now();
+        let registered: HashSet<String> = self
+            .registry
+            .find_by_provider(&source.provider)
+            .into_iter()
+            .map(|model| model.id)
+            .collect();
+        let listed: HashSet<&str> = models.iter().map(|model| model.id.as_str()).collect();
+        let mut missing: Vec<String> = registered
+            .iter()
+            .filter(|id| !listed.contains(id.as_str()))
+            .cloned()
+            .collect();
+        missing.sort();
+
+        let mut proposed = Vec::new();
+        let mut proposals = self.proposals.lock().unwrap();
+        for discovered in &models {
+            if let Some(proposal) = proposals.get_mut(&discovered.id) {
+                proposal.last_seen = now;
+                continue;
+            }
+            if registered.contains(&discovered.id)
+                || self.registry.get_model(&discovered.id).is_ok()
+                || self.ignored(&discovered.id)
+            {
+                continue;
+            }
+            let (model, priced) = self.propose(source, discovered);
+            info!(
+                "Discovered model {} of {}, waiting for approval",
+                model.id, source.provider
+            );
+            proposed.push(model.id.clone());
+            proposals.insert(
+                model.id.clone(),
+                ModelProposal {
+                    model,
+                    priced,
+                    status: ProposalStatus::Pending,
+                    discovered_at: now,
+                    last_seen: now,
+                    decided_by: None,
+                    decided_at: None,
+                },
+            );
+        }
+
+        ProviderSync {
+            provider: source.provider.clone(),
+            listed: models.len(),
+            proposed,
+            missing,
+            error: None,
+            synced_at: now,
+        }
+    }
+
+    /// Whether a model is never proposed
+    fn ignored(&self, id: &str) -> bool {
+        self.config
+            .ignore
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => id.starts_with(prefix),
+                None => id == pattern,
+            })
+    }
+
+    /// Registry entry of a discovered model, and whether it has prices
+    fn propose(&self, source: &Source, discovered: &DiscoveredModel) -> (ModelMetadata, bool) {
+        let id = discovered.id.clone();
+        let mut model = ModelMetadata::new(
+            id.clone(),
+            discovered
+                .display_name
+                .clone()
+                .unwrap_or_else(|| id.clone()),
+            source.provider.clone(),
+            version_of(&id),
+            source.endpoint.clone(),
+        );
+        let reported = |capability: &str| discovered.capabilities.iter().any(|c| c == capability);
+        let lowercase = id.to_ascii_lowercase();
+
+        model.model_type = if lowercase.contains("embed") || reported("embedding") {
+            ModelType::Embedding
+        } else if lowercase.starts_with("dall-e") || lowercase.starts_with("gpt-image") {
+            ModelType::ImageGeneration
+        } else if ["whisper", "tts", "transcribe", "audio"]
+            .iter()
+            .any(|hint| lowercase.contains(hint))
+        {
+            ModelType::AudioProcessing
+        } else {
+            ModelType::TextGeneration
+        };
+        let generates_text = model.model_type == ModelType::TextGeneration;
+
+        let capabilities = &mut model.capabilities;
+        capabilities.supports_embeddings = model.model_type == ModelType::Embedding;
+        capabilities.supports_vision = reported("vision")
+            || (discovered.capabilities.is_empty()
+                && VISION_HINTS.iter().any(|hint| lowercase.contains(hint)));
+        capabilities.supports_function_calling = reported("tools")
+            || (generates_text
+                && source.api != ProviderApi::Ollama
+                && (lowercase.starts_with("gpt-") || lowercase.starts_with("claude-")));
+        capabilities.supports_streaming = generates_text;
+        if let Some(context_length) = discovered.context_length.or_else(|| {
+            CONTEXT_HINTS
+                .iter()
+                .find(|(prefix, _)| lowercase.starts_with(prefix))
+                .map(|(_, length)| *length)
+        }) {
+            capabilities.max_context_length = context_length;
+        }
+        capabilities.version_info.release_date = discovered.created_at;
+
+        // Local models cost nothing per token
+        let prices = match source.api {
+            ProviderApi::Ollama => Some((0.0, 0.0)),
+            _ => self
+                .prices
+                .as_ref()
+                .and_then(|prices| prices.model_cost(&id)),
+        };
+        if let Some((input, output)) = prices {
+            capabilities.cost_per_1k_tokens_input = input;
+            capabilities.cost_per_1k_tokens_output = output;
+        }
+
+        model
+            .additional_metadata
+            .insert("discovered".to_string(), "true".to_string());
+        (model, prices.is_some())
+    }
+
+    /// Register a proposed model as available
+    pub fn approve(&self, id: &str, by: &str) -> Result<ModelProposal, DiscoveryError> {
+        let mut proposals = self.proposals.lock().unwrap();
+        let proposal = Self::pending(&mut proposals, id)?;
+        let mut model = proposal.model.clone();
+        model.status = ModelStatus::Available;
+        model.updated_at = Utc::now();
+        self.registry.register_model(model.clone())?;
+        proposal.model = model;
+        Self::decide(proposal, ProposalStatus::Approved, by);
+        let proposal = proposal.clone();
+        drop(proposals);
+        self.update_pending();
+        Ok(proposal)
+    }
+
+    /// Reject a proposed model, so that it is not proposed again
+    pub fn reject(&self, id: &str, by: &str) -> Result<ModelProposal, DiscoveryError> {
+        let mut proposals = self.proposals.lock().unwrap();
+        let proposal = Self::pending(&mut proposals, id)?;
+        Self::decide(proposal, ProposalStatus::Rejected, by);
+        let proposal = proposal.clone();
+        drop(proposals);
+        self.update_pending();
+        Ok(proposal)
+    }
+
+    /// Pending proposal of a model
+    fn pending<'a>(
+        proposals: &'a mut HashMap<String, ModelProposal>,
+        id: &str,
+    ) -> Result<&'a mut ModelProposal, DiscoveryError> {
+        let proposal = proposals
+            .get_mut(id)
+            .ok_or_else(|| DiscoveryError::NotFound(id.to_string()))?;
+        match proposal.status {
+            ProposalStatus::Pending => Ok(proposal),
+            status => Err(DiscoveryError::Decided(id.to_string(), status.as_str())),
+        }
+    }
+
+    /// Record an admin's decision on a proposal
+    fn decide(proposal: &mut ModelProposal, status: ProposalStatus, by: &str) {
+        proposal.status = status;
+        proposal.decided_by = Some(by.to_string());
+        proposal.decided_at = Some(Utc::now());
+        info!(
+            "Proposal of model {} {} by {}",
+            proposal.model.id,
+            status.as_str(),
+            by
+        );
+    }
+
+    /// Report the number of pending proposals
+    fn update_pending(&self) {
+        let pending = self
+            .proposals
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|proposal| proposal.status == ProposalStatus::Pending)
+            .count();
+        gauge!(catalog::MODEL_DISCOVERY_PENDING, pending as f64);
+    }
+
+    /// Models a provider lists
+    async fn list(&self, source: &Source) -> Result<Vec<DiscoveredModel>, String> {
+        match source.api {
+            ProviderApi::OpenAi => {
+                let mut request = self.client.get(source.url("/v1/models"));
+                if let Some(key) = &source.api_key {
+                    request = request.bearer_auth(key);
+                }
+                Ok(openai_models(&self.fetch(request).await?))
+            }
+            ProviderApi::Anthropic => {
+                let mut models = Vec::new();
+                let mut after: Option<String> = None;
+                loop {
+                    let mut request = self
+                        .client
+                        .get(source.url("/v1/models"))
+                        .query(&[("limit", "1000")])
+                        .header("anthropic-version", ANTHROPIC_VERSION);
+                    if let Some(after) = &after {
+                        request = request.query(&[("after_id", after)]);
+                    }
+                    if let Some(key) = &source.api_key {
+                        request = request.header("x-api-key", key);
+                    }
+                    let page = self.fetch(request).await?;
+                    models.extend(anthropic_models(&page));
+                    after = page["last_id"].as_str().map(str::to_string);
+                    if !page["has_more"].as_bool().unwrap_or(false) || after.is_none() {
+                        return Ok(models);
+                    }
+                }
+            }
+            ProviderApi::Ollama => {
+                let tags = self.fetch(self.client.get(source.url("/api/tags"))).await?;
+                let mut models = ollama_models(&tags);
+                // Context lengths and capabilities are only reported per model
+                for model in &mut models {
+                    let request = self
+                        .client
+                        .post(source.url("/api/show"))
+                        .json(&json!({ "model": model.id }));
+                    match self.fetch(request).await {
+                        Ok(details) => apply_ollama_details(model, &details),
+                        Err(e) => warn!("Failed to show Ollama model {}: {}", model.id, e),
+                    }
+                }
+                Ok(models)
+            }
+        }
+    }
+
+    /// Send a request and parse its JSON response
+    async fn fetch(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("HTTP {}", status));
+        }
+        response.json().await.map_err(|e| e.to_string())
+    }
+
+    /// Sync the models of every provider every `interval_secs`
+    fn spawn(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.interval_secs.max(1));
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + STARTUP_DELAY, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                self.sync().await;
+            }
+        });
+    }
+}
+
+/// Version of a model, from the date or tag ending its name
+fn version_of(id: &str) -> String {
+    if let Some((_, tag)) = id.rsplit_once(':') {
+        return tag.to_string();
+    }
+    let is_date = |part: &str| {
+        matches!(part.len(), 8 | 10) && part.chars().all(|c| c.is_ascii_digit() || c == '-')
+    };
+    let date = id
+        .rsplit_once('-')
+        .map(|(_, last)| last)
+        .filter(|last| is_date(last));
+    // OpenAI dates are split by dashes, as in `gpt-4o-2024-08-06`
+    let dashed = id
+        .len()
+        .checked_sub(10)
+        .and_then(|start| id.get(start..))
+        .filter(|last| is_date(last) && last.chars().filter(|c| *c == '-').count() == 2);
+    dashed
+        .or(date)
+        .map(str::to_string)
+        .unwrap_or_else(|| "latest".to_string())
+}
+
+/// Models of an OpenAI list-models response
+fn openai_models(response: &Value) -> Vec<DiscoveredModel> {
+    response["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| {
+            Some(DiscoveredModel {
+                id: model["id"].as_str()?.to_string(),
+                created_at: model["created"]
+                    .as_i64()
+                    .and_then(|created| DateTime::from_timestamp(created, 0)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Models of a page of an Anthropic list-models response
+fn anthropic_models(response: &Value) -> Vec<DiscoveredModel> {
+    response["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| {
+            Some(DiscoveredModel {
+                id: model["id"].as_str()?.to_string(),
+                display_name: model["display_name"].as_str().map(str::to_string),
+                created_at: model["created_at"]
+                    .as_str()
+                    .and_then(|created| DateTime::parse_from_rfc3339(created).ok())
+                    .map(|created| created.with_timezone(&Utc)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Models of an Ollama tags response
+fn ollama_models(response: &Value) -> Vec<DiscoveredModel> {
+    response["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|model| {
+            Some(DiscoveredModel {
+                id: model["name"].as_str()?.to_string(),
+                created_at: model["modified_at"]
+                    .as_str()
+                    .and_then(|modified| DateTime::parse_from_rfc3339(modified).ok())
+                    .map(|modified| modified.with_timezone(&Utc)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Add the context length and capabilities of an Ollama show response
+fn apply_ollama_details(model: &mut DiscoveredModel, details: &Value) {
+    model.capabilities = details["capabilities"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|capability| capability.as_str().map(str::to_string))
+        .collect();
+    model.context_length = details["model_info"].as_object().and_then(|info| {
+        info.iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, length)| length.as_u64())
+            .map(|length| length as usize)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discovery(ignore: &[&str]) -> ModelDiscovery {
+        let mut config = ModelRegistryConfig::default();
+        config.discovery.ignore = ignore.iter().map(|pattern| pattern.to_string()).collect();
+        ModelDiscovery::new(
+            &config,
+            Arc::new(ModelRegistry::new()),
+            Some(Arc::new(CostCalculator::new())),
+        )
+    }
+
+    fn source(provider: &str) -> Source {
+        Source {
+            provider: provider.to_string(),
+            api: ProviderApi::for_provider(provider).unwrap(),
+            endpoint: "https://api.example.com/v1".to_string(),
+            api_key: None,
+        }
+    }
+
+    fn listed(ids: &[&str]) -> Vec<DiscoveredModel> {
+        ids.iter()
+            .map(|id| DiscoveredModel {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_proposes_new_models() {
+        let discovery = discovery(&["ft:*"]);
+        let registered = ModelMetadata::new(
+            "gpt-4".to_string(),
+            "GPT-4".to_string(),
+            "openai".to_string(),
+            "1".to_string(),
+            "https://api.openai.com/v1".to_string(),
+        );
+        discovery.registry.register_model(registered).unwrap();
+        discovery
+            .registry
+            .register_model(ModelMetadata::new(
+                "gpt-3.5-turbo-0301".to_string(),
+                "GPT-3.5".to_string(),
+                "openai".to_string(),
+                "1".to_string(),
+                "https://api.openai.com/v1".to_string(),
+            ))
+            .unwrap();
+
+        let sync = discovery.record(
+            &source("openai"),
+            listed(&[
+                "gpt-4",
+                "gpt-4o-2024-08-06",
+                "text-embedding-3-small",
+                "ft:gpt-4o:acme",
+            ]),
+        );
+        assert_eq!(sync.listed, 4);
+        assert_eq!(
+            sync.proposed,
+            vec!["gpt-4o-2024-08-06", "text-embedding-3-small"]
+        );
+        assert_eq!(sync.missing, vec!["gpt-3.5-turbo-0301"]);
+
+        let proposals = discovery.proposals();
+        let gpt4o = &proposals[0].model;
+        assert_eq!(gpt4o.version, "2024-08-06");
+        assert_eq!(gpt4o.status, ModelStatus::Unknown);
+        assert!(gpt4o.capabilities.supports_vision);
+        assert!(gpt4o.capabilities.supports_function_calling);
+        assert_eq!(gpt4o.capabilities.max_context_length, 128_000);
+        assert!(!proposals[0].priced);
+        let embedding = &proposals[1].model;
+        assert_eq!(embedding.model_type, ModelType::Embedding);
+        assert!(embedding.capabilities.supports_embeddings);
+        assert!(!embedding.capabilities.supports_function_calling);
+
+        // Known models are proposed once
+        let sync = discovery.record(&source("openai"), listed(&["gpt-4o-2024-08-06"]));
+        assert!(sync.proposed.is_empty());
+        assert_eq!(discovery.proposals().len(), 2);
+    }
+
+    #[test]
+    fn test_detected_capabilities_and_prices() {
+        let discovery = discovery(&[]);
+        let (claude, priced) = discovery.propose(
+            &source("anthropic"),
+            &DiscoveredModel {
+                id: "claude-2".to_string(),
+                display_name: Some("Claude 2".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(priced);
+        assert_eq!(claude.name, "Claude 2");
+        assert_eq!(claude.capabilities.cost_per_1k_tokens_input, 0.01102);
+        assert_eq!(claude.capabilities.max_context_length, 200_000);
+
+        let mut llava = listed(&["llava:13b"]).remove(0);
+        apply_ollama_details(
+            &mut llava,
+            &json!({
+                "capabilities": ["completion", "vision"],
+                "model_info": { "llama.context_length": 4096 }
+            }),
+        );
+        let (llava, priced) = discovery.propose(&source("ollama"), &llava);
+        assert!(priced);
+        assert_eq!(llava.version, "13b");
+        assert!(llava.capabilities.supports_vision);
+        assert!(!llava.capabilities.supports_function_calling);
+        assert_eq!(llava.capabilities.max_context_length, 4096);
+
+        assert_eq!(version_of("claude-3-5-sonnet-20241022"), "20241022");
+        assert_eq!(version_of("gpt-4o"), "latest");
+    }
+
+    #[test]
+    fn test_approve_and_reject() {
+        let discovery = discovery(&[]);
+        discovery.record(&source("openai"), listed(&["gpt-4o", "gpt-4o-mini"]));
+
+        let approved = discovery.approve("gpt-4o", "admin").unwrap();
+        assert_eq!(approved.status, ProposalStatus::Approved);
+        assert_eq!(approved.decided_by.as_deref(), Some("admin"));
+        let model = discovery.registry.get_model("gpt-4o").unwrap();
+        assert_eq!(model.status, ModelStatus::Available);
+        assert!(matches!(
+            discovery.approve("gpt-4o", "admin"),
+            Err(DiscoveryError::Decided(_, "approved"))
+        ));
+
+        discovery.reject("gpt-4o-mini", "admin").unwrap();
+        assert!(discovery.registry.get_model("gpt-4o-mini").is_err());
+        assert!(matches!(
+            discovery.reject("o1", "admin"),
+            Err(DiscoveryError::NotFound(_))
+        ));
+
+        // Rejected models are not proposed again
+        let sync = discovery.record(&source("openai"), listed(&["gpt-4o", "gpt-4o-mini"]));
+        assert!(sync.proposed.is_empty());
+        assert!(sync.missing.is_empty());
+    }
+
+    #[test]
+    fn test_provider_responses() {
+        let openai = openai_models(&json!({
+            "data": [{ "id": "gpt-4o", "created": 1715367049 }]
+        }));
+        assert_eq!(openai[0].id, "gpt-4o");
+        assert!(openai[0].created_at.is_some());
+
+        let anthropic = anthropic_models(&json!({
+            "data": [{
+                "id": "claude-sonnet-4-20250514",
+                "display_name": "Claude Sonnet 4",
+                "created_at": "2025-05-22T00:00:00Z"
+            }],
+            "has_more": false
+        }));
+        assert_eq!(
+            anthropic[0].display_name.as_deref(),
+            Some("Claude Sonnet 4")
+        );
+
+        let ollama = ollama_models(&json!({ "models": [{ "name": "llama3:8b" }] }));
+        assert_eq!(ollama[0].id, "llama3:8b");
+
+        let source = source("openai");
+        assert_eq!(
+            source.url("/v1/models"),
+            "https://api.example.com/v1/models"
+        );
+    }
+}
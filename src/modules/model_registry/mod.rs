@@ -3,11 +3,14 @@
 //! This module handles tracking and metadata for various LLM models.
 //! It provides information about model capabilities, versions, and requirements.
 
+pub mod aliases;
 pub mod api;
 pub mod connectors;
+pub mod discovery;
 pub mod health;
 pub mod persistence;
 pub mod storage;
+pub mod tokenizer;
 pub mod types;
 
 // Tests moved to tests/unit/modules/model_registry/
@@ -15,24 +18,31 @@ pub mod types;
 use std::sync::Arc;
 
 // Re-export types for easier access
+pub use aliases::{ModelAliases, ModelResolution, ModelRoute, ResolutionKind};
 pub use api::{create_model_registry_api, ModelRegistryApi};
 pub use connectors::{
     ChatCompletionChunk, ChatCompletionRequest, ChatCompletionResponse, ConnectorConfig,
     ConnectorError, ModelConnector, ModelConnectorFactory,
 };
+pub use discovery::{
+    DiscoveredModel, DiscoveryError, ModelDiscovery, ModelProposal, ProposalStatus, ProviderSync,
+};
 pub use health::{
     check_model_health, create_health_check_manager, HealthCheckConfig, HealthCheckManager,
     HealthCheckResult,
 };
+pub use persistence::SqliteModelRegistryPersistence;
 pub use persistence::{
-    create_file_persistent_registry, ModelRegistryPersistence, PersistenceConfig,
-    PersistentModelRegistry,
+    create_embedded_persistent_registry, create_file_persistent_registry, ModelRegistryPersistence,
+    PersistenceConfig, PersistentModelRegistry,
 };
 pub use storage::ModelRegistry;
+pub use tokenizer::{Tokenizer, TokenizerError, TokenizerRegistry};
 pub use types::{
     capabilities::ModelCapabilities,
     errors::RegistryError,
     filters::ModelFilter,
+    fine_tune::{FineTuneLineage, FineTuneStatus},
     model::{ModelMetadata, ModelType},
     status::ModelStatus,
 };
@@ -45,6 +55,7 @@ use std::sync::OnceLock;
 
 static GLOBAL_REGISTRY: OnceLock<ModelRegistryApi> = OnceLock::new();
 static GLOBAL_HEALTH_MANAGER: OnceLock<std::sync::Mutex<HealthCheckManager>> = OnceLock::new();
+static GLOBAL_TOKENIZERS: OnceLock<TokenizerRegistry> = OnceLock::new();
 
 /// Get the global Model Registry API instance
 pub fn global_registry() -> &'static ModelRegistryApi {
@@ -60,6 +71,21 @@ pub fn global_health_manager() -> &'static std::sync::Mutex<HealthCheckManager>
     })
 }
 
+/// Get the global tokenizer registry
+///
+/// Until configured tokenizers are installed, only the built-in OpenAI
+/// encodings are assigned.
+pub fn global_tokenizers() -> &'static TokenizerRegistry {
+    GLOBAL_TOKENIZERS.get_or_init(TokenizerRegistry::new)
+}
+
+/// Install the global tokenizer registry
+///
+/// Returns false if the registry was already in use.
+pub fn install_tokenizers(registry: TokenizerRegistry) -> bool {
+    GLOBAL_TOKENIZERS.set(registry).is_ok()
+}
+
 /// Start global health checks with default configuration
 pub fn start_global_health_checks() -> Result<(), String> {
     match global_health_manager().lock() {
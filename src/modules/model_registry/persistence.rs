@@ -2,8 +2,9 @@
 //!
 //! This module implements persistence for the Model Registry, allowing
 //! model metadata to be saved to and loaded from persistent storage.
-//! It provides a trait for different storage backends and a file-based
-//! implementation.
+//! It provides a trait for different storage backends, a file-based
+//! implementation and a SQLite implementation for the embedded storage
+//! profile.
 
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -11,8 +12,10 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
+use super::aliases::ModelAliases;
 use super::storage::ModelRegistry;
 use super::types::{ModelMetadata, RegistryError};
+use crate::config::StorageConfig;
 
 /// Persistence configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -221,6 +224,101 @@ impl ModelRegistryPersistence for FileModelRegistryPersistence {
     }
 }
 
+/// SQLite persistence of the embedded storage profile
+///
+/// Models are kept as JSON rows of the `registry_models` table, and each
+/// save replaces the table in a single transaction.
+pub struct SqliteModelRegistryPersistence {
+    db: crate::modules::common::SqliteDatabase,
+}
+
+impl SqliteModelRegistryPersistence {
+    /// Create a SQLite persistence, creating its table if needed
+    pub fn new(db: crate::modules::common::SqliteDatabase) -> Result<Self, RegistryError> {
+        db.with(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE IF NOT EXISTS registry_models (
+                    id TEXT PRIMARY KEY,
+                    data TEXT NOT NULL
+                )",
+            )
+        })
+        .map_err(sqlite_error)?;
+        Ok(Self { db })
+    }
+}
+
+fn sqlite_error(e: crate::modules::common::SqliteError) -> RegistryError {
+    RegistryError::StorageError(format!("SQLite error: {}", e))
+}
+
+impl ModelRegistryPersistence for SqliteModelRegistryPersistence {
+    fn save(&self, registry: &ModelRegistry) -> Result<(), RegistryError> {
+        let mut rows = Vec::new();
+        for model in registry.list_models() {
+            let json = serde_json::to_string(&model).map_err(|e| {
+                RegistryError::StorageError(format!("Failed to serialize registry: {}", e))
+            })?;
+            rows.push((model.id, json));
+        }
+
+        let count = rows.len();
+        self.db
+            .transaction(|conn| {
+                conn.execute("DELETE FROM registry_models", &[])?;
+                for (id, json) in rows {
+                    conn.execute(
+                        "INSERT INTO registry_models (id, data) VALUES (?1, ?2)",
+                        &[id.into(), json.into()],
+                    )?;
+                }
+                Ok(())
+            })
+            .map_err(sqlite_error)?;
+
+        debug!("Saved {} models to {}", count, self.db.path().display());
+        Ok(())
+    }
+
+    fn load(&self) -> Result<ModelRegistry, RegistryError> {
+        let rows = self
+            .db
+            .with(|conn| conn.query("SELECT data FROM registry_models ORDER BY id", &[]))
+            .map_err(sqlite_error)?;
+
+        let registry = ModelRegistry::new();
+        for json in rows
+            .into_iter()
+            .filter_map(|row| row.into_iter().next()?.into_text())
+        {
+            let model: ModelMetadata = serde_json::from_str(&json).map_err(|e| {
+                RegistryError::StorageError(format!("Failed to deserialize registry: {}", e))
+            })?;
+            registry.register_model(model).map_err(|e| {
+                RegistryError::StorageError(format!("Failed to register model during load: {}", e))
+            })?;
+        }
+
+        info!("Registry loaded from {}", self.db.path().display());
+        Ok(registry)
+    }
+
+    fn exists(&self) -> bool {
+        self.db
+            .with(|conn| conn.query("SELECT 1 FROM registry_models LIMIT 1", &[]))
+            .map(|rows| !rows.is_empty())
+            .unwrap_or(false)
+    }
+
+    fn clear(&self) -> Result<(), RegistryError> {
+        self.db
+            .with(|conn| conn.execute("DELETE FROM registry_models", &[]))
+            .map_err(sqlite_error)?;
+        info!("Registry cleared");
+        Ok(())
+    }
+}
+
 /// Persistent model registry
 pub struct PersistentModelRegistry {
     /// In-memory registry
@@ -331,6 +429,33 @@ pub fn create_file_persistent_registry(
     }
 }
 
+/// Create the persistent model registry of the embedded storage profile
+///
+/// Returns `None` unless the storage profile is embedded. The loaded
+/// registry resolves the given aliases and is saved every
+/// `registry_save_interval_secs`.
+pub fn create_embedded_persistent_registry(
+    storage: &StorageConfig,
+    aliases: ModelAliases,
+) -> Result<Option<PersistentModelRegistry>, RegistryError> {
+    if !storage.is_embedded() {
+        return Ok(None);
+    }
+
+    let db = crate::modules::common::SqliteDatabase::from_config(storage).map_err(sqlite_error)?;
+    let persistence: Arc<dyn ModelRegistryPersistence> =
+        Arc::new(SqliteModelRegistryPersistence::new(db)?);
+    let mut persistent_registry = PersistentModelRegistry {
+        registry: Arc::new(persistence.load()?.with_aliases(aliases)),
+        persistence,
+        auto_save_task: None,
+    };
+    if storage.registry_save_interval_secs > 0 {
+        persistent_registry.start_auto_save(storage.registry_save_interval_secs);
+    }
+    Ok(Some(persistent_registry))
+}
+
 #[cfg(all(test, not(feature = "production")))]
 mod tests {
     use super::*;
@@ -390,6 +515,38 @@ mod tests {
         assert!(loaded_registry.get_model("model-2").is_ok());
     }
 
+    #[test]
+    fn test_sqlite_persistence_save_load() {
+        let temp_dir = tempdir().unwrap();
+        let db = crate::modules::common::SqliteDatabase::open(
+            temp_dir.path().join("registry.db"),
+            std::time::Duration::from_secs(1),
+        )
+        .unwrap();
+        let persistence = SqliteModelRegistryPersistence::new(db).unwrap();
+        assert!(!persistence.exists());
+
+        let registry = ModelRegistry::new();
+        registry
+            .register_model(create_test_model("model-1", "openai"))
+            .unwrap();
+        registry
+            .register_model(create_test_model("model-2", "anthropic"))
+            .unwrap();
+        persistence.save(&registry).unwrap();
+
+        // Saving again replaces the stored models
+        registry.remove_model("model-2").unwrap();
+        persistence.save(&registry).unwrap();
+
+        let loaded_registry = persistence.load().unwrap();
+        assert_eq!(loaded_registry.count(), 1);
+        assert!(loaded_registry.get_model("model-1").is_ok());
+
+        persistence.clear().unwrap();
+        assert!(!persistence.exists());
+    }
+
     #[test]
     fn test_file_persistence_backup_rotation() {
         // Create a temporary directory for the test
@@ -7,8 +7,9 @@ use dashmap::DashMap;
 use std::sync::Arc;
 use tracing::{debug, error, info};
 
+use super::aliases::{ModelAliases, ModelResolution, ResolutionKind, MAX_ALIAS_DEPTH};
 use super::types::{
-    ModelFilter, ModelMetadata, ModelStatus, ModelType, RegistryError,
+    FineTuneStatus, ModelFilter, ModelMetadata, ModelStatus, ModelType, RegistryError,
 };
 
 /// Thread-safe in-memory storage for model metadata
@@ -19,6 +20,8 @@ pub struct ModelRegistry {
     models: Arc<DashMap<String, ModelMetadata>>,
     /// Model connectors
     connectors: Arc<DashMap<String, Arc<dyn super::connectors::ModelConnector>>>,
+    /// Aliases and wildcard routes of requested model names
+    aliases: Arc<ModelAliases>,
 }
 
 // Manual Debug implementation
@@ -30,6 +33,7 @@ impl std::fmt::Debug for ModelRegistry {
                 "connectors",
                 &format!("<{} connectors>", self.connectors.len()),
             )
+            .field("aliases", &self.aliases)
             .finish()
     }
 }
@@ -41,6 +45,78 @@ impl ModelRegistry {
         Self {
             models: Arc::new(DashMap::new()),
             connectors: Arc::new(DashMap::new()),
+            aliases: Arc::new(ModelAliases::new()),
+        }
+    }
+
+    /// Set the aliases and wildcard routes of requested model names
+    pub fn with_aliases(mut self, aliases: ModelAliases) -> Self {
+        self.aliases = Arc::new(aliases);
+        self
+    }
+
+    /// Resolve a requested model name to the model serving it
+    ///
+    /// Aliases are followed first. The resulting name is then looked up as a
+    /// registered model ID, as a registered model ID prefixed with its
+    /// provider (`openai/gpt-4o`), and against the wildcard routes, in that
+    /// order. Names matching nothing resolve to themselves.
+    pub fn resolve_model(&self, requested: &str) -> ModelResolution {
+        let mut name = requested;
+        let mut aliased = false;
+        for _ in 0..MAX_ALIAS_DEPTH {
+            if self.models.contains_key(name) {
+                break;
+            }
+            match self.aliases.target(name) {
+                Some(target) => {
+                    name = target;
+                    aliased = true;
+                }
+                None => break,
+            }
+        }
+
+        let resolution = |model: String, provider: Option<String>, kind| ModelResolution {
+            requested: requested.to_string(),
+            model,
+            provider,
+            kind,
+            aliased,
+        };
+
+        if let Some(model) = self.models.get(name) {
+            let kind = if aliased {
+                ResolutionKind::Alias
+            } else {
+                ResolutionKind::Exact
+            };
+            return resolution(name.to_string(), Some(model.provider.clone()), kind);
+        }
+
+        if let Some((provider, id)) = name.split_once('/') {
+            if let Some(model) = self
+                .models
+                .get(id)
+                .filter(|model| model.provider == provider)
+            {
+                return resolution(
+                    id.to_string(),
+                    Some(model.provider.clone()),
+                    ResolutionKind::ProviderPrefix,
+                );
+            }
+        }
+
+        if let Some((route, model)) = self.aliases.route(name) {
+            debug!("Model {} routed to provider {}", name, route.provider);
+            return resolution(model, Some(route.provider.clone()), ResolutionKind::Route);
+        }
+
+        if aliased {
+            resolution(name.to_string(), None, ResolutionKind::Alias)
+        } else {
+            ModelResolution::unresolved(requested)
         }
     }
 
@@ -64,8 +140,13 @@ impl ModelRegistry {
     }
 
     /// Register a new model in the registry
-    pub fn register_model(&self, metadata: ModelMetadata) -> Result<(), RegistryError> {
+    ///
+    /// A fine-tuned model takes the status of its lifecycle stage.
+    pub fn register_model(&self, mut metadata: ModelMetadata) -> Result<(), RegistryError> {
         let id = metadata.id.clone();
+        if let Some(lineage) = &metadata.fine_tune {
+            metadata.status = lineage.status.model_status();
+        }
 
         // Check if model already exists
         if self.models.contains_key(&id) {
@@ -339,6 +420,42 @@ impl ModelRegistry {
         model.set_status(status);
         self.update_model(model)
     }
+
+    /// Move a fine-tuned model to another lifecycle stage, updating its
+    /// status to match
+    ///
+    /// The deprecation notice is kept only for deprecated models.
+    pub fn update_fine_tune_status(
+        &self,
+        id: &str,
+        status: FineTuneStatus,
+        deprecation_notice: Option<String>,
+    ) -> Result<ModelMetadata, RegistryError> {
+        debug!("Updating fine-tune status for model {}: {:?}", id, status);
+        let mut model = self.get_model(id)?;
+        if !model.set_fine_tune_status(status, deprecation_notice) {
+            return Err(RegistryError::InvalidMetadata(format!(
+                "Model {} is not fine-tuned",
+                id
+            )));
+        }
+        self.update_model(model.clone())?;
+        Ok(model)
+    }
+
+    /// Find fine-tuned models, optionally only those trained from a base
+    /// model
+    pub fn find_fine_tunes(&self, base_model: Option<&str>) -> Vec<ModelMetadata> {
+        debug!("Finding fine-tuned models of {:?}", base_model);
+        self.models
+            .iter()
+            .filter(|item| match &item.value().fine_tune {
+                Some(lineage) => base_model.is_none_or(|base| lineage.base_model == base),
+                None => false,
+            })
+            .map(|item| item.value().clone())
+            .collect()
+    }
 }
 
 impl Default for ModelRegistry {
@@ -478,6 +595,55 @@ mod tests {
         assert_eq!(filtered[0].id, "gpt-4");
     }
 
+    #[test]
+    fn test_resolve_model() {
+        let registry = ModelRegistry::new().with_aliases(
+            ModelAliases::new()
+                .with_alias("gpt-4", "gpt-4o-2024-08-06")
+                .with_alias("fast", "anthropic/claude-3-haiku")
+                .with_alias("loop-a", "loop-b")
+                .with_alias("loop-b", "loop-a")
+                .with_route("anthropic/*", "anthropic"),
+        );
+        registry
+            .register_model(create_test_model("gpt-4o-2024-08-06", "openai"))
+            .unwrap();
+        registry
+            .register_model(create_test_model("claude-3-haiku", "anthropic"))
+            .unwrap();
+
+        let exact = registry.resolve_model("claude-3-haiku");
+        assert_eq!(exact.kind, ResolutionKind::Exact);
+        assert!(!exact.is_rewritten());
+
+        let alias = registry.resolve_model("gpt-4");
+        assert_eq!(alias.kind, ResolutionKind::Alias);
+        assert_eq!(alias.model, "gpt-4o-2024-08-06");
+        assert_eq!(alias.provider.as_deref(), Some("openai"));
+
+        let prefixed = registry.resolve_model("fast");
+        assert_eq!(prefixed.kind, ResolutionKind::ProviderPrefix);
+        assert_eq!(prefixed.model, "claude-3-haiku");
+        assert!(prefixed.aliased);
+
+        // Prefixes of another provider are not stripped
+        let routed = registry.resolve_model("anthropic/claude-3-opus");
+        assert_eq!(routed.kind, ResolutionKind::Route);
+        assert_eq!(routed.model, "claude-3-opus");
+        assert_eq!(routed.provider.as_deref(), Some("anthropic"));
+        assert_eq!(
+            registry.resolve_model("openai/claude-3-haiku").kind,
+            ResolutionKind::Unresolved
+        );
+
+        // Alias cycles end without resolving
+        assert_eq!(registry.resolve_model("loop-a").kind, ResolutionKind::Alias);
+        assert_eq!(
+            registry.resolve_model("unknown"),
+            ModelResolution::unresolved("unknown")
+        );
+    }
+
     #[test]
     fn test_registry_clear() {
         let registry = ModelRegistry::new();
@@ -768,4 +934,55 @@ mod tests {
         assert_eq!(filtered3.len(), 1);
         assert_eq!(filtered3[0].id, "model3");
     }
+
+    #[test]
+    fn test_fine_tune_lifecycle() {
+        use crate::modules::model_registry::types::FineTuneLineage;
+
+        let registry = ModelRegistry::new();
+        let mut model = create_test_model("ft:gpt-4o-mini:support", "openai");
+        model.set_status(ModelStatus::Available);
+        model.fine_tune = Some(FineTuneLineage::new(
+            "gpt-4o-mini",
+            "ftjob-123",
+            "sha256:abc",
+            "support-team",
+        ));
+        registry.register_model(model).unwrap();
+        registry
+            .register_model(create_test_model("gpt-4o-mini", "openai"))
+            .unwrap();
+
+        // A model in training is not routed to
+        let model = registry.get_model("ft:gpt-4o-mini:support").unwrap();
+        assert_eq!(model.status, ModelStatus::Maintenance);
+        assert_eq!(registry.find_fine_tunes(None).len(), 1);
+        assert_eq!(registry.find_fine_tunes(Some("gpt-4o-mini")).len(), 1);
+        assert!(registry.find_fine_tunes(Some("gpt-4o")).is_empty());
+
+        let model = registry
+            .update_fine_tune_status("ft:gpt-4o-mini:support", FineTuneStatus::Ready, None)
+            .unwrap();
+        assert!(model.is_available());
+        assert_eq!(model.deprecation_warning(), None);
+
+        let model = registry
+            .update_fine_tune_status(
+                "ft:gpt-4o-mini:support",
+                FineTuneStatus::Deprecated,
+                Some("use ft:gpt-4o-mini:support-v2".to_string()),
+            )
+            .unwrap();
+        assert_eq!(model.status, ModelStatus::Deprecated);
+        assert_eq!(
+            model.deprecation_warning().as_deref(),
+            Some("Model ft:gpt-4o-mini:support is deprecated: use ft:gpt-4o-mini:support-v2")
+        );
+
+        // Only fine-tuned models have a lifecycle
+        assert!(matches!(
+            registry.update_fine_tune_status("gpt-4o-mini", FineTuneStatus::Ready, None),
+            Err(RegistryError::InvalidMetadata(_))
+        ));
+    }
 }
@@ -0,0 +1,102 @@
+//! HuggingFace Tokenizers
+//!
+//! Tokenizers loaded from a HuggingFace `tokenizer.json`, as shipped with
+//! most open-weight models.
+
+use super::{Tokenizer, TokenizerError};
+
+/// A HuggingFace tokenizer
+pub struct HuggingFaceTokenizer {
+    name: String,
+    tokenizer: tokenizers::Tokenizer,
+}
+
+impl HuggingFaceTokenizer {
+    /// Wrap a loaded tokenizer
+    pub fn new(name: impl Into<String>, tokenizer: tokenizers::Tokenizer) -> Self {
+        Self {
+            name: name.into(),
+            tokenizer,
+        }
+    }
+
+    /// Load a `tokenizer.json`
+    pub fn from_file(name: impl Into<String>, path: &str) -> Result<Self, TokenizerError> {
+        let name = name.into();
+        let tokenizer =
+            tokenizers::Tokenizer::from_file(path).map_err(|e| TokenizerError::Load {
+                name: name.clone(),
+                message: format!("{}: {}", path, e),
+            })?;
+        Ok(Self::new(name, tokenizer))
+    }
+
+    fn encode(&self, text: &str) -> Option<tokenizers::Encoding> {
+        self.tokenizer.encode(text, false).ok()
+    }
+}
+
+impl Tokenizer for HuggingFaceTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        match self.encode(text) {
+            Some(encoding) => encoding.len(),
+            None => super::HeuristicTokenizer.count(text),
+        }
+    }
+
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let Some(encoding) = self.encode(text) else {
+            return super::HeuristicTokenizer.truncate(text, max_tokens);
+        };
+        if encoding.len() <= max_tokens {
+            return text.to_string();
+        }
+        // Cut the original text at the end of the last kept token
+        let end = match max_tokens {
+            0 => 0,
+            n => encoding.get_offsets()[n - 1].1,
+        };
+        text.get(..end).unwrap_or_default().to_string()
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    /// Word-level tokenizer splitting on whitespace
+    pub(crate) const WORD_LEVEL: &str = r#"{
+        "version": "1.0",
+        "truncation": null,
+        "padding": null,
+        "added_tokens": [],
+        "normalizer": null,
+        "pre_tokenizer": {"type": "Whitespace"},
+        "post_processor": null,
+        "decoder": null,
+        "model": {
+            "type": "WordLevel",
+            "vocab": {"hello": 0, "world": 1, "[UNK]": 2},
+            "unk_token": "[UNK]"
+        }
+    }"#;
+
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.json");
+        std::fs::write(&path, WORD_LEVEL).unwrap();
+
+        let tokenizer = HuggingFaceTokenizer::from_file("words", path.to_str().unwrap()).unwrap();
+        assert_eq!(tokenizer.count("hello brave new world"), 4);
+        assert_eq!(
+            tokenizer.truncate("hello brave  new world", 3),
+            "hello brave  new"
+        );
+        assert_eq!(tokenizer.truncate("hello", 0), "");
+    }
+}
@@ -0,0 +1,279 @@
+//! Tokenizers
+//!
+//! This module counts and truncates text in the tokens of the model that
+//! will read it. Each model family is assigned a tokenizer: tiktoken BPE
+//! ranks, a HuggingFace `tokenizer.json` or a SentencePiece model, loaded
+//! from local files. OpenAI models use the built-in tiktoken encodings and
+//! models without a tokenizer fall back to an estimate of 4 characters per
+//! token.
+
+mod huggingface;
+mod sentencepiece;
+mod tiktoken;
+
+use std::fmt;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+pub use huggingface::HuggingFaceTokenizer;
+pub use tiktoken::TiktokenTokenizer;
+
+use crate::config::{TokenizerConfig, TokenizerKind};
+use crate::modules::llm_proxy::transform::model_matches;
+
+/// Errors of tokenizers
+#[derive(Debug, Error)]
+pub enum TokenizerError {
+    #[error("Failed to load tokenizer '{name}': {message}")]
+    Load { name: String, message: String },
+
+    #[error("Unknown tiktoken encoding '{0}'")]
+    UnknownEncoding(String),
+
+    #[error("Tokenizer '{0}' needs a path")]
+    MissingPath(String),
+}
+
+/// Counts and truncates text in the tokens of a model
+pub trait Tokenizer: Send + Sync {
+    /// Tokenizer name
+    fn name(&self) -> &str;
+
+    /// Whether counts are exact rather than estimated
+    fn exact(&self) -> bool {
+        true
+    }
+
+    /// Count the tokens of a text
+    fn count(&self, text: &str) -> usize;
+
+    /// Cut a text down to at most `max_tokens` tokens
+    fn truncate(&self, text: &str, max_tokens: usize) -> String;
+}
+
+/// Estimate of 4 characters per token, for models without a tokenizer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+/// Characters per token assumed by the heuristic tokenizer
+const CHARS_PER_TOKEN: usize = 4;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn exact(&self) -> bool {
+        false
+    }
+
+    fn count(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(CHARS_PER_TOKEN)
+    }
+
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        text.chars()
+            .take(max_tokens.saturating_mul(CHARS_PER_TOKEN))
+            .collect()
+    }
+}
+
+/// Tokenizers assigned to model families
+///
+/// Configured tokenizers are matched first, in order, then the built-in
+/// OpenAI encodings; other models use the heuristic tokenizer.
+pub struct TokenizerRegistry {
+    /// Tokenizers by model pattern, in the order they are matched
+    assignments: Vec<(String, Arc<dyn Tokenizer>)>,
+    /// Built-in assignments, matched after the configured ones
+    builtin: Vec<(String, Arc<dyn Tokenizer>)>,
+    fallback: Arc<dyn Tokenizer>,
+}
+
+impl TokenizerRegistry {
+    /// Create a registry with the built-in OpenAI encodings
+    pub fn new() -> Self {
+        let o200k: Arc<dyn Tokenizer> = Arc::new(TiktokenTokenizer::o200k_base());
+        let cl100k: Arc<dyn Tokenizer> = Arc::new(TiktokenTokenizer::cl100k_base());
+        let builtin = [
+            ("gpt-4o*", &o200k),
+            ("gpt-4.1*", &o200k),
+            ("gpt-5*", &o200k),
+            ("o1*", &o200k),
+            ("o3*", &o200k),
+            ("o4*", &o200k),
+            ("gpt-4*", &cl100k),
+            ("gpt-3.5*", &cl100k),
+            ("text-embedding-*", &cl100k),
+        ]
+        .into_iter()
+        .map(|(pattern, tokenizer)| (pattern.to_string(), tokenizer.clone()))
+        .collect();
+
+        Self {
+            assignments: Vec::new(),
+            builtin,
+            fallback: Arc::new(HeuristicTokenizer),
+        }
+    }
+
+    /// Create a registry with configured tokenizers, loading their files
+    pub fn from_config(configs: &[TokenizerConfig]) -> Result<Self, TokenizerError> {
+        let mut registry = Self::new();
+        for config in configs {
+            let tokenizer = load(config)?;
+            for pattern in &config.models {
+                registry.assign(pattern, tokenizer.clone());
+            }
+        }
+        Ok(registry)
+    }
+
+    /// Assign a tokenizer to the models matching a pattern, ahead of the
+    /// built-in assignments
+    pub fn assign(&mut self, pattern: &str, tokenizer: Arc<dyn Tokenizer>) {
+        self.assignments.push((pattern.to_string(), tokenizer));
+    }
+
+    /// Tokenizer of a model
+    pub fn for_model(&self, model: &str) -> Arc<dyn Tokenizer> {
+        self.assignments
+            .iter()
+            .chain(&self.builtin)
+            .find(|(pattern, _)| model_matches(pattern, model))
+            .map(|(_, tokenizer)| tokenizer.clone())
+            .unwrap_or_else(|| self.fallback.clone())
+    }
+
+    /// Count the tokens of a text for a model
+    pub fn count_tokens(&self, model: &str, text: &str) -> usize {
+        self.for_model(model).count(text)
+    }
+
+    /// Cut a text down to at most `max_tokens` tokens of a model
+    pub fn truncate(&self, model: &str, text: &str, max_tokens: usize) -> String {
+        self.for_model(model).truncate(text, max_tokens)
+    }
+}
+
+impl Default for TokenizerRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for TokenizerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.assignments
+                    .iter()
+                    .chain(&self.builtin)
+                    .map(|(pattern, tokenizer)| (pattern, tokenizer.name())),
+            )
+            .finish()
+    }
+}
+
+/// Load a configured tokenizer
+fn load(config: &TokenizerConfig) -> Result<Arc<dyn Tokenizer>, TokenizerError> {
+    let name = config.name.clone();
+    if let (TokenizerKind::Tiktoken, Some(encoding)) = (config.kind, &config.encoding) {
+        return Ok(Arc::new(TiktokenTokenizer::builtin(encoding)?));
+    }
+    let path = config
+        .path
+        .as_deref()
+        .ok_or_else(|| TokenizerError::MissingPath(name.clone()))?;
+    Ok(match config.kind {
+        TokenizerKind::Tiktoken => Arc::new(TiktokenTokenizer::from_file(
+            name,
+            path,
+            config.pattern.as_deref(),
+        )?),
+        TokenizerKind::HuggingFace => Arc::new(HuggingFaceTokenizer::from_file(name, path)?),
+        TokenizerKind::SentencePiece => Arc::new(sentencepiece::from_file(name, path)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(kind: TokenizerKind, path: &std::path::Path, models: &[&str]) -> TokenizerConfig {
+        TokenizerConfig {
+            name: "custom".to_string(),
+            kind,
+            path: Some(path.to_string_lossy().into_owned()),
+            encoding: None,
+            pattern: None,
+            models: models.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_builtin_assignments() {
+        let registry = TokenizerRegistry::new();
+        assert_eq!(registry.for_model("gpt-4o-mini").name(), "o200k_base");
+        assert_eq!(registry.for_model("gpt-4-turbo").name(), "cl100k_base");
+        assert_eq!(registry.for_model("claude-3-opus").name(), "heuristic");
+        assert!(!registry.for_model("claude-3-opus").exact());
+
+        assert_eq!(registry.count_tokens("gpt-4o", "hello world"), 2);
+        assert_eq!(registry.count_tokens("claude-3-opus", "hello world"), 3);
+        assert_eq!(
+            registry.truncate("gpt-4", "The quick brown fox jumps", 3),
+            "The quick brown"
+        );
+    }
+
+    #[test]
+    fn test_configured_tokenizer_takes_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.json");
+        std::fs::write(&path, huggingface::tests::WORD_LEVEL).unwrap();
+
+        let registry = TokenizerRegistry::from_config(&[config(
+            TokenizerKind::HuggingFace,
+            &path,
+            &["llama-*", "gpt-4o-custom"],
+        )])
+        .unwrap();
+        assert_eq!(registry.for_model("llama-3-70b").name(), "custom");
+        assert_eq!(registry.for_model("gpt-4o-custom").name(), "custom");
+        assert_eq!(registry.for_model("gpt-4o").name(), "o200k_base");
+        assert_eq!(registry.count_tokens("llama-3-70b", "hello world hello"), 3);
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        let missing = TokenizerConfig {
+            path: None,
+            ..config(TokenizerKind::SentencePiece, std::path::Path::new(""), &[])
+        };
+        assert!(matches!(
+            TokenizerRegistry::from_config(&[missing]),
+            Err(TokenizerError::MissingPath(_))
+        ));
+
+        let unknown = TokenizerConfig {
+            encoding: Some("p50k_base".to_string()),
+            ..config(TokenizerKind::Tiktoken, std::path::Path::new(""), &[])
+        };
+        assert!(matches!(
+            TokenizerRegistry::from_config(&[unknown]),
+            Err(TokenizerError::UnknownEncoding(_))
+        ));
+
+        let absent = config(
+            TokenizerKind::HuggingFace,
+            std::path::Path::new("/nonexistent/tokenizer.json"),
+            &[],
+        );
+        assert!(matches!(
+            TokenizerRegistry::from_config(&[absent]),
+            Err(TokenizerError::Load { .. })
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+//! SentencePiece Tokenizers
+//!
+//! Tokenizers loaded from a SentencePiece `.model` file, as used by Llama 2,
+//! Mistral and Gemma. The pieces and scores are read from the protobuf and
+//! tokenized with the unigram algorithm; models trained with BPE are
+//! approximated by the same scoring, which can split rare words differently.
+
+use std::fs;
+
+use prost::Message;
+use tokenizers::models::unigram::Unigram;
+use tokenizers::pre_tokenizers::metaspace::{Metaspace, PrependScheme};
+
+use super::{HuggingFaceTokenizer, TokenizerError};
+
+/// Piece types of the SentencePiece protobuf
+const TYPE_UNKNOWN: i32 = 2;
+const TYPE_BYTE: i32 = 6;
+
+/// Word boundary marker of SentencePiece
+const SPACE: char = '\u{2581}';
+
+/// The parts of a SentencePiece `ModelProto` needed to tokenize
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct ModelProto {
+    #[prost(message, repeated, tag = "1")]
+    pub pieces: Vec<SentencePiece>,
+}
+
+/// A piece of a SentencePiece model
+#[derive(Clone, PartialEq, Message)]
+pub(crate) struct SentencePiece {
+    #[prost(string, optional, tag = "1")]
+    pub piece: Option<String>,
+    #[prost(float, optional, tag = "2")]
+    pub score: Option<f32>,
+    #[prost(int32, optional, tag = "3")]
+    pub r#type: Option<i32>,
+}
+
+/// Load a SentencePiece `.model`
+pub fn from_file(name: String, path: &str) -> Result<HuggingFaceTokenizer, TokenizerError> {
+    let error = |message: String| TokenizerError::Load {
+        name: name.clone(),
+        message,
+    };
+    let bytes = fs::read(path).map_err(|e| error(format!("{}: {}", path, e)))?;
+    let model = ModelProto::decode(bytes.as_slice()).map_err(|e| error(e.to_string()))?;
+
+    let mut unk_id = None;
+    let mut byte_fallback = false;
+    let vocab: Vec<(String, f64)> = model
+        .pieces
+        .iter()
+        .enumerate()
+        .map(|(id, piece)| {
+            match piece.r#type {
+                Some(TYPE_UNKNOWN) => unk_id = unk_id.or(Some(id)),
+                Some(TYPE_BYTE) => byte_fallback = true,
+                _ => {}
+            }
+            (
+                piece.piece.clone().unwrap_or_default(),
+                piece.score.unwrap_or_default() as f64,
+            )
+        })
+        .collect();
+    if vocab.is_empty() {
+        return Err(error("model has no pieces".to_string()));
+    }
+
+    let unigram = Unigram::from(vocab, unk_id, byte_fallback).map_err(|e| error(e.to_string()))?;
+    // The same metaspace handling splits words and restores their spaces
+    let metaspace = Metaspace::new(SPACE, PrependScheme::Always, true);
+    let mut tokenizer = tokenizers::Tokenizer::new(unigram);
+    tokenizer
+        .with_pre_tokenizer(Some(metaspace.clone()))
+        .with_decoder(Some(metaspace));
+    Ok(HuggingFaceTokenizer::new(name, tokenizer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::tokenizer::Tokenizer;
+
+    fn piece(piece: &str, score: f32, r#type: i32) -> SentencePiece {
+        SentencePiece {
+            piece: Some(piece.to_string()),
+            score: Some(score),
+            r#type: Some(r#type),
+        }
+    }
+
+    #[test]
+    fn test_from_file() {
+        let model = ModelProto {
+            pieces: vec![
+                piece("<unk>", 0.0, TYPE_UNKNOWN),
+                piece("\u{2581}hello", -1.0, 1),
+                piece("\u{2581}world", -1.0, 1),
+                piece("\u{2581}", -2.0, 1),
+                piece("h", -3.0, 1),
+                piece("i", -3.0, 1),
+            ],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tokenizer.model");
+        fs::write(&path, model.encode_to_vec()).unwrap();
+
+        let tokenizer = from_file("llama".to_string(), path.to_str().unwrap()).unwrap();
+        assert_eq!(tokenizer.count("hello world"), 2);
+        assert_eq!(tokenizer.count("hi"), 3);
+        assert_eq!(tokenizer.truncate("hello world hello", 2), "hello world");
+
+        fs::write(&path, b"not a model").unwrap();
+        assert!(from_file("llama".to_string(), path.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+//! tiktoken Tokenizers
+//!
+//! BPE tokenizers in the tiktoken format, either the built-in OpenAI
+//! encodings or ranks loaded from a `.tiktoken` file, where each line holds
+//! a base64-encoded token and its rank.
+
+use std::collections::HashMap;
+use std::fs;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use tiktoken_rs::{CoreBPE, Rank};
+
+use super::{Tokenizer, TokenizerError};
+
+/// Pre-tokenization pattern of `cl100k_base`, the default for `.tiktoken`
+/// files
+const CL100K_PATTERN: &str = r"(?i:'s|'t|'re|'ve|'m|'ll|'d)|[^\r\n\p{L}\p{N}]?\p{L}+|\p{N}{1,3}| ?[^\s\p{L}\p{N}]+[\r\n]*|\s*[\r\n]+|\s+(?!\S)|\s+";
+
+/// Ranks of a tiktoken tokenizer
+enum Ranks {
+    Cl100kBase,
+    O200kBase,
+    File(Box<CoreBPE>),
+}
+
+/// A tiktoken BPE tokenizer
+pub struct TiktokenTokenizer {
+    name: String,
+    ranks: Ranks,
+}
+
+impl TiktokenTokenizer {
+    /// The `cl100k_base` encoding of GPT-4 and GPT-3.5 models
+    pub fn cl100k_base() -> Self {
+        Self {
+            name: "cl100k_base".to_string(),
+            ranks: Ranks::Cl100kBase,
+        }
+    }
+
+    /// The `o200k_base` encoding of GPT-4o and later models
+    pub fn o200k_base() -> Self {
+        Self {
+            name: "o200k_base".to_string(),
+            ranks: Ranks::O200kBase,
+        }
+    }
+
+    /// A built-in encoding by name
+    pub fn builtin(encoding: &str) -> Result<Self, TokenizerError> {
+        match encoding {
+            "cl100k_base" => Ok(Self::cl100k_base()),
+            "o200k_base" => Ok(Self::o200k_base()),
+            _ => Err(TokenizerError::UnknownEncoding(encoding.to_string())),
+        }
+    }
+
+    /// Load the ranks of a `.tiktoken` file
+    ///
+    /// `pattern` splits text into the pieces BPE merges; it defaults to the
+    /// one of `cl100k_base`.
+    pub fn from_file(
+        name: impl Into<String>,
+        path: &str,
+        pattern: Option<&str>,
+    ) -> Result<Self, TokenizerError> {
+        let name = name.into();
+        let error = |message: String| TokenizerError::Load {
+            name: name.clone(),
+            message,
+        };
+        let contents = fs::read_to_string(path).map_err(|e| error(format!("{}: {}", path, e)))?;
+        let encoder = parse_ranks(&contents).map_err(error)?;
+        let bpe = CoreBPE::new(
+            encoder,
+            HashMap::default(),
+            pattern.unwrap_or(CL100K_PATTERN),
+        )
+        .map_err(|e| error(e.to_string()))?;
+        Ok(Self {
+            ranks: Ranks::File(Box::new(bpe)),
+            name,
+        })
+    }
+
+    fn bpe(&self) -> &CoreBPE {
+        match &self.ranks {
+            Ranks::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Ranks::O200kBase => tiktoken_rs::o200k_base_singleton(),
+            Ranks::File(bpe) => bpe,
+        }
+    }
+}
+
+/// Parse the lines of a `.tiktoken` file
+///
+/// Every single byte must have a rank, so any text can be encoded.
+fn parse_ranks<S>(contents: &str) -> Result<HashMap<Vec<u8>, Rank, S>, String>
+where
+    S: std::hash::BuildHasher + Default,
+{
+    let mut encoder = HashMap::default();
+    let mut ranks = std::collections::HashSet::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || format!("invalid line {}", number + 1);
+        let (token, rank) = line.split_once(' ').ok_or_else(invalid)?;
+        let token = STANDARD.decode(token).map_err(|_| invalid())?;
+        let rank: Rank = rank.trim().parse().map_err(|_| invalid())?;
+        if !ranks.insert(rank) {
+            return Err(format!("duplicate rank {} on line {}", rank, number + 1));
+        }
+        encoder.insert(token, rank);
+    }
+    if let Some(byte) = (0..=u8::MAX).find(|byte| !encoder.contains_key(&vec![*byte])) {
+        return Err(format!("byte {:#04x} has no rank", byte));
+    }
+    Ok(encoder)
+}
+
+impl Tokenizer for TiktokenTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.bpe().encode_ordinary(text).len()
+    }
+
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let bpe = self.bpe();
+        let tokens = bpe.encode_ordinary(text);
+        if tokens.len() <= max_tokens {
+            return text.to_string();
+        }
+        // A cut inside a multi-byte character doesn't decode; drop the
+        // partial character's tokens
+        (0..=max_tokens)
+            .rev()
+            .find_map(|end| bpe.decode(tokens[..end].to_vec()).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ranks of every byte, then of `ab` and ` ab`
+    fn ranks() -> String {
+        let mut lines: Vec<String> = (0..=u8::MAX)
+            .map(|byte| format!("{} {}", STANDARD.encode([byte]), byte))
+            .collect();
+        lines.push(format!("{} 256", STANDARD.encode("ab")));
+        lines.push(format!("{} 257", STANDARD.encode(" ab")));
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom.tiktoken");
+        fs::write(&path, ranks()).unwrap();
+
+        let tokenizer =
+            TiktokenTokenizer::from_file("custom", path.to_str().unwrap(), None).unwrap();
+        assert_eq!(tokenizer.count("ab ab ab"), 3);
+        assert_eq!(tokenizer.count("abc"), 2);
+        assert_eq!(tokenizer.truncate("ab ab ab", 2), "ab ab");
+        assert_eq!(tokenizer.truncate("ab", 5), "ab");
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        let parse = |contents: &str| {
+            parse_ranks::<std::collections::hash_map::RandomState>(contents).unwrap_err()
+        };
+        assert_eq!(parse("YQ== 0\nnot-base64! 1"), "invalid line 2");
+        assert_eq!(parse("YQ== 0\nYg== 0"), "duplicate rank 0 on line 2");
+        assert_eq!(parse("YQ== 97"), "byte 0x00 has no rank");
+    }
+
+    #[test]
+    fn test_truncate_keeps_whole_characters() {
+        let tokenizer = TiktokenTokenizer::cl100k_base();
+        let text = "héllo wörld 日本語";
+        for max in 0..tokenizer.count(text) {
+            let truncated = tokenizer.truncate(text, max);
+            assert!(text.starts_with(&truncated));
+            assert!(tokenizer.count(&truncated) <= max);
+        }
+    }
+}
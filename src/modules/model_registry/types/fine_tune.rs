@@ -0,0 +1,75 @@
+//! Fine-tuned model lineage
+
+use serde::{Deserialize, Serialize};
+
+use crate::modules::model_registry::types::status::ModelStatus;
+
+/// Lifecycle stage of a fine-tuned model
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FineTuneStatus {
+    /// The training job is still running; the model can't serve requests
+    Training,
+    /// The model is trained and serves requests
+    Ready,
+    /// The model still serves requests, with a warning to move off it
+    Deprecated,
+}
+
+impl FineTuneStatus {
+    /// Registry status of a model at this stage
+    ///
+    /// Models in training are put in maintenance, which health checks don't
+    /// lift, so they aren't routed to before they are ready.
+    pub fn model_status(&self) -> ModelStatus {
+        match self {
+            FineTuneStatus::Training => ModelStatus::Maintenance,
+            FineTuneStatus::Ready => ModelStatus::Available,
+            FineTuneStatus::Deprecated => ModelStatus::Deprecated,
+        }
+    }
+}
+
+/// Where a fine-tuned model comes from, and its lifecycle stage
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FineTuneLineage {
+    /// Model the fine-tune was trained from
+    pub base_model: String,
+    /// Provider's ID of the training job
+    pub training_job_id: String,
+    /// Hash of the training dataset
+    pub dataset_hash: String,
+    /// Team or user owning the model
+    pub owner: String,
+    /// Lifecycle stage
+    pub status: FineTuneStatus,
+    /// What callers of a deprecated model should do instead, e.g. the model
+    /// replacing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_notice: Option<String>,
+}
+
+impl FineTuneLineage {
+    /// Lineage of a model in training
+    pub fn new(
+        base_model: impl Into<String>,
+        training_job_id: impl Into<String>,
+        dataset_hash: impl Into<String>,
+        owner: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_model: base_model.into(),
+            training_job_id: training_job_id.into(),
+            dataset_hash: dataset_hash.into(),
+            owner: owner.into(),
+            status: FineTuneStatus::Training,
+            deprecation_notice: None,
+        }
+    }
+
+    /// Set the lifecycle stage
+    pub fn with_status(mut self, status: FineTuneStatus) -> Self {
+        self.status = status;
+        self
+    }
+}
@@ -7,6 +7,7 @@
 pub mod capabilities;
 pub mod errors;
 pub mod filters;
+pub mod fine_tune;
 pub mod formats;
 pub mod health;
 pub mod model;
@@ -18,6 +19,7 @@ pub mod version;
 pub use capabilities::{FineTuningCapabilities, ModelCapabilities, RateLimits};
 pub use errors::RegistryError;
 pub use filters::ModelFilter;
+pub use fine_tune::{FineTuneLineage, FineTuneStatus};
 pub use formats::{InputFormat, OutputFormat};
 pub use health::ModelHealthStatus;
 pub use model::{ModelMetadata, ModelType};
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::modules::model_registry::types::capabilities::ModelCapabilities;
+use crate::modules::model_registry::types::fine_tune::{FineTuneLineage, FineTuneStatus};
 use crate::modules::model_registry::types::status::ModelStatus;
 
 /// Model type classification
@@ -50,6 +51,9 @@ pub struct ModelMetadata {
     pub status: ModelStatus,
     /// Endpoint URL for the model
     pub endpoint: String,
+    /// Region hosting the model endpoint (e.g. "eu-west-1")
+    #[serde(default)]
+    pub region: Option<String>,
     /// Authentication key for the model (if applicable)
     #[serde(skip_serializing)]
     pub auth_key: Option<String>,
@@ -61,6 +65,9 @@ pub struct ModelMetadata {
     pub updated_at: chrono::DateTime<chrono::Utc>,
     /// Additional metadata as key-value pairs
     pub additional_metadata: HashMap<String, String>,
+    /// Lineage and lifecycle stage, for fine-tuned models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fine_tune: Option<FineTuneLineage>,
 }
 
 impl ModelMetadata {
@@ -83,11 +90,13 @@ impl ModelMetadata {
             capabilities: ModelCapabilities::default(),
             status: ModelStatus::Unknown,
             endpoint,
+            region: None,
             auth_key: None,
             last_checked: None,
             created_at: now,
             updated_at: now,
             additional_metadata: HashMap::new(),
+            fine_tune: None,
         }
     }
 
@@ -101,6 +110,49 @@ impl ModelMetadata {
         matches!(self.status, ModelStatus::Deprecated)
     }
 
+    /// Warning for callers of a deprecated model, if it is deprecated
+    pub fn deprecation_warning(&self) -> Option<String> {
+        if !self.is_deprecated() {
+            return None;
+        }
+        let notice = self
+            .fine_tune
+            .as_ref()
+            .and_then(|lineage| lineage.deprecation_notice.as_deref());
+        Some(match notice {
+            Some(notice) => format!("Model {} is deprecated: {}", self.id, notice),
+            None => format!("Model {} is deprecated", self.id),
+        })
+    }
+
+    /// Set the lineage of a fine-tuned model, taking the status of its
+    /// lifecycle stage
+    pub fn set_fine_tune(&mut self, lineage: FineTuneLineage) {
+        self.status = lineage.status.model_status();
+        self.fine_tune = Some(lineage);
+        self.updated_at = chrono::Utc::now();
+    }
+
+    /// Move a fine-tuned model to another lifecycle stage
+    ///
+    /// Returns false, leaving the model unchanged, when it isn't fine-tuned.
+    pub fn set_fine_tune_status(
+        &mut self,
+        status: FineTuneStatus,
+        deprecation_notice: Option<String>,
+    ) -> bool {
+        let Some(lineage) = self.fine_tune.as_mut() else {
+            return false;
+        };
+        lineage.status = status;
+        lineage.deprecation_notice = match status {
+            FineTuneStatus::Deprecated => deprecation_notice,
+            _ => None,
+        };
+        self.set_status(status.model_status());
+        true
+    }
+
     /// Set the model status and update the last_checked timestamp
     pub fn set_status(&mut self, status: ModelStatus) {
         self.status = status;
@@ -132,6 +184,12 @@ impl ModelMetadata {
         self.updated_at = chrono::Utc::now();
     }
 
+    /// Update the hosting region
+    pub fn set_region(&mut self, region: Option<String>) {
+        self.region = region;
+        self.updated_at = chrono::Utc::now();
+    }
+
     /// Update the authentication key
     pub fn set_auth_key(&mut self, auth_key: Option<String>) {
         self.auth_key = auth_key;
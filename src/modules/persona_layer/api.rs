@@ -0,0 +1,110 @@
+//! Persona API
+//!
+//! This module lists the personas of the persona directory over HTTP, so
+//! they can be inspected on a running deployment. Callers need the viewer
+//! admin role.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use utoipa::OpenApi;
+
+use super::PersonaDirectory;
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{self, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// State of the persona API
+struct PersonaApiState {
+    personas: Arc<PersonaDirectory>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// OpenAPI description of the persona API
+#[derive(OpenApi)]
+#[openapi(paths(list_personas, get_persona))]
+pub struct PersonaApiDoc;
+
+/// Create a router for the persona API
+pub fn create_persona_router(personas: Arc<PersonaDirectory>, proxy: ProxyConfig) -> Router {
+    let state = Arc::new(PersonaApiState { personas, proxy });
+    Router::new()
+        .route("/v1/admin/personas", get(list_personas))
+        .route("/v1/admin/personas/{id}", get(get_persona))
+        .with_state(state)
+}
+
+/// List the loaded personas
+#[utoipa::path(
+    get,
+    path = "/v1/admin/personas",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Personas under `personas`", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn list_personas(State(state): State<Arc<PersonaApiState>>, headers: HeaderMap) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    let personas: Vec<_> = state
+        .personas
+        .list()
+        .iter()
+        .map(|persona| {
+            json!({
+                "id": persona.id,
+                "name": persona.name,
+                "description": persona.description,
+            })
+        })
+        .collect();
+    Json(json!({ "personas": personas })).into_response()
+}
+
+/// Get a persona, including its prompt template and guardrails
+#[utoipa::path(
+    get,
+    path = "/v1/admin/personas/{id}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(("id" = String, Path, description = "Persona ID")),
+    responses(
+        (status = 200, description = "The persona", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown persona", body = ApiError)
+    )
+)]
+async fn get_persona(
+    State(state): State<Arc<PersonaApiState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Viewer) {
+        return e.into_response();
+    }
+    match state.personas.get(&id) {
+        Some(persona) => Json(persona).into_response(),
+        None => {
+            let body = json!({
+                "error": {
+                    "message": format!("Persona not found: {}", id),
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": "persona_not_found",
+                }
+            });
+            (StatusCode::NOT_FOUND, Json(body)).into_response()
+        }
+    }
+}
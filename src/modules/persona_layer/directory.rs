@@ -0,0 +1,167 @@
+//! Persona Directory
+//!
+//! The personas of a deployment are JSON files in the persona directory,
+//! each holding an array of personas. This module keeps the loaded personas
+//! in memory for the admin API and writes updated personas back to the
+//! directory.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use super::error::PersonaError;
+use super::persona::{load_personas, Persona};
+
+/// Personas loaded from a persona directory
+#[derive(Debug)]
+pub struct PersonaDirectory {
+    dir: PathBuf,
+    personas: RwLock<Vec<Persona>>,
+}
+
+impl PersonaDirectory {
+    /// Create a directory holding already loaded personas
+    pub fn new(dir: impl Into<PathBuf>, personas: Vec<Persona>) -> Self {
+        Self {
+            dir: dir.into(),
+            personas: RwLock::new(personas),
+        }
+    }
+
+    /// List the personas
+    pub fn list(&self) -> Vec<Persona> {
+        self.personas.read().unwrap().clone()
+    }
+
+    /// Get a persona by ID
+    pub fn get(&self, id: &str) -> Option<Persona> {
+        self.personas
+            .read()
+            .unwrap()
+            .iter()
+            .find(|persona| persona.id == id)
+            .cloned()
+    }
+
+    /// Add or replace personas
+    ///
+    /// Each persona is written to `<id>.json` and removed from any other file
+    /// that defined it, so the directory loads the same personas on restart.
+    pub fn upsert(&self, personas: Vec<Persona>) -> Result<(), PersonaError> {
+        for persona in &personas {
+            validate_id(&persona.id)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+
+        let own_files: Vec<PathBuf> = personas.iter().map(|p| self.file(&p.id)).collect();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") || own_files.contains(&path) {
+                continue;
+            }
+            let existing = load_personas(&path)?;
+            let kept: Vec<&Persona> = existing
+                .iter()
+                .filter(|p| !personas.iter().any(|new| new.id == p.id))
+                .collect();
+            if kept.len() == existing.len() {
+                continue;
+            }
+            if kept.is_empty() {
+                fs::remove_file(&path)?;
+            } else {
+                fs::write(&path, serde_json::to_string_pretty(&kept)?)?;
+            }
+        }
+        for (persona, path) in personas.iter().zip(&own_files) {
+            fs::write(path, serde_json::to_string_pretty(&[persona])?)?;
+        }
+
+        let mut loaded = self.personas.write().unwrap();
+        for persona in personas {
+            match loaded.iter_mut().find(|p| p.id == persona.id) {
+                Some(existing) => *existing = persona,
+                None => loaded.push(persona),
+            }
+        }
+        Ok(())
+    }
+
+    /// File a persona is written to
+    fn file(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+/// Check that a persona ID can be used as a file name
+fn validate_id(id: &str) -> Result<(), PersonaError> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(PersonaError::ValidationError(format!(
+            "Invalid persona ID: {:?}",
+            id
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::persona_layer::{create_persona, load_personas_dir};
+
+    #[test]
+    fn test_upsert_moves_persona_to_own_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let support = create_persona("support", "Support agent", "Be helpful.");
+        let sales = create_persona("sales", "Sales agent", "Be persuasive.");
+        fs::write(
+            dir.path().join("all.json"),
+            serde_json::to_string(&[&support, &sales]).unwrap(),
+        )
+        .unwrap();
+
+        let directory = PersonaDirectory::new(dir.path(), load_personas_dir(dir.path()).unwrap());
+        let mut updated = support.clone();
+        updated.system_prompt_template = "Be concise.".to_string();
+        let legal = create_persona("legal", "Legal review", "Be careful.");
+        directory.upsert(vec![updated, legal]).unwrap();
+
+        assert_eq!(directory.list().len(), 3);
+        assert_eq!(
+            directory.get("support").unwrap().system_prompt_template,
+            "Be concise."
+        );
+
+        let mut reloaded: Vec<String> = load_personas_dir(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|p| format!("{}:{}", p.id, p.system_prompt_template))
+            .collect();
+        reloaded.sort();
+        assert_eq!(
+            reloaded,
+            vec![
+                "legal:Be careful.",
+                "sales:Be persuasive.",
+                "support:Be concise."
+            ]
+        );
+        assert!(dir.path().join("all.json").exists());
+        assert!(dir.path().join("support.json").exists());
+    }
+
+    #[test]
+    fn test_upsert_rejects_path_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let directory = PersonaDirectory::new(dir.path(), Vec::new());
+        let persona = create_persona("../escape", "", "");
+        assert!(directory.upsert(vec![persona]).is_err());
+        assert!(directory.list().is_empty());
+    }
+}
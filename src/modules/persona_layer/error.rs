@@ -13,9 +13,9 @@ pub enum PersonaError {
     #[error("Template error: {0}")]
     TemplateError(#[from] handlebars::RenderError),
 
-    /// Template registration error
+    /// Template registration error, boxed to keep the error small
     #[error("Template registration error: {0}")]
-    TemplateRegistrationError(#[from] handlebars::TemplateError),
+    TemplateRegistrationError(#[source] Box<handlebars::TemplateError>),
 
     /// Serialization error
     #[error("Serialization error: {0}")]
@@ -33,3 +33,9 @@ pub enum PersonaError {
     #[error("Error: {0}")]
     Other(String),
 }
+
+impl From<handlebars::TemplateError> for PersonaError {
+    fn from(e: handlebars::TemplateError) -> Self {
+        PersonaError::TemplateRegistrationError(Box::new(e))
+    }
+}
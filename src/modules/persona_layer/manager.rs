@@ -102,6 +102,7 @@ impl PersonaManager {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
             );
         }
@@ -120,6 +121,7 @@ impl PersonaManager {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                 );
                 index += 1;
@@ -133,6 +135,7 @@ impl PersonaManager {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                 );
                 index += 1;
@@ -369,6 +372,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -417,6 +421,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -473,6 +478,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
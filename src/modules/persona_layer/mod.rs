@@ -8,18 +8,22 @@
 //! - Few-shot examples for in-context learning
 //! - Guardrails for content filtering and response formatting
 //! - Model-specific prompt formatting
+//! - Preferred models and default sampling parameters
 
 // Private module declarations
+pub mod api;
+pub mod directory;
 mod error;
 pub mod guardrails;
 pub mod manager;
 pub mod persona;
 
 // Re-export specific types for public API
+pub use directory::PersonaDirectory;
 pub use error::PersonaError;
 pub use guardrails::{ContentFilter, Guardrail, ResponseFormat, TopicRestriction};
 pub use manager::PersonaManager;
-pub use persona::{ExampleExchange, ModelSpecificFormat, Persona};
+pub use persona::{ExampleExchange, ModelPreferences, ModelSpecificFormat, Persona};
 
 // Import these from the IPC module instead
 pub use crate::modules::ipc::persona_layer::{
@@ -27,4 +31,4 @@ pub use crate::modules::ipc::persona_layer::{
 };
 
 // Re-export the legacy API for backward compatibility
-pub use persona::{apply_persona_to_string, create_persona, load_personas};
+pub use persona::{apply_persona_to_string, create_persona, load_personas, load_personas_dir};
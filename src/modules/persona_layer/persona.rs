@@ -9,6 +9,7 @@ use std::path::Path;
 
 use super::error::PersonaError;
 use super::guardrails::Guardrail;
+use crate::modules::memory::MemorySettings;
 
 /// Example exchange for few-shot learning
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +40,34 @@ pub struct ModelSpecificFormat {
     pub few_shot_format: Option<String>,
 }
 
+/// Models and sampling defaults a persona prefers
+///
+/// Explicit request values always win: `temperature`, `top_p` and
+/// `max_tokens` only fill parameters the request leaves unset, and `models`
+/// only applies when the request doesn't already carry a preferred model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelPreferences {
+    /// Preferred model IDs, most preferred first; each later model is a
+    /// fallback used when the earlier ones are unavailable
+    pub models: Vec<String>,
+
+    /// Whether requests may only be routed to `models`
+    pub exclusive: bool,
+
+    /// Default sampling temperature
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    /// Default top-p sampling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    /// Default maximum number of tokens to generate
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+}
+
 /// Persona configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
@@ -66,6 +95,14 @@ pub struct Persona {
     /// Response format (for backward compatibility)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<String>,
+
+    /// Semantic memory settings, `None` disabling long-term memory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemorySettings>,
+
+    /// Preferred models and default sampling parameters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<ModelPreferences>,
 }
 
 impl Persona {
@@ -80,6 +117,8 @@ impl Persona {
             guardrails: Vec::new(),
             model_specific_formats: HashMap::new(),
             response_format: None,
+            memory: None,
+            model_preferences: None,
         }
     }
 
@@ -119,7 +158,27 @@ pub fn create_persona(name: &str, description: &str, system_prompt: &str) -> Per
         guardrails: Vec::new(),
         model_specific_formats: HashMap::new(),
         response_format: None,
+        memory: None,
+        model_preferences: None,
+    }
+}
+
+/// Load the personas of every JSON file in a directory
+///
+/// Each file holds an array of personas, as written by
+/// [`PersonaManager::save_to_file`](super::PersonaManager::save_to_file).
+pub fn load_personas_dir<P: AsRef<Path>>(dir: P) -> Result<Vec<Persona>, PersonaError> {
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+
+    let mut personas = Vec::new();
+    for path in paths {
+        personas.extend(load_personas(path)?);
     }
+    Ok(personas)
 }
 
 /// Apply a persona to a request string (legacy API)
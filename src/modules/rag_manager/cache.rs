@@ -0,0 +1,370 @@
+//! Retrieval Cache
+//!
+//! Caches the chunks retrieved for a query, keyed by the query's embedding,
+//! so that bursts of repeated or rephrased questions are answered without
+//! querying the vector database again. A query is served from the cache when
+//! it matches a cached query exactly, or when its embedding is close enough
+//! to one's.
+//!
+//! Cached results expire after a short TTL, and the whole cache is dropped
+//! whenever the sources of the RAG manager change. Results of a single source
+//! can be invalidated when its documents are reindexed.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use metrics::{counter, gauge};
+use tracing::{debug, warn};
+
+use crate::config::{RagConfig, RagRetrievalCacheConfig};
+use crate::modules::memory::{cosine_similarity, Embedder, OpenAIEmbedder};
+use crate::modules::rag_manager::types::{ContextChunk, RagError};
+use crate::modules::telemetry::catalog::{
+    RAG_RETRIEVAL_CACHE_ENTRIES, RAG_RETRIEVAL_CACHE_LOOKUPS,
+};
+
+/// Result of a query held by the cache
+struct CacheEntry {
+    /// Normalized query
+    query: String,
+    /// Embedding of the query
+    embedding: Vec<f32>,
+    /// Number of chunks the query asked for
+    max_chunks: usize,
+    /// Retrieved chunks, with their scores
+    chunks: Vec<ContextChunk>,
+    stored_at: Instant,
+    last_used: Instant,
+}
+
+/// Counters of the cache's lookups
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetrievalCacheStats {
+    /// Lookups served from a cached result of the same query
+    pub exact_hits: u64,
+    /// Lookups served from a cached result of a similar query
+    pub similar_hits: u64,
+    /// Lookups sent to the sources
+    pub misses: u64,
+    /// Cached results dropped past their TTL
+    pub expired: u64,
+    /// Results currently cached
+    pub entries: usize,
+    /// Age of the oldest cached result, in seconds
+    pub oldest_entry_age_secs: f64,
+}
+
+impl RetrievalCacheStats {
+    /// Share of lookups served from the cache, 0.0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.exact_hits + self.similar_hits;
+        let lookups = hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        hits as f64 / lookups as f64
+    }
+}
+
+/// Cache of retrieval results, keyed by query embedding
+pub struct RetrievalCache {
+    config: RagRetrievalCacheConfig,
+    embedder: Arc<dyn Embedder>,
+    entries: Mutex<Vec<CacheEntry>>,
+    exact_hits: AtomicU64,
+    similar_hits: AtomicU64,
+    misses: AtomicU64,
+    expired: AtomicU64,
+}
+
+impl std::fmt::Debug for RetrievalCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetrievalCache")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl RetrievalCache {
+    /// Create a cache embedding queries with an embedder
+    pub fn new(config: RagRetrievalCacheConfig, embedder: Arc<dyn Embedder>) -> Self {
+        Self {
+            config,
+            embedder,
+            entries: Mutex::new(Vec::new()),
+            exact_hits: AtomicU64::new(0),
+            similar_hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            expired: AtomicU64::new(0),
+        }
+    }
+
+    /// Create the cache of the RAG configuration, if enabled
+    ///
+    /// Queries are embedded through `default_endpoint`, the router, unless
+    /// the cache configures its own endpoint.
+    pub fn from_config(config: &RagConfig, default_endpoint: &str) -> Option<Self> {
+        let cache = &config.retrieval_cache;
+        if !cache.enabled {
+            return None;
+        }
+        let embedder = OpenAIEmbedder::new(
+            cache.endpoint.as_deref().unwrap_or(default_endpoint),
+            cache.api_key.clone(),
+            cache
+                .embedding_model
+                .as_deref()
+                .unwrap_or(&config.default_embedding_model),
+        );
+        Some(Self::new(cache.clone(), Arc::new(embedder)))
+    }
+
+    /// Return the cached chunks of a query, or retrieve and cache them
+    ///
+    /// `retrieve` returns the chunks and whether every source answered;
+    /// results missing a source, or without chunks, are not cached. Queries
+    /// are retrieved without caching when they cannot be embedded.
+    pub async fn get_or_retrieve<F, Fut>(
+        &self,
+        query: &str,
+        max_chunks: usize,
+        retrieve: F,
+    ) -> Result<Vec<ContextChunk>, RagError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(Vec<ContextChunk>, bool), RagError>>,
+    {
+        let normalized = normalize(query);
+        if let Some(chunks) = self.lookup(|entry| entry.query == normalized, max_chunks) {
+            Self::record_hit(&self.exact_hits, "exact_hit");
+            return Ok(chunks);
+        }
+
+        let embedding = match self.embedder.embed(&[query.to_string()]).await {
+            Ok(mut embeddings) if !embeddings.is_empty() => Some(embeddings.swap_remove(0)),
+            Ok(_) => None,
+            Err(e) => {
+                warn!("Failed to embed RAG query for the retrieval cache: {}", e);
+                None
+            }
+        };
+        if let Some(embedding) = &embedding {
+            let threshold = self.config.similarity_threshold;
+            if let Some(chunks) = self.lookup(
+                |entry| cosine_similarity(&entry.embedding, embedding) >= threshold,
+                max_chunks,
+            ) {
+                Self::record_hit(&self.similar_hits, "similar_hit");
+                return Ok(chunks);
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        counter!(RAG_RETRIEVAL_CACHE_LOOKUPS, 1, "outcome" => "miss");
+        let (chunks, complete) = retrieve().await?;
+        if let Some(embedding) = embedding.filter(|_| complete && !chunks.is_empty()) {
+            self.insert(CacheEntry {
+                query: normalized,
+                embedding,
+                max_chunks,
+                chunks: chunks.clone(),
+                stored_at: Instant::now(),
+                last_used: Instant::now(),
+            });
+        }
+        Ok(chunks)
+    }
+
+    /// Drop every cached result, returning how many were dropped
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let dropped = entries.len();
+        entries.clear();
+        gauge!(RAG_RETRIEVAL_CACHE_ENTRIES, 0.0);
+        dropped
+    }
+
+    /// Drop the cached results holding chunks of a source, returning how many
+    /// were dropped
+    pub fn invalidate_source(&self, source: &str) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.chunks.iter().all(|chunk| chunk.source != source));
+        gauge!(RAG_RETRIEVAL_CACHE_ENTRIES, entries.len() as f64);
+        before - entries.len()
+    }
+
+    /// Counters of the cache's lookups
+    pub fn stats(&self) -> RetrievalCacheStats {
+        let entries = self.entries.lock().unwrap();
+        RetrievalCacheStats {
+            exact_hits: self.exact_hits.load(Ordering::Relaxed),
+            similar_hits: self.similar_hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            entries: entries.len(),
+            oldest_entry_age_secs: entries
+                .iter()
+                .map(|entry| entry.stored_at.elapsed().as_secs_f64())
+                .fold(0.0, f64::max),
+        }
+    }
+
+    /// Chunks of the best fresh entry matching a predicate and holding at
+    /// least `max_chunks` chunks, dropping expired entries on the way
+    fn lookup(
+        &self,
+        matches: impl Fn(&CacheEntry) -> bool,
+        max_chunks: usize,
+    ) -> Option<Vec<ContextChunk>> {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|entry| entry.stored_at.elapsed() < ttl);
+        if entries.len() < before {
+            self.expired
+                .fetch_add((before - entries.len()) as u64, Ordering::Relaxed);
+            gauge!(RAG_RETRIEVAL_CACHE_ENTRIES, entries.len() as f64);
+        }
+
+        let entry = entries
+            .iter_mut()
+            .filter(|entry| entry.max_chunks >= max_chunks && matches(entry))
+            .min_by_key(|entry| entry.stored_at.elapsed())?;
+        entry.last_used = Instant::now();
+        Some(entry.chunks.iter().take(max_chunks).cloned().collect())
+    }
+
+    fn insert(&self, entry: CacheEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .retain(|cached| cached.query != entry.query || cached.max_chunks > entry.max_chunks);
+        while entries.len() >= self.config.max_entries {
+            let Some(lru) = entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, cached)| cached.last_used)
+                .map(|(i, _)| i)
+            else {
+                break;
+            };
+            entries.swap_remove(lru);
+        }
+        debug!(
+            "Cached {} RAG chunks for '{}'",
+            entry.chunks.len(),
+            entry.query
+        );
+        entries.push(entry);
+        gauge!(RAG_RETRIEVAL_CACHE_ENTRIES, entries.len() as f64);
+    }
+
+    fn record_hit(hits: &AtomicU64, outcome: &'static str) {
+        hits.fetch_add(1, Ordering::Relaxed);
+        counter!(RAG_RETRIEVAL_CACHE_LOOKUPS, 1, "outcome" => outcome);
+    }
+}
+
+/// Lowercase a query and collapse its whitespace
+fn normalize(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::testing::KeywordEmbedder;
+    use std::collections::HashMap;
+
+    const KEYWORDS: &[&str] = &["remote", "vacation", "salary"];
+
+    fn chunk(content: &str, source: &str, score: f32) -> ContextChunk {
+        ContextChunk {
+            content: content.to_string(),
+            source: source.to_string(),
+            relevance_score: score,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrieval_cache() {
+        let config = RagRetrievalCacheConfig {
+            enabled: true,
+            ..RagRetrievalCacheConfig::default()
+        };
+        let cache = RetrievalCache::new(config, Arc::new(KeywordEmbedder(KEYWORDS)));
+        let retrievals = AtomicU64::new(0);
+        let retrieve = || async {
+            retrievals.fetch_add(1, Ordering::Relaxed);
+            Ok((
+                vec![
+                    chunk("Remote work is allowed", "handbook.md", 0.9),
+                    chunk("Remote days are logged", "faq.md", 0.7),
+                ],
+                true,
+            ))
+        };
+
+        let chunks = cache
+            .get_or_retrieve("Remote work policy?", 2, retrieve)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        // The same query, differently cased, and a similar query are cached
+        let chunks = cache
+            .get_or_retrieve("remote  work POLICY?", 1, retrieve)
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].relevance_score, 0.9);
+        cache
+            .get_or_retrieve("Can I work remote?", 2, retrieve)
+            .await
+            .unwrap();
+        // A query asking for more chunks, or about something else, is not
+        cache
+            .get_or_retrieve("Remote work policy?", 3, retrieve)
+            .await
+            .unwrap();
+        cache
+            .get_or_retrieve("Vacation policy?", 2, retrieve)
+            .await
+            .unwrap();
+        assert_eq!(retrievals.load(Ordering::Relaxed), 3);
+
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.exact_hits, stats.similar_hits, stats.misses),
+            (1, 1, 3)
+        );
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hit_rate(), 0.4);
+
+        assert_eq!(cache.invalidate_source("faq.md"), 2);
+        cache
+            .get_or_retrieve("Remote work policy?", 2, retrieve)
+            .await
+            .unwrap();
+        assert_eq!(retrievals.load(Ordering::Relaxed), 4);
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_results_not_cached() {
+        let config = RagRetrievalCacheConfig {
+            enabled: true,
+            ..RagRetrievalCacheConfig::default()
+        };
+        let cache = RetrievalCache::new(config, Arc::new(KeywordEmbedder(KEYWORDS)));
+        let partial = || async { Ok((vec![chunk("Remote work", "handbook.md", 0.9)], false)) };
+        cache.get_or_retrieve("remote", 1, partial).await.unwrap();
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -0,0 +1,255 @@
+//! Code Chunking
+//!
+//! Splits source code between its top-level items, so that functions,
+//! classes and impls are not cut in half. An item starts at a line at the
+//! block's indentation following a blank line, which keeps the comments,
+//! attributes and decorators directly above a definition with it. Small
+//! neighbouring items are packed into the same chunk; items longer than the
+//! maximum chunk size are split between their nested items, such as the
+//! methods of a class, and as a last resort between lines.
+//!
+//! Chunks are tagged with the lines they span and the names of the functions,
+//! classes and types they define, found by the definition keywords of the
+//! common languages.
+//!
+//! This is a heuristic working on indentation and keywords, not a parser, and
+//! it does not know the language of a document. Its known limits:
+//!
+//! - A blank line followed by a line at an item's own indentation is taken for
+//!   the start of a new item unless the line opens with a closing bracket, so
+//!   an `end` keyword after a blank line, or the unindented continuation of a
+//!   multi-line string, can be split off its item.
+//! - Code that is not indented, such as minified code, has no items and is
+//!   split between lines.
+//! - Symbols are found by a single pattern on the first line of each
+//!   definition. Definitions without a keyword, such as C and Java functions,
+//!   methods declared by their return type or functions assigned to variables,
+//!   are not tagged, while a comment or string line starting with a keyword
+//!   can be tagged as a definition.
+
+use std::ops::Range;
+
+use async_trait::async_trait;
+use regex::Regex;
+
+use super::{Chunk, Chunker};
+use crate::modules::rag_manager::types::{Document, RagError};
+
+/// Metadata key of the comma-separated symbols a chunk defines
+pub const SYMBOLS_KEY: &str = "symbols";
+/// Metadata key of the first line of a chunk in its document, from 1
+pub const START_LINE_KEY: &str = "start_line";
+/// Metadata key of the last line of a chunk in its document
+pub const END_LINE_KEY: &str = "end_line";
+
+/// Definition of a function, class or type, capturing its name
+const DEFINITION: &str = r"^\s*(?:(?:pub(?:\([^)]*\))?|export|default|async|public|private|protected|internal|static|final|abstract|sealed|unsafe|override|virtual)\s+)*(?:fn|def|class|func|function|impl|struct|enum|trait|interface|type|mod|module|object|record)\s*(?:\([^)]*\)\s*)?(?:<[^>]*>\s*)?([A-Za-z_$][A-Za-z0-9_$]*(?:::[A-Za-z_$][A-Za-z0-9_$]*)*)";
+
+/// Chunker splitting source code between its top-level items
+#[derive(Debug, Clone)]
+pub struct CodeChunker {
+    max_chunk_size: usize,
+    definition: Regex,
+}
+
+impl CodeChunker {
+    /// Create a chunker of chunks of at most `max_chunk_size` characters,
+    /// unless a single line is longer
+    pub fn new(max_chunk_size: usize) -> Self {
+        Self {
+            max_chunk_size: max_chunk_size.max(1),
+            definition: Regex::new(DEFINITION).expect("Invalid definition pattern"),
+        }
+    }
+
+    /// Split a range of lines into ranges of at most the maximum chunk size
+    fn split(&self, lines: &[&str], range: Range<usize>, out: &mut Vec<Range<usize>>) {
+        if size(lines, range.clone()) <= self.max_chunk_size {
+            out.push(range);
+            return;
+        }
+
+        // Split between the items of the shallowest indentation that has some
+        let mut levels: Vec<usize> = lines[range.clone()]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| indentation(line))
+            .collect();
+        levels.sort_unstable();
+        levels.dedup();
+        for indent in levels {
+            let items = items(lines, range.clone(), indent);
+            if items.len() > 1 {
+                for item in items {
+                    self.split(lines, item, out);
+                }
+                return;
+            }
+        }
+
+        let mut start = range.start;
+        for i in range.clone() {
+            if i > start && size(lines, start..i + 1) > self.max_chunk_size {
+                out.push(start..i);
+                start = i;
+            }
+        }
+        out.push(start..range.end);
+    }
+
+    /// Create the chunk of a range of lines, if not blank
+    fn chunk_of(&self, lines: &[&str], range: Range<usize>) -> Option<Chunk> {
+        let first = range.clone().find(|&i| !lines[i].trim().is_empty())?;
+        let last = range.clone().rev().find(|&i| !lines[i].trim().is_empty())?;
+        let lines = &lines[first..=last];
+
+        let symbols: Vec<&str> = lines
+            .iter()
+            .filter_map(|line| Some(self.definition.captures(line)?.get(1)?.as_str()))
+            .collect();
+
+        let mut chunk = Chunk::new(lines.join("\n"))
+            .with_metadata(START_LINE_KEY, (first + 1).to_string())
+            .with_metadata(END_LINE_KEY, (last + 1).to_string());
+        if !symbols.is_empty() {
+            chunk = chunk.with_metadata(SYMBOLS_KEY, symbols.join(","));
+        }
+        Some(chunk)
+    }
+}
+
+#[async_trait]
+impl Chunker for CodeChunker {
+    async fn chunk(&self, document: &Document) -> Result<Vec<Chunk>, RagError> {
+        let lines: Vec<&str> = document.content.lines().collect();
+        let mut pieces = Vec::new();
+        self.split(&lines, 0..lines.len(), &mut pieces);
+
+        // Pack neighbouring pieces into chunks of at most the maximum size
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for piece in pieces {
+            match ranges.last_mut() {
+                Some(last) if size(&lines, last.start..piece.end) <= self.max_chunk_size => {
+                    last.end = piece.end;
+                }
+                _ => ranges.push(piece),
+            }
+        }
+        Ok(ranges
+            .into_iter()
+            .filter_map(|range| self.chunk_of(&lines, range))
+            .collect())
+    }
+}
+
+/// Number of leading whitespace characters of a line
+fn indentation(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Number of characters of a range of lines, newlines included
+fn size(lines: &[&str], range: Range<usize>) -> usize {
+    lines[range]
+        .iter()
+        .map(|line| line.chars().count() + 1)
+        .sum()
+}
+
+/// Split a range of lines into items starting at lines of an indentation
+/// that follow a blank line
+fn items(lines: &[&str], range: Range<usize>, indent: usize) -> Vec<Range<usize>> {
+    let mut items = Vec::new();
+    let mut start = range.start;
+    for i in range.start + 1..range.end {
+        let line = lines[i];
+        let starts_item = !line.trim().is_empty()
+            && indentation(line) == indent
+            && lines[i - 1].trim().is_empty()
+            && !line.trim_start().starts_with(['}', ')', ']'])
+            && lines[start..i].iter().any(|line| !line.trim().is_empty());
+        if starts_item {
+            items.push(start..i);
+            start = i;
+        }
+    }
+    items.push(start..range.end);
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn document(content: &str) -> Document {
+        Document {
+            id: "lib.rs".to_string(),
+            content: content.to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    const SOURCE: &str = r#"use std::fmt;
+
+/// Adds numbers
+pub fn add(a: i32, b: i32) -> i32 {
+    let sum = a + b;
+
+    sum
+}
+
+#[derive(Debug)]
+struct Point {
+    x: i32,
+}
+
+impl<T> fmt::Display for Wrapper<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "wrapper")
+    }
+
+    fn other(&self) {}
+}
+"#;
+
+    #[tokio::test]
+    async fn test_code_items() {
+        let chunks = CodeChunker::new(120)
+            .chunk(&document(SOURCE))
+            .await
+            .unwrap();
+
+        // The function stays whole despite its blank line, with its doc comment
+        assert_eq!(
+            chunks[0].content,
+            "use std::fmt;\n\n/// Adds numbers\npub fn add(a: i32, b: i32) -> i32 {\n    let sum = a + b;\n\n    sum\n}"
+        );
+        assert_eq!(chunks[0].metadata[SYMBOLS_KEY], "add");
+        assert_eq!(chunks[0].metadata[START_LINE_KEY], "1");
+        assert_eq!(chunks[0].metadata[END_LINE_KEY], "8");
+        assert!(chunks[1]
+            .content
+            .starts_with("#[derive(Debug)]\nstruct Point"));
+        assert_eq!(chunks[1].metadata[SYMBOLS_KEY], "Point");
+
+        // The impl is longer than a chunk, and is split between its methods
+        assert_eq!(chunks[2].metadata[SYMBOLS_KEY], "fmt::Display,fmt");
+        assert_eq!(chunks.last().unwrap().metadata[SYMBOLS_KEY], "other");
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.content.chars().count() <= 120));
+    }
+
+    #[tokio::test]
+    async fn test_small_files_are_one_chunk() {
+        let chunks = CodeChunker::new(2000)
+            .chunk(&document(SOURCE))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(
+            chunks[0].metadata[SYMBOLS_KEY],
+            "add,Point,fmt::Display,fmt,other"
+        );
+    }
+}
@@ -0,0 +1,320 @@
+//! Chunking Evaluation
+//!
+//! Compares chunkers on a corpus of documents and of questions whose answers
+//! are passages of the documents. Each chunker chunks the corpus, the chunks
+//! and questions are embedded, and the `k` chunks most similar to each
+//! question are retrieved. A chunk answers a question when it contains the
+//! whole passage, so chunkers cutting passages in half score lower.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::ChunkerRegistry;
+use crate::modules::memory::{cosine_similarity, Embedder};
+use crate::modules::rag_manager::types::{Document, RagError};
+
+/// Number of texts embedded per request
+const EMBEDDING_BATCH: usize = 64;
+
+/// Question of an evaluation, with the passage answering it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalQuestion {
+    /// Question, as a user would ask it
+    pub question: String,
+    /// Passage of a document answering the question
+    pub answer: String,
+}
+
+/// Scores of a chunker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkerScore {
+    /// Name of the chunker
+    pub chunker: String,
+    /// Number of chunks of the corpus
+    pub chunks: usize,
+    /// Mean number of characters of the chunks
+    pub mean_chunk_chars: f64,
+    /// Share of questions answered by one of the `k` retrieved chunks
+    pub recall_at_k: f64,
+    /// Mean reciprocal rank of the first retrieved chunk answering each
+    /// question, 0 for questions not answered
+    pub mrr: f64,
+    /// Questions whose passage no chunk contains whole
+    pub split_answers: usize,
+}
+
+/// Outcome of an evaluation of chunkers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkingEvalReport {
+    /// Number of chunks retrieved per question
+    pub k: usize,
+    /// Number of documents of the corpus
+    pub documents: usize,
+    /// Number of questions
+    pub questions: usize,
+    /// Scores of each chunker, in the order evaluated
+    pub scores: Vec<ChunkerScore>,
+}
+
+impl ChunkingEvalReport {
+    /// Render the report as a markdown summary
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        md.push_str("# Chunking Eval\n\n");
+        md.push_str(&format!(
+            "{} documents, {} questions, {} chunks retrieved per question.\n\n",
+            self.documents, self.questions, self.k
+        ));
+        md.push_str(&format!(
+            "| Chunker | Chunks | Mean size | Recall@{} | MRR | Split answers |\n",
+            self.k
+        ));
+        md.push_str("|---------|--------|-----------|----------|-----|---------------|\n");
+        for score in &self.scores {
+            md.push_str(&format!(
+                "| {} | {} | {:.0} | {:.2} | {:.2} | {} |\n",
+                score.chunker,
+                score.chunks,
+                score.mean_chunk_chars,
+                score.recall_at_k,
+                score.mrr,
+                score.split_answers
+            ));
+        }
+        md
+    }
+}
+
+/// Evaluate chunkers of a registry on documents and questions
+pub async fn evaluate_chunkers(
+    registry: &ChunkerRegistry,
+    chunkers: &[String],
+    documents: &[Document],
+    questions: &[EvalQuestion],
+    embedder: &dyn Embedder,
+    k: usize,
+) -> Result<ChunkingEvalReport, RagError> {
+    let question_texts: Vec<String> = questions.iter().map(|q| q.question.clone()).collect();
+    let question_embeddings = embed_all(embedder, &question_texts).await?;
+    let answers: Vec<String> = questions.iter().map(|q| normalize(&q.answer)).collect();
+
+    let mut scores = Vec::new();
+    for name in chunkers {
+        let chunker = registry
+            .get(name)
+            .ok_or_else(|| RagError::Other(format!("Unknown chunker '{}'", name)))?;
+        let mut chunks = Vec::new();
+        for document in documents {
+            chunks.extend(
+                chunker
+                    .chunk(document)
+                    .await?
+                    .into_iter()
+                    .map(|chunk| chunk.content),
+            );
+        }
+        let chunk_embeddings = embed_all(embedder, &chunks).await?;
+        let normalized: Vec<String> = chunks.iter().map(|chunk| normalize(chunk)).collect();
+
+        let mut answered = 0;
+        let mut reciprocal_ranks = 0.0;
+        let mut split_answers = 0;
+        for (answer, embedding) in answers.iter().zip(&question_embeddings) {
+            if !normalized
+                .iter()
+                .any(|chunk| chunk.contains(answer.as_str()))
+            {
+                split_answers += 1;
+            }
+            let mut ranked: Vec<(usize, f32)> = chunk_embeddings
+                .iter()
+                .map(|chunk| cosine_similarity(embedding, chunk))
+                .enumerate()
+                .collect();
+            ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            if let Some(rank) = ranked
+                .iter()
+                .take(k)
+                .position(|(i, _)| normalized[*i].contains(answer.as_str()))
+            {
+                answered += 1;
+                reciprocal_ranks += 1.0 / (rank + 1) as f64;
+            }
+        }
+
+        let per_question = |value: f64| {
+            if questions.is_empty() {
+                0.0
+            } else {
+                value / questions.len() as f64
+            }
+        };
+        scores.push(ChunkerScore {
+            chunker: name.clone(),
+            chunks: chunks.len(),
+            mean_chunk_chars: if chunks.is_empty() {
+                0.0
+            } else {
+                chunks.iter().map(|c| c.chars().count()).sum::<usize>() as f64 / chunks.len() as f64
+            },
+            recall_at_k: per_question(answered as f64),
+            mrr: per_question(reciprocal_ranks),
+            split_answers,
+        });
+    }
+
+    Ok(ChunkingEvalReport {
+        k,
+        documents: documents.len(),
+        questions: questions.len(),
+        scores,
+    })
+}
+
+/// Load the documents of a corpus: a file, or the text files of a directory
+/// and its subdirectories, identified by their path
+pub fn load_documents(path: &Path) -> Result<Vec<Document>, RagError> {
+    let mut files = Vec::new();
+    collect_files(path, &mut files)?;
+    files.sort();
+
+    let mut documents = Vec::new();
+    for file in files {
+        // Skip binary files
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let id = file
+            .strip_prefix(path)
+            .ok()
+            .filter(|relative| !relative.as_os_str().is_empty())
+            .unwrap_or(&file)
+            .to_string_lossy()
+            .to_string();
+        documents.push(Document {
+            id,
+            content,
+            metadata: HashMap::from([("path".to_string(), file.to_string_lossy().to_string())]),
+        });
+    }
+    Ok(documents)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), RagError> {
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    for entry in fs::read_dir(path)? {
+        let entry = entry?.path();
+        let hidden = entry
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if !hidden {
+            collect_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+/// Load questions from a JSONL file, or a JSON file with an array of
+/// questions
+pub fn load_questions(path: &Path) -> Result<Vec<EvalQuestion>, RagError> {
+    let content = fs::read_to_string(path)?;
+    let invalid = |e: serde_json::Error| {
+        RagError::SerializationError(format!("Invalid question in {}: {}", path.display(), e))
+    };
+    if path.extension().and_then(|e| e.to_str()) == Some("json") {
+        return serde_json::from_str(&content).map_err(invalid);
+    }
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).map_err(invalid))
+        .collect()
+}
+
+/// Embed texts in batches
+async fn embed_all(embedder: &dyn Embedder, texts: &[String]) -> Result<Vec<Vec<f32>>, RagError> {
+    let mut embeddings = Vec::with_capacity(texts.len());
+    for batch in texts.chunks(EMBEDDING_BATCH) {
+        embeddings.extend(
+            embedder
+                .embed(batch)
+                .await
+                .map_err(|e| RagError::RetrievalError(format!("Failed to embed texts: {}", e)))?,
+        );
+    }
+    if embeddings.len() != texts.len() {
+        return Err(RagError::Other(format!(
+            "Expected {} embeddings, got {}",
+            texts.len(),
+            embeddings.len()
+        )));
+    }
+    Ok(embeddings)
+}
+
+/// Lowercase text and collapse its whitespace
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::testing::KeywordEmbedder;
+    use crate::modules::rag_manager::chunking::{FixedSizeChunker, MarkdownChunker};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_evaluate_chunkers() {
+        let mut registry = ChunkerRegistry::new("fixed");
+        registry.register("fixed", Arc::new(FixedSizeChunker::new(40, 0)));
+        registry.register("markdown", Arc::new(MarkdownChunker::new(200)));
+        let documents = vec![Document {
+            id: "handbook.md".to_string(),
+            content: "# Remote\nRemote work is allowed three days a week.\n\
+                      # Vacation\nVacation is 25 days a year."
+                .to_string(),
+            metadata: HashMap::new(),
+        }];
+        let questions = vec![
+            EvalQuestion {
+                question: "How much remote work?".to_string(),
+                answer: "Remote work is allowed three days a week.".to_string(),
+            },
+            EvalQuestion {
+                question: "How long is vacation?".to_string(),
+                answer: "Vacation is 25 days a year.".to_string(),
+            },
+        ];
+
+        let report = evaluate_chunkers(
+            &registry,
+            &["fixed".to_string(), "markdown".to_string()],
+            &documents,
+            &questions,
+            &KeywordEmbedder(&["remote", "vacation"]),
+            1,
+        )
+        .await
+        .unwrap();
+
+        // Fixed windows cut both passages in half
+        let fixed = &report.scores[0];
+        assert_eq!(fixed.split_answers, 2);
+        assert_eq!(fixed.recall_at_k, 0.0);
+        let markdown = &report.scores[1];
+        assert_eq!((markdown.chunks, markdown.split_answers), (2, 0));
+        assert_eq!(markdown.recall_at_k, 1.0);
+        assert_eq!(markdown.mrr, 1.0);
+        assert!(report.to_markdown().contains("| markdown | 2 |"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Markdown Chunking
+//!
+//! Splits markdown at its headings, so that each chunk holds a single section
+//! and is tagged with the path of headings leading to it. Headings inside
+//! fenced code blocks are not section boundaries. Sections longer than the
+//! maximum chunk size are split between paragraphs, each continuation chunk
+//! repeating the section's heading.
+
+use async_trait::async_trait;
+
+use super::{split_by_size, Chunk, Chunker};
+use crate::modules::rag_manager::types::{Document, RagError};
+
+/// Metadata key of the headings leading to a chunk, joined by ` > `
+pub const HEADING_PATH_KEY: &str = "heading_path";
+/// Metadata key of the heading of a chunk's section
+pub const HEADING_KEY: &str = "heading";
+
+/// Chunker splitting markdown at its headings
+#[derive(Debug, Clone)]
+pub struct MarkdownChunker {
+    max_chunk_size: usize,
+}
+
+/// Section of a markdown document
+#[derive(Default)]
+struct Section<'a> {
+    /// Heading line of the section, none for text before the first heading
+    heading: Option<&'a str>,
+    /// Titles of the headings leading to the section, outermost first
+    path: Vec<String>,
+    /// Lines of the section, its heading included
+    lines: Vec<&'a str>,
+}
+
+impl MarkdownChunker {
+    /// Create a chunker of sections of at most `max_chunk_size` characters
+    pub fn new(max_chunk_size: usize) -> Self {
+        Self {
+            max_chunk_size: max_chunk_size.max(1),
+        }
+    }
+
+    /// Split a section into paragraphs packed into chunks of at most the
+    /// maximum chunk size
+    fn split_section(&self, section: &Section<'_>) -> Vec<String> {
+        let content = section.lines.join("\n");
+        if content.chars().count() <= self.max_chunk_size {
+            return vec![content.trim().to_string()];
+        }
+
+        let heading = section.heading.map(str::trim).unwrap_or_default();
+        let budget = self
+            .max_chunk_size
+            .saturating_sub(heading.chars().count() + 2)
+            .max(1);
+        let mut pieces: Vec<String> = Vec::new();
+        let mut current = String::new();
+        for paragraph in paragraphs(&section.lines) {
+            let fits = current.is_empty()
+                || current.chars().count() + paragraph.chars().count() + 2 <= budget;
+            if !fits {
+                pieces.push(std::mem::take(&mut current));
+            }
+            if paragraph.chars().count() > budget {
+                pieces.extend(split_by_size(&paragraph, budget, 0));
+                continue;
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&paragraph);
+        }
+        if !current.is_empty() {
+            pieces.push(current);
+        }
+
+        pieces
+            .into_iter()
+            .filter(|piece| !piece.trim().is_empty())
+            .enumerate()
+            .map(|(i, piece)| {
+                if i == 0 || heading.is_empty() || piece.starts_with(heading) {
+                    piece
+                } else {
+                    format!("{}\n\n{}", heading, piece)
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Chunker for MarkdownChunker {
+    async fn chunk(&self, document: &Document) -> Result<Vec<Chunk>, RagError> {
+        let mut chunks = Vec::new();
+        for section in sections(&document.content) {
+            // Headings directly followed by a subheading only appear in paths
+            let has_body = section
+                .lines
+                .iter()
+                .skip(usize::from(section.heading.is_some()))
+                .any(|line| !line.trim().is_empty());
+            if !has_body {
+                continue;
+            }
+
+            for content in self.split_section(&section) {
+                let mut chunk = Chunk::new(content);
+                if let Some(title) = section.path.last() {
+                    chunk = chunk
+                        .with_metadata(HEADING_PATH_KEY, section.path.join(" > "))
+                        .with_metadata(HEADING_KEY, title.clone());
+                }
+                chunks.push(chunk);
+            }
+        }
+        Ok(chunks)
+    }
+}
+
+/// Level and title of an ATX heading line, such as `## Install`
+fn heading(line: &str) -> Option<(usize, String)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let level = trimmed.chars().take_while(|c| *c == '#').count();
+    let rest = &trimmed[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title.to_string()))
+}
+
+/// Opening or closing marker of a fenced code block
+fn fence(line: &str) -> Option<&'static str> {
+    let trimmed = line.trim_start();
+    ["```", "~~~"]
+        .into_iter()
+        .find(|marker| trimmed.starts_with(marker))
+}
+
+/// Split markdown into sections at its headings
+fn sections(text: &str) -> Vec<Section<'_>> {
+    let mut sections = vec![Section::default()];
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut open_fence: Option<&str> = None;
+
+    for line in text.lines() {
+        match (open_fence, fence(line)) {
+            (Some(open), Some(marker)) if open == marker => open_fence = None,
+            (Some(_), _) => {}
+            (None, Some(marker)) => open_fence = Some(marker),
+            (None, None) => {
+                if let Some((level, title)) = heading(line) {
+                    while headings.last().is_some_and(|(l, _)| *l >= level) {
+                        headings.pop();
+                    }
+                    headings.push((level, title));
+                    sections.push(Section {
+                        heading: Some(line),
+                        path: headings.iter().map(|(_, title)| title.clone()).collect(),
+                        lines: Vec::new(),
+                    });
+                }
+            }
+        }
+        if let Some(section) = sections.last_mut() {
+            section.lines.push(line);
+        }
+    }
+    sections
+}
+
+/// Split lines into paragraphs at blank lines outside fenced code blocks
+fn paragraphs(lines: &[&str]) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut open_fence: Option<&str> = None;
+
+    for line in lines {
+        match (open_fence, fence(line)) {
+            (Some(open), Some(marker)) if open == marker => open_fence = None,
+            (None, Some(marker)) => open_fence = Some(marker),
+            _ => {}
+        }
+        if open_fence.is_none() && line.trim().is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(current.join("\n"));
+                current.clear();
+            }
+            continue;
+        }
+        current.push(line);
+    }
+    if !current.is_empty() {
+        paragraphs.push(current.join("\n"));
+    }
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn document(content: &str) -> Document {
+        Document {
+            id: "guide.md".to_string(),
+            content: content.to_string(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_markdown_sections() {
+        let text = "Intro text.\n\n# Guide\n## Install\nRun the installer.\n\n```sh\n# not a heading\n```\n## Usage ##\nStart it.\n# FAQ\nAsk away.";
+        let chunks = MarkdownChunker::new(1000)
+            .chunk(&document(text))
+            .await
+            .unwrap();
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "Intro text.",
+                "## Install\nRun the installer.\n\n```sh\n# not a heading\n```",
+                "## Usage ##\nStart it.",
+                "# FAQ\nAsk away.",
+            ]
+        );
+        assert!(chunks[0].metadata.is_empty());
+        assert_eq!(chunks[1].metadata[HEADING_PATH_KEY], "Guide > Install");
+        assert_eq!(chunks[2].metadata[HEADING_KEY], "Usage");
+        assert_eq!(chunks[3].metadata[HEADING_PATH_KEY], "FAQ");
+    }
+
+    #[tokio::test]
+    async fn test_long_sections_split_between_paragraphs() {
+        let text = "# Policy\nFirst paragraph of the policy.\n\nSecond paragraph of the policy.\n\nThird one.";
+        let chunks = MarkdownChunker::new(50)
+            .chunk(&document(text))
+            .await
+            .unwrap();
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks[0].content,
+            "# Policy\nFirst paragraph of the policy."
+        );
+        assert_eq!(
+            chunks[1].content,
+            "# Policy\n\nSecond paragraph of the policy."
+        );
+        assert!(chunks.iter().all(|c| c.metadata[HEADING_KEY] == "Policy"));
+    }
+}
@@ -0,0 +1,358 @@
+//! Chunking
+//!
+//! Documents are split into chunks before they are embedded and indexed.
+//! Chunkers are plugins registered by name, and each collection picks the
+//! chunker suiting its documents:
+//!
+//! - `fixed`: windows of `rag.chunk_size` characters overlapping by
+//!   `rag.chunk_overlap`, broken at whitespace
+//! - `markdown`: one chunk per section, tagged with its heading path
+//! - `code`: chunks holding whole top-level definitions (functions, classes,
+//!   impls, ...), tagged with their symbols and lines
+//! - `semantic`: sentences grouped until the embedding of the next one drifts
+//!   away from the previous one's
+//!
+//! Chunkers can be compared on a corpus of documents and questions with
+//! [`eval::evaluate_chunkers`].
+
+pub mod code;
+pub mod eval;
+pub mod markdown;
+pub mod semantic;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::config::RagConfig;
+use crate::modules::memory::OpenAIEmbedder;
+use crate::modules::rag_manager::types::{Document, RagError};
+
+pub use code::CodeChunker;
+pub use markdown::MarkdownChunker;
+pub use semantic::SemanticChunker;
+
+/// Metadata key of the ID of the document a chunk was taken from
+pub const DOCUMENT_ID_KEY: &str = "document_id";
+/// Metadata key of the position of a chunk in its document
+pub const CHUNK_INDEX_KEY: &str = "chunk_index";
+/// Metadata key of the name of the chunker that produced a chunk
+pub const CHUNKER_KEY: &str = "chunker";
+
+/// A chunk of a document, ready to be embedded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    /// Text of the chunk
+    pub content: String,
+    /// Metadata describing where the chunk comes from
+    pub metadata: HashMap<String, String>,
+}
+
+impl Chunk {
+    /// Create a chunk without metadata
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Add a metadata entry to the chunk
+    pub fn with_metadata(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// Strategy splitting documents into chunks
+#[async_trait]
+pub trait Chunker: Send + Sync {
+    /// Split a document into chunks, in document order
+    async fn chunk(&self, document: &Document) -> Result<Vec<Chunk>, RagError>;
+}
+
+/// Chunker splitting text into fixed-size, overlapping windows
+#[derive(Debug, Clone)]
+pub struct FixedSizeChunker {
+    chunk_size: usize,
+    chunk_overlap: usize,
+}
+
+impl FixedSizeChunker {
+    /// Create a chunker of windows of `chunk_size` characters, overlapping by
+    /// `chunk_overlap` characters
+    pub fn new(chunk_size: usize, chunk_overlap: usize) -> Self {
+        Self {
+            chunk_size,
+            chunk_overlap,
+        }
+    }
+}
+
+#[async_trait]
+impl Chunker for FixedSizeChunker {
+    async fn chunk(&self, document: &Document) -> Result<Vec<Chunk>, RagError> {
+        Ok(
+            split_by_size(&document.content, self.chunk_size, self.chunk_overlap)
+                .into_iter()
+                .map(Chunk::new)
+                .collect(),
+        )
+    }
+}
+
+/// Split text into windows of at most `chunk_size` characters overlapping by
+/// about `chunk_overlap` characters, breaking at whitespace where possible
+pub(crate) fn split_by_size(text: &str, chunk_size: usize, chunk_overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let chunk_size = chunk_size.max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + chunk_size).min(chars.len());
+        if end < chars.len() {
+            // Break after the last whitespace of the window's second half
+            if let Some(space) = (start + chunk_size / 2..end)
+                .rev()
+                .find(|&i| chars[i].is_whitespace())
+            {
+                end = space + 1;
+            }
+        }
+        let chunk: String = chars[start..end].iter().collect();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+
+        // Start the next window at a word boundary within the overlap
+        let mut next = end.saturating_sub(chunk_overlap).max(start + 1);
+        while next < end && !chars[next - 1].is_whitespace() {
+            next += 1;
+        }
+        start = next;
+    }
+    chunks
+}
+
+/// Chunkers by name, and the chunker of each collection
+pub struct ChunkerRegistry {
+    chunkers: HashMap<String, Arc<dyn Chunker>>,
+    default_chunker: String,
+    collections: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for ChunkerRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkerRegistry")
+            .field("chunkers", &self.names())
+            .field("default_chunker", &self.default_chunker)
+            .field("collections", &self.collections)
+            .finish()
+    }
+}
+
+impl Default for ChunkerRegistry {
+    /// The `fixed`, `markdown` and `code` chunkers at the default sizes,
+    /// `fixed` chunking every collection
+    fn default() -> Self {
+        let config = RagConfig::default();
+        let mut registry = Self::new("fixed");
+        registry.register_builtins(&config);
+        registry
+    }
+}
+
+impl ChunkerRegistry {
+    /// Create a registry without chunkers, chunking collections with
+    /// `default_chunker` unless they pick their own
+    pub fn new(default_chunker: &str) -> Self {
+        Self {
+            chunkers: HashMap::new(),
+            default_chunker: default_chunker.to_string(),
+            collections: HashMap::new(),
+        }
+    }
+
+    /// Create the registry of the RAG configuration, with every built-in
+    /// chunker
+    ///
+    /// The semantic chunker embeds sentences through `default_endpoint`, the
+    /// router, unless `[rag.chunking]` configures its own endpoint.
+    pub fn from_config(config: &RagConfig, default_endpoint: &str) -> Result<Self, RagError> {
+        let chunking = &config.chunking;
+        if !(0.0..=1.0).contains(&chunking.semantic_threshold) {
+            return Err(RagError::Other(
+                "Semantic chunking threshold must be between 0 and 1".to_string(),
+            ));
+        }
+
+        let mut registry = Self::new(&chunking.default_chunker);
+        registry.register_builtins(config);
+        let embedder = OpenAIEmbedder::new(
+            chunking.endpoint.as_deref().unwrap_or(default_endpoint),
+            chunking.api_key.clone(),
+            &config.default_embedding_model,
+        );
+        registry.register(
+            "semantic",
+            Arc::new(SemanticChunker::new(
+                Arc::new(embedder),
+                chunking.semantic_threshold,
+                config.chunk_size,
+            )),
+        );
+
+        for (collection, chunker) in &chunking.collections {
+            registry.set_collection_chunker(collection, chunker)?;
+        }
+        if !registry.chunkers.contains_key(&registry.default_chunker) {
+            return Err(RagError::Other(format!(
+                "Unknown default chunker '{}'",
+                registry.default_chunker
+            )));
+        }
+        Ok(registry)
+    }
+
+    fn register_builtins(&mut self, config: &RagConfig) {
+        self.register(
+            "fixed",
+            Arc::new(FixedSizeChunker::new(
+                config.chunk_size,
+                config.chunk_overlap,
+            )),
+        );
+        self.register(
+            "markdown",
+            Arc::new(MarkdownChunker::new(config.chunk_size)),
+        );
+        self.register("code", Arc::new(CodeChunker::new(config.chunk_size)));
+    }
+
+    /// Register a chunker under a name, replacing any chunker of that name
+    pub fn register(&mut self, name: &str, chunker: Arc<dyn Chunker>) {
+        self.chunkers.insert(name.to_string(), chunker);
+    }
+
+    /// Chunk a collection with a registered chunker
+    pub fn set_collection_chunker(
+        &mut self,
+        collection: &str,
+        chunker: &str,
+    ) -> Result<(), RagError> {
+        if !self.chunkers.contains_key(chunker) {
+            return Err(RagError::Other(format!(
+                "Unknown chunker '{}' for collection '{}'",
+                chunker, collection
+            )));
+        }
+        self.collections
+            .insert(collection.to_string(), chunker.to_string());
+        Ok(())
+    }
+
+    /// Names of the registered chunkers, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.chunkers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Get a chunker by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Chunker>> {
+        self.chunkers.get(name).cloned()
+    }
+
+    /// Name of the chunker of a collection
+    pub fn chunker_name(&self, collection: &str) -> &str {
+        self.collections
+            .get(collection)
+            .unwrap_or(&self.default_chunker)
+    }
+
+    /// Split a document of a collection into chunks
+    ///
+    /// Chunks carry the document's metadata, its ID, their position and the
+    /// name of the chunker, besides the chunker's own metadata.
+    pub async fn chunk_document(
+        &self,
+        collection: &str,
+        document: &Document,
+    ) -> Result<Vec<Chunk>, RagError> {
+        let name = self.chunker_name(collection);
+        let chunker = self
+            .get(name)
+            .ok_or_else(|| RagError::Other(format!("Unknown chunker '{}'", name)))?;
+        let chunks = chunker.chunk(document).await?;
+        Ok(chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut chunk)| {
+                for (key, value) in &document.metadata {
+                    chunk
+                        .metadata
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+                chunk
+                    .with_metadata(DOCUMENT_ID_KEY, document.id.clone())
+                    .with_metadata(CHUNK_INDEX_KEY, index.to_string())
+                    .with_metadata(CHUNKER_KEY, name)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(content: &str) -> Document {
+        Document {
+            id: "doc-1".to_string(),
+            content: content.to_string(),
+            metadata: HashMap::from([("path".to_string(), "notes.md".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_split_by_size() {
+        let text = "alpha beta gamma delta epsilon zeta eta theta";
+        let chunks = split_by_size(text, 20, 6);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 20));
+        // Windows break between words and overlap by a word
+        assert_eq!(chunks[0], "alpha beta gamma");
+        assert!(chunks[1].starts_with("gamma"));
+        assert!(chunks.last().unwrap().ends_with("theta"));
+        assert_eq!(split_by_size("", 20, 6), Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn test_registry_chunks_by_collection() {
+        let mut config = RagConfig::default();
+        config.chunking.collections = HashMap::from([("docs".to_string(), "markdown".to_string())]);
+        let registry = ChunkerRegistry::from_config(&config, "http://localhost:8080").unwrap();
+        assert_eq!(registry.names(), ["code", "fixed", "markdown", "semantic"]);
+        assert_eq!(registry.chunker_name("docs"), "markdown");
+        assert_eq!(registry.chunker_name("other"), "fixed");
+
+        let chunks = registry
+            .chunk_document("docs", &document("# Setup\nInstall it.\n# Usage\nRun it."))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].metadata[CHUNK_INDEX_KEY], "1");
+        assert_eq!(chunks[1].metadata[DOCUMENT_ID_KEY], "doc-1");
+        assert_eq!(chunks[1].metadata[CHUNKER_KEY], "markdown");
+        assert_eq!(chunks[1].metadata["path"], "notes.md");
+
+        config.chunking.collections =
+            HashMap::from([("docs".to_string(), "sentences".to_string())]);
+        assert!(ChunkerRegistry::from_config(&config, "http://localhost:8080").is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Semantic Chunking
+//!
+//! Splits text into sentences, embeds them, and groups consecutive sentences
+//! into a chunk until the topic shifts: a new chunk starts where the cosine
+//! similarity of a sentence's embedding to the previous sentence's falls
+//! below the threshold, or where the chunk would exceed the maximum size.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::{split_by_size, Chunk, Chunker};
+use crate::modules::memory::{cosine_similarity, Embedder};
+use crate::modules::rag_manager::types::{Document, RagError};
+
+/// Number of sentences embedded per request
+const EMBEDDING_BATCH: usize = 64;
+
+/// Chunker grouping sentences by the similarity of their embeddings
+pub struct SemanticChunker {
+    embedder: Arc<dyn Embedder>,
+    threshold: f32,
+    max_chunk_size: usize,
+}
+
+impl std::fmt::Debug for SemanticChunker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticChunker")
+            .field("threshold", &self.threshold)
+            .field("max_chunk_size", &self.max_chunk_size)
+            .finish()
+    }
+}
+
+impl SemanticChunker {
+    /// Create a chunker starting a new chunk where the similarity of adjacent
+    /// sentences falls below `threshold`, with chunks of at most
+    /// `max_chunk_size` characters
+    pub fn new(embedder: Arc<dyn Embedder>, threshold: f32, max_chunk_size: usize) -> Self {
+        Self {
+            embedder,
+            threshold,
+            max_chunk_size: max_chunk_size.max(1),
+        }
+    }
+}
+
+#[async_trait]
+impl Chunker for SemanticChunker {
+    async fn chunk(&self, document: &Document) -> Result<Vec<Chunk>, RagError> {
+        let sentences: Vec<String> = sentences(&document.content)
+            .into_iter()
+            .flat_map(|sentence| split_by_size(&sentence, self.max_chunk_size, 0))
+            .collect();
+        if sentences.len() <= 1 {
+            return Ok(sentences.into_iter().map(Chunk::new).collect());
+        }
+
+        let mut embeddings = Vec::with_capacity(sentences.len());
+        for batch in sentences.chunks(EMBEDDING_BATCH) {
+            let batch = self
+                .embedder
+                .embed(batch)
+                .await
+                .map_err(|e| RagError::Other(format!("Failed to embed sentences: {}", e)))?;
+            embeddings.extend(batch);
+        }
+        if embeddings.len() != sentences.len() {
+            return Err(RagError::Other(format!(
+                "Expected {} sentence embeddings, got {}",
+                sentences.len(),
+                embeddings.len()
+            )));
+        }
+
+        let mut chunks = Vec::new();
+        let mut current = sentences[0].clone();
+        for (i, sentence) in sentences.iter().enumerate().skip(1) {
+            let similarity = cosine_similarity(&embeddings[i - 1], &embeddings[i]);
+            let fits = current.chars().count() + sentence.chars().count() < self.max_chunk_size;
+            if similarity < self.threshold || !fits {
+                chunks.push(Chunk::new(std::mem::take(&mut current)));
+                current = sentence.clone();
+            } else {
+                current.push(' ');
+                current.push_str(sentence);
+            }
+        }
+        chunks.push(Chunk::new(current));
+        Ok(chunks)
+    }
+}
+
+/// Split text into sentences, at sentence punctuation followed by whitespace
+/// and at blank lines
+pub(crate) fn sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    for paragraph in text.split("\n\n") {
+        let mut start = 0;
+        for (i, c) in paragraph.char_indices() {
+            let end = i + c.len_utf8();
+            let ends_sentence = matches!(c, '.' | '!' | '?')
+                && paragraph[end..]
+                    .chars()
+                    .next()
+                    .is_none_or(char::is_whitespace);
+            if ends_sentence {
+                sentences.push(paragraph[start..end].to_string());
+                start = end;
+            }
+        }
+        sentences.push(paragraph[start..].to_string());
+    }
+    sentences
+        .into_iter()
+        .map(|sentence| sentence.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::memory::testing::KeywordEmbedder;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_semantic_chunks() {
+        let document = Document {
+            id: "policy".to_string(),
+            content: "Remote work is allowed. Remote days are logged!\n\nVacation is 25 days. \
+                      Vacation requests need approval? Ask HR."
+                .to_string(),
+            metadata: HashMap::new(),
+        };
+        let embedder = Arc::new(KeywordEmbedder(&["remote", "vacation"]));
+        let chunks = SemanticChunker::new(embedder, 0.8, 1000)
+            .chunk(&document)
+            .await
+            .unwrap();
+
+        let contents: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "Remote work is allowed. Remote days are logged!",
+                "Vacation is 25 days. Vacation requests need approval?",
+                "Ask HR.",
+            ]
+        );
+    }
+}
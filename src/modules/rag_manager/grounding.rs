@@ -0,0 +1,721 @@
+//! Answer Grounding
+//!
+//! Checks that answers generated from retrieved context are supported by it
+//! ("faithfulness"), before they are returned. A judge scores the share of
+//! the answer the retrieved chunks support:
+//!
+//! - `llm`: a chat model lists the claims of the answer the chunks do not
+//!   support, and scores the answer
+//! - `nli`: a natural language inference classifier checks whether each
+//!   sentence of the answer is entailed by one of the chunks
+//!
+//! Answers scoring below the minimum are either regenerated with stricter
+//! instructions, or returned flagged as low confidence. Judges failing to
+//! answer do not fail the request: the answer is returned unchecked.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use metrics::counter;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use crate::config::{GroundingAction, GroundingJudgeKind, RagConfig, RagGroundingConfig};
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ConnectorConfig, MessageRole,
+    ModelConnector, OpenAIConnector,
+};
+use crate::modules::rag_manager::chunking::semantic::sentences;
+use crate::modules::rag_manager::types::{ContextChunk, RagError};
+use crate::modules::telemetry::catalog::{RAG_GROUNDING_CHECKS, RAG_GROUNDING_REGENERATIONS};
+
+/// Instructions of the LLM judge
+const JUDGE_PROMPT: &str = "You check whether an answer is supported by the context it was \
+written from. List the claims of the answer that the context neither states nor implies. \
+Reply with a JSON object: {\"score\": <share of the answer's claims supported by the context, \
+from 0 to 1>, \"unsupported_claims\": [<claims not supported by the context>]}";
+
+/// Instructions added to the request when regenerating an unsupported answer
+const STRICT_PROMPT: &str = "Answer strictly from the information provided. Do not add facts, \
+figures or names that the information does not state. If the information does not answer the \
+question, say so.";
+
+/// Minimum entailment probability of a sentence supported by a chunk
+const ENTAILMENT_THRESHOLD: f32 = 0.5;
+
+/// Number of premise and hypothesis pairs classified per request
+const NLI_BATCH: usize = 32;
+
+/// Verdict of a judge on an answer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroundingVerdict {
+    /// Share of the answer supported by the chunks, from 0 to 1
+    pub score: f32,
+    /// Claims of the answer the chunks do not support
+    #[serde(default)]
+    pub unsupported_claims: Vec<String>,
+}
+
+/// Outcome of the grounding check of an answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroundingResult {
+    /// Judge that checked the answer
+    pub judge: String,
+    /// Share of the answer supported by the retrieved chunks, from 0 to 1
+    pub score: f32,
+    /// Whether the answer scored below the minimum
+    pub low_confidence: bool,
+    /// Number of times the answer was regenerated
+    pub regenerations: u32,
+    /// Claims of the answer the retrieved chunks do not support
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unsupported_claims: Vec<String>,
+}
+
+impl GroundingResult {
+    /// Value of the grounding header, e.g. `supported; score=0.92`
+    pub fn header_value(&self) -> String {
+        let status = if self.low_confidence {
+            "low-confidence"
+        } else {
+            "supported"
+        };
+        let mut value = format!("{}; score={:.2}", status, self.score);
+        if self.regenerations > 0 {
+            value.push_str(&format!("; regenerations={}", self.regenerations));
+        }
+        value
+    }
+}
+
+/// Response generated from retrieved context, with its grounding check
+#[derive(Debug, Clone)]
+pub struct GroundedResponse {
+    /// Response returned to the client
+    pub response: ChatCompletionResponse,
+    /// Grounding check of the response, none when it was not checked
+    pub grounding: Option<GroundingResult>,
+}
+
+/// Judge of whether answers are supported by retrieved chunks
+#[async_trait]
+pub trait GroundingJudge: Send + Sync {
+    /// Name of the judge, as reported in metrics and response metadata
+    fn name(&self) -> &'static str;
+
+    /// Score the share of an answer supported by chunks
+    async fn judge(
+        &self,
+        answer: &str,
+        chunks: &[ContextChunk],
+    ) -> Result<GroundingVerdict, RagError>;
+}
+
+/// Judge asking a chat model which claims of an answer are unsupported
+pub struct LlmGroundingJudge {
+    connector: Arc<dyn ModelConnector>,
+    model: String,
+}
+
+impl LlmGroundingJudge {
+    /// Create a judge asking `model` through a connector
+    pub fn new(connector: Arc<dyn ModelConnector>, model: &str) -> Self {
+        Self {
+            connector,
+            model: model.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl GroundingJudge for LlmGroundingJudge {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    async fn judge(
+        &self,
+        answer: &str,
+        chunks: &[ContextChunk],
+    ) -> Result<GroundingVerdict, RagError> {
+        let context: Vec<&str> = chunks.iter().map(|chunk| chunk.content.as_str()).collect();
+        let request = ChatCompletionRequest {
+            model: self.model.clone(),
+            messages: vec![
+                message(MessageRole::System, JUDGE_PROMPT),
+                message(
+                    MessageRole::User,
+                    &format!(
+                        "Context:\n\n{}\n\nAnswer:\n\n{}",
+                        context.join("\n\n---\n\n"),
+                        answer
+                    ),
+                ),
+            ],
+            temperature: Some(0.0),
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        };
+        let response = self
+            .connector
+            .generate(request)
+            .await
+            .map_err(|e| RagError::Other(format!("Grounding judge failed: {}", e)))?;
+        let content = response
+            .choices
+            .first()
+            .map(|choice| choice.message.content.as_str())
+            .unwrap_or_default();
+        parse_verdict(content)
+    }
+}
+
+/// Judge classifying whether the chunks entail each sentence of an answer,
+/// through a text-embeddings-inference `/predict` endpoint serving an NLI
+/// model
+pub struct NliGroundingJudge {
+    client: Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl NliGroundingJudge {
+    /// Create a judge for an endpoint
+    pub fn new(endpoint: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    /// Entailment probability of each premise and hypothesis pair
+    async fn entailment(&self, pairs: &[[&str; 2]]) -> Result<Vec<f32>, RagError> {
+        let mut request = self
+            .client
+            .post(format!("{}/predict", self.endpoint))
+            .json(&json!({ "inputs": pairs }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RagError::Other(format!("NLI request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| RagError::SerializationError(format!("Invalid NLI response: {}", e)))?;
+        if !status.is_success() {
+            return Err(RagError::Other(format!(
+                "NLI endpoint returned {}: {}",
+                status, body
+            )));
+        }
+
+        // A single pair gets a list of labels, several pairs a list of lists
+        let predictions = match body.as_array() {
+            Some(items) if items.iter().all(Value::is_array) => items.clone(),
+            Some(_) => vec![body],
+            None => Vec::new(),
+        };
+        if predictions.len() != pairs.len() {
+            return Err(RagError::SerializationError(format!(
+                "Expected {} NLI predictions, got {}",
+                pairs.len(),
+                predictions.len()
+            )));
+        }
+        Ok(predictions
+            .iter()
+            .map(|labels| {
+                labels
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|label| {
+                        label
+                            .get("label")
+                            .and_then(Value::as_str)
+                            .is_some_and(|name| name.to_lowercase().starts_with("entail"))
+                    })
+                    .filter_map(|label| label.get("score").and_then(Value::as_f64))
+                    .fold(0.0f32, |max, score| max.max(score as f32))
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl GroundingJudge for NliGroundingJudge {
+    fn name(&self) -> &'static str {
+        "nli"
+    }
+
+    async fn judge(
+        &self,
+        answer: &str,
+        chunks: &[ContextChunk],
+    ) -> Result<GroundingVerdict, RagError> {
+        let claims = sentences(answer);
+        if claims.is_empty() || chunks.is_empty() {
+            return Ok(GroundingVerdict {
+                score: if claims.is_empty() { 1.0 } else { 0.0 },
+                unsupported_claims: claims,
+            });
+        }
+
+        let pairs: Vec<[&str; 2]> = claims
+            .iter()
+            .flat_map(|claim| {
+                chunks
+                    .iter()
+                    .map(move |chunk| [chunk.content.as_str(), claim.as_str()])
+            })
+            .collect();
+        let mut entailment = Vec::with_capacity(pairs.len());
+        for batch in pairs.chunks(NLI_BATCH) {
+            entailment.extend(self.entailment(batch).await?);
+        }
+
+        // A claim is supported when one of the chunks entails it
+        let unsupported_claims: Vec<String> = claims
+            .iter()
+            .zip(entailment.chunks(chunks.len()))
+            .filter(|(_, scores)| !scores.iter().any(|s| *s >= ENTAILMENT_THRESHOLD))
+            .map(|(claim, _)| claim.clone())
+            .collect();
+        Ok(GroundingVerdict {
+            score: 1.0 - unsupported_claims.len() as f32 / claims.len() as f32,
+            unsupported_claims,
+        })
+    }
+}
+
+/// Checks answers against the chunks they were generated from
+pub struct GroundingChecker {
+    config: RagGroundingConfig,
+    judge: Arc<dyn GroundingJudge>,
+}
+
+impl std::fmt::Debug for GroundingChecker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroundingChecker")
+            .field("config", &self.config)
+            .field("judge", &self.judge.name())
+            .finish()
+    }
+}
+
+impl GroundingChecker {
+    /// Create a checker with a judge
+    pub fn new(config: RagGroundingConfig, judge: Arc<dyn GroundingJudge>) -> Self {
+        Self { config, judge }
+    }
+
+    /// Create the checker of the RAG configuration, if grounding is enabled
+    ///
+    /// The `llm` judge is asked through `default_endpoint`, the router,
+    /// unless `[rag.grounding]` configures its own endpoint.
+    pub fn from_config(config: &RagConfig, default_endpoint: &str) -> Option<Self> {
+        let grounding = &config.grounding;
+        if !grounding.enabled {
+            return None;
+        }
+        let endpoint = grounding.endpoint.as_deref().unwrap_or(default_endpoint);
+        let judge: Arc<dyn GroundingJudge> = match grounding.judge {
+            GroundingJudgeKind::Llm => {
+                let connector = Arc::new(OpenAIConnector::new(ConnectorConfig {
+                    base_url: endpoint.to_string(),
+                    api_key: grounding.api_key.clone(),
+                    ..ConnectorConfig::default()
+                }));
+                Arc::new(LlmGroundingJudge::new(connector, &grounding.model))
+            }
+            GroundingJudgeKind::Nli => {
+                Arc::new(NliGroundingJudge::new(endpoint, grounding.api_key.clone()))
+            }
+        };
+        Some(Self::new(grounding.clone(), judge))
+    }
+
+    /// Check an answer against the chunks it was generated from
+    pub async fn check(
+        &self,
+        answer: &str,
+        chunks: &[ContextChunk],
+    ) -> Result<GroundingResult, RagError> {
+        let judge = self.judge.name();
+        let verdict = match self.judge.judge(answer, chunks).await {
+            Ok(verdict) => verdict,
+            Err(e) => {
+                counter!(RAG_GROUNDING_CHECKS, 1, "judge" => judge, "outcome" => "error");
+                return Err(e);
+            }
+        };
+        let score = verdict.score.clamp(0.0, 1.0);
+        let low_confidence = score < self.config.min_score;
+        let outcome = if low_confidence {
+            "unsupported"
+        } else {
+            "supported"
+        };
+        counter!(RAG_GROUNDING_CHECKS, 1, "judge" => judge, "outcome" => outcome);
+        Ok(GroundingResult {
+            judge: judge.to_string(),
+            score,
+            low_confidence,
+            regenerations: 0,
+            unsupported_claims: verdict.unsupported_claims,
+        })
+    }
+
+    /// Generate the answer to a request carrying retrieved chunks, and check
+    /// it against them
+    ///
+    /// With the `regenerate` action, unsupported answers are regenerated
+    /// with stricter instructions, and the best scoring answer is returned.
+    /// Answers are returned unchecked when there are no chunks or the judge
+    /// fails.
+    pub async fn generate(
+        &self,
+        connector: &dyn ModelConnector,
+        request: ChatCompletionRequest,
+        chunks: &[ContextChunk],
+    ) -> Result<GroundedResponse, RagError> {
+        let mut response = generate(connector, request.clone()).await?;
+        if chunks.is_empty() {
+            return Ok(GroundedResponse {
+                response,
+                grounding: None,
+            });
+        }
+
+        let mut best: Option<(ChatCompletionResponse, GroundingResult)> = None;
+        let mut regenerations = 0;
+        loop {
+            let mut grounding = match self.check(answer(&response), chunks).await {
+                Ok(grounding) => grounding,
+                Err(e) => {
+                    warn!(
+                        "Grounding check failed, returning the answer unchecked: {}",
+                        e
+                    );
+                    let (response, grounding) = match best {
+                        Some((response, grounding)) => (response, Some(grounding)),
+                        None => (response, None),
+                    };
+                    return Ok(GroundedResponse {
+                        response,
+                        grounding,
+                    });
+                }
+            };
+            grounding.regenerations = regenerations;
+            debug!(
+                "Grounding score {:.2} after {} regenerations",
+                grounding.score, regenerations
+            );
+
+            let done = !grounding.low_confidence
+                || self.config.action == GroundingAction::Flag
+                || regenerations >= self.config.max_regenerations;
+            let unsupported_claims = grounding.unsupported_claims.clone();
+            best = match best {
+                Some((previous, previous_grounding))
+                    if previous_grounding.score >= grounding.score =>
+                {
+                    Some((
+                        previous,
+                        GroundingResult {
+                            regenerations,
+                            ..previous_grounding
+                        },
+                    ))
+                }
+                _ => Some((response, grounding)),
+            };
+            if done {
+                let (response, grounding) = best.expect("An answer was checked");
+                return Ok(GroundedResponse {
+                    response,
+                    grounding: Some(grounding),
+                });
+            }
+
+            regenerations += 1;
+            counter!(RAG_GROUNDING_REGENERATIONS, 1, "judge" => self.judge.name());
+            response = generate(connector, strict_request(&request, &unsupported_claims)).await?;
+        }
+    }
+}
+
+/// Generate the response to a request
+async fn generate(
+    connector: &dyn ModelConnector,
+    request: ChatCompletionRequest,
+) -> Result<ChatCompletionResponse, RagError> {
+    connector
+        .generate(request)
+        .await
+        .map_err(|e| RagError::Other(format!("Generation failed: {}", e)))
+}
+
+/// Text of the first choice of a response
+fn answer(response: &ChatCompletionResponse) -> &str {
+    response
+        .choices
+        .first()
+        .map(|choice| choice.message.content.as_str())
+        .unwrap_or_default()
+}
+
+/// Copy of a request instructing the model to stick to the provided
+/// information and to leave out the claims found unsupported
+fn strict_request(
+    request: &ChatCompletionRequest,
+    unsupported_claims: &[String],
+) -> ChatCompletionRequest {
+    let mut instructions = STRICT_PROMPT.to_string();
+    if !unsupported_claims.is_empty() {
+        instructions.push_str(
+            "\n\nA previous answer made these claims, which the information does not support:\n",
+        );
+        for claim in unsupported_claims {
+            instructions.push_str(&format!("- {}\n", claim));
+        }
+    }
+
+    let mut request = request.clone();
+    let position = request
+        .messages
+        .iter()
+        .position(|m| m.role != MessageRole::System)
+        .unwrap_or(request.messages.len());
+    request
+        .messages
+        .insert(position, message(MessageRole::System, &instructions));
+    request
+}
+
+fn message(role: MessageRole, content: &str) -> ChatMessage {
+    ChatMessage {
+        role,
+        content: content.to_string(),
+        name: None,
+        function_call: None,
+        tool_calls: None,
+        tool_call_id: None,
+    }
+}
+
+/// Parse the verdict of the LLM judge
+///
+/// Accepts a JSON object, optionally inside a code fence or prose.
+fn parse_verdict(content: &str) -> Result<GroundingVerdict, RagError> {
+    let trimmed = content.trim();
+    let json = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    };
+    serde_json::from_str(json).map_err(|e| {
+        RagError::SerializationError(format!("Invalid grounding verdict '{}': {}", trimmed, e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionChoice, ConnectorError, StreamingResponse,
+    };
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Connector answering with the given answers in turn, recording requests
+    struct ScriptedConnector {
+        config: ConnectorConfig,
+        answers: Mutex<Vec<&'static str>>,
+        requests: Mutex<Vec<ChatCompletionRequest>>,
+    }
+
+    impl ScriptedConnector {
+        fn new(answers: &[&'static str]) -> Self {
+            Self {
+                config: ConnectorConfig::default(),
+                answers: Mutex::new(answers.iter().rev().copied().collect()),
+                requests: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ModelConnector for ScriptedConnector {
+        async fn generate(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, ConnectorError> {
+            self.requests.lock().unwrap().push(request.clone());
+            let answer = self.answers.lock().unwrap().pop().unwrap_or_default();
+            Ok(ChatCompletionResponse {
+                id: "resp".to_string(),
+                model: request.model,
+                created: 0,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message: message(MessageRole::Assistant, answer),
+                    finish_reason: Some("stop".to_string()),
+                    content_filter: None,
+                }],
+                usage: None,
+            })
+        }
+
+        async fn generate_streaming(
+            &self,
+            _request: ChatCompletionRequest,
+        ) -> Result<StreamingResponse, ConnectorError> {
+            Err(ConnectorError::Other("not supported".to_string()))
+        }
+
+        fn get_config(&self) -> &ConnectorConfig {
+            &self.config
+        }
+
+        fn update_config(&mut self, config: ConnectorConfig) {
+            self.config = config;
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+            Ok(vec!["mock".to_string()])
+        }
+    }
+
+    fn chunks() -> Vec<ContextChunk> {
+        vec![ContextChunk {
+            content: "Vacation is 25 days a year.".to_string(),
+            source: "handbook.md".to_string(),
+            relevance_score: 0.9,
+            metadata: HashMap::new(),
+        }]
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![
+                message(MessageRole::System, "Use the following information."),
+                message(MessageRole::User, "How long is vacation?"),
+            ],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    fn checker(action: GroundingAction, judge: &[&'static str]) -> GroundingChecker {
+        let config = RagGroundingConfig {
+            enabled: true,
+            action,
+            ..RagGroundingConfig::default()
+        };
+        let judge = LlmGroundingJudge::new(Arc::new(ScriptedConnector::new(judge)), "judge");
+        GroundingChecker::new(config, Arc::new(judge))
+    }
+
+    #[test]
+    fn test_parse_verdict() {
+        let verdict = parse_verdict(
+            "```json\n{\"score\": 0.5, \"unsupported_claims\": [\"Vacation is paid\"]}\n```",
+        )
+        .unwrap();
+        assert_eq!(verdict.score, 0.5);
+        assert_eq!(verdict.unsupported_claims, ["Vacation is paid"]);
+        assert!(parse_verdict("Looks fine").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_answers_are_flagged() {
+        let checker = checker(
+            GroundingAction::Flag,
+            &[r#"{"score": 0.4, "unsupported_claims": ["Vacation is paid"]}"#],
+        );
+        let generator = ScriptedConnector::new(&["Vacation is 25 paid days."]);
+
+        let grounded = checker
+            .generate(&generator, request(), &chunks())
+            .await
+            .unwrap();
+        let grounding = grounded.grounding.unwrap();
+        assert!(grounding.low_confidence);
+        assert_eq!(grounding.regenerations, 0);
+        assert_eq!(grounding.header_value(), "low-confidence; score=0.40");
+        assert_eq!(generator.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_answers_are_regenerated() {
+        let checker = checker(
+            GroundingAction::Regenerate,
+            &[
+                r#"{"score": 0.4, "unsupported_claims": ["Vacation is paid"]}"#,
+                r#"{"score": 1.0, "unsupported_claims": []}"#,
+            ],
+        );
+        let generator =
+            ScriptedConnector::new(&["Vacation is 25 paid days.", "Vacation is 25 days a year."]);
+
+        let grounded = checker
+            .generate(&generator, request(), &chunks())
+            .await
+            .unwrap();
+        assert_eq!(answer(&grounded.response), "Vacation is 25 days a year.");
+        let grounding = grounded.grounding.unwrap();
+        assert!(!grounding.low_confidence);
+        assert_eq!(
+            grounding.header_value(),
+            "supported; score=1.00; regenerations=1"
+        );
+
+        // The stricter instructions follow the context and name the claims
+        let requests = generator.requests.lock().unwrap();
+        assert_eq!(requests[1].messages.len(), 3);
+        assert_eq!(requests[1].messages[1].role, MessageRole::System);
+        assert!(requests[1].messages[1]
+            .content
+            .contains("- Vacation is paid"));
+    }
+
+    #[tokio::test]
+    async fn test_judge_failures_return_unchecked_answers() {
+        let checker = checker(GroundingAction::Regenerate, &["not a verdict"]);
+        let generator = ScriptedConnector::new(&["Vacation is 25 days a year."]);
+
+        let grounded = checker
+            .generate(&generator, request(), &chunks())
+            .await
+            .unwrap();
+        assert!(grounded.grounding.is_none());
+        assert_eq!(answer(&grounded.response), "Vacation is 25 days a year.");
+    }
+}
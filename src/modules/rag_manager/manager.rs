@@ -1,9 +1,15 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::modules::model_registry::connectors::{ChatCompletionRequest, ChatMessage, MessageRole};
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatMessage, MessageRole, ModelConnector,
+};
+use crate::modules::rag_manager::cache::RetrievalCache;
+use crate::modules::rag_manager::chunking::{Chunk, ChunkerRegistry};
+use crate::modules::rag_manager::grounding::{GroundedResponse, GroundingChecker};
+use crate::modules::rag_manager::injection::InjectionGuard;
 use crate::modules::rag_manager::source::ContextSource;
-use crate::modules::rag_manager::types::{ContextChunk, RagError};
+use crate::modules::rag_manager::types::{ContextChunk, Document, RagError};
 
 /// The RAG Manager
 ///
@@ -12,6 +18,14 @@ use crate::modules::rag_manager::types::{ContextChunk, RagError};
 pub struct RagManager {
     /// The context sources, keyed by name
     sources: HashMap<String, Arc<dyn ContextSource>>,
+    /// Guard of prompts against instructions in retrieved chunks
+    injection_guard: InjectionGuard,
+    /// Cache of retrieval results for similar queries, if enabled
+    retrieval_cache: Option<RetrievalCache>,
+    /// Chunkers splitting documents before they are indexed
+    chunkers: ChunkerRegistry,
+    /// Checker of answers against the retrieved chunks, if enabled
+    grounding_checker: Option<GroundingChecker>,
 }
 
 impl std::fmt::Debug for RagManager {
@@ -19,6 +33,10 @@ impl std::fmt::Debug for RagManager {
         f.debug_struct("RagManager")
             .field("sources_count", &self.sources.len())
             .field("source_names", &self.sources.keys().collect::<Vec<_>>())
+            .field("injection_guard", &self.injection_guard)
+            .field("retrieval_cache", &self.retrieval_cache)
+            .field("chunkers", &self.chunkers)
+            .field("grounding_checker", &self.grounding_checker)
             .finish()
     }
 }
@@ -28,21 +46,82 @@ impl RagManager {
     pub fn new() -> Self {
         Self {
             sources: HashMap::new(),
+            injection_guard: InjectionGuard::default(),
+            retrieval_cache: None,
+            chunkers: ChunkerRegistry::default(),
+            grounding_checker: None,
         }
     }
 
+    /// Set the guard tagging injected chunks and detecting prompt injections
+    pub fn with_injection_guard(mut self, injection_guard: InjectionGuard) -> Self {
+        self.injection_guard = injection_guard;
+        self
+    }
+
+    /// Cache retrieval results, serving similar queries without querying
+    /// the sources
+    pub fn with_retrieval_cache(mut self, retrieval_cache: RetrievalCache) -> Self {
+        self.retrieval_cache = Some(retrieval_cache);
+        self
+    }
+
+    /// Set the chunkers splitting the documents of each collection
+    pub fn with_chunkers(mut self, chunkers: ChunkerRegistry) -> Self {
+        self.chunkers = chunkers;
+        self
+    }
+
+    /// Check that generated answers are supported by the retrieved chunks
+    pub fn with_grounding_checker(mut self, grounding_checker: GroundingChecker) -> Self {
+        self.grounding_checker = Some(grounding_checker);
+        self
+    }
+
+    /// Get the grounding checker, if enabled
+    pub fn grounding_checker(&self) -> Option<&GroundingChecker> {
+        self.grounding_checker.as_ref()
+    }
+
+    /// Get the chunkers splitting the documents of each collection
+    pub fn chunkers(&self) -> &ChunkerRegistry {
+        &self.chunkers
+    }
+
+    /// Split a document of a collection into chunks to index, with the
+    /// collection's chunker
+    pub async fn chunk_document(
+        &self,
+        collection: &str,
+        document: &Document,
+    ) -> Result<Vec<Chunk>, RagError> {
+        self.chunkers.chunk_document(collection, document).await
+    }
+
+    /// Get the retrieval cache, if enabled
+    pub fn retrieval_cache(&self) -> Option<&RetrievalCache> {
+        self.retrieval_cache.as_ref()
+    }
+
     /// Add a context source
     ///
+    /// Cached retrieval results are dropped, as they miss the new source.
+    ///
     /// # Arguments
     ///
     /// * `source` - The context source to add
     pub fn add_source(&mut self, source: Arc<dyn ContextSource>) {
         let name = source.get_name();
         self.sources.insert(name, source);
+        if let Some(cache) = &self.retrieval_cache {
+            cache.clear();
+        }
     }
 
     /// Remove a context source
     ///
+    /// Cached retrieval results are dropped, as they may hold its chunks.
+    ///
     /// # Arguments
     ///
     /// * `name` - The name of the source to remove
@@ -51,6 +130,9 @@ impl RagManager {
     ///
     /// The removed source, if it existed
     pub fn remove_source(&mut self, name: &str) -> Option<Arc<dyn ContextSource>> {
+        if let Some(cache) = &self.retrieval_cache {
+            cache.clear();
+        }
         self.sources.remove(name)
     }
 
@@ -125,6 +207,26 @@ impl RagManager {
         stats.insert("average_retrieval_time_ms".to_string(), 0.0);
         stats.insert("average_results_per_query".to_string(), 0.0);
 
+        if let Some(cache) = &self.retrieval_cache {
+            let cache_stats = cache.stats();
+            stats.insert(
+                "cache_exact_hits".to_string(),
+                cache_stats.exact_hits as f64,
+            );
+            stats.insert(
+                "cache_similar_hits".to_string(),
+                cache_stats.similar_hits as f64,
+            );
+            stats.insert("cache_misses".to_string(), cache_stats.misses as f64);
+            stats.insert("cache_expired".to_string(), cache_stats.expired as f64);
+            stats.insert("cache_hit_rate".to_string(), cache_stats.hit_rate());
+            stats.insert("cache_entries".to_string(), cache_stats.entries as f64);
+            stats.insert(
+                "cache_oldest_entry_age_secs".to_string(),
+                cache_stats.oldest_entry_age_secs,
+            );
+        }
+
         stats
     }
 
@@ -197,7 +299,27 @@ impl RagManager {
             return Ok(Vec::new());
         }
 
+        match &self.retrieval_cache {
+            Some(cache) => {
+                cache
+                    .get_or_retrieve(query, max_chunks, || {
+                        self.retrieve_from_sources(query, max_chunks)
+                    })
+                    .await
+            }
+            None => Ok(self.retrieve_from_sources(query, max_chunks).await?.0),
+        }
+    }
+
+    /// Retrieve context from all sources, also returning whether every
+    /// source answered
+    async fn retrieve_from_sources(
+        &self,
+        query: &str,
+        max_chunks: usize,
+    ) -> Result<(Vec<ContextChunk>, bool), RagError> {
         let mut all_chunks = Vec::new();
+        let mut complete = true;
 
         for source in self.sources.values() {
             match source.get_context(query, max_chunks).await {
@@ -205,6 +327,7 @@ impl RagManager {
                 Err(e) => {
                     // Log the error but continue with other sources
                     eprintln!("Error retrieving context from {}: {}", source.get_name(), e);
+                    complete = false;
                 }
             }
         }
@@ -221,13 +344,15 @@ impl RagManager {
             all_chunks.truncate(max_chunks);
         }
 
-        Ok(all_chunks)
+        Ok((all_chunks, complete))
     }
 
     /// Inject context into a chat completion request
     ///
     /// This method retrieves context based on the query and injects it
-    /// as a system message at the beginning of the request.
+    /// as a system message at the beginning of the request. The chunks are
+    /// tagged as untrusted and checked for prompt injections, and may be
+    /// preceded by a system message warning the model about them.
     ///
     /// # Arguments
     ///
@@ -244,18 +369,51 @@ impl RagManager {
         query: &str,
         max_chunks: usize,
     ) -> Result<(), RagError> {
-        let chunks = self.retrieve_context(query, max_chunks).await?;
+        let chunks = self
+            .injection_guard
+            .tag(self.retrieve_context(query, max_chunks).await?);
+        self.inject_chunks(request, &chunks);
+        Ok(())
+    }
+
+    /// Answer a request from the context retrieved for a query
+    ///
+    /// The context is injected into the request as by
+    /// [`RagManager::inject_context`]. With a grounding checker, the answer
+    /// is checked against the context, and regenerated or flagged when the
+    /// context does not support it.
+    pub async fn generate_grounded(
+        &self,
+        connector: &dyn ModelConnector,
+        mut request: ChatCompletionRequest,
+        query: &str,
+        max_chunks: usize,
+    ) -> Result<GroundedResponse, RagError> {
+        let chunks = self
+            .injection_guard
+            .tag(self.retrieve_context(query, max_chunks).await?);
+        self.inject_chunks(&mut request, &chunks);
+
+        match &self.grounding_checker {
+            Some(checker) => checker.generate(connector, request, &chunks).await,
+            None => Ok(GroundedResponse {
+                response: connector
+                    .generate(request)
+                    .await
+                    .map_err(|e| RagError::Other(format!("Generation failed: {}", e)))?,
+                grounding: None,
+            }),
+        }
+    }
 
+    /// Insert tagged chunks into a request as system messages
+    fn inject_chunks(&self, request: &mut ChatCompletionRequest, chunks: &[ContextChunk]) {
         if chunks.is_empty() {
-            return Ok(());
+            return;
         }
 
         // Format the context as a system message
-        let context_text = chunks
-            .iter()
-            .map(|chunk| format!("Source: {}\n\n{}", chunk.source, chunk.content))
-            .collect::<Vec<_>>()
-            .join("\n\n---\n\n");
+        let context_text = self.injection_guard.render(chunks);
 
         // Insert as a system message at the beginning
         request.messages.insert(
@@ -269,10 +427,23 @@ impl RagManager {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
         );
 
-        Ok(())
+        if let Some(warning) = self.injection_guard.system_warning(chunks) {
+            request.messages.insert(
+                0,
+                ChatMessage {
+                    role: MessageRole::System,
+                    content: warning,
+                    name: None,
+                    function_call: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+            );
+        }
     }
 
     /// Fuse multiple context chunks into a single string
@@ -416,6 +587,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -432,12 +604,14 @@ mod tests {
             .await
             .unwrap();
 
-        // Verify the request
-        assert_eq!(request.messages.len(), 2);
+        // Verify the request: a warning about the untrusted context, then the context
+        assert_eq!(request.messages.len(), 3);
         assert_eq!(request.messages[0].role, MessageRole::System);
-        assert!(request.messages[0]
+        assert!(request.messages[0].content.contains("untrusted"));
+        assert_eq!(request.messages[1].role, MessageRole::System);
+        assert!(request.messages[1]
             .content
-            .contains("This is a test document."));
+            .contains("<untrusted_content source=\"test.txt\">\nThis is a test document."));
     }
 
     #[tokio::test]
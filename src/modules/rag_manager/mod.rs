@@ -5,13 +5,21 @@
 //! integration with LLM requests.
 
 // Private module declarations
+pub mod cache;
+pub mod chunking;
 pub mod file_source;
+pub mod grounding;
+pub mod injection;
 pub mod manager;
 pub mod source;
 pub mod types;
 
 // Re-export specific types for public API
+pub use cache::{RetrievalCache, RetrievalCacheStats};
+pub use chunking::{Chunk, Chunker, ChunkerRegistry};
 pub use file_source::FileContextSource;
+pub use grounding::{GroundedResponse, GroundingChecker, GroundingResult};
+pub use injection::InjectionGuard;
 pub use manager::RagManager;
 pub use source::ContextSource;
 pub use types::{ContextChunk, Document as RagDocument, RAGConfig, RagError};
@@ -91,6 +99,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -107,11 +116,13 @@ mod tests {
             .await
             .unwrap();
 
-        // Verify the request
-        assert_eq!(request.messages.len(), 2);
+        // Verify the request: a warning about the untrusted context, then the context
+        assert_eq!(request.messages.len(), 3);
         assert_eq!(request.messages[0].role, MessageRole::System);
-        assert!(request.messages[0]
+        assert!(request.messages[0].content.contains("untrusted"));
+        assert_eq!(request.messages[1].role, MessageRole::System);
+        assert!(request.messages[1]
             .content
-            .contains("This is a test document."));
+            .contains("<untrusted_content source=\"test.txt\">\nThis is a test document."));
     }
 }
@@ -0,0 +1,163 @@
+//! Remote Client
+//!
+//! A small JSON client for the HTTP API of a deployment, authenticating
+//! with the admin key of the selected context.
+
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use serde_json::Value;
+
+use super::{Context, RemoteError};
+
+/// Timeout of a management request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Client for the HTTP API of a remote deployment
+#[derive(Debug, Clone)]
+pub struct RemoteClient {
+    http: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl RemoteClient {
+    /// Create a client for a context
+    pub fn new(context: &Context) -> Result<Self, RemoteError> {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            http,
+            endpoint: context.endpoint.trim_end_matches('/').to_string(),
+            api_key: context.api_key()?,
+        })
+    }
+
+    /// Base URL of the deployment
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Send a GET request
+    pub async fn get(&self, path: &str) -> Result<Value, RemoteError> {
+        self.send(Method::GET, path, None).await
+    }
+
+    /// Send a POST request with an optional JSON body
+    pub async fn post(&self, path: &str, body: Option<Value>) -> Result<Value, RemoteError> {
+        self.send(Method::POST, path, body).await
+    }
+
+    /// Send a PUT request with a JSON body
+    pub async fn put(&self, path: &str, body: Value) -> Result<Value, RemoteError> {
+        self.send(Method::PUT, path, Some(body)).await
+    }
+
+    /// Send a DELETE request
+    pub async fn delete(&self, path: &str) -> Result<Value, RemoteError> {
+        self.send(Method::DELETE, path, None).await
+    }
+
+    /// Send a request and decode the JSON response, `null` for an empty body
+    pub async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, RemoteError> {
+        let (status, value) = self.send_any(method, path, body).await?;
+        if status.is_success() {
+            Ok(value)
+        } else {
+            Err(api_error(status, &value))
+        }
+    }
+
+    /// Send a request and decode its response whatever its status, e.g. the
+    /// 503 diagnostics of an unhealthy role
+    pub async fn send_any(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<(StatusCode, Value), RemoteError> {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.endpoint, path));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        let value = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(&text).unwrap_or(Value::String(text))
+        };
+        Ok((status, value))
+    }
+}
+
+/// Percent-encode an ID for use as a path segment
+pub fn path_segment(segment: &str) -> String {
+    let mut url = reqwest::Url::parse("http://localhost/").expect("valid base URL");
+    url.path_segments_mut()
+        .expect("base URL has a path")
+        .push(segment);
+    url.path()[1..].to_string()
+}
+
+/// Build an error from an error response, using the message of an
+/// OpenAI-style error body when there is one
+fn api_error(status: StatusCode, body: &Value) -> RemoteError {
+    let message = body
+        .pointer("/error/message")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| body.as_str().map(str::to_string))
+        .unwrap_or_else(|| {
+            status
+                .canonical_reason()
+                .unwrap_or("request failed")
+                .to_string()
+        });
+    RemoteError::Api {
+        status: status.as_u16(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_path_segment() {
+        assert_eq!(path_segment("gpt-4o"), "gpt-4o");
+        assert_eq!(path_segment("org/model v2"), "org%2Fmodel%20v2");
+    }
+
+    #[test]
+    fn test_api_error_message() {
+        let body =
+            json!({ "error": { "message": "Model not found: x", "code": "model_not_found" } });
+        match api_error(StatusCode::NOT_FOUND, &body) {
+            RemoteError::Api { status, message } => {
+                assert_eq!(status, 404);
+                assert_eq!(message, "Model not found: x");
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        match api_error(StatusCode::BAD_GATEWAY, &Value::Null) {
+            RemoteError::Api { message, .. } => assert_eq!(message, "Bad Gateway"),
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+}
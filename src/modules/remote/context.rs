@@ -0,0 +1,279 @@
+//! Remote Contexts
+//!
+//! A context names a deployment: the endpoint of its HTTP API and the admin
+//! key to call it with. The key can be stored in the contexts file or read
+//! from an environment variable at call time. Contexts are kept in a TOML
+//! file, `~/.intellirouter/contexts.toml` by default, along with the name of
+//! the current context.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::RemoteError;
+
+/// Environment variable overriding the path of the contexts file
+pub const CONTEXTS_FILE_ENV: &str = "INTELLIROUTER_CONTEXTS";
+
+/// Environment variable selecting a context instead of the current one
+pub const CONTEXT_ENV: &str = "INTELLIROUTER_CONTEXT";
+
+/// A remote deployment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Context {
+    /// Base URL of the deployment's HTTP API
+    pub endpoint: String,
+
+    /// Admin API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+
+    /// Environment variable holding the admin API key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_env: Option<String>,
+}
+
+impl Context {
+    /// Resolve the admin API key, preferring the environment variable
+    pub fn api_key(&self) -> Result<Option<String>, RemoteError> {
+        match &self.api_key_env {
+            Some(var) => std::env::var(var).map(Some).map_err(|_| {
+                RemoteError::InvalidContext(format!("environment variable {} is not set", var))
+            }),
+            None => Ok(self.api_key.clone()),
+        }
+    }
+
+    /// Check that the endpoint is an HTTP URL
+    fn validate(&self) -> Result<(), RemoteError> {
+        let url = reqwest::Url::parse(&self.endpoint)
+            .map_err(|e| RemoteError::InvalidContext(format!("{}: {}", self.endpoint, e)))?;
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(RemoteError::InvalidContext(format!(
+                "{}: endpoint must be an http or https URL",
+                self.endpoint
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The saved contexts and the current one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContextStore {
+    /// Name of the current context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+
+    /// Contexts by name
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+
+    /// File the store was loaded from
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ContextStore {
+    /// Path of the contexts file
+    pub fn default_path() -> PathBuf {
+        if let Ok(path) = std::env::var(CONTEXTS_FILE_ENV) {
+            return PathBuf::from(path);
+        }
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home)
+            .join(".intellirouter")
+            .join("contexts.toml")
+    }
+
+    /// Load the contexts file, starting empty if it does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RemoteError> {
+        let path = path.as_ref();
+        let mut store = if path.exists() {
+            let content = fs::read_to_string(path)?;
+            toml::from_str::<ContextStore>(&content)
+                .map_err(|e| RemoteError::Store(format!("{}: {}", path.display(), e)))?
+        } else {
+            ContextStore::default()
+        };
+        store.path = path.to_path_buf();
+        Ok(store)
+    }
+
+    /// Write the contexts file, readable by the owner only since it may
+    /// hold API keys
+    pub fn save(&self) -> Result<(), RemoteError> {
+        if let Some(parent) = self.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| RemoteError::Store(e.to_string()))?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            if self.path.exists() {
+                fs::set_permissions(&self.path, fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        let mut file = options.open(&self.path)?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Add or replace a context; the first context becomes the current one
+    pub fn set(&mut self, name: &str, context: Context) -> Result<(), RemoteError> {
+        context.validate()?;
+        self.contexts.insert(name.to_string(), context);
+        if self.current.is_none() {
+            self.current = Some(name.to_string());
+        }
+        Ok(())
+    }
+
+    /// Make a context the current one
+    pub fn use_context(&mut self, name: &str) -> Result<(), RemoteError> {
+        if !self.contexts.contains_key(name) {
+            return Err(RemoteError::ContextNotFound(name.to_string()));
+        }
+        self.current = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Remove a context, clearing the current context if it was removed
+    pub fn remove(&mut self, name: &str) -> Result<Context, RemoteError> {
+        let context = self
+            .contexts
+            .remove(name)
+            .ok_or_else(|| RemoteError::ContextNotFound(name.to_string()))?;
+        if self.current.as_deref() == Some(name) {
+            self.current = None;
+        }
+        Ok(context)
+    }
+
+    /// Select a context by name, falling back to the current context
+    pub fn select(&self, name: Option<&str>) -> Result<(&str, &Context), RemoteError> {
+        let name = name
+            .or(self.current.as_deref())
+            .ok_or(RemoteError::NoContext)?;
+        self.contexts
+            .get_key_value(name)
+            .map(|(name, context)| (name.as_str(), context))
+            .ok_or_else(|| RemoteError::ContextNotFound(name.to_string()))
+    }
+
+    /// Select the context to manage: the explicit name (`--context`), then
+    /// the `INTELLIROUTER_CONTEXT` environment variable, then the current
+    /// context
+    pub fn resolve(&self, name: Option<&str>) -> Result<(&str, &Context), RemoteError> {
+        let from_env = std::env::var(CONTEXT_ENV).ok();
+        self.select(name.or(from_env.as_deref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(endpoint: &str) -> Context {
+        Context {
+            endpoint: endpoint.to_string(),
+            api_key: Some("secret".to_string()),
+            api_key_env: None,
+        }
+    }
+
+    #[test]
+    fn test_first_context_becomes_current() {
+        let mut store = ContextStore::default();
+        store
+            .set("staging", context("http://staging:8080"))
+            .unwrap();
+        store
+            .set("prod", context("https://prod.example.com"))
+            .unwrap();
+
+        assert_eq!(store.current.as_deref(), Some("staging"));
+        let (name, selected) = store.select(None).unwrap();
+        assert_eq!(name, "staging");
+        assert_eq!(selected.endpoint, "http://staging:8080");
+
+        let (name, _) = store.select(Some("prod")).unwrap();
+        assert_eq!(name, "prod");
+        assert!(matches!(
+            store.select(Some("dev")),
+            Err(RemoteError::ContextNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_use_and_remove_context() {
+        let mut store = ContextStore::default();
+        assert!(matches!(store.select(None), Err(RemoteError::NoContext)));
+
+        store
+            .set("staging", context("http://staging:8080"))
+            .unwrap();
+        store
+            .set("prod", context("https://prod.example.com"))
+            .unwrap();
+        store.use_context("prod").unwrap();
+        assert!(store.use_context("dev").is_err());
+        assert_eq!(store.current.as_deref(), Some("prod"));
+
+        store.remove("prod").unwrap();
+        assert_eq!(store.current, None);
+        assert!(matches!(store.select(None), Err(RemoteError::NoContext)));
+        assert!(store.remove("prod").is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_endpoint() {
+        let mut store = ContextStore::default();
+        assert!(store.set("bad", context("prod.example.com")).is_err());
+        assert!(store.set("bad", context("ftp://prod.example.com")).is_err());
+        assert!(store.contexts.is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("contexts.toml");
+
+        let mut store = ContextStore::load(&path).unwrap();
+        assert!(store.contexts.is_empty());
+        store
+            .set("staging", context("http://staging:8080"))
+            .unwrap();
+        store
+            .set(
+                "prod",
+                Context {
+                    endpoint: "https://prod.example.com".to_string(),
+                    api_key: None,
+                    api_key_env: Some("PROD_ADMIN_KEY".to_string()),
+                },
+            )
+            .unwrap();
+        store.save().unwrap();
+
+        let loaded = ContextStore::load(&path).unwrap();
+        assert_eq!(loaded.current.as_deref(), Some("staging"));
+        assert_eq!(loaded.contexts, store.contexts);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -0,0 +1,853 @@
+//! Prompt Regression Evals
+//!
+//! An eval runs a corpus of saved requests against two variants of a
+//! deployment's configuration, a baseline and a candidate, which differ in
+//! model, prompt version or sampling temperature. Every case's outputs are
+//! compared with a word-level similarity score and, when a judge model is
+//! set, a verdict on which output is better. Cases whose outputs are close
+//! enough count as unchanged; the others are changed, improved or regressed
+//! depending on the verdict.
+//!
+//! Corpora are JSONL files of [`EvalCase`]s or directories of payload records
+//! captured by the router, so production traffic can be replayed as an eval.
+//! Records encrypted at rest are opened with the deployment's keys.
+//! Requests go through an [`EvalExecutor`], a deployment's chat completions
+//! endpoint for `intellirouter eval` and whatever the test harness provides
+//! in its suites.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::replay::{self, diff_lines, DiffLine};
+use super::{RemoteClient, RemoteError};
+use crate::modules::encryption::EnvelopeEncryptor;
+use crate::modules::llm_proxy::domain::message::{Message, MessageRole};
+use crate::modules::llm_proxy::dto::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::modules::llm_proxy::json_mode;
+use crate::modules::llm_proxy::payloads::PayloadRecord;
+
+/// Instructions of the judge model
+pub const JUDGE_PROMPT: &str = "You compare two responses to the same conversation. \
+Judge which response answers the last user message better: consider correctness, \
+completeness and how well it follows the instructions of the conversation, not length \
+or style. Reply with only a JSON object of the form \
+{\"winner\": \"A\" | \"B\" | \"tie\", \"reason\": \"<one sentence>\"}.";
+
+/// A saved request of an eval corpus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalCase {
+    /// Case ID, the request ID for captured requests
+    pub id: String,
+    /// Request as sent to the router
+    pub request: ChatCompletionRequest,
+    /// Registry prompt the request's system message was built from, replaced
+    /// when a variant pins another prompt version
+    #[serde(default)]
+    pub prompt_template: Option<String>,
+}
+
+impl From<PayloadRecord> for EvalCase {
+    fn from(record: PayloadRecord) -> Self {
+        let mut request = record.request;
+        // Route the request again rather than sending it to the model the
+        // original was routed to
+        request.model = record.requested_model;
+        request.stream = false;
+        request.stream_options = None;
+        Self {
+            id: record.request_id,
+            request,
+            prompt_template: record.prompt.map(|prompt| prompt.template),
+        }
+    }
+}
+
+/// Load an eval corpus
+///
+/// `path` is a JSONL file of cases, a JSON file with an array of cases, or a
+/// directory of captured payload records, opened with `encryptor` when they
+/// are sealed. Cases are sorted by ID.
+pub async fn load_corpus(
+    path: &Path,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<Vec<EvalCase>, RemoteError> {
+    let read = |path: &Path| {
+        fs::read_to_string(path)
+            .map_err(|e| RemoteError::Request(format!("Failed to read {}: {}", path.display(), e)))
+    };
+    let invalid = |path: &Path, e: serde_json::Error| {
+        RemoteError::Request(format!("Invalid eval case in {}: {}", path.display(), e))
+    };
+
+    let mut cases = Vec::new();
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            if file.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let record = replay::load(&file, encryptor).await?;
+            cases.push(EvalCase::from(record));
+        }
+    } else {
+        let content = read(path)?;
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            cases = serde_json::from_str(&content).map_err(|e| invalid(path, e))?;
+        } else {
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                cases.push(serde_json::from_str(line).map_err(|e| invalid(path, e))?);
+            }
+        }
+    }
+
+    cases.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(cases)
+}
+
+/// A prompt version pinned by a variant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedPrompt {
+    /// Prompt name
+    pub id: String,
+    /// Prompt version
+    pub version: u32,
+    /// System prompt text of the version
+    pub template: String,
+}
+
+/// Fetch a version of a registry prompt from a deployment
+pub async fn fetch_prompt(
+    client: &RemoteClient,
+    id: &str,
+    version: u32,
+) -> Result<PinnedPrompt, RemoteError> {
+    let prompts = client.get("/v1/admin/prompts").await?;
+    let template = prompts
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|prompt| prompt["id"] == id)
+        .flat_map(|prompt| prompt["versions"].as_array().into_iter().flatten())
+        .find(|v| v["version"].as_u64() == Some(u64::from(version)))
+        .and_then(|v| v["template"].as_str())
+        .ok_or_else(|| {
+            RemoteError::Request(format!(
+                "Prompt '{}' has no version {} on the deployment",
+                id, version
+            ))
+        })?;
+    Ok(PinnedPrompt {
+        id: id.to_string(),
+        version,
+        template: template.to_string(),
+    })
+}
+
+/// Configuration a corpus is run against
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvalVariant {
+    /// Name of the variant in reports
+    pub name: String,
+    /// Model to send the requests to, instead of the model of each case
+    pub model: Option<String>,
+    /// Prompt version to use as the system message
+    pub prompt: Option<PinnedPrompt>,
+    /// Sampling temperature, instead of the temperature of each case
+    pub temperature: Option<f32>,
+}
+
+impl EvalVariant {
+    /// Create a variant that sends the cases unchanged
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Send the requests to a model
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Use a prompt version as the system message
+    pub fn with_prompt(mut self, prompt: PinnedPrompt) -> Self {
+        self.prompt = Some(prompt);
+        self
+    }
+
+    /// Set the sampling temperature
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    /// Request of a case for this variant
+    ///
+    /// A pinned prompt replaces the system message carrying the case's
+    /// recorded prompt, or is put first when the case has none.
+    pub fn request(&self, case: &EvalCase) -> ChatCompletionRequest {
+        let mut request = case.request.clone();
+        request.stream = false;
+        request.stream_options = None;
+        if let Some(model) = &self.model {
+            request.model = model.clone();
+        }
+        if let Some(temperature) = self.temperature {
+            request.temperature = Some(temperature);
+        }
+        if let Some(prompt) = &self.prompt {
+            if let Some(recorded) = &case.prompt_template {
+                request.messages.retain(|message| {
+                    message.role != MessageRole::System
+                        || message.extract_text_content() != *recorded
+                });
+            }
+            request
+                .messages
+                .insert(0, Message::new_system(prompt.template.clone()));
+        }
+        request
+    }
+}
+
+/// Sends the requests of an eval
+#[async_trait]
+pub trait EvalExecutor: Send + Sync {
+    /// Complete a chat request
+    async fn complete(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, RemoteError>;
+}
+
+#[async_trait]
+impl EvalExecutor for RemoteClient {
+    async fn complete(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, RemoteError> {
+        let body =
+            serde_json::to_value(&request).map_err(|e| RemoteError::Request(e.to_string()))?;
+        serde_json::from_value(self.post("/v1/chat/completions", Some(body)).await?)
+            .map_err(|e| RemoteError::Request(format!("Invalid completion: {}", e)))
+    }
+}
+
+/// Output the judge preferred
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preference {
+    Baseline,
+    Candidate,
+    Tie,
+}
+
+impl fmt::Display for Preference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Preference::Baseline => write!(f, "baseline"),
+            Preference::Candidate => write!(f, "candidate"),
+            Preference::Tie => write!(f, "tie"),
+        }
+    }
+}
+
+/// Verdict of the judge model on a case
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JudgeVerdict {
+    /// Output the judge preferred
+    pub preferred: Preference,
+    /// Why, in the judge's words
+    pub reason: String,
+}
+
+/// Outcome of a case
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    /// The outputs are at least as similar as the threshold
+    Unchanged,
+    /// The outputs differ and no judge preferred either
+    Changed,
+    /// The judge preferred the candidate's output
+    Improved,
+    /// The judge preferred the baseline's output, or only the candidate failed
+    Regressed,
+    /// Both variants failed
+    Failed,
+}
+
+impl fmt::Display for CaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaseStatus::Unchanged => write!(f, "unchanged"),
+            CaseStatus::Changed => write!(f, "changed"),
+            CaseStatus::Improved => write!(f, "improved"),
+            CaseStatus::Regressed => write!(f, "regressed"),
+            CaseStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// Result of a case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseResult {
+    /// Case ID
+    pub id: String,
+    /// Output of the baseline
+    pub baseline: Option<String>,
+    /// Error of the baseline
+    pub baseline_error: Option<String>,
+    /// Output of the candidate
+    pub candidate: Option<String>,
+    /// Error of the candidate
+    pub candidate_error: Option<String>,
+    /// Word-level similarity of the outputs, from 0 to 1
+    pub similarity: Option<f64>,
+    /// Line diff from the baseline's output to the candidate's
+    pub diff: Vec<DiffLine>,
+    /// Verdict of the judge, for outputs below the similarity threshold
+    pub verdict: Option<JudgeVerdict>,
+    /// Why the judge gave no verdict
+    pub judge_error: Option<String>,
+    /// Outcome of the case
+    pub status: CaseStatus,
+}
+
+/// Counts of a report's cases by outcome
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EvalSummary {
+    pub total: usize,
+    pub unchanged: usize,
+    pub changed: usize,
+    pub improved: usize,
+    pub regressed: usize,
+    pub failed: usize,
+    /// Mean similarity of the cases both variants answered
+    pub mean_similarity: Option<f64>,
+}
+
+/// Outcome of an eval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Baseline variant
+    pub baseline: EvalVariant,
+    /// Candidate variant
+    pub candidate: EvalVariant,
+    /// Judge model, if any
+    pub judge: Option<String>,
+    /// Similarity at or above which outputs count as unchanged
+    pub min_similarity: f64,
+    /// Counts by outcome
+    pub summary: EvalSummary,
+    /// Result of every case
+    pub cases: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    /// Cases the candidate regressed
+    pub fn regressions(&self) -> impl Iterator<Item = &CaseResult> {
+        self.cases
+            .iter()
+            .filter(|case| case.status == CaseStatus::Regressed)
+    }
+
+    /// Whether the candidate regressed any case
+    pub fn has_regressions(&self) -> bool {
+        self.regressions().next().is_some()
+    }
+
+    /// Render the report as a markdown summary
+    pub fn to_markdown(&self) -> String {
+        let summary = &self.summary;
+        let mut md = String::new();
+
+        md.push_str("# Prompt Eval\n\n");
+        md.push_str(&format!(
+            "Comparing candidate `{}` ({}) against baseline `{}` ({}) on {} cases.\n\n",
+            self.candidate.name,
+            describe(&self.candidate),
+            self.baseline.name,
+            describe(&self.baseline),
+            summary.total
+        ));
+        md.push_str(&format!(
+            "Similarity threshold: {:.2}, judge: {}\n\n",
+            self.min_similarity,
+            self.judge.as_deref().unwrap_or("none")
+        ));
+        if summary.regressed == 0 {
+            md.push_str("**Result: passed**\n\n");
+        } else {
+            md.push_str(&format!(
+                "**Result: failed, {} regressed case{}**\n\n",
+                summary.regressed,
+                if summary.regressed == 1 { "" } else { "s" }
+            ));
+        }
+        md.push_str(&format!(
+            "Unchanged: {}, changed: {}, improved: {}, regressed: {}, failed: {}, mean similarity: {}\n\n",
+            summary.unchanged,
+            summary.changed,
+            summary.improved,
+            summary.regressed,
+            summary.failed,
+            format_similarity(summary.mean_similarity)
+        ));
+
+        md.push_str("| Case | Similarity | Verdict | Status |\n");
+        md.push_str("|------|------------|---------|--------|\n");
+        for case in &self.cases {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                case.id,
+                format_similarity(case.similarity),
+                case.verdict
+                    .as_ref()
+                    .map_or("-".to_string(), |verdict| verdict.preferred.to_string()),
+                case.status
+            ));
+        }
+
+        for case in &self.cases {
+            if matches!(case.status, CaseStatus::Unchanged) {
+                continue;
+            }
+            md.push_str(&format!("\n## {} ({})\n\n", case.id, case.status));
+            if let Some(verdict) = &case.verdict {
+                md.push_str(&format!(
+                    "Judge preferred {}: {}\n\n",
+                    verdict.preferred, verdict.reason
+                ));
+            }
+            if let Some(error) = &case.judge_error {
+                md.push_str(&format!("Judge error: {}\n\n", error));
+            }
+            if let Some(error) = &case.baseline_error {
+                md.push_str(&format!("Baseline error: {}\n\n", error));
+            }
+            if let Some(error) = &case.candidate_error {
+                md.push_str(&format!("Candidate error: {}\n\n", error));
+            }
+            if !case.diff.is_empty() {
+                md.push_str("```diff\n");
+                for line in &case.diff {
+                    let (marker, text) = match line {
+                        DiffLine::Same(text) => (' ', text),
+                        DiffLine::Removed(text) => ('-', text),
+                        DiffLine::Added(text) => ('+', text),
+                    };
+                    md.push_str(&format!("{}{}\n", marker, text));
+                }
+                md.push_str("```\n");
+            }
+        }
+
+        md
+    }
+}
+
+/// What a variant changes, for reports
+fn describe(variant: &EvalVariant) -> String {
+    let mut parts = Vec::new();
+    if let Some(model) = &variant.model {
+        parts.push(format!("model {}", model));
+    }
+    if let Some(prompt) = &variant.prompt {
+        parts.push(format!("prompt {} v{}", prompt.id, prompt.version));
+    }
+    if let Some(temperature) = variant.temperature {
+        parts.push(format!("temperature {}", temperature));
+    }
+    if parts.is_empty() {
+        "as recorded".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn format_similarity(similarity: Option<f64>) -> String {
+    similarity.map_or("-".to_string(), |similarity| format!("{:.2}", similarity))
+}
+
+/// Runs eval corpora against a baseline and a candidate variant
+#[derive(Clone)]
+pub struct EvalRunner {
+    executor: Arc<dyn EvalExecutor>,
+    judge: Option<String>,
+    min_similarity: f64,
+    concurrency: usize,
+}
+
+impl EvalRunner {
+    /// Create a runner sending requests through an executor
+    pub fn new(executor: Arc<dyn EvalExecutor>) -> Self {
+        Self {
+            executor,
+            judge: None,
+            min_similarity: 0.9,
+            concurrency: 4,
+        }
+    }
+
+    /// Ask a model which output is better when the outputs differ
+    pub fn with_judge(mut self, model: impl Into<String>) -> Self {
+        self.judge = Some(model.into());
+        self
+    }
+
+    /// Set the similarity at or above which outputs count as unchanged
+    pub fn with_min_similarity(mut self, min_similarity: f64) -> Self {
+        self.min_similarity = min_similarity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how many cases run at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Run a corpus against a baseline and a candidate
+    pub async fn run(
+        &self,
+        cases: &[EvalCase],
+        baseline: &EvalVariant,
+        candidate: &EvalVariant,
+    ) -> EvalReport {
+        let results: Vec<CaseResult> = stream::iter(cases.iter().enumerate())
+            .map(|(index, case)| self.run_case(index, case, baseline, candidate))
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut summary = EvalSummary {
+            total: results.len(),
+            ..Default::default()
+        };
+        for result in &results {
+            match result.status {
+                CaseStatus::Unchanged => summary.unchanged += 1,
+                CaseStatus::Changed => summary.changed += 1,
+                CaseStatus::Improved => summary.improved += 1,
+                CaseStatus::Regressed => summary.regressed += 1,
+                CaseStatus::Failed => summary.failed += 1,
+            }
+        }
+        let similarities: Vec<f64> = results.iter().filter_map(|r| r.similarity).collect();
+        if !similarities.is_empty() {
+            summary.mean_similarity =
+                Some(similarities.iter().sum::<f64>() / similarities.len() as f64);
+        }
+
+        EvalReport {
+            baseline: baseline.clone(),
+            candidate: candidate.clone(),
+            judge: self.judge.clone(),
+            min_similarity: self.min_similarity,
+            summary,
+            cases: results,
+        }
+    }
+
+    /// Run a case against a baseline and a candidate
+    ///
+    /// `index` is the position of the case in its corpus; the judge sees the
+    /// baseline's output first for even positions and second for odd ones,
+    /// so a preference for either position does not favour one variant.
+    pub async fn run_case(
+        &self,
+        index: usize,
+        case: &EvalCase,
+        baseline: &EvalVariant,
+        candidate: &EvalVariant,
+    ) -> CaseResult {
+        let (baseline_output, candidate_output) = tokio::join!(
+            self.output(baseline.request(case)),
+            self.output(candidate.request(case)),
+        );
+        let mut result = CaseResult {
+            id: case.id.clone(),
+            baseline: None,
+            baseline_error: None,
+            candidate: None,
+            candidate_error: None,
+            similarity: None,
+            diff: Vec::new(),
+            verdict: None,
+            judge_error: None,
+            status: CaseStatus::Failed,
+        };
+        match baseline_output {
+            Ok(output) => result.baseline = Some(output),
+            Err(e) => result.baseline_error = Some(e.to_string()),
+        }
+        match candidate_output {
+            Ok(output) => result.candidate = Some(output),
+            Err(e) => result.candidate_error = Some(e.to_string()),
+        }
+
+        let (old, new) = match (&result.baseline, &result.candidate) {
+            (Some(old), Some(new)) => (old.clone(), new.clone()),
+            (Some(_), None) => {
+                result.status = CaseStatus::Regressed;
+                return result;
+            }
+            (None, Some(_)) => {
+                result.status = CaseStatus::Improved;
+                return result;
+            }
+            (None, None) => return result,
+        };
+
+        let similarity = similarity(&old, &new);
+        result.similarity = Some(similarity);
+        result.diff = diff_lines(&old, &new);
+        if similarity >= self.min_similarity {
+            result.status = CaseStatus::Unchanged;
+            return result;
+        }
+
+        result.status = CaseStatus::Changed;
+        if let Some(judge) = &self.judge {
+            match self.judge(judge, case, &old, &new, index % 2 == 1).await {
+                Ok(verdict) => {
+                    result.status = match verdict.preferred {
+                        Preference::Baseline => CaseStatus::Regressed,
+                        Preference::Candidate => CaseStatus::Improved,
+                        Preference::Tie => CaseStatus::Changed,
+                    };
+                    result.verdict = Some(verdict);
+                }
+                Err(e) => result.judge_error = Some(e.to_string()),
+            }
+        }
+        result
+    }
+
+    /// Text output of a request
+    async fn output(&self, request: ChatCompletionRequest) -> Result<String, RemoteError> {
+        let response = self.executor.complete(request).await?;
+        Ok(response
+            .choices
+            .first()
+            .map(|choice| choice.message.extract_text_content())
+            .unwrap_or_default())
+    }
+
+    /// Ask the judge model which output is better
+    async fn judge(
+        &self,
+        model: &str,
+        case: &EvalCase,
+        baseline: &str,
+        candidate: &str,
+        swapped: bool,
+    ) -> Result<JudgeVerdict, RemoteError> {
+        let (a, b) = if swapped {
+            (candidate, baseline)
+        } else {
+            (baseline, candidate)
+        };
+        let conversation = case
+            .request
+            .messages
+            .iter()
+            .map(|message| format!("[{}]\n{}", message.role, message.extract_text_content()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let mut request = case.request.clone();
+        request.model = model.to_string();
+        request.messages = vec![
+            Message::new_system(JUDGE_PROMPT.to_string()),
+            Message::new_user(format!(
+                "Conversation:\n\n{}\n\nResponse A:\n\n{}\n\nResponse B:\n\n{}",
+                conversation, a, b
+            )),
+        ];
+        request.temperature = Some(0.0);
+        request.n = None;
+        request.max_tokens = None;
+        request.stop = None;
+        request.response_format = None;
+
+        let reply = self.output(request).await?;
+        let value: Value = serde_json::from_str(&reply)
+            .ok()
+            .or_else(|| {
+                json_mode::repair(&reply).and_then(|(json, _)| serde_json::from_str(&json).ok())
+            })
+            .ok_or_else(|| RemoteError::Request(format!("Judge reply is not JSON: {}", reply)))?;
+
+        let winner = value["winner"].as_str().unwrap_or_default().to_lowercase();
+        let preferred = match (winner.as_str(), swapped) {
+            ("a", false) | ("b", true) => Preference::Baseline,
+            ("b", false) | ("a", true) => Preference::Candidate,
+            ("tie", _) => Preference::Tie,
+            _ => {
+                return Err(RemoteError::Request(format!(
+                    "Judge reply has no valid winner: {}",
+                    reply
+                )))
+            }
+        };
+        Ok(JudgeVerdict {
+            preferred,
+            reason: value["reason"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+}
+
+/// Word-level similarity of two texts, from 0 (nothing in common) to 1
+///
+/// Twice the length of the longest common subsequence of words, divided by
+/// the total number of words.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<&str> = a.split_whitespace().collect();
+    let b: Vec<&str> = b.split_whitespace().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let mut previous = vec![0usize; b.len() + 1];
+    for word in &a {
+        let mut current = vec![0usize; b.len() + 1];
+        for (j, other) in b.iter().enumerate() {
+            current[j + 1] = if word == other {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        previous = current;
+    }
+    2.0 * previous[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers from a table keyed by model, and judges for the candidate
+    struct TableExecutor;
+
+    #[async_trait]
+    impl EvalExecutor for TableExecutor {
+        async fn complete(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, RemoteError> {
+            let system = request
+                .messages
+                .iter()
+                .find(|m| m.role == MessageRole::System)
+                .map(|m| m.extract_text_content())
+                .unwrap_or_default();
+            let user = request.messages.last().unwrap().extract_text_content();
+            let answer = match request.model.as_str() {
+                "old" => format!("The answer to {} is 4.", user),
+                "new" if system.contains("terse") => "4".to_string(),
+                "new" if user == "2+2" => format!("The answer to {} is 4.", user),
+                "new" => format!("I am not sure about {}.", user),
+                // The baseline is Response A for even cases and B for odd ones
+                "judge" if user.contains("Response A:\n\nThe answer") => r#"```json
+{"winner": "A", "reason": "A answers the question."}
+```"#
+                    .to_string(),
+                "judge" => r#"{"winner": "B", "reason": "B answers the question."}"#.to_string(),
+                model => return Err(RemoteError::Request(format!("unknown model {}", model))),
+            };
+            Ok(ChatCompletionResponse::new(
+                request.model,
+                Message::new_assistant(answer),
+            ))
+        }
+    }
+
+    fn case(id: &str, question: &str) -> EvalCase {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "request": {
+                "model": "old",
+                "messages": [
+                    {"role": "system", "content": "Be helpful."},
+                    {"role": "user", "content": question}
+                ]
+            },
+            "prompt_template": "Be helpful."
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("a b c", "a b c"), 1.0);
+        assert_eq!(similarity("a b", "c d"), 0.0);
+        assert!((similarity("a b c d", "a x c d") - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_variant_replaces_recorded_prompt() {
+        let variant = EvalVariant::new("terse")
+            .with_model("new")
+            .with_prompt(PinnedPrompt {
+                id: "support".to_string(),
+                version: 2,
+                template: "Be terse.".to_string(),
+            });
+        let request = variant.request(&case("c1", "2+2"));
+        assert_eq!(request.model, "new");
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(request.messages[0].extract_text_content(), "Be terse.");
+    }
+
+    #[tokio::test]
+    async fn test_run_judges_changed_cases() {
+        let cases = vec![case("a", "2+2"), case("b", "3+1"), case("c", "1+3")];
+        let runner = EvalRunner::new(Arc::new(TableExecutor)).with_judge("judge");
+        let report = runner
+            .run(
+                &cases,
+                &EvalVariant::new("baseline").with_model("old"),
+                &EvalVariant::new("candidate").with_model("new"),
+            )
+            .await;
+
+        assert_eq!(report.summary.total, 3);
+        assert_eq!(report.cases[0].status, CaseStatus::Unchanged);
+        // Both changed cases are regressions, whichever side the baseline
+        // was shown on
+        assert_eq!(report.cases[1].status, CaseStatus::Regressed);
+        assert_eq!(report.cases[2].status, CaseStatus::Regressed);
+        assert_eq!(
+            report.cases[2].verdict.as_ref().unwrap().preferred,
+            Preference::Baseline
+        );
+        assert!(report.has_regressions());
+
+        let md = report.to_markdown();
+        assert!(md.contains("**Result: failed, 2 regressed cases**"));
+        assert!(md.contains("-The answer to 3+1 is 4.\n+I am not sure about 3+1."));
+
+        let report = runner
+            .run(
+                &cases[..1],
+                &EvalVariant::new("baseline").with_model("old"),
+                &EvalVariant::new("broken").with_model("missing"),
+            )
+            .await;
+        assert_eq!(report.cases[0].status, CaseStatus::Regressed);
+        assert!(report.cases[0].candidate_error.is_some());
+    }
+}
@@ -0,0 +1,52 @@
+//! Remote Management Module
+//!
+//! This module lets the command line manage a running deployment over its
+//! admin HTTP API. Deployments are saved as named contexts (endpoint and
+//! credentials) in a [`ContextStore`], much like kubectl contexts, and the
+//! [`RemoteClient`] sends requests to the selected one.
+
+pub mod client;
+pub mod context;
+pub mod eval;
+pub mod replay;
+pub mod support;
+pub mod traffic;
+
+use thiserror::Error;
+
+pub use client::RemoteClient;
+pub use context::{Context, ContextStore};
+
+/// Errors that can occur when managing a remote deployment
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[error("Context not found: {0}")]
+    ContextNotFound(String),
+
+    #[error("No context selected; create one with `intellirouter ctx set` or pass --context")]
+    NoContext,
+
+    #[error("Invalid context: {0}")]
+    InvalidContext(String),
+
+    #[error("Contexts file error: {0}")]
+    Store(String),
+
+    #[error("Request failed: {0}")]
+    Request(String),
+
+    #[error("Remote returned {status}: {message}")]
+    Api { status: u16, message: String },
+}
+
+impl From<std::io::Error> for RemoteError {
+    fn from(e: std::io::Error) -> Self {
+        RemoteError::Store(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for RemoteError {
+    fn from(e: reqwest::Error) -> Self {
+        RemoteError::Request(e.to_string())
+    }
+}
@@ -0,0 +1,351 @@
+//! Request Replay
+//!
+//! `intellirouter replay <request-id>` re-executes a captured production
+//! request for debugging. Its payload record is fetched from the
+//! deployment's admin API, or read from a file copied out of the capture
+//! directory, opened with the deployment's encryption keys when it is
+//! encrypted at rest, and the request is rebuilt exactly as it was sent to the
+//! model: the registry prompt, system prompts and retrieved context it
+//! carried are replayed as recorded rather than selected again. It then
+//! runs against the built-in mock, or against a chosen model through a
+//! deployment, and the new output is diffed line by line against the
+//! original.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::client::path_segment;
+use super::{RemoteClient, RemoteError};
+use crate::modules::encryption::EnvelopeEncryptor;
+use crate::modules::llm_proxy::dto::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::modules::llm_proxy::payloads::{self, PayloadError, PayloadRecord};
+use crate::modules::llm_proxy::service::ChatCompletionService;
+
+/// What a captured request is re-executed against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayTarget {
+    /// The built-in mock model
+    Mock,
+    /// A model served by a deployment
+    Model(String),
+}
+
+/// A line of the diff between the original and replayed outputs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", content = "line", rename_all = "snake_case")]
+pub enum DiffLine {
+    Same(String),
+    /// Only in the original output
+    Removed(String),
+    /// Only in the replayed output
+    Added(String),
+}
+
+/// Outcome of a replay
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayReport {
+    pub request_id: String,
+    /// Model that served the original request
+    pub recorded_model: String,
+    /// Model the request was replayed against, `mock` for the built-in mock
+    pub target: String,
+    /// Registry prompt of the original request, as `<id> v<version>`
+    pub prompt: Option<String>,
+    /// Routing policy version active for the original request
+    pub policy_version: Option<u32>,
+    /// Routing policy version active on the deployment the request was
+    /// replayed against
+    pub current_policy_version: Option<u32>,
+    /// Original output, if the original request succeeded
+    pub original: Option<String>,
+    /// Error of the original request
+    pub original_error: Option<String>,
+    /// Output of the replay
+    pub replayed: String,
+    /// Line diff from the original output to the replayed one
+    pub diff: Vec<DiffLine>,
+}
+
+impl ReplayReport {
+    /// Whether the replay reproduced the original output
+    pub fn identical(&self) -> bool {
+        self.original.as_deref() == Some(self.replayed.as_str())
+    }
+
+    /// Human-readable report, with the diff in unified style
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Request:        {}", self.request_id);
+        let _ = writeln!(out, "Recorded model: {}", self.recorded_model);
+        let _ = writeln!(out, "Replayed on:    {}", self.target);
+        if let Some(prompt) = &self.prompt {
+            let _ = writeln!(out, "Prompt:         {}", prompt);
+        }
+        let version = |v: Option<u32>| v.map_or("none".to_string(), |v| format!("v{}", v));
+        let _ = write!(out, "Policy:         {}", version(self.policy_version));
+        match self.current_policy_version {
+            Some(current) if Some(current) != self.policy_version => {
+                let _ = writeln!(out, " (deployment now on v{}; routing may differ)", current);
+            }
+            _ => out.push('\n'),
+        }
+        if let Some(error) = &self.original_error {
+            let _ = writeln!(out, "Original error: {}", error);
+        }
+        out.push('\n');
+
+        if self.identical() {
+            out.push_str("Output is identical to the original.\n");
+            return out;
+        }
+        out.push_str("--- original\n+++ replay\n");
+        for line in &self.diff {
+            let (marker, text) = match line {
+                DiffLine::Same(text) => (' ', text),
+                DiffLine::Removed(text) => ('-', text),
+                DiffLine::Added(text) => ('+', text),
+            };
+            let _ = writeln!(out, "{}{}", marker, text);
+        }
+        out
+    }
+}
+
+/// Fetch the payload record of a request from a deployment
+pub async fn fetch(client: &RemoteClient, request_id: &str) -> Result<PayloadRecord, RemoteError> {
+    let value = client
+        .get(&format!("/v1/admin/payloads/{}", path_segment(request_id)))
+        .await?;
+    serde_json::from_value(value)
+        .map_err(|e| RemoteError::Request(format!("Invalid payload record: {}", e)))
+}
+
+/// Read a payload record from a file, opening it when it is sealed
+pub async fn load(
+    path: &Path,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<PayloadRecord, RemoteError> {
+    payloads::read_record(path, encryptor)
+        .await
+        .map_err(|e| match e {
+            PayloadError::Io(e) => {
+                RemoteError::Request(format!("Failed to read {}: {}", path.display(), e))
+            }
+            e => RemoteError::Request(format!("Invalid payload record {}: {}", path.display(), e)),
+        })
+}
+
+/// Rebuild the request of a record for a target
+pub fn rebuild(record: &PayloadRecord, target: &ReplayTarget) -> ChatCompletionRequest {
+    let mut request = record.request.clone();
+    request.stream = false;
+    request.stream_options = None;
+    if let ReplayTarget::Model(model) = target {
+        request.model = model.clone();
+    }
+    request
+}
+
+/// Re-execute a captured request and diff its output against the original
+///
+/// Model targets are sent to the chat completions endpoint of `client`'s
+/// deployment, which also reports the routing policy version now active.
+pub async fn replay(
+    record: &PayloadRecord,
+    target: &ReplayTarget,
+    client: Option<&RemoteClient>,
+) -> Result<ReplayReport, RemoteError> {
+    let request = rebuild(record, target);
+    let (response, current_policy_version) = match (target, client) {
+        (ReplayTarget::Mock, _) => (
+            ChatCompletionService::legacy_process_completion_request(&request),
+            None,
+        ),
+        (ReplayTarget::Model(_), Some(client)) => {
+            let body =
+                serde_json::to_value(&request).map_err(|e| RemoteError::Request(e.to_string()))?;
+            let response: ChatCompletionResponse =
+                serde_json::from_value(client.post("/v1/chat/completions", Some(body)).await?)
+                    .map_err(|e| RemoteError::Request(format!("Invalid completion: {}", e)))?;
+            (response, active_policy_version(client).await)
+        }
+        (ReplayTarget::Model(_), None) => {
+            return Err(RemoteError::Request(
+                "Replaying against a model needs a deployment".to_string(),
+            ))
+        }
+    };
+
+    let original = record.output();
+    let replayed = response
+        .choices
+        .first()
+        .map(|choice| choice.message.extract_text_content())
+        .unwrap_or_default();
+    Ok(ReplayReport {
+        request_id: record.request_id.clone(),
+        recorded_model: record.model.clone(),
+        target: match target {
+            ReplayTarget::Mock => "mock".to_string(),
+            ReplayTarget::Model(model) => model.clone(),
+        },
+        prompt: record
+            .prompt
+            .as_ref()
+            .map(|prompt| format!("{} v{}", prompt.id, prompt.version)),
+        policy_version: record.policy_version,
+        current_policy_version,
+        diff: diff_lines(original.as_deref().unwrap_or_default(), &replayed),
+        original,
+        original_error: record.error.clone(),
+        replayed,
+    })
+}
+
+/// Version of the routing policy active on a deployment, if it reports one
+async fn active_policy_version(client: &RemoteClient) -> Option<u32> {
+    let policies = client.get("/v1/policies").await.ok()?;
+    policies
+        .as_array()?
+        .iter()
+        .find(|policy| policy["active"].as_bool() == Some(true))
+        .and_then(|policy| policy["version"].as_u64())
+        .map(|version| version as u32)
+}
+
+/// Line diff of two texts, from their longest common subsequence of lines
+pub fn diff_lines(original: &str, replayed: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = replayed.lines().collect();
+
+    // lcs[i][j]: length of the common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            diff.push(DiffLine::Same(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(DiffLine::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            diff.push(DiffLine::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    diff.extend(
+        a[i..]
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string())),
+    );
+    diff.extend(b[j..].iter().map(|line| DiffLine::Added(line.to_string())));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::llm_proxy::domain::message::Message;
+    use chrono::Utc;
+
+    fn record() -> PayloadRecord {
+        PayloadRecord {
+            request_id: "req-1".to_string(),
+            timestamp: Utc::now(),
+            tenant: None,
+            user: None,
+            requested_model: "fast".to_string(),
+            model: "gpt-4o".to_string(),
+            provider: "openai".to_string(),
+            policy_version: Some(2),
+            prompt: None,
+            request: ChatCompletionRequest {
+                model: "gpt-4o".to_string(),
+                messages: vec![
+                    Message::new_system("Context: orders ship in 2 days.".to_string()),
+                    Message::new_user("Hello".to_string()),
+                ],
+                temperature: None,
+                top_p: None,
+                n: None,
+                stream: false,
+                max_tokens: None,
+                presence_penalty: None,
+                frequency_penalty: None,
+                user: None,
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
+            },
+            response: Some(ChatCompletionResponse::new(
+                "gpt-4o".to_string(),
+                Message::new_assistant("Hi there!\nHow can I help?".to_string()),
+            )),
+            error: None,
+            latency_ms: 80,
+        }
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        let diff = diff_lines("a\nb\nc", "a\nx\nc\nd");
+        assert_eq!(
+            diff,
+            vec![
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Added("x".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("d".to_string()),
+            ]
+        );
+        assert!(diff_lines("same", "same")
+            .iter()
+            .all(|line| matches!(line, DiffLine::Same(_))));
+    }
+
+    #[test]
+    fn test_rebuild_keeps_recorded_messages() {
+        let record = record();
+        let request = rebuild(&record, &ReplayTarget::Model("claude-3".to_string()));
+        assert_eq!(request.model, "claude-3");
+        assert_eq!(request.messages.len(), 2);
+        assert_eq!(
+            request.messages[0].extract_text_content(),
+            "Context: orders ship in 2 days."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_replay_against_mock() {
+        let record = record();
+        let report = replay(&record, &ReplayTarget::Mock, None).await.unwrap();
+        assert_eq!(report.target, "mock");
+        assert_eq!(report.recorded_model, "gpt-4o");
+        assert!(!report.identical());
+        assert_eq!(report.diff[0], DiffLine::Removed("Hi there!".to_string()));
+        assert!(report
+            .render()
+            .contains("--- original\n+++ replay\n-Hi there!"));
+
+        let error = replay(&record, &ReplayTarget::Model("gpt-4o".to_string()), None).await;
+        assert!(error.is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Support Bundles
+//!
+//! `intellirouter support-bundle` collects what a bug report needs from a
+//! running deployment into a gzipped tarball: the role's support snapshot
+//! (build, redacted configuration, recent warnings and errors, cache and
+//! breaker stats), its diagnostics and readiness, and the state of its
+//! canaries, anomalies, log levels and models. Prometheus metrics are added
+//! when their URL is given.
+//!
+//! Collection is best effort, since bundles are mostly wanted from
+//! deployments in trouble. A source that fails is listed with its error in
+//! the bundle's `manifest.json` instead of failing the bundle, and
+//! responses are kept whatever their status, e.g. the 503 diagnostics of an
+//! unhealthy role. Secrets are redacted again on the way out.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use reqwest::Method;
+use serde_json::{json, Map};
+
+use super::{RemoteClient, RemoteError};
+use crate::modules::support::redact;
+
+/// Files of a bundle collected from the deployment's HTTP API, by the path
+/// they are read from
+const SOURCES: &[(&str, &str)] = &[
+    ("support.json", "/v1/admin/support"),
+    ("diagnostics.json", "/diagnostics"),
+    ("readiness.json", "/readiness"),
+    ("canaries.json", "/v1/admin/canaries"),
+    ("anomalies.json", "/v1/admin/anomalies"),
+    ("logging.json", "/v1/admin/logging"),
+    ("models.json", "/v1/admin/models"),
+];
+
+/// Timeout of the Prometheus metrics request
+const METRICS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Size of a tar block
+const BLOCK: usize = 512;
+
+/// Files collected from a deployment
+#[derive(Debug, Clone)]
+pub struct SupportBundle {
+    pub generated_at: DateTime<Utc>,
+    /// Name and content of each file, `manifest.json` first
+    pub files: Vec<(String, Vec<u8>)>,
+}
+
+impl SupportBundle {
+    /// Directory holding the files in the tarball
+    pub fn directory(&self) -> String {
+        format!(
+            "intellirouter-support-{}",
+            self.generated_at.format("%Y%m%dT%H%M%SZ")
+        )
+    }
+
+    /// Write the bundle as a gzipped tarball
+    pub fn write_tar_gz(&self, writer: impl Write) -> io::Result<()> {
+        let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+        let directory = self.directory();
+        let mtime = self.generated_at.timestamp().max(0) as u64;
+        for (name, content) in &self.files {
+            let path = format!("{}/{}", directory, name);
+            encoder.write_all(&tar_header(&path, content.len() as u64, mtime)?)?;
+            encoder.write_all(content)?;
+            let padding = (BLOCK - content.len() % BLOCK) % BLOCK;
+            encoder.write_all(&vec![0; padding])?;
+        }
+        // The archive ends with two empty blocks
+        encoder.write_all(&[0; 2 * BLOCK])?;
+        encoder.finish()?.flush()
+    }
+}
+
+/// Collect a support bundle from the deployment of a client, and from its
+/// Prometheus metrics endpoint if given
+pub async fn collect(client: &RemoteClient, metrics_url: Option<&str>) -> SupportBundle {
+    let generated_at = Utc::now();
+    let mut files = Vec::new();
+    let mut sources = Map::new();
+
+    for (name, path) in SOURCES {
+        match client.send_any(Method::GET, path, None).await {
+            Ok((status, mut value)) => {
+                redact(&mut value);
+                let content = serde_json::to_vec_pretty(&value).unwrap_or_default();
+                files.push((name.to_string(), content));
+                sources.insert(
+                    name.to_string(),
+                    json!({ "path": path, "status": status.as_u16() }),
+                );
+            }
+            Err(e) => {
+                sources.insert(
+                    name.to_string(),
+                    json!({ "path": path, "error": e.to_string() }),
+                );
+            }
+        }
+    }
+
+    if let Some(url) = metrics_url {
+        match fetch_metrics(url).await {
+            Ok(metrics) => {
+                files.push(("metrics.txt".to_string(), metrics.into_bytes()));
+                sources.insert("metrics.txt".to_string(), json!({ "url": url }));
+            }
+            Err(e) => {
+                sources.insert(
+                    "metrics.txt".to_string(),
+                    json!({ "url": url, "error": e.to_string() }),
+                );
+            }
+        }
+    }
+
+    let manifest = json!({
+        "generated_at": generated_at,
+        "endpoint": client.endpoint(),
+        "cli_version": env!("CARGO_PKG_VERSION"),
+        "sources": sources,
+    });
+    files.insert(
+        0,
+        (
+            "manifest.json".to_string(),
+            serde_json::to_vec_pretty(&manifest).unwrap_or_default(),
+        ),
+    );
+    SupportBundle {
+        generated_at,
+        files,
+    }
+}
+
+/// Fetch the Prometheus metrics of a deployment
+async fn fetch_metrics(url: &str) -> Result<String, RemoteError> {
+    let response = reqwest::Client::builder()
+        .timeout(METRICS_TIMEOUT)
+        .build()?
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.text().await?)
+}
+
+/// ustar header of a regular file
+fn tar_header(path: &str, size: u64, mtime: u64) -> io::Result<[u8; BLOCK]> {
+    // Paths longer than the name field are split at a slash into the prefix
+    // field
+    let (prefix, name) = match path.len() {
+        0..=100 => ("", path),
+        _ => path
+            .char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && path.len() - i - 1 <= 100)
+            .map(|(i, _)| (&path[..i], &path[i + 1..]))
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Path too long: {}", path),
+                )
+            })?,
+    };
+
+    let mut header = [0u8; BLOCK];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, name.as_bytes());
+    field(100, b"0000644\0");
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", size).as_bytes());
+    field(136, format!("{:011o}\0", mtime).as_bytes());
+    // The checksum is computed with its own field filled with spaces
+    field(148, b"        ");
+    field(156, b"0");
+    field(257, b"ustar\0");
+    field(263, b"00");
+    field(345, prefix.as_bytes());
+
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    /// Octal number of a tar header field
+    fn octal(field: &[u8]) -> u64 {
+        let digits = std::str::from_utf8(field).unwrap();
+        u64::from_str_radix(digits.trim_matches(|c| c == '\0' || c == ' '), 8).unwrap()
+    }
+
+    #[test]
+    fn test_write_tar_gz() {
+        let bundle = SupportBundle {
+            generated_at: "2026-10-18T09:30:00Z".parse().unwrap(),
+            files: vec![
+                ("manifest.json".to_string(), b"{}".to_vec()),
+                ("metrics.txt".to_string(), vec![b'x'; 600]),
+            ],
+        };
+        let mut tarball = Vec::new();
+        bundle.write_tar_gz(&mut tarball).unwrap();
+        let mut tar = Vec::new();
+        GzDecoder::new(&tarball[..]).read_to_end(&mut tar).unwrap();
+
+        // Two files of one and two blocks, each after its header, then the
+        // two empty blocks
+        assert_eq!(tar.len(), 7 * BLOCK);
+        let header = &tar[..BLOCK];
+        assert!(header.starts_with(b"intellirouter-support-20261018T093000Z/manifest.json\0"));
+        assert_eq!(octal(&header[124..136]), 2);
+        let checksum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(b)
+                }
+            })
+            .sum();
+        assert_eq!(octal(&header[148..156]), checksum);
+        assert_eq!(&tar[BLOCK..BLOCK + 2], b"{}");
+
+        let header = &tar[2 * BLOCK..3 * BLOCK];
+        assert_eq!(octal(&header[124..136]), 600);
+        assert!(tar[5 * BLOCK..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_tar_header_long_path() {
+        let path = format!("{}/{}", "d".repeat(120), "f".repeat(20));
+        let header = tar_header(&path, 0, 0).unwrap();
+        assert!(header.starts_with(&[b'f'; 20]));
+        assert!(header[345..].starts_with(&[b'd'; 120]));
+        assert!(tar_header(&"f".repeat(200), 0, 0).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+//! Traffic Replay
+//!
+//! `intellirouter load-replay <dir>` load tests a staging deployment with a
+//! window of real traffic. The requests captured by payload capture in that
+//! window are sent again with their original spacing, compressed or
+//! stretched by a speed multiplier, so the deployment sees production's
+//! request shapes, sizes and bursts rather than a synthetic mix.
+//!
+//! Captured payloads hold full prompts. Before they leave the machine, end
+//! user IDs are replaced with pseudonyms that are stable within a run, and
+//! message text can be masked when the staging deployment must not see it.
+//! Payloads encrypted at rest are opened with the deployment's keys.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use ring::digest;
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::{replay, RemoteClient, RemoteError};
+use crate::modules::encryption::EnvelopeEncryptor;
+use crate::modules::llm_proxy::domain::content::{ContentPart, MessageContent};
+use crate::modules::llm_proxy::dto::ChatCompletionRequest;
+use crate::modules::llm_proxy::payloads::PayloadRecord;
+
+/// Captured requests to replay, by the time they arrived
+#[derive(Debug, Clone, Default)]
+pub struct TrafficWindow {
+    /// Only requests that arrived at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only requests that arrived before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl TrafficWindow {
+    /// Whether a request arriving at a time is in the window
+    pub fn contains(&self, arrival: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| arrival >= since)
+            && self.until.is_none_or(|until| arrival < until)
+    }
+}
+
+/// How captured requests are altered before they are replayed
+#[derive(Debug, Clone)]
+pub struct Redaction {
+    /// Salt of the end user pseudonyms, new for every run
+    salt: String,
+    /// Whether the letters and digits of message text are masked
+    mask_content: bool,
+    /// Model every request is sent to, instead of the one it asked for
+    model: Option<String>,
+}
+
+impl Redaction {
+    /// Pseudonymize end users, keeping messages and models
+    pub fn new() -> Self {
+        Self {
+            salt: Uuid::new_v4().simple().to_string(),
+            mask_content: false,
+            model: None,
+        }
+    }
+
+    /// Mask the letters and digits of message text
+    ///
+    /// The length of the text is kept, but not its token count, which
+    /// depends on the words.
+    pub fn with_masked_content(mut self) -> Self {
+        self.mask_content = true;
+        self
+    }
+
+    /// Send every request to a model instead of the one it asked for
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Apply the redaction to a captured request
+    pub fn apply(&self, mut request: ChatCompletionRequest) -> ChatCompletionRequest {
+        request.stream = false;
+        request.stream_options = None;
+        request.user = request.user.map(|user| self.pseudonym(&user));
+        if let Some(model) = &self.model {
+            request.model = model.clone();
+        }
+        if self.mask_content {
+            for message in &mut request.messages {
+                match &mut message.content {
+                    MessageContent::String(text) => *text = mask(text),
+                    MessageContent::Array(parts) => {
+                        for part in parts.iter_mut() {
+                            if let ContentPart::Text { text } = part {
+                                *text = mask(text);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        request
+    }
+
+    /// Pseudonym of an end user, the same for every request of a run
+    fn pseudonym(&self, user: &str) -> String {
+        let hash = digest::digest(
+            &digest::SHA256,
+            format!("{}:{}", self.salt, user).as_bytes(),
+        );
+        format!("replay-{}", hex::encode(&hash.as_ref()[..6]))
+    }
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Text with its letters and digits replaced by `x`
+fn mask(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_alphanumeric() { 'x' } else { c })
+        .collect()
+}
+
+/// A captured request scheduled for replay
+#[derive(Debug, Clone)]
+pub struct ScheduledRequest {
+    /// ID of the captured request
+    pub request_id: String,
+    /// When to send the request, from the start of the replay
+    pub offset: Duration,
+    /// Request to send
+    pub request: ChatCompletionRequest,
+}
+
+/// Time a captured request arrived, from when it completed and its latency
+fn arrival(record: &PayloadRecord) -> DateTime<Utc> {
+    record.timestamp - chrono::Duration::milliseconds(record.latency_ms as i64)
+}
+
+/// Read the captured requests of a window from a payload capture directory,
+/// in the order they arrived
+pub async fn load_window(
+    dir: &Path,
+    window: &TrafficWindow,
+    encryptor: Option<&EnvelopeEncryptor>,
+) -> Result<Vec<PayloadRecord>, RemoteError> {
+    let mut records = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let file = entry?.path();
+        if file.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let record = replay::load(&file, encryptor).await?;
+        if window.contains(arrival(&record)) {
+            records.push(record);
+        }
+    }
+    records.sort_by_key(arrival);
+    Ok(records)
+}
+
+/// Schedule captured requests with their original spacing divided by
+/// `speed`, so `2.0` replays them twice as fast
+pub fn schedule(
+    records: &[PayloadRecord],
+    speed: f64,
+    redaction: &Redaction,
+) -> Result<Vec<ScheduledRequest>, RemoteError> {
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(RemoteError::Request(format!(
+            "Invalid speed {}, expected a positive multiplier",
+            speed
+        )));
+    }
+    let Some(start) = records.iter().map(arrival).min() else {
+        return Ok(Vec::new());
+    };
+    Ok(records
+        .iter()
+        .map(|record| {
+            let elapsed = (arrival(record) - start).to_std().unwrap_or_default();
+            ScheduledRequest {
+                request_id: record.request_id.clone(),
+                offset: elapsed.div_f64(speed),
+                request: redaction.apply(record.request.clone()),
+            }
+        })
+        .collect())
+}
+
+/// Send a replayed request to a deployment, returning its latency
+pub async fn send(
+    client: &RemoteClient,
+    request: &ChatCompletionRequest,
+) -> Result<Duration, RemoteError> {
+    let body = serde_json::to_value(request).map_err(|e| RemoteError::Request(e.to_string()))?;
+    let started = Instant::now();
+    client.post("/v1/chat/completions", Some(body)).await?;
+    Ok(started.elapsed())
+}
+
+/// Outcome of a traffic replay
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplaySummary {
+    /// Requests sent
+    pub requests: usize,
+    /// Requests that failed or timed out
+    pub errors: usize,
+    /// Share of the requests that failed, in percent
+    pub error_rate: f64,
+    /// Requests completed per second over the replay
+    pub throughput: f64,
+    /// Latency percentiles of the successful requests, in milliseconds
+    pub latency_p50_ms: f64,
+    pub latency_p95_ms: f64,
+    pub latency_p99_ms: f64,
+    pub latency_max_ms: f64,
+    /// Length of the replay
+    pub duration_secs: f64,
+    /// Length of the captured window
+    pub window_secs: f64,
+    /// Speed multiplier the window was replayed at
+    pub speed: f64,
+}
+
+impl ReplaySummary {
+    /// Summarize the latencies of the successful requests and the number of
+    /// failed ones
+    pub fn new(
+        mut latencies_ms: Vec<f64>,
+        errors: usize,
+        duration: Duration,
+        window: Duration,
+        speed: f64,
+    ) -> Self {
+        latencies_ms.sort_by(f64::total_cmp);
+        let requests = latencies_ms.len() + errors;
+        let percentile = |p: f64| {
+            if latencies_ms.is_empty() {
+                return 0.0;
+            }
+            let rank = ((p / 100.0) * latencies_ms.len() as f64).ceil() as usize;
+            latencies_ms[rank.clamp(1, latencies_ms.len()) - 1]
+        };
+        Self {
+            requests,
+            errors,
+            error_rate: if requests > 0 {
+                errors as f64 / requests as f64 * 100.0
+            } else {
+                0.0
+            },
+            throughput: if duration.as_secs_f64() > 0.0 {
+                requests as f64 / duration.as_secs_f64()
+            } else {
+                0.0
+            },
+            latency_p50_ms: percentile(50.0),
+            latency_p95_ms: percentile(95.0),
+            latency_p99_ms: percentile(99.0),
+            latency_max_ms: latencies_ms.last().copied().unwrap_or_default(),
+            duration_secs: duration.as_secs_f64(),
+            window_secs: window.as_secs_f64(),
+            speed,
+        }
+    }
+
+    /// Human-readable summary
+    pub fn render(&self) -> String {
+        format!(
+            "Window:     {:.1}s replayed at {}x in {:.1}s\n\
+             Requests:   {} ({} failed, {:.1}%)\n\
+             Throughput: {:.2} req/s\n\
+             Latency:    p50 {:.0}ms, p95 {:.0}ms, p99 {:.0}ms, max {:.0}ms\n",
+            self.window_secs,
+            self.speed,
+            self.duration_secs,
+            self.requests,
+            self.errors,
+            self.error_rate,
+            self.throughput,
+            self.latency_p50_ms,
+            self.latency_p95_ms,
+            self.latency_p99_ms,
+            self.latency_max_ms,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::llm_proxy::domain::message::Message;
+
+    fn record(id: &str, completed_secs: i64, latency_ms: u64) -> PayloadRecord {
+        let timestamp = DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(completed_secs);
+        PayloadRecord {
+            request_id: id.to_string(),
+            timestamp,
+            tenant: None,
+            user: None,
+            requested_model: "gpt-4o".to_string(),
+            model: "gpt-4o".to_string(),
+            provider: "openai".to_string(),
+            policy_version: None,
+            prompt: None,
+            request: ChatCompletionRequest {
+                model: "gpt-4o".to_string(),
+                messages: vec![Message::new_user("Where is order 42?".to_string())],
+                temperature: None,
+                top_p: None,
+                n: None,
+                stream: false,
+                max_tokens: None,
+                presence_penalty: None,
+                frequency_penalty: None,
+                user: Some("alice@example.com".to_string()),
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
+            },
+            response: None,
+            error: None,
+            latency_ms,
+        }
+    }
+
+    #[test]
+    fn test_schedule() {
+        // Requests arrive 10s and 20s after the first, whatever their latency
+        let records = vec![
+            record("a", 1, 1000),
+            record("b", 12, 2000),
+            record("c", 21, 1000),
+        ];
+        let redaction = Redaction::new().with_masked_content().with_model("staging");
+        let scheduled = schedule(&records, 2.0, &redaction).unwrap();
+        let offsets: Vec<_> = scheduled.iter().map(|s| s.offset).collect();
+        assert_eq!(
+            offsets,
+            vec![
+                Duration::ZERO,
+                Duration::from_secs(5),
+                Duration::from_secs(10)
+            ]
+        );
+
+        let request = &scheduled[0].request;
+        assert_eq!(request.model, "staging");
+        assert_eq!(
+            request.messages[0].extract_text_content(),
+            "xxxxx xx xxxxx xx?"
+        );
+        let user = request.user.as_deref().unwrap();
+        assert!(user.starts_with("replay-"));
+        assert_eq!(scheduled[1].request.user.as_deref(), Some(user));
+
+        assert!(schedule(&records, 0.0, &redaction).is_err());
+    }
+
+    #[test]
+    fn test_window() {
+        let window = TrafficWindow {
+            since: Some(DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(10)),
+            until: Some(DateTime::<Utc>::UNIX_EPOCH + chrono::Duration::seconds(20)),
+        };
+        assert!(!window.contains(arrival(&record("a", 10, 1000))));
+        assert!(window.contains(arrival(&record("b", 11, 1000))));
+        assert!(!window.contains(arrival(&record("c", 21, 1000))));
+    }
+
+    #[test]
+    fn test_summary() {
+        let latencies = (1..=100).map(f64::from).collect();
+        let summary = ReplaySummary::new(
+            latencies,
+            25,
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+            2.0,
+        );
+        assert_eq!(summary.requests, 125);
+        assert_eq!(summary.error_rate, 20.0);
+        assert_eq!(summary.throughput, 25.0);
+        assert_eq!(summary.latency_p50_ms, 50.0);
+        assert_eq!(summary.latency_p99_ms, 99.0);
+        assert_eq!(summary.latency_max_ms, 100.0);
+    }
+}
@@ -6,6 +6,15 @@ use std::collections::HashMap;
 
 use crate::modules::model_registry::ChatCompletionRequest;
 
+/// Context parameter carrying the tenant ID
+pub const TENANT_PARAMETER: &str = "tenant";
+
+/// Context parameter carrying the ID of the active persona
+pub const PERSONA_PARAMETER: &str = "persona";
+
+/// Context parameter carrying the ISO 639-1 code of the request's language
+pub const LANGUAGE_PARAMETER: &str = "language";
+
 /// Routing context containing information used during routing
 #[derive(Debug, Clone)]
 pub struct RoutingContext {
@@ -69,6 +78,41 @@ impl RoutingContext {
         self
     }
 
+    /// Set the tenant the request belongs to
+    pub fn with_tenant(self, tenant: impl Into<String>) -> Self {
+        self.with_parameter(TENANT_PARAMETER, tenant)
+    }
+
+    /// Get the tenant the request belongs to
+    ///
+    /// Falls back to the organization ID when no tenant parameter is set.
+    pub fn tenant(&self) -> Option<&str> {
+        self.parameters
+            .get(TENANT_PARAMETER)
+            .or(self.org_id.as_ref())
+            .map(String::as_str)
+    }
+
+    /// Set the persona active for the request
+    pub fn with_persona(self, persona: impl Into<String>) -> Self {
+        self.with_parameter(PERSONA_PARAMETER, persona)
+    }
+
+    /// Get the persona active for the request
+    pub fn persona(&self) -> Option<&str> {
+        self.parameters.get(PERSONA_PARAMETER).map(String::as_str)
+    }
+
+    /// Set the language of the request
+    pub fn with_language(self, language: impl Into<String>) -> Self {
+        self.with_parameter(LANGUAGE_PARAMETER, language)
+    }
+
+    /// Get the language of the request, if set or detected
+    pub fn language(&self) -> Option<&str> {
+        self.parameters.get(LANGUAGE_PARAMETER).map(String::as_str)
+    }
+
     /// Add a tag
     pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
         self.tags.push(tag.into());
@@ -35,6 +35,18 @@ pub enum RouterError {
     #[error("All fallbacks failed: {0}")]
     FallbackError(String),
 
+    /// Request rejected by a data-residency constraint
+    #[error("Data residency violation: {0}")]
+    DataResidency(String),
+
+    /// Request rejected by a router plugin
+    #[error("Rejected by plugin {0}")]
+    PluginRejected(String),
+
+    /// Provider output not in the requested format
+    #[error("Invalid model output: {0}")]
+    InvalidOutput(String),
+
     /// Other errors
     #[error("Error: {0}")]
     Other(String),
@@ -0,0 +1,457 @@
+//! Routing Explanations
+//!
+//! This module scores every registered model against a request without calling
+//! any provider, so operators can see how the candidates compare. Each
+//! candidate gets a score per dimension (cost, latency, capability fit and
+//! health) in the `0.0..=1.0` range, combined with weights that depend on the
+//! routing strategy. The ranking is a heuristic: the weights approximate what
+//! a strategy optimizes for, but the strategies do not score models this way,
+//! so the router may pick another eligible model. Models that cannot serve
+//! the request are listed with the reason they were ruled out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::modules::llm_proxy::transform::model_matches;
+use crate::modules::model_registry::{
+    global_tokenizers, ModelMetadata, ModelRegistry, ModelStatus,
+};
+use crate::modules::router_core::policy::PolicyDecision;
+use crate::modules::router_core::request::RoutingRequest;
+use crate::modules::router_core::residency::ResidencyEnforcer;
+use crate::modules::router_core::strategy::RoutingStrategy;
+
+/// Score given to a dimension when the model has no data for it
+const UNKNOWN_SCORE: f64 = 0.5;
+
+/// Heuristic weights used to combine dimension scores
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScoreWeights {
+    /// Weight of the cost score
+    pub cost: f64,
+    /// Weight of the latency score
+    pub latency: f64,
+    /// Weight of the capability fit score
+    pub capability_fit: f64,
+    /// Weight of the health score
+    pub health: f64,
+}
+
+impl ScoreWeights {
+    /// Heuristic weights approximating what a routing strategy optimizes for
+    ///
+    /// The weights are fixed per strategy, not taken from the strategy.
+    pub fn for_strategy(strategy: RoutingStrategy) -> Self {
+        let (cost, latency, capability_fit, health) = match strategy {
+            RoutingStrategy::CostOptimized => (0.6, 0.1, 0.15, 0.15),
+            RoutingStrategy::LatencyOptimized => (0.1, 0.6, 0.15, 0.15),
+            RoutingStrategy::ContentBased => (0.15, 0.15, 0.5, 0.2),
+            RoutingStrategy::LoadBalanced => (0.1, 0.3, 0.2, 0.4),
+            RoutingStrategy::RoundRobin | RoutingStrategy::Custom => (0.25, 0.25, 0.25, 0.25),
+        };
+        Self {
+            cost,
+            latency,
+            capability_fit,
+            health,
+        }
+    }
+}
+
+/// Per-dimension scores of a candidate model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CandidateScores {
+    /// Relative cost (1.0 is the cheapest candidate)
+    pub cost: f64,
+    /// Relative latency (1.0 is the fastest candidate)
+    pub latency: f64,
+    /// How well the model's capabilities fit the request
+    pub capability_fit: f64,
+    /// Health derived from the model status
+    pub health: f64,
+    /// Heuristic weighted combination of the other scores
+    pub total: f64,
+}
+
+/// A model considered for a request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteCandidate {
+    /// Model identifier
+    pub model_id: String,
+    /// Provider of the model
+    pub provider: String,
+    /// Position in the ranking (1 is the best), absent for ineligible models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rank: Option<usize>,
+    /// Whether the model can serve the request
+    pub eligible: bool,
+    /// Why the model cannot serve the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excluded_reason: Option<String>,
+    /// Dimension scores
+    pub scores: CandidateScores,
+}
+
+/// How candidates were ranked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingMethod {
+    /// Weighted dimension scores, not the strategy's own selection
+    Heuristic,
+}
+
+/// Explanation of a routing decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteExplanation {
+    /// Strategy used to weigh the scores
+    pub strategy: RoutingStrategy,
+    /// How the candidates were ranked
+    pub ranking: RankingMethod,
+    /// Weights applied to the dimension scores
+    pub weights: ScoreWeights,
+    /// Highest ranked eligible model; the router's strategy may pick another
+    /// eligible model
+    pub selected_model: Option<String>,
+    /// Routing policy decision applied to the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyDecision>,
+    /// Candidates, eligible ones first in ranking order
+    pub candidates: Vec<RouteCandidate>,
+}
+
+/// Constraints applied to a request before scoring
+#[derive(Debug, Clone, Default)]
+pub struct RouteConstraints {
+    /// Policy decision restricting the allowed models
+    pub policy: Option<PolicyDecision>,
+    /// Regions the request must stay in (empty allows all)
+    pub allowed_regions: Vec<String>,
+}
+
+/// Rank the registered models for a request without routing it
+pub fn explain(
+    request: &RoutingRequest,
+    registry: &ModelRegistry,
+    strategy: RoutingStrategy,
+    constraints: RouteConstraints,
+) -> RouteExplanation {
+    let strategy = constraints
+        .policy
+        .as_ref()
+        .and_then(|decision| decision.outcome.strategy)
+        .unwrap_or(strategy);
+    let weights = ScoreWeights::for_strategy(strategy);
+
+    let models = registry.list_models();
+    let costs: Vec<f64> = models.iter().filter_map(model_cost).collect();
+    let latencies: Vec<f64> = models
+        .iter()
+        .filter_map(|m| m.capabilities.performance.avg_latency_ms)
+        .filter(|latency| *latency > 0.0)
+        .collect();
+    let min_cost = costs.iter().copied().fold(f64::INFINITY, f64::min);
+    let min_latency = latencies.iter().copied().fold(f64::INFINITY, f64::min);
+
+    let mut candidates: Vec<RouteCandidate> = models
+        .iter()
+        .map(|model| {
+            let mut scores = CandidateScores {
+                cost: relative_score(model_cost(model), min_cost),
+                latency: relative_score(model.capabilities.performance.avg_latency_ms, min_latency),
+                capability_fit: capability_fit(model, request),
+                health: health_score(&model.status),
+                total: 0.0,
+            };
+            scores.total = weights.cost * scores.cost
+                + weights.latency * scores.latency
+                + weights.capability_fit * scores.capability_fit
+                + weights.health * scores.health;
+
+            let excluded_reason = exclusion_reason(model, request, &constraints);
+            RouteCandidate {
+                model_id: model.id.clone(),
+                provider: model.provider.clone(),
+                rank: None,
+                eligible: excluded_reason.is_none(),
+                excluded_reason,
+                scores,
+            }
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        b.eligible.cmp(&a.eligible).then(
+            b.scores
+                .total
+                .partial_cmp(&a.scores.total)
+                .unwrap_or(std::cmp::Ordering::Equal),
+        )
+    });
+
+    // The router moves an eligible preferred model to the front
+    if let Some(preferred) = &request.preferred_model_id {
+        if let Some(pos) = candidates
+            .iter()
+            .position(|c| c.eligible && &c.model_id == preferred)
+        {
+            let candidate = candidates.remove(pos);
+            candidates.insert(0, candidate);
+        }
+    }
+
+    for (i, candidate) in candidates.iter_mut().filter(|c| c.eligible).enumerate() {
+        candidate.rank = Some(i + 1);
+    }
+
+    RouteExplanation {
+        strategy,
+        ranking: RankingMethod::Heuristic,
+        weights,
+        selected_model: candidates
+            .first()
+            .filter(|c| c.eligible)
+            .map(|c| c.model_id.clone()),
+        policy: constraints.policy,
+        candidates,
+    }
+}
+
+/// Combined cost per 1K tokens of a model, if it has pricing
+fn model_cost(model: &ModelMetadata) -> Option<f64> {
+    let cost =
+        model.capabilities.cost_per_1k_tokens_input + model.capabilities.cost_per_1k_tokens_output;
+    (cost > 0.0).then_some(cost)
+}
+
+/// Score a value against the best (lowest) value among candidates
+fn relative_score(value: Option<f64>, best: f64) -> f64 {
+    match value {
+        Some(value) if value > 0.0 && best.is_finite() => (best / value).clamp(0.0, 1.0),
+        _ => UNKNOWN_SCORE,
+    }
+}
+
+/// Score derived from a model status
+fn health_score(status: &ModelStatus) -> f64 {
+    match status {
+        ModelStatus::Available => 1.0,
+        ModelStatus::Limited => 0.5,
+        ModelStatus::Deprecated => 0.25,
+        ModelStatus::Unknown => 0.1,
+        ModelStatus::Unavailable | ModelStatus::Maintenance => 0.0,
+    }
+}
+
+/// Estimate the prompt size of a request in the tokens of a model
+fn estimate_tokens(request: &RoutingRequest, model: &ModelMetadata) -> usize {
+    let tokenizer = global_tokenizers().for_model(&model.id);
+    let tokens: usize = request
+        .context
+        .request
+        .messages
+        .iter()
+        .map(|m| tokenizer.count(&m.content))
+        .sum();
+    // Plus a safety buffer for message framing
+    tokens + 100
+}
+
+/// Capabilities a request requires
+fn required_capabilities(request: &RoutingRequest) -> Vec<&'static str> {
+    let mut required = Vec::new();
+    if request.context.request.stream.unwrap_or(false) {
+        required.push("streaming");
+    }
+    if request.context.request.functions.is_some() || request.context.request.tools.is_some() {
+        required.push("function_calling");
+    }
+    required
+}
+
+/// Score how well a model fits a request
+///
+/// Averages the share of required capabilities the model supports, the
+/// context-window headroom left by the prompt and whether the model is the one
+/// the client asked for.
+fn capability_fit(model: &ModelMetadata, request: &RoutingRequest) -> f64 {
+    let required = required_capabilities(request);
+    let coverage = if required.is_empty() {
+        1.0
+    } else {
+        let supported = required
+            .iter()
+            .filter(|feature| model.capabilities.supports_feature(feature))
+            .count();
+        supported as f64 / required.len() as f64
+    };
+
+    let max_context = model.capabilities.max_context_length;
+    let headroom = if max_context == 0 {
+        UNKNOWN_SCORE
+    } else {
+        1.0 - (estimate_tokens(request, model) as f64 / max_context as f64).min(1.0)
+    };
+
+    let requested = &request.context.request.model;
+    let name_match = if &model.id == requested || &model.name == requested {
+        1.0
+    } else {
+        UNKNOWN_SCORE
+    };
+
+    (coverage + headroom + name_match) / 3.0
+}
+
+/// Reason a model cannot serve a request, if any
+fn exclusion_reason(
+    model: &ModelMetadata,
+    request: &RoutingRequest,
+    constraints: &RouteConstraints,
+) -> Option<String> {
+    if !model.is_available() {
+        return Some(format!("model status is {}", model.status));
+    }
+
+    if let Some(decision) = &constraints.policy {
+        let allowed = &decision.outcome.allowed_models;
+        if !allowed.is_empty() && !allowed.iter().any(|p| model_matches(p, &model.id)) {
+            return Some(match &decision.rule {
+                Some(rule) => format!("not allowed by policy rule '{}'", rule),
+                None => "not allowed by the policy default".to_string(),
+            });
+        }
+    }
+
+    if !constraints.allowed_regions.is_empty()
+        && !ResidencyEnforcer::region_allowed(model, &constraints.allowed_regions)
+    {
+        return Some(format!(
+            "hosted in {} which is outside the allowed regions",
+            model.region.as_deref().unwrap_or("an unknown region")
+        ));
+    }
+
+    if request.excluded_model_ids.contains(&model.id) {
+        return Some("excluded by the request".to_string());
+    }
+
+    if let Some(feature) = required_capabilities(request)
+        .into_iter()
+        .find(|feature| !model.capabilities.supports_feature(feature))
+    {
+        return Some(format!("does not support {}", feature.replace('_', " ")));
+    }
+
+    if estimate_tokens(request, model) > model.capabilities.max_context_length {
+        return Some("context window is too small for the prompt".to_string());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionRequest, ChatMessage, MessageRole,
+    };
+
+    fn model(id: &str, cost: f64, latency: f64) -> ModelMetadata {
+        let mut model = ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "test".to_string(),
+            "1.0".to_string(),
+            "http://localhost".to_string(),
+        );
+        model.set_status(ModelStatus::Available);
+        model.capabilities.max_context_length = 8192;
+        model.capabilities.cost_per_1k_tokens_input = cost;
+        model.capabilities.cost_per_1k_tokens_output = cost;
+        model.capabilities.performance.avg_latency_ms = Some(latency);
+        model
+    }
+
+    fn request() -> RoutingRequest {
+        RoutingRequest::new(ChatCompletionRequest {
+            model: "any".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        })
+    }
+
+    fn registry() -> ModelRegistry {
+        let registry = ModelRegistry::new();
+        registry
+            .register_model(model("cheap-slow", 0.001, 900.0))
+            .unwrap();
+        registry
+            .register_model(model("pricey-fast", 0.03, 150.0))
+            .unwrap();
+        let mut down = model("down", 0.0005, 100.0);
+        down.set_status(ModelStatus::Unavailable);
+        registry.register_model(down).unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_strategy_changes_ranking() {
+        let registry = registry();
+
+        let cost = explain(
+            &request(),
+            &registry,
+            RoutingStrategy::CostOptimized,
+            RouteConstraints::default(),
+        );
+        assert_eq!(cost.ranking, RankingMethod::Heuristic);
+        assert_eq!(cost.selected_model.as_deref(), Some("cheap-slow"));
+
+        let latency = explain(
+            &request(),
+            &registry,
+            RoutingStrategy::LatencyOptimized,
+            RouteConstraints::default(),
+        );
+        assert_eq!(latency.selected_model.as_deref(), Some("pricey-fast"));
+        assert_eq!(latency.candidates[0].rank, Some(1));
+    }
+
+    #[test]
+    fn test_ineligible_models_are_explained() {
+        let registry = registry();
+        let explanation = explain(
+            &request(),
+            &registry,
+            RoutingStrategy::CostOptimized,
+            RouteConstraints {
+                policy: None,
+                allowed_regions: vec!["eu-*".to_string()],
+            },
+        );
+
+        assert!(explanation.selected_model.is_none());
+        assert!(explanation.candidates.iter().all(|c| !c.eligible));
+        let down = explanation
+            .candidates
+            .iter()
+            .find(|c| c.model_id == "down")
+            .unwrap();
+        assert_eq!(
+            down.excluded_reason.as_deref(),
+            Some("model status is Unavailable")
+        );
+    }
+}
@@ -0,0 +1,489 @@
+//! Language Steering
+//!
+//! This module detects the language of a request's user messages and steers
+//! routing toward models declaring support for it in their capabilities
+//! (`supported_languages`, ISO 639-1 codes).
+//!
+//! Detection needs no model: non-Latin scripts map to their language (Han to
+//! Chinese, kana to Japanese, Hangul to Korean, ...), and Latin-script text
+//! is told apart by its most frequent function words. A language set on the
+//! request (the `language` context parameter) is used as is.
+//!
+//! Steering runs after tenant, policy, residency and persona constraints and
+//! never widens them. A preferred model chosen by the caller or a persona is
+//! kept; otherwise models without support for the language are excluded. In
+//! `prefer` mode that only happens when a supporting model is available, in
+//! `require` mode the request fails when none is.
+
+use std::collections::HashMap;
+
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::modules::model_registry::connectors::MessageRole;
+use crate::modules::model_registry::ModelRegistry;
+use crate::modules::router_core::errors::RouterError;
+use crate::modules::router_core::request::RoutingRequest;
+use crate::modules::telemetry::catalog;
+
+/// How detected languages steer routing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SteeringMode {
+    /// Detect and report the language only
+    Off,
+    /// Route to a supporting model when one is available
+    #[default]
+    Prefer,
+    /// Fail requests no available model supports
+    Require,
+}
+
+/// Language detection and steering configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageConfig {
+    /// Whether languages are detected
+    pub enabled: bool,
+    /// How detected languages steer routing
+    pub steering: SteeringMode,
+    /// Minimum confidence, from 0 to 1, for a detection to be used
+    pub min_confidence: f64,
+    /// Minimum number of letters in the user messages to attempt detection
+    pub min_chars: usize,
+    /// Languages steered; every detected language when empty
+    pub languages: Vec<String>,
+}
+
+impl Default for LanguageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            steering: SteeringMode::Prefer,
+            min_confidence: 0.6,
+            min_chars: 16,
+            languages: Vec::new(),
+        }
+    }
+}
+
+/// Language detected in a text
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedLanguage {
+    /// ISO 639-1 code of the language
+    pub code: String,
+    /// Confidence of the detection, from 0 to 1
+    pub confidence: f64,
+}
+
+/// Writing systems told apart by the detector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Han,
+    Kana,
+    Hangul,
+    Cyrillic,
+    Arabic,
+    Hebrew,
+    Greek,
+    Devanagari,
+    Thai,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F => Some(Script::Latin),
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF => Some(Script::Han),
+            0x3040..=0x30FF => Some(Script::Kana),
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Some(Script::Hangul),
+            0x400..=0x4FF => Some(Script::Cyrillic),
+            0x600..=0x6FF | 0x750..=0x77F => Some(Script::Arabic),
+            0x590..=0x5FF => Some(Script::Hebrew),
+            0x370..=0x3FF => Some(Script::Greek),
+            0x900..=0x97F => Some(Script::Devanagari),
+            0xE00..=0xE7F => Some(Script::Thai),
+            _ => None,
+        }
+    }
+}
+
+/// Frequent function words of the Latin-script languages detected
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "is", "are", "of", "to", "in", "that", "it", "with", "for", "this",
+            "you", "what", "how", "was", "be", "have", "not", "can",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "los", "las", "que", "y", "es", "en", "un", "una", "por", "para", "con",
+            "no", "del", "se", "como", "está", "qué", "pero",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "est", "un", "une", "des", "du", "que", "qui", "pour", "dans",
+            "pas", "sur", "avec", "ce", "je", "vous", "nous",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "zu", "mit", "ich", "sie",
+            "es", "den", "auf", "für", "von", "wie", "was", "sind",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "lo", "la", "gli", "le", "e", "è", "un", "una", "che", "di", "per", "non", "con",
+            "sono", "come", "questo", "anche", "mi", "ma",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "a", "as", "e", "é", "um", "uma", "que", "não", "de", "do", "da", "em",
+            "para", "com", "por", "como", "mais", "você",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "is", "van", "dat", "niet", "op", "te", "zijn", "met",
+            "voor", "ik", "je", "wat", "hoe", "maar", "ook", "er",
+        ],
+    ),
+];
+
+/// Detect the language of a text
+///
+/// Returns `None` for text without letters, or Latin-script text without any
+/// known function word.
+pub fn detect_language(text: &str) -> Option<DetectedLanguage> {
+    let mut scripts: HashMap<Script, usize> = HashMap::new();
+    let mut letters = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        if let Some(script) = Script::of(c) {
+            *scripts.entry(script).or_default() += 1;
+        }
+    }
+    let count = |script| scripts.get(&script).copied().unwrap_or(0);
+
+    // Japanese mixes kana with Han characters
+    let (script, script_letters) = if count(Script::Kana) > 0 {
+        (Script::Kana, count(Script::Kana) + count(Script::Han))
+    } else {
+        scripts
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(script, count)| (*script, *count))?
+    };
+    let share = script_letters as f64 / letters as f64;
+
+    let code = match script {
+        Script::Latin => {
+            let (code, confidence) = latin_language(text)?;
+            return Some(DetectedLanguage {
+                code: code.to_string(),
+                confidence: share * confidence,
+            });
+        }
+        Script::Han => "zh",
+        Script::Kana => "ja",
+        Script::Hangul => "ko",
+        Script::Cyrillic if text.chars().any(|c| "іїєґІЇЄҐ".contains(c)) => "uk",
+        Script::Cyrillic => "ru",
+        Script::Arabic => "ar",
+        Script::Hebrew => "he",
+        Script::Greek => "el",
+        Script::Devanagari => "hi",
+        Script::Thai => "th",
+    };
+    Some(DetectedLanguage {
+        code: code.to_string(),
+        confidence: share,
+    })
+}
+
+/// Pick the Latin-script language whose function words are most frequent
+///
+/// The confidence is the margin over the runner-up: the share of the best
+/// language among the function word hits of the two most frequent languages.
+fn latin_language(text: &str) -> Option<(&'static str, f64)> {
+    let text = text.to_lowercase();
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let mut hits: Vec<(&'static str, usize)> = STOPWORDS
+        .iter()
+        .map(|(code, stopwords)| {
+            let hits = words.iter().filter(|word| stopwords.contains(word)).count();
+            (*code, hits)
+        })
+        .collect();
+    hits.sort_by(|a, b| b.1.cmp(&a.1));
+    let (code, best) = hits[0];
+    let runner_up = hits[1].1;
+    (best > 0).then(|| (code, best as f64 / (best + runner_up) as f64))
+}
+
+/// Whether a model's supported language matches a language code, ignoring
+/// case and any region suffix (`pt-BR` matches `pt`)
+fn supports(supported: &str, code: &str) -> bool {
+    let base = supported.split(['-', '_']).next().unwrap_or(supported);
+    base.eq_ignore_ascii_case(code)
+}
+
+/// Language detection and steering applied to routed requests
+#[derive(Debug, Default)]
+pub struct LanguageSteering {
+    config: LanguageConfig,
+}
+
+impl LanguageSteering {
+    /// Create language steering from its configuration
+    pub fn new(config: LanguageConfig) -> Self {
+        Self { config }
+    }
+
+    /// Whether languages are detected
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Detect the language of a request's user messages
+    ///
+    /// Returns `None` when detection is disabled, the messages are too short
+    /// or the detection is not confident enough.
+    pub fn detect<'a>(
+        &self,
+        user_messages: impl IntoIterator<Item = &'a str>,
+    ) -> Option<DetectedLanguage> {
+        if !self.config.enabled {
+            return None;
+        }
+        let text = user_messages.into_iter().collect::<Vec<_>>().join("\n");
+        if text.chars().filter(|c| c.is_alphabetic()).count() < self.config.min_chars {
+            return None;
+        }
+        let detected = detect_language(&text)
+            .filter(|detected| detected.confidence >= self.config.min_confidence)?;
+        counter!(catalog::ROUTING_LANGUAGE_DETECTED, 1, "language" => detected.code.clone());
+        Some(detected)
+    }
+
+    /// Detect the language of a routing request and steer it toward models
+    /// supporting that language
+    ///
+    /// The language is recorded in the request's `language` context parameter.
+    /// Returns an error in `require` mode when no available model supports it.
+    pub fn apply(
+        &self,
+        request: &mut RoutingRequest,
+        registry: &ModelRegistry,
+    ) -> Result<(), RouterError> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let language = match request.context.language() {
+            Some(language) => language.to_string(),
+            None => {
+                let detected = self.detect(
+                    request
+                        .context
+                        .request
+                        .messages
+                        .iter()
+                        .filter(|message| message.role == MessageRole::User)
+                        .map(|message| message.content.as_str()),
+                );
+                let Some(detected) = detected else {
+                    return Ok(());
+                };
+                debug!(
+                    "Detected language {} ({:.2})",
+                    detected.code, detected.confidence
+                );
+                request.context = request.context.clone().with_language(&detected.code);
+                detected.code
+            }
+        };
+
+        if self.config.steering == SteeringMode::Off
+            || request.preferred_model_id.is_some()
+            || !(self.config.languages.is_empty()
+                || self.config.languages.iter().any(|l| supports(l, &language)))
+        {
+            return Ok(());
+        }
+
+        let (supporting, other): (Vec<_>, Vec<_>) = registry
+            .list_models()
+            .into_iter()
+            .filter(|model| !request.excluded_model_ids.contains(&model.id))
+            .partition(|model| {
+                model
+                    .capabilities
+                    .supported_languages
+                    .iter()
+                    .any(|supported| supports(supported, &language))
+            });
+
+        if !supporting.iter().any(|model| model.is_available()) {
+            if self.config.steering == SteeringMode::Require {
+                return Err(RouterError::NoSuitableModel(format!(
+                    "no available model supports language '{}'",
+                    language
+                )));
+            }
+            debug!("No available model supports language {}", language);
+            return Ok(());
+        }
+
+        request
+            .excluded_model_ids
+            .extend(other.into_iter().map(|model| model.id));
+        counter!(catalog::ROUTING_LANGUAGE_STEERED, 1, "language" => language);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{ChatCompletionRequest, ChatMessage};
+    use crate::modules::model_registry::{ModelMetadata, ModelStatus};
+
+    #[test]
+    fn test_detect_language() {
+        let cases = [
+            ("What is the capital of France and how big is it?", "en"),
+            ("¿Cuál es la capital de Francia y qué tan grande es?", "es"),
+            (
+                "Quelle est la capitale de la France et est-elle grande ?",
+                "fr",
+            ),
+            (
+                "Was ist die Hauptstadt von Frankreich und wie groß ist sie?",
+                "de",
+            ),
+            ("Qual é a capital da França e como ela é grande?", "pt"),
+            ("フランスの首都はどこですか？", "ja"),
+            ("法国的首都是哪里？", "zh"),
+            ("프랑스의 수도는 어디입니까?", "ko"),
+            ("Какая столица у Франции?", "ru"),
+            ("ما هي عاصمة فرنسا؟", "ar"),
+        ];
+        for (text, code) in cases {
+            let detected = detect_language(text).unwrap();
+            assert_eq!(detected.code, code, "{}", text);
+            assert!(detected.confidence > 0.5, "{}: {:?}", text, detected);
+        }
+        assert!(detect_language("1234 !?").is_none());
+        assert!(detect_language("xyzzy plugh").is_none());
+    }
+
+    fn model(id: &str, languages: &[&str]) -> ModelMetadata {
+        let mut model = ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "test".to_string(),
+            "1.0".to_string(),
+            "http://localhost".to_string(),
+        );
+        model.capabilities.supported_languages = languages.iter().map(|l| l.to_string()).collect();
+        model.set_status(ModelStatus::Available);
+        model
+    }
+
+    fn request(text: &str) -> RoutingRequest {
+        let message = ChatMessage {
+            role: MessageRole::User,
+            content: text.to_string(),
+            name: None,
+            function_call: None,
+            tool_calls: None,
+            tool_call_id: None,
+        };
+        let completion = ChatCompletionRequest {
+            model: "any".to_string(),
+            messages: vec![message],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        };
+        RoutingRequest::new(completion)
+    }
+
+    fn steering(steering: SteeringMode) -> LanguageSteering {
+        LanguageSteering::new(LanguageConfig {
+            enabled: true,
+            steering,
+            ..LanguageConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_apply_steers_to_supporting_models() {
+        let registry = ModelRegistry::new();
+        registry.register_model(model("gpt-4", &["en"])).unwrap();
+        registry
+            .register_model(model("qwen", &["en", "zh"]))
+            .unwrap();
+
+        let mut req = request("请用三句话总结这篇关于法国历史的文章。");
+        steering(SteeringMode::Prefer)
+            .apply(&mut req, &registry)
+            .unwrap();
+        assert_eq!(req.context.language(), Some("zh"));
+        assert_eq!(req.excluded_model_ids, vec!["gpt-4".to_string()]);
+
+        // A model preferred by the caller is kept
+        let mut req =
+            request("请用三句话总结这篇关于法国历史的文章。").with_preferred_model("gpt-4");
+        steering(SteeringMode::Prefer)
+            .apply(&mut req, &registry)
+            .unwrap();
+        assert!(req.excluded_model_ids.is_empty());
+
+        // Off only detects
+        let mut req = request("请用三句话总结这篇关于法国历史的文章。");
+        steering(SteeringMode::Off)
+            .apply(&mut req, &registry)
+            .unwrap();
+        assert_eq!(req.context.language(), Some("zh"));
+        assert!(req.excluded_model_ids.is_empty());
+    }
+
+    #[test]
+    fn test_apply_without_supporting_model() {
+        let registry = ModelRegistry::new();
+        registry.register_model(model("gpt-4", &["en"])).unwrap();
+
+        // A language set on the request is used as is
+        let mut req = request("Bonjour");
+        req.context = req.context.clone().with_language("fr");
+        steering(SteeringMode::Prefer)
+            .apply(&mut req, &registry)
+            .unwrap();
+        assert!(req.excluded_model_ids.is_empty());
+
+        let result = steering(SteeringMode::Require).apply(&mut req, &registry);
+        assert!(matches!(result, Err(RouterError::NoSuitableModel(_))));
+    }
+}
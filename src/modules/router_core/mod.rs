@@ -11,10 +11,16 @@
 pub mod config;
 pub mod context;
 pub mod errors;
+pub mod explain;
 pub mod functions;
 pub mod interface;
+pub mod language;
+pub mod persona;
+pub mod plugins;
+pub mod policy;
 pub mod registry_integration;
 pub mod request;
+pub mod residency;
 pub mod response;
 pub mod retry;
 pub mod router;
@@ -25,10 +31,16 @@ pub mod strategy;
 pub use config::RouterConfig;
 pub use context::RoutingContext;
 pub use errors::RouterError;
+pub use explain::{RankingMethod, RouteConstraints, RouteExplanation};
 pub use functions::{init, route_request};
 pub use interface::Router;
+pub use language::{DetectedLanguage, LanguageConfig, LanguageSteering, SteeringMode};
+pub use persona::PersonaPreferences;
+pub use plugins::{PluginChain, PluginError, PluginRegistry, RouteSelection, RouterPlugin};
+pub use policy::{PolicyEngine, RoutingPolicy};
 pub use registry_integration::RegistryIntegration;
 pub use request::RoutingRequest;
+pub use residency::ResidencyEnforcer;
 pub use response::{RoutingMetadata, RoutingResponse};
 pub use retry::{CircuitBreakerConfig, DegradedServiceMode, ErrorCategory, RetryPolicy};
 pub use router::RouterImpl;
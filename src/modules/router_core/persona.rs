@@ -0,0 +1,224 @@
+//! Persona Preferences
+//!
+//! This module applies the model preferences of the persona active for a
+//! request (the `persona` context parameter). Precedence, from highest:
+//!
+//! 1. Values set on the request: its sampling parameters and any preferred
+//!    model chosen by the caller.
+//! 2. The persona's defaults: sampling parameters fill the ones the request
+//!    leaves unset, and the first of its preferred models that is available
+//!    and not excluded becomes the preferred model.
+//! 3. The routing strategy, which picks among the remaining models.
+//!
+//! Tenant, policy and residency exclusions are applied before, so a persona
+//! can never widen the models a request may use. An exclusive persona
+//! additionally excludes every model outside its list.
+
+use std::collections::HashMap;
+
+use tracing::debug;
+
+use crate::modules::model_registry::ModelRegistry;
+use crate::modules::persona_layer::{ModelPreferences, Persona};
+use crate::modules::router_core::errors::RouterError;
+use crate::modules::router_core::request::RoutingRequest;
+
+/// Model preferences of the personas, applied to routed requests
+#[derive(Debug, Default)]
+pub struct PersonaPreferences {
+    personas: HashMap<String, ModelPreferences>,
+}
+
+impl PersonaPreferences {
+    /// Create an empty set of preferences
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect the preferences of the personas declaring any
+    pub fn from_personas<'a>(personas: impl IntoIterator<Item = &'a Persona>) -> Self {
+        personas
+            .into_iter()
+            .fold(Self::new(), |preferences, persona| {
+                match &persona.model_preferences {
+                    Some(prefs) => preferences.with_persona(persona.id.clone(), prefs.clone()),
+                    None => preferences,
+                }
+            })
+    }
+
+    /// Set the preferences of a persona
+    pub fn with_persona(
+        mut self,
+        persona: impl Into<String>,
+        preferences: ModelPreferences,
+    ) -> Self {
+        self.personas.insert(persona.into(), preferences);
+        self
+    }
+
+    /// Preferences of a persona, if it declares any
+    pub fn preferences(&self, persona: &str) -> Option<&ModelPreferences> {
+        self.personas.get(persona)
+    }
+
+    /// Merge the active persona's preferences into a routing request
+    ///
+    /// Returns an error when an exclusive persona leaves no model to route to.
+    pub fn apply(
+        &self,
+        request: &mut RoutingRequest,
+        registry: &ModelRegistry,
+    ) -> Result<(), RouterError> {
+        let Some(persona) = request.context.persona().map(str::to_string) else {
+            return Ok(());
+        };
+        let Some(preferences) = self.preferences(&persona) else {
+            return Ok(());
+        };
+
+        let completion = &mut request.context.request;
+        completion.temperature = completion.temperature.or(preferences.temperature);
+        completion.top_p = completion.top_p.or(preferences.top_p);
+        completion.max_tokens = completion.max_tokens.or(preferences.max_tokens);
+
+        if preferences.exclusive {
+            for model in registry.list_models() {
+                if !preferences.models.contains(&model.id)
+                    && !request.excluded_model_ids.contains(&model.id)
+                {
+                    request.excluded_model_ids.push(model.id);
+                }
+            }
+            if let Some(preferred) = &request.preferred_model_id {
+                if request.excluded_model_ids.contains(preferred) {
+                    debug!("Persona {} excludes preferred model {}", persona, preferred);
+                    request.preferred_model_id = None;
+                }
+            }
+        }
+
+        if request.preferred_model_id.is_none() {
+            request.preferred_model_id = preferences
+                .models
+                .iter()
+                .find(|id| {
+                    !request.excluded_model_ids.contains(id)
+                        && registry
+                            .get_model(id)
+                            .is_ok_and(|model| model.is_available())
+                })
+                .cloned();
+        }
+
+        if preferences.exclusive && request.preferred_model_id.is_none() {
+            return Err(RouterError::NoSuitableModel(format!(
+                "none of the models preferred by persona '{}' is available",
+                persona
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionRequest, ChatMessage, MessageRole,
+    };
+    use crate::modules::model_registry::{ModelMetadata, ModelStatus};
+
+    fn model(id: &str, status: ModelStatus) -> ModelMetadata {
+        let mut model = ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "test".to_string(),
+            "1.0".to_string(),
+            "http://localhost".to_string(),
+        );
+        model.set_status(status);
+        model
+    }
+
+    fn registry() -> ModelRegistry {
+        let registry = ModelRegistry::new();
+        registry
+            .register_model(model("claude-3-opus", ModelStatus::Unavailable))
+            .unwrap();
+        registry
+            .register_model(model("gpt-4o", ModelStatus::Available))
+            .unwrap();
+        registry
+            .register_model(model("gpt-3.5-turbo", ModelStatus::Available))
+            .unwrap();
+        registry
+    }
+
+    fn request(persona: &str, temperature: Option<f32>) -> RoutingRequest {
+        let mut request = RoutingRequest::new(ChatCompletionRequest {
+            model: "gpt-3.5-turbo".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        });
+        request.context = request.context.clone().with_persona(persona);
+        request
+    }
+
+    fn preferences(exclusive: bool) -> PersonaPreferences {
+        PersonaPreferences::new().with_persona(
+            "analyst",
+            ModelPreferences {
+                models: vec!["claude-3-opus".to_string(), "gpt-4o".to_string()],
+                exclusive,
+                temperature: Some(0.2),
+                top_p: None,
+                max_tokens: Some(800),
+            },
+        )
+    }
+
+    #[test]
+    fn test_defaults_fill_unset_parameters() {
+        let mut req = request("analyst", Some(0.9));
+        preferences(false).apply(&mut req, &registry()).unwrap();
+
+        // The request's temperature wins over the persona's
+        assert_eq!(req.context.request.temperature, Some(0.9));
+        assert_eq!(req.context.request.max_tokens, Some(800));
+        // The unavailable first choice falls back to the next preferred model
+        assert_eq!(req.preferred_model_id.as_deref(), Some("gpt-4o"));
+        assert!(req.excluded_model_ids.is_empty());
+
+        let mut other = request("support", None);
+        preferences(false).apply(&mut other, &registry()).unwrap();
+        assert_eq!(other.context.request.temperature, None);
+        assert!(other.preferred_model_id.is_none());
+    }
+
+    #[test]
+    fn test_exclusive_persona_restricts_models() {
+        let mut req = request("analyst", None).with_preferred_model("gpt-3.5-turbo");
+        preferences(true).apply(&mut req, &registry()).unwrap();
+        assert_eq!(req.excluded_model_ids, vec!["gpt-3.5-turbo".to_string()]);
+        assert_eq!(req.preferred_model_id.as_deref(), Some("gpt-4o"));
+
+        let mut req = request("analyst", None).exclude_model("gpt-4o");
+        let err = preferences(true).apply(&mut req, &registry()).unwrap_err();
+        assert!(matches!(err, RouterError::NoSuitableModel(_)));
+    }
+}
@@ -0,0 +1,539 @@
+//! Router Plugins
+//!
+//! This module defines the hooks through which plugins observe and change
+//! routing, so custom logic can run without forking the router:
+//!
+//! - `pre_route` sees the request before the routing policy, residency and
+//!   persona constraints are applied, and may change it
+//! - `post_route` sees the selected model before it is called, and may
+//!   select another one; residency is checked after it runs
+//! - `pre_response` sees the model's response before it is returned, and may
+//!   change it
+//!
+//! Plugins are compiled in and registered by name in a [`PluginRegistry`].
+//! The `router.plugins` configuration picks the plugins that run, in order,
+//! with their settings. A plugin can reject a request; other failures are
+//! logged and skipped, or reject the request when the plugin is configured
+//! with `on_error = "reject"`.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use metrics::counter;
+use serde_json::Value;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::config::{PluginConfig, PluginFailurePolicy};
+use crate::modules::model_registry::{storage::ModelRegistry, ModelMetadata};
+use crate::modules::telemetry::catalog;
+
+use super::{RouterError, RoutingMetadata, RoutingRequest, RoutingResponse};
+
+/// Errors returned by plugins and when loading them
+#[derive(Error, Debug, Clone)]
+pub enum PluginError {
+    /// The plugin refuses the request
+    #[error("{0}")]
+    Rejected(String),
+
+    /// The plugin failed to process the request
+    #[error("{0}")]
+    Failed(String),
+
+    #[error("Unknown plugin: {0}")]
+    Unknown(String),
+
+    #[error("Invalid settings for plugin {plugin}: {message}")]
+    InvalidSettings { plugin: String, message: String },
+}
+
+/// Model selected for a request, as seen by `post_route`
+#[derive(Debug, Clone)]
+pub struct RouteSelection {
+    /// ID of the model to call; setting another registered model's ID
+    /// routes the request to it
+    pub model_id: String,
+    /// Metadata returned with the response
+    pub metadata: RoutingMetadata,
+}
+
+/// Hooks into the routing of requests
+///
+/// Every hook does nothing by default, so plugins implement the ones they
+/// need.
+#[async_trait]
+pub trait RouterPlugin: Send + Sync {
+    /// Name of the plugin in logs and errors
+    fn name(&self) -> &str;
+
+    /// Called before routing constraints are applied to a request
+    async fn pre_route(&self, _request: &mut RoutingRequest) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    /// Called after a model is selected, before it is called
+    async fn post_route(
+        &self,
+        _request: &RoutingRequest,
+        _selection: &mut RouteSelection,
+    ) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    /// Called with the model's response, before it is returned
+    async fn pre_response(
+        &self,
+        _request: &RoutingRequest,
+        _response: &mut RoutingResponse,
+    ) -> Result<(), PluginError> {
+        Ok(())
+    }
+}
+
+/// Builds a plugin from its configured settings
+pub type PluginFactory =
+    Box<dyn Fn(&Value) -> Result<Arc<dyn RouterPlugin>, PluginError> + Send + Sync>;
+
+/// Plugins that can be enabled through configuration, by name
+pub struct PluginRegistry {
+    factories: HashMap<String, PluginFactory>,
+}
+
+impl PluginRegistry {
+    /// Create a registry without plugins
+    pub fn empty() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Create a registry of the built-in plugins
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(MetadataPlugin::NAME, |settings| {
+            Ok(Arc::new(MetadataPlugin::from_settings(settings)?) as Arc<dyn RouterPlugin>)
+        });
+        registry
+    }
+
+    /// Register a plugin under a name, replacing any plugin of that name
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&Value) -> Result<Arc<dyn RouterPlugin>, PluginError> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Names of the registered plugins
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Build a configured plugin
+    pub fn build(
+        &self,
+        name: &str,
+        settings: &Value,
+    ) -> Result<Arc<dyn RouterPlugin>, PluginError> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| PluginError::Unknown(name.to_string()))?;
+        factory(settings)
+    }
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A plugin and what to do when it fails
+struct ChainedPlugin {
+    plugin: Arc<dyn RouterPlugin>,
+    on_error: PluginFailurePolicy,
+}
+
+/// Plugins run in order at each hook
+#[derive(Default)]
+pub struct PluginChain {
+    plugins: Vec<ChainedPlugin>,
+}
+
+impl fmt::Debug for PluginChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|chained| chained.plugin.name()))
+            .finish()
+    }
+}
+
+impl PluginChain {
+    /// Create a chain without plugins
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build the chain of the enabled configured plugins
+    pub fn from_config(
+        registry: &PluginRegistry,
+        configs: &[PluginConfig],
+    ) -> Result<Self, PluginError> {
+        let mut chain = Self::new();
+        for config in configs.iter().filter(|config| config.enabled) {
+            chain = chain.with(
+                registry.build(&config.name, &config.settings)?,
+                config.on_error,
+            );
+        }
+        Ok(chain)
+    }
+
+    /// Add a plugin at the end of the chain
+    pub fn with(mut self, plugin: Arc<dyn RouterPlugin>, on_error: PluginFailurePolicy) -> Self {
+        self.plugins.push(ChainedPlugin { plugin, on_error });
+        self
+    }
+
+    /// Whether the chain has no plugins
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Names of the plugins, in order
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins
+            .iter()
+            .map(|chained| chained.plugin.name())
+            .collect()
+    }
+
+    /// Run the `pre_route` hooks
+    pub async fn pre_route(&self, request: &mut RoutingRequest) -> Result<(), RouterError> {
+        for chained in &self.plugins {
+            let result = chained.plugin.pre_route(request).await;
+            handle(chained, "pre_route", result)?;
+        }
+        Ok(())
+    }
+
+    /// Run the `post_route` hooks, resolving the model they selected
+    pub async fn post_route(
+        &self,
+        request: &RoutingRequest,
+        model: ModelMetadata,
+        metadata: RoutingMetadata,
+        registry: &ModelRegistry,
+    ) -> Result<(ModelMetadata, RoutingMetadata), RouterError> {
+        let mut selection = RouteSelection {
+            model_id: model.id.clone(),
+            metadata,
+        };
+        for chained in &self.plugins {
+            let result = chained.plugin.post_route(request, &mut selection).await;
+            handle(chained, "post_route", result)?;
+        }
+
+        let RouteSelection {
+            model_id,
+            mut metadata,
+        } = selection;
+        if model_id == model.id {
+            return Ok((model, metadata));
+        }
+        let selected = registry.get_model(&model_id).map_err(|_| {
+            RouterError::NoSuitableModel(format!("Plugin selected unknown model: {}", model_id))
+        })?;
+        debug!("Plugins routed request from {} to {}", model.id, model_id);
+        metadata.selected_model_id = model_id;
+        Ok((selected, metadata))
+    }
+
+    /// Run the `pre_response` hooks
+    pub async fn pre_response(
+        &self,
+        request: &RoutingRequest,
+        response: &mut RoutingResponse,
+    ) -> Result<(), RouterError> {
+        for chained in &self.plugins {
+            let result = chained.plugin.pre_response(request, response).await;
+            handle(chained, "pre_response", result)?;
+        }
+        Ok(())
+    }
+}
+
+/// Turn the result of a hook into the request's fate
+fn handle(
+    chained: &ChainedPlugin,
+    hook: &'static str,
+    result: Result<(), PluginError>,
+) -> Result<(), RouterError> {
+    let name = chained.plugin.name();
+    let error = match result {
+        Ok(()) => return Ok(()),
+        Err(PluginError::Rejected(reason)) => {
+            debug!(
+                "Plugin {} rejected the request at {}: {}",
+                name, hook, reason
+            );
+            return Err(RouterError::PluginRejected(format!("{}: {}", name, reason)));
+        }
+        Err(error) => error,
+    };
+
+    counter!(catalog::ROUTING_PLUGIN_ERRORS, 1, "plugin" => name.to_string(), "hook" => hook);
+    match chained.on_error {
+        PluginFailurePolicy::Continue => {
+            warn!("Plugin {} failed at {}, skipping it: {}", name, hook, error);
+            Ok(())
+        }
+        PluginFailurePolicy::Reject => Err(RouterError::Other(format!(
+            "Plugin {} failed: {}",
+            name, error
+        ))),
+    }
+}
+
+/// Built-in plugin adding fixed entries to the routing metadata
+///
+/// Settings: `{"entries": {"cost_center": "emea"}}`.
+pub struct MetadataPlugin {
+    entries: HashMap<String, String>,
+}
+
+impl MetadataPlugin {
+    /// Name the plugin is registered under
+    pub const NAME: &'static str = "metadata";
+
+    /// Build the plugin from its settings
+    pub fn from_settings(settings: &Value) -> Result<Self, PluginError> {
+        let entries = match settings.get("entries") {
+            Some(entries) => serde_json::from_value(entries.clone()).map_err(|e| {
+                PluginError::InvalidSettings {
+                    plugin: Self::NAME.to_string(),
+                    message: e.to_string(),
+                }
+            })?,
+            None => HashMap::new(),
+        };
+        Ok(Self { entries })
+    }
+}
+
+#[async_trait]
+impl RouterPlugin for MetadataPlugin {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    async fn post_route(
+        &self,
+        _request: &RoutingRequest,
+        selection: &mut RouteSelection,
+    ) -> Result<(), PluginError> {
+        selection.metadata.additional_metadata.extend(
+            self.entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionRequest, ChatMessage, MessageRole,
+    };
+    use crate::modules::model_registry::ModelStatus;
+    use serde_json::json;
+
+    fn model(id: &str) -> ModelMetadata {
+        let mut model = ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "test".to_string(),
+            "1.0".to_string(),
+            "http://localhost".to_string(),
+        );
+        model.set_status(ModelStatus::Available);
+        model
+    }
+
+    fn request() -> RoutingRequest {
+        RoutingRequest::new(ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        })
+    }
+
+    fn routing_metadata(model_id: &str) -> RoutingMetadata {
+        RoutingMetadata {
+            selected_model_id: model_id.to_string(),
+            strategy_name: "test".to_string(),
+            routing_start_time: chrono::Utc::now(),
+            routing_end_time: chrono::Utc::now(),
+            routing_time_ms: 0,
+            models_considered: 1,
+            attempts: 1,
+            is_fallback: false,
+            selection_criteria: None,
+            additional_metadata: HashMap::new(),
+        }
+    }
+
+    /// Pins requests to a model and caps their tokens
+    struct Pin;
+
+    #[async_trait]
+    impl RouterPlugin for Pin {
+        fn name(&self) -> &str {
+            "pin"
+        }
+
+        async fn pre_route(&self, request: &mut RoutingRequest) -> Result<(), PluginError> {
+            request.context.request.max_tokens = Some(100);
+            Ok(())
+        }
+
+        async fn post_route(
+            &self,
+            request: &RoutingRequest,
+            selection: &mut RouteSelection,
+        ) -> Result<(), PluginError> {
+            match request.context.request.max_tokens {
+                Some(_) => {
+                    selection.model_id = "pinned".to_string();
+                    Ok(())
+                }
+                None => Err(PluginError::Rejected("no token cap".to_string())),
+            }
+        }
+    }
+
+    /// Fails at every hook
+    struct Broken;
+
+    #[async_trait]
+    impl RouterPlugin for Broken {
+        fn name(&self) -> &str {
+            "broken"
+        }
+
+        async fn pre_route(&self, _request: &mut RoutingRequest) -> Result<(), PluginError> {
+            Err(PluginError::Failed("boom".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plugin_chain() {
+        let registry = ModelRegistry::new();
+        registry.register_model(model("pinned")).unwrap();
+        let chain = PluginChain::new()
+            .with(Arc::new(Broken), PluginFailurePolicy::Continue)
+            .with(Arc::new(Pin), PluginFailurePolicy::Continue);
+        assert_eq!(chain.names(), vec!["broken", "pin"]);
+
+        // Failures are skipped; the request and selected model are changed
+        let mut request = request();
+        chain.pre_route(&mut request).await.unwrap();
+        assert_eq!(request.context.request.max_tokens, Some(100));
+        let (selected, metadata) = chain
+            .post_route(
+                &request,
+                model("gpt-4o"),
+                routing_metadata("gpt-4o"),
+                &registry,
+            )
+            .await
+            .unwrap();
+        assert_eq!(selected.id, "pinned");
+        assert_eq!(metadata.selected_model_id, "pinned");
+
+        // Rejections always reject
+        request.context.request.max_tokens = None;
+        assert!(matches!(
+            chain
+                .post_route(
+                    &request,
+                    model("gpt-4o"),
+                    routing_metadata("gpt-4o"),
+                    &registry
+                )
+                .await,
+            Err(RouterError::PluginRejected(_))
+        ));
+
+        // Unknown models are refused
+        let empty = ModelRegistry::new();
+        request.context.request.max_tokens = Some(1);
+        assert!(chain
+            .post_route(
+                &request,
+                model("gpt-4o"),
+                routing_metadata("gpt-4o"),
+                &empty
+            )
+            .await
+            .is_err());
+
+        // Failures reject the request when configured to
+        let strict = PluginChain::new().with(Arc::new(Broken), PluginFailurePolicy::Reject);
+        assert!(strict.pre_route(&mut request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_plugins_from_config() {
+        let configs: Vec<PluginConfig> = serde_json::from_value(json!([
+            {"name": "metadata", "settings": {"entries": {"cost_center": "emea"}}},
+            {"name": "metadata", "enabled": false},
+        ]))
+        .unwrap();
+        let chain = PluginChain::from_config(&PluginRegistry::new(), &configs).unwrap();
+        assert_eq!(chain.names(), vec!["metadata"]);
+
+        let registry = ModelRegistry::new();
+        let (_, metadata) = chain
+            .post_route(
+                &request(),
+                model("gpt-4o"),
+                routing_metadata("gpt-4o"),
+                &registry,
+            )
+            .await
+            .unwrap();
+        assert_eq!(metadata.additional_metadata["cost_center"], "emea");
+
+        let unknown: Vec<PluginConfig> = serde_json::from_value(json!([{"name": "wasm"}])).unwrap();
+        assert!(matches!(
+            PluginChain::from_config(&PluginRegistry::new(), &unknown),
+            Err(PluginError::Unknown(_))
+        ));
+        let invalid: Vec<PluginConfig> =
+            serde_json::from_value(json!([{"name": "metadata", "settings": {"entries": 1}}]))
+                .unwrap();
+        assert!(PluginChain::from_config(&PluginRegistry::new(), &invalid).is_err());
+    }
+}
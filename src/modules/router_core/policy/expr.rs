@@ -0,0 +1,749 @@
+//! Policy Expressions
+//!
+//! This module implements the small CEL-like expression language used by
+//! routing policies. Expressions are compiled once when a policy is published
+//! and evaluated against the attributes of each request.
+//!
+//! Supported syntax:
+//! - literals: `42`, `0.5`, `"text"`, `'text'`, `true`, `false`, `null`, `["a", "b"]`
+//! - attribute access: `request.tenant`, `time.hour`, `request.tags[0]`
+//! - operators: `!`, `-`, `+`, `*`, `/`, `<`, `<=`, `>`, `>=`, `==`, `!=`, `in`, `&&`, `||`
+//! - functions: `size(x)`, `x.size()`, `x.startsWith(s)`, `x.endsWith(s)`, `x.contains(s)`
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::PolicyError;
+
+/// Runtime value of a policy expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Missing value
+    Null,
+    /// Boolean
+    Bool(bool),
+    /// Integer
+    Int(i64),
+    /// Floating point number
+    Float(f64),
+    /// String
+    String(String),
+    /// List of values
+    List(Vec<Value>),
+    /// Map of named values
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Name of the value's type, for error messages
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Numeric value as a float, if the value is a number
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Equality with numeric coercion between ints and floats
+    fn loose_eq(&self, other: &Value) -> bool {
+        match (self.as_f64(), other.as_f64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Int(i) => write!(f, "{}", i),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::String(s) => write!(f, "{:?}", s),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(_) => write!(f, "{{...}}"),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Value::Null)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Value::List(value.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Binary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Unary operators
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Not,
+    Neg,
+}
+
+/// Compiled policy expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+impl Expr {
+    /// Parse an expression
+    pub fn parse(source: &str) -> Result<Self, PolicyError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let node = parser.parse_or()?;
+        match parser.peek() {
+            Token::End => Ok(Self(node)),
+            token => Err(PolicyError::Parse(format!(
+                "unexpected {} after end of expression",
+                token
+            ))),
+        }
+    }
+
+    /// Evaluate the expression against a set of attributes
+    pub fn evaluate(&self, attributes: &BTreeMap<String, Value>) -> Result<Value, PolicyError> {
+        self.0.evaluate(attributes)
+    }
+}
+
+/// Expression tree node
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Literal value
+    Literal(Value),
+    /// List literal
+    List(Vec<Node>),
+    /// Top-level attribute
+    Ident(String),
+    /// Field access
+    Member(Box<Node>, String),
+    /// Index access
+    Index(Box<Node>, Box<Node>),
+    /// Function or method call (the receiver is the first argument of a method)
+    Call(String, Vec<Node>),
+    /// Unary operation
+    Unary(UnaryOp, Box<Node>),
+    /// Binary operation
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    /// Evaluate the node against a set of attributes
+    fn evaluate(&self, attributes: &BTreeMap<String, Value>) -> Result<Value, PolicyError> {
+        match self {
+            Node::Literal(value) => Ok(value.clone()),
+            Node::List(items) => items
+                .iter()
+                .map(|item| item.evaluate(attributes))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            Node::Ident(name) => attributes
+                .get(name)
+                .cloned()
+                .ok_or_else(|| PolicyError::Evaluation(format!("unknown attribute '{}'", name))),
+            Node::Member(target, field) => match target.evaluate(attributes)? {
+                Value::Map(map) => map.get(field).cloned().ok_or_else(|| {
+                    PolicyError::Evaluation(format!("unknown attribute '{}'", field))
+                }),
+                other => Err(PolicyError::Evaluation(format!(
+                    "cannot access '{}' on {}",
+                    field,
+                    other.type_name()
+                ))),
+            },
+            Node::Index(target, index) => {
+                match (target.evaluate(attributes)?, index.evaluate(attributes)?) {
+                    (Value::List(items), Value::Int(i)) => Ok(usize::try_from(i)
+                        .ok()
+                        .and_then(|i| items.get(i).cloned())
+                        .unwrap_or(Value::Null)),
+                    (Value::Map(map), Value::String(key)) => {
+                        Ok(map.get(&key).cloned().unwrap_or(Value::Null))
+                    }
+                    (target, index) => Err(PolicyError::Evaluation(format!(
+                        "cannot index {} with {}",
+                        target.type_name(),
+                        index.type_name()
+                    ))),
+                }
+            }
+            Node::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(attributes))
+                    .collect::<Result<Vec<_>, _>>()?;
+                call_function(name, &args)
+            }
+            Node::Unary(op, operand) => match (op, operand.evaluate(attributes)?) {
+                (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+                (UnaryOp::Neg, Value::Int(i)) => Ok(Value::Int(-i)),
+                (UnaryOp::Neg, Value::Float(f)) => Ok(Value::Float(-f)),
+                (op, value) => Err(PolicyError::Evaluation(format!(
+                    "invalid operand {} for {:?}",
+                    value.type_name(),
+                    op
+                ))),
+            },
+            Node::Binary(BinaryOp::And, left, right) => {
+                if !expect_bool(left.evaluate(attributes)?)? {
+                    return Ok(Value::Bool(false));
+                }
+                Ok(Value::Bool(expect_bool(right.evaluate(attributes)?)?))
+            }
+            Node::Binary(BinaryOp::Or, left, right) => {
+                if expect_bool(left.evaluate(attributes)?)? {
+                    return Ok(Value::Bool(true));
+                }
+                Ok(Value::Bool(expect_bool(right.evaluate(attributes)?)?))
+            }
+            Node::Binary(op, left, right) => {
+                binary(*op, left.evaluate(attributes)?, right.evaluate(attributes)?)
+            }
+        }
+    }
+}
+
+/// Require a boolean value
+fn expect_bool(value: Value) -> Result<bool, PolicyError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(PolicyError::Evaluation(format!(
+            "expected bool, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Evaluate a non-logical binary operation
+fn binary(op: BinaryOp, left: Value, right: Value) -> Result<Value, PolicyError> {
+    let mismatch = |left: &Value, right: &Value| {
+        PolicyError::Evaluation(format!(
+            "invalid operands {} and {} for {:?}",
+            left.type_name(),
+            right.type_name(),
+            op
+        ))
+    };
+
+    match op {
+        BinaryOp::Eq => Ok(Value::Bool(left.loose_eq(&right))),
+        BinaryOp::Ne => Ok(Value::Bool(!left.loose_eq(&right))),
+        BinaryOp::In => match &right {
+            Value::List(items) => Ok(Value::Bool(items.iter().any(|item| item.loose_eq(&left)))),
+            Value::Map(map) => match &left {
+                Value::String(key) => Ok(Value::Bool(map.contains_key(key))),
+                _ => Err(mismatch(&left, &right)),
+            },
+            _ => Err(mismatch(&left, &right)),
+        },
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = match (&left, &right) {
+                (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+                _ => match (left.as_f64(), right.as_f64()) {
+                    (Some(a), Some(b)) => a.partial_cmp(&b),
+                    _ => None,
+                },
+            }
+            .ok_or_else(|| mismatch(&left, &right))?;
+
+            Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }))
+        }
+        BinaryOp::Add => match (&left, &right) {
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.saturating_add(*b))),
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            (Value::List(a), Value::List(b)) => Ok(Value::List([a.clone(), b.clone()].concat())),
+            _ => arithmetic(&left, &right, |a, b| a + b).ok_or_else(|| mismatch(&left, &right)),
+        },
+        BinaryOp::Sub => match (&left, &right) {
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.saturating_sub(*b))),
+            _ => arithmetic(&left, &right, |a, b| a - b).ok_or_else(|| mismatch(&left, &right)),
+        },
+        BinaryOp::Mul => match (&left, &right) {
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a.saturating_mul(*b))),
+            _ => arithmetic(&left, &right, |a, b| a * b).ok_or_else(|| mismatch(&left, &right)),
+        },
+        BinaryOp::Div => match (&left, &right) {
+            (_, Value::Int(0)) => Err(PolicyError::Evaluation("division by zero".to_string())),
+            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a / b)),
+            _ => arithmetic(&left, &right, |a, b| a / b).ok_or_else(|| mismatch(&left, &right)),
+        },
+        BinaryOp::And | BinaryOp::Or => unreachable!("logical operators short-circuit"),
+    }
+}
+
+/// Apply a floating point operation to two numbers
+fn arithmetic(left: &Value, right: &Value, op: impl Fn(f64, f64) -> f64) -> Option<Value> {
+    Some(Value::Float(op(left.as_f64()?, right.as_f64()?)))
+}
+
+/// Evaluate a built-in function
+fn call_function(name: &str, args: &[Value]) -> Result<Value, PolicyError> {
+    match (name, args) {
+        ("size", [Value::String(s)]) => Ok(Value::Int(s.chars().count() as i64)),
+        ("size", [Value::List(items)]) => Ok(Value::Int(items.len() as i64)),
+        ("size", [Value::Map(map)]) => Ok(Value::Int(map.len() as i64)),
+        ("startsWith", [Value::String(s), Value::String(prefix)]) => {
+            Ok(Value::Bool(s.starts_with(prefix.as_str())))
+        }
+        ("endsWith", [Value::String(s), Value::String(suffix)]) => {
+            Ok(Value::Bool(s.ends_with(suffix.as_str())))
+        }
+        ("contains", [Value::String(s), Value::String(part)]) => {
+            Ok(Value::Bool(s.contains(part.as_str())))
+        }
+        ("contains", [Value::List(items), item]) => {
+            Ok(Value::Bool(items.iter().any(|i| i.loose_eq(item))))
+        }
+        _ => Err(PolicyError::Evaluation(format!(
+            "no function '{}' for arguments ({})",
+            name,
+            args.iter()
+                .map(Value::type_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+/// Lexical tokens
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Ident(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Int(i) => write!(f, "'{}'", i),
+            Token::Float(x) => write!(f, "'{}'", x),
+            Token::String(s) => write!(f, "{:?}", s),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+            Token::End => write!(f, "end of expression"),
+        }
+    }
+}
+
+/// Operator and punctuation symbols, longest first
+const SYMBOLS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "(", ")", "[", "]", ",",
+    ".",
+];
+
+/// Split an expression into tokens
+fn tokenize(source: &str) -> Result<Vec<Token>, PolicyError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(if text.contains('.') {
+                Token::Float(
+                    text.parse()
+                        .map_err(|_| PolicyError::Parse(format!("invalid number '{}'", text)))?,
+                )
+            } else {
+                Token::Int(
+                    text.parse()
+                        .map_err(|_| PolicyError::Parse(format!("invalid number '{}'", text)))?,
+                )
+            });
+        } else if c == '"' || c == '\'' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(PolicyError::Parse("unterminated string".to_string())),
+                    Some('\\') => {
+                        text.push(*chars.get(i + 1).ok_or_else(|| {
+                            PolicyError::Parse("unterminated string".to_string())
+                        })?);
+                        i += 2;
+                    }
+                    Some(q) if *q == c => {
+                        i += 1;
+                        break;
+                    }
+                    Some(other) => {
+                        text.push(*other);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::String(text));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| PolicyError::Parse(format!("unexpected character '{}'", c)))?;
+            tokens.push(Token::Symbol(symbol));
+            i += symbol.len();
+        }
+    }
+
+    tokens.push(Token::End);
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over a token list
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Token::Symbol(s) if *s == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), PolicyError> {
+        if self.eat(symbol) {
+            Ok(())
+        } else {
+            Err(PolicyError::Parse(format!(
+                "expected '{}', found {}",
+                symbol,
+                self.peek()
+            )))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Node, PolicyError> {
+        let mut expr = self.parse_and()?;
+        while self.eat("||") {
+            expr = Node::Binary(BinaryOp::Or, Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, PolicyError> {
+        let mut expr = self.parse_relation()?;
+        while self.eat("&&") {
+            expr = Node::Binary(
+                BinaryOp::And,
+                Box::new(expr),
+                Box::new(self.parse_relation()?),
+            );
+        }
+        Ok(expr)
+    }
+
+    fn parse_relation(&mut self) -> Result<Node, PolicyError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Token::Symbol("==") => BinaryOp::Eq,
+            Token::Symbol("!=") => BinaryOp::Ne,
+            Token::Symbol("<") => BinaryOp::Lt,
+            Token::Symbol("<=") => BinaryOp::Le,
+            Token::Symbol(">") => BinaryOp::Gt,
+            Token::Symbol(">=") => BinaryOp::Ge,
+            Token::Ident(name) if name == "in" => BinaryOp::In,
+            _ => return Ok(left),
+        };
+        self.next();
+        let right = self.parse_additive()?;
+        Ok(Node::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Node, PolicyError> {
+        let mut expr = self.parse_multiplicative()?;
+        loop {
+            let op = if self.eat("+") {
+                BinaryOp::Add
+            } else if self.eat("-") {
+                BinaryOp::Sub
+            } else {
+                return Ok(expr);
+            };
+            expr = Node::Binary(op, Box::new(expr), Box::new(self.parse_multiplicative()?));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Node, PolicyError> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinaryOp::Mul
+            } else if self.eat("/") {
+                BinaryOp::Div
+            } else {
+                return Ok(expr);
+            };
+            expr = Node::Binary(op, Box::new(expr), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, PolicyError> {
+        if self.eat("!") {
+            Ok(Node::Unary(UnaryOp::Not, Box::new(self.parse_unary()?)))
+        } else if self.eat("-") {
+            Ok(Node::Unary(UnaryOp::Neg, Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_postfix()
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Node, PolicyError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat(".") {
+                let name = match self.next() {
+                    Token::Ident(name) => name,
+                    token => {
+                        return Err(PolicyError::Parse(format!(
+                            "expected attribute name after '.', found {}",
+                            token
+                        )))
+                    }
+                };
+                if self.eat("(") {
+                    let mut args = vec![expr];
+                    args.extend(self.parse_arguments()?);
+                    expr = Node::Call(name, args);
+                } else {
+                    expr = Node::Member(Box::new(expr), name);
+                }
+            } else if self.eat("[") {
+                let index = self.parse_or()?;
+                self.expect("]")?;
+                expr = Node::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// Parse call arguments after the opening parenthesis
+    fn parse_arguments(&mut self) -> Result<Vec<Node>, PolicyError> {
+        let mut args = Vec::new();
+        if self.eat(")") {
+            return Ok(args);
+        }
+        loop {
+            args.push(self.parse_or()?);
+            if self.eat(")") {
+                return Ok(args);
+            }
+            self.expect(",")?;
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, PolicyError> {
+        match self.next() {
+            Token::Int(i) => Ok(Node::Literal(Value::Int(i))),
+            Token::Float(f) => Ok(Node::Literal(Value::Float(f))),
+            Token::String(s) => Ok(Node::Literal(Value::String(s))),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Node::Literal(Value::Bool(true))),
+                "false" => Ok(Node::Literal(Value::Bool(false))),
+                "null" => Ok(Node::Literal(Value::Null)),
+                _ if self.eat("(") => Ok(Node::Call(name, self.parse_arguments()?)),
+                _ => Ok(Node::Ident(name)),
+            },
+            Token::Symbol("(") => {
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Symbol("[") => {
+                let mut items = Vec::new();
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.parse_or()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Node::List(items))
+            }
+            token => Err(PolicyError::Parse(format!("unexpected {}", token))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attributes() -> BTreeMap<String, Value> {
+        let mut request = BTreeMap::new();
+        request.insert("tenant".to_string(), Value::from("acme"));
+        request.insert("prompt_length".to_string(), Value::Int(1200));
+        request.insert(
+            "capabilities".to_string(),
+            Value::from(vec!["function_calling"]),
+        );
+        request.insert("model".to_string(), Value::from("gpt-4o"));
+
+        let mut attributes = BTreeMap::new();
+        attributes.insert("request".to_string(), Value::Map(request));
+        attributes
+    }
+
+    fn eval(source: &str) -> Result<Value, PolicyError> {
+        Expr::parse(source)?.evaluate(&attributes())
+    }
+
+    #[test]
+    fn test_evaluate_conditions() {
+        assert_eq!(
+            eval(r#"request.tenant == "acme" && request.prompt_length > 1000"#).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval(r#"'vision' in request.capabilities || request.model.startsWith('gpt-')"#)
+                .unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval("!(size(request.capabilities) >= 2)").unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval("request.prompt_length / 4 + 0.5").unwrap(),
+            Value::Float(300.5)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Expr::parse("request.tenant =="),
+            Err(PolicyError::Parse(_))
+        ));
+        assert!(matches!(
+            Expr::parse("request.tenant == 'acme"),
+            Err(PolicyError::Parse(_))
+        ));
+        assert!(matches!(Expr::parse("a b"), Err(PolicyError::Parse(_))));
+    }
+
+    #[test]
+    fn test_evaluation_errors() {
+        assert!(matches!(
+            eval("request.region == 'eu'"),
+            Err(PolicyError::Evaluation(_))
+        ));
+        assert!(matches!(
+            eval("request.tenant > 3"),
+            Err(PolicyError::Evaluation(_))
+        ));
+    }
+}
@@ -0,0 +1,608 @@
+//! Routing Policy Engine
+//!
+//! This module lets operators declare routing policies as ordered rules. Each
+//! rule has a condition written in a small CEL-like expression language over
+//! request attributes (tenant, prompt length, required capabilities, time of
+//! day) and yields a routing decision: the allowed models, a strategy override
+//! and a request priority. The first matching rule wins; the policy's default
+//! outcome applies when no rule matches.
+//!
+//! Published policies are versioned, so a previous version can be re-activated,
+//! and any policy can be evaluated in dry-run mode without being published.
+
+pub mod expr;
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::modules::llm_proxy::transform::model_matches;
+use crate::modules::model_registry::ModelRegistry;
+use crate::modules::router_core::request::RoutingRequest;
+use crate::modules::router_core::strategy::RoutingStrategy;
+
+pub use expr::{Expr, Value};
+
+/// Errors raised by the policy engine
+#[derive(Debug, Clone, Error)]
+pub enum PolicyError {
+    /// An expression could not be parsed
+    #[error("Policy parse error: {0}")]
+    Parse(String),
+
+    /// An expression could not be evaluated
+    #[error("Policy evaluation error: {0}")]
+    Evaluation(String),
+
+    /// A rule failed to compile
+    #[error("Invalid rule '{rule}': {source}")]
+    InvalidRule {
+        /// Rule name
+        rule: String,
+        /// Underlying error
+        source: Box<PolicyError>,
+    },
+
+    /// The requested policy version does not exist
+    #[error("Unknown policy version: {0}")]
+    UnknownVersion(u32),
+}
+
+/// Routing decision produced by a rule or a policy default
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PolicyOutcome {
+    /// Models the request may be routed to (a trailing `*` matches any suffix; empty allows all)
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// Routing strategy to use instead of the configured one
+    #[serde(default)]
+    pub strategy: Option<RoutingStrategy>,
+    /// Priority to assign to the request
+    #[serde(default)]
+    pub priority: Option<u8>,
+}
+
+/// A single policy rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    /// Rule name, reported in decisions
+    pub name: String,
+    /// Condition expression
+    pub when: String,
+    /// Decision applied when the condition holds
+    #[serde(flatten)]
+    pub outcome: PolicyOutcome,
+}
+
+/// Declarative routing policy
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingPolicy {
+    /// Policy name
+    pub name: String,
+    /// Rules, evaluated in order
+    #[serde(default)]
+    pub rules: Vec<PolicyRule>,
+    /// Decision applied when no rule matches
+    #[serde(default)]
+    pub default: PolicyOutcome,
+}
+
+/// Result of evaluating a policy for a request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    /// Policy name
+    pub policy: String,
+    /// Policy version (0 for dry-runs of unpublished policies)
+    pub version: u32,
+    /// Name of the matching rule, if any
+    pub rule: Option<String>,
+    /// Decision
+    #[serde(flatten)]
+    pub outcome: PolicyOutcome,
+}
+
+/// Evaluation trace of a single rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTrace {
+    /// Rule name
+    pub name: String,
+    /// Whether the condition held
+    pub matched: bool,
+    /// Evaluation error, if the condition could not be evaluated
+    pub error: Option<String>,
+}
+
+/// Detailed result of a dry-run evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyEvaluation {
+    /// Final decision
+    pub decision: PolicyDecision,
+    /// Per-rule trace, in evaluation order (stops at the first match)
+    pub rules: Vec<RuleTrace>,
+    /// Attributes the rules were evaluated against
+    pub attributes: serde_json::Value,
+}
+
+/// Information about a published policy version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyVersionInfo {
+    /// Version number
+    pub version: u32,
+    /// Policy name
+    pub name: String,
+    /// Publication time
+    pub published_at: DateTime<Utc>,
+    /// Whether this version is active
+    pub active: bool,
+}
+
+/// Request attributes available to policy expressions
+///
+/// Exposed to expressions as `request.*` and `time.*`.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyAttributes {
+    /// Requested model
+    pub model: String,
+    /// Tenant the request belongs to
+    pub tenant: Option<String>,
+    /// End user
+    pub user: Option<String>,
+    /// Prompt length in characters
+    pub prompt_length: usize,
+    /// Number of messages
+    pub message_count: usize,
+    /// Requested completion tokens
+    pub max_tokens: Option<u32>,
+    /// Capabilities the request needs (e.g. `function_calling`, `streaming`)
+    pub capabilities: Vec<String>,
+    /// Request tags
+    pub tags: Vec<String>,
+    /// Request priority
+    pub priority: u8,
+    /// Evaluation time
+    pub time: DateTime<Utc>,
+}
+
+impl PolicyAttributes {
+    /// Extract the attributes of a routing request
+    pub fn from_request(request: &RoutingRequest) -> Self {
+        let context = &request.context;
+        let chat = &context.request;
+
+        let mut capabilities = Vec::new();
+        if chat.functions.is_some() || chat.tools.is_some() {
+            capabilities.push("function_calling".to_string());
+        }
+        if chat.stream == Some(true) {
+            capabilities.push("streaming".to_string());
+        }
+
+        Self {
+            model: chat.model.clone(),
+            tenant: context.tenant().map(str::to_string),
+            user: context.user_id.clone(),
+            prompt_length: chat
+                .messages
+                .iter()
+                .map(|m| m.content.chars().count())
+                .sum(),
+            message_count: chat.messages.len(),
+            max_tokens: chat.max_tokens,
+            capabilities,
+            tags: context.tags.clone(),
+            priority: context.priority,
+            time: context.timestamp,
+        }
+    }
+
+    /// Convert the attributes into expression values
+    pub fn to_values(&self) -> BTreeMap<String, Value> {
+        let mut request = BTreeMap::new();
+        request.insert("model".to_string(), Value::from(self.model.clone()));
+        request.insert("tenant".to_string(), Value::from(self.tenant.clone()));
+        request.insert("user".to_string(), Value::from(self.user.clone()));
+        request.insert(
+            "prompt_length".to_string(),
+            Value::Int(self.prompt_length as i64),
+        );
+        request.insert(
+            "message_count".to_string(),
+            Value::Int(self.message_count as i64),
+        );
+        request.insert(
+            "max_tokens".to_string(),
+            Value::from(self.max_tokens.map(i64::from)),
+        );
+        request.insert(
+            "capabilities".to_string(),
+            Value::from(self.capabilities.clone()),
+        );
+        request.insert("tags".to_string(), Value::from(self.tags.clone()));
+        request.insert("priority".to_string(), Value::Int(self.priority as i64));
+
+        let mut time = BTreeMap::new();
+        time.insert("hour".to_string(), Value::Int(self.time.hour() as i64));
+        time.insert("minute".to_string(), Value::Int(self.time.minute() as i64));
+        time.insert(
+            "weekday".to_string(),
+            Value::from(self.time.weekday().to_string()),
+        );
+
+        let mut values = BTreeMap::new();
+        values.insert("request".to_string(), Value::Map(request));
+        values.insert("time".to_string(), Value::Map(time));
+        values
+    }
+
+    /// JSON view of the attributes, for dry-run output
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "request": {
+                "model": self.model,
+                "tenant": self.tenant,
+                "user": self.user,
+                "prompt_length": self.prompt_length,
+                "message_count": self.message_count,
+                "max_tokens": self.max_tokens,
+                "capabilities": self.capabilities,
+                "tags": self.tags,
+                "priority": self.priority,
+            },
+            "time": {
+                "hour": self.time.hour(),
+                "minute": self.time.minute(),
+                "weekday": self.time.weekday().to_string(),
+            },
+        })
+    }
+}
+
+/// Policy with pre-compiled rule conditions
+#[derive(Debug, Clone)]
+struct CompiledPolicy {
+    policy: RoutingPolicy,
+    conditions: Vec<Expr>,
+    version: u32,
+    published_at: DateTime<Utc>,
+}
+
+impl CompiledPolicy {
+    fn compile(policy: RoutingPolicy, version: u32) -> Result<Self, PolicyError> {
+        let conditions = policy
+            .rules
+            .iter()
+            .map(|rule| {
+                Expr::parse(&rule.when).map_err(|e| PolicyError::InvalidRule {
+                    rule: rule.name.clone(),
+                    source: Box::new(e),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            policy,
+            conditions,
+            version,
+            published_at: Utc::now(),
+        })
+    }
+
+    fn evaluate(&self, attributes: &PolicyAttributes) -> (PolicyDecision, Vec<RuleTrace>) {
+        let values = attributes.to_values();
+        let mut trace = Vec::new();
+
+        for (rule, condition) in self.policy.rules.iter().zip(&self.conditions) {
+            let (matched, error) = match condition.evaluate(&values) {
+                Ok(Value::Bool(matched)) => (matched, None),
+                Ok(other) => (false, Some(format!("condition yielded {}", other))),
+                Err(e) => (false, Some(e.to_string())),
+            };
+            if let Some(error) = &error {
+                warn!(
+                    "Policy '{}' rule '{}' treated as not matching: {}",
+                    self.policy.name, rule.name, error
+                );
+            }
+            trace.push(RuleTrace {
+                name: rule.name.clone(),
+                matched,
+                error,
+            });
+
+            if matched {
+                return (
+                    PolicyDecision {
+                        policy: self.policy.name.clone(),
+                        version: self.version,
+                        rule: Some(rule.name.clone()),
+                        outcome: rule.outcome.clone(),
+                    },
+                    trace,
+                );
+            }
+        }
+
+        (
+            PolicyDecision {
+                policy: self.policy.name.clone(),
+                version: self.version,
+                rule: None,
+                outcome: self.policy.default.clone(),
+            },
+            trace,
+        )
+    }
+}
+
+/// Versioned store and evaluator of routing policies
+#[derive(Debug, Default)]
+pub struct PolicyEngine {
+    /// Published versions, oldest first
+    versions: RwLock<Vec<CompiledPolicy>>,
+    /// Active version number
+    active: RwLock<Option<u32>>,
+}
+
+impl PolicyEngine {
+    /// Create an empty policy engine
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy engine with an initial policy published and active
+    pub fn with_policy(policy: RoutingPolicy) -> Result<Self, PolicyError> {
+        let engine = Self::new();
+        engine.publish(policy)?;
+        Ok(engine)
+    }
+
+    /// Compile, publish and activate a policy, returning its version
+    pub fn publish(&self, policy: RoutingPolicy) -> Result<u32, PolicyError> {
+        let mut versions = self.versions.write().unwrap();
+        let version = versions.len() as u32 + 1;
+        let compiled = CompiledPolicy::compile(policy, version)?;
+        info!(
+            "Published routing policy '{}' version {}",
+            compiled.policy.name, version
+        );
+        versions.push(compiled);
+        *self.active.write().unwrap() = Some(version);
+        Ok(version)
+    }
+
+    /// Activate a previously published version
+    pub fn activate(&self, version: u32) -> Result<(), PolicyError> {
+        if !self
+            .versions
+            .read()
+            .unwrap()
+            .iter()
+            .any(|p| p.version == version)
+        {
+            return Err(PolicyError::UnknownVersion(version));
+        }
+        *self.active.write().unwrap() = Some(version);
+        info!("Activated routing policy version {}", version);
+        Ok(())
+    }
+
+    /// Currently active version, if any
+    pub fn active_version(&self) -> Option<u32> {
+        *self.active.read().unwrap()
+    }
+
+    /// Currently active policy, if any
+    pub fn active_policy(&self) -> Option<RoutingPolicy> {
+        let active = self.active_version()?;
+        let versions = self.versions.read().unwrap();
+        versions
+            .iter()
+            .find(|p| p.version == active)
+            .map(|p| p.policy.clone())
+    }
+
+    /// List published versions
+    pub fn versions(&self) -> Vec<PolicyVersionInfo> {
+        let active = self.active_version();
+        self.versions
+            .read()
+            .unwrap()
+            .iter()
+            .map(|p| PolicyVersionInfo {
+                version: p.version,
+                name: p.policy.name.clone(),
+                published_at: p.published_at,
+                active: Some(p.version) == active,
+            })
+            .collect()
+    }
+
+    /// Evaluate the active policy, if any
+    pub fn evaluate(&self, attributes: &PolicyAttributes) -> Option<PolicyDecision> {
+        let active = self.active_version()?;
+        let versions = self.versions.read().unwrap();
+        let policy = versions.iter().find(|p| p.version == active)?;
+        let (decision, _) = policy.evaluate(attributes);
+        debug!(
+            "Policy '{}' v{} decision: rule={:?}",
+            decision.policy, decision.version, decision.rule
+        );
+        Some(decision)
+    }
+
+    /// Evaluate a policy without publishing it or affecting routing
+    ///
+    /// Evaluates `policy` if given (reported as version 0), otherwise the
+    /// active policy. Returns `None` when neither is available.
+    pub fn dry_run(
+        &self,
+        policy: Option<RoutingPolicy>,
+        attributes: &PolicyAttributes,
+    ) -> Result<Option<PolicyEvaluation>, PolicyError> {
+        let compiled = match policy {
+            Some(policy) => CompiledPolicy::compile(policy, 0)?,
+            None => {
+                let Some(active) = self.active_version() else {
+                    return Ok(None);
+                };
+                let versions = self.versions.read().unwrap();
+                match versions.iter().find(|p| p.version == active) {
+                    Some(policy) => policy.clone(),
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        let (decision, rules) = compiled.evaluate(attributes);
+        Ok(Some(PolicyEvaluation {
+            decision,
+            rules,
+            attributes: attributes.to_json(),
+        }))
+    }
+}
+
+/// Apply a policy decision to a routing request
+///
+/// Models outside the allow-list are excluded and the priority is overridden.
+/// The strategy override is left to the caller.
+pub fn apply_decision(
+    decision: &PolicyDecision,
+    request: &mut RoutingRequest,
+    registry: &ModelRegistry,
+) {
+    if !decision.outcome.allowed_models.is_empty() {
+        for model in registry.list_models() {
+            let allowed = decision
+                .outcome
+                .allowed_models
+                .iter()
+                .any(|pattern| model_matches(pattern, &model.id));
+            if !allowed && !request.excluded_model_ids.contains(&model.id) {
+                request.excluded_model_ids.push(model.id);
+            }
+        }
+        if let Some(preferred) = &request.preferred_model_id {
+            if request.excluded_model_ids.contains(preferred) {
+                debug!("Policy disallows preferred model {}", preferred);
+                request.preferred_model_id = None;
+            }
+        }
+    }
+
+    if let Some(priority) = decision.outcome.priority {
+        request.context.priority = priority;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RoutingPolicy {
+        RoutingPolicy {
+            name: "default".to_string(),
+            rules: vec![
+                PolicyRule {
+                    name: "long-prompts".to_string(),
+                    when: "request.prompt_length > 1000".to_string(),
+                    outcome: PolicyOutcome {
+                        allowed_models: vec!["claude-*".to_string()],
+                        strategy: Some(RoutingStrategy::CostOptimized),
+                        priority: None,
+                    },
+                },
+                PolicyRule {
+                    name: "acme".to_string(),
+                    when: "request.tenant == 'acme'".to_string(),
+                    outcome: PolicyOutcome {
+                        priority: Some(10),
+                        ..Default::default()
+                    },
+                },
+            ],
+            default: PolicyOutcome::default(),
+        }
+    }
+
+    fn attributes(tenant: &str, prompt_length: usize) -> PolicyAttributes {
+        PolicyAttributes {
+            model: "gpt-4o".to_string(),
+            tenant: Some(tenant.to_string()),
+            prompt_length,
+            time: Utc::now(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let engine = PolicyEngine::with_policy(policy()).unwrap();
+
+        let decision = engine.evaluate(&attributes("acme", 5000)).unwrap();
+        assert_eq!(decision.rule.as_deref(), Some("long-prompts"));
+        assert_eq!(
+            decision.outcome.strategy,
+            Some(RoutingStrategy::CostOptimized)
+        );
+
+        let decision = engine.evaluate(&attributes("acme", 10)).unwrap();
+        assert_eq!(decision.rule.as_deref(), Some("acme"));
+        assert_eq!(decision.outcome.priority, Some(10));
+
+        let decision = engine.evaluate(&attributes("other", 10)).unwrap();
+        assert_eq!(decision.rule, None);
+    }
+
+    #[test]
+    fn test_versioning() {
+        let engine = PolicyEngine::new();
+        assert!(engine.evaluate(&attributes("acme", 10)).is_none());
+
+        assert_eq!(engine.publish(policy()).unwrap(), 1);
+        let mut second = policy();
+        second.name = "second".to_string();
+        assert_eq!(engine.publish(second).unwrap(), 2);
+        assert_eq!(engine.active_version(), Some(2));
+
+        engine.activate(1).unwrap();
+        let decision = engine.evaluate(&attributes("acme", 10)).unwrap();
+        assert_eq!(decision.policy, "default");
+        assert!(matches!(
+            engine.activate(7),
+            Err(PolicyError::UnknownVersion(7))
+        ));
+    }
+
+    #[test]
+    fn test_invalid_rule_is_rejected() {
+        let mut invalid = policy();
+        invalid.rules[1].when = "request.tenant ==".to_string();
+
+        let engine = PolicyEngine::new();
+        assert!(matches!(
+            engine.publish(invalid),
+            Err(PolicyError::InvalidRule { .. })
+        ));
+        assert!(engine.versions().is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_reports_trace() {
+        let engine = PolicyEngine::new();
+        let evaluation = engine
+            .dry_run(Some(policy()), &attributes("acme", 10))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(evaluation.decision.version, 0);
+        assert_eq!(evaluation.rules.len(), 2);
+        assert!(!evaluation.rules[0].matched);
+        assert!(evaluation.rules[1].matched);
+        assert_eq!(engine.active_version(), None);
+    }
+}
@@ -0,0 +1,286 @@
+//! Data Residency
+//!
+//! This module enforces tenant-level data-residency constraints. A tenant can
+//! be restricted to a set of regions (e.g. `eu-*`), in which case its requests
+//! are only routed to models whose endpoints are hosted in those regions.
+//! Models without region metadata never satisfy a constraint. Every request
+//! rejected for residency reasons leaves an audit record.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::modules::llm_proxy::transform::model_matches;
+use crate::modules::model_registry::{ModelMetadata, ModelRegistry};
+use crate::modules::router_core::errors::RouterError;
+use crate::modules::router_core::request::RoutingRequest;
+
+/// Maximum number of audit records kept in memory
+const MAX_AUDIT_RECORDS: usize = 1000;
+
+/// Audit record of a request rejected for residency reasons
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResidencyAuditRecord {
+    /// Time of the rejection
+    pub timestamp: DateTime<Utc>,
+    /// Tenant whose constraint rejected the request
+    pub tenant: String,
+    /// Requested model
+    pub requested_model: String,
+    /// Model that was refused, if a specific model was selected
+    pub model_id: Option<String>,
+    /// Region of the refused model
+    pub model_region: Option<String>,
+    /// Regions the tenant is restricted to
+    pub allowed_regions: Vec<String>,
+    /// Reason for the rejection
+    pub reason: String,
+}
+
+/// Enforces per-tenant region constraints on routing
+#[derive(Debug, Default)]
+pub struct ResidencyEnforcer {
+    /// Allowed region patterns per tenant
+    tenants: HashMap<String, Vec<String>>,
+    /// Recent rejections, oldest first
+    audit: Mutex<VecDeque<ResidencyAuditRecord>>,
+}
+
+impl ResidencyEnforcer {
+    /// Create an enforcer without constraints
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict a tenant to a set of region patterns
+    pub fn with_tenant(mut self, tenant: impl Into<String>, regions: Vec<String>) -> Self {
+        if !regions.is_empty() {
+            self.tenants.insert(tenant.into(), regions);
+        }
+        self
+    }
+
+    /// Region patterns a tenant is restricted to, if any
+    pub fn allowed_regions(&self, tenant: &str) -> Option<&[String]> {
+        self.tenants.get(tenant).map(Vec::as_slice)
+    }
+
+    /// Check whether a model satisfies a set of region patterns
+    pub fn region_allowed(model: &ModelMetadata, regions: &[String]) -> bool {
+        model
+            .region
+            .as_deref()
+            .is_some_and(|region| regions.iter().any(|pattern| model_matches(pattern, region)))
+    }
+
+    /// Restrict a routing request to the models allowed for its tenant
+    ///
+    /// Returns an error, and records an audit entry, when no registered model
+    /// satisfies the tenant's constraint.
+    pub fn apply(
+        &self,
+        request: &mut RoutingRequest,
+        registry: &ModelRegistry,
+    ) -> Result<(), RouterError> {
+        let Some(tenant) = request.context.tenant().map(str::to_string) else {
+            return Ok(());
+        };
+        let Some(regions) = self.allowed_regions(&tenant) else {
+            return Ok(());
+        };
+
+        let mut allowed = 0;
+        for model in registry.list_models() {
+            if Self::region_allowed(&model, regions) {
+                allowed += 1;
+            } else if !request.excluded_model_ids.contains(&model.id) {
+                request.excluded_model_ids.push(model.id);
+            }
+        }
+
+        if let Some(preferred) = &request.preferred_model_id {
+            if request.excluded_model_ids.contains(preferred) {
+                debug!(
+                    "Preferred model {} is outside the regions allowed for tenant {}",
+                    preferred, tenant
+                );
+                request.preferred_model_id = None;
+            }
+        }
+
+        if allowed == 0 {
+            let reason = format!(
+                "no model is hosted in the regions allowed for tenant '{}' ({})",
+                tenant,
+                regions.join(", ")
+            );
+            self.record(ResidencyAuditRecord {
+                timestamp: Utc::now(),
+                tenant,
+                requested_model: request.context.request.model.clone(),
+                model_id: None,
+                model_region: None,
+                allowed_regions: regions.to_vec(),
+                reason: reason.clone(),
+            });
+            return Err(RouterError::DataResidency(reason));
+        }
+
+        Ok(())
+    }
+
+    /// Verify that a selected model satisfies the request tenant's constraint
+    pub fn check_model(
+        &self,
+        request: &RoutingRequest,
+        model: &ModelMetadata,
+    ) -> Result<(), RouterError> {
+        let Some(tenant) = request.context.tenant() else {
+            return Ok(());
+        };
+        let Some(regions) = self.allowed_regions(tenant) else {
+            return Ok(());
+        };
+        if Self::region_allowed(model, regions) {
+            return Ok(());
+        }
+
+        let reason = format!(
+            "model '{}' is hosted in {} which is not allowed for tenant '{}'",
+            model.id,
+            model.region.as_deref().unwrap_or("an unknown region"),
+            tenant
+        );
+        self.record(ResidencyAuditRecord {
+            timestamp: Utc::now(),
+            tenant: tenant.to_string(),
+            requested_model: request.context.request.model.clone(),
+            model_id: Some(model.id.clone()),
+            model_region: model.region.clone(),
+            allowed_regions: regions.to_vec(),
+            reason: reason.clone(),
+        });
+        Err(RouterError::DataResidency(reason))
+    }
+
+    /// Recent audit records, oldest first
+    pub fn audit_records(&self) -> Vec<ResidencyAuditRecord> {
+        self.audit.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Store an audit record
+    fn record(&self, record: ResidencyAuditRecord) {
+        warn!(
+            tenant = %record.tenant,
+            model = ?record.model_id,
+            region = ?record.model_region,
+            "Request rejected for data residency: {}",
+            record.reason
+        );
+
+        let mut audit = self.audit.lock().unwrap();
+        if audit.len() == MAX_AUDIT_RECORDS {
+            audit.pop_front();
+        }
+        audit.push_back(record);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionRequest, ChatMessage, MessageRole,
+    };
+    use crate::modules::model_registry::ModelStatus;
+
+    fn model(id: &str, region: Option<&str>) -> ModelMetadata {
+        let mut model = ModelMetadata::new(
+            id.to_string(),
+            id.to_string(),
+            "test".to_string(),
+            "1.0".to_string(),
+            "http://localhost".to_string(),
+        );
+        model.set_status(ModelStatus::Available);
+        model.set_region(region.map(str::to_string));
+        model
+    }
+
+    fn request(tenant: &str) -> RoutingRequest {
+        let mut request = RoutingRequest::new(ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "Hello".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        });
+        request.context = request.context.clone().with_tenant(tenant);
+        request
+    }
+
+    fn registry() -> ModelRegistry {
+        let registry = ModelRegistry::new();
+        registry
+            .register_model(model("eu-model", Some("eu-west-1")))
+            .unwrap();
+        registry
+            .register_model(model("us-model", Some("us-east-1")))
+            .unwrap();
+        registry
+            .register_model(model("unknown-model", None))
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_excludes_models_outside_allowed_regions() {
+        let enforcer = ResidencyEnforcer::new().with_tenant("acme", vec!["eu-*".to_string()]);
+        let registry = registry();
+
+        let mut req = request("acme");
+        enforcer.apply(&mut req, &registry).unwrap();
+        assert!(req.excluded_model_ids.contains(&"us-model".to_string()));
+        assert!(req
+            .excluded_model_ids
+            .contains(&"unknown-model".to_string()));
+        assert!(!req.excluded_model_ids.contains(&"eu-model".to_string()));
+
+        let mut unconstrained = request("other");
+        enforcer.apply(&mut unconstrained, &registry).unwrap();
+        assert!(unconstrained.excluded_model_ids.is_empty());
+    }
+
+    #[test]
+    fn test_rejection_is_audited() {
+        let enforcer = ResidencyEnforcer::new().with_tenant("acme", vec!["ap-*".to_string()]);
+        let registry = registry();
+
+        let mut req = request("acme");
+        let err = enforcer.apply(&mut req, &registry).unwrap_err();
+        assert!(matches!(err, RouterError::DataResidency(_)));
+
+        let err = enforcer
+            .check_model(&req, &model("us-model", Some("us-east-1")))
+            .unwrap_err();
+        assert!(matches!(err, RouterError::DataResidency(_)));
+
+        let records = enforcer.audit_records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].model_region.as_deref(), Some("us-east-1"));
+    }
+}
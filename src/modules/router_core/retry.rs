@@ -15,10 +15,7 @@ use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 use crate::modules::model_registry::{
-    connectors::{
-        ChatCompletionChoice, ChatCompletionResponse, ChatMessage,
-        MessageRole,
-    },
+    connectors::{ChatCompletionChoice, ChatCompletionResponse, ChatMessage, MessageRole},
     storage::ModelRegistry,
 };
 
@@ -72,6 +69,9 @@ impl RouterError {
             RouterError::InvalidRequest(_) => ErrorCategory::InvalidRequest,
             RouterError::Timeout(_) => ErrorCategory::Timeout,
             RouterError::FallbackError(_) => ErrorCategory::Other,
+            RouterError::DataResidency(_) => ErrorCategory::InvalidRequest,
+            RouterError::PluginRejected(_) => ErrorCategory::InvalidRequest,
+            RouterError::InvalidOutput(_) => ErrorCategory::Server,
             RouterError::Other(_) => ErrorCategory::Other,
             RouterError::SerializationError(_) => ErrorCategory::Other,
         }
@@ -582,8 +582,10 @@ impl DegradedServiceHandler {
                             name: None,
                             function_call: None,
                             tool_calls: None,
+                            tool_call_id: None,
                         },
                         finish_reason: Some("degraded_mode".to_string()),
+                        content_filter: None,
                     }],
                     usage: None,
                 };
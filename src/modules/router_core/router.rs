@@ -19,6 +19,11 @@ use tracing::{debug, info, warn};
 use crate::modules::model_registry::{storage::ModelRegistry, ModelMetadata};
 
 use super::{
+    language::LanguageSteering,
+    persona::PersonaPreferences,
+    plugins::PluginChain,
+    policy::{self, PolicyAttributes, PolicyEngine},
+    residency::ResidencyEnforcer,
     retry::{DegradedServiceHandler, RetryPolicy},
     strategies::{ContentBasedConfig, ContentBasedStrategy, RoundRobinConfig, RoundRobinStrategy},
     BaseStrategy, Router, RouterConfig, RouterError, RoutingMetadata, RoutingRequest,
@@ -46,6 +51,16 @@ pub struct RouterImpl {
     error_handler: ErrorHandler,
     /// Degraded service handler
     degraded_service_handler: DegradedServiceHandler,
+    /// Routing policy engine
+    policy_engine: Option<Arc<PolicyEngine>>,
+    /// Tenant data-residency enforcement
+    residency: Option<Arc<ResidencyEnforcer>>,
+    /// Model preferences and parameter defaults of personas
+    personas: Option<Arc<PersonaPreferences>>,
+    /// Language detection and steering
+    language: Option<Arc<LanguageSteering>>,
+    /// Plugins hooked into routing
+    plugins: Option<Arc<PluginChain>>,
 }
 
 impl RouterImpl {
@@ -86,6 +101,11 @@ impl RouterImpl {
             )),
             error_handler,
             degraded_service_handler,
+            policy_engine: None,
+            residency: None,
+            personas: None,
+            language: None,
+            plugins: None,
         };
 
         // Initialize with config
@@ -94,6 +114,165 @@ impl RouterImpl {
         Ok(router)
     }
 
+    /// Set the policy engine evaluated for every routed request
+    pub fn with_policy_engine(mut self, policy_engine: Arc<PolicyEngine>) -> Self {
+        self.policy_engine = Some(policy_engine);
+        self
+    }
+
+    /// Set the data-residency enforcer applied to every routed request
+    pub fn with_residency(mut self, residency: Arc<ResidencyEnforcer>) -> Self {
+        self.residency = Some(residency);
+        self
+    }
+
+    /// Set the persona preferences merged into requests with an active persona
+    pub fn with_personas(mut self, personas: Arc<PersonaPreferences>) -> Self {
+        self.personas = Some(personas);
+        self
+    }
+
+    /// Set the language steering applied to every routed request
+    pub fn with_language_steering(mut self, language: Arc<LanguageSteering>) -> Self {
+        self.language = language.is_enabled().then_some(language);
+        self
+    }
+
+    /// Set the plugins hooked into every routed request
+    pub fn with_plugins(mut self, plugins: Arc<PluginChain>) -> Self {
+        self.plugins = (!plugins.is_empty()).then_some(plugins);
+        self
+    }
+
+    /// Apply the active routing policy to a request
+    ///
+    /// Returns a strategy to use instead of the configured one, if the policy
+    /// selects a different strategy.
+    fn apply_policy(
+        &self,
+        request: &mut RoutingRequest,
+    ) -> Result<Option<Box<dyn RoutingStrategyTrait>>, RouterError> {
+        let Some(engine) = &self.policy_engine else {
+            return Ok(None);
+        };
+        let Some(decision) = engine.evaluate(&PolicyAttributes::from_request(request)) else {
+            return Ok(None);
+        };
+
+        debug!(
+            "Routing policy '{}' v{} matched rule {:?}",
+            decision.policy, decision.version, decision.rule
+        );
+        policy::apply_decision(&decision, request, &self.registry);
+
+        match decision.outcome.strategy {
+            Some(strategy) if strategy != self.config.strategy => self
+                .create_strategy(&strategy, &self.config.strategy_config)
+                .map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Select a model for a request and call it, trying fallbacks and
+    /// degraded service mode when the primary strategy fails
+    async fn route_request(
+        &self,
+        request: &mut RoutingRequest,
+        start_time: Instant,
+    ) -> Result<RoutingResponse, RouterError> {
+        // Apply the routing policy, which may restrict models or pick a strategy
+        let policy_strategy = self.apply_policy(request)?;
+        let strategy = policy_strategy.as_deref().unwrap_or(&*self.strategy);
+
+        // Restrict the request to the regions allowed for its tenant
+        if let Some(residency) = &self.residency {
+            residency.apply(request, &self.registry)?;
+        }
+
+        // Merge the active persona's preferred models and parameter defaults
+        if let Some(personas) = &self.personas {
+            personas.apply(request, &self.registry)?;
+        }
+
+        // Steer the request toward models supporting its language
+        if let Some(language) = &self.language {
+            language.apply(request, &self.registry)?;
+        }
+
+        // Check cache if enabled
+        if self.config.cache_routing_decisions {
+            let cache_key = self.generate_cache_key(request);
+            if let Some(model) = self
+                .get_from_cache(&cache_key)
+                .filter(|model| !request.excluded_model_ids.contains(&model.id))
+            {
+                debug!("Cache hit for request: {}", cache_key);
+
+                // Create metadata
+                let metadata = strategy.get_routing_metadata(&model, start_time, 0, false);
+
+                // Create response
+                let response = self.create_response(request, model, metadata).await?;
+
+                return Ok(response);
+            }
+        }
+
+        // Get filtered models based on request criteria
+        let filtered_models = self.get_filtered_models(request).await?;
+
+        // If no models are available, return an error
+        if filtered_models.is_empty() {
+            return Err(RouterError::NoSuitableModel(
+                "No suitable models found after filtering".to_string(),
+            ));
+        }
+
+        // Try primary strategy with retries
+        debug!("Trying primary strategy: {}", strategy.name());
+        let result = self
+            .try_strategy_with_retries(strategy, request, start_time, false)
+            .await;
+
+        // If primary strategy fails, try fallbacks
+        if let Err(error) = result {
+            warn!("Primary strategy failed: {}", error);
+
+            // Try fallback strategies
+            for (i, fallback) in self.fallback_strategies.iter().enumerate() {
+                debug!("Trying fallback strategy {}: {}", i + 1, fallback.name());
+                let fallback_result = self
+                    .try_strategy_with_retries(&**fallback, request, start_time, true)
+                    .await;
+
+                if fallback_result.is_ok() {
+                    info!("Fallback strategy {} succeeded", fallback.name());
+                    return fallback_result;
+                }
+
+                warn!("Fallback strategy {} failed", fallback.name());
+            }
+
+            // All strategies failed, try degraded service mode
+            info!("All strategies failed, trying degraded service mode");
+            let degraded_result = self.degraded_service_handler.handle_request(request).await;
+
+            // If degraded service mode fails, return the original error
+            if degraded_result.is_err() {
+                warn!("Degraded service mode failed");
+                return Err(RouterError::FallbackError(format!(
+                    "All strategies and degraded service mode failed. Original error: {}",
+                    error
+                )));
+            }
+
+            info!("Degraded service mode succeeded");
+            return degraded_result;
+        }
+
+        result
+    }
+
     /// Update the router with the latest model information from the registry
     pub async fn update_from_registry(&self) -> Result<(), RouterError> {
         let mut metrics = self.metrics.lock().unwrap();
@@ -223,6 +402,21 @@ impl RouterImpl {
         model: ModelMetadata,
         metadata: RoutingMetadata,
     ) -> Result<RoutingResponse, RouterError> {
+        // Plugins may change the selected model before it is called
+        let (model, metadata) = match &self.plugins {
+            Some(plugins) => {
+                plugins
+                    .post_route(request, model, metadata, &self.registry)
+                    .await?
+            }
+            None => (model, metadata),
+        };
+
+        // Never send a request outside the regions allowed for its tenant
+        if let Some(residency) = &self.residency {
+            residency.check_model(request, &model)?;
+        }
+
         // Get the model connector
         let connector = self.registry.get_connector(&model.id).ok_or_else(|| {
             RouterError::NoSuitableModel(format!("No connector found for model: {}", model.id))
@@ -271,6 +465,8 @@ impl RouterImpl {
         for message in &request.context.request.messages {
             message.content.hash(&mut hasher);
         }
+        // Personas prefer different models for the same messages
+        request.context.persona().hash(&mut hasher);
         format!("{:x}", hasher.finish())
     }
 
@@ -407,84 +603,33 @@ impl Router for RouterImpl {
         Ok(())
     }
 
-    async fn route(&self, request: RoutingRequest) -> Result<RoutingResponse, RouterError> {
+    async fn route(&self, mut request: RoutingRequest) -> Result<RoutingResponse, RouterError> {
         // Start timing
         let start_time = Instant::now();
 
         // Validate service health before handling request
         self.validate_service_health().await?;
 
-        // Check cache if enabled
-        if self.config.cache_routing_decisions {
-            let cache_key = self.generate_cache_key(&request);
-            if let Some(model) = self.get_from_cache(&cache_key) {
-                debug!("Cache hit for request: {}", cache_key);
-
-                // Create metadata
-                let metadata = self
-                    .strategy
-                    .get_routing_metadata(&model, start_time, 0, false);
-
-                // Create response
-                let response = self.create_response(&request, model, metadata).await?;
-
-                return Ok(response);
-            }
+        // Plugins see the request before any routing constraint is applied
+        if let Some(plugins) = &self.plugins {
+            plugins.pre_route(&mut request).await?;
         }
 
-        // Get filtered models based on request criteria
-        let filtered_models = self.get_filtered_models(&request).await?;
+        let mut response = self.route_request(&mut request, start_time).await?;
 
-        // If no models are available, return an error
-        if filtered_models.is_empty() {
-            return Err(RouterError::NoSuitableModel(
-                "No suitable models found after filtering".to_string(),
-            ));
+        // Report the language of the request, if set or detected
+        if let Some(language) = request.context.language() {
+            response
+                .metadata
+                .additional_metadata
+                .insert("language".to_string(), language.to_string());
         }
 
-        // Try primary strategy with retries
-        debug!("Trying primary strategy: {}", self.strategy.name());
-        let result = self
-            .try_strategy_with_retries(&*self.strategy, &request, start_time, false)
-            .await;
-
-        // If primary strategy fails, try fallbacks
-        if let Err(error) = result {
-            warn!("Primary strategy failed: {}", error);
-
-            // Try fallback strategies
-            for (i, fallback) in self.fallback_strategies.iter().enumerate() {
-                debug!("Trying fallback strategy {}: {}", i + 1, fallback.name());
-                let fallback_result = self
-                    .try_strategy_with_retries(&**fallback, &request, start_time, true)
-                    .await;
-
-                if fallback_result.is_ok() {
-                    info!("Fallback strategy {} succeeded", fallback.name());
-                    return fallback_result;
-                }
-
-                warn!("Fallback strategy {} failed", fallback.name());
-            }
-
-            // All strategies failed, try degraded service mode
-            info!("All strategies failed, trying degraded service mode");
-            let degraded_result = self.degraded_service_handler.handle_request(&request).await;
-
-            // If degraded service mode fails, return the original error
-            if degraded_result.is_err() {
-                warn!("Degraded service mode failed");
-                return Err(RouterError::FallbackError(format!(
-                    "All strategies and degraded service mode failed. Original error: {}",
-                    error
-                )));
-            }
-
-            info!("Degraded service mode succeeded");
-            return degraded_result;
+        // Plugins see the response before it is returned
+        if let Some(plugins) = &self.plugins {
+            plugins.pre_response(&request, &mut response).await?;
         }
-
-        result
+        Ok(response)
     }
 
     fn get_config(&self) -> &RouterConfig {
@@ -578,6 +723,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
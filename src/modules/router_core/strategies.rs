@@ -22,8 +22,7 @@ pub use round_robin::{RoundRobinConfig, RoundRobinStrategy};
 use tracing::{debug, info, warn};
 
 use crate::modules::model_registry::{
-    storage::ModelRegistry,
-    ModelMetadata, ModelStatus,
+    global_tokenizers, storage::ModelRegistry, ModelMetadata, ModelStatus,
 };
 
 use super::{RouterError, RoutingMetadata, RoutingRequest, RoutingStrategy, RoutingStrategyTrait};
@@ -127,7 +126,7 @@ impl BaseStrategy {
         }
 
         // Check context length requirements
-        let estimated_tokens = self.estimate_token_count(request);
+        let estimated_tokens = self.estimate_token_count(request, &model.id);
         if estimated_tokens > model.capabilities.max_context_length {
             return false;
         }
@@ -165,27 +164,25 @@ impl BaseStrategy {
         true
     }
 
-    /// Estimate token count for a request
-    fn estimate_token_count(&self, request: &RoutingRequest) -> usize {
-        // Simple estimation based on message content lengths
-        // In a real implementation, this would use a proper tokenizer
+    /// Estimate token count for a request with the tokenizer of a model
+    fn estimate_token_count(&self, request: &RoutingRequest, model_id: &str) -> usize {
+        let tokenizer = global_tokenizers().for_model(model_id);
         let mut total_tokens = 0;
 
         for message in &request.context.request.messages {
-            // Rough estimate: 1 token per 4 characters
-            total_tokens += message.content.len() / 4;
+            total_tokens += tokenizer.count(&message.content);
 
             // Add tokens for function calls if present
             if let Some(func_call) = &message.function_call {
-                total_tokens += func_call.name.len() / 4;
-                total_tokens += func_call.arguments.len() / 4;
+                total_tokens += tokenizer.count(&func_call.name);
+                total_tokens += tokenizer.count(&func_call.arguments);
             }
 
             // Add tokens for tool calls if present
             if let Some(tool_calls) = &message.tool_calls {
                 for tool_call in tool_calls {
-                    total_tokens += tool_call.function.name.len() / 4;
-                    total_tokens += tool_call.function.arguments.len() / 4;
+                    total_tokens += tokenizer.count(&tool_call.function.name);
+                    total_tokens += tokenizer.count(&tool_call.function.arguments);
                 }
             }
         }
@@ -349,6 +346,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: None,
             top_p: None,
@@ -26,6 +26,7 @@ fn create_test_request() -> RoutingRequest {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
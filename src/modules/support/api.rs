@@ -0,0 +1,61 @@
+//! Support API
+//!
+//! This module serves the support snapshot of a role over HTTP. It holds
+//! the redacted configuration, so reading it needs the operator admin role.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use utoipa::OpenApi;
+
+use super::SupportService;
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{self, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// State of the support API
+struct SupportApiState {
+    service: Arc<SupportService>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// OpenAPI description of the support API
+#[derive(OpenApi)]
+#[openapi(paths(support_snapshot))]
+pub struct SupportApiDoc;
+
+/// Create a router for the support API
+pub fn create_support_router(service: Arc<SupportService>, proxy: ProxyConfig) -> Router {
+    let state = Arc::new(SupportApiState { service, proxy });
+    Router::new()
+        .route("/v1/admin/support", get(support_snapshot))
+        .with_state(state)
+}
+
+/// Snapshot of the role for a support bundle
+#[utoipa::path(
+    get,
+    path = "/v1/admin/support",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Build, redacted configuration, error samples and runtime stats of the role", body = Object),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError)
+    )
+)]
+async fn support_snapshot(
+    State(state): State<Arc<SupportApiState>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    Json(state.service.snapshot()).into_response()
+}
@@ -0,0 +1,204 @@
+//! Support Module
+//!
+//! This module gathers what a bug report about a running deployment needs:
+//! the build, the effective configuration with its secrets redacted, the
+//! latest warnings and errors logged, and the runtime state of the role's
+//! caches and breakers. The snapshot is served to operators at
+//! `/v1/admin/support`, and `intellirouter support-bundle` packs it into a
+//! tarball along with the role's diagnostics.
+
+pub mod api;
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::modules::health::{config_fingerprint, BuildInfo};
+use crate::modules::llm_proxy::decision_log::REDACTED;
+use crate::modules::telemetry::logging::{self, LogSample};
+
+/// Last words of configuration field names holding secrets, e.g. `api_key`
+/// or `jwt_secret`
+const SECRET_FIELDS: &[&str] = &[
+    "key",
+    "keys",
+    "secret",
+    "secrets",
+    "password",
+    "token",
+    "credentials",
+    "authorization",
+];
+
+/// Snapshot of a running role for a support bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupportSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub build: BuildInfo,
+    /// SHA-256 of the effective configuration, before redaction
+    pub config_fingerprint: String,
+    /// Effective configuration with its secrets redacted
+    pub config: Value,
+    /// Latest warnings and errors logged by the process
+    pub error_samples: Vec<LogSample>,
+    /// Runtime state of the role's caches and breakers, by component
+    pub stats: BTreeMap<String, Value>,
+}
+
+/// Source of the runtime state of a component
+type StatsSource = Box<dyn Fn() -> Value + Send + Sync>;
+
+/// Snapshots of a running role
+pub struct SupportService {
+    config: Value,
+    config_fingerprint: String,
+    stats: Vec<(String, StatsSource)>,
+}
+
+impl SupportService {
+    /// Create a service reporting an effective configuration
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: redact_config(config),
+            config_fingerprint: config_fingerprint(config),
+            stats: Vec::new(),
+        }
+    }
+
+    /// Report the runtime state of a component under a name
+    pub fn with_stats(
+        mut self,
+        name: impl Into<String>,
+        stats: impl Fn() -> Value + Send + Sync + 'static,
+    ) -> Self {
+        self.stats.push((name.into(), Box::new(stats)));
+        self
+    }
+
+    /// Snapshot of the role now
+    pub fn snapshot(&self) -> SupportSnapshot {
+        SupportSnapshot {
+            generated_at: Utc::now(),
+            build: BuildInfo::current(),
+            config_fingerprint: self.config_fingerprint.clone(),
+            config: self.config.clone(),
+            error_samples: logging::recent_errors(),
+            stats: self
+                .stats
+                .iter()
+                .map(|(name, stats)| (name.clone(), stats()))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Debug for SupportService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SupportService")
+            .field("config_fingerprint", &self.config_fingerprint)
+            .field(
+                "stats",
+                &self.stats.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// Effective configuration as JSON, with its secrets redacted
+///
+/// Every string under a field whose name ends with a secret word, such as
+/// `api_key`, `admin_keys` or `smtp_password`, is replaced, as is the
+/// password of any URL, e.g. that of `redis://:password@host`.
+pub fn redact_config(config: &Config) -> Value {
+    let mut value = serde_json::to_value(config).unwrap_or(Value::Null);
+    redact(&mut value);
+    value
+}
+
+/// Redact the secrets of a JSON value, the way [`redact_config`] does
+pub fn redact(value: &mut Value) {
+    redact_within(value, false);
+}
+
+/// Redact the strings of a JSON value, all of them if it is a secret
+fn redact_within(value: &mut Value, secret: bool) {
+    match value {
+        Value::String(s) if secret => *s = REDACTED.to_string(),
+        Value::String(s) => {
+            if let Some(url) = redact_url_password(s) {
+                *s = url;
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| redact_within(value, secret)),
+        Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                redact_within(value, secret || is_secret_field(name));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether the last word of a field name is a secret word
+fn is_secret_field(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    let last = name.rsplit(['_', '-']).next().unwrap_or_default();
+    SECRET_FIELDS.contains(&last)
+}
+
+/// A URL with its password redacted, if it has one
+fn redact_url_password(s: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse(s).ok()?;
+    url.password()?;
+    url.set_password(Some(REDACTED)).ok()?;
+    Some(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact() {
+        let mut value = json!({
+            "memory": {
+                "redis_url": "redis://:hunter2@redis:6379/0",
+                "key_prefix": "intellirouter",
+            },
+            "proxy": {
+                "admin_keys": [{ "key": "sk-admin", "role": "admin" }],
+                "max_tokens": 4096,
+            },
+            "auth": { "jwt_secret": "s3cret", "api_key_env": "OPENAI_API_KEY" },
+        });
+        redact(&mut value);
+
+        assert_eq!(
+            value["memory"]["redis_url"],
+            "redis://:%5BREDACTED%5D@redis:6379/0"
+        );
+        assert_eq!(value["memory"]["key_prefix"], "intellirouter");
+        assert_eq!(value["proxy"]["admin_keys"][0]["key"], REDACTED);
+        assert_eq!(value["proxy"]["admin_keys"][0]["role"], REDACTED);
+        assert_eq!(value["proxy"]["max_tokens"], 4096);
+        assert_eq!(value["auth"]["jwt_secret"], REDACTED);
+        assert_eq!(value["auth"]["api_key_env"], "OPENAI_API_KEY");
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let support = SupportService::new(&Config::default())
+            .with_stats("coalescing", || json!({ "in_flight": 0 }));
+        let snapshot = support.snapshot();
+
+        assert_eq!(snapshot.config_fingerprint.len(), 64);
+        assert_eq!(snapshot.stats["coalescing"]["in_flight"], 0);
+        assert!(snapshot.config.is_object());
+    }
+}
@@ -0,0 +1,584 @@
+//! Anomaly Detection
+//!
+//! This module flags unusual spikes in the traffic and cost of each tenant.
+//! Requests are aggregated into fixed windows; at the end of each window the
+//! error rate, mean latency, token consumption and spend of every tenant are
+//! compared with an exponentially weighted moving average (EWMA) of their
+//! past values. A value more than `z_threshold` standard deviations above the
+//! average starts an anomaly, which lasts until a window is back within the
+//! threshold.
+//!
+//! With `per_user` enabled, the signals of each end user, as named by the
+//! `user` field of requests, are scored the same way within their tenant.
+//! Only tenant anomalies are exported as metrics, so the number of end users
+//! doesn't inflate the metric labels.
+//!
+//! Anomalies are exposed as metrics (charted and annotated on the generated
+//! dashboard), kept for the admin API and POSTed to the configured alert
+//! webhooks when they start and end.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::catalog;
+use super::TelemetryRecord;
+use crate::config::{AlertWebhookConfig, AnomalyDetectionConfig};
+use crate::modules::chain_engine::webhooks::{
+    sign, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+
+/// Tenant of requests without a tenant ID
+const DEFAULT_TENANT: &str = "default";
+
+/// Delay before the first retry of a failed alert, doubled on each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Signal watched for anomalies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signal {
+    /// Fraction of failed requests
+    ErrorRate,
+    /// Mean request latency in milliseconds
+    Latency,
+    /// Tokens consumed
+    Tokens,
+    /// Estimated spend in USD
+    Spend,
+}
+
+impl Signal {
+    const ALL: [Signal; 4] = [
+        Signal::ErrorRate,
+        Signal::Latency,
+        Signal::Tokens,
+        Signal::Spend,
+    ];
+
+    /// Name of the signal, as used in metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Signal::ErrorRate => "error_rate",
+            Signal::Latency => "latency",
+            Signal::Tokens => "tokens",
+            Signal::Spend => "spend",
+        }
+    }
+
+    /// Smallest standard deviation a value is scored against, so a perfectly
+    /// steady baseline doesn't turn the slightest change into an anomaly
+    fn min_deviation(&self) -> f64 {
+        match self {
+            Signal::ErrorRate => 0.02,
+            Signal::Latency => 10.0,
+            Signal::Tokens => 10.0,
+            Signal::Spend => 0.001,
+        }
+    }
+}
+
+/// Unusual value of a tenant's or end user's signal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub id: String,
+    pub tenant: String,
+    /// End user of the tenant, for anomalies of a single user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    pub signal: Signal,
+    /// Highest value observed during the anomaly
+    pub value: f64,
+    /// Moving average of the signal when the anomaly started
+    pub expected: f64,
+    /// Highest z-score observed during the anomaly
+    pub z_score: f64,
+    /// Start of the first anomalous window
+    pub started_at: DateTime<Utc>,
+    /// End of the last anomalous window, once the signal is back to normal
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+/// Change in the anomalies of a tenant
+#[derive(Debug, Clone)]
+pub enum AnomalyEvent {
+    Started(Anomaly),
+    Resolved(Anomaly),
+}
+
+impl AnomalyEvent {
+    /// Name of the event, as sent to alert webhooks
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnomalyEvent::Started(_) => "anomaly.started",
+            AnomalyEvent::Resolved(_) => "anomaly.resolved",
+        }
+    }
+
+    pub fn anomaly(&self) -> &Anomaly {
+        match self {
+            AnomalyEvent::Started(anomaly) | AnomalyEvent::Resolved(anomaly) => anomaly,
+        }
+    }
+}
+
+/// Requests of a tenant in the current window
+#[derive(Debug, Default)]
+struct Window {
+    requests: u64,
+    errors: u64,
+    latency_ms: u64,
+    tokens: u64,
+    spend: f64,
+}
+
+impl Window {
+    /// Value of a signal, or `None` when the window is too small to tell
+    fn value(&self, signal: Signal, min_requests: u64) -> Option<f64> {
+        let enough = self.requests > 0 && self.requests >= min_requests;
+        match signal {
+            Signal::ErrorRate if enough => Some(self.errors as f64 / self.requests as f64),
+            Signal::Latency if enough => Some(self.latency_ms as f64 / self.requests as f64),
+            Signal::ErrorRate | Signal::Latency => None,
+            Signal::Tokens => Some(self.tokens as f64),
+            Signal::Spend => Some(self.spend),
+        }
+    }
+}
+
+/// Exponentially weighted mean and variance of a signal
+#[derive(Debug, Default, Clone, Copy)]
+struct Baseline {
+    mean: f64,
+    variance: f64,
+    windows: u32,
+}
+
+impl Baseline {
+    /// Fold a window's value into the baseline
+    fn update(&mut self, value: f64, alpha: f64) {
+        if self.windows == 0 {
+            self.mean = value;
+        } else {
+            let diff = value - self.mean;
+            let increment = alpha * diff;
+            self.mean += increment;
+            self.variance = (1.0 - alpha) * (self.variance + diff * increment);
+        }
+        self.windows = self.windows.saturating_add(1);
+    }
+
+    /// Standard deviations of a value above the mean
+    fn z_score(&self, value: f64, min_deviation: f64) -> f64 {
+        (value - self.mean) / self.variance.sqrt().max(min_deviation)
+    }
+}
+
+/// Detection state of a tenant or end user
+#[derive(Debug, Default)]
+struct TenantState {
+    window: Window,
+    baselines: HashMap<Signal, Baseline>,
+    active: HashMap<Signal, Anomaly>,
+}
+
+#[derive(Debug)]
+struct DetectorState {
+    window_start: DateTime<Utc>,
+    /// State by tenant, and end user for the signals of a single user
+    tenants: HashMap<(String, Option<String>), TenantState>,
+    /// Resolved anomalies, oldest first
+    history: VecDeque<Anomaly>,
+}
+
+/// Detector of anomalies in per-tenant traffic and cost
+#[derive(Debug)]
+pub struct AnomalyDetector {
+    config: AnomalyDetectionConfig,
+    state: Mutex<DetectorState>,
+    client: Client,
+}
+
+impl AnomalyDetector {
+    /// Create a detector and start closing its windows in the background
+    ///
+    /// Returns `None` when anomaly detection is disabled.
+    pub fn from_config(config: &AnomalyDetectionConfig) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+        let detector = Arc::new(Self::new(config.clone()));
+        detector.clone().spawn();
+        Some(detector)
+    }
+
+    /// Create a detector whose first window starts now
+    pub fn new(config: AnomalyDetectionConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(DetectorState {
+                window_start: Utc::now(),
+                tenants: HashMap::new(),
+                history: VecDeque::new(),
+            }),
+            client: Client::new(),
+        }
+    }
+
+    /// Count a completed request in its tenant's current window, and its end
+    /// user's when users are checked
+    pub fn observe(&self, record: &TelemetryRecord) {
+        let tenant = record.tenant.as_deref().unwrap_or(DEFAULT_TENANT);
+        let user = record.user.as_ref().filter(|_| self.config.per_user);
+        let mut state = self.state.lock().unwrap();
+        for subject in std::iter::once(None).chain(user.map(Some)) {
+            let window = &mut state
+                .tenants
+                .entry((tenant.to_string(), subject.cloned()))
+                .or_default()
+                .window;
+            window.requests += 1;
+            if !record.success {
+                window.errors += 1;
+            }
+            window.latency_ms += record.latency_ms;
+            window.tokens += u64::from(record.total_tokens);
+            window.spend += record.cost_usd.unwrap_or(0.0);
+        }
+    }
+
+    /// Close the current window, scoring every tenant's signals against their
+    /// baselines, and start the next one
+    pub fn close_window(&self, now: DateTime<Utc>) -> Vec<AnomalyEvent> {
+        let mut events = Vec::new();
+        let mut state = self.state.lock().unwrap();
+        let window_start = std::mem::replace(&mut state.window_start, now);
+        let DetectorState {
+            tenants, history, ..
+        } = &mut *state;
+
+        for ((tenant, user), tenant_state) in tenants.iter_mut() {
+            let subject = match user {
+                Some(user) => format!("user {} of tenant {}", user, tenant),
+                None => format!("tenant {}", tenant),
+            };
+            let window = std::mem::take(&mut tenant_state.window);
+            for signal in Signal::ALL {
+                let Some(value) = window.value(signal, self.config.min_requests) else {
+                    continue;
+                };
+                let baseline = tenant_state.baselines.entry(signal).or_default();
+                let z_score = baseline.z_score(value, signal.min_deviation());
+                let anomalous = baseline.windows >= self.config.warmup_windows
+                    && z_score > self.config.z_threshold;
+                let expected = baseline.mean;
+                baseline.update(value, self.config.alpha);
+
+                match (anomalous, tenant_state.active.get_mut(&signal)) {
+                    (true, Some(anomaly)) => {
+                        anomaly.value = anomaly.value.max(value);
+                        anomaly.z_score = anomaly.z_score.max(z_score);
+                    }
+                    (true, None) => {
+                        let anomaly = Anomaly {
+                            id: Uuid::new_v4().to_string(),
+                            tenant: tenant.clone(),
+                            user: user.clone(),
+                            signal,
+                            value,
+                            expected,
+                            z_score,
+                            started_at: window_start,
+                            ended_at: None,
+                        };
+                        info!(
+                            "Anomaly in {} of {}: {:.3} (expected {:.3}, z = {:.1})",
+                            signal.as_str(),
+                            subject,
+                            value,
+                            expected,
+                            z_score
+                        );
+                        if user.is_none() {
+                            counter!(
+                                catalog::ANOMALIES_DETECTED, 1,
+                                "tenant" => tenant.clone(), "signal" => signal.as_str()
+                            );
+                            gauge!(
+                                catalog::ANOMALIES_ACTIVE, 1.0,
+                                "tenant" => tenant.clone(), "signal" => signal.as_str()
+                            );
+                        }
+                        tenant_state.active.insert(signal, anomaly.clone());
+                        events.push(AnomalyEvent::Started(anomaly));
+                    }
+                    (false, Some(_)) => {
+                        let mut anomaly = tenant_state.active.remove(&signal).unwrap();
+                        anomaly.ended_at = Some(window_start);
+                        info!("Anomaly in {} of {} resolved", signal.as_str(), subject);
+                        if user.is_none() {
+                            gauge!(
+                                catalog::ANOMALIES_ACTIVE, 0.0,
+                                "tenant" => tenant.clone(), "signal" => signal.as_str()
+                            );
+                        }
+                        if history.len() >= self.config.history_capacity {
+                            history.pop_front();
+                        }
+                        if self.config.history_capacity > 0 {
+                            history.push_back(anomaly.clone());
+                        }
+                        events.push(AnomalyEvent::Resolved(anomaly));
+                    }
+                    (false, None) => {}
+                }
+            }
+        }
+        events
+    }
+
+    /// Ongoing anomalies followed by resolved ones, newest first
+    pub fn recent(&self) -> Vec<Anomaly> {
+        let state = self.state.lock().unwrap();
+        let mut active: Vec<Anomaly> = state
+            .tenants
+            .values()
+            .flat_map(|tenant| tenant.active.values().cloned())
+            .collect();
+        active.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        active.extend(state.history.iter().rev().cloned());
+        active
+    }
+
+    /// Close a window every `window_secs` and send the alerts it raises
+    fn spawn(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.window_secs.max(1));
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                for event in self.close_window(Utc::now()) {
+                    futures::future::join_all(
+                        self.config
+                            .webhooks
+                            .iter()
+                            .map(|webhook| self.alert(webhook, &event)),
+                    )
+                    .await;
+                }
+            }
+        });
+    }
+
+    /// Deliver an alert to a webhook
+    async fn alert(&self, webhook: &AlertWebhookConfig, event: &AnomalyEvent) {
+        let payload = json!({ "event": event.name(), "anomaly": event.anomaly() }).to_string();
+        deliver_alert(
+            &self.client,
+            webhook,
+            event.name(),
+            payload,
+            self.config.webhook_retries,
+        )
+        .await;
+    }
+}
+
+/// Deliver an alert to a webhook, retrying failures with exponential backoff
+pub(crate) async fn deliver_alert(
+    client: &Client,
+    webhook: &AlertWebhookConfig,
+    event: &str,
+    payload: String,
+    retries: u32,
+) {
+    let delivery_id = Uuid::new_v4().to_string();
+
+    let mut attempt = 0;
+    loop {
+        let mut request = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, &delivery_id)
+            .body(payload.clone());
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, payload.as_bytes()));
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => response.status().to_string(),
+            Err(e) => e.to_string(),
+        };
+        if attempt >= retries {
+            warn!(
+                "Dropping {} alert {} to {}: {}",
+                event, delivery_id, webhook.url, error
+            );
+            return;
+        }
+        debug!("Alert to {} failed, retrying: {}", webhook.url, error);
+        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::telemetry::CacheStatus;
+
+    fn config() -> AnomalyDetectionConfig {
+        AnomalyDetectionConfig {
+            enabled: true,
+            warmup_windows: 5,
+            min_requests: 5,
+            ..Default::default()
+        }
+    }
+
+    fn record(tenant: &str, success: bool, total_tokens: u32) -> TelemetryRecord {
+        TelemetryRecord {
+            request_id: "req".to_string(),
+            timestamp: Utc::now(),
+            tenant: Some(tenant.to_string()),
+            user: None,
+            requested_model: "gpt-4".to_string(),
+            model: "gpt-4".to_string(),
+            provider: "openai".to_string(),
+            prompt_tokens: total_tokens / 2,
+            completion_tokens: total_tokens - total_tokens / 2,
+            total_tokens,
+            cost_usd: Some(f64::from(total_tokens) * 1e-5),
+            latency_ms: 100,
+            cache_status: CacheStatus::Miss,
+            success,
+            error: None,
+            flagged_categories: Vec::new(),
+            language: None,
+        }
+    }
+
+    fn user_record(tenant: &str, user: &str, total_tokens: u32) -> TelemetryRecord {
+        TelemetryRecord {
+            user: Some(user.to_string()),
+            ..record(tenant, true, total_tokens)
+        }
+    }
+
+    fn window(detector: &AnomalyDetector, requests: u32, tokens: u32) -> Vec<AnomalyEvent> {
+        for _ in 0..requests {
+            detector.observe(&record("acme", true, tokens));
+        }
+        detector.close_window(Utc::now())
+    }
+
+    #[test]
+    fn test_baseline_tracks_mean_and_variance() {
+        let mut baseline = Baseline::default();
+        for value in [10.0, 12.0, 8.0, 10.0, 12.0, 8.0] {
+            baseline.update(value, 0.5);
+        }
+        assert!((baseline.mean - 9.4375).abs() < 1e-9);
+        assert!(baseline.variance > 1.0 && baseline.variance < 4.0);
+        assert!(baseline.z_score(30.0, 0.0) > 5.0);
+    }
+
+    #[test]
+    fn test_token_spike_starts_and_resolves_anomaly() {
+        let detector = AnomalyDetector::new(config());
+        for _ in 0..10 {
+            assert!(window(&detector, 10, 100).is_empty());
+        }
+
+        let events = window(&detector, 10, 1000);
+        assert_eq!(events.len(), 2);
+        let signals: Vec<Signal> = events.iter().map(|e| e.anomaly().signal).collect();
+        assert!(signals.contains(&Signal::Tokens) && signals.contains(&Signal::Spend));
+        assert!(matches!(events[0], AnomalyEvent::Started(_)));
+        assert_eq!(detector.recent().len(), 2);
+
+        let events = window(&detector, 10, 100);
+        assert!(events
+            .iter()
+            .all(|e| matches!(e, AnomalyEvent::Resolved(a) if a.ended_at.is_some())));
+        assert_eq!(detector.recent().len(), 2);
+        assert!(detector.recent().iter().all(|a| a.ended_at.is_some()));
+    }
+
+    #[test]
+    fn test_per_user_anomalies() {
+        let detector = AnomalyDetector::new(AnomalyDetectionConfig {
+            per_user: true,
+            ..config()
+        });
+        for _ in 0..10 {
+            for _ in 0..10 {
+                detector.observe(&user_record("acme", "alice", 100));
+                for user in ["bob", "carol", "dave", "erin"] {
+                    detector.observe(&user_record("acme", user, 1000));
+                }
+            }
+            assert!(detector.close_window(Utc::now()).is_empty());
+        }
+
+        // Only the user whose traffic spiked is flagged
+        for _ in 0..10 {
+            detector.observe(&user_record("acme", "alice", 1000));
+            for user in ["bob", "carol", "dave", "erin"] {
+                detector.observe(&user_record("acme", user, 1000));
+            }
+        }
+        let events = detector.close_window(Utc::now());
+        let users: Vec<Option<&str>> = events.iter().map(|e| e.anomaly().user.as_deref()).collect();
+        assert!(users.contains(&Some("alice")));
+        assert!(users
+            .iter()
+            .all(|user| matches!(user, None | Some("alice"))));
+
+        // Users aren't checked unless configured
+        let detector = AnomalyDetector::new(config());
+        detector.observe(&user_record("acme", "alice", 100));
+        assert_eq!(detector.state.lock().unwrap().tenants.len(), 1);
+    }
+
+    #[test]
+    fn test_warmup_and_min_requests() {
+        let detector = AnomalyDetector::new(config());
+        // Spikes during warm-up aren't flagged
+        assert!(window(&detector, 10, 100).is_empty());
+        assert!(window(&detector, 10, 10_000).is_empty());
+
+        let detector = AnomalyDetector::new(config());
+        for _ in 0..10 {
+            window(&detector, 10, 100);
+        }
+        // A window too small to score its error rate is skipped
+        for _ in 0..3 {
+            detector.observe(&record("acme", false, 100));
+        }
+        assert!(detector.close_window(Utc::now()).is_empty());
+
+        for _ in 0..3 {
+            detector.observe(&record("acme", false, 100));
+        }
+        for _ in 0..7 {
+            detector.observe(&record("acme", true, 100));
+        }
+        let events = detector.close_window(Utc::now());
+        assert!(events
+            .iter()
+            .any(|e| e.anomaly().signal == Signal::ErrorRate));
+    }
+}
@@ -0,0 +1,570 @@
+//! Canary Prompts
+//!
+//! This module sends synthetic requests with known answers through the whole
+//! router, to catch providers that degrade silently: models that still answer
+//! but answer wrong, or slowly. Every `interval_secs`, each canary's prompt is
+//! POSTed to the router's own chat completions endpoint, so that it passes
+//! through authentication, routing, transformations and the provider call
+//! like a client request. The answer is checked against the texts and pattern
+//! the canary expects, and its latency against the canary's limit.
+//!
+//! A canary that fails `failure_threshold` times in a row is failing until it
+//! passes again. Failing canaries degrade the router's readiness, and the
+//! configured alert webhooks are notified when a canary starts failing and
+//! when it recovers. Results are recorded as metrics and kept for the admin
+//! API.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge, histogram};
+use regex::Regex;
+use reqwest::Client;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::anomaly::deliver_alert;
+use super::catalog;
+use crate::config::{CanaryConfig, CanaryMonitorConfig};
+use crate::modules::health::{ConnectionStatus, DependencyChecker, HealthStatus};
+use crate::modules::llm_proxy::coalesce::NO_COALESCE_HEADER;
+
+/// Header naming the canary of a request
+pub const CANARY_HEADER: &str = "x-intellirouter-canary";
+
+/// Delay before the first round, leaving the router time to start listening
+const STARTUP_DELAY: Duration = Duration::from_secs(10);
+
+/// Characters of an answer kept in a result
+const ANSWER_PREVIEW_CHARS: usize = 200;
+
+/// Outcome of a canary request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanaryOutcome {
+    /// Correct answer within the latency limit
+    Pass,
+    /// The answer was not the expected one
+    WrongAnswer,
+    /// Correct answer, slower than the latency limit
+    Slow,
+    /// The request failed
+    Error,
+}
+
+impl CanaryOutcome {
+    /// Name of the outcome, as used in metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CanaryOutcome::Pass => "pass",
+            CanaryOutcome::WrongAnswer => "wrong_answer",
+            CanaryOutcome::Slow => "slow",
+            CanaryOutcome::Error => "error",
+        }
+    }
+}
+
+/// Result of a canary request
+#[derive(Debug, Clone, Serialize)]
+pub struct CanaryResult {
+    pub outcome: CanaryOutcome,
+    /// Latency of the answer, for requests that got one
+    pub latency_ms: Option<u64>,
+    /// Start of the answer
+    pub answer: Option<String>,
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// State of a canary, as reported by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct CanaryStatus {
+    pub name: String,
+    pub model: String,
+    /// Whether the canary failed `failure_threshold` times in a row
+    pub failing: bool,
+    pub consecutive_failures: u32,
+    pub last_success: Option<DateTime<Utc>>,
+    /// Latest results, newest first
+    pub results: Vec<CanaryResult>,
+}
+
+/// Change in whether a canary is failing
+#[derive(Debug, Clone)]
+pub enum CanaryEvent {
+    Failing(CanaryStatus),
+    Recovered(CanaryStatus),
+}
+
+impl CanaryEvent {
+    /// Name of the event, as sent to alert webhooks
+    pub fn name(&self) -> &'static str {
+        match self {
+            CanaryEvent::Failing(_) => "canary.failing",
+            CanaryEvent::Recovered(_) => "canary.recovered",
+        }
+    }
+
+    pub fn status(&self) -> &CanaryStatus {
+        match self {
+            CanaryEvent::Failing(status) | CanaryEvent::Recovered(status) => status,
+        }
+    }
+}
+
+/// Canary with its compiled pattern
+#[derive(Debug)]
+struct Canary {
+    config: CanaryConfig,
+    pattern: Option<Regex>,
+}
+
+impl Canary {
+    /// Outcome of an answer received after `latency_ms`
+    fn check(&self, answer: &str, latency_ms: u64) -> CanaryOutcome {
+        let lowercase = answer.to_lowercase();
+        let contains = self
+            .config
+            .expect_contains
+            .iter()
+            .all(|expected| lowercase.contains(&expected.to_lowercase()));
+        let matches = self
+            .pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(answer));
+
+        if !contains || !matches {
+            CanaryOutcome::WrongAnswer
+        } else if self
+            .config
+            .max_latency_ms
+            .is_some_and(|max| latency_ms > max)
+        {
+            CanaryOutcome::Slow
+        } else {
+            CanaryOutcome::Pass
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CanaryState {
+    consecutive_failures: u32,
+    failing: bool,
+    last_success: Option<DateTime<Utc>>,
+    /// Latest results, oldest first
+    results: VecDeque<CanaryResult>,
+}
+
+/// Monitor sending canary prompts through the router
+#[derive(Debug)]
+pub struct CanaryMonitor {
+    config: CanaryMonitorConfig,
+    canaries: Vec<Canary>,
+    /// Chat completions endpoint the canaries are sent to
+    url: String,
+    client: Client,
+    state: Mutex<HashMap<String, CanaryState>>,
+}
+
+impl CanaryMonitor {
+    /// Create a monitor and start sending canaries in the background
+    ///
+    /// Canaries go to `base_url` unless the configuration names another
+    /// router. Returns `None` when canaries are disabled.
+    pub fn from_config(
+        config: &CanaryMonitorConfig,
+        base_url: &str,
+    ) -> Result<Option<Arc<Self>>, String> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let monitor = Arc::new(Self::new(config.clone(), base_url)?);
+        monitor.clone().spawn();
+        Ok(Some(monitor))
+    }
+
+    /// Create a monitor, failing on canary patterns that are not valid regexes
+    pub fn new(config: CanaryMonitorConfig, base_url: &str) -> Result<Self, String> {
+        let canaries = config
+            .canaries
+            .iter()
+            .map(|canary| {
+                let pattern = canary
+                    .expect_pattern
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| format!("Invalid pattern of canary '{}': {}", canary.name, e))?;
+                Ok(Canary {
+                    config: canary.clone(),
+                    pattern,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let base_url = config.base_url.as_deref().unwrap_or(base_url);
+        let client = Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            url: format!("{}/v1/chat/completions", base_url.trim_end_matches('/')),
+            config,
+            canaries,
+            client,
+            state: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Send every canary once and record the results
+    pub async fn run_once(&self) -> Vec<CanaryEvent> {
+        let results =
+            futures::future::join_all(self.canaries.iter().map(|canary| self.send(canary))).await;
+        self.canaries
+            .iter()
+            .zip(results)
+            .filter_map(|(canary, result)| self.record(&canary.config, result))
+            .collect()
+    }
+
+    /// Send the first canary once, without recording its result
+    ///
+    /// Returns `None` when no canary is configured.
+    pub async fn probe(&self) -> Option<(&CanaryConfig, CanaryResult)> {
+        let canary = self.canaries.first()?;
+        Some((&canary.config, self.send(canary).await))
+    }
+
+    /// Send a canary's prompt and check its answer
+    async fn send(&self, canary: &Canary) -> CanaryResult {
+        let body = json!({
+            "model": canary.config.model,
+            "messages": [{ "role": "user", "content": canary.config.prompt }],
+            "temperature": 0,
+            "max_tokens": canary.config.max_tokens,
+        });
+        let mut request = self
+            .client
+            .post(&self.url)
+            .header(CANARY_HEADER, &canary.config.name)
+            .header(NO_COALESCE_HEADER, "1")
+            .json(&body);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let started = Instant::now();
+        let answer = match request.send().await {
+            Ok(response) if response.status().is_success() => response
+                .json::<serde_json::Value>()
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|body| {
+                    body["choices"][0]["message"]["content"]
+                        .as_str()
+                        .map(str::to_string)
+                        .ok_or_else(|| "Response without an answer".to_string())
+                }),
+            Ok(response) => Err(format!("Router answered {}", response.status())),
+            Err(e) => Err(e.to_string()),
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        match answer {
+            Ok(answer) => CanaryResult {
+                outcome: canary.check(&answer, latency_ms),
+                latency_ms: Some(latency_ms),
+                answer: Some(answer.chars().take(ANSWER_PREVIEW_CHARS).collect()),
+                error: None,
+                checked_at: Utc::now(),
+            },
+            Err(error) => CanaryResult {
+                outcome: CanaryOutcome::Error,
+                latency_ms: None,
+                answer: None,
+                error: Some(error),
+                checked_at: Utc::now(),
+            },
+        }
+    }
+
+    /// Record a canary's result, returning the event of a change in whether
+    /// it is failing
+    fn record(&self, canary: &CanaryConfig, result: CanaryResult) -> Option<CanaryEvent> {
+        counter!(
+            catalog::CANARY_CHECKS,
+            1,
+            "canary" => canary.name.clone(),
+            "model" => canary.model.clone(),
+            "outcome" => result.outcome.as_str()
+        );
+        if let Some(latency_ms) = result.latency_ms {
+            histogram!(
+                catalog::CANARY_LATENCY,
+                latency_ms as f64,
+                "canary" => canary.name.clone(),
+                "model" => canary.model.clone()
+            );
+        }
+
+        let mut states = self.state.lock().unwrap();
+        let state = states.entry(canary.name.clone()).or_default();
+        let was_failing = state.failing;
+        if result.outcome == CanaryOutcome::Pass {
+            state.consecutive_failures = 0;
+            state.failing = false;
+            state.last_success = Some(result.checked_at);
+        } else {
+            warn!(
+                "Canary '{}' of model '{}' failed: {}",
+                canary.name,
+                canary.model,
+                result
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| result.outcome.as_str().to_string())
+            );
+            state.consecutive_failures += 1;
+            state.failing = state.consecutive_failures >= self.config.failure_threshold.max(1);
+        }
+        state.results.push_back(result);
+        while state.results.len() > self.config.history_capacity.max(1) {
+            state.results.pop_front();
+        }
+
+        gauge!(
+            catalog::CANARY_FAILING,
+            if state.failing { 1.0 } else { 0.0 },
+            "canary" => canary.name.clone(),
+            "model" => canary.model.clone()
+        );
+        let status = || Self::status(canary, state);
+        match (was_failing, state.failing) {
+            (false, true) => Some(CanaryEvent::Failing(status())),
+            (true, false) => {
+                info!(
+                    "Canary '{}' of model '{}' recovered",
+                    canary.name, canary.model
+                );
+                Some(CanaryEvent::Recovered(status()))
+            }
+            _ => None,
+        }
+    }
+
+    /// State of every canary, in configuration order
+    pub fn statuses(&self) -> Vec<CanaryStatus> {
+        let states = self.state.lock().unwrap();
+        let empty = CanaryState::default();
+        self.canaries
+            .iter()
+            .map(|canary| {
+                let state = states.get(&canary.config.name).unwrap_or(&empty);
+                Self::status(&canary.config, state)
+            })
+            .collect()
+    }
+
+    fn status(canary: &CanaryConfig, state: &CanaryState) -> CanaryStatus {
+        CanaryStatus {
+            name: canary.name.clone(),
+            model: canary.model.clone(),
+            failing: state.failing,
+            consecutive_failures: state.consecutive_failures,
+            last_success: state.last_success,
+            results: state.results.iter().rev().cloned().collect(),
+        }
+    }
+
+    /// Send a round of canaries every `interval_secs` and send the alerts
+    /// they raise
+    fn spawn(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.interval_secs.max(1));
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + STARTUP_DELAY, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                for event in self.run_once().await {
+                    let payload =
+                        json!({ "event": event.name(), "canary": event.status() }).to_string();
+                    futures::future::join_all(self.config.webhooks.iter().map(|webhook| {
+                        deliver_alert(
+                            &self.client,
+                            webhook,
+                            event.name(),
+                            payload.clone(),
+                            self.config.webhook_retries,
+                        )
+                    }))
+                    .await;
+                }
+            }
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl DependencyChecker for CanaryMonitor {
+    fn name(&self) -> &str {
+        "canaries"
+    }
+
+    /// Degraded while some canaries are failing, unhealthy when all are
+    async fn check(&self) -> Result<ConnectionStatus, Box<dyn std::error::Error + Send + Sync>> {
+        let statuses = self.statuses();
+        let failing: Vec<&str> = statuses
+            .iter()
+            .filter(|status| status.failing)
+            .map(|status| status.name.as_str())
+            .collect();
+        let status = if failing.is_empty() {
+            HealthStatus::Healthy
+        } else if failing.len() == statuses.len() {
+            HealthStatus::Unhealthy
+        } else {
+            HealthStatus::Degraded
+        };
+
+        Ok(ConnectionStatus {
+            name: self.name().to_string(),
+            status,
+            last_success: statuses.iter().filter_map(|s| s.last_success).max(),
+            error: (!failing.is_empty())
+                .then(|| format!("Failing canaries: {}", failing.join(", "))),
+            response_time_ms: None,
+            details: Some(
+                statuses
+                    .iter()
+                    .map(|s| {
+                        let state = if s.failing { "failing" } else { "passing" };
+                        (s.name.clone(), state.to_string())
+                    })
+                    .collect(),
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{Json, Router};
+
+    fn canary(name: &str, expect: &str) -> CanaryConfig {
+        CanaryConfig {
+            name: name.to_string(),
+            model: "gpt-4o".to_string(),
+            prompt: "What is the capital of France?".to_string(),
+            expect_contains: vec![expect.to_string()],
+            expect_pattern: None,
+            max_latency_ms: None,
+            max_tokens: 16,
+        }
+    }
+
+    fn config(canaries: Vec<CanaryConfig>) -> CanaryMonitorConfig {
+        CanaryMonitorConfig {
+            enabled: true,
+            failure_threshold: 2,
+            canaries,
+            ..Default::default()
+        }
+    }
+
+    fn result(outcome: CanaryOutcome) -> CanaryResult {
+        CanaryResult {
+            outcome,
+            latency_ms: Some(100),
+            answer: None,
+            error: None,
+            checked_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_check_answer() {
+        let canary = Canary {
+            config: CanaryConfig {
+                max_latency_ms: Some(500),
+                ..canary("capital", "paris")
+            },
+            pattern: Some(Regex::new(r"^\W*Paris").unwrap()),
+        };
+        assert_eq!(
+            canary.check("Paris is the capital.", 100),
+            CanaryOutcome::Pass
+        );
+        assert_eq!(canary.check("It's Paris.", 100), CanaryOutcome::WrongAnswer);
+        assert_eq!(canary.check("Lyon", 100), CanaryOutcome::WrongAnswer);
+        assert_eq!(canary.check("Paris", 900), CanaryOutcome::Slow);
+    }
+
+    #[tokio::test]
+    async fn test_failing_after_threshold_and_recovery() {
+        let config = config(vec![canary("capital", "paris"), canary("math", "4")]);
+        let monitor = CanaryMonitor::new(config.clone(), "http://localhost").unwrap();
+        let capital = &config.canaries[0];
+
+        assert!(monitor
+            .record(capital, result(CanaryOutcome::WrongAnswer))
+            .is_none());
+        let event = monitor.record(capital, result(CanaryOutcome::Error));
+        assert!(matches!(event, Some(CanaryEvent::Failing(ref s)) if s.consecutive_failures == 2));
+
+        let health = monitor.check().await.unwrap();
+        assert_eq!(health.status, HealthStatus::Degraded);
+        assert_eq!(health.details.unwrap()["capital"], "failing");
+
+        let event = monitor.record(capital, result(CanaryOutcome::Pass));
+        assert!(matches!(event, Some(CanaryEvent::Recovered(_))));
+        let statuses = monitor.statuses();
+        assert_eq!(statuses[0].results.len(), 3);
+        assert_eq!(statuses[0].results[0].outcome, CanaryOutcome::Pass);
+        assert_eq!(monitor.check().await.unwrap().status, HealthStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn test_run_through_router() {
+        let app = Router::new().route(
+            "/v1/chat/completions",
+            post(|Json(body): Json<serde_json::Value>| async move {
+                let content = match body["model"].as_str() {
+                    Some("gpt-4o") => "Paris.",
+                    _ => "I don't know.",
+                };
+                Json(json!({ "choices": [{ "message": { "content": content } }] }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let degraded = CanaryConfig {
+            model: "gpt-3.5-turbo".to_string(),
+            ..canary("degraded", "paris")
+        };
+        let monitor = CanaryMonitor::new(
+            CanaryMonitorConfig {
+                failure_threshold: 1,
+                ..config(vec![canary("capital", "paris"), degraded])
+            },
+            &base_url,
+        )
+        .unwrap();
+
+        let events = monitor.run_once().await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].status().name, "degraded");
+        let statuses = monitor.statuses();
+        assert_eq!(statuses[0].results[0].outcome, CanaryOutcome::Pass);
+        assert_eq!(statuses[0].results[0].answer.as_deref(), Some("Paris."));
+        assert_eq!(statuses[1].results[0].outcome, CanaryOutcome::WrongAnswer);
+    }
+}
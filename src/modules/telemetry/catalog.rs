@@ -0,0 +1,740 @@
+//! Metrics Catalog
+//!
+//! This module names every metric the router records, with its kind and
+//! labels. Call sites use the name constants, and the Grafana dashboard
+//! generator reads the catalog, so renaming a metric here renames it
+//! everywhere it is recorded and charted.
+
+/// HTTP requests handled
+pub const HTTP_REQUESTS: &str = "intellirouter.http.requests";
+/// HTTP request latency in milliseconds
+pub const HTTP_LATENCY: &str = "intellirouter.http.latency";
+/// HTTP requests by traffic class, real or synthetic
+pub const HTTP_TRAFFIC: &str = "intellirouter.http.traffic";
+/// LLM calls made
+pub const LLM_CALLS: &str = "intellirouter.llm.calls";
+/// Prompt tokens of the last LLM call
+pub const LLM_PROMPT_TOKENS: &str = "intellirouter.llm.tokens.prompt";
+/// Completion tokens of the last LLM call
+pub const LLM_COMPLETION_TOKENS: &str = "intellirouter.llm.tokens.completion";
+/// Total tokens of the last LLM call
+pub const LLM_TOTAL_TOKENS: &str = "intellirouter.llm.tokens.total";
+/// LLM call latency in milliseconds
+pub const LLM_LATENCY: &str = "intellirouter.llm.latency";
+/// Estimated cost of the last LLM call in USD
+pub const LLM_COST: &str = "intellirouter.llm.cost";
+/// Responses ended by a provider's content filter
+pub const LLM_CONTENT_FILTERED: &str = "intellirouter.llm.content_filtered";
+/// Routing decisions made
+pub const ROUTING_DECISIONS: &str = "intellirouter.routing.decisions";
+/// Times each model was selected
+pub const ROUTING_MODEL_SELECTED: &str = "intellirouter.routing.model_selected";
+/// Candidate models of the last routing decision
+pub const ROUTING_CANDIDATE_COUNT: &str = "intellirouter.routing.candidate_count";
+/// Routing decision time in milliseconds
+pub const ROUTING_DECISION_TIME: &str = "intellirouter.routing.decision_time";
+/// Router plugin hooks that failed
+pub const ROUTING_PLUGIN_ERRORS: &str = "intellirouter.routing.plugin_errors";
+/// Requests whose language was detected
+pub const ROUTING_LANGUAGE_DETECTED: &str = "intellirouter.routing.language_detected";
+/// Requests steered toward models supporting their language
+pub const ROUTING_LANGUAGE_STEERED: &str = "intellirouter.routing.language_steered";
+/// Telemetry records exported
+pub const TELEMETRY_EXPORT_RECORDS: &str = "intellirouter.telemetry.export.records";
+/// Telemetry records dropped after failed exports
+pub const TELEMETRY_EXPORT_FAILED: &str = "intellirouter.telemetry.export.failed";
+/// Telemetry records dropped because the export queue was full
+pub const TELEMETRY_EXPORT_DROPPED: &str = "intellirouter.telemetry.export.dropped";
+/// Requests in flight to each provider
+pub const PROVIDER_POOL_IN_FLIGHT: &str = "intellirouter.provider.pool.in_flight";
+/// In-flight provider requests as a fraction of the pooled connections
+pub const PROVIDER_POOL_UTILIZATION: &str = "intellirouter.provider.pool.utilization";
+/// New connections opened to provider hosts
+pub const PROVIDER_CONNECTIONS: &str = "intellirouter.provider.connections";
+/// Provider requests made with each credential
+pub const PROVIDER_CREDENTIAL_REQUESTS: &str = "intellirouter.provider.credential.requests";
+/// Tokens used with each provider credential
+pub const PROVIDER_CREDENTIAL_TOKENS: &str = "intellirouter.provider.credential.tokens";
+/// Switches of providers to standby credentials
+pub const PROVIDER_CREDENTIAL_FAILOVERS: &str = "intellirouter.provider.credential.failovers";
+/// Fraction of the rate limit left to each pooled credential
+pub const PROVIDER_CREDENTIAL_HEADROOM: &str = "intellirouter.provider.credential.headroom";
+/// Cooldowns of pooled credentials
+pub const PROVIDER_CREDENTIAL_COOLDOWNS: &str = "intellirouter.provider.credential.cooldowns";
+/// Syncs of the models listed by providers
+pub const MODEL_DISCOVERY_SYNCS: &str = "intellirouter.model_discovery.syncs";
+/// Discovered models waiting for an admin's approval
+pub const MODEL_DISCOVERY_PENDING: &str = "intellirouter.model_discovery.pending";
+/// Requests served by a deprecated model
+pub const MODEL_DEPRECATED_REQUESTS: &str = "intellirouter.model.deprecated_requests";
+/// Streamed responses whose client stopped reading with a full buffer
+pub const STREAMS_STALLED: &str = "intellirouter.streams.stalled";
+/// Streamed responses currently waiting on a stalled client
+pub const STREAMS_STALLED_ACTIVE: &str = "intellirouter.streams.stalled_active";
+/// Time a stalled client took to catch up or be dropped, in milliseconds
+pub const STREAMS_STALL_DURATION: &str = "intellirouter.streams.stall_duration";
+/// Streaming clients disconnected for stalling too long
+pub const STREAMS_SLOW_CLIENT_DISCONNECTS: &str = "intellirouter.streams.slow_client_disconnects";
+/// Partial completions of interrupted streams recorded in conversations
+pub const STREAMS_PARTIALS_RECORDED: &str = "intellirouter.streams.partials_recorded";
+/// Requests attached to an identical request already in flight
+pub const COALESCING_COALESCED: &str = "intellirouter.coalescing.coalesced";
+/// Upstream calls currently shared by coalesced requests
+pub const COALESCING_IN_FLIGHT: &str = "intellirouter.coalescing.in_flight";
+/// Anomalies detected in tenant traffic and cost
+pub const ANOMALIES_DETECTED: &str = "intellirouter.anomalies.detected";
+/// Whether an anomaly is ongoing (1) or not (0) for a tenant and signal
+pub const ANOMALIES_ACTIVE: &str = "intellirouter.anomalies.active";
+/// Billing events emitted by metering
+pub const METERING_EVENTS: &str = "intellirouter.metering.events";
+/// Requests dropped from metering for arriving after their period's
+/// correction window
+pub const METERING_LATE_DROPPED: &str = "intellirouter.metering.late_dropped";
+/// Pushes of billing events to Stripe that failed after their retries
+pub const METERING_PUSH_FAILED: &str = "intellirouter.metering.push_failed";
+/// Discovered instances of a role in the rotation
+pub const DISCOVERY_INSTANCES: &str = "intellirouter.discovery.instances";
+/// Discovered instances evicted for failing calls or health checks
+pub const DISCOVERY_EVICTIONS: &str = "intellirouter.discovery.evictions";
+/// Bytes of HTTP bodies compressed or decompressed, before compression
+pub const COMPRESSION_RAW_BYTES: &str = "intellirouter.compression.raw_bytes";
+/// Bytes saved by compressing HTTP bodies
+pub const COMPRESSION_SAVED_BYTES: &str = "intellirouter.compression.saved_bytes";
+/// Requests rejected for exceeding their route's body size or read timeout
+pub const REQUEST_LIMIT_REJECTIONS: &str = "intellirouter.http.limit_rejections";
+/// Batch requests shed while the process was over a soft resource limit
+pub const REQUESTS_SHED: &str = "intellirouter.http.shed_requests";
+/// CPU time of the process, in percent of the CPUs available to it
+pub const PROCESS_CPU_PERCENT: &str = "intellirouter.process.cpu_percent";
+/// Resident memory of the process in bytes
+pub const PROCESS_MEMORY_BYTES: &str = "intellirouter.process.memory_bytes";
+/// Resident memory of the process, in percent of its memory limit
+pub const PROCESS_MEMORY_PERCENT: &str = "intellirouter.process.memory_percent";
+/// Open file descriptors of the process
+pub const PROCESS_OPEN_FDS: &str = "intellirouter.process.open_fds";
+/// Open file descriptors of the process, in percent of its limit
+pub const PROCESS_FD_PERCENT: &str = "intellirouter.process.fd_percent";
+/// Whether the process is over the soft limit of a resource (1) or not (0)
+pub const PROCESS_OVER_LIMIT: &str = "intellirouter.process.over_limit";
+/// Entries in the chain dead-letter queue
+pub const CHAIN_DEAD_LETTERS: &str = "intellirouter.chain.dead_letters";
+/// Failed chain steps and webhook deliveries added to the dead-letter queue
+pub const CHAIN_DEAD_LETTERED: &str = "intellirouter.chain.dead_lettered";
+/// Chain executions stopped by a limit of their budget
+pub const CHAIN_BUDGET_EXCEEDED: &str = "intellirouter.chain.budget_exceeded";
+/// Output requested as JSON that was repaired, by repair stage
+pub const JSON_MODE_REPAIRS: &str = "intellirouter.json_mode.repairs";
+/// Requests failed for output requested as JSON that was not valid JSON
+pub const JSON_MODE_FAILURES: &str = "intellirouter.json_mode.failures";
+/// Retrieved chunks matching a prompt injection heuristic, by heuristic
+pub const RAG_INJECTIONS_SUSPECTED: &str = "intellirouter.rag.injections_suspected";
+/// Lookups of the RAG retrieval cache, by outcome
+pub const RAG_RETRIEVAL_CACHE_LOOKUPS: &str = "intellirouter.rag.retrieval_cache.lookups";
+/// Results held by the RAG retrieval cache
+pub const RAG_RETRIEVAL_CACHE_ENTRIES: &str = "intellirouter.rag.retrieval_cache.entries";
+/// Grounding checks of RAG answers, by judge and outcome
+pub const RAG_GROUNDING_CHECKS: &str = "intellirouter.rag.grounding.checks";
+/// RAG answers regenerated for not being supported by the retrieved chunks
+pub const RAG_GROUNDING_REGENERATIONS: &str = "intellirouter.rag.grounding.regenerations";
+/// Canary requests sent through the router, by outcome
+pub const CANARY_CHECKS: &str = "intellirouter.canary.checks";
+/// Latency of the answers to canary requests, in milliseconds
+pub const CANARY_LATENCY: &str = "intellirouter.canary.latency";
+/// Whether a canary is failing (1) or not (0)
+pub const CANARY_FAILING: &str = "intellirouter.canary.failing";
+/// Request payloads captured for replay, by outcome
+pub const PAYLOADS_CAPTURED: &str = "intellirouter.payloads.captured";
+/// Refreshes of the Redis master address or cluster slot map, by reason and outcome
+pub const REDIS_TOPOLOGY_REFRESHES: &str = "intellirouter.redis.topology_refreshes";
+/// Redis masters replaced by a failover
+pub const REDIS_FAILOVERS: &str = "intellirouter.redis.failovers";
+/// Redis Cluster redirects followed, by kind
+pub const REDIS_REDIRECTS: &str = "intellirouter.redis.redirects";
+/// Redis masters known
+pub const REDIS_NODES: &str = "intellirouter.redis.nodes";
+/// Requests to deprecated API routes
+pub const API_DEPRECATED_REQUESTS: &str = "intellirouter.api.deprecated_requests";
+
+/// Kind of a metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+/// Description of a recorded metric
+#[derive(Debug, Clone, Copy)]
+pub struct MetricSpec {
+    /// Name as recorded
+    pub name: &'static str,
+    pub kind: MetricKind,
+    /// Human-readable title
+    pub title: &'static str,
+    /// Grafana unit of the values
+    pub unit: &'static str,
+    /// Labels attached when recording
+    pub labels: &'static [&'static str],
+}
+
+impl MetricSpec {
+    /// Name as exposed by the Prometheus exporter
+    pub fn prometheus_name(&self) -> String {
+        prometheus_name(self.name)
+    }
+}
+
+/// Convert a recorded metric name to its Prometheus form
+pub fn prometheus_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+const SERVICE_LABELS: &[&str] = &["service", "env"];
+
+/// Every metric the router records
+pub const METRICS: &[MetricSpec] = &[
+    MetricSpec {
+        name: HTTP_REQUESTS,
+        kind: MetricKind::Counter,
+        title: "HTTP requests",
+        unit: "reqps",
+        labels: &["path", "method", "status", "service", "env"],
+    },
+    MetricSpec {
+        name: HTTP_LATENCY,
+        kind: MetricKind::Histogram,
+        title: "HTTP latency",
+        unit: "ms",
+        labels: &["path", "method", "status", "service", "env"],
+    },
+    MetricSpec {
+        name: HTTP_TRAFFIC,
+        kind: MetricKind::Counter,
+        title: "HTTP requests by traffic class",
+        unit: "reqps",
+        labels: &["class", "matched"],
+    },
+    MetricSpec {
+        name: LLM_CALLS,
+        kind: MetricKind::Counter,
+        title: "LLM calls",
+        unit: "reqps",
+        labels: &["model", "success", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_PROMPT_TOKENS,
+        kind: MetricKind::Gauge,
+        title: "Prompt tokens",
+        unit: "short",
+        labels: &["model", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_COMPLETION_TOKENS,
+        kind: MetricKind::Gauge,
+        title: "Completion tokens",
+        unit: "short",
+        labels: &["model", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_TOTAL_TOKENS,
+        kind: MetricKind::Gauge,
+        title: "Total tokens",
+        unit: "short",
+        labels: &["model", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_LATENCY,
+        kind: MetricKind::Histogram,
+        title: "LLM latency",
+        unit: "ms",
+        labels: &["model", "success", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_COST,
+        kind: MetricKind::Gauge,
+        title: "LLM call cost",
+        unit: "currencyUSD",
+        labels: &["model", "service", "env"],
+    },
+    MetricSpec {
+        name: LLM_CONTENT_FILTERED,
+        kind: MetricKind::Counter,
+        title: "Content-filtered responses",
+        unit: "short",
+        labels: &["model", "provider", "stage"],
+    },
+    MetricSpec {
+        name: ROUTING_DECISIONS,
+        kind: MetricKind::Counter,
+        title: "Routing decisions",
+        unit: "reqps",
+        labels: &["success", "service", "env"],
+    },
+    MetricSpec {
+        name: ROUTING_MODEL_SELECTED,
+        kind: MetricKind::Counter,
+        title: "Model selections",
+        unit: "reqps",
+        labels: &["model", "service", "env"],
+    },
+    MetricSpec {
+        name: ROUTING_CANDIDATE_COUNT,
+        kind: MetricKind::Gauge,
+        title: "Routing candidates",
+        unit: "short",
+        labels: SERVICE_LABELS,
+    },
+    MetricSpec {
+        name: ROUTING_DECISION_TIME,
+        kind: MetricKind::Histogram,
+        title: "Routing decision time",
+        unit: "ms",
+        labels: &["success", "service", "env"],
+    },
+    MetricSpec {
+        name: ROUTING_PLUGIN_ERRORS,
+        kind: MetricKind::Counter,
+        title: "Router plugin failures",
+        unit: "short",
+        labels: &["plugin", "hook"],
+    },
+    MetricSpec {
+        name: ROUTING_LANGUAGE_DETECTED,
+        kind: MetricKind::Counter,
+        title: "Detected request languages",
+        unit: "short",
+        labels: &["language"],
+    },
+    MetricSpec {
+        name: ROUTING_LANGUAGE_STEERED,
+        kind: MetricKind::Counter,
+        title: "Language-steered requests",
+        unit: "short",
+        labels: &["language"],
+    },
+    MetricSpec {
+        name: TELEMETRY_EXPORT_RECORDS,
+        kind: MetricKind::Counter,
+        title: "Exported telemetry records",
+        unit: "short",
+        labels: &["sink"],
+    },
+    MetricSpec {
+        name: TELEMETRY_EXPORT_FAILED,
+        kind: MetricKind::Counter,
+        title: "Failed telemetry exports",
+        unit: "short",
+        labels: &["sink"],
+    },
+    MetricSpec {
+        name: TELEMETRY_EXPORT_DROPPED,
+        kind: MetricKind::Counter,
+        title: "Dropped telemetry records",
+        unit: "short",
+        labels: &[],
+    },
+    MetricSpec {
+        name: PROVIDER_POOL_IN_FLIGHT,
+        kind: MetricKind::Gauge,
+        title: "Provider requests in flight",
+        unit: "short",
+        labels: &["provider"],
+    },
+    MetricSpec {
+        name: PROVIDER_POOL_UTILIZATION,
+        kind: MetricKind::Gauge,
+        title: "Provider pool utilization",
+        unit: "percentunit",
+        labels: &["provider"],
+    },
+    MetricSpec {
+        name: PROVIDER_CONNECTIONS,
+        kind: MetricKind::Counter,
+        title: "New provider connections",
+        unit: "short",
+        labels: &["host", "dns_cached"],
+    },
+    MetricSpec {
+        name: PROVIDER_CREDENTIAL_REQUESTS,
+        kind: MetricKind::Counter,
+        title: "Provider requests by credential",
+        unit: "reqps",
+        labels: &["provider", "credential", "success"],
+    },
+    MetricSpec {
+        name: PROVIDER_CREDENTIAL_TOKENS,
+        kind: MetricKind::Counter,
+        title: "Provider tokens by credential",
+        unit: "short",
+        labels: &["provider", "credential"],
+    },
+    MetricSpec {
+        name: PROVIDER_CREDENTIAL_FAILOVERS,
+        kind: MetricKind::Counter,
+        title: "Credential failovers",
+        unit: "short",
+        labels: &["provider", "from", "to"],
+    },
+    MetricSpec {
+        name: PROVIDER_CREDENTIAL_HEADROOM,
+        kind: MetricKind::Gauge,
+        title: "Rate limit headroom by credential",
+        unit: "percentunit",
+        labels: &["provider", "credential"],
+    },
+    MetricSpec {
+        name: PROVIDER_CREDENTIAL_COOLDOWNS,
+        kind: MetricKind::Counter,
+        title: "Credential cooldowns",
+        unit: "short",
+        labels: &["provider", "credential", "reason"],
+    },
+    MetricSpec {
+        name: MODEL_DISCOVERY_SYNCS,
+        kind: MetricKind::Counter,
+        title: "Model discovery syncs",
+        unit: "short",
+        labels: &["provider", "outcome"],
+    },
+    MetricSpec {
+        name: MODEL_DISCOVERY_PENDING,
+        kind: MetricKind::Gauge,
+        title: "Pending model proposals",
+        unit: "short",
+        labels: &[],
+    },
+    MetricSpec {
+        name: MODEL_DEPRECATED_REQUESTS,
+        kind: MetricKind::Counter,
+        title: "Requests to deprecated models",
+        unit: "short",
+        labels: &["model", "owner"],
+    },
+    MetricSpec {
+        name: STREAMS_STALLED,
+        kind: MetricKind::Counter,
+        title: "Stalled streams",
+        unit: "short",
+        labels: &["source", "policy"],
+    },
+    MetricSpec {
+        name: STREAMS_STALLED_ACTIVE,
+        kind: MetricKind::Gauge,
+        title: "Streams waiting on clients",
+        unit: "short",
+        labels: &["source"],
+    },
+    MetricSpec {
+        name: STREAMS_STALL_DURATION,
+        kind: MetricKind::Histogram,
+        title: "Stall duration",
+        unit: "ms",
+        labels: &["source"],
+    },
+    MetricSpec {
+        name: STREAMS_SLOW_CLIENT_DISCONNECTS,
+        kind: MetricKind::Counter,
+        title: "Slow clients disconnected",
+        unit: "short",
+        labels: &["source", "policy"],
+    },
+    MetricSpec {
+        name: STREAMS_PARTIALS_RECORDED,
+        kind: MetricKind::Counter,
+        title: "Partial completions recorded",
+        unit: "short",
+        labels: &["interruption"],
+    },
+    MetricSpec {
+        name: COALESCING_COALESCED,
+        kind: MetricKind::Counter,
+        title: "Coalesced requests",
+        unit: "short",
+        labels: &["model"],
+    },
+    MetricSpec {
+        name: COALESCING_IN_FLIGHT,
+        kind: MetricKind::Gauge,
+        title: "Coalescable calls in flight",
+        unit: "short",
+        labels: &[],
+    },
+    MetricSpec {
+        name: ANOMALIES_DETECTED,
+        kind: MetricKind::Counter,
+        title: "Detected anomalies",
+        unit: "short",
+        labels: &["tenant", "signal"],
+    },
+    MetricSpec {
+        name: ANOMALIES_ACTIVE,
+        kind: MetricKind::Gauge,
+        title: "Active anomalies",
+        unit: "short",
+        labels: &["tenant", "signal"],
+    },
+    MetricSpec {
+        name: METERING_EVENTS,
+        kind: MetricKind::Counter,
+        title: "Billing events",
+        unit: "short",
+        labels: &["meter", "correction"],
+    },
+    MetricSpec {
+        name: METERING_LATE_DROPPED,
+        kind: MetricKind::Counter,
+        title: "Requests too late to bill",
+        unit: "short",
+        labels: &["tenant"],
+    },
+    MetricSpec {
+        name: METERING_PUSH_FAILED,
+        kind: MetricKind::Counter,
+        title: "Failed billing event pushes",
+        unit: "short",
+        labels: &[],
+    },
+    MetricSpec {
+        name: DISCOVERY_INSTANCES,
+        kind: MetricKind::Gauge,
+        title: "Available role instances",
+        unit: "short",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: DISCOVERY_EVICTIONS,
+        kind: MetricKind::Counter,
+        title: "Role instance evictions",
+        unit: "short",
+        labels: &["role", "reason"],
+    },
+    MetricSpec {
+        name: COMPRESSION_RAW_BYTES,
+        kind: MetricKind::Counter,
+        title: "Compressed body bytes",
+        unit: "bytes",
+        labels: &["direction", "algorithm"],
+    },
+    MetricSpec {
+        name: COMPRESSION_SAVED_BYTES,
+        kind: MetricKind::Counter,
+        title: "Bytes saved by compression",
+        unit: "bytes",
+        labels: &["direction", "algorithm"],
+    },
+    MetricSpec {
+        name: REQUEST_LIMIT_REJECTIONS,
+        kind: MetricKind::Counter,
+        title: "Requests rejected by body limits",
+        unit: "short",
+        labels: &["route", "reason"],
+    },
+    MetricSpec {
+        name: REQUESTS_SHED,
+        kind: MetricKind::Counter,
+        title: "Batch requests shed",
+        unit: "short",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_CPU_PERCENT,
+        kind: MetricKind::Gauge,
+        title: "Process CPU",
+        unit: "percent",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_MEMORY_BYTES,
+        kind: MetricKind::Gauge,
+        title: "Process resident memory",
+        unit: "bytes",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_MEMORY_PERCENT,
+        kind: MetricKind::Gauge,
+        title: "Process memory of its limit",
+        unit: "percent",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_OPEN_FDS,
+        kind: MetricKind::Gauge,
+        title: "Process open file descriptors",
+        unit: "short",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_FD_PERCENT,
+        kind: MetricKind::Gauge,
+        title: "Process file descriptors of their limit",
+        unit: "percent",
+        labels: &["role"],
+    },
+    MetricSpec {
+        name: PROCESS_OVER_LIMIT,
+        kind: MetricKind::Gauge,
+        title: "Process over a soft resource limit",
+        unit: "short",
+        labels: &["role", "resource"],
+    },
+    MetricSpec {
+        name: CHAIN_DEAD_LETTERS,
+        kind: MetricKind::Gauge,
+        title: "Chain dead-letter queue depth",
+        unit: "short",
+        labels: &["kind"],
+    },
+    MetricSpec {
+        name: CHAIN_DEAD_LETTERED,
+        kind: MetricKind::Counter,
+        title: "Dead-lettered chain steps and deliveries",
+        unit: "short",
+        labels: &["kind"],
+    },
+    MetricSpec {
+        name: CHAIN_BUDGET_EXCEEDED,
+        kind: MetricKind::Counter,
+        title: "Chain executions over budget",
+        unit: "short",
+        labels: &["limit"],
+    },
+    MetricSpec {
+        name: JSON_MODE_REPAIRS,
+        kind: MetricKind::Counter,
+        title: "JSON outputs repaired",
+        unit: "short",
+        labels: &["model", "method"],
+    },
+    MetricSpec {
+        name: JSON_MODE_FAILURES,
+        kind: MetricKind::Counter,
+        title: "Invalid JSON outputs failed",
+        unit: "short",
+        labels: &["model"],
+    },
+    MetricSpec {
+        name: RAG_INJECTIONS_SUSPECTED,
+        kind: MetricKind::Counter,
+        title: "Suspected prompt injections in retrieved chunks",
+        unit: "short",
+        labels: &["pattern", "action"],
+    },
+    MetricSpec {
+        name: RAG_RETRIEVAL_CACHE_LOOKUPS,
+        kind: MetricKind::Counter,
+        title: "RAG retrieval cache lookups",
+        unit: "short",
+        labels: &["outcome"],
+    },
+    MetricSpec {
+        name: RAG_RETRIEVAL_CACHE_ENTRIES,
+        kind: MetricKind::Gauge,
+        title: "RAG retrieval cache entries",
+        unit: "short",
+        labels: &[],
+    },
+    MetricSpec {
+        name: RAG_GROUNDING_CHECKS,
+        kind: MetricKind::Counter,
+        title: "RAG answer grounding checks",
+        unit: "short",
+        labels: &["judge", "outcome"],
+    },
+    MetricSpec {
+        name: RAG_GROUNDING_REGENERATIONS,
+        kind: MetricKind::Counter,
+        title: "RAG answers regenerated for grounding",
+        unit: "short",
+        labels: &["judge"],
+    },
+    MetricSpec {
+        name: CANARY_CHECKS,
+        kind: MetricKind::Counter,
+        title: "Canary checks",
+        unit: "short",
+        labels: &["canary", "model", "outcome"],
+    },
+    MetricSpec {
+        name: CANARY_LATENCY,
+        kind: MetricKind::Histogram,
+        title: "Canary latency",
+        unit: "ms",
+        labels: &["canary", "model"],
+    },
+    MetricSpec {
+        name: CANARY_FAILING,
+        kind: MetricKind::Gauge,
+        title: "Failing canaries",
+        unit: "short",
+        labels: &["canary", "model"],
+    },
+    MetricSpec {
+        name: PAYLOADS_CAPTURED,
+        kind: MetricKind::Counter,
+        title: "Request payloads captured for replay",
+        unit: "short",
+        labels: &["outcome"],
+    },
+    MetricSpec {
+        name: REDIS_TOPOLOGY_REFRESHES,
+        kind: MetricKind::Counter,
+        title: "Redis topology refreshes",
+        unit: "short",
+        labels: &["topology", "reason", "outcome"],
+    },
+    MetricSpec {
+        name: REDIS_FAILOVERS,
+        kind: MetricKind::Counter,
+        title: "Redis failovers",
+        unit: "short",
+        labels: &["topology"],
+    },
+    MetricSpec {
+        name: REDIS_REDIRECTS,
+        kind: MetricKind::Counter,
+        title: "Redis Cluster redirects",
+        unit: "short",
+        labels: &["kind"],
+    },
+    MetricSpec {
+        name: REDIS_NODES,
+        kind: MetricKind::Gauge,
+        title: "Redis masters",
+        unit: "short",
+        labels: &["topology"],
+    },
+    MetricSpec {
+        name: API_DEPRECATED_REQUESTS,
+        kind: MetricKind::Counter,
+        title: "Deprecated API requests",
+        unit: "reqps",
+        labels: &["version", "path", "outcome"],
+    },
+];
+
+/// Look up a metric by its recorded name
+pub fn find(name: &str) -> Option<&'static MetricSpec> {
+    METRICS.iter().find(|spec| spec.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_catalog_names() {
+        let mut seen = HashSet::new();
+        for spec in METRICS {
+            assert!(seen.insert(spec.name), "duplicate metric {}", spec.name);
+            assert!(spec.name.starts_with("intellirouter."));
+        }
+        assert_eq!(
+            find(LLM_LATENCY).unwrap().prometheus_name(),
+            "intellirouter_llm_latency"
+        );
+    }
+}
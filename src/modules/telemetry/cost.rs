@@ -1,65 +1,337 @@
-use std::collections::HashMap;
-use std::sync::RwLock;
+//! Cost Calculation
+//!
+//! This module prices LLM calls. Each model has a history of prices, each
+//! effective from a point in time, since providers change their prices: a
+//! call is priced with the price in effect when it completed. Prices are
+//! kept in the configured currency; prices entered in another currency are
+//! converted with the configured exchange rates.
+//!
+//! The usage of the most recent calls is kept in a bounded ledger. When a
+//! price is entered with an effective time in the past, e.g. to correct a
+//! wrong price, the costs of the calls in the ledger from that time on are
+//! recomputed, and the corrections are returned so that the usage
+//! summarized elsewhere can be adjusted.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Mutex, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::config::PricingConfig;
+use crate::modules::erasure::ErasureSubject;
+
+/// Model whose prices apply to models without prices of their own
+pub const DEFAULT_MODEL: &str = "default";
+
+/// Error entering a price
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PricingError {
+    #[error("No exchange rate from {0} to the pricing currency")]
+    UnknownCurrency(String),
+
+    #[error("Invalid price: {0}")]
+    InvalidPrice(String),
+}
+
+/// Price of a model, effective from a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ModelPrice {
+    /// Price per 1K prompt tokens, in the pricing currency
+    pub input_per_1k: f64,
+    /// Price per 1K completion tokens, in the pricing currency
+    pub output_per_1k: f64,
+    /// Time from which the price applies
+    pub effective_from: DateTime<Utc>,
+    /// Currency the price was entered in, when it was converted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entered_currency: Option<String>,
+    /// Why the price was entered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl ModelPrice {
+    /// Price effective from a point in time
+    pub fn new(input_per_1k: f64, output_per_1k: f64, effective_from: DateTime<Utc>) -> Self {
+        Self {
+            input_per_1k,
+            output_per_1k,
+            effective_from,
+            entered_currency: None,
+            note: None,
+        }
+    }
+
+    /// Cost of a call's tokens at this price
+    pub fn cost(&self, prompt_tokens: usize, completion_tokens: usize) -> f64 {
+        (self.input_per_1k * prompt_tokens as f64) / 1000.0
+            + (self.output_per_1k * completion_tokens as f64) / 1000.0
+    }
+}
+
+/// Usage of a call, as kept in the ledger
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UsageRecord {
+    /// Request, as identified in the routing decision log
+    pub request_id: String,
+    /// Time the call completed
+    pub timestamp: DateTime<Utc>,
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    pub user: Option<String>,
+    pub model: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    /// Cost of the call in the pricing currency
+    pub cost: f64,
+}
+
+/// Recomputed cost of a call in the ledger
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostCorrection {
+    pub request_id: String,
+    pub tenant: Option<String>,
+    pub user: Option<String>,
+    pub previous_cost: f64,
+    pub cost: f64,
+}
+
+impl CostCorrection {
+    /// Change of the call's cost
+    pub fn delta(&self) -> f64 {
+        self.cost - self.previous_cost
+    }
+}
+
+/// Outcome of entering a price
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CostBackfill {
+    pub model: String,
+    /// Price entered
+    pub price: ModelPrice,
+    /// Currency of the costs
+    pub currency: String,
+    /// Calls in the ledger whose cost was recomputed
+    pub recomputed: usize,
+    /// Sum of the previous costs of the recomputed calls
+    pub previous_cost: f64,
+    /// Sum of the recomputed costs
+    pub cost: f64,
+    /// Calls whose cost changed
+    #[serde(skip)]
+    pub corrections: Vec<CostCorrection>,
+}
 
 /// Cost calculator for LLM API calls
 #[derive(Debug)]
 pub struct CostCalculator {
-    /// Cost per 1K tokens for input (prompt) by model
-    input_costs: RwLock<HashMap<String, f64>>,
-    /// Cost per 1K tokens for output (completion) by model
-    output_costs: RwLock<HashMap<String, f64>>,
+    /// Currency prices and costs are kept in
+    currency: String,
+    /// Value of a unit of other currencies in the pricing currency
+    exchange_rates: HashMap<String, f64>,
+    /// Prices of each model, by effective time
+    prices: RwLock<HashMap<String, Vec<ModelPrice>>>,
+    /// Calls kept for recomputing their costs
+    ledger_capacity: usize,
+    /// Usage of the most recent calls, oldest first
+    ledger: Mutex<VecDeque<UsageRecord>>,
 }
 
 impl CostCalculator {
     /// Create a new cost calculator with default costs
     pub fn new() -> Self {
-        let mut input_costs = HashMap::new();
-        let mut output_costs = HashMap::new();
-
-        // OpenAI models
-        input_costs.insert("gpt-4".to_string(), 0.03);
-        output_costs.insert("gpt-4".to_string(), 0.06);
-
-        input_costs.insert("gpt-4-32k".to_string(), 0.06);
-        output_costs.insert("gpt-4-32k".to_string(), 0.12);
-
-        input_costs.insert("gpt-3.5-turbo".to_string(), 0.0015);
-        output_costs.insert("gpt-3.5-turbo".to_string(), 0.002);
-
-        input_costs.insert("gpt-3.5-turbo-16k".to_string(), 0.003);
-        output_costs.insert("gpt-3.5-turbo-16k".to_string(), 0.004);
-
-        // Anthropic models
-        input_costs.insert("claude-2".to_string(), 0.01102);
-        output_costs.insert("claude-2".to_string(), 0.03268);
+        Self::with_currency(&PricingConfig::default())
+    }
 
-        input_costs.insert("claude-instant-1".to_string(), 0.00163);
-        output_costs.insert("claude-instant-1".to_string(), 0.00551);
+    /// Create a cost calculator with the default costs and the configured
+    /// prices
+    ///
+    /// Fails on prices in a currency without an exchange rate.
+    pub fn from_config(config: &PricingConfig) -> Result<Self, PricingError> {
+        let calculator = Self::with_currency(config);
+        for price in &config.prices {
+            let effective_from = price.effective_from.unwrap_or(DateTime::UNIX_EPOCH);
+            let mut entered =
+                ModelPrice::new(price.input_per_1k, price.output_per_1k, effective_from);
+            entered.entered_currency = price.currency.clone();
+            calculator.set_price(&price.model, entered)?;
+        }
+        Ok(calculator)
+    }
 
-        // Default for unknown models
-        input_costs.insert("default".to_string(), 0.001);
-        output_costs.insert("default".to_string(), 0.002);
+    /// Calculator with the default costs, in the configured currency
+    fn with_currency(config: &PricingConfig) -> Self {
+        let defaults = [
+            // OpenAI models
+            ("gpt-4", 0.03, 0.06),
+            ("gpt-4-32k", 0.06, 0.12),
+            ("gpt-3.5-turbo", 0.0015, 0.002),
+            ("gpt-3.5-turbo-16k", 0.003, 0.004),
+            // Anthropic models
+            ("claude-2", 0.01102, 0.03268),
+            ("claude-instant-1", 0.00163, 0.00551),
+            // Default for unknown models
+            (DEFAULT_MODEL, 0.001, 0.002),
+        ];
+        let prices = defaults
+            .into_iter()
+            .map(|(model, input, output)| {
+                (
+                    model.to_string(),
+                    vec![ModelPrice::new(input, output, DateTime::UNIX_EPOCH)],
+                )
+            })
+            .collect();
 
         Self {
-            input_costs: RwLock::new(input_costs),
-            output_costs: RwLock::new(output_costs),
+            currency: config.currency.clone(),
+            exchange_rates: config.exchange_rates.clone(),
+            prices: RwLock::new(prices),
+            ledger_capacity: config.ledger_capacity,
+            ledger: Mutex::new(VecDeque::new()),
         }
     }
 
+    /// Currency of prices and costs
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
     /// Add or update cost for a model
+    ///
+    /// The cost applies from now on.
     pub fn set_model_cost(
         &self,
         model_id: &str,
         input_cost: f64,
         output_cost: f64,
     ) -> Result<(), String> {
-        let mut input_costs = self.input_costs.write().map_err(|e| e.to_string())?;
-        let mut output_costs = self.output_costs.write().map_err(|e| e.to_string())?;
+        self.set_price(
+            model_id,
+            ModelPrice::new(input_cost, output_cost, Utc::now()),
+        )
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+    }
+
+    /// Enter a price of a model, replacing any price effective from the
+    /// same time
+    ///
+    /// A price entered in another currency (`entered_currency`) is
+    /// converted to the pricing currency. The costs of the calls in the
+    /// ledger the price applies to are recomputed.
+    pub fn set_price(
+        &self,
+        model_id: &str,
+        mut price: ModelPrice,
+    ) -> Result<CostBackfill, PricingError> {
+        let valid = |value: f64| value.is_finite() && value >= 0.0;
+        if !valid(price.input_per_1k) || !valid(price.output_per_1k) {
+            return Err(PricingError::InvalidPrice(
+                "prices must be finite and not negative".to_string(),
+            ));
+        }
+        match price.entered_currency.as_deref() {
+            Some(currency) if !currency.eq_ignore_ascii_case(&self.currency) => {
+                let rate = self
+                    .exchange_rates
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(currency))
+                    .map(|(_, rate)| *rate)
+                    .ok_or_else(|| PricingError::UnknownCurrency(currency.to_string()))?;
+                price.input_per_1k *= rate;
+                price.output_per_1k *= rate;
+            }
+            _ => price.entered_currency = None,
+        }
+
+        {
+            let mut prices = self.prices.write().unwrap();
+            let history = prices.entry(model_id.to_string()).or_default();
+            history.retain(|entered| entered.effective_from != price.effective_from);
+            let at =
+                history.partition_point(|entered| entered.effective_from < price.effective_from);
+            history.insert(at, price.clone());
+        }
+
+        let mut backfill = CostBackfill {
+            model: model_id.to_string(),
+            price: price.clone(),
+            currency: self.currency.clone(),
+            recomputed: 0,
+            previous_cost: 0.0,
+            cost: 0.0,
+            corrections: Vec::new(),
+        };
+        let mut ledger = self.ledger.lock().unwrap();
+        for record in ledger.iter_mut() {
+            if record.model != model_id || record.timestamp < price.effective_from {
+                continue;
+            }
+            let cost = self
+                .price_at(model_id, record.timestamp)
+                .map_or(record.cost, |price| {
+                    price.cost(
+                        record.prompt_tokens as usize,
+                        record.completion_tokens as usize,
+                    )
+                });
+            backfill.recomputed += 1;
+            backfill.previous_cost += record.cost;
+            backfill.cost += cost;
+            if cost != record.cost {
+                backfill.corrections.push(CostCorrection {
+                    request_id: record.request_id.clone(),
+                    tenant: record.tenant.clone(),
+                    user: record.user.clone(),
+                    previous_cost: record.cost,
+                    cost,
+                });
+                record.cost = cost;
+            }
+        }
+        Ok(backfill)
+    }
+
+    /// Price of a model in effect at a point in time, falling back on the
+    /// default price
+    pub fn price_at(&self, model_id: &str, at: DateTime<Utc>) -> Option<ModelPrice> {
+        let prices = self.prices.read().ok()?;
+        let effective = |model: &str| {
+            prices
+                .get(model)?
+                .iter()
+                .rev()
+                .find(|price| price.effective_from <= at)
+                .cloned()
+        };
+        effective(model_id).or_else(|| effective(DEFAULT_MODEL))
+    }
 
-        input_costs.insert(model_id.to_string(), input_cost);
-        output_costs.insert(model_id.to_string(), output_cost);
+    /// Price histories of every model, oldest prices first
+    pub fn prices(&self) -> BTreeMap<String, Vec<ModelPrice>> {
+        self.prices
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(model, history)| (model.clone(), history.clone()))
+            .collect()
+    }
 
-        Ok(())
+    /// Cost per 1K input and output tokens of a model, unless it has no
+    /// costs of its own
+    pub fn model_cost(&self, model_id: &str) -> Option<(f64, f64)> {
+        let prices = self.prices.read().ok()?;
+        let now = Utc::now();
+        let price = prices
+            .get(model_id)?
+            .iter()
+            .rev()
+            .find(|price| price.effective_from <= now)?;
+        Some((price.input_per_1k, price.output_per_1k))
     }
 
     /// Calculate the cost of an LLM API call
@@ -69,20 +341,56 @@ impl CostCalculator {
         prompt_tokens: usize,
         completion_tokens: usize,
     ) -> Result<f64, String> {
-        let input_costs = self.input_costs.read().map_err(|e| e.to_string())?;
-        let output_costs = self.output_costs.read().map_err(|e| e.to_string())?;
+        self.calculate_cost_at(model_id, prompt_tokens, completion_tokens, Utc::now())
+    }
 
-        let input_cost = input_costs
-            .get(model_id)
-            .unwrap_or_else(|| input_costs.get("default").unwrap());
-        let output_cost = output_costs
-            .get(model_id)
-            .unwrap_or_else(|| output_costs.get("default").unwrap());
+    /// Calculate the cost of an LLM API call completed at a point in time
+    pub fn calculate_cost_at(
+        &self,
+        model_id: &str,
+        prompt_tokens: usize,
+        completion_tokens: usize,
+        at: DateTime<Utc>,
+    ) -> Result<f64, String> {
+        self.price_at(model_id, at)
+            .map(|price| price.cost(prompt_tokens, completion_tokens))
+            .ok_or_else(|| format!("No price of model {}", model_id))
+    }
 
-        let prompt_cost = (*input_cost * prompt_tokens as f64) / 1000.0;
-        let completion_cost = (*output_cost * completion_tokens as f64) / 1000.0;
+    /// Calculate the cost of a call and keep its usage in the ledger
+    ///
+    /// The cost of the record is replaced by the calculated one.
+    pub fn record_usage(&self, mut usage: UsageRecord) -> Result<f64, String> {
+        usage.cost = self.calculate_cost_at(
+            &usage.model,
+            usage.prompt_tokens as usize,
+            usage.completion_tokens as usize,
+            usage.timestamp,
+        )?;
+        let cost = usage.cost;
+        if self.ledger_capacity > 0 {
+            let mut ledger = self.ledger.lock().unwrap();
+            if ledger.len() >= self.ledger_capacity {
+                ledger.pop_front();
+            }
+            ledger.push_back(usage);
+        }
+        Ok(cost)
+    }
+
+    /// Usage of the calls in the ledger, oldest first
+    pub fn ledger(&self) -> Vec<UsageRecord> {
+        self.ledger.lock().unwrap().iter().cloned().collect()
+    }
 
-        Ok(prompt_cost + completion_cost)
+    /// Drop a data subject's calls from the ledger
+    ///
+    /// Returns how many calls were dropped.
+    pub fn erase(&self, subject: &ErasureSubject) -> usize {
+        let mut ledger = self.ledger.lock().unwrap();
+        let before = ledger.len();
+        ledger.retain(|record| !subject.matches(record.tenant.as_deref(), record.user.as_deref()));
+        before - ledger.len()
     }
 }
 
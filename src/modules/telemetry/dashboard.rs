@@ -0,0 +1,296 @@
+//! Grafana Dashboard Generation
+//!
+//! This module generates Grafana dashboard JSON from the metrics catalog, so
+//! panels query exactly the names and labels the router exposes. The
+//! generated dashboard is checked in under `deployment/grafana` and
+//! regenerated with `intellirouter generate-dashboard`.
+
+use serde_json::{json, Value};
+
+use super::catalog::{self, MetricKind, MetricSpec, METRICS};
+
+/// Grafana dashboard schema version the output targets
+const SCHEMA_VERSION: u32 = 39;
+
+/// Quantiles charted for histograms
+const QUANTILES: &[f64] = &[0.5, 0.95, 0.99];
+
+/// Panels per row of the grid
+const PANELS_PER_ROW: u32 = 2;
+
+/// Labels exposed as dashboard variables
+const VARIABLE_LABELS: &[&str] = &["service", "env"];
+
+/// Options of the generated dashboard
+#[derive(Debug, Clone)]
+pub struct DashboardOptions {
+    pub title: String,
+    pub uid: String,
+    /// UID of the Prometheus data source
+    pub datasource_uid: String,
+}
+
+impl Default for DashboardOptions {
+    fn default() -> Self {
+        Self {
+            title: "IntelliRouter".to_string(),
+            uid: "intellirouter".to_string(),
+            datasource_uid: "${datasource}".to_string(),
+        }
+    }
+}
+
+/// Generate the dashboard for every metric in the catalog
+pub fn generate_dashboard(options: &DashboardOptions) -> Value {
+    let datasource = json!({ "type": "prometheus", "uid": options.datasource_uid });
+
+    let mut panels = Vec::new();
+    let mut id = 1;
+    let mut y = 0;
+    for (group, specs) in groups() {
+        panels.push(json!({
+            "id": id,
+            "type": "row",
+            "title": group_title(group),
+            "collapsed": false,
+            "gridPos": { "h": 1, "w": 24, "x": 0, "y": y },
+            "panels": [],
+        }));
+        id += 1;
+        y += 1;
+
+        for (index, spec) in specs.iter().enumerate() {
+            let index = index as u32;
+            let width = 24 / PANELS_PER_ROW;
+            let grid_pos = json!({
+                "h": 8,
+                "w": width,
+                "x": (index % PANELS_PER_ROW) * width,
+                "y": y + (index / PANELS_PER_ROW) * 8,
+            });
+            panels.push(panel(id, spec, grid_pos, &datasource));
+            id += 1;
+        }
+        y += (specs.len() as u32).div_ceil(PANELS_PER_ROW) * 8;
+    }
+
+    let mut variables = vec![json!({
+        "name": "datasource",
+        "label": "Data source",
+        "type": "datasource",
+        "query": "prometheus",
+    })];
+    for label in VARIABLE_LABELS {
+        variables.push(json!({
+            "name": label,
+            "label": label,
+            "type": "query",
+            "datasource": datasource,
+            "query": format!(
+                "label_values({}, {})",
+                METRICS[0].prometheus_name(),
+                label
+            ),
+            "refresh": 2,
+            "includeAll": true,
+            "multi": true,
+            "allValue": ".*",
+            "current": { "text": "All", "value": "$__all" },
+        }));
+    }
+
+    json!({
+        "uid": options.uid,
+        "title": options.title,
+        "description": "Generated from the IntelliRouter metrics catalog",
+        "tags": ["intellirouter", "generated"],
+        "schemaVersion": SCHEMA_VERSION,
+        "editable": true,
+        "time": { "from": "now-6h", "to": "now" },
+        "refresh": "30s",
+        "templating": { "list": variables },
+        "annotations": { "list": [anomaly_annotation(&datasource)] },
+        "panels": panels,
+    })
+}
+
+/// Annotation marking the periods tenants had an active anomaly
+fn anomaly_annotation(datasource: &Value) -> Value {
+    json!({
+        "name": "Anomalies",
+        "datasource": datasource,
+        "enable": true,
+        "iconColor": "red",
+        "expr": format!("{} > 0", catalog::prometheus_name(catalog::ANOMALIES_ACTIVE)),
+        "step": "60s",
+        "titleFormat": "{{signal}} anomaly",
+        "textFormat": "tenant {{tenant}}",
+        "tagKeys": "tenant,signal",
+        "useValueForTime": false,
+    })
+}
+
+/// Metrics grouped by the second segment of their name, in catalog order
+fn groups() -> Vec<(&'static str, Vec<&'static MetricSpec>)> {
+    let mut groups: Vec<(&'static str, Vec<&'static MetricSpec>)> = Vec::new();
+    for spec in METRICS {
+        let group = spec.name.split('.').nth(1).unwrap_or("other");
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, specs)) => specs.push(spec),
+            None => groups.push((group, vec![spec])),
+        }
+    }
+    groups
+}
+
+fn group_title(group: &str) -> String {
+    match group {
+        "http" => "HTTP".to_string(),
+        "llm" => "LLM calls".to_string(),
+        "routing" => "Routing".to_string(),
+        "telemetry" => "Telemetry export".to_string(),
+        "provider" => "Provider connections".to_string(),
+        "streams" => "Streaming clients".to_string(),
+        "coalescing" => "Request coalescing".to_string(),
+        "anomalies" => "Anomalies".to_string(),
+        "metering" => "Metering".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Label selector restricting a query to the dashboard variables
+fn selector(spec: &MetricSpec) -> String {
+    let matchers: Vec<String> = VARIABLE_LABELS
+        .iter()
+        .filter(|label| spec.labels.contains(label))
+        .map(|label| format!("{}=~\"${}\"", label, label))
+        .collect();
+    if matchers.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", matchers.join(","))
+    }
+}
+
+/// Label a panel splits its series by
+fn split_label(spec: &MetricSpec) -> Option<&'static str> {
+    spec.labels
+        .iter()
+        .copied()
+        .find(|label| !VARIABLE_LABELS.contains(label))
+}
+
+/// PromQL targets charting a metric
+fn targets(spec: &MetricSpec) -> Vec<Value> {
+    let name = spec.prometheus_name();
+    let selector = selector(spec);
+    let split = split_label(spec);
+    let legend = split
+        .map(|label| format!("{{{{{}}}}}", label))
+        .unwrap_or_else(|| spec.title.to_string());
+
+    match spec.kind {
+        MetricKind::Counter => {
+            let by = split.map(|l| format!(" by ({})", l)).unwrap_or_default();
+            vec![json!({
+                "refId": "A",
+                "expr": format!("sum{}(rate({}{}[$__rate_interval]))", by, name, selector),
+                "legendFormat": legend,
+            })]
+        }
+        MetricKind::Gauge => {
+            let by = split.map(|l| format!(" by ({})", l)).unwrap_or_default();
+            vec![json!({
+                "refId": "A",
+                "expr": format!("avg{}({}{})", by, name, selector),
+                "legendFormat": legend,
+            })]
+        }
+        MetricKind::Histogram => QUANTILES
+            .iter()
+            .enumerate()
+            .map(|(index, quantile)| {
+                let by = match split {
+                    Some(label) => format!("le, {}", label),
+                    None => "le".to_string(),
+                };
+                let percentile = format!("p{}", (quantile * 100.0).round());
+                json!({
+                    "refId": ((b'A' + index as u8) as char).to_string(),
+                    "expr": format!(
+                        "histogram_quantile({}, sum by ({}) (rate({}_bucket{}[$__rate_interval])))",
+                        quantile, by, name, selector
+                    ),
+                    "legendFormat": match split {
+                        Some(label) => format!("{} {{{{{}}}}}", percentile, label),
+                        None => percentile,
+                    },
+                })
+            })
+            .collect(),
+    }
+}
+
+fn panel(id: u32, spec: &MetricSpec, grid_pos: Value, datasource: &Value) -> Value {
+    json!({
+        "id": id,
+        "type": "timeseries",
+        "title": spec.title,
+        "description": format!("{} ({})", spec.prometheus_name(), kind_name(spec.kind)),
+        "datasource": datasource,
+        "gridPos": grid_pos,
+        "fieldConfig": { "defaults": { "unit": spec.unit }, "overrides": [] },
+        "options": { "legend": { "displayMode": "list", "placement": "bottom" } },
+        "targets": targets(spec),
+    })
+}
+
+fn kind_name(kind: MetricKind) -> &'static str {
+    match kind {
+        MetricKind::Counter => "counter",
+        MetricKind::Gauge => "gauge",
+        MetricKind::Histogram => "histogram",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_metric_charted() {
+        let dashboard = generate_dashboard(&DashboardOptions::default());
+        let exprs: Vec<String> = dashboard["panels"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|panel| panel["targets"].as_array().cloned().unwrap_or_default())
+            .map(|target| target["expr"].as_str().unwrap().to_string())
+            .collect();
+
+        for spec in METRICS {
+            let name = spec.prometheus_name();
+            assert!(
+                exprs.iter().any(|expr| expr.contains(&name)),
+                "{} is not charted",
+                name
+            );
+        }
+        assert!(exprs.contains(
+            &"histogram_quantile(0.95, sum by (le, model) (rate(intellirouter_llm_latency_bucket{service=~\"$service\",env=~\"$env\"}[$__rate_interval])))".to_string()
+        ));
+    }
+
+    #[test]
+    fn test_checked_in_dashboard_current() {
+        let checked_in: Value = serde_json::from_str(include_str!(
+            "../../../deployment/grafana/intellirouter.json"
+        ))
+        .unwrap();
+        assert_eq!(
+            checked_in,
+            generate_dashboard(&DashboardOptions::default()),
+            "regenerate with `intellirouter generate-dashboard -o deployment/grafana/intellirouter.json`"
+        );
+    }
+}
@@ -0,0 +1,586 @@
+//! Telemetry Export
+//!
+//! This module exports per-request telemetry records (model, tokens, cost,
+//! latency, cache status, tenant) to analytics stores. Records are queued
+//! without blocking the request path and sent in batches by a background
+//! task to every configured [`TelemetrySink`]; ClickHouse and BigQuery sinks
+//! are provided.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use super::catalog;
+use crate::config::{BigQueryExportConfig, ClickHouseExportConfig, TelemetryExportConfig};
+
+/// Delay before the first retry of a failed batch, doubled on each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+
+/// Error exporting telemetry records
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Request failed: {0}")]
+    Request(String),
+
+    #[error("Sink rejected records: {0}")]
+    Rejected(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+}
+
+/// Whether a request was served from a cache
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    /// The request did not go through a cache
+    #[default]
+    Bypass,
+}
+
+/// Telemetry record of a single request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryRecord {
+    pub request_id: String,
+    /// Time the request completed
+    pub timestamp: DateTime<Utc>,
+    pub tenant: Option<String>,
+    /// End user reported by the client
+    pub user: Option<String>,
+    /// Model requested by the client
+    pub requested_model: String,
+    /// Model that served the request
+    pub model: String,
+    pub provider: String,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+    /// Estimated cost in USD, if pricing is known
+    pub cost_usd: Option<f64>,
+    pub latency_ms: u64,
+    pub cache_status: CacheStatus,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Data categories flagged by guardrails (e.g. `pii`)
+    #[serde(default)]
+    pub flagged_categories: Vec<String>,
+    /// ISO 639-1 code of the language detected in the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// Destination of exported telemetry records
+#[async_trait]
+pub trait TelemetrySink: Send + Sync {
+    /// Name of the sink, used in logs and metrics
+    fn name(&self) -> &str;
+
+    /// Write a batch of records
+    async fn export(&self, records: &[TelemetryRecord]) -> Result<(), ExportError>;
+}
+
+/// ClickHouse sink inserting `JSONEachRow` batches through the HTTP interface
+pub struct ClickHouseSink {
+    client: Client,
+    config: ClickHouseExportConfig,
+}
+
+impl ClickHouseSink {
+    /// Create a ClickHouse sink
+    pub fn new(config: ClickHouseExportConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+
+    /// Insert statement for the configured table
+    fn query(&self) -> String {
+        format!(
+            "INSERT INTO {}.{} FORMAT JSONEachRow",
+            self.config.database, self.config.table
+        )
+    }
+
+    /// Request to the HTTP interface, authenticated as the configured user
+    fn request(&self) -> reqwest::RequestBuilder {
+        let mut request = self.client.post(&self.config.url);
+        if let Some(username) = &self.config.username {
+            request = request.header("X-ClickHouse-User", username);
+        }
+        if let Some(password) = &self.config.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+        request
+    }
+
+    /// Run a statement with bound parameters, returning its output
+    async fn execute(
+        &self,
+        statement: String,
+        params: &[(&str, &str)],
+    ) -> Result<String, ExportError> {
+        let params: Vec<_> = params
+            .iter()
+            .map(|(name, value)| (format!("param_{}", name), *value))
+            .collect();
+        let response = self
+            .request()
+            .query(&params)
+            .body(statement)
+            .send()
+            .await
+            .map_err(|e| ExportError::Request(e.to_string()))?;
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(ExportError::Rejected(format!("{}: {}", status, body)));
+        }
+        Ok(body)
+    }
+
+    /// Delete the records of a tenant, or of one of its users, returning how
+    /// many were deleted
+    pub async fn delete_subject(
+        &self,
+        tenant: &str,
+        user: Option<&str>,
+    ) -> Result<usize, ExportError> {
+        let mut params = vec![("tenant", tenant)];
+        if let Some(user) = user {
+            params.push(("user", user));
+        }
+        let (count, delete) = subject_statements(
+            &format!("{}.{}", self.config.database, self.config.table),
+            user.is_some(),
+        );
+
+        let output = self.execute(count, &params).await?;
+        let row: Value = serde_json::from_str(output.trim())
+            .map_err(|e| ExportError::Rejected(format!("Invalid count: {}", e)))?;
+        // 64-bit integers are quoted in JSON output by default
+        let records = match &row["records"] {
+            Value::String(count) => count.parse().ok(),
+            count => count.as_u64(),
+        }
+        .ok_or_else(|| ExportError::Rejected(format!("Invalid count: {}", row)))?;
+        if records > 0 {
+            self.execute(delete, &params).await?;
+        }
+        Ok(records as usize)
+    }
+}
+
+/// Statements counting and deleting the records of a tenant, or of one of
+/// its users
+fn subject_statements(table: &str, user: bool) -> (String, String) {
+    let condition = if user {
+        "tenant = {tenant:String} AND user = {user:String}"
+    } else {
+        "tenant = {tenant:String}"
+    };
+    (
+        format!(
+            "SELECT count() AS records FROM {} WHERE {} FORMAT JSONEachRow",
+            table, condition
+        ),
+        format!("DELETE FROM {} WHERE {}", table, condition),
+    )
+}
+
+/// Encode records as newline-delimited JSON
+fn json_each_row(records: &[TelemetryRecord]) -> Result<String, ExportError> {
+    let mut body = String::new();
+    for record in records {
+        let row = serde_json::to_string(record).map_err(|e| ExportError::Config(e.to_string()))?;
+        body.push_str(&row);
+        body.push('\n');
+    }
+    Ok(body)
+}
+
+#[async_trait]
+impl TelemetrySink for ClickHouseSink {
+    fn name(&self) -> &str {
+        "clickhouse"
+    }
+
+    async fn export(&self, records: &[TelemetryRecord]) -> Result<(), ExportError> {
+        let query = self.query();
+        let response = self
+            .request()
+            .query(&[
+                ("query", query.as_str()),
+                ("date_time_input_format", "best_effort"),
+            ])
+            .body(json_each_row(records)?)
+            .send()
+            .await
+            .map_err(|e| ExportError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ExportError::Rejected(format!("{}: {}", status, body)));
+        }
+        Ok(())
+    }
+}
+
+/// BigQuery sink using the streaming `insertAll` API
+pub struct BigQuerySink {
+    client: Client,
+    config: BigQueryExportConfig,
+}
+
+impl BigQuerySink {
+    /// Create a BigQuery sink
+    pub fn new(config: BigQueryExportConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+
+    /// URL of the table's `insertAll` endpoint
+    fn insert_url(&self) -> String {
+        format!(
+            "{}/bigquery/v2/projects/{}/datasets/{}/tables/{}/insertAll",
+            self.config.endpoint.trim_end_matches('/'),
+            self.config.project_id,
+            self.config.dataset,
+            self.config.table
+        )
+    }
+}
+
+/// Build an `insertAll` body, using request IDs for best-effort deduplication
+fn insert_all_body(records: &[TelemetryRecord]) -> Value {
+    let rows: Vec<Value> = records
+        .iter()
+        .map(|record| json!({ "insertId": record.request_id, "json": record }))
+        .collect();
+    json!({ "rows": rows })
+}
+
+#[async_trait]
+impl TelemetrySink for BigQuerySink {
+    fn name(&self) -> &str {
+        "bigquery"
+    }
+
+    async fn export(&self, records: &[TelemetryRecord]) -> Result<(), ExportError> {
+        let token = self
+            .config
+            .access_token
+            .clone()
+            .or_else(|| std::env::var("BIGQUERY_ACCESS_TOKEN").ok())
+            .ok_or_else(|| ExportError::Config("No BigQuery access token".to_string()))?;
+
+        let response = self
+            .client
+            .post(self.insert_url())
+            .bearer_auth(token)
+            .json(&insert_all_body(records))
+            .send()
+            .await
+            .map_err(|e| ExportError::Request(e.to_string()))?;
+        let status = response.status();
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ExportError::Request(e.to_string()))?;
+
+        if !status.is_success() {
+            return Err(ExportError::Rejected(format!("{}: {}", status, body)));
+        }
+        match body.get("insertErrors").and_then(Value::as_array) {
+            Some(errors) if !errors.is_empty() => Err(ExportError::Rejected(format!(
+                "{} row(s) failed: {}",
+                errors.len(),
+                errors[0]
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Batching exporter feeding telemetry sinks
+///
+/// [`record`](Self::record) never blocks: when the queue is full the record
+/// is dropped and counted. Batches are flushed when they reach the batch size
+/// or the flush interval elapses, and when the exporter is dropped.
+#[derive(Debug)]
+pub struct TelemetryExporter {
+    sender: mpsc::Sender<TelemetryRecord>,
+    dropped: AtomicU64,
+}
+
+impl TelemetryExporter {
+    /// Create an exporter with the sinks named in the configuration
+    ///
+    /// Returns `None` when export is disabled or no sink is configured.
+    pub fn from_config(config: &TelemetryExportConfig) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+
+        let mut sinks: Vec<Arc<dyn TelemetrySink>> = Vec::new();
+        if let Some(clickhouse) = &config.clickhouse {
+            sinks.push(Arc::new(ClickHouseSink::new(clickhouse.clone())));
+        }
+        if let Some(bigquery) = &config.bigquery {
+            sinks.push(Arc::new(BigQuerySink::new(bigquery.clone())));
+        }
+        if sinks.is_empty() {
+            warn!("Telemetry export is enabled but no sink is configured");
+            return None;
+        }
+
+        Some(Self::spawn(sinks, config))
+    }
+
+    /// Create an exporter and start its batching task
+    pub fn spawn(sinks: Vec<Arc<dyn TelemetrySink>>, config: &TelemetryExportConfig) -> Arc<Self> {
+        let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+        tokio::spawn(run_batches(
+            receiver,
+            sinks,
+            config.batch_size.max(1),
+            Duration::from_secs(config.flush_interval_secs.max(1)),
+            config.max_retries,
+        ));
+
+        Arc::new(Self {
+            sender,
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Queue a record for export
+    pub fn record(&self, record: TelemetryRecord) {
+        if self.sender.try_send(record).is_err() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            counter!(catalog::TELEMETRY_EXPORT_DROPPED, 1);
+        }
+    }
+
+    /// Number of records dropped because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// Collect records into batches and send them to the sinks
+async fn run_batches(
+    mut receiver: mpsc::Receiver<TelemetryRecord>,
+    sinks: Vec<Arc<dyn TelemetrySink>>,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_retries: u32,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut interval = tokio::time::interval(flush_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            record = receiver.recv() => match record {
+                Some(record) => {
+                    batch.push(record);
+                    if batch.len() >= batch_size {
+                        flush(&sinks, &mut batch, max_retries).await;
+                    }
+                }
+                None => {
+                    flush(&sinks, &mut batch, max_retries).await;
+                    return;
+                }
+            },
+            _ = interval.tick() => flush(&sinks, &mut batch, max_retries).await,
+        }
+    }
+}
+
+/// Send a batch to every sink, retrying failures with exponential backoff
+async fn flush(
+    sinks: &[Arc<dyn TelemetrySink>],
+    batch: &mut Vec<TelemetryRecord>,
+    max_retries: u32,
+) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let records = std::mem::take(batch);
+    futures::future::join_all(sinks.iter().map(|sink| {
+        let records = &records;
+        async move {
+            let mut attempt = 0;
+            loop {
+                match sink.export(records).await {
+                    Ok(()) => {
+                        debug!("Exported {} record(s) to {}", records.len(), sink.name());
+                        counter!(
+                            catalog::TELEMETRY_EXPORT_RECORDS, records.len() as u64,
+                            "sink" => sink.name().to_string()
+                        );
+                        return;
+                    }
+                    Err(e) if attempt < max_retries => {
+                        debug!("Export to {} failed, retrying: {}", sink.name(), e);
+                        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+                        attempt += 1;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Dropping {} record(s) after failed export to {}: {}",
+                            records.len(),
+                            sink.name(),
+                            e
+                        );
+                        counter!(
+                            catalog::TELEMETRY_EXPORT_FAILED, records.len() as u64,
+                            "sink" => sink.name().to_string()
+                        );
+                        return;
+                    }
+                }
+            }
+        }
+    }))
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Sink keeping the batches it receives, failing the first `failures` exports
+    #[derive(Default)]
+    struct MemorySink {
+        batches: Mutex<Vec<usize>>,
+        failures: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl TelemetrySink for MemorySink {
+        fn name(&self) -> &str {
+            "memory"
+        }
+
+        async fn export(&self, records: &[TelemetryRecord]) -> Result<(), ExportError> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(ExportError::Request("unavailable".to_string()));
+            }
+            self.batches.lock().unwrap().push(records.len());
+            Ok(())
+        }
+    }
+
+    fn record(id: &str) -> TelemetryRecord {
+        TelemetryRecord {
+            request_id: id.to_string(),
+            timestamp: Utc::now(),
+            tenant: Some("acme".to_string()),
+            user: None,
+            requested_model: "gpt-4".to_string(),
+            model: "gpt-4".to_string(),
+            provider: "openai".to_string(),
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+            cost_usd: Some(0.001),
+            latency_ms: 120,
+            cache_status: CacheStatus::Miss,
+            success: true,
+            error: None,
+            flagged_categories: Vec::new(),
+            language: None,
+        }
+    }
+
+    fn config(batch_size: usize) -> TelemetryExportConfig {
+        TelemetryExportConfig {
+            enabled: true,
+            batch_size,
+            flush_interval_secs: 3600,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batches_flushed_at_size_and_on_shutdown() {
+        let sink = Arc::new(MemorySink::default());
+        let exporter = TelemetryExporter::spawn(vec![sink.clone()], &config(2));
+        for id in ["a", "b", "c"] {
+            exporter.record(record(id));
+        }
+        drop(exporter);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(*sink.batches.lock().unwrap(), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_retried() {
+        let sink = Arc::new(MemorySink {
+            failures: Mutex::new(1),
+            ..Default::default()
+        });
+        let exporter = TelemetryExporter::spawn(vec![sink.clone()], &config(1));
+        exporter.record(record("a"));
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(*sink.batches.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_sink_payloads() {
+        let records = vec![record("a"), record("b")];
+
+        let rows = json_each_row(&records).unwrap();
+        assert_eq!(rows.lines().count(), 2);
+        let row: Value = serde_json::from_str(rows.lines().next().unwrap()).unwrap();
+        assert_eq!(row["cache_status"], "miss");
+        assert_eq!(row["tenant"], "acme");
+
+        let body = insert_all_body(&records);
+        assert_eq!(body["rows"][1]["insertId"], "b");
+        assert_eq!(body["rows"][0]["json"]["total_tokens"], 15);
+    }
+
+    #[test]
+    fn test_subject_statements() {
+        let (count, delete) = subject_statements("default.telemetry", true);
+        assert_eq!(
+            count,
+            "SELECT count() AS records FROM default.telemetry \
+             WHERE tenant = {tenant:String} AND user = {user:String} FORMAT JSONEachRow"
+        );
+        assert_eq!(
+            delete,
+            "DELETE FROM default.telemetry WHERE tenant = {tenant:String} AND user = {user:String}"
+        );
+
+        let (_, delete) = subject_statements("default.telemetry", false);
+        assert_eq!(
+            delete,
+            "DELETE FROM default.telemetry WHERE tenant = {tenant:String}"
+        );
+    }
+}
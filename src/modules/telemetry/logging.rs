@@ -0,0 +1,352 @@
+//! Runtime Log Configuration
+//!
+//! Logs are filtered by a base level and per-module levels, and written as
+//! pretty, compact or JSON output. The filter is installed behind a reload
+//! handle so the levels can be changed while the service runs, e.g. to turn
+//! on debug logging for one module while investigating a production issue,
+//! without a restart.
+//!
+//! The latest warnings and errors are also kept in memory, as samples for
+//! diagnostics.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::Context, layer::SubscriberExt, reload,
+    util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+use utoipa::ToSchema;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+/// Errors of the log configuration
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("Invalid log level for {target}: {level}")]
+    InvalidLevel { target: String, level: String },
+
+    #[error("Invalid log directive: {0}")]
+    InvalidDirective(String),
+
+    #[error("Logging is not initialized")]
+    NotInitialized,
+
+    #[error("Failed to initialize logging: {0}")]
+    Init(String),
+}
+
+/// Log levels in effect
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LogLevels {
+    /// Level of modules without a level of their own
+    pub level: String,
+    /// Levels of individual modules, keyed by module path
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl LogLevels {
+    /// Levels from the configured base level and module levels
+    pub fn from_config(level: &str, config: &LoggingConfig) -> Self {
+        Self {
+            level: level.to_string(),
+            modules: config
+                .modules
+                .iter()
+                .map(|(module, level)| (module.clone(), level.clone()))
+                .collect(),
+        }
+    }
+
+    /// Parse `RUST_LOG`-style directives of the form `level` or `module=level`
+    pub fn parse(directives: &str) -> Result<Self, LoggingError> {
+        let mut levels = Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+        };
+        for directive in directives.split(',').map(str::trim) {
+            match directive.split_once('=') {
+                _ if directive.is_empty() => {}
+                Some((module, level)) if is_module_path(module) => {
+                    levels.modules.insert(module.to_string(), level.to_string());
+                }
+                Some(_) => return Err(LoggingError::InvalidDirective(directive.to_string())),
+                None if is_module_path(directive) && parse_level(directive).is_err() => {
+                    // A bare module path enables all of its levels
+                    levels
+                        .modules
+                        .insert(directive.to_string(), "trace".to_string());
+                }
+                None => levels.level = directive.to_string(),
+            }
+        }
+        levels.validate()?;
+        Ok(levels)
+    }
+
+    /// Check that every level is a known level
+    pub fn validate(&self) -> Result<(), LoggingError> {
+        parse_level(&self.level).map_err(|_| LoggingError::InvalidLevel {
+            target: "default".to_string(),
+            level: self.level.clone(),
+        })?;
+        for (module, level) in &self.modules {
+            if !is_module_path(module) {
+                return Err(LoggingError::InvalidDirective(format!(
+                    "{}={}",
+                    module, level
+                )));
+            }
+            parse_level(level).map_err(|_| LoggingError::InvalidLevel {
+                target: module.clone(),
+                level: level.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Filter directives, the base level first
+    pub fn directives(&self) -> String {
+        std::iter::once(directive_level(&self.level))
+            .chain(
+                self.modules
+                    .iter()
+                    .map(|(module, level)| format!("{}={}", module, directive_level(level))),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Filter enabling these levels
+    fn filter(&self) -> Result<EnvFilter, LoggingError> {
+        self.validate()?;
+        EnvFilter::builder()
+            .parse(self.directives())
+            .map_err(|e| LoggingError::InvalidDirective(e.to_string()))
+    }
+}
+
+/// Parse a level name, accepting `warning` and `off`
+fn parse_level(level: &str) -> Result<LevelFilter, ()> {
+    match level.to_lowercase().as_str() {
+        "warning" => Ok(LevelFilter::WARN),
+        level => LevelFilter::from_str(level).map_err(|_| ()),
+    }
+}
+
+/// Level as written in a filter directive
+fn directive_level(level: &str) -> String {
+    parse_level(level)
+        .map(|level| level.to_string().to_lowercase())
+        .unwrap_or_else(|_| level.to_lowercase())
+}
+
+/// Whether `module` is a plain module path such as `hyper` or `a::b`
+fn is_module_path(module: &str) -> bool {
+    !module.is_empty()
+        && module.split("::").all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+/// Handle changing the log levels of the installed subscriber
+#[derive(Debug)]
+pub struct LogLevelHandle {
+    levels: Mutex<LogLevels>,
+    filter: reload::Handle<EnvFilter, Registry>,
+}
+
+impl LogLevelHandle {
+    /// Log levels in effect
+    pub fn levels(&self) -> LogLevels {
+        self.levels.lock().unwrap().clone()
+    }
+
+    /// Replace the log levels
+    pub fn set(&self, levels: LogLevels) -> Result<(), LoggingError> {
+        let filter = levels.filter()?;
+        let mut current = self.levels.lock().unwrap();
+        self.filter
+            .reload(filter)
+            .map_err(|e| LoggingError::Init(e.to_string()))?;
+        *current = levels;
+        Ok(())
+    }
+}
+
+static HANDLE: OnceLock<LogLevelHandle> = OnceLock::new();
+
+/// Warnings and errors kept as samples
+const ERROR_SAMPLES: usize = 50;
+
+/// Latest warnings and errors, oldest first
+static RECENT_ERRORS: Mutex<VecDeque<LogSample>> = Mutex::new(VecDeque::new());
+
+/// Warning or error logged by the service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LogSample {
+    /// When the event was logged
+    pub timestamp: DateTime<Utc>,
+    /// `WARN` or `ERROR`
+    pub level: String,
+    /// Module that logged the event
+    pub target: String,
+    /// Message of the event, followed by its other fields
+    pub message: String,
+}
+
+/// Latest warnings and errors logged, oldest first
+pub fn recent_errors() -> Vec<LogSample> {
+    RECENT_ERRORS.lock().unwrap().iter().cloned().collect()
+}
+
+/// Keep a warning or error as a sample, dropping the oldest beyond capacity
+fn record_error(sample: LogSample) {
+    let mut recent = RECENT_ERRORS.lock().unwrap();
+    if recent.len() >= ERROR_SAMPLES {
+        recent.pop_front();
+    }
+    recent.push_back(sample);
+}
+
+/// Layer keeping the warnings and errors that pass the filter as samples
+struct ErrorSamples;
+
+impl<S: Subscriber> Layer<S> for ErrorSamples {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if *metadata.level() > Level::WARN {
+            return;
+        }
+        let mut message = MessageVisitor::default();
+        event.record(&mut message);
+        record_error(LogSample {
+            timestamp: Utc::now(),
+            level: metadata.level().to_string(),
+            target: metadata.target().to_string(),
+            message: message.0,
+        });
+    }
+}
+
+/// Formats the fields of an event, its message first
+#[derive(Default)]
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            self.0.insert_str(0, &format!("{:?}", value));
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+/// Handle of the installed subscriber, if logging was initialized
+pub fn handle() -> Option<&'static LogLevelHandle> {
+    HANDLE.get()
+}
+
+/// Install the global subscriber
+///
+/// Levels come from `RUST_LOG` when it is set, and from `level` and the
+/// configured module levels otherwise.
+pub fn init(level: &str, config: &LoggingConfig) -> Result<&'static LogLevelHandle, LoggingError> {
+    let levels = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => LogLevels::parse(&directives)?,
+        _ => LogLevels::from_config(level, config),
+    };
+    let (filter, reload_handle) = reload::Layer::new(levels.filter()?);
+
+    let format = config.format;
+    tracing_subscriber::registry()
+        .with(filter)
+        .with((format == LogFormat::Pretty).then(|| fmt::layer().pretty()))
+        .with((format == LogFormat::Compact).then(|| fmt::layer().compact()))
+        .with((format == LogFormat::Json).then(|| fmt::layer().json()))
+        .with(ErrorSamples)
+        .try_init()
+        .map_err(|e| LoggingError::Init(e.to_string()))?;
+
+    Ok(HANDLE.get_or_init(|| LogLevelHandle {
+        levels: Mutex::new(levels),
+        filter: reload_handle,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::subscriber::with_default;
+
+    #[test]
+    fn test_directives_from_config() {
+        let config = LoggingConfig {
+            format: LogFormat::Json,
+            modules: [("hyper".to_string(), "Warning".to_string())]
+                .into_iter()
+                .collect(),
+        };
+        let levels = LogLevels::from_config("info", &config);
+        assert_eq!(levels.directives(), "info,hyper=warn");
+        assert!(levels.filter().is_ok());
+    }
+
+    #[test]
+    fn test_parse_env_directives() {
+        let levels =
+            LogLevels::parse("warn, intellirouter::modules::router_core=debug,h2").unwrap();
+        assert_eq!(levels.level, "warn");
+        assert_eq!(
+            levels.modules["intellirouter::modules::router_core"],
+            "debug"
+        );
+        assert_eq!(levels.modules["h2"], "trace");
+    }
+
+    #[test]
+    fn test_invalid_levels_are_rejected() {
+        let mut levels = LogLevels::parse("info").unwrap();
+        levels
+            .modules
+            .insert("intellirouter".to_string(), "loud".to_string());
+        assert!(matches!(
+            levels.validate(),
+            Err(LoggingError::InvalidLevel { .. })
+        ));
+
+        levels.modules.clear();
+        levels
+            .modules
+            .insert("a[span]".to_string(), "debug".to_string());
+        assert!(matches!(
+            levels.validate(),
+            Err(LoggingError::InvalidDirective(_))
+        ));
+        assert!(LogLevels::parse("a[span]=debug").is_err());
+    }
+
+    #[test]
+    fn test_error_samples() {
+        let subscriber = tracing_subscriber::registry().with(ErrorSamples);
+        with_default(subscriber, || {
+            tracing::info!("Listening");
+            tracing::warn!(model = "gpt-4o", "Canary failed");
+        });
+        let sample = recent_errors().pop().unwrap();
+        assert_eq!(sample.level, "WARN");
+        assert_eq!(sample.message, "Canary failed model=\"gpt-4o\"");
+    }
+}
@@ -0,0 +1,557 @@
+//! Usage Metering
+//!
+//! This module turns per-request telemetry records into billing events. The
+//! usage of each tenant is summed per meter (tokens, requests and tokens
+//! served from a cache) over fixed periods. A period is billed once it has
+//! been over for `grace_secs`; requests reported after that still count and
+//! are billed by a correction event carrying the usage added since.
+//!
+//! Every event has an idempotency key derived from its tenant, meter, period
+//! and revision, so pushing an event again never bills it twice. Events are
+//! kept for CSV export and pushed to Stripe's meter events API when it is
+//! configured.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use metrics::counter;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use super::{catalog, CacheStatus, TelemetryRecord};
+use crate::config::{MeteringConfig, StripeMeteringConfig};
+
+/// Tenant of requests without a tenant ID
+const DEFAULT_TENANT: &str = "default";
+
+/// Delay before the first retry of a failed push, doubled on each retry
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Error pushing billing events
+#[derive(Error, Debug)]
+pub enum MeteringError {
+    #[error("Request failed: {0}")]
+    Request(String),
+
+    #[error("Stripe rejected event: {0}")]
+    Rejected(String),
+
+    #[error("Configuration error: {0}")]
+    Config(String),
+}
+
+/// Quantity metered for billing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Meter {
+    /// Prompt and completion tokens of requests sent to a provider
+    Tokens,
+    /// Successful requests
+    Requests,
+    /// Tokens of requests served from a cache instead of a provider
+    CacheSavings,
+}
+
+impl Meter {
+    const ALL: [Meter; 3] = [Meter::Tokens, Meter::Requests, Meter::CacheSavings];
+
+    /// Name of the meter, as used in configuration and exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Meter::Tokens => "tokens",
+            Meter::Requests => "requests",
+            Meter::CacheSavings => "cache_savings",
+        }
+    }
+
+    /// Usage of a request on this meter
+    fn value(&self, record: &TelemetryRecord) -> u64 {
+        let cached = record.cache_status == CacheStatus::Hit;
+        match self {
+            Meter::Tokens if !cached => u64::from(record.total_tokens),
+            Meter::Requests if record.success => 1,
+            Meter::CacheSavings if cached => u64::from(record.total_tokens),
+            _ => 0,
+        }
+    }
+}
+
+/// Usage of a tenant on a meter over a period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BillingEvent {
+    /// Key identifying the event, stable across pushes
+    pub idempotency_key: String,
+    pub tenant: String,
+    pub meter: Meter,
+    pub period_start: DateTime<Utc>,
+    pub period_end: DateTime<Utc>,
+    /// Usage billed by this event, on top of the earlier events of the period
+    pub value: u64,
+    /// 0 for the first event of a period, then counting its corrections
+    pub revision: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl BillingEvent {
+    /// Whether the event corrects a period that was already billed
+    pub fn is_correction(&self) -> bool {
+        self.revision > 0
+    }
+}
+
+/// Header of the CSV export
+const CSV_HEADER: &str =
+    "idempotency_key,tenant,meter,period_start,period_end,value,revision,created_at";
+
+/// Encode billing events as CSV, one event per row after a header
+pub fn to_csv(events: &[BillingEvent]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for event in events {
+        let row = [
+            csv_field(&event.idempotency_key),
+            csv_field(&event.tenant),
+            event.meter.as_str().to_string(),
+            event.period_start.to_rfc3339(),
+            event.period_end.to_rfc3339(),
+            event.value.to_string(),
+            event.revision.to_string(),
+            event.created_at.to_rfc3339(),
+        ];
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field when it holds a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Usage of a tenant on a meter over a period
+#[derive(Debug, Default)]
+struct Usage {
+    /// Usage observed so far
+    observed: u64,
+    /// Usage covered by the events emitted so far
+    billed: u64,
+    /// Events emitted so far
+    events: u32,
+}
+
+/// Usage keyed by period start (seconds since epoch), tenant and meter
+type UsageKey = (i64, String, Meter);
+
+#[derive(Debug, Default)]
+struct MeteringState {
+    usage: BTreeMap<UsageKey, Usage>,
+    /// Emitted events, oldest first
+    history: VecDeque<BillingEvent>,
+    /// Events waiting to be pushed to Stripe, oldest first
+    pending: VecDeque<BillingEvent>,
+}
+
+/// Meter of tenant usage
+#[derive(Debug)]
+pub struct Metering {
+    config: MeteringConfig,
+    state: Mutex<MeteringState>,
+    client: Client,
+}
+
+impl Metering {
+    /// Create a meter and start closing its periods in the background
+    ///
+    /// Returns `None` when metering is disabled.
+    pub fn from_config(config: &MeteringConfig) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+        let metering = Arc::new(Self::new(config.clone()));
+        metering.clone().spawn();
+        Some(metering)
+    }
+
+    /// Create a meter
+    pub fn new(config: MeteringConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(MeteringState::default()),
+            client: Client::new(),
+        }
+    }
+
+    fn period_secs(&self) -> i64 {
+        self.config.period_secs.max(1) as i64
+    }
+
+    /// Count a completed request in the period it completed in
+    ///
+    /// Requests older than the correction window can no longer be billed
+    /// and are dropped.
+    pub fn observe(&self, record: &TelemetryRecord, now: DateTime<Utc>) {
+        let tenant = record.tenant.as_deref().unwrap_or(DEFAULT_TENANT);
+        let period = self.period_secs();
+        let start = record.timestamp.timestamp().div_euclid(period) * period;
+        let deadline = start + period + self.config.correction_window_secs as i64;
+        if deadline < now.timestamp() {
+            warn!(
+                "Dropping usage of request {} of tenant {}: its period can no longer be corrected",
+                record.request_id, tenant
+            );
+            counter!(catalog::METERING_LATE_DROPPED, 1, "tenant" => tenant.to_string());
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        for meter in Meter::ALL {
+            let value = meter.value(record);
+            if value > 0 {
+                let usage = state
+                    .usage
+                    .entry((start, tenant.to_string(), meter))
+                    .or_default();
+                usage.observed += value;
+            }
+        }
+    }
+
+    /// Bill the usage of the periods past their grace time that wasn't
+    /// billed yet, and forget the periods past their correction window
+    pub fn close(&self, now: DateTime<Utc>) -> Vec<BillingEvent> {
+        let period = self.period_secs();
+        let now_secs = now.timestamp();
+        let mut events = Vec::new();
+        let mut state = self.state.lock().unwrap();
+
+        state.usage.retain(|(start, tenant, meter), usage| {
+            let end = start + period;
+            if end + self.config.grace_secs as i64 <= now_secs && usage.observed > usage.billed {
+                let event = BillingEvent {
+                    idempotency_key: idempotency_key(tenant, *meter, *start, usage.events),
+                    tenant: tenant.clone(),
+                    meter: *meter,
+                    period_start: timestamp(*start),
+                    period_end: timestamp(end),
+                    value: usage.observed - usage.billed,
+                    revision: usage.events,
+                    created_at: now,
+                };
+                usage.billed = usage.observed;
+                usage.events += 1;
+                counter!(
+                    catalog::METERING_EVENTS, 1,
+                    "meter" => meter.as_str(), "correction" => event.is_correction().to_string()
+                );
+                events.push(event);
+            }
+            end + (self.config.correction_window_secs as i64) >= now_secs
+        });
+
+        for event in &events {
+            if self.config.history_capacity > 0 {
+                if state.history.len() >= self.config.history_capacity {
+                    state.history.pop_front();
+                }
+                state.history.push_back(event.clone());
+            }
+            if self.config.stripe.is_some() {
+                state.pending.push_back(event.clone());
+            }
+        }
+        events
+    }
+
+    /// Emitted events, oldest first, of a tenant or all tenants, optionally
+    /// only those for periods starting at or after `since`
+    pub fn events(&self, tenant: Option<&str>, since: Option<DateTime<Utc>>) -> Vec<BillingEvent> {
+        let state = self.state.lock().unwrap();
+        state
+            .history
+            .iter()
+            .filter(|event| tenant.is_none_or(|tenant| event.tenant == tenant))
+            .filter(|event| since.is_none_or(|since| event.period_start >= since))
+            .cloned()
+            .collect()
+    }
+
+    /// Close periods every `flush_interval_secs` and push their events
+    fn spawn(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(self.config.flush_interval_secs.max(1));
+            let mut interval =
+                tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let events = self.close(Utc::now());
+                if !events.is_empty() {
+                    debug!("Billed {} metering event(s)", events.len());
+                }
+                if let Some(stripe) = &self.config.stripe {
+                    self.push_pending(stripe).await;
+                }
+            }
+        });
+    }
+
+    /// Push the pending events to Stripe in order
+    ///
+    /// An event that still fails after its retries stays pending, with the
+    /// events after it, until the next flush.
+    async fn push_pending(&self, stripe: &StripeMeteringConfig) {
+        loop {
+            let Some(event) = self.state.lock().unwrap().pending.front().cloned() else {
+                return;
+            };
+            let mut attempt = 0;
+            loop {
+                match self.push(stripe, &event).await {
+                    Ok(()) => break,
+                    Err(e) if attempt < stripe.max_retries => {
+                        debug!("Push of {} failed, retrying: {}", event.idempotency_key, e);
+                        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt)).await;
+                        attempt += 1;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Push of metering event {} failed, keeping it for the next flush: {}",
+                            event.idempotency_key, e
+                        );
+                        counter!(catalog::METERING_PUSH_FAILED, 1);
+                        return;
+                    }
+                }
+            }
+            self.state.lock().unwrap().pending.pop_front();
+        }
+    }
+
+    /// Send an event to Stripe's meter events API
+    ///
+    /// Events of tenants without a Stripe customer, or of meters without an
+    /// event name, are skipped.
+    async fn push(
+        &self,
+        stripe: &StripeMeteringConfig,
+        event: &BillingEvent,
+    ) -> Result<(), MeteringError> {
+        let Some(form) = stripe_form(stripe, event) else {
+            debug!(
+                "Not pushing metering event {}: no Stripe customer or event name",
+                event.idempotency_key
+            );
+            return Ok(());
+        };
+        let api_key = stripe
+            .api_key
+            .clone()
+            .or_else(|| std::env::var("STRIPE_API_KEY").ok())
+            .ok_or_else(|| MeteringError::Config("No Stripe API key".to_string()))?;
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/v1/billing/meter_events",
+                stripe.endpoint.trim_end_matches('/')
+            ))
+            .bearer_auth(api_key)
+            .header("Idempotency-Key", &event.idempotency_key)
+            .form(&form)
+            .send()
+            .await
+            .map_err(|e| MeteringError::Request(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(MeteringError::Rejected(format!("{}: {}", status, body)));
+        }
+        Ok(())
+    }
+}
+
+/// Form fields of a Stripe meter event, or `None` when the event's tenant
+/// or meter isn't mapped to Stripe
+///
+/// The event is timestamped at the start of its period, so corrections land
+/// in the same Stripe billing period as the usage they correct.
+fn stripe_form(
+    stripe: &StripeMeteringConfig,
+    event: &BillingEvent,
+) -> Option<Vec<(&'static str, String)>> {
+    let customer = stripe.customers.get(&event.tenant)?;
+    let event_name = stripe.event_names.get(event.meter.as_str())?;
+    Some(vec![
+        ("event_name", event_name.clone()),
+        ("identifier", event.idempotency_key.clone()),
+        ("timestamp", event.period_start.timestamp().to_string()),
+        ("payload[stripe_customer_id]", customer.clone()),
+        ("payload[value]", event.value.to_string()),
+    ])
+}
+
+/// Key of an event, the same however often its period is closed
+fn idempotency_key(tenant: &str, meter: Meter, period_start: i64, revision: u32) -> String {
+    format!(
+        "{}:{}:{}:{}",
+        tenant,
+        meter.as_str(),
+        period_start,
+        revision
+    )
+}
+
+fn timestamp(secs: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(secs, 0).single().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config() -> MeteringConfig {
+        MeteringConfig {
+            enabled: true,
+            period_secs: 3600,
+            grace_secs: 300,
+            ..Default::default()
+        }
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        timestamp(1_700_000_000 - 1_700_000_000 % 3600 + secs)
+    }
+
+    fn record(
+        tenant: &str,
+        secs: i64,
+        total_tokens: u32,
+        cache_status: CacheStatus,
+    ) -> TelemetryRecord {
+        TelemetryRecord {
+            request_id: "req".to_string(),
+            timestamp: at(secs),
+            tenant: Some(tenant.to_string()),
+            user: None,
+            requested_model: "gpt-4o".to_string(),
+            model: "gpt-4o".to_string(),
+            provider: "openai".to_string(),
+            prompt_tokens: total_tokens,
+            completion_tokens: 0,
+            total_tokens,
+            cost_usd: None,
+            latency_ms: 100,
+            cache_status,
+            success: true,
+            error: None,
+            flagged_categories: Vec::new(),
+            language: None,
+        }
+    }
+
+    fn values(events: &[BillingEvent]) -> Vec<(&str, Meter, u64, u32)> {
+        events
+            .iter()
+            .map(|e| (e.tenant.as_str(), e.meter, e.value, e.revision))
+            .collect()
+    }
+
+    #[test]
+    fn test_periods_billed_after_grace() {
+        let metering = Metering::new(config());
+        metering.observe(&record("acme", 10, 100, CacheStatus::Bypass), at(10));
+        metering.observe(&record("acme", 20, 40, CacheStatus::Hit), at(20));
+        metering.observe(&record("globex", 3700, 5, CacheStatus::Bypass), at(3700));
+
+        // Within the grace time nothing is billed
+        assert!(metering.close(at(3600 + 299)).is_empty());
+        let events = metering.close(at(3600 + 300));
+        assert_eq!(
+            values(&events),
+            vec![
+                ("acme", Meter::Tokens, 100, 0),
+                ("acme", Meter::Requests, 2, 0),
+                ("acme", Meter::CacheSavings, 40, 0),
+            ]
+        );
+        assert_eq!(events[0].period_start, at(0));
+        assert_eq!(events[0].period_end, at(3600));
+        // Closing again bills nothing new
+        assert!(metering.close(at(3600 + 400)).is_empty());
+        assert_eq!(metering.events(Some("globex"), None).len(), 0);
+    }
+
+    #[test]
+    fn test_late_requests_corrected() {
+        let metering = Metering::new(config());
+        metering.observe(&record("acme", 10, 100, CacheStatus::Bypass), at(10));
+        let first = metering.close(at(4000));
+
+        metering.observe(&record("acme", 50, 25, CacheStatus::Bypass), at(5000));
+        let correction = metering.close(at(5000));
+        assert_eq!(
+            values(&correction),
+            vec![
+                ("acme", Meter::Tokens, 25, 1),
+                ("acme", Meter::Requests, 1, 1)
+            ]
+        );
+        assert!(correction[0].is_correction());
+        assert_ne!(first[0].idempotency_key, correction[0].idempotency_key);
+        assert_eq!(metering.events(Some("acme"), Some(at(0))).len(), 4);
+
+        // Past the correction window, late requests are dropped
+        let window = config().correction_window_secs as i64;
+        metering.close(at(3600 + window + 1));
+        metering.observe(
+            &record("acme", 10, 100, CacheStatus::Bypass),
+            at(3600 + window + 1),
+        );
+        assert!(metering.close(at(3600 + window + 2)).is_empty());
+    }
+
+    #[test]
+    fn test_exports() {
+        let metering = Metering::new(config());
+        metering.observe(&record("acme, inc", 10, 7, CacheStatus::Bypass), at(10));
+        let events = metering.close(at(4000));
+
+        let csv = to_csv(&events);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with(&format!(
+            "\"acme, inc:tokens:{}:0\",\"acme, inc\",tokens,",
+            at(0).timestamp()
+        )));
+        assert!(row.ends_with(&format!(",7,0,{}", at(4000).to_rfc3339())));
+
+        let stripe = StripeMeteringConfig {
+            endpoint: "https://api.stripe.com".to_string(),
+            api_key: None,
+            event_names: HashMap::from([("tokens".to_string(), "llm_tokens".to_string())]),
+            customers: HashMap::from([("acme, inc".to_string(), "cus_123".to_string())]),
+            max_retries: 0,
+        };
+        let form: HashMap<_, _> = stripe_form(&stripe, &events[0])
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(form["event_name"], "llm_tokens");
+        assert_eq!(form["identifier"], events[0].idempotency_key);
+        assert_eq!(form["payload[stripe_customer_id]"], "cus_123");
+        assert_eq!(form["payload[value]"], "7");
+        // Requests have no Stripe event name
+        assert!(stripe_form(&stripe, &events[1]).is_none());
+    }
+}
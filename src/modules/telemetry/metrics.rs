@@ -2,6 +2,8 @@ use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 
+use super::catalog;
+
 /// Initialize the Prometheus metrics exporter
 pub fn init_prometheus_exporter(
     addr: SocketAddr,
@@ -11,19 +13,19 @@ pub fn init_prometheus_exporter(
     // Configure Prometheus metrics
     let builder = builder
         .set_buckets_for_metric(
-            Matcher::Full("intellirouter.http.latency".to_string()),
+            Matcher::Full(catalog::HTTP_LATENCY.to_string()),
             &[
                 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
             ],
         )?
         .set_buckets_for_metric(
-            Matcher::Full("intellirouter.llm.latency".to_string()),
+            Matcher::Full(catalog::LLM_LATENCY.to_string()),
             &[
                 10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0, 60000.0,
             ],
         )?
         .set_buckets_for_metric(
-            Matcher::Full("intellirouter.routing.decision_time".to_string()),
+            Matcher::Full(catalog::ROUTING_DECISION_TIME.to_string()),
             &[
                 0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0,
             ],
@@ -8,6 +8,7 @@ use std::sync::Arc;
 use uuid::Uuid;
 
 use super::telemetry::TelemetryManager;
+use crate::modules::common::TrafficClass;
 
 /// Middleware for logging HTTP requests and responses
 pub async fn telemetry_middleware(
@@ -25,13 +26,22 @@ pub async fn telemetry_middleware(
     // Start the timer
     let start_time = telemetry.start_request_timer();
 
-    // Log the request
-    tracing::info!(
-        request_id = %request_id,
-        method = %method,
-        path = %path,
-        "Request started"
-    );
+    // Log the request, keeping health checks and monitoring scrapes quiet
+    if TrafficClass::of(&request).is_synthetic() {
+        tracing::debug!(
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            "Synthetic request started"
+        );
+    } else {
+        tracing::info!(
+            request_id = %request_id,
+            method = %method,
+            path = %path,
+            "Request started"
+        );
+    }
 
     // Process the request
     let response = next.run(request).await;
@@ -1,4 +1,11 @@
+pub mod anomaly;
+pub mod canary;
+pub mod catalog;
 pub mod cost;
+pub mod dashboard;
+pub mod export;
+pub mod logging;
+pub mod metering;
 pub mod metrics;
 pub mod middleware;
 pub mod telemetry;
@@ -7,7 +14,13 @@ pub mod tests;
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-pub use cost::CostCalculator;
+pub use anomaly::AnomalyDetector;
+pub use canary::CanaryMonitor;
+pub use cost::{
+    CostBackfill, CostCalculator, CostCorrection, ModelPrice, PricingError, UsageRecord,
+};
+pub use export::{CacheStatus, TelemetryExporter, TelemetryRecord, TelemetrySink};
+pub use metering::{BillingEvent, Meter, Metering};
 pub use middleware::telemetry_middleware;
 pub use telemetry::{LlmCallMetrics, RoutingMetrics, TelemetryManager};
 
@@ -2,6 +2,10 @@ use metrics::{counter, gauge, histogram};
 use std::time::Instant;
 use tracing::{error, info};
 
+use super::catalog;
+use super::logging;
+use crate::config::{LogFormat, LoggingConfig, TelemetryConfig};
+
 /// Metrics for an LLM API call
 #[derive(Debug, Clone)]
 pub struct LlmCallMetrics {
@@ -63,24 +67,29 @@ impl TelemetryManager {
 
     /// Set up logging with the tracing crate
     pub fn setup_logging() -> Result<(), Box<dyn std::error::Error>> {
-        // Initialize tracing subscriber with JSON formatting for production
-        // and pretty printing for development
-        let env_filter = tracing_subscriber::EnvFilter::from_default_env();
-
-        if std::env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string()) == "production" {
-            // JSON formatting for production
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .json()
-                .init();
+        // JSON formatting for production and pretty printing for development
+        let format = if std::env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string())
+            == "production"
+        {
+            LogFormat::Json
         } else {
-            // Pretty printing for development
-            tracing_subscriber::fmt()
-                .with_env_filter(env_filter)
-                .pretty()
-                .init();
-        }
+            LogFormat::Pretty
+        };
+        let config = LoggingConfig {
+            format,
+            ..LoggingConfig::default()
+        };
+        logging::init("info", &config)?;
+        Ok(())
+    }
 
+    /// Set up logging with the configured format and levels
+    ///
+    /// The levels can be changed afterwards through [`logging::handle`].
+    pub fn setup_logging_with_config(
+        config: &TelemetryConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        logging::init(&config.log_level, &config.logging)?;
         Ok(())
     }
 
@@ -112,7 +121,7 @@ impl TelemetryManager {
 
         // Record metrics
         counter!(
-            "intellirouter.llm.calls", 1,
+            catalog::LLM_CALLS, 1,
             "model" => metrics.model_id.clone(),
             "success" => metrics.success.to_string(),
             "service" => self.service_name.clone(),
@@ -120,28 +129,28 @@ impl TelemetryManager {
         );
 
         gauge!(
-            "intellirouter.llm.tokens.prompt", metrics.prompt_tokens as f64,
+            catalog::LLM_PROMPT_TOKENS, metrics.prompt_tokens as f64,
             "model" => metrics.model_id.clone(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
         );
 
         gauge!(
-            "intellirouter.llm.tokens.completion", metrics.completion_tokens as f64,
+            catalog::LLM_COMPLETION_TOKENS, metrics.completion_tokens as f64,
             "model" => metrics.model_id.clone(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
         );
 
         gauge!(
-            "intellirouter.llm.tokens.total", metrics.total_tokens as f64,
+            catalog::LLM_TOTAL_TOKENS, metrics.total_tokens as f64,
             "model" => metrics.model_id.clone(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
         );
 
         histogram!(
-            "intellirouter.llm.latency", metrics.latency_ms as f64,
+            catalog::LLM_LATENCY, metrics.latency_ms as f64,
             "model" => metrics.model_id.clone(),
             "success" => metrics.success.to_string(),
             "service" => self.service_name.clone(),
@@ -149,7 +158,7 @@ impl TelemetryManager {
         );
 
         gauge!(
-            "intellirouter.llm.cost", metrics.estimated_cost,
+            catalog::LLM_COST, metrics.estimated_cost,
             "model" => metrics.model_id.clone(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
@@ -179,7 +188,7 @@ impl TelemetryManager {
 
         // Record metrics
         counter!(
-            "intellirouter.routing.decisions", 1,
+            catalog::ROUTING_DECISIONS, 1,
             "success" => metrics.success.to_string(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
@@ -187,7 +196,7 @@ impl TelemetryManager {
 
         if metrics.success {
             counter!(
-                "intellirouter.routing.model_selected", 1,
+                catalog::ROUTING_MODEL_SELECTED, 1,
                 "model" => metrics.selected_model.clone(),
                 "service" => self.service_name.clone(),
                 "env" => self.environment.clone()
@@ -195,13 +204,13 @@ impl TelemetryManager {
         }
 
         gauge!(
-            "intellirouter.routing.candidate_count", metrics.candidate_count as f64,
+            catalog::ROUTING_CANDIDATE_COUNT, metrics.candidate_count as f64,
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
         );
 
         histogram!(
-            "intellirouter.routing.decision_time", metrics.decision_time_ms as f64,
+            catalog::ROUTING_DECISION_TIME, metrics.decision_time_ms as f64,
             "success" => metrics.success.to_string(),
             "service" => self.service_name.clone(),
             "env" => self.environment.clone()
@@ -229,7 +238,7 @@ impl TelemetryManager {
 
         // Record metrics
         counter!(
-            "intellirouter.http.requests", 1,
+            catalog::HTTP_REQUESTS, 1,
             "path" => path.to_string(),
             "method" => method.to_string(),
             "status" => status.to_string(),
@@ -238,7 +247,7 @@ impl TelemetryManager {
         );
 
         histogram!(
-            "intellirouter.http.latency", duration_ms,
+            catalog::HTTP_LATENCY, duration_ms,
             "path" => path.to_string(),
             "method" => method.to_string(),
             "status" => status.to_string(),
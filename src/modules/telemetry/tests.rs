@@ -37,6 +37,93 @@ mod tests {
         assert_eq!(cost, expected_cost);
     }
 
+    #[test]
+    fn test_effective_dated_prices() {
+        use crate::modules::telemetry::cost::{CostCalculator, ModelPrice, PricingError};
+        use chrono::{TimeZone, Utc};
+
+        let mut config = crate::config::PricingConfig::default();
+        config.currency = "EUR".to_string();
+        config.exchange_rates.insert("USD".to_string(), 0.5);
+        config.prices.push(crate::config::ModelPriceConfig {
+            model: "gpt-4o".to_string(),
+            input_per_1k: 0.01,
+            output_per_1k: 0.02,
+            currency: Some("USD".to_string()),
+            effective_from: None,
+        });
+        let calculator = CostCalculator::from_config(&config).unwrap();
+        assert_eq!(calculator.currency(), "EUR");
+
+        let june = Utc.with_ymd_and_hms(2026, 6, 1, 0, 0, 0).unwrap();
+        let july = Utc.with_ymd_and_hms(2026, 7, 1, 0, 0, 0).unwrap();
+        calculator
+            .set_price("gpt-4o", ModelPrice::new(0.002, 0.004, july))
+            .unwrap();
+        // Converted from USD before July, as entered from then on
+        assert_eq!(
+            calculator
+                .calculate_cost_at("gpt-4o", 1000, 1000, june)
+                .unwrap(),
+            0.015
+        );
+        assert_eq!(
+            calculator
+                .calculate_cost_at("gpt-4o", 1000, 1000, july)
+                .unwrap(),
+            0.006
+        );
+
+        let mut unknown = ModelPrice::new(0.01, 0.01, july);
+        unknown.entered_currency = Some("GBP".to_string());
+        assert_eq!(
+            calculator.set_price("gpt-4o", unknown).unwrap_err(),
+            PricingError::UnknownCurrency("GBP".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cost_backfill() {
+        use crate::modules::telemetry::cost::{CostCalculator, ModelPrice, UsageRecord};
+        use chrono::{TimeZone, Utc};
+
+        let calculator = CostCalculator::new();
+        let usage = |id: &str, day: u32| UsageRecord {
+            request_id: id.to_string(),
+            timestamp: Utc.with_ymd_and_hms(2026, 6, day, 12, 0, 0).unwrap(),
+            tenant: Some("acme".to_string()),
+            user: Some("alice".to_string()),
+            model: "gpt-4".to_string(),
+            prompt_tokens: 1000,
+            completion_tokens: 0,
+            cost: 0.0,
+        };
+        assert_eq!(calculator.record_usage(usage("a", 1)).unwrap(), 0.03);
+        assert_eq!(calculator.record_usage(usage("b", 10)).unwrap(), 0.03);
+        assert_eq!(calculator.record_usage(usage("c", 20)).unwrap(), 0.03);
+
+        // A correction from the 5th until a later price from the 15th
+        let later = Utc.with_ymd_and_hms(2026, 6, 15, 0, 0, 0).unwrap();
+        calculator
+            .set_price("gpt-4", ModelPrice::new(0.03, 0.06, later))
+            .unwrap();
+        let correction = Utc.with_ymd_and_hms(2026, 6, 5, 0, 0, 0).unwrap();
+        let backfill = calculator
+            .set_price("gpt-4", ModelPrice::new(0.01, 0.02, correction))
+            .unwrap();
+        assert_eq!(backfill.recomputed, 2);
+        assert_eq!(backfill.corrections.len(), 1);
+        assert_eq!(backfill.corrections[0].request_id, "b");
+        assert_eq!(backfill.corrections[0].delta(), 0.01 - 0.03);
+
+        let costs: Vec<f64> = calculator
+            .ledger()
+            .iter()
+            .map(|record| record.cost)
+            .collect();
+        assert_eq!(costs, vec![0.03, 0.01, 0.03]);
+    }
+
     #[tokio::test]
     async fn test_telemetry_manager() {
         let telemetry = crate::modules::telemetry::telemetry::TelemetryManager::new(
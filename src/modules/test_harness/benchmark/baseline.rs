@@ -0,0 +1,542 @@
+//! Benchmark Baselines
+//!
+//! This module persists benchmark results as baselines, one per git ref, and
+//! gates new results against them. A comparison fails when the p95 latency of
+//! a benchmark grows, or its throughput drops, by more than the configured
+//! thresholds, and renders a markdown summary for CI to publish as an
+//! artifact.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::{BenchmarkResult, BenchmarkType};
+use crate::modules::test_harness::types::TestHarnessError;
+
+/// Summary of a benchmark result kept in a baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    /// Benchmark ID
+    pub id: String,
+    /// Benchmark name
+    pub name: String,
+    /// Benchmark type
+    pub benchmark_type: BenchmarkType,
+    /// p95 latency in milliseconds
+    pub latency_p95_ms: f64,
+    /// Throughput (operations per second)
+    pub throughput: f64,
+    /// Error rate
+    pub error_rate: f64,
+    /// Operations measured
+    pub total_operations: u64,
+}
+
+impl From<&BenchmarkResult> for BaselineEntry {
+    fn from(result: &BenchmarkResult) -> Self {
+        Self {
+            id: result.config.id.clone(),
+            name: result.config.name.clone(),
+            benchmark_type: result.config.benchmark_type,
+            latency_p95_ms: result.latency.p95_duration.as_secs_f64() * 1000.0,
+            throughput: result.throughput,
+            error_rate: result.error_rate,
+            total_operations: result.total_operations,
+        }
+    }
+}
+
+/// Benchmark results of a git ref
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    /// Git ref the results were measured on, e.g. `main`
+    pub git_ref: String,
+    /// Commit the results were measured on
+    pub commit: Option<String>,
+    /// When the baseline was created
+    pub created_at: DateTime<Utc>,
+    /// Benchmark summaries
+    pub benchmarks: Vec<BaselineEntry>,
+}
+
+impl Baseline {
+    /// Create a baseline from benchmark results
+    pub fn new(git_ref: impl Into<String>, results: &[BenchmarkResult]) -> Self {
+        Self {
+            git_ref: git_ref.into(),
+            commit: current_commit(),
+            created_at: Utc::now(),
+            benchmarks: results.iter().map(BaselineEntry::from).collect(),
+        }
+    }
+
+    /// Get the summary of a benchmark
+    pub fn get(&self, id: &str) -> Option<&BaselineEntry> {
+        self.benchmarks.iter().find(|entry| entry.id == id)
+    }
+}
+
+/// Directory of baselines, one JSON file per git ref
+#[derive(Debug, Clone)]
+pub struct BaselineStore {
+    dir: PathBuf,
+}
+
+impl BaselineStore {
+    /// Create a store in a directory, creating the directory if needed
+    pub fn new(dir: impl Into<PathBuf>) -> Result<Self, TestHarnessError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Path of the baseline of a git ref
+    ///
+    /// Slashes in refs such as `release/1.2` are flattened so every baseline
+    /// sits directly in the store directory.
+    pub fn path(&self, git_ref: &str) -> PathBuf {
+        let file: String = git_ref
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+
+    /// Save a baseline, replacing any previous baseline of its git ref
+    pub fn save(&self, baseline: &Baseline) -> Result<PathBuf, TestHarnessError> {
+        let path = self.path(&baseline.git_ref);
+        fs::write(&path, serde_json::to_string_pretty(baseline)?)?;
+        info!(
+            "Saved baseline of {} with {} benchmarks to {}",
+            baseline.git_ref,
+            baseline.benchmarks.len(),
+            path.display()
+        );
+        Ok(path)
+    }
+
+    /// Load the baseline of a git ref, if one was saved
+    pub fn load(&self, git_ref: &str) -> Result<Option<Baseline>, TestHarnessError> {
+        let path = self.path(git_ref);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+    }
+}
+
+/// Largest tolerated changes before a benchmark counts as regressed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RegressionThresholds {
+    /// Largest tolerated p95 latency increase, as a fraction (0.1 = 10%)
+    pub max_latency_p95_increase: f64,
+    /// Largest tolerated throughput decrease, as a fraction (0.1 = 10%)
+    pub max_throughput_decrease: f64,
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            max_latency_p95_increase: 0.10,
+            max_throughput_decrease: 0.10,
+        }
+    }
+}
+
+impl RegressionThresholds {
+    /// Set the largest tolerated p95 latency increase
+    pub fn with_max_latency_p95_increase(mut self, fraction: f64) -> Self {
+        self.max_latency_p95_increase = fraction.max(0.0);
+        self
+    }
+
+    /// Set the largest tolerated throughput decrease
+    pub fn with_max_throughput_decrease(mut self, fraction: f64) -> Self {
+        self.max_throughput_decrease = fraction.max(0.0);
+        self
+    }
+}
+
+/// Outcome of comparing a benchmark with its baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComparisonStatus {
+    /// The benchmark has no baseline
+    New,
+    /// Within the thresholds
+    Stable,
+    /// Better than the baseline beyond the thresholds
+    Improved,
+    /// Worse than the baseline beyond the thresholds
+    Regressed,
+}
+
+impl fmt::Display for ComparisonStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComparisonStatus::New => write!(f, "new"),
+            ComparisonStatus::Stable => write!(f, "stable"),
+            ComparisonStatus::Improved => write!(f, "improved"),
+            ComparisonStatus::Regressed => write!(f, "regressed"),
+        }
+    }
+}
+
+/// Comparison of a benchmark with its baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    /// Current result
+    pub current: BaselineEntry,
+    /// Baseline result
+    pub baseline: Option<BaselineEntry>,
+    /// Relative p95 latency change (0.1 = 10% slower)
+    pub latency_p95_change: Option<f64>,
+    /// Relative throughput change (-0.1 = 10% fewer operations per second)
+    pub throughput_change: Option<f64>,
+    /// Outcome of the comparison
+    pub status: ComparisonStatus,
+}
+
+/// Comparison of benchmark results with a baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineComparison {
+    /// Git ref of the baseline
+    pub baseline_ref: String,
+    /// Git ref of the current results
+    pub current_ref: String,
+    /// Thresholds the results were gated with
+    pub thresholds: RegressionThresholds,
+    /// Comparison of every current benchmark
+    pub benchmarks: Vec<BenchmarkComparison>,
+    /// Benchmarks of the baseline missing from the current results
+    pub missing: Vec<String>,
+}
+
+/// Relative change from `baseline` to `current`
+fn relative_change(baseline: f64, current: f64) -> Option<f64> {
+    (baseline > 0.0).then(|| (current - baseline) / baseline)
+}
+
+impl BaselineComparison {
+    /// Compare current results with a baseline
+    pub fn compare(
+        baseline: &Baseline,
+        current: &Baseline,
+        thresholds: RegressionThresholds,
+    ) -> Self {
+        let benchmarks = current
+            .benchmarks
+            .iter()
+            .map(|entry| {
+                let Some(previous) = baseline.get(&entry.id) else {
+                    return BenchmarkComparison {
+                        current: entry.clone(),
+                        baseline: None,
+                        latency_p95_change: None,
+                        throughput_change: None,
+                        status: ComparisonStatus::New,
+                    };
+                };
+
+                let latency_p95_change =
+                    relative_change(previous.latency_p95_ms, entry.latency_p95_ms);
+                let throughput_change = relative_change(previous.throughput, entry.throughput);
+
+                let regressed = latency_p95_change
+                    .is_some_and(|change| change > thresholds.max_latency_p95_increase)
+                    || throughput_change
+                        .is_some_and(|change| -change > thresholds.max_throughput_decrease);
+                let improved = latency_p95_change
+                    .is_some_and(|change| -change > thresholds.max_latency_p95_increase)
+                    || throughput_change
+                        .is_some_and(|change| change > thresholds.max_throughput_decrease);
+
+                BenchmarkComparison {
+                    current: entry.clone(),
+                    baseline: Some(previous.clone()),
+                    latency_p95_change,
+                    throughput_change,
+                    status: if regressed {
+                        ComparisonStatus::Regressed
+                    } else if improved {
+                        ComparisonStatus::Improved
+                    } else {
+                        ComparisonStatus::Stable
+                    },
+                }
+            })
+            .collect();
+
+        let missing = baseline
+            .benchmarks
+            .iter()
+            .filter(|entry| current.get(&entry.id).is_none())
+            .map(|entry| entry.id.clone())
+            .collect();
+
+        Self {
+            baseline_ref: baseline.git_ref.clone(),
+            current_ref: current.git_ref.clone(),
+            thresholds,
+            benchmarks,
+            missing,
+        }
+    }
+
+    /// Benchmarks regressed beyond the thresholds
+    pub fn regressions(&self) -> impl Iterator<Item = &BenchmarkComparison> {
+        self.benchmarks
+            .iter()
+            .filter(|comparison| comparison.status == ComparisonStatus::Regressed)
+    }
+
+    /// Whether any benchmark regressed beyond the thresholds
+    pub fn has_regressions(&self) -> bool {
+        self.regressions().next().is_some()
+    }
+
+    /// Fail if any benchmark regressed beyond the thresholds
+    pub fn check(&self) -> Result<(), TestHarnessError> {
+        let regressed: Vec<&str> = self
+            .regressions()
+            .map(|comparison| comparison.current.id.as_str())
+            .collect();
+        if regressed.is_empty() {
+            return Ok(());
+        }
+        warn!(
+            "{} benchmarks regressed against {}: {}",
+            regressed.len(),
+            self.baseline_ref,
+            regressed.join(", ")
+        );
+        Err(TestHarnessError::AssertionError(format!(
+            "performance regressed against baseline {}: {}",
+            self.baseline_ref,
+            regressed.join(", ")
+        )))
+    }
+
+    /// Render the comparison as a markdown summary
+    pub fn to_markdown(&self) -> String {
+        let regressions = self.regressions().count();
+        let mut md = String::new();
+
+        md.push_str("# Performance Comparison\n\n");
+        md.push_str(&format!(
+            "Comparing `{}` against baseline `{}`.\n\n",
+            self.current_ref, self.baseline_ref
+        ));
+        md.push_str(&format!(
+            "Thresholds: p95 latency +{:.1}%, throughput -{:.1}%\n\n",
+            self.thresholds.max_latency_p95_increase * 100.0,
+            self.thresholds.max_throughput_decrease * 100.0
+        ));
+        if regressions == 0 {
+            md.push_str("**Result: passed**\n\n");
+        } else {
+            md.push_str(&format!(
+                "**Result: failed, {} regressed benchmark{}**\n\n",
+                regressions,
+                if regressions == 1 { "" } else { "s" }
+            ));
+        }
+
+        md.push_str(
+            "| Benchmark | p95 latency (ms) | Change | Throughput (ops/s) | Change | Status |\n",
+        );
+        md.push_str(
+            "|-----------|------------------|--------|--------------------|--------|--------|\n",
+        );
+        for comparison in &self.benchmarks {
+            let (latency, throughput) = match &comparison.baseline {
+                Some(baseline) => (
+                    format!(
+                        "{:.2} → {:.2}",
+                        baseline.latency_p95_ms, comparison.current.latency_p95_ms
+                    ),
+                    format!(
+                        "{:.2} → {:.2}",
+                        baseline.throughput, comparison.current.throughput
+                    ),
+                ),
+                None => (
+                    format!("{:.2}", comparison.current.latency_p95_ms),
+                    format!("{:.2}", comparison.current.throughput),
+                ),
+            };
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                comparison.current.name,
+                latency,
+                format_change(comparison.latency_p95_change),
+                throughput,
+                format_change(comparison.throughput_change),
+                comparison.status
+            ));
+        }
+
+        if !self.missing.is_empty() {
+            md.push_str(&format!(
+                "\nMissing from this run: {}\n",
+                self.missing.join(", ")
+            ));
+        }
+
+        md
+    }
+
+    /// Write the markdown summary to a file
+    pub fn write_markdown(&self, path: impl AsRef<Path>) -> Result<(), TestHarnessError> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, self.to_markdown())?;
+        Ok(())
+    }
+}
+
+fn format_change(change: Option<f64>) -> String {
+    change
+        .map(|change| format!("{:+.1}%", change * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Commit checked out in the working directory
+fn current_commit() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Git ref checked out in the working directory
+///
+/// CI checkouts are usually detached, so `GITHUB_HEAD_REF` and
+/// `GITHUB_REF_NAME` take precedence over asking git.
+pub fn current_git_ref() -> Option<String> {
+    for var in ["GITHUB_HEAD_REF", "GITHUB_REF_NAME"] {
+        if let Ok(value) = std::env::var(var) {
+            if !value.is_empty() {
+                return Some(value);
+            }
+        }
+    }
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    let git_ref = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && git_ref != "HEAD").then_some(git_ref)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, latency_p95_ms: f64, throughput: f64) -> BaselineEntry {
+        BaselineEntry {
+            id: id.to_string(),
+            name: id.to_string(),
+            benchmark_type: BenchmarkType::Latency,
+            latency_p95_ms,
+            throughput,
+            error_rate: 0.0,
+            total_operations: 1000,
+        }
+    }
+
+    fn baseline(git_ref: &str, benchmarks: Vec<BaselineEntry>) -> Baseline {
+        Baseline {
+            git_ref: git_ref.to_string(),
+            commit: None,
+            created_at: Utc::now(),
+            benchmarks,
+        }
+    }
+
+    #[test]
+    fn test_comparison_gates_on_thresholds() {
+        let main = baseline(
+            "main",
+            vec![
+                entry("route", 10.0, 1000.0),
+                entry("chat", 50.0, 200.0),
+                entry("embed", 20.0, 500.0),
+                entry("removed", 5.0, 100.0),
+            ],
+        );
+        let branch = baseline(
+            "feature/cache",
+            vec![
+                entry("route", 10.5, 980.0),
+                entry("chat", 60.0, 200.0),
+                entry("embed", 15.0, 500.0),
+                entry("added", 1.0, 10.0),
+            ],
+        );
+
+        let comparison =
+            BaselineComparison::compare(&main, &branch, RegressionThresholds::default());
+        let statuses: Vec<ComparisonStatus> =
+            comparison.benchmarks.iter().map(|c| c.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ComparisonStatus::Stable,
+                ComparisonStatus::Regressed,
+                ComparisonStatus::Improved,
+                ComparisonStatus::New,
+            ]
+        );
+        assert_eq!(comparison.missing, vec!["removed".to_string()]);
+        assert!(comparison.check().is_err());
+
+        let markdown = comparison.to_markdown();
+        assert!(markdown.contains("failed, 1 regressed benchmark"));
+        assert!(markdown.contains("| chat | 50.00 → 60.00 | +20.0% |"));
+
+        // A looser latency threshold lets the same results through
+        let loose = RegressionThresholds::default().with_max_latency_p95_increase(0.25);
+        assert!(BaselineComparison::compare(&main, &branch, loose)
+            .check()
+            .is_ok());
+
+        // So does a throughput drop within its threshold, but not beyond it
+        let slower = baseline("slower", vec![entry("route", 10.0, 850.0)]);
+        let strict = RegressionThresholds::default().with_max_throughput_decrease(0.1);
+        assert!(BaselineComparison::compare(&main, &slower, strict).has_regressions());
+    }
+
+    #[test]
+    fn test_store_keeps_a_baseline_per_ref() {
+        let dir = std::env::temp_dir().join(format!("baselines-{}", uuid::Uuid::new_v4()));
+        let store = BaselineStore::new(&dir).unwrap();
+
+        assert!(store.load("main").unwrap().is_none());
+        let main = baseline("main", vec![entry("route", 10.0, 1000.0)]);
+        let release = baseline("release/1.2", vec![entry("route", 12.0, 900.0)]);
+        store.save(&main).unwrap();
+        store.save(&release).unwrap();
+
+        assert_eq!(store.path("release/1.2"), dir.join("release_1.2.json"));
+        assert_eq!(store.load("main").unwrap(), Some(main));
+        assert_eq!(store.load("release/1.2").unwrap(), Some(release));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
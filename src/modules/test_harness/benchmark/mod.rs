@@ -2,6 +2,8 @@
 //!
 //! This module provides functionality for performance benchmarking of IntelliRouter components.
 
+pub mod baseline;
+
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
@@ -16,6 +18,11 @@ use super::metrics::{Metric, MetricCollection, MetricType, TimeMetrics};
 use super::reporting::{TestResult, TestRun, TestStatus};
 use crate::modules::test_harness::types::TestHarnessError;
 
+pub use baseline::{
+    current_git_ref, Baseline, BaselineComparison, BaselineEntry, BaselineStore,
+    BenchmarkComparison, ComparisonStatus, RegressionThresholds,
+};
+
 /// Benchmark type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BenchmarkType {
@@ -0,0 +1,131 @@
+//! Chaos Connector
+//!
+//! This module provides a model connector injecting faults into the calls to
+//! the connector it wraps, usually the mock provider. Latency delays the
+//! call, a reset fails it with a network error (or cuts a stream after its
+//! first chunk), and an error fails it with a server error, the way an
+//! overloaded provider would.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{stream, StreamExt};
+
+use super::{Fault, FaultInjector};
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatCompletionResponse, ModelConnector, RawStreamingResponse,
+    StreamingResponse,
+};
+use crate::modules::model_registry::{ConnectorConfig, ConnectorError};
+
+/// Model connector injecting faults into the calls to another connector
+pub struct ChaosConnector {
+    inner: Arc<dyn ModelConnector>,
+    injector: Arc<FaultInjector>,
+}
+
+impl ChaosConnector {
+    /// Wrap a connector
+    pub fn new(inner: Arc<dyn ModelConnector>, injector: Arc<FaultInjector>) -> Self {
+        Self { inner, injector }
+    }
+
+    /// Injector deciding the faults of the calls
+    pub fn injector(&self) -> &Arc<FaultInjector> {
+        &self.injector
+    }
+
+    /// Apply the latency of the next call and return its fault
+    async fn next_fault(&self) -> Option<Fault> {
+        let decision = self.injector.decide();
+        if !decision.delay.is_zero() {
+            tokio::time::sleep(decision.delay).await;
+        }
+        decision.fault
+    }
+}
+
+/// Error a call fails with for a fault
+fn fault_error(fault: Fault) -> ConnectorError {
+    match fault {
+        Fault::Reset => ConnectorError::Network("connection reset by peer (injected)".to_string()),
+        Fault::Error => ConnectorError::Server("service unavailable (injected)".to_string()),
+    }
+}
+
+#[async_trait]
+impl ModelConnector for ChaosConnector {
+    async fn generate(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ConnectorError> {
+        match self.next_fault().await {
+            Some(fault) => Err(fault_error(fault)),
+            None => self.inner.generate(request).await,
+        }
+    }
+
+    async fn generate_streaming(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<StreamingResponse, ConnectorError> {
+        match self.next_fault().await {
+            Some(Fault::Reset) => {
+                // The connection drops after the first chunk
+                let chunks = self.inner.generate_streaming(request).await?;
+                Ok(chunks
+                    .take(1)
+                    .chain(stream::once(async { Err(fault_error(Fault::Reset)) }))
+                    .boxed())
+            }
+            Some(fault) => Err(fault_error(fault)),
+            None => self.inner.generate_streaming(request).await,
+        }
+    }
+
+    async fn generate_streaming_raw(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<Option<RawStreamingResponse>, ConnectorError> {
+        match self.next_fault().await {
+            Some(Fault::Reset) => {
+                let Some(body) = self.inner.generate_streaming_raw(request).await? else {
+                    return Ok(None);
+                };
+                Ok(Some(
+                    body.take(1)
+                        .chain(stream::once(async { Err(fault_error(Fault::Reset)) }))
+                        .boxed(),
+                ))
+            }
+            Some(fault) => Err(fault_error(fault)),
+            None => self.inner.generate_streaming_raw(request).await,
+        }
+    }
+
+    fn get_config(&self) -> &ConnectorConfig {
+        self.inner.get_config()
+    }
+
+    fn update_config(&mut self, config: ConnectorConfig) {
+        // The wrapped connector can only be updated while not shared
+        if let Some(inner) = Arc::get_mut(&mut self.inner) {
+            inner.update_config(config);
+        }
+    }
+
+    fn provider_name(&self) -> &'static str {
+        self.inner.provider_name()
+    }
+
+    fn supports_model(&self, model_id: &str) -> bool {
+        self.inner.supports_model(model_id)
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+        match self.next_fault().await {
+            Some(fault) => Err(fault_error(fault)),
+            None => self.inner.list_models().await,
+        }
+    }
+}
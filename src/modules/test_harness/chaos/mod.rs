@@ -0,0 +1,299 @@
+//! Chaos Engineering
+//!
+//! This module provides fault injectors for the dependencies of IntelliRouter
+//! during integration tests: Redis and inter-role calls through a TCP proxy,
+//! and provider endpoints through a connector wrapping the mock provider.
+//! Injectors add latency, reset connections and fail calls, either at random
+//! or in deterministic bursts, and count what they injected so tests can
+//! assert that retries and circuit breakers reacted to it.
+
+mod connector;
+mod proxy;
+
+pub use connector::ChaosConnector;
+pub use proxy::ChaosProxy;
+
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Latency added to calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyFault {
+    /// Minimum added latency in milliseconds
+    pub min_ms: u64,
+    /// Maximum added latency in milliseconds
+    pub max_ms: u64,
+}
+
+/// Faults injected at random into calls
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FaultConfig {
+    /// Latency added to every call
+    pub latency: Option<LatencyFault>,
+    /// Probability that a call has its connection reset (0.0 - 1.0)
+    pub reset_probability: f64,
+    /// Probability that a call fails with an error (0.0 - 1.0)
+    pub error_probability: f64,
+    /// Seed of the random faults, for reproducible runs
+    pub seed: Option<u64>,
+}
+
+impl FaultConfig {
+    /// Add latency between `min_ms` and `max_ms` to every call
+    pub fn with_latency(mut self, min_ms: u64, max_ms: u64) -> Self {
+        self.latency = Some(LatencyFault {
+            min_ms,
+            max_ms: max_ms.max(min_ms),
+        });
+        self
+    }
+
+    /// Reset the connection of a share of the calls
+    pub fn with_reset_probability(mut self, probability: f64) -> Self {
+        self.reset_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Fail a share of the calls with an error
+    pub fn with_error_probability(mut self, probability: f64) -> Self {
+        self.error_probability = probability.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seed the random faults
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Fault failing a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Fault {
+    /// The connection is reset
+    Reset,
+    /// The call fails with an error
+    Error,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fault::Reset => write!(f, "connection reset"),
+            Fault::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Faults to inject into one call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FaultDecision {
+    /// Latency to add before the call
+    pub delay: Duration,
+    /// Fault failing the call, if any
+    pub fault: Option<Fault>,
+}
+
+/// Counts of the calls seen and the faults injected into them
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FaultStats {
+    /// Calls seen
+    pub calls: u64,
+    /// Calls that had latency added
+    pub delayed: u64,
+    /// Calls whose connection was reset
+    pub resets: u64,
+    /// Calls that failed with an error
+    pub errors: u64,
+}
+
+#[derive(Debug)]
+struct InjectorState {
+    config: FaultConfig,
+    rng: StdRng,
+    /// Fault of the burst in progress and the calls it still fails
+    burst: Option<(Fault, u32)>,
+    stats: FaultStats,
+}
+
+/// Source of the faults injected into the calls to a dependency
+///
+/// An injector is shared between the test and the proxy or connector
+/// injecting its faults, so the test can change faults mid-run.
+#[derive(Debug)]
+pub struct FaultInjector {
+    name: String,
+    state: Mutex<InjectorState>,
+}
+
+impl FaultInjector {
+    /// Create an injector for a dependency
+    pub fn new(name: impl Into<String>, config: FaultConfig) -> Self {
+        Self {
+            name: name.into(),
+            state: Mutex::new(InjectorState {
+                rng: rng(&config),
+                config,
+                burst: None,
+                stats: FaultStats::default(),
+            }),
+        }
+    }
+
+    /// Create an injector that doesn't inject anything until configured
+    pub fn passthrough(name: impl Into<String>) -> Self {
+        Self::new(name, FaultConfig::default())
+    }
+
+    /// Name of the dependency
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Replace the random faults
+    pub fn set_config(&self, config: FaultConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.rng = rng(&config);
+        state.config = config;
+    }
+
+    /// Stop injecting faults, including any burst in progress
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.config = FaultConfig::default();
+        state.burst = None;
+    }
+
+    /// Fail the next `calls` calls with an error
+    pub fn error_burst(&self, calls: u32) {
+        self.state.lock().unwrap().burst = Some((Fault::Error, calls));
+    }
+
+    /// Reset the connections of the next `calls` calls
+    pub fn reset_burst(&self, calls: u32) {
+        self.state.lock().unwrap().burst = Some((Fault::Reset, calls));
+    }
+
+    /// Faults injected so far
+    pub fn stats(&self) -> FaultStats {
+        self.state.lock().unwrap().stats
+    }
+
+    /// Decide the faults of the next call
+    ///
+    /// A burst in progress takes precedence over the random faults.
+    pub fn decide(&self) -> FaultDecision {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        state.stats.calls += 1;
+
+        let delay = match state.config.latency {
+            Some(latency) => Duration::from_millis(
+                state
+                    .rng
+                    .gen_range(latency.min_ms..=latency.max_ms.max(latency.min_ms)),
+            ),
+            None => Duration::ZERO,
+        };
+
+        let fault = match &mut state.burst {
+            Some((fault, remaining)) if *remaining > 0 => {
+                *remaining -= 1;
+                Some(*fault)
+            }
+            _ => {
+                state.burst = None;
+                if state
+                    .rng
+                    .gen_bool(state.config.reset_probability.clamp(0.0, 1.0))
+                {
+                    Some(Fault::Reset)
+                } else if state
+                    .rng
+                    .gen_bool(state.config.error_probability.clamp(0.0, 1.0))
+                {
+                    Some(Fault::Error)
+                } else {
+                    None
+                }
+            }
+        };
+
+        if !delay.is_zero() {
+            state.stats.delayed += 1;
+        }
+        match fault {
+            Some(Fault::Reset) => state.stats.resets += 1,
+            Some(Fault::Error) => state.stats.errors += 1,
+            None => {}
+        }
+        if let Some(fault) = fault {
+            debug!("Injecting {} into call to {}", fault, self.name);
+        }
+
+        FaultDecision { delay, fault }
+    }
+}
+
+/// Random number generator of the faults of a configuration
+fn rng(config: &FaultConfig) -> StdRng {
+    match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bursts_take_precedence_and_end() {
+        let injector = FaultInjector::passthrough("provider");
+        injector.error_burst(2);
+
+        let faults: Vec<Option<Fault>> = (0..4).map(|_| injector.decide().fault).collect();
+        assert_eq!(
+            faults,
+            vec![Some(Fault::Error), Some(Fault::Error), None, None]
+        );
+
+        injector.reset_burst(1);
+        assert_eq!(injector.decide().fault, Some(Fault::Reset));
+        assert_eq!(
+            injector.stats(),
+            FaultStats {
+                calls: 5,
+                delayed: 0,
+                resets: 1,
+                errors: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_seeded_faults_are_reproducible() {
+        let config = FaultConfig::default()
+            .with_latency(10, 20)
+            .with_error_probability(0.5)
+            .with_seed(7);
+        let run = || {
+            let injector = FaultInjector::new("redis", config.clone());
+            (0..50).map(|_| injector.decide()).collect::<Vec<_>>()
+        };
+
+        let decisions = run();
+        assert_eq!(decisions, run());
+        assert!(decisions
+            .iter()
+            .all(|d| (10..=20).contains(&(d.delay.as_millis() as u64))));
+        let errors = decisions.iter().filter(|d| d.fault.is_some()).count();
+        assert!((10..40).contains(&errors), "errors {}", errors);
+    }
+}
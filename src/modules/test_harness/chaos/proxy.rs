@@ -0,0 +1,210 @@
+//! Chaos Proxy
+//!
+//! This module provides a TCP proxy injecting faults between a client and a
+//! dependency, such as Redis or another IntelliRouter role. Faults are
+//! decided for every chunk the client writes, so pooled connections see them
+//! per request: latency delays the chunk, a reset aborts both connections
+//! with a TCP RST, and an error closes both connections cleanly.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use super::{Fault, FaultInjector};
+use crate::modules::test_harness::types::TestHarnessError;
+
+/// Size of the buffer chunks are read into
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// TCP proxy injecting faults into the connections to a dependency
+#[derive(Debug)]
+pub struct ChaosProxy {
+    local_addr: SocketAddr,
+    upstream: SocketAddr,
+    injector: Arc<FaultInjector>,
+    task: JoinHandle<()>,
+}
+
+impl ChaosProxy {
+    /// Start a proxy to `upstream` on a free local port
+    pub async fn start(
+        upstream: SocketAddr,
+        injector: Arc<FaultInjector>,
+    ) -> Result<Self, TestHarnessError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let local_addr = listener.local_addr()?;
+        debug!(
+            "Chaos proxy for {} listening on {}, forwarding to {}",
+            injector.name(),
+            local_addr,
+            upstream
+        );
+
+        let task = tokio::spawn({
+            let injector = injector.clone();
+            async move {
+                loop {
+                    let client = match listener.accept().await {
+                        Ok((client, _)) => client,
+                        Err(e) => {
+                            warn!("Chaos proxy failed to accept a connection: {}", e);
+                            continue;
+                        }
+                    };
+                    tokio::spawn(proxy_connection(client, upstream, injector.clone()));
+                }
+            }
+        });
+
+        Ok(Self {
+            local_addr,
+            upstream,
+            injector,
+            task,
+        })
+    }
+
+    /// Address clients connect to instead of the dependency
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Address of the dependency
+    pub fn upstream(&self) -> SocketAddr {
+        self.upstream
+    }
+
+    /// URL of the proxy for a scheme, e.g. `redis://127.0.0.1:40123`
+    pub fn url(&self, scheme: &str) -> String {
+        format!("{}://{}", scheme, self.local_addr)
+    }
+
+    /// Injector deciding the faults of the proxied calls
+    pub fn injector(&self) -> &Arc<FaultInjector> {
+        &self.injector
+    }
+
+    /// Stop accepting connections
+    pub fn stop(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for ChaosProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Forward a client connection to the upstream, injecting faults
+async fn proxy_connection(client: TcpStream, upstream: SocketAddr, injector: Arc<FaultInjector>) {
+    let server = match TcpStream::connect(upstream).await {
+        Ok(server) => server,
+        Err(e) => {
+            warn!("Chaos proxy failed to connect to {}: {}", upstream, e);
+            return;
+        }
+    };
+    let (mut client_read, mut client_write) = client.into_split();
+    let (mut server_read, mut server_write) = server.into_split();
+
+    let requests = async {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        loop {
+            let n = client_read.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            let decision = injector.decide();
+            if !decision.delay.is_zero() {
+                tokio::time::sleep(decision.delay).await;
+            }
+            if let Some(fault) = decision.fault {
+                return Ok(Some(fault));
+            }
+            server_write.write_all(&buf[..n]).await?;
+        }
+    };
+    let responses = tokio::io::copy(&mut server_read, &mut client_write);
+
+    let fault = tokio::select! {
+        result = requests => result.unwrap_or_else(|e: std::io::Error| {
+            debug!("Chaos proxy connection closed: {}", e);
+            None
+        }),
+        _ = responses => None,
+    };
+
+    if fault == Some(Fault::Reset) {
+        // Closing with a zero linger sends a RST instead of a FIN
+        for stream in [
+            client_read.reunite(client_write),
+            server_read.reunite(server_write),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let _ = stream.set_zero_linger();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Echo server standing in for a dependency
+    async fn echo_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let (mut read, mut write) = stream.split();
+                    let _ = tokio::io::copy(&mut read, &mut write).await;
+                });
+            }
+        });
+        addr
+    }
+
+    async fn roundtrip(stream: &mut TcpStream, message: &[u8]) -> std::io::Result<Vec<u8>> {
+        stream.write_all(message).await?;
+        let mut buf = vec![0u8; message.len()];
+        stream.read_exact(&mut buf).await?;
+        Ok(buf)
+    }
+
+    #[tokio::test]
+    async fn test_proxy_forwards_and_injects_faults() {
+        let injector = Arc::new(FaultInjector::passthrough("redis"));
+        let proxy = ChaosProxy::start(echo_server().await, injector.clone())
+            .await
+            .unwrap();
+
+        let mut stream = TcpStream::connect(proxy.local_addr()).await.unwrap();
+        assert_eq!(roundtrip(&mut stream, b"PING").await.unwrap(), b"PING");
+
+        injector.reset_burst(1);
+        let err = roundtrip(&mut stream, b"PING").await.unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::UnexpectedEof
+        ));
+
+        // The burst is over, so a new connection works again
+        let mut stream = TcpStream::connect(proxy.local_addr()).await.unwrap();
+        assert_eq!(roundtrip(&mut stream, b"PING").await.unwrap(), b"PING");
+
+        injector.error_burst(1);
+        let err = roundtrip(&mut stream, b"PING").await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        let stats = injector.stats();
+        assert_eq!((stats.calls, stats.resets, stats.errors), (4, 1, 1));
+    }
+}
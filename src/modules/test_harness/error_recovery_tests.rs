@@ -434,6 +434,7 @@ fn create_mock_routing_request() -> RoutingRequest {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 }],
                 temperature: Some(0.7),
                 top_p: Some(0.9),
@@ -443,6 +444,12 @@ fn create_mock_routing_request() -> RoutingRequest {
                 presence_penalty: Some(0.0),
                 frequency_penalty: Some(0.0),
                 user: None,
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
             },
             user_id: Some("test-user".to_string()),
             session_id: Some("test-session".to_string()),
@@ -475,8 +482,10 @@ impl ModelConnector for MockConnector {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                content_filter: None,
             }],
             usage: None,
         })
@@ -519,8 +528,10 @@ impl ModelConnector for MockFailingConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             })
@@ -648,8 +659,10 @@ impl ModelConnector for MockTimeoutConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             })
@@ -777,8 +790,10 @@ impl ModelConnector for MockRateLimitConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             })
@@ -908,8 +923,10 @@ impl ModelConnector for MockNetworkErrorConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             })
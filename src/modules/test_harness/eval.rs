@@ -0,0 +1,88 @@
+//! Prompt Eval Suites
+//!
+//! This module turns an eval corpus into a test suite with one test case per
+//! saved request. Each case runs against the baseline and candidate variants
+//! and fails when the candidate regressed it, or when neither variant could
+//! answer it; its similarity is kept as a metric and its full result, diff
+//! and judge verdict included, as custom data.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::FutureExt;
+use tracing::info;
+
+use crate::modules::remote::eval::{CaseStatus, EvalCase, EvalRunner, EvalVariant};
+use crate::modules::test_harness::{
+    TestCase, TestCategory, TestContext, TestOutcome, TestResult, TestSuite,
+};
+
+/// Create a test suite running an eval corpus against two variants
+pub fn create_eval_test_suite(
+    name: impl Into<String>,
+    runner: EvalRunner,
+    cases: Vec<EvalCase>,
+    baseline: EvalVariant,
+    candidate: EvalVariant,
+) -> TestSuite {
+    let description = format!(
+        "Regressions of {} against {} on {} saved requests",
+        candidate.name,
+        baseline.name,
+        cases.len()
+    );
+    let runner = Arc::new(runner);
+    let variants = Arc::new((baseline, candidate));
+
+    TestSuite::new(name)
+        .with_description(description)
+        .with_test_cases(cases.into_iter().enumerate().map(|(index, case)| {
+            create_eval_test_case(index, Arc::new(case), runner.clone(), variants.clone())
+        }))
+}
+
+/// Create a test case running a saved request against two variants
+fn create_eval_test_case(
+    index: usize,
+    case: Arc<EvalCase>,
+    runner: Arc<EvalRunner>,
+    variants: Arc<(EvalVariant, EvalVariant)>,
+) -> TestCase {
+    let name = format!("eval_{}", case.id);
+    TestCase::new(
+        TestContext::new(TestCategory::Integration, name.clone()).with_tag("eval"),
+        move |_ctx| {
+            let (name, case, runner, variants) =
+                (name.clone(), case.clone(), runner.clone(), variants.clone());
+            async move {
+                let start = Instant::now();
+                let (baseline, candidate) = &*variants;
+                let result = runner.run_case(index, &case, baseline, candidate).await;
+                info!("Eval case {}: {}", case.id, result.status);
+
+                let outcome = match result.status {
+                    CaseStatus::Regressed | CaseStatus::Failed => TestOutcome::Failed,
+                    _ => TestOutcome::Passed,
+                };
+                let mut test_result = TestResult::new(&name, TestCategory::Integration, outcome)
+                    .with_duration(start.elapsed());
+                if let Some(similarity) = result.similarity {
+                    test_result = test_result.with_metric("similarity", similarity);
+                }
+                if outcome == TestOutcome::Failed {
+                    let reason = result
+                        .verdict
+                        .as_ref()
+                        .map(|verdict| verdict.reason.clone())
+                        .or_else(|| result.candidate_error.clone())
+                        .unwrap_or_default();
+                    test_result = test_result
+                        .with_error(format!("Case {} {}: {}", case.id, result.status, reason));
+                }
+                test_result.with_custom_data("eval_case", &result)
+            }
+            .boxed()
+        },
+    )
+    .with_parallel(true)
+}
@@ -428,6 +428,7 @@ fn create_end_to_end_request_flow_test_case() -> TestCase {
                             name: None,
                             function_call: None,
                             tool_calls: None,
+                            tool_call_id: None,
                         });
                     }
 
@@ -438,6 +439,7 @@ fn create_end_to_end_request_flow_test_case() -> TestCase {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     });
 
                     // Create a request with the conversation
@@ -454,6 +456,12 @@ fn create_end_to_end_request_flow_test_case() -> TestCase {
                                 presence_penalty: Some(0.0),
                                 frequency_penalty: Some(0.0),
                                 user: None,
+                                stop: None,
+                                logit_bias: None,
+                                seed: None,
+                                top_k: None,
+                                stream_options: None,
+                                response_format: None,
                             },
                             user_id: Some("test-user".to_string()),
                             session_id: Some("test-session".to_string()),
@@ -558,6 +566,7 @@ fn create_test_routing_request(model: &str) -> RoutingRequest {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 }],
                 temperature: Some(0.7),
                 top_p: Some(0.9),
@@ -567,6 +576,12 @@ fn create_test_routing_request(model: &str) -> RoutingRequest {
                 presence_penalty: Some(0.0),
                 frequency_penalty: Some(0.0),
                 user: None,
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
             },
             user_id: Some("test-user".to_string()),
             session_id: Some("test-session".to_string()),
@@ -617,8 +632,10 @@ impl ModelConnector for TestConnector {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                content_filter: None,
             }],
             usage: None,
         })
@@ -5,10 +5,12 @@
 //! performance, and security tests.
 
 pub mod assert;
+pub mod chaos;
 pub mod config;
 pub mod engine;
 pub mod environment;
 pub mod error_recovery_tests;
+pub mod eval;
 pub mod integration_tests;
 pub mod load_tests;
 pub mod mock;
@@ -17,12 +19,14 @@ pub mod plugins;
 pub mod reporting;
 pub mod scenario;
 pub mod security;
+pub mod streaming_benchmarks;
 pub mod types;
 pub mod utils;
 
 pub use assert::{
     assert_context, assert_that, AssertionBuilder, AssertionContext, AssertionResult,
 };
+pub use chaos::{ChaosConnector, ChaosProxy, FaultConfig, FaultInjector, FaultStats};
 pub use config::{
     create_config_set, create_config_test, create_config_test_suite, create_config_value,
     create_test_case_from_config_suite, ConfigSet, ConfigSource, ConfigTest, ConfigTestParams,
@@ -31,6 +35,7 @@ pub use config::{
 pub use engine::{TestEngine, TestEngineBuilder, TestExecutionOptions};
 pub use environment::{Environment, EnvironmentExt, LocalEnvironment};
 pub use error_recovery_tests::create_error_recovery_test_suite;
+pub use eval::create_eval_test_suite;
 pub use integration_tests::{
     create_integration_test_suite,
     error_recovery_integration_tests::create_error_recovery_integration_test_suite,
@@ -57,6 +62,7 @@ pub use security::{
     create_test_case_from_security_suite, create_vulnerability, SecurityTest, SecurityTestParams,
     SecurityTestResult, SecurityTestSuite, Vulnerability, VulnerabilitySeverity,
 };
+pub use streaming_benchmarks::create_streaming_benchmark_suite;
 pub use types::{
     AssertionError, TestCase, TestCategory, TestContext, TestHarnessError, TestOutcome, TestResult,
     TestSuite, TestSuiteResult,
@@ -911,55 +911,109 @@ impl LoadGenerator {
         // Calculate overall metrics
         let test_duration = start_time.elapsed();
         let metrics = metrics.lock().await;
-        let metrics_vec = metrics.clone();
-
-        // Calculate overall throughput
-        let total_requests = metrics_vec
-            .iter()
-            .filter(|m| m.metric_type == MetricType::Latency)
-            .count();
-
-        let overall_throughput = if test_duration.as_secs_f64() > 0.0 {
-            total_requests as f64 / test_duration.as_secs_f64()
-        } else {
-            0.0
-        };
+        Ok(with_overall_metrics(metrics.clone(), test_duration))
+    }
 
-        let overall_throughput_metric = Metric::new(
-            "overall_throughput",
-            MetricType::Throughput,
-            overall_throughput,
-            "rps",
-        );
+    /// Replay requests at fixed offsets from the start, as they arrived in
+    /// recorded traffic
+    ///
+    /// Unlike `generate_load`, which keeps a number of users busy, requests
+    /// are sent at their offsets whether or not earlier ones completed, so
+    /// a slow deployment sees the recorded arrival rate instead of a lower
+    /// one. `request_fn` is called with the index of the request to send.
+    /// The same per-request and overall metrics are collected, with requests
+    /// tagged by index.
+    pub async fn replay_load<F, Fut>(
+        &self,
+        offsets: &[Duration],
+        request_timeout: Duration,
+        request_fn: F,
+    ) -> Result<Vec<Metric>, TestHarnessError>
+    where
+        F: Fn(usize) -> Fut + Send + Sync + Clone + 'static,
+        Fut: std::future::Future<Output = Result<Duration, TestHarnessError>> + Send + 'static,
+    {
+        info!("Replaying {} requests", offsets.len());
 
-        // Calculate overall error rate
-        let error_count = metrics_vec
-            .iter()
-            .filter(|m| m.metric_type == MetricType::Latency && m.value < 0.0)
-            .count();
+        let start_time = Instant::now();
+        let metrics = Arc::clone(&self.metrics);
 
-        let overall_error_rate = if total_requests > 0 {
-            error_count as f64 / total_requests as f64 * 100.0
-        } else {
-            0.0
-        };
+        let mut request_futures = Vec::new();
+        for (index, offset) in offsets.iter().copied().enumerate() {
+            let request_fn = request_fn.clone();
+            let metrics = Arc::clone(&metrics);
 
-        let overall_error_rate_metric = Metric::new(
-            "overall_error_rate",
-            MetricType::ErrorRate,
-            overall_error_rate,
-            "%",
-        );
+            request_futures.push(tokio::spawn(async move {
+                tokio::time::sleep_until(tokio::time::Instant::from_std(start_time + offset)).await;
+
+                let metric = match tokio::time::timeout(request_timeout, request_fn(index)).await {
+                    Ok(Ok(latency)) => Metric::new(
+                        "request_latency",
+                        MetricType::Latency,
+                        latency.as_millis() as f64,
+                        "ms",
+                    ),
+                    Ok(Err(e)) => Metric::new("request_error", MetricType::Latency, -1.0, "ms")
+                        .with_tag("error", e.to_string()),
+                    Err(_) => Metric::new("request_timeout", MetricType::Latency, -1.0, "ms")
+                        .with_tag("error", "Request timed out"),
+                };
+                let metric = metric.with_tag("request_id", index.to_string());
+                metrics.lock().await.push(metric);
+            }));
+        }
 
-        // Add overall metrics to the result
-        let mut result = metrics_vec.clone();
-        result.push(overall_throughput_metric);
-        result.push(overall_error_rate_metric);
+        // Wait for all requests to complete
+        futures::future::join_all(request_futures).await;
 
-        Ok(result)
+        let test_duration = start_time.elapsed();
+        let metrics = metrics.lock().await;
+        Ok(with_overall_metrics(metrics.clone(), test_duration))
     }
 }
 
+/// Add the overall throughput and error rate of the requests of a load test
+/// to its metrics
+fn with_overall_metrics(mut metrics: Vec<Metric>, test_duration: Duration) -> Vec<Metric> {
+    // Calculate overall throughput
+    let total_requests = metrics
+        .iter()
+        .filter(|m| m.metric_type == MetricType::Latency)
+        .count();
+
+    let overall_throughput = if test_duration.as_secs_f64() > 0.0 {
+        total_requests as f64 / test_duration.as_secs_f64()
+    } else {
+        0.0
+    };
+
+    // Calculate overall error rate
+    let error_count = metrics
+        .iter()
+        .filter(|m| m.metric_type == MetricType::Latency && m.value < 0.0)
+        .count();
+
+    let overall_error_rate = if total_requests > 0 {
+        error_count as f64 / total_requests as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    metrics.push(Metric::new(
+        "overall_throughput",
+        MetricType::Throughput,
+        overall_throughput,
+        "rps",
+    ));
+    metrics.push(Metric::new(
+        "overall_error_rate",
+        MetricType::ErrorRate,
+        overall_error_rate,
+        "%",
+    ));
+    metrics
+}
+
 /// Create a new performance test
 pub fn create_performance_test(name: impl Into<String>) -> PerformanceTestBuilder {
     PerformanceTestBuilder::new(name)
@@ -1066,6 +1120,40 @@ mod tests {
         assert!(result.summary.contains_key("throughput_avg"));
     }
 
+    #[tokio::test]
+    async fn test_replay_load() {
+        let load_generator = create_load_generator("test_replay_load");
+        let offsets = [
+            Duration::ZERO,
+            Duration::from_millis(20),
+            Duration::from_millis(40),
+        ];
+
+        let metrics = load_generator
+            .replay_load(&offsets, Duration::from_millis(50), |index| async move {
+                match index {
+                    1 => Err(TestHarnessError::ExecutionError("failed".to_string())),
+                    2 => {
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Ok(Duration::from_millis(100))
+                    }
+                    _ => Ok(Duration::from_millis(5)),
+                }
+            })
+            .await
+            .unwrap();
+
+        let names: Vec<&str> = metrics.iter().map(|m| m.name.as_str()).collect();
+        assert!(names.contains(&"request_latency"));
+        assert!(names.contains(&"request_error"));
+        assert!(names.contains(&"request_timeout"));
+        let error_rate = metrics
+            .iter()
+            .find(|m| m.name == "overall_error_rate")
+            .unwrap();
+        assert!((error_rate.value - 200.0 / 3.0).abs() < 0.01);
+    }
+
     #[tokio::test]
     async fn test_load_generator() {
         // Create a load generator
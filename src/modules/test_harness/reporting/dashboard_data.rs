@@ -0,0 +1,472 @@
+//! Project Dashboard Data
+//!
+//! This module writes test harness results into the project dashboard's data
+//! directory, in the files its collectors read:
+//!
+//! - `performance/benchmark_results_<timestamp>.csv`, one line per benchmark
+//!   with `component,benchmark,median,mean,std_dev,min,max,timestamp` and
+//!   times in milliseconds, as written by `scripts/run_benchmarks.sh`
+//! - `security/security_metrics_<timestamp>.csv`, a `Date,Total,Critical,
+//!   High,Medium,Low` header and one line of issue counts, as written by
+//!   `scripts/security/run_security_audit.sh`
+//! - `security/vulnerabilities_<timestamp>.json`, the issues themselves
+//!
+//! Scenarios are written as benchmarks: one line per executed step with the
+//! scenario as component, and a `total` line with the whole scenario. Every
+//! report rewrites the files of the run, so they always hold all of its
+//! results and the dashboard picks them up on its next collection.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::Reporter;
+use crate::modules::test_harness::benchmark::BenchmarkResult;
+use crate::modules::test_harness::scenario::{ScenarioStepResult, ScenarioStepStatus};
+use crate::modules::test_harness::security::{
+    SecurityTestResult, Vulnerability, VulnerabilitySeverity,
+};
+use crate::modules::test_harness::types::{TestResult, TestSuiteResult};
+
+/// Header of the security metrics file
+const SECURITY_HEADER: &str = "Date,Total,Critical,High,Medium,Low";
+
+/// Benchmark line of the performance file
+#[derive(Debug, Clone, PartialEq)]
+struct BenchmarkLine {
+    median_ms: f64,
+    mean_ms: f64,
+    std_dev_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    timestamp: DateTime<Utc>,
+}
+
+impl BenchmarkLine {
+    /// Line of a single measurement
+    fn single(duration: Duration, timestamp: DateTime<Utc>) -> Self {
+        let ms = as_ms(duration);
+        Self {
+            median_ms: ms,
+            mean_ms: ms,
+            std_dev_ms: 0.0,
+            min_ms: ms,
+            max_ms: ms,
+            timestamp,
+        }
+    }
+}
+
+/// Vulnerability as read by the dashboard
+#[derive(Debug, Clone, Serialize)]
+struct DashboardVulnerability {
+    id: String,
+    name: String,
+    description: String,
+    severity: VulnerabilitySeverity,
+    location: Option<String>,
+    line: Option<u32>,
+    cve_id: Option<String>,
+    cvss_score: Option<f64>,
+    cwe_id: Option<String>,
+    remediation: Option<String>,
+}
+
+impl From<&Vulnerability> for DashboardVulnerability {
+    fn from(vulnerability: &Vulnerability) -> Self {
+        let reference = |prefix: &str| {
+            vulnerability
+                .tags
+                .iter()
+                .chain(&vulnerability.references)
+                .find(|r| r.starts_with(prefix))
+                .cloned()
+        };
+        Self {
+            id: vulnerability.id.clone(),
+            name: vulnerability.name.clone(),
+            description: vulnerability.description.clone(),
+            severity: vulnerability.severity,
+            location: Some(vulnerability.location.clone()).filter(|l| !l.is_empty()),
+            line: None,
+            cve_id: reference("CVE-"),
+            cvss_score: None,
+            cwe_id: reference("CWE-"),
+            remediation: vulnerability.remediation.clone(),
+        }
+    }
+}
+
+/// Results reported so far in the run
+#[derive(Debug, Default)]
+struct DashboardRun {
+    /// Benchmark lines by component and benchmark
+    benchmarks: BTreeMap<(String, String), BenchmarkLine>,
+    /// Vulnerabilities by security test
+    vulnerabilities: BTreeMap<String, Vec<Vulnerability>>,
+}
+
+/// Reporter writing results into the project dashboard's data directory
+pub struct DashboardDataReporter {
+    /// Data directory of the dashboard
+    data_dir: PathBuf,
+    /// Timestamp of the run, in its file names
+    timestamp: String,
+    /// Results reported so far
+    run: Mutex<DashboardRun>,
+}
+
+impl DashboardDataReporter {
+    /// Create a new dashboard data reporter for a run starting now
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            timestamp: Utc::now().format("%Y%m%d_%H%M%S").to_string(),
+            run: Mutex::new(DashboardRun::default()),
+        }
+    }
+
+    /// Path of the performance file of the run
+    pub fn benchmark_file(&self) -> PathBuf {
+        self.data_dir
+            .join("performance")
+            .join(format!("benchmark_results_{}.csv", self.timestamp))
+    }
+
+    /// Path of the security metrics file of the run
+    pub fn security_file(&self) -> PathBuf {
+        self.data_dir
+            .join("security")
+            .join(format!("security_metrics_{}.csv", self.timestamp))
+    }
+
+    /// Path of the vulnerabilities file of the run
+    pub fn vulnerabilities_file(&self) -> PathBuf {
+        self.data_dir
+            .join("security")
+            .join(format!("vulnerabilities_{}.json", self.timestamp))
+    }
+
+    /// Report a benchmark result
+    ///
+    /// The component is the benchmark's `component` parameter, or its type
+    /// when it has none.
+    pub async fn report_benchmark(&self, result: &BenchmarkResult) {
+        let component = result
+            .config
+            .parameters
+            .get("component")
+            .cloned()
+            .unwrap_or_else(|| result.config.benchmark_type.to_string());
+        let mean_ms = as_ms(result.latency.avg_duration);
+        let line = BenchmarkLine {
+            median_ms: as_ms(result.latency.median_duration),
+            mean_ms,
+            std_dev_ms: std_dev_ms(&result.operation_durations, mean_ms),
+            min_ms: as_ms(result.latency.min_duration),
+            max_ms: as_ms(result.latency.max_duration),
+            timestamp: result.end_time,
+        };
+        let content = {
+            let mut run = self.run.lock().unwrap();
+            run.benchmarks
+                .insert((component, result.config.name.clone()), line);
+            benchmark_csv(&run.benchmarks)
+        };
+        write(&self.benchmark_file(), content).await;
+    }
+
+    /// Report a security test result
+    ///
+    /// Informational findings are left out, as the dashboard has no severity
+    /// for them.
+    pub async fn report_security(&self, result: &SecurityTestResult) {
+        let (metrics, vulnerabilities) = {
+            let mut run = self.run.lock().unwrap();
+            run.vulnerabilities.insert(
+                result.name.clone(),
+                result
+                    .vulnerabilities
+                    .iter()
+                    .filter(|v| v.severity != VulnerabilitySeverity::Info)
+                    .cloned()
+                    .collect(),
+            );
+            let vulnerabilities: Vec<DashboardVulnerability> = run
+                .vulnerabilities
+                .values()
+                .flatten()
+                .map(DashboardVulnerability::from)
+                .collect();
+            (
+                security_csv(&vulnerabilities, result.end_time),
+                vulnerabilities,
+            )
+        };
+        write(&self.security_file(), metrics).await;
+        match serde_json::to_string_pretty(&vulnerabilities) {
+            Ok(content) => write(&self.vulnerabilities_file(), content).await,
+            Err(e) => eprintln!("Error serializing vulnerabilities: {}", e),
+        }
+    }
+
+    /// Record the steps of a scenario result, if it is one
+    fn record_scenario(&self, run: &mut DashboardRun, result: &TestResult) -> bool {
+        let Some(steps) = result.custom_data.get("step_results") else {
+            return false;
+        };
+        let Ok(steps) =
+            serde_json::from_value::<BTreeMap<String, ScenarioStepResult>>(steps.clone())
+        else {
+            return false;
+        };
+        for step in steps.values() {
+            if step.status == ScenarioStepStatus::Skipped {
+                continue;
+            }
+            run.benchmarks.insert(
+                (result.name.clone(), step.name.clone()),
+                BenchmarkLine::single(step.duration, result.end_time),
+            );
+        }
+        run.benchmarks.insert(
+            (result.name.clone(), "total".to_string()),
+            BenchmarkLine::single(result.duration, result.end_time),
+        );
+        true
+    }
+
+    /// Record scenario results and rewrite the performance file
+    async fn report_scenarios<'a>(&self, results: impl IntoIterator<Item = &'a TestResult>) {
+        let content = {
+            let mut run = self.run.lock().unwrap();
+            let mut recorded = false;
+            for result in results {
+                recorded |= self.record_scenario(&mut run, result);
+            }
+            if !recorded {
+                return;
+            }
+            benchmark_csv(&run.benchmarks)
+        };
+        write(&self.benchmark_file(), content).await;
+    }
+}
+
+#[async_trait]
+impl Reporter for DashboardDataReporter {
+    async fn report_test_result(&self, result: &TestResult) {
+        self.report_scenarios([result]).await;
+    }
+
+    async fn report_suite_result(&self, result: &TestSuiteResult) {
+        self.report_scenarios(&result.test_results).await;
+    }
+
+    async fn report_warning(&self, _message: &str) {}
+
+    async fn report_error(&self, _message: &str) {}
+
+    async fn report_message(&self, _message: &str) {}
+}
+
+/// Content of the performance file
+fn benchmark_csv(benchmarks: &BTreeMap<(String, String), BenchmarkLine>) -> String {
+    benchmarks
+        .iter()
+        .map(|((component, benchmark), line)| {
+            format!(
+                "{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{}\n",
+                csv_field(component),
+                csv_field(benchmark),
+                line.median_ms,
+                line.mean_ms,
+                line.std_dev_ms,
+                line.min_ms,
+                line.max_ms,
+                line.timestamp.format("%Y-%m-%d %H:%M:%S")
+            )
+        })
+        .collect()
+}
+
+/// Content of the security metrics file
+fn security_csv(vulnerabilities: &[DashboardVulnerability], date: DateTime<Utc>) -> String {
+    let count = |severity| {
+        vulnerabilities
+            .iter()
+            .filter(|v| v.severity == severity)
+            .count()
+    };
+    format!(
+        "{}\n{},{},{},{},{},{}\n",
+        SECURITY_HEADER,
+        date.format("%Y-%m-%d"),
+        vulnerabilities.len(),
+        count(VulnerabilitySeverity::Critical),
+        count(VulnerabilitySeverity::High),
+        count(VulnerabilitySeverity::Medium),
+        count(VulnerabilitySeverity::Low)
+    )
+}
+
+/// Field of a line the dashboard splits on commas
+fn csv_field(value: &str) -> String {
+    value.replace([',', '\n', '\r'], " ")
+}
+
+fn as_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Standard deviation of operation durations, in milliseconds
+fn std_dev_ms(durations: &[Duration], mean_ms: f64) -> f64 {
+    if durations.is_empty() {
+        return 0.0;
+    }
+    let variance = durations
+        .iter()
+        .map(|d| (as_ms(*d) - mean_ms).powi(2))
+        .sum::<f64>()
+        / durations.len() as f64;
+    variance.sqrt()
+}
+
+/// Write a file of the data directory, creating its directory
+async fn write(path: &Path, content: String) {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(dir).await {
+            eprintln!("Error creating dashboard data directory: {}", e);
+            return;
+        }
+    }
+    if let Err(e) = tokio::fs::write(path, content).await {
+        eprintln!("Error writing dashboard data to {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::test_harness::benchmark::{BenchmarkConfig, BenchmarkType};
+    use crate::modules::test_harness::metrics::TimeMetrics;
+    use crate::modules::test_harness::security::SecurityTestParams;
+    use crate::modules::test_harness::types::{TestCategory, TestOutcome};
+    use std::collections::HashMap;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "intellirouter_dashboard_data_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_benchmarks_and_scenarios() {
+        let dir = data_dir("performance");
+        let reporter = DashboardDataReporter::new(&dir);
+
+        let durations = vec![
+            Duration::from_millis(10),
+            Duration::from_millis(20),
+            Duration::from_millis(30),
+        ];
+        let mut result = BenchmarkResult::new(
+            BenchmarkConfig::new("route", "route_request", BenchmarkType::Latency)
+                .with_parameter("component", "router"),
+        );
+        result.latency = TimeMetrics::new(&durations);
+        result.operation_durations = durations;
+        reporter.report_benchmark(&result).await;
+
+        let mut steps = HashMap::new();
+        steps.insert(
+            "login".to_string(),
+            ScenarioStepResult::new("login").with_duration(Duration::from_millis(5)),
+        );
+        steps.insert(
+            "cleanup".to_string(),
+            ScenarioStepResult::new("cleanup").with_status(ScenarioStepStatus::Skipped),
+        );
+        let scenario = TestResult::new("checkout", TestCategory::Integration, TestOutcome::Passed)
+            .with_duration(Duration::from_millis(8))
+            .with_custom_data("step_results", steps)
+            .unwrap();
+        reporter.report_test_result(&scenario).await;
+        reporter
+            .report_test_result(&TestResult::new(
+                "plain",
+                TestCategory::Unit,
+                TestOutcome::Passed,
+            ))
+            .await;
+
+        let content = std::fs::read_to_string(reporter.benchmark_file()).unwrap();
+        let lines: Vec<Vec<&str>> = content
+            .lines()
+            .map(|line| line.split(',').collect())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0][..4], ["checkout", "login", "5.000", "5.000"]);
+        assert_eq!(lines[1][..4], ["checkout", "total", "8.000", "8.000"]);
+        assert_eq!(
+            lines[2][..7],
+            [
+                "router",
+                "route_request",
+                "20.000",
+                "20.000",
+                "8.165",
+                "10.000",
+                "30.000"
+            ]
+        );
+        assert!(lines.iter().all(|line| line.len() == 8));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_security_results() {
+        let dir = data_dir("security");
+        let reporter = DashboardDataReporter::new(&dir);
+
+        let mut result = SecurityTestResult::new(
+            "headers",
+            SecurityTestParams::default(),
+            TestOutcome::Failed,
+        );
+        result.vulnerabilities = vec![
+            Vulnerability::new("V1", "Missing HSTS", "", VulnerabilitySeverity::High, "/")
+                .with_reference("CWE-319"),
+            Vulnerability::new("V2", "Server banner", "", VulnerabilitySeverity::Info, "/"),
+        ];
+        reporter.report_security(&result).await;
+        // Reporting the same test again replaces its findings
+        reporter.report_security(&result).await;
+
+        let content = std::fs::read_to_string(reporter.security_file()).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], SECURITY_HEADER);
+        assert!(lines[1].ends_with(",1,0,1,0,0"));
+
+        let vulnerabilities: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(reporter.vulnerabilities_file()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(vulnerabilities.as_array().unwrap().len(), 1);
+        assert_eq!(vulnerabilities[0]["severity"], "High");
+        assert_eq!(vulnerabilities[0]["cwe_id"], "CWE-319");
+        assert_eq!(vulnerabilities[0]["location"], "/");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
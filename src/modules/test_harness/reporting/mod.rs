@@ -4,6 +4,7 @@
 //! and visualizing test metrics and statistics.
 
 mod dashboard;
+mod dashboard_data;
 mod exporters;
 mod formatters;
 mod renderers;
@@ -11,6 +12,7 @@ mod templates;
 mod web_server;
 
 pub use dashboard::{Dashboard, DashboardConfig, DashboardPanel, DashboardView};
+pub use dashboard_data::DashboardDataReporter;
 pub use exporters::{ExportFormat, Exporter};
 pub use formatters::{
     ConsoleFormatter, Formatter, FormatterConfig, HtmlFormatter, JsonFormatter, MarkdownFormatter,
@@ -0,0 +1,153 @@
+//! Streaming Benchmarks
+//!
+//! This module compares the CPU cost per streamed token of forwarding an
+//! OpenAI-format provider stream by parsing and re-serializing every chunk
+//! against passing the provider's events through unparsed.
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use futures::FutureExt;
+use tracing::info;
+
+use crate::modules::llm_proxy::dto::ChatCompletionChunk;
+use crate::modules::llm_proxy::passthrough::{self, FrameSplitter};
+use crate::modules::test_harness::{
+    AssertionHelper, TestCase, TestCategory, TestContext, TestOutcome, TestResult, TestSuite,
+};
+
+/// Create a test suite benchmarking streamed response forwarding
+pub fn create_streaming_benchmark_suite() -> TestSuite {
+    TestSuite::new("Streaming Benchmarks")
+        .with_description("Benchmarks of streamed response forwarding")
+        .with_test_case(create_passthrough_benchmark_test_case())
+}
+
+/// Create a test case comparing re-serialization with passthrough
+fn create_passthrough_benchmark_test_case() -> TestCase {
+    TestCase::new(
+        TestContext::new(
+            TestCategory::Performance,
+            "streaming_passthrough_benchmark".to_string(),
+        ),
+        |_ctx| {
+            async move {
+                info!("Running streaming passthrough benchmark");
+
+                // Parameters
+                let num_tokens = 2_000;
+                let read_size = 1_400;
+                let iterations = 20;
+
+                let chunks = create_provider_body(num_tokens, read_size);
+
+                let (reserialized, reserialize_time) =
+                    time_path(iterations, || forward_reserialized(&chunks));
+                let (passed_through, passthrough_time) =
+                    time_path(iterations, || forward_passthrough(&chunks));
+
+                // Both paths must forward every token and the end of the stream
+                AssertionHelper::assert_eq(
+                    reserialized,
+                    num_tokens + 1,
+                    "Re-serialized path should forward every event",
+                )?;
+                AssertionHelper::assert_eq(
+                    passed_through,
+                    num_tokens + 1,
+                    "Passthrough path should forward every event",
+                )?;
+
+                let per_token =
+                    |time: Duration| time.as_nanos() as f64 / (iterations * num_tokens) as f64;
+                let reserialize_ns = per_token(reserialize_time);
+                let passthrough_ns = per_token(passthrough_time);
+                let speedup = reserialize_ns / passthrough_ns.max(f64::MIN_POSITIVE);
+
+                info!("Streaming passthrough benchmark results:");
+                info!("Re-serialize: {:.1} ns/token", reserialize_ns);
+                info!("Passthrough: {:.1} ns/token", passthrough_ns);
+                info!("Speedup: {:.1}x", speedup);
+
+                Ok(TestResult::new(
+                    "streaming_passthrough_benchmark",
+                    TestCategory::Performance,
+                    TestOutcome::Passed,
+                )
+                .with_metric("reserialize_ns_per_token", reserialize_ns)
+                .with_metric("passthrough_ns_per_token", passthrough_ns)
+                .with_metric("speedup", speedup))
+            }
+            .boxed()
+        },
+    )
+}
+
+/// Build an OpenAI-format SSE body of one event per token, split into
+/// network reads of `read_size` bytes
+fn create_provider_body(num_tokens: usize, read_size: usize) -> Vec<Bytes> {
+    let mut body = String::new();
+    for i in 0..num_tokens {
+        body.push_str(&format!(
+            "data: {{\"id\":\"chatcmpl-bench\",\"object\":\"chat.completion.chunk\",\"created\":1700000000,\"model\":\"gpt-4o\",\"choices\":[{{\"index\":0,\"delta\":{{\"content\":\"token{} \"}},\"finish_reason\":null}}]}}\n\n",
+            i
+        ));
+    }
+    body.push_str("data: [DONE]\n\n");
+
+    Bytes::from(body)
+        .chunks(read_size)
+        .map(Bytes::copy_from_slice)
+        .collect()
+}
+
+/// Run a forwarding path `iterations` times, returning its event count and total time
+fn time_path(iterations: usize, path: impl Fn() -> usize) -> (usize, Duration) {
+    let mut events = 0;
+    let started = Instant::now();
+    for _ in 0..iterations {
+        events = std::hint::black_box(path());
+    }
+    (events, started.elapsed())
+}
+
+/// Forward a body by parsing each event into a chunk and serializing it again
+fn forward_reserialized(chunks: &[Bytes]) -> usize {
+    let mut pending = String::new();
+    let mut forwarded = Vec::new();
+    for chunk in chunks {
+        pending.push_str(&String::from_utf8_lossy(chunk));
+        while let Some(end) = pending.find("\n\n") {
+            let event: String = pending.drain(..end + 2).collect();
+            let Some(data) = passthrough::event_data(event.as_bytes()) else {
+                continue;
+            };
+            if data == passthrough::DONE {
+                forwarded.push(Bytes::from_static(b"data: [DONE]\n\n"));
+                continue;
+            }
+            if let Ok(chunk) = serde_json::from_str::<ChatCompletionChunk>(&data) {
+                let json = serde_json::to_string(&chunk).unwrap_or_default();
+                forwarded.push(Bytes::from(format!("data: {}\n\n", json)));
+            }
+        }
+    }
+    forwarded.len()
+}
+
+/// Forward a body by passing its events through unparsed
+fn forward_passthrough(chunks: &[Bytes]) -> usize {
+    let mut splitter = FrameSplitter::new();
+    let mut forwarded = Vec::new();
+    for chunk in chunks {
+        for frame in splitter.push(chunk.clone()) {
+            let done = passthrough::is_done(&frame);
+            forwarded.push(frame);
+            if done {
+                return forwarded.len();
+            }
+        }
+    }
+    forwarded.extend(splitter.finish());
+    forwarded.len()
+}
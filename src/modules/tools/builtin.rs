@@ -0,0 +1,467 @@
+//! Built-in Tools
+//!
+//! This module provides the tools shipped with IntelliRouter: a calculator, a
+//! web search tool backed by a SearxNG-compatible JSON endpoint, and generic
+//! HTTP tools described in configuration.
+
+use async_trait::async_trait;
+use reqwest::{Client, Method};
+use serde_json::{json, Map, Value};
+
+use super::{function_definition, Tool, ToolError};
+use crate::config::{HttpToolConfig, SearchToolConfig};
+use crate::modules::model_registry::connectors::ToolDefinition;
+
+/// Calculator evaluating arithmetic expressions
+///
+/// Supports `+ - * / % ^`, parentheses, the constants `pi` and `e`, and the
+/// functions `sqrt`, `abs`, `ln`, `log`, `exp`, `sin`, `cos`, `tan`, `floor`,
+/// `ceil` and `round`.
+#[derive(Debug, Default)]
+pub struct CalculatorTool;
+
+#[async_trait]
+impl Tool for CalculatorTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            "calculator",
+            "Evaluate an arithmetic expression, e.g. `(2 + 3) * sqrt(16)`",
+            json!({
+                "type": "object",
+                "properties": {
+                    "expression": {"type": "string", "description": "Expression to evaluate"}
+                },
+                "required": ["expression"]
+            }),
+        )
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, ToolError> {
+        let expression = arguments
+            .get("expression")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::InvalidArguments("expression is required".to_string()))?;
+        let result = evaluate(expression)?;
+        Ok(json!({ "expression": expression, "result": result }))
+    }
+}
+
+/// Longest expression evaluated, in characters
+const MAX_EXPRESSION_LENGTH: usize = 1024;
+
+/// Deepest nesting of parentheses, function calls, signs and powers
+/// evaluated, keeping the recursive parser within the stack
+const MAX_NESTING_DEPTH: usize = 64;
+
+/// Evaluate an arithmetic expression
+pub fn evaluate(expression: &str) -> Result<f64, ToolError> {
+    if expression.chars().count() > MAX_EXPRESSION_LENGTH {
+        return Err(ToolError::InvalidArguments(format!(
+            "Expression is longer than {} characters",
+            MAX_EXPRESSION_LENGTH
+        )));
+    }
+    let mut parser = ExpressionParser {
+        chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.expression()?;
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error("unexpected character"));
+    }
+    if !value.is_finite() {
+        return Err(ToolError::Execution(format!(
+            "Expression '{}' has no finite result",
+            expression
+        )));
+    }
+    Ok(value)
+}
+
+/// Recursive-descent parser for arithmetic expressions
+struct ExpressionParser {
+    chars: Vec<char>,
+    pos: usize,
+    /// Nesting depth at the current position
+    depth: usize,
+}
+
+impl ExpressionParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, reason: &str) -> ToolError {
+        ToolError::InvalidArguments(format!("{} at position {}", reason, self.pos))
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.term()?;
+        while let Some(op @ ('+' | '-')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<f64, ToolError> {
+        let mut value = self.unary()?;
+        while let Some(op @ ('*' | '/' | '%')) = self.peek() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary := '-' unary | power
+    ///
+    /// Every nested expression goes through here, so the nesting depth is
+    /// tracked here.
+    fn unary(&mut self) -> Result<f64, ToolError> {
+        if self.depth >= MAX_NESTING_DEPTH {
+            return Err(self.error(&format!(
+                "expression nested deeper than {} levels",
+                MAX_NESTING_DEPTH
+            )));
+        }
+        self.depth += 1;
+        let value = self.signed();
+        self.depth -= 1;
+        value
+    }
+
+    fn signed(&mut self) -> Result<f64, ToolError> {
+        match self.peek() {
+            Some('-') => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some('+') => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power := primary ('^' unary)?
+    fn power(&mut self) -> Result<f64, ToolError> {
+        let base = self.primary()?;
+        if self.peek() == Some('^') {
+            self.pos += 1;
+            return Ok(base.powf(self.unary()?));
+        }
+        Ok(base)
+    }
+
+    /// primary := number | constant | function '(' expression ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<f64, ToolError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.expression()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                number
+                    .parse()
+                    .map_err(|_| ToolError::InvalidArguments(format!("Invalid number: {}", number)))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                    self.pos += 1;
+                }
+                let name: String = self.chars[start..self.pos].iter().collect();
+                match name.as_str() {
+                    "pi" => return Ok(std::f64::consts::PI),
+                    "e" => return Ok(std::f64::consts::E),
+                    _ => {}
+                }
+
+                self.expect('(')?;
+                let arg = self.expression()?;
+                self.expect(')')?;
+                match name.as_str() {
+                    "sqrt" => Ok(arg.sqrt()),
+                    "abs" => Ok(arg.abs()),
+                    "ln" => Ok(arg.ln()),
+                    "log" => Ok(arg.log10()),
+                    "exp" => Ok(arg.exp()),
+                    "sin" => Ok(arg.sin()),
+                    "cos" => Ok(arg.cos()),
+                    "tan" => Ok(arg.tan()),
+                    "floor" => Ok(arg.floor()),
+                    "ceil" => Ok(arg.ceil()),
+                    "round" => Ok(arg.round()),
+                    _ => Err(ToolError::InvalidArguments(format!(
+                        "Unknown function: {}",
+                        name
+                    ))),
+                }
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ToolError> {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", expected)))
+        }
+    }
+}
+
+/// Web search through a SearxNG-compatible JSON endpoint
+pub struct WebSearchTool {
+    client: Client,
+    config: SearchToolConfig,
+}
+
+impl WebSearchTool {
+    /// Create a web search tool
+    pub fn new(config: SearchToolConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for WebSearchTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            "web_search",
+            "Search the web and return the top results with titles, URLs and snippets",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Search query"}
+                },
+                "required": ["query"]
+            }),
+        )
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, ToolError> {
+        let query = arguments
+            .get("query")
+            .and_then(Value::as_str)
+            .ok_or_else(|| ToolError::InvalidArguments("query is required".to_string()))?;
+
+        let mut request = self
+            .client
+            .get(&self.config.endpoint)
+            .query(&[("q", query), ("format", "json")]);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| ToolError::Execution(format!("Search request failed: {}", e)))?;
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| ToolError::Execution(format!("Invalid search response: {}", e)))?;
+
+        let results: Vec<Value> = body
+            .get("results")
+            .and_then(Value::as_array)
+            .map(|results| {
+                results
+                    .iter()
+                    .take(self.config.max_results)
+                    .map(|r| {
+                        json!({
+                            "title": r.get("title"),
+                            "url": r.get("url"),
+                            "snippet": r.get("content").or_else(|| r.get("snippet")),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(json!({ "query": query, "results": results }))
+    }
+}
+
+/// HTTP endpoint exposed to models as a tool
+///
+/// `{{name}}` placeholders in the URL are replaced with the URL-encoded
+/// argument; remaining arguments are sent as query parameters for GET and
+/// DELETE requests, and as a JSON body otherwise.
+pub struct HttpTool {
+    client: Client,
+    config: HttpToolConfig,
+}
+
+impl HttpTool {
+    /// Create an HTTP tool
+    pub fn new(config: HttpToolConfig) -> Self {
+        Self {
+            client: Client::new(),
+            config,
+        }
+    }
+}
+
+/// Percent-encode a URL component
+fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Render an argument as plain text
+fn argument_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[async_trait]
+impl Tool for HttpTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            &self.config.name,
+            &self.config.description,
+            self.config.parameters.clone(),
+        )
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, ToolError> {
+        let mut remaining = match arguments {
+            Value::Object(fields) => fields,
+            Value::Null => Map::new(),
+            _ => {
+                return Err(ToolError::InvalidArguments(
+                    "arguments must be an object".to_string(),
+                ))
+            }
+        };
+
+        let mut url = self.config.url.clone();
+        for (name, value) in remaining.clone() {
+            let placeholder = format!("{{{{{}}}}}", name);
+            if url.contains(&placeholder) {
+                url = url.replace(&placeholder, &encode_component(&argument_text(&value)));
+                remaining.remove(&name);
+            }
+        }
+
+        let method = Method::from_bytes(self.config.method.to_uppercase().as_bytes())
+            .map_err(|_| ToolError::Execution(format!("Invalid method: {}", self.config.method)))?;
+        let mut request = self.client.request(method.clone(), &url);
+        for (name, value) in &self.config.headers {
+            request = request.header(name, value);
+        }
+        request = if method == Method::GET || method == Method::DELETE {
+            let query: Vec<(String, String)> = remaining
+                .iter()
+                .map(|(k, v)| (k.clone(), argument_text(v)))
+                .collect();
+            request.query(&query)
+        } else {
+            request.json(&Value::Object(remaining))
+        };
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ToolError::Execution(format!("HTTP tool request failed: {}", e)))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| ToolError::Execution(format!("Failed to read response: {}", e)))?;
+        let body = serde_json::from_str(&text).unwrap_or(Value::String(text));
+
+        if !status.is_success() {
+            return Err(ToolError::Execution(format!(
+                "{} returned {}: {}",
+                self.config.name, status, body
+            )));
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), 7.0);
+        assert_eq!(evaluate("(1 + 2) * 3").unwrap(), 9.0);
+        assert_eq!(evaluate("-2 ^ 2").unwrap(), -4.0);
+        assert_eq!(evaluate("2 ^ 3 ^ 2").unwrap(), 512.0);
+        assert_eq!(evaluate("sqrt(16) + abs(-1) % 2").unwrap(), 5.0);
+        assert!((evaluate("cos(pi)").unwrap() + 1.0).abs() < 1e-9);
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("2 +").is_err());
+        assert!(evaluate("nope(1)").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_limits() {
+        let nested = format!("{}1{}", "(".repeat(40), ")".repeat(40));
+        assert_eq!(evaluate(&nested).unwrap(), 1.0);
+
+        for expression in [
+            format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000)),
+            format!("{}1", "-".repeat(500)),
+            format!("{}1{}", "sqrt(".repeat(100), ")".repeat(100)),
+            "2^".repeat(200) + "1",
+        ] {
+            assert!(matches!(
+                evaluate(&expression),
+                Err(ToolError::InvalidArguments(_))
+            ));
+        }
+
+        let long = vec!["1"; 600].join("+");
+        assert!(matches!(
+            evaluate(&long),
+            Err(ToolError::InvalidArguments(reason)) if reason.contains("longer")
+        ));
+        let deep = format!("{}1{}", "(".repeat(70), ")".repeat(70));
+        assert!(matches!(
+            evaluate(&deep),
+            Err(ToolError::InvalidArguments(reason)) if reason.contains("nested")
+        ));
+    }
+
+    #[test]
+    fn test_encode_component() {
+        assert_eq!(encode_component("a b/c?"), "a%20b%2Fc%3F");
+        assert_eq!(encode_component("safe-_.~"), "safe-_.~");
+    }
+}
@@ -0,0 +1,348 @@
+//! Model Context Protocol Client
+//!
+//! This module connects to external MCP tool servers over stdio or HTTP,
+//! speaking JSON-RPC 2.0, and exposes their tools through the [`Tool`] trait.
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use super::{function_definition, Tool, ToolError};
+use crate::config::{McpServerConfig, McpTransport};
+use crate::modules::model_registry::connectors::ToolDefinition;
+
+/// MCP protocol version requested during initialization
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Header carrying the session ID of the streamable HTTP transport
+const SESSION_HEADER: &str = "mcp-session-id";
+
+/// Connection to an MCP server
+enum Connection {
+    Stdio {
+        // Held so the child is killed when the client is dropped
+        _child: Child,
+        stdin: ChildStdin,
+        stdout: Box<Lines<BufReader<ChildStdout>>>,
+    },
+    Http {
+        client: Client,
+        url: String,
+        headers: HashMap<String, String>,
+        session_id: Option<String>,
+    },
+}
+
+/// Client for an MCP tool server
+pub struct McpClient {
+    name: String,
+    connection: Mutex<Connection>,
+    next_id: AtomicU64,
+}
+
+impl McpClient {
+    /// Connect to a server and perform the initialization handshake
+    pub async fn connect(config: McpServerConfig) -> Result<Arc<Self>, ToolError> {
+        let connection = match config.transport {
+            McpTransport::Stdio => {
+                let command = config.command.as_deref().ok_or_else(|| {
+                    ToolError::Mcp(format!("MCP server {} has no command", config.name))
+                })?;
+                let mut child = Command::new(command)
+                    .args(&config.args)
+                    .envs(&config.env)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::inherit())
+                    .kill_on_drop(true)
+                    .spawn()
+                    .map_err(|e| ToolError::Mcp(format!("Failed to spawn {}: {}", command, e)))?;
+                let stdin = child.stdin.take().expect("stdin is piped");
+                let stdout = child.stdout.take().expect("stdout is piped");
+                Connection::Stdio {
+                    _child: child,
+                    stdin,
+                    stdout: Box::new(BufReader::new(stdout).lines()),
+                }
+            }
+            McpTransport::Http => Connection::Http {
+                client: Client::new(),
+                url: config.url.clone().ok_or_else(|| {
+                    ToolError::Mcp(format!("MCP server {} has no url", config.name))
+                })?,
+                headers: config.headers.clone(),
+                session_id: None,
+            },
+        };
+
+        let client = Arc::new(Self {
+            name: config.name,
+            connection: Mutex::new(connection),
+            next_id: AtomicU64::new(1),
+        });
+
+        let info = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {
+                        "name": "intellirouter",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }
+                }),
+            )
+            .await?;
+        client
+            .notify("notifications/initialized", json!({}))
+            .await?;
+        let server_name = info
+            .pointer("/serverInfo/name")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown");
+        info!("Connected to MCP server {} ({})", client.name, server_name);
+
+        Ok(client)
+    }
+
+    /// Server name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// List the server's tools
+    pub async fn tools(self: &Arc<Self>) -> Result<Vec<McpTool>, ToolError> {
+        let result = self.request("tools/list", json!({})).await?;
+        let tools = result
+            .get("tools")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(tools
+            .into_iter()
+            .filter_map(|tool| {
+                let name = tool.get("name")?.as_str()?.to_string();
+                Some(McpTool {
+                    client: Arc::clone(self),
+                    qualified_name: format!("{}__{}", self.name, name),
+                    description: tool
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    input_schema: tool
+                        .get("inputSchema")
+                        .cloned()
+                        .unwrap_or_else(|| json!({"type": "object"})),
+                    name,
+                })
+            })
+            .collect())
+    }
+
+    /// Call a tool on the server
+    pub async fn call_tool(&self, name: &str, arguments: Value) -> Result<Value, ToolError> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+
+        let text: Vec<&str> = result
+            .get("content")
+            .and_then(Value::as_array)
+            .map(|content| {
+                content
+                    .iter()
+                    .filter(|c| c.get("type").and_then(Value::as_str) == Some("text"))
+                    .filter_map(|c| c.get("text").and_then(Value::as_str))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let text = text.join("\n");
+
+        if result.get("isError").and_then(Value::as_bool) == Some(true) {
+            return Err(ToolError::Execution(text));
+        }
+        if let Some(structured) = result.get("structuredContent") {
+            return Ok(structured.clone());
+        }
+        Ok(Value::String(text))
+    }
+
+    /// Send a request and wait for its response
+    async fn request(&self, method: &str, params: Value) -> Result<Value, ToolError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        debug!("MCP {} -> {}", self.name, method);
+
+        let mut connection = self.connection.lock().await;
+        let response = match &mut *connection {
+            Connection::Stdio { stdin, stdout, .. } => {
+                write_line(stdin, &message).await?;
+                loop {
+                    let line = stdout
+                        .next_line()
+                        .await
+                        .map_err(|e| ToolError::Mcp(e.to_string()))?
+                        .ok_or_else(|| {
+                            ToolError::Mcp(format!("MCP server {} closed stdout", self.name))
+                        })?;
+                    let Ok(value) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    // Skip server notifications and requests until our response arrives
+                    if value.get("id").and_then(Value::as_u64) == Some(id)
+                        && value.get("method").is_none()
+                    {
+                        break value;
+                    }
+                }
+            }
+            Connection::Http {
+                client,
+                url,
+                headers,
+                session_id,
+            } => {
+                let response = post(client, url, headers, session_id.as_deref(), &message).await?;
+                if let Some(session) = response
+                    .headers()
+                    .get(SESSION_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                {
+                    *session_id = Some(session.to_string());
+                }
+                let is_sse = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|v| v.starts_with("text/event-stream"));
+                let body = response
+                    .text()
+                    .await
+                    .map_err(|e| ToolError::Mcp(e.to_string()))?;
+                if is_sse {
+                    body.lines()
+                        .filter_map(|line| line.strip_prefix("data:"))
+                        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+                        .find(|value| value.get("id").and_then(Value::as_u64) == Some(id))
+                        .ok_or_else(|| {
+                            ToolError::Mcp(format!("No response to {} in event stream", method))
+                        })?
+                } else {
+                    serde_json::from_str(&body).map_err(|e| ToolError::Mcp(e.to_string()))?
+                }
+            }
+        };
+
+        if let Some(error) = response.get("error") {
+            return Err(ToolError::Mcp(format!(
+                "{} failed: {}",
+                method,
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            )));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Send a notification
+    async fn notify(&self, method: &str, params: Value) -> Result<(), ToolError> {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        let mut connection = self.connection.lock().await;
+        match &mut *connection {
+            Connection::Stdio { stdin, .. } => write_line(stdin, &message).await,
+            Connection::Http {
+                client,
+                url,
+                headers,
+                session_id,
+            } => post(client, url, headers, session_id.as_deref(), &message)
+                .await
+                .map(|_| ()),
+        }
+    }
+}
+
+/// Write a line-delimited JSON message
+async fn write_line(stdin: &mut ChildStdin, message: &Value) -> Result<(), ToolError> {
+    let mut line = message.to_string();
+    line.push('\n');
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| ToolError::Mcp(e.to_string()))?;
+    stdin
+        .flush()
+        .await
+        .map_err(|e| ToolError::Mcp(e.to_string()))
+}
+
+/// POST a JSON-RPC message over the HTTP transport
+async fn post(
+    client: &Client,
+    url: &str,
+    headers: &HashMap<String, String>,
+    session_id: Option<&str>,
+    message: &Value,
+) -> Result<reqwest::Response, ToolError> {
+    let mut request = client
+        .post(url)
+        .header(
+            reqwest::header::ACCEPT,
+            "application/json, text/event-stream",
+        )
+        .json(message);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(session_id) = session_id {
+        request = request.header(SESSION_HEADER, session_id);
+    }
+    request
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| ToolError::Mcp(e.to_string()))
+}
+
+/// A tool exposed by an MCP server
+///
+/// Registered as `{server}__{tool}` so tools of different servers don't clash.
+pub struct McpTool {
+    client: Arc<McpClient>,
+    name: String,
+    qualified_name: String,
+    description: String,
+    input_schema: Value,
+}
+
+#[async_trait]
+impl Tool for McpTool {
+    fn definition(&self) -> ToolDefinition {
+        function_definition(
+            &self.qualified_name,
+            &self.description,
+            self.input_schema.clone(),
+        )
+    }
+
+    async fn call(&self, arguments: Value) -> Result<Value, ToolError> {
+        self.client.call_tool(&self.name, arguments).await
+    }
+}
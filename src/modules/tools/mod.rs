@@ -0,0 +1,72 @@
+//! Tool Execution Module
+//!
+//! This module executes tool calls requested by models. Tools are registered
+//! in a [`ToolRegistry`]: built-in tools (calculator, web search, configured
+//! HTTP tools) and tools exposed by external Model Context Protocol (MCP)
+//! servers. The [`ToolRunner`] feeds tool results back to the model until it
+//! produces a final answer, up to a maximum number of iterations.
+
+pub mod builtin;
+pub mod mcp;
+pub mod registry;
+pub mod routes;
+pub mod runner;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::modules::model_registry::connectors::ToolDefinition;
+
+pub use registry::ToolRegistry;
+pub use runner::{ToolInvocation, ToolRunOutcome, ToolRunner};
+
+/// Errors that can occur during tool execution
+#[derive(Error, Debug)]
+pub enum ToolError {
+    #[error("Tool not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(String),
+
+    #[error("Tool execution failed: {0}")]
+    Execution(String),
+
+    #[error("Tool timed out: {0}")]
+    Timeout(String),
+
+    #[error("MCP error: {0}")]
+    Mcp(String),
+
+    #[error("Model error: {0}")]
+    Model(String),
+
+    #[error("Tool loop exceeded {0} iterations")]
+    MaxIterations(u32),
+}
+
+/// A tool that can be called by a model
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Definition advertised to models
+    fn definition(&self) -> ToolDefinition;
+
+    /// Call the tool with JSON arguments
+    async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, ToolError>;
+}
+
+/// Build a function tool definition
+pub fn function_definition(
+    name: &str,
+    description: &str,
+    parameters: serde_json::Value,
+) -> ToolDefinition {
+    ToolDefinition {
+        r#type: "function".to_string(),
+        function: crate::modules::model_registry::connectors::FunctionDefinition {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            parameters,
+        },
+    }
+}
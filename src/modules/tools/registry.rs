@@ -0,0 +1,168 @@
+//! Tool Registry
+//!
+//! This module keeps the tools available to models and executes their calls
+//! with a timeout.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use super::builtin::{CalculatorTool, HttpTool, WebSearchTool};
+use super::mcp::McpClient;
+use super::{Tool, ToolError};
+use crate::config::ToolsConfig;
+use crate::modules::model_registry::connectors::{
+    ChatMessage, MessageRole, ToolCall, ToolDefinition,
+};
+
+/// Registry of tools callable by models
+pub struct ToolRegistry {
+    tools: RwLock<HashMap<String, Arc<dyn Tool>>>,
+    timeout: Duration,
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.names())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30))
+    }
+}
+
+impl ToolRegistry {
+    /// Create an empty registry with the given per-call timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            tools: RwLock::new(HashMap::new()),
+            timeout,
+        }
+    }
+
+    /// Create a registry with the tools described by the configuration
+    ///
+    /// MCP servers that fail to start are logged and skipped.
+    pub async fn from_config(config: &ToolsConfig) -> Self {
+        let registry = Self::new(Duration::from_secs(config.timeout_secs));
+
+        if config.calculator {
+            registry.register(Arc::new(CalculatorTool));
+        }
+        if let Some(search) = &config.search {
+            registry.register(Arc::new(WebSearchTool::new(search.clone())));
+        }
+        for http in &config.http {
+            registry.register(Arc::new(HttpTool::new(http.clone())));
+        }
+        for server in &config.mcp_servers {
+            match McpClient::connect(server.clone()).await {
+                Ok(client) => match client.tools().await {
+                    Ok(tools) => {
+                        for tool in tools {
+                            registry.register(Arc::new(tool));
+                        }
+                    }
+                    Err(e) => warn!("Failed to list tools of MCP server {}: {}", server.name, e),
+                },
+                Err(e) => warn!("Failed to connect to MCP server {}: {}", server.name, e),
+            }
+        }
+
+        registry
+    }
+
+    /// Create a registry with the same tools and timeout
+    ///
+    /// Tools registered in the fork are not visible in this registry.
+    pub fn fork(&self) -> Self {
+        Self {
+            tools: RwLock::new(self.tools.read().unwrap().clone()),
+            timeout: self.timeout,
+        }
+    }
+
+    /// Register a tool, replacing any tool with the same name
+    pub fn register(&self, tool: Arc<dyn Tool>) {
+        let name = tool.definition().function.name;
+        debug!("Registering tool {}", name);
+        self.tools.write().unwrap().insert(name, tool);
+    }
+
+    /// Get a tool by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Tool>> {
+        self.tools.read().unwrap().get(name).cloned()
+    }
+
+    /// Names of the registered tools, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.tools.read().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Definitions of the registered tools, sorted by name
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions: Vec<ToolDefinition> = self
+            .tools
+            .read()
+            .unwrap()
+            .values()
+            .map(|tool| tool.definition())
+            .collect();
+        definitions.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+        definitions
+    }
+
+    /// Whether the registry has no tools
+    pub fn is_empty(&self) -> bool {
+        self.tools.read().unwrap().is_empty()
+    }
+
+    /// Invoke a tool by name
+    pub async fn invoke(&self, name: &str, arguments: Value) -> Result<Value, ToolError> {
+        let tool = self
+            .get(name)
+            .ok_or_else(|| ToolError::NotFound(name.to_string()))?;
+        tokio::time::timeout(self.timeout, tool.call(arguments))
+            .await
+            .map_err(|_| ToolError::Timeout(name.to_string()))?
+    }
+
+    /// Execute a model-requested tool call, producing the tool message
+    ///
+    /// Failures are reported to the model in the message content rather than
+    /// aborting the conversation.
+    pub async fn execute(&self, call: &ToolCall) -> (ChatMessage, Result<Value, String>) {
+        let result = match serde_json::from_str::<Value>(&call.function.arguments) {
+            Ok(arguments) => self
+                .invoke(&call.function.name, arguments)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(ToolError::InvalidArguments(e.to_string()).to_string()),
+        };
+
+        let content = match &result {
+            Ok(Value::String(text)) => text.clone(),
+            Ok(value) => value.to_string(),
+            Err(error) => serde_json::json!({ "error": error }).to_string(),
+        };
+        let message = ChatMessage {
+            role: MessageRole::Tool,
+            content,
+            name: Some(call.function.name.clone()),
+            function_call: None,
+            tool_calls: None,
+            tool_call_id: Some(call.id.clone()),
+        };
+        (message, result)
+    }
+}
@@ -0,0 +1,106 @@
+//! Tool API
+//!
+//! This module lists the registered tools over HTTP and lets callers invoke
+//! them directly. Invoking a tool needs the operator admin role.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use utoipa::OpenApi;
+
+use super::{ToolError, ToolRegistry};
+use crate::config::ProxyConfig;
+use crate::modules::llm_proxy::admin::{self, AdminRole};
+use crate::modules::llm_proxy::dto::ApiError;
+
+/// OpenAPI description of the tool API
+#[derive(OpenApi)]
+#[openapi(paths(list_tools, invoke_tool))]
+pub struct ToolApiDoc;
+
+/// State of the tool API
+struct ToolApiState {
+    registry: Arc<ToolRegistry>,
+    /// Admin keys
+    proxy: ProxyConfig,
+}
+
+/// Create the router for the tool API
+pub fn create_router(registry: Arc<ToolRegistry>, proxy: ProxyConfig) -> Router {
+    let state = Arc::new(ToolApiState { registry, proxy });
+    Router::new()
+        .route("/v1/tools", get(list_tools))
+        .route("/v1/tools/{name}/invoke", post(invoke_tool))
+        .with_state(state)
+}
+
+/// Build an error response
+fn error_response(status: StatusCode, message: String, code: &str) -> Response {
+    let body = json!({
+        "error": {
+            "message": message,
+            "type": "tool_error",
+            "param": null,
+            "code": code,
+        }
+    });
+    (status, Json(body)).into_response()
+}
+
+/// List the registered tool definitions
+#[utoipa::path(
+    get,
+    path = "/v1/tools",
+    tag = "tools",
+    responses((status = 200, description = "Tool definitions under `tools`", body = Object))
+)]
+async fn list_tools(State(state): State<Arc<ToolApiState>>) -> Response {
+    Json(json!({ "tools": state.registry.definitions() })).into_response()
+}
+
+/// Invoke a tool with the JSON request body as arguments
+#[utoipa::path(
+    post,
+    path = "/v1/tools/{name}/invoke",
+    tag = "tools",
+    security(("bearer_auth" = [])),
+    params(("name" = String, Path, description = "Tool name")),
+    request_body = Object,
+    responses(
+        (status = 200, description = "Tool result", body = Object),
+        (status = 400, description = "Invalid arguments", body = ApiError),
+        (status = 403, description = "Missing admin credentials or insufficient role", body = ApiError),
+        (status = 404, description = "Unknown tool", body = ApiError),
+        (status = 502, description = "Tool failed", body = ApiError),
+        (status = 504, description = "Tool timed out", body = ApiError)
+    )
+)]
+async fn invoke_tool(
+    State(state): State<Arc<ToolApiState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(arguments): Json<Value>,
+) -> Response {
+    if let Err(e) = admin::authorize(&state.proxy, &headers, AdminRole::Operator) {
+        return e.into_response();
+    }
+    match state.registry.invoke(&name, arguments).await {
+        Ok(result) => Json(json!({ "tool": name, "result": result })).into_response(),
+        Err(e) => {
+            let (status, code) = match &e {
+                ToolError::NotFound(_) => (StatusCode::NOT_FOUND, "tool_not_found"),
+                ToolError::InvalidArguments(_) => (StatusCode::BAD_REQUEST, "invalid_arguments"),
+                ToolError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "tool_timeout"),
+                _ => (StatusCode::BAD_GATEWAY, "tool_execution_failed"),
+            };
+            error_response(status, e.to_string(), code)
+        }
+    }
+}
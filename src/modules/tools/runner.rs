@@ -0,0 +1,308 @@
+//! Tool Runner
+//!
+//! This module drives the tool-call loop: the model is called, any tool calls
+//! it requests are executed and their results appended to the conversation,
+//! and the model is called again until it answers without requesting tools.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+use super::{ToolError, ToolRegistry};
+use crate::modules::model_registry::connectors::{
+    ChatCompletionRequest, ChatCompletionResponse, ChatMessage, MessageRole, ModelConnector,
+};
+
+/// Record of a single tool call made during a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolInvocation {
+    /// ID of the tool call assigned by the model
+    pub call_id: String,
+    /// Tool name
+    pub name: String,
+    /// Arguments as sent by the model
+    pub arguments: String,
+    /// Tool result, if the call succeeded
+    pub result: Option<Value>,
+    /// Error message, if the call failed
+    pub error: Option<String>,
+    /// Call duration in milliseconds
+    pub duration_ms: u64,
+}
+
+/// Result of a tool-call loop
+#[derive(Debug, Clone)]
+pub struct ToolRunOutcome {
+    /// Final model response, without tool calls
+    pub response: ChatCompletionResponse,
+    /// Full conversation, including assistant tool calls and tool results
+    pub messages: Vec<ChatMessage>,
+    /// Tool calls executed during the run
+    pub invocations: Vec<ToolInvocation>,
+    /// Number of model calls made
+    pub iterations: u32,
+}
+
+/// Runs the model/tool loop for a request
+pub struct ToolRunner {
+    registry: Arc<ToolRegistry>,
+    connector: Arc<dyn ModelConnector>,
+    max_iterations: u32,
+}
+
+impl ToolRunner {
+    /// Create a tool runner
+    pub fn new(
+        registry: Arc<ToolRegistry>,
+        connector: Arc<dyn ModelConnector>,
+        max_iterations: u32,
+    ) -> Self {
+        Self {
+            registry,
+            connector,
+            max_iterations,
+        }
+    }
+
+    /// Run the request until the model answers without tool calls
+    ///
+    /// The registry's tools are advertised when the request defines none.
+    pub async fn run(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ToolRunOutcome, ToolError> {
+        if request.tools.is_none() && !self.registry.is_empty() {
+            request.tools = Some(self.registry.definitions());
+        }
+
+        let mut invocations = Vec::new();
+        for iteration in 1..=self.max_iterations {
+            let response = self
+                .connector
+                .generate(request.clone())
+                .await
+                .map_err(|e| ToolError::Model(e.to_string()))?;
+
+            let message = match response.choices.first() {
+                Some(choice) => choice.message.clone(),
+                None => return Err(ToolError::Model("Response has no choices".to_string())),
+            };
+            let tool_calls = match &message.tool_calls {
+                Some(calls) if !calls.is_empty() => calls.clone(),
+                _ => {
+                    request.messages.push(message);
+                    return Ok(ToolRunOutcome {
+                        response,
+                        messages: request.messages,
+                        invocations,
+                        iterations: iteration,
+                    });
+                }
+            };
+
+            debug!(
+                "Iteration {}: executing {} tool call(s)",
+                iteration,
+                tool_calls.len()
+            );
+            request.messages.push(ChatMessage {
+                role: MessageRole::Assistant,
+                ..message
+            });
+
+            let results = futures::future::join_all(tool_calls.iter().map(|call| async move {
+                let start = Instant::now();
+                let (message, result) = self.registry.execute(call).await;
+                (call, message, result, start.elapsed())
+            }))
+            .await;
+
+            for (call, message, result, elapsed) in results {
+                let (result, error) = match result {
+                    Ok(value) => (Some(value), None),
+                    Err(error) => (None, Some(error)),
+                };
+                invocations.push(ToolInvocation {
+                    call_id: call.id.clone(),
+                    name: call.function.name.clone(),
+                    arguments: call.function.arguments.clone(),
+                    result,
+                    error,
+                    duration_ms: elapsed.as_millis() as u64,
+                });
+                request.messages.push(message);
+            }
+        }
+
+        Err(ToolError::MaxIterations(self.max_iterations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::model_registry::connectors::{
+        ChatCompletionChoice, ConnectorConfig, ConnectorError, FunctionCall, StreamingResponse,
+        ToolCall,
+    };
+    use crate::modules::tools::builtin::CalculatorTool;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Connector that requests the calculator until it sees a tool result
+    struct CalculatingConnector {
+        config: ConnectorConfig,
+        calls: AtomicUsize,
+        always_call_tools: bool,
+    }
+
+    impl CalculatingConnector {
+        fn new(always_call_tools: bool) -> Self {
+            Self {
+                config: ConnectorConfig::default(),
+                calls: AtomicUsize::new(0),
+                always_call_tools,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ModelConnector for CalculatingConnector {
+        async fn generate(
+            &self,
+            request: ChatCompletionRequest,
+        ) -> Result<ChatCompletionResponse, ConnectorError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let tool_result = request
+                .messages
+                .iter()
+                .rev()
+                .find(|m| m.role == MessageRole::Tool);
+
+            let message = match tool_result {
+                Some(result) if !self.always_call_tools => ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: format!("The answer is {}", result.content),
+                    name: None,
+                    function_call: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                _ => ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: String::new(),
+                    name: None,
+                    function_call: None,
+                    tool_calls: Some(vec![ToolCall {
+                        id: "call_1".to_string(),
+                        r#type: "function".to_string(),
+                        function: FunctionCall {
+                            name: "calculator".to_string(),
+                            arguments: r#"{"expression": "6 * 7"}"#.to_string(),
+                        },
+                    }]),
+                    tool_call_id: None,
+                },
+            };
+
+            Ok(ChatCompletionResponse {
+                id: "resp".to_string(),
+                model: request.model,
+                created: 0,
+                choices: vec![ChatCompletionChoice {
+                    index: 0,
+                    message,
+                    finish_reason: Some("stop".to_string()),
+                    content_filter: None,
+                }],
+                usage: None,
+            })
+        }
+
+        async fn generate_streaming(
+            &self,
+            _request: ChatCompletionRequest,
+        ) -> Result<StreamingResponse, ConnectorError> {
+            Err(ConnectorError::Other("not supported".to_string()))
+        }
+
+        fn get_config(&self) -> &ConnectorConfig {
+            &self.config
+        }
+
+        fn update_config(&mut self, config: ConnectorConfig) {
+            self.config = config;
+        }
+
+        fn provider_name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn supports_model(&self, _model_id: &str) -> bool {
+            true
+        }
+
+        async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+            Ok(vec!["mock".to_string()])
+        }
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "mock".to_string(),
+            messages: vec![ChatMessage {
+                role: MessageRole::User,
+                content: "What is 6 times 7?".to_string(),
+                name: None,
+                function_call: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            top_p: None,
+            max_tokens: None,
+            stream: None,
+            functions: None,
+            tools: None,
+            additional_params: None,
+        }
+    }
+
+    fn registry() -> Arc<ToolRegistry> {
+        let registry = ToolRegistry::default();
+        registry.register(Arc::new(CalculatorTool));
+        Arc::new(registry)
+    }
+
+    #[tokio::test]
+    async fn test_tool_results_fed_back() {
+        let runner = ToolRunner::new(registry(), Arc::new(CalculatingConnector::new(false)), 5);
+        let outcome = runner.run(request()).await.unwrap();
+
+        assert_eq!(outcome.iterations, 2);
+        assert_eq!(outcome.invocations.len(), 1);
+        assert_eq!(
+            outcome.invocations[0].result.as_ref().unwrap()["result"],
+            42.0
+        );
+        let tool_message = &outcome.messages[2];
+        assert_eq!(tool_message.role, MessageRole::Tool);
+        assert_eq!(tool_message.tool_call_id.as_deref(), Some("call_1"));
+        assert!(outcome.response.choices[0].message.content.contains("42"));
+    }
+
+    #[tokio::test]
+    async fn test_max_iterations() {
+        let connector = Arc::new(CalculatingConnector::new(true));
+        let runner = ToolRunner::new(registry(), connector.clone(), 3);
+
+        assert!(matches!(
+            runner.run(request()).await,
+            Err(ToolError::MaxIterations(3))
+        ));
+        assert_eq!(connector.calls.load(Ordering::SeqCst), 3);
+    }
+}
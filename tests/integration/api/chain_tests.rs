@@ -81,6 +81,7 @@ impl StepExecutor for ModelRegistryExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 })
             }
             _ => Err(ChainError::StepExecutionError(format!(
@@ -211,10 +212,13 @@ fn create_simple_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     steps.insert("step1".to_string(), step1);
@@ -229,9 +233,12 @@ fn create_simple_chain() -> Chain {
         steps,
         dependencies: vec![],
         variables: HashMap::new(),
+        input_schema: None,
+        output_schema: None,
         error_handling: Default::default(),
         max_parallel_steps: None,
         timeout: None,
+        budget: None,
     }
 }
 
@@ -270,10 +277,13 @@ fn create_multi_step_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     // Step 2: Generate response based on analysis
@@ -307,10 +317,13 @@ fn create_multi_step_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     steps.insert("step1".to_string(), step1);
@@ -334,9 +347,12 @@ fn create_multi_step_chain() -> Chain {
         steps,
         dependencies,
         variables: HashMap::new(),
+        input_schema: None,
+        output_schema: None,
         error_handling: Default::default(),
         max_parallel_steps: None,
         timeout: None,
+        budget: None,
     }
 }
 
@@ -377,10 +393,13 @@ fn create_conditional_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     // Step 2: Technical response
@@ -414,6 +433,8 @@ fn create_conditional_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: Some(Condition::Contains {
             variable: "classification".to_string(),
             value: serde_json::json!("technical"),
@@ -421,6 +442,7 @@ fn create_conditional_chain() -> Chain {
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     // Step 3: General response
@@ -454,6 +476,8 @@ fn create_conditional_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: Some(Condition::Contains {
             variable: "classification".to_string(),
             value: serde_json::json!("general"),
@@ -461,6 +485,7 @@ fn create_conditional_chain() -> Chain {
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     steps.insert("step1".to_string(), step1);
@@ -493,9 +518,12 @@ fn create_conditional_chain() -> Chain {
         steps,
         dependencies,
         variables: HashMap::new(),
+        input_schema: None,
+        output_schema: None,
         error_handling: Default::default(),
         max_parallel_steps: None,
         timeout: None,
+        budget: None,
     }
 }
 
@@ -534,6 +562,8 @@ fn create_error_handling_chain() -> Chain {
             },
             transform: None,
         }],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
@@ -542,6 +572,7 @@ fn create_error_handling_chain() -> Chain {
                 step_id: "fallback_step".to_string(),
             },
         ),
+        compensation: None,
     };
 
     // Step 2: Fallback step
@@ -585,10 +616,13 @@ fn create_error_handling_chain() -> Chain {
                 transform: None,
             },
         ],
+        input_schema: None,
+        output_schema: None,
         condition: None,
         retry_policy: None,
         timeout: None,
         error_handler: None,
+        compensation: None,
     };
 
     steps.insert("step1".to_string(), step1);
@@ -604,9 +638,12 @@ fn create_error_handling_chain() -> Chain {
         steps,
         dependencies: vec![],
         variables: HashMap::new(),
+        input_schema: None,
+        output_schema: None,
         error_handling: Default::default(),
         max_parallel_steps: None,
         timeout: None,
+        budget: None,
     }
 }
 
@@ -832,6 +869,7 @@ async fn test_error_handling_chain() {
                             outputs,
                             error: None,
                             execution_time: start_time.elapsed(),
+                            usage: None,
                         })
                     }
                     _ => Err(ChainError::StepExecutionError(format!(
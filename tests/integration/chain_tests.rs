@@ -81,6 +81,7 @@ impl StepExecutor for ModelRegistryExecutor {
                     outputs,
                     error: None,
                     execution_time: start_time.elapsed(),
+                    usage: None,
                 })
             }
             _ => Err(ChainError::StepExecutionError(format!(
@@ -832,6 +833,7 @@ async fn test_error_handling_chain() {
                             outputs,
                             error: None,
                             execution_time: start_time.elapsed(),
+                            usage: None,
                         })
                     }
                     _ => Err(ChainError::StepExecutionError(format!(
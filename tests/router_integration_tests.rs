@@ -26,6 +26,12 @@ async fn test_router_chat_completions() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the request
@@ -0,0 +1,345 @@
+//! Contract tests between the Rust SDK and the server
+//!
+//! Every SDK method is called against an in-process server backed by a mock
+//! provider. Besides each call succeeding, the JSON of both sides has to
+//! agree: every field the SDK sends must be known to the server, and
+//! responses must carry exactly the fields the SDK models. Drift on either
+//! side fails these tests.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use intellirouter::config::ProxyConfig;
+use intellirouter::modules::chain_engine::{api as chain_api, AgentDefinition, AgentRuntime};
+use intellirouter::modules::model_registry::connectors::{
+    ChatCompletionChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, MessageRole,
+    ModelConnector, StreamingResponse, TokenUsage,
+};
+use intellirouter::modules::model_registry::{ConnectorConfig, ConnectorError};
+use intellirouter::modules::tools::ToolRegistry;
+
+/// Provider answering every request with a fixed completion
+struct MockProvider {
+    config: ConnectorConfig,
+}
+
+#[async_trait]
+impl ModelConnector for MockProvider {
+    async fn generate(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, ConnectorError> {
+        Ok(ChatCompletionResponse {
+            id: "chatcmpl-contract".to_string(),
+            model: request.model,
+            created: 0,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage {
+                    role: MessageRole::Assistant,
+                    content: "4".to_string(),
+                    name: None,
+                    function_call: None,
+                    tool_calls: None,
+                    tool_call_id: None,
+                },
+                finish_reason: Some("stop".to_string()),
+                content_filter: None,
+            }],
+            usage: Some(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 1,
+                total_tokens: 13,
+            }),
+        })
+    }
+
+    async fn generate_streaming(
+        &self,
+        _request: ChatCompletionRequest,
+    ) -> Result<StreamingResponse, ConnectorError> {
+        Err(ConnectorError::UnsupportedOperation(
+            "streaming".to_string(),
+        ))
+    }
+
+    fn get_config(&self) -> &ConnectorConfig {
+        &self.config
+    }
+
+    fn update_config(&mut self, config: ConnectorConfig) {
+        self.config = config;
+    }
+
+    fn provider_name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn supports_model(&self, _model_id: &str) -> bool {
+        true
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ConnectorError> {
+        Ok(vec!["mock-model".to_string()])
+    }
+}
+
+/// Server listening on a local port for the duration of a test
+struct TestServer {
+    base_url: String,
+    task: JoinHandle<()>,
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Start the agent API with the mock provider, accepting the client's key as
+/// an admin key
+async fn spawn_server() -> TestServer {
+    let provider = MockProvider {
+        config: ConnectorConfig::default(),
+    };
+    let runtime = AgentRuntime::new(Arc::new(provider), Arc::new(ToolRegistry::default()));
+    let tools = HashMap::from([("contract-agent".to_string(), vec!["calculator".to_string()])]);
+    let proxy = ProxyConfig {
+        admin_api_keys: vec!["contract-test".to_string()],
+        ..ProxyConfig::default()
+    };
+    let app = chain_api::create_agent_router(Arc::new(runtime), tools, proxy);
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let task = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    TestServer {
+        base_url: format!("http://{}", addr),
+        task,
+    }
+}
+
+fn client(server: &TestServer) -> sdk::IntelliRouter {
+    sdk::IntelliRouter::with_config(sdk::ClientConfig {
+        api_key: "contract-test".to_string(),
+        base_url: server.base_url.clone(),
+        ..Default::default()
+    })
+}
+
+fn sdk_agent() -> sdk::AgentDefinition {
+    sdk::AgentDefinition {
+        id: Some("contract-agent".to_string()),
+        model: "mock-model".to_string(),
+        system_prompt: Some("Answer briefly.".to_string()),
+        tools: vec!["calculator".to_string()],
+        max_steps: Some(3),
+        max_tool_calls: Some(2),
+        stop_phrases: vec!["FINAL ANSWER:".to_string()],
+        timeout_secs: Some(30),
+        temperature: Some(0.2),
+    }
+}
+
+fn fields(value: &Value) -> BTreeSet<String> {
+    value
+        .as_object()
+        .map(|object| object.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Assert that a server response and the SDK's model of it have the same
+/// fields, recursively
+fn assert_same_fields(path: &str, server: &Value, sdk: &Value) {
+    match (server, sdk) {
+        (Value::Object(server_fields), Value::Object(_)) => {
+            let (server_keys, sdk_keys) = (fields(server), fields(sdk));
+            assert_eq!(
+                server_keys,
+                sdk_keys,
+                "fields of {} differ: server only {:?}, SDK only {:?}",
+                path,
+                server_keys.difference(&sdk_keys).collect::<Vec<_>>(),
+                sdk_keys.difference(&server_keys).collect::<Vec<_>>()
+            );
+            for (key, value) in server_fields {
+                assert_same_fields(&format!("{}.{}", path, key), value, &sdk[key]);
+            }
+        }
+        (Value::Array(server_items), Value::Array(sdk_items)) => {
+            assert_eq!(server_items.len(), sdk_items.len(), "length of {}", path);
+            for (i, (server_item, sdk_item)) in server_items.iter().zip(sdk_items).enumerate() {
+                assert_same_fields(&format!("{}[{}]", path, i), server_item, sdk_item);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn test_agent_definition_fields_are_known_to_server() {
+    let sent = serde_json::to_value(sdk_agent()).unwrap();
+    let received: AgentDefinition = serde_json::from_value(sent.clone()).unwrap();
+    let known = fields(&serde_json::to_value(&received).unwrap());
+
+    let unknown: Vec<String> = fields(&sent).difference(&known).cloned().collect();
+    assert!(
+        unknown.is_empty(),
+        "server ignores SDK fields {:?}",
+        unknown
+    );
+
+    // Values survive the trip, not just names
+    assert_eq!(received.id, "contract-agent");
+    assert_eq!(received.max_steps, 3);
+    assert_eq!(received.stop_phrases, vec!["FINAL ANSWER:".to_string()]);
+}
+
+#[tokio::test]
+async fn test_agent_methods_match_server() {
+    let server = spawn_server().await;
+    let agents = client(&server).agents();
+
+    let run = agents
+        .run(&sdk_agent(), "What is 2 + 2?", None)
+        .await
+        .unwrap();
+    assert_eq!(run.agent_id, "contract-agent");
+    assert_eq!(run.input, "What is 2 + 2?");
+    assert_eq!(run.stop_reason, "final_answer");
+    assert_eq!(run.answer.as_deref(), Some("4"));
+    assert_eq!(run.total_tokens, 13);
+    assert_eq!(run.usage.prompt_tokens, 12);
+    assert_eq!(run.usage.completion_tokens, 1);
+    assert!(!run.steps.is_empty());
+
+    let fetched = agents.get_run(&run.run_id).await.unwrap();
+    assert_eq!(fetched.run_id, run.run_id);
+    assert_eq!(fetched.answer, run.answer);
+
+    let listed = agents.list_runs().await.unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].run_id, run.run_id);
+
+    // The server's JSON has exactly the fields the SDK models
+    let raw: Value = reqwest::Client::new()
+        .get(format!("{}/v1/agents/runs/{}", server.base_url, run.run_id))
+        .bearer_auth("contract-test")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_same_fields("AgentRun", &raw, &serde_json::to_value(&fetched).unwrap());
+}
+
+#[tokio::test]
+async fn test_agent_api_requires_admin_credentials() {
+    let server = spawn_server().await;
+    let http = reqwest::Client::new();
+
+    let anonymous = http
+        .post(format!("{}/v1/agents/run", server.base_url))
+        .json(&serde_json::json!({ "agent": sdk_agent(), "input": "Hello" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anonymous.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let conversation = serde_json::json!({
+        "conversation": {
+            "participants": [
+                { "name": "Critic", "agent": sdk_agent() },
+                { "name": "Writer", "agent": sdk_agent() },
+            ],
+        },
+        "topic": "Hello",
+    });
+    let anonymous = http
+        .post(format!("{}/v1/agents/conversations", server.base_url))
+        .json(&conversation)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(anonymous.status(), reqwest::StatusCode::FORBIDDEN);
+
+    let unknown_key = http
+        .get(format!("{}/v1/agents/runs", server.base_url))
+        .bearer_auth("sk-tenant")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown_key.status(), reqwest::StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_server_errors_map_to_sdk_errors() {
+    let server = spawn_server().await;
+    let agents = client(&server).agents();
+
+    match agents.get_run("missing").await {
+        Err(sdk::Error::ApiError { code, message }) => {
+            assert_eq!(code, "agent_run_not_found");
+            assert!(message.contains("missing"));
+        }
+        other => panic!(
+            "expected an API error, got {:?}",
+            other.map(|run| run.run_id)
+        ),
+    }
+
+    let invalid = sdk::AgentDefinition {
+        max_steps: Some(0),
+        ..sdk_agent()
+    };
+    match agents.run(&invalid, "Hello", None).await {
+        Err(sdk::Error::ApiError { code, .. }) => assert_eq!(code, "invalid_agent"),
+        other => panic!(
+            "expected an API error, got {:?}",
+            other.map(|run| run.run_id)
+        ),
+    }
+}
+
+#[tokio::test]
+async fn test_call_options_and_cancellation() {
+    let server = spawn_server().await;
+    let agents = client(&server).agents();
+
+    let options = sdk::CallOptions::new()
+        .with_timeout(std::time::Duration::from_secs(5))
+        .with_header("x-request-source", "contract-test")
+        .with_model("mock-model-large")
+        .with_priority(7);
+    let run = agents
+        .run_with_options(&sdk_agent(), "What is 2 + 2?", None, &options)
+        .await
+        .unwrap();
+    assert_eq!(run.answer.as_deref(), Some("4"));
+
+    let token = sdk::CancellationToken::new();
+    token.cancel();
+    let options = sdk::CallOptions::new().with_cancellation(token);
+    match agents
+        .run_with_options(&sdk_agent(), "What is 2 + 2?", None, &options)
+        .await
+    {
+        Err(sdk::Error::Cancelled) => {}
+        other => panic!(
+            "expected a cancelled call, got {:?}",
+            other.map(|run| run.run_id)
+        ),
+    }
+
+    // The client stays usable after a cancelled call
+    assert_eq!(agents.list_runs().await.unwrap().len(), 1);
+}
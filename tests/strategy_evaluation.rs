@@ -59,6 +59,7 @@ fn create_test_request(content: &str) -> RoutingRequest {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
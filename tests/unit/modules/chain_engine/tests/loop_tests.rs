@@ -38,6 +38,7 @@ impl StepExecutor for MockStepExecutor {
             outputs: self.result.clone(),
             error: None,
             execution_time: self.delay,
+            usage: None,
         })
     }
 }
@@ -34,10 +34,15 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec![],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
             },
             shared: Arc::new(Mutex::new(SharedState::new())),
             telemetry: None,
             cost_calculator: None,
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
         };
 
         create_router(app_state)
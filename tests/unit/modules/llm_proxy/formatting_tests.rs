@@ -37,6 +37,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: "user".to_string(),
@@ -44,6 +45,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
         ];
 
@@ -70,6 +72,7 @@ mod tests {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }];
         let content = "Hi there! How can I help you today?";
         let finish_reason = "stop";
@@ -114,6 +114,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Create service
@@ -154,6 +160,12 @@ mod tests {
                 presence_penalty: None,
                 frequency_penalty: None,
                 user: None,
+                stop: None,
+                logit_bias: None,
+                seed: None,
+                top_k: None,
+                stream_options: None,
+                response_format: None,
             };
 
             // Create service
@@ -33,6 +33,7 @@ mod tests {
                 cors_enabled: false,
                 cors_allowed_origins: vec![],
                 redis_url: None,
+                proxy: crate::config::ProxyConfig::default(),
             },
             shared: Arc::new(Mutex::new(SharedState {
                 active_connections: 0,
@@ -40,6 +41,10 @@ mod tests {
             })),
             telemetry: None,
             cost_calculator: None,
+            registry: Arc::new(crate::modules::model_registry::ModelRegistry::new()),
+            quotas: Arc::new(crate::modules::llm_proxy::quota::TokenQuotaManager::default()),
+            policies: Arc::new(crate::modules::router_core::PolicyEngine::new()),
+            decisions: Arc::new(crate::modules::llm_proxy::decision_log::DecisionLog::default()),
             shared: Arc::new(Mutex::new(SharedState::new())),
         }
     }
@@ -90,6 +95,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: Some(0.7),
             top_p: None,
@@ -99,6 +105,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Serialize the request to JSON
@@ -164,6 +176,7 @@ mod tests {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             }],
             temperature: Some(0.7),
             top_p: None,
@@ -173,6 +186,12 @@ mod tests {
             presence_penalty: None,
             frequency_penalty: None,
             user: None,
+            stop: None,
+            logit_bias: None,
+            seed: None,
+            top_k: None,
+            stream_options: None,
+            response_format: None,
         };
 
         // Serialize the request to JSON
@@ -27,6 +27,7 @@ fn test_ollama_request_conversion() {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::User,
@@ -34,6 +35,7 @@ fn test_ollama_request_conversion() {
                 name: Some("user1".to_string()),
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Assistant,
@@ -41,6 +43,7 @@ fn test_ollama_request_conversion() {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Function,
@@ -48,6 +51,7 @@ fn test_ollama_request_conversion() {
                 name: Some("get_time".to_string()),
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Tool,
@@ -55,6 +59,7 @@ fn test_ollama_request_conversion() {
                 name: Some("calculator".to_string()),
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
         ],
         temperature: Some(0.7),
@@ -240,6 +245,7 @@ fn test_openai_request_conversion() {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::User,
@@ -247,6 +253,7 @@ fn test_openai_request_conversion() {
                 name: Some("user1".to_string()),
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Assistant,
@@ -257,6 +264,7 @@ fn test_openai_request_conversion() {
                     arguments: "{\"timezone\": \"UTC\"}".to_string(),
                 }),
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Function,
@@ -264,6 +272,7 @@ fn test_openai_request_conversion() {
                 name: Some("get_time".to_string()),
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             ChatMessage {
                 role: MessageRole::Tool,
@@ -278,6 +287,7 @@ fn test_openai_request_conversion() {
                         arguments: "{\"expression\": \"6 * 7\"}".to_string(),
                     },
                 }]),
+                tool_call_id: None,
             },
         ],
         temperature: Some(0.7),
@@ -475,6 +485,7 @@ fn test_openai_response_conversion() {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             finish_reason: Some("stop".to_string()),
         }],
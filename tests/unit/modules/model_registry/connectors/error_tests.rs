@@ -33,6 +33,7 @@ async fn test_ollama_authentication_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -84,6 +85,7 @@ async fn test_ollama_rate_limit_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -135,6 +137,7 @@ async fn test_ollama_model_not_found_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -231,6 +234,7 @@ async fn test_ollama_server_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -283,6 +287,7 @@ async fn test_ollama_timeout_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -332,6 +337,7 @@ async fn test_ollama_parsing_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -393,6 +399,7 @@ async fn test_ollama_retry_logic() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -441,6 +448,7 @@ async fn test_ollama_streaming_error_handling() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -497,6 +505,7 @@ async fn test_openai_authentication_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -548,6 +557,7 @@ async fn test_openai_rate_limit_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -599,6 +609,7 @@ async fn test_openai_model_not_found_error() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
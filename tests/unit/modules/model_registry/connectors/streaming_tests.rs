@@ -38,6 +38,7 @@ async fn test_ollama_streaming_success() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -136,6 +137,7 @@ async fn test_ollama_streaming_partial_json() {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -202,6 +204,7 @@ data: [DONE]
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -300,6 +303,7 @@ data: [DONE]
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -413,6 +417,7 @@ data: [DONE]
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: None,
         top_p: None,
@@ -510,6 +510,7 @@ fn create_test_routing_request(model: &str) -> RoutingRequest {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: Some(0.7),
         top_p: Some(0.9),
@@ -620,8 +621,10 @@ impl ModelConnector for MockConnector {
                     name: None,
                     function_call: None,
                     tool_calls: None,
+                    tool_call_id: None,
                 },
                 finish_reason: Some("stop".to_string()),
+                content_filter: None,
             }],
             usage: None,
         })
@@ -701,8 +704,10 @@ impl ModelConnector for MockFailingConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             })
@@ -850,8 +855,10 @@ impl ModelConnector for MockMultiFailureConnector {
                         name: None,
                         function_call: None,
                         tool_calls: None,
+                        tool_call_id: None,
                     },
                     finish_reason: Some("stop".to_string()),
+                    content_filter: None,
                 }],
                 usage: None,
             }),
@@ -556,6 +556,7 @@ fn create_test_request(id: usize) -> RoutingRequest {
             name: None,
             function_call: None,
             tool_calls: None,
+            tool_call_id: None,
         }],
         temperature: Some(0.7),
         top_p: Some(0.9),
@@ -609,8 +610,10 @@ fn create_test_response(id: usize) -> RoutingResponse {
                 name: None,
                 function_call: None,
                 tool_calls: None,
+                tool_call_id: None,
             },
             finish_reason: Some("stop".to_string()),
+            content_filter: None,
         }],
         usage: None,
     };
@@ -35,6 +35,12 @@ async fn test_mock_backend_known_model() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the request
@@ -93,6 +99,12 @@ async fn test_mock_backend_unknown_model() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the request
@@ -131,6 +143,12 @@ async fn test_mock_backend_streaming() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the streaming request
@@ -200,6 +218,12 @@ async fn test_routing_strategies() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the request
@@ -262,6 +286,12 @@ async fn test_mock_backend_error_simulation() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Process the request
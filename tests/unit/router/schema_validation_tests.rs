@@ -29,6 +29,12 @@ fn test_validate_chat_completion_request_valid() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Validate the request
@@ -50,6 +56,12 @@ fn test_validate_chat_completion_request_empty_messages() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Validate the request
@@ -86,6 +98,12 @@ fn test_validate_chat_completion_request_invalid_temperature() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Validate the request
@@ -122,6 +140,12 @@ fn test_validate_chat_completion_request_invalid_model() {
         presence_penalty: None,
         frequency_penalty: None,
         user: None,
+        stop: None,
+        logit_bias: None,
+        seed: None,
+        top_k: None,
+        stream_options: None,
+        response_format: None,
     };
 
     // Validate the request
@@ -157,6 +181,8 @@ fn test_validate_chat_completion_response_schema() {
                     name: None,
                 },
                 finish_reason: "stop".to_string(),
+                emulated: None,
+                content_filter: None,
             },
         ],
         usage: intellirouter::modules::llm_proxy::dto::TokenUsage {